@@ -15,7 +15,10 @@ use tokio::sync::{watch, Mutex, RwLock};
 use tracing::{debug, error, info, warn};
 
 use cluster_manager_interface::{ClusterEvent, ClusterManager, ClusterManagerError};
-use orchestrator_shared_types::{Node, NodeId, NodeResources, NodeStatus, OrchestrationError, Result, Keypair};
+use orchestrator_shared_types::{
+    ContainerStatusAck, ContainerStatusReport, Keypair, Node, NodeId, NodeLease, NodeResources,
+    NodeStatus, OrchestrationError, Result,
+};
 
 /// Key prefix for node metadata in chitchat's key-value store
 const NODE_ADDRESS_KEY: &str = "node:address";
@@ -27,6 +30,12 @@ const NODE_CPU_ALLOCATABLE_KEY: &str = "node:cpu_allocatable";
 const NODE_MEMORY_ALLOCATABLE_KEY: &str = "node:memory_mb_allocatable";
 const NODE_DISK_ALLOCATABLE_KEY: &str = "node:disk_mb_allocatable";
 const NODE_LABELS_KEY: &str = "node:labels";
+/// Container status delta heartbeated by the node
+const NODE_CONTAINER_STATUS_KEY: &str = "node:container_status";
+/// Prefix of the acknowledgements a control-plane node publishes, one per reporting node
+const CONTAINER_STATUS_ACK_PREFIX: &str = "ack:container_status:";
+/// Heartbeat lease renewed by the node
+const NODE_LEASE_KEY: &str = "node:lease";
 
 /// Configuration for the ChitchatClusterManager.
 #[derive(Debug, Clone)]
@@ -202,6 +211,8 @@ impl ChitchatClusterManager {
             labels,
             resources_capacity,
             resources_allocatable,
+            unschedulable: false,
+            resource_version: 0,
         }
     }
 
@@ -326,6 +337,44 @@ impl ChitchatClusterManager {
         });
     }
 
+    /// Parse the value stored under `key` by every live node.
+    async fn live_values<T: serde::de::DeserializeOwned>(&self, key: &str) -> Vec<(NodeId, T)> {
+        let chitchat_inner_guard = self.chitchat_inner.lock().await;
+        let Some(ref chitchat_arc) = *chitchat_inner_guard else {
+            return Vec::new();
+        };
+        let chitchat_guard = chitchat_arc.lock().await;
+
+        let mut values = Vec::new();
+        for chitchat_id in chitchat_guard.live_nodes() {
+            let Some(node_id) = Self::parse_node_id(chitchat_id.node_id.as_str()) else {
+                continue;
+            };
+            let Some(raw) = chitchat_guard
+                .node_state(chitchat_id)
+                .and_then(|state| state.get(key))
+            else {
+                continue;
+            };
+            match serde_json::from_str(raw) {
+                Ok(value) => values.push((node_id, value)),
+                Err(e) => warn!("Ignoring malformed {} from {}: {}", key, node_id, e),
+            }
+        }
+        values
+    }
+
+    /// Set a key on this node's gossiped state.
+    async fn set_self_value(&self, key: &str, value: String) -> Result<()> {
+        let handle_guard = self.chitchat_handle.read().await;
+        let handle = handle_guard
+            .as_ref()
+            .ok_or_else(|| OrchestrationError::ClusterError("Not initialized".to_string()))?;
+        let chitchat = handle.chitchat();
+        chitchat.lock().await.self_node_state().set(key, value);
+        Ok(())
+    }
+
     /// Update this node's status in the cluster.
     pub async fn update_self_status(&self, status: NodeStatus) -> Result<()> {
         {
@@ -466,6 +515,66 @@ impl ClusterManager for ChitchatClusterManager {
         debug!("ChitchatClusterManager: Creating event subscription");
         Ok(self.event_rx.clone())
     }
+
+    async fn publish_container_status(
+        &self,
+        node_id: &NodeId,
+        report: ContainerStatusReport,
+    ) -> Result<()> {
+        // Gossip only carries this node's own state
+        if node_id.to_string() != self.config.node_id {
+            return Err(OrchestrationError::ClusterError(format!(
+                "Cannot publish container status for remote node {}",
+                node_id
+            )));
+        }
+        let json = serde_json::to_string(&report)
+            .map_err(|e| OrchestrationError::ClusterError(e.to_string()))?;
+        self.set_self_value(NODE_CONTAINER_STATUS_KEY, json).await
+    }
+
+    async fn container_status_reports(&self) -> Result<Vec<(NodeId, ContainerStatusReport)>> {
+        Ok(self.live_values(NODE_CONTAINER_STATUS_KEY).await)
+    }
+
+    async fn acknowledge_container_status(
+        &self,
+        node_id: &NodeId,
+        ack: ContainerStatusAck,
+    ) -> Result<()> {
+        let json = serde_json::to_string(&ack)
+            .map_err(|e| OrchestrationError::ClusterError(e.to_string()))?;
+        self.set_self_value(&format!("{}{}", CONTAINER_STATUS_ACK_PREFIX, node_id), json)
+            .await
+    }
+
+    async fn container_status_ack(&self, node_id: &NodeId) -> Result<Option<ContainerStatusAck>> {
+        let key = format!("{}{}", CONTAINER_STATUS_ACK_PREFIX, node_id);
+        Ok(self
+            .live_values::<ContainerStatusAck>(&key)
+            .await
+            .into_iter()
+            .map(|(_, ack)| ack)
+            .max_by_key(|ack| ack.revision))
+    }
+
+    async fn renew_lease(&self, node_id: &NodeId, lease: NodeLease) -> Result<()> {
+        if node_id.to_string() != self.config.node_id {
+            return Err(OrchestrationError::ClusterError(format!(
+                "Cannot renew the lease of remote node {}",
+                node_id
+            )));
+        }
+        let json = serde_json::to_string(&lease)
+            .map_err(|e| OrchestrationError::ClusterError(e.to_string()))?;
+        self.set_self_value(NODE_LEASE_KEY, json).await
+    }
+
+    async fn node_leases(&self) -> Result<Vec<(NodeId, NodeLease)>> {
+        // Nodes the failure detector declared dead drop out here, so their
+        // leases stop being renewed as far as the control plane can tell
+        Ok(self.live_values(NODE_LEASE_KEY).await)
+    }
 }
 
 impl Drop for ChitchatClusterManager {
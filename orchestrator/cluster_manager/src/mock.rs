@@ -11,7 +11,10 @@ use tokio::sync::{watch, RwLock};
 use tracing::{debug, info};
 
 use cluster_manager_interface::{ClusterEvent, ClusterManager};
-use orchestrator_shared_types::{Node, NodeId, NodeResources, NodeStatus, Result};
+use orchestrator_shared_types::{
+    ContainerStatusAck, ContainerStatusReport, Node, NodeId, NodeLease, NodeResources, NodeStatus,
+    Result,
+};
 
 /// Mock cluster manager that simulates cluster operations in-memory.
 #[derive(Debug)]
@@ -24,6 +27,12 @@ pub struct MockClusterManager {
     event_rx: watch::Receiver<Option<ClusterEvent>>,
     /// Whether the cluster is initialized
     initialized: Arc<RwLock<bool>>,
+    /// Latest container status report per node
+    status_reports: Arc<RwLock<HashMap<NodeId, ContainerStatusReport>>>,
+    /// Latest container status acknowledgement per node
+    status_acks: Arc<RwLock<HashMap<NodeId, ContainerStatusAck>>>,
+    /// Latest lease renewal per node
+    leases: Arc<RwLock<HashMap<NodeId, NodeLease>>>,
 }
 
 impl Default for MockClusterManager {
@@ -41,6 +50,9 @@ impl MockClusterManager {
             event_tx,
             event_rx,
             initialized: Arc::new(RwLock::new(false)),
+            status_reports: Arc::new(RwLock::new(HashMap::new())),
+            status_acks: Arc::new(RwLock::new(HashMap::new())),
+            leases: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -113,6 +125,8 @@ impl MockClusterManager {
                 memory_mb: 7168,
                 disk_mb: 92160,
             },
+            unschedulable: false,
+            resource_version: 0,
         }
     }
 }
@@ -139,6 +153,53 @@ impl ClusterManager for MockClusterManager {
         debug!("MockClusterManager: Creating event subscription");
         Ok(self.event_rx.clone())
     }
+
+    async fn publish_container_status(
+        &self,
+        node_id: &NodeId,
+        report: ContainerStatusReport,
+    ) -> Result<()> {
+        self.status_reports.write().await.insert(*node_id, report);
+        Ok(())
+    }
+
+    async fn container_status_reports(&self) -> Result<Vec<(NodeId, ContainerStatusReport)>> {
+        Ok(self
+            .status_reports
+            .read()
+            .await
+            .iter()
+            .map(|(node_id, report)| (*node_id, report.clone()))
+            .collect())
+    }
+
+    async fn acknowledge_container_status(
+        &self,
+        node_id: &NodeId,
+        ack: ContainerStatusAck,
+    ) -> Result<()> {
+        self.status_acks.write().await.insert(*node_id, ack);
+        Ok(())
+    }
+
+    async fn container_status_ack(&self, node_id: &NodeId) -> Result<Option<ContainerStatusAck>> {
+        Ok(self.status_acks.read().await.get(node_id).copied())
+    }
+
+    async fn renew_lease(&self, node_id: &NodeId, lease: NodeLease) -> Result<()> {
+        self.leases.write().await.insert(*node_id, lease);
+        Ok(())
+    }
+
+    async fn node_leases(&self) -> Result<Vec<(NodeId, NodeLease)>> {
+        Ok(self
+            .leases
+            .read()
+            .await
+            .iter()
+            .map(|(node_id, lease)| (*node_id, *lease))
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -267,4 +328,58 @@ mod tests {
         let result = manager.get_node(&nonexistent_id).await.unwrap();
         assert!(result.is_none());
     }
+    #[tokio::test]
+    async fn test_container_status_heartbeat() {
+        let manager = MockClusterManager::new();
+        let node_id = generate_node_id();
+        assert!(manager
+            .container_status_ack(&node_id)
+            .await
+            .unwrap()
+            .is_none());
+
+        let report = ContainerStatusReport {
+            epoch: 7,
+            revision: 1,
+            full: true,
+            ..Default::default()
+        };
+        manager
+            .publish_container_status(&node_id, report.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            manager.container_status_reports().await.unwrap(),
+            vec![(node_id, report)]
+        );
+
+        let ack = ContainerStatusAck {
+            epoch: 7,
+            revision: 1,
+        };
+        manager
+            .acknowledge_container_status(&node_id, ack)
+            .await
+            .unwrap();
+        assert_eq!(
+            manager.container_status_ack(&node_id).await.unwrap(),
+            Some(ack)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lease_renewal() {
+        let manager = MockClusterManager::new();
+        let node_id = generate_node_id();
+        assert!(manager.node_leases().await.unwrap().is_empty());
+
+        for renewals in 1..=2 {
+            let lease = NodeLease {
+                renewals,
+                duration_secs: 40,
+            };
+            manager.renew_lease(&node_id, lease).await.unwrap();
+            assert_eq!(manager.node_leases().await.unwrap(), vec![(node_id, lease)]);
+        }
+    }
 }
@@ -1,5 +1,7 @@
 use async_trait::async_trait;
-use orchestrator_shared_types::{Node, NodeId, OrchestrationError, Result};
+use orchestrator_shared_types::{
+    ContainerStatusAck, ContainerStatusReport, Node, NodeId, NodeLease, OrchestrationError, Result,
+};
 use tokio::sync::watch; // For broadcasting cluster changes 
 use downcast_rs::{Downcast, impl_downcast}; // For downcasting trait objects if needed 
 
@@ -18,6 +20,57 @@ pub trait ClusterManager: Downcast + Send + Sync {
     async fn list_nodes(&self) -> Result<Vec<Node>>;
     async fn subscribe_to_events(&self) -> Result<watch::Receiver<Option<ClusterEvent>>>;
 
+    // Container status heartbeats. Nodes publish the changes the control
+    // plane has not yet acknowledged; managers without a heartbeat channel
+    // keep the defaults and report nothing.
+
+    /// Publishes a node's container status report with its next heartbeat,
+    /// replacing the previous one.
+    async fn publish_container_status(
+        &self,
+        _node_id: &NodeId,
+        _report: ContainerStatusReport,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// The latest container status report heartbeated by each node.
+    async fn container_status_reports(&self) -> Result<Vec<(NodeId, ContainerStatusReport)>> {
+        Ok(Vec::new())
+    }
+
+    /// Records how much of a node's container status the control plane has applied.
+    async fn acknowledge_container_status(
+        &self,
+        _node_id: &NodeId,
+        _ack: ContainerStatusAck,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// The control plane's latest acknowledgement for a node, if any.
+    async fn container_status_ack(&self, _node_id: &NodeId) -> Result<Option<ContainerStatusAck>> {
+        Ok(None)
+    }
+
+    // Node leases. Each node renews its own lease; the control plane treats
+    // a node whose lease lapses as failed. Unlike status reports there is no
+    // safe empty answer, so managers without leases say so.
+
+    /// Publishes a renewal of a node's lease.
+    async fn renew_lease(&self, _node_id: &NodeId, _lease: NodeLease) -> Result<()> {
+        Err(OrchestrationError::NotImplemented(
+            "node leases are not supported by this cluster manager".to_string(),
+        ))
+    }
+
+    /// The latest lease renewed by each node.
+    async fn node_leases(&self) -> Result<Vec<(NodeId, NodeLease)>> {
+        Err(OrchestrationError::NotImplemented(
+            "node leases are not supported by this cluster manager".to_string(),
+        ))
+    }
+
     // Methods for leader election might go here if the manager handles it.
     // async fn is_leader(&self) -> Result<bool>;
 
@@ -0,0 +1,617 @@
+//! Registry credentials from the cloud a node runs in.
+//!
+//! A [`CredentialProvider`] obtains short-lived logins for the registries of
+//! one cloud from the node's ambient identity, so images in private ECR,
+//! Artifact Registry or ACR repositories pull without stored passwords:
+//!
+//! - [`CloudProvider::Ecr`] calls ECR's `GetAuthorizationToken`, signed with
+//!   the keys in `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+//!   `AWS_SESSION_TOKEN`, else with the instance profile's keys from the EC2
+//!   metadata service.
+//! - [`CloudProvider::Gcp`] logs in to `gcr.io` and `*-docker.pkg.dev` with an
+//!   access token of the instance's service account from the GCE metadata
+//!   server.
+//! - [`CloudProvider::Acr`] exchanges a managed identity token from the Azure
+//!   metadata service, of the identity in `AZURE_CLIENT_ID` if set, for an ACR
+//!   refresh token at the registry.
+//!
+//! The image manager keeps what a provider returns per registry and asks for
+//! new credentials once they are within [`REFRESH_BEFORE_EXPIRY`] of expiring.
+
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+
+use crate::image::ImageError;
+use crate::push::RegistryCredentials;
+
+/// How long before they expire provided credentials are replaced.
+pub const REFRESH_BEFORE_EXPIRY: Duration = Duration::from_secs(300);
+
+/// Credentials a provider returned, valid until `expires_at`.
+#[derive(Debug, Clone)]
+pub struct ExpiringCredentials {
+    pub credentials: RegistryCredentials,
+    /// When the registry stops accepting them (None = never).
+    pub expires_at: Option<SystemTime>,
+}
+
+impl ExpiringCredentials {
+    /// Whether the credentials should be replaced at `now`.
+    pub fn needs_refresh(&self, now: SystemTime) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| now + REFRESH_BEFORE_EXPIRY >= expires_at)
+    }
+}
+
+/// Source of credentials for some registries.
+#[async_trait]
+pub trait CredentialProvider: Send + Sync {
+    /// Whether this provider has credentials for `registry`.
+    fn handles(&self, registry: &str) -> bool;
+
+    /// Fetch fresh credentials for `registry`.
+    async fn credentials(&self, registry: &str) -> Result<ExpiringCredentials, ImageError>;
+}
+
+/// Clouds whose registries the built-in providers log in to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloudProvider {
+    /// AWS Elastic Container Registry.
+    Ecr,
+    /// Google Artifact Registry and Container Registry.
+    Gcp,
+    /// Azure Container Registry.
+    Acr,
+}
+
+impl CloudProvider {
+    /// Whether `registry` is a registry host of this cloud.
+    pub fn handles(&self, registry: &str) -> bool {
+        match self {
+            CloudProvider::Ecr => ecr_registry(registry).is_some(),
+            CloudProvider::Gcp => {
+                registry == "gcr.io"
+                    || registry.ends_with(".gcr.io")
+                    || registry.ends_with("-docker.pkg.dev")
+            }
+            CloudProvider::Acr => registry.ends_with(".azurecr.io"),
+        }
+    }
+}
+
+impl FromStr for CloudProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "ecr" | "aws" => Ok(CloudProvider::Ecr),
+            "gcp" | "gcr" | "gar" => Ok(CloudProvider::Gcp),
+            "acr" | "azure" => Ok(CloudProvider::Acr),
+            other => Err(format!(
+                "unknown credential provider '{}' (expected ecr, gcp or acr)",
+                other
+            )),
+        }
+    }
+}
+
+/// Account and region of an ECR registry host, e.g.
+/// `123456789012.dkr.ecr.eu-west-1.amazonaws.com`.
+fn ecr_registry(registry: &str) -> Option<(&str, &str)> {
+    let mut parts = registry.splitn(5, '.');
+    let account = parts.next()?;
+    if account.len() != 12 || !account.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (dkr, ecr, region, domain) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    let aws = matches!(domain, "amazonaws.com" | "amazonaws.com.cn");
+    (dkr == "dkr" && ecr == "ecr" && !region.is_empty() && aws).then_some((account, region))
+}
+
+#[cfg(feature = "image-pull")]
+pub(crate) use cloud::{aws_keys, AwsRequest};
+#[cfg(feature = "image-pull")]
+pub use cloud::{AcrProvider, EcrProvider, GcpProvider};
+
+#[cfg(feature = "image-pull")]
+impl CloudProvider {
+    /// The built-in provider for this cloud.
+    pub fn provider(&self) -> Result<std::sync::Arc<dyn CredentialProvider>, ImageError> {
+        Ok(match self {
+            CloudProvider::Ecr => std::sync::Arc::new(EcrProvider::new()?),
+            CloudProvider::Gcp => std::sync::Arc::new(GcpProvider::new()?),
+            CloudProvider::Acr => std::sync::Arc::new(AcrProvider::new()?),
+        })
+    }
+}
+
+#[cfg(feature = "image-pull")]
+mod cloud {
+    use std::time::UNIX_EPOCH;
+
+    use base64::Engine;
+    use chrono::{DateTime, Utc};
+    use hmac::{Hmac, Mac};
+    use reqwest::{Client, RequestBuilder, Response};
+    use serde::Deserialize;
+    use sha2::{Digest, Sha256};
+    use tracing::debug;
+
+    use super::*;
+
+    const EC2_METADATA: &str = "http://169.254.169.254";
+    const GCE_METADATA: &str = "http://metadata.google.internal";
+    const AZURE_METADATA: &str = "http://169.254.169.254";
+
+    const ECR_CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+    const ECR_GET_TOKEN: &str = "AmazonEC2ContainerRegistry_V20150921.GetAuthorizationToken";
+
+    /// Username ACR expects alongside a refresh token.
+    const ACR_TOKEN_USERNAME: &str = "00000000-0000-0000-0000-000000000000";
+
+    fn client() -> Result<Client, ImageError> {
+        Ok(Client::builder().timeout(Duration::from_secs(30)).build()?)
+    }
+
+    /// Send a request to a credential endpoint, failing on an error status.
+    async fn send(request: RequestBuilder, what: &str) -> Result<Response, ImageError> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ImageError::Credentials(format!("{}: {}", what, e)))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await.unwrap_or_default();
+        Err(ImageError::Credentials(format!(
+            "{} returned {}: {}",
+            what,
+            status,
+            body.trim()
+        )))
+    }
+
+    /// AWS access keys.
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    pub(crate) struct AwsKeys {
+        access_key_id: String,
+        secret_access_key: String,
+        #[serde(rename = "Token")]
+        session_token: Option<String>,
+    }
+
+    /// AWS keys from the environment, else from the instance profile.
+    pub(crate) async fn aws_keys(client: &Client) -> Result<AwsKeys, ImageError> {
+        if let (Ok(access_key_id), Ok(secret_access_key)) = (
+            std::env::var("AWS_ACCESS_KEY_ID"),
+            std::env::var("AWS_SECRET_ACCESS_KEY"),
+        ) {
+            return Ok(AwsKeys {
+                access_key_id,
+                secret_access_key,
+                session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+            });
+        }
+
+        // IMDSv2: every read needs a session token
+        let what = "EC2 metadata service";
+        let token = send(
+            client
+                .put(format!("{}/latest/api/token", EC2_METADATA))
+                .header("X-aws-ec2-metadata-token-ttl-seconds", "60"),
+            what,
+        )
+        .await?
+        .text()
+        .await?;
+        let roles_url = format!(
+            "{}/latest/meta-data/iam/security-credentials/",
+            EC2_METADATA
+        );
+        let get = |url: String| client.get(url).header("X-aws-ec2-metadata-token", &token);
+        let roles = send(get(roles_url.clone()), what).await?.text().await?;
+        let role = roles
+            .lines()
+            .next()
+            .map(str::trim)
+            .filter(|role| !role.is_empty())
+            .ok_or_else(|| ImageError::Credentials("instance has no IAM role".to_string()))?;
+        Ok(send(get(format!("{}{}", roles_url, role)), what)
+            .await?
+            .json()
+            .await?)
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct EcrAuthorization {
+        authorization_data: Vec<EcrAuthorizationData>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct EcrAuthorizationData {
+        /// Base64 of `AWS:<password>`.
+        authorization_token: String,
+        /// Seconds since the epoch.
+        expires_at: f64,
+    }
+
+    /// Logs in to ECR registries.
+    pub struct EcrProvider {
+        client: Client,
+    }
+
+    impl EcrProvider {
+        pub fn new() -> Result<Self, ImageError> {
+            Ok(Self { client: client()? })
+        }
+    }
+
+    #[async_trait]
+    impl CredentialProvider for EcrProvider {
+        fn handles(&self, registry: &str) -> bool {
+            CloudProvider::Ecr.handles(registry)
+        }
+
+        async fn credentials(&self, registry: &str) -> Result<ExpiringCredentials, ImageError> {
+            let (account, region) = ecr_registry(registry).ok_or_else(|| {
+                ImageError::Credentials(format!("{} is not an ECR registry", registry))
+            })?;
+            let keys = aws_keys(&self.client).await?;
+            let host = if registry.ends_with(".cn") {
+                format!("api.ecr.{}.amazonaws.com.cn", region)
+            } else {
+                format!("api.ecr.{}.amazonaws.com", region)
+            };
+            let body = serde_json::json!({ "registryIds": [account] }).to_string();
+            debug!("Getting an ECR authorization token for {}", registry);
+
+            let headers = AwsRequest {
+                method: "POST",
+                host: &host,
+                path: "/",
+                service: "ecr",
+                region,
+                headers: vec![
+                    ("content-type", ECR_CONTENT_TYPE.to_string()),
+                    ("x-amz-target", ECR_GET_TOKEN.to_string()),
+                ],
+                payload_hash: hex::encode(Sha256::digest(body.as_bytes())),
+            }
+            .sign(&keys, Utc::now());
+            let mut request = self.client.post(format!("https://{}/", host));
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            let authorization: EcrAuthorization =
+                send(request.body(body), "ECR GetAuthorizationToken")
+                    .await?
+                    .json()
+                    .await?;
+            let data = authorization
+                .authorization_data
+                .into_iter()
+                .next()
+                .ok_or_else(|| ImageError::Credentials("ECR returned no token".to_string()))?;
+
+            let decoded = base64::engine::general_purpose::STANDARD
+                .decode(&data.authorization_token)
+                .ok()
+                .and_then(|token| String::from_utf8(token).ok())
+                .ok_or_else(|| ImageError::Credentials("invalid ECR token".to_string()))?;
+            let (username, password) = decoded
+                .split_once(':')
+                .ok_or_else(|| ImageError::Credentials("invalid ECR token".to_string()))?;
+            Ok(ExpiringCredentials {
+                credentials: RegistryCredentials {
+                    username: username.to_string(),
+                    password: password.to_string(),
+                },
+                expires_at: Some(UNIX_EPOCH + Duration::from_secs_f64(data.expires_at.max(0.0))),
+            })
+        }
+    }
+
+    /// An AWS API request to sign with Signature Version 4.
+    pub(crate) struct AwsRequest<'a> {
+        pub method: &'a str,
+        pub host: &'a str,
+        /// Path, already URI-encoded.
+        pub path: &'a str,
+        pub service: &'a str,
+        pub region: &'a str,
+        /// Headers to sign besides `host` and `x-amz-*`, with lowercase names.
+        pub headers: Vec<(&'static str, String)>,
+        /// Hex SHA-256 of the body, or `UNSIGNED-PAYLOAD`.
+        pub payload_hash: String,
+    }
+
+    impl AwsRequest<'_> {
+        /// Headers to send with the request. `host` is signed but left for
+        /// the client to send.
+        pub(crate) fn sign(
+            self,
+            keys: &AwsKeys,
+            now: DateTime<Utc>,
+        ) -> Vec<(&'static str, String)> {
+            let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+            let date = &amz_date[..8];
+
+            let mut headers = self.headers;
+            headers.push(("host", self.host.to_string()));
+            headers.push(("x-amz-date", amz_date.clone()));
+            if let Some(token) = &keys.session_token {
+                headers.push(("x-amz-security-token", token.clone()));
+            }
+            if self.service == "s3" {
+                headers.push(("x-amz-content-sha256", self.payload_hash.clone()));
+            }
+            headers.sort_by_key(|(name, _)| *name);
+
+            let canonical_headers: String = headers
+                .iter()
+                .map(|(name, value)| format!("{}:{}\n", name, value))
+                .collect();
+            let signed_headers = headers
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>()
+                .join(";");
+            let canonical_request = format!(
+                "{}\n{}\n\n{}\n{}\n{}",
+                self.method, self.path, canonical_headers, signed_headers, self.payload_hash
+            );
+            let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+            let string_to_sign = format!(
+                "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+                amz_date,
+                scope,
+                hex::encode(Sha256::digest(canonical_request.as_bytes()))
+            );
+
+            let mut key = hmac_sha256(
+                format!("AWS4{}", keys.secret_access_key).as_bytes(),
+                date.as_bytes(),
+            );
+            for part in [self.region, self.service, "aws4_request"] {
+                key = hmac_sha256(&key, part.as_bytes());
+            }
+            let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+            headers.retain(|(name, _)| *name != "host");
+            headers.push((
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    keys.access_key_id, scope, signed_headers, signature
+                ),
+            ));
+            headers
+        }
+    }
+
+    fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any size");
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    #[derive(Deserialize)]
+    struct GceToken {
+        access_token: String,
+        expires_in: u64,
+    }
+
+    /// Logs in to Artifact Registry and Container Registry.
+    pub struct GcpProvider {
+        client: Client,
+    }
+
+    impl GcpProvider {
+        pub fn new() -> Result<Self, ImageError> {
+            Ok(Self { client: client()? })
+        }
+    }
+
+    #[async_trait]
+    impl CredentialProvider for GcpProvider {
+        fn handles(&self, registry: &str) -> bool {
+            CloudProvider::Gcp.handles(registry)
+        }
+
+        async fn credentials(&self, registry: &str) -> Result<ExpiringCredentials, ImageError> {
+            debug!("Getting a service account token for {}", registry);
+            let request = self
+                .client
+                .get(format!(
+                    "{}/computeMetadata/v1/instance/service-accounts/default/token",
+                    GCE_METADATA
+                ))
+                .header("Metadata-Flavor", "Google");
+            let token: GceToken = send(request, "GCE metadata server").await?.json().await?;
+            Ok(ExpiringCredentials {
+                credentials: RegistryCredentials {
+                    username: "oauth2accesstoken".to_string(),
+                    password: token.access_token,
+                },
+                expires_at: Some(SystemTime::now() + Duration::from_secs(token.expires_in)),
+            })
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct ManagedIdentityToken {
+        access_token: String,
+        /// Seconds since the epoch, as a string.
+        expires_on: String,
+    }
+
+    #[derive(Deserialize)]
+    struct AcrRefreshToken {
+        refresh_token: String,
+    }
+
+    /// Logs in to ACR registries.
+    pub struct AcrProvider {
+        client: Client,
+        /// User-assigned identity to use instead of the system-assigned one.
+        client_id: Option<String>,
+    }
+
+    impl AcrProvider {
+        pub fn new() -> Result<Self, ImageError> {
+            Ok(Self {
+                client: client()?,
+                client_id: std::env::var("AZURE_CLIENT_ID")
+                    .ok()
+                    .filter(|s| !s.is_empty()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl CredentialProvider for AcrProvider {
+        fn handles(&self, registry: &str) -> bool {
+            CloudProvider::Acr.handles(registry)
+        }
+
+        async fn credentials(&self, registry: &str) -> Result<ExpiringCredentials, ImageError> {
+            debug!("Getting a managed identity token for {}", registry);
+            let mut query = vec![
+                ("api-version", "2018-02-01"),
+                ("resource", "https://management.azure.com/"),
+            ];
+            if let Some(client_id) = &self.client_id {
+                query.push(("client_id", client_id));
+            }
+            let request = self
+                .client
+                .get(format!("{}/metadata/identity/oauth2/token", AZURE_METADATA))
+                .query(&query)
+                .header("Metadata", "true");
+            let identity: ManagedIdentityToken = send(request, "Azure metadata service")
+                .await?
+                .json()
+                .await?;
+
+            let request = self
+                .client
+                .post(format!("https://{}/oauth2/exchange", registry))
+                .form(&[
+                    ("grant_type", "access_token"),
+                    ("service", registry),
+                    ("access_token", &identity.access_token),
+                ]);
+            let token: AcrRefreshToken = send(request, "ACR token exchange").await?.json().await?;
+            Ok(ExpiringCredentials {
+                credentials: RegistryCredentials {
+                    username: ACR_TOKEN_USERNAME.to_string(),
+                    password: token.refresh_token,
+                },
+                expires_at: identity
+                    .expires_on
+                    .parse()
+                    .ok()
+                    .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_sign_aws_request() {
+            let keys = AwsKeys {
+                access_key_id: "AKIDEXAMPLE".to_string(),
+                secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+                session_token: None,
+            };
+            let now = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc);
+            let body = r#"{"registryIds":["123456789012"]}"#;
+            let headers = AwsRequest {
+                method: "POST",
+                host: "api.ecr.eu-west-1.amazonaws.com",
+                path: "/",
+                service: "ecr",
+                region: "eu-west-1",
+                headers: vec![
+                    ("content-type", ECR_CONTENT_TYPE.to_string()),
+                    ("x-amz-target", ECR_GET_TOKEN.to_string()),
+                ],
+                payload_hash: hex::encode(Sha256::digest(body.as_bytes())),
+            }
+            .sign(&keys, now);
+            let header = |name| {
+                headers
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.as_str())
+            };
+
+            assert_eq!(header("host"), None);
+            assert_eq!(header("x-amz-date"), Some("20240501T120000Z"));
+            assert_eq!(
+                header("authorization"),
+                Some(
+                    "AWS4-HMAC-SHA256 \
+                     Credential=AKIDEXAMPLE/20240501/eu-west-1/ecr/aws4_request, \
+                     SignedHeaders=content-type;host;x-amz-date;x-amz-target, \
+                     Signature=ab4d957ed02ca70ad41891b4826fba2abfa5d8f9427e2a6a473312311f934e0a"
+                )
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cloud_registries() {
+        assert_eq!(
+            ecr_registry("123456789012.dkr.ecr.eu-west-1.amazonaws.com"),
+            Some(("123456789012", "eu-west-1"))
+        );
+        assert_eq!(
+            ecr_registry("123456789012.dkr.ecr.eu-west-1.example.com"),
+            None
+        );
+        assert_eq!(ecr_registry("public.ecr.aws"), None);
+
+        assert!(CloudProvider::Gcp.handles("gcr.io"));
+        assert!(CloudProvider::Gcp.handles("eu.gcr.io"));
+        assert!(CloudProvider::Gcp.handles("europe-west1-docker.pkg.dev"));
+        assert!(!CloudProvider::Gcp.handles("ghcr.io"));
+        assert!(CloudProvider::Acr.handles("myregistry.azurecr.io"));
+        assert!(!CloudProvider::Acr.handles("registry-1.docker.io"));
+
+        assert_eq!("AWS".parse(), Ok(CloudProvider::Ecr));
+        assert!("docker".parse::<CloudProvider>().is_err());
+    }
+
+    #[test]
+    fn test_needs_refresh() {
+        let now = SystemTime::now();
+        let credentials = |expires_at| ExpiringCredentials {
+            credentials: RegistryCredentials {
+                username: "AWS".to_string(),
+                password: "token".to_string(),
+            },
+            expires_at,
+        };
+        assert!(!credentials(None).needs_refresh(now));
+        assert!(!credentials(Some(now + Duration::from_secs(3600))).needs_refresh(now));
+        // Replaced a little before the registry would start refusing them
+        assert!(credentials(Some(now + Duration::from_secs(60))).needs_refresh(now));
+    }
+}
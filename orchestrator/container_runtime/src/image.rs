@@ -3,17 +3,51 @@
 //! This module provides functionality for:
 //! - Parsing image references (registry/repo:tag format)
 //! - Pulling manifests from Docker Hub
-//! - Pulling and extracting image layers
+//! - Pulling gzip and zstd image layers and unpacking each one once, by digest
+//! - Assembling rootfs directories from hardlinks into the shared layers
+//! - Inspecting an image's config without pulling its layers
+//! - Scanning pulled images for vulnerabilities, recorded per digest
+//! - Mounting eStargz and zstd:chunked images lazily instead of pulling them
+//! - Pushing local OCI image layouts to a registry
+//! - Pulling through a registry mirror, such as the control plane's cache
+//! - Logging in to registries with configured or cloud-provided credentials
 //! - Managing a local image cache
-
+//!
+//! The cache keeps downloaded layer blobs in `layers/`, their unpacked
+//! contents in `extracted/` and one rootfs per image in `rootfs/`. A rootfs
+//! holds no file data of its own: every file is a hardlink into the
+//! extracted layer that last wrote it, so images built on the same base
+//! layers share them on disk. Whiteouts are applied while linking, and a
+//! rootfs is assembled under `tmp/` and renamed into place once complete.
+//! Since its files are shared, a rootfs must never be written to: runtimes
+//! mount it as the read-only lower layer of an overlay per container.
+
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::io;
+use std::sync::Arc;
 use thiserror::Error;
 use tracing::info;
 use serde::{Deserialize, Serialize};
 
+use container_runtime_interface::{ImageInspection, ImageLayer, ImageScan, PullPriority};
+
+use crate::credentials::{CredentialProvider, ExpiringCredentials};
+use crate::lazy::LazyPuller;
+use crate::pull_queue::ImagePullQueue;
+use crate::push::{OciLayout, PushSummary, RegistryCredentials};
+use crate::scan::ImageScanner;
+
 #[cfg(feature = "image-pull")]
 use std::io::Write;
+#[cfg(feature = "image-pull")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(feature = "image-pull")]
+use crate::pull_queue::BandwidthLimiter;
+
+#[cfg(feature = "image-pull")]
+use crate::lazy::LazyFormat;
 
 #[cfg(feature = "image-pull")]
 use tracing::{debug, warn};
@@ -60,6 +94,21 @@ pub enum ImageError {
 
     #[error("Image pull feature not enabled")]
     FeatureNotEnabled,
+
+    #[error("No image scanner configured")]
+    ScannerNotConfigured,
+
+    #[error("Image scan failed: {0}")]
+    Scan(String),
+
+    #[error("Lazy pull failed: {0}")]
+    LazyPull(String),
+
+    #[error("Invalid OCI image layout: {0}")]
+    InvalidLayout(String),
+
+    #[error("Failed to get registry credentials: {0}")]
+    Credentials(String),
 }
 
 /// Parsed image reference.
@@ -121,6 +170,62 @@ pub struct ManifestLayer {
     pub media_type: String,
     pub size: i64,
     pub digest: String,
+    /// Marks eStargz and zstd:chunked layers, among others.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub annotations: HashMap<String, String>,
+}
+
+/// How a layer tarball is compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerCompression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl LayerCompression {
+    /// The compression of a Docker or OCI layer media type.
+    pub fn from_media_type(media_type: &str) -> Result<Self, ImageError> {
+        if media_type.contains("gzip") {
+            Ok(LayerCompression::Gzip)
+        } else if media_type.ends_with("+zstd") {
+            Ok(LayerCompression::Zstd)
+        } else if media_type.contains('+') {
+            Err(ImageError::UnsupportedMediaType(media_type.to_string()))
+        } else {
+            Ok(LayerCompression::None)
+        }
+    }
+}
+
+/// Image config blob, the fields inspection reports.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ImageConfig {
+    #[serde(default)]
+    pub architecture: String,
+    #[serde(default)]
+    pub os: String,
+    #[serde(default)]
+    pub variant: Option<String>,
+    /// Defaults for containers run from the image.
+    #[serde(default)]
+    pub config: Option<ImageRunConfig>,
+}
+
+/// Container defaults in an image config. Registries send `null` for unset fields.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ImageRunConfig {
+    #[serde(default)]
+    pub env: Option<Vec<String>>,
+    #[serde(default)]
+    pub entrypoint: Option<Vec<String>>,
+    #[serde(default)]
+    pub cmd: Option<Vec<String>>,
+    #[serde(default)]
+    pub exposed_ports: Option<HashMap<String, serde_json::Value>>,
+    #[serde(default)]
+    pub labels: Option<HashMap<String, String>>,
 }
 
 /// Docker Hub authentication token response.
@@ -139,6 +244,24 @@ pub struct ImageManager {
     /// HTTP client for registry requests
     #[cfg(feature = "image-pull")]
     client: Client,
+    /// Scans each image after it is pulled
+    #[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+    scanner: Option<ImageScanner>,
+    /// Mounts images with seekable layers instead of pulling them
+    #[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+    lazy_puller: Option<LazyPuller>,
+    /// Logins for pulls and pushes, by registry host
+    #[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+    credentials: HashMap<String, RegistryCredentials>,
+    /// Sources of short-lived logins for registries without one
+    #[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+    credential_providers: Vec<Arc<dyn CredentialProvider>>,
+    /// Logins the providers returned, by registry host
+    #[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+    provided_credentials: tokio::sync::Mutex<HashMap<String, ExpiringCredentials>>,
+    /// Pull-through cache every pull goes through, e.g. `http://10.0.0.1:5000`
+    #[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+    mirror: Option<String>,
 }
 
 impl ImageManager {
@@ -146,6 +269,8 @@ impl ImageManager {
     pub fn new(cache_dir: impl Into<PathBuf>) -> Result<Self, ImageError> {
         let cache_dir = cache_dir.into();
         std::fs::create_dir_all(&cache_dir)?;
+        // Left over from pulls interrupted by a restart
+        let _ = std::fs::remove_dir_all(cache_dir.join("tmp"));
 
         Ok(Self {
             cache_dir,
@@ -153,9 +278,53 @@ impl ImageManager {
             client: Client::builder()
                 .user_agent("ai-native-orchestrator/0.1")
                 .build()?,
+            scanner: None,
+            lazy_puller: None,
+            credentials: HashMap::new(),
+            credential_providers: Vec::new(),
+            provided_credentials: tokio::sync::Mutex::new(HashMap::new()),
+            mirror: None,
         })
     }
 
+    /// Scan every image for vulnerabilities once it is pulled.
+    pub fn with_scanner(mut self, scanner: ImageScanner) -> Self {
+        self.scanner = Some(scanner);
+        self
+    }
+
+    /// Mount eStargz and zstd:chunked images lazily rather than pulling them.
+    pub fn with_lazy_puller(mut self, lazy_puller: LazyPuller) -> Self {
+        self.lazy_puller = Some(lazy_puller);
+        self
+    }
+
+    /// Pull every image through a pull-through cache serving the registry
+    /// API at `mirror`. The image's own registry is passed as the `ns` query
+    /// parameter.
+    pub fn with_mirror(mut self, mirror: impl Into<String>) -> Self {
+        self.mirror = Some(mirror.into().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Log in to `registry` when pulling from or pushing to it.
+    pub fn with_registry_credentials(
+        mut self,
+        registry: impl Into<String>,
+        credentials: RegistryCredentials,
+    ) -> Self {
+        self.credentials.insert(registry.into(), credentials);
+        self
+    }
+
+    /// Log in to the registries `provider` handles with the credentials it
+    /// fetches, unless credentials for the registry are configured.
+    /// Providers are asked in the order they were added.
+    pub fn with_credential_provider(mut self, provider: Arc<dyn CredentialProvider>) -> Self {
+        self.credential_providers.push(provider);
+        self
+    }
+
     /// Get the cache directory path.
     pub fn cache_dir(&self) -> &Path {
         &self.cache_dir
@@ -254,33 +423,140 @@ impl ImageManager {
         Ok(token_response.token)
     }
 
+    /// A GET of `path`, e.g. "manifests/latest", in the repository of
+    /// `image_ref`: through the mirror when one is set, else from the
+    /// registry itself.
+    #[cfg(feature = "image-pull")]
+    async fn registry_get(
+        &self,
+        image_ref: &ImageReference,
+        path: &str,
+    ) -> Result<reqwest::RequestBuilder, ImageError> {
+        if let Some(mirror) = &self.mirror {
+            let url = format!("{}/v2/{}/{}", mirror, image_ref.repository, path);
+            debug!("Fetching {} through mirror", url);
+            return Ok(self.client.get(&url).query(&[("ns", &image_ref.registry)]));
+        }
+
+        let url = format!(
+            "https://{}/v2/{}/{}",
+            image_ref.registry, image_ref.repository, path
+        );
+        debug!("Fetching {}", url);
+        let request = self.client.get(&url);
+
+        if let Some(credentials) = self.registry_credentials(&image_ref.registry).await? {
+            let base = reqwest::Url::parse(&format!("https://{}/", image_ref.registry))
+                .map_err(|e| ImageError::InvalidReference(format!("{}: {}", image_ref, e)))?;
+            let auth = crate::push::authorize(
+                &self.client,
+                &base,
+                &image_ref.repository,
+                "pull",
+                Some(&credentials),
+            )
+            .await?;
+            return Ok(auth.apply(request));
+        }
+
+        // Anonymous pulls need a token on Docker Hub only
+        if image_ref.registry == "registry-1.docker.io" {
+            let token = self.get_docker_hub_token(&image_ref.repository).await?;
+            return Ok(request.header("Authorization", format!("Bearer {}", token)));
+        }
+        Ok(request)
+    }
+
+    /// Credentials for `registry`: configured ones, else ones from the first
+    /// provider handling it, fetched again shortly before they expire.
+    #[cfg(feature = "image-pull")]
+    async fn registry_credentials(
+        &self,
+        registry: &str,
+    ) -> Result<Option<RegistryCredentials>, ImageError> {
+        if let Some(credentials) = self.credentials.get(registry) {
+            return Ok(Some(credentials.clone()));
+        }
+        let Some(provider) = self
+            .credential_providers
+            .iter()
+            .find(|p| p.handles(registry))
+        else {
+            return Ok(None);
+        };
+
+        // Held while fetching, so concurrent pulls share one fetch
+        let mut provided = self.provided_credentials.lock().await;
+        if let Some(cached) = provided.get(registry) {
+            if !cached.needs_refresh(std::time::SystemTime::now()) {
+                return Ok(Some(cached.credentials.clone()));
+            }
+        }
+        debug!("Fetching credentials for {}", registry);
+        let fresh = provider.credentials(registry).await?;
+        provided.insert(registry.to_string(), fresh.clone());
+        Ok(Some(fresh.credentials))
+    }
+
     /// Pull the manifest for an image.
     #[cfg(feature = "image-pull")]
     pub async fn pull_manifest(&self, image_ref: &ImageReference) -> Result<Manifest, ImageError> {
+        let (_, manifest) = self.resolve_manifest(image_ref).await?;
+        Ok(manifest)
+    }
+
+    /// Pull the manifest for an image along with its digest.
+    #[cfg(feature = "image-pull")]
+    async fn resolve_manifest(
+        &self,
+        image_ref: &ImageReference,
+    ) -> Result<(String, Manifest), ImageError> {
         info!("Pulling manifest for {}", image_ref);
 
-        // Get authentication token (Docker Hub only for now)
-        let token = if image_ref.registry == "registry-1.docker.io" {
-            Some(self.get_docker_hub_token(&image_ref.repository).await?)
-        } else {
-            None
-        };
+        let request = self
+            .registry_get(image_ref, &format!("manifests/{}", image_ref.tag))
+            .await?
+            .header(
+                "Accept",
+                "application/vnd.docker.distribution.manifest.v2+json, application/vnd.oci.image.manifest.v1+json",
+            );
 
-        let manifest_url = format!(
-            "https://{}/v2/{}/manifests/{}",
-            image_ref.registry, image_ref.repository, image_ref.tag
-        );
+        let response = request.send().await?;
 
-        debug!("Fetching manifest from {}", manifest_url);
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(ImageError::Registry { status, message });
+        }
 
-        let mut request = self.client.get(&manifest_url).header(
-            "Accept",
-            "application/vnd.docker.distribution.manifest.v2+json, application/vnd.oci.image.manifest.v1+json",
+        let body = response.bytes().await?;
+        let digest = format!("sha256:{}", hex::encode(Sha256::digest(&body)));
+        let manifest: Manifest = serde_json::from_slice(&body)?;
+        debug!(
+            "Got manifest {} with {} layers",
+            digest,
+            manifest.layers.len()
         );
 
-        if let Some(token) = token {
-            request = request.header("Authorization", format!("Bearer {}", token));
-        }
+        Ok((digest, manifest))
+    }
+
+    /// Pull manifest (stub for when feature is disabled).
+    #[cfg(not(feature = "image-pull"))]
+    pub async fn pull_manifest(&self, _image_ref: &ImageReference) -> Result<Manifest, ImageError> {
+        Err(ImageError::FeatureNotEnabled)
+    }
+
+    /// Pull and verify the config blob of an image.
+    #[cfg(feature = "image-pull")]
+    async fn pull_config(
+        &self,
+        image_ref: &ImageReference,
+        config: &ManifestConfig,
+    ) -> Result<ImageConfig, ImageError> {
+        let request = self
+            .registry_get(image_ref, &format!("blobs/{}", config.digest))
+            .await?;
 
         let response = request.send().await?;
 
@@ -290,15 +566,31 @@ impl ImageManager {
             return Err(ImageError::Registry { status, message });
         }
 
-        let manifest: Manifest = response.json().await?;
-        debug!("Got manifest with {} layers", manifest.layers.len());
+        let body = response.bytes().await?;
+        let computed_digest = format!("sha256:{}", hex::encode(Sha256::digest(&body)));
+        if computed_digest != config.digest {
+            return Err(ImageError::DigestMismatch {
+                expected: config.digest.clone(),
+                actual: computed_digest,
+            });
+        }
 
-        Ok(manifest)
+        Ok(serde_json::from_slice(&body)?)
     }
 
-    /// Pull manifest (stub for when feature is disabled).
+    /// Resolve an image in its registry and read its config, without
+    /// pulling its layers.
+    #[cfg(feature = "image-pull")]
+    pub async fn inspect(&self, image: &str) -> Result<ImageInspection, ImageError> {
+        let image_ref = Self::parse_image_ref(image)?;
+        let (digest, manifest) = self.resolve_manifest(&image_ref).await?;
+        let config = self.pull_config(&image_ref, &manifest.config).await?;
+        Ok(inspection(image, digest, &manifest, config))
+    }
+
+    /// Inspect image (stub for when feature is disabled).
     #[cfg(not(feature = "image-pull"))]
-    pub async fn pull_manifest(&self, _image_ref: &ImageReference) -> Result<Manifest, ImageError> {
+    pub async fn inspect(&self, _image: &str) -> Result<ImageInspection, ImageError> {
         Err(ImageError::FeatureNotEnabled)
     }
 
@@ -308,6 +600,16 @@ impl ImageManager {
         &self,
         image_ref: &ImageReference,
         layer: &ManifestLayer,
+    ) -> Result<PathBuf, ImageError> {
+        self.fetch_layer(image_ref, layer, None).await
+    }
+
+    #[cfg(feature = "image-pull")]
+    async fn fetch_layer(
+        &self,
+        image_ref: &ImageReference,
+        layer: &ManifestLayer,
+        bandwidth: Option<&BandwidthLimiter>,
     ) -> Result<PathBuf, ImageError> {
         let digest = &layer.digest;
         let layer_path = self.cache_dir.join("layers").join(digest.replace(':', "_"));
@@ -323,24 +625,9 @@ impl ImageManager {
         // Ensure layers directory exists
         std::fs::create_dir_all(self.cache_dir.join("layers"))?;
 
-        // Get authentication token
-        let token = if image_ref.registry == "registry-1.docker.io" {
-            Some(self.get_docker_hub_token(&image_ref.repository).await?)
-        } else {
-            None
-        };
-
-        let blob_url = format!(
-            "https://{}/v2/{}/blobs/{}",
-            image_ref.registry, image_ref.repository, digest
-        );
-
-        debug!("Fetching blob from {}", blob_url);
-
-        let mut request = self.client.get(&blob_url);
-        if let Some(token) = token {
-            request = request.header("Authorization", format!("Bearer {}", token));
-        }
+        let request = self
+            .registry_get(image_ref, &format!("blobs/{}", digest))
+            .await?;
 
         let response = request.send().await?;
 
@@ -357,6 +644,9 @@ impl ImageManager {
 
         while let Some(chunk) = stream.next().await {
             let chunk = chunk?;
+            if let Some(limiter) = bandwidth {
+                limiter.consume(chunk.len() as u64).await;
+            }
             hasher.update(&chunk);
             file.write_all(&chunk)?;
         }
@@ -391,6 +681,16 @@ impl ImageManager {
         &self,
         image_ref: &ImageReference,
         manifest: &Manifest,
+    ) -> Result<PathBuf, ImageError> {
+        self.fetch_layers(image_ref, manifest, None).await
+    }
+
+    #[cfg(feature = "image-pull")]
+    async fn fetch_layers(
+        &self,
+        image_ref: &ImageReference,
+        manifest: &Manifest,
+        bandwidth: Option<&BandwidthLimiter>,
     ) -> Result<PathBuf, ImageError> {
         let image_id = format!("{}_{}",
             image_ref.repository.replace('/', "_"),
@@ -404,25 +704,107 @@ impl ImageManager {
             return Ok(rootfs_path);
         }
 
-        info!("Extracting {} layers to {:?}", manifest.layers.len(), rootfs_path);
+        if let Some(mountpoint) = self.mount_lazily(image_ref, manifest, &image_id).await? {
+            return Ok(mountpoint);
+        }
 
-        // Create rootfs directory
-        std::fs::create_dir_all(&rootfs_path)?;
+        info!("Assembling {} layers into {:?}", manifest.layers.len(), rootfs_path);
+        let staging = self.staging_dir(&image_id)?;
 
-        // Pull and extract each layer in order
+        // Link each layer's files over those of the layers below it
         for (i, layer) in manifest.layers.iter().enumerate() {
             info!("Processing layer {}/{}: {}", i + 1, manifest.layers.len(), layer.digest);
 
-            let layer_path = self.pull_layer(image_ref, layer).await?;
-
-            // Extract the layer
-            self.extract_layer(&layer_path, &rootfs_path, &layer.media_type)?;
+            let layer_dir = self.unpack_layer(image_ref, layer, bandwidth).await?;
+            link_layer(&layer_dir, &staging)?;
         }
 
-        info!("Rootfs extraction complete: {:?}", rootfs_path);
+        commit_staged(&staging, &rootfs_path)?;
+        info!("Rootfs assembly complete: {:?}", rootfs_path);
         Ok(rootfs_path)
     }
 
+    /// Mount an image whose layers are all seekable through the lazy puller.
+    /// Mounts are not kept across restarts, so the helper is asked again for
+    /// each new container. A failed mount falls back to a full pull.
+    #[cfg(feature = "image-pull")]
+    async fn mount_lazily(
+        &self,
+        image_ref: &ImageReference,
+        manifest: &Manifest,
+        image_id: &str,
+    ) -> Result<Option<PathBuf>, ImageError> {
+        let (Some(lazy_puller), Some(format)) =
+            (&self.lazy_puller, LazyFormat::of_manifest(manifest))
+        else {
+            return Ok(None);
+        };
+        let mountpoint = self.cache_dir.join("lazy").join(image_id);
+        std::fs::create_dir_all(&mountpoint)?;
+        match lazy_puller
+            .mount(image_ref, manifest, format, &mountpoint)
+            .await
+        {
+            Ok(()) => {
+                info!(
+                    "Mounted {} {} lazily at {:?}",
+                    format.as_str(),
+                    image_ref,
+                    mountpoint
+                );
+                Ok(Some(mountpoint))
+            }
+            Err(e) => {
+                warn!("{}; pulling {} in full", e, image_ref);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Unpack a layer into the layer store, once per digest, downloading its
+    /// blob first if needed.
+    #[cfg(feature = "image-pull")]
+    async fn unpack_layer(
+        &self,
+        image_ref: &ImageReference,
+        layer: &ManifestLayer,
+        bandwidth: Option<&BandwidthLimiter>,
+    ) -> Result<PathBuf, ImageError> {
+        let layer_dir = self.layer_dir(&layer.digest);
+        if layer_dir.exists() {
+            debug!("Layer {} already unpacked", layer.digest);
+            return Ok(layer_dir);
+        }
+
+        let blob = self.fetch_layer(image_ref, layer, bandwidth).await?;
+        let staging = self.staging_dir(&layer.digest.replace(':', "_"))?;
+        self.extract_layer(&blob, &staging, &layer.media_type)?;
+        commit_staged(&staging, &layer_dir)?;
+        Ok(layer_dir)
+    }
+
+    /// Where the unpacked contents of the layer `digest` are stored.
+    #[cfg(feature = "image-pull")]
+    fn layer_dir(&self, digest: &str) -> PathBuf {
+        self.cache_dir
+            .join("extracted")
+            .join(digest.replace(':', "_"))
+    }
+
+    /// A fresh directory under `tmp/` to build `name` in, on the same
+    /// filesystem as the cache so it can be hardlinked and renamed.
+    #[cfg(feature = "image-pull")]
+    fn staging_dir(&self, name: &str) -> Result<PathBuf, ImageError> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let dir = self.cache_dir.join("tmp").join(format!(
+            "{}.{}",
+            name,
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
     /// Extract layers (stub for when feature is disabled).
     #[cfg(not(feature = "image-pull"))]
     pub async fn extract_layers(
@@ -433,31 +815,37 @@ impl ImageManager {
         Err(ImageError::FeatureNotEnabled)
     }
 
-    /// Extract a single layer tarball to the rootfs.
+    /// Extract a single layer tarball into its own directory.
     #[cfg(feature = "image-pull")]
     fn extract_layer(&self, layer_path: &Path, rootfs: &Path, media_type: &str) -> Result<(), ImageError> {
+        let compression = LayerCompression::from_media_type(media_type)?;
         let file = std::fs::File::open(layer_path)?;
 
-        // Determine if layer is gzipped
-        let is_gzipped = media_type.contains("gzip")
-            || media_type.contains(".tar.gzip")
-            || media_type.ends_with("+gzip");
-
-        if is_gzipped {
-            debug!("Extracting gzipped layer");
-            let decoder = GzDecoder::new(file);
-            let mut archive = Archive::new(decoder);
-            self.extract_archive(&mut archive, rootfs)?;
-        } else {
-            debug!("Extracting uncompressed layer");
-            let mut archive = Archive::new(file);
-            self.extract_archive(&mut archive, rootfs)?;
+        match compression {
+            LayerCompression::Gzip => {
+                debug!("Extracting gzipped layer");
+                let decoder = GzDecoder::new(file);
+                let mut archive = Archive::new(decoder);
+                self.extract_archive(&mut archive, rootfs)?;
+            }
+            LayerCompression::Zstd => {
+                debug!("Extracting zstd layer");
+                let decoder = zstd::stream::read::Decoder::new(file)?;
+                let mut archive = Archive::new(decoder);
+                self.extract_archive(&mut archive, rootfs)?;
+            }
+            LayerCompression::None => {
+                debug!("Extracting uncompressed layer");
+                let mut archive = Archive::new(file);
+                self.extract_archive(&mut archive, rootfs)?;
+            }
         }
 
         Ok(())
     }
 
-    /// Extract a tar archive, handling OCI whiteouts.
+    /// Extract a tar archive. Whiteout markers are kept as files and
+    /// applied when the layer is linked into a rootfs.
     #[cfg(feature = "image-pull")]
     fn extract_archive<R: io::Read>(&self, archive: &mut Archive<R>, rootfs: &Path) -> Result<(), ImageError> {
         for entry in archive.entries()? {
@@ -466,52 +854,6 @@ impl ImageManager {
             let path_str = path.to_string_lossy().to_string(); // Clone to owned String
             let path_owned = path.to_path_buf(); // Clone for later use
 
-            // Handle OCI whiteout files
-            if let Some(filename) = path_owned.file_name() {
-                let filename_str = filename.to_string_lossy();
-
-                if filename_str.starts_with(".wh.") {
-                    // Whiteout file - delete the corresponding file/directory
-                    let target_name = &filename_str[4..]; // Remove ".wh." prefix
-                    let target_path = if let Some(parent) = path_owned.parent() {
-                        rootfs.join(parent).join(target_name)
-                    } else {
-                        rootfs.join(target_name)
-                    };
-
-                    if target_path.exists() {
-                        debug!("Applying whiteout: removing {:?}", target_path);
-                        if target_path.is_dir() {
-                            std::fs::remove_dir_all(&target_path)?;
-                        } else {
-                            std::fs::remove_file(&target_path)?;
-                        }
-                    }
-                    continue;
-                }
-
-                // Opaque whiteout - clear the directory
-                if filename_str == ".wh..wh..opq" {
-                    if let Some(parent) = path_owned.parent() {
-                        let dir_path = rootfs.join(parent);
-                        if dir_path.exists() {
-                            debug!("Applying opaque whiteout: clearing {:?}", dir_path);
-                            // Remove all contents but keep the directory
-                            for dir_entry in std::fs::read_dir(&dir_path)? {
-                                let dir_entry = dir_entry?;
-                                let entry_path = dir_entry.path();
-                                if entry_path.is_dir() {
-                                    std::fs::remove_dir_all(&entry_path)?;
-                                } else {
-                                    std::fs::remove_file(&entry_path)?;
-                                }
-                            }
-                        }
-                    }
-                    continue;
-                }
-            }
-
             // Skip problematic paths
             if path_str.starts_with('/') || path_str.contains("..") {
                 warn!("Skipping potentially unsafe path: {}", path_str);
@@ -554,8 +896,10 @@ impl ImageManager {
         }
 
         // Pull and extract
-        let manifest = self.pull_manifest(&image_ref).await?;
-        self.extract_layers(&image_ref, &manifest).await
+        let (digest, manifest) = self.resolve_manifest(&image_ref).await?;
+        let rootfs = self.extract_layers(&image_ref, &manifest).await?;
+        self.scan_pulled(image, &digest, &rootfs).await;
+        Ok(rootfs)
     }
 
     /// Get rootfs (stub for when feature is disabled).
@@ -564,6 +908,211 @@ impl ImageManager {
         Err(ImageError::FeatureNotEnabled)
     }
 
+    /// Like [`get_rootfs`](Self::get_rootfs), but an image that is not cached
+    /// waits for a slot on the node's pull queue and downloads within its
+    /// bandwidth limit.
+    #[cfg(feature = "image-pull")]
+    pub async fn get_rootfs_queued(
+        &self,
+        image: &str,
+        queue: &Arc<ImagePullQueue>,
+        priority: PullPriority,
+    ) -> Result<PathBuf, ImageError> {
+        let image_ref = Self::parse_image_ref(image)?;
+        let image_id = format!("{}_{}",
+            image_ref.repository.replace('/', "_"),
+            image_ref.tag
+        );
+        let rootfs_path = self.cache_dir.join("rootfs").join(&image_id);
+
+        if rootfs_path.exists() {
+            info!("Using cached rootfs for {}", image);
+            return Ok(rootfs_path);
+        }
+
+        debug!("Queueing {:?} pull of {}", priority, image);
+        let permit = queue.acquire(priority).await;
+
+        // Another pull of the same image may have finished while we waited
+        if rootfs_path.exists() {
+            info!("Using cached rootfs for {}", image);
+            return Ok(rootfs_path);
+        }
+
+        let (digest, manifest) = self.resolve_manifest(&image_ref).await?;
+        let rootfs = self
+            .fetch_layers(&image_ref, &manifest, permit.bandwidth())
+            .await?;
+        // Scanning does not hold up the node's other pulls
+        drop(permit);
+        self.scan_pulled(image, &digest, &rootfs).await;
+        Ok(rootfs)
+    }
+
+    /// Get rootfs through the pull queue (stub for when feature is disabled).
+    #[cfg(not(feature = "image-pull"))]
+    pub async fn get_rootfs_queued(
+        &self,
+        _image: &str,
+        _queue: &Arc<ImagePullQueue>,
+        _priority: PullPriority,
+    ) -> Result<PathBuf, ImageError> {
+        Err(ImageError::FeatureNotEnabled)
+    }
+
+    /// Push the manifest of a local OCI image layout tagged like `image_ref`,
+    /// and every blob it references that the registry lacks, to `image_ref`.
+    #[cfg(feature = "image-pull")]
+    pub async fn push(
+        &self,
+        image_ref: &ImageReference,
+        layout: &OciLayout,
+    ) -> Result<PushSummary, ImageError> {
+        let credentials = self.registry_credentials(&image_ref.registry).await?;
+        crate::push::push_layout(&self.client, credentials.as_ref(), layout, image_ref).await
+    }
+
+    /// Push an image (stub for when feature is disabled).
+    #[cfg(not(feature = "image-pull"))]
+    pub async fn push(
+        &self,
+        _image_ref: &ImageReference,
+        _layout: &OciLayout,
+    ) -> Result<PushSummary, ImageError> {
+        Err(ImageError::FeatureNotEnabled)
+    }
+
+    /// Pull the layers of an OCI artifact, e.g. a model pushed with ORAS, into
+    /// `dest`, each as a file named by its [`ARTIFACT_TITLE_ANNOTATION`] or
+    /// else by its digest. Returns the artifact's manifest digest.
+    #[cfg(feature = "image-pull")]
+    pub async fn pull_artifact(&self, image: &str, dest: &Path) -> Result<String, ImageError> {
+        let image_ref = Self::parse_image_ref(image)?;
+        let (digest, manifest) = self.resolve_manifest(&image_ref).await?;
+        std::fs::create_dir_all(dest)?;
+        for layer in &manifest.layers {
+            let target = dest.join(artifact_file_name(layer)?);
+            // Moved out of the layer cache so evicting the artifact frees it
+            let blob = self.fetch_layer(&image_ref, layer, None).await?;
+            if std::fs::rename(&blob, &target).is_err() {
+                std::fs::copy(&blob, &target)?;
+                std::fs::remove_file(&blob)?;
+            }
+        }
+        info!(
+            "Pulled artifact {} ({} files)",
+            image_ref,
+            manifest.layers.len()
+        );
+        Ok(digest)
+    }
+
+    /// Pull an artifact (stub for when feature is disabled).
+    #[cfg(not(feature = "image-pull"))]
+    pub async fn pull_artifact(&self, _image: &str, _dest: &Path) -> Result<String, ImageError> {
+        Err(ImageError::FeatureNotEnabled)
+    }
+
+    /// Scan a freshly pulled image when a scanner is configured. A failed
+    /// scan is logged and leaves the image usable.
+    #[cfg(feature = "image-pull")]
+    async fn scan_pulled(&self, image: &str, digest: &str, rootfs: &Path) {
+        if self.scanner.is_none() {
+            return;
+        }
+        // Scanning would read the whole image before its container starts
+        if rootfs.starts_with(self.cache_dir.join("lazy")) {
+            debug!("Not scanning lazily mounted {}", image);
+            return;
+        }
+        if let Err(e) = self.scan_rootfs(image, digest, rootfs).await {
+            warn!("Failed to scan {} for vulnerabilities: {}", image, e);
+        }
+    }
+
+    #[cfg(feature = "image-pull")]
+    async fn scan_rootfs(
+        &self,
+        image: &str,
+        digest: &str,
+        rootfs: &Path,
+    ) -> Result<ImageScan, ImageError> {
+        let scanner = self
+            .scanner
+            .as_ref()
+            .ok_or(ImageError::ScannerNotConfigured)?;
+        info!("Scanning {} ({}) with {}", image, digest, scanner.name());
+        let scan = ImageScan {
+            image: image.to_string(),
+            digest: digest.to_string(),
+            scanner: scanner.name().to_string(),
+            scanned_at: std::time::SystemTime::now(),
+            vulnerabilities: scanner.scan(rootfs).await?,
+        };
+        info!(
+            "{} has {} known vulnerabilities",
+            image,
+            scan.vulnerabilities.len()
+        );
+
+        std::fs::create_dir_all(self.cache_dir.join("scans"))?;
+        std::fs::write(self.scan_path(digest), serde_json::to_vec(&scan)?)?;
+        Ok(scan)
+    }
+
+    /// Scan an image for vulnerabilities, pulling it first if needed. The
+    /// recorded scan is returned when the image's digest was scanned before.
+    #[cfg(feature = "image-pull")]
+    pub async fn scan(&self, image: &str) -> Result<ImageScan, ImageError> {
+        if self.scanner.is_none() {
+            return Err(ImageError::ScannerNotConfigured);
+        }
+        let image_ref = Self::parse_image_ref(image)?;
+        let (digest, manifest) = self.resolve_manifest(&image_ref).await?;
+        if let Some(scan) = self.recorded_scan(&digest)? {
+            return Ok(scan);
+        }
+        let rootfs = self.extract_layers(&image_ref, &manifest).await?;
+        self.scan_rootfs(image, &digest, &rootfs).await
+    }
+
+    /// Scan image (stub for when feature is disabled).
+    #[cfg(not(feature = "image-pull"))]
+    pub async fn scan(&self, _image: &str) -> Result<ImageScan, ImageError> {
+        Err(ImageError::FeatureNotEnabled)
+    }
+
+    fn scan_path(&self, digest: &str) -> PathBuf {
+        self.cache_dir
+            .join("scans")
+            .join(format!("{}.json", digest.replace(':', "_")))
+    }
+
+    /// The recorded scan of an image digest, if it was scanned.
+    pub fn recorded_scan(&self, digest: &str) -> Result<Option<ImageScan>, ImageError> {
+        match std::fs::read(self.scan_path(digest)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Every recorded scan, most recent first.
+    pub fn list_scans(&self) -> Result<Vec<ImageScan>, ImageError> {
+        let scans_dir = self.cache_dir.join("scans");
+        if !scans_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut scans = Vec::new();
+        for entry in std::fs::read_dir(scans_dir)? {
+            let bytes = std::fs::read(entry?.path())?;
+            scans.push(serde_json::from_slice::<ImageScan>(&bytes)?);
+        }
+        scans.sort_by(|a, b| b.scanned_at.cmp(&a.scanned_at));
+        Ok(scans)
+    }
+
     /// List cached images.
     pub fn list_cached(&self) -> Result<Vec<String>, ImageError> {
         let rootfs_dir = self.cache_dir.join("rootfs");
@@ -614,6 +1163,145 @@ impl ImageManager {
     }
 }
 
+/// Move a staged directory to `dest`, unless another pull got there first.
+#[cfg(feature = "image-pull")]
+fn commit_staged(staged: &Path, dest: &Path) -> Result<(), ImageError> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::rename(staged, dest) {
+        Ok(()) => Ok(()),
+        Err(_) if dest.exists() => {
+            std::fs::remove_dir_all(staged)?;
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+const WHITEOUT_PREFIX: &str = ".wh.";
+const OPAQUE_WHITEOUT: &str = ".wh..wh..opq";
+
+/// Apply an unpacked layer on top of `rootfs`. Whiteouts remove what lower
+/// layers put there; files are hardlinked from the layer rather than copied,
+/// and symlinks are recreated.
+#[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+fn link_layer(layer: &Path, rootfs: &Path) -> io::Result<()> {
+    let entries = std::fs::read_dir(layer)?.collect::<io::Result<Vec<_>>>()?;
+
+    // An opaque whiteout hides everything lower layers put in this directory
+    if entries.iter().any(|e| e.file_name() == OPAQUE_WHITEOUT) {
+        for entry in std::fs::read_dir(rootfs)? {
+            remove_path(&entry?.path())?;
+        }
+    }
+
+    for entry in entries {
+        let name = entry.file_name();
+        let name_str = name.to_string_lossy();
+        if name_str == OPAQUE_WHITEOUT {
+            continue;
+        }
+        if let Some(hidden) = name_str.strip_prefix(WHITEOUT_PREFIX) {
+            remove_path(&rootfs.join(hidden))?;
+            continue;
+        }
+
+        let source = entry.path();
+        let target = rootfs.join(&name);
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            let is_dir = std::fs::symlink_metadata(&target)
+                .map(|m| m.is_dir())
+                .unwrap_or(false);
+            if !is_dir {
+                remove_path(&target)?;
+                std::fs::create_dir(&target)?;
+            }
+            link_layer(&source, &target)?;
+            // After linking, so read-only directories can still be filled
+            std::fs::set_permissions(&target, entry.metadata()?.permissions())?;
+        } else {
+            remove_path(&target)?;
+            if file_type.is_symlink() {
+                std::os::unix::fs::symlink(std::fs::read_link(&source)?, &target)?;
+            } else {
+                std::fs::hard_link(&source, &target)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Remove a file, symlink or directory tree; missing paths are fine.
+#[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+fn remove_path(path: &Path) -> io::Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(m) if m.is_dir() => std::fs::remove_dir_all(path),
+        Ok(_) => std::fs::remove_file(path),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Layer annotation naming the file an artifact layer holds.
+pub const ARTIFACT_TITLE_ANNOTATION: &str = "org.opencontainers.image.title";
+
+/// File an artifact layer is written to. Titles that are not a plain file
+/// name are rejected rather than written outside the artifact's directory.
+#[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+fn artifact_file_name(layer: &ManifestLayer) -> Result<String, ImageError> {
+    let Some(title) = layer.annotations.get(ARTIFACT_TITLE_ANNOTATION) else {
+        return Ok(layer.digest.replace(':', "_"));
+    };
+    let mut components = Path::new(title).components();
+    match (components.next(), components.next()) {
+        (Some(std::path::Component::Normal(_)), None) => Ok(title.clone()),
+        _ => Err(ImageError::InvalidLayout(format!(
+            "artifact layer {} has an invalid title {:?}",
+            layer.digest, title
+        ))),
+    }
+}
+
+/// Combine a resolved manifest and its image config into an inspection.
+#[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+fn inspection(
+    reference: &str,
+    digest: String,
+    manifest: &Manifest,
+    config: ImageConfig,
+) -> ImageInspection {
+    let mut platform = format!("{}/{}", config.os, config.architecture);
+    if let Some(variant) = &config.variant {
+        platform.push('/');
+        platform.push_str(variant);
+    }
+    let run = config.config.unwrap_or_default();
+    let mut exposed_ports: Vec<String> =
+        run.exposed_ports.unwrap_or_default().into_keys().collect();
+    exposed_ports.sort();
+
+    ImageInspection {
+        reference: reference.to_string(),
+        digest,
+        platform,
+        layers: manifest
+            .layers
+            .iter()
+            .map(|l| ImageLayer {
+                digest: l.digest.clone(),
+                size_bytes: l.size.max(0) as u64,
+            })
+            .collect(),
+        exposed_ports,
+        env: run.env.unwrap_or_default(),
+        entrypoint: run.entrypoint.unwrap_or_default(),
+        cmd: run.cmd.unwrap_or_default(),
+        labels: run.labels.unwrap_or_default(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -711,4 +1399,167 @@ mod tests {
         assert!(cache_path.exists());
         assert!(std::fs::read_dir(&cache_path).unwrap().next().is_none());
     }
+
+    #[test]
+    fn test_recorded_scans() {
+        let temp = TempDir::new().unwrap();
+        let manager = ImageManager::new(temp.path()).unwrap();
+        assert!(manager.list_scans().unwrap().is_empty());
+
+        let scan = ImageScan {
+            image: "nginx:1.27".to_string(),
+            digest: "sha256:abc".to_string(),
+            scanner: "trivy".to_string(),
+            scanned_at: std::time::SystemTime::now(),
+            vulnerabilities: vec![],
+        };
+        std::fs::create_dir_all(temp.path().join("scans")).unwrap();
+        std::fs::write(
+            manager.scan_path(&scan.digest),
+            serde_json::to_vec(&scan).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            manager.recorded_scan("sha256:abc").unwrap(),
+            Some(scan.clone())
+        );
+        assert_eq!(manager.recorded_scan("sha256:def").unwrap(), None);
+        assert_eq!(manager.list_scans().unwrap(), vec![scan]);
+    }
+
+    #[test]
+    fn test_layer_compression() {
+        let compression = |media_type| LayerCompression::from_media_type(media_type).unwrap();
+        assert_eq!(
+            compression("application/vnd.docker.image.rootfs.diff.tar.gzip"),
+            LayerCompression::Gzip
+        );
+        assert_eq!(
+            compression("application/vnd.oci.image.layer.v1.tar+gzip"),
+            LayerCompression::Gzip
+        );
+        assert_eq!(
+            compression("application/vnd.oci.image.layer.v1.tar+zstd"),
+            LayerCompression::Zstd
+        );
+        assert_eq!(
+            compression("application/vnd.oci.image.layer.v1.tar"),
+            LayerCompression::None
+        );
+        assert!(matches!(
+            LayerCompression::from_media_type("application/vnd.oci.image.layer.v1.tar+bzip2"),
+            Err(ImageError::UnsupportedMediaType(_))
+        ));
+    }
+
+    #[test]
+    fn test_artifact_file_name() {
+        let layer = |title: Option<&str>| ManifestLayer {
+            media_type: "application/octet-stream".to_string(),
+            size: 1,
+            digest: "sha256:abc".to_string(),
+            annotations: title
+                .map(|t| HashMap::from([(ARTIFACT_TITLE_ANNOTATION.to_string(), t.to_string())]))
+                .unwrap_or_default(),
+        };
+        assert_eq!(
+            artifact_file_name(&layer(Some("model.safetensors"))).unwrap(),
+            "model.safetensors"
+        );
+        assert_eq!(artifact_file_name(&layer(None)).unwrap(), "sha256_abc");
+        for title in ["../escape", "/etc/passwd", "dir/file", ".", ""] {
+            assert!(
+                artifact_file_name(&layer(Some(title))).is_err(),
+                "{}",
+                title
+            );
+        }
+    }
+
+    #[test]
+    fn test_link_layers_shares_files() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let write = |path: &str, contents: &str| {
+            let path = temp_dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write("base/etc/os-release", "debian");
+        write("base/etc/motd", "hello");
+        write("base/opt/app/old.so", "v1");
+        std::os::unix::fs::symlink("etc/os-release", temp_dir.path().join("base/release")).unwrap();
+        write("app/etc/.wh.motd", "");
+        write("app/opt/app/.wh..wh..opq", "");
+        write("app/opt/app/new.so", "v2");
+
+        // Two images sharing the base layer
+        let rootfs = |name: &str, layers: &[&str]| {
+            let rootfs = temp_dir.path().join(name);
+            std::fs::create_dir(&rootfs).unwrap();
+            for layer in layers {
+                link_layer(&temp_dir.path().join(layer), &rootfs).unwrap();
+            }
+            rootfs
+        };
+        let base = rootfs("rootfs-base", &["base"]);
+        let app = rootfs("rootfs-app", &["base", "app"]);
+
+        let ino = |path: PathBuf| std::fs::metadata(path).unwrap().ino();
+        assert_eq!(
+            ino(base.join("etc/os-release")),
+            ino(app.join("etc/os-release"))
+        );
+        assert!(base.join("etc/motd").exists());
+        assert!(!app.join("etc/motd").exists());
+        assert!(!app.join("opt/app/old.so").exists());
+        assert!(app.join("opt/app/new.so").exists());
+        assert!(!app.join("opt/app/.wh..wh..opq").exists());
+        assert_eq!(
+            std::fs::read_link(app.join("release")).unwrap(),
+            Path::new("etc/os-release")
+        );
+    }
+
+    #[test]
+    fn test_inspection_from_config() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{
+                "schemaVersion": 2,
+                "config": {"mediaType": "config", "size": 2, "digest": "sha256:c"},
+                "layers": [
+                    {"mediaType": "layer", "size": 100, "digest": "sha256:a"},
+                    {"mediaType": "layer", "size": 50, "digest": "sha256:b"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let config: ImageConfig = serde_json::from_str(
+            r#"{
+                "architecture": "arm64",
+                "os": "linux",
+                "variant": "v8",
+                "config": {
+                    "Env": ["PATH=/usr/bin"],
+                    "Entrypoint": ["python3", "-m", "vllm"],
+                    "Cmd": null,
+                    "ExposedPorts": {"8000/tcp": {}, "443/tcp": {}},
+                    "Labels": {"version": "0.6.0"}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let inspection = inspection("vllm:0.6.0", "sha256:m".to_string(), &manifest, config);
+        assert_eq!(inspection.platform, "linux/arm64/v8");
+        assert_eq!(inspection.digest, "sha256:m");
+        assert_eq!(inspection.size_bytes(), 150);
+        assert_eq!(inspection.layers[1].digest, "sha256:b");
+        assert_eq!(inspection.exposed_ports, vec!["443/tcp", "8000/tcp"]);
+        assert_eq!(inspection.entrypoint, vec!["python3", "-m", "vllm"]);
+        assert!(inspection.cmd.is_empty());
+        assert_eq!(inspection.labels["version"], "0.6.0");
+    }
 }
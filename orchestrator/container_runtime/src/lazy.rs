@@ -0,0 +1,230 @@
+//! Lazy pulling of eStargz and zstd:chunked images.
+//!
+//! Both formats are ordinary gzip or zstd layers with a table of contents
+//! appended, so any runtime can pull them in full. A lazy puller instead
+//! hands the image to an external helper, e.g. a wrapper around
+//! stargz-snapshotter's store, which mounts its filesystem and fetches file
+//! chunks from the registry as they are first read. Containers of huge
+//! images, such as model servers, then start before the image has been
+//! downloaded.
+//!
+//! The helper is run as
+//! `<helper> mount --format <estargz|zstd:chunked> --mountpoint <dir>
+//! [--layer <digest>]... <image>`, must exit once the filesystem is mounted,
+//! and must treat mounting an already mounted image as success.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::image::{ImageError, ImageReference, Manifest, ManifestLayer};
+
+/// Layer annotation holding the digest of an eStargz layer's table of contents.
+pub const ESTARGZ_TOC_ANNOTATION: &str = "containerd.io/snapshot/stargz/toc.digest";
+
+/// Layer annotation holding the checksum of a zstd:chunked layer's manifest.
+pub const ZSTD_CHUNKED_ANNOTATION: &str = "io.github.containers.zstd-chunked.manifest-checksum";
+
+/// Seekable layer formats a lazy puller can mount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LazyFormat {
+    Estargz,
+    ZstdChunked,
+}
+
+impl LazyFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LazyFormat::Estargz => "estargz",
+            LazyFormat::ZstdChunked => "zstd:chunked",
+        }
+    }
+
+    /// The format of a layer, going by its annotations.
+    pub fn of_layer(layer: &ManifestLayer) -> Option<Self> {
+        if layer.annotations.contains_key(ESTARGZ_TOC_ANNOTATION) {
+            Some(LazyFormat::Estargz)
+        } else if layer.annotations.contains_key(ZSTD_CHUNKED_ANNOTATION) {
+            Some(LazyFormat::ZstdChunked)
+        } else {
+            None
+        }
+    }
+
+    /// The format of an image, if every one of its layers has it.
+    pub fn of_manifest(manifest: &Manifest) -> Option<Self> {
+        let mut layers = manifest.layers.iter().map(Self::of_layer);
+        let format = layers.next()??;
+        layers.all(|f| f == Some(format)).then_some(format)
+    }
+}
+
+/// How to run the lazy pull helper.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LazyPullConfig {
+    /// Helper binary.
+    pub helper: PathBuf,
+    /// How long mounting an image may take (default: 60s).
+    pub timeout: Duration,
+}
+
+impl LazyPullConfig {
+    pub fn new(helper: impl Into<PathBuf>) -> Self {
+        Self {
+            helper: helper.into(),
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Mounts lazily pulled images through the helper.
+#[derive(Debug, Clone)]
+pub struct LazyPuller {
+    config: LazyPullConfig,
+}
+
+impl LazyPuller {
+    pub fn new(config: LazyPullConfig) -> Self {
+        Self { config }
+    }
+
+    /// Arguments mounting `image` at `mountpoint`.
+    fn args(
+        &self,
+        image_ref: &ImageReference,
+        manifest: &Manifest,
+        format: LazyFormat,
+        mountpoint: &Path,
+    ) -> Vec<String> {
+        let mut args = vec![
+            "mount".to_string(),
+            "--format".to_string(),
+            format.as_str().to_string(),
+            "--mountpoint".to_string(),
+            mountpoint.display().to_string(),
+        ];
+        for layer in &manifest.layers {
+            args.push("--layer".to_string());
+            args.push(layer.digest.clone());
+        }
+        args.push(image_ref.to_string());
+        args
+    }
+
+    /// Mount the filesystem of `image_ref` at `mountpoint`.
+    pub async fn mount(
+        &self,
+        image_ref: &ImageReference,
+        manifest: &Manifest,
+        format: LazyFormat,
+        mountpoint: &Path,
+    ) -> Result<(), ImageError> {
+        let args = self.args(image_ref, manifest, format, mountpoint);
+        debug!(
+            "Mounting {} with {:?} {:?}",
+            image_ref, self.config.helper, args
+        );
+
+        let output = tokio::time::timeout(
+            self.config.timeout,
+            Command::new(&self.config.helper)
+                .args(&args)
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await
+        .map_err(|_| {
+            ImageError::LazyPull(format!(
+                "mounting {} timed out after {}s",
+                image_ref,
+                self.config.timeout.as_secs()
+            ))
+        })?
+        .map_err(|e| {
+            ImageError::LazyPull(format!("failed to run {:?}: {}", self.config.helper, e))
+        })?;
+
+        if !output.status.success() {
+            return Err(ImageError::LazyPull(format!(
+                "{:?} exited with {}: {}",
+                self.config.helper,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::image::ManifestConfig;
+
+    fn layer(digest: &str, annotation: Option<&str>) -> ManifestLayer {
+        ManifestLayer {
+            media_type: "application/vnd.oci.image.layer.v1.tar+zstd".to_string(),
+            size: 1,
+            digest: digest.to_string(),
+            annotations: annotation
+                .map(|a| HashMap::from([(a.to_string(), "sha256:toc".to_string())]))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn manifest(layers: Vec<ManifestLayer>) -> Manifest {
+        Manifest {
+            schema_version: 2,
+            media_type: None,
+            config: ManifestConfig {
+                media_type: "application/vnd.oci.image.config.v1+json".to_string(),
+                size: 1,
+                digest: "sha256:c".to_string(),
+            },
+            layers,
+        }
+    }
+
+    #[test]
+    fn test_lazy_format_of_manifest() {
+        let chunked = manifest(vec![
+            layer("sha256:a", Some(ZSTD_CHUNKED_ANNOTATION)),
+            layer("sha256:b", Some(ZSTD_CHUNKED_ANNOTATION)),
+        ]);
+        assert_eq!(
+            LazyFormat::of_manifest(&chunked),
+            Some(LazyFormat::ZstdChunked)
+        );
+
+        // One plain layer means the image has to be pulled in full
+        let mixed = manifest(vec![
+            layer("sha256:a", Some(ESTARGZ_TOC_ANNOTATION)),
+            layer("sha256:b", None),
+        ]);
+        assert_eq!(LazyFormat::of_manifest(&mixed), None);
+        assert_eq!(LazyFormat::of_manifest(&manifest(vec![])), None);
+    }
+
+    #[test]
+    fn test_mount_args() {
+        let puller = LazyPuller::new(LazyPullConfig::new("/usr/bin/lazy-mount"));
+        let image_ref = ImageReference::new("ghcr.io", "owner/model", "v1");
+        let manifest = manifest(vec![layer("sha256:a", Some(ESTARGZ_TOC_ANNOTATION))]);
+        assert_eq!(
+            puller
+                .args(
+                    &image_ref,
+                    &manifest,
+                    LazyFormat::Estargz,
+                    Path::new("/images/lazy/m")
+                )
+                .join(" "),
+            "mount --format estargz --mountpoint /images/lazy/m --layer sha256:a \
+             ghcr.io/owner/model:v1"
+        );
+    }
+}
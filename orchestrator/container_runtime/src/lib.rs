@@ -1,7 +1,7 @@
 //! Container Runtime implementations for the orchestrator.
 //!
 //! This crate provides implementations of the `ContainerRuntime` trait:
-//! - `MockRuntime`: In-memory mock for testing (default)
+//! - `MockRuntime`: In-memory mock for testing, with injectable faults (default)
 //! - `YoukiRuntime`: Real container runtime using libcontainer (requires `youki-runtime` feature)
 //! - `YoukiCliRuntime`: CLI-based runtime using youki binary (requires `youki-cli` feature)
 //!
@@ -9,10 +9,26 @@
 //! that can be used by any OCI-compliant runtime.
 //!
 //! The `image` module (requires `image-pull` feature) provides image pulling
-//! and extraction from Docker Hub and other registries.
+//! and extraction from Docker Hub and other registries, throttled per node by
+//! the `pull_queue` module, and scanned for vulnerabilities by the `scan`
+//! module. The `lazy` module mounts eStargz and zstd:chunked images through
+//! an external helper instead of pulling them, and the `push` module
+//! publishes local OCI image layouts to a registry. The `credentials` module
+//! logs in to ECR, Artifact Registry and ACR with the node's cloud identity.
+//! The `models` module (requires `image-pull` feature) downloads, verifies
+//! and caches the model artifacts mounted into inference services.
+//! The `log_rotation` module (requires `youki-cli` feature) rotates the
+//! container logs the CLI runtime captures.
 
 pub mod oci_bundle;
+pub mod credentials;
 pub mod image;
+pub mod lazy;
+#[cfg(feature = "image-pull")]
+pub mod models;
+pub mod pull_queue;
+pub mod push;
+pub mod scan;
 
 #[cfg(feature = "mock-runtime")]
 pub mod mock;
@@ -20,18 +36,29 @@ pub mod mock;
 #[cfg(feature = "youki-runtime")]
 pub mod youki;
 
+#[cfg(feature = "youki-cli")]
+pub mod log_rotation;
+
 #[cfg(feature = "youki-cli")]
 pub mod youki_cli;
 
 // Re-export common types
 pub use container_runtime_interface::{
-    ContainerRuntime, ContainerStatus, CreateContainerOptions, RuntimeError,
+    ContainerRuntime, ContainerStatus, CreateContainerOptions, ImagePullStatus, PullPriority,
+    RuntimeError,
 };
 
+pub use credentials::{CloudProvider, CredentialProvider, ExpiringCredentials};
 pub use image::{ImageManager, ImageReference, ImageError, Manifest};
+pub use lazy::{LazyFormat, LazyPullConfig, LazyPuller};
+#[cfg(feature = "image-pull")]
+pub use models::{ModelError, ModelLease, ModelStore};
+pub use pull_queue::{ImagePullQueue, PullQueueConfig};
+pub use push::{OciLayout, PushSummary, RegistryCredentials};
+pub use scan::{ImageScanner, ScannerConfig, ScannerKind};
 
 #[cfg(feature = "mock-runtime")]
-pub use mock::MockRuntime;
+pub use mock::{ContainerScript, MockFaults, MockOperation, MockRuntime, MockRuntimeBuilder};
 
 #[cfg(feature = "youki-runtime")]
 pub use youki::YoukiRuntime;
@@ -42,6 +69,9 @@ pub use youki_cli::{
     LogEntry, LogOptions, LogReceiver,
 };
 
+#[cfg(feature = "youki-cli")]
+pub use log_rotation::LogRotationConfig;
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -0,0 +1,282 @@
+//! Rotation of container logs.
+//!
+//! A container's live log is rotated once it outgrows a size limit or its
+//! oldest line passes an age limit, checked whenever a line is written:
+//! `container.log` moves to `container.log.1`, older segments shift up by one
+//! and those beyond the kept count are deleted. Rotated segments are
+//! gzip-compressed (`container.log.1.gz`) unless disabled. [`read_lines`]
+//! reads the segments oldest first followed by the live log, so readers see
+//! one continuous log, a line at a time.
+
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tokio::io::AsyncBufReadExt;
+use tokio::sync::mpsc;
+
+/// Lines read ahead of a [`LogLines`] reader.
+const READ_AHEAD_LINES: usize = 256;
+
+/// When container logs are rotated and what is kept of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRotationConfig {
+    /// Size the live log may reach (default: 10 MiB).
+    pub max_size_bytes: u64,
+    /// Age of the oldest line in the live log (default: unlimited).
+    pub max_age: Option<Duration>,
+    /// Rotated segments kept; 0 discards the log on rotation (default: 5).
+    pub max_files: usize,
+    /// Gzip rotated segments (default: true).
+    pub compress: bool,
+}
+
+impl Default for LogRotationConfig {
+    fn default() -> Self {
+        Self {
+            max_size_bytes: 10 * 1024 * 1024,
+            max_age: None,
+            max_files: 5,
+            compress: true,
+        }
+    }
+}
+
+/// Rotated segment `n` of `log_path`, 1 being the newest.
+fn segment_path(log_path: &Path, n: usize, compressed: bool) -> PathBuf {
+    let mut name = log_path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    if compressed {
+        name.push(".gz");
+    }
+    PathBuf::from(name)
+}
+
+/// Rotated segments of `log_path`, oldest first.
+fn segments(log_path: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for n in 1.. {
+        let compressed = segment_path(log_path, n, true);
+        let plain = segment_path(log_path, n, false);
+        if compressed.exists() {
+            found.push(compressed);
+        } else if plain.exists() {
+            found.push(plain);
+        } else {
+            break;
+        }
+    }
+    found.reverse();
+    found
+}
+
+/// Whether writing `incoming` more bytes to the log at `log_path` should
+/// rotate it first. An empty or missing log is never rotated.
+pub async fn needs_rotation(log_path: &Path, config: &LogRotationConfig, incoming: u64) -> bool {
+    let Ok(metadata) = tokio::fs::metadata(log_path).await else {
+        return false;
+    };
+    if metadata.len() == 0 {
+        return false;
+    }
+    if metadata.len() + incoming > config.max_size_bytes {
+        return true;
+    }
+    match config.max_age {
+        Some(max_age) => oldest_line_age(log_path)
+            .await
+            .is_some_and(|age| age > max_age),
+        None => false,
+    }
+}
+
+/// Age of the first line of the log at `log_path`, if it is timestamped.
+async fn oldest_line_age(log_path: &Path) -> Option<Duration> {
+    let file = tokio::fs::File::open(log_path).await.ok()?;
+    let mut line = String::new();
+    tokio::io::BufReader::new(file)
+        .read_line(&mut line)
+        .await
+        .ok()?;
+    let timestamp = DateTime::parse_from_rfc3339(line.split(' ').next()?).ok()?;
+    (Utc::now() - timestamp.with_timezone(&Utc)).to_std().ok()
+}
+
+/// Rotate the log at `log_path`, leaving an empty live log behind.
+pub async fn rotate(log_path: &Path, config: &LogRotationConfig) -> std::io::Result<()> {
+    if config.max_files == 0 {
+        return tokio::fs::File::create(log_path).await.map(|_| ());
+    }
+
+    // Shift existing segments up by one, dropping those past the kept count
+    for n in (1..=segments(log_path).len()).rev() {
+        for compressed in [false, true] {
+            let from = segment_path(log_path, n, compressed);
+            if !from.exists() {
+                continue;
+            }
+            if n >= config.max_files {
+                tokio::fs::remove_file(&from).await?;
+            } else {
+                tokio::fs::rename(&from, segment_path(log_path, n + 1, compressed)).await?;
+            }
+        }
+    }
+
+    let newest = segment_path(log_path, 1, false);
+    tokio::fs::rename(log_path, &newest).await?;
+    tokio::fs::File::create(log_path).await?;
+    if config.compress {
+        let compressed = segment_path(log_path, 1, true);
+        tokio::task::spawn_blocking(move || compress(&newest, &compressed))
+            .await
+            .map_err(std::io::Error::other)??;
+    }
+    Ok(())
+}
+
+/// Replace the file at `from` by its gzipped copy at `to`.
+fn compress(from: &Path, to: &Path) -> std::io::Result<()> {
+    let mut input = std::fs::File::open(from)?;
+    let mut encoder = GzEncoder::new(std::fs::File::create(to)?, Compression::default());
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    std::fs::remove_file(from)
+}
+
+/// Lines of a log, read on a blocking thread as they are asked for.
+pub struct LogLines {
+    lines: mpsc::Receiver<std::io::Result<String>>,
+}
+
+impl LogLines {
+    /// The next line without its line ending, or `None` at the end of the
+    /// live log.
+    pub async fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        self.lines.recv().await.transpose()
+    }
+}
+
+/// The whole log at `log_path`, line by line: its rotated segments oldest
+/// first, then the live log. Only a few lines are held in memory at once.
+pub fn read_lines(log_path: &Path) -> LogLines {
+    let (sender, lines) = mpsc::channel(READ_AHEAD_LINES);
+    let log_path = log_path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = send_lines(&log_path, &sender) {
+            let _ = sender.blocking_send(Err(e));
+        }
+    });
+    LogLines { lines }
+}
+
+fn send_lines(
+    log_path: &Path,
+    sender: &mpsc::Sender<std::io::Result<String>>,
+) -> std::io::Result<()> {
+    let mut sources = segments(log_path);
+    sources.push(log_path.to_path_buf());
+    for source in sources {
+        // A segment may be rotated away after it was listed
+        let file = match std::fs::File::open(&source) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let mut reader: Box<dyn BufRead> = if source.extension().is_some_and(|ext| ext == "gz") {
+            Box::new(BufReader::new(GzDecoder::new(file)))
+        } else {
+            Box::new(BufReader::new(file))
+        };
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\n', '\r']).to_string();
+            if sender.blocking_send(Ok(text)).is_err() {
+                // The reader stopped early
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn collect_lines(log: &Path) -> Vec<String> {
+        let mut lines = read_lines(log);
+        let mut collected = Vec::new();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            collected.push(line);
+        }
+        collected
+    }
+
+    #[tokio::test]
+    async fn test_rotation_keeps_max_files_and_reads_across_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("container.log");
+        let config = LogRotationConfig {
+            max_size_bytes: 40,
+            max_files: 2,
+            ..Default::default()
+        };
+
+        for i in 0..4 {
+            let line = format!("2024-01-15T10:30:0{}Z stdout line {}\n", i, i);
+            if needs_rotation(&log, &config, line.len() as u64).await {
+                rotate(&log, &config).await.unwrap();
+            }
+            let mut content = tokio::fs::read_to_string(&log).await.unwrap_or_default();
+            content.push_str(&line);
+            tokio::fs::write(&log, content).await.unwrap();
+        }
+
+        // Each line fills a segment; the oldest fell past the two kept
+        assert!(segment_path(&log, 1, true).exists());
+        assert!(segment_path(&log, 2, true).exists());
+        assert!(!segment_path(&log, 3, true).exists());
+        assert!(!segment_path(&log, 1, false).exists());
+        let lines = collect_lines(&log).await;
+        let messages: Vec<_> = lines
+            .iter()
+            .map(|l| l.rsplit(' ').next().unwrap())
+            .collect();
+        assert_eq!(messages, ["1", "2", "3"]);
+    }
+
+    #[tokio::test]
+    async fn test_age_based_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("container.log");
+        tokio::fs::write(&log, "2024-01-15T10:30:00Z stdout old\n")
+            .await
+            .unwrap();
+
+        let config = LogRotationConfig::default();
+        assert!(!needs_rotation(&log, &config, 10).await);
+        let config = LogRotationConfig {
+            max_age: Some(Duration::from_secs(3600)),
+            compress: false,
+            max_files: 0,
+            ..config
+        };
+        assert!(needs_rotation(&log, &config, 10).await);
+        assert!(!needs_rotation(&dir.path().join("missing.log"), &config, 10).await);
+
+        // Keeping no segments empties the live log
+        rotate(&log, &config).await.unwrap();
+        assert!(collect_lines(&log).await.is_empty());
+        assert!(segments(&log).is_empty());
+    }
+}
@@ -1,33 +1,274 @@
 //! Mock container runtime for testing and development.
 //!
 //! This provides an in-memory implementation that simulates container operations
-//! without actually creating containers.
+//! without actually creating containers. Containers publish their lifecycle
+//! [`ContainerEvent`]s, scripted exits included.
+//!
+//! For testing retries and backoff, [`MockFaults`] injects failures and
+//! latencies, and a [`ContainerScript`] drives the states a container reports.
+//! Both are set with [`MockRuntime::builder`] and can be changed while the
+//! runtime is in use:
+//!
+//! ```
+//! use std::time::Duration;
+//! use container_runtime::mock::{ContainerScript, MockOperation, MockRuntime};
+//!
+//! // Every third create fails, stops take 5s, and `web` crashes after
+//! // two status reports, over and over
+//! let runtime = MockRuntime::builder()
+//!     .with_create_failures_every(3)
+//!     .with_latency(MockOperation::Stop, Duration::from_secs(5))
+//!     .with_script("web", ContainerScript::new().running(2).exited(1, 1).repeat())
+//!     .build();
+//! ```
 
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tracing::{debug, info};
 use uuid::Uuid;
 
 use container_runtime_interface::{
-    ContainerRuntime, ContainerStatus, CreateContainerOptions,
+    Checkpoint, CheckpointOptions, ContainerEvent, ContainerEventKind, ContainerRuntime,
+    ContainerStatus, ContainerUsage, CreateContainerOptions, DebugOptions, ExecInput, ExecOptions,
+    ExecOutput, ExecSession, ImageInspection, ImageLayer, ImageScan, PullPriority, ResourceLimits,
+    Vulnerability, CONTAINER_EVENT_CAPACITY,
 };
-use orchestrator_shared_types::{ContainerConfig, ContainerId, NodeId, Result};
+use orchestrator_shared_types::{ContainerConfig, ContainerId, NodeId, OrchestrationError, Result};
 
 /// Mock container state
 #[derive(Debug, Clone)]
 struct MockContainer {
     id: ContainerId,
-    _config: ContainerConfig,
+    config: ContainerConfig,
     node_id: NodeId,
     state: String,
     exit_code: Option<i32>,
+    /// Status reports so far, for flapping
+    reports: u64,
+    /// Script driving the state, until the container is stopped
+    script: Option<ScriptProgress>,
 }
 
-/// Mock runtime that simulates container operations in-memory.
+impl MockContainer {
+    /// The status to report now, advancing the script and flapping every
+    /// `flap_every` reports of a running container. Scripted starts and
+    /// exits are published on `events`.
+    fn report(
+        &mut self,
+        flap_every: Option<u32>,
+        events: &broadcast::Sender<ContainerEvent>,
+    ) -> ContainerStatus {
+        self.reports += 1;
+        if let Some(step) = self.script.as_mut().and_then(ScriptProgress::advance) {
+            let was_running = self.state == "running";
+            self.state = step.state;
+            self.exit_code = step.exit_code;
+            let kinds = match (was_running, self.state == "running") {
+                (true, false) if step.oom_killed => vec![
+                    ContainerEventKind::OomKilled,
+                    ContainerEventKind::Exited {
+                        exit_code: self.exit_code,
+                    },
+                ],
+                (true, false) => vec![ContainerEventKind::Exited {
+                    exit_code: self.exit_code,
+                }],
+                (false, true) => vec![ContainerEventKind::Started],
+                _ => vec![],
+            };
+            for kind in kinds {
+                let _ = events.send(ContainerEvent::now(self.id.clone(), kind));
+            }
+        }
+        let flapping = self.state == "running"
+            && flap_every.is_some_and(|n| n > 0 && self.reports % u64::from(n) == 0);
+        ContainerStatus {
+            id: self.id.clone(),
+            state: if flapping { "stopped".to_string() } else { self.state.clone() },
+            exit_code: if flapping { None } else { self.exit_code },
+            error_message: None,
+            oom_killed: false,
+        }
+    }
+}
+
+/// Runtime calls that [`MockFaults::latencies`] can slow down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockOperation {
+    Create,
+    Stop,
+    Remove,
+    Status,
+    List,
+    Exec,
+    Checkpoint,
+    Restore,
+    Pull,
+}
+
+/// Failures and delays a [`MockRuntime`] injects.
+#[derive(Debug, Clone, Default)]
+pub struct MockFaults {
+    /// Fail every Nth `create_container` call, counting all calls.
+    pub fail_create_every: Option<u32>,
+    /// Report every Nth status of a running container as stopped without
+    /// an exit code; the report after it is true again.
+    pub flap_status_every: Option<u32>,
+    /// How long each call takes before it does anything.
+    pub latencies: HashMap<MockOperation, Duration>,
+    /// Fail every `restore_container` call onto these nodes.
+    pub fail_restore_on: Vec<NodeId>,
+}
+
+/// States a container reports, one step per status report, so tests can
+/// crash and recover containers deterministically. After the last step the
+/// container keeps its state, or starts over if the script repeats.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContainerScript {
+    steps: Vec<ScriptStep>,
+    repeat: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ScriptStep {
+    state: String,
+    exit_code: Option<i32>,
+    reports: u32,
+    /// Entering the step is an OOM kill
+    oom_killed: bool,
+}
+
+impl ContainerScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report `state` with `exit_code` for `reports` status reports.
+    pub fn then(mut self, state: impl Into<String>, exit_code: Option<i32>, reports: u32) -> Self {
+        self.steps.push(ScriptStep {
+            state: state.into(),
+            exit_code,
+            reports: reports.max(1),
+            oom_killed: false,
+        });
+        self
+    }
+
+    /// Report running for `reports` status reports.
+    pub fn running(self, reports: u32) -> Self {
+        self.then("running", None, reports)
+    }
+
+    /// Report stopped with `exit_code` for `reports` status reports.
+    pub fn exited(self, exit_code: i32, reports: u32) -> Self {
+        self.then("stopped", Some(exit_code), reports)
+    }
+
+    /// Report stopped with exit code 137 for `reports` status reports, after
+    /// an OOM kill.
+    pub fn oom_killed(self, reports: u32) -> Self {
+        let mut script = self.then("stopped", Some(137), reports);
+        if let Some(step) = script.steps.last_mut() {
+            step.oom_killed = true;
+        }
+        script
+    }
+
+    /// Start over after the last step, e.g. for a crash loop.
+    pub fn repeat(mut self) -> Self {
+        self.repeat = true;
+        self
+    }
+}
+
+/// A script being played by one container.
+#[derive(Debug, Clone)]
+struct ScriptProgress {
+    script: ContainerScript,
+    step: usize,
+    reports: u32,
+}
+
+impl ScriptProgress {
+    fn new(script: ContainerScript) -> Self {
+        Self {
+            script,
+            step: 0,
+            reports: 0,
+        }
+    }
+
+    /// The step to report now, moving on once it has been reported enough.
+    fn advance(&mut self) -> Option<ScriptStep> {
+        let steps = &self.script.steps;
+        let step = steps.get(self.step)?.clone();
+        self.reports += 1;
+        let last = self.step + 1 == steps.len();
+        if self.reports >= step.reports && (!last || self.script.repeat) {
+            self.step = (self.step + 1) % steps.len();
+            self.reports = 0;
+        }
+        Some(step)
+    }
+}
+
+/// Builder for a [`MockRuntime`] with faults and scripts.
 #[derive(Debug, Default)]
+pub struct MockRuntimeBuilder {
+    faults: MockFaults,
+    scripts: HashMap<String, ContainerScript>,
+}
+
+impl MockRuntimeBuilder {
+    /// Fail every Nth `create_container` call.
+    pub fn with_create_failures_every(mut self, n: u32) -> Self {
+        self.faults.fail_create_every = Some(n);
+        self
+    }
+
+    /// Report every Nth status of a running container as stopped.
+    pub fn with_status_flaps_every(mut self, n: u32) -> Self {
+        self.faults.flap_status_every = Some(n);
+        self
+    }
+
+    /// Fail every restore onto `node_id`.
+    pub fn with_restore_failures_on(mut self, node_id: NodeId) -> Self {
+        self.faults.fail_restore_on.push(node_id);
+        self
+    }
+
+    /// Make every `operation` call take `latency`.
+    pub fn with_latency(mut self, operation: MockOperation, latency: Duration) -> Self {
+        self.faults.latencies.insert(operation, latency);
+        self
+    }
+
+    /// Drive containers named `name` with `script`.
+    pub fn with_script(mut self, name: impl Into<String>, script: ContainerScript) -> Self {
+        self.scripts.insert(name.into(), script);
+        self
+    }
+
+    pub fn build(self) -> MockRuntime {
+        MockRuntime {
+            faults: Arc::new(RwLock::new(self.faults)),
+            scripts: Arc::new(RwLock::new(self.scripts)),
+            ..MockRuntime::default()
+        }
+    }
+}
+
+/// Mock runtime that simulates container operations in-memory.
+#[derive(Debug)]
 pub struct MockRuntime {
     /// All containers by ID
     containers: Arc<RwLock<HashMap<ContainerId, MockContainer>>>,
@@ -35,6 +276,52 @@ pub struct MockRuntime {
     containers_by_node: Arc<RwLock<HashMap<NodeId, Vec<ContainerId>>>>,
     /// Initialized nodes
     initialized_nodes: Arc<RwLock<Vec<NodeId>>>,
+    /// Images whose containers exit immediately, with their exit codes
+    completions: Arc<RwLock<HashMap<String, i32>>>,
+    /// Create, stop, checkpoint and restore calls in order, by container name
+    calls: Arc<RwLock<Vec<String>>>,
+    /// Configs of checkpointed containers, by checkpoint path
+    checkpoints: Arc<RwLock<HashMap<PathBuf, ContainerConfig>>>,
+    /// Resource usage reported per container
+    usage: Arc<RwLock<HashMap<ContainerId, ContainerUsage>>>,
+    /// Limits set by resource updates, per container
+    limits: Arc<RwLock<HashMap<ContainerId, ResourceLimits>>>,
+    /// Injected failures and latencies
+    faults: Arc<RwLock<MockFaults>>,
+    /// Scripts by container name, for containers created from now on
+    scripts: Arc<RwLock<HashMap<String, ContainerScript>>>,
+    /// `create_container` calls so far, failed ones included
+    create_calls: AtomicU64,
+    /// Lifecycle events, for [`ContainerRuntime::events`]
+    events: broadcast::Sender<ContainerEvent>,
+    /// Images pulled onto each node
+    images: Arc<RwLock<HashMap<NodeId, HashSet<String>>>>,
+    /// Vulnerabilities scans find, by image
+    vulnerabilities: Arc<RwLock<HashMap<String, Vec<Vulnerability>>>>,
+    /// Scans so far, by digest
+    scans: Arc<RwLock<HashMap<String, ImageScan>>>,
+}
+
+impl Default for MockRuntime {
+    fn default() -> Self {
+        Self {
+            containers: Default::default(),
+            containers_by_node: Default::default(),
+            initialized_nodes: Default::default(),
+            completions: Default::default(),
+            calls: Default::default(),
+            checkpoints: Default::default(),
+            usage: Default::default(),
+            limits: Default::default(),
+            faults: Default::default(),
+            scripts: Default::default(),
+            create_calls: AtomicU64::new(0),
+            events: broadcast::channel(CONTAINER_EVENT_CAPACITY).0,
+            images: Default::default(),
+            vulnerabilities: Default::default(),
+            scans: Default::default(),
+        }
+    }
 }
 
 impl MockRuntime {
@@ -43,11 +330,109 @@ impl MockRuntime {
         Self::default()
     }
 
+    /// Start building a runtime with injected faults or scripts.
+    pub fn builder() -> MockRuntimeBuilder {
+        MockRuntimeBuilder::default()
+    }
+
+    /// The faults injected now.
+    pub async fn faults(&self) -> MockFaults {
+        self.faults.read().await.clone()
+    }
+
+    /// Replace the injected faults; `MockFaults::default()` clears them.
+    pub async fn set_faults(&self, faults: MockFaults) {
+        *self.faults.write().await = faults;
+    }
+
+    /// Drive containers named `name` created from now on with `script`.
+    pub async fn set_script(&self, name: impl Into<String>, script: ContainerScript) {
+        self.scripts.write().await.insert(name.into(), script);
+    }
+
+    /// Stop scripting containers named `name` created from now on.
+    pub async fn clear_script(&self, name: &str) {
+        self.scripts.write().await.remove(name);
+    }
+
+    /// Fail unless the container exists, without reporting its status.
+    async fn check_exists(&self, container_id: &ContainerId) -> Result<()> {
+        if self.containers.read().await.contains_key(container_id) {
+            Ok(())
+        } else {
+            Err(orchestrator_shared_types::OrchestrationError::RuntimeError(
+                format!("Container not found: {}", container_id),
+            ))
+        }
+    }
+
+    /// Publish an event of a container, if anyone listens.
+    fn publish(&self, container_id: &ContainerId, kind: ContainerEventKind) {
+        let _ = self.events.send(ContainerEvent::now(container_id.clone(), kind));
+    }
+
+    /// Wait for the latency injected into `operation`, if any.
+    async fn delay(&self, operation: MockOperation) {
+        let latency = self.faults.read().await.latencies.get(&operation).copied();
+        if let Some(latency) = latency {
+            tokio::time::sleep(latency).await;
+        }
+    }
+
     /// Get the count of containers (for testing).
     pub async fn container_count(&self) -> usize {
         self.containers.read().await.len()
     }
 
+    /// Make containers created from `image` exit immediately with
+    /// `exit_code`, as a run-to-completion container would (for testing).
+    pub async fn set_completion(&self, image: impl Into<String>, exit_code: i32) {
+        self.completions.write().await.insert(image.into(), exit_code);
+    }
+
+    /// Create, stop, checkpoint and restore calls so far, e.g. `create:web`
+    /// then `stop:web` (for testing).
+    pub async fn calls(&self) -> Vec<String> {
+        self.calls.read().await.clone()
+    }
+
+    /// Report `usage` for a container; others report none (for testing).
+    pub async fn set_usage(&self, container_id: &ContainerId, usage: ContainerUsage) {
+        self.usage.write().await.insert(container_id.clone(), usage);
+    }
+
+    /// The limits resource updates set on a container so far (for testing).
+    pub async fn resource_limits(&self, container_id: &ContainerId) -> ResourceLimits {
+        self.limits
+            .read()
+            .await
+            .get(container_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Whether `image` was pulled onto `node_id` (for testing).
+    pub async fn has_image(&self, node_id: &NodeId, image: &str) -> bool {
+        self.images
+            .read()
+            .await
+            .get(node_id)
+            .is_some_and(|images| images.contains(image))
+    }
+
+    /// Make scans of `image` find `vulnerabilities`; other images are
+    /// clean (for testing).
+    pub async fn set_vulnerabilities(
+        &self,
+        image: impl Into<String>,
+        vulnerabilities: Vec<Vulnerability>,
+    ) {
+        self.vulnerabilities
+            .write()
+            .await
+            .insert(image.into(), vulnerabilities);
+    }
+
     /// Check if a node is initialized (for testing).
     pub async fn is_node_initialized(&self, node_id: &NodeId) -> bool {
         self.initialized_nodes.read().await.contains(node_id)
@@ -76,6 +461,15 @@ impl ContainerRuntime for MockRuntime {
         config: &ContainerConfig,
         options: &CreateContainerOptions,
     ) -> Result<ContainerId> {
+        self.delay(MockOperation::Create).await;
+        let call = self.create_calls.fetch_add(1, Ordering::SeqCst) + 1;
+        let fail_every = self.faults.read().await.fail_create_every;
+        if fail_every.is_some_and(|n| n > 0 && call % u64::from(n) == 0) {
+            return Err(orchestrator_shared_types::OrchestrationError::RuntimeError(
+                format!("Injected failure of create call {} ({})", call, config.name),
+            ));
+        }
+
         let container_id = format!("mock-container-{}", Uuid::new_v4());
 
         info!(
@@ -84,13 +478,32 @@ impl ContainerRuntime for MockRuntime {
         );
         debug!("Container config: {:?}", config);
 
+        let completion = self.completions.read().await.get(&config.image).copied();
+        let state = if completion.is_some() { "stopped" } else { "running" };
+        let script = self.scripts.read().await.get(&config.name).cloned();
         let container = MockContainer {
             id: container_id.clone(),
-            _config: config.clone(),
+            config: config.clone(),
             node_id: options.node_id,
-            state: "running".to_string(),
-            exit_code: None,
+            state: state.to_string(),
+            exit_code: completion,
+            reports: 0,
+            script: script.map(ScriptProgress::new),
         };
+        self.calls
+            .write()
+            .await
+            .push(format!("create:{}", config.name));
+        self.publish(&container_id, ContainerEventKind::Created);
+        self.publish(
+            &container_id,
+            match completion {
+                Some(exit_code) => ContainerEventKind::Exited {
+                    exit_code: Some(exit_code),
+                },
+                None => ContainerEventKind::Started,
+            },
+        );
 
         // Store container
         self.containers.write().await.insert(container_id.clone(), container);
@@ -108,11 +521,21 @@ impl ContainerRuntime for MockRuntime {
 
     async fn stop_container(&self, container_id: &ContainerId) -> Result<()> {
         info!("MockRuntime: Stopping container {}", container_id);
+        self.delay(MockOperation::Stop).await;
 
         let mut containers = self.containers.write().await;
         if let Some(container) = containers.get_mut(container_id) {
-            container.state = "stopped".to_string();
-            container.exit_code = Some(0);
+            container.script = None;
+            if container.state != "stopped" {
+                container.state = "stopped".to_string();
+                container.exit_code = Some(0);
+                self.publish(container_id, ContainerEventKind::Exited { exit_code: Some(0) });
+            }
+            let mut calls = self.calls.write().await;
+            if container.config.pre_stop.is_some() {
+                calls.push(format!("pre_stop:{}", container.config.name));
+            }
+            calls.push(format!("stop:{}", container.config.name));
             Ok(())
         } else {
             Err(orchestrator_shared_types::OrchestrationError::RuntimeError(
@@ -123,14 +546,17 @@ impl ContainerRuntime for MockRuntime {
 
     async fn remove_container(&self, container_id: &ContainerId) -> Result<()> {
         info!("MockRuntime: Removing container {}", container_id);
+        self.delay(MockOperation::Remove).await;
 
         let mut containers = self.containers.write().await;
         if let Some(container) = containers.remove(container_id) {
+            self.usage.write().await.remove(container_id);
             // Remove from node tracking
             let mut by_node = self.containers_by_node.write().await;
             if let Some(node_containers) = by_node.get_mut(&container.node_id) {
                 node_containers.retain(|id| id != container_id);
             }
+            self.publish(container_id, ContainerEventKind::Removed);
             Ok(())
         } else {
             Err(orchestrator_shared_types::OrchestrationError::RuntimeError(
@@ -141,15 +567,12 @@ impl ContainerRuntime for MockRuntime {
 
     async fn get_container_status(&self, container_id: &ContainerId) -> Result<ContainerStatus> {
         debug!("MockRuntime: Getting status for container {}", container_id);
+        self.delay(MockOperation::Status).await;
 
-        let containers = self.containers.read().await;
-        if let Some(container) = containers.get(container_id) {
-            Ok(ContainerStatus {
-                id: container.id.clone(),
-                state: container.state.clone(),
-                exit_code: container.exit_code,
-                error_message: None,
-            })
+        let flap_every = self.faults.read().await.flap_status_every;
+        let mut containers = self.containers.write().await;
+        if let Some(container) = containers.get_mut(container_id) {
+            Ok(container.report(flap_every, &self.events))
         } else {
             Err(orchestrator_shared_types::OrchestrationError::RuntimeError(
                 format!("Container not found: {}", container_id),
@@ -159,8 +582,10 @@ impl ContainerRuntime for MockRuntime {
 
     async fn list_containers(&self, node_id: NodeId) -> Result<Vec<ContainerStatus>> {
         debug!("MockRuntime: Listing containers for node {}", node_id);
+        self.delay(MockOperation::List).await;
 
-        let containers = self.containers.read().await;
+        let flap_every = self.faults.read().await.flap_status_every;
+        let mut containers = self.containers.write().await;
         let by_node = self.containers_by_node.read().await;
 
         let container_ids = by_node.get(&node_id).cloned().unwrap_or_default();
@@ -168,23 +593,271 @@ impl ContainerRuntime for MockRuntime {
         let statuses: Vec<ContainerStatus> = container_ids
             .iter()
             .filter_map(|id| {
-                containers.get(id).map(|c| ContainerStatus {
-                    id: c.id.clone(),
-                    state: c.state.clone(),
-                    exit_code: c.exit_code,
-                    error_message: None,
-                })
+                containers
+                    .get_mut(id)
+                    .map(|c| c.report(flap_every, &self.events))
             })
             .collect();
 
         Ok(statuses)
     }
+
+    async fn container_usage(&self, container_id: &ContainerId) -> Result<ContainerUsage> {
+        self.check_exists(container_id).await?;
+        Ok(self
+            .usage
+            .read()
+            .await
+            .get(container_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn update_container_resources(
+        &self,
+        container_id: &ContainerId,
+        resources: &ResourceLimits,
+    ) -> Result<()> {
+        self.check_exists(container_id).await?;
+        debug!(
+            "MockRuntime: Updating resources of {}: {:?}",
+            container_id, resources
+        );
+        let mut limits = self.limits.write().await;
+        let current = limits.entry(container_id.clone()).or_default();
+        current.cpu_cores = resources.cpu_cores.or(current.cpu_cores);
+        current.memory_mb = resources.memory_mb.or(current.memory_mb);
+        current.pids_limit = resources.pids_limit.or(current.pids_limit);
+        Ok(())
+    }
+
+    /// Echoes stdin back on stdout and exits with 0 once stdin closes.
+    async fn exec(&self, container_id: &ContainerId, options: &ExecOptions) -> Result<ExecSession> {
+        self.delay(MockOperation::Exec).await;
+        self.check_exists(container_id).await?;
+        debug!("MockRuntime: Exec {:?} in container {}", options.command, container_id);
+
+        let (input, mut input_rx) = mpsc::channel(16);
+        let (output_tx, output) = mpsc::channel(16);
+        tokio::spawn(async move {
+            while let Some(message) = input_rx.recv().await {
+                match message {
+                    ExecInput::Stdin(data) => {
+                        if output_tx.send(ExecOutput::Stdout(data)).await.is_err() {
+                            return;
+                        }
+                    }
+                    ExecInput::Resize { .. } => {}
+                    ExecInput::CloseStdin => break,
+                }
+            }
+            let _ = output_tx.send(ExecOutput::Exit(Some(0))).await;
+        });
+        Ok(ExecSession { input, output })
+    }
+
+    /// Behaves like [`exec`](Self::exec) in the target container.
+    async fn debug_container(
+        &self,
+        container_id: &ContainerId,
+        options: &DebugOptions,
+    ) -> Result<ExecSession> {
+        if options.image.is_empty() {
+            return Err(orchestrator_shared_types::OrchestrationError::ConfigError(
+                "Debug containers need an image".to_string(),
+            ));
+        }
+        let exec = ExecOptions {
+            command: options.command.clone(),
+            tty: options.tty,
+        };
+        self.exec(container_id, &exec).await
+    }
+
+    async fn checkpoint_container(
+        &self,
+        container_id: &ContainerId,
+        options: &CheckpointOptions,
+    ) -> Result<Checkpoint> {
+        info!("MockRuntime: Checkpointing container {}", container_id);
+        self.delay(MockOperation::Checkpoint).await;
+
+        let mut containers = self.containers.write().await;
+        let container = containers.get_mut(container_id).ok_or_else(|| {
+            orchestrator_shared_types::OrchestrationError::RuntimeError(format!(
+                "Container not found: {}",
+                container_id
+            ))
+        })?;
+        if container.state != "running" {
+            return Err(orchestrator_shared_types::OrchestrationError::RuntimeError(
+                format!("Container {} is {}, not running", container_id, container.state),
+            ));
+        }
+        // Like a CRIU dump, stopping is not an exit
+        if !options.leave_running {
+            container.script = None;
+            container.state = "checkpointed".to_string();
+        }
+        let path = PathBuf::from(format!("/mock/checkpoints/{}", Uuid::new_v4()));
+        self.checkpoints
+            .write()
+            .await
+            .insert(path.clone(), container.config.clone());
+        self.calls
+            .write()
+            .await
+            .push(format!("checkpoint:{}", container.config.name));
+
+        Ok(Checkpoint {
+            container_id: container_id.clone(),
+            path,
+            created_at: std::time::SystemTime::now(),
+        })
+    }
+
+    async fn restore_container(
+        &self,
+        checkpoint: &Checkpoint,
+        options: &CreateContainerOptions,
+    ) -> Result<ContainerId> {
+        info!(
+            "MockRuntime: Restoring checkpoint of {} on node {}",
+            checkpoint.container_id, options.node_id
+        );
+        self.delay(MockOperation::Restore).await;
+
+        let config = self
+            .checkpoints
+            .read()
+            .await
+            .get(&checkpoint.path)
+            .cloned()
+            .ok_or_else(|| {
+                orchestrator_shared_types::OrchestrationError::RuntimeError(format!(
+                    "Checkpoint not found: {}",
+                    checkpoint.path.display()
+                ))
+            })?;
+        if self.faults.read().await.fail_restore_on.contains(&options.node_id) {
+            return Err(orchestrator_shared_types::OrchestrationError::RuntimeError(
+                format!("Injected failure of restore onto node {}", options.node_id),
+            ));
+        }
+
+        let container_id = format!("mock-container-{}", Uuid::new_v4());
+        let container = MockContainer {
+            id: container_id.clone(),
+            config: config.clone(),
+            node_id: options.node_id,
+            state: "running".to_string(),
+            exit_code: None,
+            reports: 0,
+            script: None,
+        };
+        self.calls
+            .write()
+            .await
+            .push(format!("restore:{}", config.name));
+        self.publish(&container_id, ContainerEventKind::Created);
+        self.publish(&container_id, ContainerEventKind::Started);
+
+        self.containers.write().await.insert(container_id.clone(), container);
+        self.containers_by_node
+            .write()
+            .await
+            .entry(options.node_id)
+            .or_default()
+            .push(container_id.clone());
+
+        Ok(container_id)
+    }
+
+    async fn remove_checkpoint(&self, checkpoint: &Checkpoint) -> Result<()> {
+        self.checkpoints.write().await.remove(&checkpoint.path);
+        Ok(())
+    }
+
+    async fn pull_image(
+        &self,
+        node_id: NodeId,
+        image: &str,
+        _priority: PullPriority,
+    ) -> Result<()> {
+        self.delay(MockOperation::Pull).await;
+        self.images
+            .write()
+            .await
+            .entry(node_id)
+            .or_default()
+            .insert(image.to_string());
+        Ok(())
+    }
+
+    /// Every image resolves to a single-layer linux/amd64 image whose
+    /// digest is derived from the reference.
+    async fn inspect_image(&self, image: &str) -> Result<ImageInspection> {
+        if image.trim().is_empty() {
+            return Err(OrchestrationError::ConfigError(
+                "Invalid image reference: empty image reference".to_string(),
+            ));
+        }
+        let hash = image_hash(image);
+        Ok(ImageInspection {
+            reference: image.to_string(),
+            digest: format!("sha256:{:064x}", hash),
+            platform: "linux/amd64".to_string(),
+            layers: vec![ImageLayer {
+                digest: format!("sha256:{:064x}", hash.rotate_left(32)),
+                size_bytes: 1024 * 1024,
+            }],
+            ..Default::default()
+        })
+    }
+
+    async fn scan_image(&self, image: &str) -> Result<ImageScan> {
+        let digest = format!("sha256:{:064x}", image_hash(image));
+        if let Some(scan) = self.scans.read().await.get(&digest) {
+            return Ok(scan.clone());
+        }
+        let scan = ImageScan {
+            image: image.to_string(),
+            digest: digest.clone(),
+            scanner: "mock".to_string(),
+            scanned_at: std::time::SystemTime::now(),
+            vulnerabilities: self
+                .vulnerabilities
+                .read()
+                .await
+                .get(image)
+                .cloned()
+                .unwrap_or_default(),
+        };
+        self.scans.write().await.insert(digest, scan.clone());
+        Ok(scan)
+    }
+
+    async fn image_scans(&self) -> Result<Vec<ImageScan>> {
+        Ok(self.scans.read().await.values().cloned().collect())
+    }
+
+    fn events(&self) -> Option<broadcast::Receiver<ContainerEvent>> {
+        Some(self.events.subscribe())
+    }
+}
+
+/// Stands in for the digest an image reference resolves to.
+fn image_hash(image: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    image.hash(&mut hasher);
+    hasher.finish()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use orchestrator_shared_types::{NodeResources, PortMapping};
+    use container_runtime_interface::InstanceSpec;
+    use orchestrator_shared_types::{LifecycleHandler, NodeResources, PortMapping};
     use std::collections::HashMap;
 
     fn create_test_config() -> ContainerConfig {
@@ -198,8 +871,21 @@ mod tests {
                 container_port: 80,
                 host_port: Some(8080),
                 protocol: "tcp".to_string(),
+                host_ip: None,
             }],
             resource_requests: NodeResources::default(),
+            volume_mounts: vec![],
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+            readiness_probe: None,
+            limits: Default::default(),
+            sysctls: HashMap::new(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
         }
     }
 
@@ -229,6 +915,7 @@ mod tests {
         let options = CreateContainerOptions {
             workload_id,
             node_id,
+            pull_priority: Default::default(),
         };
 
         let container_id = runtime.create_container(&config, &options).await.unwrap();
@@ -248,6 +935,7 @@ mod tests {
         let options = CreateContainerOptions {
             workload_id,
             node_id,
+            pull_priority: Default::default(),
         };
 
         let container_id = runtime.create_container(&config, &options).await.unwrap();
@@ -275,6 +963,7 @@ mod tests {
         let options = CreateContainerOptions {
             workload_id,
             node_id,
+            pull_priority: Default::default(),
         };
 
         // Create 3 containers
@@ -293,4 +982,421 @@ mod tests {
         let result = runtime.get_container_status(&"nonexistent".to_string()).await;
         assert!(result.is_err());
     }
+
+    fn named_config(name: &str, image: &str) -> ContainerConfig {
+        ContainerConfig {
+            name: name.to_string(),
+            image: image.to_string(),
+            ..create_test_config()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_instance_orders_lifecycle() {
+        let runtime = MockRuntime::new();
+        runtime.set_completion("migrate:1", 0).await;
+        let options = CreateContainerOptions {
+            workload_id: Uuid::new_v4(),
+            node_id: generate_node_id(),
+            pull_priority: Default::default(),
+        };
+        let spec = InstanceSpec {
+            init_containers: vec![named_config("migrate", "migrate:1")],
+            sidecars: vec![named_config("proxy", "envoy:1")],
+            containers: vec![named_config("web", "nginx:latest")],
+            ..Default::default()
+        };
+
+        let ids = runtime.start_instance(&spec, &options).await.unwrap();
+        assert_eq!(ids.len(), 2);
+        // The init container is gone once it has completed
+        assert_eq!(runtime.container_count().await, 2);
+
+        runtime.stop_instance(&ids).await.unwrap();
+        assert_eq!(runtime.container_count().await, 0);
+        assert_eq!(
+            runtime.calls().await,
+            vec![
+                "create:migrate",
+                "stop:migrate",
+                "create:proxy",
+                "create:web",
+                "stop:web",
+                "stop:proxy",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_init_container_aborts_instance() {
+        let runtime = MockRuntime::new();
+        runtime.set_completion("migrate:1", 1).await;
+        let options = CreateContainerOptions {
+            workload_id: Uuid::new_v4(),
+            node_id: generate_node_id(),
+            pull_priority: Default::default(),
+        };
+        let spec = InstanceSpec {
+            init_containers: vec![named_config("migrate", "migrate:1")],
+            sidecars: vec![named_config("proxy", "envoy:1")],
+            containers: vec![named_config("web", "nginx:latest")],
+            ..Default::default()
+        };
+
+        let err = runtime.start_instance(&spec, &options).await.unwrap_err();
+        assert!(err.to_string().contains("exited with code 1"));
+        assert_eq!(runtime.container_count().await, 0);
+        assert_eq!(runtime.calls().await, vec!["create:migrate", "stop:migrate"]);
+    }
+
+    #[tokio::test]
+    async fn test_stop_runs_pre_stop_hook() {
+        let runtime = MockRuntime::new();
+        let mut config = create_test_config();
+        config.pre_stop = Some(LifecycleHandler::Exec {
+            command: vec!["nginx".to_string(), "-s".to_string(), "quit".to_string()],
+        });
+        let id = runtime
+            .create_container(&config, &create_options())
+            .await
+            .unwrap();
+        runtime.stop_container(&id).await.unwrap();
+
+        assert_eq!(
+            runtime.calls().await,
+            vec![
+                "create:test-container",
+                "pre_stop:test-container",
+                "stop:test-container"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_resources_keeps_unset_limits() {
+        let runtime = MockRuntime::new();
+        let id = runtime
+            .create_container(&create_test_config(), &create_options())
+            .await
+            .unwrap();
+
+        let cpu = ResourceLimits {
+            cpu_cores: Some(2.0),
+            ..Default::default()
+        };
+        runtime.update_container_resources(&id, &cpu).await.unwrap();
+        let memory = ResourceLimits {
+            memory_mb: Some(1024),
+            ..Default::default()
+        };
+        runtime
+            .update_container_resources(&id, &memory)
+            .await
+            .unwrap();
+        assert_eq!(
+            runtime.resource_limits(&id).await,
+            ResourceLimits {
+                cpu_cores: Some(2.0),
+                memory_mb: Some(1024),
+                pids_limit: None,
+            }
+        );
+        assert!(runtime
+            .update_container_resources(&"missing".to_string(), &cpu)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_and_restore() {
+        let unreachable = generate_node_id();
+        let runtime = MockRuntime::builder()
+            .with_restore_failures_on(unreachable)
+            .build();
+        let id = runtime
+            .create_container(&create_test_config(), &create_options())
+            .await
+            .unwrap();
+
+        let checkpoint = runtime
+            .checkpoint_container(&id, &CheckpointOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(checkpoint.container_id, id);
+        assert_eq!(
+            runtime.get_container_status(&id).await.unwrap().state,
+            "checkpointed"
+        );
+
+        let onto = |node_id| CreateContainerOptions {
+            node_id,
+            ..create_options()
+        };
+        assert!(runtime
+            .restore_container(&checkpoint, &onto(unreachable))
+            .await
+            .is_err());
+
+        let target = generate_node_id();
+        let restored = runtime
+            .restore_container(&checkpoint, &onto(target))
+            .await
+            .unwrap();
+        assert_ne!(restored, id);
+        let on_target = runtime.list_containers(target).await.unwrap();
+        assert_eq!(on_target.len(), 1);
+        assert_eq!(on_target[0].state, "running");
+        assert_eq!(
+            runtime.calls().await,
+            vec![
+                "create:test-container",
+                "checkpoint:test-container",
+                "restore:test-container"
+            ]
+        );
+
+        runtime.remove_checkpoint(&checkpoint).await.unwrap();
+        assert!(runtime
+            .restore_container(&checkpoint, &create_options())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_exec_echoes_stdin() {
+        let runtime = MockRuntime::new();
+        let options = CreateContainerOptions {
+            workload_id: Uuid::new_v4(),
+            node_id: generate_node_id(),
+            pull_priority: Default::default(),
+        };
+        let id = runtime
+            .create_container(&create_test_config(), &options)
+            .await
+            .unwrap();
+
+        let exec = ExecOptions {
+            command: vec!["cat".to_string()],
+            tty: false,
+        };
+        let mut session = runtime.exec(&id, &exec).await.unwrap();
+        session.input.send(ExecInput::Stdin(b"hi".to_vec())).await.unwrap();
+        session.input.send(ExecInput::CloseStdin).await.unwrap();
+        assert_eq!(session.output.recv().await, Some(ExecOutput::Stdout(b"hi".to_vec())));
+        assert_eq!(session.output.recv().await, Some(ExecOutput::Exit(Some(0))));
+
+        assert!(runtime.exec(&"missing".to_string(), &exec).await.is_err());
+
+        let debug = DebugOptions {
+            image: "busybox".to_string(),
+            ..Default::default()
+        };
+        let mut session = runtime.debug_container(&id, &debug).await.unwrap();
+        session.input.send(ExecInput::CloseStdin).await.unwrap();
+        assert_eq!(session.output.recv().await, Some(ExecOutput::Exit(Some(0))));
+        assert!(runtime
+            .debug_container(&id, &DebugOptions::default())
+            .await
+            .is_err());
+    }
+
+    fn create_options() -> CreateContainerOptions {
+        CreateContainerOptions {
+            workload_id: Uuid::new_v4(),
+            node_id: generate_node_id(),
+            pull_priority: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_injected_create_failures() {
+        let runtime = MockRuntime::builder().with_create_failures_every(3).build();
+        let config = create_test_config();
+        let mut created = Vec::new();
+        for _ in 0..6 {
+            created.push(runtime.create_container(&config, &create_options()).await.is_ok());
+        }
+        assert_eq!(created, [true, true, false, true, true, false]);
+
+        // Clearing the faults at runtime stops the failures
+        runtime.set_faults(MockFaults::default()).await;
+        assert!(runtime.create_container(&config, &create_options()).await.is_ok());
+        assert_eq!(runtime.container_count().await, 5);
+    }
+
+    #[tokio::test]
+    async fn test_status_flaps() {
+        let runtime = MockRuntime::builder().with_status_flaps_every(2).build();
+        let id = runtime
+            .create_container(&create_test_config(), &create_options())
+            .await
+            .unwrap();
+
+        let mut states = Vec::new();
+        for _ in 0..4 {
+            states.push(runtime.get_container_status(&id).await.unwrap().state);
+        }
+        assert_eq!(states, ["running", "stopped", "running", "stopped"]);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_crash_loop() {
+        let script = ContainerScript::new().running(2).exited(137, 1).repeat();
+        let runtime = MockRuntime::builder()
+            .with_script("test-container", script)
+            .build();
+        let id = runtime
+            .create_container(&create_test_config(), &create_options())
+            .await
+            .unwrap();
+
+        let mut reports = Vec::new();
+        for _ in 0..6 {
+            let status = runtime.get_container_status(&id).await.unwrap();
+            reports.push((status.state, status.exit_code));
+        }
+        let running = ("running".to_string(), None);
+        let crashed = ("stopped".to_string(), Some(137));
+        assert_eq!(
+            reports,
+            [
+                running.clone(),
+                running.clone(),
+                crashed.clone(),
+                running.clone(),
+                running,
+                crashed
+            ]
+        );
+
+        // Stopping ends the script; the crashed container keeps its exit code
+        runtime.stop_container(&id).await.unwrap();
+        for _ in 0..2 {
+            let status = runtime.get_container_status(&id).await.unwrap();
+            assert_eq!((status.state.as_str(), status.exit_code), ("stopped", Some(137)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_events() {
+        let script = ContainerScript::new().running(1).oom_killed(1);
+        let runtime = MockRuntime::builder()
+            .with_script("test-container", script)
+            .build();
+        let mut events = runtime.events().unwrap();
+        let id = runtime
+            .create_container(&create_test_config(), &create_options())
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            runtime.get_container_status(&id).await.unwrap();
+        }
+        runtime.stop_container(&id).await.unwrap();
+        runtime.remove_container(&id).await.unwrap();
+
+        let mut kinds = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            assert_eq!(event.container_id, id);
+            kinds.push(event.kind);
+        }
+        assert_eq!(
+            kinds,
+            [
+                ContainerEventKind::Created,
+                ContainerEventKind::Started,
+                ContainerEventKind::OomKilled,
+                ContainerEventKind::Exited {
+                    exit_code: Some(137)
+                },
+                ContainerEventKind::Removed,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_script_keeps_last_state() {
+        let runtime = MockRuntime::new();
+        runtime
+            .set_script("test-container", ContainerScript::new().running(1).exited(1, 1))
+            .await;
+        let id = runtime
+            .create_container(&create_test_config(), &create_options())
+            .await
+            .unwrap();
+
+        assert_eq!(runtime.get_container_status(&id).await.unwrap().state, "running");
+        for _ in 0..3 {
+            let status = runtime.get_container_status(&id).await.unwrap();
+            assert_eq!(status.exit_code, Some(1));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_injected_latency() {
+        let runtime = MockRuntime::builder()
+            .with_latency(MockOperation::Stop, Duration::from_secs(5))
+            .build();
+        let id = runtime
+            .create_container(&create_test_config(), &create_options())
+            .await
+            .unwrap();
+
+        let start = tokio::time::Instant::now();
+        runtime.stop_container(&id).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+
+        let start = tokio::time::Instant::now();
+        runtime.remove_container(&id).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_pull_image() {
+        let runtime = MockRuntime::new();
+        let node_id = create_options().node_id;
+        assert!(!runtime.has_image(&node_id, "nginx:latest").await);
+        runtime
+            .pull_image(node_id, "nginx:latest", PullPriority::Batch)
+            .await
+            .unwrap();
+        assert!(runtime.has_image(&node_id, "nginx:latest").await);
+    }
+
+    #[tokio::test]
+    async fn test_inspect_image() {
+        let runtime = MockRuntime::new();
+        let first = runtime.inspect_image("nginx:1.27").await.unwrap();
+        let again = runtime.inspect_image("nginx:1.27").await.unwrap();
+        let other = runtime.inspect_image("nginx:1.28").await.unwrap();
+        assert_eq!(first.digest, again.digest);
+        assert_ne!(first.digest, other.digest);
+        assert_eq!(first.platform, "linux/amd64");
+        assert!(runtime.inspect_image("").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scan_image() {
+        use container_runtime_interface::Severity;
+
+        let runtime = MockRuntime::new();
+        runtime
+            .set_vulnerabilities(
+                "nginx:1.20",
+                vec![Vulnerability {
+                    id: "CVE-2021-23017".to_string(),
+                    package: "nginx".to_string(),
+                    installed_version: "1.20.0".to_string(),
+                    fixed_version: Some("1.20.1".to_string()),
+                    severity: Severity::High,
+                }],
+            )
+            .await;
+
+        let scan = runtime.scan_image("nginx:1.20").await.unwrap();
+        assert_eq!(scan.highest_severity(), Some(Severity::High));
+        assert_eq!(scan.count_above(Severity::Medium), 1);
+        let clean = runtime.scan_image("nginx:1.27").await.unwrap();
+        assert!(clean.vulnerabilities.is_empty());
+        assert_eq!(runtime.image_scans().await.unwrap().len(), 2);
+    }
 }
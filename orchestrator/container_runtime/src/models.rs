@@ -0,0 +1,598 @@
+//! Model artifacts of inference services.
+//!
+//! A container whose config declares a [`ModelArtifact`] gets the model
+//! mounted read-only at its mount path. The [`ModelStore`] of a node
+//! downloads each model once, into `{root}/<key>` where the key is the
+//! SHA-256 of its URI, and shares it between the containers using it:
+//!
+//! - `s3://bucket/key` downloads one object, signed with the node's AWS keys
+//!   (see [`crate::credentials`]) for the region in `AWS_REGION` (default:
+//!   us-east-1).
+//! - `hf://org/model[@revision]` downloads every file of a Hugging Face model
+//!   repository at the revision (default: main), with the token in `HF_TOKEN`
+//!   for gated and private models.
+//! - `oci://registry/repository:tag` pulls the layers of an OCI artifact,
+//!   e.g. one pushed with ORAS, through the [`ImageManager`].
+//!
+//! The digest of a model is the SHA-256 of its file list: one
+//! `<sha256 hex>  <relative path>\n` line per file, sorted by path, which
+//! `find . -type f | cut -c3- | LC_ALL=C sort | xargs sha256sum | sha256sum`
+//! computes in the model's directory. A declared digest is checked before a
+//! download is used, and a cached model with another digest is downloaded
+//! again.
+//!
+//! Once the cache holds more than `max_bytes`, the least recently used
+//! models no container is using are evicted.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use futures_util::StreamExt;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use orchestrator_shared_types::ModelArtifact;
+
+use crate::credentials::{aws_keys, AwsRequest};
+use crate::image::{ImageError, ImageManager};
+
+/// Default size of a node's model cache.
+pub const DEFAULT_MAX_BYTES: u64 = 100 * 1024 * 1024 * 1024;
+
+const HUGGING_FACE: &str = "https://huggingface.co";
+
+/// Errors fetching a model.
+#[derive(Debug, Error)]
+pub enum ModelError {
+    #[error("Invalid model URI: {0}")]
+    InvalidUri(String),
+
+    #[error("Model download failed: {0}")]
+    Download(String),
+
+    #[error("Model digest mismatch: expected {expected}, got {actual}")]
+    DigestMismatch { expected: String, actual: String },
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Image error: {0}")]
+    Image(#[from] ImageError),
+}
+
+/// Where a model is downloaded from.
+#[derive(Debug, PartialEq, Eq)]
+enum ModelSource<'a> {
+    S3 { bucket: &'a str, key: &'a str },
+    HuggingFace { repo: &'a str, revision: &'a str },
+    Oci(&'a str),
+}
+
+impl<'a> ModelSource<'a> {
+    fn parse(uri: &'a str) -> Result<Self, ModelError> {
+        let invalid = || ModelError::InvalidUri(uri.to_string());
+        if let Some(rest) = uri.strip_prefix("s3://") {
+            let (bucket, key) = rest.split_once('/').ok_or_else(invalid)?;
+            if bucket.is_empty() || key.is_empty() || key.ends_with('/') {
+                return Err(invalid());
+            }
+            Ok(ModelSource::S3 { bucket, key })
+        } else if let Some(rest) = uri.strip_prefix("hf://") {
+            let (repo, revision) = rest.split_once('@').unwrap_or((rest, "main"));
+            let mut parts = repo.split('/');
+            let well_formed = matches!(
+                (parts.next(), parts.next(), parts.next()),
+                (Some(org), Some(name), None) if !org.is_empty() && !name.is_empty()
+            );
+            if !well_formed || revision.is_empty() {
+                return Err(invalid());
+            }
+            Ok(ModelSource::HuggingFace { repo, revision })
+        } else if let Some(rest) = uri.strip_prefix("oci://") {
+            Ok(ModelSource::Oci(rest))
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct HfModelInfo {
+    siblings: Vec<HfSibling>,
+}
+
+#[derive(Deserialize)]
+struct HfSibling {
+    rfilename: String,
+}
+
+/// Downloads, verifies and caches the models of a node's containers.
+pub struct ModelStore {
+    root: PathBuf,
+    max_bytes: u64,
+    client: Client,
+    /// Key of the model each container uses
+    users: std::sync::Mutex<HashMap<String, String>>,
+    /// Held while a model is downloaded or evicted
+    locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl ModelStore {
+    pub fn new(root: impl Into<PathBuf>, max_bytes: u64) -> Result<Self, ModelError> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(30))
+            .build()?;
+        Ok(Self {
+            root,
+            max_bytes,
+            client,
+            users: std::sync::Mutex::new(HashMap::new()),
+            locks: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// `artifact` for `container_id`, downloaded first if it is not cached.
+    /// The model stays cached until the lease is dropped or, once kept, the
+    /// container is [released](Self::release).
+    pub async fn acquire(
+        &self,
+        container_id: &str,
+        artifact: &ModelArtifact,
+        images: &ImageManager,
+    ) -> Result<ModelLease<'_>, ModelError> {
+        let key = hex::encode(Sha256::digest(artifact.uri.as_bytes()));
+        let dir = self.root.join(&key);
+        {
+            let lock = self.lock(&key).await;
+            let _guard = lock.lock().await;
+            if !self.is_cached(&key, artifact.digest.as_deref()).await? {
+                self.download(&key, artifact, images).await?;
+            }
+            self.users
+                .lock()
+                .unwrap()
+                .insert(container_id.to_string(), key.clone());
+            self.touch(&key);
+        }
+
+        if let Err(e) = self.evict().await {
+            warn!("Failed to evict cached models: {}", e);
+        }
+        Ok(ModelLease {
+            store: self,
+            container_id: container_id.to_string(),
+            path: dir,
+            kept: false,
+        })
+    }
+
+    /// Stop keeping the model of `container_id` cached for it.
+    pub fn release(&self, container_id: &str) {
+        if let Some(key) = self.users.lock().unwrap().remove(container_id) {
+            self.touch(&key);
+        }
+    }
+
+    /// Record that the model was just used.
+    fn touch(&self, key: &str) {
+        if let Err(e) = std::fs::write(self.root.join(format!("{}.used", key)), b"") {
+            warn!("Failed to mark model {} used: {}", key, e);
+        }
+    }
+
+    async fn lock(&self, key: &str) -> Arc<Mutex<()>> {
+        self.locks
+            .lock()
+            .await
+            .entry(key.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Whether the model is cached, with `digest` if one is declared.
+    async fn is_cached(&self, key: &str, digest: Option<&str>) -> Result<bool, ModelError> {
+        if !tokio::fs::try_exists(self.root.join(key)).await? {
+            return Ok(false);
+        }
+        let Some(digest) = digest else {
+            return Ok(true);
+        };
+        let recorded = tokio::fs::read_to_string(self.root.join(format!("{}.digest", key)))
+            .await
+            .unwrap_or_default();
+        Ok(recorded == digest)
+    }
+
+    async fn download(
+        &self,
+        key: &str,
+        artifact: &ModelArtifact,
+        images: &ImageManager,
+    ) -> Result<(), ModelError> {
+        let source = ModelSource::parse(&artifact.uri)?;
+        let staging = self.root.join(format!(".{}.tmp", key));
+        remove_dir(&staging).await?;
+        tokio::fs::create_dir_all(&staging).await?;
+        info!("Downloading model {}", artifact.uri);
+
+        let result = async {
+            match source {
+                ModelSource::S3 {
+                    bucket,
+                    key: object,
+                } => self.download_s3(bucket, object, &staging).await?,
+                ModelSource::HuggingFace { repo, revision } => {
+                    self.download_hf(repo, revision, &staging).await?
+                }
+                ModelSource::Oci(image) => {
+                    images.pull_artifact(image, &staging).await?;
+                }
+            }
+            let path = staging.clone();
+            let digest = tokio::task::spawn_blocking(move || tree_digest(&path))
+                .await
+                .map_err(std::io::Error::other)??;
+            if let Some(expected) = &artifact.digest {
+                if digest != *expected {
+                    return Err(ModelError::DigestMismatch {
+                        expected: expected.clone(),
+                        actual: digest,
+                    });
+                }
+            }
+            Ok::<_, ModelError>(digest)
+        }
+        .await;
+        let digest = match result {
+            Ok(digest) => digest,
+            Err(e) => {
+                remove_dir(&staging).await?;
+                return Err(e);
+            }
+        };
+
+        let dir = self.root.join(key);
+        remove_dir(&dir).await?;
+        tokio::fs::rename(&staging, &dir).await?;
+        tokio::fs::write(self.root.join(format!("{}.digest", key)), &digest).await?;
+        info!("Model {} downloaded ({})", artifact.uri, digest);
+        Ok(())
+    }
+
+    async fn download_s3(&self, bucket: &str, key: &str, dest: &Path) -> Result<(), ModelError> {
+        let region = std::env::var("AWS_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+        let host = format!("{}.s3.{}.amazonaws.com", bucket, region);
+        let path = format!("/{}", uri_encode_path(key));
+        let keys = aws_keys(&self.client).await?;
+        let headers = AwsRequest {
+            method: "GET",
+            host: &host,
+            path: &path,
+            service: "s3",
+            region: &region,
+            headers: Vec::new(),
+            payload_hash: "UNSIGNED-PAYLOAD".to_string(),
+        }
+        .sign(&keys, chrono::Utc::now());
+
+        let mut request = self.client.get(format!("https://{}{}", host, path));
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let file_name = key.rsplit('/').next().unwrap_or(key);
+        self.fetch(request, &dest.join(file_name)).await
+    }
+
+    async fn download_hf(&self, repo: &str, revision: &str, dest: &Path) -> Result<(), ModelError> {
+        let token = std::env::var("HF_TOKEN").ok();
+        let get = |url: String| {
+            let request = self.client.get(url);
+            match &token {
+                Some(token) => request.bearer_auth(token),
+                None => request,
+            }
+        };
+
+        let url = format!("{}/api/models/{}/revision/{}", HUGGING_FACE, repo, revision);
+        let response = get(url.clone()).send().await?;
+        if !response.status().is_success() {
+            return Err(ModelError::Download(format!(
+                "{} returned {}",
+                url,
+                response.status()
+            )));
+        }
+        let info: HfModelInfo = response.json().await?;
+        for file in info.siblings {
+            if !is_relative_path(&file.rfilename) {
+                return Err(ModelError::Download(format!(
+                    "{} lists an invalid file name {:?}",
+                    repo, file.rfilename
+                )));
+            }
+            let url = format!(
+                "{}/{}/resolve/{}/{}",
+                HUGGING_FACE, repo, revision, file.rfilename
+            );
+            self.fetch(get(url), &dest.join(&file.rfilename)).await?;
+        }
+        Ok(())
+    }
+
+    /// Stream the body of `request` to `path`.
+    async fn fetch(&self, request: RequestBuilder, path: &Path) -> Result<(), ModelError> {
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let url = response.url().clone();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ModelError::Download(format!(
+                "{} returned {}: {}",
+                url,
+                status,
+                body.trim()
+            )));
+        }
+        debug!("Downloading {} to {:?}", response.url(), path);
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            file.write_all(&chunk?).await?;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Evict the least recently used models nobody uses until the cache fits
+    /// in `max_bytes`.
+    async fn evict(&self) -> Result<(), ModelError> {
+        let root = self.root.clone();
+        let mut models = tokio::task::spawn_blocking(move || cached_models(&root))
+            .await
+            .map_err(std::io::Error::other)??;
+        let mut total: u64 = models.iter().map(|m| m.size).sum();
+        if total <= self.max_bytes {
+            return Ok(());
+        }
+
+        models.sort_by_key(|m| m.last_used);
+        for model in models {
+            if total <= self.max_bytes {
+                break;
+            }
+            let lock = self.lock(&model.key).await;
+            let _guard = lock.lock().await;
+            if self
+                .users
+                .lock()
+                .unwrap()
+                .values()
+                .any(|key| *key == model.key)
+            {
+                continue;
+            }
+            info!("Evicting model {} ({} bytes)", model.key, model.size);
+            remove_dir(&self.root.join(&model.key)).await?;
+            for suffix in ["digest", "used"] {
+                let _ = tokio::fs::remove_file(self.root.join(format!("{}.{}", model.key, suffix)))
+                    .await;
+            }
+            total = total.saturating_sub(model.size);
+        }
+        if total > self.max_bytes {
+            warn!(
+                "Model cache holds {} bytes, over its {} byte limit, in models still in use",
+                total, self.max_bytes
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A model a container is about to use.
+pub struct ModelLease<'a> {
+    store: &'a ModelStore,
+    container_id: String,
+    path: PathBuf,
+    kept: bool,
+}
+
+impl ModelLease<'_> {
+    /// Directory holding the model's files.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Keep the model for the container until it is released.
+    pub fn keep(mut self) {
+        self.kept = true;
+    }
+}
+
+impl Drop for ModelLease<'_> {
+    fn drop(&mut self) {
+        if !self.kept {
+            self.store.release(&self.container_id);
+        }
+    }
+}
+
+/// A model in the cache.
+struct CachedModel {
+    key: String,
+    size: u64,
+    last_used: SystemTime,
+}
+
+fn cached_models(root: &Path) -> std::io::Result<Vec<CachedModel>> {
+    let mut models = Vec::new();
+    for entry in std::fs::read_dir(root)? {
+        let entry = entry?;
+        let key = entry.file_name().to_string_lossy().into_owned();
+        if key.starts_with('.') || !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let last_used = std::fs::metadata(root.join(format!("{}.used", key)))
+            .and_then(|m| m.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let size = files(&entry.path())?
+            .iter()
+            .map(|(_, path)| std::fs::metadata(path).map(|m| m.len()))
+            .sum::<std::io::Result<u64>>()?;
+        models.push(CachedModel {
+            key,
+            size,
+            last_used,
+        });
+    }
+    Ok(models)
+}
+
+/// Digest of the files under `dir`, see the module docs.
+fn tree_digest(dir: &Path) -> std::io::Result<String> {
+    let mut files = files(dir)?;
+    files.sort();
+    let mut list = Sha256::new();
+    for (name, path) in files {
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut std::fs::File::open(&path)?, &mut hasher)?;
+        list.update(format!("{}  {}\n", hex::encode(hasher.finalize()), name));
+    }
+    Ok(format!("sha256:{}", hex::encode(list.finalize())))
+}
+
+/// Regular files under `dir`, with their paths relative to it.
+fn files(dir: &Path) -> std::io::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![(String::new(), dir.to_path_buf())];
+    while let Some((prefix, dir)) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push((format!("{}/", name), entry.path()));
+            } else if file_type.is_file() {
+                files.push((name, entry.path()));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Whether `path` only descends from where it is joined to.
+fn is_relative_path(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// `path` percent-encoded the way AWS signs it, keeping `/`.
+fn uri_encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+async fn remove_dir(path: &Path) -> std::io::Result<()> {
+    match tokio::fs::remove_dir_all(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_model_source() {
+        assert_eq!(
+            ModelSource::parse("s3://models/llama/weights.safetensors").unwrap(),
+            ModelSource::S3 {
+                bucket: "models",
+                key: "llama/weights.safetensors"
+            }
+        );
+        assert_eq!(
+            ModelSource::parse("hf://meta-llama/Llama-3.1-8B").unwrap(),
+            ModelSource::HuggingFace {
+                repo: "meta-llama/Llama-3.1-8B",
+                revision: "main"
+            }
+        );
+        assert_eq!(
+            ModelSource::parse("hf://org/model@v1.0").unwrap(),
+            ModelSource::HuggingFace {
+                repo: "org/model",
+                revision: "v1.0"
+            }
+        );
+        assert_eq!(
+            ModelSource::parse("oci://ghcr.io/org/model:v1").unwrap(),
+            ModelSource::Oci("ghcr.io/org/model:v1")
+        );
+        for uri in [
+            "s3://bucket",
+            "s3://bucket/prefix/",
+            "hf://model",
+            "hf://a/b/c",
+            "http://example.com/model",
+        ] {
+            assert!(ModelSource::parse(uri).is_err(), "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_tree_digest() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("tokenizer")).unwrap();
+        std::fs::write(root.join("config.json"), "{}").unwrap();
+        std::fs::write(root.join("tokenizer/vocab.txt"), "a\nb\n").unwrap();
+
+        let list = format!(
+            "{}  config.json\n{}  tokenizer/vocab.txt\n",
+            hex::encode(Sha256::digest(b"{}")),
+            hex::encode(Sha256::digest(b"a\nb\n"))
+        );
+        assert_eq!(
+            tree_digest(root).unwrap(),
+            format!("sha256:{}", hex::encode(Sha256::digest(list.as_bytes())))
+        );
+    }
+
+    #[test]
+    fn test_uri_encode_path() {
+        assert_eq!(
+            uri_encode_path("models/llama 3/weights+v1.bin"),
+            "models/llama%203/weights%2Bv1.bin"
+        );
+        assert!(is_relative_path("tokenizer/vocab.txt"));
+        assert!(!is_relative_path("../escape"));
+        assert!(!is_relative_path("/etc/passwd"));
+    }
+}
@@ -7,12 +7,16 @@ use std::path::{Path, PathBuf};
 use thiserror::Error;
 use tracing::{debug, info};
 
-use orchestrator_shared_types::ContainerConfig;
+use orchestrator_shared_types::{
+    is_allowed_sysctl, is_valid_hostname, ContainerConfig, ContainerLimits, HugepageLimit,
+    IoThrottle,
+};
 
 use super::rootfs::{Rootfs, RootfsBuilder, RootfsError};
 use super::spec::{
-    Capabilities, CpuResources, Device, Linux, MemoryResources, Mount, Namespace, OciSpec,
-    PidsResources, Process, Resources, Root, User,
+    self, BlockIoResources, Capabilities, CpuResources, Device, Hooks, Linux, MemoryResources,
+    Mount, Namespace, OciSpec, PidsResources, Process, Resources, Rlimit, Root, ThrottleDevice,
+    User,
 };
 
 /// Errors that can occur during bundle operations.
@@ -94,6 +98,9 @@ impl OciBundle {
     }
 }
 
+/// PID limit of containers that set none.
+const DEFAULT_PIDS_LIMIT: i64 = 1024;
+
 /// Builder for creating OCI bundles.
 pub struct OciBundleBuilder {
     path: PathBuf,
@@ -102,11 +109,20 @@ pub struct OciBundleBuilder {
     cpu_cores: Option<f32>,
     memory_mb: Option<u64>,
     pids_limit: Option<i64>,
+    io_weight: Option<u16>,
+    io_throttle: Vec<IoThrottle>,
+    hugepages: Vec<HugepageLimit>,
     privileged: bool,
     additional_mounts: Vec<Mount>,
     additional_env: Vec<String>,
     skip_rootfs_setup: bool,
     annotations: std::collections::HashMap<String, String>,
+    nameservers: Option<Vec<std::net::IpAddr>>,
+    node_local_dns: bool,
+    host_entries: Vec<(std::net::IpAddr, Vec<String>)>,
+    capabilities: Vec<String>,
+    namespaces_of: Option<i32>,
+    hooks: Hooks,
 }
 
 impl OciBundleBuilder {
@@ -118,12 +134,21 @@ impl OciBundleBuilder {
             hostname: None,
             cpu_cores: None,
             memory_mb: None,
-            pids_limit: Some(1024), // Default PID limit
+            pids_limit: None,
+            io_weight: None,
+            io_throttle: Vec::new(),
+            hugepages: Vec::new(),
             privileged: false,
             additional_mounts: Vec::new(),
             additional_env: Vec::new(),
             skip_rootfs_setup: false,
             annotations: std::collections::HashMap::new(),
+            nameservers: None,
+            node_local_dns: false,
+            host_entries: Vec::new(),
+            capabilities: Vec::new(),
+            namespaces_of: None,
+            hooks: Hooks::default(),
         }
     }
 
@@ -157,6 +182,32 @@ impl OciBundleBuilder {
         self
     }
 
+    /// Set the relative block I/O weight, from 10 to 1000.
+    pub fn with_io_weight(mut self, weight: u16) -> Self {
+        self.io_weight = Some(weight);
+        self
+    }
+
+    /// Throttle reads and writes of a block device. Throttling a device
+    /// again replaces its limits.
+    pub fn with_io_throttle(mut self, throttle: IoThrottle) -> Self {
+        self.io_throttle
+            .retain(|t| (t.major, t.minor) != (throttle.major, throttle.minor));
+        self.io_throttle.push(throttle);
+        self
+    }
+
+    /// Limit the memory backed by hugepages of `page_size`, e.g. "2MB".
+    pub fn with_hugepage_limit(mut self, page_size: impl Into<String>, limit_bytes: u64) -> Self {
+        let page_size = page_size.into();
+        self.hugepages.retain(|h| h.page_size != page_size);
+        self.hugepages.push(HugepageLimit {
+            page_size,
+            limit_bytes,
+        });
+        self
+    }
+
     /// Run as a privileged container (all capabilities).
     pub fn privileged(mut self) -> Self {
         self.privileged = true;
@@ -187,6 +238,49 @@ impl OciBundleBuilder {
         self
     }
 
+    /// Override the nameservers in the container's /etc/resolv.conf.
+    ///
+    /// Defaults to the host's nameservers.
+    pub fn with_nameservers(mut self, nameservers: Vec<std::net::IpAddr>) -> Self {
+        self.nameservers = Some(nameservers);
+        self
+    }
+
+    /// Put the node-local DNS cache
+    /// ([`NODE_LOCAL_DNS_ADDRESS`](super::NODE_LOCAL_DNS_ADDRESS)) ahead of
+    /// the host's nameservers, for nodes running one.
+    pub fn with_node_local_dns(mut self, enabled: bool) -> Self {
+        self.node_local_dns = enabled;
+        self
+    }
+
+    /// Add an entry to the container's /etc/hosts.
+    pub fn with_host_entry(mut self, ip: std::net::IpAddr, hostnames: Vec<String>) -> Self {
+        self.host_entries.push((ip, hostnames));
+        self
+    }
+
+    /// Grant a capability beyond the defaults, e.g. `CAP_SYS_PTRACE`.
+    pub fn with_capability(mut self, capability: impl Into<String>) -> Self {
+        self.capabilities.push(capability.into());
+        self
+    }
+
+    /// Join the PID, network, IPC and UTS namespaces of the running process
+    /// `pid` instead of creating new ones. The mount namespace stays private,
+    /// so the container keeps its own root filesystem.
+    pub fn with_namespaces_of(mut self, pid: i32) -> Self {
+        self.namespaces_of = Some(pid);
+        self
+    }
+
+    /// Add lifecycle hooks, e.g. the NVIDIA container toolkit's prestart hook
+    /// for GPU access. Hooks from several calls all run, in call order.
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks.extend(hooks);
+        self
+    }
+
     /// Build the OCI bundle.
     pub fn build(self) -> BundleResult<OciBundle> {
         info!("Building OCI bundle at {:?}", self.path);
@@ -195,6 +289,7 @@ impl OciBundleBuilder {
         if self.path.exists() {
             return Err(BundleError::PathExists(self.path.clone()));
         }
+        self.validate_network_config()?;
         std::fs::create_dir_all(&self.path)?;
 
         // Create rootfs
@@ -203,7 +298,28 @@ impl OciBundleBuilder {
             std::fs::create_dir_all(&rootfs_path)?;
             Rootfs::from_path(rootfs_path)
         } else {
-            RootfsBuilder::new(&rootfs_path).build()?
+            let mut builder =
+                RootfsBuilder::new(&rootfs_path).with_node_local_dns(self.node_local_dns);
+            if let Some(hostname) = self.hostname() {
+                builder = builder.with_hostname(hostname);
+            }
+            let config = self.container_config.as_ref();
+            let dns = config.map(|c| c.dns.clone()).unwrap_or_default();
+            if let Some(nameservers) = &self.nameservers {
+                builder = builder.with_nameservers(nameservers.clone());
+            } else if !dns.nameservers.is_empty() {
+                builder = builder.with_nameservers(dns.nameservers);
+            }
+            builder = builder
+                .with_dns_searches(dns.searches)
+                .with_dns_options(dns.options);
+            for alias in config.iter().flat_map(|c| &c.host_aliases) {
+                builder = builder.with_host_entry(alias.ip, alias.hostnames.clone());
+            }
+            for (ip, hostnames) in &self.host_entries {
+                builder = builder.with_host_entry(*ip, hostnames.clone());
+            }
+            builder.build()?
         };
 
         // Build the OCI spec
@@ -223,6 +339,34 @@ impl OciBundleBuilder {
         })
     }
 
+    /// The UTS hostname: the builder's, else the container's.
+    fn hostname(&self) -> Option<String> {
+        self.hostname.clone().or_else(|| {
+            self.container_config
+                .as_ref()
+                .map(|c| c.effective_hostname().to_string())
+        })
+    }
+
+    /// Check the container's hostname and DNS settings before writing them.
+    fn validate_network_config(&self) -> BundleResult<()> {
+        let Some(config) = &self.container_config else {
+            return Ok(());
+        };
+        if let Some(hostname) = config.hostname.as_deref() {
+            if !is_valid_hostname(hostname) {
+                return Err(BundleError::InvalidConfig(format!(
+                    "Invalid hostname '{}'",
+                    hostname
+                )));
+            }
+        }
+        config
+            .dns
+            .validate()
+            .map_err(|e| BundleError::InvalidConfig(e.to_string()))
+    }
+
     /// Build the OCI specification.
     fn build_spec(&self) -> BundleResult<OciSpec> {
         let config = self.container_config.as_ref();
@@ -237,16 +381,20 @@ impl OciBundleBuilder {
         };
 
         // Build mounts
-        let mounts = self.build_mounts();
+        let mounts = self.build_mounts()?;
 
         // Build Linux config
         let linux = self.build_linux(config)?;
 
+        let hooks = self.build_hooks()?;
+
         // Determine hostname
-        let hostname = self
-            .hostname
-            .clone()
-            .or_else(|| config.map(|c| c.name.clone()));
+        let hostname = self.hostname();
+
+        // Hooks read the container's annotations, e.g. its instance's
+        // addresses, from config.json; ones set on the builder win
+        let mut annotations = config.map(|c| c.annotations.clone()).unwrap_or_default();
+        annotations.extend(self.annotations.clone());
 
         let spec = OciSpec {
             oci_version: "1.0.2".to_string(),
@@ -254,13 +402,37 @@ impl OciBundleBuilder {
             process: Some(process),
             hostname,
             mounts,
+            hooks,
             linux: Some(linux),
-            annotations: self.annotations.clone(),
+            annotations,
         };
 
         Ok(spec)
     }
 
+    /// Validate the hooks, which the spec requires to have absolute paths
+    /// and positive timeouts.
+    fn build_hooks(&self) -> BundleResult<Option<Hooks>> {
+        if self.hooks.is_empty() {
+            return Ok(None);
+        }
+        for hook in self.hooks.all() {
+            if !hook.path.starts_with('/') {
+                return Err(BundleError::InvalidConfig(format!(
+                    "Hook path must be absolute: {}",
+                    hook.path
+                )));
+            }
+            if hook.timeout == Some(0) {
+                return Err(BundleError::InvalidConfig(format!(
+                    "Hook timeout must be positive: {}",
+                    hook.path
+                )));
+            }
+        }
+        Ok(Some(self.hooks.clone()))
+    }
+
     /// Build the process configuration.
     fn build_process(&self, config: Option<&ContainerConfig>) -> BundleResult<Process> {
         // Build args
@@ -292,7 +464,11 @@ impl OciBundleBuilder {
         let capabilities = if self.privileged {
             Some(Capabilities::privileged())
         } else {
-            Some(Capabilities::default())
+            let capabilities = self
+                .capabilities
+                .iter()
+                .fold(Capabilities::default(), |caps, cap| caps.with(cap));
+            Some(capabilities)
         };
 
         Ok(Process {
@@ -302,18 +478,27 @@ impl OciBundleBuilder {
             env,
             cwd: "/".to_string(),
             capabilities,
-            rlimits: Vec::new(),
+            rlimits: build_rlimits(config)?,
             no_new_privileges: !self.privileged,
         })
     }
 
     /// Build mount configurations.
-    fn build_mounts(&self) -> Vec<Mount> {
+    fn build_mounts(&self) -> BundleResult<Vec<Mount>> {
+        let shm = match self.container_config.as_ref().and_then(|c| c.shm_size_mb) {
+            Some(0) => {
+                return Err(BundleError::InvalidConfig(
+                    "/dev/shm size must be positive".to_string(),
+                ))
+            }
+            Some(size_mb) => Mount::shm_sized(size_mb),
+            None => Mount::shm(),
+        };
         let mut mounts = vec![
             Mount::proc(),
             Mount::tmpfs("/dev"),
             Mount::devpts(),
-            Mount::shm(),
+            shm,
             Mount::mqueue(),
             Mount::sysfs(),
             Mount::cgroup(),
@@ -321,19 +506,30 @@ impl OciBundleBuilder {
 
         mounts.extend(self.additional_mounts.clone());
 
-        mounts
+        if let Some(config) = &self.container_config {
+            mounts.extend(config.volume_mounts.iter().map(|volume| {
+                Mount::bind(&volume.host_path, &volume.mount_path, volume.read_only)
+            }));
+        }
+
+        Ok(mounts)
     }
 
     /// Build Linux-specific configuration.
     fn build_linux(&self, config: Option<&ContainerConfig>) -> BundleResult<Linux> {
         // Namespaces
-        let namespaces = vec![
+        let mut namespaces = vec![
             Namespace::pid(),
             Namespace::network(),
             Namespace::ipc(),
             Namespace::uts(),
             Namespace::mount(),
         ];
+        if let Some(pid) = self.namespaces_of {
+            for (namespace, name) in namespaces.iter_mut().zip(["pid", "net", "ipc", "uts"]) {
+                namespace.path = Some(format!("/proc/{}/ns/{}", pid, name));
+            }
+        }
 
         // Devices
         let devices = vec![
@@ -346,7 +542,7 @@ impl OciBundleBuilder {
         ];
 
         // Resources
-        let resources = self.build_resources(config);
+        let resources = self.build_resources(config)?;
 
         // Masked and readonly paths for security
         let masked_paths = vec![
@@ -379,11 +575,13 @@ impl OciBundleBuilder {
             masked_paths: if self.privileged { Vec::new() } else { masked_paths },
             readonly_paths: if self.privileged { Vec::new() } else { readonly_paths },
             seccomp: None, // Could add default seccomp profile
+            sysctl: build_sysctls(config)?,
         })
     }
 
-    /// Build resource limits.
-    fn build_resources(&self, config: Option<&ContainerConfig>) -> Resources {
+    /// Build resource limits. Limits set on the builder win over the
+    /// container config's.
+    fn build_resources(&self, config: Option<&ContainerConfig>) -> BundleResult<Resources> {
         // Get CPU from config or builder override
         let cpu_cores = self.cpu_cores.or_else(|| {
             config.map(|c| c.resource_requests.cpu_cores)
@@ -394,15 +592,110 @@ impl OciBundleBuilder {
             config.map(|c| c.resource_requests.memory_mb)
         }).filter(|&m| m > 0);
 
-        Resources {
+        let config_limits = config.map(|c| c.limits.clone()).unwrap_or_default();
+        let mut limits = ContainerLimits {
+            pids: self.pids_limit.or(config_limits.pids),
+            io_weight: self.io_weight.or(config_limits.io_weight),
+            io_throttle: self.io_throttle.clone(),
+            hugepages: self.hugepages.clone(),
+        };
+        for throttle in config_limits.io_throttle {
+            let throttled = |t: &IoThrottle| (t.major, t.minor) == (throttle.major, throttle.minor);
+            if !limits.io_throttle.iter().any(throttled) {
+                limits.io_throttle.push(throttle);
+            }
+        }
+        for hugepage in config_limits.hugepages {
+            let limited = |h: &HugepageLimit| h.page_size == hugepage.page_size;
+            if !limits.hugepages.iter().any(limited) {
+                limits.hugepages.push(hugepage);
+            }
+        }
+        limits
+            .validate()
+            .map_err(|e| BundleError::InvalidConfig(e.to_string()))?;
+
+        Ok(Resources {
             cpu: cpu_cores.map(CpuResources::from_cores),
             memory: memory_mb.map(MemoryResources::from_mb),
-            block_io: None,
-            pids: self.pids_limit.map(|limit| PidsResources { limit }),
-        }
+            block_io: block_io(limits.io_weight, &limits.io_throttle),
+            pids: Some(PidsResources {
+                limit: limits.pids.unwrap_or(DEFAULT_PIDS_LIMIT),
+            }),
+            hugepage_limits: limits
+                .hugepages
+                .into_iter()
+                .map(|h| spec::HugepageLimit {
+                    page_size: h.page_size,
+                    limit: h.limit_bytes,
+                })
+                .collect(),
+        })
     }
 }
 
+/// The rlimits of a container, e.g. `RLIMIT_MEMLOCK` for `memlock`.
+fn build_rlimits(config: Option<&ContainerConfig>) -> BundleResult<Vec<Rlimit>> {
+    let Some(config) = config else {
+        return Ok(Vec::new());
+    };
+    config
+        .rlimits
+        .iter()
+        .map(|rlimit| {
+            rlimit
+                .validate()
+                .map_err(|e| BundleError::InvalidConfig(e.to_string()))?;
+            Ok(Rlimit {
+                limit_type: format!("RLIMIT_{}", rlimit.name.to_uppercase()),
+                hard: rlimit.hard,
+                soft: rlimit.soft,
+            })
+        })
+        .collect()
+}
+
+/// The sysctls of a container, refusing those that would change the node.
+fn build_sysctls(
+    config: Option<&ContainerConfig>,
+) -> BundleResult<std::collections::HashMap<String, String>> {
+    let sysctls = config.map(|c| c.sysctls.clone()).unwrap_or_default();
+    if let Some(name) = sysctls.keys().find(|name| !is_allowed_sysctl(name)) {
+        return Err(BundleError::InvalidConfig(format!(
+            "Sysctl {} is not namespaced to the container",
+            name
+        )));
+    }
+    Ok(sysctls)
+}
+
+/// The block I/O resources of a weight and device throttles, if any.
+fn block_io(weight: Option<u16>, throttles: &[IoThrottle]) -> Option<BlockIoResources> {
+    if weight.is_none() && throttles.is_empty() {
+        return None;
+    }
+    let devices = |rate: fn(&IoThrottle) -> Option<u64>| -> Vec<ThrottleDevice> {
+        throttles
+            .iter()
+            .filter_map(|t| {
+                rate(t).map(|rate| ThrottleDevice {
+                    major: t.major,
+                    minor: t.minor,
+                    rate,
+                })
+            })
+            .collect()
+    };
+    Some(BlockIoResources {
+        weight,
+        leaf_weight: None,
+        throttle_read_bps_device: devices(|t| t.read_bps),
+        throttle_write_bps_device: devices(|t| t.write_bps),
+        throttle_read_iops_device: devices(|t| t.read_iops),
+        throttle_write_iops_device: devices(|t| t.write_iops),
+    })
+}
+
 /// Create a bundle from a ContainerConfig with default settings.
 pub fn create_bundle_from_config(
     path: impl Into<PathBuf>,
@@ -430,7 +723,8 @@ pub async fn create_bundle_async(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use orchestrator_shared_types::NodeResources;
+    use crate::oci_bundle::{Hook, NODE_LOCAL_DNS_ADDRESS};
+    use orchestrator_shared_types::{HostAlias, NodeResources};
     use std::collections::HashMap;
     use tempfile::TempDir;
 
@@ -449,6 +743,18 @@ mod tests {
                 memory_mb: 256,
                 disk_mb: 0,
             },
+            volume_mounts: vec![],
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+            readiness_probe: None,
+            limits: Default::default(),
+            sysctls: Default::default(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
         }
     }
 
@@ -485,6 +791,29 @@ mod tests {
         assert!(resources.memory.is_some());
     }
 
+    #[test]
+    fn test_bundle_carries_container_annotations() {
+        let temp = TempDir::new().unwrap();
+        let bundle_path = temp.path().join("bundle");
+
+        let mut config = test_container_config();
+        config
+            .annotations
+            .insert("ip_addresses".to_string(), "10.244.0.5,fd00::5".to_string());
+        config
+            .annotations
+            .insert("owner".to_string(), "config".to_string());
+        let bundle = OciBundleBuilder::new(&bundle_path)
+            .with_container_config(&config)
+            .with_annotation("owner", "builder")
+            .build()
+            .expect("Failed to build bundle");
+
+        let annotations = &bundle.spec().annotations;
+        assert_eq!(annotations["ip_addresses"], "10.244.0.5,fd00::5");
+        assert_eq!(annotations["owner"], "builder");
+    }
+
     #[test]
     fn test_bundle_with_resource_limits() {
         let temp = TempDir::new().unwrap();
@@ -510,6 +839,184 @@ mod tests {
         assert_eq!(pids.limit, 100);
     }
 
+    #[test]
+    fn test_bundle_with_io_and_hugepage_limits() {
+        let temp = TempDir::new().unwrap();
+        let bundle_path = temp.path().join("bundle");
+
+        let mut config = test_container_config();
+        config.limits = ContainerLimits {
+            pids: Some(64),
+            io_weight: Some(200),
+            io_throttle: vec![IoThrottle {
+                major: 8,
+                minor: 0,
+                read_bps: Some(10_000_000),
+                write_bps: None,
+                read_iops: None,
+                write_iops: Some(500),
+            }],
+            hugepages: vec![HugepageLimit {
+                page_size: "2MB".to_string(),
+                limit_bytes: 64 << 20,
+            }],
+        };
+        let bundle = OciBundleBuilder::new(&bundle_path)
+            .with_container_config(&config)
+            .with_io_weight(500)
+            .with_hugepage_limit("1GB", 1 << 30)
+            .build()
+            .expect("Failed to build bundle");
+
+        let linux = bundle.spec().linux.as_ref().unwrap();
+        let resources = linux.resources.as_ref().unwrap();
+        assert_eq!(resources.pids.as_ref().unwrap().limit, 64);
+        let block_io = resources.block_io.as_ref().unwrap();
+        assert_eq!(block_io.weight, Some(500));
+        assert_eq!(block_io.throttle_read_bps_device.len(), 1);
+        assert_eq!(block_io.throttle_read_bps_device[0].rate, 10_000_000);
+        assert!(block_io.throttle_write_bps_device.is_empty());
+        assert_eq!(block_io.throttle_write_iops_device[0].rate, 500);
+        let mut page_sizes: Vec<_> = resources
+            .hugepage_limits
+            .iter()
+            .map(|h| h.page_size.as_str())
+            .collect();
+        page_sizes.sort();
+        assert_eq!(page_sizes, vec!["1GB", "2MB"]);
+
+        // Containers without limits get the default PID limit and no block I/O
+        let plain = OciBundleBuilder::new(temp.path().join("plain"))
+            .with_container_config(&test_container_config())
+            .build()
+            .expect("Failed to build bundle");
+        let resources = plain.spec().linux.as_ref().unwrap().resources.clone().unwrap();
+        assert_eq!(resources.pids.unwrap().limit, DEFAULT_PIDS_LIMIT);
+        assert!(resources.block_io.is_none());
+    }
+
+    #[test]
+    fn test_bundle_rejects_invalid_limits() {
+        let temp = TempDir::new().unwrap();
+        let result = OciBundleBuilder::new(temp.path().join("weight"))
+            .with_io_weight(5)
+            .build();
+        assert!(matches!(result, Err(BundleError::InvalidConfig(_))));
+        let result = OciBundleBuilder::new(temp.path().join("hugepages"))
+            .with_hugepage_limit("2M", 1 << 20)
+            .build();
+        assert!(matches!(result, Err(BundleError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_bundle_volume_mounts() {
+        let temp = TempDir::new().unwrap();
+        let bundle_path = temp.path().join("bundle");
+
+        let mut config = test_container_config();
+        config.volume_mounts.push(orchestrator_shared_types::VolumeMount {
+            name: "data".to_string(),
+            host_path: "/var/lib/orchestrator/volumes/db/data-db-0".to_string(),
+            mount_path: "/var/lib/postgresql".to_string(),
+            read_only: false,
+        });
+
+        let bundle = OciBundleBuilder::new(&bundle_path)
+            .with_container_config(&config)
+            .build()
+            .expect("Failed to build bundle");
+
+        let mount = bundle
+            .spec()
+            .mounts
+            .iter()
+            .find(|m| m.destination == "/var/lib/postgresql")
+            .expect("volume mount present");
+        assert_eq!(
+            mount.source.as_deref(),
+            Some("/var/lib/orchestrator/volumes/db/data-db-0")
+        );
+        assert!(mount.options.contains(&"bind".to_string()));
+        assert!(!mount.options.contains(&"ro".to_string()));
+    }
+
+    #[test]
+    fn test_bundle_sysctls_rlimits_and_shm_size() {
+        let temp = TempDir::new().unwrap();
+        let bundle_path = temp.path().join("bundle");
+
+        let mut config = test_container_config();
+        config
+            .sysctls
+            .insert("net.core.somaxconn".to_string(), "4096".to_string());
+        config
+            .rlimits
+            .push(orchestrator_shared_types::Rlimit::unlimited("memlock"));
+        config.shm_size_mb = Some(1024);
+
+        let bundle = OciBundleBuilder::new(&bundle_path)
+            .with_container_config(&config)
+            .build()
+            .expect("Failed to build bundle");
+
+        let spec = bundle.spec();
+        let linux = spec.linux.as_ref().unwrap();
+        assert_eq!(linux.sysctl["net.core.somaxconn"], "4096");
+        let rlimit = &spec.process.as_ref().unwrap().rlimits[0];
+        assert_eq!(rlimit.limit_type, "RLIMIT_MEMLOCK");
+        assert_eq!(rlimit.hard, u64::MAX);
+        let shm = spec
+            .mounts
+            .iter()
+            .find(|m| m.destination == "/dev/shm")
+            .unwrap();
+        assert!(shm.options.contains(&"size=1048576k".to_string()));
+
+        config
+            .sysctls
+            .insert("kernel.hostname".to_string(), "x".to_string());
+        let result = OciBundleBuilder::new(temp.path().join("other"))
+            .with_container_config(&config)
+            .build();
+        assert!(matches!(result, Err(BundleError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_bundle_hostname_hosts_and_dns() {
+        let temp = TempDir::new().unwrap();
+        let bundle_path = temp.path().join("bundle");
+
+        let mut config = test_container_config();
+        config.hostname = Some("db-0".to_string());
+        config.host_aliases.push(HostAlias {
+            ip: "10.0.0.7".parse().unwrap(),
+            hostnames: vec!["primary".to_string()],
+        });
+        config.dns.searches = vec!["db.svc.cluster.local".to_string()];
+        config.dns.options = vec!["ndots:2".to_string()];
+
+        let bundle = OciBundleBuilder::new(&bundle_path)
+            .with_container_config(&config)
+            .with_node_local_dns(true)
+            .build()
+            .expect("Failed to build bundle");
+
+        assert_eq!(bundle.spec().hostname.as_deref(), Some("db-0"));
+        let etc = bundle_path.join("rootfs/etc");
+        let hosts = std::fs::read_to_string(etc.join("hosts")).unwrap();
+        assert!(hosts.contains("127.0.1.1\tdb-0\n"));
+        assert!(hosts.contains("10.0.0.7\tprimary\n"));
+        let resolv = std::fs::read_to_string(etc.join("resolv.conf")).unwrap();
+        assert!(resolv.starts_with(&format!("nameserver {}\n", NODE_LOCAL_DNS_ADDRESS)));
+        assert!(resolv.contains("search db.svc.cluster.local\noptions ndots:2\n"));
+
+        config.hostname = Some("DB_0".to_string());
+        let result = OciBundleBuilder::new(temp.path().join("other"))
+            .with_container_config(&config)
+            .build();
+        assert!(matches!(result, Err(BundleError::InvalidConfig(_))));
+    }
+
     #[test]
     fn test_privileged_bundle() {
         let temp = TempDir::new().unwrap();
@@ -535,6 +1042,90 @@ mod tests {
         assert!(linux.masked_paths.is_empty());
     }
 
+    #[test]
+    fn test_bundle_joining_namespaces() {
+        let temp = TempDir::new().unwrap();
+        let bundle = OciBundleBuilder::new(temp.path().join("bundle"))
+            .with_namespaces_of(4242)
+            .with_capability("CAP_SYS_PTRACE")
+            .build()
+            .expect("Failed to build bundle");
+
+        let linux = bundle.spec().linux.as_ref().unwrap();
+        let paths: Vec<_> = linux
+            .namespaces
+            .iter()
+            .map(|ns| (ns.ns_type.as_str(), ns.path.as_deref()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("pid", Some("/proc/4242/ns/pid")),
+                ("network", Some("/proc/4242/ns/net")),
+                ("ipc", Some("/proc/4242/ns/ipc")),
+                ("uts", Some("/proc/4242/ns/uts")),
+                ("mount", None),
+            ]
+        );
+
+        let caps = bundle.spec().process.as_ref().unwrap().capabilities.as_ref().unwrap();
+        assert!(caps.effective.iter().any(|c| c == "CAP_SYS_PTRACE"));
+        assert!(caps.bounding.iter().any(|c| c == "CAP_KILL"));
+    }
+
+    #[test]
+    fn test_bundle_with_hooks() {
+        let temp = TempDir::new().unwrap();
+        let gpu = Hooks {
+            prestart: vec![Hook::new("/usr/bin/nvidia-container-runtime-hook")
+                .with_args(["nvidia-container-runtime-hook", "prestart"])],
+            ..Default::default()
+        };
+        let audit = Hooks {
+            prestart: vec![Hook::new("/usr/local/bin/audit").with_timeout(5)],
+            poststop: vec![Hook::new("/usr/local/bin/audit").with_env("EVENT=stop")],
+            ..Default::default()
+        };
+        let bundle = OciBundleBuilder::new(temp.path().join("bundle"))
+            .with_hooks(gpu)
+            .with_hooks(audit)
+            .build()
+            .expect("Failed to build bundle");
+
+        let hooks = bundle.spec().hooks.as_ref().unwrap();
+        let prestart: Vec<_> = hooks.prestart.iter().map(|h| h.path.as_str()).collect();
+        assert_eq!(
+            prestart,
+            [
+                "/usr/bin/nvidia-container-runtime-hook",
+                "/usr/local/bin/audit"
+            ]
+        );
+        assert_eq!(hooks.poststop[0].env, ["EVENT=stop"]);
+
+        let config = std::fs::read_to_string(bundle.config_path()).unwrap();
+        assert!(config.contains("\"prestart\""));
+        assert!(!config.contains("createRuntime"));
+
+        // No hooks leaves the field out
+        let plain = OciBundleBuilder::new(temp.path().join("plain"))
+            .build()
+            .unwrap();
+        assert!(plain.spec().hooks.is_none());
+    }
+
+    #[test]
+    fn test_bundle_rejects_relative_hook_paths() {
+        let temp = TempDir::new().unwrap();
+        let result = OciBundleBuilder::new(temp.path().join("bundle"))
+            .with_hooks(Hooks {
+                poststart: vec![Hook::new("netsetup")],
+                ..Default::default()
+            })
+            .build();
+        assert!(matches!(result, Err(BundleError::InvalidConfig(_))));
+    }
+
     #[test]
     fn test_bundle_cleanup() {
         let temp = TempDir::new().unwrap();
@@ -3,10 +3,11 @@
 //! This module provides comprehensive OCI bundle generation for container runtimes.
 //! It creates OCI-compliant bundles with:
 //! - Runtime specification (config.json)
-//! - Rootfs directory structure
-//! - Resource limits (CPU, memory, I/O)
+//! - Rootfs directory structure, with /etc/hostname, /etc/hosts and /etc/resolv.conf
+//! - Resource limits (CPU, memory, PIDs, block I/O, hugepages)
 //! - Linux namespaces and cgroups
 //! - Proper mount configurations
+//! - Lifecycle hooks (prestart, createRuntime, poststart, poststop)
 //!
 //! # Example
 //!
@@ -7,6 +7,7 @@
 //! - Creating symlinks
 //! - Preparing a minimal rootfs for testing
 
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::{Path, PathBuf};
 use std::io;
 use thiserror::Error;
@@ -71,6 +72,39 @@ pub const STANDARD_DIRS: &[&str] = &[
     "var/tmp",
 ];
 
+/// Address of the node-local DNS cache, when the node runs one.
+///
+/// Link-local, so it is identical on every node and never routed off-host.
+pub const NODE_LOCAL_DNS_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::new(169, 254, 20, 10));
+
+/// The host's resolver configuration, whose nameservers containers inherit.
+pub const HOST_RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Nameservers listed in resolv.conf `content`.
+///
+/// Loopback nameservers, e.g. systemd-resolved's stub, are skipped: inside
+/// the container's network namespace they would not reach the host's.
+pub fn parse_nameservers(content: &str) -> Vec<IpAddr> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("nameserver") => fields.next()?.parse::<IpAddr>().ok(),
+                _ => None,
+            }
+        })
+        .filter(|ns| !ns.is_loopback())
+        .collect()
+}
+
+/// Nameservers of the host, from [`HOST_RESOLV_CONF`]; none if it is unreadable.
+pub fn host_nameservers() -> Vec<IpAddr> {
+    std::fs::read_to_string(HOST_RESOLV_CONF)
+        .map(|content| parse_nameservers(&content))
+        .unwrap_or_default()
+}
+
 /// Device node specifications for /dev.
 #[derive(Debug, Clone)]
 pub struct DeviceNode {
@@ -107,6 +141,12 @@ pub struct RootfsBuilder {
     create_dirs: bool,
     create_dev_symlinks: bool,
     create_etc_files: bool,
+    hostname: Option<String>,
+    nameservers: Option<Vec<IpAddr>>,
+    node_local_dns: bool,
+    dns_searches: Vec<String>,
+    dns_options: Vec<String>,
+    host_entries: Vec<(IpAddr, Vec<String>)>,
 }
 
 impl RootfsBuilder {
@@ -117,9 +157,54 @@ impl RootfsBuilder {
             create_dirs: true,
             create_dev_symlinks: true,
             create_etc_files: true,
+            hostname: None,
+            nameservers: None,
+            node_local_dns: false,
+            dns_searches: Vec::new(),
+            dns_options: Vec::new(),
+            host_entries: Vec::new(),
         }
     }
 
+    /// Write `hostname` to /etc/hostname and resolve it to 127.0.1.1 in
+    /// /etc/hosts.
+    pub fn with_hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = Some(hostname.into());
+        self
+    }
+
+    /// Replace the nameservers written to /etc/resolv.conf, which default
+    /// to the host's.
+    pub fn with_nameservers(mut self, nameservers: Vec<IpAddr>) -> Self {
+        self.nameservers = Some(nameservers);
+        self
+    }
+
+    /// Resolve through the node-local DNS cache first, falling back to the
+    /// host's nameservers. Nameservers set explicitly are kept as they are.
+    pub fn with_node_local_dns(mut self, enabled: bool) -> Self {
+        self.node_local_dns = enabled;
+        self
+    }
+
+    /// Set the search domains in /etc/resolv.conf.
+    pub fn with_dns_searches(mut self, searches: Vec<String>) -> Self {
+        self.dns_searches = searches;
+        self
+    }
+
+    /// Set the resolver options in /etc/resolv.conf, e.g. "ndots:2".
+    pub fn with_dns_options(mut self, options: Vec<String>) -> Self {
+        self.dns_options = options;
+        self
+    }
+
+    /// Add an /etc/hosts entry mapping `ip` to one or more hostnames.
+    pub fn with_host_entry(mut self, ip: IpAddr, hostnames: Vec<String>) -> Self {
+        self.host_entries.push((ip, hostnames));
+        self
+    }
+
     /// Skip creating standard directories.
     pub fn skip_dirs(mut self) -> Self {
         self.create_dirs = false;
@@ -232,18 +317,55 @@ impl RootfsBuilder {
         std::fs::write(etc_path.join("shadow"), shadow_content)?;
 
         // Create /etc/hostname
-        std::fs::write(etc_path.join("hostname"), "container\n")?;
+        let hostname = self.hostname.as_deref().unwrap_or("container");
+        std::fs::write(etc_path.join("hostname"), format!("{}\n", hostname))?;
 
         // Create /etc/hosts
-        let hosts_content = "127.0.0.1\tlocalhost\n::1\tlocalhost\n";
-        std::fs::write(etc_path.join("hosts"), hosts_content)?;
+        std::fs::write(etc_path.join("hosts"), self.hosts_content())?;
 
         // Create /etc/resolv.conf
-        let resolv_content = "nameserver 8.8.8.8\nnameserver 8.8.4.4\n";
-        std::fs::write(etc_path.join("resolv.conf"), resolv_content)?;
+        std::fs::write(etc_path.join("resolv.conf"), self.resolv_conf_content())?;
 
         Ok(())
     }
+
+    fn hosts_content(&self) -> String {
+        let mut content = String::from("127.0.0.1\tlocalhost\n::1\tlocalhost\n");
+        if let Some(hostname) = &self.hostname {
+            content.push_str(&format!("127.0.1.1\t{}\n", hostname));
+        }
+        for (ip, hostnames) in &self.host_entries {
+            content.push_str(&format!("{}\t{}\n", ip, hostnames.join(" ")));
+        }
+        content
+    }
+
+    fn nameservers(&self) -> Vec<IpAddr> {
+        if let Some(nameservers) = &self.nameservers {
+            return nameservers.clone();
+        }
+        let mut nameservers = host_nameservers();
+        if self.node_local_dns {
+            nameservers.retain(|ns| *ns != NODE_LOCAL_DNS_ADDRESS);
+            nameservers.insert(0, NODE_LOCAL_DNS_ADDRESS);
+        }
+        nameservers
+    }
+
+    fn resolv_conf_content(&self) -> String {
+        let mut content: String = self
+            .nameservers()
+            .iter()
+            .map(|ns| format!("nameserver {}\n", ns))
+            .collect();
+        if !self.dns_searches.is_empty() {
+            content.push_str(&format!("search {}\n", self.dns_searches.join(" ")));
+        }
+        if !self.dns_options.is_empty() {
+            content.push_str(&format!("options {}\n", self.dns_options.join(" ")));
+        }
+        content
+    }
 }
 
 /// Represents a prepared rootfs directory.
@@ -426,4 +548,82 @@ mod tests {
         assert!(rootfs_path.join("etc").exists());
         // passwd may or may not exist depending on skip behavior
     }
+
+    #[test]
+    fn test_default_resolv_conf_inherits_host_nameservers() {
+        let temp = TempDir::new().unwrap();
+        let host: String = host_nameservers()
+            .iter()
+            .map(|ns| format!("nameserver {}\n", ns))
+            .collect();
+
+        let plain = temp.path().join("plain");
+        RootfsBuilder::new(&plain).build().unwrap();
+        let resolv = fs::read_to_string(plain.join("etc/resolv.conf")).unwrap();
+        assert_eq!(resolv, host);
+
+        let cached = temp.path().join("cached");
+        RootfsBuilder::new(&cached)
+            .with_node_local_dns(true)
+            .build()
+            .unwrap();
+        let resolv = fs::read_to_string(cached.join("etc/resolv.conf")).unwrap();
+        let first = resolv.lines().next().unwrap();
+        assert_eq!(first, format!("nameserver {}", NODE_LOCAL_DNS_ADDRESS));
+    }
+
+    #[test]
+    fn test_parse_nameservers() {
+        let resolv = "# generated\nnameserver 10.0.0.2\nnameserver 127.0.0.53\n\
+                      search corp.example\nnameserver fd00::53\nnameserver bogus\n";
+        assert_eq!(
+            parse_nameservers(resolv),
+            vec![
+                "10.0.0.2".parse::<IpAddr>().unwrap(),
+                "fd00::53".parse().unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_nameservers_and_hosts() {
+        let temp = TempDir::new().unwrap();
+        let rootfs_path = temp.path().join("rootfs");
+
+        RootfsBuilder::new(&rootfs_path)
+            .with_nameservers(vec!["fd00::53".parse().unwrap()])
+            .with_host_entry("10.0.0.5".parse().unwrap(), vec!["db".to_string(), "db.local".to_string()])
+            .build()
+            .unwrap();
+
+        let resolv = fs::read_to_string(rootfs_path.join("etc/resolv.conf")).unwrap();
+        assert_eq!(resolv, "nameserver fd00::53\n");
+        let hosts = fs::read_to_string(rootfs_path.join("etc/hosts")).unwrap();
+        assert!(hosts.contains("10.0.0.5\tdb db.local\n"));
+        assert!(hosts.starts_with("127.0.0.1\tlocalhost\n"));
+    }
+
+    #[test]
+    fn test_hostname_and_dns_options() {
+        let temp = TempDir::new().unwrap();
+        let rootfs_path = temp.path().join("rootfs");
+
+        RootfsBuilder::new(&rootfs_path)
+            .with_hostname("db-0")
+            .with_nameservers(vec!["10.96.0.10".parse().unwrap()])
+            .with_dns_searches(vec!["db.svc.cluster.local".to_string()])
+            .with_dns_options(vec!["ndots:2".to_string(), "edns0".to_string()])
+            .build()
+            .unwrap();
+
+        let hostname = fs::read_to_string(rootfs_path.join("etc/hostname")).unwrap();
+        assert_eq!(hostname, "db-0\n");
+        let hosts = fs::read_to_string(rootfs_path.join("etc/hosts")).unwrap();
+        assert!(hosts.contains("127.0.1.1\tdb-0\n"));
+        let resolv = fs::read_to_string(rootfs_path.join("etc/resolv.conf")).unwrap();
+        assert_eq!(
+            resolv,
+            "nameserver 10.96.0.10\nsearch db.svc.cluster.local\noptions ndots:2 edns0\n"
+        );
+    }
 }
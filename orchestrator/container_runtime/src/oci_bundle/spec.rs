@@ -31,6 +31,10 @@ pub struct OciSpec {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub mounts: Vec<Mount>,
 
+    /// Lifecycle hooks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<Hooks>,
+
     /// Linux-specific configuration
     #[serde(skip_serializing_if = "Option::is_none")]
     pub linux: Option<Linux>,
@@ -48,6 +52,7 @@ impl Default for OciSpec {
             process: None,
             hostname: None,
             mounts: Vec::new(),
+            hooks: None,
             linux: None,
             annotations: HashMap::new(),
         }
@@ -193,6 +198,22 @@ impl Default for Capabilities {
 }
 
 impl Capabilities {
+    /// Grant `capability` in every set, e.g. `CAP_SYS_PTRACE`.
+    pub fn with(mut self, capability: &str) -> Self {
+        for set in [
+            &mut self.bounding,
+            &mut self.effective,
+            &mut self.inheritable,
+            &mut self.permitted,
+            &mut self.ambient,
+        ] {
+            if !set.iter().any(|c| c == capability) {
+                set.push(capability.to_string());
+            }
+        }
+        self
+    }
+
     /// Create capabilities with all capabilities granted (privileged container).
     pub fn privileged() -> Self {
         let all_caps: Vec<String> = vec![
@@ -277,6 +298,107 @@ pub struct Mount {
     pub options: Vec<String>,
 }
 
+/// Lifecycle hooks, run by the runtime in the runtime's namespaces.
+///
+/// `prestart` is deprecated in favour of `createRuntime`, but is still what
+/// tools such as the NVIDIA container toolkit install.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hooks {
+    /// After the container is created, before pivot_root
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prestart: Vec<Hook>,
+
+    /// After the container is created, before pivot_root
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub create_runtime: Vec<Hook>,
+
+    /// After the container's process starts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub poststart: Vec<Hook>,
+
+    /// After the container is deleted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub poststop: Vec<Hook>,
+}
+
+impl Hooks {
+    /// Whether no hooks are set.
+    pub fn is_empty(&self) -> bool {
+        self.all().next().is_none()
+    }
+
+    /// Every hook, in no particular order.
+    pub fn all(&self) -> impl Iterator<Item = &Hook> {
+        self.prestart
+            .iter()
+            .chain(&self.create_runtime)
+            .chain(&self.poststart)
+            .chain(&self.poststop)
+    }
+
+    /// Append the hooks of `other` to these.
+    pub fn extend(&mut self, other: Hooks) {
+        self.prestart.extend(other.prestart);
+        self.create_runtime.extend(other.create_runtime);
+        self.poststart.extend(other.poststart);
+        self.poststop.extend(other.poststop);
+    }
+}
+
+/// A hook: a command on the host, which gets the container's state on stdin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    /// Absolute path of the executable
+    pub path: String,
+
+    /// Arguments, including argv[0]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+
+    /// Environment variables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+
+    /// Seconds before the hook is aborted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u32>,
+}
+
+impl Hook {
+    /// Create a hook running `path` with no arguments.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            args: Vec::new(),
+            env: Vec::new(),
+            timeout: None,
+        }
+    }
+
+    /// Set the arguments, including argv[0].
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add an environment variable (`KEY=value`).
+    pub fn with_env(mut self, env: impl Into<String>) -> Self {
+        self.env.push(env.into());
+        self
+    }
+
+    /// Abort the hook after `seconds`.
+    pub fn with_timeout(mut self, seconds: u32) -> Self {
+        self.timeout = Some(seconds);
+        self
+    }
+}
+
 impl Mount {
     /// Create a proc mount.
     pub fn proc() -> Self {
@@ -378,8 +500,13 @@ impl Mount {
         }
     }
 
-    /// Create a shm mount.
+    /// Create a shm mount of the default size, 64 MB.
     pub fn shm() -> Self {
+        Self::shm_sized(64)
+    }
+
+    /// Create a shm mount of `size_mb` megabytes.
+    pub fn shm_sized(size_mb: u64) -> Self {
         Self {
             destination: "/dev/shm".to_string(),
             mount_type: Some("tmpfs".to_string()),
@@ -389,7 +516,7 @@ impl Mount {
                 "noexec".to_string(),
                 "nodev".to_string(),
                 "mode=1777".to_string(),
-                "size=65536k".to_string(),
+                format!("size={}k", size_mb * 1024),
             ],
         }
     }
@@ -434,6 +561,10 @@ pub struct Linux {
     /// Seccomp configuration
     #[serde(skip_serializing_if = "Option::is_none")]
     pub seccomp: Option<Seccomp>,
+
+    /// Kernel parameters to set in the container's namespaces
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sysctl: HashMap<String, String>,
 }
 
 /// Linux namespace configuration.
@@ -476,6 +607,12 @@ impl Namespace {
     pub fn cgroup() -> Self {
         Self { ns_type: "cgroup".to_string(), path: None }
     }
+
+    /// Join the existing namespace at `path` instead of creating one.
+    pub fn joining(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
 }
 
 /// User/Group ID mapping for user namespaces.
@@ -617,6 +754,10 @@ pub struct Resources {
     /// PIDs limit
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pids: Option<PidsResources>,
+
+    /// Hugepage limits, by page size
+    #[serde(rename = "hugepageLimits", default, skip_serializing_if = "Vec::is_empty")]
+    pub hugepage_limits: Vec<HugepageLimit>,
 }
 
 /// CPU resource limits.
@@ -731,6 +872,14 @@ pub struct BlockIoResources {
     /// Throttle write BPS device limits
     #[serde(rename = "throttleWriteBpsDevice", default, skip_serializing_if = "Vec::is_empty")]
     pub throttle_write_bps_device: Vec<ThrottleDevice>,
+
+    /// Throttle read IOPS device limits
+    #[serde(rename = "throttleReadIOPSDevice", default, skip_serializing_if = "Vec::is_empty")]
+    pub throttle_read_iops_device: Vec<ThrottleDevice>,
+
+    /// Throttle write IOPS device limits
+    #[serde(rename = "throttleWriteIOPSDevice", default, skip_serializing_if = "Vec::is_empty")]
+    pub throttle_write_iops_device: Vec<ThrottleDevice>,
 }
 
 /// Throttle device configuration.
@@ -741,6 +890,17 @@ pub struct ThrottleDevice {
     pub rate: u64,
 }
 
+/// Hugepage limit of one page size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HugepageLimit {
+    /// Page size, e.g. "2MB"
+    pub page_size: String,
+
+    /// Limit in bytes
+    pub limit: u64,
+}
+
 /// PIDs resource limit.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PidsResources {
@@ -861,6 +1021,7 @@ mod tests {
             process: Some(Process::default()),
             hostname: Some("test-container".to_string()),
             mounts: vec![Mount::proc(), Mount::tmpfs("/dev")],
+            hooks: None,
             linux: Some(Linux {
                 namespaces: vec![
                     Namespace::pid(),
@@ -878,6 +1039,24 @@ mod tests {
         assert!(json.contains("rootfs"));
     }
 
+    #[test]
+    fn test_hooks_serialization() {
+        let hooks = Hooks {
+            prestart: vec![Hook::new("/usr/bin/nvidia-container-runtime-hook")
+                .with_args(["nvidia-container-runtime-hook", "prestart"])],
+            create_runtime: vec![Hook::new("/usr/local/bin/netsetup").with_timeout(5)],
+            ..Default::default()
+        };
+        assert!(!hooks.is_empty());
+        assert!(Hooks::default().is_empty());
+
+        let json = serde_json::to_value(&hooks).unwrap();
+        assert_eq!(json["prestart"][0]["args"][1], "prestart");
+        assert_eq!(json["createRuntime"][0]["timeout"], 5);
+        assert!(json["createRuntime"][0].get("env").is_none());
+        assert!(json.get("poststop").is_none());
+    }
+
     #[test]
     fn test_cpu_resources_from_cores() {
         let cpu = CpuResources::from_cores(0.5);
@@ -890,4 +1069,28 @@ mod tests {
         let mem = MemoryResources::from_mb(256);
         assert_eq!(mem.limit, Some(256 * 1024 * 1024));
     }
+
+    #[test]
+    fn test_block_io_and_hugepage_serialization() {
+        let resources = Resources {
+            block_io: Some(BlockIoResources {
+                weight: Some(100),
+                throttle_read_iops_device: vec![ThrottleDevice {
+                    major: 8,
+                    minor: 0,
+                    rate: 1000,
+                }],
+                ..Default::default()
+            }),
+            hugepage_limits: vec![HugepageLimit {
+                page_size: "2MB".to_string(),
+                limit: 1 << 21,
+            }],
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&resources).unwrap();
+        assert_eq!(json["blockIO"]["throttleReadIOPSDevice"][0]["rate"], 1000);
+        assert!(json["blockIO"].get("throttleReadBpsDevice").is_none());
+        assert_eq!(json["hugepageLimits"][0]["pageSize"], "2MB");
+    }
 }
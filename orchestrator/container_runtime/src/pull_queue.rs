@@ -0,0 +1,307 @@
+//! Per-node image pull queue.
+//!
+//! When many instances land on a node at once, their image pulls wait here
+//! for one of a limited number of slots. Interactive pulls are always served
+//! before batch pulls; pulls of equal priority are served in arrival order.
+//! An optional bandwidth limit is shared by every pull on the node.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+
+use container_runtime_interface::{ImagePullStatus, PullPriority};
+
+/// Limits applied to the image pulls of one node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PullQueueConfig {
+    /// Pulls downloading at the same time (default: 3).
+    pub max_concurrent_pulls: usize,
+    /// Total download rate across all pulls (default: unlimited).
+    pub max_bandwidth_bytes_per_sec: Option<u64>,
+}
+
+impl Default for PullQueueConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_pulls: 3,
+            max_bandwidth_bytes_per_sec: None,
+        }
+    }
+}
+
+type WaiterKey = (Reverse<PullPriority>, u64);
+
+#[derive(Default)]
+struct QueueState {
+    active: usize,
+    next_ticket: u64,
+    /// Ordered so the first entry is the next pull to start.
+    waiting: BTreeMap<WaiterKey, oneshot::Sender<()>>,
+}
+
+/// Hands out pull slots by priority.
+pub struct ImagePullQueue {
+    config: PullQueueConfig,
+    state: Mutex<QueueState>,
+    bandwidth: Option<BandwidthLimiter>,
+}
+
+impl ImagePullQueue {
+    pub fn new(config: PullQueueConfig) -> Self {
+        let config = PullQueueConfig {
+            max_concurrent_pulls: config.max_concurrent_pulls.max(1),
+            ..config
+        };
+        Self {
+            bandwidth: config
+                .max_bandwidth_bytes_per_sec
+                .map(BandwidthLimiter::new),
+            config,
+            state: Mutex::new(QueueState::default()),
+        }
+    }
+
+    pub fn config(&self) -> &PullQueueConfig {
+        &self.config
+    }
+
+    /// Waits for a pull slot. The slot is released when the permit is dropped.
+    pub async fn acquire(self: &Arc<Self>, priority: PullPriority) -> PullPermit {
+        let (key, rx) = {
+            let mut state = self.state.lock().unwrap();
+            if state.active < self.config.max_concurrent_pulls && state.waiting.is_empty() {
+                state.active += 1;
+                return PullPermit {
+                    queue: Arc::clone(self),
+                };
+            }
+            let key = (Reverse(priority), state.next_ticket);
+            state.next_ticket += 1;
+            let (tx, rx) = oneshot::channel();
+            state.waiting.insert(key, tx);
+            (key, rx)
+        };
+
+        let mut waiter = Waiter {
+            queue: self,
+            key,
+            granted: false,
+        };
+        // The sender is only dropped after a successful send
+        let _ = rx.await;
+        waiter.granted = true;
+        PullPermit {
+            queue: Arc::clone(self),
+        }
+    }
+
+    /// Current slot usage and queue depth.
+    pub fn status(&self) -> ImagePullStatus {
+        let state = self.state.lock().unwrap();
+        let queued_interactive = state
+            .waiting
+            .keys()
+            .filter(|(Reverse(priority), _)| *priority == PullPriority::Interactive)
+            .count();
+        ImagePullStatus {
+            active: state.active,
+            queued_interactive,
+            queued_batch: state.waiting.len() - queued_interactive,
+            max_concurrent_pulls: self.config.max_concurrent_pulls,
+            max_bandwidth_bytes_per_sec: self.config.max_bandwidth_bytes_per_sec,
+        }
+    }
+
+    /// Hands a finished pull's slot to the next waiter, or frees it.
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        while let Some((_, tx)) = state.waiting.pop_first() {
+            if tx.send(()).is_ok() {
+                return;
+            }
+        }
+        state.active -= 1;
+    }
+}
+
+/// Removes an abandoned acquire from the queue, returning its slot if one
+/// was already handed over.
+struct Waiter<'a> {
+    queue: &'a ImagePullQueue,
+    key: WaiterKey,
+    granted: bool,
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        if self.granted {
+            return;
+        }
+        let removed = self.queue.state.lock().unwrap().waiting.remove(&self.key);
+        if removed.is_none() {
+            self.queue.release();
+        }
+    }
+}
+
+/// A pull slot on a node's queue.
+pub struct PullPermit {
+    queue: Arc<ImagePullQueue>,
+}
+
+impl PullPermit {
+    /// The node's bandwidth limiter, if downloads are rate limited.
+    pub fn bandwidth(&self) -> Option<&BandwidthLimiter> {
+        self.queue.bandwidth.as_ref()
+    }
+}
+
+impl Drop for PullPermit {
+    fn drop(&mut self) {
+        self.queue.release();
+    }
+}
+
+/// Token bucket holding downloads to a byte rate, with up to one second of
+/// burst.
+pub struct BandwidthLimiter {
+    bytes_per_sec: u64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    /// Bytes that may be read without waiting; negative once overdrawn.
+    available: f64,
+    refilled_at: Instant,
+}
+
+impl BandwidthLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let bytes_per_sec = bytes_per_sec.max(1);
+        Self {
+            bytes_per_sec,
+            bucket: Mutex::new(Bucket {
+                available: bytes_per_sec as f64,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec
+    }
+
+    /// Accounts for `bytes` just downloaded, sleeping until the rate allows it.
+    pub async fn consume(&self, bytes: u64) {
+        let wait = {
+            let rate = self.bytes_per_sec as f64;
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+            bucket.available = (bucket.available + elapsed * rate).min(rate) - bytes as f64;
+            bucket.refilled_at = now;
+            if bucket.available < 0.0 {
+                Duration::from_secs_f64(-bucket.available / rate)
+            } else {
+                Duration::ZERO
+            }
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn wait_for_depth(queue: &ImagePullQueue, depth: usize) {
+        while queue.status().queue_depth() < depth {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interactive_pulls_jump_batch_pulls() {
+        let queue = Arc::new(ImagePullQueue::new(PullQueueConfig {
+            max_concurrent_pulls: 1,
+            max_bandwidth_bytes_per_sec: None,
+        }));
+        let running = queue.acquire(PullPriority::Interactive).await;
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let mut handles = Vec::new();
+        for priority in [PullPriority::Batch, PullPriority::Interactive] {
+            let (task_queue, task_order) = (queue.clone(), order.clone());
+            handles.push(tokio::spawn(async move {
+                let _permit = task_queue.acquire(priority).await;
+                task_order.lock().unwrap().push(priority);
+            }));
+            wait_for_depth(&queue, handles.len()).await;
+        }
+
+        let status = queue.status();
+        assert_eq!(status.active, 1);
+        assert_eq!(status.queued_batch, 1);
+        assert_eq!(status.queued_interactive, 1);
+
+        drop(running);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![PullPriority::Interactive, PullPriority::Batch]
+        );
+        assert_eq!(
+            queue.status(),
+            ImagePullStatus {
+                max_concurrent_pulls: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_waiters_do_not_hold_slots() {
+        let queue = Arc::new(ImagePullQueue::new(PullQueueConfig {
+            max_concurrent_pulls: 1,
+            max_bandwidth_bytes_per_sec: None,
+        }));
+        let running = queue.acquire(PullPriority::Batch).await;
+
+        let waiting = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                let _permit = queue.acquire(PullPriority::Batch).await;
+            })
+        };
+        wait_for_depth(&queue, 1).await;
+        waiting.abort();
+        let _ = waiting.await;
+        assert_eq!(queue.status().queue_depth(), 0);
+
+        drop(running);
+        assert_eq!(queue.status().active, 0);
+        let _permit = queue.acquire(PullPriority::Batch).await;
+        assert_eq!(queue.status().active, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bandwidth_limiter_holds_rate() {
+        let limiter = BandwidthLimiter::new(1000);
+        let start = Instant::now();
+
+        // The first second of traffic is allowed as a burst
+        limiter.consume(1000).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter.consume(2000).await;
+        assert!(start.elapsed() >= Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,483 @@
+//! Pushing local OCI image layouts to a registry.
+//!
+//! An [`OciLayout`] is a directory with an `oci-layout` file, an `index.json`
+//! and content-addressed `blobs/`, as written by `buildah push`, `skopeo copy
+//! oci:DIR` or BuildKit's OCI exporter. Pushing one uploads each blob the
+//! registry lacks in chunks (POST, then PATCH per chunk, then a PUT with the
+//! digest) before putting the manifest under the tag. Registries asking for
+//! a bearer token get one from their token service, with basic credentials
+//! if given.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::image::ImageError;
+
+/// Annotation naming the tag of a manifest in `index.json`.
+const REF_NAME_ANNOTATION: &str = "org.opencontainers.image.ref.name";
+
+/// Size of each PATCH of a chunked blob upload.
+#[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Login for a registry.
+#[derive(Clone)]
+pub struct RegistryCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl std::fmt::Debug for RegistryCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistryCredentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// What a push uploaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushSummary {
+    /// Digest of the pushed manifest.
+    pub digest: String,
+    /// Blobs uploaded.
+    pub uploaded: usize,
+    /// Blobs the registry already had.
+    pub existing: usize,
+    /// Bytes uploaded.
+    pub bytes: u64,
+}
+
+/// Reference to a blob in `index.json` or a manifest.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Descriptor {
+    pub media_type: String,
+    pub digest: String,
+    pub size: i64,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct OciIndex {
+    manifests: Vec<Descriptor>,
+}
+
+/// A local OCI image layout directory.
+#[derive(Debug, Clone)]
+pub struct OciLayout {
+    root: PathBuf,
+}
+
+impl OciLayout {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self, ImageError> {
+        let root = root.into();
+        if !root.join("oci-layout").is_file() {
+            return Err(ImageError::InvalidLayout(format!(
+                "{} has no oci-layout file",
+                root.display()
+            )));
+        }
+        Ok(Self { root })
+    }
+
+    /// Where the blob `digest` is stored.
+    pub fn blob_path(&self, digest: &str) -> Result<PathBuf, ImageError> {
+        let (algorithm, hex) = digest
+            .split_once(':')
+            .filter(|(a, h)| {
+                !a.is_empty() && !h.is_empty() && h.chars().all(|c| c.is_ascii_alphanumeric())
+            })
+            .ok_or_else(|| ImageError::InvalidLayout(format!("invalid digest '{}'", digest)))?;
+        let path = self.root.join("blobs").join(algorithm).join(hex);
+        if !path.is_file() {
+            return Err(ImageError::LayerNotFound(digest.to_string()));
+        }
+        Ok(path)
+    }
+
+    /// The manifest tagged `tag`, or the layout's only manifest when none
+    /// carries a tag, along with its raw bytes.
+    pub fn manifest(&self, tag: &str) -> Result<(Descriptor, Vec<u8>), ImageError> {
+        let index: OciIndex =
+            serde_json::from_slice(&std::fs::read(self.root.join("index.json"))?)?;
+        let tagged = |d: &&Descriptor| {
+            d.annotations.get(REF_NAME_ANNOTATION).map(String::as_str) == Some(tag)
+        };
+        let descriptor = match index.manifests.iter().find(tagged) {
+            Some(d) => d.clone(),
+            None => match index.manifests.as_slice() {
+                [only] if !only.annotations.contains_key(REF_NAME_ANNOTATION) => only.clone(),
+                _ => {
+                    return Err(ImageError::ManifestNotFound(format!(
+                        "{} has no manifest tagged '{}'",
+                        self.root.display(),
+                        tag
+                    )))
+                }
+            },
+        };
+        if descriptor.media_type.contains("index") || descriptor.media_type.contains("list") {
+            return Err(ImageError::UnsupportedMediaType(descriptor.media_type));
+        }
+        let bytes = std::fs::read(self.blob_path(&descriptor.digest)?)?;
+        Ok((descriptor, bytes))
+    }
+}
+
+/// Authentication a registry asks for in its `WWW-Authenticate` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+enum Challenge {
+    Basic,
+    Bearer {
+        realm: String,
+        service: Option<String>,
+    },
+}
+
+/// Parse a `WWW-Authenticate` header, e.g.
+/// `Bearer realm="https://auth.example.com/token",service="registry"`.
+#[cfg_attr(not(feature = "image-pull"), allow(dead_code))]
+fn parse_challenge(header: &str) -> Option<Challenge> {
+    let (scheme, params) = header.trim().split_once(' ').unwrap_or((header.trim(), ""));
+    if scheme.eq_ignore_ascii_case("basic") {
+        return Some(Challenge::Basic);
+    }
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+
+    // Quoted values may themselves contain commas, e.g. scope="repo:a:pull,push"
+    let mut values = HashMap::new();
+    let mut rest = params.trim();
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key
+            .trim()
+            .trim_start_matches(',')
+            .trim()
+            .to_ascii_lowercase();
+        let (value, after) = match after.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => after.split_once(',').unwrap_or((after, "")),
+        };
+        values.insert(key, value.to_string());
+        rest = after.trim_start_matches(',').trim();
+    }
+    Some(Challenge::Bearer {
+        realm: values.remove("realm")?,
+        service: values.remove("service"),
+    })
+}
+
+#[cfg(feature = "image-pull")]
+pub(crate) use upload::{authorize, push_layout};
+
+#[cfg(feature = "image-pull")]
+mod upload {
+    use std::path::Path;
+
+    use reqwest::header::{
+        CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, LOCATION, WWW_AUTHENTICATE,
+    };
+    use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+    use tokio::io::AsyncReadExt;
+    use tracing::{debug, info};
+
+    use super::*;
+    use crate::image::{ImageReference, Manifest};
+
+    /// Authorization sent with each request.
+    pub(crate) enum Auth {
+        None,
+        Basic(RegistryCredentials),
+        Bearer(String),
+    }
+
+    impl Auth {
+        pub(crate) fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+            match self {
+                Auth::None => request,
+                Auth::Basic(c) => request.basic_auth(&c.username, Some(&c.password)),
+                Auth::Bearer(token) => request.bearer_auth(token),
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct TokenResponse {
+        #[serde(default)]
+        token: Option<String>,
+        #[serde(default)]
+        access_token: Option<String>,
+    }
+
+    /// Fail with the registry's message unless `response` has `expected` status.
+    async fn expect(response: Response, expected: StatusCode) -> Result<Response, ImageError> {
+        if response.status() == expected {
+            return Ok(response);
+        }
+        Err(ImageError::Registry {
+            status: response.status().as_u16(),
+            message: response.text().await.unwrap_or_default(),
+        })
+    }
+
+    /// The upload URL a registry returned, relative to `base`.
+    fn location(base: &Url, response: &Response) -> Result<Url, ImageError> {
+        let registry_error = |message: String| ImageError::Registry {
+            status: response.status().as_u16(),
+            message,
+        };
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|l| l.to_str().ok())
+            .ok_or_else(|| registry_error("upload response has no Location".to_string()))?;
+        base.join(location)
+            .map_err(|e| registry_error(format!("invalid Location '{}': {}", location, e)))
+    }
+
+    /// Work out how to authorize `actions`, e.g. "pull,push", on `repository`.
+    pub(crate) async fn authorize(
+        client: &Client,
+        base: &Url,
+        repository: &str,
+        actions: &str,
+        credentials: Option<&RegistryCredentials>,
+    ) -> Result<Auth, ImageError> {
+        let response = client
+            .get(base.join("/v2/").expect("static path"))
+            .send()
+            .await?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(Auth::None);
+        }
+        let challenge = response
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|h| h.to_str().ok())
+            .and_then(parse_challenge);
+        match (challenge, credentials) {
+            (Some(Challenge::Basic), Some(credentials)) => Ok(Auth::Basic(credentials.clone())),
+            (Some(Challenge::Bearer { realm, service }), credentials) => {
+                let scope = format!("repository:{}:{}", repository, actions);
+                let mut query = vec![("scope", scope.as_str())];
+                if let Some(service) = &service {
+                    query.push(("service", service.as_str()));
+                }
+                let mut request = client.get(&realm).query(&query);
+                if let Some(c) = credentials {
+                    request = request.basic_auth(&c.username, Some(&c.password));
+                }
+                let response = expect(request.send().await?, StatusCode::OK).await?;
+                let token: TokenResponse = response.json().await?;
+                token
+                    .token
+                    .or(token.access_token)
+                    .map(Auth::Bearer)
+                    .ok_or_else(|| ImageError::Registry {
+                        status: 200,
+                        message: format!("token service {} returned no token", realm),
+                    })
+            }
+            _ => Err(ImageError::Registry {
+                status: 401,
+                message: "registry requires credentials".to_string(),
+            }),
+        }
+    }
+
+    /// Upload one blob unless the registry has it. Returns whether it did.
+    async fn upload_blob(
+        client: &Client,
+        auth: &Auth,
+        base: &Url,
+        repository: &str,
+        digest: &str,
+        path: &Path,
+    ) -> Result<Option<u64>, ImageError> {
+        let blob_url = format!("{}v2/{}/blobs/{}", base, repository, digest);
+        let response = auth.apply(client.head(&blob_url)).send().await?;
+        if response.status().is_success() {
+            debug!("Blob {} already in registry", digest);
+            return Ok(None);
+        }
+
+        let uploads_url = format!("{}v2/{}/blobs/uploads/", base, repository);
+        let response = auth.apply(client.post(&uploads_url)).send().await?;
+        let response = expect(response, StatusCode::ACCEPTED).await?;
+        let mut upload_url = location(base, &response)?;
+
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut offset = 0u64;
+        loop {
+            let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+            (&mut file)
+                .take(CHUNK_SIZE as u64)
+                .read_to_end(&mut chunk)
+                .await?;
+            if chunk.is_empty() {
+                break;
+            }
+            let len = chunk.len() as u64;
+            let request = client
+                .patch(upload_url.clone())
+                .header(CONTENT_TYPE, "application/octet-stream")
+                .header(CONTENT_RANGE, format!("{}-{}", offset, offset + len - 1))
+                .header(CONTENT_LENGTH, len)
+                .body(chunk);
+            let response = expect(auth.apply(request).send().await?, StatusCode::ACCEPTED).await?;
+            upload_url = location(base, &response)?;
+            offset += len;
+        }
+
+        upload_url.query_pairs_mut().append_pair("digest", digest);
+        let request = client.put(upload_url).header(CONTENT_LENGTH, 0);
+        expect(auth.apply(request).send().await?, StatusCode::CREATED).await?;
+        debug!("Uploaded blob {} ({} bytes)", digest, offset);
+        Ok(Some(offset))
+    }
+
+    /// Push the manifest of `layout` tagged like `image_ref`, and the blobs
+    /// it references, to `image_ref`.
+    pub(crate) async fn push_layout(
+        client: &Client,
+        credentials: Option<&RegistryCredentials>,
+        layout: &OciLayout,
+        image_ref: &ImageReference,
+    ) -> Result<PushSummary, ImageError> {
+        let (descriptor, manifest_bytes) = layout.manifest(&image_ref.tag)?;
+        let manifest: Manifest = serde_json::from_slice(&manifest_bytes)?;
+        let base = Url::parse(&format!("https://{}/", image_ref.registry))
+            .map_err(|e| ImageError::InvalidReference(format!("{}: {}", image_ref, e)))?;
+        let auth = authorize(
+            client,
+            &base,
+            &image_ref.repository,
+            "pull,push",
+            credentials,
+        )
+        .await?;
+
+        info!(
+            "Pushing {} ({} layers) to {}",
+            descriptor.digest,
+            manifest.layers.len(),
+            image_ref
+        );
+        let mut summary = PushSummary {
+            digest: descriptor.digest.clone(),
+            uploaded: 0,
+            existing: 0,
+            bytes: 0,
+        };
+        let blobs = std::iter::once(&manifest.config.digest)
+            .chain(manifest.layers.iter().map(|l| &l.digest));
+        for digest in blobs {
+            let path = layout.blob_path(digest)?;
+            match upload_blob(client, &auth, &base, &image_ref.repository, digest, &path).await? {
+                Some(bytes) => {
+                    summary.uploaded += 1;
+                    summary.bytes += bytes;
+                }
+                None => summary.existing += 1,
+            }
+        }
+
+        let manifest_url = format!(
+            "{}v2/{}/manifests/{}",
+            base, image_ref.repository, image_ref.tag
+        );
+        let request = client
+            .put(&manifest_url)
+            .header(CONTENT_TYPE, &descriptor.media_type)
+            .body(manifest_bytes);
+        expect(auth.apply(request).send().await?, StatusCode::CREATED).await?;
+        info!("Pushed {} as {}", descriptor.digest, image_ref);
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_challenge() {
+        assert_eq!(
+            parse_challenge(
+                r#"Bearer realm="https://a.io/t",service="reg.io",scope="repository:a:pull,push""#
+            ),
+            Some(Challenge::Bearer {
+                realm: "https://a.io/t".to_string(),
+                service: Some("reg.io".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_challenge(r#"Basic realm="Registry""#),
+            Some(Challenge::Basic)
+        );
+        assert_eq!(parse_challenge("Bearer service=\"x\""), None);
+        assert_eq!(parse_challenge("Negotiate"), None);
+    }
+
+    #[test]
+    fn test_layout_manifest() {
+        let temp = TempDir::new().unwrap();
+        let blob = |digest: &str, contents: &str| {
+            let dir = temp.path().join("blobs/sha256");
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(digest), contents).unwrap();
+        };
+        assert!(matches!(
+            OciLayout::open(temp.path()),
+            Err(ImageError::InvalidLayout(_))
+        ));
+
+        std::fs::write(
+            temp.path().join("oci-layout"),
+            r#"{"imageLayoutVersion":"1.0.0"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp.path().join("index.json"),
+            r#"{"schemaVersion":2,"manifests":[
+                {"mediaType":"application/vnd.oci.image.manifest.v1+json",
+                 "digest":"sha256:aaa","size":2,
+                 "annotations":{"org.opencontainers.image.ref.name":"v1"}},
+                {"mediaType":"application/vnd.oci.image.manifest.v1+json",
+                 "digest":"sha256:bbb","size":2,
+                 "annotations":{"org.opencontainers.image.ref.name":"v2"}}
+            ]}"#,
+        )
+        .unwrap();
+        blob("aaa", "{}");
+        blob("bbb", "[]");
+
+        let layout = OciLayout::open(temp.path()).unwrap();
+        let (descriptor, bytes) = layout.manifest("v2").unwrap();
+        assert_eq!(descriptor.digest, "sha256:bbb");
+        assert_eq!(bytes, b"[]");
+        assert!(matches!(
+            layout.manifest("v3"),
+            Err(ImageError::ManifestNotFound(_))
+        ));
+        assert!(matches!(
+            layout.blob_path("sha256:../../etc"),
+            Err(ImageError::InvalidLayout(_))
+        ));
+        assert!(matches!(
+            layout.blob_path("sha256:ccc"),
+            Err(ImageError::LayerNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,336 @@
+//! Vulnerability scanning of pulled images.
+//!
+//! An [`ImageScanner`] runs Trivy or Grype against an extracted rootfs and
+//! turns its JSON report into [`Vulnerability`] entries. Trivy can hand the
+//! work to a Trivy server, so nodes share one vulnerability database instead
+//! of each downloading their own. The image manager records the result per
+//! image digest.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::process::Command;
+use tracing::debug;
+
+use container_runtime_interface::Vulnerability;
+
+use crate::image::ImageError;
+
+/// Scanner binaries the runtime knows how to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScannerKind {
+    Trivy,
+    Grype,
+}
+
+impl ScannerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScannerKind::Trivy => "trivy",
+            ScannerKind::Grype => "grype",
+        }
+    }
+}
+
+impl FromStr for ScannerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "trivy" => Ok(ScannerKind::Trivy),
+            "grype" => Ok(ScannerKind::Grype),
+            other => Err(format!(
+                "unknown scanner '{}', expected trivy or grype",
+                other
+            )),
+        }
+    }
+}
+
+/// How to run the scanner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannerConfig {
+    pub kind: ScannerKind,
+    /// Scanner binary (default: the scanner's name, searched in PATH).
+    pub binary: Option<PathBuf>,
+    /// Trivy server to scan through, e.g. `http://trivy:4954`.
+    pub server: Option<String>,
+    /// How long one scan may take (default: 5m).
+    pub timeout: Duration,
+}
+
+impl ScannerConfig {
+    pub fn new(kind: ScannerKind) -> Self {
+        Self {
+            kind,
+            binary: None,
+            server: None,
+            timeout: Duration::from_secs(300),
+        }
+    }
+
+    pub fn with_binary(mut self, binary: impl Into<PathBuf>) -> Self {
+        self.binary = Some(binary.into());
+        self
+    }
+
+    pub fn with_server(mut self, server: impl Into<String>) -> Self {
+        self.server = Some(server.into());
+        self
+    }
+}
+
+/// Runs a vulnerability scanner binary.
+#[derive(Debug, Clone)]
+pub struct ImageScanner {
+    config: ScannerConfig,
+}
+
+impl ImageScanner {
+    pub fn new(config: ScannerConfig) -> Self {
+        Self { config }
+    }
+
+    /// Name recorded with each scan.
+    pub fn name(&self) -> &'static str {
+        self.config.kind.as_str()
+    }
+
+    /// Arguments scanning `rootfs`.
+    fn args(&self, rootfs: &Path) -> Vec<String> {
+        let rootfs = rootfs.display().to_string();
+        match self.config.kind {
+            ScannerKind::Trivy => {
+                let mut args: Vec<String> = [
+                    "rootfs",
+                    "--quiet",
+                    "--format",
+                    "json",
+                    "--scanners",
+                    "vuln",
+                ]
+                .into_iter()
+                .map(String::from)
+                .collect();
+                if let Some(server) = &self.config.server {
+                    args.push("--server".to_string());
+                    args.push(server.clone());
+                }
+                args.push(rootfs);
+                args
+            }
+            ScannerKind::Grype => vec![
+                format!("dir:{}", rootfs),
+                "--output".to_string(),
+                "json".to_string(),
+                "--quiet".to_string(),
+            ],
+        }
+    }
+
+    /// Scans an extracted image rootfs.
+    pub async fn scan(&self, rootfs: &Path) -> Result<Vec<Vulnerability>, ImageError> {
+        let binary = self
+            .config
+            .binary
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(self.name()));
+        let args = self.args(rootfs);
+        debug!("Scanning {} with {:?} {:?}", rootfs.display(), binary, args);
+
+        let output = tokio::time::timeout(
+            self.config.timeout,
+            Command::new(&binary)
+                .args(&args)
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await
+        .map_err(|_| {
+            ImageError::Scan(format!(
+                "{} timed out after {}s",
+                self.name(),
+                self.config.timeout.as_secs()
+            ))
+        })?
+        .map_err(|e| ImageError::Scan(format!("failed to run {:?}: {}", binary, e)))?;
+
+        if !output.status.success() {
+            return Err(ImageError::Scan(format!(
+                "{} exited with {}: {}",
+                self.name(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        match self.config.kind {
+            ScannerKind::Trivy => parse_trivy(&output.stdout),
+            ScannerKind::Grype => parse_grype(&output.stdout),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TrivyReport {
+    #[serde(default)]
+    results: Option<Vec<TrivyResult>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TrivyResult {
+    #[serde(default)]
+    vulnerabilities: Option<Vec<TrivyVulnerability>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TrivyVulnerability {
+    #[serde(rename = "VulnerabilityID")]
+    vulnerability_id: String,
+    pkg_name: String,
+    #[serde(default)]
+    installed_version: String,
+    #[serde(default)]
+    fixed_version: Option<String>,
+    #[serde(default)]
+    severity: String,
+}
+
+/// Vulnerabilities of a `trivy --format json` report.
+fn parse_trivy(report: &[u8]) -> Result<Vec<Vulnerability>, ImageError> {
+    let report: TrivyReport = serde_json::from_slice(report)?;
+    Ok(report
+        .results
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|r| r.vulnerabilities.unwrap_or_default())
+        .map(|v| Vulnerability {
+            id: v.vulnerability_id,
+            package: v.pkg_name,
+            installed_version: v.installed_version,
+            fixed_version: v.fixed_version.filter(|f| !f.is_empty()),
+            severity: v.severity.parse().unwrap_or_default(),
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct GrypeReport {
+    #[serde(default)]
+    matches: Vec<GrypeMatch>,
+}
+
+#[derive(Deserialize)]
+struct GrypeMatch {
+    vulnerability: GrypeVulnerability,
+    artifact: GrypeArtifact,
+}
+
+#[derive(Deserialize)]
+struct GrypeVulnerability {
+    id: String,
+    #[serde(default)]
+    severity: String,
+    #[serde(default)]
+    fix: Option<GrypeFix>,
+}
+
+#[derive(Deserialize)]
+struct GrypeFix {
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct GrypeArtifact {
+    name: String,
+    #[serde(default)]
+    version: String,
+}
+
+/// Vulnerabilities of a `grype --output json` report.
+fn parse_grype(report: &[u8]) -> Result<Vec<Vulnerability>, ImageError> {
+    let report: GrypeReport = serde_json::from_slice(report)?;
+    Ok(report
+        .matches
+        .into_iter()
+        .map(|m| Vulnerability {
+            id: m.vulnerability.id,
+            package: m.artifact.name,
+            installed_version: m.artifact.version,
+            fixed_version: m
+                .vulnerability
+                .fix
+                .and_then(|f| f.versions.into_iter().next()),
+            severity: m.vulnerability.severity.parse().unwrap_or_default(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use container_runtime_interface::Severity;
+
+    #[test]
+    fn test_parse_trivy() {
+        let report = br#"{
+            "Results": [
+                {"Target": "rootfs", "Vulnerabilities": [
+                    {"VulnerabilityID": "CVE-2024-3094", "PkgName": "xz-utils",
+                     "InstalledVersion": "5.6.0", "FixedVersion": "5.6.1", "Severity": "CRITICAL"},
+                    {"VulnerabilityID": "CVE-2023-1", "PkgName": "zlib",
+                     "InstalledVersion": "1.2", "Severity": "LOW"}
+                ]},
+                {"Target": "app.jar", "Vulnerabilities": null}
+            ]
+        }"#;
+        let vulnerabilities = parse_trivy(report).unwrap();
+        assert_eq!(vulnerabilities.len(), 2);
+        assert_eq!(vulnerabilities[0].id, "CVE-2024-3094");
+        assert_eq!(vulnerabilities[0].severity, Severity::Critical);
+        assert_eq!(vulnerabilities[0].fixed_version.as_deref(), Some("5.6.1"));
+        assert_eq!(vulnerabilities[1].fixed_version, None);
+
+        // A clean image has no results at all
+        assert!(parse_trivy(br#"{"Results": null}"#).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_grype() {
+        let report = br#"{
+            "matches": [
+                {"vulnerability": {"id": "CVE-2023-2", "severity": "Negligible",
+                                   "fix": {"versions": [], "state": "not-fixed"}},
+                 "artifact": {"name": "bash", "version": "5.1"}},
+                {"vulnerability": {"id": "GHSA-x", "severity": "High",
+                                   "fix": {"versions": ["2.0.1"], "state": "fixed"}},
+                 "artifact": {"name": "requests", "version": "2.0.0"}}
+            ]
+        }"#;
+        let vulnerabilities = parse_grype(report).unwrap();
+        assert_eq!(vulnerabilities[0].severity, Severity::Low);
+        assert_eq!(vulnerabilities[0].fixed_version, None);
+        assert_eq!(vulnerabilities[1].package, "requests");
+        assert_eq!(vulnerabilities[1].fixed_version.as_deref(), Some("2.0.1"));
+    }
+
+    #[test]
+    fn test_scanner_args() {
+        let trivy = ImageScanner::new(
+            ScannerConfig::new(ScannerKind::Trivy).with_server("http://trivy:4954"),
+        );
+        assert_eq!(
+            trivy.args(Path::new("/images/rootfs/nginx")).join(" "),
+            "rootfs --quiet --format json --scanners vuln --server http://trivy:4954 \
+             /images/rootfs/nginx"
+        );
+        let grype = ImageScanner::new(ScannerConfig::new(ScannerKind::Grype));
+        assert_eq!(grype.args(Path::new("/r"))[0], "dir:/r");
+    }
+}
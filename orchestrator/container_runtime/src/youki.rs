@@ -34,8 +34,8 @@ use libcontainer::container::builder::ContainerBuilder;
 use libcontainer::syscall::syscall::SyscallType;
 use nix::sys::signal::Signal;
 use oci_spec::runtime::{
-    LinuxBuilder, LinuxNamespaceBuilder, LinuxNamespaceType, ProcessBuilder, RootBuilder, Spec,
-    SpecBuilder, UserBuilder,
+    get_default_mounts, LinuxBuilder, LinuxNamespaceBuilder, LinuxNamespaceType, MountBuilder,
+    ProcessBuilder, RootBuilder, Spec, SpecBuilder, UserBuilder,
 };
 
 /// Metadata about a managed container
@@ -189,12 +189,35 @@ impl YoukiRuntime {
             .map_err(|e| OrchestrationError::RuntimeError(e.to_string()))?;
 
         // Build the full spec
-        let spec = SpecBuilder::default()
+        let mut spec_builder = SpecBuilder::default()
             .version("1.0.2".to_string())
             .root(root)
             .process(process)
             .linux(linux)
-            .hostname(config.name.clone())
+            .hostname(config.effective_hostname().to_string());
+
+        // Bind-mount volumes on top of the default mounts
+        if !config.volume_mounts.is_empty() {
+            let mut mounts = get_default_mounts();
+            for volume in &config.volume_mounts {
+                let mut options = vec!["rbind".to_string()];
+                if volume.read_only {
+                    options.push("ro".to_string());
+                }
+                mounts.push(
+                    MountBuilder::default()
+                        .destination(PathBuf::from(&volume.mount_path))
+                        .typ("bind".to_string())
+                        .source(PathBuf::from(&volume.host_path))
+                        .options(options)
+                        .build()
+                        .map_err(|e| OrchestrationError::RuntimeError(e.to_string()))?,
+                );
+            }
+            spec_builder = spec_builder.mounts(mounts);
+        }
+
+        let spec = spec_builder
             .build()
             .map_err(|e| OrchestrationError::RuntimeError(e.to_string()))?;
 
@@ -216,6 +239,15 @@ impl YoukiRuntime {
         let spec = self.create_oci_spec(&container_config)?;
 
         tokio::task::spawn_blocking(move || {
+            for volume in &container_config.volume_mounts {
+                std::fs::create_dir_all(&volume.host_path).map_err(|e| {
+                    OrchestrationError::RuntimeError(format!(
+                        "Failed to create volume {} at {}: {}",
+                        volume.name, volume.host_path, e
+                    ))
+                })?;
+            }
+
             // Create directories
             std::fs::create_dir_all(&rootfs_path).map_err(|e| {
                 OrchestrationError::RuntimeError(format!(
@@ -515,6 +547,7 @@ impl ContainerRuntime for YoukiRuntime {
                         state: state.to_string(),
                         exit_code: None, // Would need to read from container state
                         error_message: None,
+                        oom_killed: false,
                     })
                 }
                 Err(e) => {
@@ -524,6 +557,7 @@ impl ContainerRuntime for YoukiRuntime {
                         state: "unknown".to_string(),
                         exit_code: None,
                         error_message: Some(e.to_string()),
+                        oom_killed: false,
                     })
                 }
             }
@@ -553,6 +587,7 @@ impl ContainerRuntime for YoukiRuntime {
                         state: "unknown".to_string(),
                         exit_code: None,
                         error_message: Some(e.to_string()),
+                        oom_killed: false,
                     });
                 }
             }
@@ -614,6 +649,18 @@ mod tests {
                 .collect(),
             ports: vec![],
             resource_requests: orchestrator_shared_types::NodeResources::default(),
+            volume_mounts: vec![],
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+            readiness_probe: None,
+            limits: Default::default(),
+            sysctls: Default::default(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
         };
 
         let spec = runtime.create_oci_spec(&config).unwrap();
@@ -9,16 +9,138 @@
 //! - Linux with cgroups v2
 //! - Root privileges (or appropriate capabilities)
 //!
+//! # Root filesystem
+//!
+//! A container's `rootfs` is an overlay mounted into its bundle: the image's
+//! rootfs from the image cache is the read-only lower layer, shared by all
+//! containers of the image, and `{bundle}/overlay/upper` takes the
+//! container's writes. The overlay is unmounted, and the upper directory
+//! deleted with the bundle, when the container is removed.
+//!
 //! # Log Collection
 //!
 //! Container stdout/stderr is captured to log files stored at:
 //! `{state_root}/{container_id}/container.log`
 //!
+//! `youki create` runs with its stdout and stderr on pipes, which the
+//! container's process inherits. A log shim task per pipe reads it line by
+//! line and appends each line through [`YoukiCliRuntime::write_log`]'s
+//! rotation path until the container closes it. The shims live in the node
+//! process: output a container writes after the node restarts is lost.
+//!
 //! The log file contains both stdout and stderr interleaved with timestamps.
 //! Use `get_logs()` or `stream_logs()` to access container logs.
+//!
+//! The log is rotated by size and age as configured in [`LogRotationConfig`];
+//! reads stream the rotated segments line by line.
+//!
+//! When a container is removed its log moves to
+//! `{log_archive_root}/{workload_id}/{container_id}.log`, where log searches
+//! still find it.
+//!
+//! # Exec
+//!
+//! Commands run in a container through `youki exec`, on pipes or, for `tty`
+//! sessions, on a pseudo-terminal that merges stderr into stdout.
+//!
+//! # Stopping
+//!
+//! A stop runs the container's preStop hook, if any, then sends SIGTERM and,
+//! once the container's termination grace period (or `stop_timeout`) since
+//! the stop request has passed, SIGKILL. HTTP hooks connect to the port on
+//! the container's loopback from inside its network namespace.
+//!
+//! # Checkpoints
+//!
+//! `youki checkpoint` dumps a container's processes with CRIU into
+//! `{checkpoint_root}/{container_id}-{time}/images`, next to a copy of its
+//! `config.json` and a `checkpoint.json` naming its image. A restore pulls
+//! the image on the target node, mounts it into a new bundle with that config
+//! and runs `youki restore`. Changes to the root filesystem are not carried
+//! over; state that must survive belongs on volumes.
+//!
+//! # Resource updates
+//!
+//! New CPU, memory and PID limits are written straight to the container's
+//! cgroup (`cpu.max`, `memory.max` and `pids.max`), so they apply at once
+//! and the container keeps running. The bundle's `config.json` keeps the
+//! limits it was created with.
+//!
+//! # Bandwidth
+//!
+//! Containers with `ingress_bandwidth` or `egress_bandwidth` annotations get
+//! their traffic limited with `tc` on `eth0`, their end of the veth pair,
+//! between `youki create` and `youki start`: an HTB class shapes what they
+//! send and an ingress policer drops what they receive above the rate.
+//!
+//! # Addresses
+//!
+//! The addresses the orchestrator allocates to an instance reach its
+//! containers in the `ip_addresses` annotation. Between `youki create` and
+//! `youki start` each is added to `eth0` as a /32 or /128, and the link is
+//! brought up. The runtime does not create interfaces: a `createRuntime`
+//! hook must move the container's end of a veth pair into its network
+//! namespace as `eth0`, and route the addresses to the host end. A
+//! container whose annotation lists addresses fails to create without
+//! `eth0`, as does one with bandwidth limits.
+//!
+//! # Image scanning
+//!
+//! With an `image_scanner` configured, each image is scanned with Trivy or
+//! Grype right after it is pulled and extracted, and the report is kept per
+//! manifest digest under `{image_cache}/scans`. A failed scan is logged and
+//! does not fail the pull.
+//!
+//! # Lazy pulling
+//!
+//! With `lazy_pull` configured, images whose layers are all eStargz or all
+//! zstd:chunked are mounted by the helper under `{image_cache}/lazy` and
+//! their containers start while the helper fetches files on first read. Such
+//! images are not scanned on pull. Other images, and any the helper fails to
+//! mount, are pulled in full.
+//!
+//! # Models
+//!
+//! Containers with a `model_uri` annotation, as inference services have,
+//! get the model mounted read-only at `model_mount_path` (default:
+//! `/models`). The model is downloaded into the node's model cache under
+//! `{bundle_root}/../models` before the bundle is built, checked against its
+//! `model_digest` if set, and shared by every container using it. Models no
+//! container uses are evicted once the cache exceeds `model_cache_bytes`.
+//!
+//! # GPUs
+//!
+//! The `gpu_devices` of the node are handed to containers by their `gpu`
+//! annotation: whole exclusive GPUs, a percentage of an MPS GPU, or a MIG
+//! instance, the profile's position among the GPU's MIG devices. They are
+//! exposed through `NVIDIA_VISIBLE_DEVICES` (and, for MPS,
+//! `CUDA_MPS_ACTIVE_THREAD_PERCENTAGE`), which the NVIDIA container toolkit
+//! hook acts on; the MPS control daemon must already run on the host.
+//!
+//! # Events
+//!
+//! Creates, starts and removals are published as [`ContainerEvent`]s when
+//! they happen. While anyone subscribes, running containers are checked every
+//! `event_poll_interval` for exits, with `youki state`, and for OOM kills,
+//! with the `oom_kill` count of their cgroup's `memory.events`.
+//!
+//! # Exit codes
+//!
+//! youki keeps no exit status, so the runtime makes the node a child
+//! subreaper: a container's process is reparented to the node once
+//! `youki create` returns, and is reaped with `waitpid` when the container
+//! is found stopped. A process killed by a signal exits with 128 plus the
+//! signal number. A container whose process was killed by SIGKILL after an
+//! OOM kill in its cgroup since it started is reported as OOM killed.
+//!
+//! Other processes reparented to the node from containers' PID namespaces
+//! are reaped every `event_poll_interval` so they do not pile up as zombies.
+//! Processes the node spawns itself are left to whoever waits for them.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::SeekFrom;
+use std::net::IpAddr;
+use std::os::fd::AsRawFd;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::Arc;
@@ -27,17 +149,49 @@ use std::time::Duration;
 use async_trait::async_trait;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncBufReadExt, AsyncSeekExt, AsyncWriteExt, BufReader};
-use tokio::process::Command;
-use tokio::sync::{broadcast, RwLock};
+use tokio::io::{
+    AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::process::{Child, Command};
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use container_runtime_interface::{ContainerRuntime, ContainerStatus, CreateContainerOptions};
-use orchestrator_shared_types::{ContainerConfig, ContainerId, NodeId, OrchestrationError, Result};
-
-use crate::image::ImageManager;
-use crate::oci_bundle::OciBundleBuilder;
+use container_runtime_interface::{
+    Checkpoint, CheckpointOptions, ContainerEvent, ContainerEventKind, ContainerRuntime,
+    ContainerStatus, ContainerUsage, CreateContainerOptions, DebugOptions, ExecInput, ExecOptions,
+    ExecOutput, ExecSession, ImageInspection, ImagePullStatus, ImageScan, LogMatch, LogMatcher,
+    LogSearchOptions, PullPriority, ResourceLimits, CONTAINER_EVENT_CAPACITY,
+};
+use orchestrator_shared_types::{
+    BandwidthLimits, ContainerConfig, ContainerId, GpuAssignment, GpuDevice, GpuPool,
+    LifecycleHandler, NodeId, OrchestrationError, Result, VolumeMount, WorkloadId,
+    WorkloadInstance,
+};
+
+use crate::credentials::CloudProvider;
+use crate::image::{ImageError, ImageManager};
+use crate::lazy::{LazyPullConfig, LazyPuller};
+use crate::log_rotation::{self, LogRotationConfig};
+use crate::models::{self, ModelStore};
+use crate::oci_bundle::{CpuResources, OciBundleBuilder};
+use crate::pull_queue::{ImagePullQueue, PullQueueConfig};
+use crate::scan::{ImageScanner, ScannerConfig};
+
+/// How long output of an exited exec command is still forwarded, in case
+/// something it started keeps its terminal open.
+const EXEC_OUTPUT_DRAIN: Duration = Duration::from_secs(1);
+
+/// How long a container has to handle SIGTERM after a preStop hook used up
+/// its grace period.
+const MIN_TERMINATION_GRACE: Duration = Duration::from_secs(2);
+
+/// File in a checkpoint directory holding its [`CheckpointManifest`].
+const CHECKPOINT_MANIFEST: &str = "checkpoint.json";
+
+/// A container's end of its veth pair, in its network namespace.
+const CONTAINER_INTERFACE: &str = "eth0";
 
 /// Errors specific to Youki CLI operations.
 #[derive(Debug, thiserror::Error)]
@@ -68,6 +222,9 @@ pub enum YoukiCliError {
 
     #[error("Log error: {0}")]
     LogError(String),
+
+    #[error("Model error: {0}")]
+    Model(#[from] crate::models::ModelError),
 }
 
 /// A single log entry from a container.
@@ -131,10 +288,22 @@ pub struct YoukiState {
 #[derive(Debug, Clone)]
 pub struct ContainerState {
     pub id: ContainerId,
+    /// Name from the container's config
+    pub name: String,
+    pub image: String,
     pub node_id: NodeId,
+    pub workload_id: WorkloadId,
     pub bundle_path: PathBuf,
     pub status: String,
     pub pid: Option<i32>,
+    /// Reaped once the container has stopped, if its process was our child
+    pub exit_code: Option<i32>,
+    pub oom_killed: bool,
+    /// OOM kills its cgroup had counted when it started
+    pub oom_kills_at_start: u64,
+    /// Time from stop request to SIGKILL, if not `stop_timeout`
+    pub termination_grace: Option<Duration>,
+    pub pre_stop: Option<LifecycleHandler>,
 }
 
 /// Configuration for YoukiCliRuntime.
@@ -148,8 +317,41 @@ pub struct YoukiCliConfig {
     pub state_root: PathBuf,
     /// Timeout for commands (default: 30s)
     pub command_timeout: Duration,
-    /// Timeout before SIGKILL (default: 10s)
+    /// Time from stop request to SIGKILL for containers without a
+    /// termination grace period (default: 10s)
     pub stop_timeout: Duration,
+    /// Image pull limits applied to each node
+    pub pull_queue: PullQueueConfig,
+    /// Where logs of removed containers are kept; `None` deletes them
+    pub log_archive_root: Option<PathBuf>,
+    /// When container logs are rotated
+    pub log_rotation: LogRotationConfig,
+    /// How often running containers are checked for exits and OOM kills
+    /// while events are subscribed to (default: 1s)
+    pub event_poll_interval: Duration,
+    /// Where container checkpoints are kept
+    pub checkpoint_root: PathBuf,
+    /// Timeout for checkpoints and restores, which copy a container's memory
+    /// (default: 5m)
+    pub checkpoint_timeout: Duration,
+    /// Vulnerability scanner run on each pulled image (default: none)
+    pub image_scanner: Option<ScannerConfig>,
+    /// Helper mounting seekable images lazily (default: none, pulled in full)
+    pub lazy_pull: Option<LazyPullConfig>,
+    /// Pull-through registry cache to pull images from (default: none, each
+    /// image's own registry)
+    pub registry_mirror: Option<String>,
+    /// Clouds whose registries are logged in to with the node's identity
+    /// (default: none)
+    pub credential_providers: Vec<CloudProvider>,
+    /// Size of the model cache before unused models are evicted
+    /// (default: 100 GiB)
+    pub model_cache_bytes: u64,
+    /// GPUs handed to containers, and how each is shared (default: none)
+    pub gpu_devices: Vec<GpuDevice>,
+    /// Whether containers resolve through the node-local DNS cache before
+    /// the host's nameservers (default: false)
+    pub node_local_dns: bool,
 }
 
 impl Default for YoukiCliConfig {
@@ -160,6 +362,19 @@ impl Default for YoukiCliConfig {
             state_root: PathBuf::from("/run/youki"),
             command_timeout: Duration::from_secs(30),
             stop_timeout: Duration::from_secs(10),
+            pull_queue: PullQueueConfig::default(),
+            log_archive_root: Some(PathBuf::from("/var/lib/orchestrator/log-archive")),
+            log_rotation: LogRotationConfig::default(),
+            event_poll_interval: Duration::from_secs(1),
+            checkpoint_root: PathBuf::from("/var/lib/orchestrator/checkpoints"),
+            checkpoint_timeout: Duration::from_secs(300),
+            image_scanner: None,
+            lazy_pull: None,
+            registry_mirror: None,
+            credential_providers: Vec::new(),
+            model_cache_bytes: models::DEFAULT_MAX_BYTES,
+            gpu_devices: Vec::new(),
+            node_local_dns: false,
         }
     }
 }
@@ -168,10 +383,28 @@ impl Default for YoukiCliConfig {
 pub struct YoukiCliRuntime {
     config: YoukiCliConfig,
     image_manager: ImageManager,
+    /// Models mounted into containers
+    models: ModelStore,
+    /// GPUs assigned to containers
+    gpus: std::sync::Mutex<GpuAllocations>,
     containers: Arc<RwLock<HashMap<String, ContainerState>>>,
     containers_by_node: Arc<RwLock<HashMap<NodeId, Vec<ContainerId>>>>,
     /// Active log streams for follow mode
     log_streams: Arc<RwLock<HashMap<String, LogStreamHandle>>>,
+    /// Image pull queue of each node
+    pull_queues: Arc<RwLock<HashMap<NodeId, Arc<ImagePullQueue>>>>,
+    /// Held while writing a log line, so rotations never interleave
+    log_writes: Arc<Mutex<()>>,
+    /// Container lifecycle events
+    events: broadcast::Sender<ContainerEvent>,
+    /// Checks running containers for exits and OOM kills
+    event_watcher: JoinHandle<()>,
+}
+
+impl Drop for YoukiCliRuntime {
+    fn drop(&mut self) {
+        self.event_watcher.abort();
+    }
 }
 
 impl YoukiCliRuntime {
@@ -194,19 +427,89 @@ impl YoukiCliRuntime {
             .unwrap_or(Path::new("/var/lib/orchestrator"))
             .join("images");
         tokio::fs::create_dir_all(&image_cache).await?;
-        let image_manager = ImageManager::new(&image_cache)?;
+        let mut image_manager = ImageManager::new(&image_cache)?;
+        if let Some(scanner) = &config.image_scanner {
+            image_manager = image_manager.with_scanner(ImageScanner::new(scanner.clone()));
+        }
+        if let Some(lazy_pull) = &config.lazy_pull {
+            image_manager = image_manager.with_lazy_puller(LazyPuller::new(lazy_pull.clone()));
+        }
+        if let Some(mirror) = &config.registry_mirror {
+            image_manager = image_manager.with_mirror(mirror);
+        }
+        for cloud in &config.credential_providers {
+            image_manager = image_manager.with_credential_provider(cloud.provider()?);
+        }
+        let models = ModelStore::new(
+            image_cache.with_file_name("models"),
+            config.model_cache_bytes,
+        )?;
+
+        let gpus = GpuAllocations {
+            pool: GpuPool::new(config.gpu_devices.clone()),
+            assigned: HashMap::new(),
+        };
+
+        // Container processes outlive `youki create`; as their subreaper the
+        // node can reap them for their exit codes
+        if let Err(e) = nix::sys::prctl::set_child_subreaper(true) {
+            warn!(
+                "Could not become child subreaper, exit codes will be unknown: {}",
+                e
+            );
+        }
 
         info!("YoukiCliRuntime initialized with binary: {:?}", config.youki_binary);
 
+        let containers = Arc::new(RwLock::new(HashMap::new()));
+        let (events, _) = broadcast::channel(CONTAINER_EVENT_CAPACITY);
+        let event_watcher = tokio::spawn(Self::watch_containers(
+            config.clone(),
+            containers.clone(),
+            events.clone(),
+        ));
+
         Ok(Self {
             config,
             image_manager,
-            containers: Arc::new(RwLock::new(HashMap::new())),
+            models,
+            gpus: std::sync::Mutex::new(gpus),
+            containers,
             containers_by_node: Arc::new(RwLock::new(HashMap::new())),
             log_streams: Arc::new(RwLock::new(HashMap::new())),
+            pull_queues: Arc::new(RwLock::new(HashMap::new())),
+            log_writes: Arc::new(Mutex::new(())),
+            events,
+            event_watcher,
         })
     }
 
+    /// Assign the GPUs a container's `gpu` annotation asks for.
+    fn assign_gpus(
+        &self,
+        container_id: &str,
+        config: &ContainerConfig,
+    ) -> Result<Option<GpuLease<'_>>> {
+        let Some(request) = config.gpu_request()? else {
+            return Ok(None);
+        };
+        let mut gpus = self.gpus.lock().unwrap();
+        let assignments = gpus.pool.allocate(&[request]).map_err(|e| {
+            OrchestrationError::RuntimeError(format!(
+                "Failed to assign GPUs to {}: {}",
+                container_id, e
+            ))
+        })?;
+        gpus.assigned
+            .insert(container_id.to_string(), assignments.clone());
+        Ok(Some(GpuLease {
+            gpus: &self.gpus,
+            container_id: container_id.to_string(),
+            assignments,
+            kept: false,
+        }))
+    }
+
     /// Verify youki binary exists and is executable.
     async fn verify_binary(binary: &Path) -> std::result::Result<(), YoukiCliError> {
         let output = Command::new(binary)
@@ -236,19 +539,49 @@ impl YoukiCliRuntime {
             .join(container_id)
     }
 
+    /// Get the image pull queue for a node, creating it on first use.
+    async fn pull_queue(&self, node_id: &NodeId) -> Arc<ImagePullQueue> {
+        if let Some(queue) = self.pull_queues.read().await.get(node_id) {
+            return queue.clone();
+        }
+        self.pull_queues
+            .write()
+            .await
+            .entry(*node_id)
+            .or_insert_with(|| Arc::new(ImagePullQueue::new(self.config.pull_queue.clone())))
+            .clone()
+    }
+
     // ==================== Youki CLI Helper Methods ====================
 
     /// Execute youki command with timeout.
     async fn exec_youki(&self, args: &[&str]) -> std::result::Result<std::process::Output, YoukiCliError> {
+        Self::run_youki(&self.config, args).await
+    }
+
+    /// Execute youki command with timeout, as configured by `config`.
+    async fn run_youki(
+        config: &YoukiCliConfig,
+        args: &[&str],
+    ) -> std::result::Result<std::process::Output, YoukiCliError> {
+        Self::run_youki_with_timeout(config, args, config.command_timeout).await
+    }
+
+    /// Execute youki command, giving up after `timeout`.
+    async fn run_youki_with_timeout(
+        config: &YoukiCliConfig,
+        args: &[&str],
+        timeout: Duration,
+    ) -> std::result::Result<std::process::Output, YoukiCliError> {
         let cmd_str = format!("youki {}", args.join(" "));
         debug!("Executing: {}", cmd_str);
 
         let result = tokio::time::timeout(
-            self.config.command_timeout,
-            Command::new(&self.config.youki_binary)
+            timeout,
+            Command::new(&config.youki_binary)
                 .args(args)
                 .arg("--root")
-                .arg(&self.config.state_root)
+                .arg(&config.state_root)
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
                 .output(),
@@ -260,16 +593,61 @@ impl YoukiCliRuntime {
         Ok(result)
     }
 
-    /// youki create <id> --bundle <path>
+    /// youki create <id> --bundle <path>, with the container's stdout and
+    /// stderr going to log shims that append them to its log.
     pub async fn youki_create(&self, id: &str, bundle_path: &Path) -> std::result::Result<(), YoukiCliError> {
         let bundle_str = bundle_path.to_string_lossy();
-        let output = self.exec_youki(&["create", id, "--bundle", &bundle_str]).await?;
+        let cmd_str = format!("youki create {} --bundle {}", id, bundle_str);
+        debug!("Executing: {}", cmd_str);
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut child = Command::new(&self.config.youki_binary)
+            .args(["create", id, "--bundle", &bundle_str])
+            .arg("--root")
+            .arg(&self.config.state_root)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let log_path = self.log_path(id);
+        let rotation = &self.config.log_rotation;
+        if let Some(stdout) = child.stdout.take() {
+            spawn_log_shim(
+                stdout,
+                "stdout",
+                log_path.clone(),
+                rotation.clone(),
+                self.log_writes.clone(),
+            );
+        }
+        let stderr = child.stderr.take().map(|stderr| {
+            spawn_log_shim(
+                stderr,
+                "stderr",
+                log_path,
+                rotation.clone(),
+                self.log_writes.clone(),
+            )
+        });
+
+        let status = match tokio::time::timeout(self.config.command_timeout, child.wait()).await {
+            Ok(status) => status?,
+            Err(_) => {
+                let _ = child.kill().await;
+                return Err(YoukiCliError::Timeout(cmd_str));
+            }
+        };
+        if !status.success() {
+            // Without a container holding the pipes, the shims end with youki
+            let last_error = match stderr {
+                Some(shim) => tokio::time::timeout(EXEC_OUTPUT_DRAIN, shim)
+                    .await
+                    .ok()
+                    .and_then(|shim| shim.ok().flatten()),
+                None => None,
+            };
             return Err(YoukiCliError::CommandFailed {
                 command: "create".to_string(),
-                message: stderr.to_string(),
+                message: last_error.unwrap_or_else(|| status.to_string()),
             });
         }
 
@@ -336,9 +714,84 @@ impl YoukiCliRuntime {
         Ok(())
     }
 
+    /// youki checkpoint --image-path <dir>/images --work-path <dir>/work
+    /// [--leave-running] <id>
+    pub async fn youki_checkpoint(
+        &self,
+        id: &str,
+        checkpoint_path: &Path,
+        leave_running: bool,
+    ) -> std::result::Result<(), YoukiCliError> {
+        let image_path = checkpoint_path.join("images").to_string_lossy().into_owned();
+        let work_path = checkpoint_path.join("work").to_string_lossy().into_owned();
+        let mut args = vec!["checkpoint", "--image-path", &image_path, "--work-path", &work_path];
+        if leave_running {
+            args.push("--leave-running");
+        }
+        args.push(id);
+        let timeout = self.config.checkpoint_timeout;
+        let output = Self::run_youki_with_timeout(&self.config, &args, timeout).await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(YoukiCliError::CommandFailed {
+                command: "checkpoint".to_string(),
+                message: stderr.to_string(),
+            });
+        }
+
+        debug!("Container {} checkpointed to {:?}", id, checkpoint_path);
+        Ok(())
+    }
+
+    /// youki restore --image-path <dir>/images --work-path <dir>/work
+    /// --bundle <path> --detach <id>
+    pub async fn youki_restore(
+        &self,
+        id: &str,
+        checkpoint_path: &Path,
+        bundle_path: &Path,
+    ) -> std::result::Result<(), YoukiCliError> {
+        let image_path = checkpoint_path.join("images").to_string_lossy().into_owned();
+        let work_path = checkpoint_path.join("work").to_string_lossy().into_owned();
+        let bundle_str = bundle_path.to_string_lossy();
+        let args = [
+            "restore",
+            "--image-path",
+            &image_path,
+            "--work-path",
+            &work_path,
+            "--bundle",
+            &bundle_str,
+            "--detach",
+            id,
+        ];
+        let timeout = self.config.checkpoint_timeout;
+        let output = Self::run_youki_with_timeout(&self.config, &args, timeout).await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(YoukiCliError::CommandFailed {
+                command: "restore".to_string(),
+                message: stderr.to_string(),
+            });
+        }
+
+        debug!("Container {} restored from {:?}", id, checkpoint_path);
+        Ok(())
+    }
+
     /// youki state <id> -> YoukiState
     pub async fn youki_state(&self, id: &str) -> std::result::Result<YoukiState, YoukiCliError> {
-        let output = self.exec_youki(&["state", id]).await?;
+        Self::query_state(&self.config, id).await
+    }
+
+    /// youki state <id>, as configured by `config`.
+    async fn query_state(
+        config: &YoukiCliConfig,
+        id: &str,
+    ) -> std::result::Result<YoukiState, YoukiCliError> {
+        let output = Self::run_youki(config, &["state", id]).await?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -358,6 +811,196 @@ impl YoukiCliRuntime {
         Ok(state)
     }
 
+    /// Run a container's preStop hook, giving HTTP requests `timeout`.
+    async fn run_pre_stop(
+        &self,
+        container_id: &str,
+        hook: &LifecycleHandler,
+        timeout: Duration,
+    ) -> std::result::Result<(), YoukiCliError> {
+        match hook {
+            LifecycleHandler::Exec { command } => {
+                let output = Command::new(&self.config.youki_binary)
+                    .arg("--root")
+                    .arg(&self.config.state_root)
+                    .args(["exec", container_id, "--"])
+                    .args(command)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true)
+                    .output()
+                    .await?;
+                if !output.status.success() {
+                    return Err(YoukiCliError::CommandFailed {
+                        command: command.join(" "),
+                        message: format!(
+                            "{}: {}",
+                            output.status,
+                            String::from_utf8_lossy(&output.stderr).trim()
+                        ),
+                    });
+                }
+                Ok(())
+            }
+            LifecycleHandler::HttpGet { port, path } => {
+                let pid = self
+                    .youki_state(container_id)
+                    .await?
+                    .pid
+                    .ok_or_else(|| YoukiCliError::ContainerNotFound(container_id.to_string()))?;
+                let status = http_get_in_netns(pid, *port, path, timeout).await?;
+                if !(200..400).contains(&status) {
+                    return Err(YoukiCliError::CommandFailed {
+                        command: format!("GET :{}{}", port, path),
+                        message: format!("HTTP status {}", status),
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Limit the traffic of created container `container_id` by running tc
+    /// in its network namespace.
+    async fn apply_bandwidth_limits(
+        &self,
+        container_id: &str,
+        limits: &BandwidthLimits,
+    ) -> std::result::Result<(), YoukiCliError> {
+        self.run_in_netns(container_id, "tc", tc_commands(CONTAINER_INTERFACE, limits))
+            .await
+    }
+
+    /// Put the instance's `addresses` on created container `container_id`'s
+    /// interface and bring it up. The interface must already exist.
+    async fn configure_addresses(
+        &self,
+        container_id: &str,
+        addresses: &[IpAddr],
+    ) -> std::result::Result<(), YoukiCliError> {
+        let missing = self
+            .run_in_netns(
+                container_id,
+                "ip",
+                vec![format!("link show dev {}", CONTAINER_INTERFACE)],
+            )
+            .await;
+        if let Err(e) = missing {
+            return Err(YoukiCliError::CommandFailed {
+                command: format!("ip link show dev {}", CONTAINER_INTERFACE),
+                message: format!(
+                    "{} is not set up; a createRuntime hook must create it: {}",
+                    CONTAINER_INTERFACE, e
+                ),
+            });
+        }
+        self.run_in_netns(
+            container_id,
+            "ip",
+            ip_commands(CONTAINER_INTERFACE, addresses),
+        )
+        .await
+    }
+
+    /// Run `program` with each of `commands` as arguments in created
+    /// container `container_id`'s network namespace, stopping at the first
+    /// failure.
+    async fn run_in_netns(
+        &self,
+        container_id: &str,
+        program: &str,
+        commands: Vec<String>,
+    ) -> std::result::Result<(), YoukiCliError> {
+        let pid = self
+            .youki_state(container_id)
+            .await?
+            .pid
+            .ok_or_else(|| YoukiCliError::ContainerNotFound(container_id.to_string()))?;
+        let netns = format!("--net=/proc/{}/ns/net", pid);
+        for command in commands {
+            let command_str = format!("{} {}", program, command);
+            debug!("Executing: {}", command_str);
+            let output = tokio::time::timeout(
+                self.config.command_timeout,
+                Command::new("nsenter")
+                    .arg(&netns)
+                    .arg(program)
+                    .args(command.split_whitespace())
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true)
+                    .output(),
+            )
+            .await
+            .map_err(|_| YoukiCliError::Timeout(command_str.clone()))??;
+            if !output.status.success() {
+                return Err(YoukiCliError::CommandFailed {
+                    command: command_str,
+                    message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // ==================== Event Methods ====================
+
+    /// Publish an event of a container, if anyone listens.
+    fn publish(&self, container_id: &str, kind: ContainerEventKind) {
+        let _ = self.events.send(ContainerEvent::now(container_id, kind));
+    }
+
+    /// Check running containers for exits and OOM kills every
+    /// `event_poll_interval`, while anyone subscribes to events.
+    async fn watch_containers(
+        config: YoukiCliConfig,
+        containers: Arc<RwLock<HashMap<String, ContainerState>>>,
+        events: broadcast::Sender<ContainerEvent>,
+    ) {
+        let mut interval = tokio::time::interval(config.event_poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // OOM kills seen so far, from those counted when each container started
+        let mut oom_kills: HashMap<ContainerId, u64> = HashMap::new();
+        loop {
+            interval.tick().await;
+            reap_orphans(&containers).await;
+            if events.receiver_count() == 0 {
+                continue;
+            }
+            let running: Vec<(ContainerId, u64)> = containers
+                .read()
+                .await
+                .values()
+                .filter(|state| state.status == "running")
+                .map(|state| (state.id.clone(), state.oom_kills_at_start))
+                .collect();
+            oom_kills.retain(|id, _| running.iter().any(|(running, _)| running == id));
+
+            for (id, oom_kills_at_start) in running {
+                let kills = oom_kill_count(&id).await;
+                let seen = oom_kills.entry(id.clone()).or_insert(oom_kills_at_start);
+                if kills > *seen {
+                    *seen = kills;
+                    let event = ContainerEvent::now(id.as_str(), ContainerEventKind::OomKilled);
+                    let _ = events.send(event);
+                }
+
+                match Self::query_state(&config, &id).await {
+                    Ok(state) if state.status == "stopped" => {
+                        mark_exited(&containers, &events, &id).await;
+                    }
+                    Err(YoukiCliError::ContainerNotFound(_)) => {
+                        mark_exited(&containers, &events, &id).await;
+                    }
+                    Ok(_) => {}
+                    Err(e) => debug!("Could not get state of container {}: {}", id, e),
+                }
+            }
+        }
+    }
+
     // ==================== Log Management Methods ====================
 
     /// Get log directory for a container.
@@ -370,6 +1013,16 @@ impl YoukiCliRuntime {
         self.log_dir(container_id).join("container.log")
     }
 
+    /// Log file of a container, falling back to its archived log once the
+    /// container is removed.
+    async fn find_log(&self, container_id: &str) -> Option<PathBuf> {
+        let live = self.log_path(container_id);
+        if live.exists() {
+            return Some(live);
+        }
+        find_archived_log(self.config.log_archive_root.as_deref()?, container_id).await
+    }
+
     /// Initialize log capture for a container.
     /// Creates log directory and empty log file.
     async fn init_log_capture(&self, container_id: &str) -> std::result::Result<PathBuf, YoukiCliError> {
@@ -407,14 +1060,9 @@ impl YoukiCliRuntime {
             return Ok(String::new());
         }
 
-        let content = tokio::fs::read_to_string(&log_path).await?;
-        let mut lines: Vec<&str> = content.lines().collect();
-
         // Apply tail filter
-        if let Some(n) = options.tail {
-            let start = lines.len().saturating_sub(n);
-            lines = lines[start..].to_vec();
-        }
+        let lines = Self::read_log_tail(&log_path, options.tail).await?;
+        let mut lines: Vec<&str> = lines.iter().map(String::as_str).collect();
 
         // Apply since/until filters if timestamps are present in log lines
         if options.since.is_some() || options.until.is_some() {
@@ -464,18 +1112,13 @@ impl YoukiCliRuntime {
             return Ok(Vec::new());
         }
 
-        let content = tokio::fs::read_to_string(&log_path).await?;
-        let mut entries: Vec<LogEntry> = content
-            .lines()
+        // Apply tail filter
+        let mut entries: Vec<LogEntry> = Self::read_log_tail(&log_path, options.tail)
+            .await?
+            .iter()
             .filter_map(|line| Self::parse_log_line(line))
             .collect();
 
-        // Apply tail filter
-        if let Some(n) = options.tail {
-            let start = entries.len().saturating_sub(n);
-            entries = entries[start..].to_vec();
-        }
-
         // Apply since/until filters
         if let Some(ref since) = options.since {
             entries.retain(|e| e.timestamp.as_str() >= since.as_str());
@@ -487,6 +1130,25 @@ impl YoukiCliRuntime {
         Ok(entries)
     }
 
+    /// Lines of a container's log, only the last `tail` if set, holding no
+    /// more than those in memory.
+    async fn read_log_tail(
+        log_path: &Path,
+        tail: Option<usize>,
+    ) -> std::result::Result<VecDeque<String>, YoukiCliError> {
+        let mut lines = log_rotation::read_lines(log_path);
+        let mut kept = VecDeque::new();
+        while let Some(line) = lines.next_line().await? {
+            if tail.is_some_and(|n| kept.len() >= n) {
+                kept.pop_front();
+            }
+            if tail != Some(0) {
+                kept.push_back(line);
+            }
+        }
+        Ok(kept)
+    }
+
     /// Parse a single log line into a LogEntry.
     fn parse_log_line(line: &str) -> Option<LogEntry> {
         if line.is_empty() {
@@ -597,6 +1259,11 @@ impl YoukiCliRuntime {
                     // No new data, wait and retry
                     tokio::time::sleep(Duration::from_millis(100)).await;
 
+                    // Follow the live log to its new file once it is rotated
+                    if Self::log_rotated(&log_path, reader.get_ref()).await {
+                        reader = BufReader::new(tokio::fs::File::open(&log_path).await?);
+                    }
+
                     // Check if channel is closed
                     if sender.receiver_count() == 0 {
                         debug!("No more receivers, stopping log watcher");
@@ -619,6 +1286,16 @@ impl YoukiCliRuntime {
         Ok(())
     }
 
+    /// Whether the file at `log_path` is no longer the one `open` reads.
+    async fn log_rotated(log_path: &Path, open: &tokio::fs::File) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        match (tokio::fs::metadata(log_path).await, open.metadata().await) {
+            (Ok(current), Ok(open)) => current.ino() != open.ino(),
+            _ => false,
+        }
+    }
+
     /// Stop streaming logs for a container.
     pub async fn stop_log_stream(&self, container_id: &str) {
         let mut streams = self.log_streams.write().await;
@@ -636,101 +1313,540 @@ impl YoukiCliRuntime {
         stream: &str,
         message: &str,
     ) -> std::result::Result<(), YoukiCliError> {
-        let log_path = self.log_path(container_id);
-
-        // Ensure log directory exists
-        if let Some(parent) = log_path.parent() {
-            tokio::fs::create_dir_all(parent).await?;
-        }
-
-        let timestamp = Utc::now().to_rfc3339();
-        let line = format!("{} {} {}\n", timestamp, stream, message);
-
-        let mut file = tokio::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)
-            .await?;
-
-        file.write_all(line.as_bytes()).await?;
-        Ok(())
+        append_log(
+            &self.log_path(container_id),
+            &self.config.log_rotation,
+            &self.log_writes,
+            stream,
+            message,
+        )
+        .await
     }
 
     // ==================== Resource Stats Methods ====================
 
     /// Get basic stats from cgroups.
     pub async fn get_stats(&self, container_id: &str) -> std::result::Result<ContainerStats, YoukiCliError> {
-        let cgroup_path = PathBuf::from("/sys/fs/cgroup/youki").join(container_id);
+        let cgroup_path = cgroup_dir(container_id);
+        let read = |file: &str| tokio::fs::read_to_string(cgroup_path.join(file));
 
-        let cpu_usage = tokio::fs::read_to_string(cgroup_path.join("cpu.stat"))
+        let cpu_usage = read("cpu.stat")
             .await
             .ok()
             .map(|s| parse_cpu_usage(&s))
             .unwrap_or(0);
 
-        let memory_usage = tokio::fs::read_to_string(cgroup_path.join("memory.current"))
+        let memory_usage: u64 = read("memory.current")
             .await
             .ok()
             .and_then(|s| s.trim().parse().ok())
             .unwrap_or(0);
 
+        // Page cache the kernel can reclaim does not count toward the working set
+        let inactive_file = read("memory.stat")
+            .await
+            .ok()
+            .and_then(|s| parse_stat_field(&s, "inactive_file"))
+            .unwrap_or(0);
+
+        let oom_kills = read("memory.events")
+            .await
+            .ok()
+            .and_then(|s| parse_stat_field(&s, "oom_kill"))
+            .unwrap_or(0);
+
         Ok(ContainerStats {
             container_id: container_id.to_string(),
             cpu_usage_ns: cpu_usage,
             memory_usage_bytes: memory_usage,
+            memory_working_set_bytes: memory_usage.saturating_sub(inactive_file),
+            oom_kills,
         })
     }
 
+    /// Pull `image` on the node of `options` and mount its rootfs into a
+    /// bundle.
+    async fn link_rootfs(
+        &self,
+        image: &str,
+        bundle_path: &Path,
+        options: &CreateContainerOptions,
+    ) -> Result<()> {
+        // Pull image and get rootfs
+        info!("Pulling image: {}", image);
+        let pull_queue = self.pull_queue(&options.node_id).await;
+        let rootfs_source = self.image_manager
+            .get_rootfs_queued(image, &pull_queue, options.pull_priority)
+            .await
+            .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to pull image: {}", e)))?;
+
+        mount_rootfs(&rootfs_source, bundle_path)
+            .await
+            .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to mount rootfs: {}", e)))
+    }
+
+    /// Start tracking a running container.
+    async fn track(&self, state: ContainerState) {
+        let (id, node_id) = (state.id.clone(), state.node_id);
+        self.containers.write().await.insert(id.clone(), state);
+        self.containers_by_node.write().await
+            .entry(node_id)
+            .or_default()
+            .push(id);
+    }
+
+    /// Set the status of a tracked container.
+    async fn set_status(&self, container_id: &str, status: &str) {
+        if let Some(state) = self.containers.write().await.get_mut(container_id) {
+            state.status = status.to_string();
+        }
+    }
+
     /// Clean up container bundle.
     async fn cleanup_bundle(&self, bundle_path: &Path) -> std::result::Result<(), YoukiCliError> {
-        if bundle_path.exists() {
-            tokio::fs::remove_dir_all(bundle_path).await?;
-        }
+        remove_bundle(bundle_path).await?;
         Ok(())
     }
 }
 
+/// Mount a container's root filesystem at `{bundle}/rootfs`: an overlay with
+/// the image's rootfs, shared by every container of the image, as its
+/// read-only lower layer, and the container's own upper and work directories
+/// under `{bundle}/overlay`. Whatever the container writes lands in its upper
+/// directory; the image cache is never modified.
+async fn mount_rootfs(lower: &Path, bundle_path: &Path) -> std::io::Result<()> {
+    let target = bundle_path.join("rootfs");
+    let upper = bundle_path.join("overlay").join("upper");
+    let work = bundle_path.join("overlay").join("work");
+    // A bundle being rebuilt may still have an earlier overlay mounted
+    unmount_rootfs(bundle_path)?;
+    tokio::fs::remove_dir_all(&target).await.ok();
+    for dir in [&target, &upper, &work] {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+
+    let options = format!(
+        "lowerdir={},upperdir={},workdir={}",
+        lower.display(),
+        upper.display(),
+        work.display()
+    );
+    nix::mount::mount(
+        Some("overlay"),
+        &target,
+        Some("overlay"),
+        nix::mount::MsFlags::empty(),
+        Some(options.as_str()),
+    )?;
+    Ok(())
+}
+
+/// Unmount the overlay at `{bundle}/rootfs`; nothing mounted there is fine.
+fn unmount_rootfs(bundle_path: &Path) -> std::io::Result<()> {
+    use nix::errno::Errno;
+
+    match nix::mount::umount2(&bundle_path.join("rootfs"), nix::mount::MntFlags::MNT_DETACH) {
+        // Not a mount point, or no rootfs at all
+        Ok(()) | Err(Errno::EINVAL) | Err(Errno::ENOENT) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Unmount a bundle's rootfs and delete the bundle, with the container's
+/// upper directory. Unmounting first keeps the removal out of the image.
+async fn remove_bundle(bundle_path: &Path) -> std::io::Result<()> {
+    if !bundle_path.exists() {
+        return Ok(());
+    }
+    unmount_rootfs(bundle_path)?;
+    tokio::fs::remove_dir_all(bundle_path).await
+}
+
+/// What a checkpoint needs besides CRIU's images and the container's
+/// `config.json` to recreate the container.
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointManifest {
+    name: String,
+    image: String,
+    termination_grace: Option<Duration>,
+    pre_stop: Option<LifecycleHandler>,
+}
+
 /// Container resource statistics.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContainerStats {
     pub container_id: String,
     pub cpu_usage_ns: u64,
     pub memory_usage_bytes: u64,
+    pub memory_working_set_bytes: u64,
+    pub oom_kills: u64,
 }
 
-fn parse_cpu_usage(content: &str) -> u64 {
-    for line in content.lines() {
-        if line.starts_with("usage_usec") {
-            if let Some(val) = line.split_whitespace().nth(1) {
-                return val.parse::<u64>().unwrap_or(0) * 1000;
-            }
-        }
+/// Send an HTTP GET for `path` to `port` on the loopback of the network
+/// namespace of process `pid`, e.g. a container's, and return the status.
+async fn http_get_in_netns(
+    pid: i32,
+    port: u16,
+    path: &str,
+    timeout: Duration,
+) -> std::io::Result<u16> {
+    use std::io::{BufRead, BufReader, Write};
+
+    let namespace = std::fs::File::open(format!("/proc/{}/ns/net", pid))?;
+    let request = format!("GET {} HTTP/1.0\r\nHost: localhost\r\n\r\n", path);
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    // setns only moves the calling thread, so use a thread of our own rather
+    // than one the runtime would reuse
+    std::thread::spawn(move || {
+        let get = || -> std::io::Result<u16> {
+            nix::sched::setns(&namespace, nix::sched::CloneFlags::CLONE_NEWNET)?;
+            let address = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+            let mut stream = std::net::TcpStream::connect_timeout(&address, timeout)?;
+            stream.set_read_timeout(Some(timeout))?;
+            stream.set_write_timeout(Some(timeout))?;
+            stream.write_all(request.as_bytes())?;
+            let mut status_line = String::new();
+            BufReader::new(stream).read_line(&mut status_line)?;
+            status_line
+                .split_whitespace()
+                .nth(1)
+                .and_then(|status| status.parse().ok())
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Invalid HTTP status line: {:?}", status_line.trim()),
+                    )
+                })
+        };
+        let _ = tx.send(get());
+    });
+    rx.await
+        .map_err(|_| std::io::Error::other("preStop request thread panicked"))?
+}
+
+/// tc commands limiting the traffic of `device` to `limits`: an HTB root
+/// qdisc for what leaves it and a policer on its ingress qdisc.
+fn tc_commands(device: &str, limits: &BandwidthLimits) -> Vec<String> {
+    let mut commands = Vec::new();
+    if let Some(bps) = limits.egress_bps {
+        commands.push(format!("qdisc add dev {} root handle 1: htb default 1", device));
+        commands.push(format!(
+            "class add dev {} parent 1: classid 1:1 htb rate {}bit ceil {}bit",
+            device, bps, bps
+        ));
     }
-    0
+    if let Some(bps) = limits.ingress_bps {
+        // Allow bursts of 100ms at the rate, and at least 64 KiB
+        let burst = (bps / 80).max(64 * 1024);
+        commands.push(format!("qdisc add dev {} handle ffff: ingress", device));
+        commands.push(format!(
+            "filter add dev {} parent ffff: matchall action police rate {}bit burst {} drop",
+            device, bps, burst
+        ));
+    }
+    commands
 }
 
-#[async_trait]
-impl ContainerRuntime for YoukiCliRuntime {
-    async fn init_node(&self, node_id: NodeId) -> Result<()> {
-        info!("YoukiCliRuntime: Initializing node {}", node_id);
+/// `ip` commands adding each of `addresses` to `device` on its own and
+/// bringing the device up.
+fn ip_commands(device: &str, addresses: &[IpAddr]) -> Vec<String> {
+    let mut commands: Vec<String> = addresses
+        .iter()
+        .map(|addr| {
+            let prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+            format!("addr add {}/{} dev {}", addr, prefix_len, device)
+        })
+        .collect();
+    commands.push(format!("link set dev {} up", device));
+    commands
+}
 
-        let bundle_dir = self.config.bundle_root.join(node_id.to_string());
-        tokio::fs::create_dir_all(&bundle_dir)
-            .await
-            .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to create bundle dir: {}", e)))?;
+/// Addresses allocated to a container's instance, from its
+/// [`WorkloadInstance::IP_ADDRESSES_ANNOTATION`].
+fn instance_addresses(annotations: &HashMap<String, String>) -> Result<Vec<IpAddr>> {
+    let Some(value) = annotations.get(WorkloadInstance::IP_ADDRESSES_ANNOTATION) else {
+        return Ok(vec![]);
+    };
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|addr| !addr.is_empty())
+        .map(|addr| {
+            addr.parse().map_err(|_| {
+                OrchestrationError::ConfigError(format!(
+                    "Invalid address '{}' in {}",
+                    addr,
+                    WorkloadInstance::IP_ADDRESSES_ANNOTATION
+                ))
+            })
+        })
+        .collect()
+}
 
-        let mut by_node = self.containers_by_node.write().await;
-        by_node.entry(node_id).or_insert_with(Vec::new);
+/// GPUs of the host and the containers they are assigned to.
+struct GpuAllocations {
+    pool: GpuPool,
+    assigned: HashMap<ContainerId, Vec<GpuAssignment>>,
+}
 
-        Ok(())
+impl GpuAllocations {
+    fn release(&mut self, container_id: &str) {
+        if let Some(assignments) = self.assigned.remove(container_id) {
+            self.pool.release(&assignments);
+        }
     }
+}
 
-    async fn create_container(
-        &self,
-        config: &ContainerConfig,
-        options: &CreateContainerOptions,
+/// GPUs assigned to a container about to be created.
+struct GpuLease<'a> {
+    gpus: &'a std::sync::Mutex<GpuAllocations>,
+    container_id: String,
+    assignments: Vec<GpuAssignment>,
+    kept: bool,
+}
+
+impl GpuLease<'_> {
+    /// Keep the GPUs assigned until the container is removed.
+    fn keep(mut self) {
+        self.kept = true;
+    }
+}
+
+impl Drop for GpuLease<'_> {
+    fn drop(&mut self) {
+        if !self.kept {
+            self.gpus.lock().unwrap().release(&self.container_id);
+        }
+    }
+}
+
+/// Environment the NVIDIA container toolkit exposes `assignments` by.
+fn gpu_env(assignments: &[GpuAssignment]) -> Vec<(String, String)> {
+    let mut devices = Vec::new();
+    let mut env = Vec::new();
+    for assignment in assignments {
+        match assignment {
+            GpuAssignment::Device(device) => devices.push(device.to_string()),
+            GpuAssignment::Share { device, percent } => {
+                devices.push(device.to_string());
+                env.push((
+                    "CUDA_MPS_ACTIVE_THREAD_PERCENTAGE".to_string(),
+                    percent.to_string(),
+                ));
+            }
+            GpuAssignment::Mig { device, instance } => {
+                devices.push(format!("{}:{}", device, instance))
+            }
+        }
+    }
+    env.push(("NVIDIA_VISIBLE_DEVICES".to_string(), devices.join(",")));
+    env
+}
+
+/// The cgroup of a container.
+fn cgroup_dir(container_id: &str) -> PathBuf {
+    PathBuf::from("/sys/fs/cgroup/youki").join(container_id)
+}
+
+/// The cgroup v2 files that set `resources`, and their values, in the units
+/// the bundle's OCI resources use at create.
+fn cgroup_limits(resources: &ResourceLimits) -> Vec<(&'static str, String)> {
+    let mut limits = Vec::new();
+    if let Some(cores) = resources.cpu_cores {
+        let cpu = CpuResources::from_cores(cores);
+        let quota = cpu.quota.unwrap_or_default();
+        let period = cpu.period.unwrap_or_default();
+        limits.push(("cpu.max", format!("{} {}", quota, period)));
+    }
+    if let Some(mb) = resources.memory_mb {
+        limits.push(("memory.max", (mb * 1024 * 1024).to_string()));
+    }
+    if let Some(pids) = resources.pids_limit {
+        limits.push(("pids.max", pids.to_string()));
+    }
+    limits
+}
+
+/// Mark a tracked container stopped, reap its process for the exit code and
+/// publish its exit, unless it was already known to have stopped.
+async fn mark_exited(
+    containers: &RwLock<HashMap<String, ContainerState>>,
+    events: &broadcast::Sender<ContainerEvent>,
+    container_id: &str,
+) {
+    let oom_kills = oom_kill_count(container_id).await;
+    let mut containers = containers.write().await;
+    if let Some(state) = containers.get_mut(container_id) {
+        if state.status == "running" {
+            state.status = "stopped".to_string();
+            state.exit_code = state.pid.and_then(reap);
+            // An OOM kill of another process in the container does not end it
+            state.oom_killed =
+                oom_kills > state.oom_kills_at_start && matches!(state.exit_code, None | Some(137));
+            let kind = ContainerEventKind::Exited {
+                exit_code: state.exit_code,
+            };
+            let _ = events.send(ContainerEvent::now(container_id, kind));
+        }
+    }
+}
+
+/// Exit code of an exited child process, 128 plus the signal number for one
+/// killed by a signal. `None` while it runs or when it is not our child.
+fn reap(pid: i32) -> Option<i32> {
+    use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+
+    match waitpid(nix::unistd::Pid::from_raw(pid), Some(WaitPidFlag::WNOHANG)) {
+        Ok(WaitStatus::Exited(_, code)) => Some(code),
+        Ok(WaitStatus::Signaled(_, signal, _)) => Some(128 + signal as i32),
+        _ => None,
+    }
+}
+
+/// Reap zombies reparented to the node from containers' PID namespaces.
+/// Running containers' own processes are left for [`mark_exited`] to reap
+/// for their exit codes.
+async fn reap_orphans(containers: &RwLock<HashMap<String, ContainerState>>) {
+    let tracked: HashSet<i32> = containers
+        .read()
+        .await
+        .values()
+        .filter(|state| state.status == "running")
+        .filter_map(|state| state.pid)
+        .collect();
+    let Ok(mut entries) = tokio::fs::read_dir("/proc").await else {
+        return;
+    };
+    let node = std::process::id() as i32;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse().ok())
+        else {
+            continue;
+        };
+        if tracked.contains(&pid) {
+            continue;
+        }
+        if let Ok(status) = tokio::fs::read_to_string(entry.path().join("status")).await {
+            if is_orphaned_zombie(&status, node) {
+                let _ = reap(pid);
+            }
+        }
+    }
+}
+
+/// Whether `/proc/<pid>/status` describes a zombie child of `parent` from a
+/// nested PID namespace: one reparented from a container rather than one
+/// `parent` spawned and waits for itself.
+fn is_orphaned_zombie(status: &str, parent: i32) -> bool {
+    let field = |key: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .map(str::trim)
+    };
+    field("State:").is_some_and(|state| state.starts_with('Z'))
+        && field("PPid:").and_then(|ppid| ppid.parse().ok()) == Some(parent)
+        && field("NSpid:").is_some_and(|pids| pids.split_whitespace().count() > 1)
+}
+
+/// OOM kills in a container's cgroup so far.
+async fn oom_kill_count(container_id: &str) -> u64 {
+    tokio::fs::read_to_string(cgroup_dir(container_id).join("memory.events"))
+        .await
+        .ok()
+        .and_then(|s| parse_stat_field(&s, "oom_kill"))
+        .unwrap_or(0)
+}
+
+/// The value of `key` in a flat-keyed cgroup file such as `memory.stat`.
+fn parse_stat_field(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once(' ')?;
+        if name == key {
+            value.trim().parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+fn parse_cpu_usage(content: &str) -> u64 {
+    for line in content.lines() {
+        if line.starts_with("usage_usec") {
+            if let Some(val) = line.split_whitespace().nth(1) {
+                return val.parse::<u64>().unwrap_or(0) * 1000;
+            }
+        }
+    }
+    0
+}
+
+#[async_trait]
+impl ContainerRuntime for YoukiCliRuntime {
+    async fn init_node(&self, node_id: NodeId) -> Result<()> {
+        info!("YoukiCliRuntime: Initializing node {}", node_id);
+
+        let bundle_dir = self.config.bundle_root.join(node_id.to_string());
+        tokio::fs::create_dir_all(&bundle_dir)
+            .await
+            .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to create bundle dir: {}", e)))?;
+
+        let mut by_node = self.containers_by_node.write().await;
+        by_node.entry(node_id).or_insert_with(Vec::new);
+
+        Ok(())
+    }
+
+    async fn create_container(
+        &self,
+        config: &ContainerConfig,
+        options: &CreateContainerOptions,
     ) -> Result<ContainerId> {
         let container_id = format!("{}-{}", config.name, Uuid::new_v4());
+        let bandwidth = config.bandwidth_limits()?;
+        let addresses = instance_addresses(&config.annotations)?;
+
+        // The model is bind-mounted from the node's model cache
+        let model = match config.model_artifact()? {
+            Some(artifact) => {
+                let model = self
+                    .models
+                    .acquire(&container_id, &artifact, &self.image_manager)
+                    .await
+                    .map_err(|e| {
+                        OrchestrationError::RuntimeError(format!(
+                            "Failed to fetch model {}: {}",
+                            artifact.uri, e
+                        ))
+                    })?;
+                Some((model, artifact.mount_path))
+            }
+            None => None,
+        };
+        let gpus = self.assign_gpus(&container_id, config)?;
+
+        let with_devices;
+        let config = if model.is_none() && gpus.is_none() {
+            config
+        } else {
+            let mut config = config.clone();
+            if let Some((model, mount_path)) = &model {
+                config.volume_mounts.push(VolumeMount {
+                    name: "model".to_string(),
+                    host_path: model.path().display().to_string(),
+                    mount_path: mount_path.clone(),
+                    read_only: true,
+                });
+            }
+            if let Some(gpus) = &gpus {
+                config.env_vars.extend(gpu_env(&gpus.assignments));
+            }
+            with_devices = config;
+            &with_devices
+        };
 
         info!(
             "YoukiCliRuntime: Creating container {} on node {}",
@@ -744,26 +1860,22 @@ impl ContainerRuntime for YoukiCliRuntime {
             .await
             .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to create bundle: {}", e)))?;
 
-        // Pull image and get rootfs
-        info!("Pulling image: {}", config.image);
-        let rootfs_source = self.image_manager.get_rootfs(&config.image)
-            .await
-            .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to pull image: {}", e)))?;
+        self.link_rootfs(&config.image, &bundle_path, options).await?;
 
-        // Link rootfs to bundle
-        let rootfs_dest = bundle_path.join("rootfs");
-        if rootfs_dest.exists() {
-            tokio::fs::remove_dir_all(&rootfs_dest).await.ok();
+        // Volume host directories must exist before they can be bind-mounted
+        for volume in &config.volume_mounts {
+            tokio::fs::create_dir_all(&volume.host_path)
+                .await
+                .map_err(|e| OrchestrationError::RuntimeError(format!(
+                    "Failed to create volume {} at {}: {}",
+                    volume.name, volume.host_path, e
+                )))?;
         }
 
-        #[cfg(unix)]
-        tokio::fs::symlink(&rootfs_source, &rootfs_dest)
-            .await
-            .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to link rootfs: {}", e)))?;
-
         // Build OCI bundle (generates config.json)
         let mut builder = OciBundleBuilder::new(&bundle_path)
             .with_container_config(config)
+            .with_node_local_dns(self.config.node_local_dns)
             .skip_rootfs_setup();
 
         // Apply resource limits
@@ -791,26 +1903,65 @@ impl ContainerRuntime for YoukiCliRuntime {
         self.youki_create(&container_id, &bundle_path)
             .await
             .map_err(|e| OrchestrationError::RuntimeError(format!("youki create failed: {}", e)))?;
+        self.publish(&container_id, ContainerEventKind::Created);
+
+        // Address the interface before the container's process runs
+        if !addresses.is_empty() {
+            if let Err(e) = self.configure_addresses(&container_id, &addresses).await {
+                let _ = self.youki_delete(&container_id, true).await;
+                return Err(OrchestrationError::RuntimeError(format!(
+                    "Failed to configure addresses of {}: {}",
+                    container_id, e
+                )));
+            }
+        }
+
+        // Limit bandwidth before the container's process runs
+        if !bandwidth.is_empty() {
+            if let Err(e) = self.apply_bandwidth_limits(&container_id, &bandwidth).await {
+                let _ = self.youki_delete(&container_id, true).await;
+                return Err(OrchestrationError::RuntimeError(format!(
+                    "Failed to limit bandwidth of {}: {}",
+                    container_id, e
+                )));
+            }
+        }
 
         // youki start
         self.youki_start(&container_id)
             .await
             .map_err(|e| OrchestrationError::RuntimeError(format!("youki start failed: {}", e)))?;
+        self.publish(&container_id, ContainerEventKind::Started);
+        let pid = self
+            .youki_state(&container_id)
+            .await
+            .ok()
+            .and_then(|state| state.pid);
+        let oom_kills_at_start = oom_kill_count(&container_id).await;
 
         // Track container
-        let state = ContainerState {
+        self.track(ContainerState {
             id: container_id.clone(),
+            name: config.name.clone(),
+            image: config.image.clone(),
             node_id: options.node_id,
+            workload_id: options.workload_id,
             bundle_path,
             status: "running".to_string(),
-            pid: None,
-        };
-
-        self.containers.write().await.insert(container_id.clone(), state);
-        self.containers_by_node.write().await
-            .entry(options.node_id)
-            .or_default()
-            .push(container_id.clone());
+            pid,
+            exit_code: None,
+            oom_killed: false,
+            oom_kills_at_start,
+            termination_grace: config.termination_grace(),
+            pre_stop: config.pre_stop.clone(),
+        })
+        .await;
+        if let Some((model, _)) = model {
+            model.keep();
+        }
+        if let Some(gpus) = gpus {
+            gpus.keep();
+        }
 
         info!("Container {} created and started", container_id);
         Ok(container_id)
@@ -824,13 +1975,36 @@ impl ContainerRuntime for YoukiCliRuntime {
             .await
             .ok();
 
+        let (grace, pre_stop) = match self.containers.read().await.get(container_id) {
+            Some(state) => (
+                state.termination_grace.unwrap_or(self.config.stop_timeout),
+                state.pre_stop.clone(),
+            ),
+            None => (self.config.stop_timeout, None),
+        };
+        let deadline = tokio::time::Instant::now() + grace;
+
+        // The hook's time counts toward the grace period
+        if let Some(hook) = &pre_stop {
+            let run = self.run_pre_stop(container_id, hook, grace);
+            let message = match tokio::time::timeout(grace, run).await {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(format!("preStop hook failed: {}", e)),
+                Err(_) => Some(format!("preStop hook did not finish within {:?}", grace)),
+            };
+            if let Some(message) = message {
+                warn!("Container {}: {}", container_id, message);
+                self.write_log(container_id, "system", &message).await.ok();
+            }
+        }
+        let deadline = deadline.max(tokio::time::Instant::now() + MIN_TERMINATION_GRACE);
+
         // Send SIGTERM
         if let Err(e) = self.youki_kill(container_id, "SIGTERM").await {
             warn!("SIGTERM failed: {}", e);
         }
 
         // Wait for stop or timeout
-        let deadline = tokio::time::Instant::now() + self.config.stop_timeout;
         loop {
             match self.youki_state(container_id).await {
                 Ok(state) if state.status == "stopped" => break,
@@ -857,9 +2031,7 @@ impl ContainerRuntime for YoukiCliRuntime {
             .ok();
 
         // Update state
-        if let Some(state) = self.containers.write().await.get_mut(container_id) {
-            state.status = "stopped".to_string();
-        }
+        mark_exited(&self.containers, &self.events, container_id).await;
 
         Ok(())
     }
@@ -875,10 +2047,23 @@ impl ContainerRuntime for YoukiCliRuntime {
             .await
             .map_err(|e| OrchestrationError::RuntimeError(e.to_string()))?;
 
+        self.models.release(container_id);
+        self.gpus.lock().unwrap().release(container_id);
+
         // Cleanup bundle
         if let Some(state) = self.containers.write().await.remove(container_id) {
             self.cleanup_bundle(&state.bundle_path).await.ok();
 
+            if let Some(root) = &self.config.log_archive_root {
+                let archive_path = archived_log_path(root, &state.workload_id, container_id);
+                if let Err(e) = archive_log(&self.log_path(container_id), &archive_path).await {
+                    warn!(
+                        "Failed to archive logs of container {}: {}",
+                        container_id, e
+                    );
+                }
+            }
+
             let mut by_node = self.containers_by_node.write().await;
             if let Some(list) = by_node.get_mut(&state.node_id) {
                 list.retain(|id| id != container_id);
@@ -891,6 +2076,7 @@ impl ContainerRuntime for YoukiCliRuntime {
             tokio::fs::remove_dir_all(&log_dir).await.ok();
         }
 
+        self.publish(container_id, ContainerEventKind::Removed);
         Ok(())
     }
 
@@ -898,20 +2084,31 @@ impl ContainerRuntime for YoukiCliRuntime {
         debug!("YoukiCliRuntime: Getting status for {}", container_id);
 
         match self.youki_state(container_id).await {
-            Ok(state) => Ok(ContainerStatus {
-                id: container_id.clone(),
-                state: state.status,
-                exit_code: None,
-                error_message: None,
-            }),
+            Ok(state) => {
+                if state.status == "stopped" {
+                    mark_exited(&self.containers, &self.events, container_id).await;
+                }
+                let (exit_code, oom_killed) = match self.containers.read().await.get(container_id) {
+                    Some(tracked) => (tracked.exit_code, tracked.oom_killed),
+                    None => (None, false),
+                };
+                Ok(ContainerStatus {
+                    id: container_id.clone(),
+                    state: state.status,
+                    exit_code,
+                    error_message: None,
+                    oom_killed,
+                })
+            }
             Err(YoukiCliError::ContainerNotFound(_)) => {
                 let containers = self.containers.read().await;
                 if let Some(state) = containers.get(container_id) {
                     Ok(ContainerStatus {
                         id: container_id.clone(),
                         state: state.status.clone(),
-                        exit_code: None,
+                        exit_code: state.exit_code,
                         error_message: None,
+                        oom_killed: state.oom_killed,
                     })
                 } else {
                     Err(OrchestrationError::RuntimeError(format!(
@@ -924,6 +2121,7 @@ impl ContainerRuntime for YoukiCliRuntime {
                 state: "unknown".to_string(),
                 exit_code: None,
                 error_message: Some(e.to_string()),
+                oom_killed: false,
             }),
         }
     }
@@ -946,6 +2144,7 @@ impl ContainerRuntime for YoukiCliRuntime {
                         state: "unknown".to_string(),
                         exit_code: None,
                         error_message: Some(e.to_string()),
+                        oom_killed: false,
                     });
                 }
             }
@@ -972,6 +2171,725 @@ impl ContainerRuntime for YoukiCliRuntime {
             .await
             .map_err(|e| OrchestrationError::RuntimeError(e.to_string()))
     }
+
+    async fn search_container_logs(
+        &self,
+        container_id: &ContainerId,
+        search: &LogSearchOptions,
+    ) -> Result<Vec<LogMatch>> {
+        let matcher = LogMatcher::new(search)?;
+        let Some(path) = self.find_log(container_id).await else {
+            return Ok(Vec::new());
+        };
+        let since = search.since.as_deref();
+        let mut lines = log_rotation::read_lines(&path);
+        let mut matches = Vec::new();
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|e| OrchestrationError::RuntimeError(e.to_string()))?
+        {
+            matches.extend(matcher.match_line(&line, since));
+        }
+        Ok(matches)
+    }
+
+    async fn container_log_path(&self, container_id: &ContainerId) -> Result<PathBuf> {
+        if !self.containers.read().await.contains_key(container_id) {
+            return Err(OrchestrationError::RuntimeError(format!(
+                "Container not found: {}",
+                container_id
+            )));
+        }
+        Ok(self.log_path(container_id))
+    }
+
+    async fn archived_log_containers(&self, workload_id: WorkloadId) -> Result<Vec<ContainerId>> {
+        match &self.config.log_archive_root {
+            Some(root) => archived_containers(root, &workload_id)
+                .await
+                .map_err(|e| OrchestrationError::RuntimeError(e.to_string())),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn exec(&self, container_id: &ContainerId, options: &ExecOptions) -> Result<ExecSession> {
+        if options.command.is_empty() {
+            return Err(OrchestrationError::ConfigError(
+                "Exec needs a command".to_string(),
+            ));
+        }
+        let mut command = Command::new(&self.config.youki_binary);
+        command
+            .arg("--root")
+            .arg(&self.config.state_root)
+            .args(["exec", container_id.as_str(), "--"])
+            .args(&options.command)
+            .kill_on_drop(true);
+        debug!("Exec {:?} in container {}", options.command, container_id);
+
+        let (input, input_rx) = mpsc::channel(16);
+        let (output_tx, output) = mpsc::channel(16);
+        let spawned = if options.tty {
+            spawn_on_pty(command, output_tx.clone())
+        } else {
+            spawn_on_pipes(command, output_tx.clone())
+        };
+        let (child, stdin, pty, forwarders) =
+            spawned.map_err(|e| OrchestrationError::RuntimeError(format!("Exec failed: {}", e)))?;
+        tokio::spawn(run_exec(child, stdin, pty, forwarders, input_rx, output_tx));
+        Ok(ExecSession { input, output })
+    }
+
+    async fn debug_container(
+        &self,
+        container_id: &ContainerId,
+        options: &DebugOptions,
+    ) -> Result<ExecSession> {
+        let node_id = self
+            .containers
+            .read()
+            .await
+            .get(container_id)
+            .map(|state| state.node_id)
+            .ok_or_else(|| {
+                OrchestrationError::RuntimeError(format!("Container not found: {}", container_id))
+            })?;
+        let pid = self
+            .youki_state(container_id)
+            .await
+            .map_err(|e| OrchestrationError::RuntimeError(e.to_string()))?
+            .pid
+            .filter(|pid| *pid > 0)
+            .ok_or_else(|| {
+                OrchestrationError::RuntimeError(format!(
+                    "Container {} is not running",
+                    container_id
+                ))
+            })?;
+
+        let debug_id = format!("debug-{}", Uuid::new_v4());
+        info!("YoukiCliRuntime: Starting debug container {} for {}", debug_id, container_id);
+        let pull_queue = self.pull_queue(&node_id).await;
+        let rootfs_source = self
+            .image_manager
+            .get_rootfs_queued(&options.image, &pull_queue, PullPriority::Interactive)
+            .await
+            .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to pull image: {}", e)))?;
+
+        let config = ContainerConfig {
+            name: debug_id.clone(),
+            image: options.image.clone(),
+            command: (!options.command.is_empty()).then(|| options.command.clone()),
+            args: None,
+            env_vars: HashMap::new(),
+            ports: Vec::new(),
+            resource_requests: Default::default(),
+            volume_mounts: Vec::new(),
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+            readiness_probe: None,
+            limits: Default::default(),
+            sysctls: HashMap::new(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
+        };
+        let bundle_path = self.bundle_path(&node_id, &debug_id);
+        // Ptrace lets the tools inspect the target's processes and files
+        OciBundleBuilder::new(&bundle_path)
+            .with_container_config(&config)
+            .with_namespaces_of(pid)
+            .with_capability("CAP_SYS_PTRACE")
+            .skip_rootfs_setup()
+            .build()
+            .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to build bundle: {}", e)))?;
+        mount_rootfs(&rootfs_source, &bundle_path)
+            .await
+            .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to mount rootfs: {}", e)))?;
+
+        // `youki run` stays in the foreground and hands its stdio to the container
+        let mut command = Command::new(&self.config.youki_binary);
+        command
+            .arg("--root")
+            .arg(&self.config.state_root)
+            .args(["run", "--bundle"])
+            .arg(&bundle_path)
+            .arg(&debug_id)
+            .kill_on_drop(true);
+        let mut cleanup = Command::new(&self.config.youki_binary);
+        cleanup
+            .arg("--root")
+            .arg(&self.config.state_root)
+            .args(["delete", "--force", debug_id.as_str()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        let (input, input_rx) = mpsc::channel(16);
+        let (output_tx, output) = mpsc::channel(16);
+        let spawned = if options.tty {
+            spawn_on_pty(command, output_tx.clone())
+        } else {
+            spawn_on_pipes(command, output_tx.clone())
+        };
+        let (child, stdin, pty, forwarders) = match spawned {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                remove_bundle(&bundle_path).await.ok();
+                return Err(OrchestrationError::RuntimeError(format!(
+                    "Debug container failed: {}",
+                    e
+                )));
+            }
+        };
+        tokio::spawn(async move {
+            run_exec(child, stdin, pty, forwarders, input_rx, output_tx).await;
+            if let Err(e) = cleanup.status().await {
+                warn!("Failed to delete debug container {}: {}", debug_id, e);
+            }
+            remove_bundle(&bundle_path).await.ok();
+        });
+        Ok(ExecSession { input, output })
+    }
+
+    async fn container_usage(&self, container_id: &ContainerId) -> Result<ContainerUsage> {
+        if !self.containers.read().await.contains_key(container_id) {
+            return Err(OrchestrationError::RuntimeError(format!(
+                "Container not found: {}",
+                container_id
+            )));
+        }
+        let stats = self
+            .get_stats(container_id)
+            .await
+            .map_err(|e| OrchestrationError::RuntimeError(e.to_string()))?;
+        Ok(ContainerUsage {
+            cpu_usage_ns: stats.cpu_usage_ns,
+            memory_working_set_bytes: stats.memory_working_set_bytes,
+            oom_kills: stats.oom_kills,
+        })
+    }
+
+    async fn update_container_resources(
+        &self,
+        container_id: &ContainerId,
+        resources: &ResourceLimits,
+    ) -> Result<()> {
+        if !self.containers.read().await.contains_key(container_id) {
+            return Err(OrchestrationError::RuntimeError(format!(
+                "Container not found: {}",
+                container_id
+            )));
+        }
+        info!(
+            "YoukiCliRuntime: Updating resources of container {}: {:?}",
+            container_id, resources
+        );
+        let cgroup_path = cgroup_dir(container_id);
+        for (file, value) in cgroup_limits(resources) {
+            tokio::fs::write(cgroup_path.join(file), &value)
+                .await
+                .map_err(|e| {
+                    OrchestrationError::RuntimeError(format!(
+                        "Failed to set {} of container {} to {}: {}",
+                        file, container_id, value, e
+                    ))
+                })?;
+        }
+        Ok(())
+    }
+
+    async fn checkpoint_container(
+        &self,
+        container_id: &ContainerId,
+        options: &CheckpointOptions,
+    ) -> Result<Checkpoint> {
+        info!("YoukiCliRuntime: Checkpointing container {}", container_id);
+
+        let state = self
+            .containers
+            .read()
+            .await
+            .get(container_id)
+            .cloned()
+            .ok_or_else(|| {
+                OrchestrationError::RuntimeError(format!("Container not found: {}", container_id))
+            })?;
+        if state.status != "running" {
+            return Err(OrchestrationError::RuntimeError(format!(
+                "Container {} is {}, not running",
+                container_id, state.status
+            )));
+        }
+
+        let created_at = std::time::SystemTime::now();
+        let path = self.config.checkpoint_root.join(format!(
+            "{}-{}",
+            container_id,
+            Utc::now().format("%Y%m%dT%H%M%SZ")
+        ));
+        let manifest = CheckpointManifest {
+            name: state.name,
+            image: state.image,
+            termination_grace: state.termination_grace,
+            pre_stop: state.pre_stop,
+        };
+        let saved = async {
+            tokio::fs::create_dir_all(path.join("images")).await?;
+            tokio::fs::copy(state.bundle_path.join("config.json"), path.join("config.json"))
+                .await?;
+            let manifest = serde_json::to_vec_pretty(&manifest)?;
+            tokio::fs::write(path.join(CHECKPOINT_MANIFEST), manifest).await?;
+            Ok::<(), YoukiCliError>(())
+        };
+        if let Err(e) = saved.await {
+            tokio::fs::remove_dir_all(&path).await.ok();
+            return Err(OrchestrationError::RuntimeError(format!(
+                "Failed to save checkpoint: {}",
+                e
+            )));
+        }
+
+        // The dump stops the container; keep the watcher from reporting that
+        // as an exit
+        if !options.leave_running {
+            self.set_status(container_id, "checkpointing").await;
+        }
+        if let Err(e) = self
+            .youki_checkpoint(container_id, &path, options.leave_running)
+            .await
+        {
+            if !options.leave_running {
+                self.set_status(container_id, "running").await;
+            }
+            tokio::fs::remove_dir_all(&path).await.ok();
+            return Err(OrchestrationError::RuntimeError(format!(
+                "youki checkpoint failed: {}",
+                e
+            )));
+        }
+        if !options.leave_running {
+            self.set_status(container_id, "checkpointed").await;
+        }
+        let message = format!("Container checkpointed to {}", path.display());
+        self.write_log(container_id, "system", &message).await.ok();
+
+        Ok(Checkpoint {
+            container_id: container_id.clone(),
+            path,
+            created_at,
+        })
+    }
+
+    async fn restore_container(
+        &self,
+        checkpoint: &Checkpoint,
+        options: &CreateContainerOptions,
+    ) -> Result<ContainerId> {
+        let manifest: CheckpointManifest = async {
+            let manifest = tokio::fs::read(checkpoint.path.join(CHECKPOINT_MANIFEST)).await?;
+            Ok::<_, YoukiCliError>(serde_json::from_slice(&manifest)?)
+        }
+        .await
+        .map_err(|e| OrchestrationError::RuntimeError(format!("Invalid checkpoint: {}", e)))?;
+        let container_id = format!("{}-{}", manifest.name, Uuid::new_v4());
+
+        info!(
+            "YoukiCliRuntime: Restoring container {} from checkpoint of {} on node {}",
+            container_id, checkpoint.container_id, options.node_id
+        );
+
+        let bundle_path = self.bundle_path(&options.node_id, &container_id);
+        tokio::fs::create_dir_all(&bundle_path).await.map_err(|e| {
+            OrchestrationError::RuntimeError(format!("Failed to create bundle: {}", e))
+        })?;
+        self.link_rootfs(&manifest.image, &bundle_path, options).await?;
+        tokio::fs::copy(checkpoint.path.join("config.json"), bundle_path.join("config.json"))
+            .await
+            .map_err(|e| {
+                OrchestrationError::RuntimeError(format!("Failed to copy config: {}", e))
+            })?;
+
+        self.init_log_capture(&container_id).await.map_err(|e| {
+            OrchestrationError::RuntimeError(format!("Failed to init log capture: {}", e))
+        })?;
+        let message = format!(
+            "Container {} restoring from checkpoint of {}",
+            container_id, checkpoint.container_id
+        );
+        self.write_log(&container_id, "system", &message).await.ok();
+
+        if let Err(e) = self.youki_restore(&container_id, &checkpoint.path, &bundle_path).await {
+            self.cleanup_bundle(&bundle_path).await.ok();
+            return Err(OrchestrationError::RuntimeError(format!("youki restore failed: {}", e)));
+        }
+        self.publish(&container_id, ContainerEventKind::Created);
+        self.publish(&container_id, ContainerEventKind::Started);
+        let pid = self
+            .youki_state(&container_id)
+            .await
+            .ok()
+            .and_then(|state| state.pid);
+        let oom_kills_at_start = oom_kill_count(&container_id).await;
+
+        self.track(ContainerState {
+            id: container_id.clone(),
+            name: manifest.name,
+            image: manifest.image,
+            node_id: options.node_id,
+            workload_id: options.workload_id,
+            bundle_path,
+            status: "running".to_string(),
+            pid,
+            exit_code: None,
+            oom_killed: false,
+            oom_kills_at_start,
+            termination_grace: manifest.termination_grace,
+            pre_stop: manifest.pre_stop,
+        })
+        .await;
+
+        info!("Container {} restored", container_id);
+        Ok(container_id)
+    }
+
+    async fn remove_checkpoint(&self, checkpoint: &Checkpoint) -> Result<()> {
+        if !checkpoint.path.starts_with(&self.config.checkpoint_root) {
+            return Err(OrchestrationError::RuntimeError(format!(
+                "Not a checkpoint of this runtime: {}",
+                checkpoint.path.display()
+            )));
+        }
+        if checkpoint.path.exists() {
+            tokio::fs::remove_dir_all(&checkpoint.path).await.map_err(|e| {
+                OrchestrationError::RuntimeError(format!("Failed to remove checkpoint: {}", e))
+            })?;
+        }
+        Ok(())
+    }
+
+    async fn image_pull_status(&self, node_id: NodeId) -> Option<ImagePullStatus> {
+        let status = match self.pull_queues.read().await.get(&node_id) {
+            Some(queue) => queue.status(),
+            // No pulls yet: report an idle queue with the configured limits
+            None => ImagePullQueue::new(self.config.pull_queue.clone()).status(),
+        };
+        Some(status)
+    }
+
+    async fn pull_image(&self, node_id: NodeId, image: &str, priority: PullPriority) -> Result<()> {
+        info!("Pulling image {} on node {}", image, node_id);
+        let pull_queue = self.pull_queue(&node_id).await;
+        self.image_manager
+            .get_rootfs_queued(image, &pull_queue, priority)
+            .await
+            .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to pull image: {}", e)))?;
+        Ok(())
+    }
+
+    async fn inspect_image(&self, image: &str) -> Result<ImageInspection> {
+        debug!("Inspecting image {}", image);
+        self.image_manager
+            .inspect(image)
+            .await
+            .map_err(|e| match e {
+                ImageError::InvalidReference(_) => OrchestrationError::ConfigError(e.to_string()),
+                e => OrchestrationError::RuntimeError(format!("Failed to inspect image: {}", e)),
+            })
+    }
+
+    async fn scan_image(&self, image: &str) -> Result<ImageScan> {
+        self.image_manager.scan(image).await.map_err(|e| match e {
+            ImageError::InvalidReference(_) => OrchestrationError::ConfigError(e.to_string()),
+            e => OrchestrationError::RuntimeError(format!("Failed to scan image: {}", e)),
+        })
+    }
+
+    async fn image_scans(&self) -> Result<Vec<ImageScan>> {
+        self.image_manager
+            .list_scans()
+            .map_err(|e| OrchestrationError::RuntimeError(format!("Failed to list scans: {}", e)))
+    }
+
+    fn events(&self) -> Option<broadcast::Receiver<ContainerEvent>> {
+        Some(self.events.subscribe())
+    }
+}
+
+/// A spawned exec command: the child, its stdin, the pty master for `tty`
+/// sessions and the tasks forwarding its output.
+type SpawnedExec = (
+    Child,
+    Option<Box<dyn AsyncWrite + Send + Unpin>>,
+    Option<std::fs::File>,
+    Vec<JoinHandle<()>>,
+);
+
+fn spawn_on_pipes(
+    mut command: Command,
+    output: mpsc::Sender<ExecOutput>,
+) -> std::io::Result<SpawnedExec> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdin = child
+        .stdin
+        .take()
+        .map(|stdin| Box::new(stdin) as Box<dyn AsyncWrite + Send + Unpin>);
+    let mut forwarders = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        forwarders.push(forward_output(stdout, output.clone(), ExecOutput::Stdout));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forwarders.push(forward_output(stderr, output, ExecOutput::Stderr));
+    }
+    Ok((child, stdin, None, forwarders))
+}
+
+fn spawn_on_pty(
+    mut command: Command,
+    output: mpsc::Sender<ExecOutput>,
+) -> std::io::Result<SpawnedExec> {
+    let pty = nix::pty::openpty(None, None).map_err(std::io::Error::from)?;
+    let terminal = std::fs::File::from(pty.slave);
+    command
+        .stdin(terminal.try_clone()?)
+        .stdout(terminal.try_clone()?)
+        .stderr(terminal);
+    let child = command.spawn()?;
+    // Close our copies of the terminal so reads end when the command exits
+    drop(command);
+
+    let master = std::fs::File::from(pty.master);
+    let reader = tokio::fs::File::from_std(master.try_clone()?);
+    let writer = tokio::fs::File::from_std(master.try_clone()?);
+    let forwarder = forward_output(reader, output, ExecOutput::Stdout);
+    Ok((child, Some(Box::new(writer)), Some(master), vec![forwarder]))
+}
+
+/// Forward everything read from `reader` to `output`. A pty reports the
+/// command exiting as an I/O error, so errors end the output too.
+fn forward_output(
+    mut reader: impl AsyncRead + Send + Unpin + 'static,
+    output: mpsc::Sender<ExecOutput>,
+    wrap: fn(Vec<u8>) -> ExecOutput,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut buf = vec![0; 8192];
+        loop {
+            match reader.read(&mut buf).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => {
+                    if output.send(wrap(buf[..n].to_vec())).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Feed session input to an exec command until it exits, then report its
+/// exit code. Kills the command if the session is dropped.
+async fn run_exec(
+    mut child: Child,
+    mut stdin: Option<Box<dyn AsyncWrite + Send + Unpin>>,
+    pty: Option<std::fs::File>,
+    forwarders: Vec<JoinHandle<()>>,
+    mut input: mpsc::Receiver<ExecInput>,
+    output: mpsc::Sender<ExecOutput>,
+) {
+    let mut input_open = true;
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status.ok().and_then(|s| s.code()),
+            _ = output.closed() => {
+                let _ = child.kill().await;
+                return;
+            }
+            message = input.recv(), if input_open => match message {
+                Some(ExecInput::Stdin(data)) => {
+                    if let Some(writer) = stdin.as_mut() {
+                        if writer.write_all(&data).await.is_err() {
+                            stdin = None;
+                        }
+                    }
+                }
+                Some(ExecInput::Resize { cols, rows }) => {
+                    if let Some(master) = &pty {
+                        resize_pty(master, cols, rows);
+                    }
+                }
+                Some(ExecInput::CloseStdin) | None => {
+                    input_open = false;
+                    match (&pty, stdin.as_mut()) {
+                        // A terminal has no end of input; send EOT as a shell expects
+                        (Some(_), Some(writer)) => {
+                            let _ = writer.write_all(b"\x04").await;
+                        }
+                        _ => stdin = None,
+                    }
+                }
+            },
+        }
+    };
+    for forwarder in forwarders {
+        let _ = tokio::time::timeout(EXEC_OUTPUT_DRAIN, forwarder).await;
+    }
+    let _ = output.send(ExecOutput::Exit(status)).await;
+}
+
+fn resize_pty(master: &std::fs::File, cols: u16, rows: u16) {
+    let size = nix::libc::winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: the fd is an open pty master and `size` outlives the call
+    let result = unsafe { nix::libc::ioctl(master.as_raw_fd(), nix::libc::TIOCSWINSZ, &size) };
+    if result != 0 {
+        warn!("Failed to resize exec terminal: {}", std::io::Error::last_os_error());
+    }
+}
+
+/// Append a timestamped line to the log at `log_path`, rotating it first if
+/// the line would outgrow it. `writes` serializes writers of the node's logs.
+async fn append_log(
+    log_path: &Path,
+    rotation: &LogRotationConfig,
+    writes: &Mutex<()>,
+    stream: &str,
+    message: &str,
+) -> std::result::Result<(), YoukiCliError> {
+    // Ensure log directory exists
+    if let Some(parent) = log_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let timestamp = Utc::now().to_rfc3339();
+    let line = format!("{} {} {}\n", timestamp, stream, message);
+
+    let _writing = writes.lock().await;
+    if log_rotation::needs_rotation(log_path, rotation, line.len() as u64).await {
+        log_rotation::rotate(log_path, rotation).await?;
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .await?;
+
+    file.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+/// Copy what a container writes to `pipe` into its log as `stream` lines,
+/// until every holder of the pipe's other end closes it. Returns the last
+/// line, which is youki's error when the container could not be created.
+fn spawn_log_shim(
+    pipe: impl AsyncRead + Unpin + Send + 'static,
+    stream: &'static str,
+    log_path: PathBuf,
+    rotation: LogRotationConfig,
+    writes: Arc<Mutex<()>>,
+) -> JoinHandle<Option<String>> {
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        let mut last = None;
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line).await {
+                Ok(0) => break,
+                Ok(_) => {
+                    let message = String::from_utf8_lossy(&line)
+                        .trim_end_matches(['\n', '\r'])
+                        .to_string();
+                    if let Err(e) =
+                        append_log(&log_path, &rotation, &writes, stream, &message).await
+                    {
+                        warn!("Failed to write container log {:?}: {}", log_path, e);
+                    }
+                    last = Some(message);
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to read container {} of {:?}: {}",
+                        stream, log_path, e
+                    );
+                    break;
+                }
+            }
+        }
+        last
+    })
+}
+
+/// Archived log file of a removed container.
+fn archived_log_path(root: &Path, workload_id: &WorkloadId, container_id: &str) -> PathBuf {
+    root.join(workload_id.to_string())
+        .join(format!("{}.log", container_id))
+}
+
+/// Move a container log, rotated segments included, into the archive as one
+/// file. Copies rather than renames since the state root is usually on a tmpfs.
+async fn archive_log(log_path: &Path, archive_path: &Path) -> std::io::Result<()> {
+    if !log_path.exists() {
+        return Ok(());
+    }
+    if let Some(dir) = archive_path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut archive = tokio::io::BufWriter::new(tokio::fs::File::create(archive_path).await?);
+    let mut lines = log_rotation::read_lines(log_path);
+    while let Some(line) = lines.next_line().await? {
+        archive.write_all(line.as_bytes()).await?;
+        archive.write_all(b"\n").await?;
+    }
+    archive.flush().await?;
+    tokio::fs::remove_file(log_path).await
+}
+
+/// Find the archived log of a container without knowing its workload.
+async fn find_archived_log(root: &Path, container_id: &str) -> Option<PathBuf> {
+    let mut workloads = tokio::fs::read_dir(root).await.ok()?;
+    while let Ok(Some(entry)) = workloads.next_entry().await {
+        let path = entry.path().join(format!("{}.log", container_id));
+        if path.exists() {
+            return Some(path);
+        }
+    }
+    None
+}
+
+/// Containers of a workload with an archived log.
+async fn archived_containers(
+    root: &Path,
+    workload_id: &WorkloadId,
+) -> std::io::Result<Vec<ContainerId>> {
+    let mut entries = match tokio::fs::read_dir(root.join(workload_id.to_string())).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut containers = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "log") {
+            if let Some(stem) = path.file_stem() {
+                containers.push(stem.to_string_lossy().into_owned());
+            }
+        }
+    }
+    containers.sort();
+    Ok(containers)
 }
 
 #[cfg(test)]
@@ -983,6 +2901,7 @@ mod tests {
         let config = YoukiCliConfig::default();
         assert_eq!(config.youki_binary, PathBuf::from("youki"));
         assert_eq!(config.command_timeout, Duration::from_secs(30));
+        assert_eq!(config.pull_queue.max_concurrent_pulls, 3);
     }
 
     #[test]
@@ -1013,10 +2932,191 @@ mod tests {
             container_id: "test".to_string(),
             cpu_usage_ns: 1000000,
             memory_usage_bytes: 1048576,
+            memory_working_set_bytes: 524288,
+            oom_kills: 0,
         };
         assert_eq!(stats.memory_usage_bytes, 1024 * 1024);
     }
 
+    #[test]
+    fn test_parse_stat_field() {
+        let stat = "anon 4096\nfile 8192\ninactive_file 2048\nactive_file 6144\n";
+        assert_eq!(parse_stat_field(stat, "inactive_file"), Some(2048));
+        assert_eq!(parse_stat_field(stat, "file"), Some(8192));
+        assert_eq!(parse_stat_field("low 0\noom 1\noom_kill 3\n", "oom_kill"), Some(3));
+        assert_eq!(parse_stat_field(stat, "shmem"), None);
+    }
+
+    #[test]
+    fn test_cgroup_limits() {
+        let resources = ResourceLimits {
+            cpu_cores: Some(1.5),
+            memory_mb: Some(512),
+            pids_limit: Some(256),
+        };
+        assert_eq!(
+            cgroup_limits(&resources),
+            vec![
+                ("cpu.max", "150000 100000".to_string()),
+                ("memory.max", "536870912".to_string()),
+                ("pids.max", "256".to_string()),
+            ]
+        );
+        let memory_only = ResourceLimits {
+            memory_mb: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            cgroup_limits(&memory_only),
+            vec![("memory.max", "1048576".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_tc_commands() {
+        let limits = BandwidthLimits {
+            ingress_bps: Some(100_000_000),
+            egress_bps: Some(10_000_000),
+        };
+        assert_eq!(
+            tc_commands("eth0", &limits),
+            vec![
+                "qdisc add dev eth0 root handle 1: htb default 1",
+                "class add dev eth0 parent 1: classid 1:1 htb rate 10000000bit ceil 10000000bit",
+                "qdisc add dev eth0 handle ffff: ingress",
+                "filter add dev eth0 parent ffff: matchall action police rate 100000000bit \
+                 burst 1250000 drop",
+            ]
+        );
+        let slow = BandwidthLimits {
+            ingress_bps: Some(1_000_000),
+            egress_bps: None,
+        };
+        assert!(tc_commands("eth0", &slow)[1].ends_with("burst 65536 drop"));
+        assert!(tc_commands("eth0", &BandwidthLimits::default()).is_empty());
+    }
+
+    #[test]
+    fn test_instance_addresses() {
+        let annotations = |value: &str| {
+            HashMap::from([(
+                WorkloadInstance::IP_ADDRESSES_ANNOTATION.to_string(),
+                value.to_string(),
+            )])
+        };
+        let addresses = instance_addresses(&annotations("10.96.0.5,fd00:10:96::5")).unwrap();
+        assert_eq!(
+            ip_commands("eth0", &addresses),
+            vec![
+                "addr add 10.96.0.5/32 dev eth0",
+                "addr add fd00:10:96::5/128 dev eth0",
+                "link set dev eth0 up",
+            ]
+        );
+        assert!(instance_addresses(&HashMap::new()).unwrap().is_empty());
+        assert!(instance_addresses(&annotations("10.96.0.300")).is_err());
+    }
+
+    #[test]
+    fn test_gpu_env() {
+        let env = |assignments: &[GpuAssignment]| -> HashMap<String, String> {
+            gpu_env(assignments).into_iter().collect()
+        };
+        let devices = env(&[GpuAssignment::Device(0), GpuAssignment::Device(2)]);
+        assert_eq!(devices["NVIDIA_VISIBLE_DEVICES"], "0,2");
+        assert!(!devices.contains_key("CUDA_MPS_ACTIVE_THREAD_PERCENTAGE"));
+
+        let share = env(&[GpuAssignment::Share {
+            device: 1,
+            percent: 25,
+        }]);
+        assert_eq!(share["NVIDIA_VISIBLE_DEVICES"], "1");
+        assert_eq!(share["CUDA_MPS_ACTIVE_THREAD_PERCENTAGE"], "25");
+
+        let mig = env(&[GpuAssignment::Mig {
+            device: 0,
+            instance: 3,
+        }]);
+        assert_eq!(mig["NVIDIA_VISIBLE_DEVICES"], "0:3");
+    }
+
+    #[tokio::test]
+    async fn test_mark_exited_publishes_once() {
+        let containers = RwLock::new(HashMap::new());
+        containers.write().await.insert(
+            "web-1".to_string(),
+            ContainerState {
+                id: "web-1".to_string(),
+                name: "web".to_string(),
+                image: "nginx:latest".to_string(),
+                node_id: orchestrator_shared_types::Keypair::generate().public_key(),
+                workload_id: Uuid::new_v4(),
+                bundle_path: PathBuf::new(),
+                status: "running".to_string(),
+                pid: None,
+                exit_code: None,
+                oom_killed: false,
+                oom_kills_at_start: 0,
+                termination_grace: None,
+                pre_stop: None,
+            },
+        );
+        let (events, mut received) = broadcast::channel(4);
+
+        mark_exited(&containers, &events, "web-1").await;
+        mark_exited(&containers, &events, "web-1").await;
+        mark_exited(&containers, &events, "unknown").await;
+
+        let event = received.try_recv().unwrap();
+        assert_eq!(event.container_id, "web-1");
+        assert_eq!(event.kind, ContainerEventKind::Exited { exit_code: None });
+        assert!(received.try_recv().is_err());
+        assert_eq!(containers.read().await["web-1"].status, "stopped");
+    }
+
+    #[test]
+    fn test_is_orphaned_zombie() {
+        let status = |state: &str, ppid: i32, nspid: &str| {
+            format!(
+                "Name:\tsleep\nState:\t{}\nPid:\t4242\nPPid:\t{}\nNSpid:\t{}\n",
+                state, ppid, nspid
+            )
+        };
+        let nested = "4242\t7";
+        assert!(is_orphaned_zombie(&status("Z (zombie)", 100, nested), 100));
+        // Still running
+        let running = status("R (running)", 100, nested);
+        assert!(!is_orphaned_zombie(&running, 100));
+        // Spawned by the node itself
+        assert!(!is_orphaned_zombie(&status("Z (zombie)", 100, "4242"), 100));
+        // Another process's child
+        assert!(!is_orphaned_zombie(&status("Z (zombie)", 1, nested), 100));
+    }
+
+    #[test]
+    #[allow(clippy::zombie_processes)] // reaped by `reap`
+    fn test_reap_exit_codes() {
+        let exit_code = |script: &str| {
+            let child = std::process::Command::new("sh")
+                .args(["-c", script])
+                .spawn()
+                .unwrap();
+            let pid = child.id() as i32;
+            for _ in 0..500 {
+                if let Some(code) = reap(pid) {
+                    return Some(code);
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            None
+        };
+        assert_eq!(exit_code("exit 0"), Some(0));
+        assert_eq!(exit_code("exit 3"), Some(3));
+        assert_eq!(exit_code("kill -9 $$"), Some(137));
+        // Not our child
+        assert_eq!(reap(1), None);
+    }
+
     #[test]
     fn test_log_entry_serde() {
         let entry = LogEntry {
@@ -1084,4 +3184,93 @@ mod tests {
         // Timestamp should be valid RFC3339
         assert!(entry.timestamp.contains('T'));
     }
+
+    #[tokio::test]
+    async fn test_log_shim_writes_through_rotation() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("c1").join("container.log");
+        let rotation = LogRotationConfig {
+            max_size_bytes: 100,
+            max_files: 3,
+            compress: false,
+            ..Default::default()
+        };
+        let output: &[u8] = b"starting\nlistening on :8080\r\nerror: disk full\n";
+
+        let last = spawn_log_shim(
+            output,
+            "stderr",
+            log_path.clone(),
+            rotation,
+            Arc::new(Mutex::new(())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(last.as_deref(), Some("error: disk full"));
+
+        // No two lines fit within the limit, so the log spans rotated segments
+        assert!(log_path.with_extension("log.1").exists());
+        let lines = YoukiCliRuntime::read_log_tail(&log_path, None)
+            .await
+            .unwrap();
+        let messages: Vec<_> = lines
+            .iter()
+            .filter_map(|line| YoukiCliRuntime::parse_log_line(line))
+            .map(|entry| (entry.stream, entry.message))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                ("stderr".to_string(), "starting".to_string()),
+                ("stderr".to_string(), "listening on :8080".to_string()),
+                ("stderr".to_string(), "error: disk full".to_string()),
+            ]
+        );
+        let tail = YoukiCliRuntime::read_log_tail(&log_path, Some(1))
+            .await
+            .unwrap();
+        assert_eq!(tail.len(), 1);
+        assert!(tail[0].ends_with("stderr error: disk full"));
+    }
+
+    #[tokio::test]
+    async fn test_archived_logs() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().join("archive");
+        let workload_id = Uuid::new_v4();
+        let log_path = temp_dir.path().join("container.log");
+        tokio::fs::write(&log_path, "2024-01-15T10:30:00Z stdout hello\n")
+            .await
+            .unwrap();
+
+        assert!(archived_containers(&root, &workload_id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let archive_path = archived_log_path(&root, &workload_id, "c1");
+        archive_log(&log_path, &archive_path).await.unwrap();
+        assert!(!log_path.exists());
+        assert_eq!(
+            find_archived_log(&root, "c1").await,
+            Some(archive_path.clone())
+        );
+        assert_eq!(find_archived_log(&root, "c2").await, None);
+        assert_eq!(
+            archived_containers(&root, &workload_id).await.unwrap(),
+            vec!["c1".to_string()]
+        );
+
+        let content = tokio::fs::read_to_string(&archive_path).await.unwrap();
+        let matcher = LogMatcher::new(&LogSearchOptions {
+            query: "h[e]llo".to_string(),
+            regex: true,
+            since: None,
+        })
+        .unwrap();
+        assert_eq!(matcher.search(&content, None).len(), 1);
+        assert!(matcher
+            .search(&content, Some("2024-02-01T00:00:00Z"))
+            .is_empty());
+    }
 }
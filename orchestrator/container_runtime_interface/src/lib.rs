@@ -1,20 +1,269 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
 use async_trait::async_trait;
-use orchestrator_shared_types::{ContainerConfig, ContainerId, NodeId, OrchestrationError, Result, WorkloadId};
+use orchestrator_shared_types::{
+    ContainerConfig, ContainerId, NodeId, OrchestrationError, Result, TerminationReason,
+    WorkloadDefinition, WorkloadId,
+};
 use serde::{Deserialize, Serialize};
 
+/// How long an init container may run before the instance start is abandoned.
+pub const DEFAULT_INIT_CONTAINER_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How often a running init container is polled for completion.
+const INIT_CONTAINER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateContainerOptions {
     pub workload_id: WorkloadId,
     pub node_id: NodeId, // Where the container should run (managed by scheduler)
+    /// Place in the node's image pull queue if the image is not cached.
+    #[serde(default)]
+    pub pull_priority: PullPriority,
     // Potentially OCI spec details or other runtime-specific configurations
 }
 
+/// Order in which a node pulls images when more are requested than it pulls
+/// at once.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum PullPriority {
+    /// Jobs and other work that can wait for interactive deploys.
+    Batch,
+    #[default]
+    Interactive,
+}
+
+/// Image pulls on one node.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImagePullStatus {
+    /// Pulls currently downloading.
+    pub active: usize,
+    pub queued_interactive: usize,
+    pub queued_batch: usize,
+    pub max_concurrent_pulls: usize,
+    /// Download limit shared by all pulls on the node, if any.
+    pub max_bandwidth_bytes_per_sec: Option<u64>,
+}
+
+impl ImagePullStatus {
+    /// Pulls waiting for a slot.
+    pub fn queue_depth(&self) -> usize {
+        self.queued_interactive + self.queued_batch
+    }
+}
+
+/// What an image reference resolves to in its registry, read from the
+/// manifest and image config.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImageInspection {
+    /// The reference as given, e.g. `vllm/vllm-openai:v0.6.0`.
+    pub reference: String,
+    /// Digest of the manifest the reference resolved to.
+    pub digest: String,
+    /// Platform the image was built for, e.g. `linux/arm64/v8`.
+    pub platform: String,
+    pub layers: Vec<ImageLayer>,
+    /// Ports the image declares, e.g. `8000/tcp`.
+    pub exposed_ports: Vec<String>,
+    pub env: Vec<String>,
+    pub entrypoint: Vec<String>,
+    pub cmd: Vec<String>,
+    pub labels: HashMap<String, String>,
+}
+
+impl ImageInspection {
+    /// Compressed size of all layers, i.e. what a node downloads.
+    pub fn size_bytes(&self) -> u64 {
+        self.layers.iter().map(|l| l.size_bytes).sum()
+    }
+}
+
+/// A layer of an inspected image.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImageLayer {
+    pub digest: String,
+    /// Compressed size.
+    pub size_bytes: u64,
+}
+
+/// Severity a scanner assigns to a vulnerability, lowest first.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Unknown,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 5] = [
+        Severity::Unknown,
+        Severity::Low,
+        Severity::Medium,
+        Severity::High,
+        Severity::Critical,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Unknown => "unknown",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    /// Parses scanner severities case-insensitively; Grype's "negligible"
+    /// counts as low.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "unknown" => Ok(Severity::Unknown),
+            "negligible" | "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            other => Err(format!("unknown severity '{}'", other)),
+        }
+    }
+}
+
+/// A known vulnerability in a package of an image.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Vulnerability {
+    /// CVE or advisory ID, e.g. `CVE-2024-3094`.
+    pub id: String,
+    pub package: String,
+    pub installed_version: String,
+    /// First version with the fix, if one is released.
+    pub fixed_version: Option<String>,
+    pub severity: Severity,
+}
+
+/// Vulnerabilities a scanner found in one image digest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageScan {
+    /// The reference the image was scanned as.
+    pub image: String,
+    pub digest: String,
+    /// Scanner that produced the report, e.g. `trivy`.
+    pub scanner: String,
+    pub scanned_at: std::time::SystemTime,
+    pub vulnerabilities: Vec<Vulnerability>,
+}
+
+impl ImageScan {
+    /// Vulnerabilities of exactly `severity`.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.vulnerabilities
+            .iter()
+            .filter(|v| v.severity == severity)
+            .count()
+    }
+
+    /// Vulnerabilities more severe than `severity`.
+    pub fn count_above(&self, severity: Severity) -> usize {
+        self.vulnerabilities
+            .iter()
+            .filter(|v| v.severity > severity)
+            .count()
+    }
+
+    pub fn highest_severity(&self) -> Option<Severity> {
+        self.vulnerabilities.iter().map(|v| v.severity).max()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContainerStatus {
     pub id: ContainerId,
     pub state: String, // e.g., "running", "stopped", "error" (OCI states)
     pub exit_code: Option<i32>,
     pub error_message: Option<String>,
+    /// Whether the kernel killed the process for exceeding its memory limit.
+    #[serde(default)]
+    pub oom_killed: bool,
+}
+
+impl ContainerStatus {
+    /// Whether the container's process has exited.
+    pub fn has_exited(&self) -> bool {
+        matches!(self.state.as_str(), "stopped" | "exited")
+    }
+
+    /// Why the container's process exited, once it has.
+    pub fn termination_reason(&self) -> Option<TerminationReason> {
+        self.has_exited()
+            .then(|| TerminationReason::classify(self.exit_code, self.oom_killed))
+    }
+}
+
+/// The containers making up one instance, grouped by lifecycle role.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceSpec {
+    /// Run one at a time, in order; each must exit successfully.
+    pub init_containers: Vec<ContainerConfig>,
+    /// Started before and stopped after the main containers.
+    pub sidecars: Vec<ContainerConfig>,
+    pub containers: Vec<ContainerConfig>,
+    pub init_timeout: Duration,
+}
+
+impl Default for InstanceSpec {
+    fn default() -> Self {
+        Self {
+            init_containers: Vec::new(),
+            sidecars: Vec::new(),
+            containers: Vec::new(),
+            init_timeout: DEFAULT_INIT_CONTAINER_TIMEOUT,
+        }
+    }
+}
+
+impl InstanceSpec {
+    pub fn from_workload(workload: &WorkloadDefinition) -> Self {
+        Self {
+            init_containers: workload.init_containers.clone(),
+            sidecars: workload.sidecars.clone(),
+            containers: workload.containers.clone(),
+            init_timeout: DEFAULT_INIT_CONTAINER_TIMEOUT,
+        }
+    }
+
+    pub fn with_init_timeout(mut self, timeout: Duration) -> Self {
+        self.init_timeout = timeout;
+        self
+    }
+
+    /// Apply `f` to every container regardless of role.
+    pub fn map_containers(mut self, mut f: impl FnMut(&mut ContainerConfig)) -> Self {
+        self.init_containers
+            .iter_mut()
+            .chain(self.sidecars.iter_mut())
+            .chain(self.containers.iter_mut())
+            .for_each(&mut f);
+        self
+    }
 }
 
 /// Options for retrieving container logs.
@@ -30,6 +279,224 @@ pub struct LogOptions {
     pub until: Option<String>,
 }
 
+/// What to look for when searching container logs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogSearchOptions {
+    /// Text each line must contain, or a regular expression when `regex` is set.
+    pub query: String,
+    #[serde(default)]
+    pub regex: bool,
+    /// Only search lines since this timestamp (RFC3339).
+    pub since: Option<String>,
+}
+
+/// A log line that matched a search.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogMatch {
+    /// RFC3339 timestamp the runtime recorded for the line.
+    pub timestamp: String,
+    /// "stdout" or "stderr".
+    pub stream: String,
+    pub message: String,
+}
+
+impl LogMatch {
+    /// Parse a log line in the "TIMESTAMP STREAM MESSAGE" format. Lines
+    /// without a timestamp yield `None`.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.splitn(3, ' ');
+        let timestamp = parts.next().filter(|ts| ts.contains('T'))?;
+        let (stream, message) = match (parts.next(), parts.next()) {
+            (Some(stream), Some(message)) => (stream, message),
+            (Some(message), None) => ("stdout", message),
+            _ => return None,
+        };
+        Some(Self {
+            timestamp: timestamp.to_string(),
+            stream: stream.to_string(),
+            message: message.to_string(),
+        })
+    }
+}
+
+/// A command to run inside a running container.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecOptions {
+    /// The program and its arguments.
+    pub command: Vec<String>,
+    /// Run on a pseudo-terminal, merging stderr into stdout.
+    pub tty: bool,
+}
+
+/// A throwaway container to troubleshoot a running one with, e.g. one built
+/// from a distroless image that has no shell.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DebugOptions {
+    /// Image with the tools, e.g. `busybox`.
+    pub image: String,
+    /// The program and its arguments; the image's shell when empty.
+    pub command: Vec<String>,
+    /// Run on a pseudo-terminal, merging stderr into stdout.
+    pub tty: bool,
+}
+
+/// Input sent to an exec session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecInput {
+    Stdin(Vec<u8>),
+    /// Resize the terminal of a `tty` session.
+    Resize { cols: u16, rows: u16 },
+    CloseStdin,
+}
+
+/// Output of an exec session. `Exit` comes last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecOutput {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+    /// The command exited, with its exit code if known.
+    Exit(Option<i32>),
+}
+
+/// A command running inside a container. Dropping `input` closes stdin;
+/// dropping the session stops the command.
+#[derive(Debug)]
+pub struct ExecSession {
+    pub input: tokio::sync::mpsc::Sender<ExecInput>,
+    pub output: tokio::sync::mpsc::Receiver<ExecOutput>,
+}
+
+/// Resource usage of a running container, cumulative since it started.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContainerUsage {
+    pub cpu_usage_ns: u64,
+    /// Memory in use minus reclaimable page cache.
+    pub memory_working_set_bytes: u64,
+    /// Processes killed for exceeding the memory limit.
+    pub oom_kills: u64,
+}
+
+/// New resource limits of a running container. Limits left `None` keep
+/// their current value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_cores: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_mb: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids_limit: Option<i64>,
+}
+
+impl ResourceLimits {
+    /// Whether no limit changes.
+    pub fn is_empty(&self) -> bool {
+        self.cpu_cores.is_none() && self.memory_mb.is_none() && self.pids_limit.is_none()
+    }
+}
+
+/// How many [`ContainerEvent`]s a runtime buffers per subscriber; one that
+/// falls further behind misses the oldest and should resync from statuses.
+pub const CONTAINER_EVENT_CAPACITY: usize = 256;
+
+/// A change in a container's lifecycle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContainerEventKind {
+    Created,
+    Started,
+    /// The kernel killed a process for exceeding the memory limit. The
+    /// container exits too when it was the main process.
+    OomKilled,
+    /// The container's process exited, with its exit code if known.
+    Exited { exit_code: Option<i32> },
+    Removed,
+}
+
+/// A lifecycle change of one container, as the runtime saw it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerEvent {
+    pub container_id: ContainerId,
+    pub kind: ContainerEventKind,
+    pub timestamp: std::time::SystemTime,
+}
+
+impl ContainerEvent {
+    /// An event that happened now.
+    pub fn now(container_id: impl Into<ContainerId>, kind: ContainerEventKind) -> Self {
+        Self {
+            container_id: container_id.into(),
+            kind,
+            timestamp: std::time::SystemTime::now(),
+        }
+    }
+}
+
+/// Options for checkpointing a container.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CheckpointOptions {
+    /// Keep the container running after the checkpoint. By default it stops,
+    /// so it does nothing the restored copy would not know about.
+    #[serde(default)]
+    pub leave_running: bool,
+}
+
+/// A container saved to disk: the memory and state of its processes, and
+/// what the runtime needs to recreate it with
+/// [`ContainerRuntime::restore_container`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The container that was checkpointed.
+    pub container_id: ContainerId,
+    /// Where the runtime keeps the checkpoint.
+    pub path: PathBuf,
+    pub created_at: std::time::SystemTime,
+}
+
+/// Compiled form of [`LogSearchOptions`].
+#[derive(Debug, Clone)]
+pub enum LogMatcher {
+    Substring(String),
+    Regex(regex::Regex),
+}
+
+impl LogMatcher {
+    /// Fails with a config error when the query is not a valid regex.
+    pub fn new(options: &LogSearchOptions) -> Result<Self> {
+        if !options.regex {
+            return Ok(Self::Substring(options.query.clone()));
+        }
+        regex::Regex::new(&options.query)
+            .map(Self::Regex)
+            .map_err(|e| {
+                OrchestrationError::ConfigError(format!("Invalid log search regex: {}", e))
+            })
+    }
+
+    pub fn is_match(&self, message: &str) -> bool {
+        match self {
+            Self::Substring(text) => message.contains(text.as_str()),
+            Self::Regex(regex) => regex.is_match(message),
+        }
+    }
+
+    /// Search log output in the "TIMESTAMP STREAM MESSAGE" line format.
+    /// Lines without a timestamp cannot be ordered and are skipped.
+    pub fn search(&self, content: &str, since: Option<&str>) -> Vec<LogMatch> {
+        content
+            .lines()
+            .filter_map(|line| self.match_line(line, since))
+            .collect()
+    }
+
+    /// One line of a [`search`](Self::search), for logs read a line at a time.
+    pub fn match_line(&self, line: &str, since: Option<&str>) -> Option<LogMatch> {
+        LogMatch::parse(line)
+            .filter(|m| since.is_none_or(|since| m.timestamp.as_str() >= since))
+            .filter(|m| self.is_match(&m.message))
+    }
+}
+
 /// Trait for interacting with a container runtime (e.g., Youki, runc)
 #[async_trait]
 pub trait ContainerRuntime: Send + Sync {
@@ -69,8 +536,256 @@ pub trait ContainerRuntime: Send + Sync {
         ))
     }
 
-    // Potentially methods for pulling images, managing networks, volumes, etc.
-    // async fn pull_image(&self, image_name: &str) -> Result<()>;
+    /// Searches a container's logs where they live, so only matching lines
+    /// leave the node. Covers containers whose logs the runtime archived on
+    /// removal. Matches come back in timestamp order.
+    async fn search_container_logs(
+        &self,
+        container_id: &ContainerId,
+        search: &LogSearchOptions,
+    ) -> Result<Vec<LogMatch>> {
+        let matcher = LogMatcher::new(search)?;
+        let options = LogOptions {
+            timestamps: true,
+            since: search.since.clone(),
+            ..Default::default()
+        };
+        let content = self.get_container_logs(container_id, &options).await?;
+        Ok(matcher.search(&content, search.since.as_deref()))
+    }
+
+    /// The file a running container's output is appended to, in the
+    /// "TIMESTAMP STREAM MESSAGE" line format, for log shippers to tail.
+    async fn container_log_path(&self, container_id: &ContainerId) -> Result<PathBuf> {
+        let _ = container_id;
+        Err(OrchestrationError::RuntimeError(
+            "Log files not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// Removed containers of a workload whose logs the runtime still keeps.
+    async fn archived_log_containers(&self, workload_id: WorkloadId) -> Result<Vec<ContainerId>> {
+        let _ = workload_id;
+        Ok(Vec::new())
+    }
+
+    /// Starts all containers of an instance in lifecycle order: init
+    /// containers one by one to completion, then sidecars, then the main
+    /// containers. Init containers are removed once they succeed.
+    ///
+    /// Returns the ids of the long-running containers, sidecars first, which
+    /// is the order [`stop_instance`](Self::stop_instance) expects. If any
+    /// step fails, everything already started is torn down.
+    async fn start_instance(
+        &self,
+        spec: &InstanceSpec,
+        options: &CreateContainerOptions,
+    ) -> Result<Vec<ContainerId>> {
+        if spec.containers.is_empty() {
+            return Err(OrchestrationError::ConfigError(
+                "Instance has no main containers".to_string(),
+            ));
+        }
+
+        for init in &spec.init_containers {
+            let id = self.create_container(init, options).await?;
+            let outcome = self.wait_for_exit(&id, spec.init_timeout).await;
+            let _ = self.stop_container(&id).await;
+            let _ = self.remove_container(&id).await;
+            match outcome? {
+                // Runtimes that cannot report exit codes count a clean stop as success
+                None | Some(0) => {}
+                Some(code) => {
+                    return Err(OrchestrationError::RuntimeError(format!(
+                        "Init container {} exited with code {}",
+                        init.name, code
+                    )))
+                }
+            }
+        }
+
+        let mut started = Vec::with_capacity(spec.sidecars.len() + spec.containers.len());
+        for config in spec.sidecars.iter().chain(&spec.containers) {
+            match self.create_container(config, options).await {
+                Ok(id) => started.push(id),
+                Err(e) => {
+                    let _ = self.stop_instance(&started).await;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(started)
+    }
+
+    /// Stops and removes an instance's containers in the reverse of their
+    /// start order, so main containers go before their sidecars. Keeps going
+    /// past failures and reports the first one.
+    async fn stop_instance(&self, container_ids: &[ContainerId]) -> Result<()> {
+        let mut first_error = None;
+        for id in container_ids.iter().rev() {
+            if let Err(e) = self.stop_container(id).await {
+                first_error.get_or_insert(e);
+            }
+            if let Err(e) = self.remove_container(id).await {
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Waits for a container to exit and returns its exit code, if known.
+    async fn wait_for_exit(
+        &self,
+        container_id: &ContainerId,
+        timeout: Duration,
+    ) -> Result<Option<i32>> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let status = self.get_container_status(container_id).await?;
+            if status.has_exited() {
+                return Ok(status.exit_code);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(OrchestrationError::RuntimeError(format!(
+                    "Container {} did not exit within {:?}",
+                    container_id, timeout
+                )));
+            }
+            tokio::time::sleep(INIT_CONTAINER_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Runs a command inside a running container, streaming its stdin,
+    /// stdout and stderr through the returned session.
+    async fn exec(&self, container_id: &ContainerId, options: &ExecOptions) -> Result<ExecSession> {
+        let _ = (container_id, options);
+        Err(OrchestrationError::RuntimeError(
+            "Exec not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// Starts a container from `options.image` that shares the PID, network,
+    /// IPC and UTS namespaces of a running container, and attaches to it like
+    /// [`exec`](Self::exec). Its mount namespace is its own, so its tools are
+    /// available; the target's filesystem is under `/proc/<pid>/root`. The
+    /// container is removed when the session ends.
+    async fn debug_container(
+        &self,
+        container_id: &ContainerId,
+        options: &DebugOptions,
+    ) -> Result<ExecSession> {
+        let _ = (container_id, options);
+        Err(OrchestrationError::RuntimeError(
+            "Debug containers not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// Current resource usage of a container.
+    async fn container_usage(&self, container_id: &ContainerId) -> Result<ContainerUsage> {
+        let _ = container_id;
+        Err(OrchestrationError::RuntimeError(
+            "Resource usage not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// Changes the resource limits of a running container in place, without
+    /// restarting it. Lowering the memory limit below what the container
+    /// uses makes the kernel reclaim memory, or OOM kill it.
+    async fn update_container_resources(
+        &self,
+        container_id: &ContainerId,
+        resources: &ResourceLimits,
+    ) -> Result<()> {
+        let _ = (container_id, resources);
+        Err(OrchestrationError::RuntimeError(
+            "Resource updates not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// The image pull queue of a node, for runtimes that pull images.
+    async fn image_pull_status(&self, node_id: NodeId) -> Option<ImagePullStatus> {
+        let _ = node_id;
+        None
+    }
+
+    /// Pulls `image` into the image cache of `node_id` without creating a
+    /// container, e.g. ahead of a rollout. Returns once it is cached.
+    async fn pull_image(&self, node_id: NodeId, image: &str, priority: PullPriority) -> Result<()> {
+        let _ = (node_id, image, priority);
+        Err(OrchestrationError::RuntimeError(
+            "Image pulls not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// Resolves `image` in its registry without pulling its layers, e.g. to
+    /// check what a tag points at before deploying it.
+    async fn inspect_image(&self, image: &str) -> Result<ImageInspection> {
+        let _ = image;
+        Err(OrchestrationError::RuntimeError(
+            "Image inspection not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// Scans `image` for known vulnerabilities, pulling it into the image
+    /// cache first if needed. A digest that was already scanned returns the
+    /// recorded report.
+    async fn scan_image(&self, image: &str) -> Result<ImageScan> {
+        let _ = image;
+        Err(OrchestrationError::RuntimeError(
+            "Image scanning not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// Every recorded image scan.
+    async fn image_scans(&self) -> Result<Vec<ImageScan>> {
+        Err(OrchestrationError::RuntimeError(
+            "Image scanning not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// Saves a running container to disk, e.g. to move it to another node
+    /// without losing its progress. The container stops unless
+    /// `options.leave_running`.
+    async fn checkpoint_container(
+        &self,
+        container_id: &ContainerId,
+        options: &CheckpointOptions,
+    ) -> Result<Checkpoint> {
+        let _ = (container_id, options);
+        Err(OrchestrationError::RuntimeError(
+            "Checkpoints not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// Creates a container from a checkpoint on `options.node_id`, its
+    /// processes resuming where they were. Returns the new container's ID.
+    async fn restore_container(
+        &self,
+        checkpoint: &Checkpoint,
+        options: &CreateContainerOptions,
+    ) -> Result<ContainerId> {
+        let _ = (checkpoint, options);
+        Err(OrchestrationError::RuntimeError(
+            "Checkpoints not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// Deletes a checkpoint that is no longer needed.
+    async fn remove_checkpoint(&self, checkpoint: &Checkpoint) -> Result<()> {
+        let _ = checkpoint;
+        Err(OrchestrationError::RuntimeError(
+            "Checkpoints not supported by this runtime".to_string(),
+        ))
+    }
+
+    /// Subscribes to the lifecycle events of this runtime's containers, for
+    /// runtimes that publish them. `None` means callers have to poll
+    /// [`get_container_status`](Self::get_container_status) to see changes.
+    fn events(&self) -> Option<tokio::sync::broadcast::Receiver<ContainerEvent>> {
+        None
+    }
+
+    // Potentially methods for managing networks, volumes, etc.
 }
 
 // Example of a specific error for this interface
@@ -949,6 +949,10 @@ mod tests {
         async fn list_all_instances(&self) -> orchestrator_shared_types::Result<Vec<orchestrator_shared_types::WorkloadInstance>> { Ok(vec![]) }
         async fn delete_instance(&self, _: &str) -> orchestrator_shared_types::Result<()> { Ok(()) }
         async fn delete_instances_for_workload(&self, _: &orchestrator_shared_types::WorkloadId) -> orchestrator_shared_types::Result<()> { Ok(()) }
+        async fn put_namespace(&self, _: orchestrator_shared_types::Namespace) -> orchestrator_shared_types::Result<()> { Ok(()) }
+        async fn get_namespace(&self, _: &str) -> orchestrator_shared_types::Result<Option<orchestrator_shared_types::Namespace>> { Ok(None) }
+        async fn list_namespaces(&self) -> orchestrator_shared_types::Result<Vec<orchestrator_shared_types::Namespace>> { Ok(vec![]) }
+        async fn delete_namespace(&self, _: &str) -> orchestrator_shared_types::Result<()> { Ok(()) }
     }
 
     #[derive(Clone)]
@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use orchestrator_shared_types::{
-    ContainerConfig, NodeResources, PortMapping, WorkloadDefinition, WorkloadId,
+    ContainerConfig, NodeResources, PortMapping, WorkloadDefinition, WorkloadId, DEFAULT_NAMESPACE,
 };
 
 /// Input for creating a new workload
@@ -219,6 +219,7 @@ impl CreateWorkloadInput {
                     container_port: p.container_port,
                     host_port: p.host_port,
                     protocol: p.protocol.clone(),
+                    host_ip: None,
                 })
                 .collect(),
             resource_requests: NodeResources {
@@ -226,6 +227,18 @@ impl CreateWorkloadInput {
                 memory_mb: self.memory_mb,
                 disk_mb: 0,
             },
+            volume_mounts: vec![],
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+            readiness_probe: None,
+            limits: Default::default(),
+            sysctls: Default::default(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
         };
 
         WorkloadDefinition {
@@ -234,6 +247,12 @@ impl CreateWorkloadInput {
             containers: vec![container],
             replicas: self.replicas,
             labels: HashMap::new(),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: DEFAULT_NAMESPACE.to_string(),
+            resource_version: 0,
         }
     }
 }
@@ -17,11 +17,11 @@ mod mock {
     use async_trait::async_trait;
     use cluster_manager_interface::{ClusterEvent, ClusterManager};
     use orchestrator_shared_types::{
-        Node, NodeId, NodeResources, NodeStatus, Result, WorkloadDefinition,
+        Namespace, Node, NodeId, NodeResources, NodeStatus, Result, WorkloadDefinition,
         WorkloadInstance, WorkloadId, Keypair,
     };
     use scheduler_interface::{ScheduleDecision, ScheduleRequest, Scheduler};
-    use state_store_interface::StateStore;
+    use state_store_interface::{StateStore, WatchEvent};
     use tokio::sync::{watch, mpsc};
     use uuid::Uuid;
     use std::collections::HashMap;
@@ -161,12 +161,28 @@ mod mock {
             Ok(())
         }
 
-        async fn watch_nodes(&self) -> Result<mpsc::Receiver<Node>> {
+        async fn put_namespace(&self, _namespace: Namespace) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_namespace(&self, _name: &str) -> Result<Option<Namespace>> {
+            Ok(None)
+        }
+
+        async fn list_namespaces(&self) -> Result<Vec<Namespace>> {
+            Ok(vec![])
+        }
+
+        async fn delete_namespace(&self, _name: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn watch_nodes(&self) -> Result<mpsc::Receiver<WatchEvent<Node>>> {
             let (_tx, rx) = mpsc::channel(1);
             Ok(rx)
         }
 
-        async fn watch_workloads(&self) -> Result<mpsc::Receiver<WorkloadDefinition>> {
+        async fn watch_workloads(&self) -> Result<mpsc::Receiver<WatchEvent<WorkloadDefinition>>> {
             let (_tx, rx) = mpsc::channel(1);
             Ok(rx)
         }
@@ -204,6 +220,8 @@ mod mock {
                 memory_mb: 7168,
                 disk_mb: 92160,
             },
+            unschedulable: false,
+            resource_version: 0,
         }
     }
 
@@ -215,6 +233,12 @@ mod mock {
             containers: vec![],
             replicas: 1,
             labels: HashMap::new(),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
         }
     }
 }
@@ -0,0 +1,564 @@
+//! Alerting on metric thresholds.
+//!
+//! An [`AlertRule`] compares the series of one metric against a threshold,
+//! e.g. `orchestrator_instances_by_status{status="Failed"} > 0 for 5m`. The
+//! [`AlertEngine`] evaluates its rules against the metrics registry on an
+//! interval. Each series meeting a rule's condition is a pending alert, which
+//! fires once the condition has held for the rule's `for` duration and
+//! resolves as soon as it no longer holds. Firing and resolved alerts are
+//! sent to every [`AlertNotifier`]; the current ones are served at `/alerts`.
+//!
+//! Rules read the series the metrics backend keeps, so they see nothing when
+//! metrics go to statsd.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::task::JoinHandle;
+
+use crate::metrics::{parse_sample_line, MetricSample};
+use crate::server::ObservabilityState;
+
+/// Events API v2 endpoint PagerDuty notifications go to.
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+/// Errors in alerting configuration.
+#[derive(Debug, thiserror::Error)]
+pub enum AlertError {
+    #[error("Invalid alert rule: {0}")]
+    InvalidRule(String),
+    #[error("Invalid alert notifier: {0}")]
+    InvalidNotifier(String),
+}
+
+/// How a series is compared to a rule's threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    /// Operators longest first, so `>=` is not read as `>`.
+    const OPERATORS: [(&'static str, Comparison); 6] = [
+        (">=", Self::GreaterOrEqual),
+        ("<=", Self::LessOrEqual),
+        ("==", Self::Equal),
+        ("!=", Self::NotEqual),
+        (">", Self::Greater),
+        ("<", Self::Less),
+    ];
+
+    pub fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Self::Greater => value > threshold,
+            Self::GreaterOrEqual => value >= threshold,
+            Self::Less => value < threshold,
+            Self::LessOrEqual => value <= threshold,
+            Self::Equal => value == threshold,
+            Self::NotEqual => value != threshold,
+        }
+    }
+}
+
+/// A threshold condition on the series of one metric.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    pub name: String,
+    /// The expression the rule was parsed from.
+    pub expr: String,
+    pub metric: String,
+    /// Labels a series must carry to be compared.
+    pub matchers: BTreeMap<String, String>,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    /// How long the condition must hold before the alert fires.
+    pub for_duration: Duration,
+    /// "critical", "error", "warning" or "info" (default: "warning").
+    pub severity: String,
+    pub summary: Option<String>,
+}
+
+/// A rule as written in a rules file.
+#[derive(Debug, Deserialize)]
+struct RuleSpec {
+    name: String,
+    expr: String,
+    severity: Option<String>,
+    summary: Option<String>,
+}
+
+impl AlertRule {
+    /// Parse a rule from an expression of the form
+    /// `metric{label="value",...} <op> <threshold> [for <duration>]`, where
+    /// `<op>` is one of `>`, `>=`, `<`, `<=`, `==`, `!=` and durations are
+    /// written like `30s`, `5m` or `1h`.
+    pub fn parse(name: impl Into<String>, expr: &str) -> Result<Self, AlertError> {
+        let invalid = |reason: &str| AlertError::InvalidRule(format!("'{}': {}", expr, reason));
+        let (condition, for_duration) = match expr.rsplit_once(" for ") {
+            Some((condition, duration)) => (
+                condition,
+                parse_duration(duration.trim()).ok_or_else(|| invalid("invalid duration"))?,
+            ),
+            None => (expr, Duration::ZERO),
+        };
+
+        // The operator comes after the selector, whose label values may hold one
+        let selector_end = condition.rfind('}').map_or(0, |i| i + 1);
+        let (position, operator, comparison) = Comparison::OPERATORS
+            .iter()
+            .filter_map(|(operator, comparison)| {
+                let position = condition[selector_end..].find(operator)? + selector_end;
+                Some((position, *operator, *comparison))
+            })
+            .min_by_key(|(position, operator, _)| (*position, usize::MAX - operator.len()))
+            .ok_or_else(|| invalid("expected a comparison"))?;
+
+        let selector = parse_sample_line(&format!("{} 0", condition[..position].trim()))
+            .filter(|sample| !sample.name.is_empty())
+            .ok_or_else(|| invalid("invalid metric selector"))?;
+        let threshold = condition[position + operator.len()..]
+            .trim()
+            .parse()
+            .map_err(|_| invalid("invalid threshold"))?;
+
+        Ok(Self {
+            name: name.into(),
+            expr: expr.trim().to_string(),
+            metric: selector.name,
+            matchers: selector.labels,
+            comparison,
+            threshold,
+            for_duration,
+            severity: "warning".to_string(),
+            summary: None,
+        })
+    }
+
+    /// Parse a JSON list of rules, each with a `name` and an `expr` and
+    /// optionally a `severity` and a `summary`.
+    pub fn parse_list(json: &str) -> Result<Vec<Self>, AlertError> {
+        let specs: Vec<RuleSpec> =
+            serde_json::from_str(json).map_err(|e| AlertError::InvalidRule(e.to_string()))?;
+        specs
+            .into_iter()
+            .map(|spec| {
+                let mut rule = Self::parse(spec.name, &spec.expr)?;
+                if let Some(severity) = spec.severity {
+                    rule = rule.with_severity(severity);
+                }
+                if let Some(summary) = spec.summary {
+                    rule = rule.with_summary(summary);
+                }
+                Ok(rule)
+            })
+            .collect()
+    }
+
+    pub fn with_severity(mut self, severity: impl Into<String>) -> Self {
+        self.severity = severity.into();
+        self
+    }
+
+    pub fn with_summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    fn selects(&self, sample: &MetricSample) -> bool {
+        sample.name == self.metric
+            && self
+                .matchers
+                .iter()
+                .all(|(key, value)| sample.labels.get(key) == Some(value))
+    }
+}
+
+/// Parse a duration such as `90s`, `5m`, `1h` or `1d`.
+fn parse_duration(s: &str) -> Option<Duration> {
+    let unit_start = s.find(|c: char| !c.is_ascii_digit())?;
+    let count: u64 = s[..unit_start].parse().ok()?;
+    let unit = match &s[unit_start..] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(count * unit))
+}
+
+/// Whether an alert is still waiting out its rule's `for` duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    Pending,
+    Firing,
+}
+
+/// A series meeting the condition of a rule.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    pub rule: String,
+    pub expr: String,
+    pub severity: String,
+    pub summary: Option<String>,
+    /// Labels of the series.
+    pub labels: BTreeMap<String, String>,
+    /// The series' value at the last evaluation.
+    pub value: f64,
+    pub state: AlertState,
+    /// When the condition started to hold.
+    pub active_since: DateTime<Utc>,
+    pub fired_at: Option<DateTime<Utc>>,
+}
+
+impl Alert {
+    /// Identifies the alert across evaluations and to PagerDuty.
+    fn key(&self) -> String {
+        let labels: Vec<String> = self
+            .labels
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        format!("{}{{{}}}", self.rule, labels.join(","))
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}): {} = {}",
+            self.key(),
+            self.severity,
+            self.summary.as_deref().unwrap_or(&self.expr),
+            self.value
+        )
+    }
+}
+
+/// An alert that started firing, or stopped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertNotification {
+    pub alert: Alert,
+    pub resolved: bool,
+}
+
+/// Where notifications of firing and resolved alerts are sent.
+///
+/// Parses from `webhook+https://host/path`, `slack+https://hooks.slack.com/...`
+/// or `pagerduty:<routing key>`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertNotifier {
+    /// POSTed `{"status": "firing"|"resolved", "alert": {...}}`.
+    Webhook(String),
+    /// A Slack incoming webhook, sent a message.
+    Slack(String),
+    /// PagerDuty Events API v2 routing key; incidents are triggered and
+    /// resolved with the alert.
+    PagerDuty(String),
+}
+
+impl FromStr for AlertNotifier {
+    type Err = AlertError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(url) = s.strip_prefix("webhook+") {
+            return Ok(Self::Webhook(url.to_string()));
+        }
+        if let Some(url) = s.strip_prefix("slack+") {
+            return Ok(Self::Slack(url.to_string()));
+        }
+        match s.strip_prefix("pagerduty:") {
+            Some(key) if !key.is_empty() => Ok(Self::PagerDuty(key.to_string())),
+            _ => Err(AlertError::InvalidNotifier(format!(
+                "'{}', expected webhook+URL, slack+URL or pagerduty:<routing key>",
+                s
+            ))),
+        }
+    }
+}
+
+impl AlertNotifier {
+    async fn send(
+        &self,
+        client: &reqwest::Client,
+        notification: &AlertNotification,
+    ) -> reqwest::Result<()> {
+        let alert = &notification.alert;
+        let request = match self {
+            Self::Webhook(url) => client.post(url).json(&json!({
+                "status": if notification.resolved { "resolved" } else { "firing" },
+                "alert": alert,
+            })),
+            Self::Slack(url) => {
+                let status = if notification.resolved {
+                    "RESOLVED"
+                } else {
+                    "FIRING"
+                };
+                client
+                    .post(url)
+                    .json(&json!({ "text": format!("[{}] {}", status, alert) }))
+            }
+            Self::PagerDuty(routing_key) => {
+                let severity = match alert.severity.as_str() {
+                    severity @ ("critical" | "error" | "warning" | "info") => severity,
+                    _ => "warning",
+                };
+                client.post(PAGERDUTY_EVENTS_URL).json(&json!({
+                    "routing_key": routing_key,
+                    "event_action": if notification.resolved { "resolve" } else { "trigger" },
+                    "dedup_key": alert.key(),
+                    "payload": {
+                        "summary": alert.to_string(),
+                        "source": "orchestrator",
+                        "severity": severity,
+                        "custom_details": alert,
+                    },
+                }))
+            }
+        };
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Evaluates alert rules and notifies about the alerts they raise.
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    notifiers: Vec<AlertNotifier>,
+    /// Pending and firing alerts by key.
+    active: Mutex<BTreeMap<String, Alert>>,
+    client: reqwest::Client,
+}
+
+impl AlertEngine {
+    /// How often [`spawn`](Self::spawn) evaluates when run with defaults.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(15);
+
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            notifiers: Vec::new(),
+            active: Mutex::new(BTreeMap::new()),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send firing and resolved alerts to `notifier`.
+    pub fn with_notifier(mut self, notifier: AlertNotifier) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Pending and firing alerts.
+    pub fn alerts(&self) -> Vec<Alert> {
+        self.active.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Evaluate every rule against `samples` at `now`. Returns the alerts
+    /// that started firing and those that resolved.
+    pub fn evaluate(&self, samples: &[MetricSample], now: DateTime<Utc>) -> Vec<AlertNotification> {
+        let mut active = self.active.lock().unwrap();
+        let mut holding = BTreeMap::new();
+        let mut notifications = Vec::new();
+
+        for rule in &self.rules {
+            let for_duration = chrono::Duration::from_std(rule.for_duration).unwrap_or_default();
+            for sample in samples.iter().filter(|sample| rule.selects(sample)) {
+                if !rule.comparison.holds(sample.value, rule.threshold) {
+                    continue;
+                }
+                let mut alert = Alert {
+                    rule: rule.name.clone(),
+                    expr: rule.expr.clone(),
+                    severity: rule.severity.clone(),
+                    summary: rule.summary.clone(),
+                    labels: sample.labels.clone(),
+                    value: sample.value,
+                    state: AlertState::Pending,
+                    active_since: now,
+                    fired_at: None,
+                };
+                let key = alert.key();
+                if let Some(previous) = active.get(&key) {
+                    alert.state = previous.state;
+                    alert.active_since = previous.active_since;
+                    alert.fired_at = previous.fired_at;
+                }
+                if alert.state == AlertState::Pending && now - alert.active_since >= for_duration {
+                    alert.state = AlertState::Firing;
+                    alert.fired_at = Some(now);
+                    notifications.push(AlertNotification {
+                        alert: alert.clone(),
+                        resolved: false,
+                    });
+                }
+                holding.insert(key, alert);
+            }
+        }
+
+        for (key, alert) in std::mem::replace(&mut *active, holding.clone()) {
+            if alert.state == AlertState::Firing && !holding.contains_key(&key) {
+                notifications.push(AlertNotification {
+                    alert,
+                    resolved: true,
+                });
+            }
+        }
+        notifications
+    }
+
+    /// Send `notification` to every notifier.
+    pub async fn notify(&self, notification: &AlertNotification) {
+        for notifier in &self.notifiers {
+            if let Err(e) = notifier.send(&self.client, notification).await {
+                tracing::warn!(
+                    "Failed to send alert {} to {:?}: {}",
+                    notification.alert.key(),
+                    notifier,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Evaluate against the metrics registry of `state` every `interval`
+    /// until the task is aborted.
+    pub fn spawn(self: Arc<Self>, state: ObservabilityState, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                let samples = state.metric_samples().await;
+                for notification in self.evaluate(&samples, Utc::now()) {
+                    if notification.resolved {
+                        tracing::info!("Alert resolved: {}", notification.alert);
+                    } else {
+                        tracing::warn!("Alert firing: {}", notification.alert);
+                    }
+                    self.notify(&notification).await;
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(status: &str, value: f64) -> MetricSample {
+        MetricSample {
+            name: "orchestrator_instances_by_status".to_string(),
+            labels: BTreeMap::from([("status".to_string(), status.to_string())]),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let rule = AlertRule::parse(
+            "failed",
+            r#"orchestrator_instances_by_status{status="Failed"} > 0 for 5m"#,
+        )
+        .unwrap();
+        assert_eq!(rule.metric, "orchestrator_instances_by_status");
+        assert_eq!(rule.matchers["status"], "Failed");
+        assert_eq!(rule.comparison, Comparison::Greater);
+        assert_eq!(rule.threshold, 0.0);
+        assert_eq!(rule.for_duration, Duration::from_secs(300));
+
+        let rule = AlertRule::parse("nodes", "orchestrator_nodes_ready <= 2").unwrap();
+        assert_eq!(rule.comparison, Comparison::LessOrEqual);
+        assert!(rule.matchers.is_empty());
+        assert_eq!(rule.for_duration, Duration::ZERO);
+
+        // Operators inside label values belong to the selector
+        let rule = AlertRule::parse("odd", r#"m{op=">="} != 1"#).unwrap();
+        assert_eq!(rule.matchers["op"], ">=");
+        assert_eq!(rule.comparison, Comparison::NotEqual);
+
+        for invalid in ["m", "m > x", "> 1", "m > 1 for 5 minutes"] {
+            assert!(AlertRule::parse("bad", invalid).is_err(), "{}", invalid);
+        }
+
+        let rules = AlertRule::parse_list(
+            r#"[{"name": "down", "expr": "orchestrator_cluster_health < 1",
+                 "severity": "critical", "summary": "Cluster unhealthy"}]"#,
+        )
+        .unwrap();
+        assert_eq!(rules[0].severity, "critical");
+        assert_eq!(rules[0].summary.as_deref(), Some("Cluster unhealthy"));
+    }
+
+    #[test]
+    fn test_parse_notifier() {
+        assert_eq!(
+            "slack+https://hooks.slack.com/services/T/B/X"
+                .parse::<AlertNotifier>()
+                .unwrap(),
+            AlertNotifier::Slack("https://hooks.slack.com/services/T/B/X".to_string())
+        );
+        assert_eq!(
+            "pagerduty:abc123".parse::<AlertNotifier>().unwrap(),
+            AlertNotifier::PagerDuty("abc123".to_string())
+        );
+        assert!("pagerduty:".parse::<AlertNotifier>().is_err());
+        assert!("https://example.com".parse::<AlertNotifier>().is_err());
+    }
+
+    #[test]
+    fn test_alert_fires_after_for_duration_and_resolves() {
+        let rule = AlertRule::parse(
+            "failed",
+            r#"orchestrator_instances_by_status{status="Failed"} > 0 for 5m"#,
+        )
+        .unwrap();
+        let engine = AlertEngine::new(vec![rule]);
+        let start = Utc::now();
+        let minutes = |m| start + chrono::Duration::minutes(m);
+
+        let samples = [sample("Failed", 2.0), sample("Running", 5.0)];
+        assert!(engine.evaluate(&samples, start).is_empty());
+        let alerts = engine.alerts();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].state, AlertState::Pending);
+        assert_eq!(alerts[0].labels["status"], "Failed");
+
+        assert!(engine.evaluate(&samples, minutes(4)).is_empty());
+        let fired = engine.evaluate(&samples, minutes(5));
+        assert_eq!(fired.len(), 1);
+        assert!(!fired[0].resolved);
+        assert_eq!(fired[0].alert.state, AlertState::Firing);
+        assert_eq!(fired[0].alert.active_since, start);
+        // Firing alerts are only announced once
+        assert!(engine.evaluate(&samples, minutes(6)).is_empty());
+
+        let resolved = engine.evaluate(&[sample("Failed", 0.0)], minutes(7));
+        assert_eq!(resolved.len(), 1);
+        assert!(resolved[0].resolved);
+        assert!(engine.alerts().is_empty());
+
+        // A pending alert whose condition clears goes away silently
+        engine.evaluate(&samples, minutes(8));
+        assert!(engine.evaluate(&[], minutes(9)).is_empty());
+        assert!(engine.alerts().is_empty());
+    }
+}
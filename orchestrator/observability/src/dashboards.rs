@@ -0,0 +1,754 @@
+//! Grafana dashboards for the orchestrator's metrics.
+//!
+//! [`dashboards`] describes a cluster overview, the control plane and the
+//! containers, querying the metrics [`OrchestratorMetrics`] records under
+//! their exact names and labels. [`Dashboard::to_json`] renders one as JSON
+//! for Grafana's dashboard import; it picks its Prometheus data source through
+//! a `datasource` variable.
+//!
+//! Histograms are exported as summaries, so latency panels chart their
+//! `quantile` series.
+//!
+//! [`OrchestratorMetrics`]: crate::metrics::OrchestratorMetrics
+
+use serde_json::{json, Value};
+
+/// Width of a Grafana dashboard in grid units.
+const GRID_WIDTH: u32 = 24;
+const PANEL_WIDTH: u32 = 12;
+const PANEL_HEIGHT: u32 = 8;
+
+/// How a query aggregates its metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    /// Sum of the current values.
+    Sum,
+    /// The largest current value.
+    Max,
+    /// Per-second rate of a counter, summed.
+    Rate,
+    /// Increase of a counter over the dashboard interval, summed.
+    Increase,
+    /// A quantile of a summary, the largest across series.
+    Quantile(&'static str),
+}
+
+/// One series of a panel: a metric, aggregated and grouped by labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub metric: &'static str,
+    pub aggregation: Aggregation,
+    /// Labels the series are grouped by.
+    pub by: &'static [&'static str],
+    /// Series legend, in Grafana's `{{label}}` syntax.
+    pub legend: &'static str,
+}
+
+impl Query {
+    pub fn sum(metric: &'static str, by: &'static [&'static str], legend: &'static str) -> Self {
+        Self {
+            metric,
+            aggregation: Aggregation::Sum,
+            by,
+            legend,
+        }
+    }
+
+    pub fn max(metric: &'static str, by: &'static [&'static str], legend: &'static str) -> Self {
+        Self {
+            aggregation: Aggregation::Max,
+            ..Self::sum(metric, by, legend)
+        }
+    }
+
+    pub fn rate(metric: &'static str, by: &'static [&'static str], legend: &'static str) -> Self {
+        Self {
+            aggregation: Aggregation::Rate,
+            ..Self::sum(metric, by, legend)
+        }
+    }
+
+    pub fn increase(
+        metric: &'static str,
+        by: &'static [&'static str],
+        legend: &'static str,
+    ) -> Self {
+        Self {
+            aggregation: Aggregation::Increase,
+            ..Self::sum(metric, by, legend)
+        }
+    }
+
+    pub fn quantile(
+        metric: &'static str,
+        quantile: &'static str,
+        by: &'static [&'static str],
+        legend: &'static str,
+    ) -> Self {
+        Self {
+            aggregation: Aggregation::Quantile(quantile),
+            ..Self::sum(metric, by, legend)
+        }
+    }
+
+    /// The PromQL expression of this query.
+    pub fn expr(&self) -> String {
+        let by = if self.by.is_empty() {
+            String::new()
+        } else {
+            format!(" by ({})", self.by.join(", "))
+        };
+        match self.aggregation {
+            Aggregation::Sum => format!("sum{} ({})", by, self.metric),
+            Aggregation::Max => format!("max{} ({})", by, self.metric),
+            Aggregation::Rate => format!("sum{} (rate({}[$__rate_interval]))", by, self.metric),
+            Aggregation::Increase => format!("sum{} (increase({}[$__range]))", by, self.metric),
+            Aggregation::Quantile(q) => {
+                format!("max{} ({}{{quantile=\"{}\"}})", by, self.metric, q)
+            }
+        }
+    }
+
+    /// Labels the expression refers to.
+    pub fn labels(&self) -> Vec<&'static str> {
+        let mut labels = self.by.to_vec();
+        if let Aggregation::Quantile(_) = self.aggregation {
+            labels.push("quantile");
+        }
+        labels
+    }
+}
+
+/// How a panel shows its queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelKind {
+    TimeSeries,
+    /// The latest value, as a single number.
+    Stat,
+}
+
+/// A chart of one or more queries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Panel {
+    pub title: &'static str,
+    pub kind: PanelKind,
+    /// Grafana unit, e.g. "s", "bytes" or "ops".
+    pub unit: &'static str,
+    pub queries: Vec<Query>,
+}
+
+impl Panel {
+    pub fn time_series(title: &'static str, unit: &'static str, queries: Vec<Query>) -> Self {
+        Self {
+            title,
+            kind: PanelKind::TimeSeries,
+            unit,
+            queries,
+        }
+    }
+
+    pub fn stat(title: &'static str, unit: &'static str, queries: Vec<Query>) -> Self {
+        Self {
+            kind: PanelKind::Stat,
+            ..Self::time_series(title, unit, queries)
+        }
+    }
+}
+
+/// A Grafana dashboard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dashboard {
+    pub uid: &'static str,
+    pub title: &'static str,
+    pub panels: Vec<Panel>,
+}
+
+impl Dashboard {
+    /// Dashboard JSON as accepted by Grafana's import. Panels are laid out two
+    /// per row, stats four per row.
+    pub fn to_json(&self) -> Value {
+        let datasource = json!({"type": "prometheus", "uid": "${datasource}"});
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        let panels: Vec<Value> = self
+            .panels
+            .iter()
+            .enumerate()
+            .map(|(id, panel)| {
+                let (width, height) = match panel.kind {
+                    PanelKind::TimeSeries => (PANEL_WIDTH, PANEL_HEIGHT),
+                    PanelKind::Stat => (PANEL_WIDTH / 2, PANEL_HEIGHT / 2),
+                };
+                if x + width > GRID_WIDTH {
+                    (x, y) = (0, y + row_height);
+                    row_height = 0;
+                }
+                let grid_pos = json!({"x": x, "y": y, "w": width, "h": height});
+                x += width;
+                row_height = row_height.max(height);
+
+                let targets: Vec<Value> = panel
+                    .queries
+                    .iter()
+                    .zip('A'..)
+                    .map(|(query, ref_id)| {
+                        json!({
+                            "datasource": datasource,
+                            "expr": query.expr(),
+                            "legendFormat": query.legend,
+                            "refId": ref_id.to_string(),
+                        })
+                    })
+                    .collect();
+                json!({
+                    "id": id + 1,
+                    "type": match panel.kind {
+                        PanelKind::TimeSeries => "timeseries",
+                        PanelKind::Stat => "stat",
+                    },
+                    "title": panel.title,
+                    "datasource": datasource,
+                    "gridPos": grid_pos,
+                    "fieldConfig": {"defaults": {"unit": panel.unit}, "overrides": []},
+                    "targets": targets,
+                })
+            })
+            .collect();
+
+        json!({
+            "uid": self.uid,
+            "title": self.title,
+            "tags": ["orchestrator"],
+            "timezone": "browser",
+            "schemaVersion": 39,
+            "refresh": "30s",
+            "time": {"from": "now-6h", "to": "now"},
+            "templating": {
+                "list": [{
+                    "name": "datasource",
+                    "label": "Data source",
+                    "type": "datasource",
+                    "query": "prometheus",
+                }],
+            },
+            "panels": panels,
+        })
+    }
+}
+
+/// Every dashboard for the orchestrator's metrics.
+pub fn dashboards() -> Vec<Dashboard> {
+    vec![cluster_overview(), control_plane(), containers()]
+}
+
+fn cluster_overview() -> Dashboard {
+    Dashboard {
+        uid: "orchestrator-cluster",
+        title: "Orchestrator / Cluster",
+        panels: vec![
+            Panel::stat(
+                "Nodes",
+                "none",
+                vec![Query::sum("orchestrator_nodes_total", &[], "nodes")],
+            ),
+            Panel::stat(
+                "Ready nodes",
+                "none",
+                vec![Query::sum("orchestrator_nodes_ready", &[], "ready")],
+            ),
+            Panel::stat(
+                "Workloads",
+                "none",
+                vec![Query::sum("orchestrator_workloads_total", &[], "workloads")],
+            ),
+            Panel::stat(
+                "Cluster healthy",
+                "bool_yes_no",
+                vec![Query::sum("orchestrator_cluster_health", &[], "healthy")],
+            ),
+            Panel::time_series(
+                "Instances by status",
+                "none",
+                vec![
+                    Query::sum("orchestrator_instances_total", &[], "total"),
+                    Query::sum(
+                        "orchestrator_instances_by_status",
+                        &["status"],
+                        "{{status}}",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "Replicas",
+                "none",
+                vec![
+                    Query::sum(
+                        "orchestrator_workload_replicas_desired",
+                        &["workload_id"],
+                        "{{workload_id}} desired",
+                    ),
+                    Query::sum(
+                        "orchestrator_workload_replicas_running",
+                        &["workload_id"],
+                        "{{workload_id}} running",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "Node CPU",
+                "none",
+                vec![
+                    Query::sum(
+                        "orchestrator_node_cpu_capacity",
+                        &["node_id"],
+                        "{{node_id}} capacity",
+                    ),
+                    Query::sum(
+                        "orchestrator_node_cpu_allocatable",
+                        &["node_id"],
+                        "{{node_id}} allocatable",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "Node memory",
+                "bytes",
+                vec![
+                    Query::sum(
+                        "orchestrator_node_memory_capacity_bytes",
+                        &["node_id"],
+                        "{{node_id}} capacity",
+                    ),
+                    Query::sum(
+                        "orchestrator_node_memory_allocatable_bytes",
+                        &["node_id"],
+                        "{{node_id}} allocatable",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "Cluster events",
+                "ops",
+                vec![Query::rate(
+                    "orchestrator_cluster_events_total",
+                    &["event_type"],
+                    "{{event_type}}",
+                )],
+            ),
+        ],
+    }
+}
+
+fn control_plane() -> Dashboard {
+    Dashboard {
+        uid: "orchestrator-control-plane",
+        title: "Orchestrator / Control plane",
+        panels: vec![
+            Panel::time_series(
+                "Scheduling",
+                "ops",
+                vec![
+                    Query::rate("orchestrator_scheduling_attempts_total", &[], "attempts"),
+                    Query::rate("orchestrator_scheduling_successes_total", &[], "successes"),
+                    Query::rate(
+                        "orchestrator_scheduling_failures_total",
+                        &["reason"],
+                        "failed: {{reason}}",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "Scheduling latency",
+                "s",
+                vec![
+                    Query::quantile(
+                        "orchestrator_scheduling_duration_seconds",
+                        "0.5",
+                        &[],
+                        "p50",
+                    ),
+                    Query::quantile(
+                        "orchestrator_scheduling_duration_seconds",
+                        "0.99",
+                        &[],
+                        "p99",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "Time to schedule",
+                "s",
+                vec![
+                    Query::quantile(
+                        "orchestrator_scheduling_time_to_schedule_seconds",
+                        "0.5",
+                        &[],
+                        "p50",
+                    ),
+                    Query::quantile(
+                        "orchestrator_scheduling_time_to_schedule_seconds",
+                        "0.99",
+                        &[],
+                        "p99",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "Placement attempts",
+                "none",
+                vec![
+                    Query::quantile(
+                        "orchestrator_scheduling_placement_attempts",
+                        "0.99",
+                        &[],
+                        "p99 attempts",
+                    ),
+                    Query::rate(
+                        "orchestrator_scheduling_preemptions_total",
+                        &["reason"],
+                        "preempted: {{reason}}",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "Reconciliation",
+                "none",
+                vec![
+                    Query::rate("orchestrator_reconciliation_runs_total", &[], "runs/s"),
+                    Query::sum(
+                        "orchestrator_reconciliation_queue_depth",
+                        &[],
+                        "queue depth",
+                    ),
+                    Query::sum(
+                        "orchestrator_reconciliation_drift_replicas",
+                        &["kind"],
+                        "drift: {{kind}}",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "Reconciliation latency",
+                "s",
+                vec![Query::quantile(
+                    "orchestrator_reconciliation_duration_seconds",
+                    "0.99",
+                    &[],
+                    "p99",
+                )],
+            ),
+            Panel::time_series(
+                "State store operations",
+                "ops",
+                vec![Query::rate(
+                    "orchestrator_state_operations_total",
+                    &["operation", "success"],
+                    "{{operation}} (success={{success}})",
+                )],
+            ),
+            Panel::time_series(
+                "State store latency",
+                "s",
+                vec![Query::quantile(
+                    "orchestrator_state_operation_duration_seconds",
+                    "0.99",
+                    &["operation"],
+                    "{{operation}} p99",
+                )],
+            ),
+            Panel::time_series(
+                "API requests throttled",
+                "ops",
+                vec![Query::rate(
+                    "orchestrator_api_throttled_requests_total",
+                    &["client"],
+                    "{{client}}",
+                )],
+            ),
+            Panel::time_series(
+                "Garbage collected",
+                "none",
+                vec![Query::increase(
+                    "orchestrator_gc_reclaimed_total",
+                    &["kind"],
+                    "{{kind}}",
+                )],
+            ),
+            Panel::time_series(
+                "DNS cache",
+                "ops",
+                vec![
+                    Query::rate(
+                        "orchestrator_dns_cache_lookups_total",
+                        &["result"],
+                        "{{result}}",
+                    ),
+                    Query::rate(
+                        "orchestrator_dns_upstream_errors_total",
+                        &[],
+                        "upstream errors",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "DNS upstream latency",
+                "s",
+                vec![Query::quantile(
+                    "orchestrator_dns_upstream_duration_seconds",
+                    "0.99",
+                    &[],
+                    "p99",
+                )],
+            ),
+            Panel::time_series(
+                "MCP requests",
+                "ops",
+                vec![
+                    Query::rate("orchestrator_mcp_requests_total", &["tool"], "{{tool}}"),
+                    Query::rate(
+                        "orchestrator_mcp_errors_total",
+                        &["tool", "error_type"],
+                        "{{tool}} {{error_type}}",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "MCP latency",
+                "s",
+                vec![Query::quantile(
+                    "orchestrator_mcp_request_duration_seconds",
+                    "0.99",
+                    &["tool"],
+                    "{{tool}} p99",
+                )],
+            ),
+        ],
+    }
+}
+
+fn containers() -> Dashboard {
+    Dashboard {
+        uid: "orchestrator-containers",
+        title: "Orchestrator / Containers",
+        panels: vec![
+            Panel::time_series(
+                "Container operations",
+                "ops",
+                vec![
+                    Query::rate("orchestrator_container_creates_total", &[], "creates"),
+                    Query::rate("orchestrator_container_starts_total", &[], "starts"),
+                    Query::rate("orchestrator_container_stops_total", &[], "stops"),
+                    Query::rate(
+                        "orchestrator_container_errors_total",
+                        &["operation"],
+                        "{{operation}} errors",
+                    ),
+                ],
+            ),
+            Panel::time_series(
+                "Container operation latency",
+                "s",
+                vec![Query::quantile(
+                    "orchestrator_container_operation_duration_seconds",
+                    "0.99",
+                    &["operation"],
+                    "{{operation}} p99",
+                )],
+            ),
+            Panel::time_series(
+                "CPU by workload",
+                "none",
+                vec![Query::rate(
+                    "orchestrator_container_cpu_usage_seconds",
+                    &["namespace", "workload"],
+                    "{{namespace}}/{{workload}}",
+                )],
+            ),
+            Panel::time_series(
+                "Memory by workload",
+                "bytes",
+                vec![Query::sum(
+                    "orchestrator_container_memory_working_set_bytes",
+                    &["namespace", "workload"],
+                    "{{namespace}}/{{workload}}",
+                )],
+            ),
+            Panel::time_series(
+                "Restarts",
+                "none",
+                vec![Query::increase(
+                    "orchestrator_container_restarts_total",
+                    &["namespace", "workload", "container"],
+                    "{{namespace}}/{{workload}} {{container}}",
+                )],
+            ),
+            Panel::time_series(
+                "OOM kills",
+                "none",
+                vec![Query::increase(
+                    "orchestrator_container_oom_kills_total",
+                    &["namespace", "workload", "container", "node"],
+                    "{{namespace}}/{{workload}} {{container}} on {{node}}",
+                )],
+            ),
+            Panel::time_series(
+                "Anomaly score",
+                "none",
+                vec![Query::max(
+                    "orchestrator_container_anomaly_score",
+                    &["namespace", "workload", "metric"],
+                    "{{namespace}}/{{workload}} {{metric}}",
+                )],
+            ),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{parse_prometheus_text, ContainerUsageSample, OrchestratorMetrics};
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    /// Names and label keys of the series of every metric recorded.
+    async fn exported_series() -> BTreeMap<String, BTreeSet<String>> {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let _guard = metrics::set_default_local_recorder(&recorder);
+
+        let metrics = OrchestratorMetrics::new();
+        metrics.set_nodes_total(3).await;
+        metrics.set_nodes_ready(2).await;
+        metrics.set_node_capacity("node-1", 8.0, 16 << 30);
+        metrics.set_node_allocatable("node-1", 7.5, 15 << 30);
+        metrics.set_workloads_total(1).await;
+        metrics.set_workload_replicas("web", 3, 2);
+        metrics.set_instances_total(2).await;
+        metrics.set_instances_by_status("Running", 2);
+        metrics.inc_scheduling_attempts();
+        metrics.inc_scheduling_successes();
+        metrics.inc_scheduling_failures("no_capacity");
+        metrics.record_scheduling_duration(0.01);
+        metrics.record_time_to_schedule(2.0);
+        metrics.record_placement_attempts(3);
+        metrics.inc_scheduling_preemptions("priority");
+        metrics.inc_reconciliation_runs();
+        metrics.record_reconciliation_duration(0.1);
+        metrics.set_reconciliation_queue_depth(4);
+        metrics.set_reconciliation_drift("missing_replicas", 1);
+        metrics.inc_container_creates(false);
+        metrics.inc_container_starts(true);
+        metrics.inc_container_stops(true);
+        metrics.record_container_operation_duration("create", 0.2);
+        metrics.record_container_usage(&ContainerUsageSample {
+            namespace: "default".to_string(),
+            workload: "web".to_string(),
+            instance: "web-1".to_string(),
+            container: "nginx".to_string(),
+            node: "node-1".to_string(),
+            cpu_seconds: 1.5,
+            memory_working_set_bytes: 64 << 20,
+            restarts: 1,
+            oom_kills: 1,
+        });
+        metrics.record_container_anomaly_score("default", "web", "web-1", "nginx", "cpu", 1.5);
+        metrics.inc_cluster_events("node_joined");
+        metrics.set_cluster_health(true);
+        metrics.inc_state_operations("put", true);
+        metrics.record_state_operation_duration("put", 0.001);
+        metrics.inc_mcp_requests("list_nodes");
+        metrics.inc_mcp_errors("list_nodes", "timeout");
+        metrics.record_mcp_request_duration("list_nodes", 0.05);
+        metrics.inc_dns_cache_lookups(true);
+        metrics.record_dns_upstream_query(0.002, false);
+        metrics.inc_gc_reclaimed("job_instance", 2);
+        metrics.inc_api_throttled("subject");
+
+        let mut series: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for sample in parse_prometheus_text(&handle.render()) {
+            // Summaries also export _sum and _count series
+            if sample.name.ends_with("_sum") || sample.name.ends_with("_count") {
+                continue;
+            }
+            series
+                .entry(sample.name)
+                .or_default()
+                .extend(sample.labels.into_keys());
+        }
+        series
+    }
+
+    #[tokio::test]
+    async fn test_dashboards_match_exported_metrics() {
+        let exported = exported_series().await;
+        let mut charted = BTreeSet::new();
+
+        for dashboard in dashboards() {
+            for query in dashboard.panels.iter().flat_map(|panel| &panel.queries) {
+                let labels = exported
+                    .get(query.metric)
+                    .unwrap_or_else(|| panic!("{} charts unknown {}", dashboard.uid, query.metric));
+                for label in query.labels() {
+                    assert!(
+                        labels.contains(label),
+                        "{} groups {} by unknown label {}",
+                        dashboard.uid,
+                        query.metric,
+                        label
+                    );
+                }
+                if let Aggregation::Rate | Aggregation::Increase = query.aggregation {
+                    assert!(
+                        query.metric.ends_with("_total") || query.metric.ends_with("_seconds"),
+                        "{} is not a counter",
+                        query.metric
+                    );
+                }
+                charted.insert(query.metric.to_string());
+            }
+        }
+
+        let uncharted: Vec<_> = exported
+            .keys()
+            .filter(|name| !charted.contains(*name))
+            .collect();
+        assert!(
+            uncharted.is_empty(),
+            "metrics on no dashboard: {:?}",
+            uncharted
+        );
+    }
+
+    #[test]
+    fn test_dashboard_json() {
+        let dashboard = control_plane().to_json();
+        assert_eq!(dashboard["uid"], "orchestrator-control-plane");
+        assert_eq!(dashboard["templating"]["list"][0]["query"], "prometheus");
+
+        let panels = dashboard["panels"].as_array().unwrap();
+        assert_eq!(
+            panels[0]["gridPos"],
+            json!({"x": 0, "y": 0, "w": 12, "h": 8})
+        );
+        assert_eq!(
+            panels[1]["gridPos"],
+            json!({"x": 12, "y": 0, "w": 12, "h": 8})
+        );
+        assert_eq!(
+            panels[2]["gridPos"],
+            json!({"x": 0, "y": 8, "w": 12, "h": 8})
+        );
+        assert_eq!(
+            panels[0]["targets"][2]["expr"],
+            "sum by (reason) (rate(orchestrator_scheduling_failures_total[$__rate_interval]))"
+        );
+        assert_eq!(panels[0]["targets"][2]["refId"], "C");
+        assert_eq!(
+            panels[1]["targets"][1]["expr"],
+            "max (orchestrator_scheduling_duration_seconds{quantile=\"0.99\"})"
+        );
+
+        let ids: BTreeSet<_> = dashboards().iter().map(|d| d.uid).collect();
+        assert_eq!(ids.len(), dashboards().len());
+    }
+}
@@ -668,6 +668,8 @@ mod tests {
                 memory_mb: 7372,
                 disk_mb: 90000,
             },
+            unschedulable: false,
+            resource_version: 0,
         };
 
         let data = NodeEventData::from(&node);
@@ -1,9 +1,16 @@
 //! Health check system for the orchestrator.
 //!
 //! Provides component health tracking and aggregated health status.
+//!
+//! Components may depend on others, e.g. the API on the state store. A
+//! component that reports itself healthy while a component it depends on,
+//! directly or transitively, is not operational is reported degraded, with
+//! the impaired dependencies listed. A component whose own check fails is
+//! failed (unhealthy) and takes readiness down with it; degraded components
+//! do not.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
@@ -60,6 +67,12 @@ pub struct ComponentHealth {
     /// Additional metadata about the component.
     #[serde(skip_serializing_if = "HashMap::is_empty")]
     pub metadata: HashMap<String, String>,
+    /// Components this one depends on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Dependencies, direct or transitive, that are not operational.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub impaired_dependencies: Vec<String>,
 }
 
 impl ComponentHealth {
@@ -71,6 +84,8 @@ impl ComponentHealth {
             message: None,
             last_check: Utc::now(),
             metadata: HashMap::new(),
+            depends_on: Vec::new(),
+            impaired_dependencies: Vec::new(),
         }
     }
 
@@ -82,6 +97,8 @@ impl ComponentHealth {
             message: Some(message.into()),
             last_check: Utc::now(),
             metadata: HashMap::new(),
+            depends_on: Vec::new(),
+            impaired_dependencies: Vec::new(),
         }
     }
 
@@ -93,6 +110,8 @@ impl ComponentHealth {
             message: Some(message.into()),
             last_check: Utc::now(),
             metadata: HashMap::new(),
+            depends_on: Vec::new(),
+            impaired_dependencies: Vec::new(),
         }
     }
 
@@ -112,7 +131,8 @@ pub struct AggregatedHealth {
     pub service: String,
     /// Version of the service.
     pub version: String,
-    /// Individual component health statuses.
+    /// Individual component health statuses, sorted by name, with their
+    /// dependencies.
     pub components: Vec<ComponentHealth>,
     /// Timestamp of this health check.
     pub timestamp: DateTime<Utc>,
@@ -138,6 +158,9 @@ pub struct ReadinessStatus {
     /// Reason for the readiness state.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// Component health statuses with their dependencies.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<ComponentHealth>,
     /// Timestamp of this check.
     pub timestamp: DateTime<Utc>,
 }
@@ -161,6 +184,7 @@ struct HealthCheckerInner {
     service_name: String,
     version: String,
     components: HashMap<String, ComponentHealth>,
+    dependencies: HashMap<String, Vec<String>>,
     ready: bool,
     ready_reason: Option<String>,
 }
@@ -173,6 +197,7 @@ impl HealthChecker {
                 service_name: service_name.into(),
                 version: version.into(),
                 components: HashMap::new(),
+                dependencies: HashMap::new(),
                 ready: false,
                 ready_reason: Some("Service starting".to_string()),
             })),
@@ -193,6 +218,20 @@ impl HealthChecker {
         }
     }
 
+    /// Declare that `component` depends on `dependency`.
+    pub async fn add_dependency(
+        &self,
+        component: impl Into<String>,
+        dependency: impl Into<String>,
+    ) {
+        let mut inner = self.inner.write().await;
+        let dependencies = inner.dependencies.entry(component.into()).or_default();
+        let dependency = dependency.into();
+        if !dependencies.contains(&dependency) {
+            dependencies.push(dependency);
+        }
+    }
+
     /// Mark the service as ready.
     pub async fn set_ready(&self) {
         let mut inner = self.inner.write().await;
@@ -211,7 +250,7 @@ impl HealthChecker {
     pub async fn get_health(&self) -> AggregatedHealth {
         let inner = self.inner.read().await;
 
-        let components: Vec<ComponentHealth> = inner.components.values().cloned().collect();
+        let components = inner.component_graph();
 
         // Calculate overall status from components
         let status = if components.is_empty() {
@@ -235,9 +274,21 @@ impl HealthChecker {
     /// Get readiness status.
     pub async fn get_readiness(&self) -> ReadinessStatus {
         let inner = self.inner.read().await;
+        let components = inner.component_graph();
+        let failed: Vec<&str> = components
+            .iter()
+            .filter(|c| c.status == HealthStatus::Unhealthy)
+            .map(|c| c.name.as_str())
+            .collect();
+        let (ready, reason) = if inner.ready && !failed.is_empty() {
+            (false, Some(format!("Failed components: {}", failed.join(", "))))
+        } else {
+            (inner.ready, inner.ready_reason.clone())
+        };
         ReadinessStatus {
-            ready: inner.ready,
-            reason: inner.ready_reason.clone(),
+            ready,
+            reason,
+            components,
             timestamp: Utc::now(),
         }
     }
@@ -254,7 +305,7 @@ impl HealthChecker {
 
     /// Check if service is ready (simple boolean).
     pub async fn is_ready(&self) -> bool {
-        self.inner.read().await.ready
+        self.get_readiness().await.ready
     }
 
     /// Update a component to healthy status.
@@ -279,6 +330,67 @@ impl HealthChecker {
     }
 }
 
+impl HealthCheckerInner {
+    /// Components sorted by name, each with its dependencies and its status
+    /// lowered to degraded when any of them is not operational. Dependencies
+    /// that never reported count as unknown.
+    fn component_graph(&self) -> Vec<ComponentHealth> {
+        let mut components: Vec<ComponentHealth> = self
+            .components
+            .values()
+            .map(|component| {
+                let mut component = component.clone();
+                component.depends_on = self
+                    .dependencies
+                    .get(&component.name)
+                    .cloned()
+                    .unwrap_or_default();
+                component.impaired_dependencies = self.impaired_dependencies(&component.name);
+                if component.status == HealthStatus::Healthy
+                    && !component.impaired_dependencies.is_empty()
+                {
+                    component.status = HealthStatus::Degraded;
+                    component.message = Some(format!(
+                        "Impaired dependencies: {}",
+                        component.impaired_dependencies.join(", ")
+                    ));
+                }
+                component
+            })
+            .collect();
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+        components
+    }
+
+    /// Dependencies of `component`, direct or transitive, that are not
+    /// operational, in the order they are reached.
+    fn impaired_dependencies(&self, component: &str) -> Vec<String> {
+        let mut impaired = Vec::new();
+        let mut visited = HashSet::from([component.to_string()]);
+        let mut pending: Vec<&String> = self
+            .dependencies
+            .get(component)
+            .map(|deps| deps.iter().rev().collect())
+            .unwrap_or_default();
+        while let Some(dependency) = pending.pop() {
+            if !visited.insert(dependency.clone()) {
+                continue;
+            }
+            let operational = self
+                .components
+                .get(dependency)
+                .is_some_and(|health| health.status.is_operational());
+            if !operational {
+                impaired.push(dependency.clone());
+            }
+            if let Some(deps) = self.dependencies.get(dependency) {
+                pending.extend(deps.iter().rev());
+            }
+        }
+        impaired
+    }
+}
+
 impl Default for HealthChecker {
     fn default() -> Self {
         Self::new("orchestrator", env!("CARGO_PKG_VERSION"))
@@ -357,6 +469,57 @@ mod tests {
         assert_eq!(status.reason.as_deref(), Some("maintenance"));
     }
 
+    #[tokio::test]
+    async fn test_dependencies_degrade_dependents() {
+        let checker = HealthChecker::new("test", "1.0.0");
+        checker.add_dependency("api", "state_store").await;
+        checker.add_dependency("scheduler", "cluster_manager").await;
+        checker.add_dependency("cluster_manager", "network").await;
+        checker.mark_healthy("api").await;
+        checker.mark_healthy("scheduler").await;
+        checker.mark_healthy("cluster_manager").await;
+        checker.mark_healthy("state_store").await;
+        checker.mark_healthy("network").await;
+        checker.set_ready().await;
+
+        let health = checker.get_health().await;
+        assert_eq!(health.status, HealthStatus::Healthy);
+        let names: Vec<_> = health.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["api", "cluster_manager", "network", "scheduler", "state_store"]);
+        assert_eq!(health.components[0].depends_on, ["state_store"]);
+
+        // A transitive failure degrades the dependents but fails only its source
+        checker.mark_unhealthy("network", "interface down").await;
+        let health = checker.get_health().await;
+        let status = |name: &str| {
+            health
+                .components
+                .iter()
+                .find(|c| c.name == name)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(status("network").status, HealthStatus::Unhealthy);
+        assert_eq!(status("cluster_manager").status, HealthStatus::Degraded);
+        assert_eq!(status("scheduler").status, HealthStatus::Degraded);
+        assert_eq!(status("scheduler").impaired_dependencies, ["network"]);
+        assert_eq!(status("api").status, HealthStatus::Healthy);
+
+        let readiness = checker.get_readiness().await;
+        assert!(!readiness.ready);
+        assert_eq!(readiness.reason.as_deref(), Some("Failed components: network"));
+
+        // Degraded components keep the service ready
+        checker.mark_degraded("network", "packet loss").await;
+        assert!(checker.is_ready().await);
+
+        // Dependencies that never reported are impaired
+        checker.add_dependency("api", "registry").await;
+        let health = checker.get_health().await;
+        assert_eq!(health.components[0].impaired_dependencies, ["registry"]);
+        assert_eq!(health.components[0].status, HealthStatus::Degraded);
+    }
+
     #[tokio::test]
     async fn test_liveness() {
         let checker = HealthChecker::new("test", "1.0.0");
@@ -2,10 +2,15 @@
 //!
 //! This crate provides comprehensive observability capabilities:
 //!
-//! - **Tracing**: Structured logging with spans for distributed tracing
-//! - **Metrics**: Prometheus-compatible metrics for monitoring
+//! - **Tracing**: Structured logging with spans, exported over OTLP for
+//!   distributed tracing
+//! - **Metrics**: Scraped by Prometheus or pushed to statsd or OTLP, with
+//!   Grafana dashboards generated for them
 //! - **Health Endpoints**: HTTP endpoints for health checks and readiness probes
 //! - **Event Streaming**: WebSocket endpoint for real-time cluster events
+//! - **Alerting**: Threshold rules over metrics, notified to webhooks, Slack
+//!   or PagerDuty
+//! - **Profiling**: pprof CPU and heap profiles over HTTP
 //!
 //! # Architecture
 //!
@@ -30,17 +35,39 @@
 
 pub mod tracing_setup;
 pub mod metrics;
+pub mod statsd;
+pub mod dashboards;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+#[cfg(feature = "otlp")]
+pub mod otlp_trace;
+pub mod trace_context;
 pub mod health;
 pub mod server;
 pub mod events;
 pub mod websocket;
+#[cfg(feature = "alerting")]
+pub mod alerts;
+#[cfg(feature = "profiling")]
+pub mod profiling;
 
 pub use tracing_setup::{init_tracing, TracingConfig};
-pub use metrics::{OrchestratorMetrics, MetricsRegistry};
+pub use metrics::{
+    ContainerUsageSample, MetricSample, MetricsBackend, MetricsError, MetricsRecorder,
+    MetricsRegistry, OrchestratorMetrics,
+};
+pub use statsd::{StatsdConfig, StatsdRecorder};
+#[cfg(feature = "otlp")]
+pub use otlp::{OtlpConfig, OtlpRecorder};
+#[cfg(feature = "otlp")]
+pub use otlp_trace::{OtlpTraceConfig, OtlpTraceLayer};
+pub use trace_context::{current_traceparent, set_remote_parent, TraceParent, TRACEPARENT_HEADER};
 pub use health::{HealthChecker, HealthStatus, ComponentHealth};
 pub use server::{ObservabilityServer, ObservabilityConfig};
 pub use events::{EventHub, EventTopic, StreamEvent, EventType};
 pub use websocket::events_handler;
+#[cfg(feature = "alerting")]
+pub use alerts::{Alert, AlertEngine, AlertError, AlertNotifier, AlertRule, AlertState};
 
 /// Re-export tracing macros for convenience
 pub use tracing::{debug, error, info, instrument, trace, warn, span, Level};
@@ -1,29 +1,201 @@
-//! Prometheus-compatible metrics for the orchestrator.
+//! Metrics for the orchestrator.
 //!
 //! Provides counters, gauges, and histograms for monitoring cluster health,
-//! workload status, and operational performance.
+//! workload status, and operational performance. They are recorded through the
+//! `metrics` facade and exported by the [`MetricsBackend`] the registry
+//! installs: scraped by Prometheus, or pushed to statsd or an OTLP collector.
 
 use metrics::{counter, gauge, histogram, describe_counter, describe_gauge, describe_histogram, Label};
 use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::collections::BTreeMap;
+use std::net::ToSocketAddrs;
+use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-/// Registry for orchestrator metrics with Prometheus exporter.
+#[cfg(feature = "otlp")]
+use crate::otlp::{OtlpConfig, OtlpRecorder};
+use crate::statsd::{StatsdConfig, StatsdRecorder};
+
+/// Errors setting up a metrics backend.
+#[derive(Debug, thiserror::Error)]
+pub enum MetricsError {
+    #[error("Invalid metrics backend: {0}")]
+    InvalidBackend(String),
+    #[error("A metrics recorder is already installed in this process")]
+    AlreadyInstalled,
+    #[error("Metrics I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Where metrics are exported.
+///
+/// Parses from `prometheus`, `statsd://host:port[/prefix]` or, with the `otlp`
+/// feature, `otlp+http://collector:4318`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum MetricsBackend {
+    /// Scraped from `/metrics` in Prometheus text format.
+    #[default]
+    Prometheus,
+    /// Pushed to a statsd daemon over UDP as values change.
+    Statsd(StatsdConfig),
+    /// Pushed to an OpenTelemetry collector over OTLP/HTTP.
+    #[cfg(feature = "otlp")]
+    Otlp(OtlpConfig),
+}
+
+impl FromStr for MetricsBackend {
+    type Err = MetricsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s == "prometheus" {
+            return Ok(Self::Prometheus);
+        }
+        if let Some(rest) = s.strip_prefix("statsd://") {
+            let (host, prefix) = match rest.split_once('/') {
+                Some((host, prefix)) => (host, Some(prefix).filter(|p| !p.is_empty())),
+                None => (rest, None),
+            };
+            let addr = host
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.next())
+                .ok_or_else(|| MetricsError::InvalidBackend(format!("cannot resolve {}", host)))?;
+            let mut config = StatsdConfig::new(addr);
+            config.prefix = prefix.map(str::to_string);
+            return Ok(Self::Statsd(config));
+        }
+        if let Some(endpoint) = s.strip_prefix("otlp+") {
+            #[cfg(feature = "otlp")]
+            return Ok(Self::Otlp(OtlpConfig::new(endpoint)));
+            #[cfg(not(feature = "otlp"))]
+            return Err(MetricsError::InvalidBackend(format!(
+                "{} needs the otlp feature",
+                endpoint
+            )));
+        }
+        Err(MetricsError::InvalidBackend(format!(
+            "'{}', expected prometheus, statsd://host:port or otlp+http://host:port",
+            s
+        )))
+    }
+}
+
+/// The current value of one series.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+/// An installed metrics backend.
+///
+/// Backends are installed as the global `metrics` recorder, so
+/// [`OrchestratorMetrics`] records into whichever one is in use.
+pub trait MetricsRecorder: Send + Sync {
+    /// Current metrics in Prometheus text format, for backends that are scraped.
+    fn render(&self) -> Option<String> {
+        None
+    }
+
+    /// Current value of every series, for backends that keep them; by
+    /// default read back from [`render`](Self::render).
+    fn samples(&self) -> Vec<MetricSample> {
+        self.render()
+            .map(|text| parse_prometheus_text(&text))
+            .unwrap_or_default()
+    }
+}
+
+/// Samples of metrics in the Prometheus text format. Histogram and summary
+/// series come out under their suffixed names, e.g. `..._count`.
+pub fn parse_prometheus_text(text: &str) -> Vec<MetricSample> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(parse_sample_line)
+        .collect()
+}
+
+/// Parse `name{label="value",...} value [timestamp]`.
+pub(crate) fn parse_sample_line(line: &str) -> Option<MetricSample> {
+    let name_end = line.find(|c: char| c == '{' || c.is_whitespace())?;
+    let name = line[..name_end].to_string();
+    let mut rest = &line[name_end..];
+    let mut labels = BTreeMap::new();
+
+    if let Some(body) = rest.strip_prefix('{') {
+        let mut chars = body.char_indices();
+        loop {
+            let key_start = chars.clone().next()?.0;
+            let (key_end, _) = chars.by_ref().find(|(_, c)| *c == '=' || *c == '}')?;
+            let key = body[key_start..key_end].trim().trim_start_matches(',').trim();
+            if body[key_end..].starts_with('}') {
+                rest = &body[key_end + 1..];
+                break;
+            }
+            if chars.next()?.1 != '"' {
+                return None;
+            }
+            let mut value = String::new();
+            loop {
+                match chars.next()?.1 {
+                    '"' => break,
+                    '\\' => match chars.next()?.1 {
+                        'n' => value.push('\n'),
+                        other => value.push(other),
+                    },
+                    c => value.push(c),
+                }
+            }
+            labels.insert(key.to_string(), value);
+        }
+    }
+
+    let value = rest.split_whitespace().next()?.parse().ok()?;
+    Some(MetricSample {
+        name,
+        labels,
+        value,
+    })
+}
+
+impl MetricsRecorder for PrometheusHandle {
+    fn render(&self) -> Option<String> {
+        Some(PrometheusHandle::render(self))
+    }
+}
+
+/// Registry for orchestrator metrics, exported through one backend.
 pub struct MetricsRegistry {
-    handle: PrometheusHandle,
+    recorder: Box<dyn MetricsRecorder>,
 }
 
 impl MetricsRegistry {
     /// Create a new metrics registry with Prometheus exporter.
     pub fn new() -> Self {
-        let handle = PrometheusBuilder::new()
-            .install_recorder()
-            .expect("Failed to install Prometheus recorder");
+        Self::install(&MetricsBackend::Prometheus).expect("Failed to install Prometheus recorder")
+    }
+
+    /// Install `backend` as the process-wide metrics recorder. Only one
+    /// registry can be installed per process.
+    pub fn install(backend: &MetricsBackend) -> Result<Self, MetricsError> {
+        let recorder: Box<dyn MetricsRecorder> = match backend {
+            MetricsBackend::Prometheus => Box::new(
+                PrometheusBuilder::new()
+                    .install_recorder()
+                    .map_err(|_| MetricsError::AlreadyInstalled)?,
+            ),
+            MetricsBackend::Statsd(config) => Box::new(StatsdRecorder::new(config)?.install()?),
+            #[cfg(feature = "otlp")]
+            MetricsBackend::Otlp(config) => Box::new(OtlpRecorder::new(config.clone()).install()?),
+        };
 
         // Register metric descriptions
         Self::register_descriptions();
 
-        Self { handle }
+        Ok(Self { recorder })
     }
 
     /// Register all metric descriptions.
@@ -93,6 +265,18 @@ impl MetricsRegistry {
             "orchestrator_scheduling_duration_seconds",
             "Time taken for scheduling decisions"
         );
+        describe_histogram!(
+            "orchestrator_scheduling_time_to_schedule_seconds",
+            "Time from a workload falling short of replicas to a replica being placed"
+        );
+        describe_histogram!(
+            "orchestrator_scheduling_placement_attempts",
+            "Scheduling attempts a workload needed before a replica was placed"
+        );
+        describe_counter!(
+            "orchestrator_scheduling_preemptions_total",
+            "Total number of instances preempted to place others by reason"
+        );
 
         // Reconciliation metrics
         describe_counter!(
@@ -107,6 +291,10 @@ impl MetricsRegistry {
             "orchestrator_reconciliation_queue_depth",
             "Number of items in reconciliation queue"
         );
+        describe_gauge!(
+            "orchestrator_reconciliation_drift_replicas",
+            "Number of replicas currently missing from or in excess of the desired state by kind"
+        );
 
         // Container runtime metrics
         describe_counter!(
@@ -129,6 +317,26 @@ impl MetricsRegistry {
             "orchestrator_container_operation_duration_seconds",
             "Time taken for container operations"
         );
+        describe_gauge!(
+            "orchestrator_container_cpu_usage_seconds",
+            "CPU time a container has used since it started"
+        );
+        describe_gauge!(
+            "orchestrator_container_memory_working_set_bytes",
+            "Memory a container uses, less reclaimable page cache"
+        );
+        describe_counter!(
+            "orchestrator_container_restarts_total",
+            "Total number of restarts of a container"
+        );
+        describe_counter!(
+            "orchestrator_container_oom_kills_total",
+            "Total number of processes of a container killed for running out of memory"
+        );
+        describe_gauge!(
+            "orchestrator_container_anomaly_score",
+            "Standard deviations a container metric is from its workload's baseline"
+        );
 
         // Cluster manager metrics
         describe_counter!(
@@ -163,11 +371,43 @@ impl MetricsRegistry {
             "orchestrator_mcp_request_duration_seconds",
             "Time taken for MCP requests"
         );
+
+        // Node-local DNS cache metrics
+        describe_counter!(
+            "orchestrator_dns_cache_lookups_total",
+            "Total number of DNS cache lookups by result (hit/miss)"
+        );
+        describe_counter!(
+            "orchestrator_dns_upstream_errors_total",
+            "Total number of failed upstream DNS queries"
+        );
+        describe_histogram!(
+            "orchestrator_dns_upstream_duration_seconds",
+            "Latency of upstream DNS queries on cache misses"
+        );
+
+        // Garbage collection metrics
+        describe_counter!(
+            "orchestrator_gc_reclaimed_total",
+            "Total number of objects reclaimed by garbage collection by kind"
+        );
+
+        // API server metrics
+        describe_counter!(
+            "orchestrator_api_throttled_requests_total",
+            "Total number of API requests rejected by rate limiting by client kind"
+        );
+    }
+
+    /// Render metrics in Prometheus text format, unless the backend pushes
+    /// them elsewhere.
+    pub fn render(&self) -> Option<String> {
+        self.recorder.render()
     }
 
-    /// Render metrics in Prometheus text format.
-    pub fn render(&self) -> String {
-        self.handle.render()
+    /// Current value of every series; empty for backends that keep none.
+    pub fn samples(&self) -> Vec<MetricSample> {
+        self.recorder.samples()
     }
 }
 
@@ -177,6 +417,21 @@ impl Default for MetricsRegistry {
     }
 }
 
+/// Resource usage of one container, as scraped from its runtime.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContainerUsageSample {
+    pub namespace: String,
+    /// Workload name.
+    pub workload: String,
+    pub instance: String,
+    pub container: String,
+    pub node: String,
+    pub cpu_seconds: f64,
+    pub memory_working_set_bytes: u64,
+    pub restarts: u64,
+    pub oom_kills: u64,
+}
+
 /// High-level interface for recording orchestrator metrics.
 #[derive(Clone)]
 pub struct OrchestratorMetrics {
@@ -282,6 +537,22 @@ impl OrchestratorMetrics {
         histogram!("orchestrator_scheduling_duration_seconds").record(duration_secs);
     }
 
+    /// Record how long a workload waited before a replica was placed.
+    pub fn record_time_to_schedule(&self, duration_secs: f64) {
+        histogram!("orchestrator_scheduling_time_to_schedule_seconds").record(duration_secs);
+    }
+
+    /// Record how many attempts a workload needed before a replica was placed.
+    pub fn record_placement_attempts(&self, attempts: u32) {
+        histogram!("orchestrator_scheduling_placement_attempts").record(attempts as f64);
+    }
+
+    /// Record an instance preempted to place another.
+    pub fn inc_scheduling_preemptions(&self, reason: &str) {
+        let labels = [("reason", reason.to_string())];
+        counter!("orchestrator_scheduling_preemptions_total", &labels).increment(1);
+    }
+
     // === Reconciliation Metrics ===
 
     /// Record a reconciliation run.
@@ -299,6 +570,12 @@ impl OrchestratorMetrics {
         gauge!("orchestrator_reconciliation_queue_depth").set(depth as f64);
     }
 
+    /// Set the replicas currently missing from or in excess of the desired state.
+    pub fn set_reconciliation_drift(&self, kind: &str, replicas: u64) {
+        let labels = [("kind", kind.to_string())];
+        gauge!("orchestrator_reconciliation_drift_replicas", &labels).set(replicas as f64);
+    }
+
     // === Container Runtime Metrics ===
 
     /// Record a container create operation.
@@ -331,6 +608,44 @@ impl OrchestratorMetrics {
         histogram!("orchestrator_container_operation_duration_seconds", &labels).record(duration_secs);
     }
 
+    /// Record a container's resource usage, labeled by namespace, workload,
+    /// instance, container and node.
+    pub fn record_container_usage(&self, sample: &ContainerUsageSample) {
+        let labels = [
+            ("namespace", sample.namespace.clone()),
+            ("workload", sample.workload.clone()),
+            ("instance", sample.instance.clone()),
+            ("container", sample.container.clone()),
+            ("node", sample.node.clone()),
+        ];
+        gauge!("orchestrator_container_cpu_usage_seconds", &labels).set(sample.cpu_seconds);
+        gauge!("orchestrator_container_memory_working_set_bytes", &labels)
+            .set(sample.memory_working_set_bytes as f64);
+        counter!("orchestrator_container_restarts_total", &labels).absolute(sample.restarts);
+        counter!("orchestrator_container_oom_kills_total", &labels).absolute(sample.oom_kills);
+    }
+
+    /// Record how many standard deviations a container's `metric` ("cpu",
+    /// "memory", "restarts" or "log_errors") is from its workload's baseline.
+    pub fn record_container_anomaly_score(
+        &self,
+        namespace: &str,
+        workload: &str,
+        instance: &str,
+        container: &str,
+        metric: &str,
+        sigmas: f64,
+    ) {
+        let labels = [
+            ("namespace", namespace.to_string()),
+            ("workload", workload.to_string()),
+            ("instance", instance.to_string()),
+            ("container", container.to_string()),
+            ("metric", metric.to_string()),
+        ];
+        gauge!("orchestrator_container_anomaly_score", &labels).set(sigmas);
+    }
+
     // === Cluster Manager Metrics ===
 
     /// Record a cluster event.
@@ -383,6 +698,38 @@ impl OrchestratorMetrics {
         let labels = [("tool", tool.to_string())];
         histogram!("orchestrator_mcp_request_duration_seconds", &labels).record(duration_secs);
     }
+
+    // === DNS Cache Metrics ===
+
+    /// Record a DNS cache lookup.
+    pub fn inc_dns_cache_lookups(&self, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        counter!("orchestrator_dns_cache_lookups_total", "result" => result).increment(1);
+    }
+
+    /// Record an upstream DNS query made on a cache miss.
+    pub fn record_dns_upstream_query(&self, duration_secs: f64, success: bool) {
+        histogram!("orchestrator_dns_upstream_duration_seconds").record(duration_secs);
+        if !success {
+            counter!("orchestrator_dns_upstream_errors_total").increment(1);
+        }
+    }
+
+    // === Garbage Collection Metrics ===
+
+    /// Record objects of one kind reclaimed by garbage collection.
+    pub fn inc_gc_reclaimed(&self, kind: &str, count: u64) {
+        let labels = [("kind", kind.to_string())];
+        counter!("orchestrator_gc_reclaimed_total", &labels).increment(count);
+    }
+
+    // === API Server Metrics ===
+
+    /// Record an API request rejected by rate limiting.
+    pub fn inc_api_throttled(&self, client_kind: &str) {
+        let labels = [("client", client_kind.to_string())];
+        counter!("orchestrator_api_throttled_requests_total", &labels).increment(1);
+    }
 }
 
 impl Default for OrchestratorMetrics {
@@ -430,6 +777,25 @@ impl Drop for MetricTimer {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_prometheus_text() {
+        let text = "# HELP orchestrator_nodes_total Total number of nodes\n\
+            # TYPE orchestrator_nodes_total gauge\n\
+            orchestrator_nodes_total 5\n\
+            orchestrator_instances_by_status{status=\"Failed\",note=\"a \\\"b\\\", c}\"} 2 1700000000\n\
+            orchestrator_empty_labels{} +Inf\n\
+            not a sample\n";
+        let samples = parse_prometheus_text(text);
+        assert_eq!(samples.len(), 3);
+        assert_eq!(samples[0].name, "orchestrator_nodes_total");
+        assert_eq!(samples[0].value, 5.0);
+        assert!(samples[0].labels.is_empty());
+        assert_eq!(samples[1].labels["status"], "Failed");
+        assert_eq!(samples[1].labels["note"], "a \"b\", c}");
+        assert_eq!(samples[1].value, 2.0);
+        assert_eq!(samples[2].value, f64::INFINITY);
+    }
+
     #[test]
     fn test_metrics_registry_creation() {
         // Note: Can only create one registry per process
@@ -457,6 +823,49 @@ mod tests {
         // Test histogram recordings
         metrics.record_scheduling_duration(0.05);
         metrics.record_reconciliation_duration(0.1);
+        metrics.record_time_to_schedule(1.5);
+        metrics.record_placement_attempts(2);
+        metrics.inc_scheduling_preemptions("priority");
+        metrics.set_reconciliation_drift("missing_replicas", 2);
+
+        // Test DNS cache metrics
+        metrics.inc_dns_cache_lookups(true);
+        metrics.inc_dns_cache_lookups(false);
+        metrics.record_dns_upstream_query(0.002, true);
+        metrics.inc_gc_reclaimed("job_instance", 3);
+        metrics.inc_api_throttled("subject");
+        metrics.record_container_usage(&ContainerUsageSample {
+            namespace: "default".to_string(),
+            workload: "web".to_string(),
+            container: "nginx".to_string(),
+            cpu_seconds: 1.5,
+            memory_working_set_bytes: 64 * 1024 * 1024,
+            restarts: 2,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_parse_metrics_backend() {
+        let parse = |s: &str| s.parse::<MetricsBackend>();
+        let statsd = StatsdConfig::new("127.0.0.1:8125".parse().unwrap());
+
+        assert_eq!(parse("prometheus").unwrap(), MetricsBackend::Prometheus);
+        assert_eq!(
+            parse("statsd://127.0.0.1:8125").unwrap(),
+            MetricsBackend::Statsd(statsd.clone())
+        );
+        assert_eq!(
+            parse("statsd://127.0.0.1:8125/orch").unwrap(),
+            MetricsBackend::Statsd(statsd.with_prefix("orch"))
+        );
+        #[cfg(feature = "otlp")]
+        assert_eq!(
+            parse("otlp+http://collector:4318").unwrap(),
+            MetricsBackend::Otlp(OtlpConfig::new("http://collector:4318"))
+        );
+        assert!(parse("statsd://").is_err());
+        assert!(parse("graphite://host:2003").is_err());
     }
 
     #[test]
@@ -0,0 +1,408 @@
+//! OTLP metrics backend.
+//!
+//! Values are aggregated in memory and pushed to an OpenTelemetry collector
+//! every export interval, as an OTLP/HTTP request with a JSON body to
+//! `{endpoint}/v1/metrics`. Counters and histograms are cumulative since the
+//! recorder was created.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use metrics::atomics::AtomicU64;
+use metrics::{
+    Counter, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+};
+use serde_json::{json, Value};
+
+use crate::metrics::{MetricSample, MetricsError, MetricsRecorder};
+
+/// OTLP `AGGREGATION_TEMPORALITY_CUMULATIVE`.
+const CUMULATIVE: u8 = 2;
+
+/// Histogram bucket upper bounds, suited to durations in seconds.
+const BUCKET_BOUNDS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Where and how often to push OTLP metrics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpConfig {
+    /// Base URL of the collector's OTLP/HTTP receiver, e.g. `http://collector:4318`.
+    pub endpoint: String,
+    pub interval: Duration,
+    /// Reported as the `service.name` resource attribute.
+    pub service_name: String,
+}
+
+impl OtlpConfig {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            interval: Duration::from_secs(10),
+            service_name: "orchestrator".to_string(),
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_service_name(mut self, service_name: impl Into<String>) -> Self {
+        self.service_name = service_name.into();
+        self
+    }
+}
+
+/// Recorder that aggregates metrics and pushes them over OTLP.
+#[derive(Clone)]
+pub struct OtlpRecorder {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    config: OtlpConfig,
+    started_at: SystemTime,
+    descriptions: Mutex<HashMap<String, String>>,
+    counters: Mutex<HashMap<Key, Arc<AtomicU64>>>,
+    // f64 bits, as `metrics` stores gauges
+    gauges: Mutex<HashMap<Key, Arc<AtomicU64>>>,
+    histograms: Mutex<HashMap<Key, Arc<OtlpHistogram>>>,
+}
+
+impl OtlpRecorder {
+    pub fn new(config: OtlpConfig) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                started_at: SystemTime::now(),
+                descriptions: Mutex::new(HashMap::new()),
+                counters: Mutex::new(HashMap::new()),
+                gauges: Mutex::new(HashMap::new()),
+                histograms: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Install as the process-wide recorder and start pushing. Must be called
+    /// from within a Tokio runtime.
+    pub fn install(self) -> Result<Self, MetricsError> {
+        metrics::set_global_recorder(self.clone()).map_err(|_| MetricsError::AlreadyInstalled)?;
+        tokio::spawn(self.clone().run());
+        Ok(self)
+    }
+
+    async fn run(self) {
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}/v1/metrics",
+            self.inner.config.endpoint.trim_end_matches('/')
+        );
+        let mut interval = tokio::time::interval(self.inner.config.interval);
+        // The first tick completes immediately
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let request = self.export_request(SystemTime::now());
+            match client.post(&url).json(&request).send().await {
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) => {
+                    tracing::warn!(
+                        "OTLP metrics export to {} failed: {}",
+                        url,
+                        response.status()
+                    )
+                }
+                Err(e) => tracing::warn!("OTLP metrics export to {} failed: {}", url, e),
+            }
+        }
+    }
+
+    /// The OTLP `ExportMetricsServiceRequest` for the current values.
+    fn export_request(&self, now: SystemTime) -> Value {
+        let start = unix_nanos(self.inner.started_at);
+        let now = unix_nanos(now);
+        let descriptions = self.inner.descriptions.lock().unwrap().clone();
+        let mut metrics = Vec::new();
+
+        for (name, points) in by_name(&self.inner.counters) {
+            let points: Vec<Value> = points
+                .into_iter()
+                .map(|(key, counter)| {
+                    json!({
+                        "attributes": attributes(&key),
+                        "startTimeUnixNano": start,
+                        "timeUnixNano": now,
+                        "asInt": counter.load(Ordering::Relaxed).to_string(),
+                    })
+                })
+                .collect();
+            metrics.push(json!({
+                "name": name,
+                "description": descriptions.get(&name).cloned().unwrap_or_default(),
+                "sum": {
+                    "aggregationTemporality": CUMULATIVE,
+                    "isMonotonic": true,
+                    "dataPoints": points,
+                },
+            }));
+        }
+
+        for (name, points) in by_name(&self.inner.gauges) {
+            let points: Vec<Value> = points
+                .into_iter()
+                .map(|(key, gauge)| {
+                    json!({
+                        "attributes": attributes(&key),
+                        "timeUnixNano": now,
+                        "asDouble": f64::from_bits(gauge.load(Ordering::Relaxed)),
+                    })
+                })
+                .collect();
+            metrics.push(json!({
+                "name": name,
+                "description": descriptions.get(&name).cloned().unwrap_or_default(),
+                "gauge": { "dataPoints": points },
+            }));
+        }
+
+        for (name, points) in by_name(&self.inner.histograms) {
+            let points: Vec<Value> = points
+                .into_iter()
+                .map(|(key, histogram)| {
+                    let data = histogram.0.lock().unwrap();
+                    let mut point = json!({
+                        "attributes": attributes(&key),
+                        "startTimeUnixNano": start,
+                        "timeUnixNano": now,
+                        "count": data.count.to_string(),
+                        "sum": data.sum,
+                        "bucketCounts": data.buckets.iter().map(u64::to_string).collect::<Vec<_>>(),
+                        "explicitBounds": BUCKET_BOUNDS,
+                    });
+                    if data.count > 0 {
+                        point["min"] = json!(data.min);
+                        point["max"] = json!(data.max);
+                    }
+                    point
+                })
+                .collect();
+            metrics.push(json!({
+                "name": name,
+                "description": descriptions.get(&name).cloned().unwrap_or_default(),
+                "histogram": {
+                    "aggregationTemporality": CUMULATIVE,
+                    "dataPoints": points,
+                },
+            }));
+        }
+
+        json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [{
+                        "key": "service.name",
+                        "value": { "stringValue": self.inner.config.service_name },
+                    }],
+                },
+                "scopeMetrics": [{
+                    "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                    "metrics": metrics,
+                }],
+            }],
+        })
+    }
+
+    fn describe(&self, key: KeyName, description: SharedString) {
+        self.inner
+            .descriptions
+            .lock()
+            .unwrap()
+            .insert(key.as_str().to_string(), description.into_owned());
+    }
+}
+
+impl MetricsRecorder for OtlpRecorder {
+    fn samples(&self) -> Vec<MetricSample> {
+        let sample = |name: String, key: &Key, value: f64| MetricSample {
+            name,
+            labels: key
+                .labels()
+                .map(|label| (label.key().to_string(), label.value().to_string()))
+                .collect(),
+            value,
+        };
+        let mut samples = Vec::new();
+        for (key, value) in self.inner.counters.lock().unwrap().iter() {
+            let value = value.load(Ordering::Relaxed) as f64;
+            samples.push(sample(key.name().to_string(), key, value));
+        }
+        for (key, value) in self.inner.gauges.lock().unwrap().iter() {
+            let value = f64::from_bits(value.load(Ordering::Relaxed));
+            samples.push(sample(key.name().to_string(), key, value));
+        }
+        for (key, histogram) in self.inner.histograms.lock().unwrap().iter() {
+            let data = histogram.0.lock().unwrap();
+            samples.push(sample(format!("{}_count", key.name()), key, data.count as f64));
+            samples.push(sample(format!("{}_sum", key.name()), key, data.sum));
+        }
+        samples
+    }
+}
+
+impl Recorder for OtlpRecorder {
+    fn describe_counter(&self, key: KeyName, _unit: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn describe_gauge(&self, key: KeyName, _unit: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn describe_histogram(&self, key: KeyName, _unit: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn register_counter(&self, key: &Key, _metadata: &Metadata<'_>) -> Counter {
+        let mut counters = self.inner.counters.lock().unwrap();
+        Counter::from_arc(counters.entry(key.clone()).or_default().clone())
+    }
+
+    fn register_gauge(&self, key: &Key, _metadata: &Metadata<'_>) -> Gauge {
+        let mut gauges = self.inner.gauges.lock().unwrap();
+        Gauge::from_arc(gauges.entry(key.clone()).or_default().clone())
+    }
+
+    fn register_histogram(&self, key: &Key, _metadata: &Metadata<'_>) -> Histogram {
+        let mut histograms = self.inner.histograms.lock().unwrap();
+        Histogram::from_arc(histograms.entry(key.clone()).or_default().clone())
+    }
+}
+
+/// Cumulative explicit-bucket histogram.
+#[derive(Default)]
+struct OtlpHistogram(Mutex<HistogramData>);
+
+struct HistogramData {
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+    /// One per bound, plus the overflow bucket.
+    buckets: Vec<u64>,
+}
+
+impl Default for HistogramData {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            buckets: vec![0; BUCKET_BOUNDS.len() + 1],
+        }
+    }
+}
+
+impl HistogramFn for OtlpHistogram {
+    fn record(&self, value: f64) {
+        let mut data = self.0.lock().unwrap();
+        data.count += 1;
+        data.sum += value;
+        data.min = data.min.min(value);
+        data.max = data.max.max(value);
+        let bucket = BUCKET_BOUNDS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(BUCKET_BOUNDS.len());
+        data.buckets[bucket] += 1;
+    }
+}
+
+/// Series grouped under their metric name, in name order.
+fn by_name<T>(series: &Mutex<HashMap<Key, Arc<T>>>) -> BTreeMap<String, Vec<(Key, Arc<T>)>> {
+    let mut groups: BTreeMap<String, Vec<(Key, Arc<T>)>> = BTreeMap::new();
+    for (key, value) in series.lock().unwrap().iter() {
+        groups
+            .entry(key.name().to_string())
+            .or_default()
+            .push((key.clone(), value.clone()));
+    }
+    groups
+}
+
+fn attributes(key: &Key) -> Vec<Value> {
+    key.labels()
+        .map(|label| json!({ "key": label.key(), "value": { "stringValue": label.value() } }))
+        .collect()
+}
+
+/// Nanoseconds since the Unix epoch; OTLP JSON encodes 64-bit integers as strings.
+pub(crate) fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_request() {
+        let recorder = OtlpRecorder::new(OtlpConfig::new("http://collector:4318"));
+        metrics::with_local_recorder(&recorder, || {
+            metrics::describe_counter!("requests_total", "Requests served");
+            metrics::counter!("requests_total", "tool" => "deploy").increment(2);
+            metrics::counter!("requests_total", "tool" => "deploy").increment(1);
+            metrics::gauge!("queue_depth").set(4.0);
+            metrics::histogram!("duration_seconds").record(0.02);
+            metrics::histogram!("duration_seconds").record(20.0);
+        });
+
+        let request = recorder.export_request(SystemTime::now());
+        let resource = &request["resourceMetrics"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "orchestrator"
+        );
+        let metrics = resource["scopeMetrics"][0]["metrics"].as_array().unwrap();
+        assert_eq!(metrics.len(), 3);
+
+        let counter = &metrics[0];
+        assert_eq!(counter["name"], "requests_total");
+        assert_eq!(counter["description"], "Requests served");
+        let point = &counter["sum"]["dataPoints"][0];
+        assert_eq!(point["asInt"], "3");
+        assert_eq!(point["attributes"][0]["key"], "tool");
+        assert_eq!(point["attributes"][0]["value"]["stringValue"], "deploy");
+
+        assert_eq!(metrics[1]["gauge"]["dataPoints"][0]["asDouble"], 4.0);
+
+        let point = &metrics[2]["histogram"]["dataPoints"][0];
+        assert_eq!(point["count"], "2");
+        assert_eq!(point["min"], 0.02);
+        assert_eq!(point["max"], 20.0);
+        let buckets = point["bucketCounts"].as_array().unwrap();
+        assert_eq!(buckets.len(), BUCKET_BOUNDS.len() + 1);
+        assert_eq!(buckets[2], "1");
+        assert_eq!(buckets[BUCKET_BOUNDS.len()], "1");
+
+        let mut samples = recorder.samples();
+        samples.sort_by(|a, b| a.name.cmp(&b.name));
+        let values: Vec<_> = samples.iter().map(|s| (s.name.as_str(), s.value)).collect();
+        assert_eq!(
+            values,
+            [
+                ("duration_seconds_count", 2.0),
+                ("duration_seconds_sum", 20.02),
+                ("queue_depth", 4.0),
+                ("requests_total", 3.0),
+            ]
+        );
+        assert_eq!(samples[3].labels["tool"], "deploy");
+    }
+}
@@ -0,0 +1,425 @@
+//! OTLP trace export.
+//!
+//! [`OtlpTraceLayer`] records spans and pushes finished ones to an
+//! OpenTelemetry collector such as Tempo or Jaeger in batches, as OTLP/HTTP
+//! requests with a JSON body to `{endpoint}/v1/traces`. Whether a trace is
+//! exported is decided once at its root from the trace id, so traces are kept
+//! or dropped whole; traces continued from a caller follow the caller's
+//! decision (see [`crate::trace_context`]).
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use serde_json::{json, Value};
+use tokio::task::JoinHandle;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+use uuid::Uuid;
+
+use crate::otlp::unix_nanos;
+use crate::trace_context::SpanContext;
+
+/// Finished spans kept while the collector is unreachable; newer spans are
+/// dropped once this many wait.
+pub const MAX_QUEUED_SPANS: usize = 4096;
+
+/// OTLP `SPAN_KIND_INTERNAL`.
+const SPAN_KIND_INTERNAL: u8 = 1;
+
+/// Where to push traces, and which to keep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpTraceConfig {
+    /// Base URL of the collector's OTLP/HTTP receiver, e.g. `http://tempo:4318`.
+    pub endpoint: String,
+    /// Sent with every export, e.g. for authentication.
+    pub headers: Vec<(String, String)>,
+    /// Share of traces exported, from 0.0 to 1.0.
+    pub sampling_ratio: f64,
+    pub interval: Duration,
+    /// Reported as the `service.name` resource attribute.
+    pub service_name: String,
+}
+
+impl OtlpTraceConfig {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            headers: vec![],
+            sampling_ratio: 1.0,
+            interval: Duration::from_secs(5),
+            service_name: "orchestrator".to_string(),
+        }
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Export this share of traces; clamped to 0.0..=1.0.
+    pub fn with_sampling_ratio(mut self, ratio: f64) -> Self {
+        self.sampling_ratio = if ratio.is_nan() {
+            0.0
+        } else {
+            ratio.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_service_name(mut self, service_name: impl Into<String>) -> Self {
+        self.service_name = service_name.into();
+        self
+    }
+
+    /// Parse headers written as "name=value,name=value", as in
+    /// `OTEL_EXPORTER_OTLP_HEADERS`.
+    pub fn parse_headers(s: &str) -> Result<Vec<(String, String)>, String> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+            .map(|h| match h.split_once('=') {
+                Some((name, value)) if !name.trim().is_empty() => {
+                    Ok((name.trim().to_string(), value.trim().to_string()))
+                }
+                _ => Err(format!("expected name=value, got '{}'", h)),
+            })
+            .collect()
+    }
+}
+
+/// Layer that records spans and exports finished ones over OTLP.
+#[derive(Clone)]
+pub struct OtlpTraceLayer {
+    config: Arc<OtlpTraceConfig>,
+    queue: Arc<Mutex<VecDeque<FinishedSpan>>>,
+}
+
+/// Start and fields of an open span, kept in its extensions.
+struct SpanData {
+    start: SystemTime,
+    attributes: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+struct FinishedSpan {
+    context: SpanContext,
+    name: &'static str,
+    target: &'static str,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(String, String)>,
+}
+
+impl OtlpTraceLayer {
+    pub fn new(config: OtlpTraceConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            queue: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Push finished spans every interval. Must be called from within a
+    /// Tokio runtime.
+    pub fn spawn_exporter(&self) -> JoinHandle<()> {
+        let layer = self.clone();
+        tokio::spawn(async move {
+            let client = reqwest::Client::new();
+            let url = format!("{}/v1/traces", layer.config.endpoint.trim_end_matches('/'));
+            let mut interval = tokio::time::interval(layer.config.interval);
+            loop {
+                interval.tick().await;
+                let Some(request) = layer.export_request() else {
+                    continue;
+                };
+                let mut post = client.post(&url).json(&request);
+                for (name, value) in &layer.config.headers {
+                    post = post.header(name, value);
+                }
+                match post.send().await {
+                    Ok(response) if response.status().is_success() => {}
+                    Ok(response) => {
+                        tracing::warn!(
+                            "OTLP trace export to {} failed: {}",
+                            url,
+                            response.status()
+                        )
+                    }
+                    Err(e) => tracing::warn!("OTLP trace export to {} failed: {}", url, e),
+                }
+            }
+        })
+    }
+
+    /// Whether a new trace is exported, by its id so the decision is stable.
+    fn sampled(&self, trace_id: u128) -> bool {
+        let threshold = self.config.sampling_ratio * u64::MAX as f64;
+        ((trace_id >> 64) as u64 as f64) < threshold || self.config.sampling_ratio >= 1.0
+    }
+
+    /// The OTLP `ExportTraceServiceRequest` of the spans finished since the
+    /// last call, if any.
+    fn export_request(&self) -> Option<Value> {
+        let spans: Vec<FinishedSpan> = self.queue.lock().unwrap().drain(..).collect();
+        if spans.is_empty() {
+            return None;
+        }
+        let spans: Vec<Value> = spans
+            .iter()
+            .map(|span| {
+                let mut attributes: Vec<Value> = span
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } }))
+                    .collect();
+                attributes
+                    .push(json!({ "key": "target", "value": { "stringValue": span.target } }));
+                json!({
+                    "traceId": format!("{:032x}", span.context.trace_id),
+                    "spanId": format!("{:016x}", span.context.span_id),
+                    "parentSpanId": span
+                        .context
+                        .parent_id
+                        .map(|id| format!("{:016x}", id))
+                        .unwrap_or_default(),
+                    "name": span.name,
+                    "kind": SPAN_KIND_INTERNAL,
+                    "startTimeUnixNano": unix_nanos(span.start),
+                    "endTimeUnixNano": unix_nanos(span.end),
+                    "attributes": attributes,
+                })
+            })
+            .collect();
+        Some(json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [{
+                        "key": "service.name",
+                        "value": { "stringValue": self.config.service_name },
+                    }],
+                },
+                "scopeSpans": [{
+                    "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans,
+                }],
+            }],
+        }))
+    }
+}
+
+impl<S> Layer<S> for OtlpTraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let parent = span
+            .parent()
+            .and_then(|parent| parent.extensions().get::<SpanContext>().copied());
+        let span_id = Uuid::new_v4().as_u64_pair().0;
+        let context = match parent {
+            Some(parent) => SpanContext {
+                trace_id: parent.trace_id,
+                span_id,
+                parent_id: Some(parent.span_id),
+                sampled: parent.sampled,
+            },
+            None => {
+                let trace_id = Uuid::new_v4().as_u128();
+                SpanContext {
+                    trace_id,
+                    span_id,
+                    parent_id: None,
+                    sampled: self.sampled(trace_id),
+                }
+            }
+        };
+        let mut data = SpanData {
+            start: SystemTime::now(),
+            attributes: vec![],
+        };
+        attrs.record(&mut FieldVisitor(&mut data.attributes));
+
+        let mut extensions = span.extensions_mut();
+        extensions.insert(context);
+        extensions.insert(data);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(data) = span.extensions_mut().get_mut::<SpanData>() {
+                values.record(&mut FieldVisitor(&mut data.attributes));
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        let (Some(context), Some(data)) = (
+            extensions.get_mut::<SpanContext>().copied(),
+            extensions.remove::<SpanData>(),
+        ) else {
+            return;
+        };
+        if !context.sampled {
+            return;
+        }
+        let mut queue = self.queue.lock().unwrap();
+        if queue.len() < MAX_QUEUED_SPANS {
+            queue.push_back(FinishedSpan {
+                context,
+                name: span.name(),
+                target: span.metadata().target(),
+                start: data.start,
+                end: SystemTime::now(),
+                attributes: data.attributes,
+            });
+        }
+    }
+}
+
+/// Records span fields as string attributes.
+struct FieldVisitor<'a>(&'a mut Vec<(String, String)>);
+
+impl FieldVisitor<'_> {
+    fn set(&mut self, field: &Field, value: String) {
+        match self.0.iter_mut().find(|(key, _)| key == field.name()) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((field.name().to_string(), value)),
+        }
+    }
+}
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.set(field, format!("{:?}", value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace_context::{current_traceparent, set_remote_parent, TraceParent};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn traced<T>(layer: &OtlpTraceLayer, f: impl FnOnce() -> T) -> T {
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+        tracing::subscriber::with_default(subscriber, f)
+    }
+
+    #[test]
+    fn test_spans_exported_with_parents() {
+        let layer = OtlpTraceLayer::new(
+            OtlpTraceConfig::new("http://tempo:4318").with_service_name("orchestrator-node"),
+        );
+        let traceparent = traced(&layer, || {
+            let request = tracing::info_span!("request", method = "GET");
+            let _request = request.enter();
+            let schedule = tracing::info_span!("schedule", workload = tracing::field::Empty);
+            schedule.record("workload", "web");
+            let _schedule = schedule.enter();
+            current_traceparent().unwrap()
+        });
+
+        let request = layer.export_request().unwrap();
+        assert!(layer.export_request().is_none());
+        let resource = &request["resourceSpans"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "orchestrator-node"
+        );
+        let spans = resource["scopeSpans"][0]["spans"].as_array().unwrap();
+        let (schedule, request) = (&spans[0], &spans[1]);
+        assert_eq!(schedule["name"], "schedule");
+        assert_eq!(schedule["traceId"], request["traceId"]);
+        assert_eq!(schedule["parentSpanId"], request["spanId"]);
+        assert_eq!(request["parentSpanId"], "");
+        assert_eq!(schedule["attributes"][0]["key"], "workload");
+        assert_eq!(schedule["attributes"][0]["value"]["stringValue"], "web");
+        assert_eq!(request["attributes"][0]["value"]["stringValue"], "GET");
+
+        let parent = TraceParent::parse(&traceparent).unwrap();
+        assert_eq!(format!("{:032x}", parent.trace_id), schedule["traceId"]);
+        assert_eq!(format!("{:016x}", parent.parent_id), schedule["spanId"]);
+    }
+
+    #[test]
+    fn test_remote_parent_continues_trace() {
+        let layer = OtlpTraceLayer::new(OtlpTraceConfig::new("http://tempo:4318"));
+        traced(&layer, || {
+            let span = tracing::info_span!("request");
+            assert!(set_remote_parent(
+                &span,
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+            ));
+            let _span = span.enter();
+            tracing::info_span!("child").in_scope(|| {});
+
+            // Callers that did not sample the trace keep it unexported
+            let unsampled = tracing::info_span!(parent: None, "unsampled");
+            set_remote_parent(
+                &unsampled,
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00",
+            );
+        });
+
+        let request = layer.export_request().unwrap();
+        let spans = request["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap();
+        assert_eq!(spans.len(), 2);
+        for span in spans {
+            assert_eq!(span["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        }
+        assert_eq!(spans[1]["parentSpanId"], "00f067aa0ba902b7");
+        assert_eq!(spans[0]["parentSpanId"], spans[1]["spanId"]);
+    }
+
+    #[test]
+    fn test_sampling_ratio() {
+        let layer =
+            OtlpTraceLayer::new(OtlpTraceConfig::new("http://tempo:4318").with_sampling_ratio(0.0));
+        traced(&layer, || tracing::info_span!("request").in_scope(|| {}));
+        assert!(layer.export_request().is_none());
+
+        let half = OtlpTraceLayer::new(OtlpTraceConfig::new("x").with_sampling_ratio(0.5));
+        assert!(half.sampled(0x1000_0000_0000_0000_0000_0000_0000_0000));
+        assert!(!half.sampled(0xf000_0000_0000_0000_0000_0000_0000_0000));
+        assert_eq!(
+            OtlpTraceConfig::new("x")
+                .with_sampling_ratio(7.0)
+                .sampling_ratio,
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_parse_headers() {
+        assert_eq!(
+            OtlpTraceConfig::parse_headers("authorization=Bearer abc, x-scope-orgid=ops").unwrap(),
+            vec![
+                ("authorization".to_string(), "Bearer abc".to_string()),
+                ("x-scope-orgid".to_string(), "ops".to_string()),
+            ]
+        );
+        assert!(OtlpTraceConfig::parse_headers("novalue").is_err());
+        assert!(OtlpTraceConfig::parse_headers("").unwrap().is_empty());
+    }
+}
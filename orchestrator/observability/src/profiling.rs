@@ -0,0 +1,212 @@
+//! pprof-style CPU and heap profiling endpoints.
+//!
+//! - `GET /profile?seconds=30` samples the CPU of every thread for `seconds`
+//!   (default 30, at most 300) and returns the profile in pprof's protobuf
+//!   format, or as an SVG flamegraph with `format=flamegraph`.
+//! - `GET /heap` returns a pprof profile of live allocations sampled by
+//!   jemalloc. It needs the process to allocate through jemalloc with
+//!   profiling active (`prof:true` in `malloc_conf`), and to say so with
+//!   [`enable_heap_profiles`]; otherwise it answers `SERVICE_UNAVAILABLE`.
+//!
+//! The router has no authentication of its own; serve it behind one, e.g.
+//! under `/debug/pprof` of the REST API.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use axum::{
+    extract::Query,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use pprof::protos::Message;
+use serde::Deserialize;
+
+/// Sampling rate of CPU profiles, in Hz.
+const CPU_SAMPLE_FREQUENCY: i32 = 99;
+
+const DEFAULT_PROFILE_SECONDS: u64 = 30;
+const MAX_PROFILE_SECONDS: u64 = 300;
+
+/// Set while a CPU profile is taken; the profiler is process-wide.
+static CPU_PROFILING: AtomicBool = AtomicBool::new(false);
+
+/// Set once the process declared jemalloc its allocator.
+static HEAP_PROFILING: AtomicBool = AtomicBool::new(false);
+
+/// Serve heap profiles at `/heap`.
+///
+/// Call it from a binary whose global allocator is jemalloc. jemalloc's
+/// profiling controls panic when read in a process that does not allocate
+/// through it, so until then `/heap` answers without touching them.
+pub fn enable_heap_profiles() {
+    HEAP_PROFILING.store(true, Ordering::SeqCst);
+}
+
+/// Routes serving profiles, to be nested under a prefix such as
+/// `/debug/pprof`.
+pub fn router<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new()
+        .route("/profile", get(cpu_profile_handler))
+        .route("/heap", get(heap_profile_handler))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ProfileFormat {
+    #[default]
+    Pprof,
+    Flamegraph,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileQuery {
+    seconds: Option<u64>,
+    #[serde(default)]
+    format: ProfileFormat,
+}
+
+/// CPU profile endpoint handler.
+async fn cpu_profile_handler(Query(query): Query<ProfileQuery>) -> Response {
+    let seconds = query
+        .seconds
+        .unwrap_or(DEFAULT_PROFILE_SECONDS)
+        .clamp(1, MAX_PROFILE_SECONDS);
+    if CPU_PROFILING.swap(true, Ordering::SeqCst) {
+        return (StatusCode::CONFLICT, "A CPU profile is already being taken").into_response();
+    }
+
+    tracing::info!(seconds, "Taking CPU profile");
+    let result = tokio::task::spawn_blocking(move || {
+        cpu_profile(Duration::from_secs(seconds), query.format)
+    })
+    .await;
+    CPU_PROFILING.store(false, Ordering::SeqCst);
+
+    match result {
+        Ok(Ok((content_type, body))) => {
+            ([(header::CONTENT_TYPE, content_type)], body).into_response()
+        }
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Sample the CPU for `duration` and encode the report as `format`.
+fn cpu_profile(
+    duration: Duration,
+    format: ProfileFormat,
+) -> Result<(&'static str, Vec<u8>), String> {
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(CPU_SAMPLE_FREQUENCY)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(|e| format!("Failed to start profiler: {}", e))?;
+    std::thread::sleep(duration);
+    let report = guard
+        .report()
+        .build()
+        .map_err(|e| format!("Failed to build profile: {}", e))?;
+
+    let mut body = Vec::new();
+    match format {
+        ProfileFormat::Pprof => {
+            let profile = report
+                .pprof()
+                .map_err(|e| format!("Failed to encode profile: {}", e))?;
+            profile
+                .encode(&mut body)
+                .map_err(|e| format!("Failed to encode profile: {}", e))?;
+            Ok(("application/octet-stream", body))
+        }
+        ProfileFormat::Flamegraph => {
+            report
+                .flamegraph(&mut body)
+                .map_err(|e| format!("Failed to render flamegraph: {}", e))?;
+            Ok(("image/svg+xml", body))
+        }
+    }
+}
+
+/// Heap profile endpoint handler.
+async fn heap_profile_handler() -> Response {
+    if !HEAP_PROFILING.load(Ordering::SeqCst) {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Heap profiling needs the jemalloc allocator",
+        )
+            .into_response();
+    }
+    let Some(prof_ctl) = jemalloc_pprof::PROF_CTL.as_ref() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Heap profiling needs jemalloc with profiling enabled",
+        )
+            .into_response();
+    };
+    let mut prof_ctl = prof_ctl.lock().await;
+    if !prof_ctl.activated() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Heap profiling is not active",
+        )
+            .into_response();
+    }
+    match prof_ctl.dump_pprof() {
+        Ok(body) => ([(header::CONTENT_TYPE, "application/octet-stream")], body).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to dump heap profile: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_cpu_profile_formats() {
+        let router: Router = router();
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/profile?seconds=1&format=flamegraph")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/svg+xml");
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/profile?format=svg")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_heap_profile_without_jemalloc_profiling() {
+        // Tests allocate through the system allocator and never enable
+        // heap profiles
+        let response = router::<()>()
+            .oneshot(Request::builder().uri("/heap").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
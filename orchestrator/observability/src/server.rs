@@ -4,13 +4,14 @@
 //!
 //! # Endpoints
 //!
-//! - `GET /health` - Health check
+//! - `GET /health` - Health check, with the component dependency graph
 //! - `GET /healthz` - Kubernetes-style health check
-//! - `GET /ready` - Readiness probe
+//! - `GET /ready` - Readiness probe, not ready while a component has failed
 //! - `GET /readyz` - Kubernetes-style readiness
 //! - `GET /live` - Liveness probe
 //! - `GET /livez` - Kubernetes-style liveness
-//! - `GET /metrics` - Prometheus metrics
+//! - `GET /metrics` - Prometheus metrics (404 when another backend is configured)
+//! - `GET /alerts` - Pending and firing alerts (with the `alerting` feature)
 //! - `GET /api/v1/events` - WebSocket event streaming
 
 use axum::{
@@ -26,9 +27,11 @@ use tokio::sync::RwLock;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
+#[cfg(feature = "alerting")]
+use crate::alerts::AlertEngine;
 use crate::events::EventHub;
 use crate::health::{AggregatedHealth, HealthChecker};
-use crate::metrics::MetricsRegistry;
+use crate::metrics::{MetricSample, MetricsBackend, MetricsRegistry};
 use crate::websocket::events_handler;
 
 /// Configuration for the observability server.
@@ -40,6 +43,8 @@ pub struct ObservabilityConfig {
     pub enable_cors: bool,
     /// Enable request tracing.
     pub enable_tracing: bool,
+    /// Where metrics are exported.
+    pub metrics_backend: MetricsBackend,
 }
 
 impl Default for ObservabilityConfig {
@@ -48,6 +53,7 @@ impl Default for ObservabilityConfig {
             bind_addr: SocketAddr::from(([0, 0, 0, 0], 9090)),
             enable_cors: true,
             enable_tracing: true,
+            metrics_backend: MetricsBackend::default(),
         }
     }
 }
@@ -68,6 +74,12 @@ impl ObservabilityConfig {
             ..Default::default()
         }
     }
+
+    /// Export metrics through `backend` instead of Prometheus.
+    pub fn with_metrics_backend(mut self, backend: MetricsBackend) -> Self {
+        self.metrics_backend = backend;
+        self
+    }
 }
 
 /// Shared state for the observability server.
@@ -76,6 +88,8 @@ pub struct ObservabilityState {
     health_checker: HealthChecker,
     metrics_registry: Arc<RwLock<Option<MetricsRegistry>>>,
     event_hub: EventHub,
+    #[cfg(feature = "alerting")]
+    alert_engine: Option<Arc<AlertEngine>>,
 }
 
 impl ObservabilityState {
@@ -85,6 +99,8 @@ impl ObservabilityState {
             health_checker,
             metrics_registry: Arc::new(RwLock::new(None)),
             event_hub: EventHub::default(),
+            #[cfg(feature = "alerting")]
+            alert_engine: None,
         }
     }
 
@@ -94,6 +110,8 @@ impl ObservabilityState {
             health_checker,
             metrics_registry: Arc::new(RwLock::new(None)),
             event_hub,
+            #[cfg(feature = "alerting")]
+            alert_engine: None,
         }
     }
 
@@ -102,6 +120,16 @@ impl ObservabilityState {
         *self.metrics_registry.write().await = Some(registry);
     }
 
+    /// Samples of the metrics in the registry, empty without one.
+    pub async fn metric_samples(&self) -> Vec<MetricSample> {
+        self.metrics_registry
+            .read()
+            .await
+            .as_ref()
+            .map(MetricsRegistry::samples)
+            .unwrap_or_default()
+    }
+
     /// Get the health checker.
     pub fn health_checker(&self) -> &HealthChecker {
         &self.health_checker
@@ -156,10 +184,14 @@ impl ObservabilityServer {
 
     /// Set a custom event hub.
     pub fn with_event_hub(mut self, event_hub: EventHub) -> Self {
-        self.state = ObservabilityState::with_event_hub(
-            self.state.health_checker.clone(),
-            event_hub,
-        );
+        self.state.event_hub = event_hub;
+        self
+    }
+
+    /// Serve the alerts of `engine` at `/alerts`.
+    #[cfg(feature = "alerting")]
+    pub fn with_alert_engine(mut self, engine: Arc<AlertEngine>) -> Self {
+        self.state.alert_engine = Some(engine);
         self
     }
 
@@ -203,7 +235,7 @@ impl ObservabilityServer {
             .route("/events", get(events_ws_handler))
             .with_state(self.state.event_hub.clone());
 
-        let mut router = Router::new()
+        let router = Router::new()
             // Health endpoints
             .route("/health", get(health_handler))
             .route("/healthz", get(health_handler))
@@ -211,7 +243,10 @@ impl ObservabilityServer {
             .route("/readyz", get(ready_handler))
             .route("/live", get(live_handler))
             .route("/livez", get(live_handler))
-            .route("/metrics", get(metrics_handler))
+            .route("/metrics", get(metrics_handler));
+        #[cfg(feature = "alerting")]
+        let router = router.route("/alerts", get(alerts_handler));
+        let mut router = router
             .with_state(self.state.clone())
             // Nest the events API under /api/v1
             .nest("/api/v1", events_router);
@@ -243,7 +278,11 @@ impl ObservabilityServer {
             "Starting observability server"
         );
 
-        axum::serve(listener, router).await
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
     }
 
     /// Start the server in the background.
@@ -280,15 +319,17 @@ async fn live_handler(State(state): State<ObservabilityState>) -> impl IntoRespo
 /// Metrics endpoint handler (Prometheus format).
 async fn metrics_handler(State(state): State<ObservabilityState>) -> Response {
     let registry = state.metrics_registry.read().await;
-    match registry.as_ref() {
-        Some(reg) => {
-            let body = reg.render();
-            Response::builder()
-                .status(StatusCode::OK)
-                .header("content-type", "text/plain; version=0.0.4; charset=utf-8")
-                .body(body.into())
-                .unwrap()
-        }
+    match registry.as_ref().map(MetricsRegistry::render) {
+        Some(Some(body)) => Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "text/plain; version=0.0.4; charset=utf-8")
+            .body(body.into())
+            .unwrap(),
+        Some(None) => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header("content-type", "text/plain; charset=utf-8")
+            .body("# Metrics are pushed to the configured backend\n".into())
+            .unwrap(),
         None => Response::builder()
             .status(StatusCode::OK)
             .header("content-type", "text/plain; version=0.0.4; charset=utf-8")
@@ -297,6 +338,17 @@ async fn metrics_handler(State(state): State<ObservabilityState>) -> Response {
     }
 }
 
+/// Alerts endpoint handler.
+#[cfg(feature = "alerting")]
+async fn alerts_handler(State(state): State<ObservabilityState>) -> impl IntoResponse {
+    let alerts = state
+        .alert_engine
+        .as_ref()
+        .map(|engine| engine.alerts())
+        .unwrap_or_default();
+    Json(alerts)
+}
+
 /// WebSocket events endpoint handler.
 ///
 /// Accepts WebSocket upgrade requests and delegates to the events handler.
@@ -409,12 +461,44 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    #[cfg(feature = "alerting")]
+    #[tokio::test]
+    async fn test_alerts_endpoint() {
+        use crate::alerts::AlertRule;
+        use http_body_util::BodyExt;
+
+        let rule = AlertRule::parse("no_nodes", "orchestrator_nodes_total < 1").unwrap();
+        let engine = Arc::new(AlertEngine::new(vec![rule]));
+        let sample = MetricSample {
+            name: "orchestrator_nodes_total".to_string(),
+            labels: Default::default(),
+            value: 0.0,
+        };
+        engine.evaluate(&[sample], chrono::Utc::now());
+
+        let health_checker = HealthChecker::new("test", "1.0.0");
+        let mut server = ObservabilityServer::with_defaults(health_checker)
+            .with_alert_engine(engine);
+        let response = server
+            .router()
+            .oneshot(Request::builder().uri("/alerts").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let alerts: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(alerts[0]["rule"], "no_nodes");
+        assert_eq!(alerts[0]["state"], "firing");
+    }
+
     #[test]
     fn test_config_defaults() {
         let config = ObservabilityConfig::default();
         assert_eq!(config.bind_addr.port(), 9090);
         assert!(config.enable_cors);
         assert!(config.enable_tracing);
+        assert_eq!(config.metrics_backend, MetricsBackend::Prometheus);
     }
 
     #[test]
@@ -0,0 +1,198 @@
+//! Statsd metrics backend.
+//!
+//! Every counter increment, gauge change and histogram sample is sent to a
+//! statsd daemon as one UDP datagram. Labels become DogStatsD-style tags
+//! (`|#key:value`), which Datadog, Telegraf and the Prometheus statsd exporter
+//! understand. Histograms use the `h` type and keep the unit they are recorded
+//! in, so durations arrive in seconds.
+
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::sync::Arc;
+
+use metrics::{
+    Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+    SharedString, Unit,
+};
+
+use crate::metrics::{MetricsError, MetricsRecorder};
+
+/// Where and how to send statsd metrics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsdConfig {
+    /// Address of the statsd daemon.
+    pub addr: SocketAddr,
+    /// Prepended to every metric name, separated by a dot.
+    pub prefix: Option<String>,
+}
+
+impl StatsdConfig {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self { addr, prefix: None }
+    }
+
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+}
+
+/// Recorder that sends every metric update to statsd.
+#[derive(Clone)]
+pub struct StatsdRecorder {
+    socket: Arc<UdpSocket>,
+    prefix: Option<String>,
+}
+
+impl StatsdRecorder {
+    pub fn new(config: &StatsdConfig) -> Result<Self, MetricsError> {
+        let local: SocketAddr = if config.addr.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(config.addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket: Arc::new(socket),
+            prefix: config.prefix.clone(),
+        })
+    }
+
+    /// Install as the process-wide recorder.
+    pub fn install(self) -> Result<Self, MetricsError> {
+        metrics::set_global_recorder(self.clone()).map_err(|_| MetricsError::AlreadyInstalled)?;
+        Ok(self)
+    }
+
+    fn metric(&self, key: &Key, kind: &str) -> Arc<StatsdMetric> {
+        let name = match &self.prefix {
+            Some(prefix) => format!("{}.{}", prefix, key.name()),
+            None => key.name().to_string(),
+        };
+        let tags: Vec<String> = key
+            .labels()
+            .map(|label| format!("{}:{}", label.key(), label.value()))
+            .collect();
+        let suffix = if tags.is_empty() {
+            format!("|{}", kind)
+        } else {
+            format!("|{}|#{}", kind, tags.join(","))
+        };
+        Arc::new(StatsdMetric {
+            socket: self.socket.clone(),
+            name,
+            suffix,
+        })
+    }
+}
+
+impl MetricsRecorder for StatsdRecorder {}
+
+impl Recorder for StatsdRecorder {
+    // Statsd has nowhere to put descriptions
+    fn describe_counter(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn describe_gauge(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn describe_histogram(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _metadata: &Metadata<'_>) -> Counter {
+        Counter::from_arc(self.metric(key, "c"))
+    }
+
+    fn register_gauge(&self, key: &Key, _metadata: &Metadata<'_>) -> Gauge {
+        Gauge::from_arc(self.metric(key, "g"))
+    }
+
+    fn register_histogram(&self, key: &Key, _metadata: &Metadata<'_>) -> Histogram {
+        Histogram::from_arc(self.metric(key, "h"))
+    }
+}
+
+/// One metric, with its name and tags formatted once.
+struct StatsdMetric {
+    socket: Arc<UdpSocket>,
+    name: String,
+    suffix: String,
+}
+
+impl StatsdMetric {
+    fn send(&self, value: impl std::fmt::Display) {
+        let line = format!("{}:{}{}", self.name, value, self.suffix);
+        // Fire and forget, so a missing daemon never slows the orchestrator
+        let _ = self.socket.send(line.as_bytes());
+    }
+}
+
+impl CounterFn for StatsdMetric {
+    fn increment(&self, value: u64) {
+        self.send(value);
+    }
+
+    // Statsd counters only carry deltas
+    fn absolute(&self, _value: u64) {}
+}
+
+impl GaugeFn for StatsdMetric {
+    fn increment(&self, value: f64) {
+        self.send(format!("+{}", value));
+    }
+
+    fn decrement(&self, value: f64) {
+        self.send(format!("-{}", value));
+    }
+
+    fn set(&self, value: f64) {
+        // A signed value is read as a delta, so reset before going negative
+        if value < 0.0 {
+            self.send(0);
+        }
+        self.send(value);
+    }
+}
+
+impl HistogramFn for StatsdMetric {
+    fn record(&self, value: f64) {
+        self.send(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_statsd_lines() {
+        let daemon = UdpSocket::bind("127.0.0.1:0").unwrap();
+        daemon
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let config = StatsdConfig::new(daemon.local_addr().unwrap()).with_prefix("orch");
+        let recorder = StatsdRecorder::new(&config).unwrap();
+
+        metrics::with_local_recorder(&recorder, || {
+            metrics::counter!("requests_total", "tool" => "deploy").increment(2);
+            metrics::gauge!("queue_depth").set(-3.0);
+            metrics::histogram!("duration_seconds").record(0.25);
+        });
+
+        let mut buf = [0u8; 512];
+        let received: Vec<String> = (0..4)
+            .map(|_| {
+                let n = daemon.recv(&mut buf).unwrap();
+                String::from_utf8_lossy(&buf[..n]).into_owned()
+            })
+            .collect();
+        assert_eq!(
+            received,
+            [
+                "orch.requests_total:2|c|#tool:deploy",
+                "orch.queue_depth:0|g",
+                "orch.queue_depth:-3|g",
+                "orch.duration_seconds:0.25|h",
+            ]
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! W3C trace context of the current span.
+//!
+//! With OTLP trace export enabled, every span carries a [`SpanContext`] in its
+//! extensions. Outgoing calls pass the current one to the callee in a
+//! `traceparent` header from [`current_traceparent`], and the span handling an
+//! incoming call continues the caller's trace with [`set_remote_parent`].
+//! Without the export layer installed both do nothing.
+
+use std::fmt;
+
+use tracing::Span;
+use tracing_subscriber::registry::{LookupSpan, Registry};
+
+/// Header carrying the trace context of a call.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// A parsed `traceparent` value, e.g.
+/// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceParent {
+    pub trace_id: u128,
+    /// The calling span.
+    pub parent_id: u64,
+    pub sampled: bool,
+}
+
+impl TraceParent {
+    /// Parse a version 00 `traceparent`; all-zero ids are invalid.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split('-');
+        let (version, trace_id, parent_id, flags) =
+            (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        if version != "00" || parts.next().is_some() {
+            return None;
+        }
+        if trace_id.len() != 32 || parent_id.len() != 16 || flags.len() != 2 {
+            return None;
+        }
+        let trace_id = u128::from_str_radix(trace_id, 16).ok()?;
+        let parent_id = u64::from_str_radix(parent_id, 16).ok()?;
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+        if trace_id == 0 || parent_id == 0 {
+            return None;
+        }
+        Some(Self {
+            trace_id,
+            parent_id,
+            sampled: flags & 1 == 1,
+        })
+    }
+}
+
+impl fmt::Display for TraceParent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "00-{:032x}-{:016x}-{:02x}",
+            self.trace_id, self.parent_id, self.sampled as u8
+        )
+    }
+}
+
+/// Where a span sits in its trace.
+#[cfg_attr(not(feature = "otlp"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SpanContext {
+    pub trace_id: u128,
+    pub span_id: u64,
+    pub parent_id: Option<u64>,
+    /// Whether the trace is exported.
+    pub sampled: bool,
+}
+
+/// The `traceparent` of the current span, to pass on to outgoing calls.
+pub fn current_traceparent() -> Option<String> {
+    Span::current()
+        .with_subscriber(|(id, dispatch)| {
+            let span = dispatch.downcast_ref::<Registry>()?.span(id)?;
+            let context = *span.extensions().get::<SpanContext>()?;
+            Some(
+                TraceParent {
+                    trace_id: context.trace_id,
+                    parent_id: context.span_id,
+                    sampled: context.sampled,
+                }
+                .to_string(),
+            )
+        })
+        .flatten()
+}
+
+/// Make `span` continue the trace of a caller that sent `traceparent`. Must
+/// be called before any child of `span` is created. Returns whether the
+/// value was valid and the span is exported.
+pub fn set_remote_parent(span: &Span, traceparent: &str) -> bool {
+    let Some(parent) = TraceParent::parse(traceparent) else {
+        return false;
+    };
+    span.with_subscriber(|(id, dispatch)| {
+        let span = dispatch.downcast_ref::<Registry>()?.span(id)?;
+        let mut extensions = span.extensions_mut();
+        let context = extensions.get_mut::<SpanContext>()?;
+        context.trace_id = parent.trace_id;
+        context.parent_id = Some(parent.parent_id);
+        context.sampled = parent.sampled;
+        Some(())
+    })
+    .flatten()
+    .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_round_trip() {
+        let value = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let parent = TraceParent::parse(value).unwrap();
+        assert_eq!(parent.trace_id, 0x4bf92f3577b34da6a3ce929d0e0e4736);
+        assert_eq!(parent.parent_id, 0x00f067aa0ba902b7);
+        assert!(parent.sampled);
+        assert_eq!(parent.to_string(), value);
+
+        for invalid in [
+            "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6-00f067aa0ba902b7-01",
+            "not a traceparent",
+        ] {
+            assert_eq!(TraceParent::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_no_context_without_export_layer() {
+        let span = tracing::info_span!("request");
+        let _entered = span.enter();
+        assert_eq!(current_traceparent(), None);
+        assert!(!set_remote_parent(
+            &span,
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        ));
+    }
+}
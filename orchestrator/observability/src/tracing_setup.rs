@@ -1,8 +1,11 @@
 //! Tracing configuration and initialization.
 //!
-//! Provides structured logging with span-based context propagation.
+//! Provides structured logging with span-based context propagation, and
+//! with the `otlp` feature, export of spans to an OpenTelemetry collector.
 
 use tracing::Level;
+#[cfg(feature = "otlp")]
+use crate::otlp_trace::{OtlpTraceConfig, OtlpTraceLayer};
 use tracing_subscriber::{
     fmt::{self, format::FmtSpan},
     layer::SubscriberExt,
@@ -27,6 +30,9 @@ pub struct TracingConfig {
     pub include_thread_ids: bool,
     /// Whether to include target (module path)
     pub include_target: bool,
+    /// Collector to export spans to (None = local output only)
+    #[cfg(feature = "otlp")]
+    pub otlp: Option<OtlpTraceConfig>,
 }
 
 impl Default for TracingConfig {
@@ -39,6 +45,8 @@ impl Default for TracingConfig {
             include_location: true,
             include_thread_ids: false,
             include_target: true,
+            #[cfg(feature = "otlp")]
+            otlp: None,
         }
     }
 }
@@ -70,6 +78,13 @@ impl TracingConfig {
         self
     }
 
+    /// Export spans over OTLP as well.
+    #[cfg(feature = "otlp")]
+    pub fn with_otlp(mut self, otlp: OtlpTraceConfig) -> Self {
+        self.otlp = Some(otlp);
+        self
+    }
+
     /// Build an EnvFilter from this config.
     fn build_filter(&self) -> EnvFilter {
         EnvFilter::try_from_default_env().unwrap_or_else(|_| {
@@ -80,7 +95,8 @@ impl TracingConfig {
 
 /// Initialize tracing with the given configuration.
 ///
-/// This should be called once at application startup.
+/// This should be called once at application startup. Exporting spans over
+/// OTLP needs a Tokio runtime; without one they are only logged.
 ///
 /// # Example
 ///
@@ -93,6 +109,18 @@ impl TracingConfig {
 pub fn init_tracing(config: TracingConfig) {
     let filter = config.build_filter();
 
+    #[cfg(feature = "otlp")]
+    let otlp_layer = config.otlp.clone().and_then(|otlp| {
+        let layer = OtlpTraceLayer::new(otlp);
+        tokio::runtime::Handle::try_current().ok()?;
+        layer.spawn_exporter();
+        Some(layer)
+    });
+    #[cfg(feature = "otlp")]
+    let otlp_exporting = otlp_layer.is_some();
+    #[cfg(not(feature = "otlp"))]
+    let otlp_layer: Option<tracing_subscriber::layer::Identity> = None;
+
     let span_events = if config.include_span_events {
         FmtSpan::NEW | FmtSpan::CLOSE
     } else {
@@ -112,6 +140,7 @@ pub fn init_tracing(config: TracingConfig) {
         tracing_subscriber::registry()
             .with(filter)
             .with(fmt_layer)
+            .with(otlp_layer)
             .init();
     } else {
         // Human-readable format for development
@@ -125,6 +154,7 @@ pub fn init_tracing(config: TracingConfig) {
         tracing_subscriber::registry()
             .with(filter)
             .with(fmt_layer)
+            .with(otlp_layer)
             .init();
     }
 
@@ -133,6 +163,16 @@ pub fn init_tracing(config: TracingConfig) {
         level = %config.log_level,
         "Tracing initialized"
     );
+    #[cfg(feature = "otlp")]
+    match &config.otlp {
+        Some(otlp) if otlp_exporting => tracing::info!(
+            endpoint = %otlp.endpoint,
+            sampling_ratio = otlp.sampling_ratio,
+            "Exporting traces over OTLP"
+        ),
+        Some(_) => tracing::warn!("OTLP trace export needs a Tokio runtime; spans are only logged"),
+        None => {}
+    }
 }
 
 /// Initialize tracing with default configuration.
@@ -215,6 +255,15 @@ mod tests {
         assert!(config.include_span_events);
     }
 
+    #[cfg(feature = "otlp")]
+    #[test]
+    fn test_tracing_config_otlp() {
+        let config = TracingConfig::new("node")
+            .with_otlp(OtlpTraceConfig::new("http://tempo:4318").with_sampling_ratio(0.25));
+        assert_eq!(config.otlp.unwrap().sampling_ratio, 0.25);
+        assert!(TracingConfig::default().otlp.is_none());
+    }
+
     #[test]
     fn test_env_filter_building() {
         let config = TracingConfig::default();
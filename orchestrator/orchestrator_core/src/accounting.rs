@@ -0,0 +1,465 @@
+//! Resource accounting for chargeback.
+//!
+//! The [`UsageMeter`] runs on the leader and, on an interval, charges each
+//! workload for what its instances request while they hold it on a node: CPU
+//! cores, memory and GPUs, times the time since the previous pass. Instances
+//! that succeeded or failed hold nothing, as for the scheduler. Charges
+//! accumulate in hourly [`UsageRecord`]s in the state store, which outlive the
+//! workloads they cover.
+//!
+//! A [`UsageReport`] totals the records of a time range per namespace and per
+//! workload in core-hours, GB-hours and GPU-hours. Records cover whole hours,
+//! so a report includes all of the hour its range starts in.
+//!
+//! A meter charges nothing for the time before its first pass, so an interval
+//! or so is lost whenever leadership changes.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+
+use orchestrator_shared_types::{
+    Result, UsageRecord, WorkloadDefinition, WorkloadId, WorkloadInstanceStatus,
+};
+use state_store_interface::StateStore;
+
+const SECONDS_PER_HOUR: f64 = 3600.0;
+
+/// What a workload's instances hold at once.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Holding {
+    cpu_cores: f64,
+    memory_gb: f64,
+    gpus: f64,
+}
+
+impl Holding {
+    /// What `instances` replicas of `workload` hold.
+    fn of(workload: &WorkloadDefinition, instances: usize) -> Self {
+        let mut replica = Self {
+            // Invalid requests are rejected by the API, so none are running
+            gpus: workload
+                .gpu_requests()
+                .unwrap_or_default()
+                .iter()
+                .map(|r| r.gpus())
+                .sum(),
+            ..Default::default()
+        };
+        for container in &workload.containers {
+            replica.cpu_cores += f64::from(container.resource_requests.cpu_cores);
+            replica.memory_gb += container.resource_requests.memory_mb as f64 / 1024.0;
+        }
+        let replicas = instances as f64;
+        Self {
+            cpu_cores: replica.cpu_cores * replicas,
+            memory_gb: replica.memory_gb * replicas,
+            gpus: replica.gpus * replicas,
+        }
+    }
+
+    fn charge(&self, record: &mut UsageRecord, seconds: f64) {
+        record.cpu_core_seconds += self.cpu_cores * seconds;
+        record.memory_gb_seconds += self.memory_gb * seconds;
+        record.gpu_seconds += self.gpus * seconds;
+    }
+}
+
+/// Periodically charges workloads for the resources their instances hold.
+pub struct UsageMeter {
+    state_store: Arc<dyn StateStore>,
+    last_metered: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl UsageMeter {
+    /// How often [`spawn`](Self::spawn) meters when run with defaults.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+    pub fn new(state_store: Arc<dyn StateStore>) -> Self {
+        Self {
+            state_store,
+            last_metered: Mutex::new(None),
+        }
+    }
+
+    /// Charge workloads for the time since the previous pass.
+    pub async fn meter(&self) -> Result<()> {
+        self.meter_at(Utc::now()).await
+    }
+
+    /// Charge workloads for the time from the previous pass to `now`.
+    pub async fn meter_at(&self, now: DateTime<Utc>) -> Result<()> {
+        let Some(since) = self.last_metered.lock().await.replace(now) else {
+            return Ok(());
+        };
+        let (from_ms, to_ms) = (millis(since), millis(now));
+        if from_ms >= to_ms {
+            return Ok(());
+        }
+
+        let mut instances: HashMap<WorkloadId, usize> = HashMap::new();
+        for instance in self.state_store.list_all_instances().await? {
+            if !matches!(
+                instance.status,
+                WorkloadInstanceStatus::Succeeded | WorkloadInstanceStatus::Failed
+            ) {
+                *instances.entry(instance.workload_id).or_default() += 1;
+            }
+        }
+        let holdings: Vec<(WorkloadDefinition, Holding)> = self
+            .state_store
+            .list_workloads()
+            .await?
+            .into_iter()
+            .filter_map(|w| {
+                let count = *instances.get(&w.id)?;
+                let holding = Holding::of(&w, count);
+                Some((w, holding))
+            })
+            .filter(|(_, holding)| *holding != Holding::default())
+            .collect();
+        if holdings.is_empty() {
+            return Ok(());
+        }
+
+        // Split the time between the hours it spans
+        let mut start_ms = from_ms;
+        while start_ms < to_ms {
+            let period = UsageRecord::period_start(start_ms);
+            let end_ms = (period + UsageRecord::PERIOD_MS).min(to_ms);
+            let seconds = (end_ms - start_ms) as f64 / 1000.0;
+
+            let mut records: HashMap<WorkloadId, UsageRecord> = self
+                .state_store
+                .list_usage_records(period, period + 1)
+                .await?
+                .into_iter()
+                .map(|r| (r.workload_id, r))
+                .collect();
+            for (workload, holding) in &holdings {
+                let mut record = records.remove(&workload.id).unwrap_or(UsageRecord {
+                    workload_id: workload.id,
+                    workload_name: String::new(),
+                    namespace: String::new(),
+                    period_start_ms: period,
+                    cpu_core_seconds: 0.0,
+                    memory_gb_seconds: 0.0,
+                    gpu_seconds: 0.0,
+                });
+                record.workload_name = workload.name.clone();
+                record.namespace = workload.namespace.clone();
+                holding.charge(&mut record, seconds);
+                self.state_store.put_usage_record(record).await?;
+            }
+            start_ms = end_ms;
+        }
+        debug!(
+            "Metered {} workload(s) for {}s",
+            holdings.len(),
+            (to_ms - from_ms) / 1000
+        );
+        Ok(())
+    }
+
+    /// Meter every `interval` until the task is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            // Time before this task started was metered elsewhere, if at all
+            *self.last_metered.lock().await = None;
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.meter().await {
+                    error!("Usage metering failed: {:?}", e);
+                }
+            }
+        })
+    }
+}
+
+fn millis(at: DateTime<Utc>) -> u64 {
+    at.timestamp_millis().max(0) as u64
+}
+
+/// Resources held over time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct ResourceHours {
+    pub cpu_core_hours: f64,
+    pub memory_gb_hours: f64,
+    pub gpu_hours: f64,
+}
+
+impl ResourceHours {
+    fn add(&mut self, record: &UsageRecord) {
+        self.cpu_core_hours += record.cpu_core_seconds / SECONDS_PER_HOUR;
+        self.memory_gb_hours += record.memory_gb_seconds / SECONDS_PER_HOUR;
+        self.gpu_hours += record.gpu_seconds / SECONDS_PER_HOUR;
+    }
+}
+
+/// What the workloads of one namespace held.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct NamespaceUsage {
+    pub namespace: String,
+    #[serde(flatten)]
+    pub usage: ResourceHours,
+}
+
+/// What one workload held.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct WorkloadUsage {
+    #[cfg_attr(feature = "rest-api", schema(value_type = uuid::Uuid))]
+    pub workload_id: WorkloadId,
+    /// Name of the workload as last metered.
+    pub workload_name: String,
+    pub namespace: String,
+    #[serde(flatten)]
+    pub usage: ResourceHours,
+}
+
+/// Resources held between two times, per namespace and per workload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct UsageReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub total: ResourceHours,
+    /// By namespace name.
+    pub namespaces: Vec<NamespaceUsage>,
+    /// By namespace, then workload name.
+    pub workloads: Vec<WorkloadUsage>,
+}
+
+impl UsageReport {
+    /// Read the records of the hours from `from` to `to`, of one namespace's
+    /// workloads or all of them, and total them.
+    pub async fn build(
+        state_store: &dyn StateStore,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        namespace: Option<&str>,
+    ) -> Result<Self> {
+        let records: Vec<UsageRecord> = state_store
+            .list_usage_records(UsageRecord::period_start(millis(from)), millis(to))
+            .await?
+            .into_iter()
+            .filter(|r| namespace.is_none_or(|ns| ns == r.namespace))
+            .collect();
+        Ok(Self::from_records(from, to, &records))
+    }
+
+    /// Total `records`, which should cover `from` to `to`.
+    pub fn from_records(from: DateTime<Utc>, to: DateTime<Utc>, records: &[UsageRecord]) -> Self {
+        let mut total = ResourceHours::default();
+        let mut namespaces: BTreeMap<&str, ResourceHours> = BTreeMap::new();
+        // The latest record of each workload names it
+        let mut workloads: HashMap<WorkloadId, (&UsageRecord, ResourceHours)> = HashMap::new();
+        for record in records {
+            total.add(record);
+            namespaces
+                .entry(record.namespace.as_str())
+                .or_default()
+                .add(record);
+            let (latest, usage) = workloads
+                .entry(record.workload_id)
+                .or_insert((record, ResourceHours::default()));
+            if record.period_start_ms > latest.period_start_ms {
+                *latest = record;
+            }
+            usage.add(record);
+        }
+
+        let mut workloads: Vec<WorkloadUsage> = workloads
+            .into_iter()
+            .map(|(workload_id, (latest, usage))| WorkloadUsage {
+                workload_id,
+                workload_name: latest.workload_name.clone(),
+                namespace: latest.namespace.clone(),
+                usage,
+            })
+            .collect();
+        workloads.sort_by(|a, b| {
+            (&a.namespace, &a.workload_name, a.workload_id).cmp(&(
+                &b.namespace,
+                &b.workload_name,
+                b.workload_id,
+            ))
+        });
+        Self {
+            from,
+            to,
+            total,
+            namespaces: namespaces
+                .into_iter()
+                .map(|(namespace, usage)| NamespaceUsage {
+                    namespace: namespace.to_string(),
+                    usage,
+                })
+                .collect(),
+            workloads,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use chrono::TimeZone;
+    use orchestrator_shared_types::{
+        ContainerConfig, GpuRequest, Keypair, NodeResources, WorkloadInstance,
+    };
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use uuid::Uuid;
+
+    fn workload(name: &str, namespace: &str, cpu: f32, memory_mb: u64) -> WorkloadDefinition {
+        WorkloadDefinition {
+            containers: vec![ContainerConfig {
+                resource_requests: NodeResources {
+                    cpu_cores: cpu,
+                    memory_mb,
+                    disk_mb: 0,
+                },
+                ..test_support::container(name)
+            }],
+            replicas: 2,
+            namespace: namespace.to_string(),
+            ..test_support::workload(name)
+        }
+    }
+
+    async fn run(
+        store: &InMemoryStateStore,
+        workload: &WorkloadDefinition,
+        status: WorkloadInstanceStatus,
+    ) {
+        store
+            .put_instance(WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id: workload.id,
+                node_id: Keypair::generate().public_key(),
+                container_ids: vec![],
+                status,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: workload.namespace.clone(),
+                resource_version: 0,
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_meter_charges_held_resources_per_hour() {
+        use WorkloadInstanceStatus::*;
+
+        let store = Arc::new(InMemoryStateStore::new());
+        // Two running 0.5-core, 1 GiB replicas and one that failed
+        let web = workload("web", "shop", 0.5, 1024);
+        store.put_workload(web.clone()).await.unwrap();
+        run(&store, &web, Running).await;
+        run(&store, &web, CrashLoopBackOff).await;
+        run(&store, &web, Failed).await;
+        // One replica with half a GPU
+        let mut train = workload("train", "ml", 2.0, 4096);
+        train.containers[0]
+            .annotations
+            .insert(GpuRequest::ANNOTATION.to_string(), "0.5".to_string());
+        store.put_workload(train.clone()).await.unwrap();
+        run(&store, &train, Running).await;
+        // Nothing running
+        store
+            .put_workload(workload("idle", "shop", 1.0, 1024))
+            .await
+            .unwrap();
+
+        let meter = UsageMeter::new(store.clone());
+        let start = Utc.with_ymd_and_hms(2026, 3, 1, 9, 30, 0).unwrap();
+        let at = |minutes: i64| start + chrono::Duration::minutes(minutes);
+        // The first pass only starts the clock
+        meter.meter_at(at(0)).await.unwrap();
+        assert!(store
+            .list_usage_records(0, u64::MAX)
+            .await
+            .unwrap()
+            .is_empty());
+        // 9:30 to 10:15 splits into 30 and 15 minutes
+        meter.meter_at(at(45)).await.unwrap();
+        meter.meter_at(at(45)).await.unwrap();
+
+        let ten = millis(at(30));
+        let records = store.list_usage_records(0, u64::MAX).await.unwrap();
+        assert_eq!(records.len(), 4);
+        let record = |workload: &WorkloadDefinition, period: u64| {
+            records
+                .iter()
+                .find(|r| r.workload_id == workload.id && r.period_start_ms == period)
+                .unwrap()
+        };
+        let web_before = record(&web, ten - UsageRecord::PERIOD_MS);
+        assert_eq!(web_before.namespace, "shop");
+        assert_eq!(web_before.cpu_core_seconds, 1800.0);
+        assert_eq!(web_before.memory_gb_seconds, 3600.0);
+        assert_eq!(web_before.gpu_seconds, 0.0);
+        let train_after = record(&train, ten);
+        assert_eq!(train_after.cpu_core_seconds, 1800.0);
+        assert_eq!(train_after.gpu_seconds, 450.0);
+
+        let report = UsageReport::build(&*store, at(0), at(45), None)
+            .await
+            .unwrap();
+        assert_eq!(report.total.cpu_core_hours, 0.75 + 1.5);
+        assert_eq!(
+            report
+                .namespaces
+                .iter()
+                .map(|n| (n.namespace.as_str(), n.usage.gpu_hours))
+                .collect::<Vec<_>>(),
+            [("ml", 0.375), ("shop", 0.0)]
+        );
+        assert_eq!(report.workloads.len(), 2);
+        assert_eq!(report.workloads[1].workload_name, "web");
+        assert_eq!(report.workloads[1].usage.memory_gb_hours, 1.5);
+
+        let report = UsageReport::build(&*store, at(0), at(45), Some("ml"))
+            .await
+            .unwrap();
+        assert_eq!(report.total.cpu_core_hours, 1.5);
+        assert_eq!(report.workloads.len(), 1);
+    }
+
+    #[test]
+    fn test_report_names_workloads_as_last_metered() {
+        let id = Uuid::new_v4();
+        let record = |period: u64, name: &str, namespace: &str| UsageRecord {
+            workload_id: id,
+            workload_name: name.to_string(),
+            namespace: namespace.to_string(),
+            period_start_ms: period * UsageRecord::PERIOD_MS,
+            cpu_core_seconds: 3600.0,
+            memory_gb_seconds: 0.0,
+            gpu_seconds: 0.0,
+        };
+        let now = Utc::now();
+        let report = UsageReport::from_records(
+            now,
+            now,
+            &[record(2, "api", "prod"), record(1, "web", "staging")],
+        );
+        assert_eq!(report.total.cpu_core_hours, 2.0);
+        assert_eq!(report.namespaces.len(), 2);
+        assert_eq!(report.workloads.len(), 1);
+        assert_eq!(report.workloads[0].workload_name, "api");
+        assert_eq!(report.workloads[0].namespace, "prod");
+        assert_eq!(report.workloads[0].usage.cpu_core_hours, 2.0);
+    }
+}
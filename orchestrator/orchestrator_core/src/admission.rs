@@ -0,0 +1,738 @@
+//! Control-plane admission limits.
+//!
+//! Every workload is kept in memory by the state store and fanned out to each
+//! watcher, so an unbounded number of objects (or a single enormous spec) can
+//! exhaust a node. [`AdmissionLimits`] caps the object count per namespace, the
+//! containers per workload, and the serialized spec size, and rejects
+//! offending writes before they are stored.
+//!
+//! Admission also fills in per-namespace [`ResourceDefaults`], in the manner
+//! of a Kubernetes LimitRange: containers that leave a resource request at
+//! zero get the namespace default, so the scheduler never bin-packs
+//! workloads that claim to need nothing, and requests above the namespace
+//! maximum are rejected.
+//!
+//! Built-in policy rules can further require labels on every workload, limit
+//! images to allowed registries, and require every container to set CPU and
+//! memory requests, which the runtime also enforces as its limits. With a
+//! maximum image severity set, images with known vulnerabilities above it are
+//! rejected, going by the runtime's [`ImageScan`] of each image. The API can
+//! add external admission webhooks; see `api::webhook`.
+
+use std::collections::HashMap;
+
+use container_runtime_interface::{ImageScan, Severity};
+use orchestrator_shared_types::{ContainerConfig, NodeResources, WorkloadDefinition};
+use thiserror::Error;
+
+/// Namespace key whose [`ResourceDefaults`] apply to namespaces without
+/// their own.
+pub const ALL_NAMESPACES: &str = "*";
+
+/// Default maximum number of workloads in one namespace.
+pub const DEFAULT_MAX_WORKLOADS_PER_NAMESPACE: usize = 1000;
+
+/// Default maximum containers (init, sidecar and main) in one workload.
+pub const DEFAULT_MAX_CONTAINERS_PER_WORKLOAD: usize = 32;
+
+/// Default maximum serialized size of one workload spec, in bytes.
+pub const DEFAULT_MAX_SPEC_BYTES: usize = 256 * 1024;
+
+/// Resource defaults and maximums for each container of a namespace.
+///
+/// Zero fields are unset: a zero default leaves the request alone and a
+/// zero maximum does not cap it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ResourceDefaults {
+    /// Filled into each resource a container requests zero of.
+    pub default_requests: NodeResources,
+    /// Largest request one container may make.
+    pub max_requests: NodeResources,
+}
+
+impl ResourceDefaults {
+    pub fn new(default_requests: NodeResources) -> Self {
+        Self {
+            default_requests,
+            max_requests: NodeResources::default(),
+        }
+    }
+
+    pub fn with_max_requests(mut self, max_requests: NodeResources) -> Self {
+        self.max_requests = max_requests;
+        self
+    }
+
+    fn apply(&self, container: &mut ContainerConfig) {
+        let requests = &mut container.resource_requests;
+        if requests.cpu_cores <= 0.0 {
+            requests.cpu_cores = self.default_requests.cpu_cores;
+        }
+        if requests.memory_mb == 0 {
+            requests.memory_mb = self.default_requests.memory_mb;
+        }
+        if requests.disk_mb == 0 {
+            requests.disk_mb = self.default_requests.disk_mb;
+        }
+    }
+
+    fn check(&self, container: &ContainerConfig) -> Result<(), AdmissionError> {
+        let requests = &container.resource_requests;
+        let max = &self.max_requests;
+        let exceeded = [
+            ("cpu_cores", requests.cpu_cores as f64, max.cpu_cores as f64),
+            ("memory_mb", requests.memory_mb as f64, max.memory_mb as f64),
+            ("disk_mb", requests.disk_mb as f64, max.disk_mb as f64),
+        ]
+        .into_iter()
+        .find(|&(_, requested, limit)| limit > 0.0 && requested > limit);
+
+        match exceeded {
+            Some((resource, requested, limit)) => Err(AdmissionError::ResourceLimitExceeded {
+                container: container.name.clone(),
+                resource,
+                requested,
+                limit,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Parses `cpu=0.25,memory_mb=256,disk_mb=0,max_cpu=2,max_memory_mb=4096`;
+    /// keys left out stay unset.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut defaults = Self::default();
+        for pair in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got '{}'", pair))?;
+            let invalid = || format!("invalid value for {}: '{}'", key, value);
+            match key.trim() {
+                "cpu" => {
+                    defaults.default_requests.cpu_cores = value.parse().map_err(|_| invalid())?
+                }
+                "memory_mb" => {
+                    defaults.default_requests.memory_mb = value.parse().map_err(|_| invalid())?
+                }
+                "disk_mb" => {
+                    defaults.default_requests.disk_mb = value.parse().map_err(|_| invalid())?
+                }
+                "max_cpu" => {
+                    defaults.max_requests.cpu_cores = value.parse().map_err(|_| invalid())?
+                }
+                "max_memory_mb" => {
+                    defaults.max_requests.memory_mb = value.parse().map_err(|_| invalid())?
+                }
+                "max_disk_mb" => {
+                    defaults.max_requests.disk_mb = value.parse().map_err(|_| invalid())?
+                }
+                other => return Err(format!("unknown resource default '{}'", other)),
+            }
+        }
+        Ok(defaults)
+    }
+}
+
+/// Parses per-namespace defaults such as
+/// `*:cpu=0.1,memory_mb=128;ml:cpu=1,memory_mb=4096,max_memory_mb=65536`,
+/// where `*` is [`ALL_NAMESPACES`].
+pub fn parse_resource_defaults(spec: &str) -> Result<HashMap<String, ResourceDefaults>, String> {
+    spec.split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (namespace, defaults) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("expected namespace:defaults, got '{}'", entry))?;
+            Ok((
+                namespace.trim().to_string(),
+                ResourceDefaults::parse(defaults)?,
+            ))
+        })
+        .collect()
+}
+
+/// Why a workload was rejected at admission.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum AdmissionError {
+    /// The namespace already holds the maximum number of workloads.
+    #[error("namespace '{namespace}' already has {limit} workloads, the maximum allowed")]
+    TooManyWorkloads { namespace: String, limit: usize },
+
+    /// The workload declares more containers than allowed.
+    #[error("workload has {count} containers, exceeding the limit of {limit}")]
+    TooManyContainers { count: usize, limit: usize },
+
+    /// The serialized workload spec is larger than allowed.
+    #[error("workload spec is {size} bytes, exceeding the limit of {limit} bytes")]
+    SpecTooLarge { size: usize, limit: usize },
+
+    /// A container requests more of a resource than its namespace allows.
+    #[error("container '{container}' requests {requested} {resource}, exceeding the namespace maximum of {limit}")]
+    ResourceLimitExceeded {
+        container: String,
+        resource: &'static str,
+        requested: f64,
+        limit: f64,
+    },
+
+    /// The workload lacks a label every workload must carry.
+    #[error("workload is missing the required label '{label}'")]
+    MissingLabel { label: String },
+
+    /// A container's image is not from an allowed registry.
+    #[error("image '{image}' of container '{container}' is not from an allowed registry")]
+    ImageNotAllowed { container: String, image: String },
+
+    /// A container leaves its CPU or memory unbounded.
+    #[error("container '{container}' must request {resource}")]
+    ResourcesRequired {
+        container: String,
+        resource: &'static str,
+    },
+
+    /// A container's image has vulnerabilities above the allowed severity.
+    #[error("image '{image}' of container '{container}' has {count} known vulnerabilities above {max} severity, the worst {severity}")]
+    VulnerableImage {
+        container: String,
+        image: String,
+        count: usize,
+        severity: Severity,
+        max: Severity,
+    },
+}
+
+impl AdmissionError {
+    /// Machine-readable code clients can branch on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AdmissionError::TooManyWorkloads { .. } => "QUOTA_EXCEEDED",
+            AdmissionError::TooManyContainers { .. } => "TOO_MANY_CONTAINERS",
+            AdmissionError::SpecTooLarge { .. } => "SPEC_TOO_LARGE",
+            AdmissionError::ResourceLimitExceeded { .. } => "LIMIT_EXCEEDED",
+            AdmissionError::MissingLabel { .. } => "MISSING_LABEL",
+            AdmissionError::ImageNotAllowed { .. } => "IMAGE_NOT_ALLOWED",
+            AdmissionError::ResourcesRequired { .. } => "RESOURCES_REQUIRED",
+            AdmissionError::VulnerableImage { .. } => "VULNERABLE_IMAGE",
+        }
+    }
+}
+
+/// Configurable caps enforced when workloads are created or updated.
+///
+/// A limit of `None` disables that check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdmissionLimits {
+    pub max_workloads_per_namespace: Option<usize>,
+    pub max_containers_per_workload: Option<usize>,
+    pub max_spec_bytes: Option<usize>,
+    /// Container resource defaults by namespace, or [`ALL_NAMESPACES`].
+    pub resource_defaults: HashMap<String, ResourceDefaults>,
+    /// Labels every workload must carry.
+    pub required_labels: Vec<String>,
+    /// Registries, optionally with a repository path such as
+    /// `ghcr.io/univrs`, images must come from; empty allows any.
+    pub allowed_registries: Vec<String>,
+    /// Whether every container must request CPU and memory, after
+    /// namespace defaults are filled in.
+    pub require_resource_requests: bool,
+    /// Most severe known vulnerability an image may have; `None` skips
+    /// image scans.
+    pub max_image_severity: Option<Severity>,
+}
+
+impl Default for AdmissionLimits {
+    fn default() -> Self {
+        Self {
+            max_workloads_per_namespace: Some(DEFAULT_MAX_WORKLOADS_PER_NAMESPACE),
+            max_containers_per_workload: Some(DEFAULT_MAX_CONTAINERS_PER_WORKLOAD),
+            max_spec_bytes: Some(DEFAULT_MAX_SPEC_BYTES),
+            resource_defaults: HashMap::new(),
+            required_labels: Vec::new(),
+            allowed_registries: Vec::new(),
+            require_resource_requests: false,
+            max_image_severity: None,
+        }
+    }
+}
+
+impl AdmissionLimits {
+    /// Limits with every check disabled.
+    pub fn unlimited() -> Self {
+        Self {
+            max_workloads_per_namespace: None,
+            max_containers_per_workload: None,
+            max_spec_bytes: None,
+            resource_defaults: HashMap::new(),
+            required_labels: Vec::new(),
+            allowed_registries: Vec::new(),
+            require_resource_requests: false,
+            max_image_severity: None,
+        }
+    }
+
+    pub fn with_max_workloads_per_namespace(mut self, limit: usize) -> Self {
+        self.max_workloads_per_namespace = Some(limit);
+        self
+    }
+
+    pub fn with_max_containers_per_workload(mut self, limit: usize) -> Self {
+        self.max_containers_per_workload = Some(limit);
+        self
+    }
+
+    pub fn with_max_spec_bytes(mut self, limit: usize) -> Self {
+        self.max_spec_bytes = Some(limit);
+        self
+    }
+
+    /// Apply `defaults` to the containers of `namespace`, which may be
+    /// [`ALL_NAMESPACES`].
+    pub fn with_resource_defaults(
+        mut self,
+        namespace: impl Into<String>,
+        defaults: ResourceDefaults,
+    ) -> Self {
+        self.resource_defaults.insert(namespace.into(), defaults);
+        self
+    }
+
+    pub fn with_required_labels(mut self, labels: Vec<String>) -> Self {
+        self.required_labels = labels;
+        self
+    }
+
+    pub fn with_allowed_registries(mut self, registries: Vec<String>) -> Self {
+        self.allowed_registries = registries;
+        self
+    }
+
+    pub fn with_resource_requests_required(mut self) -> Self {
+        self.require_resource_requests = true;
+        self
+    }
+
+    pub fn with_max_image_severity(mut self, severity: Severity) -> Self {
+        self.max_image_severity = Some(severity);
+        self
+    }
+
+    /// Resource defaults that apply to `namespace`, if any.
+    pub fn resource_defaults_for(&self, namespace: &str) -> Option<&ResourceDefaults> {
+        self.resource_defaults
+            .get(namespace)
+            .or_else(|| self.resource_defaults.get(ALL_NAMESPACES))
+    }
+
+    /// Fills in the namespace's default requests for every container
+    /// (init, sidecar and main) that leaves them at zero.
+    pub fn apply_defaults(&self, workload: &mut WorkloadDefinition) {
+        let Some(defaults) = self.resource_defaults_for(&workload.namespace).cloned() else {
+            return;
+        };
+        for container in workload
+            .containers
+            .iter_mut()
+            .chain(workload.init_containers.iter_mut())
+            .chain(workload.sidecars.iter_mut())
+        {
+            defaults.apply(container);
+        }
+    }
+
+    /// Checks the limits that depend only on the workload itself.
+    pub fn check_spec(&self, workload: &WorkloadDefinition) -> Result<(), AdmissionError> {
+        if let Some(limit) = self.max_containers_per_workload {
+            let count = workload.containers.len()
+                + workload.init_containers.len()
+                + workload.sidecars.len();
+            if count > limit {
+                return Err(AdmissionError::TooManyContainers { count, limit });
+            }
+        }
+
+        if let Some(limit) = self.max_spec_bytes {
+            // WorkloadDefinition only holds plain data, so serialization cannot fail.
+            let size = serde_json::to_vec(workload).map(|b| b.len()).unwrap_or(0);
+            if size > limit {
+                return Err(AdmissionError::SpecTooLarge { size, limit });
+            }
+        }
+
+        if let Some(defaults) = self.resource_defaults_for(&workload.namespace) {
+            workload
+                .containers
+                .iter()
+                .chain(&workload.init_containers)
+                .chain(&workload.sidecars)
+                .try_for_each(|c| defaults.check(c))?;
+        }
+
+        self.check_policy(workload)
+    }
+
+    /// Checks the built-in policy rules: required labels, allowed registries
+    /// and mandatory resource requests.
+    pub fn check_policy(&self, workload: &WorkloadDefinition) -> Result<(), AdmissionError> {
+        if let Some(label) = self
+            .required_labels
+            .iter()
+            .find(|label| !workload.labels.contains_key(*label))
+        {
+            return Err(AdmissionError::MissingLabel {
+                label: label.clone(),
+            });
+        }
+
+        for container in workload
+            .containers
+            .iter()
+            .chain(&workload.init_containers)
+            .chain(&workload.sidecars)
+        {
+            if !self.allowed_registries.is_empty()
+                && !self
+                    .allowed_registries
+                    .iter()
+                    .any(|allowed| image_is_from(&container.image, allowed))
+            {
+                return Err(AdmissionError::ImageNotAllowed {
+                    container: container.name.clone(),
+                    image: container.image.clone(),
+                });
+            }
+
+            if self.require_resource_requests {
+                let requests = &container.resource_requests;
+                let missing = if requests.cpu_cores <= 0.0 {
+                    Some("cpu_cores")
+                } else if requests.memory_mb == 0 {
+                    Some("memory_mb")
+                } else {
+                    None
+                };
+                if let Some(resource) = missing {
+                    return Err(AdmissionError::ResourcesRequired {
+                        container: container.name.clone(),
+                        resource,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks the scan of `container`'s image against the maximum image
+    /// severity.
+    pub fn check_image_scan(
+        &self,
+        container: &ContainerConfig,
+        scan: &ImageScan,
+    ) -> Result<(), AdmissionError> {
+        let Some(max) = self.max_image_severity else {
+            return Ok(());
+        };
+        match scan.count_above(max) {
+            0 => Ok(()),
+            count => Err(AdmissionError::VulnerableImage {
+                container: container.name.clone(),
+                image: container.image.clone(),
+                count,
+                severity: scan.highest_severity().unwrap_or_default(),
+                max,
+            }),
+        }
+    }
+
+    /// Checks whether `workload` may be stored alongside `existing`.
+    ///
+    /// An entry in `existing` with the same id is the workload being replaced
+    /// and does not count towards the namespace limit.
+    pub fn admit(
+        &self,
+        workload: &WorkloadDefinition,
+        existing: &[WorkloadDefinition],
+    ) -> Result<(), AdmissionError> {
+        self.check_spec(workload)?;
+
+        if let Some(limit) = self.max_workloads_per_namespace {
+            let namespace = &workload.namespace;
+            let count = existing
+                .iter()
+                .filter(|w| w.id != workload.id && &w.namespace == namespace)
+                .count();
+            if count >= limit {
+                return Err(AdmissionError::TooManyWorkloads {
+                    namespace: namespace.clone(),
+                    limit,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `image` comes from `allowed`, a registry host optionally followed
+/// by a repository path. Images without a registry host are from Docker Hub,
+/// `docker.io`, and single-name images from its `library` path.
+pub fn image_is_from(image: &str, allowed: &str) -> bool {
+    let name = image.split('@').next().unwrap_or(image);
+    let (host, path) = match name.split_once('/') {
+        Some((host, path)) if host.contains('.') || host.contains(':') || host == "localhost" => {
+            (host, path.to_string())
+        }
+        Some(_) => ("docker.io", name.to_string()),
+        None => ("docker.io", format!("library/{}", name)),
+    };
+    // Drop the tag, which follows the last path segment
+    let path = match path.rsplit_once('/') {
+        Some((dir, last)) => format!("{}/{}", dir, last.split(':').next().unwrap_or(last)),
+        None => path.split(':').next().unwrap_or(&path).to_string(),
+    };
+    let full = format!("{}/{}", host, path);
+    let allowed = allowed.trim_end_matches('/');
+    full == allowed || full.starts_with(&format!("{}/", allowed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use container_runtime_interface::Vulnerability;
+    use orchestrator_shared_types::{ContainerConfig, DEFAULT_NAMESPACE};
+
+    fn container(name: &str) -> ContainerConfig {
+        ContainerConfig {
+            image: "nginx:latest".to_string(),
+            ..test_support::container(name)
+        }
+    }
+
+    fn workload(namespace: Option<&str>, containers: usize) -> WorkloadDefinition {
+        WorkloadDefinition {
+            containers: (0..containers)
+                .map(|i| container(&format!("c{}", i)))
+                .collect(),
+            namespace: namespace.unwrap_or(DEFAULT_NAMESPACE).to_string(),
+            ..test_support::workload("web")
+        }
+    }
+
+    #[test]
+    fn test_namespace_workload_limit() {
+        let limits = AdmissionLimits::unlimited().with_max_workloads_per_namespace(2);
+        let existing = vec![
+            workload(None, 1),
+            workload(None, 1),
+            workload(Some("team-a"), 1),
+        ];
+
+        let err = limits.admit(&workload(None, 1), &existing).unwrap_err();
+        assert_eq!(err.code(), "QUOTA_EXCEEDED");
+        assert!(err.to_string().contains("'default'"));
+
+        // Another namespace still has room.
+        assert!(limits
+            .admit(&workload(Some("team-a"), 1), &existing)
+            .is_ok());
+
+        // Replacing an existing workload does not count it twice.
+        assert!(limits.admit(&existing[0], &existing).is_ok());
+    }
+
+    #[test]
+    fn test_container_limit_counts_all_kinds() {
+        let limits = AdmissionLimits::unlimited().with_max_containers_per_workload(2);
+        let mut w = workload(None, 2);
+        assert!(limits.check_spec(&w).is_ok());
+
+        w.sidecars.push(container("proxy"));
+        assert_eq!(
+            limits.check_spec(&w),
+            Err(AdmissionError::TooManyContainers { count: 3, limit: 2 })
+        );
+    }
+
+    #[test]
+    fn test_spec_size_limit() {
+        let limits = AdmissionLimits::unlimited().with_max_spec_bytes(2048);
+        let mut w = workload(None, 1);
+        assert!(limits.check_spec(&w).is_ok());
+
+        w.containers[0]
+            .env_vars
+            .insert("BLOB".to_string(), "x".repeat(4096));
+        let err = limits.check_spec(&w).unwrap_err();
+        assert_eq!(err.code(), "SPEC_TOO_LARGE");
+    }
+
+    #[test]
+    fn test_resource_defaults_fill_unset_requests() {
+        let limits = AdmissionLimits::unlimited()
+            .with_resource_defaults(
+                ALL_NAMESPACES,
+                ResourceDefaults::new(NodeResources {
+                    cpu_cores: 0.1,
+                    memory_mb: 128,
+                    disk_mb: 0,
+                }),
+            )
+            .with_resource_defaults(
+                "ml",
+                ResourceDefaults::new(NodeResources {
+                    cpu_cores: 1.0,
+                    memory_mb: 4096,
+                    disk_mb: 1024,
+                }),
+            );
+
+        let mut w = workload(None, 2);
+        w.containers[1].resource_requests.memory_mb = 512;
+        w.sidecars.push(container("proxy"));
+        limits.apply_defaults(&mut w);
+        assert_eq!(w.containers[0].resource_requests.cpu_cores, 0.1);
+        assert_eq!(w.containers[0].resource_requests.memory_mb, 128);
+        assert_eq!(w.containers[0].resource_requests.disk_mb, 0);
+        // Explicit requests are kept
+        assert_eq!(w.containers[1].resource_requests.memory_mb, 512);
+        assert_eq!(w.sidecars[0].resource_requests.memory_mb, 128);
+
+        let mut w = workload(Some("ml"), 1);
+        limits.apply_defaults(&mut w);
+        assert_eq!(w.containers[0].resource_requests.memory_mb, 4096);
+    }
+
+    #[test]
+    fn test_resource_maximum() {
+        let limits = AdmissionLimits::unlimited().with_resource_defaults(
+            "team-a",
+            ResourceDefaults::parse("cpu=0.5,max_cpu=2,max_memory_mb=1024").unwrap(),
+        );
+
+        let mut w = workload(Some("team-a"), 1);
+        limits.apply_defaults(&mut w);
+        assert!(limits.check_spec(&w).is_ok());
+
+        w.containers[0].resource_requests.memory_mb = 2048;
+        let err = limits.check_spec(&w).unwrap_err();
+        assert_eq!(err.code(), "LIMIT_EXCEEDED");
+        assert!(err.to_string().contains("memory_mb"));
+
+        // Other namespaces are not capped
+        let mut w = workload(None, 1);
+        w.containers[0].resource_requests.memory_mb = 2048;
+        assert!(limits.check_spec(&w).is_ok());
+    }
+
+    #[test]
+    fn test_policy_rules() {
+        let limits = AdmissionLimits::unlimited()
+            .with_required_labels(vec!["team".to_string()])
+            .with_allowed_registries(vec!["ghcr.io/univrs".to_string(), "docker.io".to_string()])
+            .with_resource_requests_required();
+
+        let mut w = workload(None, 1);
+        let err = limits.check_spec(&w).unwrap_err();
+        assert_eq!(
+            err,
+            AdmissionError::MissingLabel {
+                label: "team".to_string()
+            }
+        );
+
+        w.labels.insert("team".to_string(), "core".to_string());
+        assert_eq!(
+            limits.check_spec(&w).unwrap_err().code(),
+            "RESOURCES_REQUIRED"
+        );
+
+        w.containers[0].resource_requests.cpu_cores = 0.5;
+        w.containers[0].resource_requests.memory_mb = 256;
+        assert!(limits.check_spec(&w).is_ok());
+
+        w.containers[0].image = "quay.io/evil/miner:latest".to_string();
+        assert_eq!(
+            limits.check_spec(&w).unwrap_err().code(),
+            "IMAGE_NOT_ALLOWED"
+        );
+        w.containers[0].image = "ghcr.io/univrs/web:1.2".to_string();
+        assert!(limits.check_spec(&w).is_ok());
+    }
+
+    #[test]
+    fn test_image_scan_severity() {
+        let scan = |severities: &[Severity]| ImageScan {
+            image: "nginx:latest".to_string(),
+            digest: "sha256:abc".to_string(),
+            scanner: "trivy".to_string(),
+            scanned_at: std::time::SystemTime::now(),
+            vulnerabilities: severities
+                .iter()
+                .enumerate()
+                .map(|(i, &severity)| Vulnerability {
+                    id: format!("CVE-2024-{}", i),
+                    package: "openssl".to_string(),
+                    installed_version: "3.0.0".to_string(),
+                    fixed_version: None,
+                    severity,
+                })
+                .collect(),
+        };
+        let c = container("web");
+        let vulnerable = scan(&[Severity::Low, Severity::High, Severity::Critical]);
+
+        // No maximum: scans are not consulted
+        assert!(AdmissionLimits::unlimited()
+            .check_image_scan(&c, &vulnerable)
+            .is_ok());
+
+        let limits = AdmissionLimits::unlimited().with_max_image_severity(Severity::Medium);
+        assert_eq!(
+            limits.check_image_scan(&c, &vulnerable).unwrap_err(),
+            AdmissionError::VulnerableImage {
+                container: "web".to_string(),
+                image: "nginx:latest".to_string(),
+                count: 2,
+                severity: Severity::Critical,
+                max: Severity::Medium,
+            }
+        );
+        assert!(limits
+            .check_image_scan(&c, &scan(&[Severity::Low, Severity::Medium]))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_image_registries() {
+        assert!(image_is_from("nginx:latest", "docker.io"));
+        assert!(image_is_from("nginx", "docker.io/library/nginx"));
+        assert!(image_is_from("bitnami/redis:7", "docker.io/bitnami"));
+        assert!(image_is_from("localhost:5000/app:dev", "localhost:5000"));
+        assert!(image_is_from(
+            "ghcr.io/univrs/web@sha256:abc",
+            "ghcr.io/univrs/"
+        ));
+        assert!(!image_is_from("ghcr.io/univrs-fork/web", "ghcr.io/univrs"));
+        assert!(!image_is_from("ghcr.io/univrs/web", "docker.io"));
+        assert!(!image_is_from("nginx:latest", "docker.io/bitnami"));
+    }
+
+    #[test]
+    fn test_parse_resource_defaults() {
+        let defaults = ResourceDefaults::parse("cpu=0.25, memory_mb=256,max_disk_mb=10").unwrap();
+        assert_eq!(defaults.default_requests.cpu_cores, 0.25);
+        assert_eq!(defaults.default_requests.memory_mb, 256);
+        assert_eq!(defaults.max_requests.disk_mb, 10);
+
+        assert!(ResourceDefaults::parse("gpu=1").is_err());
+        assert!(ResourceDefaults::parse("cpu").is_err());
+        assert!(ResourceDefaults::parse("memory_mb=lots").is_err());
+
+        let by_namespace = parse_resource_defaults("*:memory_mb=128; ml:cpu=1").unwrap();
+        assert_eq!(by_namespace[ALL_NAMESPACES].default_requests.memory_mb, 128);
+        assert_eq!(by_namespace["ml"].default_requests.cpu_cores, 1.0);
+        assert!(parse_resource_defaults("cpu=1").is_err());
+    }
+}
@@ -0,0 +1,533 @@
+//! Anomaly detection on workload metrics.
+//!
+//! An [`AnomalyDetector`] watches the samples of a node's
+//! [`UsageScraper`](crate::usage::UsageScraper) and learns, for each
+//! container of each workload, a baseline of every [`AnomalyMetric`]: CPU
+//! cores, memory, restarts per minute and error lines logged per minute.
+//! Baselines are exponentially weighted means and variances shared by the
+//! workload's instances on the node.
+//!
+//! Once a baseline has learned from `warmup` readings, a reading more than
+//! `threshold` standard deviations from its mean makes the instance abnormal.
+//! An `AbnormalBehavior` warning is recorded on the instance when it becomes
+//! abnormal and a `BehaviorNormal` event once all its readings are back
+//! within bounds; `orch status --detailed` shows instances whose latest such
+//! event is the warning. Every reading's deviation is also handed to an
+//! [`AnomalyObserver`], e.g. exported as a metric for alert rules.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use orchestrator_shared_types::ObjectReference;
+
+use crate::events::EventRecorder;
+use crate::usage::{ContainerSample, UsageObserver};
+
+/// Log lines counted as errors when none are configured.
+pub const DEFAULT_ERROR_PATTERN: &str = r"(?i)\b(error|fatal|panic|exception)\b";
+
+/// Reason of the event recorded when an instance becomes abnormal.
+pub const ABNORMAL_REASON: &str = "AbnormalBehavior";
+/// Reason of the event recorded when an abnormal instance recovers.
+pub const NORMAL_REASON: &str = "BehaviorNormal";
+
+/// Minutes state of containers and workloads no longer sampled is kept.
+const FORGET_AFTER_MINUTES: i64 = 60;
+
+/// A metric baselines are learned for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AnomalyMetric {
+    /// CPU cores in use.
+    Cpu,
+    /// Working set bytes.
+    Memory,
+    /// Restarts per minute.
+    Restarts,
+    /// Error lines logged per minute.
+    LogErrors,
+}
+
+impl AnomalyMetric {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Memory => "memory",
+            Self::Restarts => "restarts",
+            Self::LogErrors => "log_errors",
+        }
+    }
+
+    /// Smallest standard deviation a baseline is given, so a metric that
+    /// barely moved while learning is not flagged for noise.
+    fn min_deviation(self) -> f64 {
+        match self {
+            Self::Cpu => 0.05,
+            Self::Memory => 16.0 * 1024.0 * 1024.0,
+            Self::Restarts => 0.1,
+            Self::LogErrors => 1.0,
+        }
+    }
+
+    fn format(self, value: f64) -> String {
+        match self {
+            Self::Cpu => format!("{:.2} cores", value),
+            Self::Memory => format!("{:.0} MiB", value / (1024.0 * 1024.0)),
+            Self::Restarts => format!("{:.2} restarts/min", value),
+            Self::LogErrors => format!("{:.1} errors/min", value),
+        }
+    }
+}
+
+/// When readings count as abnormal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnomalyConfig {
+    /// Distance from the baseline mean, in standard deviations, beyond which
+    /// a reading is abnormal (default: 4).
+    pub threshold: f64,
+    /// Readings a baseline learns from before it flags any (default: 20,
+    /// five minutes of scrapes).
+    pub warmup: u32,
+    /// Weight of each new reading in a baseline (default: 0.05).
+    pub alpha: f64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 4.0,
+            warmup: 20,
+            alpha: 0.05,
+        }
+    }
+}
+
+/// Receives how far each reading is from its baseline.
+pub trait AnomalyObserver: Send + Sync {
+    /// `sigmas` is the signed distance from the baseline mean in standard
+    /// deviations, 0 while the baseline is still learning.
+    fn on_score(&self, sample: &ContainerSample, metric: AnomalyMetric, sigmas: f64);
+}
+
+#[cfg(feature = "observability")]
+impl AnomalyObserver for observability::OrchestratorMetrics {
+    fn on_score(&self, sample: &ContainerSample, metric: AnomalyMetric, sigmas: f64) {
+        self.record_container_anomaly_score(
+            &sample.namespace,
+            &sample.workload,
+            &sample.instance_id.to_string(),
+            &sample.container,
+            metric.name(),
+            sigmas,
+        );
+    }
+}
+
+/// Exponentially weighted mean and variance of a metric.
+#[derive(Debug, Clone, PartialEq)]
+struct Baseline {
+    mean: f64,
+    variance: f64,
+    readings: u32,
+    updated_at: DateTime<Utc>,
+}
+
+impl Baseline {
+    fn new(at: DateTime<Utc>) -> Self {
+        Self {
+            mean: 0.0,
+            variance: 0.0,
+            readings: 0,
+            updated_at: at,
+        }
+    }
+
+    fn deviation(&self, metric: AnomalyMetric) -> f64 {
+        self.variance.sqrt().max(metric.min_deviation())
+    }
+
+    fn update(&mut self, value: f64, alpha: f64, at: DateTime<Utc>) {
+        if self.readings == 0 {
+            self.mean = value;
+        } else {
+            let diff = value - self.mean;
+            let step = alpha * diff;
+            self.mean += step;
+            self.variance = (1.0 - alpha) * (self.variance + diff * step);
+        }
+        self.readings = self.readings.saturating_add(1);
+        self.updated_at = at;
+    }
+}
+
+/// What the detector remembers of a container between readings.
+#[derive(Debug, Clone)]
+struct ContainerState {
+    at: DateTime<Utc>,
+    cpu_usage_ns: u64,
+    restarts: u32,
+}
+
+/// A workload container's baseline of one metric.
+type BaselineKey = (String, String, String, AnomalyMetric);
+
+#[derive(Default)]
+struct DetectorState {
+    baselines: HashMap<BaselineKey, Baseline>,
+    containers: HashMap<(Uuid, String), ContainerState>,
+    /// Per abnormal instance, its abnormal readings by container and metric.
+    abnormal: HashMap<Uuid, BTreeMap<(String, AnomalyMetric), String>>,
+    last_pruned: Option<DateTime<Utc>>,
+}
+
+/// An instance that became abnormal, or recovered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnomalyTransition {
+    pub instance_id: Uuid,
+    pub namespace: String,
+    pub abnormal: bool,
+    pub message: String,
+}
+
+impl AnomalyTransition {
+    /// Record the transition as an event on the instance.
+    pub async fn record(&self, events: &EventRecorder) {
+        let instance = self.instance_id.to_string();
+        let object = ObjectReference {
+            kind: "Instance".to_string(),
+            id: instance.clone(),
+            name: instance,
+        };
+        let message = self.message.clone();
+        if self.abnormal {
+            events
+                .warning(object, &self.namespace, ABNORMAL_REASON, message)
+                .await
+        } else {
+            events
+                .normal(object, &self.namespace, NORMAL_REASON, message)
+                .await
+        }
+    }
+}
+
+/// Learns per-workload baselines and flags instances that deviate from them.
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    events: Option<Arc<EventRecorder>>,
+    observer: Option<Arc<dyn AnomalyObserver>>,
+    state: Mutex<DetectorState>,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config,
+            events: None,
+            observer: None,
+            state: Mutex::new(DetectorState::default()),
+        }
+    }
+
+    /// Record transitions as events on the instances.
+    pub fn with_events(mut self, events: Arc<EventRecorder>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Report every reading's deviation to `observer`.
+    pub fn with_observer(mut self, observer: Arc<dyn AnomalyObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Instances currently abnormal.
+    pub fn abnormal_instances(&self) -> Vec<Uuid> {
+        self.state
+            .lock()
+            .unwrap()
+            .abnormal
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    /// Learn from a sample taken at `at`. Returns the transition when the
+    /// sample makes its instance abnormal or the last abnormal reading of the
+    /// instance returns to normal.
+    pub fn observe(
+        &self,
+        sample: &ContainerSample,
+        at: DateTime<Utc>,
+    ) -> Option<AnomalyTransition> {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        if state
+            .last_pruned
+            .is_none_or(|pruned| at - pruned > Duration::minutes(1))
+        {
+            state.prune(at);
+        }
+
+        let current = ContainerState {
+            at,
+            cpu_usage_ns: sample.usage.cpu_usage_ns,
+            restarts: sample.restarts,
+        };
+        let previous = state.containers.insert(
+            (sample.instance_id, sample.container.clone()),
+            current.clone(),
+        );
+
+        let mut readings = vec![(
+            AnomalyMetric::Memory,
+            sample.usage.memory_working_set_bytes as f64,
+        )];
+        if let Some(previous) = previous {
+            let elapsed = (at - previous.at).num_milliseconds() as f64 / 1000.0;
+            if elapsed > 0.0 {
+                let per_minute = |count: f64| count * 60.0 / elapsed;
+                // A lower count means the container was replaced
+                if let Some(cpu_ns) = current.cpu_usage_ns.checked_sub(previous.cpu_usage_ns) {
+                    readings.push((AnomalyMetric::Cpu, cpu_ns as f64 / 1e9 / elapsed));
+                }
+                let restarts = current.restarts.saturating_sub(previous.restarts);
+                readings.push((AnomalyMetric::Restarts, per_minute(f64::from(restarts))));
+                if let Some(errors) = sample.log_errors {
+                    readings.push((AnomalyMetric::LogErrors, per_minute(errors as f64)));
+                }
+            }
+        }
+
+        let was_abnormal = state.abnormal.contains_key(&sample.instance_id);
+        for (metric, value) in readings {
+            let key = (
+                sample.namespace.clone(),
+                sample.workload.clone(),
+                sample.container.clone(),
+                metric,
+            );
+            let baseline = state
+                .baselines
+                .entry(key)
+                .or_insert_with(|| Baseline::new(at));
+            let sigmas = if baseline.readings >= self.config.warmup {
+                (value - baseline.mean) / baseline.deviation(metric)
+            } else {
+                0.0
+            };
+            if let Some(observer) = &self.observer {
+                observer.on_score(sample, metric, sigmas);
+            }
+
+            let reading = (sample.container.clone(), metric);
+            if sigmas.abs() > self.config.threshold {
+                let description = format!(
+                    "container {} {} at {}, baseline {} ± {} ({:+.1}σ)",
+                    sample.container,
+                    metric.name(),
+                    metric.format(value),
+                    metric.format(baseline.mean),
+                    metric.format(baseline.deviation(metric)),
+                    sigmas
+                );
+                state
+                    .abnormal
+                    .entry(sample.instance_id)
+                    .or_default()
+                    .insert(reading, description);
+            } else if let Some(abnormal) = state.abnormal.get_mut(&sample.instance_id) {
+                abnormal.remove(&reading);
+                if abnormal.is_empty() {
+                    state.abnormal.remove(&sample.instance_id);
+                }
+            }
+            baseline.update(value, self.config.alpha, at);
+        }
+
+        let message = match (was_abnormal, state.abnormal.get(&sample.instance_id)) {
+            (false, Some(abnormal)) => format!(
+                "Instance {} is behaving abnormally: {}",
+                sample.instance_id,
+                abnormal.values().cloned().collect::<Vec<_>>().join("; ")
+            ),
+            (true, None) => format!("Instance {} is behaving normally again", sample.instance_id),
+            _ => return None,
+        };
+        Some(AnomalyTransition {
+            instance_id: sample.instance_id,
+            namespace: sample.namespace.clone(),
+            abnormal: !was_abnormal,
+            message,
+        })
+    }
+}
+
+impl DetectorState {
+    /// Forget containers and baselines not sampled for a while.
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let forget_after = Duration::minutes(FORGET_AFTER_MINUTES);
+        self.containers
+            .retain(|_, container| now - container.at < forget_after);
+        self.baselines
+            .retain(|_, baseline| now - baseline.updated_at < forget_after);
+        let containers = &self.containers;
+        self.abnormal.retain(|instance_id, _| {
+            containers
+                .keys()
+                .any(|(instance, _)| instance == instance_id)
+        });
+        self.last_pruned = Some(now);
+    }
+}
+
+impl UsageObserver for AnomalyDetector {
+    fn on_sample(&self, sample: &ContainerSample) {
+        let Some(transition) = self.observe(sample, Utc::now()) else {
+            return;
+        };
+        if transition.abnormal {
+            tracing::warn!("{}", transition.message);
+        } else {
+            tracing::info!("{}", transition.message);
+        }
+        if let Some(events) = self.events.clone() {
+            tokio::spawn(async move { transition.record(&events).await });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use container_runtime_interface::ContainerUsage;
+    use orchestrator_shared_types::{EventType, Keypair};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+
+    fn sample(
+        instance_id: Uuid,
+        cpu_seconds: f64,
+        memory_mb: u64,
+        log_errors: u64,
+    ) -> ContainerSample {
+        ContainerSample {
+            namespace: "shop".to_string(),
+            workload: "web".to_string(),
+            instance_id,
+            container: "web".to_string(),
+            node_id: Keypair::generate().public_key(),
+            usage: ContainerUsage {
+                cpu_usage_ns: (cpu_seconds * 1e9) as u64,
+                memory_working_set_bytes: memory_mb * 1024 * 1024,
+                oom_kills: 0,
+            },
+            restarts: 0,
+            log_errors: Some(log_errors),
+        }
+    }
+
+    #[test]
+    fn test_instances_deviating_from_the_baseline_are_abnormal() {
+        let detector = AnomalyDetector::new(AnomalyConfig::default());
+        let (steady, noisy) = (Uuid::new_v4(), Uuid::new_v4());
+        let start = Utc::now();
+        let at = |scrape: i64| start + Duration::seconds(15 * scrape);
+
+        // Both instances use half a core and about 200 MiB while learning
+        let mut cpu = [0.0, 0.0];
+        for scrape in 0..30 {
+            for (i, instance) in [steady, noisy].into_iter().enumerate() {
+                cpu[i] += 7.5;
+                let memory = 200 + (scrape % 3) as u64;
+                assert_eq!(
+                    detector.observe(&sample(instance, cpu[i], memory, 0), at(scrape)),
+                    None
+                );
+            }
+        }
+
+        // One starts logging errors and spinning on four cores
+        cpu[0] += 7.5;
+        assert_eq!(
+            detector.observe(&sample(steady, cpu[0], 201, 0), at(30)),
+            None
+        );
+        cpu[1] += 60.0;
+        let transition = detector
+            .observe(&sample(noisy, cpu[1], 201, 50), at(30))
+            .unwrap();
+        assert!(transition.abnormal);
+        assert_eq!(transition.namespace, "shop");
+        assert!(transition
+            .message
+            .starts_with(&format!("Instance {} is behaving abnormally: ", noisy)));
+        assert!(transition
+            .message
+            .contains("container web cpu at 4.00 cores"));
+        assert!(transition
+            .message
+            .contains("log_errors at 200.0 errors/min"));
+        assert_eq!(detector.abnormal_instances(), vec![noisy]);
+
+        // Still abnormal: no new transition
+        cpu[1] += 60.0;
+        assert_eq!(
+            detector.observe(&sample(noisy, cpu[1], 201, 50), at(31)),
+            None
+        );
+
+        cpu[1] += 7.5;
+        let transition = detector
+            .observe(&sample(noisy, cpu[1], 200, 0), at(32))
+            .unwrap();
+        assert!(!transition.abnormal);
+        assert_eq!(
+            transition.message,
+            format!("Instance {} is behaving normally again", noisy)
+        );
+        assert!(detector.abnormal_instances().is_empty());
+    }
+
+    #[test]
+    fn test_nothing_is_flagged_while_learning() {
+        let detector = AnomalyDetector::new(AnomalyConfig {
+            warmup: 5,
+            ..Default::default()
+        });
+        let instance = Uuid::new_v4();
+        let start = Utc::now();
+        for (scrape, memory) in [100, 4000, 100, 4000, 100].into_iter().enumerate() {
+            let at = start + Duration::seconds(15 * scrape as i64);
+            assert_eq!(
+                detector.observe(&sample(instance, 0.0, memory, 0), at),
+                None
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transitions_are_recorded_as_events() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let events = EventRecorder::new(store.clone(), "anomaly-detector");
+        let transition = AnomalyTransition {
+            instance_id: Uuid::new_v4(),
+            namespace: "shop".to_string(),
+            abnormal: true,
+            message: "Instance is behaving abnormally".to_string(),
+        };
+        transition.record(&events).await;
+
+        let recorded = store.list_events().await.unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].reason, ABNORMAL_REASON);
+        assert_eq!(recorded[0].event_type, EventType::Warning);
+        assert_eq!(recorded[0].involved_object.kind, "Instance");
+        assert_eq!(
+            recorded[0].involved_object.id,
+            transition.instance_id.to_string()
+        );
+    }
+}
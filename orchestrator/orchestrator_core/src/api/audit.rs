@@ -0,0 +1,811 @@
+//! Audit log of mutating API calls.
+//!
+//! [`audit_layer`] records every authenticated request that is not a read:
+//! who made it, what it targeted, the object before and after, the fields
+//! that changed and the response status. Requests RBAC denies are recorded
+//! too. Records go to an [`AuditLog`], which hands them to its sinks in order
+//! and serves `GET /api/v1/audit`.
+//!
+//! Sinks are configured as URIs (see [`AuditSinkConfig`]):
+//!
+//! - `file:///var/log/orchestrator/audit.jsonl` appends JSON lines to a file,
+//!   which is also what `GET /api/v1/audit` reads, so history survives restarts
+//! - `syslog:///dev/log` or `syslog+udp://host:514` sends RFC 5424 messages
+//! - `http://...` or `https://...` posts each record as JSON to a webhook
+//!
+//! Without a file sink, `GET /api/v1/audit` serves the most recent records
+//! held in memory.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use orchestrator_shared_types::{NodeId, OrchestrationError, Result};
+
+use super::auth::{AuthInfo, AuthMethod};
+use super::state::ApiState;
+
+/// Largest response body kept as the `after` of a create.
+const MAX_CREATED_BYTES: usize = 1024 * 1024;
+
+/// How a recorded call ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    /// Refused by authorization.
+    Denied,
+    Failed,
+}
+
+impl AuditOutcome {
+    fn of(status: u16) -> Self {
+        match status {
+            200..=399 => AuditOutcome::Success,
+            401 | 403 => AuditOutcome::Denied,
+            _ => AuditOutcome::Failed,
+        }
+    }
+}
+
+/// The object a call targeted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AuditResource {
+    /// e.g. `workload`, `namespace`, `node`.
+    pub kind: String,
+    /// ID or name; absent when a create failed before one was assigned.
+    pub name: Option<String>,
+}
+
+/// A field that differs between the before and after of an object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditChange {
+    /// JSON pointer to the field, e.g. `/replicas`.
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// One mutating API call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditRecord {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    /// Who made the call, as role bindings name them.
+    pub subject: String,
+    pub auth_method: AuthMethod,
+    pub method: String,
+    pub path: String,
+    pub resource: Option<AuditResource>,
+    pub status: u16,
+    pub outcome: AuditOutcome,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub changes: Vec<AuditChange>,
+}
+
+/// The fields that differ between two JSON values, descending into objects;
+/// arrays and scalars are compared whole.
+pub fn diff(before: Option<&Value>, after: Option<&Value>) -> Vec<AuditChange> {
+    let mut changes = Vec::new();
+    diff_into(String::new(), before, after, &mut changes);
+    changes
+}
+
+fn diff_into(
+    path: String,
+    before: Option<&Value>,
+    after: Option<&Value>,
+    changes: &mut Vec<AuditChange>,
+) {
+    match (before, after) {
+        (Some(Value::Object(b)), Some(Value::Object(a))) => {
+            let mut keys: Vec<&String> = b.keys().chain(a.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let pointer = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                diff_into(pointer, b.get(key), a.get(key), changes);
+            }
+        }
+        (b, a) if b != a => changes.push(AuditChange {
+            path: if path.is_empty() {
+                "/".to_string()
+            } else {
+                path
+            },
+            before: b.cloned(),
+            after: a.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+/// Filters for `GET /api/v1/audit`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    pub subject: Option<String>,
+    /// Resource kind, e.g. `workload`.
+    pub kind: Option<String>,
+    /// Resource ID or name.
+    pub name: Option<String>,
+    /// HTTP method, e.g. `DELETE`.
+    pub method: Option<String>,
+    pub outcome: Option<AuditOutcome>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Most records returned, newest first (default 100).
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    pub const DEFAULT_LIMIT: usize = 100;
+
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        let resource = record.resource.as_ref();
+        self.subject.as_ref().is_none_or(|s| *s == record.subject)
+            && self
+                .kind
+                .as_ref()
+                .is_none_or(|k| resource.is_some_and(|r| r.kind == *k))
+            && self
+                .name
+                .as_ref()
+                .is_none_or(|n| resource.is_some_and(|r| r.name.as_ref() == Some(n)))
+            && self
+                .method
+                .as_ref()
+                .is_none_or(|m| m.eq_ignore_ascii_case(&record.method))
+            && self.outcome.is_none_or(|o| o == record.outcome)
+            && self.since.is_none_or(|t| record.timestamp >= t)
+            && self.until.is_none_or(|t| record.timestamp < t)
+    }
+
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT)
+    }
+}
+
+/// Somewhere audit records are delivered.
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    /// Short description for log messages.
+    fn describe(&self) -> String;
+
+    async fn write(&self, record: &AuditRecord) -> Result<()>;
+
+    /// Records matching `query`, newest first, if this sink can read its
+    /// records back.
+    async fn read(&self, _query: &AuditQuery) -> Option<Result<Vec<AuditRecord>>> {
+        None
+    }
+}
+
+/// Appends records as JSON lines to a file.
+pub struct FileAuditSink {
+    path: PathBuf,
+    file: tokio::sync::Mutex<Option<tokio::fs::File>>,
+}
+
+impl FileAuditSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            file: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl AuditSink for FileAuditSink {
+    fn describe(&self) -> String {
+        format!("file {}", self.path.display())
+    }
+
+    async fn write(&self, record: &AuditRecord) -> Result<()> {
+        let io_error = |e: std::io::Error| {
+            OrchestrationError::StateError(format!("{}: {}", self.path.display(), e))
+        };
+        let mut line = serde_json::to_vec(record)
+            .map_err(|e| OrchestrationError::InternalError(e.to_string()))?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().await;
+        if file.is_none() {
+            *file = Some(
+                tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)
+                    .await
+                    .map_err(io_error)?,
+            );
+        }
+        let handle = file.as_mut().expect("opened above");
+        handle.write_all(&line).await.map_err(io_error)?;
+        handle.flush().await.map_err(io_error)
+    }
+
+    async fn read(&self, query: &AuditQuery) -> Option<Result<Vec<AuditRecord>>> {
+        let file = match tokio::fs::File::open(&self.path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Some(Ok(Vec::new())),
+            Err(e) => {
+                return Some(Err(OrchestrationError::StateError(format!(
+                    "{}: {}",
+                    self.path.display(),
+                    e
+                ))))
+            }
+        };
+        // Keep only the newest matches while scanning the whole file
+        let mut matches = VecDeque::with_capacity(query.limit());
+        let mut lines = tokio::io::BufReader::new(file).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let Ok(record) = serde_json::from_str::<AuditRecord>(&line) else {
+                continue;
+            };
+            if query.matches(&record) {
+                if matches.len() == query.limit() {
+                    matches.pop_front();
+                }
+                matches.push_back(record);
+            }
+        }
+        Some(Ok(matches.into_iter().rev().collect()))
+    }
+}
+
+/// Where a syslog sink sends messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyslogTarget {
+    /// A local socket such as `/dev/log`.
+    Unix(PathBuf),
+    Udp(SocketAddr),
+}
+
+/// Sends records as RFC 5424 messages with the JSON record as the message.
+pub struct SyslogAuditSink {
+    target: SyslogTarget,
+}
+
+impl SyslogAuditSink {
+    /// Facility `authpriv`.
+    const FACILITY: u8 = 10;
+
+    pub fn new(target: SyslogTarget) -> Self {
+        Self { target }
+    }
+
+    fn message(record: &AuditRecord) -> Result<String> {
+        // Notice for changes, warning for denied and failed calls
+        let severity = if record.outcome == AuditOutcome::Success {
+            5
+        } else {
+            4
+        };
+        let json = serde_json::to_string(record)
+            .map_err(|e| OrchestrationError::InternalError(e.to_string()))?;
+        Ok(format!(
+            "<{}>1 {} - orchestrator - audit - {}",
+            Self::FACILITY * 8 + severity,
+            record
+                .timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            json
+        ))
+    }
+}
+
+#[async_trait]
+impl AuditSink for SyslogAuditSink {
+    fn describe(&self) -> String {
+        match &self.target {
+            SyslogTarget::Unix(path) => format!("syslog {}", path.display()),
+            SyslogTarget::Udp(addr) => format!("syslog udp {}", addr),
+        }
+    }
+
+    async fn write(&self, record: &AuditRecord) -> Result<()> {
+        let message = Self::message(record)?;
+        let net_error = |e: std::io::Error| OrchestrationError::NetworkError(e.to_string());
+        match &self.target {
+            SyslogTarget::Unix(path) => {
+                let socket = tokio::net::UnixDatagram::unbound().map_err(net_error)?;
+                socket
+                    .send_to(message.as_bytes(), path)
+                    .await
+                    .map_err(net_error)?;
+            }
+            SyslogTarget::Udp(addr) => {
+                let bind: SocketAddr = if addr.is_ipv6() {
+                    "[::]:0".parse().unwrap()
+                } else {
+                    "0.0.0.0:0".parse().unwrap()
+                };
+                let socket = tokio::net::UdpSocket::bind(bind).await.map_err(net_error)?;
+                socket
+                    .send_to(message.as_bytes(), addr)
+                    .await
+                    .map_err(net_error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Posts each record as JSON to a URL.
+pub struct WebhookAuditSink {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookAuditSink {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[async_trait]
+impl AuditSink for WebhookAuditSink {
+    fn describe(&self) -> String {
+        format!("webhook {}", self.url)
+    }
+
+    async fn write(&self, record: &AuditRecord) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .json(record)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| OrchestrationError::NetworkError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// An audit sink named by URI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditSinkConfig {
+    File(PathBuf),
+    Syslog(SyslogTarget),
+    Webhook(String),
+}
+
+impl AuditSinkConfig {
+    /// Parse a comma-separated list of sink URIs.
+    pub fn parse_list(s: &str) -> std::result::Result<Vec<Self>, String> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    pub fn build(&self) -> Arc<dyn AuditSink> {
+        match self {
+            AuditSinkConfig::File(path) => Arc::new(FileAuditSink::new(path.clone())),
+            AuditSinkConfig::Syslog(target) => Arc::new(SyslogAuditSink::new(target.clone())),
+            AuditSinkConfig::Webhook(url) => Arc::new(WebhookAuditSink::new(url.clone())),
+        }
+    }
+}
+
+impl FromStr for AuditSinkConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("file://") {
+            if path.is_empty() {
+                return Err(format!("no path in {}", s));
+            }
+            Ok(AuditSinkConfig::File(PathBuf::from(path)))
+        } else if let Some(path) = s.strip_prefix("syslog://") {
+            if path.is_empty() {
+                return Err(format!("no socket path in {}", s));
+            }
+            Ok(AuditSinkConfig::Syslog(SyslogTarget::Unix(PathBuf::from(
+                path,
+            ))))
+        } else if let Some(addr) = s.strip_prefix("syslog+udp://") {
+            use std::net::ToSocketAddrs;
+            let addr = addr
+                .to_socket_addrs()
+                .map_err(|e| format!("invalid syslog address {}: {}", addr, e))?
+                .next()
+                .ok_or_else(|| format!("no addresses found for {}", addr))?;
+            Ok(AuditSinkConfig::Syslog(SyslogTarget::Udp(addr)))
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            Ok(AuditSinkConfig::Webhook(s.to_string()))
+        } else {
+            Err(format!(
+                "unknown audit sink {} (expected file://, syslog://, syslog+udp:// or http(s)://)",
+                s
+            ))
+        }
+    }
+}
+
+/// Records mutating API calls and delivers them to sinks in order.
+pub struct AuditLog {
+    sinks: Vec<Arc<dyn AuditSink>>,
+    recent: Mutex<VecDeque<AuditRecord>>,
+    capacity: usize,
+    tx: mpsc::UnboundedSender<AuditRecord>,
+}
+
+impl AuditLog {
+    /// Records kept in memory for queries when no sink can read back.
+    pub const DEFAULT_CAPACITY: usize = 10_000;
+
+    /// Start delivering to `sinks` from a background task.
+    pub fn new(sinks: Vec<Arc<dyn AuditSink>>) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<AuditRecord>();
+        let writers = sinks.clone();
+        tokio::spawn(async move {
+            while let Some(record) = rx.recv().await {
+                for sink in &writers {
+                    if let Err(e) = sink.write(&record).await {
+                        warn!(sink = %sink.describe(), "Failed to write audit record: {}", e);
+                    }
+                }
+            }
+        });
+        Self {
+            sinks,
+            recent: Mutex::new(VecDeque::new()),
+            capacity: Self::DEFAULT_CAPACITY,
+            tx,
+        }
+    }
+
+    /// Keep up to `capacity` records in memory.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn record(&self, record: AuditRecord) {
+        {
+            let mut recent = self.recent.lock().unwrap();
+            if recent.len() >= self.capacity {
+                recent.pop_front();
+            }
+            recent.push_back(record.clone());
+        }
+        // Only fails once the runtime is shutting down
+        let _ = self.tx.send(record);
+    }
+
+    /// Records matching `query`, newest first.
+    pub async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>> {
+        for sink in &self.sinks {
+            if let Some(records) = sink.read(query).await {
+                return records;
+            }
+        }
+        let recent = self.recent.lock().unwrap();
+        Ok(recent
+            .iter()
+            .rev()
+            .filter(|record| query.matches(record))
+            .take(query.limit())
+            .cloned()
+            .collect())
+    }
+}
+
+/// What a request path targets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Target {
+    kind: &'static str,
+    /// None for a collection, where a create names the object in its response.
+    name: Option<String>,
+}
+
+impl Target {
+    fn of(path: &str) -> Option<Self> {
+        let rest = path
+            .strip_prefix("/api/v1/")
+            .or_else(|| path.strip_prefix("/api/v1beta1/"))?;
+        let segments: Vec<&str> = rest.split('/').filter(|s| !s.is_empty()).collect();
+        let kind = match *segments.first()? {
+            "workloads" => "workload",
+            "namespaces" => "namespace",
+            "nodes" => "node",
+            "disruption-budgets" => "disruption_budget",
+            "network-policies" => "network_policy",
+            "tunnels" => "tunnel",
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            name: segments.get(1).map(|s| s.to_string()),
+        })
+    }
+
+    /// The object as the API stores it, if it exists.
+    async fn snapshot(&self, state: &ApiState) -> Option<Value> {
+        let name = self.name.as_deref()?;
+        let value = match self.kind {
+            "workload" => {
+                let id = Uuid::parse_str(name).ok()?;
+                let workload = state.state_store.get_workload(&id).await.ok()??;
+                serde_json::to_value(workload)
+            }
+            "namespace" => {
+                let namespace = state.state_store.get_namespace(name).await.ok()??;
+                serde_json::to_value(namespace)
+            }
+            "node" => {
+                let id: NodeId = name.parse().ok()?;
+                let node = state.state_store.get_node(&id).await.ok()??;
+                serde_json::to_value(node)
+            }
+            "disruption_budget" => {
+                let id = Uuid::parse_str(name).ok()?;
+                let budget = state.disruption.as_ref()?.get_budget(&id).await?;
+                serde_json::to_value(budget)
+            }
+            "network_policy" => {
+                let id = Uuid::parse_str(name).ok()?;
+                let policy = state.network_policies.as_ref()?.get_policy(&id).await?;
+                serde_json::to_value(policy)
+            }
+            "tunnel" => {
+                let id = Uuid::parse_str(name).ok()?;
+                let tunnel = state.tunnels.as_ref()?.get(&id)?;
+                serde_json::to_value(tunnel)
+            }
+            _ => return None,
+        };
+        value.ok()
+    }
+}
+
+/// Record mutating calls to the state's audit log.
+///
+/// Runs inside authentication, so the caller is known, and outside RBAC, so
+/// denied calls are recorded as well.
+pub async fn audit_layer(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let log = match &state.audit {
+        Some(log) => log.clone(),
+        None => return next.run(request).await,
+    };
+    if matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) {
+        return next.run(request).await;
+    }
+    let Some(auth) = request.extensions().get::<AuthInfo>().cloned() else {
+        return next.run(request).await;
+    };
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let target = Target::of(&path);
+    let before = match &target {
+        Some(target) => target.snapshot(&state).await,
+        None => None,
+    };
+
+    let mut response = next.run(request).await;
+    let status = response.status();
+
+    let mut name = target.as_ref().and_then(|t| t.name.clone());
+    let after = match &target {
+        // A create names the object in its response, so keep that
+        Some(target) if target.name.is_none() && method == Method::POST && status.is_success() => {
+            let (parts, body) = response.into_parts();
+            let bytes = match axum::body::to_bytes(body, MAX_CREATED_BYTES).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    warn!("Failed to buffer response for audit: {}", e);
+                    Default::default()
+                }
+            };
+            let created = serde_json::from_slice::<Value>(&bytes).ok();
+            name = created
+                .as_ref()
+                .and_then(|v| v.get("id").or_else(|| v.get("name")))
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            response = Response::from_parts(parts, Body::from(bytes));
+            created
+        }
+        Some(target) => target.snapshot(&state).await,
+        None => None,
+    };
+
+    log.record(AuditRecord {
+        id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        subject: auth.subject,
+        auth_method: auth.method,
+        method: method.to_string(),
+        changes: diff(before.as_ref(), after.as_ref()),
+        resource: target.map(|t| AuditResource {
+            kind: t.kind.to_string(),
+            name,
+        }),
+        path,
+        status: status.as_u16(),
+        outcome: AuditOutcome::of(status.as_u16()),
+        before,
+        after,
+    });
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(subject: &str, kind: &str, method: &str, status: u16) -> AuditRecord {
+        AuditRecord {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            subject: subject.to_string(),
+            auth_method: AuthMethod::Signature,
+            method: method.to_string(),
+            path: format!("/api/v1/{}s/x", kind),
+            resource: Some(AuditResource {
+                kind: kind.to_string(),
+                name: Some("x".to_string()),
+            }),
+            status,
+            outcome: AuditOutcome::of(status),
+            before: None,
+            after: None,
+            changes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_changed_fields() {
+        let before = json!({"name": "web", "replicas": 2, "labels": {"tier": "front"}});
+        let after = json!({"name": "web", "replicas": 3, "labels": {"tier": "front", "a/b": "c"}});
+        let changes = diff(Some(&before), Some(&after));
+        assert_eq!(
+            changes,
+            vec![
+                AuditChange {
+                    path: "/labels/a~1b".to_string(),
+                    before: None,
+                    after: Some(json!("c")),
+                },
+                AuditChange {
+                    path: "/replicas".to_string(),
+                    before: Some(json!(2)),
+                    after: Some(json!(3)),
+                },
+            ]
+        );
+
+        let deleted = diff(Some(&before), None);
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].path, "/");
+        assert!(diff(Some(&before), Some(&before)).is_empty());
+    }
+
+    #[test]
+    fn test_target_from_path() {
+        let target = Target::of("/api/v1/workloads/abc").unwrap();
+        assert_eq!(target.kind, "workload");
+        assert_eq!(target.name.as_deref(), Some("abc"));
+        let target = Target::of("/api/v1beta1/nodes/n1/drain").unwrap();
+        assert_eq!(target.kind, "node");
+        assert_eq!(target.name.as_deref(), Some("n1"));
+        assert_eq!(Target::of("/api/v1/namespaces").unwrap().name, None);
+        assert!(Target::of("/api/v1/cluster/backup").is_none());
+        assert!(Target::of("/health").is_none());
+    }
+
+    #[test]
+    fn test_parse_sink_configs() {
+        let sinks = AuditSinkConfig::parse_list(
+            "file:///var/log/audit.jsonl, syslog:///dev/log,syslog+udp://127.0.0.1:514,https://x/y",
+        )
+        .unwrap();
+        assert_eq!(
+            sinks,
+            vec![
+                AuditSinkConfig::File(PathBuf::from("/var/log/audit.jsonl")),
+                AuditSinkConfig::Syslog(SyslogTarget::Unix(PathBuf::from("/dev/log"))),
+                AuditSinkConfig::Syslog(SyslogTarget::Udp("127.0.0.1:514".parse().unwrap())),
+                AuditSinkConfig::Webhook("https://x/y".to_string()),
+            ]
+        );
+        assert!("ftp://x".parse::<AuditSinkConfig>().is_err());
+        assert!("file://".parse::<AuditSinkConfig>().is_err());
+    }
+
+    #[test]
+    fn test_syslog_message_format() {
+        let message =
+            SyslogAuditSink::message(&record("alice", "workload", "DELETE", 200)).unwrap();
+        assert!(message.starts_with("<85>1 "));
+        assert!(message.contains(" - orchestrator - audit - {"));
+        let denied = SyslogAuditSink::message(&record("bob", "workload", "DELETE", 403)).unwrap();
+        assert!(denied.starts_with("<84>1 "));
+    }
+
+    #[tokio::test]
+    async fn test_query_filters_in_memory() {
+        let log = AuditLog::new(Vec::new()).with_capacity(3);
+        log.record(record("alice", "workload", "POST", 201));
+        log.record(record("bob", "node", "POST", 200));
+        log.record(record("alice", "workload", "DELETE", 403));
+        log.record(record("alice", "namespace", "DELETE", 200));
+
+        let all = log.query(&AuditQuery::default()).await.unwrap();
+        assert_eq!(all.len(), 3, "oldest record is evicted");
+        assert_eq!(all[0].resource.as_ref().unwrap().kind, "namespace");
+
+        let query = AuditQuery {
+            subject: Some("alice".to_string()),
+            outcome: Some(AuditOutcome::Denied),
+            ..Default::default()
+        };
+        let denied = log.query(&query).await.unwrap();
+        assert_eq!(denied.len(), 1);
+        assert_eq!(denied[0].method, "DELETE");
+    }
+
+    #[tokio::test]
+    async fn test_file_sink_appends_and_reads_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = FileAuditSink::new(dir.path().join("audit.jsonl"));
+        for i in 0..5 {
+            let method = if i % 2 == 0 { "POST" } else { "DELETE" };
+            sink.write(&record("alice", "workload", method, 200))
+                .await
+                .unwrap();
+        }
+
+        let query = AuditQuery {
+            method: Some("post".to_string()),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let records = sink.read(&query).await.unwrap().unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r.method == "POST"));
+        assert!(records[0].timestamp >= records[1].timestamp);
+
+        let contents = std::fs::read_to_string(sink.path()).unwrap();
+        assert_eq!(contents.lines().count(), 5);
+    }
+}
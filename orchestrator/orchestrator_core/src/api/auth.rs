@@ -1,6 +1,7 @@
 //! Ed25519 request signing authentication middleware.
 //!
-//! Implements request authentication using Ed25519 signatures.
+//! Implements request authentication using Ed25519 signatures, or bearer
+//! tokens when an issuer is configured (see [`token`](super::token)).
 //!
 //! # Headers
 //!
@@ -17,13 +18,31 @@
 //! ```
 //!
 //! Where BODY_SHA256_HEX is the lowercase hex SHA-256 hash of the request body.
+//!
+//! # Bearer Tokens
+//!
+//! Instead of signing, clients may send `Authorization: Bearer <JWT>` with a
+//! token from the control plane's [`LocalTokenIssuer`] or the configured OIDC
+//! issuer.
+//!
+//! # Bootstrap Tokens
+//!
+//! Node agents register themselves with `Authorization: Bearer <token>` using
+//! one of the configured bootstrap tokens. A bootstrap token is accepted only
+//! for `POST /api/v1/nodes/register` and for patching or deleting a node.
+//!
+//! # Client Certificates
+//!
+//! Over mutual TLS (`mtls` feature), requests on a connection with a client
+//! certificate from the cluster CA need neither: they are authenticated as the
+//! certificate's subject. See [`tls`](super::tls).
 
 use std::sync::Arc;
 
 use axum::{
     body::Body,
     extract::{Request, State},
-    http::{header::HeaderMap, StatusCode},
+    http::{header::{HeaderMap, AUTHORIZATION}, Method, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
     Json,
@@ -34,6 +53,14 @@ use ed25519_dalek::{Signature, VerifyingKey, Verifier};
 use http_body_util::BodyExt;
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
+use utoipa::ToSchema;
+
+use super::rbac::{RbacPolicy, RoleBinding};
+#[cfg(feature = "oidc")]
+use super::token::OidcVerifier;
+use super::token::{LocalTokenIssuer, TokenIdentity};
+#[cfg(feature = "mtls")]
+use super::tls::{ClientCertificate, ClusterCa};
 
 /// Authentication error response.
 #[derive(Debug, Serialize, Deserialize)]
@@ -77,6 +104,13 @@ impl AuthError {
             "INVALID_SIGNATURE",
         )
     }
+
+    pub fn invalid_token(reason: &str) -> Self {
+        Self::new(
+            format!("Invalid bearer token: {}", reason),
+            "INVALID_TOKEN",
+        )
+    }
 }
 
 impl IntoResponse for AuthError {
@@ -85,6 +119,9 @@ impl IntoResponse for AuthError {
     }
 }
 
+/// Subject of requests authenticated with a bootstrap token.
+pub const BOOTSTRAP_SUBJECT: &str = "system:bootstrap";
+
 /// Authentication configuration.
 #[derive(Debug, Clone)]
 pub struct AuthConfig {
@@ -96,6 +133,18 @@ pub struct AuthConfig {
     pub trusted_keys: Vec<[u8; 32]>,
     /// Whether auth is required (false = bypass auth for testing).
     pub required: bool,
+    /// Role bindings authorizing each request (None = any authenticated key may do anything).
+    pub rbac: Option<RbacPolicy>,
+    /// Issuer of tokens for `POST /api/v1/auth/token` (None = tokens not issued).
+    pub token_issuer: Option<Arc<LocalTokenIssuer>>,
+    /// Shared secrets node agents register with (empty = no self-registration).
+    pub bootstrap_tokens: Vec<String>,
+    /// OIDC provider whose tokens are accepted.
+    #[cfg(feature = "oidc")]
+    pub oidc: Option<Arc<OidcVerifier>>,
+    /// CA signing client certificates for `POST /api/v1/auth/certificate`.
+    #[cfg(feature = "mtls")]
+    pub certificate_authority: Option<Arc<ClusterCa>>,
 }
 
 impl Default for AuthConfig {
@@ -105,6 +154,13 @@ impl Default for AuthConfig {
             allow_future_timestamp_secs: 60, // 1 minute clock skew
             trusted_keys: Vec::new(),
             required: true,
+            rbac: None,
+            token_issuer: None,
+            bootstrap_tokens: Vec::new(),
+            #[cfg(feature = "oidc")]
+            oidc: None,
+            #[cfg(feature = "mtls")]
+            certificate_authority: None,
         }
     }
 }
@@ -129,17 +185,175 @@ impl AuthConfig {
         self.required = required;
         self
     }
+
+    /// Authorize requests against role bindings, denying anything not granted.
+    pub fn with_rbac(mut self, policy: RbacPolicy) -> Self {
+        self.rbac = Some(policy);
+        self
+    }
+
+    /// Issue and accept local tokens.
+    pub fn with_token_issuer(mut self, issuer: LocalTokenIssuer) -> Self {
+        self.token_issuer = Some(Arc::new(issuer));
+        self
+    }
+
+    /// Accept `token` from node agents registering themselves.
+    pub fn with_bootstrap_token(mut self, token: impl Into<String>) -> Self {
+        self.bootstrap_tokens.push(token.into());
+        self
+    }
+
+    /// Whether `token` is a bootstrap token, compared in constant time.
+    fn is_bootstrap_token(&self, token: &str) -> bool {
+        self.bootstrap_tokens.iter().any(|expected| {
+            expected.len() == token.len()
+                && expected
+                    .bytes()
+                    .zip(token.bytes())
+                    .fold(0, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+        })
+    }
+
+    /// Accept tokens from an OIDC provider.
+    ///
+    /// Enables RBAC, with no bindings unless a policy is set, so that OIDC
+    /// users get only the roles their claims map to.
+    #[cfg(feature = "oidc")]
+    pub fn with_oidc(mut self, verifier: OidcVerifier) -> Self {
+        self.oidc = Some(Arc::new(verifier));
+        self.rbac.get_or_insert_with(RbacPolicy::new);
+        self
+    }
+
+    /// Issue client certificates from the cluster CA.
+    #[cfg(feature = "mtls")]
+    pub fn with_certificate_authority(mut self, ca: Arc<ClusterCa>) -> Self {
+        self.certificate_authority = Some(ca);
+        self
+    }
+}
+
+/// How a request was authenticated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// Authentication is disabled.
+    None,
+    Signature,
+    LocalToken,
+    OidcToken,
+    ClientCertificate,
+    /// A node agent's bootstrap token, limited to node registration.
+    BootstrapToken,
 }
 
 /// Verified authentication information extracted from request.
 #[derive(Debug, Clone)]
 pub struct AuthInfo {
-    /// The public key that signed the request.
+    /// The public key that signed the request (zeroed for bearer tokens and
+    /// node certificates).
     pub public_key: [u8; 32],
     /// Base64-encoded public key for display.
     pub public_key_base64: String,
     /// The timestamp from the request.
     pub timestamp: DateTime<Utc>,
+    /// Who role bindings are matched against: the base64 public key, or a
+    /// token's or client certificate's subject.
+    pub subject: String,
+    pub method: AuthMethod,
+    /// Roles granted by a token's claims, on top of the RBAC policy.
+    pub granted: Vec<RoleBinding>,
+}
+
+impl AuthInfo {
+    fn bootstrap() -> Self {
+        Self {
+            public_key: [0u8; 32],
+            public_key_base64: String::new(),
+            timestamp: Utc::now(),
+            subject: BOOTSTRAP_SUBJECT.to_string(),
+            method: AuthMethod::BootstrapToken,
+            granted: Vec::new(),
+        }
+    }
+
+    fn from_token(identity: TokenIdentity, method: AuthMethod) -> Self {
+        Self {
+            public_key: [0u8; 32],
+            public_key_base64: String::new(),
+            timestamp: Utc::now(),
+            subject: identity.subject,
+            method,
+            granted: identity.bindings,
+        }
+    }
+
+    /// Authenticate as a client certificate's subject, checking the key it
+    /// names against the trusted keys as a signature would be.
+    #[cfg(feature = "mtls")]
+    fn from_client_certificate(
+        config: &AuthConfig,
+        certificate: &ClientCertificate,
+    ) -> Result<Self, AuthError> {
+        let public_key: Option<[u8; 32]> = BASE64_STANDARD
+            .decode(&certificate.subject)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok());
+        if let Some(key) = &public_key {
+            if !config.trusted_keys.is_empty() && !config.trusted_keys.contains(key) {
+                return Err(AuthError::new("Public key is not trusted", "UNTRUSTED_KEY"));
+            }
+        }
+        Ok(Self {
+            public_key: public_key.unwrap_or([0u8; 32]),
+            public_key_base64: if public_key.is_some() {
+                certificate.subject.clone()
+            } else {
+                String::new()
+            },
+            timestamp: Utc::now(),
+            subject: certificate.subject.clone(),
+            method: AuthMethod::ClientCertificate,
+            granted: Vec::new(),
+        })
+    }
+}
+
+/// Verify a bearer token with the local issuer, then the OIDC provider.
+async fn verify_bearer(config: &AuthConfig, token: &str) -> Result<AuthInfo, AuthError> {
+    let mut error = AuthError::invalid_token("no token issuer is configured");
+    if let Some(issuer) = &config.token_issuer {
+        match issuer.verify(token) {
+            Ok(identity) => return Ok(AuthInfo::from_token(identity, AuthMethod::LocalToken)),
+            Err(e) => error = e,
+        }
+    }
+    #[cfg(feature = "oidc")]
+    if let Some(oidc) = &config.oidc {
+        return oidc
+            .verify(token)
+            .await
+            .map(|identity| AuthInfo::from_token(identity, AuthMethod::OidcToken));
+    }
+    Err(error)
+}
+
+/// Whether a request registers, patches or deregisters a node, which is all
+/// a bootstrap token allows.
+fn is_node_registration(method: &Method, path: &str) -> bool {
+    let segments: Vec<&str> = path
+        .strip_prefix("/api/v1/nodes/")
+        .unwrap_or("")
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    match segments.as_slice() {
+        ["register"] => method == Method::POST,
+        [_] => method == Method::PATCH || method == Method::DELETE,
+        _ => false,
+    }
 }
 
 /// Authentication middleware.
@@ -156,9 +370,40 @@ pub async fn auth_middleware(
             public_key: [0u8; 32],
             public_key_base64: "disabled".to_string(),
             timestamp: Utc::now(),
+            subject: "disabled".to_string(),
+            method: AuthMethod::None,
+            granted: Vec::new(),
         }));
     }
 
+    if let Some(token) = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    {
+        let token = token.trim();
+        if config.is_bootstrap_token(token) {
+            if !is_node_registration(request.method(), request.uri().path()) {
+                return Err(AuthError::invalid_token(
+                    "bootstrap tokens may only register nodes",
+                ));
+            }
+            return Ok((request, AuthInfo::bootstrap()));
+        }
+        let auth_info = verify_bearer(&config, token).await?;
+        return Ok((request, auth_info));
+    }
+
+    // Signed requests speak for their key even on a connection with a
+    // client certificate
+    #[cfg(feature = "mtls")]
+    if !headers.contains_key("X-Auth-PublicKey") {
+        if let Some(certificate) = request.extensions().get::<ClientCertificate>() {
+            let auth_info = AuthInfo::from_client_certificate(&config, certificate)?;
+            return Ok((request, auth_info));
+        }
+    }
+
     // Extract headers
     let public_key_b64 = headers
         .get("X-Auth-PublicKey")
@@ -265,6 +510,9 @@ pub async fn auth_middleware(
         public_key,
         public_key_base64: public_key_b64.to_string(),
         timestamp,
+        subject: public_key_b64.to_string(),
+        method: AuthMethod::Signature,
+        granted: Vec::new(),
     };
 
     Ok((request, auth_info))
@@ -374,6 +622,7 @@ mod tests {
         assert_eq!(config.allow_future_timestamp_secs, 60);
         assert!(config.trusted_keys.is_empty());
         assert!(config.required);
+        assert!(config.rbac.is_none());
     }
 
     #[test]
@@ -382,6 +631,21 @@ mod tests {
         assert!(!config.required);
     }
 
+    #[test]
+    fn test_bootstrap_token_scope() {
+        let config = AuthConfig::default().with_bootstrap_token("join-secret");
+        assert!(config.is_bootstrap_token("join-secret"));
+        assert!(!config.is_bootstrap_token("join-secreT"));
+        assert!(!config.is_bootstrap_token("join"));
+
+        assert!(is_node_registration(&Method::POST, "/api/v1/nodes/register"));
+        assert!(is_node_registration(&Method::PATCH, "/api/v1/nodes/abc"));
+        assert!(is_node_registration(&Method::DELETE, "/api/v1/nodes/abc"));
+        assert!(!is_node_registration(&Method::POST, "/api/v1/nodes/abc/drain"));
+        assert!(!is_node_registration(&Method::GET, "/api/v1/nodes"));
+        assert!(!is_node_registration(&Method::DELETE, "/api/v1/workloads/abc"));
+    }
+
     #[test]
     fn test_auth_error_responses() {
         let error = AuthError::missing_header("X-Auth-PublicKey");
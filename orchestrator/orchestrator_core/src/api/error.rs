@@ -6,11 +6,14 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use orchestrator_shared_types::OrchestrationError;
 
+use crate::admission::AdmissionError;
+
 /// API error response.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ApiError {
     /// Human-readable error message.
     pub error: String,
@@ -64,10 +67,20 @@ impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let status = match self.code.as_str() {
             "NOT_FOUND" => StatusCode::NOT_FOUND,
-            "BAD_REQUEST" | "VALIDATION_ERROR" => StatusCode::BAD_REQUEST,
+            "BAD_REQUEST" | "VALIDATION_ERROR" | "TOO_MANY_CONTAINERS" => StatusCode::BAD_REQUEST,
             "CONFLICT" => StatusCode::CONFLICT,
             "UNAUTHORIZED" => StatusCode::UNAUTHORIZED,
-            "FORBIDDEN" => StatusCode::FORBIDDEN,
+            "FORBIDDEN" | "QUOTA_EXCEEDED" | "LIMIT_EXCEEDED" => StatusCode::FORBIDDEN,
+            "MISSING_LABEL" | "IMAGE_NOT_ALLOWED" | "RESOURCES_REQUIRED" | "ADMISSION_DENIED" => {
+                StatusCode::FORBIDDEN
+            }
+            "VULNERABLE_IMAGE" => StatusCode::FORBIDDEN,
+            "SPEC_TOO_LARGE" => StatusCode::PAYLOAD_TOO_LARGE,
+            "RATE_LIMITED" => StatusCode::TOO_MANY_REQUESTS,
+            "UNSUPPORTED_MEDIA_TYPE" => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "INVALID_PATCH" => StatusCode::UNPROCESSABLE_ENTITY,
+            "NOT_IMPLEMENTED" => StatusCode::NOT_IMPLEMENTED,
+            "CLUSTER_UNREACHABLE" => StatusCode::BAD_GATEWAY,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
@@ -108,10 +121,60 @@ impl From<OrchestrationError> for ApiError {
             OrchestrationError::NotImplemented(msg) => {
                 ApiError::new(msg, "NOT_IMPLEMENTED")
             }
+            OrchestrationError::Conflict(msg) => ApiError::conflict(msg),
         }
     }
 }
 
+impl From<AdmissionError> for ApiError {
+    fn from(err: AdmissionError) -> Self {
+        let details = match &err {
+            AdmissionError::TooManyWorkloads { namespace, limit } => {
+                serde_json::json!({ "namespace": namespace, "limit": limit })
+            }
+            AdmissionError::TooManyContainers { count, limit } => {
+                serde_json::json!({ "count": count, "limit": limit })
+            }
+            AdmissionError::SpecTooLarge { size, limit } => {
+                serde_json::json!({ "size": size, "limit": limit })
+            }
+            AdmissionError::ResourceLimitExceeded {
+                container,
+                resource,
+                requested,
+                limit,
+            } => serde_json::json!({
+                "container": container,
+                "resource": resource,
+                "requested": requested,
+                "limit": limit,
+            }),
+            AdmissionError::MissingLabel { label } => serde_json::json!({ "label": label }),
+            AdmissionError::ImageNotAllowed { container, image } => {
+                serde_json::json!({ "container": container, "image": image })
+            }
+            AdmissionError::ResourcesRequired {
+                container,
+                resource,
+            } => serde_json::json!({ "container": container, "resource": resource }),
+            AdmissionError::VulnerableImage {
+                container,
+                image,
+                count,
+                severity,
+                max,
+            } => serde_json::json!({
+                "container": container,
+                "image": image,
+                "count": count,
+                "severity": severity,
+                "max_severity": max,
+            }),
+        };
+        ApiError::new(err.to_string(), err.code()).with_details(details)
+    }
+}
+
 /// Result type for API handlers.
 pub type ApiResult<T> = Result<T, ApiError>;
 
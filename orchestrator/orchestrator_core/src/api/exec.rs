@@ -0,0 +1,304 @@
+//! WebSocket protocol of `GET /api/v1/instances/:id/exec`.
+//!
+//! The command is given as one `cmd` parameter per argument, e.g.
+//! `?cmd=sh&cmd=-c&cmd=ls`, and runs in `container`, or the instance's first
+//! main container when absent.
+//!
+//! Every frame is binary. Its first byte names the channel and the rest is
+//! the payload:
+//!
+//! - `0` stdin, from the client. An empty payload closes stdin.
+//! - `1` stdout and `2` stderr, from the server. `tty` sessions send all
+//!   output on stdout.
+//! - `3` status, from the server, sent last before closing: JSON
+//!   `{"exit_code": 0}`, `null` when unknown, or `{"error": "..."}`.
+//! - `4` resize, from the client: JSON `{"cols": 80, "rows": 24}`.
+//!
+//! The command is stopped when the client disconnects.
+//!
+//! `GET /api/v1/instances/:id/debug?image=busybox` speaks the same protocol
+//! with a throwaway container from `image` that shares the namespaces of the
+//! target container, for images without a shell; `cmd` defaults to the
+//! image's shell there. The container is removed when the session ends.
+
+use axum::extract::ws::{Message, WebSocket};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use container_runtime_interface::{ExecInput, ExecOutput, ExecSession};
+use orchestrator_shared_types::{ContainerId, WorkloadDefinition, WorkloadInstance};
+
+use super::error::{ApiError, ApiResult};
+
+pub const STDIN: u8 = 0;
+pub const STDOUT: u8 = 1;
+pub const STDERR: u8 = 2;
+pub const STATUS: u8 = 3;
+pub const RESIZE: u8 = 4;
+
+/// Query parameters of an exec.
+#[derive(Debug, Clone, Default, PartialEq, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExecQuery {
+    /// Container to run in; the first main container when absent.
+    pub container: Option<String>,
+    /// The command, one parameter per argument.
+    pub cmd: Vec<String>,
+    /// Run on a terminal.
+    pub tty: bool,
+    /// Only instances in this namespace.
+    pub namespace: Option<String>,
+}
+
+impl ExecQuery {
+    /// Read the query from its pairs, where `cmd` repeats.
+    pub fn from_pairs(pairs: Vec<(String, String)>) -> ApiResult<Self> {
+        let query = Self::read(pairs)?;
+        if query.cmd.is_empty() {
+            return Err(ApiError::bad_request("cmd is required"));
+        }
+        Ok(query)
+    }
+
+    /// Read the query from its pairs, ignoring unknown keys.
+    fn read(pairs: Vec<(String, String)>) -> ApiResult<Self> {
+        let mut query = Self::default();
+        for (key, value) in pairs {
+            match key.as_str() {
+                "container" => query.container = Some(value),
+                "cmd" => query.cmd.push(value),
+                "tty" => {
+                    query.tty = value.parse().map_err(|_| {
+                        ApiError::bad_request(format!("Invalid tty '{}': expected a bool", value))
+                    })?
+                }
+                "namespace" => query.namespace = Some(value),
+                _ => {}
+            }
+        }
+        Ok(query)
+    }
+}
+
+/// Query parameters of a debug container.
+#[derive(Debug, Clone, Default, PartialEq, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DebugQuery {
+    /// Image of the debug container, e.g. `busybox`.
+    pub image: String,
+    /// Container whose namespaces to share; the first main container when
+    /// absent.
+    pub container: Option<String>,
+    /// The command, one parameter per argument; the image's shell when
+    /// absent.
+    pub cmd: Vec<String>,
+    /// Run on a terminal.
+    pub tty: bool,
+    /// Only instances in this namespace.
+    pub namespace: Option<String>,
+}
+
+impl DebugQuery {
+    /// Read the query from its pairs, where `cmd` repeats.
+    pub fn from_pairs(pairs: Vec<(String, String)>) -> ApiResult<Self> {
+        let image = pairs
+            .iter()
+            .find(|(key, _)| key == "image")
+            .map(|(_, value)| value.clone())
+            .filter(|image| !image.is_empty())
+            .ok_or_else(|| ApiError::bad_request("image is required"))?;
+        let ExecQuery {
+            container,
+            cmd,
+            tty,
+            namespace,
+        } = ExecQuery::read(pairs)?;
+        Ok(Self {
+            image,
+            container,
+            cmd,
+            tty,
+            namespace,
+        })
+    }
+}
+
+/// Size of the client's terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerminalSize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+/// The id of the container named `name` in `instance`, or of its first main
+/// container when `name` is absent. Instances list sidecars before main
+/// containers.
+pub fn container_id(
+    workload: &WorkloadDefinition,
+    instance: &WorkloadInstance,
+    name: Option<&str>,
+) -> Option<ContainerId> {
+    let mut containers = workload
+        .sidecars
+        .iter()
+        .chain(&workload.containers)
+        .map(|c| c.name.as_str())
+        .zip(&instance.container_ids);
+    let found = match name {
+        Some(name) => containers.find(|(n, _)| *n == name),
+        None => containers.nth(workload.sidecars.len()),
+    };
+    found.map(|(_, id)| id.clone())
+}
+
+/// A frame on `channel`.
+pub fn frame(channel: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 1);
+    frame.push(channel);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// The input a client frame carries, if it is a valid one.
+pub fn decode_input(frame: &[u8]) -> Option<ExecInput> {
+    let (&channel, payload) = frame.split_first()?;
+    match channel {
+        STDIN if payload.is_empty() => Some(ExecInput::CloseStdin),
+        STDIN => Some(ExecInput::Stdin(payload.to_vec())),
+        RESIZE => {
+            let size: TerminalSize = serde_json::from_slice(payload).ok()?;
+            Some(ExecInput::Resize {
+                cols: size.cols,
+                rows: size.rows,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Relay frames between `socket` and `session` until the command exits or
+/// the client goes away.
+pub async fn bridge(mut socket: WebSocket, mut session: ExecSession) {
+    let status = loop {
+        tokio::select! {
+            output = session.output.recv() => {
+                let frame = match output {
+                    Some(ExecOutput::Stdout(data)) => frame(STDOUT, &data),
+                    Some(ExecOutput::Stderr(data)) => frame(STDERR, &data),
+                    Some(ExecOutput::Exit(code)) => break serde_json::json!({ "exit_code": code }),
+                    None => break serde_json::json!({ "error": "Exec session ended unexpectedly" }),
+                };
+                if socket.send(Message::Binary(frame)).await.is_err() {
+                    return;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Binary(data))) => match decode_input(&data) {
+                    Some(input) => {
+                        let _ = session.input.send(input).await;
+                    }
+                    None => tracing::debug!("Ignoring invalid exec frame"),
+                },
+                Some(Ok(Message::Ping(data))) => {
+                    let _ = socket.send(Message::Pong(data)).await;
+                }
+                // Dropping the session stops the command
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    };
+    let status = frame(STATUS, status.to_string().as_bytes());
+    let _ = socket.send(Message::Binary(status)).await;
+    let _ = socket.close().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, container};
+    use orchestrator_shared_types::{Keypair, WorkloadInstanceStatus};
+    use uuid::Uuid;
+
+    fn pairs(query: &[(&str, &str)]) -> Vec<(String, String)> {
+        query
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_query_repeats_cmd() {
+        let query = ExecQuery::from_pairs(pairs(&[
+            ("cmd", "sh"),
+            ("cmd", "-c"),
+            ("cmd", "ls /"),
+            ("tty", "true"),
+            ("container", "web"),
+        ]))
+        .unwrap();
+        assert_eq!(query.cmd, ["sh", "-c", "ls /"]);
+        assert!(query.tty);
+        assert_eq!(query.container.as_deref(), Some("web"));
+
+        assert!(ExecQuery::from_pairs(pairs(&[("tty", "true")])).is_err());
+        assert!(ExecQuery::from_pairs(pairs(&[("cmd", "sh"), ("tty", "yes")])).is_err());
+    }
+
+    #[test]
+    fn test_debug_query_needs_image() {
+        let query =
+            DebugQuery::from_pairs(pairs(&[("image", "busybox"), ("tty", "true")])).unwrap();
+        assert_eq!(query.image, "busybox");
+        assert!(query.cmd.is_empty());
+        assert!(query.tty);
+
+        assert!(DebugQuery::from_pairs(pairs(&[("cmd", "sh")])).is_err());
+        assert!(DebugQuery::from_pairs(pairs(&[("image", "")])).is_err());
+    }
+
+    #[test]
+    fn test_decode_input() {
+        assert_eq!(
+            decode_input(&frame(STDIN, b"ls\n")),
+            Some(ExecInput::Stdin(b"ls\n".to_vec()))
+        );
+        assert_eq!(decode_input(&[STDIN]), Some(ExecInput::CloseStdin));
+        assert_eq!(
+            decode_input(&frame(RESIZE, br#"{"cols":120,"rows":40}"#)),
+            Some(ExecInput::Resize {
+                cols: 120,
+                rows: 40
+            })
+        );
+        assert_eq!(decode_input(&frame(RESIZE, b"80x24")), None);
+        assert_eq!(decode_input(&frame(STDOUT, b"x")), None);
+        assert_eq!(decode_input(&[]), None);
+    }
+
+    #[test]
+    fn test_container_id_skips_sidecars() {
+        let workload = WorkloadDefinition {
+            containers: vec![container("web"), container("worker")],
+            sidecars: vec![container("proxy")],
+            ..test_support::workload("web")
+        };
+        let instance = WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: workload.id,
+            node_id: Keypair::generate().public_key(),
+            container_ids: vec!["c-proxy".into(), "c-web".into(), "c-worker".into()],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+
+        let id = |name| container_id(&workload, &instance, name);
+        assert_eq!(id(None).as_deref(), Some("c-web"));
+        assert_eq!(id(Some("worker")).as_deref(), Some("c-worker"));
+        assert_eq!(id(Some("proxy")).as_deref(), Some("c-proxy"));
+        assert_eq!(id(Some("db")), None);
+    }
+}
@@ -0,0 +1,782 @@
+//! Federation of clusters.
+//!
+//! With a [`Federation`] set on the state, a control plane registers the API
+//! servers of remote clusters, each with a bearer token issued by that
+//! cluster, and then:
+//!
+//! - lists the workloads or nodes of every cluster, itself included as
+//!   [`LOCAL_CLUSTER`], in one view. Clusters that cannot be reached are
+//!   listed under `unreachable` instead of failing the request.
+//! - dispatches workloads to a chosen cluster.
+//! - proxies any other `/api/...` request to a cluster, which is how
+//!   `orch --cluster NAME` reaches it. WebSockets, such as exec and followed
+//!   logs, are not proxied. The proxy is left out of the OpenAPI document as
+//!   its operations are the remote cluster's own.
+//!
+//! Every federation endpoint needs the cluster `admin` role here; what a
+//! request may do on a remote cluster is up to that cluster's role bindings
+//! for the token.
+//!
+//! Registered clusters are kept in the state store, so every control-plane
+//! instance sharing it reaches them and they survive restarts. Their tokens
+//! are stored encrypted with an age key that every instance loads from the
+//! same file; see [`Federation::load_or_create_key`].
+
+use std::collections::BTreeMap;
+use std::path::Path as FsPath;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, RawQuery, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio_stream::wrappers::ReceiverStream;
+use utoipa::ToSchema;
+
+use orchestrator_shared_types::{Namespace, OrchestrationError, Result};
+use state_store_interface::StateStore;
+use user_config::{ConfigPaths, SecretStore};
+
+use super::error::{ApiError, ApiResult};
+use super::handlers::{
+    CreateWorkloadRequest, DryRunQuery, ListResponse, NamespaceQuery, NodeResponse,
+    WorkloadResponse,
+};
+use super::state::ApiState;
+use super::v1beta1::DEPRECATION;
+use crate::resources::{self, Resource};
+
+/// Name the control plane's own cluster is listed under.
+pub const LOCAL_CLUSTER: &str = "local";
+
+/// Response headers passed back from a remote cluster besides its status.
+const RELAYED_HEADERS: [header::HeaderName; 4] = [
+    header::CONTENT_TYPE,
+    header::WARNING,
+    header::RETRY_AFTER,
+    DEPRECATION,
+];
+
+/// A registered remote cluster.
+#[derive(Clone)]
+pub struct RemoteCluster {
+    pub name: String,
+    /// URL of its API server, without a trailing slash.
+    pub endpoint: String,
+    /// Bearer token sent with every request to it.
+    pub token: Option<String>,
+}
+
+/// A remote cluster as stored, its token encrypted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCluster {
+    pub name: String,
+    pub endpoint: String,
+    /// The token, age-encrypted and ASCII-armored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_token: Option<String>,
+}
+
+impl Resource for StoredCluster {
+    const KIND: &'static str = "remoteclusters";
+
+    fn key(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// Remote clusters this control plane reaches.
+pub struct Federation {
+    client: reqwest::Client,
+    timeout: Duration,
+    state_store: Arc<dyn StateStore>,
+    /// Encrypts tokens before they are stored.
+    key: SecretStore,
+}
+
+impl Federation {
+    /// How long listing a remote cluster may take.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Create a federation keeping its clusters in `state_store`, their
+    /// tokens encrypted with `key`. `timeout` bounds connecting to a cluster
+    /// and listing it; proxied requests, such as watches, may run longer.
+    pub fn new(
+        timeout: Duration,
+        state_store: Arc<dyn StateStore>,
+        key: SecretStore,
+    ) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(timeout)
+            .build()
+            .map_err(|e| OrchestrationError::ConfigError(format!("Federation client: {}", e)))?;
+        Ok(Self {
+            client,
+            timeout,
+            state_store,
+            key,
+        })
+    }
+
+    /// Load the age key tokens are encrypted with from `path`, generating and
+    /// saving one if there is none. Control-plane instances sharing a state
+    /// store must share the key file too.
+    pub async fn load_or_create_key(path: &FsPath) -> Result<SecretStore> {
+        let config_error = |e: user_config::ConfigError| {
+            OrchestrationError::ConfigError(format!("{}: {}", path.display(), e))
+        };
+        let paths = ConfigPaths::with_base(path.parent().unwrap_or(FsPath::new(".")));
+        if path.exists() {
+            return SecretStore::load_identity(path, paths)
+                .await
+                .map_err(config_error);
+        }
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await.map_err(|e| {
+                OrchestrationError::ConfigError(format!("{}: {}", dir.display(), e))
+            })?;
+        }
+        let key = SecretStore::generate(paths);
+        key.save_identity(path).await.map_err(config_error)?;
+        tracing::info!(path = %path.display(), "Created federation key");
+        Ok(key)
+    }
+
+    /// Register `cluster`, failing if its name is taken or invalid or its
+    /// endpoint is not an http(s) URL.
+    pub async fn register(&self, mut cluster: RemoteCluster) -> ApiResult<()> {
+        if !Namespace::is_valid_name(&cluster.name) || cluster.name == LOCAL_CLUSTER {
+            return Err(ApiError::validation_error(format!(
+                "Invalid cluster name '{}': use at most 63 lowercase letters, digits and \
+                 hyphens, other than '{}'",
+                cluster.name, LOCAL_CLUSTER
+            )));
+        }
+        let endpoint = reqwest::Url::parse(&cluster.endpoint).map_err(|e| {
+            ApiError::validation_error(format!("Invalid endpoint '{}': {}", cluster.endpoint, e))
+        })?;
+        if !matches!(endpoint.scheme(), "http" | "https") || endpoint.query().is_some() {
+            return Err(ApiError::validation_error(format!(
+                "Invalid endpoint '{}': expected an http(s) URL without a query",
+                cluster.endpoint
+            )));
+        }
+        cluster.endpoint = cluster.endpoint.trim_end_matches('/').to_string();
+        cluster.token = cluster.token.filter(|token| !token.is_empty());
+
+        let store = self.state_store.as_ref();
+        if resources::get::<StoredCluster>(store, &cluster.name)
+            .await
+            .map_err(ApiError::from)?
+            .is_some()
+        {
+            return Err(ApiError::conflict(format!(
+                "Cluster '{}' is already registered",
+                cluster.name
+            )));
+        }
+        let encrypted_token = cluster
+            .token
+            .map(|token| self.key.encrypt_armor(token.as_bytes()))
+            .transpose()
+            .map_err(|e| ApiError::internal_error(format!("Failed to encrypt token: {}", e)))?;
+        let stored = StoredCluster {
+            name: cluster.name,
+            endpoint: cluster.endpoint,
+            encrypted_token,
+        };
+        resources::put(store, &stored).await.map_err(ApiError::from)
+    }
+
+    pub async fn get(&self, name: &str) -> ApiResult<RemoteCluster> {
+        let stored = resources::get::<StoredCluster>(self.state_store.as_ref(), name)
+            .await
+            .map_err(ApiError::from)?
+            .ok_or_else(|| ApiError::not_found("Cluster", name))?;
+        self.decrypt(stored)
+    }
+
+    /// Registered clusters, by name.
+    pub async fn list(&self) -> ApiResult<Vec<RemoteCluster>> {
+        let mut stored = resources::list::<StoredCluster>(self.state_store.as_ref())
+            .await
+            .map_err(ApiError::from)?;
+        stored.sort_by(|a, b| a.name.cmp(&b.name));
+        stored
+            .into_iter()
+            .map(|cluster| self.decrypt(cluster))
+            .collect()
+    }
+
+    /// Forget a cluster, failing if it is not registered.
+    pub async fn remove(&self, name: &str) -> ApiResult<()> {
+        let store = self.state_store.as_ref();
+        if resources::get::<StoredCluster>(store, name)
+            .await
+            .map_err(ApiError::from)?
+            .is_none()
+        {
+            return Err(ApiError::not_found("Cluster", name));
+        }
+        resources::delete::<StoredCluster>(store, name)
+            .await
+            .map_err(ApiError::from)
+    }
+
+    fn decrypt(&self, stored: StoredCluster) -> ApiResult<RemoteCluster> {
+        let token = stored
+            .encrypted_token
+            .map(|encrypted| {
+                let token = self.key.decrypt_armor(&encrypted).map_err(|e| {
+                    ApiError::internal_error(format!(
+                        "Failed to decrypt the token of cluster '{}': {}",
+                        stored.name, e
+                    ))
+                })?;
+                String::from_utf8(token).map_err(|e| ApiError::internal_error(e.to_string()))
+            })
+            .transpose()?;
+        Ok(RemoteCluster {
+            name: stored.name,
+            endpoint: stored.endpoint,
+            token,
+        })
+    }
+
+    /// Request `path`, relative to the API server, from `cluster`.
+    fn request(
+        &self,
+        cluster: &RemoteCluster,
+        method: Method,
+        path: &str,
+    ) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}/{}", cluster.endpoint, path));
+        match &cluster.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// The objects of `path` on every cluster, with `local` as this control
+    /// plane's.
+    async fn list_everywhere(
+        &self,
+        path: &'static str,
+        query: Vec<(&'static str, String)>,
+        local: Vec<Value>,
+    ) -> ApiResult<FederatedListResponse> {
+        let mut fetches = JoinSet::new();
+        for cluster in self.list().await? {
+            let request = self
+                .request(&cluster, Method::GET, path)
+                .query(&query)
+                .timeout(self.timeout);
+            fetches.spawn(async move {
+                let result = async {
+                    request
+                        .send()
+                        .await?
+                        .error_for_status()?
+                        .json::<ListResponse<Value>>()
+                        .await
+                }
+                .await;
+                (cluster.name, result)
+            });
+        }
+        let mut results = BTreeMap::new();
+        while let Some(Ok((name, result))) = fetches.join_next().await {
+            results.insert(name, result);
+        }
+
+        let mut items: Vec<FederatedObject> = local
+            .into_iter()
+            .map(|object| FederatedObject::new(LOCAL_CLUSTER, object))
+            .collect();
+        let mut unreachable = Vec::new();
+        for (cluster, result) in results {
+            match result {
+                Ok(list) => items.extend(
+                    list.items
+                        .into_iter()
+                        .map(|object| FederatedObject::new(&cluster, object)),
+                ),
+                Err(e) => unreachable.push(UnreachableCluster {
+                    cluster,
+                    error: e.to_string(),
+                }),
+            }
+        }
+        Ok(FederatedListResponse {
+            count: items.len(),
+            items,
+            unreachable,
+        })
+    }
+
+    /// Send a request to `cluster` and pass its response back as it streams
+    /// in, so watches keep working.
+    async fn forward(
+        &self,
+        cluster: &RemoteCluster,
+        method: Method,
+        path: &str,
+        query: Option<String>,
+        content_type: Option<&str>,
+        body: Bytes,
+    ) -> ApiResult<Response> {
+        let path = match query {
+            Some(query) => format!("{}?{}", path, query),
+            None => path.to_string(),
+        };
+        let mut request = self.request(cluster, method, &path).body(body);
+        if let Some(content_type) = content_type {
+            request = request.header(header::CONTENT_TYPE, content_type);
+        }
+        let mut remote = request.send().await.map_err(|e| {
+            ApiError::new(
+                format!("Cluster '{}' is unreachable: {}", cluster.name, e),
+                "CLUSTER_UNREACHABLE",
+            )
+        })?;
+
+        let mut response = Response::builder().status(remote.status());
+        for name in RELAYED_HEADERS {
+            if let Some(value) = remote.headers().get(&name) {
+                response = response.header(name, value);
+            }
+        }
+        let (tx, rx) = mpsc::channel::<reqwest::Result<Bytes>>(16);
+        tokio::spawn(async move {
+            loop {
+                let chunk = match remote.chunk().await {
+                    Ok(Some(chunk)) => Ok(chunk),
+                    Ok(None) => break,
+                    Err(e) => Err(e),
+                };
+                let failed = chunk.is_err();
+                // A client that goes away stops the remote request
+                if tx.send(chunk).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        response
+            .body(Body::from_stream(ReceiverStream::new(rx)))
+            .map_err(|e| ApiError::internal_error(e.to_string()))
+    }
+}
+
+/// The path a proxy request is for, if it is an API path.
+fn proxied_path(path: &str) -> ApiResult<&str> {
+    let path = path.trim_start_matches('/');
+    let traverses = path
+        .split('/')
+        .any(|segment| segment == "." || segment == "..");
+    if !path.starts_with("api/") || traverses {
+        return Err(ApiError::bad_request(format!(
+            "Only API paths are proxied, not /{}",
+            path
+        )));
+    }
+    Ok(path)
+}
+
+/// Request to register a remote cluster.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RegisterClusterRequest {
+    /// Name the cluster is selected by, e.g. "prod-eu".
+    pub name: String,
+    /// URL of the cluster's API server, e.g. "https://eu.example.com:9090".
+    pub endpoint: String,
+    /// Bearer token issued by the cluster, e.g. by `orch login` against it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// A registered remote cluster; its token is never returned.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RemoteClusterResponse {
+    pub name: String,
+    pub endpoint: String,
+    /// Whether requests to the cluster carry a token.
+    pub authenticated: bool,
+}
+
+impl From<RemoteCluster> for RemoteClusterResponse {
+    fn from(cluster: RemoteCluster) -> Self {
+        Self {
+            name: cluster.name,
+            endpoint: cluster.endpoint,
+            authenticated: cluster.token.is_some(),
+        }
+    }
+}
+
+/// An object of one of the federated clusters.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FederatedObject {
+    /// Cluster holding the object, "local" for this control plane's.
+    pub cluster: String,
+    /// The object as that cluster's API returns it.
+    #[schema(value_type = Object)]
+    pub object: Value,
+}
+
+impl FederatedObject {
+    fn new(cluster: &str, object: Value) -> Self {
+        Self {
+            cluster: cluster.to_string(),
+            object,
+        }
+    }
+}
+
+/// A registered cluster that could not be listed.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UnreachableCluster {
+    pub cluster: String,
+    pub error: String,
+}
+
+/// Objects of every federated cluster.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FederatedListResponse {
+    pub items: Vec<FederatedObject>,
+    pub count: usize,
+    /// Clusters missing from `items`.
+    pub unreachable: Vec<UnreachableCluster>,
+}
+
+fn federation(state: &ApiState) -> ApiResult<&Federation> {
+    state
+        .federation
+        .as_deref()
+        .ok_or_else(|| ApiError::internal_error("Federation not configured"))
+}
+
+/// Register a remote cluster.
+#[utoipa::path(
+    post,
+    path = "/api/v1/federation/clusters",
+    tag = "federation",
+    request_body = RegisterClusterRequest,
+    responses(
+        (status = 201, description = "Cluster registered", body = RemoteClusterResponse),
+        (status = 400, description = "Invalid name or endpoint", body = ApiError),
+        (status = 409, description = "Name already registered", body = ApiError),
+    )
+)]
+pub async fn register_cluster(
+    State(state): State<ApiState>,
+    Json(request): Json<RegisterClusterRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let federation = federation(&state)?;
+    federation
+        .register(RemoteCluster {
+            name: request.name.clone(),
+            endpoint: request.endpoint,
+            token: request.token,
+        })
+        .await?;
+    let cluster = federation.get(&request.name).await?;
+    tracing::info!(cluster = %cluster.name, endpoint = %cluster.endpoint, "Registered cluster");
+    Ok((
+        StatusCode::CREATED,
+        Json(RemoteClusterResponse::from(cluster)),
+    ))
+}
+
+/// List registered remote clusters.
+#[utoipa::path(
+    get,
+    path = "/api/v1/federation/clusters",
+    tag = "federation",
+    responses(
+        (status = 200, description = "Remote clusters", body = ListResponse<RemoteClusterResponse>),
+    )
+)]
+pub async fn list_clusters(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let clusters: Vec<RemoteClusterResponse> = federation(&state)?
+        .list()
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    Ok(Json(ListResponse::new(clusters)))
+}
+
+/// Forget a remote cluster.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/federation/clusters/{name}",
+    tag = "federation",
+    params(("name" = String, Path, description = "Cluster name")),
+    responses(
+        (status = 204, description = "Cluster removed"),
+        (status = 404, description = "Cluster not found", body = ApiError),
+    )
+)]
+pub async fn remove_cluster(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    federation(&state)?.remove(&name).await?;
+    tracing::info!(cluster = %name, "Removed cluster");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List the workloads of every cluster, or those of one namespace.
+#[utoipa::path(
+    get,
+    path = "/api/v1/federation/workloads",
+    tag = "federation",
+    params(NamespaceQuery),
+    responses(
+        (
+            status = 200,
+            description = "Workloads, as the clusters return them",
+            body = FederatedListResponse
+        ),
+    )
+)]
+pub async fn list_federated_workloads(
+    State(state): State<ApiState>,
+    Query(scope): Query<NamespaceQuery>,
+) -> ApiResult<impl IntoResponse> {
+    let federation = federation(&state)?;
+    let mut local = Vec::new();
+    for workload in state
+        .state_store
+        .list_workloads()
+        .await
+        .map_err(ApiError::from)?
+    {
+        if scope
+            .namespace
+            .as_ref()
+            .is_some_and(|ns| *ns != workload.namespace)
+        {
+            continue;
+        }
+        local.push(to_value(WorkloadResponse::from(workload))?);
+    }
+    let query = scope
+        .namespace
+        .map(|namespace| vec![("namespace", namespace)])
+        .unwrap_or_default();
+    Ok(Json(
+        federation
+            .list_everywhere("api/v1/workloads", query, local)
+            .await?,
+    ))
+}
+
+/// List the nodes of every cluster.
+#[utoipa::path(
+    get,
+    path = "/api/v1/federation/nodes",
+    tag = "federation",
+    responses(
+        (
+            status = 200,
+            description = "Nodes, as the clusters return them",
+            body = FederatedListResponse
+        ),
+    )
+)]
+pub async fn list_federated_nodes(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let federation = federation(&state)?;
+    let mut local = Vec::new();
+    for node in state
+        .state_store
+        .list_nodes()
+        .await
+        .map_err(ApiError::from)?
+    {
+        local.push(to_value(NodeResponse::from(node))?);
+    }
+    Ok(Json(
+        federation
+            .list_everywhere("api/v1/nodes", Vec::new(), local)
+            .await?,
+    ))
+}
+
+fn to_value(object: impl Serialize) -> ApiResult<Value> {
+    serde_json::to_value(object).map_err(|e| ApiError::internal_error(e.to_string()))
+}
+
+/// Create a workload on a remote cluster.
+///
+/// The cluster admits the workload as it would a create sent to it directly,
+/// and its response is passed back unchanged.
+#[utoipa::path(
+    post,
+    path = "/api/v1/federation/clusters/{name}/workloads",
+    tag = "federation",
+    params(("name" = String, Path, description = "Cluster name"), DryRunQuery),
+    request_body = CreateWorkloadRequest,
+    responses(
+        (status = 201, description = "Workload created on the cluster", body = WorkloadResponse),
+        (status = 404, description = "Cluster not found", body = ApiError),
+        (status = 502, description = "Cluster unreachable", body = ApiError),
+    )
+)]
+pub async fn dispatch_workload(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    RawQuery(query): RawQuery,
+    Json(request): Json<CreateWorkloadRequest>,
+) -> ApiResult<Response> {
+    let federation = federation(&state)?;
+    let cluster = federation.get(&name).await?;
+    let body = serde_json::to_vec(&request).map_err(|e| ApiError::internal_error(e.to_string()))?;
+    tracing::info!(cluster = %name, workload = %request.name, "Dispatching workload");
+    federation
+        .forward(
+            &cluster,
+            Method::POST,
+            "api/v1/workloads",
+            query,
+            Some("application/json"),
+            Bytes::from(body),
+        )
+        .await
+}
+
+/// Proxy a request to the API of a remote cluster.
+pub async fn proxy(
+    State(state): State<ApiState>,
+    Path((name, path)): Path<(String, String)>,
+    method: Method,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<Response> {
+    let federation = federation(&state)?;
+    let cluster = federation.get(&name).await?;
+    let path = proxied_path(&path)?;
+    if headers.contains_key(header::UPGRADE) {
+        return Err(ApiError::bad_request(
+            "WebSocket requests are not proxied to federated clusters",
+        ));
+    }
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    federation
+        .forward(&cluster, method, path, query, content_type, body)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    fn cluster(name: &str, endpoint: &str) -> RemoteCluster {
+        RemoteCluster {
+            name: name.to_string(),
+            endpoint: endpoint.to_string(),
+            token: Some("secret".to_string()),
+        }
+    }
+
+    fn federation(store: Arc<InMemoryStateStore>) -> Federation {
+        let key = SecretStore::generate(ConfigPaths::with_base("/tmp"));
+        Federation::new(Federation::DEFAULT_TIMEOUT, store, key).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_register() {
+        let federation = federation(Arc::new(InMemoryStateStore::new()));
+        federation
+            .register(cluster("prod-eu", "https://eu.example.com:9090/"))
+            .await
+            .unwrap();
+        assert_eq!(
+            federation.get("prod-eu").await.unwrap().endpoint,
+            "https://eu.example.com:9090"
+        );
+
+        let conflict = federation
+            .register(cluster("prod-eu", "https://other.example.com"))
+            .await
+            .unwrap_err();
+        assert_eq!(conflict.code, "CONFLICT");
+        for (name, endpoint) in [
+            ("local", "https://eu.example.com"),
+            ("Prod_EU", "https://eu.example.com"),
+            ("prod-us", "ftp://us.example.com"),
+            ("prod-us", "us.example.com"),
+            ("prod-us", "https://us.example.com/?x=1"),
+        ] {
+            let error = federation
+                .register(cluster(name, endpoint))
+                .await
+                .unwrap_err();
+            assert_eq!(error.code, "VALIDATION_ERROR", "{} {}", name, endpoint);
+        }
+
+        federation.remove("prod-eu").await.unwrap();
+        assert!(federation.list().await.unwrap().is_empty());
+        assert_eq!(
+            federation.remove("prod-eu").await.unwrap_err().code,
+            "NOT_FOUND"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clusters_are_stored_with_encrypted_tokens() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let dir = std::env::temp_dir().join(format!("federation-key-{}", uuid::Uuid::new_v4()));
+        let key_file = dir.join("federation.key");
+        let key = Federation::load_or_create_key(&key_file).await.unwrap();
+        Federation::new(Federation::DEFAULT_TIMEOUT, store.clone(), key)
+            .unwrap()
+            .register(cluster("prod-eu", "https://eu.example.com"))
+            .await
+            .unwrap();
+
+        let stored = resources::get::<StoredCluster>(store.as_ref(), "prod-eu")
+            .await
+            .unwrap()
+            .unwrap();
+        let encrypted = stored.encrypted_token.unwrap();
+        assert!(!encrypted.contains("secret"));
+
+        // Another instance with the same key file reads the token back
+        let key = Federation::load_or_create_key(&key_file).await.unwrap();
+        let restarted = Federation::new(Federation::DEFAULT_TIMEOUT, store.clone(), key).unwrap();
+        let clusters = restarted.list().await.unwrap();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].token.as_deref(), Some("secret"));
+
+        // One with another key cannot
+        let stranger = federation(store);
+        assert!(stranger.get("prod-eu").await.is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_proxied_path() {
+        assert_eq!(
+            proxied_path("api/v1/workloads").unwrap(),
+            "api/v1/workloads"
+        );
+        assert_eq!(proxied_path("/api/v1/nodes").unwrap(), "api/v1/nodes");
+        assert!(proxied_path("debug/pprof/profile").is_err());
+        assert!(proxied_path("api/../debug/pprof/profile").is_err());
+    }
+}
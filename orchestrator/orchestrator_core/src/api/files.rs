@@ -0,0 +1,269 @@
+//! Copying files into and out of containers, for `orch cp`.
+//!
+//! Files travel as tar archives, streamed through a `tar` run in the
+//! container over the runtime's exec channel, so images need a `tar` binary
+//! but no other changes:
+//!
+//! - `GET /api/v1/instances/:id/files?path=/models/model.onnx` returns an
+//!   archive holding the file or directory at `path` under its base name.
+//! - `PUT /api/v1/instances/:id/files?path=/models` extracts the archive in
+//!   the request body into the existing directory `path`.
+//!
+//! Both run in `container`, or the instance's first main container when
+//! absent, and take `namespace` like exec does.
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use container_runtime_interface::{ExecInput, ExecOptions, ExecOutput, ExecSession};
+
+use super::error::{ApiError, ApiResult};
+use super::handlers;
+use super::state::ApiState;
+
+/// Query parameters of a file copy.
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FilesQuery {
+    /// Absolute path in the container.
+    pub path: String,
+    /// Container to copy from or to; the first main container when absent.
+    pub container: Option<String>,
+    /// Only instances in this namespace.
+    pub namespace: Option<String>,
+}
+
+/// Copy a file or directory out of a container of an instance, as a tar
+/// archive.
+#[utoipa::path(
+    get,
+    path = "/api/v1/instances/{instance_id}/files",
+    tag = "workloads",
+    params(("instance_id" = Uuid, Path, description = "Instance ID"), FilesQuery),
+    responses(
+        (
+            status = 200,
+            description = "Tar archive holding the file or directory under its base name",
+            content_type = "application/x-tar"
+        ),
+        (
+            status = 400,
+            description = "Relative path, unknown container or tar failed",
+            body = ApiError
+        ),
+        (status = 404, description = "Instance or path not found", body = ApiError),
+    )
+)]
+pub async fn download_files(
+    State(state): State<ApiState>,
+    Path(instance_id): Path<Uuid>,
+    Query(query): Query<FilesQuery>,
+) -> ApiResult<Response> {
+    let (dir, name) = split_path(&query.path)?;
+    let command = ["tar", "-cf", "-", "-C", dir, name];
+    let mut session = start(&state, instance_id, &query, &command).await?;
+    let _ = session.input.send(ExecInput::CloseStdin).await;
+
+    // Wait for the archive to start, so a missing path fails the request
+    // instead of sending an empty archive
+    let mut stderr = Vec::new();
+    let first = loop {
+        match session.output.recv().await {
+            Some(ExecOutput::Stdout(data)) => break data,
+            Some(ExecOutput::Stderr(data)) => stderr.extend(data),
+            Some(ExecOutput::Exit(code)) => return Err(tar_failed(&query.path, code, &stderr)),
+            None => return Err(ApiError::internal_error("Exec session ended unexpectedly")),
+        }
+    };
+
+    let (tx, rx) = mpsc::channel::<std::io::Result<Vec<u8>>>(16);
+    tokio::spawn(async move {
+        if tx.send(Ok(first)).await.is_err() {
+            return;
+        }
+        while let Some(output) = session.output.recv().await {
+            let chunk = match output {
+                ExecOutput::Stdout(data) => Ok(data),
+                ExecOutput::Stderr(data) => {
+                    stderr.extend(data);
+                    continue;
+                }
+                ExecOutput::Exit(code) if code.unwrap_or(0) == 0 => break,
+                ExecOutput::Exit(code) => Err(std::io::Error::other(format!(
+                    "tar exited with {:?}: {}",
+                    code,
+                    String::from_utf8_lossy(&stderr).trim()
+                ))),
+            };
+            let failed = chunk.is_err();
+            // A client that goes away drops the session, which stops tar
+            if tx.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-tar")],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+        .into_response())
+}
+
+/// Extract a tar archive into a directory of a container of an instance.
+#[utoipa::path(
+    put,
+    path = "/api/v1/instances/{instance_id}/files",
+    tag = "workloads",
+    params(("instance_id" = Uuid, Path, description = "Instance ID"), FilesQuery),
+    request_body(content = Vec<u8>, content_type = "application/x-tar"),
+    responses(
+        (status = 204, description = "Archive extracted"),
+        (
+            status = 400,
+            description = "Relative path, unknown container or tar failed",
+            body = ApiError
+        ),
+        (status = 404, description = "Instance or directory not found", body = ApiError),
+    )
+)]
+pub async fn upload_files(
+    State(state): State<ApiState>,
+    Path(instance_id): Path<Uuid>,
+    Query(query): Query<FilesQuery>,
+    body: Body,
+) -> ApiResult<StatusCode> {
+    if !query.path.starts_with('/') {
+        return Err(ApiError::bad_request("path must be absolute"));
+    }
+    let command = ["tar", "-xf", "-", "-C", query.path.as_str()];
+    let ExecSession { input, mut output } = start(&state, instance_id, &query, &command).await?;
+
+    // Feed stdin while reading output, so tar never blocks on a full pipe
+    let feed = async move {
+        let mut chunks = body.into_data_stream();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk
+                .map_err(|e| ApiError::bad_request(format!("Failed to read the archive: {}", e)))?;
+            if input.send(ExecInput::Stdin(chunk.to_vec())).await.is_err() {
+                // tar exited early; its status says why
+                break;
+            }
+        }
+        let _ = input.send(ExecInput::CloseStdin).await;
+        Ok::<(), ApiError>(())
+    };
+    let wait = async {
+        let mut stderr = Vec::new();
+        loop {
+            match output.recv().await {
+                Some(ExecOutput::Stdout(_)) => {}
+                Some(ExecOutput::Stderr(data)) => stderr.extend(data),
+                Some(ExecOutput::Exit(code)) => break Ok((code, stderr)),
+                None => break Err(ApiError::internal_error("Exec session ended unexpectedly")),
+            }
+        }
+    };
+    let (fed, waited) = tokio::join!(feed, wait);
+    let (code, stderr) = waited?;
+    if code.unwrap_or(0) != 0 {
+        return Err(tar_failed(&query.path, code, &stderr));
+    }
+    fed?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Start `command` in the container `query` names.
+async fn start(
+    state: &ApiState,
+    instance_id: Uuid,
+    query: &FilesQuery,
+    command: &[&str],
+) -> ApiResult<ExecSession> {
+    let runtime = state
+        .container_runtime
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured"))?;
+    let container_id = handlers::instance_container(
+        state,
+        instance_id,
+        query.namespace.clone(),
+        query.container.as_deref(),
+    )
+    .await?;
+    let options = ExecOptions {
+        command: command.iter().map(|arg| arg.to_string()).collect(),
+        tty: false,
+    };
+    runtime
+        .exec(&container_id, &options)
+        .await
+        .map_err(ApiError::from)
+}
+
+/// The directory of an absolute `path` and the name of what it names, as
+/// `tar -C DIR NAME` takes them.
+fn split_path(path: &str) -> ApiResult<(&str, &str)> {
+    if !path.starts_with('/') {
+        return Err(ApiError::bad_request("path must be absolute"));
+    }
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rsplit_once('/') {
+        None => Ok(("/", ".")),
+        Some(("", name)) => Ok(("/", name)),
+        Some((dir, name)) => Ok((dir, name)),
+    }
+}
+
+/// The error of a tar that exited with `code`, printing `stderr`.
+fn tar_failed(path: &str, code: Option<i32>, stderr: &[u8]) -> ApiError {
+    let message = String::from_utf8_lossy(stderr);
+    if message.contains("No such file or directory") {
+        return ApiError::not_found("Path", path);
+    }
+    ApiError::bad_request(format!(
+        "tar failed in the container (exit code {}): {}",
+        code.map_or_else(|| "unknown".to_string(), |code| code.to_string()),
+        message.trim()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_path() {
+        assert_eq!(
+            split_path("/models/model.onnx").unwrap(),
+            ("/models", "model.onnx")
+        );
+        assert_eq!(split_path("/models/").unwrap(), ("/", "models"));
+        assert_eq!(split_path("/etc").unwrap(), ("/", "etc"));
+        assert_eq!(split_path("/").unwrap(), ("/", "."));
+        assert!(split_path("models").is_err());
+    }
+
+    #[test]
+    fn test_tar_failed_maps_missing_paths() {
+        let error = tar_failed(
+            "/models/x",
+            Some(2),
+            b"tar: x: Cannot stat: No such file or directory\n",
+        );
+        assert_eq!(error.code, "NOT_FOUND");
+        let error = tar_failed("/models", Some(127), b"sh: tar: not found\n");
+        assert_eq!(error.code, "BAD_REQUEST");
+        assert!(error.error.contains("exit code 127"));
+    }
+}
@@ -1,49 +1,195 @@
 //! API request handlers.
 
 use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime};
 
+use async_trait::async_trait;
 use axum::{
+    body::Bytes,
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Path, Query, State,
+        Extension, Path, Query, State,
     },
-    http::StatusCode,
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-use container_runtime_interface::LogOptions as RuntimeLogOptions;
+use container_runtime_interface::{
+    DebugOptions, ExecOptions, ImageInspection, ImagePullStatus, ImageScan, LogMatcher,
+    LogOptions as RuntimeLogOptions, LogSearchOptions, ResourceLimits, Severity,
+};
 
 use orchestrator_shared_types::{
-    ContainerConfig, Node, NodeId, NodeResources, NodeStatus, PortMapping,
-    WorkloadDefinition, WorkloadInstance, WorkloadInstanceStatus,
+    ContainerConfig, ContainerId, ContainerLimits, ContainerRestartStatus, Event, HugepageLimit,
+    InstanceAntiAffinity, IoThrottle, Namespace,
+    Node, NodeAffinityRules, NodeId, NodeResources, NodeStatus, ObjectReference, Placement,
+    LifecycleHandler, PortMapping, Probe, Rlimit, DnsConfig, HostAlias, OrchestrationError,
+    RestartPolicy, WorkloadDefinition, WorkloadInstance, WorkloadInstanceStatus, WorkloadRevision,
+    DEFAULT_NAMESPACE, IpFamilyPolicy,
+};
+
+use crate::accounting::UsageReport;
+use crate::autoscaling::ReplicaScaler;
+use crate::backup::ClusterBackup;
+use crate::controllers::{
+    DaemonSet, DaemonSetController, DaemonSetId, StatefulSet, StatefulSetController, StatefulSetId,
+};
+use crate::disruption::{
+    DisruptionBudget, DisruptionBudgetId, DisruptionBudgetStatus, DisruptionController,
 };
+use crate::events::EventRecorder;
+use crate::fsck::{ConsistencyChecker, Discrepancy, FsckReport};
+use crate::jobs::{ConcurrencyPolicy, CronJob, CronJobController, CronJobId, Job};
+use crate::migration::InstanceMigrator;
+use crate::network::tunnel::{self, TunnelManager};
+use crate::network::{
+    Endpoint, NetworkPolicy, NetworkPolicyController, NetworkPolicyId, PolicyRule, Service,
+    ServicePort, ServiceProxy, SessionAffinity,
+};
+use crate::prepull::{ImagePrepuller, Prepull, PrepullId};
+use crate::resources;
+use crate::rollout::{self, RolloutStatus};
 
+use super::audit::{AuditQuery, AuditRecord};
+use super::auth::{AuthInfo, AuthMethod};
 use super::error::{ApiError, ApiResult};
+use super::exec::{self, DebugQuery, ExecQuery};
+use super::list::ListQuery;
+use super::patch::{self, JsonPatchBody};
+use super::rbac::RoleBinding;
 use super::state::ApiState;
+use super::watch::{self, WatchQuery};
 
 // ============================================================================
 // Request/Response Types
 // ============================================================================
 
 /// Request to create a new workload.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateWorkloadRequest {
     /// User-friendly name for the workload.
     pub name: String,
     /// Container configurations.
     pub containers: Vec<ContainerConfigRequest>,
+    /// Containers run to completion, in order, before the others start.
+    #[serde(default)]
+    pub init_containers: Vec<ContainerConfigRequest>,
+    /// Containers started before and stopped after the main containers.
+    #[serde(default)]
+    pub sidecars: Vec<ContainerConfigRequest>,
     /// Desired number of replicas.
     pub replicas: u32,
     /// Optional labels for scheduling and selection.
     #[serde(default)]
     pub labels: HashMap<String, String>,
+    /// Labels a node must carry to run this workload.
+    #[serde(default)]
+    pub node_selector: HashMap<String, String>,
+    /// Required and preferred node label rules.
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub node_affinity: Option<NodeAffinityRules>,
+    /// Spreads replicas across nodes or topology domains.
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub instance_anti_affinity: Option<InstanceAntiAffinity>,
+    /// Scheduling strategy for this workload, e.g. "bin-pack" or "spread".
+    #[serde(default)]
+    pub scheduling_strategy: Option<String>,
+    /// Replicas that fit on no node may preempt instances of workloads with
+    /// a lower priority (default: 0).
+    #[serde(default)]
+    pub priority: i32,
+    /// When exited containers are restarted (default: always).
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub restart_policy: RestartPolicy,
+    /// Namespace to create the workload in (default: "default"). A workload
+    /// cannot move to another namespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Version of the workload this update was based on, as last returned by
+    /// the API. Required on update; ignored on create.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_version: Option<u64>,
+}
+
+/// Request to change some fields of a workload.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct PatchWorkloadRequest {
+    /// Version of the workload this change was based on (required).
+    #[serde(default)]
+    pub resource_version: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<u32>,
+    /// Replaces all labels when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<HashMap<String, String>>,
+}
+
+/// Request to set a workload's replica count.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScaleRequest {
+    pub replicas: u32,
+    /// Only scale if the workload is still at this version; when absent the
+    /// latest version is scaled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_version: Option<u64>,
+}
+
+/// Desired and current replicas of a workload.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScaleResponse {
+    pub workload_id: Uuid,
+    /// Replicas the workload asks for.
+    pub replicas: u32,
+    /// Instances currently running.
+    pub running_replicas: u32,
+    /// Instances that exist, in any state.
+    pub current_replicas: u32,
+    pub resource_version: u64,
+}
+
+/// One recorded template of a workload.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RevisionResponse {
+    pub revision: u64,
+    pub created_at: DateTime<Utc>,
+    /// Whether the workload runs this template now.
+    pub current: bool,
+    /// The workload as of this revision.
+    pub workload: WorkloadResponse,
+}
+
+/// Query parameters of a rollback.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UndoQuery {
+    /// Revision to roll back to; the one before the current template when
+    /// absent.
+    pub to: Option<u64>,
+}
+
+impl CreateWorkloadRequest {
+    fn placement(&self) -> Placement {
+        Placement {
+            node_selector: self.node_selector.clone(),
+            node_affinity: self.node_affinity.clone(),
+            instance_anti_affinity: self.instance_anti_affinity.clone(),
+            scheduling_strategy: self.scheduling_strategy.clone(),
+            priority: self.priority,
+        }
+    }
 }
 
 /// Container configuration in API request.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ContainerConfigRequest {
     pub name: String,
     pub image: String,
@@ -57,21 +203,68 @@ pub struct ContainerConfigRequest {
     pub ports: Vec<PortMappingRequest>,
     #[serde(default)]
     pub resource_requests: ResourceRequestsRequest,
+    /// Probe gating service traffic to this container's instances.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub readiness_probe: Option<Probe>,
+    /// Seconds a stop may take, preStop hook included, before the container
+    /// is killed; the node's default when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub termination_grace_period_seconds: Option<u64>,
+    /// Hook run before the container is sent SIGTERM: `{"type": "exec",
+    /// "command": [...]}` or `{"type": "http_get", "port": 8080, "path": "/drain"}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub pre_stop: Option<LifecycleHandler>,
+    /// Namespaced kernel parameters, e.g. `{"net.core.somaxconn": "4096"}`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sysctls: HashMap<String, String>,
+    /// Process limits: `{"name": "memlock", "soft": ..., "hard": ...}`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<Object>)]
+    pub rlimits: Vec<Rlimit>,
+    /// Size of `/dev/shm`; 64 MB when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shm_size_mb: Option<u64>,
+    /// Hostname the container sees; the container name when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Extra /etc/hosts entries: `{"ip": "10.0.0.7", "hostnames": ["db"]}`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<Object>)]
+    pub host_aliases: Vec<HostAlias>,
+    /// /etc/resolv.conf settings: `{"nameservers": [...], "searches": [...],
+    /// "options": ["ndots:2"]}`; the node-local DNS cache when absent.
+    #[serde(default, skip_serializing_if = "DnsConfig::is_empty")]
+    #[schema(value_type = Object)]
+    pub dns: DnsConfig,
+    /// Runtime settings, e.g. `{"ingress_bandwidth": "1G", "egress_bandwidth":
+    /// "100M"}` to limit the container's traffic in bits per second.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub annotations: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PortMappingRequest {
     pub container_port: u16,
     pub host_port: Option<u16>,
     #[serde(default = "default_protocol")]
     pub protocol: String,
+    /// Host address to bind, e.g. "0.0.0.0" or "::". Defaults to all addresses.
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub host_ip: Option<IpAddr>,
+}
+
+fn default_namespace() -> String {
+    DEFAULT_NAMESPACE.to_string()
 }
 
 fn default_protocol() -> String {
     "tcp".to_string()
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
 pub struct ResourceRequestsRequest {
     #[serde(default)]
     pub cpu_cores: f32,
@@ -79,19 +272,61 @@ pub struct ResourceRequestsRequest {
     pub memory_mb: u64,
     #[serde(default)]
     pub disk_mb: u64,
+    /// Maximum number of processes; the node's default when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids_limit: Option<i64>,
+    /// Relative block I/O weight, from 10 to 1000.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_weight: Option<u16>,
+    /// Per-device limits: `{"major": 8, "minor": 0, "read_bps": ...,
+    /// "write_bps": ..., "read_iops": ..., "write_iops": ...}`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<Object>)]
+    pub io_throttle: Vec<IoThrottle>,
+    /// Hugepage limits: `{"page_size": "2MB", "limit_bytes": ...}`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<Object>)]
+    pub hugepages: Vec<HugepageLimit>,
+}
+
+impl ResourceRequestsRequest {
+    /// The limits beyond CPU and memory, which only containers have.
+    fn limits(&self) -> ContainerLimits {
+        ContainerLimits {
+            pids: self.pids_limit,
+            io_weight: self.io_weight,
+            io_throttle: self.io_throttle.clone(),
+            hugepages: self.hugepages.clone(),
+        }
+    }
 }
 
 /// Response for workload operations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct WorkloadResponse {
     pub id: Uuid,
     pub name: String,
     pub replicas: u32,
     pub labels: HashMap<String, String>,
     pub containers: Vec<ContainerConfigResponse>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub init_containers: Vec<ContainerConfigResponse>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sidecars: Vec<ContainerConfigResponse>,
+    #[serde(default, skip_serializing_if = "Placement::is_empty")]
+    #[schema(value_type = Object)]
+    pub placement: Placement,
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    /// Pass back on update; changes with every write.
+    #[serde(default)]
+    pub resource_version: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ContainerConfigResponse {
     pub name: String,
     pub image: String,
@@ -100,31 +335,88 @@ pub struct ContainerConfigResponse {
     pub env_vars: HashMap<String, String>,
     pub ports: Vec<PortMappingResponse>,
     pub resource_requests: ResourceRequestsResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub readiness_probe: Option<Probe>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub termination_grace_period_seconds: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub pre_stop: Option<LifecycleHandler>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sysctls: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<Object>)]
+    pub rlimits: Vec<Rlimit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shm_size_mb: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<Object>)]
+    pub host_aliases: Vec<HostAlias>,
+    #[serde(default, skip_serializing_if = "DnsConfig::is_empty")]
+    #[schema(value_type = Object)]
+    pub dns: DnsConfig,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub annotations: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PortMappingResponse {
     pub container_port: u16,
     pub host_port: Option<u16>,
     pub protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub host_ip: Option<IpAddr>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ResourceRequestsResponse {
     pub cpu_cores: f32,
     pub memory_mb: u64,
     pub disk_mb: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids_limit: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_weight: Option<u16>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<Object>)]
+    pub io_throttle: Vec<IoThrottle>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<Object>)]
+    pub hugepages: Vec<HugepageLimit>,
 }
 
 /// Response for list operations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ListResponse<T> {
     pub items: Vec<T>,
+    /// Items in this response.
     pub count: usize,
+    /// Pass as `continue` to read the next page; absent on the last page.
+    #[serde(rename = "continue", default, skip_serializing_if = "Option::is_none")]
+    pub continue_token: Option<String>,
+}
+
+impl<T> ListResponse<T> {
+    pub fn new(items: Vec<T>) -> Self {
+        Self {
+            count: items.len(),
+            items,
+            continue_token: None,
+        }
+    }
+
+    pub fn with_continue(mut self, continue_token: Option<String>) -> Self {
+        self.continue_token = continue_token;
+        self
+    }
 }
 
 /// Node response.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct NodeResponse {
     pub id: String,
     pub address: String,
@@ -132,21 +424,69 @@ pub struct NodeResponse {
     pub labels: HashMap<String, String>,
     pub resources_capacity: ResourceRequestsResponse,
     pub resources_allocatable: ResourceRequestsResponse,
+    /// Cordoned: no new instances are placed on the node.
+    #[serde(default)]
+    pub unschedulable: bool,
+    /// Image pulls running and queued on the node, when the runtime reports them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub image_pulls: Option<ImagePullStatus>,
+    #[serde(default)]
+    pub resource_version: u64,
 }
 
 /// Workload instance response.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct InstanceResponse {
     pub id: Uuid,
     pub workload_id: Uuid,
     pub node_id: String,
     pub container_ids: Vec<String>,
     pub status: String,
+    /// Instance addresses; IPv6 addresses are rendered without brackets.
+    pub ip_addresses: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub restarts: Vec<ContainerRestartResponse>,
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    /// Latest readiness probe verdict; absent until the instance is probed or
+    /// when none of its containers has a readiness probe.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready: Option<bool>,
+    #[serde(default)]
+    pub resource_version: u64,
+}
+
+/// Restart history of one container in an instance.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ContainerRestartResponse {
+    pub container_name: String,
+    pub restart_count: u32,
+    pub consecutive_failures: u32,
+    pub last_exit_code: Option<i32>,
+    /// Seconds until the next restart attempt while crash-looping.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub back_off_remaining_secs: Option<u64>,
+    /// How the container last exited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_termination: Option<ContainerTerminationResponse>,
+}
+
+/// How a container exited.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ContainerTerminationResponse {
+    /// `Completed`, `Error` or `OOMKilled`.
+    pub reason: String,
+    pub exit_code: Option<i32>,
+    pub finished_at: DateTime<Utc>,
 }
 
 /// Cluster status response.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ClusterStatusResponse {
+    /// Release version of the API server.
+    #[serde(default)]
+    pub version: String,
     pub total_nodes: usize,
     pub ready_nodes: usize,
     pub not_ready_nodes: usize,
@@ -161,8 +501,305 @@ pub struct ClusterStatusResponse {
     pub total_memory_allocatable_mb: u64,
 }
 
+/// Control-plane leader response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LeaderResponse {
+    /// Identity of the leading instance, if any instance holds the lease.
+    pub leader: Option<String>,
+    /// Increases every time leadership changes hands.
+    pub term: Option<u64>,
+    /// Milliseconds until the lease expires unless the leader renews it.
+    pub lease_expires_in_ms: Option<u64>,
+    /// Identity of the instance that served this request.
+    pub instance: String,
+    /// Whether the serving instance is the leader.
+    pub is_leader: bool,
+}
+
+/// Identity of the caller and what it may do.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WhoAmIResponse {
+    /// Base64-encoded public key that signed the request, or a token's subject.
+    pub subject: String,
+    /// Whether the server verified the caller; false with auth disabled.
+    pub authenticated: bool,
+    pub method: AuthMethod,
+    /// Whether requests are checked against role bindings; without them any
+    /// authenticated key may do anything.
+    pub rbac_enabled: bool,
+    /// Role bindings of the caller, including those its token grants.
+    pub bindings: Vec<RoleBinding>,
+}
+
+/// Bearer token issued by the control plane.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TokenResponse {
+    pub token: String,
+    /// Always `Bearer`.
+    pub token_type: String,
+    pub expires_at: DateTime<Utc>,
+    /// Subject the token speaks for, the key that requested it.
+    pub subject: String,
+}
+
+/// Request for a client certificate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CertificateRequest {
+    /// PEM certificate signing request for the client's own key pair.
+    pub csr: String,
+}
+
+/// Client certificate signed by the cluster CA.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CertificateResponse {
+    /// PEM certificate whose subject is the requesting key.
+    pub certificate: String,
+    /// PEM certificate of the cluster CA, to verify the API server with.
+    pub ca_certificate: String,
+    pub expires_at: DateTime<Utc>,
+    pub subject: String,
+}
+
+/// Query parameter restricting a request to one namespace.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NamespaceQuery {
+    /// Only objects in this namespace; every namespace when absent.
+    pub namespace: Option<String>,
+}
+
+impl NamespaceQuery {
+    fn contains(&self, namespace: &str) -> bool {
+        self.namespace.as_deref().is_none_or(|ns| ns == namespace)
+    }
+}
+
+/// Query parameter asking for a write to be checked but not made.
+#[derive(Debug, Clone, Copy, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DryRunQuery {
+    /// Validate and admit the object and return it as it would be stored,
+    /// without storing it.
+    #[serde(default, rename = "dryRun")]
+    pub dry_run: bool,
+}
+
+/// Request from a node agent registering its node.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RegisterNodeRequest {
+    /// Base64-encoded node public key.
+    pub id: String,
+    /// Address the node is reached at, e.g. "10.0.0.1:7280".
+    pub address: String,
+    /// Labels the node reports, such as its zone; added to any set before.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Detected capacity.
+    pub capacity: ResourceRequestsRequest,
+    /// What instances may use; 90% of capacity when absent.
+    #[serde(default)]
+    pub allocatable: Option<ResourceRequestsRequest>,
+}
+
+/// Request to create a namespace.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateNamespaceRequest {
+    pub name: String,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
+/// Namespace response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NamespaceResponse {
+    pub name: String,
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub resource_version: u64,
+}
+
+/// Counts of objects written by a backup restore.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RestoreResponse {
+    pub namespaces: usize,
+    pub nodes: usize,
+    pub workloads: usize,
+    pub instances: usize,
+    #[serde(default)]
+    pub revisions: usize,
+    #[serde(default)]
+    pub events: usize,
+    #[serde(default)]
+    pub usage_records: usize,
+    /// Daemon sets, cron jobs, services and the other controller objects.
+    #[serde(default)]
+    pub resources: usize,
+}
+
+/// Service endpoint response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EndpointResponse {
+    pub instance_id: Uuid,
+    pub workload_id: Uuid,
+    pub node_id: String,
+    pub addresses: Vec<String>,
+    pub weight: u32,
+    /// Whether the endpoint currently receives new connections.
+    pub ready: bool,
+}
+
+/// A port of a service.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ServicePortRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Port on the service's virtual IPs.
+    pub port: u16,
+    /// Container port traffic is forwarded to; the service port when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_port: Option<u16>,
+    /// "tcp" (default) or "udp"; only TCP ports are forwarded.
+    #[serde(default = "default_service_protocol")]
+    pub protocol: String,
+}
+
+fn default_service_protocol() -> String {
+    "tcp".to_string()
+}
+
+impl From<ServicePort> for ServicePortRequest {
+    fn from(port: ServicePort) -> Self {
+        Self {
+            name: port.name,
+            port: port.port,
+            target_port: Some(port.target_port),
+            protocol: port.protocol,
+        }
+    }
+}
+
+/// Request to create a service.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateServiceRequest {
+    pub name: String,
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    /// Workloads carrying all of these labels back the service.
+    pub selector: HashMap<String, String>,
+    pub ports: Vec<ServicePortRequest>,
+    /// "SingleStack" (default), "PreferDualStack" or "RequireDualStack".
+    #[serde(default)]
+    #[schema(value_type = String)]
+    pub ip_family_policy: IpFamilyPolicy,
+    #[serde(default)]
+    pub session_affinity: SessionAffinity,
+    /// Relative traffic share per backend workload; 0 drains a workload.
+    #[serde(default)]
+    pub backend_weights: HashMap<Uuid, u32>,
+}
+
+/// Request to update a service. Its name, namespace and virtual IPs cannot change.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateServiceRequest {
+    pub selector: HashMap<String, String>,
+    pub ports: Vec<ServicePortRequest>,
+    #[serde(default)]
+    pub session_affinity: SessionAffinity,
+    #[serde(default)]
+    pub backend_weights: HashMap<Uuid, u32>,
+}
+
+/// Service response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ServiceResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub namespace: String,
+    pub selector: HashMap<String, String>,
+    pub ports: Vec<ServicePortRequest>,
+    #[schema(value_type = String)]
+    pub ip_family_policy: IpFamilyPolicy,
+    /// Virtual IPs, one per family.
+    pub cluster_ips: Vec<String>,
+    pub session_affinity: SessionAffinity,
+    pub backend_weights: HashMap<Uuid, u32>,
+}
+
+impl From<Service> for ServiceResponse {
+    fn from(service: Service) -> Self {
+        Self {
+            id: service.id,
+            name: service.name,
+            namespace: service.namespace,
+            selector: service.selector,
+            ports: service.ports.into_iter().map(Into::into).collect(),
+            ip_family_policy: service.ip_family_policy,
+            cluster_ips: service
+                .cluster_ips
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            session_affinity: service.session_affinity,
+            backend_weights: service.backend_weights,
+        }
+    }
+}
+
+/// Check a service's selector and ports, converting the ports.
+fn service_ports(
+    selector: &HashMap<String, String>,
+    ports: Vec<ServicePortRequest>,
+) -> ApiResult<Vec<ServicePort>> {
+    if selector.is_empty() {
+        return Err(ApiError::validation_error(
+            "A service needs a selector; an empty one selects nothing",
+        ));
+    }
+    if ports.is_empty() {
+        return Err(ApiError::validation_error(
+            "A service needs at least one port",
+        ));
+    }
+    ports
+        .into_iter()
+        .map(|port| {
+            let protocol = port.protocol.to_ascii_lowercase();
+            if protocol != "tcp" && protocol != "udp" {
+                return Err(ApiError::validation_error(format!(
+                    "Port {}: protocol must be tcp or udp",
+                    port.port
+                )));
+            }
+            let target_port = port.target_port.unwrap_or(port.port);
+            if port.port == 0 || target_port == 0 {
+                return Err(ApiError::validation_error(
+                    "Ports must be between 1 and 65535",
+                ));
+            }
+            Ok(ServicePort {
+                name: port.name,
+                port: port.port,
+                target_port,
+                protocol,
+            })
+        })
+        .collect()
+}
+
+/// Endpoint membership of a service.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ServiceEndpointsResponse {
+    pub service_id: Uuid,
+    pub service_name: String,
+    pub session_affinity: SessionAffinity,
+    pub endpoints: Vec<EndpointResponse>,
+    pub ready_count: usize,
+    pub not_ready_count: usize,
+}
+
 /// Query parameters for log requests.
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct LogsQuery {
     /// Return only the last N lines.
     pub tail: Option<usize>,
@@ -175,8 +812,32 @@ pub struct LogsQuery {
     pub until: Option<String>,
 }
 
+/// Query parameters for searching workload logs.
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LogSearchQuery {
+    /// Text to look for, or a regular expression when `regex` is set.
+    pub q: String,
+    #[serde(default)]
+    pub regex: bool,
+    /// Only search logs since this timestamp (RFC3339).
+    pub since: Option<String>,
+}
+
+/// One matching line of a workload log search, streamed as NDJSON.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LogSearchHit {
+    /// Instance the container belongs to; absent for archived logs of
+    /// instances that are gone.
+    pub instance_id: Option<Uuid>,
+    pub container_id: String,
+    pub timestamp: String,
+    pub stream: String,
+    pub message: String,
+}
+
 /// Response for workload logs.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LogsResponse {
     /// Workload ID.
     pub workload_id: Uuid,
@@ -190,344 +851,3439 @@ pub struct LogsResponse {
     pub lines: usize,
 }
 
-// ============================================================================
-// Conversion Helpers
-// ============================================================================
+/// Request to create a disruption budget.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateDisruptionBudgetRequest {
+    pub name: String,
+    /// Workload labels the budget applies to; empty matches every workload.
+    #[serde(default)]
+    pub selector: HashMap<String, String>,
+    #[serde(default)]
+    pub min_available: Option<u32>,
+    #[serde(default)]
+    pub max_unavailable: Option<u32>,
+}
 
-impl From<CreateWorkloadRequest> for WorkloadDefinition {
-    fn from(req: CreateWorkloadRequest) -> Self {
-        WorkloadDefinition {
-            id: Uuid::new_v4(),
-            name: req.name,
-            containers: req.containers.into_iter().map(Into::into).collect(),
-            replicas: req.replicas,
-            labels: req.labels,
-        }
-    }
+/// Disruption budget response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DisruptionBudgetResponse {
+    #[serde(flatten)]
+    pub budget: DisruptionBudget,
+    pub status: DisruptionBudgetStatus,
 }
 
-impl From<ContainerConfigRequest> for ContainerConfig {
-    fn from(req: ContainerConfigRequest) -> Self {
-        ContainerConfig {
-            name: req.name,
-            image: req.image,
-            command: req.command,
-            args: req.args,
-            env_vars: req.env_vars,
-            ports: req.ports.into_iter().map(Into::into).collect(),
-            resource_requests: req.resource_requests.into(),
-        }
-    }
+/// Request to pre-pull an image onto nodes.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PrepullImageRequest {
+    pub image: String,
+    /// Labels of the nodes to pull onto; every ready node when empty.
+    #[serde(default)]
+    pub node_selector: HashMap<String, String>,
 }
 
-impl From<PortMappingRequest> for PortMapping {
-    fn from(req: PortMappingRequest) -> Self {
-        PortMapping {
-            container_port: req.container_port,
-            host_port: req.host_port,
-            protocol: req.protocol,
-        }
-    }
+/// What an image reference resolves to in its registry.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImageInspectionResponse {
+    /// The reference as requested.
+    pub reference: String,
+    /// Digest of the manifest the reference resolved to.
+    pub digest: String,
+    /// OS and architecture the image was built for, e.g. "linux/amd64".
+    pub platform: String,
+    /// Compressed size of all layers.
+    pub size_bytes: u64,
+    pub layers: Vec<ImageLayerResponse>,
+    /// Ports the image declares, e.g. "8000/tcp".
+    pub exposed_ports: Vec<String>,
+    pub env: Vec<String>,
+    pub entrypoint: Vec<String>,
+    pub cmd: Vec<String>,
+    pub labels: HashMap<String, String>,
 }
 
-impl From<ResourceRequestsRequest> for NodeResources {
-    fn from(req: ResourceRequestsRequest) -> Self {
-        NodeResources {
-            cpu_cores: req.cpu_cores,
-            memory_mb: req.memory_mb,
-            disk_mb: req.disk_mb,
-        }
-    }
+/// A layer of an inspected image.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImageLayerResponse {
+    pub digest: String,
+    /// Compressed size.
+    pub size_bytes: u64,
 }
 
-impl From<WorkloadDefinition> for WorkloadResponse {
-    fn from(def: WorkloadDefinition) -> Self {
-        WorkloadResponse {
-            id: def.id,
-            name: def.name,
-            replicas: def.replicas,
-            labels: def.labels,
-            containers: def.containers.into_iter().map(Into::into).collect(),
-        }
-    }
+/// Vulnerability scan of an image digest.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImageScanResponse {
+    /// The reference the image was scanned as.
+    pub image: String,
+    pub digest: String,
+    /// Scanner that produced the report, e.g. "trivy".
+    pub scanner: String,
+    pub scanned_at: DateTime<Utc>,
+    pub summary: SeverityCountsResponse,
+    /// Left out when listing scans.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vulnerabilities: Vec<VulnerabilityResponse>,
 }
 
-impl From<ContainerConfig> for ContainerConfigResponse {
-    fn from(cfg: ContainerConfig) -> Self {
-        ContainerConfigResponse {
-            name: cfg.name,
-            image: cfg.image,
-            command: cfg.command,
-            args: cfg.args,
-            env_vars: cfg.env_vars,
-            ports: cfg.ports.into_iter().map(Into::into).collect(),
-            resource_requests: cfg.resource_requests.into(),
-        }
-    }
+/// Vulnerabilities found at each severity.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct SeverityCountsResponse {
+    pub critical: usize,
+    pub high: usize,
+    pub medium: usize,
+    pub low: usize,
+    pub unknown: usize,
 }
 
-impl From<PortMapping> for PortMappingResponse {
-    fn from(pm: PortMapping) -> Self {
-        PortMappingResponse {
-            container_port: pm.container_port,
-            host_port: pm.host_port,
-            protocol: pm.protocol,
-        }
-    }
+/// A known vulnerability in a package of an image.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VulnerabilityResponse {
+    /// CVE or advisory ID.
+    pub id: String,
+    pub package: String,
+    pub installed_version: String,
+    /// First version with the fix, if one is released.
+    pub fixed_version: Option<String>,
+    /// "critical", "high", "medium", "low" or "unknown".
+    pub severity: String,
 }
 
-impl From<NodeResources> for ResourceRequestsResponse {
-    fn from(res: NodeResources) -> Self {
-        ResourceRequestsResponse {
-            cpu_cores: res.cpu_cores,
-            memory_mb: res.memory_mb,
-            disk_mb: res.disk_mb,
-        }
-    }
+/// Request to create a network policy.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateNetworkPolicyRequest {
+    pub name: String,
+    /// Namespace of the workloads the policy selects; "default" when absent.
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Workload labels the policy applies to; empty matches every workload
+    /// of the namespace.
+    #[serde(default)]
+    pub selector: HashMap<String, String>,
+    /// Traffic the selected instances accept; unrestricted when absent, none
+    /// when empty.
+    #[serde(default)]
+    pub ingress: Option<Vec<PolicyRule>>,
+    /// Traffic the selected instances send; unrestricted when absent, only
+    /// DNS when empty.
+    #[serde(default)]
+    pub egress: Option<Vec<PolicyRule>>,
 }
 
-impl From<Node> for NodeResponse {
-    fn from(node: Node) -> Self {
-        NodeResponse {
-            id: node.id.to_string(),
-            address: node.address,
-            status: format!("{:?}", node.status),
-            labels: node.labels,
-            resources_capacity: node.resources_capacity.into(),
-            resources_allocatable: node.resources_allocatable.into(),
-        }
-    }
+/// Request to create a cron job.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateCronJobRequest {
+    pub name: String,
+    /// Five-field cron expression, evaluated in UTC, e.g. "0 2 * * *".
+    pub schedule: String,
+    /// Workload each run creates; runs are named after the cron job.
+    pub template: CreateWorkloadRequest,
+    /// "Allow", "Forbid" or "Replace" a run still active when the next one
+    /// is due (default: "Allow").
+    #[serde(default)]
+    #[schema(value_type = String)]
+    pub concurrency_policy: ConcurrencyPolicy,
+    /// Skip a run that could not start within this many seconds of its
+    /// scheduled time.
+    #[serde(default)]
+    pub starting_deadline_seconds: Option<u64>,
+    /// Finished runs to keep (default: 3 successful, 1 failed).
+    #[serde(default)]
+    pub successful_jobs_history_limit: Option<u32>,
+    #[serde(default)]
+    pub failed_jobs_history_limit: Option<u32>,
+    /// Create the cron job without running it until resumed.
+    #[serde(default)]
+    pub suspend: bool,
 }
 
-impl From<WorkloadInstance> for InstanceResponse {
-    fn from(inst: WorkloadInstance) -> Self {
-        InstanceResponse {
-            id: inst.id,
+/// Cron job response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CronJobResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub namespace: String,
+    pub schedule: String,
+    pub concurrency_policy: String,
+    pub suspend: bool,
+    pub created_at: DateTime<Utc>,
+    /// The most recent time a run was scheduled for.
+    pub last_schedule_time: Option<DateTime<Utc>>,
+    /// Runs kept by the history limits, oldest first.
+    pub jobs: Vec<CronJobRunResponse>,
+}
+
+/// A job created by a cron job.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CronJobRunResponse {
+    pub name: String,
+    /// The workload running the job.
+    pub workload_id: Uuid,
+    /// `Active`, `Succeeded` or `Failed`.
+    pub status: String,
+    pub scheduled_time: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl CronJobResponse {
+    fn new(cron_job: CronJob, jobs: Vec<Job>) -> Self {
+        Self {
+            id: cron_job.id,
+            name: cron_job.name,
+            namespace: cron_job.job_template.namespace,
+            schedule: cron_job.schedule,
+            concurrency_policy: format!("{:?}", cron_job.concurrency_policy),
+            suspend: cron_job.suspend,
+            created_at: cron_job.created_at,
+            last_schedule_time: cron_job.last_schedule_time,
+            jobs: jobs
+                .into_iter()
+                .map(|job| CronJobRunResponse {
+                    workload_id: job.workload_id(),
+                    name: job.name,
+                    status: format!("{:?}", job.status),
+                    scheduled_time: job.scheduled_time,
+                    completed_at: job.completed_at,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Request to create a daemon set.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateDaemonSetRequest {
+    pub name: String,
+    /// Workload run on each matching node; its `replicas` is ignored.
+    pub template: CreateWorkloadRequest,
+    /// Only nodes carrying all of these labels run an instance.
+    #[serde(default)]
+    pub node_selector: HashMap<String, String>,
+}
+
+/// Daemon set response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DaemonSetResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub namespace: String,
+    /// The workload running on each node.
+    pub workload_id: Uuid,
+    pub node_selector: HashMap<String, String>,
+    /// One instance per matching node, once placed.
+    pub instances: Vec<InstanceResponse>,
+}
+
+impl DaemonSetResponse {
+    fn new(daemon_set: DaemonSet, instances: Vec<WorkloadInstance>) -> Self {
+        Self {
+            id: daemon_set.id,
+            workload_id: daemon_set.workload_id(),
+            name: daemon_set.name,
+            namespace: daemon_set.template.namespace,
+            node_selector: daemon_set.node_selector,
+            instances: instances.into_iter().map(InstanceResponse::from).collect(),
+        }
+    }
+}
+
+/// A volume each instance of a stateful set gets a directory of its own for.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VolumeClaimRequest {
+    pub name: String,
+    /// Where the volume is mounted in the container.
+    pub mount_path: String,
+}
+
+/// Request to create a stateful set.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateStatefulSetRequest {
+    pub name: String,
+    /// Workload each instance runs; its `replicas` is ignored.
+    pub template: CreateWorkloadRequest,
+    pub replicas: u32,
+    #[serde(default)]
+    pub volume_claims: Vec<VolumeClaimRequest>,
+}
+
+/// Request to replace the template of a stateful set.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateStatefulSetRequest {
+    /// Workload each instance runs from now on; its `replicas` is ignored.
+    pub template: CreateWorkloadRequest,
+}
+
+/// Request to scale a stateful set.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScaleStatefulSetRequest {
+    pub replicas: u32,
+}
+
+/// Stateful set response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StatefulSetResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub namespace: String,
+    /// The workload the instances belong to.
+    pub workload_id: Uuid,
+    pub image: String,
+    pub replicas: u32,
+    /// Template revision instances are being brought to.
+    pub revision: u64,
+    pub volume_claims: Vec<VolumeClaimRequest>,
+    pub instances: Vec<InstanceResponse>,
+}
+
+impl StatefulSetResponse {
+    fn new(stateful_set: StatefulSet, instances: Vec<WorkloadInstance>) -> Self {
+        Self {
+            id: stateful_set.id,
+            workload_id: stateful_set.workload_id(),
+            name: stateful_set.name,
+            namespace: stateful_set.template.namespace,
+            image: stateful_set
+                .template
+                .containers
+                .first()
+                .map(|c| c.image.clone())
+                .unwrap_or_default(),
+            replicas: stateful_set.replicas,
+            revision: stateful_set.revision,
+            volume_claims: stateful_set
+                .volume_claims
+                .into_iter()
+                .map(|claim| VolumeClaimRequest {
+                    name: claim.name,
+                    mount_path: claim.mount_path,
+                })
+                .collect(),
+            instances: instances.into_iter().map(InstanceResponse::from).collect(),
+        }
+    }
+}
+
+/// Node drain response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DrainNodeResponse {
+    pub node_id: String,
+    pub evicted: Vec<Uuid>,
+    /// Instances a disruption budget kept on the node; drain again later.
+    pub blocked: Vec<Uuid>,
+    pub complete: bool,
+}
+
+/// Request to move an instance to another node.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MigrateInstanceRequest {
+    /// Base64-encoded public key of the node to move the instance to.
+    pub node_id: String,
+}
+
+/// New resource limits of a container of an instance. Limits left out keep
+/// their current value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct UpdateResourcesRequest {
+    /// Container to update; the first main container when absent.
+    #[serde(default)]
+    pub container: Option<String>,
+    /// CPU limit in cores, e.g. 0.5.
+    #[serde(default)]
+    pub cpu_cores: Option<f32>,
+    /// Memory limit in megabytes.
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+    /// Maximum number of processes.
+    #[serde(default)]
+    pub pids_limit: Option<i64>,
+}
+
+/// Request to open a temporary tunnel to a workload.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct OpenTunnelRequest {
+    /// Container port to forward to; defaults to the first declared port.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Seconds until the tunnel is torn down.
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+/// Consistency check response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FsckResponse {
+    pub clean: bool,
+    pub repaired: usize,
+    pub discrepancies: Vec<DiscrepancyResponse>,
+    /// Checks that could not run, with the reason.
+    pub skipped: Vec<String>,
+}
+
+/// One inconsistency found by a consistency check.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DiscrepancyResponse {
+    pub kind: String,
+    pub object: String,
+    pub node_id: Option<String>,
+    pub detail: String,
+    pub repaired: bool,
+}
+
+/// Query parameters selecting events.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventQuery {
+    /// ID of the workload, instance or node the events are about.
+    #[serde(rename = "involvedObject")]
+    pub involved_object: Option<String>,
+    /// Only events in this namespace; every namespace when absent.
+    pub namespace: Option<String>,
+}
+
+/// Query parameters of a usage report.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UsageReportQuery {
+    /// Start of the report, whose whole hour is included (default: 30 days
+    /// before `to`).
+    pub from: Option<DateTime<Utc>>,
+    /// End of the report (default: now).
+    pub to: Option<DateTime<Utc>>,
+    /// Only workloads in this namespace; every namespace when absent.
+    pub namespace: Option<String>,
+}
+
+impl UsageReportQuery {
+    /// Days a report covers when `from` is absent.
+    pub const DEFAULT_DAYS: i64 = 30;
+}
+
+/// Something that happened to an object.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EventResponse {
+    pub id: Uuid,
+    pub involved_object: ObjectReferenceResponse,
+    /// Namespace of the involved object; empty for nodes.
+    pub namespace: String,
+    /// `Normal` or `Warning`.
+    #[serde(rename = "type")]
+    pub event_type: String,
+    /// Short machine-readable cause, e.g. `FailedScheduling`.
+    pub reason: String,
+    pub message: String,
+    /// Component that reported the event.
+    pub source: String,
+    /// How many times the event occurred.
+    pub count: u32,
+    pub first_timestamp: DateTime<Utc>,
+    pub last_timestamp: DateTime<Utc>,
+}
+
+/// The object an event is about.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ObjectReferenceResponse {
+    /// `Workload`, `Instance` or `Node`.
+    pub kind: String,
+    pub id: String,
+    pub name: String,
+}
+
+// ============================================================================
+// Conversion Helpers
+// ============================================================================
+
+impl From<CreateWorkloadRequest> for WorkloadDefinition {
+    fn from(req: CreateWorkloadRequest) -> Self {
+        let placement = req.placement();
+        WorkloadDefinition {
+            id: Uuid::new_v4(),
+            name: req.name,
+            containers: req.containers.into_iter().map(Into::into).collect(),
+            init_containers: req.init_containers.into_iter().map(Into::into).collect(),
+            sidecars: req.sidecars.into_iter().map(Into::into).collect(),
+            replicas: req.replicas,
+            labels: req.labels,
+            placement,
+            restart_policy: req.restart_policy,
+            namespace: req.namespace.unwrap_or_else(default_namespace),
+            resource_version: 0,
+        }
+    }
+}
+
+impl From<ContainerConfigRequest> for ContainerConfig {
+    fn from(req: ContainerConfigRequest) -> Self {
+        ContainerConfig {
+            name: req.name,
+            image: req.image,
+            command: req.command,
+            args: req.args,
+            env_vars: req.env_vars,
+            ports: req.ports.into_iter().map(Into::into).collect(),
+            limits: req.resource_requests.limits(),
+            resource_requests: req.resource_requests.into(),
+            volume_mounts: vec![],
+            termination_grace_period_seconds: req.termination_grace_period_seconds,
+            pre_stop: req.pre_stop,
+            readiness_probe: req.readiness_probe,
+            sysctls: req.sysctls,
+            rlimits: req.rlimits,
+            shm_size_mb: req.shm_size_mb,
+            hostname: req.hostname,
+            host_aliases: req.host_aliases,
+            dns: req.dns,
+            annotations: req.annotations,
+        }
+    }
+}
+
+impl From<PortMappingRequest> for PortMapping {
+    fn from(req: PortMappingRequest) -> Self {
+        PortMapping {
+            container_port: req.container_port,
+            host_port: req.host_port,
+            protocol: req.protocol,
+            host_ip: req.host_ip,
+        }
+    }
+}
+
+impl From<ResourceRequestsRequest> for NodeResources {
+    fn from(req: ResourceRequestsRequest) -> Self {
+        NodeResources {
+            cpu_cores: req.cpu_cores,
+            memory_mb: req.memory_mb,
+            disk_mb: req.disk_mb,
+        }
+    }
+}
+
+impl From<WorkloadDefinition> for WorkloadResponse {
+    fn from(def: WorkloadDefinition) -> Self {
+        WorkloadResponse {
+            id: def.id,
+            name: def.name,
+            replicas: def.replicas,
+            labels: def.labels,
+            containers: def.containers.into_iter().map(Into::into).collect(),
+            init_containers: def.init_containers.into_iter().map(Into::into).collect(),
+            sidecars: def.sidecars.into_iter().map(Into::into).collect(),
+            placement: def.placement,
+            restart_policy: def.restart_policy,
+            namespace: def.namespace,
+            resource_version: def.resource_version,
+        }
+    }
+}
+
+impl From<ContainerConfig> for ContainerConfigResponse {
+    fn from(cfg: ContainerConfig) -> Self {
+        let mut resource_requests = ResourceRequestsResponse::from(cfg.resource_requests);
+        resource_requests.pids_limit = cfg.limits.pids;
+        resource_requests.io_weight = cfg.limits.io_weight;
+        resource_requests.io_throttle = cfg.limits.io_throttle;
+        resource_requests.hugepages = cfg.limits.hugepages;
+        ContainerConfigResponse {
+            name: cfg.name,
+            image: cfg.image,
+            command: cfg.command,
+            args: cfg.args,
+            env_vars: cfg.env_vars,
+            ports: cfg.ports.into_iter().map(Into::into).collect(),
+            resource_requests,
+            readiness_probe: cfg.readiness_probe,
+            termination_grace_period_seconds: cfg.termination_grace_period_seconds,
+            pre_stop: cfg.pre_stop,
+            sysctls: cfg.sysctls,
+            rlimits: cfg.rlimits,
+            shm_size_mb: cfg.shm_size_mb,
+            hostname: cfg.hostname,
+            host_aliases: cfg.host_aliases,
+            dns: cfg.dns,
+            annotations: cfg.annotations,
+        }
+    }
+}
+
+impl From<PortMapping> for PortMappingResponse {
+    fn from(pm: PortMapping) -> Self {
+        PortMappingResponse {
+            container_port: pm.container_port,
+            host_port: pm.host_port,
+            protocol: pm.protocol,
+            host_ip: pm.host_ip,
+        }
+    }
+}
+
+impl From<NodeResources> for ResourceRequestsResponse {
+    fn from(res: NodeResources) -> Self {
+        ResourceRequestsResponse {
+            cpu_cores: res.cpu_cores,
+            memory_mb: res.memory_mb,
+            disk_mb: res.disk_mb,
+            pids_limit: None,
+            io_weight: None,
+            io_throttle: Vec::new(),
+            hugepages: Vec::new(),
+        }
+    }
+}
+
+impl From<Node> for NodeResponse {
+    fn from(node: Node) -> Self {
+        NodeResponse {
+            id: node.id.to_string(),
+            address: node.address,
+            status: format!("{:?}", node.status),
+            labels: node.labels,
+            resources_capacity: node.resources_capacity.into(),
+            resources_allocatable: node.resources_allocatable.into(),
+            unschedulable: node.unschedulable,
+            image_pulls: None,
+            resource_version: node.resource_version,
+        }
+    }
+}
+
+impl From<Namespace> for NamespaceResponse {
+    fn from(namespace: Namespace) -> Self {
+        NamespaceResponse {
+            name: namespace.name,
+            labels: namespace.labels,
+            resource_version: namespace.resource_version,
+        }
+    }
+}
+
+impl From<WorkloadInstance> for InstanceResponse {
+    fn from(inst: WorkloadInstance) -> Self {
+        InstanceResponse {
+            id: inst.id,
             workload_id: inst.workload_id,
             node_id: inst.node_id.to_string(),
             container_ids: inst.container_ids,
             status: format!("{:?}", inst.status),
+            ip_addresses: inst.ip_addresses.iter().map(ToString::to_string).collect(),
+            restarts: inst.restarts.into_iter().map(Into::into).collect(),
+            namespace: inst.namespace,
+            ready: None,
+            resource_version: inst.resource_version,
+        }
+    }
+}
+
+impl From<ImageInspection> for ImageInspectionResponse {
+    fn from(image: ImageInspection) -> Self {
+        ImageInspectionResponse {
+            size_bytes: image.size_bytes(),
+            reference: image.reference,
+            digest: image.digest,
+            platform: image.platform,
+            layers: image
+                .layers
+                .into_iter()
+                .map(|l| ImageLayerResponse {
+                    digest: l.digest,
+                    size_bytes: l.size_bytes,
+                })
+                .collect(),
+            exposed_ports: image.exposed_ports,
+            env: image.env,
+            entrypoint: image.entrypoint,
+            cmd: image.cmd,
+            labels: image.labels,
+        }
+    }
+}
+
+impl From<ImageScan> for ImageScanResponse {
+    fn from(scan: ImageScan) -> Self {
+        let summary = SeverityCountsResponse {
+            critical: scan.count(Severity::Critical),
+            high: scan.count(Severity::High),
+            medium: scan.count(Severity::Medium),
+            low: scan.count(Severity::Low),
+            unknown: scan.count(Severity::Unknown),
+        };
+        ImageScanResponse {
+            image: scan.image,
+            digest: scan.digest,
+            scanner: scan.scanner,
+            scanned_at: scan.scanned_at.into(),
+            summary,
+            vulnerabilities: scan
+                .vulnerabilities
+                .into_iter()
+                .map(|v| VulnerabilityResponse {
+                    id: v.id,
+                    package: v.package,
+                    installed_version: v.installed_version,
+                    fixed_version: v.fixed_version,
+                    severity: v.severity.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// `instances` as responses carrying their readiness probe verdicts.
+fn instance_responses(
+    state: &ApiState,
+    instances: Vec<WorkloadInstance>,
+) -> Vec<InstanceResponse> {
+    instances
+        .into_iter()
+        .map(|instance| {
+            let ready = state
+                .service_proxy
+                .as_ref()
+                .and_then(|proxy| proxy.instance_ready(&instance.id));
+            InstanceResponse {
+                ready,
+                ..instance.into()
+            }
+        })
+        .collect()
+}
+
+impl From<ContainerRestartStatus> for ContainerRestartResponse {
+    fn from(status: ContainerRestartStatus) -> Self {
+        let back_off_remaining_secs = status
+            .back_off_remaining(SystemTime::now())
+            .map(|remaining| remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0));
+        ContainerRestartResponse {
+            container_name: status.container_name,
+            restart_count: status.restart_count,
+            consecutive_failures: status.consecutive_failures,
+            last_exit_code: status.last_exit_code,
+            back_off_remaining_secs,
+            last_termination: status.last_termination.map(|termination| {
+                ContainerTerminationResponse {
+                    reason: termination.reason.to_string(),
+                    exit_code: termination.exit_code,
+                    finished_at: termination.finished_at.into(),
+                }
+            }),
+        }
+    }
+}
+
+impl From<FsckReport> for FsckResponse {
+    fn from(report: FsckReport) -> Self {
+        Self {
+            clean: report.is_clean(),
+            repaired: report.repaired(),
+            discrepancies: report.discrepancies.into_iter().map(Into::into).collect(),
+            skipped: report.skipped,
+        }
+    }
+}
+
+impl From<Discrepancy> for DiscrepancyResponse {
+    fn from(d: Discrepancy) -> Self {
+        Self {
+            kind: d.kind.as_str().to_string(),
+            object: d.object,
+            node_id: d.node_id.map(|id| id.to_string()),
+            detail: d.detail,
+            repaired: d.repaired,
+        }
+    }
+}
+
+impl From<Event> for EventResponse {
+    fn from(event: Event) -> Self {
+        let timestamp = |ms: u64| DateTime::from_timestamp_millis(ms as i64).unwrap_or_default();
+        EventResponse {
+            id: event.id,
+            involved_object: ObjectReferenceResponse {
+                kind: event.involved_object.kind,
+                id: event.involved_object.id,
+                name: event.involved_object.name,
+            },
+            namespace: event.namespace,
+            event_type: format!("{:?}", event.event_type),
+            reason: event.reason,
+            message: event.message,
+            source: event.source,
+            count: event.count,
+            first_timestamp: timestamp(event.first_timestamp_ms),
+            last_timestamp: timestamp(event.last_timestamp_ms),
+        }
+    }
+}
+
+impl From<Endpoint> for EndpointResponse {
+    fn from(ep: Endpoint) -> Self {
+        EndpointResponse {
+            instance_id: ep.instance_id,
+            workload_id: ep.workload_id,
+            node_id: ep.node_id.to_string(),
+            addresses: ep.addresses.iter().map(ToString::to_string).collect(),
+            weight: ep.weight,
+            ready: ep.ready,
         }
     }
 }
 
 // ============================================================================
-// Workload Handlers
+// Workload Handlers
+// ============================================================================
+
+/// Create a new workload.
+///
+/// With `dryRun=true` the workload is validated and admitted but not stored.
+#[utoipa::path(
+    post,
+    path = "/api/v1/workloads",
+    tag = "workloads",
+    params(DryRunQuery),
+    request_body = CreateWorkloadRequest,
+    responses(
+        (status = 201, description = "Workload created", body = WorkloadResponse),
+        (status = 200, description = "Workload as it would be created", body = WorkloadResponse),
+        (status = 400, description = "Invalid workload", body = ApiError),
+        (status = 403, description = "Admission limit exceeded", body = ApiError),
+        (status = 404, description = "Namespace not found", body = ApiError),
+    )
+)]
+pub async fn create_workload(
+    State(state): State<ApiState>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<CreateWorkloadRequest>,
+) -> ApiResult<impl IntoResponse> {
+    if query.dry_run {
+        let response: WorkloadResponse = prepare_workload(&state, request).await?.into();
+        return Ok((StatusCode::OK, Json(response)));
+    }
+    let response: WorkloadResponse = submit_workload(&state, request).await?.into();
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Validate and admit a new workload.
+async fn prepare_workload(
+    state: &ApiState,
+    request: CreateWorkloadRequest,
+) -> ApiResult<WorkloadDefinition> {
+    if request.replicas == 0 {
+        return Err(ApiError::validation_error("Replicas must be at least 1"));
+    }
+
+    // Convert to workload definition
+    let mut workload: WorkloadDefinition = request.into();
+    admit_workload(state, &mut workload).await?;
+    ensure_namespace(state, &workload.namespace).await?;
+    Ok(workload)
+}
+
+/// Validate, admit, store and schedule a new workload.
+pub(super) async fn submit_workload(
+    state: &ApiState,
+    request: CreateWorkloadRequest,
+) -> ApiResult<WorkloadDefinition> {
+    let workload = prepare_workload(state, request).await?;
+
+    // Store workload
+    let workload = state
+        .state_store
+        .compare_and_put_workload(workload)
+        .await
+        .map_err(ApiError::from)?;
+
+    record_revision(state, &workload).await;
+
+    // Send to orchestrator for scheduling
+    state
+        .workload_tx
+        .send(workload.clone())
+        .await
+        .map_err(|_| ApiError::internal_error("Failed to submit workload to orchestrator"))?;
+
+    Ok(workload)
+}
+
+/// Rejects a workload that fails [`WorkloadDefinition::validate`].
+pub(super) fn validate_workload(workload: &WorkloadDefinition) -> ApiResult<()> {
+    workload.validate().map_err(|e| match e {
+        OrchestrationError::ConfigError(message) => ApiError::validation_error(message),
+        other => ApiError::from(other),
+    })
+}
+
+/// Validates the workload, fills in namespace resource defaults and runs the
+/// mutating webhooks, then rejects a workload that would exceed the
+/// configured admission limits or break a policy rule, or that a validating
+/// webhook denies.
+///
+/// Every create, update and patch of a workload goes through here, so they
+/// all apply the same checks.
+pub(super) async fn admit_workload(
+    state: &ApiState,
+    workload: &mut WorkloadDefinition,
+) -> ApiResult<()> {
+    validate_workload(workload)?;
+    state.admission.apply_defaults(workload);
+    let existing = state
+        .state_store
+        .list_workloads()
+        .await
+        .map_err(ApiError::from)?;
+    let old = existing.iter().find(|w| w.id == workload.id);
+
+    if let Some(webhooks) = &state.admission_webhooks {
+        webhooks.mutate(workload, old).await?;
+    }
+    state.admission.admit(workload, &existing)?;
+    check_image_scans(state, workload).await?;
+    if let Some(webhooks) = &state.admission_webhooks {
+        webhooks.validate(workload, old).await?;
+    }
+    Ok(())
+}
+
+/// Rejects a workload whose images have vulnerabilities above the maximum
+/// image severity. An image that cannot be scanned is rejected too.
+async fn check_image_scans(state: &ApiState, workload: &WorkloadDefinition) -> ApiResult<()> {
+    if state.admission.max_image_severity.is_none() {
+        return Ok(());
+    }
+    let runtime = state
+        .container_runtime
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured"))?;
+    for container in workload
+        .containers
+        .iter()
+        .chain(&workload.init_containers)
+        .chain(&workload.sidecars)
+    {
+        let scan = runtime.scan_image(&container.image).await.map_err(|e| {
+            ApiError::internal_error(format!("Could not scan image '{}': {}", container.image, e))
+        })?;
+        state.admission.check_image_scan(container, &scan)?;
+    }
+    Ok(())
+}
+
+/// List all workloads, or those of one namespace.
+///
+/// Watching ignores paging and sorting but applies the label selector.
+#[utoipa::path(
+    get,
+    path = "/api/v1/workloads",
+    tag = "workloads",
+    params(
+        WatchQuery,
+        NamespaceQuery,
+        ListQuery,
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Workloads, or a stream of changes with `watch=true`",
+            body = ListResponse<WorkloadResponse>
+        ),
+        (status = 400, description = "Invalid paging, sort or selector", body = ApiError),
+    )
+)]
+pub async fn list_workloads(
+    State(state): State<ApiState>,
+    Query(query): Query<WatchQuery>,
+    Query(scope): Query<NamespaceQuery>,
+    Query(list): Query<ListQuery>,
+) -> ApiResult<Response> {
+    if !query.watch {
+        let (workloads, next) = list
+            .fetch(scope.namespace.as_deref(), |page| {
+                state.state_store.list_workloads_page(page)
+            })
+            .await?;
+        let items: Vec<WorkloadResponse> = workloads.into_iter().map(Into::into).collect();
+        return Ok(Json(ListResponse::new(items).with_continue(next)).into_response());
+    }
+
+    let selector = list.selector()?;
+    let changes = state.state_store.watch_workloads().await.map_err(ApiError::from)?;
+    let keep = move |workload: &WorkloadDefinition| {
+        scope.contains(&workload.namespace) && selector.matches(&workload.labels)
+    };
+    let workloads: Vec<WorkloadDefinition> = state
+        .state_store
+        .list_workloads()
+        .await
+        .map_err(ApiError::from)?
+        .into_iter()
+        .filter(&keep)
+        .collect();
+
+    Ok(watch::sse(workloads, changes, keep, WorkloadResponse::from))
+}
+
+/// Get a workload by ID.
+#[utoipa::path(
+    get,
+    path = "/api/v1/workloads/{workload_id}",
+    tag = "workloads",
+    params(
+        ("workload_id" = Uuid, Path, description = "Workload ID"),
+        NamespaceQuery,
+    ),
+    responses(
+        (status = 200, description = "The workload", body = WorkloadResponse),
+        (status = 404, description = "Workload not found", body = ApiError),
+    )
+)]
+pub async fn get_workload(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+    Query(scope): Query<NamespaceQuery>,
+) -> ApiResult<impl IntoResponse> {
+    let workload = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .filter(|w| scope.contains(&w.namespace))
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+
+    let response: WorkloadResponse = workload.into();
+    Ok(Json(response))
+}
+
+/// Update a workload.
+///
+/// Fails with 409 Conflict when the workload changed since the request's
+/// `resource_version`. With `dryRun=true` the workload is returned as it
+/// would be stored, without storing it.
+#[utoipa::path(
+    put,
+    path = "/api/v1/workloads/{workload_id}",
+    tag = "workloads",
+    params(("workload_id" = Uuid, Path, description = "Workload ID"), DryRunQuery),
+    request_body = CreateWorkloadRequest,
+    responses(
+        (status = 200, description = "Workload updated", body = WorkloadResponse),
+        (status = 400, description = "Invalid workload", body = ApiError),
+        (status = 404, description = "Workload not found", body = ApiError),
+        (status = 409, description = "Workload changed since resource_version", body = ApiError),
+    )
+)]
+pub async fn update_workload(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<CreateWorkloadRequest>,
+) -> ApiResult<impl IntoResponse> {
+    // Check workload exists
+    let existing = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+
+    let resource_version = required_resource_version(request.resource_version)?;
+    if request
+        .namespace
+        .as_ref()
+        .is_some_and(|namespace| *namespace != existing.namespace)
+    {
+        return Err(ApiError::validation_error(
+            "A workload cannot move to another namespace",
+        ));
+    }
+
+    // Create updated workload with same ID
+    let mut workload = WorkloadDefinition {
+        id: workload_id,
+        resource_version,
+        namespace: existing.namespace,
+        ..request.into()
+    };
+
+    if query.dry_run {
+        if resource_version != existing.resource_version {
+            return Err(ApiError::conflict(format!(
+                "Workload {} changed since version {}",
+                workload_id, resource_version
+            )));
+        }
+        admit_workload(&state, &mut workload).await?;
+        let response: WorkloadResponse = workload.into();
+        return Ok(Json(response));
+    }
+
+    let response: WorkloadResponse = resubmit_workload(&state, workload).await?.into();
+    Ok(Json(response))
+}
+
+/// Change some fields of a workload.
+///
+/// With `application/json` the body is a [`PatchWorkloadRequest`] and must
+/// carry a `resource_version`. A JSON Merge Patch or JSON Patch (see
+/// [`patch`](super::patch)) may change any field but the ID and namespace.
+/// Fails with 409 Conflict when the workload changed since the version the
+/// request was based on.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/workloads/{workload_id}",
+    tag = "workloads",
+    params(("workload_id" = Uuid, Path, description = "Workload ID")),
+    request_body(content(
+        (PatchWorkloadRequest = "application/json"),
+        (Object = "application/merge-patch+json"),
+        (Object = "application/json-patch+json"),
+    )),
+    responses(
+        (status = 200, description = "Workload updated", body = WorkloadResponse),
+        (status = 404, description = "Workload not found", body = ApiError),
+        (status = 409, description = "Workload changed since resource_version", body = ApiError),
+        (status = 415, description = "Unsupported content type", body = ApiError),
+        (status = 422, description = "Patch could not be applied", body = ApiError),
+    )
+)]
+pub async fn patch_workload(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<impl IntoResponse> {
+    let mut workload = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+
+    if let Some(patch) = JsonPatchBody::from_request(&headers, &body)? {
+        let patched: WorkloadDefinition = patch.apply(&workload)?;
+        if patched.id != workload.id || patched.namespace != workload.namespace {
+            return Err(ApiError::validation_error(
+                "A workload's id and namespace cannot be changed",
+            ));
+        }
+        workload = patched;
+    } else {
+        let request: PatchWorkloadRequest = serde_json::from_slice(&body)
+            .map_err(|e| ApiError::bad_request(format!("Invalid request body: {}", e)))?;
+        workload.resource_version = required_resource_version(request.resource_version)?;
+        if let Some(replicas) = request.replicas {
+            workload.replicas = replicas;
+        }
+        if let Some(labels) = request.labels {
+            workload.labels = labels;
+        }
+    }
+
+    let response: WorkloadResponse = resubmit_workload(&state, workload).await?.into();
+    Ok(Json(response))
+}
+
+/// List a workload's revisions, oldest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/workloads/{workload_id}/revisions",
+    tag = "workloads",
+    params(("workload_id" = Uuid, Path, description = "Workload ID")),
+    responses(
+        (status = 200, description = "Revisions", body = ListResponse<RevisionResponse>),
+        (status = 404, description = "Workload not found", body = ApiError),
+    )
+)]
+pub async fn list_workload_revisions(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+) -> ApiResult<Json<ListResponse<RevisionResponse>>> {
+    let workload = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+    let history = revision_history(&state, &workload_id).await?;
+
+    let current = history
+        .iter()
+        .rev()
+        .find(|r| r.workload.same_template(&workload))
+        .map(|r| r.revision);
+    let items = history
+        .into_iter()
+        .map(|r| RevisionResponse {
+            revision: r.revision,
+            created_at: DateTime::from_timestamp_millis(r.created_at_ms as i64)
+                .unwrap_or_default(),
+            current: Some(r.revision) == current,
+            workload: r.workload.into(),
+        })
+        .collect();
+    Ok(Json(ListResponse::new(items)))
+}
+
+/// Get a workload's current revision and replica progress.
+#[utoipa::path(
+    get,
+    path = "/api/v1/workloads/{workload_id}/rollout/status",
+    tag = "workloads",
+    params(("workload_id" = Uuid, Path, description = "Workload ID")),
+    responses(
+        (status = 200, description = "Rollout status", body = RolloutStatus),
+        (status = 404, description = "Workload not found", body = ApiError),
+    )
+)]
+pub async fn get_rollout_status(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+) -> ApiResult<Json<RolloutStatus>> {
+    let workload = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+    let history = rollout::revisions(&*state.state_store, &workload_id)
+        .await
+        .map_err(ApiError::from)?;
+    let revision = history.and_then(|history| {
+        history
+            .iter()
+            .rev()
+            .find(|r| r.workload.same_template(&workload))
+            .map(|r| r.revision)
+    });
+    let instances = state
+        .state_store
+        .list_instances_for_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(RolloutStatus::new(&workload, revision, &instances)))
+}
+
+/// Roll a workload's template back to an earlier revision.
+///
+/// Replicas and labels are left as they are. The rollback is recorded as a
+/// new revision.
+#[utoipa::path(
+    post,
+    path = "/api/v1/workloads/{workload_id}/rollout/undo",
+    tag = "workloads",
+    params(
+        ("workload_id" = Uuid, Path, description = "Workload ID"),
+        UndoQuery,
+    ),
+    responses(
+        (status = 200, description = "Workload rolled back", body = WorkloadResponse),
+        (status = 400, description = "No earlier revision", body = ApiError),
+        (status = 404, description = "Workload or revision not found", body = ApiError),
+        (status = 409, description = "Workload changed during the rollback", body = ApiError),
+    )
+)]
+pub async fn undo_rollout(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+    Query(query): Query<UndoQuery>,
+) -> ApiResult<Json<WorkloadResponse>> {
+    let workload = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+    let history = revision_history(&state, &workload_id).await?;
+
+    let target = rollout::rollback_target(&workload, &history, query.to).ok_or_else(|| {
+        match query.to {
+            Some(to) => ApiError::not_found("Revision", &to.to_string()),
+            None => ApiError::bad_request("No earlier revision to roll back to"),
+        }
+    })?;
+    let rolled_back = workload.with_template_of(&target.workload);
+
+    let response: WorkloadResponse = resubmit_workload(&state, rolled_back).await?.into();
+    Ok(Json(response))
+}
+
+async fn revision_history(
+    state: &ApiState,
+    workload_id: &Uuid,
+) -> ApiResult<Vec<WorkloadRevision>> {
+    rollout::revisions(&*state.state_store, workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| {
+            ApiError::new(
+                "The state store does not keep workload revisions",
+                "NOT_IMPLEMENTED",
+            )
+        })
+}
+
+/// Record the workload's template in its rollout history. The workload is
+/// already stored by then, so a failure is logged rather than returned.
+async fn record_revision(state: &ApiState, workload: &WorkloadDefinition) {
+    if let Err(e) = rollout::record_revision(&*state.state_store, workload).await {
+        tracing::warn!("Failed to record a revision of workload {}: {}", workload.id, e);
+    }
+}
+
+/// Times a scale without `resource_version` is retried after the workload
+/// changed underneath it.
+const SCALE_CONFLICT_RETRIES: usize = 5;
+
+/// Get a workload's desired and current replicas.
+#[utoipa::path(
+    get,
+    path = "/api/v1/workloads/{workload_id}/scale",
+    tag = "workloads",
+    params(("workload_id" = Uuid, Path, description = "Workload ID")),
+    responses(
+        (status = 200, description = "Replica counts", body = ScaleResponse),
+        (status = 404, description = "Workload not found", body = ApiError),
+    )
+)]
+pub async fn get_workload_scale(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+) -> ApiResult<Json<ScaleResponse>> {
+    let workload = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+
+    Ok(Json(scale_response(&state, &workload).await?))
+}
+
+/// Set a workload's replica count without sending its whole spec.
+///
+/// Without `resource_version` the latest version is scaled, retrying when
+/// the workload changes concurrently; with it, fails with 409 Conflict when
+/// the workload changed since.
+#[utoipa::path(
+    post,
+    path = "/api/v1/workloads/{workload_id}/scale",
+    tag = "workloads",
+    params(("workload_id" = Uuid, Path, description = "Workload ID")),
+    request_body = ScaleRequest,
+    responses(
+        (status = 200, description = "Workload scaled", body = ScaleResponse),
+        (status = 404, description = "Workload not found", body = ApiError),
+        (status = 409, description = "Workload changed since resource_version", body = ApiError),
+    )
+)]
+pub async fn scale_workload(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+    Json(request): Json<ScaleRequest>,
+) -> ApiResult<Json<ScaleResponse>> {
+    let workload = scale_to(
+        &state,
+        &workload_id,
+        request.replicas,
+        request.resource_version,
+    )
+    .await?;
+    Ok(Json(scale_response(&state, &workload).await?))
+}
+
+/// Set a workload's replicas, at `resource_version` or else at whatever
+/// version it is, retrying conflicts.
+async fn scale_to(
+    state: &ApiState,
+    workload_id: &Uuid,
+    replicas: u32,
+    resource_version: Option<u64>,
+) -> ApiResult<WorkloadDefinition> {
+    let mut attempt = 0;
+    loop {
+        let mut workload = state
+            .state_store
+            .get_workload(workload_id)
+            .await
+            .map_err(ApiError::from)?
+            .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+        if let Some(resource_version) = resource_version {
+            workload.resource_version = resource_version;
+        }
+        workload.replicas = replicas;
+
+        match resubmit_workload(state, workload).await {
+            Ok(workload) => return Ok(workload),
+            Err(e)
+                if e.code == "CONFLICT"
+                    && resource_version.is_none()
+                    && attempt < SCALE_CONFLICT_RETRIES =>
+            {
+                attempt += 1
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// The autoscaler scales workloads the way the scale endpoint does, with
+/// admission and rescheduling.
+#[async_trait]
+impl ReplicaScaler for ApiState {
+    async fn scale(&self, workload_id: &Uuid, replicas: u32) -> Result<(), OrchestrationError> {
+        scale_to(self, workload_id, replicas, None)
+            .await
+            .map(|_| ())
+            .map_err(|e| match e.code.as_str() {
+                "NOT_FOUND" => OrchestrationError::WorkloadNotFound(*workload_id),
+                "CONFLICT" => OrchestrationError::Conflict(e.error),
+                _ => OrchestrationError::InternalError(e.error),
+            })
+    }
+}
+
+async fn scale_response(
+    state: &ApiState,
+    workload: &WorkloadDefinition,
+) -> ApiResult<ScaleResponse> {
+    let instances = state
+        .state_store
+        .list_instances_for_workload(&workload.id)
+        .await
+        .map_err(ApiError::from)?;
+    let running = instances
+        .iter()
+        .filter(|i| i.status == WorkloadInstanceStatus::Running)
+        .count();
+
+    Ok(ScaleResponse {
+        workload_id: workload.id,
+        replicas: workload.replicas,
+        running_replicas: running as u32,
+        current_replicas: instances.len() as u32,
+        resource_version: workload.resource_version,
+    })
+}
+
+fn required_resource_version(resource_version: Option<u64>) -> ApiResult<u64> {
+    resource_version.ok_or_else(|| {
+        ApiError::validation_error(
+            "resource_version is required; read the workload and send back its resource_version",
+        )
+    })
+}
+
+/// Admit, store and reschedule an updated workload, provided it is still at
+/// `workload.resource_version`.
+pub(super) async fn resubmit_workload(
+    state: &ApiState,
+    mut workload: WorkloadDefinition,
+) -> ApiResult<WorkloadDefinition> {
+    admit_workload(state, &mut workload).await?;
+
+    // Store updated workload
+    let workload = state
+        .state_store
+        .compare_and_put_workload(workload)
+        .await
+        .map_err(ApiError::from)?;
+
+    record_revision(state, &workload).await;
+
+    // Send to orchestrator for re-reconciliation
+    state
+        .workload_tx
+        .send(workload.clone())
+        .await
+        .map_err(|_| ApiError::internal_error("Failed to submit workload update to orchestrator"))?;
+
+    Ok(workload)
+}
+
+/// Delete a workload.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/workloads/{workload_id}",
+    tag = "workloads",
+    params(("workload_id" = Uuid, Path, description = "Workload ID")),
+    responses(
+        (status = 204, description = "Workload deleted"),
+        (status = 404, description = "Workload not found", body = ApiError),
+    )
+)]
+pub async fn delete_workload(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+) -> ApiResult<impl IntoResponse> {
+    // Check workload exists
+    let _ = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+
+    // Delete instances first
+    state
+        .state_store
+        .delete_instances_for_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?;
+
+    // Delete workload
+    state
+        .state_store
+        .delete_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List instances for a workload.
+#[utoipa::path(
+    get,
+    path = "/api/v1/workloads/{workload_id}/instances",
+    tag = "workloads",
+    params(
+        ("workload_id" = Uuid, Path, description = "Workload ID"),
+        WatchQuery,
+        NamespaceQuery,
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Instances, or a stream of changes with `watch=true`",
+            body = ListResponse<InstanceResponse>
+        ),
+        (status = 404, description = "Workload not found", body = ApiError),
+    )
+)]
+pub async fn list_workload_instances(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+    Query(query): Query<WatchQuery>,
+    Query(scope): Query<NamespaceQuery>,
+) -> ApiResult<Response> {
+    // Check workload exists
+    let _ = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .filter(|w| scope.contains(&w.namespace))
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+
+    let changes = if query.watch {
+        Some(state.state_store.watch_instances().await.map_err(ApiError::from)?)
+    } else {
+        None
+    };
+    let instances = state
+        .state_store
+        .list_instances_for_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?;
+
+    if let Some(changes) = changes {
+        let keep = move |instance: &WorkloadInstance| instance.workload_id == workload_id;
+        return Ok(watch::sse(instances, changes, keep, InstanceResponse::from));
+    }
+    let items = instance_responses(&state, instances);
+
+    Ok(Json(ListResponse::new(items)).into_response())
+}
+
+/// List instances of every workload, or of those in one namespace.
+///
+/// The label selector matches the labels of the instance's workload. Watches
+/// filter by namespace only and reject a label selector.
+#[utoipa::path(
+    get,
+    path = "/api/v1/instances",
+    tag = "workloads",
+    params(WatchQuery, NamespaceQuery, ListQuery),
+    responses(
+        (
+            status = 200,
+            description = "Instances, or a stream of changes with `watch=true`",
+            body = ListResponse<InstanceResponse>
+        ),
+        (status = 400, description = "Invalid paging, sort or selector", body = ApiError),
+    )
+)]
+pub async fn list_instances(
+    State(state): State<ApiState>,
+    Query(query): Query<WatchQuery>,
+    Query(scope): Query<NamespaceQuery>,
+    Query(list): Query<ListQuery>,
+) -> ApiResult<Response> {
+    if query.watch {
+        if list.label_selector.is_some() {
+            return Err(ApiError::validation_error(
+                "labelSelector is not supported when watching instances",
+            ));
+        }
+        let changes = state.state_store.watch_instances().await.map_err(ApiError::from)?;
+        let keep = move |instance: &WorkloadInstance| scope.contains(&instance.namespace);
+        let instances: Vec<WorkloadInstance> = state
+            .state_store
+            .list_all_instances()
+            .await
+            .map_err(ApiError::from)?
+            .into_iter()
+            .filter(&keep)
+            .collect();
+        return Ok(watch::sse(instances, changes, keep, InstanceResponse::from));
+    }
+
+    let (instances, next) = list
+        .fetch(scope.namespace.as_deref(), |page| {
+            state.state_store.list_instances_page(page)
+        })
+        .await?;
+    let items = instance_responses(&state, instances);
+
+    Ok(Json(ListResponse::new(items).with_continue(next)).into_response())
+}
+
+/// Restart an instance by replacing it: its containers are stopped and
+/// removed, and the orchestrator schedules a new instance in its place.
+#[utoipa::path(
+    post,
+    path = "/api/v1/instances/{instance_id}/restart",
+    tag = "workloads",
+    params(("instance_id" = Uuid, Path, description = "Instance ID"), NamespaceQuery),
+    responses(
+        (status = 202, description = "The instance being replaced", body = InstanceResponse),
+        (status = 404, description = "Instance not found", body = ApiError),
+    )
+)]
+pub async fn restart_instance(
+    State(state): State<ApiState>,
+    Path(instance_id): Path<Uuid>,
+    Query(scope): Query<NamespaceQuery>,
+) -> ApiResult<impl IntoResponse> {
+    let instance = remove_instance(&state, instance_id, &scope, "Restart requested").await?;
+    let response: InstanceResponse = instance.into();
+    Ok((StatusCode::ACCEPTED, Json(response)))
+}
+
+/// Move a running instance to another node without restarting it: its
+/// containers are checkpointed, restored on the node and resume where they
+/// stopped. On failure the instance resumes on its own node.
+#[utoipa::path(
+    post,
+    path = "/api/v1/instances/{instance_id}/migrate",
+    tag = "workloads",
+    params(("instance_id" = Uuid, Path, description = "Instance ID"), NamespaceQuery),
+    request_body = MigrateInstanceRequest,
+    responses(
+        (status = 200, description = "The instance on its new node", body = InstanceResponse),
+        (status = 400, description = "Invalid node ID", body = ApiError),
+        (status = 404, description = "Instance or node not found", body = ApiError),
+        (
+            status = 409,
+            description = "Instance not running, or node not ready or cordoned",
+            body = ApiError
+        ),
+        (status = 500, description = "Checkpoint or restore failed", body = ApiError),
+    )
+)]
+pub async fn migrate_instance(
+    State(state): State<ApiState>,
+    Path(instance_id): Path<Uuid>,
+    Query(scope): Query<NamespaceQuery>,
+    Json(request): Json<MigrateInstanceRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let node_id: NodeId = request.node_id.parse().map_err(|_| {
+        ApiError::validation_error(format!("Invalid node ID: {}", request.node_id))
+    })?;
+    let runtime = state
+        .container_runtime
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured"))?;
+    let id = instance_id.to_string();
+    let instance = state
+        .state_store
+        .get_instance(&id)
+        .await
+        .map_err(ApiError::from)?
+        .filter(|i| scope.contains(&i.namespace))
+        .ok_or_else(|| ApiError::not_found("Instance", &id))?;
+
+    let migrated = InstanceMigrator::new(state.state_store.clone(), runtime.clone())
+        .migrate(&instance, node_id)
+        .await
+        .map_err(ApiError::from)?;
+    let response: InstanceResponse = migrated.into();
+    Ok(Json(response))
+}
+
+/// Change the resource limits of a running container of an instance without
+/// restarting it. The workload keeps its resources, so replacements of the
+/// instance get those.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/instances/{instance_id}/resources",
+    tag = "workloads",
+    params(("instance_id" = Uuid, Path, description = "Instance ID"), NamespaceQuery),
+    request_body = UpdateResourcesRequest,
+    responses(
+        (status = 204, description = "Limits updated"),
+        (status = 400, description = "Invalid limits or unknown container", body = ApiError),
+        (status = 404, description = "Instance not found", body = ApiError),
+        (status = 500, description = "The runtime failed to apply the limits", body = ApiError),
+    )
+)]
+pub async fn update_instance_resources(
+    State(state): State<ApiState>,
+    Path(instance_id): Path<Uuid>,
+    Query(scope): Query<NamespaceQuery>,
+    Json(request): Json<UpdateResourcesRequest>,
+) -> ApiResult<StatusCode> {
+    let resources = ResourceLimits {
+        cpu_cores: request.cpu_cores,
+        memory_mb: request.memory_mb,
+        pids_limit: request.pids_limit,
+    };
+    if resources.is_empty() {
+        return Err(ApiError::validation_error(
+            "Set at least one of cpu_cores, memory_mb and pids_limit",
+        ));
+    }
+    if resources.cpu_cores.is_some_and(|cores| cores <= 0.0)
+        || resources.memory_mb == Some(0)
+        || resources.pids_limit.is_some_and(|pids| pids <= 0)
+    {
+        return Err(ApiError::validation_error(
+            "Resource limits must be positive",
+        ));
+    }
+    let runtime = state
+        .container_runtime
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured"))?;
+    let container_id = instance_container(
+        &state,
+        instance_id,
+        scope.namespace.clone(),
+        request.container.as_deref(),
+    )
+    .await?;
+
+    runtime
+        .update_container_resources(&container_id, &resources)
+        .await
+        .map_err(ApiError::from)?;
+
+    if let Some(instance) = state
+        .state_store
+        .get_instance(&instance_id.to_string())
+        .await
+        .map_err(ApiError::from)?
+    {
+        let mut limits = Vec::new();
+        if let Some(cores) = resources.cpu_cores {
+            limits.push(format!("cpu {} cores", cores));
+        }
+        if let Some(mb) = resources.memory_mb {
+            limits.push(format!("memory {} MB", mb));
+        }
+        if let Some(pids) = resources.pids_limit {
+            limits.push(format!("pids {}", pids));
+        }
+        let message = format!(
+            "Updated limits of container {}: {}",
+            container_id,
+            limits.join(", ")
+        );
+        EventRecorder::new(state.state_store.clone(), "api-server")
+            .instance_normal(&instance, "ResourcesUpdated", message)
+            .await;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delete an instance. Its containers are stopped and removed; the
+/// orchestrator schedules a replacement while its workload wants the replica.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/instances/{instance_id}",
+    tag = "workloads",
+    params(("instance_id" = Uuid, Path, description = "Instance ID"), NamespaceQuery),
+    responses(
+        (status = 204, description = "Instance deleted"),
+        (status = 404, description = "Instance not found", body = ApiError),
+    )
+)]
+pub async fn delete_instance(
+    State(state): State<ApiState>,
+    Path(instance_id): Path<Uuid>,
+    Query(scope): Query<NamespaceQuery>,
+) -> ApiResult<impl IntoResponse> {
+    remove_instance(&state, instance_id, &scope, "Deletion requested").await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Stop an instance's containers, remove it from the state store and
+/// resubmit its workload, recording `message` as the reason.
+async fn remove_instance(
+    state: &ApiState,
+    instance_id: Uuid,
+    scope: &NamespaceQuery,
+    message: &str,
+) -> ApiResult<WorkloadInstance> {
+    let runtime = state
+        .container_runtime
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured"))?;
+    let id = instance_id.to_string();
+    let instance = state
+        .state_store
+        .get_instance(&id)
+        .await
+        .map_err(ApiError::from)?
+        .filter(|i| scope.contains(&i.namespace))
+        .ok_or_else(|| ApiError::not_found("Instance", &id))?;
+
+    // Stop routing traffic to it before the containers go away
+    if let Some(proxy) = &state.service_proxy {
+        proxy.instance_terminating(instance.id);
+    }
+    EventRecorder::new(state.state_store.clone(), "api-server")
+        .instance_normal(&instance, "Killing", message)
+        .await;
+    if let Err(e) = runtime.stop_instance(&instance.container_ids).await {
+        tracing::warn!("Failed to stop containers of instance {}: {:?}", id, e);
+    }
+    state
+        .state_store
+        .delete_instance(&id)
+        .await
+        .map_err(ApiError::from)?;
+
+    if let Some(workload) = state
+        .state_store
+        .get_workload(&instance.workload_id)
+        .await
+        .map_err(ApiError::from)?
+    {
+        state
+            .workload_tx
+            .send(workload)
+            .await
+            .map_err(|_| ApiError::internal_error("Failed to submit workload to orchestrator"))?;
+    }
+    Ok(instance)
+}
+
+/// Run a command in a container of an instance, streaming its input and
+/// output over a WebSocket; see [`exec`] for the frames.
+#[utoipa::path(
+    get,
+    path = "/api/v1/instances/{instance_id}/exec",
+    tag = "workloads",
+    params(("instance_id" = Uuid, Path, description = "Instance ID"), ExecQuery),
+    responses(
+        (status = 101, description = "WebSocket multiplexing stdin, stdout, stderr and resizes"),
+        (status = 400, description = "No command or unknown container", body = ApiError),
+        (status = 404, description = "Instance not found", body = ApiError),
+    )
+)]
+pub async fn exec_instance(
+    State(state): State<ApiState>,
+    Path(instance_id): Path<Uuid>,
+    Query(pairs): Query<Vec<(String, String)>>,
+    ws: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    let query = ExecQuery::from_pairs(pairs)?;
+    let runtime = state
+        .container_runtime
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured"))?;
+    let container_id = instance_container(
+        &state,
+        instance_id,
+        query.namespace.clone(),
+        query.container.as_deref(),
+    )
+    .await?;
+
+    // Start before upgrading so runtime errors reach the client as HTTP errors
+    let options = ExecOptions {
+        command: query.cmd,
+        tty: query.tty,
+    };
+    let session = runtime
+        .exec(&container_id, &options)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(ws.on_upgrade(move |socket| exec::bridge(socket, session)))
+}
+
+/// Start a throwaway container from an image with tools in the namespaces of
+/// a container of an instance, and stream its input and output over a
+/// WebSocket like exec does; see [`exec`].
+#[utoipa::path(
+    get,
+    path = "/api/v1/instances/{instance_id}/debug",
+    tag = "workloads",
+    params(("instance_id" = Uuid, Path, description = "Instance ID"), DebugQuery),
+    responses(
+        (status = 101, description = "WebSocket multiplexing stdin, stdout, stderr and resizes"),
+        (status = 400, description = "No image or unknown container", body = ApiError),
+        (status = 404, description = "Instance not found", body = ApiError),
+    )
+)]
+pub async fn debug_instance(
+    State(state): State<ApiState>,
+    Path(instance_id): Path<Uuid>,
+    Query(pairs): Query<Vec<(String, String)>>,
+    ws: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    let query = DebugQuery::from_pairs(pairs)?;
+    let runtime = state
+        .container_runtime
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured"))?;
+    let container_id = instance_container(
+        &state,
+        instance_id,
+        query.namespace.clone(),
+        query.container.as_deref(),
+    )
+    .await?;
+
+    // Start before upgrading so pull and runtime errors reach the client as
+    // HTTP errors
+    let options = DebugOptions {
+        image: query.image,
+        command: query.cmd,
+        tty: query.tty,
+    };
+    let session = runtime
+        .debug_container(&container_id, &options)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(ws.on_upgrade(move |socket| exec::bridge(socket, session)))
+}
+
+/// The id of the container named `container` of an instance in `namespace`,
+/// or of its first main container; see [`exec::container_id`].
+pub(super) async fn instance_container(
+    state: &ApiState,
+    instance_id: Uuid,
+    namespace: Option<String>,
+    container: Option<&str>,
+) -> ApiResult<ContainerId> {
+    let id = instance_id.to_string();
+    let scope = NamespaceQuery { namespace };
+    let instance = state
+        .state_store
+        .get_instance(&id)
+        .await
+        .map_err(ApiError::from)?
+        .filter(|i| scope.contains(&i.namespace))
+        .ok_or_else(|| ApiError::not_found("Instance", &id))?;
+    let workload = state
+        .state_store
+        .get_workload(&instance.workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Workload", &instance.workload_id.to_string()))?;
+    exec::container_id(&workload, &instance, container).ok_or_else(|| {
+        ApiError::bad_request(format!(
+            "Instance {} has no container '{}'",
+            id,
+            container.unwrap_or_default()
+        ))
+    })
+}
+
+// ============================================================================
+// Namespace Handlers
+// ============================================================================
+
+pub(super) fn validate_namespace_name(name: &str) -> ApiResult<()> {
+    if Namespace::is_valid_name(name) {
+        Ok(())
+    } else {
+        Err(ApiError::validation_error(format!(
+            "Invalid namespace name '{}': use at most 63 lowercase letters, digits and hyphens",
+            name
+        )))
+    }
+}
+
+/// Fails with 404 unless `name` is the default namespace or was created.
+async fn ensure_namespace(state: &ApiState, name: &str) -> ApiResult<()> {
+    lookup_namespace(state, name)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Namespace", name))?;
+    Ok(())
+}
+
+/// A stored namespace; the default namespace exists even when not stored.
+pub(super) async fn lookup_namespace(
+    state: &ApiState,
+    name: &str,
+) -> ApiResult<Option<Namespace>> {
+    let namespace = state
+        .state_store
+        .get_namespace(name)
+        .await
+        .map_err(ApiError::from)?;
+    if namespace.is_none() && name == DEFAULT_NAMESPACE {
+        return Ok(Some(Namespace::new(DEFAULT_NAMESPACE)));
+    }
+    Ok(namespace)
+}
+
+/// Create a namespace.
+#[utoipa::path(
+    post,
+    path = "/api/v1/namespaces",
+    tag = "namespaces",
+    request_body = CreateNamespaceRequest,
+    responses(
+        (status = 201, description = "Namespace created", body = NamespaceResponse),
+        (status = 400, description = "Invalid name", body = ApiError),
+        (status = 409, description = "Namespace already exists", body = ApiError),
+    )
+)]
+pub async fn create_namespace(
+    State(state): State<ApiState>,
+    Json(request): Json<CreateNamespaceRequest>,
+) -> ApiResult<impl IntoResponse> {
+    validate_namespace_name(&request.name)?;
+    if lookup_namespace(&state, &request.name).await?.is_some() {
+        return Err(ApiError::conflict(format!(
+            "Namespace {} already exists",
+            request.name
+        )));
+    }
+
+    let namespace = Namespace {
+        labels: request.labels,
+        ..Namespace::new(request.name)
+    };
+    state
+        .state_store
+        .put_namespace(namespace.clone())
+        .await
+        .map_err(ApiError::from)?;
+    let stored = lookup_namespace(&state, &namespace.name)
+        .await?
+        .unwrap_or(namespace);
+
+    Ok((StatusCode::CREATED, Json(NamespaceResponse::from(stored))))
+}
+
+/// List all namespaces, including the default one.
+#[utoipa::path(
+    get,
+    path = "/api/v1/namespaces",
+    tag = "namespaces",
+    responses(
+        (status = 200, description = "Namespaces", body = ListResponse<NamespaceResponse>),
+    )
+)]
+pub async fn list_namespaces(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let mut namespaces = state
+        .state_store
+        .list_namespaces()
+        .await
+        .map_err(ApiError::from)?;
+    if !namespaces.iter().any(|ns| ns.name == DEFAULT_NAMESPACE) {
+        namespaces.push(Namespace::new(DEFAULT_NAMESPACE));
+    }
+    namespaces.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let items: Vec<NamespaceResponse> = namespaces.into_iter().map(Into::into).collect();
+    Ok(Json(ListResponse::new(items)))
+}
+
+/// Get a namespace by name.
+#[utoipa::path(
+    get,
+    path = "/api/v1/namespaces/{name}",
+    tag = "namespaces",
+    params(("name" = String, Path, description = "Namespace name")),
+    responses(
+        (status = 200, description = "The namespace", body = NamespaceResponse),
+        (status = 404, description = "Namespace not found", body = ApiError),
+    )
+)]
+pub async fn get_namespace(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    let namespace = lookup_namespace(&state, &name)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Namespace", &name))?;
+    Ok(Json(NamespaceResponse::from(namespace)))
+}
+
+/// Delete a namespace together with its workloads, their instances and its
+/// services. The default namespace cannot be deleted.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/namespaces/{name}",
+    tag = "namespaces",
+    params(("name" = String, Path, description = "Namespace name")),
+    responses(
+        (status = 204, description = "Namespace and its contents deleted"),
+        (status = 400, description = "The default namespace", body = ApiError),
+        (status = 404, description = "Namespace not found", body = ApiError),
+    )
+)]
+pub async fn delete_namespace(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    if name == DEFAULT_NAMESPACE {
+        return Err(ApiError::validation_error(
+            "The default namespace cannot be deleted",
+        ));
+    }
+    ensure_namespace(&state, &name).await?;
+
+    let workloads = state
+        .state_store
+        .list_workloads()
+        .await
+        .map_err(ApiError::from)?;
+    for workload in workloads.iter().filter(|w| w.namespace == name) {
+        state
+            .state_store
+            .delete_instances_for_workload(&workload.id)
+            .await
+            .map_err(ApiError::from)?;
+        state
+            .state_store
+            .delete_workload(&workload.id)
+            .await
+            .map_err(ApiError::from)?;
+    }
+
+    if let Some(proxy) = &state.service_proxy {
+        let services: Vec<Service> = resources::list(state.state_store.as_ref())
+            .await
+            .map_err(ApiError::from)?;
+        for service in services.into_iter().filter(|s| s.namespace == name) {
+            proxy
+                .remove_service(&service.id)
+                .await
+                .map_err(ApiError::from)?;
+        }
+    }
+
+    // Removed last, so a delete that fails part way can be retried
+    state
+        .state_store
+        .delete_namespace(&name)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ============================================================================
+// Node Handlers
+// ============================================================================
+
+/// List all nodes.
+///
+/// Watched node events omit the image pull queue. Watching ignores paging
+/// and sorting but applies the label selector.
+#[utoipa::path(
+    get,
+    path = "/api/v1/nodes",
+    tag = "nodes",
+    params(WatchQuery, ListQuery),
+    responses(
+        (
+            status = 200,
+            description = "Nodes, or a stream of changes with `watch=true`",
+            body = ListResponse<NodeResponse>
+        ),
+        (status = 400, description = "Invalid paging, sort or selector", body = ApiError),
+    )
+)]
+pub async fn list_nodes(
+    State(state): State<ApiState>,
+    Query(query): Query<WatchQuery>,
+    Query(list): Query<ListQuery>,
+) -> ApiResult<Response> {
+    if query.watch {
+        let selector = list.selector()?;
+        let changes = state.state_store.watch_nodes().await.map_err(ApiError::from)?;
+        let keep = move |node: &Node| selector.matches(&node.labels);
+        let nodes: Vec<Node> = state
+            .state_store
+            .list_nodes()
+            .await
+            .map_err(ApiError::from)?
+            .into_iter()
+            .filter(&keep)
+            .collect();
+        return Ok(watch::sse(nodes, changes, keep, NodeResponse::from));
+    }
+
+    let (nodes, next) = list
+        .fetch(None, |page| state.state_store.list_nodes_page(page))
+        .await?;
+    let mut items = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        items.push(node_response(&state, node).await);
+    }
+
+    Ok(Json(ListResponse::new(items).with_continue(next)).into_response())
+}
+
+/// Build a node response, adding the node's image pull queue from the runtime.
+async fn node_response(state: &ApiState, node: Node) -> NodeResponse {
+    let node_id = node.id;
+    let mut response = NodeResponse::from(node);
+    if let Some(runtime) = &state.container_runtime {
+        response.image_pulls = runtime.image_pull_status(node_id).await;
+    }
+    response
+}
+
+/// Register a node, or update a registered one, as its agent does at startup
+/// with a bootstrap token.
+///
+/// The node is Ready until its lease lapses. Labels it reports are added to
+/// those set before, and a cordon stays in place.
+#[utoipa::path(
+    post,
+    path = "/api/v1/nodes/register",
+    tag = "nodes",
+    request_body = RegisterNodeRequest,
+    responses(
+        (status = 201, description = "Node registered", body = NodeResponse),
+        (status = 200, description = "Registered node updated", body = NodeResponse),
+        (status = 400, description = "Invalid node ID or address", body = ApiError),
+    )
+)]
+pub async fn register_node(
+    State(state): State<ApiState>,
+    Json(req): Json<RegisterNodeRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let node_id: NodeId = req
+        .id
+        .parse()
+        .map_err(|_| ApiError::validation_error(format!("Invalid node ID: {}", req.id)))?;
+    if req.address.trim().is_empty() {
+        return Err(ApiError::validation_error("Node address is required"));
+    }
+
+    let existing = state
+        .state_store
+        .get_node(&node_id)
+        .await
+        .map_err(ApiError::from)?;
+    let capacity: NodeResources = req.capacity.into();
+    let allocatable = req
+        .allocatable
+        .map_or_else(|| capacity.allocatable(), Into::into);
+    let mut labels = existing
+        .as_ref()
+        .map(|node| node.labels.clone())
+        .unwrap_or_default();
+    labels.extend(req.labels);
+    let message = format!("Node registered at {}", req.address);
+    let node = Node {
+        id: node_id,
+        address: req.address,
+        status: NodeStatus::Ready,
+        labels,
+        resources_capacity: capacity,
+        resources_allocatable: allocatable,
+        unschedulable: existing.as_ref().is_some_and(|node| node.unschedulable),
+        resource_version: existing.as_ref().map_or(0, |node| node.resource_version),
+    };
+
+    if existing.is_none() {
+        if let Some(runtime) = &state.container_runtime {
+            if let Err(e) = runtime.init_node(node_id).await {
+                tracing::warn!("Failed to initialize runtime on node {}: {:?}", node_id, e);
+            }
+        }
+    }
+    state.state_store.put_node(node).await.map_err(ApiError::from)?;
+    EventRecorder::new(state.state_store.clone(), "api-server")
+        .normal(ObjectReference::node(&node_id), "", "Registered", message)
+        .await;
+
+    let node = state
+        .state_store
+        .get_node(&node_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Node", &req.id))?;
+    let status = if existing.is_some() {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(node_response(&state, node).await)))
+}
+
+/// Deregister a node, as its agent does on shutdown.
+///
+/// Instances still assigned to it are rescheduled once the eviction grace
+/// period passes.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/nodes/{node_id}",
+    tag = "nodes",
+    params(("node_id" = String, Path, description = "Base64-encoded node public key")),
+    responses(
+        (status = 204, description = "Node deregistered"),
+        (status = 400, description = "Invalid node ID", body = ApiError),
+        (status = 404, description = "Node not found", body = ApiError),
+    )
+)]
+pub async fn deregister_node(
+    State(state): State<ApiState>,
+    Path(node_id_str): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    let node_id: NodeId = node_id_str
+        .parse()
+        .map_err(|_| ApiError::validation_error(format!("Invalid node ID: {}", node_id_str)))?;
+    state
+        .state_store
+        .get_node(&node_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Node", &node_id_str))?;
+
+    state
+        .state_store
+        .delete_node(&node_id)
+        .await
+        .map_err(ApiError::from)?;
+    EventRecorder::new(state.state_store.clone(), "api-server")
+        .normal(
+            ObjectReference::node(&node_id),
+            "",
+            "Deregistered",
+            "Node deregistered",
+        )
+        .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get a node by ID.
+#[utoipa::path(
+    get,
+    path = "/api/v1/nodes/{node_id}",
+    tag = "nodes",
+    params(("node_id" = String, Path, description = "Base64-encoded node public key")),
+    responses(
+        (status = 200, description = "The node", body = NodeResponse),
+        (status = 400, description = "Invalid node ID", body = ApiError),
+        (status = 404, description = "Node not found", body = ApiError),
+    )
+)]
+pub async fn get_node(
+    State(state): State<ApiState>,
+    Path(node_id_str): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    let node_id: NodeId = node_id_str.parse()
+        .map_err(|_| ApiError::validation_error(&format!("Invalid node ID: {}", node_id_str)))?;
+
+    let node = state
+        .state_store
+        .get_node(&node_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Node", &node_id_str))?;
+
+    Ok(Json(node_response(&state, node).await))
+}
+
+/// Change a node's labels or cordon it with a JSON Merge Patch or JSON Patch
+/// of the node as returned by the API.
+///
+/// Other fields are reported by the node and cannot be changed.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/nodes/{node_id}",
+    tag = "nodes",
+    params(("node_id" = String, Path, description = "Base64-encoded node public key")),
+    request_body(content(
+        (Object = "application/merge-patch+json"),
+        (Object = "application/json-patch+json"),
+    )),
+    responses(
+        (status = 200, description = "Node updated", body = NodeResponse),
+        (status = 400, description = "Patch changes a read-only field", body = ApiError),
+        (status = 404, description = "Node not found", body = ApiError),
+        (status = 409, description = "Node changed since resource_version", body = ApiError),
+        (status = 415, description = "Not a patch content type", body = ApiError),
+        (status = 422, description = "Patch could not be applied", body = ApiError),
+    )
+)]
+pub async fn patch_node(
+    State(state): State<ApiState>,
+    Path(node_id_str): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<impl IntoResponse> {
+    let node_id: NodeId = node_id_str
+        .parse()
+        .map_err(|_| ApiError::validation_error(format!("Invalid node ID: {}", node_id_str)))?;
+    let Some(patch) = JsonPatchBody::from_request(&headers, &body)? else {
+        return Err(ApiError::new(
+            format!("Send a {} or {} body", patch::MERGE_PATCH, patch::JSON_PATCH),
+            "UNSUPPORTED_MEDIA_TYPE",
+        ));
+    };
+
+    let mut node = state
+        .state_store
+        .get_node(&node_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Node", &node_id_str))?;
+    let current = NodeResponse::from(node.clone());
+    let patched = patch.apply(&current)?;
+    state_store_interface::check_resource_version(
+        "node",
+        &node_id_str,
+        Some(current.resource_version),
+        patched.resource_version,
+    )
+    .map_err(ApiError::from)?;
+    let unchanged = NodeResponse {
+        labels: current.labels.clone(),
+        unschedulable: current.unschedulable,
+        ..patched.clone()
+    };
+    if serde_json::to_value(&unchanged).ok() != serde_json::to_value(&current).ok() {
+        return Err(ApiError::validation_error(
+            "Only a node's labels and unschedulable can be changed",
+        ));
+    }
+
+    node.labels = patched.labels;
+    node.unschedulable = patched.unschedulable;
+    state.state_store.put_node(node).await.map_err(ApiError::from)?;
+    let node = state
+        .state_store
+        .get_node(&node_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Node", &node_id_str))?;
+    Ok(Json(node_response(&state, node).await))
+}
+
+/// Stop placing new instances on a node.
+#[utoipa::path(
+    post,
+    path = "/api/v1/nodes/{node_id}/cordon",
+    tag = "nodes",
+    params(("node_id" = String, Path, description = "Base64-encoded node public key")),
+    responses(
+        (status = 200, description = "Node cordoned", body = NodeResponse),
+        (status = 404, description = "Node not found", body = ApiError),
+    )
+)]
+pub async fn cordon_node(
+    State(state): State<ApiState>,
+    Path(node_id_str): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    set_node_unschedulable(&state, &node_id_str, true).await
+}
+
+/// Allow new instances on a cordoned node again.
+#[utoipa::path(
+    post,
+    path = "/api/v1/nodes/{node_id}/uncordon",
+    tag = "nodes",
+    params(("node_id" = String, Path, description = "Base64-encoded node public key")),
+    responses(
+        (status = 200, description = "Node uncordoned", body = NodeResponse),
+        (status = 404, description = "Node not found", body = ApiError),
+    )
+)]
+pub async fn uncordon_node(
+    State(state): State<ApiState>,
+    Path(node_id_str): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    set_node_unschedulable(&state, &node_id_str, false).await
+}
+
+async fn set_node_unschedulable(
+    state: &ApiState,
+    node_id_str: &str,
+    unschedulable: bool,
+) -> ApiResult<Json<NodeResponse>> {
+    let node_id: NodeId = node_id_str
+        .parse()
+        .map_err(|_| ApiError::validation_error(format!("Invalid node ID: {}", node_id_str)))?;
+    let node = disruption_controller(state)?
+        .cordon(&node_id, unschedulable)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(node_response(state, node).await))
+}
+
+/// Cordon a node and evict every instance on it that disruption budgets allow.
+#[utoipa::path(
+    post,
+    path = "/api/v1/nodes/{node_id}/drain",
+    tag = "nodes",
+    params(("node_id" = String, Path, description = "Base64-encoded node public key")),
+    responses(
+        (status = 200, description = "Instances evicted so far", body = DrainNodeResponse),
+        (status = 404, description = "Node not found", body = ApiError),
+    )
+)]
+pub async fn drain_node(
+    State(state): State<ApiState>,
+    Path(node_id_str): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    let node_id: NodeId = node_id_str
+        .parse()
+        .map_err(|_| ApiError::validation_error(format!("Invalid node ID: {}", node_id_str)))?;
+    let disruption = disruption_controller(&state)?;
+
+    let report = disruption
+        .drain_node(&node_id)
+        .await
+        .map_err(ApiError::from)?;
+
+    // Resubmit affected workloads so the orchestrator schedules replacements
+    // on the remaining nodes
+    for workload_id in &report.workloads {
+        let Some(workload) = state
+            .state_store
+            .get_workload(workload_id)
+            .await
+            .map_err(ApiError::from)?
+        else {
+            continue;
+        };
+        state
+            .workload_tx
+            .send(workload)
+            .await
+            .map_err(|_| ApiError::internal_error("Failed to submit workload to orchestrator"))?;
+    }
+
+    Ok(Json(DrainNodeResponse {
+        node_id: node_id_str,
+        complete: report.is_complete(),
+        evicted: report.evicted,
+        blocked: report.blocked,
+    }))
+}
+
+// ============================================================================
+// Disruption Budget Handlers
 // ============================================================================
 
-/// Create a new workload.
-pub async fn create_workload(
+fn disruption_controller(state: &ApiState) -> ApiResult<&DisruptionController> {
+    state
+        .disruption
+        .as_deref()
+        .ok_or_else(|| ApiError::internal_error("Disruption controller not configured"))
+}
+
+async fn budget_response(
+    disruption: &DisruptionController,
+    budget: DisruptionBudget,
+) -> ApiResult<DisruptionBudgetResponse> {
+    let status = disruption.status(&budget).await.map_err(ApiError::from)?;
+    Ok(DisruptionBudgetResponse { budget, status })
+}
+
+/// Create a disruption budget.
+#[utoipa::path(
+    post,
+    path = "/api/v1/disruption-budgets",
+    tag = "disruption-budgets",
+    request_body = CreateDisruptionBudgetRequest,
+    responses(
+        (status = 201, description = "Budget created", body = DisruptionBudgetResponse),
+        (status = 400, description = "Invalid budget", body = ApiError),
+    )
+)]
+pub async fn create_disruption_budget(
     State(state): State<ApiState>,
-    Json(request): Json<CreateWorkloadRequest>,
+    Json(request): Json<CreateDisruptionBudgetRequest>,
 ) -> ApiResult<impl IntoResponse> {
-    // Validate request
+    let disruption = disruption_controller(&state)?;
     if request.name.is_empty() {
-        return Err(ApiError::validation_error("Workload name cannot be empty"));
+        return Err(ApiError::validation_error(
+            "Disruption budget name cannot be empty",
+        ));
     }
-
-    if request.containers.is_empty() {
-        return Err(ApiError::validation_error("Workload must have at least one container"));
+    if request.min_available.is_none() && request.max_unavailable.is_none() {
+        return Err(ApiError::validation_error(
+            "Disruption budget must set min_available or max_unavailable",
+        ));
     }
 
-    if request.replicas == 0 {
-        return Err(ApiError::validation_error("Replicas must be at least 1"));
-    }
+    let budget = DisruptionBudget {
+        min_available: request.min_available,
+        max_unavailable: request.max_unavailable,
+        ..DisruptionBudget::new(request.name, request.selector)
+    };
+    disruption
+        .add_budget(budget.clone())
+        .await
+        .map_err(ApiError::from)?;
 
-    // Convert to workload definition
-    let workload: WorkloadDefinition = request.into();
+    let response = budget_response(disruption, budget).await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
 
-    // Store workload
-    state
-        .state_store
-        .put_workload(workload.clone())
+/// List all disruption budgets with their current status.
+#[utoipa::path(
+    get,
+    path = "/api/v1/disruption-budgets",
+    tag = "disruption-budgets",
+    responses(
+        (
+            status = 200,
+            description = "Budgets with their status",
+            body = Vec<DisruptionBudgetResponse>
+        ),
+    )
+)]
+pub async fn list_disruption_budgets(
+    State(state): State<ApiState>,
+) -> ApiResult<impl IntoResponse> {
+    let disruption = disruption_controller(&state)?;
+    let mut responses = Vec::new();
+    for budget in disruption.list_budgets().await {
+        responses.push(budget_response(disruption, budget).await?);
+    }
+    Ok(Json(responses))
+}
+
+/// Get a disruption budget with its current status.
+#[utoipa::path(
+    get,
+    path = "/api/v1/disruption-budgets/{budget_id}",
+    tag = "disruption-budgets",
+    params(("budget_id" = Uuid, Path, description = "Disruption budget ID")),
+    responses(
+        (status = 200, description = "The budget with its status", body = DisruptionBudgetResponse),
+        (status = 404, description = "Budget not found", body = ApiError),
+    )
+)]
+pub async fn get_disruption_budget(
+    State(state): State<ApiState>,
+    Path(budget_id): Path<DisruptionBudgetId>,
+) -> ApiResult<impl IntoResponse> {
+    let disruption = disruption_controller(&state)?;
+    let budget = disruption
+        .get_budget(&budget_id)
         .await
-        .map_err(ApiError::from)?;
+        .ok_or_else(|| ApiError::not_found("DisruptionBudget", &budget_id.to_string()))?;
+    Ok(Json(budget_response(disruption, budget).await?))
+}
 
-    // Send to orchestrator for scheduling
-    state
-        .workload_tx
-        .send(workload.clone())
+/// Delete a disruption budget.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/disruption-budgets/{budget_id}",
+    tag = "disruption-budgets",
+    params(("budget_id" = Uuid, Path, description = "Disruption budget ID")),
+    responses(
+        (status = 204, description = "Budget deleted"),
+        (status = 404, description = "Budget not found", body = ApiError),
+    )
+)]
+pub async fn delete_disruption_budget(
+    State(state): State<ApiState>,
+    Path(budget_id): Path<DisruptionBudgetId>,
+) -> ApiResult<impl IntoResponse> {
+    let disruption = disruption_controller(&state)?;
+    disruption
+        .remove_budget(&budget_id)
         .await
-        .map_err(|_| ApiError::internal_error("Failed to submit workload to orchestrator"))?;
+        .ok_or_else(|| ApiError::not_found("DisruptionBudget", &budget_id.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    let response: WorkloadResponse = workload.into();
-    Ok((StatusCode::CREATED, Json(response)))
+// ============================================================================
+// Network Policy Handlers
+// ============================================================================
+
+fn network_policy_controller(state: &ApiState) -> ApiResult<&NetworkPolicyController> {
+    state
+        .network_policies
+        .as_deref()
+        .ok_or_else(|| ApiError::internal_error("Network policies are not enforced on this node"))
 }
 
-/// List all workloads.
-pub async fn list_workloads(
+/// Create a network policy and enforce it.
+#[utoipa::path(
+    post,
+    path = "/api/v1/network-policies",
+    tag = "network-policies",
+    request_body = CreateNetworkPolicyRequest,
+    responses(
+        (status = 201, description = "Policy created and enforced", body = NetworkPolicy),
+        (status = 400, description = "Invalid policy", body = ApiError),
+    )
+)]
+pub async fn create_network_policy(
     State(state): State<ApiState>,
+    Json(request): Json<CreateNetworkPolicyRequest>,
 ) -> ApiResult<impl IntoResponse> {
-    let workloads = state
-        .state_store
-        .list_workloads()
+    let policies = network_policy_controller(&state)?;
+    let namespace = request.namespace.unwrap_or_else(default_namespace);
+    validate_namespace_name(&namespace)?;
+
+    let policy = NetworkPolicy {
+        ingress: request.ingress,
+        egress: request.egress,
+        ..NetworkPolicy::new(request.name, request.selector).with_namespace(namespace)
+    };
+    policies
+        .add_policy(policy.clone())
         .await
         .map_err(ApiError::from)?;
+    Ok((StatusCode::CREATED, Json(policy)))
+}
 
-    let items: Vec<WorkloadResponse> = workloads.into_iter().map(Into::into).collect();
-    let count = items.len();
+/// List all network policies.
+#[utoipa::path(
+    get,
+    path = "/api/v1/network-policies",
+    tag = "network-policies",
+    responses(
+        (status = 200, description = "Network policies", body = Vec<NetworkPolicy>),
+    )
+)]
+pub async fn list_network_policies(
+    State(state): State<ApiState>,
+) -> ApiResult<impl IntoResponse> {
+    let mut policies = network_policy_controller(&state)?.list_policies().await;
+    policies.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+    Ok(Json(policies))
+}
 
-    Ok(Json(ListResponse { items, count }))
+/// Get a network policy.
+#[utoipa::path(
+    get,
+    path = "/api/v1/network-policies/{policy_id}",
+    tag = "network-policies",
+    params(("policy_id" = Uuid, Path, description = "Network policy ID")),
+    responses(
+        (status = 200, description = "The policy", body = NetworkPolicy),
+        (status = 404, description = "Policy not found", body = ApiError),
+    )
+)]
+pub async fn get_network_policy(
+    State(state): State<ApiState>,
+    Path(policy_id): Path<NetworkPolicyId>,
+) -> ApiResult<impl IntoResponse> {
+    let policy = network_policy_controller(&state)?
+        .get_policy(&policy_id)
+        .await
+        .ok_or_else(|| ApiError::not_found("NetworkPolicy", &policy_id.to_string()))?;
+    Ok(Json(policy))
 }
 
-/// Get a workload by ID.
-pub async fn get_workload(
+/// Delete a network policy and lift its restrictions.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/network-policies/{policy_id}",
+    tag = "network-policies",
+    params(("policy_id" = Uuid, Path, description = "Network policy ID")),
+    responses(
+        (status = 204, description = "Policy deleted"),
+        (status = 404, description = "Policy not found", body = ApiError),
+    )
+)]
+pub async fn delete_network_policy(
     State(state): State<ApiState>,
-    Path(workload_id): Path<Uuid>,
+    Path(policy_id): Path<NetworkPolicyId>,
 ) -> ApiResult<impl IntoResponse> {
-    let workload = state
-        .state_store
-        .get_workload(&workload_id)
+    network_policy_controller(&state)?
+        .remove_policy(&policy_id)
         .await
         .map_err(ApiError::from)?
-        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+        .ok_or_else(|| ApiError::not_found("NetworkPolicy", &policy_id.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    let response: WorkloadResponse = workload.into();
+// ============================================================================
+// Cron Job Handlers
+// ============================================================================
+
+fn cron_job_controller(state: &ApiState) -> ApiResult<&CronJobController> {
+    state
+        .cron_jobs
+        .as_deref()
+        .ok_or_else(|| ApiError::internal_error("Cron jobs are not run on this node"))
+}
+
+/// Cron jobs and daemon sets live on the leader, the only node whose
+/// controllers run them.
+fn ensure_leader(state: &ApiState, kind: &str) -> ApiResult<()> {
+    match &state.leader {
+        Some(elector) if !elector.is_leader() => Err(ApiError::conflict(format!(
+            "{} are kept by the control-plane leader; retry against it",
+            kind
+        ))),
+        _ => Ok(()),
+    }
+}
+
+async fn cron_job_response(
+    cron_jobs: &CronJobController,
+    id: &CronJobId,
+) -> ApiResult<CronJobResponse> {
+    let cron_job = cron_jobs
+        .get_cron_job(id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("CronJob", &id.to_string()))?;
+    let jobs = cron_jobs.list_jobs(id).await.map_err(ApiError::from)?;
+    Ok(CronJobResponse::new(cron_job, jobs))
+}
+
+/// Create a cron job.
+#[utoipa::path(
+    post,
+    path = "/api/v1/cronjobs",
+    tag = "cronjobs",
+    request_body = CreateCronJobRequest,
+    responses(
+        (status = 201, description = "Cron job created", body = CronJobResponse),
+        (status = 400, description = "Invalid schedule or template", body = ApiError),
+        (status = 409, description = "This node is not the leader", body = ApiError),
+    )
+)]
+pub async fn create_cron_job(
+    State(state): State<ApiState>,
+    Json(request): Json<CreateCronJobRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let cron_jobs = cron_job_controller(&state)?;
+    ensure_leader(&state, "Cron jobs")?;
+    let template = prepare_workload(&state, request.template).await?;
+
+    let mut cron_job = CronJob::new(request.name, request.schedule, template)
+        .with_concurrency_policy(request.concurrency_policy);
+    cron_job.starting_deadline_seconds = request.starting_deadline_seconds;
+    if let Some(limit) = request.successful_jobs_history_limit {
+        cron_job.successful_jobs_history_limit = limit;
+    }
+    if let Some(limit) = request.failed_jobs_history_limit {
+        cron_job.failed_jobs_history_limit = limit;
+    }
+    cron_job.suspend = request.suspend;
+    let id = cron_jobs
+        .add_cron_job(cron_job)
+        .await
+        .map_err(ApiError::from)?;
+    let response = cron_job_response(cron_jobs, &id).await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// List all cron jobs.
+#[utoipa::path(
+    get,
+    path = "/api/v1/cronjobs",
+    tag = "cronjobs",
+    responses(
+        (status = 200, description = "Cron jobs", body = Vec<CronJobResponse>),
+    )
+)]
+pub async fn list_cron_jobs(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let cron_jobs = cron_job_controller(&state)?;
+    let mut responses = Vec::new();
+    for cron_job in cron_jobs.list_cron_jobs().await.map_err(ApiError::from)? {
+        let jobs = cron_jobs
+            .list_jobs(&cron_job.id)
+            .await
+            .map_err(ApiError::from)?;
+        responses.push(CronJobResponse::new(cron_job, jobs));
+    }
+    responses.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+    Ok(Json(responses))
+}
+
+/// Get a cron job and its recent runs.
+#[utoipa::path(
+    get,
+    path = "/api/v1/cronjobs/{cron_job_id}",
+    tag = "cronjobs",
+    params(("cron_job_id" = Uuid, Path, description = "Cron job ID")),
+    responses(
+        (status = 200, description = "The cron job", body = CronJobResponse),
+        (status = 404, description = "Cron job not found", body = ApiError),
+    )
+)]
+pub async fn get_cron_job(
+    State(state): State<ApiState>,
+    Path(cron_job_id): Path<CronJobId>,
+) -> ApiResult<impl IntoResponse> {
+    let response = cron_job_response(cron_job_controller(&state)?, &cron_job_id).await?;
     Ok(Json(response))
 }
 
-/// Update a workload.
-pub async fn update_workload(
+/// Delete a cron job along with the jobs it created.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/cronjobs/{cron_job_id}",
+    tag = "cronjobs",
+    params(("cron_job_id" = Uuid, Path, description = "Cron job ID")),
+    responses(
+        (status = 204, description = "Cron job deleted"),
+        (status = 404, description = "Cron job not found", body = ApiError),
+        (status = 409, description = "This node is not the leader", body = ApiError),
+    )
+)]
+pub async fn delete_cron_job(
     State(state): State<ApiState>,
-    Path(workload_id): Path<Uuid>,
-    Json(request): Json<CreateWorkloadRequest>,
+    Path(cron_job_id): Path<CronJobId>,
 ) -> ApiResult<impl IntoResponse> {
-    // Check workload exists
-    let _ = state
-        .state_store
-        .get_workload(&workload_id)
+    let cron_jobs = cron_job_controller(&state)?;
+    ensure_leader(&state, "Cron jobs")?;
+    cron_jobs
+        .remove_cron_job(&cron_job_id)
         .await
         .map_err(ApiError::from)?
-        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+        .ok_or_else(|| ApiError::not_found("CronJob", &cron_job_id.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    // Validate request
-    if request.name.is_empty() {
-        return Err(ApiError::validation_error("Workload name cannot be empty"));
-    }
+/// Stop a cron job from creating jobs; running jobs are left alone.
+#[utoipa::path(
+    post,
+    path = "/api/v1/cronjobs/{cron_job_id}/suspend",
+    tag = "cronjobs",
+    params(("cron_job_id" = Uuid, Path, description = "Cron job ID")),
+    responses(
+        (status = 200, description = "Cron job suspended", body = CronJobResponse),
+        (status = 404, description = "Cron job not found", body = ApiError),
+        (status = 409, description = "This node is not the leader", body = ApiError),
+    )
+)]
+pub async fn suspend_cron_job(
+    State(state): State<ApiState>,
+    Path(cron_job_id): Path<CronJobId>,
+) -> ApiResult<impl IntoResponse> {
+    set_cron_job_suspended(&state, cron_job_id, true).await
+}
+
+/// Let a suspended cron job create jobs again.
+#[utoipa::path(
+    post,
+    path = "/api/v1/cronjobs/{cron_job_id}/resume",
+    tag = "cronjobs",
+    params(("cron_job_id" = Uuid, Path, description = "Cron job ID")),
+    responses(
+        (status = 200, description = "Cron job resumed", body = CronJobResponse),
+        (status = 404, description = "Cron job not found", body = ApiError),
+        (status = 409, description = "This node is not the leader", body = ApiError),
+    )
+)]
+pub async fn resume_cron_job(
+    State(state): State<ApiState>,
+    Path(cron_job_id): Path<CronJobId>,
+) -> ApiResult<impl IntoResponse> {
+    set_cron_job_suspended(&state, cron_job_id, false).await
+}
 
-    if request.containers.is_empty() {
-        return Err(ApiError::validation_error("Workload must have at least one container"));
+async fn set_cron_job_suspended(
+    state: &ApiState,
+    cron_job_id: CronJobId,
+    suspend: bool,
+) -> ApiResult<Json<CronJobResponse>> {
+    let cron_jobs = cron_job_controller(state)?;
+    ensure_leader(state, "Cron jobs")?;
+    let stored = cron_jobs
+        .get_cron_job(&cron_job_id)
+        .await
+        .map_err(ApiError::from)?;
+    if stored.is_none() {
+        return Err(ApiError::not_found("CronJob", &cron_job_id.to_string()));
     }
+    cron_jobs
+        .set_suspended(&cron_job_id, suspend)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(cron_job_response(cron_jobs, &cron_job_id).await?))
+}
 
-    // Create updated workload with same ID
-    let workload = WorkloadDefinition {
-        id: workload_id,
-        name: request.name,
-        containers: request.containers.into_iter().map(Into::into).collect(),
-        replicas: request.replicas,
-        labels: request.labels,
-    };
+// ============================================================================
+// Daemon Set Handlers
+// ============================================================================
 
-    // Store updated workload
+fn daemon_set_controller(state: &ApiState) -> ApiResult<&DaemonSetController> {
     state
+        .daemon_sets
+        .as_deref()
+        .ok_or_else(|| ApiError::internal_error("Daemon sets are not run on this node"))
+}
+
+async fn daemon_set_response(
+    state: &ApiState,
+    daemon_set: DaemonSet,
+) -> ApiResult<DaemonSetResponse> {
+    let instances = state
         .state_store
-        .put_workload(workload.clone())
+        .list_instances_for_workload(&daemon_set.workload_id())
         .await
         .map_err(ApiError::from)?;
+    Ok(DaemonSetResponse::new(daemon_set, instances))
+}
 
-    // Send to orchestrator for re-reconciliation
-    state
-        .workload_tx
-        .send(workload.clone())
+/// Create a daemon set, running its template on every matching node.
+#[utoipa::path(
+    post,
+    path = "/api/v1/daemonsets",
+    tag = "daemonsets",
+    request_body = CreateDaemonSetRequest,
+    responses(
+        (status = 201, description = "Daemon set created", body = DaemonSetResponse),
+        (status = 400, description = "Invalid template", body = ApiError),
+        (status = 409, description = "This node is not the leader", body = ApiError),
+    )
+)]
+pub async fn create_daemon_set(
+    State(state): State<ApiState>,
+    Json(request): Json<CreateDaemonSetRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let daemon_sets = daemon_set_controller(&state)?;
+    ensure_leader(&state, "Daemon sets")?;
+    let template = prepare_workload(&state, request.template).await?;
+
+    let mut daemon_set = DaemonSet::new(request.name, template);
+    daemon_set.node_selector = request.node_selector;
+    let id = daemon_sets
+        .add_daemon_set(daemon_set)
         .await
-        .map_err(|_| ApiError::internal_error("Failed to submit workload update to orchestrator"))?;
+        .map_err(ApiError::from)?;
+    let daemon_set = daemon_sets
+        .get_daemon_set(&id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("DaemonSet", &id.to_string()))?;
+    let response = daemon_set_response(&state, daemon_set).await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
 
-    let response: WorkloadResponse = workload.into();
-    Ok(Json(response))
+/// List all daemon sets.
+#[utoipa::path(
+    get,
+    path = "/api/v1/daemonsets",
+    tag = "daemonsets",
+    responses(
+        (status = 200, description = "Daemon sets", body = Vec<DaemonSetResponse>),
+    )
+)]
+pub async fn list_daemon_sets(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let mut responses = Vec::new();
+    let daemon_sets = daemon_set_controller(&state)?
+        .list_daemon_sets()
+        .await
+        .map_err(ApiError::from)?;
+    for daemon_set in daemon_sets {
+        responses.push(daemon_set_response(&state, daemon_set).await?);
+    }
+    responses.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+    Ok(Json(responses))
 }
 
-/// Delete a workload.
-pub async fn delete_workload(
+/// Get a daemon set and its instances.
+#[utoipa::path(
+    get,
+    path = "/api/v1/daemonsets/{daemon_set_id}",
+    tag = "daemonsets",
+    params(("daemon_set_id" = Uuid, Path, description = "Daemon set ID")),
+    responses(
+        (status = 200, description = "The daemon set", body = DaemonSetResponse),
+        (status = 404, description = "Daemon set not found", body = ApiError),
+    )
+)]
+pub async fn get_daemon_set(
     State(state): State<ApiState>,
-    Path(workload_id): Path<Uuid>,
+    Path(daemon_set_id): Path<DaemonSetId>,
 ) -> ApiResult<impl IntoResponse> {
-    // Check workload exists
-    let _ = state
-        .state_store
-        .get_workload(&workload_id)
+    let daemon_set = daemon_set_controller(&state)?
+        .get_daemon_set(&daemon_set_id)
         .await
         .map_err(ApiError::from)?
-        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+        .ok_or_else(|| ApiError::not_found("DaemonSet", &daemon_set_id.to_string()))?;
+    Ok(Json(daemon_set_response(&state, daemon_set).await?))
+}
 
-    // Delete instances first
-    state
-        .state_store
-        .delete_instances_for_workload(&workload_id)
+/// Delete a daemon set and stop its instances.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/daemonsets/{daemon_set_id}",
+    tag = "daemonsets",
+    params(("daemon_set_id" = Uuid, Path, description = "Daemon set ID")),
+    responses(
+        (status = 204, description = "Daemon set deleted"),
+        (status = 404, description = "Daemon set not found", body = ApiError),
+        (status = 409, description = "This node is not the leader", body = ApiError),
+    )
+)]
+pub async fn delete_daemon_set(
+    State(state): State<ApiState>,
+    Path(daemon_set_id): Path<DaemonSetId>,
+) -> ApiResult<impl IntoResponse> {
+    let daemon_sets = daemon_set_controller(&state)?;
+    ensure_leader(&state, "Daemon sets")?;
+    daemon_sets
+        .remove_daemon_set(&daemon_set_id)
         .await
-        .map_err(ApiError::from)?;
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("DaemonSet", &daemon_set_id.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    // Delete workload
+// ============================================================================
+// Stateful Set Handlers
+// ============================================================================
+
+fn stateful_set_controller(state: &ApiState) -> ApiResult<&StatefulSetController> {
     state
+        .stateful_sets
+        .as_deref()
+        .ok_or_else(|| ApiError::internal_error("Stateful sets are not run on this node"))
+}
+
+async fn stateful_set_response(
+    state: &ApiState,
+    stateful_set: StatefulSet,
+) -> ApiResult<StatefulSetResponse> {
+    let instances = state
         .state_store
-        .delete_workload(&workload_id)
+        .list_instances_for_workload(&stateful_set.workload_id())
         .await
         .map_err(ApiError::from)?;
+    Ok(StatefulSetResponse::new(stateful_set, instances))
+}
 
-    Ok(StatusCode::NO_CONTENT)
+async fn get_stateful_set_response(
+    state: &ApiState,
+    id: &StatefulSetId,
+) -> ApiResult<StatefulSetResponse> {
+    let stateful_set = stateful_set_controller(state)?
+        .get_stateful_set(id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("StatefulSet", &id.to_string()))?;
+    stateful_set_response(state, stateful_set).await
 }
 
-/// List instances for a workload.
-pub async fn list_workload_instances(
+/// Create a stateful set, starting with its instance of ordinal 0.
+#[utoipa::path(
+    post,
+    path = "/api/v1/statefulsets",
+    tag = "statefulsets",
+    request_body = CreateStatefulSetRequest,
+    responses(
+        (status = 201, description = "Stateful set created", body = StatefulSetResponse),
+        (status = 400, description = "Invalid template", body = ApiError),
+        (status = 409, description = "This node is not the leader", body = ApiError),
+    )
+)]
+pub async fn create_stateful_set(
     State(state): State<ApiState>,
-    Path(workload_id): Path<Uuid>,
+    Json(request): Json<CreateStatefulSetRequest>,
 ) -> ApiResult<impl IntoResponse> {
-    // Check workload exists
-    let _ = state
-        .state_store
-        .get_workload(&workload_id)
+    let stateful_sets = stateful_set_controller(&state)?;
+    ensure_leader(&state, "Stateful sets")?;
+    let template = prepare_workload(&state, request.template).await?;
+
+    let mut stateful_set = StatefulSet::new(request.name, template, request.replicas);
+    for claim in request.volume_claims {
+        stateful_set = stateful_set.with_volume_claim(claim.name, claim.mount_path);
+    }
+    let id = stateful_sets
+        .add_stateful_set(stateful_set)
         .await
-        .map_err(ApiError::from)?
-        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+        .map_err(ApiError::from)?;
+    let response = get_stateful_set_response(&state, &id).await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
 
-    let instances = state
-        .state_store
-        .list_instances_for_workload(&workload_id)
+/// List all stateful sets.
+#[utoipa::path(
+    get,
+    path = "/api/v1/statefulsets",
+    tag = "statefulsets",
+    responses(
+        (status = 200, description = "Stateful sets", body = Vec<StatefulSetResponse>),
+    )
+)]
+pub async fn list_stateful_sets(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let stateful_sets = stateful_set_controller(&state)?
+        .list_stateful_sets()
+        .await
+        .map_err(ApiError::from)?;
+    let mut responses = Vec::new();
+    for stateful_set in stateful_sets {
+        responses.push(stateful_set_response(&state, stateful_set).await?);
+    }
+    responses.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+    Ok(Json(responses))
+}
+
+/// Get a stateful set and its instances.
+#[utoipa::path(
+    get,
+    path = "/api/v1/statefulsets/{stateful_set_id}",
+    tag = "statefulsets",
+    params(("stateful_set_id" = Uuid, Path, description = "Stateful set ID")),
+    responses(
+        (status = 200, description = "The stateful set", body = StatefulSetResponse),
+        (status = 404, description = "Stateful set not found", body = ApiError),
+    )
+)]
+pub async fn get_stateful_set(
+    State(state): State<ApiState>,
+    Path(stateful_set_id): Path<StatefulSetId>,
+) -> ApiResult<impl IntoResponse> {
+    Ok(Json(
+        get_stateful_set_response(&state, &stateful_set_id).await?,
+    ))
+}
+
+/// Replace the template of a stateful set, rolling it out from the highest ordinal.
+#[utoipa::path(
+    put,
+    path = "/api/v1/statefulsets/{stateful_set_id}",
+    tag = "statefulsets",
+    params(("stateful_set_id" = Uuid, Path, description = "Stateful set ID")),
+    request_body = UpdateStatefulSetRequest,
+    responses(
+        (status = 200, description = "Template replaced", body = StatefulSetResponse),
+        (status = 400, description = "Invalid template", body = ApiError),
+        (status = 404, description = "Stateful set not found", body = ApiError),
+        (status = 409, description = "This node is not the leader", body = ApiError),
+    )
+)]
+pub async fn update_stateful_set(
+    State(state): State<ApiState>,
+    Path(stateful_set_id): Path<StatefulSetId>,
+    Json(request): Json<UpdateStatefulSetRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let stateful_sets = stateful_set_controller(&state)?;
+    ensure_leader(&state, "Stateful sets")?;
+    let template = prepare_workload(&state, request.template).await?;
+    stateful_sets
+        .update_template(&stateful_set_id, template)
         .await
         .map_err(ApiError::from)?;
+    Ok(Json(
+        get_stateful_set_response(&state, &stateful_set_id).await?,
+    ))
+}
 
-    let items: Vec<InstanceResponse> = instances.into_iter().map(Into::into).collect();
-    let count = items.len();
+/// Change the replica count of a stateful set.
+#[utoipa::path(
+    post,
+    path = "/api/v1/statefulsets/{stateful_set_id}/scale",
+    tag = "statefulsets",
+    params(("stateful_set_id" = Uuid, Path, description = "Stateful set ID")),
+    request_body = ScaleStatefulSetRequest,
+    responses(
+        (status = 200, description = "Stateful set scaled", body = StatefulSetResponse),
+        (status = 404, description = "Stateful set not found", body = ApiError),
+        (status = 409, description = "This node is not the leader", body = ApiError),
+    )
+)]
+pub async fn scale_stateful_set(
+    State(state): State<ApiState>,
+    Path(stateful_set_id): Path<StatefulSetId>,
+    Json(request): Json<ScaleStatefulSetRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let stateful_sets = stateful_set_controller(&state)?;
+    ensure_leader(&state, "Stateful sets")?;
+    stateful_sets
+        .scale(&stateful_set_id, request.replicas)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(
+        get_stateful_set_response(&state, &stateful_set_id).await?,
+    ))
+}
 
-    Ok(Json(ListResponse { items, count }))
+/// Delete a stateful set and stop its instances; volumes are kept.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/statefulsets/{stateful_set_id}",
+    tag = "statefulsets",
+    params(("stateful_set_id" = Uuid, Path, description = "Stateful set ID")),
+    responses(
+        (status = 204, description = "Stateful set deleted"),
+        (status = 404, description = "Stateful set not found", body = ApiError),
+        (status = 409, description = "This node is not the leader", body = ApiError),
+    )
+)]
+pub async fn delete_stateful_set(
+    State(state): State<ApiState>,
+    Path(stateful_set_id): Path<StatefulSetId>,
+) -> ApiResult<impl IntoResponse> {
+    let stateful_sets = stateful_set_controller(&state)?;
+    ensure_leader(&state, "Stateful sets")?;
+    stateful_sets
+        .remove_stateful_set(&stateful_set_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("StatefulSet", &stateful_set_id.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
 }
 
 // ============================================================================
-// Node Handlers
+// Image Handlers
 // ============================================================================
 
-/// List all nodes.
-pub async fn list_nodes(
+fn image_prepuller(state: &ApiState) -> ApiResult<&ImagePrepuller> {
+    state
+        .prepuller
+        .as_deref()
+        .ok_or_else(|| ApiError::internal_error("Image pre-pulls are not configured"))
+}
+
+/// Have the selected nodes pull an image ahead of a rollout.
+#[utoipa::path(
+    post,
+    path = "/api/v1/images/prepull",
+    tag = "images",
+    request_body = PrepullImageRequest,
+    responses(
+        (status = 202, description = "Pulls started", body = Prepull),
+        (status = 400, description = "Empty image or no matching ready node", body = ApiError),
+    )
+)]
+pub async fn prepull_image(
     State(state): State<ApiState>,
+    Json(request): Json<PrepullImageRequest>,
 ) -> ApiResult<impl IntoResponse> {
-    let nodes = state
-        .state_store
-        .list_nodes()
+    let prepull = image_prepuller(&state)?
+        .start(request.image, request.node_selector)
         .await
         .map_err(ApiError::from)?;
+    Ok((StatusCode::ACCEPTED, Json(prepull)))
+}
 
-    let items: Vec<NodeResponse> = nodes.into_iter().map(Into::into).collect();
-    let count = items.len();
-
-    Ok(Json(ListResponse { items, count }))
+/// List image pre-pulls with their progress, oldest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/images/prepull",
+    tag = "images",
+    responses(
+        (status = 200, description = "Pre-pulls", body = Vec<Prepull>),
+    )
+)]
+pub async fn list_prepulls(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    Ok(Json(image_prepuller(&state)?.list().await))
 }
 
-/// Get a node by ID.
-pub async fn get_node(
+/// Get the progress of an image pre-pull on each node.
+#[utoipa::path(
+    get,
+    path = "/api/v1/images/prepull/{prepull_id}",
+    tag = "images",
+    params(("prepull_id" = Uuid, Path, description = "Pre-pull ID")),
+    responses(
+        (status = 200, description = "The pre-pull", body = Prepull),
+        (status = 404, description = "Pre-pull not found", body = ApiError),
+    )
+)]
+pub async fn get_prepull(
     State(state): State<ApiState>,
-    Path(node_id_str): Path<String>,
+    Path(prepull_id): Path<PrepullId>,
 ) -> ApiResult<impl IntoResponse> {
-    let node_id: NodeId = node_id_str.parse()
-        .map_err(|_| ApiError::validation_error(&format!("Invalid node ID: {}", node_id_str)))?;
+    let prepull = image_prepuller(&state)?
+        .get(&prepull_id)
+        .await
+        .ok_or_else(|| ApiError::not_found("Prepull", &prepull_id.to_string()))?;
+    Ok(Json(prepull))
+}
 
-    let node = state
-        .state_store
-        .get_node(&node_id)
+/// Resolve an image reference in its registry and read its config, without
+/// pulling its layers. References containing `/` are percent-encoded.
+#[utoipa::path(
+    get,
+    path = "/api/v1/images/{image}/inspect",
+    tag = "images",
+    params(("image" = String, Path, description = "Image reference, e.g. nginx:1.27")),
+    responses(
+        (status = 200, description = "The resolved image", body = ImageInspectionResponse),
+        (status = 400, description = "Invalid image reference", body = ApiError),
+        (status = 500, description = "Registry request failed", body = ApiError),
+    )
+)]
+pub async fn inspect_image(
+    State(state): State<ApiState>,
+    Path(image): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    let runtime = state
+        .container_runtime
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured"))?;
+    let inspection = runtime
+        .inspect_image(&image)
         .await
-        .map_err(ApiError::from)?
-        .ok_or_else(|| ApiError::not_found("Node", &node_id_str))?;
+        .map_err(ApiError::from)?;
+    Ok(Json(ImageInspectionResponse::from(inspection)))
+}
 
-    let response: NodeResponse = node.into();
-    Ok(Json(response))
+/// Scan an image for known vulnerabilities, pulling it first if needed. The
+/// recorded scan is returned when the image's digest was scanned before.
+#[utoipa::path(
+    get,
+    path = "/api/v1/images/{image}/scan",
+    tag = "images",
+    params(("image" = String, Path, description = "Image reference, e.g. nginx:1.27")),
+    responses(
+        (status = 200, description = "The image's vulnerabilities", body = ImageScanResponse),
+        (status = 400, description = "Invalid image reference", body = ApiError),
+        (status = 500, description = "No scanner configured or the scan failed", body = ApiError),
+    )
+)]
+pub async fn scan_image(
+    State(state): State<ApiState>,
+    Path(image): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    let runtime = state
+        .container_runtime
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured"))?;
+    let scan = runtime.scan_image(&image).await.map_err(ApiError::from)?;
+    Ok(Json(ImageScanResponse::from(scan)))
+}
+
+/// List recorded image scans with their vulnerability counts, most recent first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/images/scans",
+    tag = "images",
+    responses(
+        (status = 200, description = "Recorded scans", body = Vec<ImageScanResponse>),
+    )
+)]
+pub async fn list_image_scans(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let runtime = state
+        .container_runtime
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured"))?;
+    let mut scans = runtime.image_scans().await.map_err(ApiError::from)?;
+    scans.sort_by(|a, b| b.scanned_at.cmp(&a.scanned_at));
+    let scans: Vec<ImageScanResponse> = scans
+        .into_iter()
+        .map(|scan| ImageScanResponse {
+            vulnerabilities: vec![],
+            ..scan.into()
+        })
+        .collect();
+    Ok(Json(scans))
 }
 
 // ============================================================================
@@ -535,6 +4291,14 @@ pub async fn get_node(
 // ============================================================================
 
 /// Get cluster status.
+#[utoipa::path(
+    get,
+    path = "/api/v1/cluster/status",
+    tag = "cluster",
+    responses(
+        (status = 200, description = "Cluster summary", body = ClusterStatusResponse),
+    )
+)]
 pub async fn get_cluster_status(
     State(state): State<ApiState>,
 ) -> ApiResult<impl IntoResponse> {
@@ -589,6 +4353,7 @@ pub async fn get_cluster_status(
         .count();
 
     Ok(Json(ClusterStatusResponse {
+        version: env!("CARGO_PKG_VERSION").to_string(),
         total_nodes,
         ready_nodes,
         not_ready_nodes,
@@ -604,12 +4369,706 @@ pub async fn get_cluster_status(
     }))
 }
 
+/// Get the control-plane leader.
+#[utoipa::path(
+    get,
+    path = "/api/v1/cluster/leader",
+    tag = "cluster",
+    responses(
+        (status = 200, description = "Current leader", body = LeaderResponse),
+    )
+)]
+pub async fn get_cluster_leader(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let elector = state
+        .leader
+        .as_deref()
+        .ok_or_else(|| ApiError::internal_error("Leader election not configured"))?;
+    let lease = elector.leader().await.map_err(ApiError::from)?;
+    let now_ms = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    Ok(Json(LeaderResponse {
+        leader: lease.as_ref().map(|l| l.holder.clone()),
+        term: lease.as_ref().map(|l| l.term),
+        lease_expires_in_ms: lease.as_ref().map(|l| l.expires_at_ms.saturating_sub(now_ms)),
+        instance: elector.identity().to_string(),
+        is_leader: elector.is_leader(),
+    }))
+}
+
+/// Snapshot everything the control plane stores. Secrets are not stored,
+/// so there are none in the backup.
+#[utoipa::path(
+    get,
+    path = "/api/v1/cluster/backup",
+    tag = "cluster",
+    responses(
+        (
+            status = 200,
+            description = "Snapshot of everything the control plane stores",
+            body = serde_json::Value
+        ),
+    )
+)]
+pub async fn get_backup(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let backup = ClusterBackup::snapshot(state.state_store.as_ref())
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(backup))
+}
+
+/// Restore a backup into an empty control plane, then hand the restored
+/// workloads to the orchestrator for reconciliation.
+#[utoipa::path(
+    post,
+    path = "/api/v1/cluster/backup/restore",
+    tag = "cluster",
+    request_body = serde_json::Value,
+    responses(
+        (status = 200, description = "Objects restored", body = RestoreResponse),
+        (status = 400, description = "Invalid backup", body = ApiError),
+        (status = 409, description = "The control plane is not empty", body = ApiError),
+    )
+)]
+pub async fn restore_backup(
+    State(state): State<ApiState>,
+    Json(backup): Json<ClusterBackup>,
+) -> ApiResult<impl IntoResponse> {
+    let workloads = backup.workloads.clone();
+    let summary = backup
+        .restore(state.state_store.as_ref())
+        .await
+        .map_err(ApiError::from)?;
+
+    // Controllers keep their objects in memory once loaded
+    if let Some(cron_jobs) = &state.cron_jobs {
+        cron_jobs.load().await.map_err(ApiError::from)?;
+    }
+    if let Some(daemon_sets) = &state.daemon_sets {
+        daemon_sets.load().await.map_err(ApiError::from)?;
+    }
+    if let Some(stateful_sets) = &state.stateful_sets {
+        stateful_sets.load().await.map_err(ApiError::from)?;
+    }
+    for workload in workloads {
+        state
+            .workload_tx
+            .send(workload)
+            .await
+            .map_err(|_| ApiError::internal_error("Failed to submit workload to orchestrator"))?;
+    }
+
+    Ok(Json(RestoreResponse {
+        namespaces: summary.namespaces,
+        nodes: summary.nodes,
+        workloads: summary.workloads,
+        instances: summary.instances,
+        revisions: summary.revisions,
+        events: summary.events,
+        usage_records: summary.usage_records,
+        resources: summary.resources,
+    }))
+}
+
+// ============================================================================
+// Service Handlers
+// ============================================================================
+
+fn service_proxy(state: &ApiState) -> ApiResult<&ServiceProxy> {
+    state
+        .service_proxy
+        .as_deref()
+        .ok_or_else(|| ApiError::internal_error("Service proxy not configured"))
+}
+
+/// Get a stored service, as long as it is in the namespace asked for.
+async fn stored_service(
+    state: &ApiState,
+    service_id: &Uuid,
+    scope: &NamespaceQuery,
+) -> ApiResult<Service> {
+    resources::get::<Service>(state.state_store.as_ref(), &service_id.to_string())
+        .await
+        .map_err(ApiError::from)?
+        .filter(|s| scope.contains(&s.namespace))
+        .ok_or_else(|| ApiError::not_found("Service", &service_id.to_string()))
+}
+
+/// Create a service, giving it virtual IPs that every node forwards to its endpoints.
+#[utoipa::path(
+    post,
+    path = "/api/v1/services",
+    tag = "services",
+    request_body = CreateServiceRequest,
+    responses(
+        (status = 201, description = "Service created", body = ServiceResponse),
+        (status = 400, description = "Invalid service", body = ApiError),
+        (status = 409, description = "A service with this name exists", body = ApiError),
+    )
+)]
+pub async fn create_service(
+    State(state): State<ApiState>,
+    Json(request): Json<CreateServiceRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let proxy = service_proxy(&state)?;
+    // Service names are DNS labels, like namespace names
+    if !Namespace::is_valid_name(&request.name) {
+        return Err(ApiError::validation_error(format!(
+            "Invalid service name '{}': use at most 63 lowercase letters, digits and hyphens",
+            request.name
+        )));
+    }
+    let ports = service_ports(&request.selector, request.ports)?;
+    ensure_namespace(&state, &request.namespace).await?;
+
+    let existing: Vec<Service> = resources::list(state.state_store.as_ref())
+        .await
+        .map_err(ApiError::from)?;
+    if existing
+        .iter()
+        .any(|s| s.name == request.name && s.namespace == request.namespace)
+    {
+        return Err(ApiError::conflict(format!(
+            "Service {}/{} already exists",
+            request.namespace, request.name
+        )));
+    }
+
+    let mut service = Service::new(request.name, request.selector)
+        .with_namespace(request.namespace)
+        .with_ip_family_policy(request.ip_family_policy)
+        .with_session_affinity(request.session_affinity);
+    service.ports = ports;
+    service.backend_weights = request.backend_weights;
+    let service = proxy.add_service(service).await.map_err(ApiError::from)?;
+    Ok((StatusCode::CREATED, Json(ServiceResponse::from(service))))
+}
+
+/// List services.
+#[utoipa::path(
+    get,
+    path = "/api/v1/services",
+    tag = "services",
+    params(NamespaceQuery),
+    responses(
+        (status = 200, description = "Services", body = Vec<ServiceResponse>),
+    )
+)]
+pub async fn list_services(
+    State(state): State<ApiState>,
+    Query(scope): Query<NamespaceQuery>,
+) -> ApiResult<impl IntoResponse> {
+    let mut services: Vec<ServiceResponse> = resources::list::<Service>(state.state_store.as_ref())
+        .await
+        .map_err(ApiError::from)?
+        .into_iter()
+        .filter(|s| scope.contains(&s.namespace))
+        .map(ServiceResponse::from)
+        .collect();
+    services.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+    Ok(Json(services))
+}
+
+/// Get a service.
+#[utoipa::path(
+    get,
+    path = "/api/v1/services/{service_id}",
+    tag = "services",
+    params(
+        ("service_id" = Uuid, Path, description = "Service ID"),
+        NamespaceQuery,
+    ),
+    responses(
+        (status = 200, description = "The service", body = ServiceResponse),
+        (status = 404, description = "Service not found", body = ApiError),
+    )
+)]
+pub async fn get_service(
+    State(state): State<ApiState>,
+    Path(service_id): Path<Uuid>,
+    Query(scope): Query<NamespaceQuery>,
+) -> ApiResult<impl IntoResponse> {
+    let service = stored_service(&state, &service_id, &scope).await?;
+    Ok(Json(ServiceResponse::from(service)))
+}
+
+/// Replace a service's selector, ports, session affinity and backend weights.
+#[utoipa::path(
+    put,
+    path = "/api/v1/services/{service_id}",
+    tag = "services",
+    params(("service_id" = Uuid, Path, description = "Service ID")),
+    request_body = UpdateServiceRequest,
+    responses(
+        (status = 200, description = "Service updated", body = ServiceResponse),
+        (status = 400, description = "Invalid service", body = ApiError),
+        (status = 404, description = "Service not found", body = ApiError),
+    )
+)]
+pub async fn update_service(
+    State(state): State<ApiState>,
+    Path(service_id): Path<Uuid>,
+    Json(request): Json<UpdateServiceRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let proxy = service_proxy(&state)?;
+    let mut service = stored_service(&state, &service_id, &NamespaceQuery::default()).await?;
+    service.ports = service_ports(&request.selector, request.ports)?;
+    service.selector = request.selector;
+    service.session_affinity = request.session_affinity;
+    service.backend_weights = request.backend_weights;
+    let service = proxy
+        .update_service(service)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(ServiceResponse::from(service)))
+}
+
+/// Delete a service and release its virtual IPs.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/services/{service_id}",
+    tag = "services",
+    params(("service_id" = Uuid, Path, description = "Service ID")),
+    responses(
+        (status = 204, description = "Service deleted"),
+        (status = 404, description = "Service not found", body = ApiError),
+    )
+)]
+pub async fn delete_service(
+    State(state): State<ApiState>,
+    Path(service_id): Path<Uuid>,
+) -> ApiResult<impl IntoResponse> {
+    service_proxy(&state)?
+        .remove_service(&service_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Service", &service_id.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get the endpoints currently backing a service, including ones that are
+/// not ready.
+#[utoipa::path(
+    get,
+    path = "/api/v1/services/{service_id}/endpoints",
+    tag = "services",
+    params(
+        ("service_id" = Uuid, Path, description = "Service ID"),
+        NamespaceQuery,
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Endpoints backing the service",
+            body = ServiceEndpointsResponse
+        ),
+        (status = 404, description = "Service not found", body = ApiError),
+    )
+)]
+pub async fn get_service_endpoints(
+    State(state): State<ApiState>,
+    Path(service_id): Path<Uuid>,
+    Query(scope): Query<NamespaceQuery>,
+) -> ApiResult<impl IntoResponse> {
+    let proxy = state
+        .service_proxy
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Service proxy not configured"))?;
+
+    let service = proxy
+        .get_service(&service_id)
+        .filter(|s| scope.contains(&s.namespace))
+        .ok_or_else(|| ApiError::not_found("Service", &service_id.to_string()))?;
+    let status = proxy
+        .endpoints(&service_id)
+        .ok_or_else(|| ApiError::not_found("Service", &service_id.to_string()))?;
+
+    let endpoints: Vec<EndpointResponse> = status.endpoints.into_iter().map(Into::into).collect();
+    let ready_count = endpoints.iter().filter(|e| e.ready).count();
+    let not_ready_count = endpoints.len() - ready_count;
+
+    Ok(Json(ServiceEndpointsResponse {
+        service_id,
+        service_name: service.name,
+        session_affinity: status.session_affinity,
+        endpoints,
+        ready_count,
+        not_ready_count,
+    }))
+}
+
+// ============================================================================
+// Tunnel Handlers
+// ============================================================================
+
+fn tunnel_manager(state: &ApiState) -> ApiResult<&TunnelManager> {
+    state
+        .tunnels
+        .as_deref()
+        .ok_or_else(|| ApiError::internal_error("Tunnels not configured on this node"))
+}
+
+/// Open a temporary public tunnel to a workload's instances.
+#[utoipa::path(
+    post,
+    path = "/api/v1/workloads/{workload_id}/tunnels",
+    tag = "tunnels",
+    params(("workload_id" = Uuid, Path, description = "Workload ID")),
+    request_body = OpenTunnelRequest,
+    responses(
+        (status = 201, description = "Tunnel opened", body = tunnel::Tunnel),
+        (status = 404, description = "Workload not found", body = ApiError),
+    )
+)]
+pub async fn open_tunnel(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+    Json(request): Json<OpenTunnelRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let ttl = request
+        .ttl_secs
+        .map(Duration::from_secs)
+        .unwrap_or(tunnel::DEFAULT_TTL);
+    let tunnel = tunnel_manager(&state)?
+        .open(workload_id, request.port, ttl)
+        .await
+        .map_err(ApiError::from)?;
+    Ok((StatusCode::CREATED, Json(tunnel)))
+}
+
+/// List open tunnels.
+#[utoipa::path(
+    get,
+    path = "/api/v1/tunnels",
+    tag = "tunnels",
+    responses(
+        (status = 200, description = "Open tunnels", body = ListResponse<tunnel::Tunnel>),
+    )
+)]
+pub async fn list_tunnels(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let items = tunnel_manager(&state)?.list();
+    Ok(Json(ListResponse::new(items)))
+}
+
+/// Close a tunnel before it expires.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/tunnels/{tunnel_id}",
+    tag = "tunnels",
+    params(("tunnel_id" = Uuid, Path, description = "Tunnel ID")),
+    responses(
+        (status = 204, description = "Tunnel closed"),
+        (status = 404, description = "Tunnel not found", body = ApiError),
+    )
+)]
+pub async fn close_tunnel(
+    State(state): State<ApiState>,
+    Path(tunnel_id): Path<Uuid>,
+) -> ApiResult<impl IntoResponse> {
+    tunnel_manager(&state)?
+        .close(&tunnel_id)
+        .ok_or_else(|| ApiError::not_found("Tunnel", &tunnel_id.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ============================================================================
+// Event Handlers
+// ============================================================================
+
+/// List events, oldest first by when they last occurred.
+#[utoipa::path(
+    get,
+    path = "/api/v1/events",
+    tag = "events",
+    params(EventQuery),
+    responses(
+        (status = 200, description = "Matching events", body = ListResponse<EventResponse>),
+        (status = 501, description = "The state store keeps no events", body = ApiError),
+    )
+)]
+pub async fn list_events(
+    State(state): State<ApiState>,
+    Query(query): Query<EventQuery>,
+) -> ApiResult<Json<ListResponse<EventResponse>>> {
+    let items = state
+        .state_store
+        .list_events()
+        .await
+        .map_err(ApiError::from)?
+        .into_iter()
+        .filter(|e| {
+            query
+                .involved_object
+                .as_ref()
+                .is_none_or(|id| *id == e.involved_object.id)
+                && query.namespace.as_ref().is_none_or(|ns| *ns == e.namespace)
+        })
+        .map(Into::into)
+        .collect();
+    Ok(Json(ListResponse::new(items)))
+}
+
+// ============================================================================
+// Report Handlers
+// ============================================================================
+
+/// Report the CPU, memory and GPU hours workloads held, per namespace and
+/// workload, for chargeback.
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/usage",
+    tag = "reports",
+    params(UsageReportQuery),
+    responses(
+        (status = 200, description = "Resources held in the range", body = UsageReport),
+        (status = 400, description = "The range ends before it starts", body = ApiError),
+        (status = 501, description = "The state store keeps no usage records", body = ApiError),
+    )
+)]
+pub async fn get_usage_report(
+    State(state): State<ApiState>,
+    Query(query): Query<UsageReportQuery>,
+) -> ApiResult<Json<UsageReport>> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query
+        .from
+        .unwrap_or(to - chrono::Duration::days(UsageReportQuery::DEFAULT_DAYS));
+    if from > to {
+        return Err(ApiError::bad_request(format!(
+            "Report starts at {} after it ends at {}",
+            from.to_rfc3339(),
+            to.to_rfc3339()
+        )));
+    }
+    let report = UsageReport::build(&*state.state_store, from, to, query.namespace.as_deref())
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(report))
+}
+
+// ============================================================================
+// Audit Handlers
+// ============================================================================
+
+/// List audit records matching the query, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/audit",
+    tag = "audit",
+    params(AuditQuery),
+    responses(
+        (
+            status = 200,
+            description = "Matching records, newest first",
+            body = ListResponse<AuditRecord>
+        ),
+    )
+)]
+pub async fn list_audit(
+    State(state): State<ApiState>,
+    Query(query): Query<AuditQuery>,
+) -> ApiResult<impl IntoResponse> {
+    let audit = state
+        .audit
+        .as_ref()
+        .ok_or_else(|| ApiError::internal_error("Audit logging not configured on this node"))?;
+    let items = audit.query(&query).await.map_err(ApiError::from)?;
+    Ok(Json(ListResponse::new(items)))
+}
+
+// ============================================================================
+// Auth Handlers
+// ============================================================================
+
+/// Report the caller and its role bindings.
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/whoami",
+    tag = "auth",
+    responses(
+        (status = 200, description = "The caller", body = WhoAmIResponse),
+    )
+)]
+pub async fn whoami(
+    State(state): State<ApiState>,
+    Extension(auth): Extension<AuthInfo>,
+) -> ApiResult<impl IntoResponse> {
+    let config = &state.auth_config;
+    let rbac = config.rbac.as_ref().filter(|_| config.required);
+    let mut bindings: Vec<RoleBinding> = rbac
+        .map(|policy| policy.bindings_for(&auth.subject).cloned().collect())
+        .unwrap_or_default();
+    bindings.extend(auth.granted);
+    Ok(Json(WhoAmIResponse {
+        subject: auth.subject,
+        authenticated: config.required,
+        method: auth.method,
+        rbac_enabled: rbac.is_some(),
+        bindings,
+    }))
+}
+
+/// Issue a bearer token to a caller that signed its request.
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/token",
+    tag = "auth",
+    responses(
+        (status = 201, description = "Token issued", body = TokenResponse),
+        (status = 400, description = "Tokens not issued to this caller", body = ApiError),
+    )
+)]
+pub async fn issue_token(
+    State(state): State<ApiState>,
+    Extension(auth): Extension<AuthInfo>,
+) -> ApiResult<impl IntoResponse> {
+    let issuer = state
+        .auth_config
+        .token_issuer
+        .as_ref()
+        .ok_or_else(|| ApiError::bad_request("Token issuance is not enabled on this cluster"))?;
+    if auth.method != AuthMethod::Signature {
+        return Err(ApiError::bad_request(
+            "Tokens are only issued to requests signed with an Ed25519 key",
+        ));
+    }
+    let issued = issuer
+        .issue(&auth.subject)
+        .map_err(|e| ApiError::internal_error(e.error))?;
+    Ok((
+        StatusCode::CREATED,
+        Json(TokenResponse {
+            token: issued.token,
+            token_type: "Bearer".to_string(),
+            expires_at: issued.expires_at,
+            subject: auth.subject,
+        }),
+    ))
+}
+
+/// Sign a client certificate for a caller that signed its request or
+/// presented a certificate it is renewing.
+#[cfg(feature = "mtls")]
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/certificate",
+    tag = "auth",
+    request_body = CertificateRequest,
+    responses(
+        (status = 201, description = "Certificate issued", body = CertificateResponse),
+        (
+            status = 400,
+            description = "Invalid request or certificates not issued to this caller",
+            body = ApiError
+        ),
+    )
+)]
+pub async fn issue_certificate(
+    State(state): State<ApiState>,
+    Extension(auth): Extension<AuthInfo>,
+    Json(request): Json<CertificateRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let ca = state
+        .auth_config
+        .certificate_authority
+        .as_ref()
+        .ok_or_else(|| ApiError::bad_request("Client certificates are not issued by this cluster"))?;
+    if !matches!(auth.method, AuthMethod::Signature | AuthMethod::ClientCertificate) {
+        return Err(ApiError::bad_request(
+            "Client certificates are only issued to requests signed with an Ed25519 key \
+             or made with a client certificate",
+        ));
+    }
+    let issued = ca
+        .sign_client_request(
+            &request.csr,
+            &auth.subject,
+            chrono::Duration::days(super::tls::ClusterCa::CLIENT_CERT_TTL_DAYS),
+        )
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    Ok((
+        StatusCode::CREATED,
+        Json(CertificateResponse {
+            certificate: issued.certificate_pem,
+            ca_certificate: ca.certificate_pem().to_string(),
+            expires_at: issued.expires_at,
+            subject: auth.subject,
+        }),
+    ))
+}
+
+// ============================================================================
+// Admin Handlers
+// ============================================================================
+
+fn consistency_checker(state: &ApiState) -> ApiResult<ConsistencyChecker> {
+    let runtime = state
+        .container_runtime
+        .clone()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured"))?;
+    Ok(ConsistencyChecker::new(
+        state.state_store.clone(),
+        runtime,
+        state.cluster_manager.clone(),
+        state.workload_tx.clone(),
+    ))
+}
+
+/// Cross-check stored state against the runtime and cluster.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/fsck",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Discrepancies found", body = FsckResponse),
+    )
+)]
+pub async fn check_consistency(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let report = consistency_checker(&state)?
+        .check()
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(FsckResponse::from(report)))
+}
+
+/// Cross-check stored state and repair what is inconsistent.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/fsck",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Discrepancies found and repaired", body = FsckResponse),
+    )
+)]
+pub async fn repair_consistency(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let report = consistency_checker(&state)?
+        .repair()
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(FsckResponse::from(report)))
+}
+
 // ============================================================================
 // Log Handlers
 // ============================================================================
 
 /// Get logs for a workload.
 /// Returns logs from all instances/containers of the workload.
+#[utoipa::path(
+    get,
+    path = "/api/v1/workloads/{workload_id}/logs",
+    tag = "logs",
+    params(
+        ("workload_id" = Uuid, Path, description = "Workload ID"),
+        LogsQuery,
+    ),
+    responses(
+        (status = 200, description = "Logs of every container", body = LogsResponse),
+        (status = 404, description = "Workload not found", body = ApiError),
+    )
+)]
 pub async fn get_workload_logs(
     State(state): State<ApiState>,
     Path(workload_id): Path<Uuid>,
@@ -682,6 +5141,20 @@ pub async fn get_workload_logs(
 }
 
 /// Get logs for a specific workload instance.
+#[utoipa::path(
+    get,
+    path = "/api/v1/workloads/{workload_id}/instances/{instance_id}/logs",
+    tag = "logs",
+    params(
+        ("workload_id" = Uuid, Path, description = "Workload ID"),
+        ("instance_id" = Uuid, Path, description = "Instance ID"),
+        LogsQuery,
+    ),
+    responses(
+        (status = 200, description = "Logs of the instance's containers", body = LogsResponse),
+        (status = 404, description = "Workload or instance not found", body = ApiError),
+    )
+)]
 pub async fn get_instance_logs(
     State(state): State<ApiState>,
     Path((workload_id, instance_id)): Path<(Uuid, Uuid)>,
@@ -746,14 +5219,186 @@ pub async fn get_instance_logs(
     }))
 }
 
+/// Search the logs of every container of a workload, live and archived.
+///
+/// The runtime filters each log, so only matching lines are collected. Hits
+/// are merged by timestamp and streamed as newline-delimited JSON.
+#[utoipa::path(
+    get,
+    path = "/api/v1/workloads/{workload_id}/logs/search",
+    tag = "logs",
+    params(
+        ("workload_id" = Uuid, Path, description = "Workload ID"),
+        LogSearchQuery,
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Matching lines, one JSON object per line",
+            body = LogSearchHit,
+            content_type = "application/x-ndjson"
+        ),
+        (status = 400, description = "Empty query or invalid regex", body = ApiError),
+        (status = 404, description = "Workload not found", body = ApiError),
+    )
+)]
+pub async fn search_workload_logs(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+    Query(query): Query<LogSearchQuery>,
+) -> ApiResult<Response> {
+    let runtime = state.container_runtime.clone()
+        .ok_or_else(|| ApiError::internal_error("Container runtime not configured for log access"))?;
+
+    if query.q.is_empty() {
+        return Err(ApiError::validation_error("q must not be empty"));
+    }
+    let search = LogSearchOptions {
+        query: query.q,
+        regex: query.regex,
+        since: query.since,
+    };
+    // Reject a bad regex before fanning out
+    LogMatcher::new(&search).map_err(ApiError::from)?;
+
+    // Check workload exists
+    let _ = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+
+    let instances = state
+        .state_store
+        .list_instances_for_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?;
+
+    let mut containers: Vec<(Option<Uuid>, String)> = instances
+        .iter()
+        .flat_map(|i| i.container_ids.iter().map(move |c| (Some(i.id), c.clone())))
+        .collect();
+    match runtime.archived_log_containers(workload_id).await {
+        Ok(archived) => {
+            for container_id in archived {
+                if !containers.iter().any(|(_, c)| *c == container_id) {
+                    containers.push((None, container_id));
+                }
+            }
+        }
+        Err(e) => tracing::warn!(
+            "Failed to list archived logs of workload {}: {}",
+            workload_id,
+            e
+        ),
+    }
+
+    let mut searches = tokio::task::JoinSet::new();
+    for (instance_id, container_id) in containers {
+        let runtime = runtime.clone();
+        let search = search.clone();
+        searches.spawn(async move {
+            let result = runtime.search_container_logs(&container_id, &search).await;
+            (instance_id, container_id, result)
+        });
+    }
+
+    let mut hits = Vec::new();
+    while let Some(joined) = searches.join_next().await {
+        let Ok((instance_id, container_id, result)) = joined else {
+            continue;
+        };
+        match result {
+            Ok(matches) => hits.extend(matches.into_iter().map(|m| LogSearchHit {
+                instance_id,
+                container_id: container_id.clone(),
+                timestamp: m.timestamp,
+                stream: m.stream,
+                message: m.message,
+            })),
+            Err(e) => {
+                tracing::warn!("Failed to search logs of container {}: {}", container_id, e);
+            }
+        }
+    }
+    // Stable, so lines of one container keep their order on equal timestamps
+    hits.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    let lines = tokio_stream::iter(hits.into_iter().map(|hit| {
+        serde_json::to_vec(&hit).map(|mut line| {
+            line.push(b'\n');
+            line
+        })
+    }));
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, "application/x-ndjson")],
+        axum::body::Body::from_stream(lines),
+    )
+        .into_response())
+}
+
 /// WebSocket endpoint for streaming logs in real-time.
 /// Connects to the container runtime's log stream and forwards messages.
+///
+/// Lines always carry their timestamp. The stream starts with the last
+/// `tail` lines of each container (100 unless `tail` or `since` is given),
+/// then sends new lines as they are written, including those of instances
+/// started after the stream was opened.
+#[utoipa::path(
+    get,
+    path = "/api/v1/workloads/{workload_id}/logs/stream",
+    tag = "logs",
+    params(
+        ("workload_id" = Uuid, Path, description = "Workload ID"),
+        LogsQuery,
+    ),
+    responses(
+        (status = 101, description = "WebSocket streaming new log lines as JSON messages"),
+    )
+)]
 pub async fn stream_workload_logs(
     State(state): State<ApiState>,
     Path(workload_id): Path<Uuid>,
+    Query(query): Query<LogsQuery>,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_log_stream(socket, state, workload_id))
+    ws.on_upgrade(move |socket| handle_log_stream(socket, state, workload_id, query))
+}
+
+/// Position in a container's log: the timestamp of the last line sent, and
+/// how many lines with that timestamp were sent.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct LogCursor {
+    timestamp: String,
+    sent: usize,
+}
+
+impl LogCursor {
+    /// The lines of `logs` after the cursor, moving it past them. Lines are
+    /// `TIMESTAMP STREAM MESSAGE`, in timestamp order.
+    fn advance<'a>(&mut self, logs: &'a str) -> Vec<&'a str> {
+        let mut skip = self.sent;
+        let mut lines = Vec::new();
+        for line in logs.lines() {
+            let timestamp = line.split(' ').next().unwrap_or_default();
+            match timestamp.cmp(self.timestamp.as_str()) {
+                std::cmp::Ordering::Less => continue,
+                std::cmp::Ordering::Equal if skip > 0 => {
+                    skip -= 1;
+                    continue;
+                }
+                std::cmp::Ordering::Equal => self.sent += 1,
+                std::cmp::Ordering::Greater => {
+                    self.timestamp = timestamp.to_string();
+                    self.sent = 1;
+                    skip = 0;
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
 }
 
 /// Handle WebSocket connection for log streaming.
@@ -761,6 +5406,7 @@ async fn handle_log_stream(
     mut socket: WebSocket,
     state: ApiState,
     workload_id: Uuid,
+    query: LogsQuery,
 ) {
     // Check runtime is available
     let runtime = match state.container_runtime.as_ref() {
@@ -811,10 +5457,8 @@ async fn handle_log_stream(
 
     if instances.is_empty() {
         let _ = socket.send(Message::Text(
-            r#"{"info":"No instances for this workload"}"#.into()
+            r#"{"info":"No instances for this workload yet"}"#.into()
         )).await;
-        let _ = socket.close().await;
-        return;
     }
 
     // Send initial message
@@ -825,46 +5469,58 @@ async fn handle_log_stream(
         )
     )).await;
 
-    // Build log options for follow mode
-    let log_options = RuntimeLogOptions {
-        tail: Some(100), // Start with last 100 lines
+    // The first read of a container honors tail and since; later reads pick
+    // up after the last line sent
+    let initial_options = RuntimeLogOptions {
+        tail: query.tail.or(if query.since.is_none() { Some(100) } else { None }),
         timestamps: true,
-        since: None,
+        since: query.since.clone(),
         until: None,
     };
 
-    // Stream logs from all containers
-    // For now, poll periodically since the runtime interface doesn't expose streaming
-    let mut last_line_counts: HashMap<String, usize> = HashMap::new();
+    // Poll periodically since the runtime interface doesn't expose streaming
+    let mut cursors: HashMap<String, LogCursor> = HashMap::new();
     let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(500));
 
     loop {
         tokio::select! {
             _ = interval.tick() => {
+                // Instances come and go, e.g. during a rollout
+                let instances = match state
+                    .state_store
+                    .list_instances_for_workload(&workload_id)
+                    .await
+                {
+                    Ok(instances) => instances,
+                    Err(e) => {
+                        tracing::debug!("Failed to list instances of {}: {}", workload_id, e);
+                        continue;
+                    }
+                };
                 for instance in &instances {
                     for container_id in &instance.container_ids {
-                        match runtime.get_container_logs(container_id, &log_options).await {
-                            Ok(logs) if !logs.is_empty() => {
-                                let lines: Vec<&str> = logs.lines().collect();
-                                let line_count = lines.len();
-                                let last_count = *last_line_counts.get(container_id).unwrap_or(&0);
-
-                                if line_count > last_count {
-                                    // Send new lines
-                                    for line in lines.iter().skip(last_count) {
-                                        let msg = serde_json::json!({
-                                            "container_id": container_id,
-                                            "instance_id": instance.id.to_string(),
-                                            "log": line
-                                        });
-                                        if socket.send(Message::Text(msg.to_string())).await.is_err() {
-                                            return; // Client disconnected
-                                        }
+                        let options = match cursors.get(container_id) {
+                            Some(cursor) => RuntimeLogOptions {
+                                timestamps: true,
+                                since: Some(cursor.timestamp.clone()),
+                                ..Default::default()
+                            },
+                            None => initial_options.clone(),
+                        };
+                        match runtime.get_container_logs(container_id, &options).await {
+                            Ok(logs) => {
+                                let cursor = cursors.entry(container_id.clone()).or_default();
+                                for line in cursor.advance(&logs) {
+                                    let msg = serde_json::json!({
+                                        "container_id": container_id,
+                                        "instance_id": instance.id.to_string(),
+                                        "log": line
+                                    });
+                                    if socket.send(Message::Text(msg.to_string())).await.is_err() {
+                                        return; // Client disconnected
                                     }
-                                    last_line_counts.insert(container_id.clone(), line_count);
                                 }
                             }
-                            Ok(_) => {} // Empty logs
                             Err(e) => {
                                 tracing::debug!("Log fetch error for {}: {}", container_id, e);
                             }
@@ -895,7 +5551,7 @@ async fn handle_log_stream(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use orchestrator_shared_types::Keypair;
+    use orchestrator_shared_types::{Keypair, RestartMode};
 
     fn generate_node_id() -> NodeId {
         Keypair::generate().public_key()
@@ -915,15 +5571,44 @@ mod tests {
                     container_port: 80,
                     host_port: Some(8080),
                     protocol: "tcp".to_string(),
+                    host_ip: None,
                 }],
                 resource_requests: ResourceRequestsRequest {
                     cpu_cores: 0.5,
                     memory_mb: 512,
                     disk_mb: 1024,
+                    pids_limit: Some(256),
+                    hugepages: vec![HugepageLimit {
+                        page_size: "2MB".to_string(),
+                        limit_bytes: 1 << 30,
+                    }],
+                    ..Default::default()
                 },
+                readiness_probe: None,
+                termination_grace_period_seconds: Some(45),
+                pre_stop: Some(LifecycleHandler::Exec {
+                    command: vec!["nginx".to_string(), "-s".to_string(), "quit".to_string()],
+                }),
+                sysctls: HashMap::new(),
+                rlimits: vec![Rlimit::unlimited("memlock")],
+                shm_size_mb: Some(256),
+                hostname: None,
+                host_aliases: vec![],
+                dns: Default::default(),
+                annotations: Default::default(),
             }],
             replicas: 3,
             labels: HashMap::new(),
+            init_containers: vec![],
+            sidecars: vec![],
+            node_selector: HashMap::from([("disk".to_string(), "ssd".to_string())]),
+            node_affinity: None,
+            instance_anti_affinity: Some(InstanceAntiAffinity::required()),
+            scheduling_strategy: Some("bin-pack".to_string()),
+            priority: 0,
+            restart_policy: RestartPolicy::on_failure().with_fatal_exit_codes([78]),
+            namespace: None,
+            resource_version: None,
         };
 
         let workload: WorkloadDefinition = request.into();
@@ -931,6 +5616,23 @@ mod tests {
         assert_eq!(workload.replicas, 3);
         assert_eq!(workload.containers.len(), 1);
         assert_eq!(workload.containers[0].image, "nginx:latest");
+        assert_eq!(
+            workload.containers[0].termination_grace(),
+            Some(std::time::Duration::from_secs(45))
+        );
+        assert!(workload.containers[0].pre_stop.is_some());
+        assert_eq!(workload.containers[0].limits.pids, Some(256));
+        assert_eq!(workload.containers[0].limits.hugepages[0].page_size, "2MB");
+        assert_eq!(workload.containers[0].rlimits[0].name, "memlock");
+        assert_eq!(workload.containers[0].shm_size_mb, Some(256));
+        assert_eq!(workload.placement.node_selector["disk"], "ssd");
+        assert!(workload.placement.instance_anti_affinity.unwrap().required);
+        assert_eq!(
+            workload.placement.scheduling_strategy.as_deref(),
+            Some("bin-pack")
+        );
+        assert_eq!(workload.restart_policy.mode, RestartMode::OnFailure);
+        assert!(!workload.restart_policy.should_restart(Some(78)));
     }
 
     #[test]
@@ -951,6 +5653,8 @@ mod tests {
                 memory_mb: 7372,
                 disk_mb: 92160,
             },
+            unschedulable: false,
+            resource_version: 0,
         };
 
         let response: NodeResponse = node.clone().into();
@@ -967,10 +5671,73 @@ mod tests {
             node_id: generate_node_id(),
             container_ids: vec!["container-1".to_string()],
             status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
         };
 
         let response: InstanceResponse = instance.clone().into();
         assert_eq!(response.id, instance.id);
         assert_eq!(response.status, "Running");
     }
+
+    #[test]
+    fn test_dual_stack_instance_response() {
+        let instance = WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: Uuid::new_v4(),
+            node_id: generate_node_id(),
+            container_ids: vec![],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec!["10.244.0.5".parse().unwrap(), "fd00:10:244::5".parse().unwrap()],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+
+        let response: InstanceResponse = instance.into();
+        assert_eq!(response.ip_addresses, vec!["10.244.0.5", "fd00:10:244::5"]);
+    }
+
+    #[test]
+    fn test_ipv6_host_port_binding() {
+        let request: PortMappingRequest = serde_json::from_value(serde_json::json!({
+            "container_port": 80,
+            "host_port": 8080,
+            "host_ip": "::"
+        }))
+        .unwrap();
+
+        let mapping: PortMapping = request.into();
+        assert_eq!(mapping.host_ip, Some("::".parse().unwrap()));
+        assert_eq!(mapping.protocol, "tcp");
+
+        let response: PortMappingResponse = mapping.into();
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["host_ip"], "::");
+    }
+
+    #[test]
+    fn test_log_cursor_sends_each_line_once() {
+        let mut cursor = LogCursor::default();
+        let logs = "2024-01-15T10:30:00+00:00 stdout a\n2024-01-15T10:30:01+00:00 stdout b";
+        assert_eq!(cursor.advance(logs).len(), 2);
+        assert!(cursor.advance(logs).is_empty());
+
+        // A read since the cursor repeats its line, and lines can share a
+        // timestamp
+        let logs = "2024-01-15T10:30:01+00:00 stdout b\n\
+                    2024-01-15T10:30:01+00:00 stdout c\n\
+                    2024-01-15T10:30:02+00:00 stdout d";
+        assert_eq!(
+            cursor.advance(logs),
+            vec![
+                "2024-01-15T10:30:01+00:00 stdout c",
+                "2024-01-15T10:30:02+00:00 stdout d"
+            ]
+        );
+        assert_eq!(cursor.timestamp, "2024-01-15T10:30:02+00:00");
+        assert_eq!(cursor.sent, 1);
+    }
 }
@@ -0,0 +1,1178 @@
+//! Import of Kubernetes manifests.
+//!
+//! `POST /api/v1/import/kubernetes` maps the common Kubernetes kinds onto this
+//! orchestrator's resources, so teams migrating can reuse their manifests:
+//!
+//! - `Namespace` becomes a namespace, created when missing.
+//! - `Deployment`, `ReplicaSet`, `StatefulSet` and `Pod` become workloads. The
+//!   pod template's labels join the workload's, since services select
+//!   workloads by label.
+//! - `Service` is registered with the service proxy. Named target ports are
+//!   resolved against the container ports of the pods it selects.
+//! - `ConfigMap` and `Secret` have no counterpart; the values of environment
+//!   variables referencing them are inlined into the workloads.
+//! - `List` objects are expanded.
+//!
+//! Anything else, such as volumes or liveness probes, is left out and listed
+//! in the response's `warnings`. With `?dryRun=true` nothing is created, and
+//! the response's workload create requests can be sent to
+//! `POST /api/v1/workloads` or kept as manifests for `orch apply`.
+//!
+//! Services only live in the proxy's memory and have to be imported again
+//! after the control plane restarts.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use orchestrator_shared_types::{
+    IpFamilyPolicy, LifecycleHandler, Namespace, Probe, ProbeAction, RestartPolicy,
+    WorkloadDefinition, DEFAULT_NAMESPACE,
+};
+
+use crate::network::service::DEFAULT_AFFINITY_TIMEOUT_SECONDS;
+use crate::network::{Service, ServicePort, SessionAffinity};
+use crate::resources;
+
+use super::error::{ApiError, ApiResult};
+use super::handlers::{
+    self, ContainerConfigRequest, CreateWorkloadRequest, DryRunQuery, PortMappingRequest,
+    ResourceRequestsRequest, WorkloadResponse,
+};
+use super::state::ApiState;
+
+/// Pod spec fields that do not change how the pod runs here.
+const IGNORED_POD_FIELDS: &[&str] = &[
+    "dnsPolicy",
+    "schedulerName",
+    "enableServiceLinks",
+    "automountServiceAccountToken",
+];
+
+/// Container fields that do not change how the container runs here.
+const IGNORED_CONTAINER_FIELDS: &[&str] = &[
+    "imagePullPolicy",
+    "terminationMessagePath",
+    "terminationMessagePolicy",
+    "resizePolicy",
+];
+
+/// Kubernetes objects to import.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct KubernetesImportRequest {
+    /// Kubernetes objects as JSON, e.g. the documents of a YAML manifest.
+    #[schema(value_type = Vec<Object>)]
+    pub objects: Vec<Value>,
+    /// Namespace of objects whose metadata names none (default: "default").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+/// What Kubernetes objects were imported as.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct KubernetesImportResponse {
+    /// Namespaces the objects define.
+    pub namespaces: Vec<String>,
+    /// Create requests of the workloads, in manifest order.
+    pub workloads: Vec<CreateWorkloadRequest>,
+    /// The workloads as stored; empty on a dry run.
+    pub created: Vec<WorkloadResponse>,
+    pub services: Vec<ImportedServiceResponse>,
+    /// What was not imported and why, one line each.
+    pub warnings: Vec<String>,
+}
+
+/// A Kubernetes service as mapped onto the service proxy.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportedServiceResponse {
+    /// Set once the service is registered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub namespace: String,
+    pub selector: HashMap<String, String>,
+    #[schema(value_type = Vec<Object>)]
+    pub ports: Vec<ServicePort>,
+    pub session_affinity: SessionAffinity,
+    /// Virtual IPs, once the service is registered.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cluster_ips: Vec<String>,
+}
+
+impl ImportedServiceResponse {
+    fn new(service: Service, registered: bool) -> Self {
+        Self {
+            id: registered.then_some(service.id),
+            name: service.name,
+            namespace: service.namespace,
+            selector: service.selector,
+            ports: service.ports,
+            session_affinity: service.session_affinity,
+            cluster_ips: service
+                .cluster_ips
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+/// Import Kubernetes Namespaces, Deployments, ReplicaSets, StatefulSets,
+/// Pods, Services, ConfigMaps and Secrets.
+#[utoipa::path(
+    post,
+    path = "/api/v1/import/kubernetes",
+    tag = "import",
+    params(DryRunQuery),
+    request_body = KubernetesImportRequest,
+    responses(
+        (
+            status = 200,
+            description = "Objects converted but not created (dry run)",
+            body = KubernetesImportResponse
+        ),
+        (status = 201, description = "Objects imported", body = KubernetesImportResponse),
+        (status = 400, description = "Invalid object", body = ApiError),
+    )
+)]
+pub async fn import_kubernetes(
+    State(state): State<ApiState>,
+    Query(query): Query<DryRunQuery>,
+    Json(request): Json<KubernetesImportRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let default_namespace = request.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE);
+    handlers::validate_namespace_name(default_namespace)?;
+    let Converted {
+        namespaces,
+        workloads,
+        services,
+        mut warnings,
+    } = convert(request.objects, default_namespace)?;
+
+    for namespace in &namespaces {
+        handlers::validate_namespace_name(namespace)?;
+    }
+    for workload in &workloads {
+        let definition: WorkloadDefinition = workload.clone().into();
+        handlers::validate_workload(&definition)?;
+        if workload.replicas == 0 {
+            return Err(ApiError::validation_error(format!(
+                "Workload {}: replicas must be at least 1",
+                workload.name
+            )));
+        }
+    }
+
+    if query.dry_run {
+        for workload in &workloads {
+            let mut definition: WorkloadDefinition = workload.clone().into();
+            handlers::admit_workload(&state, &mut definition).await?;
+        }
+        let services = services
+            .into_iter()
+            .map(|service| ImportedServiceResponse::new(service, false))
+            .collect();
+        let response = KubernetesImportResponse {
+            namespaces,
+            workloads,
+            created: vec![],
+            services,
+            warnings,
+        };
+        return Ok((StatusCode::OK, Json(response)));
+    }
+
+    for name in &namespaces {
+        if handlers::lookup_namespace(&state, name).await?.is_none() {
+            state
+                .state_store
+                .put_namespace(Namespace::new(name.clone()))
+                .await
+                .map_err(ApiError::from)?;
+        }
+    }
+    let mut created = Vec::new();
+    for workload in &workloads {
+        let workload = handlers::submit_workload(&state, workload.clone()).await?;
+        created.push(WorkloadResponse::from(workload));
+    }
+    let services = register_services(&state, services, &mut warnings).await?;
+
+    let response = KubernetesImportResponse {
+        namespaces,
+        workloads,
+        created,
+        services,
+        warnings,
+    };
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Register `services` with the proxy, leaving existing ones unchanged.
+async fn register_services(
+    state: &ApiState,
+    services: Vec<Service>,
+    warnings: &mut Vec<String>,
+) -> ApiResult<Vec<ImportedServiceResponse>> {
+    let Some(proxy) = &state.service_proxy else {
+        if !services.is_empty() {
+            warnings.push("No service proxy runs here; services were not imported".to_string());
+        }
+        return Ok(services
+            .into_iter()
+            .map(|service| ImportedServiceResponse::new(service, false))
+            .collect());
+    };
+
+    let existing: Vec<Service> = resources::list(state.state_store.as_ref())
+        .await
+        .map_err(ApiError::from)?;
+    let mut responses = Vec::new();
+    for service in services {
+        if existing
+            .iter()
+            .any(|s| s.name == service.name && s.namespace == service.namespace)
+        {
+            warnings.push(format!(
+                "Service {}/{}: already exists, left unchanged",
+                service.namespace, service.name
+            ));
+            responses.push(ImportedServiceResponse::new(service, false));
+            continue;
+        }
+        let name = service.name.clone();
+        let service = proxy.add_service(service).await.map_err(|e| {
+            ApiError::internal_error(format!("Failed to register service {}: {}", name, e))
+        })?;
+        responses.push(ImportedServiceResponse::new(service, true));
+    }
+    Ok(responses)
+}
+
+/// Kubernetes objects mapped onto this orchestrator's resources.
+#[derive(Debug, Default)]
+struct Converted {
+    namespaces: Vec<String>,
+    workloads: Vec<CreateWorkloadRequest>,
+    services: Vec<Service>,
+    warnings: Vec<String>,
+}
+
+/// Kind, namespace and name of an object.
+#[derive(Debug, Clone)]
+struct Meta {
+    kind: String,
+    namespace: String,
+    name: String,
+}
+
+impl Meta {
+    fn of(object: &Value, default_namespace: &str) -> ApiResult<Self> {
+        let kind = object["kind"]
+            .as_str()
+            .ok_or_else(|| ApiError::validation_error("Object without a kind"))?;
+        let name = object["metadata"]["name"]
+            .as_str()
+            .ok_or_else(|| ApiError::validation_error(format!("{} without metadata.name", kind)))?;
+        let namespace = object["metadata"]["namespace"]
+            .as_str()
+            .unwrap_or(default_namespace);
+        Ok(Self {
+            kind: kind.to_string(),
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Meta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.kind == "Namespace" {
+            write!(f, "Namespace {}", self.name)
+        } else {
+            write!(f, "{} {}/{}", self.kind, self.namespace, self.name)
+        }
+    }
+}
+
+/// A named container port, for resolving named service target ports.
+#[derive(Debug)]
+struct NamedPort {
+    namespace: String,
+    labels: HashMap<String, String>,
+    name: String,
+    port: u16,
+}
+
+/// Data of the ConfigMaps and Secrets of the manifests, by kind, namespace
+/// and name.
+#[derive(Debug, Default)]
+struct References(HashMap<(String, String, String), BTreeMap<String, String>>);
+
+impl References {
+    fn insert(&mut self, meta: &Meta, object: &Value, warnings: &mut Vec<String>) {
+        let mut data = BTreeMap::new();
+        for (key, value) in object["data"].as_object().into_iter().flatten() {
+            let value = if meta.kind == "Secret" {
+                let decoded = value
+                    .as_str()
+                    .and_then(|v| BASE64_STANDARD.decode(v).ok())
+                    .and_then(|bytes| String::from_utf8(bytes).ok());
+                match decoded {
+                    Some(value) => value,
+                    None => {
+                        warnings.push(format!("{}: data.{} is not base64 UTF-8 text", meta, key));
+                        continue;
+                    }
+                }
+            } else {
+                value.as_str().unwrap_or_default().to_string()
+            };
+            data.insert(key.clone(), value);
+        }
+        // Plain-text Secret values, which win over `data`
+        for (key, value) in object["stringData"].as_object().into_iter().flatten() {
+            data.insert(key.clone(), value.as_str().unwrap_or_default().to_string());
+        }
+        if object.get("binaryData").is_some() {
+            warnings.push(format!("{}: binaryData is not supported", meta));
+        }
+        let key = (meta.kind.clone(), meta.namespace.clone(), meta.name.clone());
+        self.0.insert(key, data);
+    }
+
+    fn get(&self, kind: &str, namespace: &str, name: &str) -> Option<&BTreeMap<String, String>> {
+        self.0
+            .get(&(kind.to_string(), namespace.to_string(), name.to_string()))
+    }
+}
+
+/// Map `objects` onto workloads, namespaces and services, placing objects
+/// without a namespace in `default_namespace`.
+fn convert(objects: Vec<Value>, default_namespace: &str) -> ApiResult<Converted> {
+    let mut flattened = Vec::new();
+    for object in objects {
+        match object["kind"].as_str() {
+            Some("List") => {
+                flattened.extend(object["items"].as_array().cloned().unwrap_or_default())
+            }
+            _ => flattened.push(object),
+        }
+    }
+    let objects = flattened
+        .into_iter()
+        .map(|object| Ok((Meta::of(&object, default_namespace)?, object)))
+        .collect::<ApiResult<Vec<_>>>()?;
+
+    let mut converted = Converted::default();
+    let warnings = &mut converted.warnings;
+
+    // Workloads may reference ConfigMaps and Secrets defined after them
+    let mut references = References::default();
+    for (meta, object) in &objects {
+        if meta.kind == "ConfigMap" || meta.kind == "Secret" {
+            references.insert(meta, object, warnings);
+        }
+    }
+
+    let mut named_ports = Vec::new();
+    let mut service_objects = Vec::new();
+    for (meta, object) in &objects {
+        let template = &object["spec"]["template"];
+        match meta.kind.as_str() {
+            "Namespace" => converted.namespaces.push(meta.name.clone()),
+            "Deployment" | "ReplicaSet" | "StatefulSet" => {
+                if meta.kind == "StatefulSet" {
+                    warnings.push(format!(
+                        "{}: imported as a workload; instances get no stable names or volume \
+                         claims",
+                        meta
+                    ));
+                }
+                let replicas = object["spec"]["replicas"].as_u64().unwrap_or(1) as u32;
+                let context = WorkloadContext {
+                    meta,
+                    path: "spec.template.spec",
+                    references: &references,
+                };
+                let labels = labels(&object["metadata"], template);
+                converted.workloads.push(context.workload(
+                    &template["spec"],
+                    labels,
+                    replicas,
+                    &mut named_ports,
+                    warnings,
+                )?);
+            }
+            "Pod" => {
+                let context = WorkloadContext {
+                    meta,
+                    path: "spec",
+                    references: &references,
+                };
+                let labels = labels(&object["metadata"], object);
+                converted.workloads.push(context.workload(
+                    &object["spec"],
+                    labels,
+                    1,
+                    &mut named_ports,
+                    warnings,
+                )?);
+            }
+            "Service" => service_objects.push((meta, object)),
+            "ConfigMap" | "Secret" => {}
+            kind => warnings.push(format!("{}: kind {} is not supported, skipped", meta, kind)),
+        }
+    }
+
+    for (meta, object) in service_objects {
+        if let Some(service) = service(meta, &object["spec"], &named_ports, warnings)? {
+            converted.services.push(service);
+        }
+    }
+
+    let mut seen = BTreeSet::new();
+    converted
+        .namespaces
+        .retain(|name| seen.insert(name.clone()));
+    Ok(converted)
+}
+
+/// Labels of an object's `metadata` with those of its pod `template`.
+fn labels(metadata: &Value, template: &Value) -> HashMap<String, String> {
+    let mut labels = string_map(&metadata["labels"]);
+    labels.extend(string_map(&template["metadata"]["labels"]));
+    labels
+}
+
+/// What converting the pod spec of one object needs.
+struct WorkloadContext<'a> {
+    meta: &'a Meta,
+    /// Path of the pod spec in the object, for warnings.
+    path: &'a str,
+    references: &'a References,
+}
+
+impl WorkloadContext<'_> {
+    /// The workload running pod `spec`.
+    fn workload(
+        &self,
+        spec: &Value,
+        labels: HashMap<String, String>,
+        replicas: u32,
+        named_ports: &mut Vec<NamedPort>,
+        warnings: &mut Vec<String>,
+    ) -> ApiResult<CreateWorkloadRequest> {
+        let meta = self.meta;
+        let mut containers = Vec::new();
+        let mut init_containers = Vec::new();
+        let mut node_selector = HashMap::new();
+        let mut restart_policy = RestartPolicy::default();
+        let mut termination_grace_period_seconds = None;
+        for (key, value) in spec.as_object().into_iter().flatten() {
+            match key.as_str() {
+                "containers" | "initContainers" => {
+                    for container in value.as_array().into_iter().flatten() {
+                        let (container, ports) = self.container(key, container, warnings)?;
+                        named_ports.extend(ports.into_iter().map(|(name, port)| NamedPort {
+                            namespace: meta.namespace.clone(),
+                            labels: labels.clone(),
+                            name,
+                            port,
+                        }));
+                        if key == "containers" {
+                            containers.push(container);
+                        } else {
+                            init_containers.push(container);
+                        }
+                    }
+                }
+                "nodeSelector" => node_selector = string_map(value),
+                "restartPolicy" => {
+                    restart_policy = match value.as_str() {
+                        Some("OnFailure") => RestartPolicy::on_failure(),
+                        Some("Never") => RestartPolicy::never(),
+                        _ => RestartPolicy::default(),
+                    }
+                }
+                "terminationGracePeriodSeconds" => {
+                    termination_grace_period_seconds = value.as_u64()
+                }
+                key if IGNORED_POD_FIELDS.contains(&key) => {}
+                key => warnings.push(format!("{}: {}.{} is not supported", meta, self.path, key)),
+            }
+        }
+
+        for container in containers.iter_mut().chain(&mut init_containers) {
+            container.termination_grace_period_seconds = termination_grace_period_seconds;
+        }
+
+        Ok(CreateWorkloadRequest {
+            name: meta.name.clone(),
+            containers,
+            init_containers,
+            sidecars: vec![],
+            replicas,
+            labels,
+            node_selector,
+            node_affinity: None,
+            instance_anti_affinity: None,
+            scheduling_strategy: None,
+            priority: 0,
+            restart_policy,
+            namespace: Some(meta.namespace.clone()),
+            resource_version: None,
+        })
+    }
+
+    /// A container of the pod spec's `list`, along with its named ports.
+    fn container(
+        &self,
+        list: &str,
+        container: &Value,
+        warnings: &mut Vec<String>,
+    ) -> ApiResult<(ContainerConfigRequest, Vec<(String, u16)>)> {
+        let meta = self.meta;
+        let name = container["name"].as_str().unwrap_or_default();
+        let path = format!("{}.{}[{}]", self.path, list, name);
+        let invalid = |field: &str| {
+            ApiError::validation_error(format!("{}: invalid {}.{}", meta, path, field))
+        };
+        let image = container["image"]
+            .as_str()
+            .ok_or_else(|| invalid("image"))?;
+
+        let mut ports = Vec::new();
+        let mut named_ports = Vec::new();
+        for port in container["ports"].as_array().into_iter().flatten() {
+            let container_port =
+                port_number(&port["containerPort"]).ok_or_else(|| invalid("ports"))?;
+            if let Some(name) = port["name"].as_str() {
+                named_ports.push((name.to_string(), container_port));
+            }
+            ports.push(PortMappingRequest {
+                container_port,
+                host_port: port_number(&port["hostPort"]),
+                protocol: port["protocol"].as_str().unwrap_or("TCP").to_lowercase(),
+                host_ip: match port["hostIP"].as_str() {
+                    Some(ip) => Some(ip.parse().map_err(|_| invalid("ports"))?),
+                    None => None,
+                },
+            });
+        }
+
+        let mut resource_requests = ResourceRequestsRequest::default();
+        let mut readiness_probe = None;
+        let mut pre_stop = None;
+        for (key, value) in container.as_object().into_iter().flatten() {
+            match key.as_str() {
+                "name" | "image" | "command" | "args" | "env" | "envFrom" | "ports" => {}
+                "resources" => {
+                    resource_requests = resources(value, &path, warnings, meta)
+                        .ok_or_else(|| invalid("resources"))?;
+                }
+                "readinessProbe" => {
+                    readiness_probe = probe(value, &named_ports, &path, meta, warnings)
+                        .map_err(|()| invalid("readinessProbe"))?;
+                }
+                "lifecycle" => {
+                    pre_stop = lifecycle(value, &named_ports, &path, meta, warnings)
+                        .map_err(|()| invalid("lifecycle"))?;
+                }
+                key if IGNORED_CONTAINER_FIELDS.contains(&key) => {}
+                key => warnings.push(format!("{}: {}.{} is not supported", meta, path, key)),
+            }
+        }
+
+        let container = ContainerConfigRequest {
+            name: name.to_string(),
+            image: image.to_string(),
+            command: strings(&container["command"]),
+            args: strings(&container["args"]),
+            env_vars: self.env_vars(container, &path, warnings),
+            ports,
+            resource_requests,
+            readiness_probe,
+            termination_grace_period_seconds: None,
+            pre_stop,
+            sysctls: HashMap::new(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
+        };
+        Ok((container, named_ports))
+    }
+
+    /// Environment of a container, with the values of ConfigMap and Secret
+    /// references inlined.
+    fn env_vars(
+        &self,
+        container: &Value,
+        path: &str,
+        warnings: &mut Vec<String>,
+    ) -> HashMap<String, String> {
+        let meta = self.meta;
+        let mut env = HashMap::new();
+        let mut secrets = BTreeSet::new();
+
+        for source in container["envFrom"].as_array().into_iter().flatten() {
+            let prefix = source["prefix"].as_str().unwrap_or_default();
+            let Some((kind, reference)) = referenced(source, "configMapRef", "secretRef") else {
+                warnings.push(format!(
+                    "{}: {}.envFrom source is not supported",
+                    meta, path
+                ));
+                continue;
+            };
+            let name = reference["name"].as_str().unwrap_or_default();
+            match self.references.get(kind, &meta.namespace, name) {
+                Some(data) => {
+                    for (key, value) in data {
+                        env.insert(format!("{}{}", prefix, key), value.clone());
+                    }
+                    if kind == "Secret" {
+                        secrets.insert(name.to_string());
+                    }
+                }
+                None if reference["optional"] == true => {}
+                None => warnings.push(format!(
+                    "{}: {}.envFrom: {} {} is not in the manifests; its variables are left out",
+                    meta, path, kind, name
+                )),
+            }
+        }
+
+        for var in container["env"].as_array().into_iter().flatten() {
+            let Some(name) = var["name"].as_str() else {
+                continue;
+            };
+            let Some(from) = var.get("valueFrom") else {
+                let value = scalar(&var["value"]).unwrap_or_default();
+                env.insert(name.to_string(), value);
+                continue;
+            };
+            let Some((kind, reference)) = referenced(from, "configMapKeyRef", "secretKeyRef")
+            else {
+                warnings.push(format!(
+                    "{}: {}.env[{}]: only configMapKeyRef and secretKeyRef are supported; left out",
+                    meta, path, name
+                ));
+                continue;
+            };
+            let source = reference["name"].as_str().unwrap_or_default();
+            let key = reference["key"].as_str().unwrap_or_default();
+            let value = self
+                .references
+                .get(kind, &meta.namespace, source)
+                .and_then(|data| data.get(key));
+            match value {
+                Some(value) => {
+                    env.insert(name.to_string(), value.clone());
+                    if kind == "Secret" {
+                        secrets.insert(source.to_string());
+                    }
+                }
+                None if reference["optional"] == true => {}
+                None => warnings.push(format!(
+                    "{}: {}.env[{}]: key {} of {} {} is not in the manifests; left out",
+                    meta, path, name, key, kind, source
+                )),
+            }
+        }
+
+        for secret in secrets {
+            warnings.push(format!(
+                "{}: values of Secret {} are inlined into the environment of {}",
+                meta, secret, path
+            ));
+        }
+        env
+    }
+}
+
+/// The ConfigMap or Secret reference of an env source, by the field names
+/// the source uses for each.
+fn referenced<'a>(
+    source: &'a Value,
+    config_map: &str,
+    secret: &str,
+) -> Option<(&'static str, &'a Value)> {
+    if let Some(reference) = source.get(config_map) {
+        Some(("ConfigMap", reference))
+    } else {
+        source.get(secret).map(|reference| ("Secret", reference))
+    }
+}
+
+/// Resource requests of a container's `resources`, taken from `requests` and
+/// else `limits`; `None` if a quantity is invalid.
+fn resources(
+    value: &Value,
+    path: &str,
+    warnings: &mut Vec<String>,
+    meta: &Meta,
+) -> Option<ResourceRequestsRequest> {
+    let mut requests = ResourceRequestsRequest::default();
+    for kind in ["limits", "requests"] {
+        for (resource, quantity) in value[kind].as_object().into_iter().flatten() {
+            match resource.as_str() {
+                "cpu" => requests.cpu_cores = cpu_cores(quantity)?,
+                "memory" => requests.memory_mb = megabytes(quantity)?,
+                "ephemeral-storage" => requests.disk_mb = megabytes(quantity)?,
+                resource => warnings.push(format!(
+                    "{}: {}.resources.{}.{} is not supported",
+                    meta, path, kind, resource
+                )),
+            }
+        }
+    }
+    Some(requests)
+}
+
+/// A readiness probe, or `None` if its check cannot be mapped. `Err` if it
+/// names a port the container does not have.
+fn probe(
+    value: &Value,
+    named_ports: &[(String, u16)],
+    path: &str,
+    meta: &Meta,
+    warnings: &mut Vec<String>,
+) -> Result<Option<Probe>, ()> {
+    let port = |port: &Value| container_port(port, named_ports).ok_or(());
+    let action = if let Some(http) = value.get("httpGet") {
+        if http["scheme"] == "HTTPS" {
+            warnings.push(format!(
+                "{}: {}.readinessProbe.httpGet.scheme HTTPS is not supported; probed over HTTP",
+                meta, path
+            ));
+        }
+        ProbeAction::HttpGet {
+            port: port(&http["port"])?,
+            path: http["path"].as_str().unwrap_or("/").to_string(),
+        }
+    } else if let Some(tcp) = value.get("tcpSocket") {
+        ProbeAction::TcpSocket {
+            port: port(&tcp["port"])?,
+        }
+    } else {
+        warnings.push(format!(
+            "{}: {}.readinessProbe: only httpGet and tcpSocket are supported; left out",
+            meta, path
+        ));
+        return Ok(None);
+    };
+
+    let mut probe = Probe::new(action);
+    let fields = [
+        ("initialDelaySeconds", &mut probe.initial_delay_seconds),
+        ("periodSeconds", &mut probe.period_seconds),
+        ("timeoutSeconds", &mut probe.timeout_seconds),
+        ("successThreshold", &mut probe.success_threshold),
+        ("failureThreshold", &mut probe.failure_threshold),
+    ];
+    for (field, target) in fields {
+        if let Some(seconds) = value[field].as_u64() {
+            *target = seconds as u32;
+        }
+    }
+    Ok(Some(probe))
+}
+
+/// The preStop hook of a container's `lifecycle`, if it has one that maps.
+fn lifecycle(
+    value: &Value,
+    named_ports: &[(String, u16)],
+    path: &str,
+    meta: &Meta,
+    warnings: &mut Vec<String>,
+) -> Result<Option<LifecycleHandler>, ()> {
+    if value.get("postStart").is_some() {
+        warnings.push(format!(
+            "{}: {}.lifecycle.postStart is not supported",
+            meta, path
+        ));
+    }
+    let Some(hook) = value.get("preStop") else {
+        return Ok(None);
+    };
+    if let Some(exec) = hook.get("exec") {
+        let command = strings(&exec["command"])
+            .filter(|command| !command.is_empty())
+            .ok_or(())?;
+        Ok(Some(LifecycleHandler::Exec { command }))
+    } else if let Some(http) = hook.get("httpGet") {
+        Ok(Some(LifecycleHandler::HttpGet {
+            port: container_port(&http["port"], named_ports).ok_or(())?,
+            path: http["path"].as_str().unwrap_or("/").to_string(),
+        }))
+    } else {
+        warnings.push(format!(
+            "{}: {}.lifecycle.preStop: only exec and httpGet are supported; left out",
+            meta, path
+        ));
+        Ok(None)
+    }
+}
+
+/// A container port given by number or by the name of one of `named_ports`.
+fn container_port(port: &Value, named_ports: &[(String, u16)]) -> Option<u16> {
+    match port {
+        Value::String(name) => named_ports
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, port)| *port),
+        port => port_number(port),
+    }
+}
+
+/// The service for a Kubernetes Service's `spec`, or `None` if it cannot be
+/// mapped.
+fn service(
+    meta: &Meta,
+    spec: &Value,
+    named_ports: &[NamedPort],
+    warnings: &mut Vec<String>,
+) -> ApiResult<Option<Service>> {
+    let selector = string_map(&spec["selector"]);
+    if selector.is_empty() {
+        warnings.push(format!(
+            "{}: services without a selector are not supported, skipped",
+            meta
+        ));
+        return Ok(None);
+    }
+    let selects = |labels: &HashMap<String, String>| {
+        selector
+            .iter()
+            .all(|(key, value)| labels.get(key) == Some(value))
+    };
+
+    let mut service =
+        Service::new(meta.name.clone(), selector.clone()).with_namespace(meta.namespace.clone());
+    for port in spec["ports"].as_array().into_iter().flatten() {
+        let number = port_number(&port["port"]).ok_or_else(|| {
+            ApiError::validation_error(format!("{}: invalid spec.ports.port", meta))
+        })?;
+        let target_port = match &port["targetPort"] {
+            Value::Null => Some(number),
+            Value::String(name) => name.parse().ok().or_else(|| {
+                named_ports
+                    .iter()
+                    .find(|p| {
+                        &p.name == name && p.namespace == meta.namespace && selects(&p.labels)
+                    })
+                    .map(|p| p.port)
+            }),
+            target => port_number(target),
+        };
+        let Some(target_port) = target_port else {
+            warnings.push(format!(
+                "{}: target port {} of port {} is not a container port of the manifests; \
+                 port left out",
+                meta, port["targetPort"], number
+            ));
+            continue;
+        };
+        if port.get("nodePort").is_some() {
+            warnings.push(format!(
+                "{}: nodePort of port {} is not supported",
+                meta, number
+            ));
+        }
+        service.ports.push(ServicePort {
+            name: port["name"].as_str().map(String::from),
+            port: number,
+            target_port,
+            protocol: port["protocol"].as_str().unwrap_or("TCP").to_lowercase(),
+        });
+    }
+
+    for (key, value) in spec.as_object().into_iter().flatten() {
+        match key.as_str() {
+            "selector" | "ports" | "sessionAffinityConfig" | "ipFamilies" | "clusterIPs" => {}
+            "clusterIP" if value != "None" => {}
+            "clusterIP" => warnings.push(format!(
+                "{}: headless services are not supported; imported with a virtual IP",
+                meta
+            )),
+            "type" if value == "ClusterIP" => {}
+            "type" => warnings.push(format!(
+                "{}: type {} is not supported; reachable on its virtual IP only",
+                meta,
+                value.as_str().unwrap_or_default()
+            )),
+            "sessionAffinity" if value == "ClientIP" => {
+                let timeout = spec["sessionAffinityConfig"]["clientIP"]["timeoutSeconds"]
+                    .as_u64()
+                    .unwrap_or(DEFAULT_AFFINITY_TIMEOUT_SECONDS);
+                service = service.with_session_affinity(SessionAffinity::ClientIp {
+                    timeout_seconds: timeout,
+                });
+            }
+            "sessionAffinity" => {}
+            "ipFamilyPolicy" => {
+                let policy = match value.as_str() {
+                    Some("PreferDualStack") => IpFamilyPolicy::PreferDualStack,
+                    Some("RequireDualStack") => IpFamilyPolicy::RequireDualStack,
+                    _ => IpFamilyPolicy::SingleStack,
+                };
+                service = service.with_ip_family_policy(policy);
+            }
+            key => warnings.push(format!("{}: spec.{} is not supported", meta, key)),
+        }
+    }
+    Ok(Some(service))
+}
+
+/// Cores of a CPU quantity such as `500m` or `2`.
+fn cpu_cores(quantity: &Value) -> Option<f32> {
+    let quantity = scalar(quantity)?;
+    match quantity.strip_suffix('m') {
+        Some(millicores) => Some(millicores.parse::<f32>().ok()? / 1000.0),
+        None => quantity.parse().ok(),
+    }
+}
+
+/// Megabytes of a byte quantity such as `128Mi`, `1G` or `1e9`, rounded up.
+fn megabytes(quantity: &Value) -> Option<u64> {
+    const SUFFIXES: [(&str, f64); 10] = [
+        ("Ki", 1024.0),
+        ("Mi", 1_048_576.0),
+        ("Gi", 1_073_741_824.0),
+        ("Ti", 1_099_511_627_776.0),
+        ("Pi", 1_125_899_906_842_624.0),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+        ("P", 1e15),
+    ];
+    let quantity = scalar(quantity)?;
+    let (number, multiplier) = SUFFIXES
+        .iter()
+        .find_map(|(suffix, multiplier)| Some((quantity.strip_suffix(suffix)?, *multiplier)))
+        .unwrap_or((quantity.as_str(), 1.0));
+    let bytes = number.parse::<f64>().ok()? * multiplier;
+    Some((bytes / 1_048_576.0).ceil() as u64)
+}
+
+fn port_number(value: &Value) -> Option<u16> {
+    value.as_u64().and_then(|port| u16::try_from(port).ok())
+}
+
+/// A string, number or boolean as text.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn strings(value: &Value) -> Option<Vec<String>> {
+    let list = value.as_array()?;
+    Some(list.iter().filter_map(scalar).collect())
+}
+
+fn string_map(value: &Value) -> HashMap<String, String> {
+    value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| Some((key.clone(), scalar(value)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn manifests() -> Vec<Value> {
+        vec![
+            json!({"apiVersion": "v1", "kind": "Namespace", "metadata": {"name": "shop"}}),
+            json!({
+                "apiVersion": "apps/v1",
+                "kind": "Deployment",
+                "metadata": {"name": "web", "namespace": "shop", "labels": {"team": "a"}},
+                "spec": {
+                    "replicas": 3,
+                    "selector": {"matchLabels": {"app": "web"}},
+                    "template": {
+                        "metadata": {"labels": {"app": "web"}},
+                        "spec": {
+                            "nodeSelector": {"disk": "ssd"},
+                            "terminationGracePeriodSeconds": 45,
+                            "volumes": [{"name": "cache", "emptyDir": {}}],
+                            "containers": [{
+                                "name": "web",
+                                "image": "nginx:1.27",
+                                "imagePullPolicy": "IfNotPresent",
+                                "args": ["--port", "8080"],
+                                "ports": [{"name": "http", "containerPort": 8080}],
+                                "env": [
+                                    {"name": "MODE", "value": "production"},
+                                    {"name": "PASSWORD", "valueFrom": {
+                                        "secretKeyRef": {"name": "db", "key": "password"}}},
+                                    {"name": "POD", "valueFrom": {
+                                        "fieldRef": {"fieldPath": "metadata.name"}}}
+                                ],
+                                "envFrom": [{"configMapRef": {"name": "web"}, "prefix": "WEB_"}],
+                                "resources": {
+                                    "requests": {"cpu": "250m", "memory": "64Mi"},
+                                    "limits": {"cpu": "1", "memory": "1Gi"}
+                                },
+                                "readinessProbe": {
+                                    "httpGet": {"path": "/healthz", "port": "http"},
+                                    "periodSeconds": 5
+                                },
+                                "lifecycle": {
+                                    "preStop": {"httpGet": {"path": "/drain", "port": "http"}}
+                                },
+                                "volumeMounts": [{"name": "cache", "mountPath": "/cache"}]
+                            }]
+                        }
+                    }
+                }
+            }),
+            json!({
+                "kind": "Service",
+                "metadata": {"name": "web", "namespace": "shop"},
+                "spec": {
+                    "type": "NodePort",
+                    "selector": {"app": "web"},
+                    "sessionAffinity": "ClientIP",
+                    "ports": [{"port": 80, "targetPort": "http", "nodePort": 30080}]
+                }
+            }),
+            json!({
+                "kind": "List",
+                "items": [
+                    {"kind": "ConfigMap", "metadata": {"name": "web", "namespace": "shop"},
+                     "data": {"LEVEL": "info"}},
+                    {"kind": "Secret", "metadata": {"name": "db", "namespace": "shop"},
+                     "data": {"password": BASE64_STANDARD.encode("hunter2")}}
+                ]
+            }),
+            json!({"kind": "Ingress", "metadata": {"name": "web", "namespace": "shop"}}),
+        ]
+    }
+
+    #[test]
+    fn test_converts_deployment() {
+        let converted = convert(manifests(), DEFAULT_NAMESPACE).unwrap();
+        assert_eq!(converted.namespaces, ["shop"]);
+
+        let workload = &converted.workloads[0];
+        assert_eq!(workload.name, "web");
+        assert_eq!(workload.namespace.as_deref(), Some("shop"));
+        assert_eq!(workload.replicas, 3);
+        assert_eq!(workload.labels["team"], "a");
+        assert_eq!(workload.labels["app"], "web");
+        assert_eq!(workload.node_selector["disk"], "ssd");
+
+        let container = &workload.containers[0];
+        assert_eq!(container.args.as_deref().unwrap(), ["--port", "8080"]);
+        assert_eq!(container.env_vars["MODE"], "production");
+        assert_eq!(container.env_vars["PASSWORD"], "hunter2");
+        assert_eq!(container.env_vars["WEB_LEVEL"], "info");
+        assert!(!container.env_vars.contains_key("POD"));
+        assert_eq!(container.resource_requests.cpu_cores, 0.25);
+        assert_eq!(container.resource_requests.memory_mb, 64);
+        let probe = container.readiness_probe.as_ref().unwrap();
+        assert_eq!(
+            probe.action,
+            ProbeAction::HttpGet {
+                port: 8080,
+                path: "/healthz".to_string()
+            }
+        );
+        assert_eq!(probe.period_seconds, 5);
+        assert_eq!(container.termination_grace_period_seconds, Some(45));
+        assert_eq!(
+            container.pre_stop,
+            Some(LifecycleHandler::HttpGet {
+                port: 8080,
+                path: "/drain".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_converts_service_and_reports_the_rest() {
+        let converted = convert(manifests(), DEFAULT_NAMESPACE).unwrap();
+        let service = &converted.services[0];
+        assert_eq!(service.namespace, "shop");
+        assert_eq!(service.selector["app"], "web");
+        assert_eq!(service.ports[0].port, 80);
+        assert_eq!(service.ports[0].target_port, 8080);
+        assert_eq!(service.session_affinity, SessionAffinity::client_ip());
+
+        let warnings = converted.warnings.join("\n");
+        for expected in [
+            "Deployment shop/web: spec.template.spec.volumes is not supported",
+            "spec.template.spec.containers[web].volumeMounts is not supported",
+            "env[POD]: only configMapKeyRef and secretKeyRef are supported",
+            "values of Secret db are inlined",
+            "Service shop/web: type NodePort is not supported",
+            "nodePort of port 80",
+            "kind Ingress is not supported",
+        ] {
+            assert!(
+                warnings.contains(expected),
+                "missing {:?} in\n{}",
+                expected,
+                warnings
+            );
+        }
+        assert!(!warnings.contains("imagePullPolicy"));
+        assert_eq!(converted.warnings.len(), 7, "{}", warnings);
+    }
+
+    #[test]
+    fn test_pod_and_defaults() {
+        let pod = json!({
+            "kind": "Pod",
+            "metadata": {"name": "job"},
+            "spec": {
+                "restartPolicy": "OnFailure",
+                "containers": [{"name": "job", "image": "busybox", "command": ["true"]}]
+            }
+        });
+        let converted = convert(vec![pod], "batch").unwrap();
+        let workload = &converted.workloads[0];
+        assert_eq!(workload.namespace.as_deref(), Some("batch"));
+        assert_eq!(workload.replicas, 1);
+        assert_eq!(workload.restart_policy, RestartPolicy::on_failure());
+        assert!(converted.warnings.is_empty());
+
+        let nameless = json!({"kind": "Pod", "metadata": {}});
+        assert!(convert(vec![nameless], "batch").is_err());
+    }
+
+    #[test]
+    fn test_quantities() {
+        assert_eq!(cpu_cores(&json!("500m")), Some(0.5));
+        assert_eq!(cpu_cores(&json!(2)), Some(2.0));
+        assert_eq!(cpu_cores(&json!("lots")), None);
+        assert_eq!(megabytes(&json!("128Mi")), Some(128));
+        assert_eq!(megabytes(&json!("1Gi")), Some(1024));
+        assert_eq!(megabytes(&json!("1G")), Some(954));
+        assert_eq!(megabytes(&json!(1_048_577)), Some(2));
+    }
+}
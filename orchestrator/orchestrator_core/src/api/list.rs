@@ -0,0 +1,346 @@
+//! Paging, sorting and label selection for list endpoints.
+//!
+//! List endpoints accept:
+//!
+//! - `limit`: at most this many items (up to [`MAX_LIMIT`]); every match when
+//!   absent.
+//! - `continue`: the token from the previous page's `continue`, to read the
+//!   next one with the same query.
+//! - `sort`: a field to order by, e.g. `name`, or `-name` for descending; ID
+//!   order when absent. Pages in ID order are read from the store a page at a
+//!   time, while other orders read every match.
+//! - `labelSelector`: labels to match, e.g. `app=web,env!=prod`; see
+//!   [`LabelSelector`].
+
+use base64::prelude::*;
+use serde::Deserialize;
+use std::future::Future;
+use utoipa::IntoParams;
+
+use orchestrator_shared_types::{Node, Result, WorkloadDefinition, WorkloadInstance};
+use state_store_interface::{LabelSelector, Page, PageRequest};
+
+use super::error::{ApiError, ApiResult};
+
+/// Largest `limit` accepted.
+pub const MAX_LIMIT: usize = 1000;
+
+/// Query parameters of list endpoints.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    /// Most items to return; every match when absent.
+    pub limit: Option<usize>,
+    /// `continue` token from the previous page.
+    #[serde(rename = "continue")]
+    pub continue_token: Option<String>,
+    /// Field to sort by, prefixed with `-` for descending order.
+    pub sort: Option<String>,
+    /// Labels to match, e.g. `app=web,env!=prod`.
+    #[serde(rename = "labelSelector")]
+    pub label_selector: Option<String>,
+}
+
+/// Objects a listing can be sorted by field.
+pub trait Sortable {
+    /// Fields `sort` accepts besides `id`.
+    const SORT_FIELDS: &'static [&'static str];
+
+    fn id(&self) -> String;
+
+    /// Key ordering objects by `field`, one of `SORT_FIELDS`.
+    fn sort_key(&self, field: &str) -> String;
+}
+
+// Pad numbers so their keys order like the numbers
+fn number_key(n: u64) -> String {
+    format!("{:020}", n)
+}
+
+impl Sortable for WorkloadDefinition {
+    const SORT_FIELDS: &'static [&'static str] =
+        &["name", "namespace", "replicas", "resource_version"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn sort_key(&self, field: &str) -> String {
+        match field {
+            "name" => self.name.clone(),
+            "namespace" => self.namespace.clone(),
+            "replicas" => number_key(self.replicas.into()),
+            _ => number_key(self.resource_version),
+        }
+    }
+}
+
+impl Sortable for Node {
+    const SORT_FIELDS: &'static [&'static str] = &["address", "status", "resource_version"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn sort_key(&self, field: &str) -> String {
+        match field {
+            "address" => self.address.clone(),
+            "status" => format!("{:?}", self.status),
+            _ => number_key(self.resource_version),
+        }
+    }
+}
+
+impl Sortable for WorkloadInstance {
+    const SORT_FIELDS: &'static [&'static str] = &[
+        "workload_id",
+        "node_id",
+        "namespace",
+        "status",
+        "resource_version",
+    ];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn sort_key(&self, field: &str) -> String {
+        match field {
+            "workload_id" => self.workload_id.to_string(),
+            "node_id" => self.node_id.to_string(),
+            "namespace" => self.namespace.clone(),
+            "status" => format!("{:?}", self.status),
+            _ => number_key(self.resource_version),
+        }
+    }
+}
+
+/// A requested sort order other than by ID.
+struct Sort {
+    field: String,
+    descending: bool,
+}
+
+impl ListQuery {
+    /// The label selector, empty when absent.
+    pub fn selector(&self) -> ApiResult<LabelSelector> {
+        self.label_selector
+            .as_deref()
+            .unwrap_or("")
+            .parse()
+            .map_err(|e| ApiError::bad_request(format!("Invalid labelSelector: {}", e)))
+    }
+
+    fn limit(&self) -> ApiResult<usize> {
+        match self.limit {
+            None => Ok(usize::MAX),
+            Some(limit) if (1..=MAX_LIMIT).contains(&limit) => Ok(limit),
+            Some(_) => Err(ApiError::bad_request(format!(
+                "limit must be between 1 and {}",
+                MAX_LIMIT
+            ))),
+        }
+    }
+
+    fn sort<T: Sortable>(&self) -> ApiResult<Option<Sort>> {
+        let Some(sort) = self.sort.as_deref().filter(|s| !s.is_empty()) else {
+            return Ok(None);
+        };
+        let (field, descending) = match sort.strip_prefix('-') {
+            Some(field) => (field, true),
+            None => (sort, false),
+        };
+        if field == "id" && !descending {
+            return Ok(None);
+        }
+        if field != "id" && !T::SORT_FIELDS.contains(&field) {
+            return Err(ApiError::bad_request(format!(
+                "Cannot sort by '{}'; expected id, {}",
+                field,
+                T::SORT_FIELDS.join(", ")
+            )));
+        }
+        Ok(Some(Sort {
+            field: field.to_string(),
+            descending,
+        }))
+    }
+
+    fn after(&self) -> ApiResult<Option<String>> {
+        let Some(token) = &self.continue_token else {
+            return Ok(None);
+        };
+        BASE64_URL_SAFE_NO_PAD
+            .decode(token)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .map(Some)
+            .ok_or_else(|| ApiError::bad_request("Invalid continue token"))
+    }
+
+    /// Read the page this query asks for, in the optional `namespace`, using
+    /// `fetch` to list from the store. Returns the items and the token for
+    /// the next page, if any.
+    pub async fn fetch<T, F, Fut>(
+        &self,
+        namespace: Option<&str>,
+        fetch: F,
+    ) -> ApiResult<(Vec<T>, Option<String>)>
+    where
+        T: Sortable,
+        F: FnOnce(PageRequest) -> Fut,
+        Fut: Future<Output = Result<Page<T>>>,
+    {
+        let limit = self.limit()?;
+        let sort = self.sort::<T>()?;
+        let after = self.after()?;
+        let mut request = PageRequest::first(limit).with_selector(self.selector()?);
+        if let Some(namespace) = namespace {
+            request = request.with_namespace(namespace);
+        }
+
+        let Some(sort) = sort else {
+            request.after = after;
+            let page = fetch(request).await.map_err(ApiError::from)?;
+            return Ok((page.items, page.next.map(encode_token)));
+        };
+
+        // Other orders can only be paged once every match is sorted
+        request.limit = usize::MAX;
+        let mut keyed: Vec<(String, T)> = fetch(request)
+            .await
+            .map_err(ApiError::from)?
+            .items
+            .into_iter()
+            .map(|item| {
+                let key = match sort.field.as_str() {
+                    "id" => item.id(),
+                    field => format!("{}\n{}", item.sort_key(field), item.id()),
+                };
+                (key, item)
+            })
+            .collect();
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+        if sort.descending {
+            keyed.reverse();
+        }
+        if let Some(after) = after {
+            keyed.retain(|(key, _)| {
+                if sort.descending {
+                    key.as_str() < after.as_str()
+                } else {
+                    key.as_str() > after.as_str()
+                }
+            });
+        }
+        let page = Page::from_ordered(keyed, limit);
+        Ok((page.items, page.next.map(encode_token)))
+    }
+}
+
+fn encode_token(after: String) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use orchestrator_shared_types::OrchestrationError;
+    use std::collections::HashMap;
+
+    fn workload(name: &str, replicas: u32, app: &str) -> WorkloadDefinition {
+        WorkloadDefinition {
+            replicas,
+            labels: HashMap::from([("app".to_string(), app.to_string())]),
+            resource_version: 1,
+            ..test_support::workload(name)
+        }
+    }
+
+    fn query(limit: Option<usize>, sort: Option<&str>, token: Option<String>) -> ListQuery {
+        ListQuery {
+            limit,
+            continue_token: token,
+            sort: sort.map(str::to_string),
+            label_selector: Some("app!=db".to_string()),
+        }
+    }
+
+    async fn fetch_all(
+        workloads: &[WorkloadDefinition],
+        query: &ListQuery,
+    ) -> ApiResult<(Vec<WorkloadDefinition>, Option<String>)> {
+        query
+            .fetch(None, |request| async move {
+                let items: Vec<WorkloadDefinition> = workloads
+                    .iter()
+                    .filter(|w| request.selects_workload(w))
+                    .cloned()
+                    .collect();
+                Ok::<_, OrchestrationError>(Page::of(items, &request, |w| w.id.to_string()))
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_sorted_pages() {
+        let workloads = vec![
+            workload("c", 1, "web"),
+            workload("a", 3, "web"),
+            workload("b", 2, "web"),
+            workload("d", 9, "db"),
+        ];
+        let (page, token) = fetch_all(&workloads, &query(Some(2), Some("-replicas"), None))
+            .await
+            .unwrap();
+        let names: Vec<&str> = page.iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+
+        let (page, token) = fetch_all(&workloads, &query(Some(2), Some("-replicas"), token))
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].name, "c");
+        assert!(token.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_id_pages() {
+        let workloads: Vec<_> = (0..5).map(|i| workload(&i.to_string(), 1, "web")).collect();
+        let mut token = None;
+        let mut seen = Vec::new();
+        loop {
+            let (page, next) = fetch_all(&workloads, &query(Some(2), None, token))
+                .await
+                .unwrap();
+            seen.extend(page.into_iter().map(|w| w.id.to_string()));
+            token = next;
+            if token.is_none() {
+                break;
+            }
+        }
+        let mut sorted = seen.clone();
+        sorted.sort();
+        assert_eq!(seen, sorted);
+        assert_eq!(seen.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_queries() {
+        let workloads = vec![workload("a", 1, "web")];
+        for bad in [
+            query(Some(0), None, None),
+            query(Some(MAX_LIMIT + 1), None, None),
+            query(None, Some("image"), None),
+            query(None, None, Some("not base64!".to_string())),
+            ListQuery {
+                label_selector: Some("tier in (web".to_string()),
+                ..Default::default()
+            },
+        ] {
+            let err = fetch_all(&workloads, &bad).await.unwrap_err();
+            assert_eq!(err.code, "BAD_REQUEST");
+        }
+    }
+}
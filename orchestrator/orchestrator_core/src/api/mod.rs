@@ -9,15 +9,157 @@
 //! - `GET /api/v1/workloads` - List all workloads
 //! - `GET /api/v1/workloads/:id` - Get a specific workload
 //! - `PUT /api/v1/workloads/:id` - Update a workload
+//! - `PATCH /api/v1/workloads/:id` - Change some fields of a workload; see [`patch`]
 //! - `DELETE /api/v1/workloads/:id` - Delete a workload
+//! - `GET /api/v1/workloads/:id/scale` - Desired, current and running replicas
+//! - `POST /api/v1/workloads/:id/scale` - Set the replica count alone
+//! - `GET /api/v1/workloads/:id/revisions` - Templates the workload has run,
+//!   oldest first; see [`rollout`](crate::rollout)
+//! - `GET /api/v1/workloads/:id/rollout/status` - Current revision and replica
+//!   progress
+//! - `POST /api/v1/workloads/:id/rollout/undo?to=N` - Roll the template back to
+//!   revision `N`, or the previous one
 //! - `GET /api/v1/workloads/:id/instances` - List instances for a workload
+//! - `GET /api/v1/instances` - List instances of all workloads
+//! - `POST /api/v1/instances/:id/restart` - Replace an instance with a fresh one
+//! - `POST /api/v1/instances/:id/migrate` - Move a running instance to the node
+//!   `node_id` through container checkpoints, without restarting it
+//! - `PATCH /api/v1/instances/:id/resources` - Change the CPU, memory and PID
+//!   limits of one of the instance's containers in place
+//! - `DELETE /api/v1/instances/:id` - Stop and remove an instance; its workload
+//!   gets a replacement while it wants the replica
+//! - `GET /api/v1/instances/:id/exec?cmd=...&container=...&tty=true` - Run a
+//!   command in one of the instance's containers over a WebSocket; see [`exec`]
+//! - `GET /api/v1/instances/:id/debug?image=busybox&tty=true` - Start a
+//!   throwaway container sharing the namespaces of one of the instance's
+//!   containers and attach to it like exec; see [`exec`]
+//! - `GET|PUT /api/v1/instances/:id/files?path=...&container=...` - Copy files
+//!   out of or into a container as a tar archive; see [`files`]
+//! - `GET /api/v1/workloads/:id/logs/search?q=...&regex=...&since=...` - Search
+//!   the logs of all the workload's containers, including archived ones;
+//!   streams matching lines as NDJSON, ordered by timestamp
+//! - `POST /api/v1/workloads/:id/tunnels` - Open a temporary public tunnel to a
+//!   workload (edge nodes only)
+//!
+//! Creates and updates are checked against the configured
+//! [`AdmissionLimits`](crate::admission::AdmissionLimits). Rejections carry the
+//! code `QUOTA_EXCEEDED` (403), `TOO_MANY_CONTAINERS` (400) or
+//! `SPEC_TOO_LARGE` (413), with the limit in `details`, or the policy rule
+//! broken: `MISSING_LABEL`, `IMAGE_NOT_ALLOWED` or `RESOURCES_REQUIRED` (403).
+//! With a maximum image severity, each image is scanned before admission
+//! (pulling it if it was not scanned before) and one with vulnerabilities
+//! above the maximum is rejected as `VULNERABLE_IMAGE` (403).
+//! Configured [`webhook`]s are consulted too, mutating ones before the
+//! built-in checks and validating ones after.
+//!
+//! Every stored object carries a `resource_version` that changes with each
+//! write. Updates must send back the version they were based on and fail with
+//! `CONFLICT` (409) if the workload has changed since; read it again and retry.
+//!
+//! Workload creates and `PUT` updates accept `?dryRun=true` to be validated and
+//! admitted without being stored; the response is the workload as it would be
+//! stored, so clients can diff it against the live one.
+//!
+//! The list endpoints for workloads, workload instances and nodes accept
+//! `?watch=true` to stream changes as Server-Sent Events instead; see
+//! [`watch`].
+//!
+//! The workload, node and instance lists accept `limit`, `continue`, `sort`
+//! and `labelSelector` to page through large clusters; see [`list`]. Instances
+//! are selected by their workload's labels.
+//!
+//! Workloads belong to a namespace, `default` unless the create request names
+//! another. The workload list, get and instance endpoints and service endpoints
+//! accept `?namespace=...` and treat objects outside it as not found.
+//!
+//! ## Namespaces
+//! - `POST /api/v1/namespaces` - Create a namespace
+//! - `GET /api/v1/namespaces` - List namespaces, including `default`
+//! - `GET /api/v1/namespaces/:name` - Get a namespace
+//! - `DELETE /api/v1/namespaces/:name` - Delete a namespace with its workloads,
+//!   their instances and its services
+//!
+//! ## Tunnels
+//! - `GET /api/v1/tunnels` - List open tunnels
+//! - `DELETE /api/v1/tunnels/:id` - Close a tunnel before it expires
 //!
 //! ## Nodes
 //! - `GET /api/v1/nodes` - List all nodes
+//! - `POST /api/v1/nodes/register` - Register a node, or update it; sent by its
+//!   agent at startup with a bootstrap token
 //! - `GET /api/v1/nodes/:id` - Get a specific node
+//! - `PATCH /api/v1/nodes/:id` - Change a node's labels or cordon it
+//! - `DELETE /api/v1/nodes/:id` - Deregister a node
+//! - `POST /api/v1/nodes/:id/cordon` - Stop scheduling new instances onto a node
+//! - `POST /api/v1/nodes/:id/uncordon` - Allow scheduling onto a node again
+//! - `POST /api/v1/nodes/:id/drain` - Cordon a node and evict its instances within
+//!   disruption budgets; repeat until `complete`
+//!
+//! ## Disruption Budgets
+//! - `POST /api/v1/disruption-budgets` - Create a disruption budget
+//! - `GET /api/v1/disruption-budgets` - List disruption budgets with their status
+//! - `GET /api/v1/disruption-budgets/:id` - Get a specific disruption budget
+//! - `DELETE /api/v1/disruption-budgets/:id` - Delete a disruption budget
+//!
+//! ## Network Policies
+//! - `POST /api/v1/network-policies` - Restrict the traffic of workloads; see
+//!   [`policy`](crate::network::policy)
+//! - `GET /api/v1/network-policies` - List network policies
+//! - `GET /api/v1/network-policies/:id` - Get a specific network policy
+//! - `DELETE /api/v1/network-policies/:id` - Delete a network policy
+//!
+//! ## Images
+//! - `POST /api/v1/images/prepull` - Pull an image onto the nodes matching a
+//!   selector ahead of a rollout
+//! - `GET /api/v1/images/prepull` - List pre-pulls with their progress
+//! - `GET /api/v1/images/prepull/:id` - Get the progress of a pre-pull on each node
+//! - `GET /api/v1/images/:image/inspect` - Resolve an image reference to its digest,
+//!   platform, layers and config; `/` in the reference is percent-encoded
+//! - `GET /api/v1/images/:image/scan` - Scan an image for known vulnerabilities,
+//!   pulling it first; a digest scanned before returns the recorded scan
+//! - `GET /api/v1/images/scans` - List recorded scans with their vulnerability counts
+//!
+//! ## Import
+//! - `POST /api/v1/import/kubernetes` - Create namespaces, workloads and
+//!   services from Kubernetes objects; `?dryRun=true` only converts them. See
+//!   [`kubernetes`]
+//!
+//! ## Events
+//! - `GET /api/v1/events?involvedObject=...` - What happened to a workload,
+//!   instance or node, e.g. why an instance is pending; see
+//!   [`events`](crate::events)
+//!
+//! ## Reports
+//! - `GET /api/v1/reports/usage?from=...&to=...` - CPU core-hours, GB-hours and
+//!   GPU-hours workloads held, per namespace and workload; see
+//!   [`accounting`](crate::accounting)
 //!
 //! ## Cluster
 //! - `GET /api/v1/cluster/status` - Get cluster status summary
+//! - `GET /api/v1/cluster/leader` - Get the control-plane leader
+//! - `GET /api/v1/cluster/backup` - Snapshot nodes, workloads and instances
+//! - `POST /api/v1/cluster/backup/restore` - Restore a snapshot into an empty control plane
+//!
+//! ## Admin
+//! - `GET /api/v1/admin/fsck` - Cross-check stored state against the runtime and cluster
+//! - `POST /api/v1/admin/fsck` - Run the same check and repair what it finds
+//!
+//! ## Federation
+//! - `POST /api/v1/federation/clusters` - Register a remote cluster's endpoint and token
+//! - `GET /api/v1/federation/clusters` - List registered clusters
+//! - `DELETE /api/v1/federation/clusters/:name` - Forget a cluster
+//! - `POST /api/v1/federation/clusters/:name/workloads` - Create a workload on a cluster
+//! - `GET /api/v1/federation/workloads` - Workloads of every cluster, this one included
+//! - `GET /api/v1/federation/nodes` - Nodes of every cluster, this one included
+//! - `* /api/v1/federation/clusters/:name/proxy/api/...` - Any other API request,
+//!   sent to the cluster; see [`federation`]
+//!
+//! # Versions
+//!
+//! `/api/v1` is the stable API. The deprecated `/api/v1beta1` group serves
+//! workloads (create, list, get, update, delete) in their older shape; see
+//! [`v1beta1`]. Its responses carry `Deprecation: true` and `Warning` headers
+//! naming the deprecated fields a request used.
 //!
 //! # Authentication
 //!
@@ -28,6 +170,57 @@
 //!
 //! See [`auth`] module for signing details.
 //!
+//! Node agents instead send `Authorization: Bearer <token>` with a bootstrap
+//! token, which only registers, patches and deregisters nodes.
+//!
+//! Alternatively send `Authorization: Bearer <JWT>` with a token issued by the
+//! control plane or, with the `oidc` feature, by an OpenID Connect provider;
+//! see [`token`].
+//!
+//! - `POST /api/v1/auth/token` - Exchange a signed request for a bearer token
+//!
+//! With the `mtls` feature the API can be served over mutual TLS, where a
+//! client certificate from the cluster CA authenticates every request on its
+//! connection; see [`tls`].
+//!
+//! - `POST /api/v1/auth/certificate` - Sign a client certificate for the caller
+//!
+//! # Authorization
+//!
+//! With an [`RbacPolicy`] configured, each key may only do what its role
+//! bindings grant: `viewer` reads, `operator` also changes workloads, and
+//! `admin` also manages namespaces, nodes and backups, either cluster-wide or
+//! in one namespace. Everything else is denied with `FORBIDDEN` (403). See
+//! [`rbac`].
+//!
+//! - `GET /api/v1/auth/whoami` - The calling key and its role bindings
+//!
+//! # Audit
+//!
+//! With an [`AuditLog`] set on the state, every authenticated call that is not
+//! a read is recorded with its caller, target, before and after, and result,
+//! including calls RBAC denies. Records go to file, syslog or webhook sinks;
+//! see [`audit`].
+//!
+//! - `GET /api/v1/audit` - Audit records, newest first (cluster `admin` only),
+//!   filtered by `subject`, `kind`, `name`, `method`, `outcome`, `since` and
+//!   `until`, at most `limit` (default 100)
+//!
+//! # Rate Limiting
+//!
+//! With a [`RateLimiter`] set on the state, each client, identified by the
+//! subject it authenticated as or else its IP address, may send a burst of
+//! requests and then a steady rate. Requests beyond that fail with
+//! `RATE_LIMITED` (429) and a `Retry-After` header; see [`rate_limit`].
+//!
+//! # OpenAPI
+//!
+//! An OpenAPI 3 document describing every endpoint is generated from the
+//! handlers; see [`openapi`]. It needs no credentials.
+//!
+//! - `GET /api/v1/openapi.json` - The OpenAPI document
+//! - `GET /swagger-ui` - Swagger UI for the document (`swagger-ui` feature)
+//!
 //! # Example
 //!
 //! ```no_run
@@ -57,13 +250,39 @@
 //! # }
 //! ```
 
+pub mod audit;
 pub mod auth;
 pub mod error;
+pub mod exec;
+pub mod federation;
+pub mod files;
 pub mod handlers;
+pub mod kubernetes;
+pub mod list;
+pub mod openapi;
+pub mod patch;
+pub mod rate_limit;
+pub mod rbac;
 pub mod routes;
 pub mod state;
+#[cfg(feature = "mtls")]
+pub mod tls;
+pub mod token;
+pub mod v1beta1;
+pub mod watch;
+pub mod webhook;
 
+pub use audit::{AuditLog, AuditQuery, AuditRecord, AuditSink, AuditSinkConfig};
 pub use auth::{AuthConfig, AuthInfo, SignedRequestHeaders, sign_request};
 pub use error::{ApiError, ApiResult};
+pub use federation::{Federation, RemoteCluster};
+pub use rate_limit::{RateLimitConfig, RateLimiter};
+pub use rbac::{RbacPolicy, Role, RoleBinding};
+pub use token::{ClaimRoleMapping, LocalTokenIssuer, OidcConfig};
+#[cfg(feature = "oidc")]
+pub use token::OidcVerifier;
 pub use routes::{ApiServer, ApiServerConfig, build_router};
+#[cfg(feature = "mtls")]
+pub use tls::{ClientCertificate, ClusterCa, TlsServer};
 pub use state::ApiState;
+pub use webhook::{AdmissionWebhook, AdmissionWebhooks, FailurePolicy};
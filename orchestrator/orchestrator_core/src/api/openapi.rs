@@ -0,0 +1,259 @@
+//! OpenAPI 3 document for the REST API.
+//!
+//! The document is generated from the `#[utoipa::path]` attributes on
+//! [`handlers`](super::handlers) and the schemas of their request and response
+//! types. It is served unauthenticated at `/api/v1/openapi.json` so clients
+//! can generate SDKs from a running cluster, and with the `swagger-ui` feature
+//! browsed at `/swagger-ui`.
+//!
+//! `tests/snapshots/openapi_operations.txt` lists every operation; CI fails
+//! when it drifts from the handlers. Regenerate it with
+//! `UPDATE_SNAPSHOTS=1 cargo test --features rest-api,mtls openapi`.
+
+use axum::Json;
+use utoipa::openapi::security::{
+    ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme,
+};
+use utoipa::{Modify, OpenApi};
+
+use super::federation;
+use super::files;
+use super::handlers;
+use super::kubernetes;
+
+/// Path the document is served at.
+pub const OPENAPI_PATH: &str = "/api/v1/openapi.json";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "RustOrchestration API",
+        description = "Manage workloads, namespaces and nodes of a RustOrchestration cluster."
+    ),
+    paths(
+        handlers::create_workload,
+        handlers::list_workloads,
+        handlers::get_workload,
+        handlers::update_workload,
+        handlers::patch_workload,
+        handlers::delete_workload,
+        handlers::get_workload_scale,
+        handlers::scale_workload,
+        handlers::list_workload_revisions,
+        handlers::get_rollout_status,
+        handlers::undo_rollout,
+        handlers::list_workload_instances,
+        handlers::list_instances,
+        handlers::restart_instance,
+        handlers::migrate_instance,
+        handlers::update_instance_resources,
+        handlers::delete_instance,
+        handlers::exec_instance,
+        handlers::debug_instance,
+        files::download_files,
+        files::upload_files,
+        handlers::get_workload_logs,
+        handlers::get_instance_logs,
+        handlers::search_workload_logs,
+        handlers::stream_workload_logs,
+        handlers::open_tunnel,
+        handlers::create_namespace,
+        handlers::list_namespaces,
+        handlers::get_namespace,
+        handlers::delete_namespace,
+        handlers::list_nodes,
+        handlers::register_node,
+        handlers::get_node,
+        handlers::patch_node,
+        handlers::deregister_node,
+        handlers::cordon_node,
+        handlers::uncordon_node,
+        handlers::drain_node,
+        handlers::create_disruption_budget,
+        handlers::list_disruption_budgets,
+        handlers::get_disruption_budget,
+        handlers::delete_disruption_budget,
+        handlers::create_network_policy,
+        handlers::list_network_policies,
+        handlers::get_network_policy,
+        handlers::delete_network_policy,
+        handlers::create_cron_job,
+        handlers::list_cron_jobs,
+        handlers::get_cron_job,
+        handlers::delete_cron_job,
+        handlers::suspend_cron_job,
+        handlers::resume_cron_job,
+        handlers::create_daemon_set,
+        handlers::list_daemon_sets,
+        handlers::get_daemon_set,
+        handlers::delete_daemon_set,
+        handlers::create_stateful_set,
+        handlers::list_stateful_sets,
+        handlers::get_stateful_set,
+        handlers::update_stateful_set,
+        handlers::scale_stateful_set,
+        handlers::delete_stateful_set,
+        handlers::prepull_image,
+        handlers::list_prepulls,
+        handlers::get_prepull,
+        handlers::inspect_image,
+        handlers::scan_image,
+        handlers::list_image_scans,
+        handlers::create_service,
+        handlers::list_services,
+        handlers::get_service,
+        handlers::update_service,
+        handlers::delete_service,
+        handlers::get_service_endpoints,
+        handlers::list_tunnels,
+        handlers::close_tunnel,
+        handlers::get_cluster_status,
+        handlers::get_cluster_leader,
+        handlers::get_backup,
+        handlers::restore_backup,
+        handlers::check_consistency,
+        handlers::repair_consistency,
+        handlers::whoami,
+        handlers::issue_token,
+        kubernetes::import_kubernetes,
+        handlers::list_events,
+        handlers::get_usage_report,
+        handlers::list_audit,
+        federation::register_cluster,
+        federation::list_clusters,
+        federation::remove_cluster,
+        federation::dispatch_workload,
+        federation::list_federated_workloads,
+        federation::list_federated_nodes,
+    ),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "workloads", description = "Workloads and their instances"),
+        (name = "logs", description = "Container logs of workloads"),
+        (name = "tunnels", description = "Temporary access to workload ports"),
+        (name = "namespaces", description = "Namespaces and their quotas"),
+        (name = "nodes", description = "Cluster nodes and maintenance"),
+        (name = "disruption-budgets", description = "Limits on voluntary disruption"),
+        (name = "network-policies", description = "Traffic allowed between workloads"),
+        (name = "cronjobs", description = "Jobs created on a cron schedule"),
+        (name = "daemonsets", description = "Workloads run once on every matching node"),
+        (name = "statefulsets", description = "Workloads whose instances keep their identity and volumes"),
+        (name = "images", description = "Image inspection, vulnerability scans and pre-pulls"),
+        (name = "services", description = "Virtual IPs in front of workloads and their endpoints"),
+        (name = "cluster", description = "Cluster status and backups"),
+        (name = "admin", description = "State consistency checks"),
+        (name = "import", description = "Import of Kubernetes manifests"),
+        (name = "events", description = "What happened to workloads, instances and nodes"),
+        (name = "reports", description = "Resource usage reports for chargeback"),
+        (name = "auth", description = "Caller identity and credentials"),
+        (name = "audit", description = "Audit log of mutating calls"),
+        (name = "federation", description = "Remote clusters and a view across them"),
+    )
+)]
+struct ApiDoc;
+
+#[cfg(feature = "mtls")]
+#[derive(OpenApi)]
+#[openapi(paths(handlers::issue_certificate))]
+struct CertificateDoc;
+
+/// Declares the signed-request and bearer token schemes, either of which
+/// authorizes every operation.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        // The signature covers the other two headers, see `auth`
+        for header in ["X-Auth-PublicKey", "X-Auth-Timestamp", "X-Auth-Signature"] {
+            components.add_security_scheme(
+                header,
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(header))),
+            );
+        }
+
+        let scopes: [&str; 0] = [];
+        let signed = SecurityRequirement::new("X-Auth-PublicKey", scopes)
+            .add("X-Auth-Timestamp", scopes)
+            .add("X-Auth-Signature", scopes);
+        let bearer = SecurityRequirement::new("bearer", scopes);
+        openapi.security = Some(vec![signed, bearer]);
+    }
+}
+
+/// The OpenAPI document for the operations compiled into this build.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    #[allow(unused_mut)]
+    let mut doc = ApiDoc::openapi();
+    #[cfg(feature = "mtls")]
+    doc.merge(CertificateDoc::openapi());
+    doc
+}
+
+/// One `METHOD path operation_id` line per operation, sorted, as kept in the
+/// snapshot.
+pub fn operations(doc: &utoipa::openapi::OpenApi) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (path, item) in &doc.paths.paths {
+        let operations = [
+            ("GET", &item.get),
+            ("PUT", &item.put),
+            ("POST", &item.post),
+            ("PATCH", &item.patch),
+            ("DELETE", &item.delete),
+        ];
+        for (method, operation) in operations {
+            let Some(operation) = operation else {
+                continue;
+            };
+            let id = operation.operation_id.as_deref().unwrap_or("-");
+            lines.push(format!("{} {} {}", method, path, id));
+        }
+    }
+    lines.sort();
+    lines
+}
+
+/// Serve the document.
+pub async fn serve() -> Json<utoipa::openapi::OpenApi> {
+    Json(openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_operation_documented_once() {
+        let lines = operations(&openapi());
+        assert_eq!(lines.len(), if cfg!(feature = "mtls") { 95 } else { 94 });
+        let mut ids: Vec<&str> = lines.iter().filter_map(|l| l.rsplit(' ').next()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), lines.len());
+        assert!(lines.contains(&"GET /api/v1/workloads/{workload_id} get_workload".to_string()));
+    }
+
+    #[test]
+    fn test_security_schemes() {
+        let doc = openapi();
+        let schemes = &doc.components.as_ref().unwrap().security_schemes;
+        assert!(schemes.contains_key("bearer"));
+        assert!(schemes.contains_key("X-Auth-Signature"));
+        assert_eq!(doc.security.as_ref().unwrap().len(), 2);
+        assert!(doc
+            .components
+            .unwrap()
+            .schemas
+            .contains_key("WorkloadResponse"));
+    }
+}
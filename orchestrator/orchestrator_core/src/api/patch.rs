@@ -0,0 +1,187 @@
+//! JSON Merge Patch (RFC 7396) and JSON Patch (RFC 6902) request bodies.
+//!
+//! `PATCH` endpoints pick the format from `Content-Type`:
+//!
+//! - `application/merge-patch+json`: an object whose fields replace the
+//!   object's, with `null` removing a field, e.g. `{"replicas": 3}`.
+//! - `application/json-patch+json`: a list of operations, e.g.
+//!   `[{"op": "replace", "path": "/containers/0/image", "value": "nginx:1.27"}]`.
+//!   A failed `test` operation is reported as a conflict, so a test on
+//!   `/resource_version` guards against concurrent changes.
+//!
+//! Either is applied to the object as the API returns it.
+
+use axum::http::{header, HeaderMap};
+use json_patch::PatchErrorKind;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use super::error::{ApiError, ApiResult};
+
+pub const MERGE_PATCH: &str = "application/merge-patch+json";
+pub const JSON_PATCH: &str = "application/json-patch+json";
+
+/// A patch in one of the standard formats.
+#[derive(Debug, Clone)]
+pub enum JsonPatchBody {
+    Merge(Value),
+    Patch(json_patch::Patch),
+}
+
+impl JsonPatchBody {
+    /// Parse `body` if the request's content type is a standard patch format.
+    ///
+    /// Returns `None` for `application/json` (or no content type), which
+    /// endpoints may accept in their own shape.
+    pub fn from_request(headers: &HeaderMap, body: &[u8]) -> ApiResult<Option<Self>> {
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| {
+                value
+                    .split(';')
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .to_ascii_lowercase()
+            })
+            .unwrap_or_default();
+
+        let invalid = |e: serde_json::Error| ApiError::bad_request(format!("Invalid patch: {}", e));
+        match content_type.as_str() {
+            MERGE_PATCH => Ok(Some(JsonPatchBody::Merge(
+                serde_json::from_slice(body).map_err(invalid)?,
+            ))),
+            JSON_PATCH => Ok(Some(JsonPatchBody::Patch(
+                serde_json::from_slice(body).map_err(invalid)?,
+            ))),
+            "" | "application/json" => Ok(None),
+            other => Err(ApiError::new(
+                format!(
+                    "Unsupported content type {}; use application/json, {} or {}",
+                    other, MERGE_PATCH, JSON_PATCH
+                ),
+                "UNSUPPORTED_MEDIA_TYPE",
+            )),
+        }
+    }
+
+    /// Apply the patch to `target`'s JSON form and read the result back.
+    pub fn apply<T: Serialize + DeserializeOwned>(&self, target: &T) -> ApiResult<T> {
+        let mut doc = serde_json::to_value(target)
+            .map_err(|e| ApiError::internal_error(format!("Failed to serialize: {}", e)))?;
+        match self {
+            JsonPatchBody::Merge(patch) => json_patch::merge(&mut doc, patch),
+            JsonPatchBody::Patch(patch) => {
+                json_patch::patch(&mut doc, patch).map_err(|e| match e.kind {
+                    PatchErrorKind::TestFailed => ApiError::conflict(format!("Patch {}", e)),
+                    _ => ApiError::new(format!("Patch {}", e), "INVALID_PATCH"),
+                })?
+            }
+        }
+        serde_json::from_value(doc).map_err(|e| {
+            ApiError::new(format!("Patched object is invalid: {}", e), "INVALID_PATCH")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Thing {
+        replicas: u32,
+        labels: HashMap<String, String>,
+        resource_version: u64,
+    }
+
+    fn thing() -> Thing {
+        Thing {
+            replicas: 1,
+            labels: HashMap::from([("tier".to_string(), "web".to_string())]),
+            resource_version: 7,
+        }
+    }
+
+    fn parse(content_type: &str, body: &str) -> ApiResult<Option<JsonPatchBody>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_str(content_type).unwrap(),
+        );
+        JsonPatchBody::from_request(&headers, body.as_bytes())
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        let patch = parse(
+            MERGE_PATCH,
+            r#"{"replicas": 3, "labels": {"tier": null, "env": "prod"}}"#,
+        )
+        .unwrap()
+        .unwrap();
+        let patched = patch.apply(&thing()).unwrap();
+        assert_eq!(patched.replicas, 3);
+        assert_eq!(
+            patched.labels,
+            HashMap::from([("env".to_string(), "prod".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_json_patch() {
+        let body = r#"[
+            {"op": "test", "path": "/resource_version", "value": 7},
+            {"op": "replace", "path": "/replicas", "value": 5},
+            {"op": "add", "path": "/labels/env", "value": "prod"}
+        ]"#;
+        let patched = parse(JSON_PATCH, body)
+            .unwrap()
+            .unwrap()
+            .apply(&thing())
+            .unwrap();
+        assert_eq!(patched.replicas, 5);
+        assert_eq!(patched.labels.len(), 2);
+
+        let stale = r#"[{"op": "test", "path": "/resource_version", "value": 6}]"#;
+        let err = parse(JSON_PATCH, stale)
+            .unwrap()
+            .unwrap()
+            .apply(&thing())
+            .unwrap_err();
+        assert_eq!(err.code, "CONFLICT");
+
+        let missing = r#"[{"op": "remove", "path": "/nope"}]"#;
+        let err = parse(JSON_PATCH, missing)
+            .unwrap()
+            .unwrap()
+            .apply(&thing())
+            .unwrap_err();
+        assert_eq!(err.code, "INVALID_PATCH");
+    }
+
+    #[test]
+    fn test_patched_object_must_still_be_valid() {
+        let patch = parse(MERGE_PATCH, r#"{"replicas": "many"}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(patch.apply(&thing()).unwrap_err().code, "INVALID_PATCH");
+    }
+
+    #[test]
+    fn test_content_types() {
+        assert!(parse("application/json", "{}").unwrap().is_none());
+        assert!(parse("application/merge-patch+json; charset=utf-8", "{}")
+            .unwrap()
+            .is_some());
+        assert!(parse(JSON_PATCH, "{}").is_err());
+        assert_eq!(
+            parse("text/plain", "{}").unwrap_err().code,
+            "UNSUPPORTED_MEDIA_TYPE"
+        );
+    }
+}
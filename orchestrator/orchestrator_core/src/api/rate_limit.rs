@@ -0,0 +1,255 @@
+//! Per-client rate limiting.
+//!
+//! Each client gets a token bucket holding up to `burst` requests that refills
+//! at `requests_per_second`. Clients are told apart by the subject they
+//! authenticated as, so a token or key is limited wherever it connects from,
+//! and otherwise by their IP address. Requests over the limit get
+//! `RATE_LIMITED` (429) with a `Retry-After` header.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::debug;
+
+use super::auth::{AuthInfo, AuthMethod};
+use super::error::ApiError;
+use super::state::ApiState;
+
+/// Buckets tracked before full ones are dropped.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// How fast each client may send requests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitConfig {
+    /// Steady rate a client's bucket refills at.
+    pub requests_per_second: f64,
+    /// Requests a client may send at once after being idle.
+    pub burst: u32,
+}
+
+impl RateLimitConfig {
+    /// A steady rate with a burst of twice that.
+    pub fn per_second(requests_per_second: f64) -> Self {
+        Self {
+            requests_per_second,
+            burst: (requests_per_second * 2.0).ceil().max(1.0) as u32,
+        }
+    }
+
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+/// Who a request is counted against.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClientKey {
+    /// An authenticated key, token or certificate subject.
+    Subject(String),
+    Address(IpAddr),
+    /// Neither authenticated nor connected over TCP, e.g. in tests.
+    Unknown,
+}
+
+impl ClientKey {
+    /// Label for metrics, which would grow without bound keyed by client.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClientKey::Subject(_) => "subject",
+            ClientKey::Address(_) => "address",
+            ClientKey::Unknown => "unknown",
+        }
+    }
+
+    fn of(request: &Request) -> Self {
+        match request.extensions().get::<AuthInfo>() {
+            Some(auth) if auth.method != AuthMethod::None => {
+                ClientKey::Subject(auth.subject.clone())
+            }
+            _ => request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map_or(ClientKey::Unknown, |info| ClientKey::Address(info.0.ip())),
+        }
+    }
+}
+
+/// Receives throttled requests, e.g. to export them as metrics.
+pub trait ThrottleObserver: Send + Sync {
+    fn on_throttled(&self, client: &ClientKey);
+}
+
+#[cfg(feature = "observability")]
+impl ThrottleObserver for observability::OrchestratorMetrics {
+    fn on_throttled(&self, client: &ClientKey) {
+        self.inc_api_throttled(client.kind());
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets for every client seen recently.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<ClientKey, Bucket>>,
+    observer: Option<Arc<dyn ThrottleObserver>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+            observer: None,
+        }
+    }
+
+    /// Report throttled requests to `observer`.
+    pub fn with_observer(mut self, observer: Arc<dyn ThrottleObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    pub fn config(&self) -> RateLimitConfig {
+        self.config
+    }
+
+    /// Take a token for `client`, or return how long until one is available.
+    pub fn check(&self, client: &ClientKey) -> Result<(), Duration> {
+        let result = self.check_at(client, Instant::now());
+        if result.is_err() {
+            if let Some(observer) = &self.observer {
+                observer.on_throttled(client);
+            }
+        }
+        result
+    }
+
+    fn check_at(&self, client: &ClientKey, now: Instant) -> Result<(), Duration> {
+        let burst = self.config.burst as f64;
+        let rate = self.config.requests_per_second;
+        let refilled = |bucket: &Bucket| {
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            (bucket.tokens + elapsed * rate).min(burst)
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= PRUNE_THRESHOLD {
+            // A full bucket is the same as no bucket
+            buckets.retain(|_, bucket| refilled(bucket) < burst);
+        }
+        let bucket = buckets.entry(client.clone()).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        bucket.tokens = refilled(bucket);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+/// Axum layer throttling clients that exceed the state's rate limit.
+///
+/// Must run after the auth layer so authenticated clients are limited by
+/// subject rather than address.
+pub async fn rate_limit_layer(
+    State(state): State<ApiState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(limiter) = &state.rate_limiter else {
+        return next.run(request).await;
+    };
+    let client = ClientKey::of(&request);
+    match limiter.check(&client) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            debug!(?client, retry_after, "Throttled API request");
+            let mut response = ApiError::new("Too many requests", "RATE_LIMITED")
+                .with_details(serde_json::json!({ "retry_after_secs": retry_after }))
+                .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_steady_rate() {
+        let limiter = RateLimiter::new(RateLimitConfig::per_second(2.0).with_burst(3));
+        let client = ClientKey::Subject("ci".to_string());
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at(&client, start).is_ok());
+        }
+        let wait = limiter.check_at(&client, start).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+
+        // Refills at two per second, never beyond the burst
+        let later = start + Duration::from_millis(500);
+        assert!(limiter.check_at(&client, later).is_ok());
+        assert!(limiter.check_at(&client, later).is_err());
+        let idle = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(limiter.check_at(&client, idle).is_ok());
+        }
+        assert!(limiter.check_at(&client, idle).is_err());
+    }
+
+    #[test]
+    fn test_clients_limited_separately() {
+        let limiter = RateLimiter::new(RateLimitConfig::per_second(1.0).with_burst(1));
+        let now = Instant::now();
+        let ci = ClientKey::Subject("ci".to_string());
+        let user = ClientKey::Address("10.0.0.1".parse().unwrap());
+
+        assert!(limiter.check_at(&ci, now).is_ok());
+        assert!(limiter.check_at(&ci, now).is_err());
+        assert!(limiter.check_at(&user, now).is_ok());
+    }
+
+    #[test]
+    fn test_full_buckets_pruned() {
+        let limiter = RateLimiter::new(RateLimitConfig::per_second(1.0).with_burst(1));
+        let start = Instant::now();
+        for i in 0..PRUNE_THRESHOLD {
+            let client = ClientKey::Subject(i.to_string());
+            limiter.check_at(&client, start).unwrap();
+        }
+        let later = start + Duration::from_secs(2);
+        limiter.check_at(&ClientKey::Unknown, later).unwrap();
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_default_burst() {
+        assert_eq!(RateLimitConfig::per_second(10.0).burst, 20);
+        assert_eq!(RateLimitConfig::per_second(0.2).burst, 1);
+    }
+}
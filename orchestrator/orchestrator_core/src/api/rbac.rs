@@ -0,0 +1,584 @@
+//! Role-based access control for the API.
+//!
+//! Callers are identified by the public key that signed the request (see
+//! [`auth`](super::auth)). A [`RoleBinding`] grants a key one [`Role`], either
+//! cluster-wide or in one namespace. Once an [`RbacPolicy`] is configured on
+//! [`AuthConfig`](super::AuthConfig), [`rbac_layer`] denies every request no
+//! binding allows, with `FORBIDDEN` (403).
+//!
+//! Requests about a workload or service are checked against its namespace;
+//! listing workloads is checked against `?namespace=` and needs a cluster-wide
+//! binding without it. Nodes, tunnels, disruption budgets, network policies,
+//! cron jobs, daemon sets, image pre-pulls, backups, imports and the admin
+//! endpoints are cluster-wide, as are the profiling endpoints under
+//! `/debug/pprof`, which need the admin role.
+//! `GET /api/v1/auth/whoami` and `POST /api/v1/auth/token` are open to every
+//! authenticated caller.
+//!
+//! Roles carried by a bearer token (see [`token`](super::token)) are granted
+//! on top of the policy's bindings for the token's subject.
+//!
+//! Bootstrap tokens are not checked against the policy: authentication already
+//! limits them to registering, patching and deregistering nodes.
+//!
+//! The policy only applies while authentication is required; without it there
+//! is no verified identity to bind roles to.
+
+use std::fmt;
+use std::str::FromStr;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use http_body_util::BodyExt;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use orchestrator_shared_types::DEFAULT_NAMESPACE;
+
+use super::auth::{AuthInfo, AuthMethod};
+use super::error::ApiError;
+use super::state::ApiState;
+
+/// A set of permissions, each including the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Read anything in scope.
+    Viewer,
+    /// Also create, change and delete workloads, tunnels and disruption budgets.
+    Operator,
+    /// Also manage namespaces, nodes, network policies and image pre-pulls,
+    /// back up and restore, and run repairs.
+    Admin,
+}
+
+impl Role {
+    /// Whether this role may perform `verb`.
+    pub fn allows(self, verb: Verb) -> bool {
+        match verb {
+            Verb::Read => true,
+            Verb::Write => self >= Role::Operator,
+            Verb::Manage => self == Role::Admin,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "viewer" => Ok(Role::Viewer),
+            "operator" => Ok(Role::Operator),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!(
+                "unknown role '{}', expected admin, operator or viewer",
+                other
+            )),
+        }
+    }
+}
+
+/// What a request does, as far as authorization is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verb {
+    Read,
+    Write,
+    Manage,
+}
+
+/// Grants a public key a role cluster-wide or in one namespace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RoleBinding {
+    /// Base64-encoded Ed25519 public key, as sent in `X-Auth-PublicKey`.
+    pub subject: String,
+    pub role: Role,
+    /// Namespace the role applies in; cluster-wide when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+impl RoleBinding {
+    /// Whether this binding grants `verb` in `namespace`, or cluster-wide
+    /// when `namespace` is `None`.
+    pub fn allows(&self, verb: Verb, namespace: Option<&str>) -> bool {
+        self.role.allows(verb)
+            && match (&self.namespace, namespace) {
+                (None, _) => true,
+                (Some(bound), Some(namespace)) => bound == namespace,
+                (Some(_), None) => false,
+            }
+    }
+}
+
+impl FromStr for RoleBinding {
+    type Err = String;
+
+    /// Parses `KEY:ROLE` or `KEY:ROLE:NAMESPACE`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split(':');
+        let (Some(subject), Some(role)) = (parts.next(), parts.next()) else {
+            return Err(format!("expected KEY:ROLE[:NAMESPACE], got '{}'", s));
+        };
+        let namespace = parts.next().map(|ns| ns.trim().to_string());
+        if subject.trim().is_empty() || parts.next().is_some() || namespace.as_deref() == Some("") {
+            return Err(format!("expected KEY:ROLE[:NAMESPACE], got '{}'", s));
+        }
+        Ok(Self {
+            subject: subject.trim().to_string(),
+            role: role.parse()?,
+            namespace,
+        })
+    }
+}
+
+/// Role bindings checked against every request; anything not granted is denied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RbacPolicy {
+    pub bindings: Vec<RoleBinding>,
+}
+
+impl RbacPolicy {
+    /// Create a policy that denies everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses comma-separated bindings such as
+    /// `KEY1:admin,KEY2:operator:ml,KEY3:viewer:ml`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let bindings = spec
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { bindings })
+    }
+
+    /// Add a role binding.
+    pub fn with_binding(mut self, binding: RoleBinding) -> Self {
+        self.bindings.push(binding);
+        self
+    }
+
+    /// Bindings of one public key.
+    pub fn bindings_for<'a>(&'a self, subject: &'a str) -> impl Iterator<Item = &'a RoleBinding> {
+        self.bindings.iter().filter(move |b| b.subject == subject)
+    }
+
+    /// Whether `subject` may perform `verb` in `namespace`, or cluster-wide
+    /// when `namespace` is `None`.
+    pub fn allows(&self, subject: &str, verb: Verb, namespace: Option<&str>) -> bool {
+        self.bindings_for(subject)
+            .any(|binding| binding.allows(verb, namespace))
+    }
+}
+
+/// What a request is checked against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    /// Open to every authenticated caller.
+    Anyone,
+    Cluster,
+    Namespace(String),
+    /// The namespace of a stored workload.
+    Workload(Uuid),
+    /// The namespace of a stored instance.
+    Instance(Uuid),
+    /// The namespace of a service.
+    Service(Uuid),
+    /// The namespace named in the body of a create request.
+    RequestBody,
+}
+
+/// The verb and target of a request to `path`.
+fn classify(method: &Method, path: &str, query: Option<&str>) -> (Verb, Target) {
+    let read_or = |verb| {
+        if method == Method::GET {
+            Verb::Read
+        } else {
+            verb
+        }
+    };
+    let (v1beta1, rest) = match path.strip_prefix("/api/v1beta1/") {
+        Some(rest) => (true, rest),
+        None => (false, path.strip_prefix("/api/v1/").unwrap_or("")),
+    };
+    let segments: Vec<&str> = rest.split('/').filter(|s| !s.is_empty()).collect();
+
+    match segments.as_slice() {
+        ["auth", "whoami"] | ["auth", "token"] | ["auth", "certificate"] => {
+            (Verb::Read, Target::Anyone)
+        }
+        ["workloads"] | ["instances"] | ["events"] | ["reports", "usage"]
+            if method == Method::GET =>
+        {
+            (Verb::Read, query_namespace(query))
+        }
+        // v1beta1 creates always land in the default namespace
+        ["workloads"] if v1beta1 => (Verb::Write, Target::Namespace(DEFAULT_NAMESPACE.into())),
+        ["workloads"] => (Verb::Write, Target::RequestBody),
+        ["workloads", id, ..] => (read_or(Verb::Write), workload_target(id)),
+        // Exec and debug run commands in the containers, so they are never
+        // reads
+        ["instances", id, "exec" | "debug"] => (Verb::Write, instance_target(id)),
+        // Copying files out can read secrets the workload mounts
+        ["instances", id, "files"] => (Verb::Write, instance_target(id)),
+        // Choosing an instance's node is node maintenance, like a drain
+        ["instances", _, "migrate"] => (Verb::Manage, Target::Cluster),
+        ["instances", id, ..] => (read_or(Verb::Write), instance_target(id)),
+        ["namespaces", name] if method == Method::GET => {
+            (Verb::Read, Target::Namespace(name.to_string()))
+        }
+        ["namespaces", ..] => (read_or(Verb::Manage), Target::Cluster),
+        ["services"] if method == Method::GET => (Verb::Read, query_namespace(query)),
+        ["services"] => (Verb::Manage, Target::RequestBody),
+        ["services", id, ..] => match Uuid::parse_str(id) {
+            Ok(id) => (read_or(Verb::Manage), Target::Service(id)),
+            Err(_) => (read_or(Verb::Manage), Target::Cluster),
+        },
+        ["tunnels", ..]
+        | ["disruption-budgets", ..]
+        | ["cronjobs", ..]
+        | ["daemonsets", ..]
+        | ["statefulsets", ..] => (read_or(Verb::Write), Target::Cluster),
+        ["nodes", ..] | ["network-policies", ..] | ["images", ..] => {
+            (read_or(Verb::Manage), Target::Cluster)
+        }
+        ["cluster", "status"] | ["cluster", "leader"] => (read_or(Verb::Manage), Target::Cluster),
+        // Requests to other clusters carry their tokens, whatever they do
+        ["federation", ..] => (Verb::Manage, Target::Cluster),
+        _ => (Verb::Manage, Target::Cluster),
+    }
+}
+
+fn query_namespace(query: Option<&str>) -> Target {
+    query
+        .into_iter()
+        .flat_map(|q| q.split('&'))
+        .find_map(|pair| pair.strip_prefix("namespace="))
+        .filter(|ns| !ns.is_empty())
+        .map_or(Target::Cluster, |ns| Target::Namespace(ns.to_string()))
+}
+
+fn workload_target(id: &str) -> Target {
+    Uuid::parse_str(id).map_or(Target::Cluster, Target::Workload)
+}
+
+fn instance_target(id: &str) -> Target {
+    Uuid::parse_str(id).map_or(Target::Cluster, Target::Instance)
+}
+
+/// Request body fields read to authorize a workload create.
+#[derive(Deserialize)]
+struct NamespaceField {
+    #[serde(default)]
+    namespace: Option<String>,
+}
+
+/// Axum layer enforcing the [`RbacPolicy`] of the API's auth config.
+///
+/// Must run after the auth layer, which stores the caller's [`AuthInfo`].
+pub async fn rbac_layer(
+    State(state): State<ApiState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let policy = match &state.auth_config.rbac {
+        Some(policy) if state.auth_config.required => policy,
+        _ => return Ok(next.run(request).await),
+    };
+    let auth = request
+        .extensions()
+        .get::<AuthInfo>()
+        .cloned()
+        .ok_or_else(|| ApiError::new("Request is not authenticated", "FORBIDDEN"))?;
+    // Authentication already limits bootstrap tokens to node registration
+    if auth.method == AuthMethod::BootstrapToken {
+        return Ok(next.run(request).await);
+    }
+
+    let (verb, target) = classify(
+        request.method(),
+        request.uri().path(),
+        request.uri().query(),
+    );
+    let (request, namespace) = match target {
+        Target::Anyone => return Ok(next.run(request).await),
+        Target::Cluster => (request, None),
+        Target::Namespace(namespace) => (request, Some(namespace)),
+        // Unknown objects are checked cluster-wide rather than leaking their absence
+        Target::Workload(id) => {
+            let namespace = state
+                .state_store
+                .get_workload(&id)
+                .await?
+                .map(|w| w.namespace);
+            (request, namespace)
+        }
+        Target::Instance(id) => {
+            let namespace = state
+                .state_store
+                .get_instance(&id.to_string())
+                .await?
+                .map(|i| i.namespace);
+            (request, namespace)
+        }
+        Target::Service(id) => {
+            let namespace = state
+                .service_proxy
+                .as_ref()
+                .and_then(|proxy| proxy.get_service(&id))
+                .map(|service| service.namespace);
+            (request, namespace)
+        }
+        Target::RequestBody => {
+            let (parts, body) = request.into_parts();
+            let bytes = body
+                .collect()
+                .await
+                .map_err(|_| ApiError::bad_request("Failed to read request body"))?
+                .to_bytes();
+            let namespace = serde_json::from_slice::<NamespaceField>(&bytes)
+                .ok()
+                .and_then(|field| field.namespace)
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string());
+            (
+                Request::from_parts(parts, Body::from(bytes)),
+                Some(namespace),
+            )
+        }
+    };
+
+    let namespace = namespace.as_deref();
+    let granted = auth.granted.iter().any(|b| b.allows(verb, namespace));
+    if !granted && !policy.allows(&auth.subject, verb, namespace) {
+        let access = match verb {
+            Verb::Read => "Read",
+            Verb::Write => "Write",
+            Verb::Manage => "Admin",
+        };
+        let scope = match &namespace {
+            Some(namespace) => format!("in namespace '{}'", namespace),
+            None => "cluster-wide".to_string(),
+        };
+        return Err(ApiError::new(
+            format!(
+                "{} access {} is not granted to {}",
+                access, scope, auth.subject
+            ),
+            "FORBIDDEN",
+        ));
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_permissions() {
+        assert!(Role::Viewer.allows(Verb::Read));
+        assert!(!Role::Viewer.allows(Verb::Write));
+        assert!(Role::Operator.allows(Verb::Write));
+        assert!(!Role::Operator.allows(Verb::Manage));
+        assert!(Role::Admin.allows(Verb::Manage));
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        let policy = RbacPolicy::parse("a2V5MQ==:admin, a2V5Mg==:Viewer:ml").unwrap();
+        assert_eq!(
+            policy.bindings[1],
+            RoleBinding {
+                subject: "a2V5Mg==".to_string(),
+                role: Role::Viewer,
+                namespace: Some("ml".to_string()),
+            }
+        );
+        assert!(RbacPolicy::parse("key:root").is_err());
+        assert!(RbacPolicy::parse("key").is_err());
+        assert!(RbacPolicy::parse("key:viewer:ml:extra").is_err());
+    }
+
+    #[test]
+    fn test_policy_denies_by_default() {
+        let policy = RbacPolicy::new()
+            .with_binding("admin:admin".parse().unwrap())
+            .with_binding("dev:operator:ml".parse().unwrap());
+
+        assert!(policy.allows("admin", Verb::Manage, None));
+        assert!(policy.allows("admin", Verb::Write, Some("ml")));
+        assert!(policy.allows("dev", Verb::Write, Some("ml")));
+        assert!(!policy.allows("dev", Verb::Read, Some("default")));
+        assert!(!policy.allows("dev", Verb::Read, None));
+        assert!(!policy.allows("dev", Verb::Manage, Some("ml")));
+        assert!(!policy.allows("stranger", Verb::Read, Some("ml")));
+    }
+
+    #[test]
+    fn test_classify_requests() {
+        let id = Uuid::new_v4();
+        let workload = format!("/api/v1/workloads/{}", id);
+
+        assert_eq!(
+            classify(
+                &Method::GET,
+                "/api/v1/workloads",
+                Some("watch=true&namespace=ml")
+            ),
+            (Verb::Read, Target::Namespace("ml".to_string()))
+        );
+        assert_eq!(
+            classify(&Method::GET, "/api/v1/workloads", None),
+            (Verb::Read, Target::Cluster)
+        );
+        assert_eq!(
+            classify(
+                &Method::GET,
+                "/api/v1/instances",
+                Some("namespace=ml&limit=10")
+            ),
+            (Verb::Read, Target::Namespace("ml".to_string()))
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/workloads", None),
+            (Verb::Write, Target::RequestBody)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/services", None),
+            (Verb::Manage, Target::RequestBody)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/api/v1/services", Some("namespace=ml")),
+            (Verb::Read, Target::Namespace("ml".to_string()))
+        );
+        assert_eq!(
+            classify(
+                &Method::GET,
+                "/api/v1/federation/clusters/prod-eu/proxy/api/v1/workloads",
+                Some("namespace=ml")
+            ),
+            (Verb::Manage, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1beta1/workloads", None),
+            (Verb::Write, Target::Namespace("default".to_string()))
+        );
+        assert_eq!(
+            classify(&Method::DELETE, &workload, None),
+            (Verb::Write, Target::Workload(id))
+        );
+        assert_eq!(
+            classify(&Method::DELETE, "/api/v1/namespaces/ml", None),
+            (Verb::Manage, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/nodes/abc/drain", None),
+            (Verb::Manage, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/network-policies", None),
+            (Verb::Manage, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/api/v1/network-policies", None),
+            (Verb::Read, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/cronjobs", None),
+            (Verb::Write, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::DELETE, "/api/v1/daemonsets/abc", None),
+            (Verb::Write, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/statefulsets/abc/scale", None),
+            (Verb::Write, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/images/prepull", None),
+            (Verb::Manage, Target::Cluster)
+        );
+        assert_eq!(
+            classify(
+                &Method::GET,
+                "/api/v1/images/ghcr.io%2Fowner%2Fapp:v1/inspect",
+                None
+            ),
+            (Verb::Read, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/api/v1/cluster/backup", None),
+            (Verb::Manage, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/api/v1/audit", Some("subject=alice")),
+            (Verb::Manage, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/debug/pprof/profile", Some("seconds=30")),
+            (Verb::Manage, Target::Cluster)
+        );
+        let instance = format!("/api/v1/instances/{}/restart", id);
+        assert_eq!(
+            classify(&Method::POST, &instance, None),
+            (Verb::Write, Target::Instance(id))
+        );
+        let exec = format!("/api/v1/instances/{}/exec", id);
+        assert_eq!(
+            classify(&Method::GET, &exec, Some("cmd=sh&tty=true")),
+            (Verb::Write, Target::Instance(id))
+        );
+        let debug = format!("/api/v1/instances/{}/debug", id);
+        assert_eq!(
+            classify(&Method::GET, &debug, Some("image=busybox&tty=true")),
+            (Verb::Write, Target::Instance(id))
+        );
+        let files = format!("/api/v1/instances/{}/files", id);
+        assert_eq!(
+            classify(&Method::GET, &files, Some("path=/models")),
+            (Verb::Write, Target::Instance(id))
+        );
+        let migrate = format!("/api/v1/instances/{}/migrate", id);
+        assert_eq!(
+            classify(&Method::POST, &migrate, None),
+            (Verb::Manage, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/api/v1/events", Some("namespace=ml")),
+            (Verb::Read, Target::Namespace("ml".to_string()))
+        );
+        assert_eq!(
+            classify(
+                &Method::GET,
+                "/api/v1/reports/usage",
+                Some("from=2026-01-01T00:00:00Z")
+            ),
+            (Verb::Read, Target::Cluster)
+        );
+        assert_eq!(
+            classify(&Method::GET, "/api/v1/auth/whoami", None),
+            (Verb::Read, Target::Anyone)
+        );
+    }
+}
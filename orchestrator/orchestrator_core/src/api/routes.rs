@@ -1,17 +1,31 @@
 //! API route definitions.
 
 use axum::{
+    extract::DefaultBodyLimit,
     middleware,
-    routing::{delete, get, post, put},
+    routing::{any, delete, get, patch, post, put},
     Router,
 };
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
+#[cfg(feature = "swagger-ui")]
+use utoipa_swagger_ui::SwaggerUi;
 
+use super::audit::audit_layer;
 use super::auth::auth_layer;
+use super::federation;
+use super::files;
 use super::handlers;
+use super::kubernetes;
+use super::openapi;
+use super::rate_limit::rate_limit_layer;
+use super::rbac::rbac_layer;
+use super::v1beta1;
 use super::state::ApiState;
 
+/// Largest backup accepted for restore, well above axum's 2 MB default.
+const MAX_BACKUP_BYTES: usize = 64 * 1024 * 1024;
+
 /// Build the API router with all routes.
 pub fn build_router(state: ApiState) -> Router {
     let auth_config = state.auth_config.clone();
@@ -22,33 +36,207 @@ pub fn build_router(state: ApiState) -> Router {
         .route("/", get(handlers::list_workloads))
         .route("/:workload_id", get(handlers::get_workload))
         .route("/:workload_id", put(handlers::update_workload))
+        .route("/:workload_id", patch(handlers::patch_workload))
         .route("/:workload_id", delete(handlers::delete_workload))
+        .route("/:workload_id/scale", get(handlers::get_workload_scale))
+        .route("/:workload_id/scale", post(handlers::scale_workload))
+        .route("/:workload_id/revisions", get(handlers::list_workload_revisions))
+        .route("/:workload_id/rollout/status", get(handlers::get_rollout_status))
+        .route("/:workload_id/rollout/undo", post(handlers::undo_rollout))
         .route("/:workload_id/instances", get(handlers::list_workload_instances))
         .route("/:workload_id/logs", get(handlers::get_workload_logs))
         .route("/:workload_id/logs/stream", get(handlers::stream_workload_logs))
-        .route("/:workload_id/instances/:instance_id/logs", get(handlers::get_instance_logs));
+        .route("/:workload_id/logs/search", get(handlers::search_workload_logs))
+        .route("/:workload_id/instances/:instance_id/logs", get(handlers::get_instance_logs))
+        .route("/:workload_id/tunnels", post(handlers::open_tunnel));
+
+    // Instance routes
+    let instance_routes = Router::new()
+        .route("/", get(handlers::list_instances))
+        .route("/:instance_id", delete(handlers::delete_instance))
+        .route("/:instance_id/restart", post(handlers::restart_instance))
+        .route("/:instance_id/migrate", post(handlers::migrate_instance))
+        .route(
+            "/:instance_id/resources",
+            patch(handlers::update_instance_resources),
+        )
+        .route("/:instance_id/exec", get(handlers::exec_instance))
+        .route("/:instance_id/debug", get(handlers::debug_instance))
+        .route("/:instance_id/files", get(files::download_files))
+        .route("/:instance_id/files", put(files::upload_files));
+
+    // Namespace routes
+    let namespace_routes = Router::new()
+        .route("/", post(handlers::create_namespace))
+        .route("/", get(handlers::list_namespaces))
+        .route("/:name", get(handlers::get_namespace))
+        .route("/:name", delete(handlers::delete_namespace));
 
     // Node routes
     let node_routes = Router::new()
         .route("/", get(handlers::list_nodes))
-        .route("/:node_id", get(handlers::get_node));
+        .route("/register", post(handlers::register_node))
+        .route("/:node_id", get(handlers::get_node))
+        .route("/:node_id", patch(handlers::patch_node))
+        .route("/:node_id", delete(handlers::deregister_node))
+        .route("/:node_id/cordon", post(handlers::cordon_node))
+        .route("/:node_id/uncordon", post(handlers::uncordon_node))
+        .route("/:node_id/drain", post(handlers::drain_node));
+
+    // Disruption budget routes
+    let disruption_budget_routes = Router::new()
+        .route("/", post(handlers::create_disruption_budget))
+        .route("/", get(handlers::list_disruption_budgets))
+        .route("/:budget_id", get(handlers::get_disruption_budget))
+        .route("/:budget_id", delete(handlers::delete_disruption_budget));
+
+    // Network policy routes
+    let network_policy_routes = Router::new()
+        .route("/", post(handlers::create_network_policy))
+        .route("/", get(handlers::list_network_policies))
+        .route("/:policy_id", get(handlers::get_network_policy))
+        .route("/:policy_id", delete(handlers::delete_network_policy));
+
+    // Cron job routes
+    let cron_job_routes = Router::new()
+        .route("/", post(handlers::create_cron_job))
+        .route("/", get(handlers::list_cron_jobs))
+        .route("/:cron_job_id", get(handlers::get_cron_job))
+        .route("/:cron_job_id", delete(handlers::delete_cron_job))
+        .route("/:cron_job_id/suspend", post(handlers::suspend_cron_job))
+        .route("/:cron_job_id/resume", post(handlers::resume_cron_job));
+
+    // Daemon set routes
+    let daemon_set_routes = Router::new()
+        .route("/", post(handlers::create_daemon_set))
+        .route("/", get(handlers::list_daemon_sets))
+        .route("/:daemon_set_id", get(handlers::get_daemon_set))
+        .route("/:daemon_set_id", delete(handlers::delete_daemon_set));
+
+    // Stateful set routes
+    let stateful_set_routes = Router::new()
+        .route("/", post(handlers::create_stateful_set))
+        .route("/", get(handlers::list_stateful_sets))
+        .route("/:stateful_set_id", get(handlers::get_stateful_set))
+        .route("/:stateful_set_id", put(handlers::update_stateful_set))
+        .route("/:stateful_set_id", delete(handlers::delete_stateful_set))
+        .route("/:stateful_set_id/scale", post(handlers::scale_stateful_set));
+
+    // Image routes
+    let image_routes = Router::new()
+        .route("/prepull", post(handlers::prepull_image))
+        .route("/prepull", get(handlers::list_prepulls))
+        .route("/prepull/:prepull_id", get(handlers::get_prepull))
+        .route("/scans", get(handlers::list_image_scans))
+        .route("/:image/inspect", get(handlers::inspect_image))
+        .route("/:image/scan", get(handlers::scan_image));
+
+    // Service routes
+    let service_routes = Router::new()
+        .route("/", post(handlers::create_service))
+        .route("/", get(handlers::list_services))
+        .route("/:service_id", get(handlers::get_service))
+        .route("/:service_id", put(handlers::update_service))
+        .route("/:service_id", delete(handlers::delete_service))
+        .route("/:service_id/endpoints", get(handlers::get_service_endpoints));
+
+    // Tunnel routes
+    let tunnel_routes = Router::new()
+        .route("/", get(handlers::list_tunnels))
+        .route("/:tunnel_id", delete(handlers::close_tunnel));
 
     // Cluster routes
     let cluster_routes = Router::new()
-        .route("/status", get(handlers::get_cluster_status));
+        .route("/status", get(handlers::get_cluster_status))
+        .route("/leader", get(handlers::get_cluster_leader))
+        .route("/backup", get(handlers::get_backup))
+        .route(
+            "/backup/restore",
+            post(handlers::restore_backup).layer(DefaultBodyLimit::max(MAX_BACKUP_BYTES)),
+        );
+
+    // Admin routes
+    let admin_routes = Router::new()
+        .route("/fsck", get(handlers::check_consistency))
+        .route("/fsck", post(handlers::repair_consistency));
+
+    // Federation routes
+    let federation_routes = Router::new()
+        .route("/clusters", post(federation::register_cluster))
+        .route("/clusters", get(federation::list_clusters))
+        .route("/clusters/:name", delete(federation::remove_cluster))
+        .route("/clusters/:name/workloads", post(federation::dispatch_workload))
+        .route("/clusters/:name/proxy/*path", any(federation::proxy))
+        .route("/workloads", get(federation::list_federated_workloads))
+        .route("/nodes", get(federation::list_federated_nodes));
+
+    // Auth routes
+    let auth_routes = Router::new()
+        .route("/whoami", get(handlers::whoami))
+        .route("/token", post(handlers::issue_token));
+    #[cfg(feature = "mtls")]
+    let auth_routes = auth_routes.route("/certificate", post(handlers::issue_certificate));
 
     // Combine all v1 API routes
     let api_v1 = Router::new()
+        .nest("/auth", auth_routes)
         .nest("/workloads", workload_routes)
+        .nest("/instances", instance_routes)
+        .nest("/namespaces", namespace_routes)
         .nest("/nodes", node_routes)
-        .nest("/cluster", cluster_routes);
+        .nest("/disruption-budgets", disruption_budget_routes)
+        .nest("/network-policies", network_policy_routes)
+        .nest("/cronjobs", cron_job_routes)
+        .nest("/daemonsets", daemon_set_routes)
+        .nest("/statefulsets", stateful_set_routes)
+        .nest("/images", image_routes)
+        .nest("/services", service_routes)
+        .nest("/tunnels", tunnel_routes)
+        .nest("/cluster", cluster_routes)
+        .nest("/admin", admin_routes)
+        .nest("/federation", federation_routes)
+        .route("/import/kubernetes", post(kubernetes::import_kubernetes))
+        .route("/events", get(handlers::list_events))
+        .route("/reports/usage", get(handlers::get_usage_report))
+        .route("/audit", get(handlers::list_audit));
+
+    // Deprecated v1beta1 workload routes, converted to and from v1
+    let api_v1beta1 = Router::new()
+        .route("/workloads", post(v1beta1::create_workload))
+        .route("/workloads", get(v1beta1::list_workloads))
+        .route("/workloads/:workload_id", get(v1beta1::get_workload))
+        .route("/workloads/:workload_id", put(v1beta1::update_workload))
+        .route("/workloads/:workload_id", delete(handlers::delete_workload))
+        .layer(middleware::from_fn(v1beta1::deprecation_layer));
+
+    let router = Router::new()
+        .nest("/api/v1", api_v1)
+        .nest("/api/v1beta1", api_v1beta1);
+
+    // CPU and heap profiles, admin-only like every path outside the API
+    #[cfg(feature = "profiling")]
+    let router = router.nest("/debug/pprof", observability::profiling::router());
 
     // Build main router with middleware
-    let mut router = Router::new()
-        .nest("/api/v1", api_v1)
+    let mut router = router
+        .layer(middleware::from_fn_with_state(state.clone(), rbac_layer))
+        .layer(middleware::from_fn_with_state(state.clone(), audit_layer))
+        .layer(middleware::from_fn_with_state(state.clone(), rate_limit_layer))
         .layer(middleware::from_fn_with_state(auth_config, auth_layer))
         .with_state(state);
 
+    // OpenAPI document, readable without credentials
+    #[cfg(feature = "swagger-ui")]
+    {
+        router = router.merge(
+            SwaggerUi::new("/swagger-ui").url(openapi::OPENAPI_PATH, openapi::openapi()),
+        );
+    }
+    #[cfg(not(feature = "swagger-ui"))]
+    {
+        router = router.route(openapi::OPENAPI_PATH, get(openapi::serve));
+    }
+
     // Add CORS support
     router = router.layer(
         CorsLayer::new()
@@ -57,12 +245,31 @@ pub fn build_router(state: ApiState) -> Router {
             .allow_headers(Any),
     );
 
-    // Add request tracing
-    router = router.layer(TraceLayer::new_for_http());
+    // Add request tracing, continuing the trace of callers that send one
+    router = router.layer(TraceLayer::new_for_http().make_span_with(request_span));
 
     router
 }
 
+/// Span of one API request.
+fn request_span(request: &axum::http::Request<axum::body::Body>) -> tracing::Span {
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+    );
+    #[cfg(feature = "observability")]
+    if let Some(traceparent) = request
+        .headers()
+        .get(observability::TRACEPARENT_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        observability::set_remote_parent(&span, traceparent);
+    }
+    span
+}
+
 /// Configuration for the API server.
 #[derive(Debug, Clone)]
 pub struct ApiServerConfig {
@@ -91,12 +298,26 @@ impl ApiServerConfig {
 pub struct ApiServer {
     config: ApiServerConfig,
     state: ApiState,
+    #[cfg(feature = "mtls")]
+    tls: Option<std::sync::Arc<super::tls::TlsServer>>,
 }
 
 impl ApiServer {
     /// Create a new API server.
     pub fn new(config: ApiServerConfig, state: ApiState) -> Self {
-        Self { config, state }
+        Self {
+            config,
+            state,
+            #[cfg(feature = "mtls")]
+            tls: None,
+        }
+    }
+
+    /// Serve over mutual TLS instead of plain HTTP.
+    #[cfg(feature = "mtls")]
+    pub fn with_tls(mut self, tls: std::sync::Arc<super::tls::TlsServer>) -> Self {
+        self.tls = Some(tls);
+        self
     }
 
     /// Start the server (blocking).
@@ -104,12 +325,21 @@ impl ApiServer {
         let router = build_router(self.state);
         let listener = tokio::net::TcpListener::bind(self.config.bind_addr).await?;
 
+        #[cfg(feature = "mtls")]
+        if let Some(tls) = self.tls {
+            return tls.serve(listener, router).await;
+        }
+
         tracing::info!(
             addr = %self.config.bind_addr,
             "Starting API server"
         );
 
-        axum::serve(listener, router).await
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .await
     }
 
     /// Start the server in the background.
@@ -9,7 +9,19 @@ use container_runtime_interface::ContainerRuntime;
 use orchestrator_shared_types::WorkloadDefinition;
 use state_store_interface::StateStore;
 
+use crate::admission::AdmissionLimits;
+use crate::controllers::{DaemonSetController, StatefulSetController};
+use crate::disruption::DisruptionController;
+use crate::jobs::CronJobController;
+use crate::leader::LeaderElector;
+use crate::network::{NetworkPolicyController, ServiceProxy, TunnelManager};
+use crate::prepull::ImagePrepuller;
+
+use super::audit::AuditLog;
 use super::auth::AuthConfig;
+use super::federation::Federation;
+use super::rate_limit::RateLimiter;
+use super::webhook::AdmissionWebhooks;
 
 /// Shared state for the API server.
 #[derive(Clone)]
@@ -24,6 +36,34 @@ pub struct ApiState {
     pub auth_config: Arc<AuthConfig>,
     /// Optional container runtime for log access.
     pub container_runtime: Option<Arc<dyn ContainerRuntime>>,
+    /// Optional service proxy for endpoint inspection.
+    pub service_proxy: Option<Arc<ServiceProxy>>,
+    /// Optional disruption controller for budgets and node drains.
+    pub disruption: Option<Arc<DisruptionController>>,
+    /// Limits enforced when workloads are created or updated.
+    pub admission: Arc<AdmissionLimits>,
+    /// Optional external admission webhooks.
+    pub admission_webhooks: Option<Arc<AdmissionWebhooks>>,
+    /// Optional leader elector of a replicated control plane.
+    pub leader: Option<Arc<LeaderElector>>,
+    /// Optional tunnel manager, set on edge nodes.
+    pub tunnels: Option<Arc<TunnelManager>>,
+    /// Optional network policy controller, set on nodes that enforce policies.
+    pub network_policies: Option<Arc<NetworkPolicyController>>,
+    /// Optional cron job controller, run while this node leads.
+    pub cron_jobs: Option<Arc<CronJobController>>,
+    /// Optional daemon set controller, run while this node leads.
+    pub daemon_sets: Option<Arc<DaemonSetController>>,
+    /// Optional stateful set controller, run while this node leads.
+    pub stateful_sets: Option<Arc<StatefulSetController>>,
+    /// Optional image pre-puller for rollouts of large images.
+    pub prepuller: Option<Arc<ImagePrepuller>>,
+    /// Optional audit log of mutating calls.
+    pub audit: Option<Arc<AuditLog>>,
+    /// Optional per-client rate limiter.
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// Optional federation of remote clusters.
+    pub federation: Option<Arc<Federation>>,
 }
 
 impl ApiState {
@@ -40,6 +80,20 @@ impl ApiState {
             workload_tx,
             auth_config: Arc::new(auth_config),
             container_runtime: None,
+            service_proxy: None,
+            disruption: None,
+            admission: Arc::new(AdmissionLimits::default()),
+            admission_webhooks: None,
+            leader: None,
+            tunnels: None,
+            network_policies: None,
+            cron_jobs: None,
+            daemon_sets: None,
+            stateful_sets: None,
+            prepuller: None,
+            audit: None,
+            rate_limiter: None,
+            federation: None,
         }
     }
 
@@ -57,6 +111,20 @@ impl ApiState {
             workload_tx,
             auth_config: Arc::new(auth_config),
             container_runtime: Some(container_runtime),
+            service_proxy: None,
+            disruption: None,
+            admission: Arc::new(AdmissionLimits::default()),
+            admission_webhooks: None,
+            leader: None,
+            tunnels: None,
+            network_policies: None,
+            cron_jobs: None,
+            daemon_sets: None,
+            stateful_sets: None,
+            prepuller: None,
+            audit: None,
+            rate_limiter: None,
+            federation: None,
         }
     }
 
@@ -73,4 +141,74 @@ impl ApiState {
     pub fn set_runtime(&mut self, runtime: Arc<dyn ContainerRuntime>) {
         self.container_runtime = Some(runtime);
     }
+
+    /// Set the service proxy for endpoint inspection.
+    pub fn set_service_proxy(&mut self, proxy: Arc<ServiceProxy>) {
+        self.service_proxy = Some(proxy);
+    }
+
+    /// Set the disruption controller for budgets and node drains.
+    pub fn set_disruption_controller(&mut self, disruption: Arc<DisruptionController>) {
+        self.disruption = Some(disruption);
+    }
+
+    /// Set the limits enforced when workloads are created or updated.
+    pub fn set_admission_limits(&mut self, limits: AdmissionLimits) {
+        self.admission = Arc::new(limits);
+    }
+
+    /// Set the webhooks consulted when workloads are created or updated.
+    pub fn set_admission_webhooks(&mut self, webhooks: Arc<AdmissionWebhooks>) {
+        self.admission_webhooks = Some(webhooks);
+    }
+
+    /// Set the leader elector reported by the cluster leader endpoint.
+    pub fn set_leader_elector(&mut self, elector: Arc<LeaderElector>) {
+        self.leader = Some(elector);
+    }
+
+    /// Set the tunnel manager serving temporary public tunnels.
+    pub fn set_tunnel_manager(&mut self, tunnels: Arc<TunnelManager>) {
+        self.tunnels = Some(tunnels);
+    }
+
+    /// Set the controller enforcing network policies on this node.
+    pub fn set_network_policy_controller(&mut self, policies: Arc<NetworkPolicyController>) {
+        self.network_policies = Some(policies);
+    }
+
+    /// Set the controller creating jobs for cron jobs.
+    pub fn set_cron_job_controller(&mut self, cron_jobs: Arc<CronJobController>) {
+        self.cron_jobs = Some(cron_jobs);
+    }
+
+    /// Set the controller placing daemon set instances.
+    pub fn set_daemon_set_controller(&mut self, daemon_sets: Arc<DaemonSetController>) {
+        self.daemon_sets = Some(daemon_sets);
+    }
+
+    /// Set the controller placing stateful set instances.
+    pub fn set_stateful_set_controller(&mut self, stateful_sets: Arc<StatefulSetController>) {
+        self.stateful_sets = Some(stateful_sets);
+    }
+
+    /// Set the image pre-puller.
+    pub fn set_image_prepuller(&mut self, prepuller: Arc<ImagePrepuller>) {
+        self.prepuller = Some(prepuller);
+    }
+
+    /// Set the audit log mutating calls are recorded to.
+    pub fn set_audit_log(&mut self, audit: Arc<AuditLog>) {
+        self.audit = Some(audit);
+    }
+
+    /// Set the rate limiter applied to every client.
+    pub fn set_rate_limiter(&mut self, limiter: Arc<RateLimiter>) {
+        self.rate_limiter = Some(limiter);
+    }
+
+    /// Set the federation of remote clusters this control plane reaches.
+    pub fn set_federation(&mut self, federation: Arc<Federation>) {
+        self.federation = Some(federation);
+    }
 }
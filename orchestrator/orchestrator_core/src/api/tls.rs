@@ -0,0 +1,568 @@
+//! Mutual TLS for the API.
+//!
+//! A [`ClusterCa`] kept in a directory shared by the control-plane nodes
+//! issues two kinds of certificate:
+//!
+//! - Server certificates for each node's API port, served by [`TlsServer`]
+//!   and re-issued halfway through their lifetime without a restart. They
+//!   are also valid as client certificates, for node agents calling the API.
+//! - Client certificates for CLI users. `orch init` sends a certificate
+//!   signing request to `POST /api/v1/auth/certificate`, signed with the
+//!   user's Ed25519 key, and the CA signs it with that key as the subject.
+//!
+//! A connection's client certificate authenticates its requests as the
+//! certificate's subject (see [`ClientCertificate`]), so role bindings apply
+//! to it as to a signed request. Connections without one are accepted unless
+//! [`TlsServer::with_client_cert_required`] is set, since they are how users
+//! enroll, and must sign their requests or send a bearer token instead.
+
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use axum::extract::ConnectInfo;
+use axum::Router;
+use chrono::{DateTime, Duration, Utc};
+use hyper::body::Incoming;
+use hyper::service::service_fn;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use rcgen::{
+    BasicConstraints, CertificateParams, CertificateSigningRequestParams, DistinguishedName,
+    DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair, KeyUsagePurpose, SerialNumber,
+};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::crypto::{ring, CryptoProvider};
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use tower::Service;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use orchestrator_shared_types::{OrchestrationError, Result};
+
+fn config_error(context: &str, e: impl std::fmt::Display) -> OrchestrationError {
+    OrchestrationError::ConfigError(format!("{}: {}", context, e))
+}
+
+fn to_offset(at: DateTime<Utc>) -> time::OffsetDateTime {
+    time::OffsetDateTime::from_unix_timestamp(at.timestamp())
+        .unwrap_or(time::OffsetDateTime::UNIX_EPOCH)
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+/// A certificate signed by the [`ClusterCa`].
+#[derive(Debug, Clone)]
+pub struct IssuedCertificate {
+    pub certificate_pem: String,
+    /// The private key, when the CA generated it rather than signing a request.
+    pub key_pem: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// The cluster's certificate authority.
+pub struct ClusterCa {
+    certificate: rcgen::Certificate,
+    key: KeyPair,
+    certificate_pem: String,
+}
+
+impl std::fmt::Debug for ClusterCa {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClusterCa").finish_non_exhaustive()
+    }
+}
+
+impl ClusterCa {
+    pub const CERT_FILE: &'static str = "ca.crt";
+    pub const KEY_FILE: &'static str = "ca.key";
+    /// How long client certificates are valid.
+    pub const CLIENT_CERT_TTL_DAYS: i64 = 30;
+    /// How long node server certificates are valid.
+    pub const SERVER_CERT_TTL_DAYS: i64 = 7;
+    const CA_TTL_DAYS: i64 = 3650;
+    /// Tolerated clock skew between nodes and clients.
+    const BACKDATE_MINUTES: i64 = 5;
+
+    /// Generate a new CA named after the cluster.
+    pub fn generate(cluster_id: &str) -> Result<Self> {
+        let key = KeyPair::generate().map_err(|e| config_error("cannot generate CA key", e))?;
+        let mut params = CertificateParams::default();
+        params.distinguished_name = DistinguishedName::new();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, format!("{} CA", cluster_id));
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+        params.not_before = to_offset(Utc::now() - Duration::minutes(Self::BACKDATE_MINUTES));
+        params.not_after = to_offset(Utc::now() + Duration::days(Self::CA_TTL_DAYS));
+        let certificate = params
+            .self_signed(&key)
+            .map_err(|e| config_error("cannot sign CA certificate", e))?;
+        let certificate_pem = certificate.pem();
+        Ok(Self {
+            certificate,
+            key,
+            certificate_pem,
+        })
+    }
+
+    /// Load a CA from its PEM-encoded certificate and private key.
+    pub fn from_pem(certificate_pem: &str, key_pem: &str) -> Result<Self> {
+        let key = KeyPair::from_pem(key_pem).map_err(|e| config_error("invalid CA key", e))?;
+        let params = CertificateParams::from_ca_cert_pem(certificate_pem)
+            .map_err(|e| config_error("invalid CA certificate", e))?;
+        let der = pem_to_der(certificate_pem)?;
+        let (_, parsed) = x509_parser::parse_x509_certificate(&der)
+            .map_err(|e| config_error("invalid CA certificate", e))?;
+        if parsed.public_key().raw != key.public_key_der().as_slice() {
+            return Err(OrchestrationError::ConfigError(
+                "CA key does not match its certificate".to_string(),
+            ));
+        }
+        // Re-signing yields an issuer with the same name and key, which is
+        // all that signing needs; clients keep trusting the original
+        let certificate = params
+            .self_signed(&key)
+            .map_err(|e| config_error("cannot load CA certificate", e))?;
+        Ok(Self {
+            certificate,
+            key,
+            certificate_pem: certificate_pem.to_string(),
+        })
+    }
+
+    /// Load the CA from `dir`, generating and saving one if it has none.
+    pub async fn load_or_create(dir: &Path, cluster_id: &str) -> Result<Self> {
+        let cert_path = dir.join(Self::CERT_FILE);
+        let key_path = dir.join(Self::KEY_FILE);
+        if cert_path.exists() {
+            let certificate_pem = tokio::fs::read_to_string(&cert_path)
+                .await
+                .map_err(|e| config_error(&cert_path.display().to_string(), e))?;
+            let key_pem = tokio::fs::read_to_string(&key_path)
+                .await
+                .map_err(|e| config_error(&key_path.display().to_string(), e))?;
+            return Self::from_pem(&certificate_pem, &key_pem);
+        }
+
+        let ca = Self::generate(cluster_id)?;
+        let io_error = |e: std::io::Error| config_error(&dir.display().to_string(), e);
+        tokio::fs::create_dir_all(dir).await.map_err(io_error)?;
+        tokio::fs::write(&key_path, ca.key.serialize_pem())
+            .await
+            .map_err(io_error)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            tokio::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600))
+                .await
+                .map_err(io_error)?;
+        }
+        tokio::fs::write(&cert_path, &ca.certificate_pem)
+            .await
+            .map_err(io_error)?;
+        info!(path = %cert_path.display(), "Created cluster CA");
+        Ok(ca)
+    }
+
+    /// The CA certificate clients and nodes trust.
+    pub fn certificate_pem(&self) -> &str {
+        &self.certificate_pem
+    }
+
+    fn leaf_params(subject: &str, ttl: Duration) -> (CertificateParams, DateTime<Utc>) {
+        let now = Utc::now();
+        let expires_at = now + ttl;
+        let mut params = CertificateParams::default();
+        params.distinguished_name = DistinguishedName::new();
+        params.distinguished_name.push(DnType::CommonName, subject);
+        params.serial_number = Some(SerialNumber::from(Uuid::new_v4().as_bytes().to_vec()));
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        params.not_before = to_offset(now - Duration::minutes(Self::BACKDATE_MINUTES));
+        params.not_after = to_offset(expires_at);
+        params.use_authority_key_identifier_extension = true;
+        (params, expires_at)
+    }
+
+    /// Sign a PEM certificate signing request as a client certificate for
+    /// `subject`, ignoring the names the request asks for.
+    pub fn sign_client_request(
+        &self,
+        request_pem: &str,
+        subject: &str,
+        ttl: Duration,
+    ) -> Result<IssuedCertificate> {
+        let mut request = CertificateSigningRequestParams::from_pem(request_pem)
+            .map_err(|e| config_error("invalid certificate signing request", e))?;
+        let (mut params, expires_at) = Self::leaf_params(subject, ttl);
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+        request.params = params;
+        let certificate = request
+            .signed_by(&self.certificate, &self.key)
+            .map_err(|e| config_error("cannot sign client certificate", e))?;
+        Ok(IssuedCertificate {
+            certificate_pem: certificate.pem(),
+            key_pem: None,
+            expires_at,
+        })
+    }
+
+    /// Issue a key and certificate for a node, valid for `names` (DNS names
+    /// or IP addresses) as a server and as a client.
+    pub fn issue_node_certificate(
+        &self,
+        subject: &str,
+        names: &[String],
+        ttl: Duration,
+    ) -> Result<IssuedCertificate> {
+        let key = KeyPair::generate().map_err(|e| config_error("cannot generate key", e))?;
+        let (mut params, expires_at) = Self::leaf_params(subject, ttl);
+        params.subject_alt_names = CertificateParams::new(names.to_vec())
+            .map_err(|e| config_error("invalid server name", e))?
+            .subject_alt_names;
+        params.extended_key_usages = vec![
+            ExtendedKeyUsagePurpose::ServerAuth,
+            ExtendedKeyUsagePurpose::ClientAuth,
+        ];
+        let certificate = params
+            .signed_by(&key, &self.certificate, &self.key)
+            .map_err(|e| config_error("cannot sign node certificate", e))?;
+        Ok(IssuedCertificate {
+            certificate_pem: certificate.pem(),
+            key_pem: Some(key.serialize_pem()),
+            expires_at,
+        })
+    }
+
+    fn roots(&self) -> Result<Arc<RootCertStore>> {
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(self.certificate.der().clone())
+            .map_err(|e| config_error("invalid CA certificate", e))?;
+        Ok(Arc::new(roots))
+    }
+}
+
+/// The verified client certificate of a TLS connection, added to each of its
+/// requests' extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    /// The certificate's common name: a user's base64 public key, or a node's
+    /// `node/<id>`.
+    pub subject: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ClientCertificate {
+    /// Read the subject of a DER certificate rustls has already verified.
+    pub fn from_der(der: &[u8]) -> Option<Self> {
+        let (_, certificate) = x509_parser::parse_x509_certificate(der).ok()?;
+        let subject = certificate
+            .subject()
+            .iter_common_name()
+            .next()?
+            .as_str()
+            .ok()?
+            .to_string();
+        let expires_at = DateTime::from_timestamp(certificate.validity().not_after.timestamp(), 0)?;
+        Some(Self {
+            subject,
+            expires_at,
+        })
+    }
+}
+
+/// HTTPS server for the API that verifies client certificates against the
+/// cluster CA and rotates its own certificate.
+pub struct TlsServer {
+    ca: Arc<ClusterCa>,
+    subject: String,
+    names: Vec<String>,
+    require_client_cert: bool,
+    current: RwLock<(Arc<ServerConfig>, DateTime<Utc>)>,
+}
+
+impl TlsServer {
+    /// Issue a server certificate for `names` and prepare to serve with it.
+    pub fn new(ca: Arc<ClusterCa>, subject: impl Into<String>, names: Vec<String>) -> Result<Self> {
+        let subject = subject.into();
+        let (config, expires_at) = Self::build_config(&ca, &subject, &names, false)?;
+        Ok(Self {
+            ca,
+            subject,
+            names,
+            require_client_cert: false,
+            current: RwLock::new((config, expires_at)),
+        })
+    }
+
+    /// Refuse connections without a client certificate from the CA.
+    pub fn with_client_cert_required(mut self, required: bool) -> Result<Self> {
+        self.require_client_cert = required;
+        let (config, expires_at) =
+            Self::build_config(&self.ca, &self.subject, &self.names, required)?;
+        self.current = RwLock::new((config, expires_at));
+        Ok(self)
+    }
+
+    fn build_config(
+        ca: &ClusterCa,
+        subject: &str,
+        names: &[String],
+        require_client_cert: bool,
+    ) -> Result<(Arc<ServerConfig>, DateTime<Utc>)> {
+        let issued = ca.issue_node_certificate(
+            subject,
+            names,
+            Duration::days(ClusterCa::SERVER_CERT_TTL_DAYS),
+        )?;
+        let key_pem = issued.key_pem.unwrap_or_default();
+        let key = KeyPair::from_pem(&key_pem).map_err(|e| config_error("invalid key", e))?;
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der()));
+        let certificate = pem_to_der(&issued.certificate_pem)?;
+
+        let verifier = WebPkiClientVerifier::builder_with_provider(ca.roots()?, provider());
+        let verifier = if require_client_cert {
+            verifier
+        } else {
+            verifier.allow_unauthenticated()
+        };
+        let verifier = verifier
+            .build()
+            .map_err(|e| config_error("invalid client verifier", e))?;
+
+        let mut config = ServerConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| config_error("invalid TLS versions", e))?
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(vec![certificate], key)
+            .map_err(|e| config_error("invalid server certificate", e))?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok((Arc::new(config), issued.expires_at))
+    }
+
+    /// When the current server certificate expires.
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.current.read().unwrap().1
+    }
+
+    /// Issue a fresh server certificate; new connections use it.
+    pub fn rotate(&self) -> Result<DateTime<Utc>> {
+        let (config, expires_at) = Self::build_config(
+            &self.ca,
+            &self.subject,
+            &self.names,
+            self.require_client_cert,
+        )?;
+        *self.current.write().unwrap() = (config, expires_at);
+        info!(expires_at = %expires_at, "Rotated API server certificate");
+        Ok(expires_at)
+    }
+
+    /// Rotate the certificate halfway through each one's lifetime.
+    pub fn spawn_rotation(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let remaining = self.expires_at() - Utc::now();
+                let wait = (remaining / 2).to_std().unwrap_or_default();
+                tokio::time::sleep(wait.max(std::time::Duration::from_secs(60))).await;
+                if let Err(e) = self.rotate() {
+                    warn!("Failed to rotate API server certificate: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Serve `router` over TLS on `listener` until it fails.
+    pub async fn serve(
+        self: Arc<Self>,
+        listener: TcpListener,
+        router: Router,
+    ) -> std::io::Result<()> {
+        info!(addr = %listener.local_addr()?, "Starting API server with mutual TLS");
+        loop {
+            let (stream, remote_addr) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // Out of file descriptors and the like; keep serving
+                    warn!("Failed to accept connection: {}", e);
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            let acceptor = TlsAcceptor::from(self.current.read().unwrap().0.clone());
+            let router = router.clone();
+            tokio::spawn(async move {
+                let stream = match acceptor.accept(stream).await {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        debug!(%remote_addr, "TLS handshake failed: {}", e);
+                        return;
+                    }
+                };
+                let client_certificate = stream
+                    .get_ref()
+                    .1
+                    .peer_certificates()
+                    .and_then(|chain| chain.first())
+                    .and_then(|der| ClientCertificate::from_der(der));
+                let service = service_fn(move |mut request: hyper::Request<Incoming>| {
+                    request.extensions_mut().insert(ConnectInfo(remote_addr));
+                    if let Some(certificate) = &client_certificate {
+                        request.extensions_mut().insert(certificate.clone());
+                    }
+                    router.clone().call(request)
+                });
+                if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                    .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                    .await
+                {
+                    debug!(%remote_addr, "Connection closed with error: {}", e);
+                }
+            });
+        }
+    }
+}
+
+fn pem_to_der(pem: &str) -> Result<CertificateDer<'static>> {
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    CertificateDer::from_pem_slice(pem.as_bytes()).map_err(|e| config_error("invalid PEM", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Extension};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls::pki_types::ServerName;
+    use tokio_rustls::rustls::ClientConfig;
+    use tokio_rustls::TlsConnector;
+
+    fn client_request(subject_hint: &str) -> (KeyPair, String) {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::default();
+        params.distinguished_name = DistinguishedName::new();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, subject_hint);
+        let request = params.serialize_request(&key).unwrap().pem().unwrap();
+        (key, request)
+    }
+
+    #[test]
+    fn test_client_certificate_subject_is_set_by_ca() {
+        let ca = ClusterCa::generate("test").unwrap();
+        let (_, request) = client_request("admin");
+        let issued = ca
+            .sign_client_request(&request, "a2V5", Duration::days(1))
+            .unwrap();
+
+        let certificate =
+            ClientCertificate::from_der(&pem_to_der(&issued.certificate_pem).unwrap()).unwrap();
+        assert_eq!(certificate.subject, "a2V5");
+        assert_eq!(
+            certificate.expires_at.timestamp(),
+            issued.expires_at.timestamp()
+        );
+        assert!(ca
+            .sign_client_request("not a request", "a2V5", Duration::days(1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_ca_reloads_from_pem() {
+        let ca = ClusterCa::generate("test").unwrap();
+        let reloaded = ClusterCa::from_pem(ca.certificate_pem(), &ca.key.serialize_pem()).unwrap();
+        assert_eq!(reloaded.certificate_pem(), ca.certificate_pem());
+        let other = ClusterCa::generate("other").unwrap();
+        assert!(ClusterCa::from_pem(ca.certificate_pem(), &other.key.serialize_pem()).is_err());
+    }
+
+    async fn request_subject(
+        addr: std::net::SocketAddr,
+        ca: &ClusterCa,
+        client: Option<(KeyPair, IssuedCertificate)>,
+    ) -> std::io::Result<String> {
+        let builder = ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(ca.roots().unwrap());
+        let config = match client {
+            Some((key, issued)) => builder
+                .with_client_auth_cert(
+                    vec![pem_to_der(&issued.certificate_pem).unwrap()],
+                    PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der())),
+                )
+                .unwrap(),
+            None => builder.with_no_client_auth(),
+        };
+        let stream = tokio::net::TcpStream::connect(addr).await?;
+        let mut stream = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await?;
+        stream
+            .write_all(b"GET /subject HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok(response
+            .rsplit("\r\n\r\n")
+            .next()
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    #[tokio::test]
+    async fn test_server_passes_client_certificate_to_requests() {
+        let ca = Arc::new(ClusterCa::generate("test").unwrap());
+        let server = Arc::new(
+            TlsServer::new(ca.clone(), "node/test", vec!["localhost".to_string()]).unwrap(),
+        );
+        let router = Router::new().route(
+            "/subject",
+            get(
+                |certificate: Option<Extension<ClientCertificate>>| async move {
+                    certificate
+                        .map(|Extension(c)| c.subject)
+                        .unwrap_or_else(|| "anonymous".to_string())
+                },
+            ),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(server.clone().serve(listener, router));
+
+        let (key, request) = client_request("ignored");
+        let issued = ca
+            .sign_client_request(&request, "a2V5", Duration::days(1))
+            .unwrap();
+        assert_eq!(
+            request_subject(addr, &ca, Some((key, issued)))
+                .await
+                .unwrap(),
+            "a2V5"
+        );
+        assert_eq!(request_subject(addr, &ca, None).await.unwrap(), "anonymous");
+
+        // A certificate from another CA is refused at the handshake
+        let other = ClusterCa::generate("other").unwrap();
+        let (key, request) = client_request("ignored");
+        let issued = other
+            .sign_client_request(&request, "a2V5", Duration::days(1))
+            .unwrap();
+        assert!(request_subject(addr, &ca, Some((key, issued)))
+            .await
+            .is_err());
+
+        // Rotation keeps serving
+        let before = server.expires_at();
+        server.rotate().unwrap();
+        assert!(server.expires_at() >= before);
+        assert_eq!(request_subject(addr, &ca, None).await.unwrap(), "anonymous");
+    }
+}
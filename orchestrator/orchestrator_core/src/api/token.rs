@@ -0,0 +1,508 @@
+//! Bearer token authentication.
+//!
+//! Besides Ed25519 request signing, the API accepts `Authorization: Bearer`
+//! JWTs from two kinds of issuer:
+//!
+//! - [`LocalTokenIssuer`]: the control plane itself. `POST /api/v1/auth/token`,
+//!   signed with an Ed25519 key, returns a short-lived HS256 token whose
+//!   subject is that key, which is what `orch login` stores.
+//! - [`OidcVerifier`] (`oidc` feature): an OpenID Connect provider such as
+//!   Keycloak or Auth0. Tokens are checked against the keys the issuer
+//!   publishes and the configured audience, and [`ClaimRoleMapping`]s turn
+//!   claim values, such as group names, into role bindings.
+//!
+//! Roles a token carries are granted on top of the RBAC policy's bindings for
+//! its subject.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::auth::AuthError;
+use super::rbac::{Role, RoleBinding};
+
+/// Who a verified token speaks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenIdentity {
+    /// The token's `sub` claim.
+    pub subject: String,
+    /// Roles granted by the token's claims.
+    pub bindings: Vec<RoleBinding>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A token handed out by [`LocalTokenIssuer`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedToken {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Claims of locally issued tokens.
+#[derive(Debug, Serialize, Deserialize)]
+struct LocalClaims {
+    sub: String,
+    iss: String,
+    aud: String,
+    iat: i64,
+    exp: i64,
+}
+
+/// Issues and verifies HS256 tokens with a secret shared by every
+/// control-plane instance.
+#[derive(Clone)]
+pub struct LocalTokenIssuer {
+    issuer: String,
+    ttl: Duration,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+}
+
+impl LocalTokenIssuer {
+    /// `iss` of locally issued tokens unless changed.
+    pub const DEFAULT_ISSUER: &'static str = "orchestrator";
+
+    /// `aud` of locally issued tokens.
+    pub const AUDIENCE: &'static str = "orchestrator-api";
+
+    /// Default token lifetime in seconds.
+    pub const DEFAULT_TTL_SECS: i64 = 3600;
+
+    /// Create an issuer signing with `secret`, which should be at least 32
+    /// random bytes.
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            issuer: Self::DEFAULT_ISSUER.to_string(),
+            ttl: Duration::seconds(Self::DEFAULT_TTL_SECS),
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret),
+        }
+    }
+
+    /// Set the `iss` claim, e.g. to tell clusters sharing a secret apart.
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = issuer.into();
+        self
+    }
+
+    /// Set how long issued tokens are valid.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Issue a token for `subject`.
+    pub fn issue(&self, subject: &str) -> Result<IssuedToken, AuthError> {
+        let now = Utc::now();
+        let expires_at = now + self.ttl;
+        let claims = LocalClaims {
+            sub: subject.to_string(),
+            iss: self.issuer.clone(),
+            aud: Self::AUDIENCE.to_string(),
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        let token =
+            jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
+                .map_err(|e| {
+                    AuthError::new(format!("Failed to issue token: {}", e), "TOKEN_ERROR")
+                })?;
+        Ok(IssuedToken { token, expires_at })
+    }
+
+    /// Verify a token this issuer handed out.
+    pub fn verify(&self, token: &str) -> Result<TokenIdentity, AuthError> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[Self::AUDIENCE]);
+        let claims = jsonwebtoken::decode::<LocalClaims>(token, &self.decoding_key, &validation)
+            .map_err(|e| AuthError::invalid_token(&e.to_string()))?
+            .claims;
+        Ok(TokenIdentity {
+            subject: claims.sub,
+            bindings: Vec::new(),
+            expires_at: timestamp(claims.exp),
+        })
+    }
+}
+
+impl fmt::Debug for LocalTokenIssuer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalTokenIssuer")
+            .field("issuer", &self.issuer)
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Grants a role to tokens whose role claim contains `value`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimRoleMapping {
+    pub value: String,
+    pub role: Role,
+    /// Namespace the role applies in; cluster-wide when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+impl FromStr for ClaimRoleMapping {
+    type Err = String;
+
+    /// Parses `VALUE=ROLE` or `VALUE=ROLE:NAMESPACE`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, grant) = s
+            .trim()
+            .rsplit_once('=')
+            .filter(|(value, _)| !value.trim().is_empty())
+            .ok_or_else(|| format!("expected VALUE=ROLE[:NAMESPACE], got '{}'", s))?;
+        let (role, namespace) = match grant.split_once(':') {
+            Some((role, namespace)) if !namespace.trim().is_empty() => {
+                (role, Some(namespace.trim().to_string()))
+            }
+            Some(_) => return Err(format!("expected VALUE=ROLE[:NAMESPACE], got '{}'", s)),
+            None => (grant, None),
+        };
+        Ok(Self {
+            value: value.trim().to_string(),
+            role: role.parse()?,
+            namespace,
+        })
+    }
+}
+
+/// How tokens from an OpenID Connect provider are checked and mapped to roles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcConfig {
+    /// Issuer URL; tokens' `iss` must match it exactly, and its discovery
+    /// document is read from `/.well-known/openid-configuration` below it.
+    pub issuer: String,
+    /// Required `aud` claim, usually the client ID.
+    pub audience: String,
+    /// Claim holding role names or groups, e.g. `groups` or Keycloak's
+    /// `realm_access.roles`; dots descend into nested objects.
+    pub roles_claim: String,
+    pub role_mappings: Vec<ClaimRoleMapping>,
+}
+
+impl OidcConfig {
+    /// Claim read for role names unless changed.
+    pub const DEFAULT_ROLES_CLAIM: &'static str = "groups";
+
+    pub fn new(issuer: impl Into<String>, audience: impl Into<String>) -> Self {
+        Self {
+            issuer: issuer.into(),
+            audience: audience.into(),
+            roles_claim: Self::DEFAULT_ROLES_CLAIM.to_string(),
+            role_mappings: Vec::new(),
+        }
+    }
+
+    /// Set the claim holding role names.
+    pub fn with_roles_claim(mut self, claim: impl Into<String>) -> Self {
+        self.roles_claim = claim.into();
+        self
+    }
+
+    /// Add a claim value to role mapping.
+    pub fn with_role_mapping(mut self, mapping: ClaimRoleMapping) -> Self {
+        self.role_mappings.push(mapping);
+        self
+    }
+
+    /// Parses comma-separated mappings such as
+    /// `platform-admins=admin,ml-team=operator:ml`.
+    pub fn parse_role_mappings(spec: &str) -> Result<Vec<ClaimRoleMapping>, String> {
+        spec.split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    /// The identity behind verified claims, with the roles its role claim maps to.
+    pub fn identity(&self, claims: &Map<String, Value>) -> Result<TokenIdentity, AuthError> {
+        let subject = claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .ok_or_else(|| AuthError::invalid_token("missing sub claim"))?
+            .to_string();
+        let exp = claims
+            .get("exp")
+            .and_then(Value::as_i64)
+            .ok_or_else(|| AuthError::invalid_token("missing exp claim"))?;
+
+        let mut keys = self.roles_claim.split('.');
+        let mut roles = keys.next().and_then(|key| claims.get(key));
+        for key in keys {
+            roles = roles.and_then(|value| value.get(key));
+        }
+        let values: Vec<&str> = match roles {
+            Some(Value::String(value)) => vec![value.as_str()],
+            Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let bindings = self
+            .role_mappings
+            .iter()
+            .filter(|mapping| values.contains(&mapping.value.as_str()))
+            .map(|mapping| RoleBinding {
+                subject: subject.clone(),
+                role: mapping.role,
+                namespace: mapping.namespace.clone(),
+            })
+            .collect();
+
+        Ok(TokenIdentity {
+            subject,
+            bindings,
+            expires_at: timestamp(exp),
+        })
+    }
+}
+
+fn timestamp(secs: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(secs, 0).single().unwrap_or_default()
+}
+
+#[cfg(feature = "oidc")]
+pub use oidc::OidcVerifier;
+
+#[cfg(feature = "oidc")]
+mod oidc {
+    use std::time::{Duration, Instant};
+
+    use jsonwebtoken::jwk::{Jwk, JwkSet};
+    use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+    use serde::Deserialize;
+    use serde_json::{Map, Value};
+    use tokio::sync::RwLock;
+
+    use super::{OidcConfig, TokenIdentity};
+    use crate::api::auth::AuthError;
+
+    /// Refetch the issuer's keys at most this often, however many tokens
+    /// name an unknown key.
+    const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+    #[derive(Deserialize)]
+    struct Discovery {
+        jwks_uri: String,
+    }
+
+    struct CachedKeys {
+        keys: JwkSet,
+        fetched_at: Instant,
+    }
+
+    /// Verifies tokens against the signing keys an OIDC issuer publishes.
+    pub struct OidcVerifier {
+        config: OidcConfig,
+        client: reqwest::Client,
+        keys: RwLock<Option<CachedKeys>>,
+    }
+
+    impl OidcVerifier {
+        pub fn new(config: OidcConfig) -> Self {
+            Self {
+                config,
+                client: reqwest::Client::new(),
+                keys: RwLock::new(None),
+            }
+        }
+
+        pub fn config(&self) -> &OidcConfig {
+            &self.config
+        }
+
+        /// Verify a token's signature, issuer, audience and expiry.
+        pub async fn verify(&self, token: &str) -> Result<TokenIdentity, AuthError> {
+            let header = jsonwebtoken::decode_header(token)
+                .map_err(|e| AuthError::invalid_token(&e.to_string()))?;
+            // Shared-secret algorithms would let anyone holding the public key sign
+            if matches!(
+                header.alg,
+                Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+            ) {
+                return Err(AuthError::invalid_token(
+                    "symmetric algorithms are not accepted",
+                ));
+            }
+
+            let jwk = self.signing_key(header.kid.as_deref()).await?;
+            let key = DecodingKey::from_jwk(&jwk)
+                .map_err(|e| AuthError::invalid_token(&e.to_string()))?;
+            let mut validation = Validation::new(header.alg);
+            validation.set_issuer(&[&self.config.issuer]);
+            validation.set_audience(&[&self.config.audience]);
+            let claims = jsonwebtoken::decode::<Map<String, Value>>(token, &key, &validation)
+                .map_err(|e| AuthError::invalid_token(&e.to_string()))?
+                .claims;
+            self.config.identity(&claims)
+        }
+
+        /// The issuer's key `kid`, refetching the key set if it is unknown.
+        async fn signing_key(&self, kid: Option<&str>) -> Result<Jwk, AuthError> {
+            if let Some(jwk) = self.cached_key(kid).await {
+                return Ok(jwk);
+            }
+            {
+                let mut cache = self.keys.write().await;
+                let stale = cache
+                    .as_ref()
+                    .is_none_or(|c| c.fetched_at.elapsed() >= MIN_REFRESH_INTERVAL);
+                if stale {
+                    *cache = Some(CachedKeys {
+                        keys: self.fetch_keys().await?,
+                        fetched_at: Instant::now(),
+                    });
+                }
+            }
+            self.cached_key(kid)
+                .await
+                .ok_or_else(|| AuthError::invalid_token("signed with an unknown key"))
+        }
+
+        async fn cached_key(&self, kid: Option<&str>) -> Option<Jwk> {
+            let cache = self.keys.read().await;
+            let keys = &cache.as_ref()?.keys;
+            match kid {
+                Some(kid) => keys.find(kid).cloned(),
+                None if keys.keys.len() == 1 => keys.keys.first().cloned(),
+                None => None,
+            }
+        }
+
+        async fn fetch_keys(&self) -> Result<JwkSet, AuthError> {
+            let unavailable = |e: reqwest::Error| {
+                AuthError::new(
+                    format!("Cannot fetch OIDC signing keys: {}", e),
+                    "OIDC_UNAVAILABLE",
+                )
+            };
+            let discovery_url = format!(
+                "{}/.well-known/openid-configuration",
+                self.config.issuer.trim_end_matches('/')
+            );
+            let discovery: Discovery = self
+                .client
+                .get(&discovery_url)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(unavailable)?
+                .json()
+                .await
+                .map_err(unavailable)?;
+            self.client
+                .get(&discovery.jwks_uri)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(unavailable)?
+                .json()
+                .await
+                .map_err(unavailable)
+        }
+    }
+
+    impl std::fmt::Debug for OidcVerifier {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("OidcVerifier")
+                .field("config", &self.config)
+                .finish_non_exhaustive()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_token_roundtrip() {
+        let issuer = LocalTokenIssuer::new(b"0123456789abcdef0123456789abcdef");
+        let issued = issuer.issue("a2V5").unwrap();
+        assert!(issued.expires_at > Utc::now());
+
+        let identity = issuer.verify(&issued.token).unwrap();
+        assert_eq!(identity.subject, "a2V5");
+        assert!(identity.bindings.is_empty());
+        assert_eq!(
+            identity.expires_at.timestamp(),
+            issued.expires_at.timestamp()
+        );
+
+        let other = LocalTokenIssuer::new(b"another secret of at least 32 bytes");
+        assert!(other.verify(&issued.token).is_err());
+        let other_cluster =
+            LocalTokenIssuer::new(b"0123456789abcdef0123456789abcdef").with_issuer("staging");
+        assert!(other_cluster.verify(&issued.token).is_err());
+    }
+
+    #[test]
+    fn test_expired_local_token_rejected() {
+        let issuer = LocalTokenIssuer::new(b"0123456789abcdef0123456789abcdef")
+            .with_ttl(Duration::seconds(-300));
+        let issued = issuer.issue("a2V5").unwrap();
+        let err = issuer.verify(&issued.token).unwrap_err();
+        assert_eq!(err.code, "INVALID_TOKEN");
+    }
+
+    #[test]
+    fn test_parse_role_mappings() {
+        let mappings =
+            OidcConfig::parse_role_mappings("platform-admins=admin, ml-team=Operator:ml").unwrap();
+        assert_eq!(
+            mappings,
+            vec![
+                ClaimRoleMapping {
+                    value: "platform-admins".to_string(),
+                    role: Role::Admin,
+                    namespace: None,
+                },
+                ClaimRoleMapping {
+                    value: "ml-team".to_string(),
+                    role: Role::Operator,
+                    namespace: Some("ml".to_string()),
+                },
+            ]
+        );
+        assert!(OidcConfig::parse_role_mappings("ml-team").is_err());
+        assert!(OidcConfig::parse_role_mappings("=admin").is_err());
+        assert!(OidcConfig::parse_role_mappings("ml-team=owner").is_err());
+    }
+
+    #[test]
+    fn test_claims_map_to_roles() {
+        let config = OidcConfig::new("https://sso.example.com/realms/ml", "orchestrator")
+            .with_roles_claim("realm_access.roles")
+            .with_role_mapping("ml-team=operator:ml".parse().unwrap())
+            .with_role_mapping("platform-admins=admin".parse().unwrap());
+        let claims = serde_json::json!({
+            "sub": "alex",
+            "exp": 1_900_000_000,
+            "realm_access": {"roles": ["ml-team", "offline_access"]},
+        });
+
+        let identity = config.identity(claims.as_object().unwrap()).unwrap();
+        assert_eq!(identity.subject, "alex");
+        assert_eq!(
+            identity.bindings,
+            vec![RoleBinding {
+                subject: "alex".to_string(),
+                role: Role::Operator,
+                namespace: Some("ml".to_string()),
+            }]
+        );
+
+        let config = config.with_roles_claim("groups");
+        let identity = config.identity(claims.as_object().unwrap()).unwrap();
+        assert!(identity.bindings.is_empty());
+
+        let no_subject = serde_json::json!({"exp": 1_900_000_000});
+        assert!(config.identity(no_subject.as_object().unwrap()).is_err());
+    }
+}
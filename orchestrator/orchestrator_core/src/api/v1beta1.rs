@@ -0,0 +1,321 @@
+//! The deprecated `v1beta1` API group.
+//!
+//! v1beta1 predates init containers, sidecars and placement rules, and names a
+//! few fields differently: containers carry `env` and `resources`, and
+//! workloads choose a `strategy`. Requests are converted to their v1 form and
+//! handled by the v1 code paths; results are converted back.
+//!
+//! Every response carries `Deprecation: true` and a `Warning` pointing at
+//! `/api/v1`, plus one `Warning` for each renamed field the request set.
+//!
+//! v1beta1 has no `resource_version`: an update applies to the workload as
+//! stored when the request arrives, and fails with 409 only if it changes
+//! while the request is handled.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Request, State},
+    http::{
+        header::{HeaderName, HeaderValue, WARNING},
+        StatusCode,
+    },
+    middleware::Next,
+    response::{AppendHeaders, IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use orchestrator_shared_types::WorkloadDefinition;
+
+use super::error::{ApiError, ApiResult};
+use super::handlers::{
+    self, ContainerConfigRequest, CreateWorkloadRequest, ListResponse, PortMappingRequest,
+    PortMappingResponse, ResourceRequestsRequest, ResourceRequestsResponse,
+};
+use super::state::ApiState;
+
+/// Header marking a response as coming from a deprecated API.
+pub const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+
+const VERSION_WARNING: &str = "299 - \"API version v1beta1 is deprecated; use /api/v1\"";
+
+/// v1beta1 workload request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkloadRequest {
+    pub name: String,
+    pub containers: Vec<ContainerRequest>,
+    pub replicas: u32,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub node_selector: HashMap<String, String>,
+    /// Renamed `scheduling_strategy` in v1.
+    #[serde(default)]
+    pub strategy: Option<String>,
+}
+
+/// v1beta1 container configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerRequest {
+    pub name: String,
+    pub image: String,
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+    #[serde(default)]
+    pub args: Option<Vec<String>>,
+    /// Renamed `env_vars` in v1.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub ports: Vec<PortMappingRequest>,
+    /// Renamed `resource_requests` in v1.
+    #[serde(default)]
+    pub resources: ResourceRequestsRequest,
+}
+
+/// v1beta1 workload response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkloadResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub replicas: u32,
+    pub labels: HashMap<String, String>,
+    pub containers: Vec<ContainerResponse>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub node_selector: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<String>,
+}
+
+/// v1beta1 container configuration in responses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerResponse {
+    pub name: String,
+    pub image: String,
+    pub command: Option<Vec<String>>,
+    pub args: Option<Vec<String>>,
+    pub env: HashMap<String, String>,
+    pub ports: Vec<PortMappingResponse>,
+    pub resources: ResourceRequestsResponse,
+}
+
+impl WorkloadRequest {
+    /// Convert to the v1 request, along with a warning for each renamed field
+    /// the request set.
+    pub fn into_v1(self) -> (CreateWorkloadRequest, Vec<String>) {
+        let mut deprecated = Vec::new();
+        if self.strategy.is_some() {
+            deprecated.push(("strategy", "scheduling_strategy"));
+        }
+        if self.containers.iter().any(|c| !c.env.is_empty()) {
+            deprecated.push(("containers[].env", "env_vars"));
+        }
+        if self.containers.iter().any(|c| c.resources.is_set()) {
+            deprecated.push(("containers[].resources", "resource_requests"));
+        }
+        let warnings = deprecated
+            .into_iter()
+            .map(|(field, replacement)| {
+                format!(
+                    "299 - \"v1beta1 field {} is deprecated; use {} in /api/v1\"",
+                    field, replacement
+                )
+            })
+            .collect();
+
+        let request = CreateWorkloadRequest {
+            name: self.name,
+            containers: self.containers.into_iter().map(Into::into).collect(),
+            init_containers: Vec::new(),
+            sidecars: Vec::new(),
+            replicas: self.replicas,
+            labels: self.labels,
+            node_selector: self.node_selector,
+            node_affinity: None,
+            instance_anti_affinity: None,
+            scheduling_strategy: self.strategy,
+            priority: 0,
+            restart_policy: Default::default(),
+            namespace: None,
+            resource_version: None,
+        };
+        (request, warnings)
+    }
+}
+
+impl ResourceRequestsRequest {
+    fn is_set(&self) -> bool {
+        self.cpu_cores > 0.0 || self.memory_mb > 0 || self.disk_mb > 0
+    }
+}
+
+impl From<WorkloadRequest> for CreateWorkloadRequest {
+    fn from(req: WorkloadRequest) -> Self {
+        req.into_v1().0
+    }
+}
+
+impl From<ContainerRequest> for ContainerConfigRequest {
+    fn from(req: ContainerRequest) -> Self {
+        Self {
+            name: req.name,
+            image: req.image,
+            command: req.command,
+            args: req.args,
+            env_vars: req.env,
+            ports: req.ports,
+            resource_requests: req.resources,
+            readiness_probe: None,
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+            sysctls: HashMap::new(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
+        }
+    }
+}
+
+impl From<handlers::WorkloadResponse> for WorkloadResponse {
+    fn from(res: handlers::WorkloadResponse) -> Self {
+        Self {
+            id: res.id,
+            name: res.name,
+            replicas: res.replicas,
+            labels: res.labels,
+            containers: res.containers.into_iter().map(Into::into).collect(),
+            node_selector: res.placement.node_selector,
+            strategy: res.placement.scheduling_strategy,
+        }
+    }
+}
+
+impl From<handlers::ContainerConfigResponse> for ContainerResponse {
+    fn from(res: handlers::ContainerConfigResponse) -> Self {
+        Self {
+            name: res.name,
+            image: res.image,
+            command: res.command,
+            args: res.args,
+            env: res.env_vars,
+            ports: res.ports,
+            resources: res.resource_requests,
+        }
+    }
+}
+
+fn beta_response(def: WorkloadDefinition) -> WorkloadResponse {
+    handlers::WorkloadResponse::from(def).into()
+}
+
+fn warning_headers(warnings: Vec<String>) -> AppendHeaders<Vec<(HeaderName, String)>> {
+    AppendHeaders(warnings.into_iter().map(|w| (WARNING, w)).collect())
+}
+
+/// Mark every v1beta1 response as deprecated.
+pub async fn deprecation_layer(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(DEPRECATION, HeaderValue::from_static("true"));
+    headers.append(WARNING, HeaderValue::from_static(VERSION_WARNING));
+    response
+}
+
+/// Create a workload from a v1beta1 request.
+pub async fn create_workload(
+    State(state): State<ApiState>,
+    Json(request): Json<WorkloadRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let (request, warnings) = request.into_v1();
+    let workload = handlers::submit_workload(&state, request).await?;
+    Ok((
+        StatusCode::CREATED,
+        warning_headers(warnings),
+        Json(beta_response(workload)),
+    ))
+}
+
+/// List all workloads in v1beta1 form.
+pub async fn list_workloads(State(state): State<ApiState>) -> ApiResult<impl IntoResponse> {
+    let workloads = state
+        .state_store
+        .list_workloads()
+        .await
+        .map_err(ApiError::from)?;
+
+    let items: Vec<WorkloadResponse> = workloads.into_iter().map(beta_response).collect();
+
+    Ok(Json(ListResponse::new(items)))
+}
+
+/// Get a workload in v1beta1 form.
+pub async fn get_workload(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+) -> ApiResult<impl IntoResponse> {
+    let workload = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+
+    Ok(Json(beta_response(workload)))
+}
+
+/// Update a workload from a v1beta1 request, keeping the settings v1beta1
+/// cannot express.
+pub async fn update_workload(
+    State(state): State<ApiState>,
+    Path(workload_id): Path<Uuid>,
+    Json(request): Json<WorkloadRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let existing = state
+        .state_store
+        .get_workload(&workload_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::not_found("Workload", &workload_id.to_string()))?;
+
+    let (request, warnings) = request.into_v1();
+
+    let mut workload = WorkloadDefinition {
+        id: workload_id,
+        resource_version: existing.resource_version,
+        init_containers: existing.init_containers,
+        sidecars: existing.sidecars,
+        restart_policy: existing.restart_policy,
+        namespace: existing.namespace,
+        ..request.into()
+    };
+    workload.placement.node_affinity = existing.placement.node_affinity;
+    workload.placement.instance_anti_affinity = existing.placement.instance_anti_affinity;
+    workload.placement.priority = existing.placement.priority;
+    for container in &mut workload.containers {
+        let previous = existing
+            .containers
+            .iter()
+            .find(|previous| previous.name == container.name);
+        container.readiness_probe = previous.and_then(|previous| previous.readiness_probe.clone());
+        container.termination_grace_period_seconds =
+            previous.and_then(|previous| previous.termination_grace_period_seconds);
+        container.pre_stop = previous.and_then(|previous| previous.pre_stop.clone());
+        if let Some(previous) = previous {
+            container.sysctls = previous.sysctls.clone();
+            container.rlimits = previous.rlimits.clone();
+            container.shm_size_mb = previous.shm_size_mb;
+            container.hostname = previous.hostname.clone();
+            container.host_aliases = previous.host_aliases.clone();
+            container.dns = previous.dns.clone();
+            container.annotations = previous.annotations.clone();
+        }
+    }
+
+    let workload = handlers::resubmit_workload(&state, workload).await?;
+    Ok((warning_headers(warnings), Json(beta_response(workload))))
+}
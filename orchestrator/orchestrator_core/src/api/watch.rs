@@ -0,0 +1,68 @@
+//! `?watch=true` support for list endpoints.
+//!
+//! A watch answers with Server-Sent Events: one `ADDED` event per existing
+//! object, then one event per change as it happens. Each
+//! event is named after its type, its `id` is the change's resource version,
+//! and its data is the JSON [`WatchEvent`]. A watch that falls behind the
+//! store is closed; list again and re-watch.
+
+use std::convert::Infallible;
+
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use state_store_interface::{Versioned, WatchEvent, WatchEventType};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+use utoipa::IntoParams;
+
+/// Query parameters shared by watchable list endpoints.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WatchQuery {
+    /// Stream changes instead of returning a single list.
+    #[serde(default)]
+    pub watch: bool,
+}
+
+/// Stream `current` followed by the `changes` to objects that `keep` accepts,
+/// each converted to its response type.
+///
+/// Subscribe to `changes` before listing `current`, so no change falls
+/// between the two.
+pub(crate) fn sse<T, U>(
+    current: Vec<T>,
+    changes: mpsc::Receiver<WatchEvent<T>>,
+    keep: impl Fn(&T) -> bool + Send + 'static,
+    convert: impl Fn(T) -> U + Send + 'static,
+) -> Response
+where
+    T: Versioned + Send + 'static,
+    U: Serialize + Send + 'static,
+{
+    let current: Vec<WatchEvent<U>> = current
+        .into_iter()
+        .map(|object| {
+            let version = object.resource_version();
+            WatchEvent::new(WatchEventType::Added, convert(object), version)
+        })
+        .collect();
+    let changes = ReceiverStream::new(changes)
+        .filter(move |event| keep(&event.object))
+        .map(move |event| event.map(&convert));
+    let events = tokio_stream::iter(current)
+        .chain(changes)
+        .map(|event| Ok::<_, Infallible>(sse_event(&event)));
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+fn sse_event<T: Serialize>(event: &WatchEvent<T>) -> Event {
+    Event::default()
+        .event(event.event_type.as_str())
+        .id(event.resource_version.to_string())
+        .json_data(event)
+        .unwrap_or_else(|e| Event::default().comment(format!("failed to encode event: {}", e)))
+}
@@ -0,0 +1,421 @@
+//! External admission webhooks.
+//!
+//! Before a workload is created or updated it is posted to each configured
+//! webhook as an [`AdmissionReview`]. Mutating webhooks run first, in order,
+//! and may answer with a JSON Patch against the workload; validating webhooks
+//! run after the built-in admission checks and can only allow or deny. A
+//! denial reaches the client as `ADMISSION_DENIED` (403) with the webhook's
+//! message.
+//!
+//! A webhook that cannot be reached, times out, or answers with something
+//! other than an [`AdmissionResponse`] fails the request with
+//! `ADMISSION_WEBHOOK_FAILED` (500) under [`FailurePolicy::Fail`], or is
+//! skipped under [`FailurePolicy::Ignore`].
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+use orchestrator_shared_types::WorkloadDefinition;
+
+use super::error::{ApiError, ApiResult};
+use super::patch::JsonPatchBody;
+
+/// How long a webhook may take to answer unless configured otherwise.
+pub const DEFAULT_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether a webhook may change workloads or only judge them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookKind {
+    Validating,
+    Mutating,
+}
+
+/// What to do when a webhook cannot give an answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailurePolicy {
+    /// Reject the request.
+    #[default]
+    Fail,
+    /// Admit the request as if the webhook had allowed it.
+    Ignore,
+}
+
+/// An external webhook consulted on every workload write.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdmissionWebhook {
+    pub kind: WebhookKind,
+    pub url: String,
+    pub timeout: Duration,
+    pub failure_policy: FailurePolicy,
+}
+
+impl AdmissionWebhook {
+    pub fn validating(url: impl Into<String>) -> Self {
+        Self::new(WebhookKind::Validating, url)
+    }
+
+    pub fn mutating(url: impl Into<String>) -> Self {
+        Self::new(WebhookKind::Mutating, url)
+    }
+
+    fn new(kind: WebhookKind, url: impl Into<String>) -> Self {
+        Self {
+            kind,
+            url: url.into(),
+            timeout: DEFAULT_WEBHOOK_TIMEOUT,
+            failure_policy: FailurePolicy::default(),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Parse a semicolon-separated list of webhooks.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(';')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl FromStr for AdmissionWebhook {
+    type Err = String;
+
+    /// Parses `kind:url[,timeout=secs][,failure=fail|ignore]`, e.g.
+    /// `mutating:https://inject.example/mutate,timeout=3,failure=ignore`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, rest) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected kind:url, got '{}'", s))?;
+        let mut parts = rest.split(',').map(str::trim);
+        let url = parts.next().unwrap_or_default();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("webhook URL must be http(s), got '{}'", url));
+        }
+        let mut webhook = match kind.trim() {
+            "validating" => Self::validating(url),
+            "mutating" => Self::mutating(url),
+            other => {
+                return Err(format!(
+                    "unknown webhook kind '{}' (expected validating or mutating)",
+                    other
+                ))
+            }
+        };
+
+        for option in parts.filter(|p| !p.is_empty()) {
+            match option.split_once('=') {
+                Some(("timeout", secs)) => {
+                    let secs: u64 = secs
+                        .parse()
+                        .map_err(|_| format!("invalid webhook timeout '{}'", secs))?;
+                    webhook.timeout = Duration::from_secs(secs.max(1));
+                }
+                Some(("failure", "fail")) => webhook.failure_policy = FailurePolicy::Fail,
+                Some(("failure", "ignore")) => webhook.failure_policy = FailurePolicy::Ignore,
+                _ => return Err(format!("unknown webhook option '{}'", option)),
+            }
+        }
+        Ok(webhook)
+    }
+}
+
+/// The write a review is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AdmissionOperation {
+    Create,
+    Update,
+}
+
+/// Body posted to a webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdmissionReview {
+    /// Identifies this review in the webhook's logs.
+    pub uid: Uuid,
+    pub operation: AdmissionOperation,
+    /// The workload as it would be stored.
+    pub workload: WorkloadDefinition,
+    /// The stored workload an update replaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_workload: Option<WorkloadDefinition>,
+}
+
+/// A webhook's answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdmissionResponse {
+    pub allowed: bool,
+    /// Why the workload was denied, shown to the client.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// JSON Patch to apply to the workload; honoured from mutating webhooks
+    /// only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<json_patch::Patch>,
+}
+
+/// The webhooks configured for a cluster.
+pub struct AdmissionWebhooks {
+    webhooks: Vec<AdmissionWebhook>,
+    client: reqwest::Client,
+}
+
+impl AdmissionWebhooks {
+    pub fn new(webhooks: Vec<AdmissionWebhook>) -> Self {
+        Self {
+            webhooks,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn webhooks(&self) -> &[AdmissionWebhook] {
+        &self.webhooks
+    }
+
+    /// Run the mutating webhooks over `workload` in order, applying their
+    /// patches. `old` is the stored workload of an update.
+    pub async fn mutate(
+        &self,
+        workload: &mut WorkloadDefinition,
+        old: Option<&WorkloadDefinition>,
+    ) -> ApiResult<()> {
+        for webhook in self.of_kind(WebhookKind::Mutating) {
+            let Some(response) = self.review(webhook, workload, old).await? else {
+                continue;
+            };
+            if !response.allowed {
+                return Err(denied(webhook, response.message));
+            }
+            let Some(patch) = response.patch else {
+                continue;
+            };
+            let patched = JsonPatchBody::Patch(patch).apply(&*workload).and_then(
+                |patched: WorkloadDefinition| {
+                    if patched.id != workload.id || patched.namespace != workload.namespace {
+                        Err(ApiError::bad_request("patch changes the id or namespace"))
+                    } else {
+                        Ok(patched)
+                    }
+                },
+            );
+            match patched {
+                Ok(patched) => *workload = patched,
+                Err(e) => fail(webhook, &e.error)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Ask the validating webhooks whether `workload` may be stored.
+    pub async fn validate(
+        &self,
+        workload: &WorkloadDefinition,
+        old: Option<&WorkloadDefinition>,
+    ) -> ApiResult<()> {
+        for webhook in self.of_kind(WebhookKind::Validating) {
+            if let Some(response) = self.review(webhook, workload, old).await? {
+                if !response.allowed {
+                    return Err(denied(webhook, response.message));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn of_kind(&self, kind: WebhookKind) -> impl Iterator<Item = &AdmissionWebhook> {
+        self.webhooks.iter().filter(move |w| w.kind == kind)
+    }
+
+    /// Post a review to `webhook`. Returns `None` if it failed and its
+    /// failure policy ignores that.
+    async fn review(
+        &self,
+        webhook: &AdmissionWebhook,
+        workload: &WorkloadDefinition,
+        old: Option<&WorkloadDefinition>,
+    ) -> ApiResult<Option<AdmissionResponse>> {
+        let review = AdmissionReview {
+            uid: Uuid::new_v4(),
+            operation: match old {
+                Some(_) => AdmissionOperation::Update,
+                None => AdmissionOperation::Create,
+            },
+            workload: workload.clone(),
+            old_workload: old.cloned(),
+        };
+        let mut request = self.client.post(&webhook.url).timeout(webhook.timeout);
+        if let Some(traceparent) = crate::current_traceparent() {
+            request = request.header("traceparent", traceparent);
+        }
+        let result = async {
+            request
+                .json(&review)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<AdmissionResponse>()
+                .await
+        }
+        .await;
+
+        match result {
+            Ok(response) => Ok(Some(response)),
+            Err(e) => fail(webhook, &e.to_string()).map(|()| None),
+        }
+    }
+}
+
+/// Apply `webhook`'s failure policy to an error calling it.
+fn fail(webhook: &AdmissionWebhook, error: &str) -> ApiResult<()> {
+    match webhook.failure_policy {
+        FailurePolicy::Ignore => {
+            warn!(
+                "Ignoring failed admission webhook {}: {}",
+                webhook.url, error
+            );
+            Ok(())
+        }
+        FailurePolicy::Fail => Err(ApiError::new(
+            format!("Admission webhook {} failed: {}", webhook.url, error),
+            "ADMISSION_WEBHOOK_FAILED",
+        )
+        .with_details(serde_json::json!({ "webhook": webhook.url }))),
+    }
+}
+
+fn denied(webhook: &AdmissionWebhook, message: Option<String>) -> ApiError {
+    ApiError::new(
+        format!(
+            "Denied by admission webhook {}: {}",
+            webhook.url,
+            message.as_deref().unwrap_or("no reason given")
+        ),
+        "ADMISSION_DENIED",
+    )
+    .with_details(serde_json::json!({ "webhook": webhook.url }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use axum::{routing::post, Json, Router};
+
+    /// Serve webhooks on a local port and return its base URL.
+    async fn serve() -> String {
+        let router = Router::new()
+            .route(
+                "/label",
+                post(|Json(review): Json<AdmissionReview>| async move {
+                    let op = match review.operation {
+                        AdmissionOperation::Create => "create",
+                        AdmissionOperation::Update => "update",
+                    };
+                    let patch = serde_json::json!([
+                        {"op": "add", "path": "/labels/injected", "value": op}
+                    ]);
+                    Json(serde_json::json!({"allowed": true, "patch": patch}))
+                }),
+            )
+            .route(
+                "/deny-unlabelled",
+                post(|Json(review): Json<AdmissionReview>| async move {
+                    let allowed = review.workload.labels.contains_key("team");
+                    Json(serde_json::json!({"allowed": allowed, "message": "team label required"}))
+                }),
+            )
+            .route(
+                "/slow",
+                post(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    Json(serde_json::json!({"allowed": true}))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_mutating_then_validating() {
+        let base = serve().await;
+        let webhooks = AdmissionWebhooks::new(vec![
+            AdmissionWebhook::validating(format!("{}/deny-unlabelled", base)),
+            AdmissionWebhook::mutating(format!("{}/label", base)),
+        ]);
+
+        let mut w = test_support::workload("web");
+        webhooks.mutate(&mut w, None).await.unwrap();
+        assert_eq!(w.labels["injected"], "create");
+        let old = w.clone();
+        webhooks.mutate(&mut w, Some(&old)).await.unwrap();
+        assert_eq!(w.labels["injected"], "update");
+
+        let err = webhooks.validate(&w, None).await.unwrap_err();
+        assert_eq!(err.code, "ADMISSION_DENIED");
+        assert!(err.error.contains("team label required"));
+
+        w.labels.insert("team".to_string(), "core".to_string());
+        assert!(webhooks.validate(&w, None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_failure_policy() {
+        let base = serve().await;
+        let slow = AdmissionWebhook::validating(format!("{}/slow", base))
+            .with_timeout(Duration::from_millis(100));
+        let unreachable = AdmissionWebhook::validating("http://127.0.0.1:1/validate");
+        let w = test_support::workload("web");
+
+        for webhook in [slow, unreachable] {
+            let failing = AdmissionWebhooks::new(vec![webhook.clone()]);
+            let err = failing.validate(&w, None).await.unwrap_err();
+            assert_eq!(err.code, "ADMISSION_WEBHOOK_FAILED");
+
+            let ignoring =
+                AdmissionWebhooks::new(vec![webhook.with_failure_policy(FailurePolicy::Ignore)]);
+            assert!(ignoring.validate(&w, None).await.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_parse_webhooks() {
+        let webhooks = AdmissionWebhook::parse_list(
+            "validating:https://policy.example/validate; \
+             mutating:http://inject:8443/mutate,timeout=3,failure=ignore",
+        )
+        .unwrap();
+        assert_eq!(
+            webhooks,
+            vec![
+                AdmissionWebhook::validating("https://policy.example/validate"),
+                AdmissionWebhook::mutating("http://inject:8443/mutate")
+                    .with_timeout(Duration::from_secs(3))
+                    .with_failure_policy(FailurePolicy::Ignore),
+            ]
+        );
+
+        assert!("https://policy.example/validate"
+            .parse::<AdmissionWebhook>()
+            .is_err());
+        assert!("auditing:https://x".parse::<AdmissionWebhook>().is_err());
+        assert!("validating:ftp://x".parse::<AdmissionWebhook>().is_err());
+        assert!("validating:https://x,retries=3"
+            .parse::<AdmissionWebhook>()
+            .is_err());
+    }
+}
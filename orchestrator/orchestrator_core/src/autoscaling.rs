@@ -0,0 +1,615 @@
+//! Replica forecasts for predictive autoscaling.
+//!
+//! A [`ScalingPredictor`] turns the recent history of a workload's load
+//! metric, e.g. requests per second or CPU cores summed over its replicas,
+//! into the number of replicas it will need. [`MovingAveragePredictor`]
+//! sizes the workload for the mean of the latest samples, and
+//! [`HttpPredictor`] (requires the `scaling-predictor` feature) asks a
+//! forecasting model served over HTTP.
+//!
+//! The [`Autoscaler`] runs on the leader and, on an interval, sizes every
+//! workload that opts in with a [`ScalingPolicy`] in its labels. It reads the
+//! workload's recent CPU usage, summed over its replicas, from a
+//! [`MetricSource`] such as [`PrometheusMetrics`] (requires the
+//! `scaling-predictor` feature), asks its predictor for a forecast and hands
+//! any change to a [`ReplicaScaler`]; the API's implementation scales the
+//! workload as `POST /api/v1/workloads/{id}/scale` does.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use orchestrator_shared_types::{OrchestrationError, Result, WorkloadDefinition, WorkloadId};
+use state_store_interface::StateStore;
+
+/// Default look-ahead of a forecast.
+pub const DEFAULT_HORIZON: Duration = Duration::from_secs(300);
+
+/// One sample of a workload's load metric.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricPoint {
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// What a predictor forecasts from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScalingContext {
+    pub workload_id: WorkloadId,
+    /// Name of the metric, e.g. "http_requests_per_second".
+    pub metric: String,
+    /// Samples of the metric across all replicas, oldest first.
+    pub history: Vec<MetricPoint>,
+    pub current_replicas: u32,
+    /// Load one replica should carry.
+    pub target_per_replica: f64,
+    pub min_replicas: u32,
+    pub max_replicas: u32,
+    /// How far ahead the forecast should look, in seconds.
+    pub horizon_secs: u64,
+}
+
+impl ScalingContext {
+    /// Replicas needed to carry `load`, within the workload's bounds.
+    pub fn replicas_for(&self, load: f64) -> u32 {
+        let needed = if self.target_per_replica > 0.0 && load.is_finite() {
+            (load.max(0.0) / self.target_per_replica).ceil() as u32
+        } else {
+            self.current_replicas
+        };
+        self.clamp(needed)
+    }
+
+    /// `replicas` within the workload's bounds.
+    pub fn clamp(&self, replicas: u32) -> u32 {
+        replicas.clamp(self.min_replicas, self.max_replicas.max(self.min_replicas))
+    }
+}
+
+/// A predictor's answer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicaForecast {
+    /// Replicas the workload should run.
+    pub replicas: u32,
+    /// Load the replicas were sized for, if the predictor reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub predicted_load: Option<f64>,
+}
+
+/// Forecasts how many replicas a workload needs.
+#[async_trait]
+pub trait ScalingPredictor: Send + Sync {
+    async fn forecast(&self, context: &ScalingContext) -> Result<ReplicaForecast>;
+}
+
+/// Sizes a workload for the mean of its latest samples.
+#[derive(Debug, Clone)]
+pub struct MovingAveragePredictor {
+    /// Samples averaged (default: 12, a few minutes of scrapes).
+    pub window: usize,
+}
+
+impl Default for MovingAveragePredictor {
+    fn default() -> Self {
+        Self { window: 12 }
+    }
+}
+
+#[async_trait]
+impl ScalingPredictor for MovingAveragePredictor {
+    async fn forecast(&self, context: &ScalingContext) -> Result<ReplicaForecast> {
+        let latest = &context.history[context.history.len().saturating_sub(self.window)..];
+        if latest.is_empty() {
+            return Ok(ReplicaForecast {
+                replicas: context.clamp(context.current_replicas),
+                predicted_load: None,
+            });
+        }
+        let mean = latest.iter().map(|p| p.value).sum::<f64>() / latest.len() as f64;
+        Ok(ReplicaForecast {
+            replicas: context.replicas_for(mean),
+            predicted_load: Some(mean),
+        })
+    }
+}
+
+/// Asks an external model for forecasts: POSTs the [`ScalingContext`] as
+/// JSON and expects a [`ReplicaForecast`] back, which is kept within the
+/// workload's bounds.
+#[cfg(feature = "scaling-predictor")]
+pub struct HttpPredictor {
+    client: reqwest::Client,
+    endpoint: String,
+}
+
+#[cfg(feature = "scaling-predictor")]
+impl HttpPredictor {
+    pub fn new(endpoint: impl Into<String>, timeout: Duration) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| {
+                OrchestrationError::ConfigError(format!("Failed to build predictor client: {}", e))
+            })?;
+        Ok(Self {
+            client,
+            endpoint: endpoint.into(),
+        })
+    }
+}
+
+#[cfg(feature = "scaling-predictor")]
+#[async_trait]
+impl ScalingPredictor for HttpPredictor {
+    async fn forecast(&self, context: &ScalingContext) -> Result<ReplicaForecast> {
+        let failed = |e: reqwest::Error| {
+            OrchestrationError::NetworkError(format!(
+                "Scaling predictor {} failed: {}",
+                self.endpoint, e
+            ))
+        };
+        let mut forecast: ReplicaForecast = self
+            .client
+            .post(&self.endpoint)
+            .json(context)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(failed)?
+            .json()
+            .await
+            .map_err(failed)?;
+        forecast.replicas = context.clamp(forecast.replicas);
+        Ok(forecast)
+    }
+}
+
+/// Label holding the CPU cores one replica should use; setting it enables
+/// autoscaling.
+pub const AUTOSCALE_TARGET_CPU_LABEL: &str = "autoscale-target-cpu";
+/// Label holding the fewest replicas the autoscaler keeps (default: 1).
+pub const AUTOSCALE_MIN_REPLICAS_LABEL: &str = "autoscale-min-replicas";
+/// Label holding the most replicas the autoscaler scales to; required with
+/// [`AUTOSCALE_TARGET_CPU_LABEL`].
+pub const AUTOSCALE_MAX_REPLICAS_LABEL: &str = "autoscale-max-replicas";
+
+/// Metric the autoscaler sizes workloads by.
+pub const CPU_METRIC: &str = "cpu_cores";
+
+/// How a workload wants to be autoscaled, as set in its labels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalingPolicy {
+    /// CPU cores one replica should use.
+    pub target_cpu: f64,
+    pub min_replicas: u32,
+    pub max_replicas: u32,
+}
+
+impl ScalingPolicy {
+    /// The policy in a workload's labels, or `None` when it does not opt in.
+    pub fn from_labels(labels: &HashMap<String, String>) -> Result<Option<Self>> {
+        let Some(target) = labels.get(AUTOSCALE_TARGET_CPU_LABEL) else {
+            return Ok(None);
+        };
+        let invalid = |label: &str, value: &str| {
+            OrchestrationError::ConfigError(format!("Invalid {} label: {:?}", label, value))
+        };
+        let target_cpu: f64 = target
+            .parse()
+            .ok()
+            .filter(|t: &f64| t.is_finite() && *t > 0.0)
+            .ok_or_else(|| invalid(AUTOSCALE_TARGET_CPU_LABEL, target))?;
+        let replicas = |label: &str| -> Result<Option<u32>> {
+            labels
+                .get(label)
+                .map(|v| v.parse().map_err(|_| invalid(label, v)))
+                .transpose()
+        };
+        let min_replicas = replicas(AUTOSCALE_MIN_REPLICAS_LABEL)?.unwrap_or(1);
+        let max_replicas = replicas(AUTOSCALE_MAX_REPLICAS_LABEL)?.ok_or_else(|| {
+            OrchestrationError::ConfigError(format!(
+                "{} requires the {} label",
+                AUTOSCALE_TARGET_CPU_LABEL, AUTOSCALE_MAX_REPLICAS_LABEL
+            ))
+        })?;
+        if max_replicas < min_replicas {
+            return Err(OrchestrationError::ConfigError(format!(
+                "{} is below {}",
+                AUTOSCALE_MAX_REPLICAS_LABEL, AUTOSCALE_MIN_REPLICAS_LABEL
+            )));
+        }
+        Ok(Some(Self {
+            target_cpu,
+            min_replicas,
+            max_replicas,
+        }))
+    }
+}
+
+/// Where the autoscaler reads workloads' load from.
+#[async_trait]
+pub trait MetricSource: Send + Sync {
+    /// CPU cores the workload used since `since`, summed over its replicas,
+    /// oldest first.
+    async fn history(
+        &self,
+        workload: &WorkloadDefinition,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<MetricPoint>>;
+}
+
+/// Reads CPU usage from the Prometheus server scraping the nodes' container
+/// metrics.
+#[cfg(feature = "scaling-predictor")]
+pub struct PrometheusMetrics {
+    client: reqwest::Client,
+    endpoint: String,
+    step: Duration,
+}
+
+#[cfg(feature = "scaling-predictor")]
+impl PrometheusMetrics {
+    /// `endpoint` is the server's base URL, e.g. "http://prometheus:9090".
+    pub fn new(endpoint: impl Into<String>, step: Duration, timeout: Duration) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| {
+                OrchestrationError::ConfigError(format!("Failed to build Prometheus client: {}", e))
+            })?;
+        Ok(Self {
+            client,
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+            step,
+        })
+    }
+
+    fn query(workload: &WorkloadDefinition) -> String {
+        format!(
+            "sum(rate(orchestrator_container_cpu_usage_seconds{{namespace={:?},workload={:?}}}[1m]))",
+            workload.namespace, workload.name
+        )
+    }
+}
+
+#[cfg(feature = "scaling-predictor")]
+#[derive(Deserialize)]
+struct QueryRangeResponse {
+    data: QueryRangeData,
+}
+
+#[cfg(feature = "scaling-predictor")]
+#[derive(Deserialize)]
+struct QueryRangeData {
+    result: Vec<QueryRangeSeries>,
+}
+
+#[cfg(feature = "scaling-predictor")]
+#[derive(Deserialize)]
+struct QueryRangeSeries {
+    values: Vec<(f64, String)>,
+}
+
+#[cfg(feature = "scaling-predictor")]
+#[async_trait]
+impl MetricSource for PrometheusMetrics {
+    async fn history(
+        &self,
+        workload: &WorkloadDefinition,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<MetricPoint>> {
+        let failed = |e: reqwest::Error| {
+            OrchestrationError::NetworkError(format!(
+                "Prometheus query to {} failed: {}",
+                self.endpoint, e
+            ))
+        };
+        let response: QueryRangeResponse = self
+            .client
+            .get(format!("{}/api/v1/query_range", self.endpoint))
+            .query(&[
+                ("query", Self::query(workload)),
+                ("start", since.timestamp().to_string()),
+                ("end", Utc::now().timestamp().to_string()),
+                ("step", format!("{}s", self.step.as_secs().max(1))),
+            ])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(failed)?
+            .json()
+            .await
+            .map_err(failed)?;
+
+        // A sum is a single series, absent when nothing was scraped
+        let values = response
+            .data
+            .result
+            .into_iter()
+            .next()
+            .map(|series| series.values)
+            .unwrap_or_default();
+        Ok(values
+            .into_iter()
+            .filter_map(|(seconds, value)| {
+                Some(MetricPoint {
+                    timestamp: DateTime::from_timestamp_millis((seconds * 1000.0) as i64)?,
+                    value: value.parse().ok()?,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Applies the autoscaler's decisions.
+#[async_trait]
+pub trait ReplicaScaler: Send + Sync {
+    /// Set the workload's replica count, whatever version it is at.
+    async fn scale(&self, workload_id: &WorkloadId, replicas: u32) -> Result<()>;
+}
+
+/// Periodically resizes the workloads with a [`ScalingPolicy`] to the
+/// replicas its predictor forecasts.
+pub struct Autoscaler {
+    state_store: Arc<dyn StateStore>,
+    metrics: Arc<dyn MetricSource>,
+    predictor: Arc<dyn ScalingPredictor>,
+    scaler: Arc<dyn ReplicaScaler>,
+    lookback: Duration,
+}
+
+impl Autoscaler {
+    /// How often [`spawn`](Self::spawn) evaluates when run with defaults.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+    /// History a forecast is made from by default.
+    pub const DEFAULT_LOOKBACK: Duration = Duration::from_secs(600);
+
+    pub fn new(
+        state_store: Arc<dyn StateStore>,
+        metrics: Arc<dyn MetricSource>,
+        predictor: Arc<dyn ScalingPredictor>,
+        scaler: Arc<dyn ReplicaScaler>,
+    ) -> Self {
+        Self {
+            state_store,
+            metrics,
+            predictor,
+            scaler,
+            lookback: Self::DEFAULT_LOOKBACK,
+        }
+    }
+
+    /// Forecast from `lookback` of history.
+    pub fn with_lookback(mut self, lookback: Duration) -> Self {
+        self.lookback = lookback;
+        self
+    }
+
+    /// Resize every autoscaled workload once, returning how many were scaled.
+    /// A workload that fails is logged and skipped.
+    pub async fn evaluate(&self) -> Result<usize> {
+        let since = Utc::now() - chrono::Duration::from_std(self.lookback).unwrap_or_default();
+        let mut scaled = 0;
+        for workload in self.state_store.list_workloads().await? {
+            let policy = match ScalingPolicy::from_labels(&workload.labels) {
+                Ok(Some(policy)) => policy,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Not autoscaling workload {}: {}", workload.id, e);
+                    continue;
+                }
+            };
+            match self.evaluate_workload(&workload, &policy, since).await {
+                Ok(true) => scaled += 1,
+                Ok(false) => {}
+                Err(e) => warn!("Failed to autoscale workload {}: {}", workload.id, e),
+            }
+        }
+        Ok(scaled)
+    }
+
+    async fn evaluate_workload(
+        &self,
+        workload: &WorkloadDefinition,
+        policy: &ScalingPolicy,
+        since: DateTime<Utc>,
+    ) -> Result<bool> {
+        let context = ScalingContext {
+            workload_id: workload.id,
+            metric: CPU_METRIC.to_string(),
+            history: self.metrics.history(workload, since).await?,
+            current_replicas: workload.replicas,
+            target_per_replica: policy.target_cpu,
+            min_replicas: policy.min_replicas,
+            max_replicas: policy.max_replicas,
+            horizon_secs: DEFAULT_HORIZON.as_secs(),
+        };
+        let replicas = context.clamp(self.predictor.forecast(&context).await?.replicas);
+        if replicas == workload.replicas {
+            return Ok(false);
+        }
+        self.scaler.scale(&workload.id, replicas).await?;
+        info!(
+            workload = %workload.id,
+            from = workload.replicas,
+            to = replicas,
+            "Autoscaled workload"
+        );
+        Ok(true)
+    }
+
+    /// Evaluate every `interval` until the task is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.evaluate().await {
+                    error!("Autoscaling failed: {:?}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    fn context(values: &[f64]) -> ScalingContext {
+        let start = Utc::now();
+        ScalingContext {
+            workload_id: Uuid::new_v4(),
+            metric: "http_requests_per_second".to_string(),
+            history: values
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| MetricPoint {
+                    timestamp: start + chrono::Duration::seconds(15 * i as i64),
+                    value,
+                })
+                .collect(),
+            current_replicas: 3,
+            target_per_replica: 100.0,
+            min_replicas: 2,
+            max_replicas: 10,
+            horizon_secs: DEFAULT_HORIZON.as_secs(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_moving_average_forecast() {
+        let predictor = MovingAveragePredictor { window: 3 };
+
+        // Only the last three samples count: (400 + 500 + 600) / 3 = 500
+        let forecast = predictor
+            .forecast(&context(&[10.0, 400.0, 500.0, 600.0]))
+            .await
+            .unwrap();
+        assert_eq!(forecast.replicas, 5);
+        assert_eq!(forecast.predicted_load, Some(500.0));
+
+        // Kept within the bounds
+        let forecast = predictor.forecast(&context(&[5000.0])).await.unwrap();
+        assert_eq!(forecast.replicas, 10);
+        let forecast = predictor.forecast(&context(&[0.0])).await.unwrap();
+        assert_eq!(forecast.replicas, 2);
+
+        // No history keeps the current count
+        let forecast = predictor.forecast(&context(&[])).await.unwrap();
+        assert_eq!(forecast.replicas, 3);
+        assert_eq!(forecast.predicted_load, None);
+    }
+
+    fn workload(replicas: u32, labels: &[(&str, &str)]) -> WorkloadDefinition {
+        WorkloadDefinition {
+            replicas,
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..test_support::workload("web")
+        }
+    }
+
+    #[test]
+    fn test_scaling_policy_from_labels() {
+        let policy =
+            |labels: &[(&str, &str)]| ScalingPolicy::from_labels(&workload(1, labels).labels);
+
+        assert_eq!(policy(&[]).unwrap(), None);
+        assert_eq!(
+            policy(&[
+                (AUTOSCALE_TARGET_CPU_LABEL, "0.5"),
+                (AUTOSCALE_MAX_REPLICAS_LABEL, "8"),
+            ])
+            .unwrap(),
+            Some(ScalingPolicy {
+                target_cpu: 0.5,
+                min_replicas: 1,
+                max_replicas: 8,
+            })
+        );
+
+        // Without a maximum, or with bounds the wrong way round
+        assert!(policy(&[(AUTOSCALE_TARGET_CPU_LABEL, "0.5")]).is_err());
+        assert!(policy(&[
+            (AUTOSCALE_TARGET_CPU_LABEL, "0.5"),
+            (AUTOSCALE_MIN_REPLICAS_LABEL, "4"),
+            (AUTOSCALE_MAX_REPLICAS_LABEL, "2"),
+        ])
+        .is_err());
+        assert!(policy(&[
+            (AUTOSCALE_TARGET_CPU_LABEL, "0"),
+            (AUTOSCALE_MAX_REPLICAS_LABEL, "2"),
+        ])
+        .is_err());
+    }
+
+    /// Every workload used the same CPU for the whole lookback.
+    struct FlatMetrics(f64);
+
+    #[async_trait]
+    impl MetricSource for FlatMetrics {
+        async fn history(
+            &self,
+            _workload: &WorkloadDefinition,
+            since: DateTime<Utc>,
+        ) -> Result<Vec<MetricPoint>> {
+            Ok(vec![MetricPoint {
+                timestamp: since,
+                value: self.0,
+            }])
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingScaler {
+        scaled: Mutex<Vec<(WorkloadId, u32)>>,
+    }
+
+    #[async_trait]
+    impl ReplicaScaler for RecordingScaler {
+        async fn scale(&self, workload_id: &WorkloadId, replicas: u32) -> Result<()> {
+            self.scaled.lock().unwrap().push((*workload_id, replicas));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_autoscaler_scales_labeled_workloads_to_the_forecast() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let labels = [
+            (AUTOSCALE_TARGET_CPU_LABEL, "0.5"),
+            (AUTOSCALE_MIN_REPLICAS_LABEL, "2"),
+            (AUTOSCALE_MAX_REPLICAS_LABEL, "6"),
+        ];
+        let autoscaled = workload(2, &labels);
+        let sized = workload(4, &labels);
+        let unlabeled = workload(1, &[]);
+        for w in [&autoscaled, &sized, &unlabeled] {
+            store.put_workload(w.clone()).await.unwrap();
+        }
+        let scaler = Arc::new(RecordingScaler::default());
+        let autoscaler = Autoscaler::new(
+            store,
+            Arc::new(FlatMetrics(1.8)),
+            Arc::new(MovingAveragePredictor::default()),
+            scaler.clone(),
+        );
+
+        // 1.8 cores at 0.5 per replica need 4 replicas
+        assert_eq!(autoscaler.evaluate().await.unwrap(), 1);
+        assert_eq!(*scaler.scaled.lock().unwrap(), vec![(autoscaled.id, 4)]);
+    }
+}
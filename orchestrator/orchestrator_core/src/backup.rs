@@ -0,0 +1,378 @@
+//! Cluster state backup and restore.
+//!
+//! A [`ClusterBackup`] is a point-in-time copy of everything the control plane
+//! stores: namespaces, nodes, workloads and instances, workload revisions,
+//! events, usage records, and the objects controllers keep as resources of
+//! the [`resources::KINDS`]. It serializes to a single JSON document carrying
+//! a format version, so a backup taken by one release can be checked before
+//! another release restores it. Kinds a store does not implement are backed
+//! up as empty.
+//!
+//! Restore only writes into an empty store, which keeps it from silently
+//! merging two clusters. Resource versions are not preserved; the target store
+//! assigns its own as objects are written.
+//!
+//! Secrets are out of scope: the control plane has no secret objects to back
+//! up. Values imported from Kubernetes Secrets are inlined into workload
+//! environments and so are part of the backup, which must be kept as safe as
+//! the secrets themselves.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use orchestrator_shared_types::{
+    Event, Namespace, Node, OrchestrationError, Result, UsageRecord, WorkloadDefinition,
+    WorkloadInstance, WorkloadRevision,
+};
+use state_store_interface::StateStore;
+
+use crate::resources;
+
+/// Version of the backup format written by this release.
+///
+/// Version 2 added revisions, events, usage records and resources; version 1
+/// backups restore without them.
+pub const BACKUP_FORMAT_VERSION: u32 = 2;
+
+/// Point-in-time copy of the cluster state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterBackup {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Version of the orchestrator that took the backup.
+    pub orchestrator_version: String,
+    /// Absent from backups taken before namespaces existed.
+    #[serde(default)]
+    pub namespaces: Vec<Namespace>,
+    pub nodes: Vec<Node>,
+    pub workloads: Vec<WorkloadDefinition>,
+    pub instances: Vec<WorkloadInstance>,
+    #[serde(default)]
+    pub revisions: Vec<WorkloadRevision>,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    #[serde(default)]
+    pub usage_records: Vec<UsageRecord>,
+    #[serde(default)]
+    pub resources: Vec<StoredResource>,
+}
+
+/// One document of the state store's resource API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredResource {
+    pub kind: String,
+    pub name: String,
+    pub data: serde_json::Value,
+}
+
+/// Number of objects written by a restore.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RestoreSummary {
+    pub namespaces: usize,
+    pub nodes: usize,
+    pub workloads: usize,
+    pub instances: usize,
+    pub revisions: usize,
+    pub events: usize,
+    pub usage_records: usize,
+    pub resources: usize,
+}
+
+/// Treat a kind the store does not implement as empty.
+fn optional<T>(result: Result<Vec<T>>) -> Result<Vec<T>> {
+    match result {
+        Err(OrchestrationError::NotImplemented(_)) => Ok(vec![]),
+        result => result,
+    }
+}
+
+impl ClusterBackup {
+    /// Copy the current contents of `store`.
+    pub async fn snapshot(store: &dyn StateStore) -> Result<Self> {
+        let workloads = store.list_workloads().await?;
+        let mut revisions = Vec::new();
+        for workload in &workloads {
+            revisions.extend(optional(store.list_workload_revisions(&workload.id).await)?);
+        }
+        let mut stored = Vec::new();
+        for kind in resources::KINDS {
+            for data in optional(store.list_resources(kind.kind).await)? {
+                stored.push(StoredResource {
+                    kind: kind.kind.to_string(),
+                    name: (kind.key)(&data)?,
+                    data,
+                });
+            }
+        }
+        Ok(Self {
+            format_version: BACKUP_FORMAT_VERSION,
+            created_at: Utc::now(),
+            orchestrator_version: env!("CARGO_PKG_VERSION").to_string(),
+            namespaces: store.list_namespaces().await?,
+            nodes: store.list_nodes().await?,
+            workloads,
+            instances: store.list_all_instances().await?,
+            revisions,
+            events: optional(store.list_events().await)?,
+            usage_records: optional(store.list_usage_records(0, u64::MAX).await)?,
+            resources: stored,
+        })
+    }
+
+    /// Write the backup into `store`, which must hold no namespaces, nodes,
+    /// workloads, instances or resources.
+    pub async fn restore(self, store: &dyn StateStore) -> Result<RestoreSummary> {
+        if self.format_version > BACKUP_FORMAT_VERSION {
+            return Err(OrchestrationError::ConfigError(format!(
+                "backup format version {} is newer than supported version {}",
+                self.format_version, BACKUP_FORMAT_VERSION
+            )));
+        }
+        let mut has_resources = false;
+        for kind in resources::KINDS {
+            has_resources |= !optional(store.list_resources(kind.kind).await)?.is_empty();
+        }
+        if has_resources
+            || !store.list_namespaces().await?.is_empty()
+            || !store.list_nodes().await?.is_empty()
+            || !store.list_workloads().await?.is_empty()
+            || !store.list_all_instances().await?.is_empty()
+        {
+            return Err(OrchestrationError::Conflict(
+                "cluster state is not empty; restore requires a fresh control plane".to_string(),
+            ));
+        }
+
+        let summary = RestoreSummary {
+            namespaces: self.namespaces.len(),
+            nodes: self.nodes.len(),
+            workloads: self.workloads.len(),
+            instances: self.instances.len(),
+            revisions: self.revisions.len(),
+            events: self.events.len(),
+            usage_records: self.usage_records.len(),
+            resources: self.resources.len(),
+        };
+        for namespace in self.namespaces {
+            store.put_namespace(namespace).await?;
+        }
+        for node in self.nodes {
+            store.put_node(node).await?;
+        }
+        for workload in self.workloads {
+            store.put_workload(workload).await?;
+        }
+        store.put_instances_batch(self.instances).await?;
+        for revision in self.revisions {
+            store.put_workload_revision(revision).await?;
+        }
+        for event in self.events {
+            store.put_event(event).await?;
+        }
+        for record in self.usage_records {
+            store.put_usage_record(record).await?;
+        }
+        for resource in self.resources {
+            store
+                .put_resource(&resource.kind, &resource.name, resource.data)
+                .await?;
+        }
+
+        info!(
+            "Restored backup taken at {}: {} namespaces, {} nodes, {} workloads, {} instances, \
+             {} revisions, {} events, {} usage records, {} resources",
+            self.created_at,
+            summary.namespaces,
+            summary.nodes,
+            summary.workloads,
+            summary.instances,
+            summary.revisions,
+            summary.events,
+            summary.usage_records,
+            summary.resources
+        );
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controllers::DaemonSet;
+    use crate::test_support;
+    use orchestrator_shared_types::{
+        EventType, Keypair, NodeId, NodeResources, NodeStatus, ObjectReference,
+        WorkloadInstanceStatus,
+    };
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn node(id: NodeId) -> Node {
+        Node {
+            id,
+            address: "10.0.0.1:8080".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            resources_capacity: NodeResources::default(),
+            resources_allocatable: NodeResources::default(),
+            unschedulable: false,
+            resource_version: 0,
+        }
+    }
+
+    async fn populated_store() -> InMemoryStateStore {
+        let store = InMemoryStateStore::new();
+        store.put_namespace(Namespace::new("team-a")).await.unwrap();
+        let node_id = Keypair::generate().public_key();
+        store.put_node(node(node_id)).await.unwrap();
+        let web = test_support::workload("web");
+        store.put_workload(web.clone()).await.unwrap();
+        store
+            .put_instance(WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id: web.id,
+                node_id,
+                container_ids: vec!["c1".to_string()],
+                status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
+            })
+            .await
+            .unwrap();
+        store
+            .put_workload_revision(WorkloadRevision {
+                workload_id: web.id,
+                revision: 1,
+                workload: web.clone(),
+                created_at_ms: 1,
+            })
+            .await
+            .unwrap();
+        store
+            .put_event(Event {
+                id: Uuid::new_v4(),
+                involved_object: ObjectReference::workload(&web),
+                namespace: "default".to_string(),
+                event_type: EventType::Normal,
+                reason: "Scheduled".to_string(),
+                message: "Assigned".to_string(),
+                source: "orchestrator".to_string(),
+                count: 1,
+                first_timestamp_ms: 1,
+                last_timestamp_ms: 1,
+            })
+            .await
+            .unwrap();
+        store
+            .put_usage_record(UsageRecord {
+                workload_id: web.id,
+                workload_name: web.name.clone(),
+                namespace: "default".to_string(),
+                period_start_ms: 0,
+                cpu_core_seconds: 3600.0,
+                memory_gb_seconds: 0.0,
+                gpu_seconds: 0.0,
+            })
+            .await
+            .unwrap();
+        resources::put(&store, &DaemonSet::new("agent", web))
+            .await
+            .unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn test_backup_round_trip() {
+        let source = populated_store().await;
+        let backup = ClusterBackup::snapshot(&source).await.unwrap();
+        assert_eq!(backup.format_version, BACKUP_FORMAT_VERSION);
+
+        // Through JSON, as the API and CLI move it
+        let json = serde_json::to_string(&backup).unwrap();
+        let backup: ClusterBackup = serde_json::from_str(&json).unwrap();
+
+        let target = InMemoryStateStore::new();
+        let summary = backup.restore(&target).await.unwrap();
+        assert_eq!(
+            summary,
+            RestoreSummary {
+                namespaces: 1,
+                nodes: 1,
+                workloads: 1,
+                instances: 1,
+                revisions: 1,
+                events: 1,
+                usage_records: 1,
+                resources: 1,
+            }
+        );
+
+        let workloads = target.list_workloads().await.unwrap();
+        assert_eq!(
+            workloads[0].id,
+            source.list_workloads().await.unwrap()[0].id
+        );
+        let instances = target.list_all_instances().await.unwrap();
+        assert_eq!(instances[0].container_ids, vec!["c1".to_string()]);
+        assert_eq!(target.list_nodes().await.unwrap().len(), 1);
+        assert!(target.get_namespace("team-a").await.unwrap().is_some());
+        assert_eq!(
+            target
+                .list_workload_revisions(&workloads[0].id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(target.list_events().await.unwrap().len(), 1);
+        assert_eq!(
+            target.list_usage_records(0, u64::MAX).await.unwrap().len(),
+            1
+        );
+        let daemon_sets = resources::list::<DaemonSet>(&target).await.unwrap();
+        assert_eq!(daemon_sets[0].name, "agent");
+        assert_eq!(
+            resources::get::<DaemonSet>(&target, &daemon_sets[0].id.to_string())
+                .await
+                .unwrap()
+                .unwrap()
+                .name,
+            "agent"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restore_version_1_backup() {
+        let source = populated_store().await;
+        let backup = ClusterBackup::snapshot(&source).await.unwrap();
+        let mut json = serde_json::to_value(backup).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        for field in ["revisions", "events", "usage_records", "resources"] {
+            fields.remove(field);
+        }
+        fields.insert("format_version".to_string(), 1.into());
+
+        let backup: ClusterBackup = serde_json::from_value(json).unwrap();
+        let summary = backup.restore(&InMemoryStateStore::new()).await.unwrap();
+        assert_eq!(summary.workloads, 1);
+        assert_eq!(summary.resources, 0);
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_non_empty_store_and_newer_format() {
+        let source = populated_store().await;
+        let backup = ClusterBackup::snapshot(&source).await.unwrap();
+
+        let err = backup.clone().restore(&source).await.unwrap_err();
+        assert!(matches!(err, OrchestrationError::Conflict(_)));
+
+        let mut newer = backup;
+        newer.format_version = BACKUP_FORMAT_VERSION + 1;
+        let err = newer.restore(&InMemoryStateStore::new()).await.unwrap_err();
+        assert!(matches!(err, OrchestrationError::ConfigError(_)));
+    }
+}
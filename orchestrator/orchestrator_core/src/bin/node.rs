@@ -7,22 +7,182 @@
 //!
 //! - `NODE_ID`: Unique node identifier (UUID, auto-generated if not set)
 //! - `NODE_ROLE`: Node role: "bootstrap" or "worker" (default: "worker")
-//! - `LISTEN_ADDR`: Address to bind for gossip (default: "0.0.0.0:7280")
+//! - `LISTEN_ADDR`: Address to bind for gossip (default: "0.0.0.0:7280", or e.g. "[::]:7280" for IPv6)
 //! - `PUBLIC_ADDR`: Address to advertise to peers (default: same as LISTEN_ADDR)
 //! - `SEED_NODES`: Comma-separated list of seed node addresses (required for workers)
 //! - `CLUSTER_ID`: Cluster identifier (default: "orchestrator-cluster")
-//! - `API_PORT`: HTTP API port for REST API and health/metrics (default: 9090)
-//! - `NODE_CPU`: CPU cores capacity (default: 4.0)
-//! - `NODE_MEMORY_MB`: Memory capacity in MB (default: 8192)
-//! - `NODE_DISK_MB`: Disk capacity in MB (default: 102400)
+//! - `API_PORT`: HTTP API port for REST API and health/metrics (default: 9090).
+//!   Bound on the same address family as `LISTEN_ADDR`
+//! - `NODE_CPU`: CPU cores capacity (default: detected, within cgroup limits)
+//! - `NODE_MEMORY_MB`: Memory capacity in MB (default: detected, within cgroup limits)
+//! - `NODE_DISK_MB`: Disk capacity in MB (default: size of the filesystem holding `BUNDLE_ROOT`)
+//! - `NODE_GPUS`: GPUs reported as the "orchestrator/gpus" label (default: detected NVIDIA GPUs)
+//! - `NODE_GPU_DEVICES`: How each GPU is shared, in index order, e.g.
+//!   "exclusive,mps,mig:3g.40gb+3g.40gb"; sets the GPU count (default: all exclusive)
+//! - `SYSTEM_RESERVED`: Held back from instances for the system, e.g.
+//!   "cpu=0.5,memory_mb=1024,disk_mb=10240" (default: 10% of capacity)
+//! - `NODE_LABELS`: Comma-separated labels the node reports, e.g. "zone=eu-1,disk=ssd"
+//!   (default: unset)
 //! - `LOG_LEVEL`: Log level (default: "info")
 //! - `LOG_JSON`: Use JSON log format (default: false)
 //! - `AUTH_DISABLED`: Disable Ed25519 request authentication (default: true for dev)
+//! - `RBAC_BINDINGS`: Comma-separated role bindings `KEY:ROLE[:NAMESPACE]`, where ROLE is
+//!   admin, operator or viewer; once set, API requests no binding allows are denied
+//!   (requires `AUTH_DISABLED=false`; default: unset, any authenticated key may do anything)
+//! - `AUTH_TOKEN_SECRET`: Secret for signing bearer tokens handed out to `orch login`,
+//!   shared by every control-plane instance (default: unset, no tokens issued)
+//! - `AUTH_TOKEN_TTL_SECS`: How long issued tokens are valid (default: 3600)
+//! - `OIDC_ISSUER`: OpenID Connect issuer URL whose bearer tokens are accepted, e.g. a
+//!   Keycloak realm; enables role checks (requires `oidc` feature; default: unset)
+//! - `OIDC_AUDIENCE`: Audience OIDC tokens must carry (required with `OIDC_ISSUER`)
+//! - `OIDC_ROLES_CLAIM`: Claim listing a user's groups or roles, with dots for nested
+//!   claims such as "realm_access.roles" (default: "groups")
+//! - `OIDC_ROLE_MAP`: Comma-separated `VALUE=ROLE[:NAMESPACE]` entries granting a role to
+//!   tokens whose roles claim contains VALUE, e.g. "ops=admin,ml-team=operator:ml"
+//! - `TLS_CA_DIR`: Directory holding the cluster CA (`ca.crt`, `ca.key`), generated on
+//!   first start if empty; copy it to every node serving the API. Serves the API over
+//!   mutual TLS with a certificate from this CA, re-issued before it expires, and signs
+//!   client certificates for `orch init` (requires `mtls` feature; default: unset, plain HTTP)
+//! - `TLS_SERVER_NAMES`: Comma-separated DNS names and IPs the API certificate is valid
+//!   for, besides "localhost" and the public address (default: unset)
+//! - `TLS_REQUIRE_CLIENT_CERT`: Refuse connections without a client certificate from the
+//!   CA, so users must already hold one (default: false)
 //! - `RUNTIME_TYPE`: Container runtime type: "mock" or "youki" (default: based on feature)
 //! - `YOUKI_BINARY`: Path to youki binary (default: "youki" - searches PATH)
 //! - `BUNDLE_ROOT`: Root directory for OCI bundles (default: "/var/lib/orchestrator/bundles")
 //! - `STATE_ROOT`: Root directory for runtime state (default: "/run/orchestrator")
+//! - `LOG_MAX_SIZE_MB`: Size a container log may reach before it is rotated (default: 10)
+//! - `LOG_MAX_AGE_SECS`: Seconds the oldest line of a container log may age before it is
+//!   rotated; 0 means no limit (default: 0)
+//! - `LOG_MAX_FILES`: Rotated segments kept per container (default: 5)
+//! - `LOG_COMPRESS`: Gzip rotated segments (default: true)
+//! - `ANOMALY_THRESHOLD`: Flag instances whose CPU, memory, restart rate or log error rate
+//!   is this many standard deviations from their workload's baseline, e.g. 4
+//!   (default: unset, no anomaly detection)
+//! - `IMAGE_PULL_CONCURRENCY`: Image pulls downloading at once per node (default: 3)
+//! - `IMAGE_PULL_BANDWIDTH_KBPS`: Total image download rate per node in KiB/s;
+//!   0 means unlimited (default: 0)
+//! - `IMAGE_SCANNER`: Scan pulled images for vulnerabilities with "trivy" or "grype"
+//!   (requires `youki-runtime` feature; default: unset, not scanned)
+//! - `IMAGE_SCANNER_BINARY`: Path to the scanner binary (default: its name, from PATH)
+//! - `TRIVY_SERVER`: Trivy server to scan through instead of a local database (default: unset)
+//! - `LAZY_PULL_HELPER`: Helper binary that mounts eStargz and zstd:chunked images so their
+//!   containers start before the image is downloaded (requires `youki-runtime` feature;
+//!   default: unset, images are pulled in full)
+//! - `REGISTRY_MIRROR`: Registry cache images are pulled through, e.g. "http://10.0.0.1:5000"
+//!   (requires `youki-runtime` feature; default: unset, pulled from the registry)
+//! - `REGISTRY_CREDENTIAL_PROVIDERS`: Comma-separated clouds whose registries are logged in
+//!   to with the node's identity: "ecr", "gcp" and "acr" (requires `youki-runtime` feature;
+//!   default: unset, anonymous pulls)
+//! - `MODEL_CACHE_GB`: Size of the cache of models mounted into inference services before
+//!   unused models are evicted (requires `youki-runtime` feature; default: 100)
+//! - `REGISTRY_CACHE_LISTEN`: Serve a pull-through registry cache for other nodes on this
+//!   address, e.g. "0.0.0.0:5000" (requires `registry-cache` feature; default: unset)
+//! - `REGISTRY_CACHE_DIR`: Directory of cached images
+//!   (default: "/var/lib/orchestrator/registry-cache")
+//! - `REGISTRY_CACHE_UPSTREAMS`: Comma-separated registries that may be pulled through the
+//!   cache (default: "registry-1.docker.io")
 //! - `MCP_STDIO`: Enable MCP server over stdio for Claude Code integration (default: false)
+//! - `DNS_CACHE_ENABLED`: Run the node-local DNS cache instances resolve through (default: false)
+//! - `DNS_CACHE_LISTEN`: Address for the DNS cache, added to a dummy `nodelocaldns`
+//!   interface unless it is a loopback or unspecified address (default: "169.254.20.10:53")
+//! - `DNS_UPSTREAMS`: Comma-separated upstream resolvers for the DNS cache
+//!   (default: nameservers from /etc/resolv.conf)
+//! - `MDNS_ENABLED`: Advertise workloads with host ports as `<name>.local` on the LAN
+//!   (default: true for a bootstrap node without seed nodes, i.e. a single-node dev cluster)
+//! - `POD_CIDRS`: Comma-separated ranges instance addresses are allocated from, one IPv4
+//!   and/or one IPv6, e.g. "10.244.0.0/16,fd00:10:244::/64" (default: "10.244.0.0/16")
+//! - `SERVICE_CIDRS`: Comma-separated ranges service virtual IPs are allocated from, one
+//!   IPv4 and/or one IPv6 (default: "10.96.0.0/12")
+//! - `HOST_PORTS`: Publish the host ports of this node's instances with nftables, on the
+//!   address each port's `host_ip` names, "true" or "1" (default: true with the youki runtime)
+//! - `SERVICE_FORWARDING`: Forward TCP connections to service virtual IPs to the endpoints
+//!   the service proxy picks, redirecting them with nftables, "true" or "1" (default: true
+//!   with the youki runtime)
+//! - `GC_JOB_TTL_SECS`: Seconds a succeeded job is kept before garbage collection;
+//!   0 keeps them forever (default: 3600)
+//! - `EVENT_TTL_SECS`: Seconds an event is kept after it last occurred; 0 keeps them forever
+//!   (default: 3600)
+//! - `NODE_LEASE_DURATION_SECS`: How long a node may go without renewing its lease before
+//!   it is marked NotReady (default: 40)
+//! - `NODE_EVICTION_GRACE_SECS`: Seconds a node stays NotReady before its instances are
+//!   rescheduled elsewhere (default: 300)
+//! - `LEADER_LEASE_DURATION_SECS`: How long control-plane leadership survives without renewal;
+//!   instances sharing a state store elect one leader to run controllers (default: 15)
+//! - `SCHEDULER_STRATEGY`: Cluster default scheduling strategy: "spread", "bin-pack" or
+//!   "random" (default: "spread"); workloads may override it
+//! - `EVENT_LOG_PATH`: Append every workload and node change to this JSON-lines file,
+//!   for replay with `orchestrator_replay` (default: unset, not recorded)
+//! - `MAX_WORKLOADS_PER_NAMESPACE`: Workloads allowed per namespace; 0 disables (default: 1000)
+//! - `MAX_CONTAINERS_PER_WORKLOAD`: Init, sidecar and main containers allowed per workload;
+//!   0 disables (default: 32)
+//! - `MAX_WORKLOAD_SPEC_BYTES`: Largest serialized workload spec accepted; 0 disables (default: 262144)
+//! - `RESOURCE_DEFAULTS`: Per-namespace container requests filled in when omitted, and
+//!   per-container maximums, e.g. "*:cpu=0.1,memory_mb=128;ml:cpu=1,max_memory_mb=65536"
+//!   (`*` matches namespaces without their own entry; default: unset)
+//! - `REQUIRED_LABELS`: Comma-separated labels every workload must carry (default: unset)
+//! - `ALLOWED_REGISTRIES`: Comma-separated registries images must come from, optionally
+//!   with a path, e.g. "ghcr.io/univrs,docker.io/library" (default: unset, any registry)
+//! - `REQUIRE_RESOURCE_REQUESTS`: Reject containers that request no CPU or memory after
+//!   namespace defaults are filled in (default: false)
+//! - `MAX_IMAGE_SEVERITY`: Reject workloads whose images have vulnerabilities above this
+//!   severity: "low", "medium", "high" or "critical" (default: unset, not checked)
+//! - `ADMISSION_WEBHOOKS`: Semicolon-separated webhooks consulted on workload writes, as
+//!   "validating:URL" or "mutating:URL" with optional ",timeout=SECS" and
+//!   ",failure=fail|ignore" (default: unset; timeout 10s, failure policy fail)
+//! - `ETCD_ENDPOINTS`: Comma-separated etcd endpoints to keep cluster state in, shared by
+//!   every control-plane instance (requires `etcd-store` feature; default: in-memory store)
+//! - `STATE_STORE_URL`: SQL database to keep cluster state in, e.g.
+//!   "sqlite:///var/lib/orchestrator/state.db?mode=rwc" for a single node or
+//!   "postgres://user:pass@db/orchestrator" shared by every control-plane instance;
+//!   the schema is migrated on startup (requires `sql-store` feature; default: in-memory store)
+//! - `TUNNEL_PORTS`: Port range such as "40000-40999" for temporary public tunnels to
+//!   workloads; setting it makes this node an edge node (default: unset, no tunnels)
+//! - `NETWORK_POLICIES`: Enforce network policies between workloads with nftables on this
+//!   node, "true" or "1" (default: false)
+//! - `API_RATE_LIMIT`: Requests per second each API client (token, key or IP address)
+//!   may send on average (default: unset, unlimited)
+//! - `API_RATE_BURST`: Requests a client may send at once on top of `API_RATE_LIMIT`
+//!   (default: twice the rate)
+//! - `AUDIT_SINKS`: Comma-separated sinks for the audit log of mutating API calls:
+//!   "file:///path/audit.jsonl", "syslog:///dev/log", "syslog+udp://host:514" or an
+//!   http(s) webhook URL (default: unset, no audit log)
+//! - `FEDERATION_ENABLED`: Let this control plane register remote clusters, list their
+//!   workloads and nodes alongside its own and send them requests for `orch --cluster`,
+//!   "true" or "1" (default: false)
+//! - `FEDERATION_KEY_FILE`: age key the tokens of registered clusters are stored encrypted
+//!   with, created if missing; every control-plane instance sharing the state store needs
+//!   a copy of the same file (default: "/var/lib/orchestrator/federation.key")
+//! - `AUTOSCALER_PROMETHEUS_URL`: Prometheus server scraping the nodes' container metrics,
+//!   e.g. "http://prometheus:9090"; the leader then resizes workloads labeled with
+//!   "autoscale-target-cpu" and "autoscale-max-replicas" every 30s (requires `rest-api` and
+//!   `scaling-predictor` features; default: unset, no autoscaling)
+//! - `SCALING_PREDICTOR_URL`: Forecasting model the autoscaler asks for replica counts
+//!   (default: unset, sized for the mean CPU use of the last few minutes)
+//! - `BOOTSTRAP_TOKENS`: Comma-separated tokens node agents may register with
+//!   (default: unset, registration needs regular credentials)
+//! - `REGISTER_API_URL`: API of the control plane this node registers with at startup and
+//!   deregisters from on shutdown, e.g. "http://10.0.0.1:9090" (default: unset)
+//! - `BOOTSTRAP_TOKEN`: Token to register with (required with `REGISTER_API_URL`)
+//! - `METRICS_BACKEND`: Where metrics go: "prometheus" (served at `/metrics`),
+//!   "statsd://host:8125[/prefix]", or "otlp+http://collector:4318" with the
+//!   `otlp-metrics` feature (requires `observability` feature; default: "prometheus")
+//! - `TRACES_OTLP_ENDPOINT`: OpenTelemetry collector to export spans to, e.g.
+//!   "http://collector:4318" (requires `otlp-traces` feature; default: unset, not exported)
+//! - `TRACES_OTLP_HEADERS`: Comma-separated `name=value` headers sent with each export
+//! - `TRACES_SAMPLING_RATIO`: Fraction of traces exported, 0.0-1.0 (default: 1.0)
+//! - `LOG_FORWARD_SINK`: Where container logs are shipped: "loki+http://loki:3100" or
+//!   "elasticsearch+http://es:9200[/index]" (requires `log-forwarding` feature;
+//!   default: unset, not shipped)
+//! - `LOG_FORWARD_BUFFER_DIR`: Directory buffering logs the sink has not taken
+//!   (default: "/var/lib/orchestrator/log-buffer")
+//! - `LOG_FORWARD_BUFFER_MB`: Disk the buffer may use before tailing pauses (default: 256)
+//! - `ALERT_RULES_FILE`: JSON list of alert rules evaluated against the metrics every 15s,
+//!   each like `{"name": "no_nodes", "expr": "orchestrator_nodes_ready < 1 for 5m"}` with
+//!   an optional "severity" and "summary" (requires `alerting` feature; default: unset)
+//! - `ALERT_NOTIFIERS`: Comma-separated destinations of firing and resolved alerts:
+//!   "webhook+https://host/path", "slack+https://hooks.slack.com/..." or
+//!   "pagerduty:<routing key>" (default: unset, alerts only served at `/alerts`)
 //!
 //! # API Endpoints (port 9090 by default)
 //!
@@ -33,19 +193,44 @@
 //! - `PUT /api/v1/workloads/:id` - Update workload
 //! - `DELETE /api/v1/workloads/:id` - Delete workload
 //! - `GET /api/v1/workloads/:id/instances` - List instances
+//! - `POST /api/v1/workloads/:id/tunnels` - Open a temporary tunnel (edge nodes)
+//! - `POST /api/v1/namespaces` - Create namespace
+//! - `GET /api/v1/namespaces` - List namespaces
+//! - `DELETE /api/v1/namespaces/:name` - Delete namespace and its workloads
+//! - `GET /api/v1/tunnels` - List tunnels
+//! - `DELETE /api/v1/tunnels/:id` - Close tunnel
+//! - `POST /api/v1/cronjobs` - Create a cron job on the leader (`GET` to list)
+//! - `DELETE /api/v1/cronjobs/:id` - Delete a cron job and its jobs
+//! - `POST /api/v1/daemonsets` - Create a daemon set on the leader (`GET` to list)
+//! - `DELETE /api/v1/daemonsets/:id` - Delete a daemon set and its instances
 //! - `GET /api/v1/nodes` - List nodes
+//! - `GET /api/v1/auth/whoami` - Calling key and its role bindings
+//! - `POST /api/v1/auth/certificate` - Sign a client certificate (`mtls` feature)
 //! - `GET /api/v1/nodes/:id` - Get node
+//! - `POST /api/v1/nodes/register` - Register a node (bootstrap token)
+//! - `DELETE /api/v1/nodes/:id` - Deregister a node
 //! - `GET /api/v1/cluster/status` - Cluster status
+//! - `GET /api/v1/cluster/leader` - Control-plane leader
+//! - `GET /api/v1/cluster/backup` - Back up cluster state (`POST .../restore` to restore)
+//! - `GET /api/v1/admin/fsck` - Check state consistency (`POST` to repair)
+//! - `GET /debug/pprof/profile?seconds=30` - CPU profile in pprof format, or an SVG
+//!   flamegraph with `&format=flamegraph` (`profiling` feature; admin only)
+//! - `GET /debug/pprof/heap` - Heap profile in pprof format (`profiling` feature; admin only)
 //!
 //! ## Observability (requires `observability` feature)
-//! - `GET /health` - Health check
-//! - `GET /ready` - Readiness probe
+//! - `GET /health` - Health check with each component's dependencies; components whose
+//!   dependencies are impaired report degraded
+//! - `GET /ready` - Readiness probe, failing while any component is unhealthy
 //! - `GET /live` - Liveness probe
-//! - `GET /metrics` - Prometheus metrics
+//! - `GET /metrics` - Prometheus metrics, including per-container usage scraped every 15s
+//!   (when `METRICS_BACKEND` is "prometheus")
+//! - `GET /alerts` - Pending and firing alerts (`alerting` feature)
 //! - `GET /api/v1/events` - WebSocket event streaming
 
 use std::collections::HashMap;
 use std::net::SocketAddr;
+#[cfg(feature = "rest-api")]
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -56,28 +241,86 @@ use uuid::Uuid;
 use cluster_manager::chitchat_manager::{ChitchatClusterConfig, ChitchatClusterManager};
 use cluster_manager_interface::ClusterManager;
 use container_runtime_interface::ContainerRuntime;
-use orchestrator_core::start_orchestrator_service;
+use orchestrator_core::accounting::UsageMeter;
+use orchestrator_core::anomaly::{self, AnomalyConfig, AnomalyDetector};
+use orchestrator_core::capacity::{self, SystemReserved};
+use orchestrator_core::container_events::ContainerEventForwarder;
+use orchestrator_core::controllers::{DaemonSetController, StatefulSetController};
+use orchestrator_core::disruption::DisruptionController;
+use orchestrator_core::events::EventRecorder;
+use orchestrator_core::gc::{GarbageCollector, RetentionPolicy};
+use orchestrator_core::heartbeat::{StatusCollector, StatusReporter};
+use orchestrator_core::jobs::CronJobController;
+use orchestrator_core::leader::LeaderElector;
+#[cfg(feature = "log-forwarding")]
+use orchestrator_core::log_forwarder::{LogForwarder, LogForwarderConfig, LogSink};
+use orchestrator_core::network::dns_cache::{self, DnsCacheConfig, NodeLocalDnsServer};
+use orchestrator_core::network::mdns::{self, MdnsConfig, MdnsResponder};
+use orchestrator_core::network::{
+    Cidr, DualStackAllocator, HostPortPublisher, InstanceAddressAllocator, NetworkProbeRunner,
+    NftablesFirewall, ReadinessProber, ServiceForwarder, ServiceProxy,
+};
+use orchestrator_core::node_lifecycle::{LeaseRenewer, NodeLifecycleController};
+#[cfg(feature = "registry-cache")]
+use orchestrator_core::registry_cache::{RegistryCache, RegistryCacheConfig};
+use orchestrator_core::replay;
+use orchestrator_core::restart::RestartManager;
+use orchestrator_core::scheduling::StrategyScheduler;
+use orchestrator_core::usage::UsageScraper;
+use orchestrator_core::Orchestrator;
 
 #[cfg(feature = "youki-runtime")]
-use container_runtime::{YoukiCliRuntime, YoukiCliConfig};
+use container_runtime::{LogRotationConfig, PullQueueConfig, YoukiCliRuntime, YoukiCliConfig};
+#[cfg(feature = "youki-runtime")]
+use container_runtime::{CloudProvider, LazyPullConfig, ScannerConfig, ScannerKind};
 use orchestrator_shared_types::{
-    ContainerId, ContainerConfig, Node, NodeId, NodeResources, NodeStatus,
-    OrchestrationError, Result as OrchResult,
+    ContainerId, ContainerConfig, GpuDevice, GpuSharing, Node, NodeId, NodeResources, NodeStatus,
+    NODE_GPU_DEVICES_LABEL, NODE_GPU_LABEL, NODE_VERSION_LABEL, OrchestrationError,
+    Result as OrchResult,
 };
-use scheduler_interface::{Scheduler, SimpleScheduler};
+use scheduler_interface::Scheduler;
+#[cfg(feature = "etcd-store")]
+use state_store_interface::etcd_store::EtcdStateStore;
+#[cfg(feature = "sql-store")]
+use state_store_interface::sql_store::SqlStateStore;
 use state_store_interface::in_memory::InMemoryStateStore;
 use state_store_interface::StateStore;
 
 #[cfg(feature = "mcp")]
 use mcp_server::OrchestratorMcpServer;
+#[cfg(feature = "mcp")]
+use scheduler_interface::SimpleScheduler;
 
 #[cfg(feature = "observability")]
 use observability::{
-    EventHub, HealthChecker, MetricsRegistry, ObservabilityConfig, ObservabilityServer,
+    EventHub, HealthChecker, MetricsBackend, MetricsRegistry, ObservabilityConfig,
+    ObservabilityServer, OrchestratorMetrics,
 };
-
+#[cfg(feature = "otlp-traces")]
+use observability::{OtlpTraceConfig, TracingConfig};
+#[cfg(feature = "alerting")]
+use observability::{AlertEngine, AlertNotifier, AlertRule};
+
+#[cfg(all(feature = "rest-api", feature = "scaling-predictor"))]
+use orchestrator_core::autoscaling::{
+    Autoscaler, HttpPredictor, MovingAveragePredictor, PrometheusMetrics, ScalingPredictor,
+};
+#[cfg(feature = "rest-api")]
+use orchestrator_core::api::{
+    AdmissionWebhook, AdmissionWebhooks, ApiState, AuditLog, AuditSinkConfig, AuthConfig,
+    Federation, LocalTokenIssuer, RateLimitConfig, RateLimiter, RbacPolicy,
+    build_router as build_api_router,
+};
+#[cfg(feature = "oidc")]
+use orchestrator_core::api::{OidcConfig, OidcVerifier};
+#[cfg(feature = "mtls")]
+use orchestrator_core::api::{ClusterCa, TlsServer};
+#[cfg(feature = "rest-api")]
+use orchestrator_core::admission::{parse_resource_defaults, AdmissionLimits};
+#[cfg(feature = "rest-api")]
+use orchestrator_core::network::{NetworkPolicyController, TunnelManager};
 #[cfg(feature = "rest-api")]
-use orchestrator_core::api::{ApiState, AuthConfig, build_router as build_api_router};
+use orchestrator_core::prepull::ImagePrepuller;
 
 /// Node configuration parsed from environment.
 #[derive(Debug, Clone)]
@@ -92,6 +335,12 @@ struct NodeConfig {
     cpu_cores: f32,
     memory_mb: u64,
     disk_mb: u64,
+    /// GPUs the node reports, and how each is shared
+    gpu_devices: Vec<GpuDevice>,
+    /// Held back from instances (None = 10% of capacity)
+    system_reserved: Option<SystemReserved>,
+    /// Labels the node reports
+    labels: HashMap<String, String>,
     log_level: String,
     log_json: bool,
     /// Disable authentication for development
@@ -104,9 +353,146 @@ struct NodeConfig {
     bundle_root: String,
     /// Root directory for runtime state
     state_root: String,
+    /// Size a container log may reach before it is rotated
+    log_max_size_mb: u64,
+    /// Age of a container log's oldest line before it is rotated (None = no limit)
+    log_max_age: Option<Duration>,
+    /// Rotated log segments kept per container
+    log_max_files: usize,
+    /// Gzip rotated log segments
+    log_compress: bool,
+    /// Deviation flagging instances as abnormal (None = not detected)
+    anomaly_threshold: Option<f64>,
+    /// Image pulls downloading at once per node
+    image_pull_concurrency: usize,
+    /// Image download rate per node in bytes/s (None = unlimited)
+    image_pull_bandwidth: Option<u64>,
+    /// Vulnerability scanner run on pulled images (None = not scanned)
+    #[cfg(feature = "youki-runtime")]
+    image_scanner: Option<ScannerConfig>,
+    /// Helper mounting seekable images lazily (None = pulled in full)
+    #[cfg(feature = "youki-runtime")]
+    lazy_pull: Option<LazyPullConfig>,
+    /// Registry cache images are pulled through (None = pulled from the registry)
+    #[cfg(feature = "youki-runtime")]
+    registry_mirror: Option<String>,
+    /// Clouds whose registries are logged in to with the node's identity
+    #[cfg(feature = "youki-runtime")]
+    credential_providers: Vec<CloudProvider>,
+    /// Size of the model cache in GiB
+    #[cfg(feature = "youki-runtime")]
+    model_cache_gb: u64,
+    /// Pull-through registry cache served to other nodes (None = not served)
+    #[cfg(feature = "registry-cache")]
+    registry_cache: Option<RegistryCacheConfig>,
     /// Enable MCP stdio server for Claude Code integration
     #[cfg(feature = "mcp")]
     mcp_stdio: bool,
+    /// Run the node-local DNS cache
+    dns_cache_enabled: bool,
+    /// Address the DNS cache listens on
+    dns_cache_listen: SocketAddr,
+    /// Upstream resolvers for the DNS cache (empty = use /etc/resolv.conf)
+    dns_upstreams: Vec<SocketAddr>,
+    /// Advertise exposed workloads over mDNS
+    mdns_enabled: bool,
+    /// Ranges instance addresses are allocated from
+    pod_cidrs: Vec<Cidr>,
+    /// Ranges service virtual IPs are allocated from
+    service_cidrs: Vec<Cidr>,
+    /// Publish instances' host ports with nftables
+    host_ports: bool,
+    /// Forward connections to service virtual IPs
+    service_forwarding: bool,
+    /// How long succeeded jobs are kept (None = forever)
+    gc_job_ttl: Option<Duration>,
+    /// How long events are kept (None = forever)
+    gc_event_ttl: Option<Duration>,
+    /// How long this node's lease holds after each renewal
+    lease_duration: Duration,
+    /// How long a node stays NotReady before its instances are evicted
+    eviction_grace_period: Duration,
+    /// How long control-plane leadership survives without renewal
+    leader_lease_duration: Duration,
+    /// Default scheduling strategy for workloads that do not pick one
+    scheduler_strategy: String,
+    /// File to record workload and node changes to, for replay (None = off)
+    event_log_path: Option<String>,
+    /// etcd endpoints of a shared state store (empty = in-memory)
+    #[cfg(feature = "etcd-store")]
+    etcd_endpoints: Vec<String>,
+    /// URL of a SQL state store (None = in-memory)
+    #[cfg(feature = "sql-store")]
+    state_store_url: Option<String>,
+    /// Object count and size limits and policy rules enforced by the API
+    #[cfg(feature = "rest-api")]
+    admission_limits: AdmissionLimits,
+    /// External webhooks consulted on workload writes (empty = none)
+    #[cfg(feature = "rest-api")]
+    admission_webhooks: Vec<AdmissionWebhook>,
+    /// Role bindings authorizing API requests (None = no authorization)
+    #[cfg(feature = "rest-api")]
+    rbac_policy: Option<RbacPolicy>,
+    /// Issuer of bearer tokens for `orch login` (None = not issued)
+    #[cfg(feature = "rest-api")]
+    token_issuer: Option<LocalTokenIssuer>,
+    /// Tokens node agents may register with (empty = none)
+    #[cfg(feature = "rest-api")]
+    bootstrap_tokens: Vec<String>,
+    /// Control-plane API to register with, and the token to use (None = not registered)
+    #[cfg(feature = "rest-api")]
+    registration: Option<(String, String)>,
+    /// OIDC provider whose tokens are accepted (None = not accepted)
+    #[cfg(feature = "oidc")]
+    oidc: Option<OidcConfig>,
+    /// Directory of the cluster CA (None = plain HTTP)
+    #[cfg(feature = "mtls")]
+    tls_ca_dir: Option<std::path::PathBuf>,
+    /// Extra names the API certificate is valid for
+    #[cfg(feature = "mtls")]
+    tls_server_names: Vec<String>,
+    /// Refuse connections without a client certificate
+    #[cfg(feature = "mtls")]
+    tls_require_client_cert: bool,
+    /// Public ports for temporary tunnels (None = not an edge node)
+    #[cfg(feature = "rest-api")]
+    tunnel_ports: Option<RangeInclusive<u16>>,
+    /// Enforce network policies with nftables
+    #[cfg(feature = "rest-api")]
+    network_policies: bool,
+    /// Where audit records of mutating API calls go (empty = not recorded)
+    #[cfg(feature = "rest-api")]
+    audit_sinks: Vec<AuditSinkConfig>,
+    /// Per-client API rate limit (None = unlimited)
+    #[cfg(feature = "rest-api")]
+    rate_limit: Option<RateLimitConfig>,
+    /// Serve the federation endpoints for remote clusters
+    #[cfg(feature = "rest-api")]
+    federation_enabled: bool,
+    /// Key the tokens of remote clusters are encrypted with
+    #[cfg(feature = "rest-api")]
+    federation_key_file: std::path::PathBuf,
+    /// Prometheus server the autoscaler reads CPU usage from (None = no autoscaling)
+    #[cfg(all(feature = "rest-api", feature = "scaling-predictor"))]
+    autoscaler_metrics_url: Option<String>,
+    /// Model the autoscaler asks for forecasts (None = moving average)
+    #[cfg(all(feature = "rest-api", feature = "scaling-predictor"))]
+    scaling_predictor_url: Option<String>,
+    /// Where metrics are exported
+    #[cfg(feature = "observability")]
+    metrics_backend: MetricsBackend,
+    /// Collector spans are exported to (None = not exported)
+    #[cfg(feature = "otlp-traces")]
+    otlp_traces: Option<OtlpTraceConfig>,
+    /// Where container logs are shipped (None = not shipped)
+    #[cfg(feature = "log-forwarding")]
+    log_forwarding: Option<LogForwarderConfig>,
+    /// Rules evaluated against the metrics (empty = no alerting)
+    #[cfg(feature = "alerting")]
+    alert_rules: Vec<AlertRule>,
+    /// Where firing and resolved alerts are sent
+    #[cfg(feature = "alerting")]
+    alert_notifiers: Vec<AlertNotifier>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -176,21 +562,53 @@ impl NodeConfig {
             .parse()
             .unwrap_or(9090);
 
+        let bundle_root = std::env::var("BUNDLE_ROOT")
+            .unwrap_or_else(|_| "/var/lib/orchestrator/bundles".to_string());
+
+        // Capacity is detected unless set by hand
+        let detected = capacity::detect(std::path::Path::new(&bundle_root));
         let cpu_cores: f32 = std::env::var("NODE_CPU")
-            .unwrap_or_else(|_| "4.0".to_string())
-            .parse()
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .or(Some(detected.resources.cpu_cores).filter(|c| *c > 0.0))
             .unwrap_or(4.0);
 
         let memory_mb: u64 = std::env::var("NODE_MEMORY_MB")
-            .unwrap_or_else(|_| "8192".to_string())
-            .parse()
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .or(Some(detected.resources.memory_mb).filter(|m| *m > 0))
             .unwrap_or(8192);
 
         let disk_mb: u64 = std::env::var("NODE_DISK_MB")
-            .unwrap_or_else(|_| "102400".to_string())
-            .parse()
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .or(Some(detected.resources.disk_mb).filter(|d| *d > 0))
             .unwrap_or(102400);
 
+        let gpus: u32 = match std::env::var("NODE_GPUS") {
+            Ok(v) => v.parse().context("Invalid NODE_GPUS")?,
+            Err(_) => detected.gpus,
+        };
+        let gpu_devices = match std::env::var("NODE_GPU_DEVICES") {
+            Ok(v) => GpuDevice::parse_list(&v).context("Invalid NODE_GPU_DEVICES")?,
+            Err(_) => (0..gpus)
+                .map(|index| GpuDevice {
+                    index,
+                    sharing: GpuSharing::Exclusive,
+                })
+                .collect(),
+        };
+
+        let system_reserved = std::env::var("SYSTEM_RESERVED")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|v| SystemReserved::parse(&v).map_err(|e| anyhow::anyhow!(e)))
+            .transpose()
+            .context("Invalid SYSTEM_RESERVED")?;
+
+        let labels = parse_labels(&std::env::var("NODE_LABELS").unwrap_or_default())
+            .context("Invalid NODE_LABELS")?;
+
         let log_level = std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
         let log_json = std::env::var("LOG_JSON")
             .map(|v| v == "true" || v == "1")
@@ -220,17 +638,490 @@ impl NodeConfig {
         let youki_binary = std::env::var("YOUKI_BINARY")
             .unwrap_or_else(|_| "youki".to_string());
 
-        let bundle_root = std::env::var("BUNDLE_ROOT")
-            .unwrap_or_else(|_| "/var/lib/orchestrator/bundles".to_string());
-
         let state_root = std::env::var("STATE_ROOT")
             .unwrap_or_else(|_| "/run/orchestrator".to_string());
 
+        let log_max_size_mb: u64 = std::env::var("LOG_MAX_SIZE_MB")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(10))
+            .context("Invalid LOG_MAX_SIZE_MB")?;
+        let log_max_age_secs: u64 = std::env::var("LOG_MAX_AGE_SECS")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(0))
+            .context("Invalid LOG_MAX_AGE_SECS")?;
+        let log_max_age = (log_max_age_secs > 0).then(|| Duration::from_secs(log_max_age_secs));
+        let log_max_files: usize = std::env::var("LOG_MAX_FILES")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(5))
+            .context("Invalid LOG_MAX_FILES")?;
+        let log_compress = std::env::var("LOG_COMPRESS")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(true);
+        let anomaly_threshold: Option<f64> = std::env::var("ANOMALY_THRESHOLD")
+            .ok()
+            .map(|v| v.parse())
+            .transpose()
+            .context("Invalid ANOMALY_THRESHOLD")?;
+
+        let image_pull_concurrency: usize = std::env::var("IMAGE_PULL_CONCURRENCY")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(3))
+            .context("Invalid IMAGE_PULL_CONCURRENCY")?;
+        let image_pull_bandwidth_kbps: u64 = std::env::var("IMAGE_PULL_BANDWIDTH_KBPS")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(0))
+            .context("Invalid IMAGE_PULL_BANDWIDTH_KBPS")?;
+        let image_pull_bandwidth =
+            (image_pull_bandwidth_kbps > 0).then_some(image_pull_bandwidth_kbps * 1024);
+
+        #[cfg(feature = "youki-runtime")]
+        let image_scanner = match std::env::var("IMAGE_SCANNER") {
+            Ok(v) if !v.is_empty() => {
+                let kind: ScannerKind = v
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("Invalid IMAGE_SCANNER: {}", e))?;
+                let mut scanner = ScannerConfig::new(kind);
+                if let Ok(binary) = std::env::var("IMAGE_SCANNER_BINARY") {
+                    scanner = scanner.with_binary(binary);
+                }
+                if let Ok(server) = std::env::var("TRIVY_SERVER") {
+                    scanner = scanner.with_server(server);
+                }
+                Some(scanner)
+            }
+            _ => None,
+        };
+        #[cfg(feature = "youki-runtime")]
+        let lazy_pull = std::env::var("LAZY_PULL_HELPER")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(LazyPullConfig::new);
+        #[cfg(feature = "youki-runtime")]
+        let registry_mirror = std::env::var("REGISTRY_MIRROR")
+            .ok()
+            .filter(|s| !s.is_empty());
+        #[cfg(feature = "youki-runtime")]
+        let credential_providers = match std::env::var("REGISTRY_CREDENTIAL_PROVIDERS") {
+            Ok(clouds) => clouds
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<CloudProvider>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(anyhow::Error::msg)
+                .context("Invalid REGISTRY_CREDENTIAL_PROVIDERS")?,
+            Err(_) => Vec::new(),
+        };
+        #[cfg(feature = "youki-runtime")]
+        let model_cache_gb: u64 = std::env::var("MODEL_CACHE_GB")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(100))
+            .context("Invalid MODEL_CACHE_GB")?;
+
+        #[cfg(feature = "registry-cache")]
+        let registry_cache = match std::env::var("REGISTRY_CACHE_LISTEN") {
+            Ok(listen) => {
+                let mut cache_config = RegistryCacheConfig::new(
+                    std::env::var("REGISTRY_CACHE_DIR")
+                        .unwrap_or_else(|_| "/var/lib/orchestrator/registry-cache".to_string()),
+                );
+                cache_config.listen_addr =
+                    listen.parse().context("Invalid REGISTRY_CACHE_LISTEN")?;
+                if let Ok(upstreams) = std::env::var("REGISTRY_CACHE_UPSTREAMS") {
+                    cache_config.upstreams = upstreams
+                        .split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect();
+                }
+                Some(cache_config)
+            }
+            Err(_) => None,
+        };
+
         #[cfg(feature = "mcp")]
         let mcp_stdio = std::env::var("MCP_STDIO")
             .map(|v| v == "true" || v == "1")
             .unwrap_or(false);
 
+        let dns_cache_enabled = std::env::var("DNS_CACHE_ENABLED")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        let dns_cache_listen: SocketAddr = std::env::var("DNS_CACHE_LISTEN")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(dns_cache::DEFAULT_LISTEN_ADDR))
+            .context("Invalid DNS_CACHE_LISTEN")?;
+
+        let dns_upstreams: Vec<SocketAddr> = std::env::var("DNS_UPSTREAMS")
+            .map(|v| {
+                v.split(',')
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.parse())
+                    .collect::<std::result::Result<Vec<_>, _>>()
+            })
+            .unwrap_or(Ok(Vec::new()))
+            .context("Invalid DNS_UPSTREAMS")?;
+
+        let single_node = role == NodeRole::Bootstrap && seed_nodes.is_empty();
+        let mdns_enabled = std::env::var("MDNS_ENABLED")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(single_node);
+
+        let parse_cidrs = |name: &str, default: &str| -> Result<Vec<Cidr>> {
+            std::env::var(name)
+                .unwrap_or_else(|_| default.to_string())
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<Cidr>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .with_context(|| format!("Invalid {}", name))
+        };
+        let pod_cidrs = parse_cidrs("POD_CIDRS", "10.244.0.0/16")?;
+        let service_cidrs = parse_cidrs("SERVICE_CIDRS", "10.96.0.0/12")?;
+
+        #[cfg(feature = "youki-runtime")]
+        let youki = matches!(runtime_type, RuntimeType::Youki);
+        #[cfg(not(feature = "youki-runtime"))]
+        let youki = false;
+        let host_ports = std::env::var("HOST_PORTS")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(youki);
+        let service_forwarding = std::env::var("SERVICE_FORWARDING")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(youki);
+
+        let gc_job_ttl_secs: u64 = std::env::var("GC_JOB_TTL_SECS")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(3600))
+            .context("Invalid GC_JOB_TTL_SECS")?;
+        let gc_job_ttl = (gc_job_ttl_secs > 0).then(|| Duration::from_secs(gc_job_ttl_secs));
+
+        let event_ttl_secs: u64 = std::env::var("EVENT_TTL_SECS")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(3600))
+            .context("Invalid EVENT_TTL_SECS")?;
+        let gc_event_ttl = (event_ttl_secs > 0).then(|| Duration::from_secs(event_ttl_secs));
+
+        let lease_duration_secs: u64 = std::env::var("NODE_LEASE_DURATION_SECS")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(40))
+            .context("Invalid NODE_LEASE_DURATION_SECS")?;
+        if lease_duration_secs == 0 {
+            anyhow::bail!("NODE_LEASE_DURATION_SECS must be greater than 0");
+        }
+        let lease_duration = Duration::from_secs(lease_duration_secs);
+
+        let eviction_grace_secs: u64 = std::env::var("NODE_EVICTION_GRACE_SECS")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(300))
+            .context("Invalid NODE_EVICTION_GRACE_SECS")?;
+        let eviction_grace_period = Duration::from_secs(eviction_grace_secs);
+
+        let leader_lease_secs: u64 = std::env::var("LEADER_LEASE_DURATION_SECS")
+            .map(|v| v.parse())
+            .unwrap_or(Ok(15))
+            .context("Invalid LEADER_LEASE_DURATION_SECS")?;
+        if leader_lease_secs < 3 {
+            anyhow::bail!("LEADER_LEASE_DURATION_SECS must be at least 3");
+        }
+        let leader_lease_duration = Duration::from_secs(leader_lease_secs);
+
+        let scheduler_strategy = std::env::var("SCHEDULER_STRATEGY")
+            .unwrap_or_else(|_| "spread".to_string());
+
+        let event_log_path = std::env::var("EVENT_LOG_PATH").ok().filter(|s| !s.is_empty());
+
+        #[cfg(feature = "rest-api")]
+        let admission_limits = {
+            let defaults = AdmissionLimits::default();
+            let limit = |name: &str, default: Option<usize>| -> Result<Option<usize>> {
+                match std::env::var(name) {
+                    Ok(v) => {
+                        let n: usize = v.parse().with_context(|| format!("Invalid {}", name))?;
+                        Ok((n > 0).then_some(n))
+                    }
+                    Err(_) => Ok(default),
+                }
+            };
+            let list = |name: &str| -> Vec<String> {
+                std::env::var(name)
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(String::from)
+                    .collect()
+            };
+            AdmissionLimits {
+                max_workloads_per_namespace: limit(
+                    "MAX_WORKLOADS_PER_NAMESPACE",
+                    defaults.max_workloads_per_namespace,
+                )?,
+                max_containers_per_workload: limit(
+                    "MAX_CONTAINERS_PER_WORKLOAD",
+                    defaults.max_containers_per_workload,
+                )?,
+                max_spec_bytes: limit("MAX_WORKLOAD_SPEC_BYTES", defaults.max_spec_bytes)?,
+                resource_defaults: match std::env::var("RESOURCE_DEFAULTS") {
+                    Ok(v) => parse_resource_defaults(&v)
+                        .map_err(|e| anyhow::anyhow!("Invalid RESOURCE_DEFAULTS: {}", e))?,
+                    Err(_) => defaults.resource_defaults,
+                },
+                required_labels: list("REQUIRED_LABELS"),
+                allowed_registries: list("ALLOWED_REGISTRIES"),
+                require_resource_requests: std::env::var("REQUIRE_RESOURCE_REQUESTS")
+                    .map(|v| v == "true" || v == "1")
+                    .unwrap_or(false),
+                max_image_severity: match std::env::var("MAX_IMAGE_SEVERITY") {
+                    Ok(v) => Some(
+                        v.parse()
+                            .map_err(|e| anyhow::anyhow!("Invalid MAX_IMAGE_SEVERITY: {}", e))?,
+                    ),
+                    Err(_) => None,
+                },
+            }
+        };
+
+        #[cfg(feature = "etcd-store")]
+        let etcd_endpoints: Vec<String> = std::env::var("ETCD_ENDPOINTS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.trim().to_string())
+            .collect();
+
+        #[cfg(feature = "sql-store")]
+        let state_store_url = std::env::var("STATE_STORE_URL").ok().filter(|s| !s.is_empty());
+        #[cfg(all(feature = "sql-store", feature = "etcd-store"))]
+        if state_store_url.is_some() && !etcd_endpoints.is_empty() {
+            anyhow::bail!("Set only one of STATE_STORE_URL and ETCD_ENDPOINTS");
+        }
+
+        #[cfg(feature = "rest-api")]
+        let rbac_policy = std::env::var("RBAC_BINDINGS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|v| RbacPolicy::parse(&v).map_err(|e| anyhow::anyhow!(e)))
+            .transpose()
+            .context("Invalid RBAC_BINDINGS")?;
+        #[cfg(feature = "rest-api")]
+        if rbac_policy.is_some() && auth_disabled {
+            anyhow::bail!("RBAC_BINDINGS requires AUTH_DISABLED=false");
+        }
+
+        #[cfg(feature = "rest-api")]
+        let token_issuer = match std::env::var("AUTH_TOKEN_SECRET") {
+            Ok(secret) if !secret.is_empty() => {
+                if secret.len() < 32 {
+                    anyhow::bail!("AUTH_TOKEN_SECRET must be at least 32 bytes");
+                }
+                let ttl_secs: i64 = std::env::var("AUTH_TOKEN_TTL_SECS")
+                    .ok()
+                    .map(|v| v.parse())
+                    .transpose()
+                    .context("Invalid AUTH_TOKEN_TTL_SECS")?
+                    .unwrap_or(LocalTokenIssuer::DEFAULT_TTL_SECS);
+                Some(
+                    LocalTokenIssuer::new(secret.as_bytes())
+                        .with_ttl(chrono::Duration::seconds(ttl_secs)),
+                )
+            }
+            _ => None,
+        };
+
+        #[cfg(feature = "rest-api")]
+        let bootstrap_tokens: Vec<String> = std::env::var("BOOTSTRAP_TOKENS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        #[cfg(feature = "rest-api")]
+        let registration = match std::env::var("REGISTER_API_URL") {
+            Ok(url) if !url.is_empty() => {
+                let token = std::env::var("BOOTSTRAP_TOKEN")
+                    .ok()
+                    .filter(|s| !s.is_empty())
+                    .context("BOOTSTRAP_TOKEN is required with REGISTER_API_URL")?;
+                Some((url.trim_end_matches('/').to_string(), token))
+            }
+            _ => None,
+        };
+
+        #[cfg(feature = "oidc")]
+        let oidc = match std::env::var("OIDC_ISSUER") {
+            Ok(issuer) if !issuer.is_empty() => {
+                let audience = std::env::var("OIDC_AUDIENCE")
+                    .context("OIDC_AUDIENCE is required with OIDC_ISSUER")?;
+                let mut oidc = OidcConfig::new(issuer, audience);
+                if let Ok(claim) = std::env::var("OIDC_ROLES_CLAIM") {
+                    oidc = oidc.with_roles_claim(claim);
+                }
+                oidc.role_mappings = OidcConfig::parse_role_mappings(
+                    &std::env::var("OIDC_ROLE_MAP").unwrap_or_default(),
+                )
+                .map_err(|e| anyhow::anyhow!("Invalid OIDC_ROLE_MAP: {}", e))?;
+                Some(oidc)
+            }
+            _ => None,
+        };
+        #[cfg(feature = "oidc")]
+        if oidc.is_some() && auth_disabled {
+            anyhow::bail!("OIDC_ISSUER requires AUTH_DISABLED=false");
+        }
+
+        #[cfg(feature = "mtls")]
+        let tls_ca_dir = std::env::var("TLS_CA_DIR")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(std::path::PathBuf::from);
+        #[cfg(feature = "mtls")]
+        let tls_server_names: Vec<String> = std::env::var("TLS_SERVER_NAMES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        #[cfg(feature = "mtls")]
+        let tls_require_client_cert = std::env::var("TLS_REQUIRE_CLIENT_CERT")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        #[cfg(feature = "mtls")]
+        if tls_require_client_cert && tls_ca_dir.is_none() {
+            anyhow::bail!("TLS_REQUIRE_CLIENT_CERT requires TLS_CA_DIR");
+        }
+
+        #[cfg(feature = "rest-api")]
+        let tunnel_ports = std::env::var("TUNNEL_PORTS")
+            .ok()
+            .map(|v| -> Result<RangeInclusive<u16>> {
+                let (start, end) = v
+                    .split_once('-')
+                    .ok_or_else(|| anyhow::anyhow!("expected START-END, got {}", v))?;
+                let (start, end): (u16, u16) = (start.trim().parse()?, end.trim().parse()?);
+                if start == 0 || start > end {
+                    anyhow::bail!("empty port range {}", v);
+                }
+                Ok(start..=end)
+            })
+            .transpose()
+            .context("Invalid TUNNEL_PORTS")?;
+        #[cfg(feature = "rest-api")]
+        let network_policies = std::env::var("NETWORK_POLICIES")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        #[cfg(feature = "rest-api")]
+        let admission_webhooks = AdmissionWebhook::parse_list(
+            &std::env::var("ADMISSION_WEBHOOKS").unwrap_or_default(),
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid ADMISSION_WEBHOOKS: {}", e))?;
+
+        #[cfg(feature = "rest-api")]
+        let audit_sinks = AuditSinkConfig::parse_list(
+            &std::env::var("AUDIT_SINKS").unwrap_or_default(),
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid AUDIT_SINKS: {}", e))?;
+
+        #[cfg(feature = "rest-api")]
+        let rate_limit = match std::env::var("API_RATE_LIMIT").ok().filter(|s| !s.is_empty()) {
+            Some(rate) => {
+                let rate: f64 = rate.parse().context("Invalid API_RATE_LIMIT")?;
+                if !(rate > 0.0 && rate.is_finite()) {
+                    anyhow::bail!("API_RATE_LIMIT must be positive, got {}", rate);
+                }
+                let mut config = RateLimitConfig::per_second(rate);
+                if let Ok(burst) = std::env::var("API_RATE_BURST") {
+                    config = config.with_burst(burst.parse().context("Invalid API_RATE_BURST")?);
+                }
+                Some(config)
+            }
+            None => None,
+        };
+
+        #[cfg(feature = "rest-api")]
+        let federation_enabled = std::env::var("FEDERATION_ENABLED")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        #[cfg(feature = "rest-api")]
+        let federation_key_file = std::env::var("FEDERATION_KEY_FILE")
+            .unwrap_or_else(|_| "/var/lib/orchestrator/federation.key".to_string())
+            .into();
+        #[cfg(all(feature = "rest-api", feature = "scaling-predictor"))]
+        let autoscaler_metrics_url = std::env::var("AUTOSCALER_PROMETHEUS_URL").ok();
+        #[cfg(all(feature = "rest-api", feature = "scaling-predictor"))]
+        let scaling_predictor_url = std::env::var("SCALING_PREDICTOR_URL").ok();
+
+        #[cfg(feature = "observability")]
+        let metrics_backend: MetricsBackend = std::env::var("METRICS_BACKEND")
+            .unwrap_or_default()
+            .parse()
+            .context("Invalid METRICS_BACKEND")?;
+
+        #[cfg(feature = "otlp-traces")]
+        let otlp_traces = match std::env::var("TRACES_OTLP_ENDPOINT") {
+            Ok(endpoint) => {
+                let mut otlp = OtlpTraceConfig::new(endpoint);
+                if let Ok(headers) = std::env::var("TRACES_OTLP_HEADERS") {
+                    for (name, value) in OtlpTraceConfig::parse_headers(&headers)
+                        .map_err(anyhow::Error::msg)
+                        .context("Invalid TRACES_OTLP_HEADERS")?
+                    {
+                        otlp = otlp.with_header(name, value);
+                    }
+                }
+                if let Ok(ratio) = std::env::var("TRACES_SAMPLING_RATIO") {
+                    let ratio: f64 = ratio.parse().context("Invalid TRACES_SAMPLING_RATIO")?;
+                    otlp = otlp.with_sampling_ratio(ratio);
+                }
+                Some(otlp)
+            }
+            Err(_) => None,
+        };
+
+        #[cfg(feature = "log-forwarding")]
+        let log_forwarding = match std::env::var("LOG_FORWARD_SINK") {
+            Ok(sink) => {
+                let sink: LogSink = sink
+                    .parse()
+                    .map_err(anyhow::Error::msg)
+                    .context("Invalid LOG_FORWARD_SINK")?;
+                let buffer_dir = std::env::var("LOG_FORWARD_BUFFER_DIR")
+                    .unwrap_or_else(|_| "/var/lib/orchestrator/log-buffer".to_string());
+                let buffer_mb: u64 = std::env::var("LOG_FORWARD_BUFFER_MB")
+                    .map(|v| v.parse())
+                    .unwrap_or(Ok(256))
+                    .context("Invalid LOG_FORWARD_BUFFER_MB")?;
+                Some(
+                    LogForwarderConfig::new(sink, buffer_dir)
+                        .with_max_buffer_bytes(buffer_mb * 1024 * 1024),
+                )
+            }
+            Err(_) => None,
+        };
+
+        #[cfg(feature = "alerting")]
+        let alert_rules = match std::env::var("ALERT_RULES_FILE") {
+            Ok(path) => {
+                let rules = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read ALERT_RULES_FILE {}", path))?;
+                AlertRule::parse_list(&rules).context("Invalid ALERT_RULES_FILE")?
+            }
+            Err(_) => Vec::new(),
+        };
+        #[cfg(feature = "alerting")]
+        let alert_notifiers = std::env::var("ALERT_NOTIFIERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<AlertNotifier>, _>>()
+            .context("Invalid ALERT_NOTIFIERS")?;
+
         Ok(NodeConfig {
             node_id,
             role,
@@ -242,6 +1133,9 @@ impl NodeConfig {
             cpu_cores,
             memory_mb,
             disk_mb,
+            gpu_devices,
+            system_reserved,
+            labels,
             log_level,
             log_json,
             auth_disabled,
@@ -249,8 +1143,92 @@ impl NodeConfig {
             youki_binary,
             bundle_root,
             state_root,
+            log_max_size_mb,
+            log_max_age,
+            log_max_files,
+            log_compress,
+            anomaly_threshold,
+            image_pull_concurrency,
+            image_pull_bandwidth,
+            #[cfg(feature = "youki-runtime")]
+            image_scanner,
+            #[cfg(feature = "youki-runtime")]
+            lazy_pull,
+            #[cfg(feature = "youki-runtime")]
+            registry_mirror,
+            #[cfg(feature = "youki-runtime")]
+            credential_providers,
+            #[cfg(feature = "youki-runtime")]
+            model_cache_gb,
+            #[cfg(feature = "registry-cache")]
+            registry_cache,
             #[cfg(feature = "mcp")]
             mcp_stdio,
+            dns_cache_enabled,
+            dns_cache_listen,
+            dns_upstreams,
+            mdns_enabled,
+            pod_cidrs,
+            service_cidrs,
+            host_ports,
+            service_forwarding,
+            gc_job_ttl,
+            gc_event_ttl,
+            lease_duration,
+            eviction_grace_period,
+            leader_lease_duration,
+            scheduler_strategy,
+            event_log_path,
+            #[cfg(feature = "etcd-store")]
+            etcd_endpoints,
+            #[cfg(feature = "sql-store")]
+            state_store_url,
+            #[cfg(feature = "rest-api")]
+            admission_limits,
+            #[cfg(feature = "rest-api")]
+            admission_webhooks,
+            #[cfg(feature = "rest-api")]
+            rbac_policy,
+            #[cfg(feature = "rest-api")]
+            token_issuer,
+            #[cfg(feature = "rest-api")]
+            bootstrap_tokens,
+            #[cfg(feature = "rest-api")]
+            registration,
+            #[cfg(feature = "oidc")]
+            oidc,
+            #[cfg(feature = "mtls")]
+            tls_ca_dir,
+            #[cfg(feature = "mtls")]
+            tls_server_names,
+            #[cfg(feature = "mtls")]
+            tls_require_client_cert,
+            #[cfg(feature = "rest-api")]
+            tunnel_ports,
+            #[cfg(feature = "rest-api")]
+            network_policies,
+            #[cfg(feature = "rest-api")]
+            audit_sinks,
+            #[cfg(feature = "rest-api")]
+            rate_limit,
+            #[cfg(feature = "rest-api")]
+            federation_enabled,
+            #[cfg(feature = "rest-api")]
+            federation_key_file,
+            #[cfg(all(feature = "rest-api", feature = "scaling-predictor"))]
+            autoscaler_metrics_url,
+            #[cfg(all(feature = "rest-api", feature = "scaling-predictor"))]
+            scaling_predictor_url,
+            #[cfg(feature = "observability")]
+            metrics_backend,
+            #[cfg(feature = "otlp-traces")]
+            otlp_traces,
+            #[cfg(feature = "log-forwarding")]
+            log_forwarding,
+            #[cfg(feature = "alerting")]
+            alert_rules,
+            #[cfg(feature = "alerting")]
+            alert_notifiers,
         })
     }
 }
@@ -304,6 +1282,7 @@ impl ContainerRuntime for MockRuntime {
                 state: "Running".to_string(),
                 exit_code: None,
                 error_message: None,
+                oom_killed: false,
             })
         } else {
             Err(OrchestrationError::RuntimeError(format!(
@@ -325,6 +1304,7 @@ impl ContainerRuntime for MockRuntime {
                 state: "Running".to_string(),
                 exit_code: None,
                 error_message: None,
+                oom_killed: false,
             })
             .collect())
     }
@@ -333,6 +1313,18 @@ impl ContainerRuntime for MockRuntime {
 fn init_logging(config: &NodeConfig) {
     use tracing_subscriber::{fmt, EnvFilter};
 
+    #[cfg(feature = "otlp-traces")]
+    if let Some(otlp) = &config.otlp_traces {
+        let level = config.log_level.parse().unwrap_or(tracing::Level::INFO);
+        observability::init_tracing(
+            TracingConfig::new("orchestrator-node")
+                .with_level(level)
+                .with_json(config.log_json)
+                .with_otlp(otlp.clone().with_service_name("orchestrator-node")),
+        );
+        return;
+    }
+
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(&config.log_level));
 
@@ -348,6 +1340,103 @@ fn init_logging(config: &NodeConfig) {
     }
 }
 
+/// Parse comma-separated `key=value` labels.
+fn parse_labels(s: &str) -> Result<HashMap<String, String>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| match l.split_once('=') {
+            Some((k, v)) if !k.trim().is_empty() => {
+                Ok((k.trim().to_string(), v.trim().to_string()))
+            }
+            _ => anyhow::bail!("expected key=value, got '{}'", l),
+        })
+        .collect()
+}
+
+/// Pass the trace of the current span on to the control plane.
+#[cfg(feature = "rest-api")]
+fn with_trace_context(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match orchestrator_core::current_traceparent() {
+        Some(traceparent) => request.header("traceparent", traceparent),
+        None => request,
+    }
+}
+
+/// Times the agent tries to reach the control plane before giving up.
+#[cfg(feature = "rest-api")]
+const REGISTER_ATTEMPTS: u32 = 5;
+
+/// Register `node` with the control plane at `api_url`, retrying while it
+/// comes up.
+#[cfg(feature = "rest-api")]
+#[tracing::instrument(skip_all, fields(api_url = %api_url))]
+async fn register_node(api_url: &str, token: &str, node: &Node) -> Result<()> {
+    use orchestrator_core::api::handlers::{RegisterNodeRequest, ResourceRequestsRequest};
+
+    let resources = |r: &NodeResources| ResourceRequestsRequest {
+        cpu_cores: r.cpu_cores,
+        memory_mb: r.memory_mb,
+        disk_mb: r.disk_mb,
+        ..Default::default()
+    };
+    let request = RegisterNodeRequest {
+        id: node.id.to_string(),
+        address: node.address.clone(),
+        labels: node.labels.clone(),
+        capacity: resources(&node.resources_capacity),
+        allocatable: Some(resources(&node.resources_allocatable)),
+    };
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/v1/nodes/register", api_url);
+    let mut attempt = 1;
+    loop {
+        let result = with_trace_context(client.post(&url))
+            .bearer_auth(token)
+            .json(&request)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        match result {
+            Ok(_) => return Ok(()),
+            Err(e)
+                if attempt < REGISTER_ATTEMPTS
+                    && e.status().is_none_or(|s| s.is_server_error()) =>
+            {
+                warn!("Registration attempt {} failed, retrying: {}", attempt, e);
+                tokio::time::sleep(Duration::from_secs(2 * attempt as u64)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Remove the registration of node `node_id` from the control plane.
+#[cfg(feature = "rest-api")]
+#[tracing::instrument(skip_all, fields(api_url = %api_url))]
+async fn deregister_node(api_url: &str, token: &str, node_id: &str) -> Result<()> {
+    let url = format!("{}/api/v1/nodes/{}", api_url, node_id);
+    with_trace_context(reqwest::Client::new().delete(url))
+        .bearer_auth(token)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Allocate through jemalloc so heap profiles can be taken.
+#[cfg(feature = "profiling")]
+#[global_allocator]
+static ALLOCATOR: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+/// Sample allocations every 512 KiB on average for heap profiles.
+#[cfg(feature = "profiling")]
+#[allow(non_upper_case_globals)]
+#[export_name = "malloc_conf"]
+pub static malloc_conf: &[u8] = b"prof:true,prof_active:true,lg_prof_sample:19\0";
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let config = NodeConfig::from_env()?;
@@ -355,6 +1444,10 @@ async fn main() -> Result<()> {
     // Initialize logging
     init_logging(&config);
 
+    // ALLOCATOR is jemalloc, so heap profiles can be taken
+    #[cfg(feature = "profiling")]
+    observability::profiling::enable_heap_profiles();
+
     info!(
         node_id = %config.node_id,
         role = ?config.role,
@@ -372,21 +1465,38 @@ async fn main() -> Result<()> {
     }
 
     // Create node info
+    let capacity = NodeResources {
+        cpu_cores: config.cpu_cores,
+        memory_mb: config.memory_mb,
+        disk_mb: config.disk_mb,
+    };
+    let mut labels = config.labels.clone();
+    labels.insert(
+        NODE_VERSION_LABEL.to_string(),
+        env!("CARGO_PKG_VERSION").to_string(),
+    );
+    if !config.gpu_devices.is_empty() {
+        labels.insert(
+            NODE_GPU_LABEL.to_string(),
+            config.gpu_devices.len().to_string(),
+        );
+        labels.insert(
+            NODE_GPU_DEVICES_LABEL.to_string(),
+            GpuDevice::format_list(&config.gpu_devices),
+        );
+    }
     let self_node = Node {
         id: config.node_id,
         address: config.public_addr.to_string(),
         status: NodeStatus::Ready,
-        labels: HashMap::new(),
-        resources_capacity: NodeResources {
-            cpu_cores: config.cpu_cores,
-            memory_mb: config.memory_mb,
-            disk_mb: config.disk_mb,
-        },
-        resources_allocatable: NodeResources {
-            cpu_cores: config.cpu_cores * 0.9, // Reserve 10% for system
-            memory_mb: (config.memory_mb as f64 * 0.9) as u64,
-            disk_mb: (config.disk_mb as f64 * 0.9) as u64,
+        labels,
+        resources_capacity: capacity.clone(),
+        resources_allocatable: match &config.system_reserved {
+            Some(reserved) => reserved.allocatable(&capacity),
+            None => capacity.allocatable(),
         },
+        unschedulable: false,
+        resource_version: 0,
     };
 
     // Create chitchat cluster manager
@@ -430,6 +1540,29 @@ async fn main() -> Result<()> {
                 state_root: config.state_root.clone().into(),
                 command_timeout: Duration::from_secs(30),
                 stop_timeout: Duration::from_secs(10),
+                pull_queue: PullQueueConfig {
+                    max_concurrent_pulls: config.image_pull_concurrency,
+                    max_bandwidth_bytes_per_sec: config.image_pull_bandwidth,
+                },
+                log_archive_root: Some(
+                    std::path::Path::new(&config.bundle_root).with_file_name("log-archive"),
+                ),
+                log_rotation: LogRotationConfig {
+                    max_size_bytes: config.log_max_size_mb * 1024 * 1024,
+                    max_age: config.log_max_age,
+                    max_files: config.log_max_files,
+                    compress: config.log_compress,
+                },
+                checkpoint_root: std::path::Path::new(&config.bundle_root)
+                    .with_file_name("checkpoints"),
+                image_scanner: config.image_scanner.clone(),
+                lazy_pull: config.lazy_pull.clone(),
+                registry_mirror: config.registry_mirror.clone(),
+                credential_providers: config.credential_providers.clone(),
+                model_cache_bytes: config.model_cache_gb * 1024 * 1024 * 1024,
+                gpu_devices: config.gpu_devices.clone(),
+                node_local_dns: config.dns_cache_enabled,
+                ..Default::default()
             };
             match YoukiCliRuntime::with_config(youki_config).await {
                 Ok(runtime) => Arc::new(runtime),
@@ -443,11 +1576,45 @@ async fn main() -> Result<()> {
 
     // Create concrete stores first (needed for MCP server if enabled)
     let in_memory_store = InMemoryStateStore::new();
-    let simple_scheduler = SimpleScheduler;
 
     // Wrap in Arc for orchestrator service
-    let scheduler = Arc::new(simple_scheduler.clone());
+    #[cfg(not(feature = "etcd-store"))]
     let state_store: Arc<dyn StateStore> = Arc::new(in_memory_store.clone());
+    #[cfg(feature = "etcd-store")]
+    let state_store: Arc<dyn StateStore> = if config.etcd_endpoints.is_empty() {
+        Arc::new(in_memory_store.clone())
+    } else {
+        info!(endpoints = ?config.etcd_endpoints, "Using etcd state store");
+        Arc::new(
+            EtcdStateStore::new(config.etcd_endpoints.clone())
+                .await
+                .context("Failed to connect to etcd")?,
+        )
+    };
+    #[cfg(feature = "sql-store")]
+    let state_store: Arc<dyn StateStore> = match &config.state_store_url {
+        Some(url) => {
+            info!("Using SQL state store");
+            Arc::new(
+                SqlStateStore::connect(url)
+                    .await
+                    .context("Failed to open SQL state store")?,
+            )
+        }
+        None => state_store,
+    };
+    let scheduler: Arc<dyn Scheduler> = Arc::new(
+        StrategyScheduler::new(state_store.clone())
+            .with_default_strategy(&config.scheduler_strategy)
+            .context("Invalid SCHEDULER_STRATEGY")?,
+    );
+    info!("Default scheduling strategy: {}", config.scheduler_strategy);
+
+    if let Some(path) = &config.event_log_path {
+        replay::spawn_recorder(state_store.clone(), path)
+            .await
+            .context("Failed to open EVENT_LOG_PATH")?;
+    }
 
     // Convert to trait object for orchestrator
     let cluster_manager_trait: Arc<dyn ClusterManager> = cluster_manager.clone();
@@ -458,7 +1625,7 @@ async fn main() -> Result<()> {
         // Create MCP server with cloned concrete types
         let mcp_store = in_memory_store.clone();
         let mcp_cluster = (*cluster_manager).clone();  // Clone the ChitchatClusterManager
-        let mcp_scheduler = simple_scheduler;
+        let mcp_scheduler = SimpleScheduler;
 
         tokio::spawn(async move {
             let mcp_server = OrchestratorMcpServer::new(
@@ -474,15 +1641,82 @@ async fn main() -> Result<()> {
         });
     }
 
+    // Service endpoints, gated on readiness probes
+    let vip_allocator =
+        DualStackAllocator::new(&config.service_cidrs).context("Invalid SERVICE_CIDRS")?;
+    let service_proxy =
+        Arc::new(ServiceProxy::new(state_store.clone()).with_vip_allocator(vip_allocator));
+    service_proxy.clone().spawn(ServiceProxy::DEFAULT_SYNC_INTERVAL);
+    let readiness_prober = Arc::new(ReadinessProber::new(
+        state_store.clone(),
+        service_proxy.clone(),
+        Arc::new(NetworkProbeRunner::new()),
+    ));
+    readiness_prober.spawn(ReadinessProber::DEFAULT_PROBE_TICK);
+
+    // Carry connections to service virtual IPs to their endpoints
+    if config.service_forwarding {
+        Arc::new(ServiceForwarder::new(
+            service_proxy.clone(),
+            Arc::new(NftablesFirewall::new()),
+        ))
+        .spawn(ServiceForwarder::DEFAULT_INTERVAL);
+        info!("Service traffic forwarded through nftables redirects");
+    }
+
+    // Forward host ports to the instances running here
+    if config.host_ports {
+        Arc::new(HostPortPublisher::new(
+            state_store.clone(),
+            Arc::new(NftablesFirewall::new()),
+            config.node_id,
+        ))
+        .spawn(HostPortPublisher::DEFAULT_INTERVAL);
+        info!("Host ports published with nftables");
+    }
+
     // Start the orchestrator service
-    let _workload_tx = start_orchestrator_service(
+    state_store
+        .initialize()
+        .await
+        .context("Failed to initialize state store")?;
+
+    // Instances sharing a state store elect one leader to run controllers
+    let leader_elector = Arc::new(
+        LeaderElector::new(state_store.clone(), config.node_id.to_string())
+            .with_lease_duration(config.leader_lease_duration),
+    );
+    leader_elector.clone().spawn(config.leader_lease_duration / 3);
+
+    let instance_addresses = Arc::new(InstanceAddressAllocator::new(
+        state_store.clone(),
+        DualStackAllocator::new(&config.pod_cidrs).context("Invalid POD_CIDRS")?,
+    ));
+    // Preemption and the API evict through the same disruption budgets
+    let disruption = Arc::new(DisruptionController::new(
+        state_store.clone(),
+        runtime.clone(),
+    ));
+    let mut orchestrator = Orchestrator::new(
         state_store.clone(),
         runtime.clone(),
         cluster_manager_trait,
-        scheduler,
+        scheduler.clone(),
     )
-    .await
-    .context("Failed to start orchestrator service")?;
+    .with_service_proxy(service_proxy.clone())
+    .with_instance_addresses(instance_addresses.clone())
+    .with_leader_elector(leader_elector.clone())
+    .with_disruption_budgets(disruption.clone());
+    #[cfg(feature = "observability")]
+    {
+        orchestrator = orchestrator.with_observer(Arc::new(OrchestratorMetrics::new()));
+    }
+    let _workload_tx = orchestrator.get_workload_sender();
+    tokio::spawn(async move {
+        if let Err(e) = orchestrator.run().await {
+            error!("Orchestrator service exited with error: {:?}", e);
+        }
+    });
 
     info!(
         node_id = %config.node_id,
@@ -490,6 +1724,207 @@ async fn main() -> Result<()> {
         "Orchestrator service started"
     );
 
+    // Drive stateful sets towards their replicas and template
+    let stateful_sets = Arc::new(StatefulSetController::new(
+        state_store.clone(),
+        runtime.clone(),
+        cluster_manager.clone(),
+        scheduler,
+    ));
+
+    // Reclaim succeeded jobs, old revisions and old events
+    let gc_policy = RetentionPolicy {
+        succeeded_job_ttl: config.gc_job_ttl,
+        event_ttl: config.gc_event_ttl,
+        ..Default::default()
+    };
+    let garbage_collector = GarbageCollector::new(state_store.clone(), runtime.clone(), gc_policy)
+        .with_stateful_sets(stateful_sets.clone());
+    #[cfg(feature = "observability")]
+    let garbage_collector = garbage_collector.with_observer(Arc::new(OrchestratorMetrics::new()));
+    let garbage_collector = Arc::new(garbage_collector);
+
+    // Charge workloads for the resources their instances hold
+    let usage_meter = Arc::new(UsageMeter::new(state_store.clone()));
+
+    // Restart exited containers according to their workload's policy
+    Arc::new(RestartManager::new(state_store.clone(), runtime.clone()))
+        .spawn(RestartManager::DEFAULT_INTERVAL);
+
+    // Record the lifecycle events the runtime reports on their instances
+    Arc::new(ContainerEventForwarder::new(state_store.clone())).spawn(runtime.clone());
+
+    // Heartbeat container status deltas; the leading bootstrap node applies them
+    Arc::new(StatusReporter::new(
+        config.node_id,
+        runtime.clone(),
+        cluster_manager.clone(),
+    ))
+    .spawn(StatusReporter::DEFAULT_INTERVAL);
+
+    // Sample container resource usage for per-container metrics
+    let usage_scraper = UsageScraper::new(state_store.clone(), runtime.clone(), config.node_id);
+    #[cfg(feature = "observability")]
+    let usage_scraper = usage_scraper.with_observer(Arc::new(OrchestratorMetrics::new()));
+    // Flag instances straying from their workload's baselines
+    let usage_scraper = match config.anomaly_threshold {
+        Some(threshold) => {
+            let detector = AnomalyDetector::new(AnomalyConfig {
+                threshold,
+                ..Default::default()
+            })
+            .with_events(Arc::new(EventRecorder::new(
+                state_store.clone(),
+                "anomaly-detector",
+            )));
+            #[cfg(feature = "observability")]
+            let detector = detector.with_observer(Arc::new(OrchestratorMetrics::new()));
+            usage_scraper
+                .with_observer(Arc::new(detector))
+                .with_log_errors(anomaly::DEFAULT_ERROR_PATTERN)?
+        }
+        None => usage_scraper,
+    };
+    Arc::new(usage_scraper).spawn(UsageScraper::DEFAULT_INTERVAL);
+
+    // Ship container logs to Loki or Elasticsearch
+    #[cfg(feature = "log-forwarding")]
+    if let Some(log_config) = config.log_forwarding.clone() {
+        let forwarder =
+            LogForwarder::new(state_store.clone(), runtime.clone(), config.node_id, log_config)
+                .context("Failed to set up log forwarding")?;
+        Arc::new(forwarder).spawn(LogForwarder::DEFAULT_INTERVAL);
+    }
+    let status_collector = Arc::new(StatusCollector::new(cluster_manager.clone()));
+
+    // Renew this node's lease; the leading bootstrap node reschedules instances off
+    // nodes whose lease lapses
+    let renew_interval = (config.lease_duration / 4).min(LeaseRenewer::DEFAULT_INTERVAL);
+    Arc::new(LeaseRenewer::new(
+        cluster_manager.clone(),
+        config.node_id,
+        config.lease_duration,
+    ))
+    .spawn(renew_interval.max(Duration::from_secs(1)));
+    let node_lifecycle = Arc::new(
+        NodeLifecycleController::new(
+            cluster_manager.clone(),
+            state_store.clone(),
+            _workload_tx.clone(),
+        )
+        .with_lease_duration(config.lease_duration)
+        .with_eviction_grace_period(config.eviction_grace_period),
+    );
+
+    // Create jobs for the cron jobs registered through the API
+    let cron_jobs = Arc::new(CronJobController::new(
+        state_store.clone(),
+        runtime.clone(),
+        _workload_tx.clone(),
+    ));
+
+    // Place the instances of the daemon sets registered through the API
+    let daemon_sets = Arc::new(DaemonSetController::new(
+        state_store.clone(),
+        runtime.clone(),
+        cluster_manager.clone(),
+    ));
+
+    // Cluster-wide controllers run on the leader only
+    let is_bootstrap = config.role == NodeRole::Bootstrap;
+    let leader_cron_jobs = cron_jobs.clone();
+    let leader_daemon_sets = daemon_sets.clone();
+    let leader_stateful_sets = stateful_sets.clone();
+    let leader_instance_addresses = instance_addresses.clone();
+    leader_elector.spawn_while_leader(move || {
+        let mut tasks = vec![
+            garbage_collector
+                .clone()
+                .spawn(GarbageCollector::DEFAULT_INTERVAL),
+            usage_meter.clone().spawn(UsageMeter::DEFAULT_INTERVAL),
+            leader_cron_jobs
+                .clone()
+                .spawn(CronJobController::DEFAULT_TICK_INTERVAL),
+            leader_daemon_sets
+                .clone()
+                .spawn(DaemonSetController::DEFAULT_RESYNC_INTERVAL),
+            leader_stateful_sets
+                .clone()
+                .spawn(StatefulSetController::DEFAULT_RESYNC_INTERVAL),
+            // Reclaim the addresses of instances deleted anywhere
+            leader_instance_addresses
+                .clone()
+                .spawn(InstanceAddressAllocator::DEFAULT_RESYNC_INTERVAL),
+        ];
+        if is_bootstrap {
+            tasks.push(
+                status_collector
+                    .clone()
+                    .spawn(StatusCollector::DEFAULT_INTERVAL),
+            );
+            tasks.push(
+                node_lifecycle
+                    .clone()
+                    .spawn(NodeLifecycleController::DEFAULT_INTERVAL),
+            );
+        }
+        tasks
+    });
+
+    // Start the node-local DNS cache
+    if config.dns_cache_enabled {
+        // Instances reach the cache on an address no interface holds yet
+        let listen_ip = config.dns_cache_listen.ip();
+        let mut dns_config = DnsCacheConfig {
+            listen_addr: config.dns_cache_listen,
+            upstreams: config.dns_upstreams.clone(),
+            link: (!listen_ip.is_loopback() && !listen_ip.is_unspecified())
+                .then(|| dns_cache::LINK_NAME.to_string()),
+            ..Default::default()
+        };
+        if dns_config.upstreams.is_empty() {
+            let resolv_conf = std::fs::read_to_string("/etc/resolv.conf").unwrap_or_default();
+            dns_config = dns_config.with_upstreams_from_resolv_conf(&resolv_conf);
+        }
+
+        let dns_server = NodeLocalDnsServer::new(dns_config);
+        #[cfg(feature = "observability")]
+        let dns_server = dns_server.with_observer(Arc::new(OrchestratorMetrics::new()));
+
+        match Arc::new(dns_server).spawn().await {
+            Ok(_) => info!(listen = %config.dns_cache_listen, "Node-local DNS cache started"),
+            Err(e) => warn!("Node-local DNS cache not started: {}", e),
+        }
+    }
+
+    // Serve images to nodes that pull through this one
+    #[cfg(feature = "registry-cache")]
+    if let Some(cache_config) = config.registry_cache.clone() {
+        match Arc::new(RegistryCache::new(cache_config)).spawn().await {
+            Ok(_) => info!("Registry cache started"),
+            Err(e) => warn!("Registry cache not started: {}", e),
+        }
+    }
+
+    // Advertise exposed workloads on the LAN
+    if config.mdns_enabled {
+        let advertise_ip = if config.public_addr.ip().is_unspecified() {
+            mdns::lan_address()
+        } else {
+            Some(config.public_addr.ip())
+        };
+        match advertise_ip {
+            Some(ip) => {
+                let responder = Arc::new(MdnsResponder::new(MdnsConfig::new(vec![ip])));
+                match responder.spawn(state_store.clone()).await {
+                    Ok(_) => info!(address = %ip, "mDNS responder started"),
+                    Err(e) => warn!("mDNS responder not started: {}", e),
+                }
+            }
+            None => warn!("mDNS responder not started: no LAN address found"),
+        }
+    }
+
     // Start combined API/observability server
     #[cfg(feature = "observability")]
     {
@@ -497,34 +1932,195 @@ async fn main() -> Result<()> {
             format!("orchestrator-{}", config.node_id),
             env!("CARGO_PKG_VERSION"),
         );
+        health_checker.add_dependency("scheduler", "cluster_manager").await;
+        health_checker.add_dependency("scheduler", "state_store").await;
         health_checker.mark_healthy("cluster_manager").await;
         health_checker.mark_healthy("state_store").await;
         health_checker.mark_healthy("runtime").await;
+        health_checker.mark_healthy("scheduler").await;
+        #[cfg(feature = "rest-api")]
+        {
+            health_checker.add_dependency("api", "state_store").await;
+            health_checker.mark_healthy("api").await;
+        }
         health_checker.set_ready().await;
 
-        let metrics_registry = MetricsRegistry::new();
-        let obs_config = ObservabilityConfig::with_port(config.api_port);
+        // Bind the API on the wildcard address of the gossip family so
+        // IPv6-only nodes listen on `[::]` rather than `0.0.0.0`
+        let api_family = orchestrator_shared_types::IpFamily::of(&config.listen_addr.ip());
+        let obs_config = ObservabilityConfig::with_addr((api_family.unspecified(), config.api_port))
+            .with_metrics_backend(config.metrics_backend.clone());
+        let metrics_registry = MetricsRegistry::install(&obs_config.metrics_backend)
+            .context("Failed to install metrics backend")?;
 
         // Create event hub for WebSocket streaming
         let event_hub = EventHub::default();
         let event_hub_clone = event_hub.clone();
 
+        // Load the cluster CA and issue this node's API certificate
+        #[cfg(feature = "mtls")]
+        let (cluster_ca, tls_server) = match &config.tls_ca_dir {
+            Some(dir) => {
+                let ca = Arc::new(
+                    ClusterCa::load_or_create(dir, &config.cluster_id)
+                        .await
+                        .context("Failed to load cluster CA")?,
+                );
+                let mut names = vec!["localhost".to_string()];
+                if !config.public_addr.ip().is_unspecified() {
+                    names.push(config.public_addr.ip().to_string());
+                }
+                names.extend(config.tls_server_names.iter().cloned());
+                let server = TlsServer::new(ca.clone(), format!("node/{}", config.node_id), names)
+                    .and_then(|server| {
+                        server.with_client_cert_required(config.tls_require_client_cert)
+                    })
+                    .context("Failed to issue API server certificate")?;
+                let server = Arc::new(server);
+                info!(expires_at = %server.expires_at(), "Serving the API over mutual TLS");
+                server.clone().spawn_rotation();
+                (Some(ca), Some(server))
+            }
+            None => (None, None),
+        };
+
         // Build API router if rest-api feature is enabled
         #[cfg(feature = "rest-api")]
         let api_router = {
             // Create API state
-            let auth_config = if config.auth_disabled {
+            let mut auth_config = if config.auth_disabled {
                 AuthConfig::disabled()
             } else {
                 AuthConfig::default()
             };
+            if let Some(policy) = config.rbac_policy.clone() {
+                auth_config = auth_config.with_rbac(policy);
+            }
+            if let Some(issuer) = config.token_issuer.clone() {
+                auth_config = auth_config.with_token_issuer(issuer);
+            }
+            for token in &config.bootstrap_tokens {
+                auth_config = auth_config.with_bootstrap_token(token.clone());
+            }
+            #[cfg(feature = "oidc")]
+            if let Some(oidc) = config.oidc.clone() {
+                info!(issuer = %oidc.issuer, "Accepting OIDC bearer tokens");
+                auth_config = auth_config.with_oidc(OidcVerifier::new(oidc));
+            }
+            #[cfg(feature = "mtls")]
+            if let Some(ca) = cluster_ca {
+                auth_config = auth_config.with_certificate_authority(ca);
+            }
 
-            let api_state = ApiState::new(
+            let mut api_state = ApiState::new(
                 state_store.clone(),
                 cluster_manager.clone() as Arc<dyn ClusterManager>,
                 _workload_tx.clone(),
                 auth_config,
             );
+            api_state.set_service_proxy(service_proxy.clone());
+            api_state.set_disruption_controller(disruption.clone());
+            api_state.set_image_prepuller(Arc::new(ImagePrepuller::new(
+                state_store.clone(),
+                runtime.clone(),
+            )));
+            api_state.set_admission_limits(config.admission_limits.clone());
+            if !config.admission_webhooks.is_empty() {
+                let webhooks = AdmissionWebhooks::new(config.admission_webhooks.clone());
+                api_state.set_admission_webhooks(Arc::new(webhooks));
+                info!(webhooks = config.admission_webhooks.len(), "Calling admission webhooks");
+            }
+            api_state.set_leader_elector(leader_elector.clone());
+            api_state.set_cron_job_controller(cron_jobs.clone());
+            api_state.set_daemon_set_controller(daemon_sets.clone());
+            api_state.set_stateful_set_controller(stateful_sets.clone());
+            if !config.audit_sinks.is_empty() {
+                let sinks = config.audit_sinks.iter().map(AuditSinkConfig::build).collect();
+                api_state.set_audit_log(Arc::new(AuditLog::new(sinks)));
+                info!(sinks = config.audit_sinks.len(), "Recording API audit log");
+            }
+            if let Some(rate_limit) = config.rate_limit {
+                let limiter = RateLimiter::new(rate_limit);
+                #[cfg(feature = "observability")]
+                let limiter = limiter.with_observer(Arc::new(OrchestratorMetrics::new()));
+                api_state.set_rate_limiter(Arc::new(limiter));
+                info!(
+                    requests_per_second = rate_limit.requests_per_second,
+                    burst = rate_limit.burst,
+                    "Rate limiting API clients"
+                );
+            }
+            if config.federation_enabled {
+                let key = Federation::load_or_create_key(&config.federation_key_file)
+                    .await
+                    .context("Failed to load FEDERATION_KEY_FILE")?;
+                let federation =
+                    Federation::new(Federation::DEFAULT_TIMEOUT, state_store.clone(), key)
+                        .context("Failed to create federation client")?;
+                api_state.set_federation(Arc::new(federation));
+                info!("Federation enabled; register remote clusters through the API");
+            }
+
+            // Serve temporary tunnels on edge nodes
+            if let Some(ports) = config.tunnel_ports.clone() {
+                let public_ip = if config.public_addr.ip().is_unspecified() {
+                    mdns::lan_address()
+                } else {
+                    Some(config.public_addr.ip())
+                };
+                match public_ip {
+                    Some(ip) => {
+                        let tunnels = Arc::new(
+                            TunnelManager::new(state_store.clone(), ip).with_port_range(ports),
+                        );
+                        tunnels.clone().spawn(TunnelManager::DEFAULT_INTERVAL);
+                        api_state.set_tunnel_manager(tunnels);
+                        info!(address = %ip, "Tunnels enabled on this edge node");
+                    }
+                    None => warn!("Tunnels not enabled: no public address found"),
+                }
+            }
+
+            // Enforce network policies between workloads
+            if config.network_policies {
+                let policies = Arc::new(NetworkPolicyController::new(
+                    state_store.clone(),
+                    Arc::new(NftablesFirewall::new()),
+                ));
+                policies
+                    .clone()
+                    .spawn(NetworkPolicyController::DEFAULT_INTERVAL);
+                api_state.set_network_policy_controller(policies);
+                info!("Network policies enforced with nftables");
+            }
+
+            // Resize autoscaled workloads on the leader, through the scale endpoint's path
+            #[cfg(feature = "scaling-predictor")]
+            if let Some(url) = config.autoscaler_metrics_url.clone() {
+                let metrics = PrometheusMetrics::new(
+                    url,
+                    UsageScraper::DEFAULT_INTERVAL,
+                    Duration::from_secs(10),
+                )
+                .context("Invalid AUTOSCALER_PROMETHEUS_URL")?;
+                let predictor: Arc<dyn ScalingPredictor> = match &config.scaling_predictor_url {
+                    Some(endpoint) => Arc::new(
+                        HttpPredictor::new(endpoint.clone(), Duration::from_secs(10))
+                            .context("Invalid SCALING_PREDICTOR_URL")?,
+                    ),
+                    None => Arc::new(MovingAveragePredictor::default()),
+                };
+                let autoscaler = Arc::new(Autoscaler::new(
+                    state_store.clone(),
+                    Arc::new(metrics),
+                    predictor,
+                    Arc::new(api_state.clone()),
+                ));
+                leader_elector.spawn_while_leader(move || {
+                    vec![autoscaler.clone().spawn(Autoscaler::DEFAULT_INTERVAL)]
+                });
+                info!("Autoscaling workloads from Prometheus CPU usage");
+            }
 
             // Build API router
             build_api_router(api_state)
@@ -544,6 +2140,26 @@ async fn main() -> Result<()> {
             .with_metrics(metrics_registry)
             .await;
 
+        // Evaluate alert rules against the metrics served above
+        #[cfg(feature = "alerting")]
+        let obs_server = if config.alert_rules.is_empty() {
+            obs_server
+        } else {
+            let engine = config
+                .alert_notifiers
+                .iter()
+                .cloned()
+                .fold(AlertEngine::new(config.alert_rules.clone()), AlertEngine::with_notifier);
+            let engine = Arc::new(engine);
+            engine.clone().spawn(obs_server.state(), AlertEngine::DEFAULT_INTERVAL);
+            info!(
+                rules = config.alert_rules.len(),
+                notifiers = config.alert_notifiers.len(),
+                "Alerting enabled"
+            );
+            obs_server.with_alert_engine(engine)
+        };
+
         // Start cluster event forwarder to WebSocket clients
         let cluster_manager_for_events = cluster_manager.clone();
         tokio::spawn(async move {
@@ -565,8 +2181,23 @@ async fn main() -> Result<()> {
             }
         });
 
-        // Start combined server using ObservabilityServer's serve method
+        // Start combined server using ObservabilityServer's serve method,
+        // or over mutual TLS with its routes when a cluster CA is configured
+        #[cfg(feature = "mtls")]
+        let api_addr = obs_config.bind_addr;
         tokio::spawn(async move {
+            #[cfg(feature = "mtls")]
+            if let Some(tls) = tls_server {
+                let mut obs_server = obs_server;
+                let result = match tokio::net::TcpListener::bind(api_addr).await {
+                    Ok(listener) => tls.serve(listener, obs_server.router()).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    error!("API server error: {}", e);
+                }
+                return;
+            }
             if let Err(e) = obs_server.serve().await {
                 error!("API server error: {}", e);
             }
@@ -602,6 +2233,22 @@ async fn main() -> Result<()> {
         }
     }
 
+    // Register with the control plane; only deregister what was registered
+    #[cfg(feature = "rest-api")]
+    let registration = match &config.registration {
+        Some((api_url, token)) => match register_node(api_url, token, &self_node).await {
+            Ok(()) => {
+                info!("Registered with {}", api_url);
+                config.registration.clone()
+            }
+            Err(e) => {
+                warn!("Failed to register with {}: {}", api_url, e);
+                None
+            }
+        },
+        None => None,
+    };
+
     // Keep running
     info!("Node is running. Press Ctrl+C to stop.");
 
@@ -612,6 +2259,18 @@ async fn main() -> Result<()> {
 
     info!("Shutting down...");
 
+    #[cfg(feature = "rest-api")]
+    if let Some((api_url, token)) = &registration {
+        if let Err(e) = deregister_node(api_url, token, &self_node.id.to_string()).await {
+            warn!("Failed to deregister from {}: {}", api_url, e);
+        }
+    }
+
+    // Hand leadership over without waiting for the lease to expire
+    if let Err(e) = leader_elector.resign().await {
+        warn!("Failed to resign leadership on shutdown: {}", e);
+    }
+
     // Update status to NotReady before shutdown
     if let Err(e) = cluster_manager.update_self_status(NodeStatus::NotReady).await {
         warn!("Failed to update status on shutdown: {}", e);
@@ -0,0 +1,133 @@
+//! Replays a recorded event log through the reconciler against the mock
+//! runtime, to reproduce a control-plane incident locally.
+//!
+//! # Usage
+//!
+//! ```text
+//! orchestrator_replay EVENT_LOG [--exit IMAGE=CODE]... [--until N] [--json]
+//! ```
+//!
+//! - `EVENT_LOG`: JSON-lines log written by a node started with `EVENT_LOG_PATH`
+//! - `--exit IMAGE=CODE`: Containers of `IMAGE` exit with `CODE` as soon as they
+//!   start, to reproduce crash loops (repeatable)
+//! - `--until N`: Stop after the Nth entry
+//! - `--json`: Print each step as a JSON line instead of text
+//!
+//! Set `RUST_LOG=info` to see the reconciler's own decisions between steps.
+
+use anyhow::{bail, Context, Result};
+
+use orchestrator_core::replay::{parse_log, ReplayStep, Replayer};
+
+/// Parsed command-line arguments.
+struct Args {
+    log_path: String,
+    exits: Vec<(String, i32)>,
+    until: Option<usize>,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut log_path = None;
+    let mut exits = Vec::new();
+    let mut until = None;
+    let mut json = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--exit" => {
+                let value = args.next().context("--exit needs IMAGE=CODE")?;
+                let (image, code) = value
+                    .rsplit_once('=')
+                    .with_context(|| format!("expected IMAGE=CODE, got {}", value))?;
+                exits.push((
+                    image.to_string(),
+                    code.parse().context("invalid exit code")?,
+                ));
+            }
+            "--until" => {
+                let value = args.next().context("--until needs an entry number")?;
+                until = Some(value.parse().context("invalid --until")?);
+            }
+            "--json" => json = true,
+            _ if arg.starts_with("--") => bail!("unknown option {}", arg),
+            _ if log_path.is_none() => log_path = Some(arg),
+            _ => bail!("unexpected argument {}", arg),
+        }
+    }
+
+    Ok(Args {
+        log_path: log_path.context(
+            "usage: orchestrator_replay EVENT_LOG [--exit IMAGE=CODE]... [--until N] [--json]",
+        )?,
+        exits,
+        until,
+        json,
+    })
+}
+
+fn print_step(step: &ReplayStep) {
+    println!("#{} {} {}", step.index, step.at.to_rfc3339(), step.event);
+    if let Some(error) = &step.error {
+        println!("    error: {}", error);
+    }
+    let restarts = &step.restarts;
+    if restarts.restarted + restarts.backing_off + restarts.failed > 0 {
+        println!(
+            "    restarts: {} restarted, {} backing off, {} failed",
+            restarts.restarted, restarts.backing_off, restarts.failed
+        );
+    }
+    for workload in &step.workloads {
+        let instances: Vec<String> = workload
+            .instances
+            .iter()
+            .map(|(status, count)| format!("{} {}", count, status))
+            .collect();
+        println!(
+            "    {} ({} replicas): {}",
+            workload.name,
+            workload.replicas,
+            if instances.is_empty() {
+                "no instances".to_string()
+            } else {
+                instances.join(", ")
+            }
+        );
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    let args = parse_args()?;
+    let contents = std::fs::read_to_string(&args.log_path)
+        .with_context(|| format!("cannot read {}", args.log_path))?;
+    let mut entries = parse_log(&contents)?;
+    if let Some(until) = args.until {
+        entries.truncate(until);
+    }
+
+    let replayer = Replayer::new().await?;
+    for (image, code) in args.exits {
+        replayer.runtime().set_completion(image, code).await;
+    }
+
+    for (index, entry) in entries.iter().enumerate() {
+        let step = replayer.step(index + 1, entry).await?;
+        if args.json {
+            println!("{}", serde_json::to_string(&step)?);
+        } else {
+            print_step(&step);
+        }
+    }
+    Ok(())
+}
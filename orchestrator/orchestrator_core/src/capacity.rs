@@ -0,0 +1,173 @@
+//! Detection of a node's capacity.
+//!
+//! Nodes report the CPU cores, memory and disk of the machine they run on as
+//! their capacity, and what is left after a [`SystemReserved`] overhead for
+//! the OS and the agent itself as allocatable. CPU and memory honour cgroup
+//! limits, so a node in a container reports its share rather than the host's.
+//! Disk is the size of the filesystem holding the given data directory.
+//!
+//! GPUs are counted through the NVIDIA driver's `/proc` entries. As they can be
+//! shared or partitioned, they are reported as the
+//! [`NODE_GPU_LABEL`](orchestrator_shared_types::NODE_GPU_LABEL) and
+//! [`NODE_GPU_DEVICES_LABEL`](orchestrator_shared_types::NODE_GPU_DEVICES_LABEL)
+//! labels rather than in [`NodeResources`].
+
+use std::path::{Path, PathBuf};
+
+use orchestrator_shared_types::NodeResources;
+use sysinfo::{CpuRefreshKind, Disks, MemoryRefreshKind, RefreshKind, System};
+
+/// Where the NVIDIA driver lists one directory per GPU.
+pub const NVIDIA_GPU_DIR: &str = "/proc/driver/nvidia/gpus";
+
+/// What this machine offers to instances.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DetectedCapacity {
+    pub resources: NodeResources,
+    pub gpus: u32,
+}
+
+/// Detect the capacity of this machine, with disk measured on the filesystem
+/// holding `data_dir`. Anything undetectable is reported as zero.
+pub fn detect(data_dir: &Path) -> DetectedCapacity {
+    let system = System::new_with_specifics(
+        RefreshKind::new()
+            .with_cpu(CpuRefreshKind::new())
+            .with_memory(MemoryRefreshKind::new().with_ram()),
+    );
+    let cpu_cores = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or_else(|_| system.cpus().len());
+    let memory = match system.cgroup_limits() {
+        Some(limits) => limits.total_memory.min(system.total_memory()),
+        None => system.total_memory(),
+    };
+    let disks: Vec<(PathBuf, u64)> = Disks::new_with_refreshed_list()
+        .iter()
+        .map(|disk| (disk.mount_point().to_path_buf(), disk.total_space()))
+        .collect();
+
+    DetectedCapacity {
+        resources: NodeResources {
+            cpu_cores: cpu_cores as f32,
+            memory_mb: memory / (1024 * 1024),
+            disk_mb: disk_size(&disks, data_dir).unwrap_or(0) / (1024 * 1024),
+        },
+        gpus: count_gpus(Path::new(NVIDIA_GPU_DIR)),
+    }
+}
+
+/// Size of the disk mounted closest above `path`.
+fn disk_size(disks: &[(PathBuf, u64)], path: &Path) -> Option<u64> {
+    disks
+        .iter()
+        .filter(|(mount, _)| path.starts_with(mount))
+        .max_by_key(|(mount, _)| mount.components().count())
+        .map(|(_, size)| *size)
+}
+
+/// GPUs listed in `driver_dir`; none when the driver is not loaded.
+pub fn count_gpus(driver_dir: &Path) -> u32 {
+    std::fs::read_dir(driver_dir)
+        .map(|entries| entries.filter_map(|e| e.ok()).count() as u32)
+        .unwrap_or(0)
+}
+
+/// Resources held back from instances for the OS and the node agent.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SystemReserved {
+    pub cpu_cores: f32,
+    pub memory_mb: u64,
+    pub disk_mb: u64,
+}
+
+impl SystemReserved {
+    /// Parse a reservation such as "cpu=0.5,memory_mb=1024,disk_mb=10240";
+    /// omitted resources are not reserved.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut reserved = Self::default();
+        for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got '{}'", pair))?;
+            let invalid = || format!("invalid {} '{}'", key, value);
+            let value = value.trim();
+            match key.trim() {
+                "cpu" => reserved.cpu_cores = value.parse().map_err(|_| invalid())?,
+                "memory_mb" => reserved.memory_mb = value.parse().map_err(|_| invalid())?,
+                "disk_mb" => reserved.disk_mb = value.parse().map_err(|_| invalid())?,
+                other => return Err(format!("unknown resource '{}'", other)),
+            }
+        }
+        if !(reserved.cpu_cores >= 0.0 && reserved.cpu_cores.is_finite()) {
+            return Err(format!("invalid cpu '{}'", reserved.cpu_cores));
+        }
+        Ok(reserved)
+    }
+
+    /// What instances may use of `capacity` once this is held back.
+    pub fn allocatable(&self, capacity: &NodeResources) -> NodeResources {
+        NodeResources {
+            cpu_cores: (capacity.cpu_cores - self.cpu_cores).max(0.0),
+            memory_mb: capacity.memory_mb.saturating_sub(self.memory_mb),
+            disk_mb: capacity.disk_mb.saturating_sub(self.disk_mb),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_reserved() {
+        let reserved = SystemReserved::parse("cpu=0.5, memory_mb=1024").unwrap();
+        assert_eq!(reserved.disk_mb, 0);
+        let capacity = NodeResources {
+            cpu_cores: 4.0,
+            memory_mb: 512,
+            disk_mb: 2048,
+        };
+        assert_eq!(
+            reserved.allocatable(&capacity),
+            NodeResources {
+                cpu_cores: 3.5,
+                memory_mb: 0,
+                disk_mb: 2048,
+            }
+        );
+
+        assert!(SystemReserved::parse("gpu=1").is_err());
+        assert!(SystemReserved::parse("cpu=-1").is_err());
+        assert!(SystemReserved::parse("memory_mb").is_err());
+        assert_eq!(
+            SystemReserved::parse("").unwrap(),
+            SystemReserved::default()
+        );
+    }
+
+    #[test]
+    fn test_disk_size_uses_closest_mount() {
+        let disks = vec![
+            (PathBuf::from("/"), 100),
+            (PathBuf::from("/var/lib"), 500),
+            (PathBuf::from("/var/lib2"), 900),
+        ];
+        assert_eq!(
+            disk_size(&disks, Path::new("/var/lib/orchestrator")),
+            Some(500)
+        );
+        assert_eq!(disk_size(&disks, Path::new("/home")), Some(100));
+        assert_eq!(disk_size(&[], Path::new("/home")), None);
+    }
+
+    #[test]
+    fn test_count_gpus() {
+        let dir = std::env::temp_dir().join(format!("gpus-{}", uuid::Uuid::new_v4()));
+        assert_eq!(count_gpus(&dir), 0);
+        std::fs::create_dir_all(dir.join("0000:01:00.0")).unwrap();
+        std::fs::create_dir_all(dir.join("0000:02:00.0")).unwrap();
+        assert_eq!(count_gpus(&dir), 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,246 @@
+//! Recording the lifecycle events containers report.
+//!
+//! Runtimes that publish [`ContainerEvent`]s (creates, starts, OOM kills,
+//! exits and removals) tell the orchestrator as they happen, instead of it
+//! finding out on the next status poll. The [`ContainerEventForwarder`]
+//! records each on the instance that owns the container, so `orch events`
+//! shows why an instance restarted or failed.
+//!
+//! A container is created before its instance lists it, so events of unknown
+//! containers wait up to [`ContainerEventForwarder::PENDING_TTL`] for the
+//! instance to be stored, and are dropped after that, e.g. the removals of
+//! deleted instances' containers.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+
+use crate::events::EventRecorder;
+use container_runtime_interface::{ContainerEvent, ContainerEventKind, ContainerRuntime};
+use orchestrator_shared_types::{Result, WorkloadInstance};
+use state_store_interface::StateStore;
+
+/// Records container lifecycle events on their instances.
+pub struct ContainerEventForwarder {
+    state_store: Arc<dyn StateStore>,
+    events: EventRecorder,
+}
+
+impl ContainerEventForwarder {
+    /// How long an event of a container no instance lists is kept.
+    pub const PENDING_TTL: Duration = Duration::from_secs(30);
+
+    /// How often pending events are matched against instances again.
+    const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new(state_store: Arc<dyn StateStore>) -> Self {
+        Self {
+            events: EventRecorder::new(state_store.clone(), "container-runtime"),
+            state_store,
+        }
+    }
+
+    /// Record the `pending` events whose containers an instance lists, in
+    /// order, keeping the others until they are older than
+    /// [`PENDING_TTL`](Self::PENDING_TTL) as of `now`.
+    pub async fn flush(&self, pending: &mut Vec<ContainerEvent>, now: SystemTime) -> Result<()> {
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let instances = self.state_store.list_all_instances().await?;
+        let owners: HashMap<&str, &WorkloadInstance> = instances
+            .iter()
+            .flat_map(|instance| {
+                instance
+                    .container_ids
+                    .iter()
+                    .map(move |id| (id.as_str(), instance))
+            })
+            .collect();
+
+        // Later events of a container wait for its earlier ones
+        let mut waiting = HashSet::new();
+        let mut kept = Vec::new();
+        for event in pending.drain(..) {
+            let owner = owners.get(event.container_id.as_str());
+            match owner {
+                Some(instance) if !waiting.contains(&event.container_id) => {
+                    self.record(instance, &event).await;
+                }
+                _ => {
+                    let age = now.duration_since(event.timestamp).unwrap_or_default();
+                    if age < Self::PENDING_TTL {
+                        waiting.insert(event.container_id.clone());
+                        kept.push(event);
+                    } else {
+                        debug!(
+                            "Dropping {:?} event of container {}, which no instance lists",
+                            event.kind, event.container_id
+                        );
+                    }
+                }
+            }
+        }
+        *pending = kept;
+        Ok(())
+    }
+
+    async fn record(&self, instance: &WorkloadInstance, event: &ContainerEvent) {
+        let id = &event.container_id;
+        match &event.kind {
+            ContainerEventKind::Created => {
+                self.events
+                    .instance_normal(instance, "Created", format!("Created container {}", id))
+                    .await
+            }
+            ContainerEventKind::Started => {
+                self.events
+                    .instance_normal(instance, "Started", format!("Started container {}", id))
+                    .await
+            }
+            ContainerEventKind::OomKilled => {
+                let message = format!("Container {} was killed for exceeding its memory limit", id);
+                self.events
+                    .instance_warning(instance, "OOMKilled", message)
+                    .await
+            }
+            ContainerEventKind::Exited { exit_code: Some(0) } => {
+                let message = format!("Container {} exited with code 0", id);
+                self.events
+                    .instance_normal(instance, "Exited", message)
+                    .await
+            }
+            ContainerEventKind::Exited { exit_code } => {
+                let message = match exit_code {
+                    Some(code) => format!("Container {} exited with code {}", id, code),
+                    None => format!("Container {} exited", id),
+                };
+                self.events
+                    .instance_warning(instance, "Exited", message)
+                    .await
+            }
+            ContainerEventKind::Removed => {
+                self.events
+                    .instance_normal(instance, "Removed", format!("Removed container {}", id))
+                    .await
+            }
+        }
+    }
+
+    /// Forward the events of `runtime` until it stops publishing them, or
+    /// `None` when it publishes none.
+    pub fn spawn(self: Arc<Self>, runtime: Arc<dyn ContainerRuntime>) -> Option<JoinHandle<()>> {
+        let mut events = runtime.events()?;
+        Some(tokio::spawn(async move {
+            let mut pending = Vec::new();
+            let mut retry = tokio::time::interval(Self::RETRY_INTERVAL);
+            loop {
+                tokio::select! {
+                    received = events.recv() => match received {
+                        Ok(event) => pending.push(event),
+                        Err(RecvError::Lagged(missed)) => {
+                            warn!("Missed {} container events", missed);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                    _ = retry.tick(), if !pending.is_empty() => {}
+                }
+                if let Err(e) = self.flush(&mut pending, SystemTime::now()).await {
+                    error!("Failed to record container events: {:?}", e);
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orchestrator_shared_types::{EventType, Keypair, WorkloadInstanceStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use uuid::Uuid;
+
+    fn instance(container_ids: &[&str]) -> WorkloadInstance {
+        WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: Uuid::new_v4(),
+            node_id: Keypair::generate().public_key(),
+            container_ids: container_ids.iter().map(|id| id.to_string()).collect(),
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_are_recorded_on_their_instance() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let forwarder = ContainerEventForwarder::new(store.clone());
+        let api = instance(&["api-0"]);
+        store.put_instance(api.clone()).await.unwrap();
+
+        let mut pending = vec![
+            ContainerEvent::now("api-0", ContainerEventKind::OomKilled),
+            ContainerEvent::now(
+                "api-0",
+                ContainerEventKind::Exited {
+                    exit_code: Some(137),
+                },
+            ),
+        ];
+        forwarder
+            .flush(&mut pending, SystemTime::now())
+            .await
+            .unwrap();
+        assert!(pending.is_empty());
+
+        let events = store.list_events().await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|e| e.involved_object.id == api.id.to_string()
+                && e.event_type == EventType::Warning
+                && e.source == "container-runtime"));
+        assert!(events.iter().any(|e| e.reason == "OOMKilled"));
+        assert!(events
+            .iter()
+            .any(|e| e.message == "Container api-0 exited with code 137"));
+    }
+
+    #[tokio::test]
+    async fn test_events_of_unknown_containers_wait_for_their_instance() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let forwarder = ContainerEventForwarder::new(store.clone());
+        let now = SystemTime::now();
+
+        let mut pending = vec![
+            ContainerEvent::now("web-0", ContainerEventKind::Created),
+            ContainerEvent::now("web-0", ContainerEventKind::Started),
+            ContainerEvent::now("gone-0", ContainerEventKind::Removed),
+        ];
+        forwarder.flush(&mut pending, now).await.unwrap();
+        assert_eq!(pending.len(), 3);
+
+        store.put_instance(instance(&["web-0"])).await.unwrap();
+        let later = now + ContainerEventForwarder::PENDING_TTL + Duration::from_secs(1);
+        forwarder.flush(&mut pending, later).await.unwrap();
+        assert!(pending.is_empty());
+
+        let mut reasons: Vec<_> = store
+            .list_events()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.reason)
+            .collect();
+        reasons.sort();
+        assert_eq!(reasons, vec!["Created", "Started"]);
+    }
+}
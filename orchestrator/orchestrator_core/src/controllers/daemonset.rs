@@ -0,0 +1,568 @@
+//! DaemonSet controller.
+//!
+//! A [`DaemonSet`] runs exactly one instance of its template on every Ready
+//! node that matches its node selector. The controller bypasses the scheduler:
+//! placement is fully determined by cluster membership. It reconciles on
+//! every cluster event and on a periodic resync.
+//!
+//! - Matching Ready nodes without an instance get one.
+//! - Instances on nodes that left the cluster or no longer match the
+//!   selector are stopped and removed.
+//! - Nodes that are temporarily NotReady or cordoned keep their instance but
+//!   do not receive a new one, so a drained node stays empty.
+//!
+//! Daemon sets are stored as resources and reloaded each time the controller
+//! starts, so a newly elected leader keeps placing their instances.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use cluster_manager_interface::ClusterManager;
+use container_runtime_interface::{
+    ContainerRuntime, CreateContainerOptions, InstanceSpec, PullPriority,
+};
+use orchestrator_shared_types::{
+    Node, NodeId, OrchestrationError, Result, WorkloadDefinition, WorkloadInstance,
+    WorkloadInstanceStatus,
+};
+use state_store_interface::StateStore;
+
+use super::{list_nodes, node_matches_selector, DAEMON_SET_NAME_LABEL};
+use crate::resources::{self, Resource};
+
+pub type DaemonSetId = Uuid;
+
+/// A workload that runs one instance per matching node.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DaemonSet {
+    pub id: DaemonSetId,
+    pub name: String,
+    /// Template for the per-node instance. `replicas` is ignored.
+    pub template: WorkloadDefinition,
+    /// Only nodes carrying all of these labels run an instance.
+    #[serde(default)]
+    pub node_selector: HashMap<String, String>,
+}
+
+impl DaemonSet {
+    pub fn new(name: impl Into<String>, template: WorkloadDefinition) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            template,
+            node_selector: HashMap::new(),
+        }
+    }
+
+    pub fn with_node_selector(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.node_selector.insert(key.into(), value.into());
+        self
+    }
+
+    /// The id of the workload backing this daemon set.
+    pub fn workload_id(&self) -> Uuid {
+        self.template.id
+    }
+}
+
+impl Resource for DaemonSet {
+    const KIND: &'static str = "daemonsets";
+
+    fn key(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+/// Controller keeping one instance of each daemon set on every matching node.
+pub struct DaemonSetController {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    cluster_manager: Arc<dyn ClusterManager>,
+    daemon_sets: RwLock<HashMap<DaemonSetId, DaemonSet>>,
+}
+
+impl DaemonSetController {
+    /// How often the controller resyncs when no cluster events arrive.
+    pub const DEFAULT_RESYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+    pub fn new(
+        state_store: Arc<dyn StateStore>,
+        runtime: Arc<dyn ContainerRuntime>,
+        cluster_manager: Arc<dyn ClusterManager>,
+    ) -> Self {
+        Self {
+            state_store,
+            runtime,
+            cluster_manager,
+            daemon_sets: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Register a daemon set, persist its workload, and place its instances.
+    pub async fn add_daemon_set(&self, mut daemon_set: DaemonSet) -> Result<DaemonSetId> {
+        daemon_set
+            .template
+            .labels
+            .insert(DAEMON_SET_NAME_LABEL.to_string(), daemon_set.name.clone());
+        daemon_set.template.name = daemon_set.name.clone();
+        self.state_store
+            .put_workload(daemon_set.template.clone())
+            .await?;
+        resources::put(self.state_store.as_ref(), &daemon_set).await?;
+
+        let id = daemon_set.id;
+        info!("Registered daemon set {} ({})", daemon_set.name, id);
+        self.daemon_sets
+            .write()
+            .await
+            .insert(id, daemon_set.clone());
+
+        let nodes = list_nodes(self.cluster_manager.as_ref(), self.state_store.as_ref()).await?;
+        self.reconcile_daemon_set(&daemon_set, &nodes).await?;
+        Ok(id)
+    }
+
+    /// Remove a daemon set and tear down all of its instances.
+    pub async fn remove_daemon_set(&self, id: &DaemonSetId) -> Result<Option<DaemonSet>> {
+        let Some(daemon_set) = self.daemon_sets.write().await.remove(id) else {
+            return Ok(None);
+        };
+        let instances = self
+            .state_store
+            .list_instances_for_workload(&daemon_set.workload_id())
+            .await?;
+        for instance in &instances {
+            self.remove_instance(instance).await;
+        }
+        self.state_store
+            .delete_workload(&daemon_set.workload_id())
+            .await?;
+        resources::delete::<DaemonSet>(self.state_store.as_ref(), &id.to_string()).await?;
+        info!("Removed daemon set {} ({})", daemon_set.name, id);
+        Ok(Some(daemon_set))
+    }
+
+    /// Get a daemon set as stored, which any node can answer.
+    pub async fn get_daemon_set(&self, id: &DaemonSetId) -> Result<Option<DaemonSet>> {
+        resources::get(self.state_store.as_ref(), &id.to_string()).await
+    }
+
+    /// List the stored daemon sets, which any node can answer.
+    pub async fn list_daemon_sets(&self) -> Result<Vec<DaemonSet>> {
+        resources::list(self.state_store.as_ref()).await
+    }
+
+    /// Replace the daemon sets held in memory with those in the state store,
+    /// as the controller does each time it starts.
+    pub async fn load(&self) -> Result<()> {
+        let daemon_sets: Vec<DaemonSet> = resources::list(self.state_store.as_ref()).await?;
+        info!("Loaded {} daemon set(s)", daemon_sets.len());
+        *self.daemon_sets.write().await = daemon_sets.into_iter().map(|d| (d.id, d)).collect();
+        Ok(())
+    }
+
+    /// Load the stored daemon sets, reconcile, and then keep reconciling on
+    /// cluster events and every `resync_interval`.
+    pub fn spawn(self: Arc<Self>, resync_interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(resync_interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.load().await {
+                    error!("Failed to load daemon sets: {:?}", e);
+                    continue;
+                }
+                break;
+            }
+            let mut events = match self.cluster_manager.subscribe_to_events().await {
+                Ok(rx) => rx,
+                Err(e) => {
+                    error!(
+                        "Daemon set controller failed to subscribe to cluster events: {:?}",
+                        e
+                    );
+                    return;
+                }
+            };
+            loop {
+                tokio::select! {
+                    changed = events.changed() => {
+                        if changed.is_err() {
+                            warn!("Cluster event channel closed; daemon set controller stopping");
+                            break;
+                        }
+                        let event = events.borrow_and_update().clone();
+                        if let Some(event) = event {
+                            debug!("Daemon set controller reacting to {:?}", event);
+                        }
+                    }
+                    _ = interval.tick() => {}
+                }
+                if let Err(e) = self.reconcile_all().await {
+                    error!("Daemon set reconciliation failed: {:?}", e);
+                }
+            }
+        })
+    }
+
+    /// Reconcile every registered daemon set against current cluster membership.
+    pub async fn reconcile_all(&self) -> Result<()> {
+        let nodes = list_nodes(self.cluster_manager.as_ref(), self.state_store.as_ref()).await?;
+        let daemon_sets: Vec<DaemonSet> = self.daemon_sets.read().await.values().cloned().collect();
+        for daemon_set in daemon_sets {
+            if let Err(e) = self.reconcile_daemon_set(&daemon_set, &nodes).await {
+                error!(
+                    "Failed to reconcile daemon set {}: {:?}",
+                    daemon_set.name, e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn reconcile_daemon_set(&self, daemon_set: &DaemonSet, nodes: &[Node]) -> Result<()> {
+        let instances = self
+            .state_store
+            .list_instances_for_workload(&daemon_set.workload_id())
+            .await?;
+
+        let matching: HashMap<NodeId, &Node> = nodes
+            .iter()
+            .filter(|n| node_matches_selector(n, &daemon_set.node_selector))
+            .map(|n| (n.id, n))
+            .collect();
+
+        let mut covered: HashMap<NodeId, usize> = HashMap::new();
+        for instance in &instances {
+            let active = matches!(
+                instance.status,
+                WorkloadInstanceStatus::Running
+                    | WorkloadInstanceStatus::Pending
+                    | WorkloadInstanceStatus::CrashLoopBackOff
+            );
+            if !matching.contains_key(&instance.node_id) {
+                info!(
+                    "Daemon set {}: removing instance {} from node {} (left cluster or no longer matches)",
+                    daemon_set.name, instance.id, instance.node_id
+                );
+                self.remove_instance(instance).await;
+                continue;
+            }
+            if !active {
+                // Finished or failed instances are replaced below
+                self.remove_instance(instance).await;
+                continue;
+            }
+            let count = covered.entry(instance.node_id).or_insert(0);
+            *count += 1;
+            if *count > 1 {
+                info!(
+                    "Daemon set {}: removing duplicate instance {} on node {}",
+                    daemon_set.name, instance.id, instance.node_id
+                );
+                self.remove_instance(instance).await;
+            }
+        }
+
+        for (node_id, node) in &matching {
+            if covered.contains_key(node_id) || !node.is_schedulable() {
+                continue;
+            }
+            if let Err(e) = self.create_instance(daemon_set, *node_id).await {
+                error!(
+                    "Daemon set {}: failed to create instance on node {}: {:?}",
+                    daemon_set.name, node_id, e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn create_instance(&self, daemon_set: &DaemonSet, node_id: NodeId) -> Result<()> {
+        let options = CreateContainerOptions {
+            workload_id: daemon_set.workload_id(),
+            node_id,
+            pull_priority: PullPriority::Interactive,
+        };
+        if daemon_set.template.containers.is_empty() {
+            return Err(OrchestrationError::ConfigError(format!(
+                "Daemon set {} has no containers",
+                daemon_set.name
+            )));
+        }
+        let spec = InstanceSpec::from_workload(&daemon_set.template);
+        let container_ids = self.runtime.start_instance(&spec, &options).await?;
+
+        let instance = WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: daemon_set.workload_id(),
+            node_id,
+            container_ids,
+            status: WorkloadInstanceStatus::Pending,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: daemon_set.template.namespace.clone(),
+            resource_version: 0,
+        };
+        info!(
+            "Daemon set {}: created instance {} on node {}",
+            daemon_set.name, instance.id, node_id
+        );
+        self.state_store.put_instance(instance).await
+    }
+
+    async fn remove_instance(&self, instance: &WorkloadInstance) {
+        if let Err(e) = self.runtime.stop_instance(&instance.container_ids).await {
+            warn!(
+                "Failed to stop containers of instance {}: {:?}",
+                instance.id, e
+            );
+        }
+        if let Err(e) = self
+            .state_store
+            .delete_instance(&instance.id.to_string())
+            .await
+        {
+            error!(
+                "Failed to delete instance {} from state: {:?}",
+                instance.id, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, StubRuntime};
+    use async_trait::async_trait;
+    use cluster_manager_interface::ClusterEvent;
+    use orchestrator_shared_types::{ContainerConfig, Keypair, NodeResources, NodeStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use std::sync::atomic::Ordering;
+    use tokio::sync::watch;
+
+    struct StaticClusterManager {
+        nodes: RwLock<Vec<Node>>,
+        events_tx: watch::Sender<Option<ClusterEvent>>,
+    }
+
+    impl StaticClusterManager {
+        fn new(nodes: Vec<Node>) -> Self {
+            let (events_tx, _) = watch::channel(None);
+            Self {
+                nodes: RwLock::new(nodes),
+                events_tx,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ClusterManager for StaticClusterManager {
+        async fn initialize(&self) -> Result<()> {
+            Ok(())
+        }
+        async fn get_node(&self, node_id: &NodeId) -> Result<Option<Node>> {
+            Ok(self
+                .nodes
+                .read()
+                .await
+                .iter()
+                .find(|n| &n.id == node_id)
+                .cloned())
+        }
+        async fn list_nodes(&self) -> Result<Vec<Node>> {
+            Ok(self.nodes.read().await.clone())
+        }
+        async fn subscribe_to_events(&self) -> Result<watch::Receiver<Option<ClusterEvent>>> {
+            Ok(self.events_tx.subscribe())
+        }
+    }
+
+    fn node(labels: &[(&str, &str)], status: NodeStatus) -> Node {
+        Node {
+            id: Keypair::generate().public_key(),
+            address: "10.0.0.1:8080".to_string(),
+            status,
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            resources_capacity: NodeResources::default(),
+            resources_allocatable: NodeResources::default(),
+            unschedulable: false,
+            resource_version: 0,
+        }
+    }
+
+    fn template() -> WorkloadDefinition {
+        WorkloadDefinition {
+            containers: vec![ContainerConfig {
+                image: "fluent-bit:latest".to_string(),
+                ..test_support::container("shipper")
+            }],
+            ..test_support::workload("log-shipper")
+        }
+    }
+
+    struct Harness {
+        controller: DaemonSetController,
+        store: Arc<InMemoryStateStore>,
+        runtime: Arc<StubRuntime>,
+        cluster: Arc<StaticClusterManager>,
+    }
+
+    fn harness(nodes: Vec<Node>) -> Harness {
+        let store = Arc::new(InMemoryStateStore::new());
+        let runtime = Arc::new(StubRuntime::default());
+        let cluster = Arc::new(StaticClusterManager::new(nodes));
+        let controller = DaemonSetController::new(store.clone(), runtime.clone(), cluster.clone());
+        Harness {
+            controller,
+            store,
+            runtime,
+            cluster,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_one_instance_per_ready_node() {
+        let h = harness(vec![
+            node(&[], NodeStatus::Ready),
+            node(&[], NodeStatus::Ready),
+            node(&[], NodeStatus::NotReady),
+        ]);
+        let ds = DaemonSet::new("log-shipper", template());
+        let workload_id = ds.workload_id();
+        h.controller.add_daemon_set(ds).await.unwrap();
+
+        let instances = h
+            .store
+            .list_instances_for_workload(&workload_id)
+            .await
+            .unwrap();
+        assert_eq!(instances.len(), 2);
+        assert_ne!(instances[0].node_id, instances[1].node_id);
+
+        // Reconciling again is a no-op
+        h.controller.reconcile_all().await.unwrap();
+        assert_eq!(h.runtime.created.lock().unwrap().len(), 2);
+
+        let stored = h.store.get_workload(&workload_id).await.unwrap().unwrap();
+        assert!(crate::controllers::is_controller_managed(&stored));
+    }
+
+    #[tokio::test]
+    async fn test_node_selector_limits_placement() {
+        let gpu = node(&[("gpu", "true")], NodeStatus::Ready);
+        let gpu_id = gpu.id;
+        let h = harness(vec![gpu, node(&[("gpu", "false")], NodeStatus::Ready)]);
+        let ds = DaemonSet::new("gpu-exporter", template()).with_node_selector("gpu", "true");
+        let workload_id = ds.workload_id();
+        h.controller.add_daemon_set(ds).await.unwrap();
+
+        let instances = h
+            .store
+            .list_instances_for_workload(&workload_id)
+            .await
+            .unwrap();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].node_id, gpu_id);
+    }
+
+    #[tokio::test]
+    async fn test_reacts_to_node_join_and_leave() {
+        let first = node(&[], NodeStatus::Ready);
+        let first_id = first.id;
+        let h = harness(vec![first]);
+        let ds = DaemonSet::new("metrics-agent", template());
+        let workload_id = ds.workload_id();
+        h.controller.add_daemon_set(ds).await.unwrap();
+
+        // Node joins
+        let joined = node(&[], NodeStatus::Ready);
+        let joined_id = joined.id;
+        h.cluster.nodes.write().await.push(joined);
+        h.controller.reconcile_all().await.unwrap();
+        assert_eq!(
+            h.store
+                .list_instances_for_workload(&workload_id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // First node leaves
+        h.cluster.nodes.write().await.retain(|n| n.id != first_id);
+        h.controller.reconcile_all().await.unwrap();
+        let instances = h
+            .store
+            .list_instances_for_workload(&workload_id)
+            .await
+            .unwrap();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].node_id, joined_id);
+        assert_eq!(h.runtime.removed.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_remove_daemon_set_tears_down_instances() {
+        let h = harness(vec![
+            node(&[], NodeStatus::Ready),
+            node(&[], NodeStatus::Ready),
+        ]);
+        let ds = DaemonSet::new("log-shipper", template());
+        let workload_id = ds.workload_id();
+        let id = h.controller.add_daemon_set(ds).await.unwrap();
+
+        assert!(h.controller.remove_daemon_set(&id).await.unwrap().is_some());
+        assert!(h
+            .store
+            .list_instances_for_workload(&workload_id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(h.store.get_workload(&workload_id).await.unwrap().is_none());
+        assert_eq!(h.runtime.removed.load(Ordering::SeqCst), 2);
+        assert!(h.controller.list_daemon_sets().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_new_leader_reloads_daemon_sets() {
+        let h = harness(vec![node(&[], NodeStatus::Ready)]);
+        let ds = DaemonSet::new("log-shipper", template());
+        let workload_id = ds.workload_id();
+        let id = h.controller.add_daemon_set(ds).await.unwrap();
+
+        // Another node's controller takes over and sees a node join
+        let successor =
+            DaemonSetController::new(h.store.clone(), h.runtime.clone(), h.cluster.clone());
+        successor.load().await.unwrap();
+        assert_eq!(
+            successor.get_daemon_set(&id).await.unwrap().unwrap().name,
+            "log-shipper"
+        );
+        h.cluster
+            .nodes
+            .write()
+            .await
+            .push(node(&[], NodeStatus::Ready));
+        successor.reconcile_all().await.unwrap();
+        assert_eq!(
+            h.store
+                .list_instances_for_workload(&workload_id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
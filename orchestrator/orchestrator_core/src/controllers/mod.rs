@@ -0,0 +1,51 @@
+//! Workload controllers.
+//!
+//! Controllers own placement for workloads that do not fit the orchestrator's
+//! replica-count model. Workloads they manage carry an owner label and are
+//! skipped by [`Orchestrator`](crate::Orchestrator) reconciliation.
+
+pub mod daemonset;
+pub mod statefulset;
+
+pub use daemonset::{DaemonSet, DaemonSetController, DaemonSetId};
+pub use statefulset::{
+    StatefulSet, StatefulSetController, StatefulSetId, StatefulSetRevision, VolumeClaimTemplate,
+};
+
+use std::collections::HashMap;
+
+use cluster_manager_interface::ClusterManager;
+use orchestrator_shared_types::{Node, Result, WorkloadDefinition};
+use state_store_interface::StateStore;
+
+/// Label set on workloads owned by a daemon set, holding the daemon set name.
+pub const DAEMON_SET_NAME_LABEL: &str = "daemonset-name";
+
+/// Label set on workloads owned by a stateful set, holding the stateful set name.
+pub const STATEFUL_SET_NAME_LABEL: &str = "statefulset-name";
+
+/// Whether a workload's placement is owned by a controller rather than the scheduler.
+pub fn is_controller_managed(workload: &WorkloadDefinition) -> bool {
+    workload.labels.contains_key(DAEMON_SET_NAME_LABEL)
+        || workload.labels.contains_key(STATEFUL_SET_NAME_LABEL)
+}
+
+/// Whether a node carries every label in `selector`. An empty selector matches all nodes.
+pub fn node_matches_selector(node: &Node, selector: &HashMap<String, String>) -> bool {
+    selector
+        .iter()
+        .all(|(key, value)| node.labels.get(key) == Some(value))
+}
+
+/// Cluster members, with nodes cordoned in the state store marked unschedulable.
+pub(crate) async fn list_nodes(
+    cluster_manager: &dyn ClusterManager,
+    state_store: &dyn StateStore,
+) -> Result<Vec<Node>> {
+    let cordoned = crate::disruption::cordoned_nodes(state_store).await?;
+    let mut nodes = cluster_manager.list_nodes().await?;
+    for node in &mut nodes {
+        node.unschedulable = cordoned.contains(&node.id);
+    }
+    Ok(nodes)
+}
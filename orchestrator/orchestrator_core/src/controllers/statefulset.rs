@@ -0,0 +1,1024 @@
+//! StatefulSet controller.
+//!
+//! A [`StatefulSet`] runs `replicas` instances that each keep a stable
+//! identity across restarts and rescheduling:
+//!
+//! - **Name and id**: ordinal `n` is always `<name>-<n>` and its instance id is
+//!   derived from the set id and ordinal, so it is the same every time the
+//!   instance is recreated.
+//! - **Volumes**: each volume claim gets a per-ordinal host directory that is
+//!   never deleted by the controller. A recreated instance is placed back on
+//!   the node it last ran on when that node is still Ready.
+//! - **Ordering**: instances are created in ascending ordinal order, each only
+//!   once every lower ordinal is Running. Scale-down and template updates
+//!   proceed from the highest ordinal down, one instance at a time, and only
+//!   while every other instance is Running.
+//!
+//! The controller takes at most one create or delete action per set on each
+//! pass. Sets and the node and revision each instance was created with are
+//! kept in the state store as [`Resource`]s and reloaded whenever the
+//! controller starts, e.g. on a new leader, so ordering and volume placement
+//! hold across controller restarts. Replaced templates are kept in the set's
+//! revision history until pruned with [`StatefulSetController::prune_revisions`].
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use cluster_manager_interface::ClusterManager;
+use container_runtime_interface::{
+    ContainerRuntime, CreateContainerOptions, InstanceSpec, PullPriority,
+};
+use orchestrator_shared_types::{
+    Node, NodeId, OrchestrationError, Result, VolumeMount, WorkloadDefinition, WorkloadInstance,
+    WorkloadInstanceStatus,
+};
+use scheduler_interface::{ScheduleDecision, ScheduleRequest, Scheduler};
+use state_store_interface::StateStore;
+
+use super::{list_nodes, STATEFUL_SET_NAME_LABEL};
+use crate::disruption::DisruptionController;
+use crate::resources::{self, Resource};
+
+pub type StatefulSetId = Uuid;
+
+/// Environment variable holding the instance's stable name, e.g. `db-0`.
+pub const INSTANCE_NAME_ENV: &str = "INSTANCE_NAME";
+
+/// Environment variable holding the instance's ordinal.
+pub const INSTANCE_ORDINAL_ENV: &str = "INSTANCE_ORDINAL";
+
+/// Default root under which per-instance volume directories are created.
+pub const DEFAULT_VOLUME_ROOT: &str = "/var/lib/orchestrator/volumes";
+
+/// A volume created for every instance of a stateful set.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VolumeClaimTemplate {
+    pub name: String,
+    /// Where the volume is mounted in every container of the instance.
+    pub mount_path: String,
+}
+
+/// A template a stateful set rolled out before its current one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatefulSetRevision {
+    pub revision: u64,
+    pub template: WorkloadDefinition,
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// A workload whose instances have stable names, ids, and volumes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatefulSet {
+    pub id: StatefulSetId,
+    pub name: String,
+    /// Template for each instance. `replicas` is ignored in favour of the set's.
+    pub template: WorkloadDefinition,
+    pub replicas: u32,
+    #[serde(default)]
+    pub volume_claims: Vec<VolumeClaimTemplate>,
+    /// Host directory per-instance volumes are created under.
+    pub volume_root: PathBuf,
+    /// Incremented whenever the template changes; drives rolling updates.
+    pub revision: u64,
+    /// Previous templates, oldest first.
+    #[serde(default)]
+    pub history: Vec<StatefulSetRevision>,
+}
+
+impl StatefulSet {
+    pub fn new(name: impl Into<String>, template: WorkloadDefinition, replicas: u32) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            template,
+            replicas,
+            volume_claims: Vec::new(),
+            volume_root: PathBuf::from(DEFAULT_VOLUME_ROOT),
+            revision: 1,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn with_volume_claim(
+        mut self,
+        name: impl Into<String>,
+        mount_path: impl Into<String>,
+    ) -> Self {
+        self.volume_claims.push(VolumeClaimTemplate {
+            name: name.into(),
+            mount_path: mount_path.into(),
+        });
+        self
+    }
+
+    pub fn with_volume_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.volume_root = root.into();
+        self
+    }
+
+    /// The id of the workload backing this stateful set.
+    pub fn workload_id(&self) -> Uuid {
+        self.template.id
+    }
+
+    /// Stable name of the instance with the given ordinal.
+    pub fn instance_name(&self, ordinal: u32) -> String {
+        format!("{}-{}", self.name, ordinal)
+    }
+
+    /// Stable instance id for the given ordinal.
+    pub fn instance_id(&self, ordinal: u32) -> Uuid {
+        Uuid::new_v5(&self.id, self.instance_name(ordinal).as_bytes())
+    }
+
+    /// Host directory backing `claim` for the given ordinal.
+    pub fn volume_path(&self, claim: &VolumeClaimTemplate, ordinal: u32) -> PathBuf {
+        self.volume_root.join(&self.name).join(format!(
+            "{}-{}",
+            claim.name,
+            self.instance_name(ordinal)
+        ))
+    }
+
+    /// The ordinal of an instance of this set, if it is one.
+    ///
+    /// Ordinals are probed from 0 up to (excluding) `limit`.
+    fn ordinal_of(&self, instance_id: &Uuid, limit: u32) -> Option<u32> {
+        (0..limit).find(|&ordinal| self.instance_id(ordinal) == *instance_id)
+    }
+}
+
+impl Resource for StatefulSet {
+    const KIND: &'static str = "statefulsets";
+
+    fn key(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+/// Node and template revision an instance of a set was last created with.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct InstanceRecord {
+    instance_id: Uuid,
+    stateful_set_id: StatefulSetId,
+    node_id: NodeId,
+    revision: u64,
+}
+
+impl Resource for InstanceRecord {
+    const KIND: &'static str = "statefulset-instances";
+
+    fn key(&self) -> String {
+        self.instance_id.to_string()
+    }
+}
+
+/// Controller driving stateful sets towards their desired replica count and template.
+pub struct StatefulSetController {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    cluster_manager: Arc<dyn ClusterManager>,
+    scheduler: Arc<dyn Scheduler>,
+    stateful_sets: RwLock<HashMap<StatefulSetId, StatefulSet>>,
+    /// Node and template revision each instance was last created with.
+    records: RwLock<HashMap<Uuid, InstanceRecord>>,
+    /// Budgets consulted before an instance is replaced by a rolling update.
+    disruption: Option<Arc<DisruptionController>>,
+}
+
+impl StatefulSetController {
+    /// How often the controller re-evaluates every stateful set.
+    pub const DEFAULT_RESYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(
+        state_store: Arc<dyn StateStore>,
+        runtime: Arc<dyn ContainerRuntime>,
+        cluster_manager: Arc<dyn ClusterManager>,
+        scheduler: Arc<dyn Scheduler>,
+    ) -> Self {
+        Self {
+            state_store,
+            runtime,
+            cluster_manager,
+            scheduler,
+            stateful_sets: RwLock::new(HashMap::new()),
+            records: RwLock::new(HashMap::new()),
+            disruption: None,
+        }
+    }
+
+    /// Hold rolling updates back while a disruption budget has no room.
+    pub fn with_disruption_budgets(mut self, disruption: Arc<DisruptionController>) -> Self {
+        self.disruption = Some(disruption);
+        self
+    }
+
+    /// Register a stateful set, persist its workload, and start creating ordinal 0.
+    pub async fn add_stateful_set(&self, mut stateful_set: StatefulSet) -> Result<StatefulSetId> {
+        stateful_set.template.labels.insert(
+            STATEFUL_SET_NAME_LABEL.to_string(),
+            stateful_set.name.clone(),
+        );
+        stateful_set.template.name = stateful_set.name.clone();
+        stateful_set.template.replicas = stateful_set.replicas;
+        self.state_store
+            .put_workload(stateful_set.template.clone())
+            .await?;
+
+        resources::put(self.state_store.as_ref(), &stateful_set).await?;
+
+        let id = stateful_set.id;
+        info!("Registered stateful set {} ({})", stateful_set.name, id);
+        self.stateful_sets
+            .write()
+            .await
+            .insert(id, stateful_set.clone());
+        self.reconcile_stateful_set(&stateful_set).await?;
+        Ok(id)
+    }
+
+    /// Remove a stateful set and its instances, highest ordinal first.
+    ///
+    /// Volume directories are left in place.
+    pub async fn remove_stateful_set(&self, id: &StatefulSetId) -> Result<Option<StatefulSet>> {
+        let Some(stateful_set) = self.stateful_sets.write().await.remove(id) else {
+            return Ok(None);
+        };
+        let mut instances = self.ordered_instances(&stateful_set).await?;
+        instances.reverse();
+        for (_, instance) in &instances {
+            self.remove_instance(instance).await;
+        }
+        self.state_store
+            .delete_workload(&stateful_set.workload_id())
+            .await?;
+
+        let records: Vec<Uuid> = {
+            let mut records = self.records.write().await;
+            let ids: Vec<Uuid> = records
+                .values()
+                .filter(|r| r.stateful_set_id == *id)
+                .map(|r| r.instance_id)
+                .collect();
+            for instance_id in &ids {
+                records.remove(instance_id);
+            }
+            ids
+        };
+        for instance_id in records {
+            resources::delete::<InstanceRecord>(
+                self.state_store.as_ref(),
+                &instance_id.to_string(),
+            )
+            .await?;
+        }
+        resources::delete::<StatefulSet>(self.state_store.as_ref(), &id.to_string()).await?;
+        info!("Removed stateful set {} ({})", stateful_set.name, id);
+        Ok(Some(stateful_set))
+    }
+
+    /// Get a stateful set as stored, which any node can answer.
+    pub async fn get_stateful_set(&self, id: &StatefulSetId) -> Result<Option<StatefulSet>> {
+        resources::get(self.state_store.as_ref(), &id.to_string()).await
+    }
+
+    /// List the stored stateful sets, which any node can answer.
+    pub async fn list_stateful_sets(&self) -> Result<Vec<StatefulSet>> {
+        resources::list(self.state_store.as_ref()).await
+    }
+
+    /// Replace the sets and instance records held in memory with those in the
+    /// state store, as the controller does each time it starts.
+    pub async fn load(&self) -> Result<()> {
+        let stateful_sets: Vec<StatefulSet> = resources::list(self.state_store.as_ref()).await?;
+        let records: Vec<InstanceRecord> = resources::list(self.state_store.as_ref()).await?;
+        info!(
+            "Loaded {} stateful set(s) and {} instance record(s)",
+            stateful_sets.len(),
+            records.len()
+        );
+        *self.stateful_sets.write().await = stateful_sets.into_iter().map(|s| (s.id, s)).collect();
+        *self.records.write().await = records.into_iter().map(|r| (r.instance_id, r)).collect();
+        Ok(())
+    }
+
+    /// Change the desired replica count.
+    pub async fn scale(&self, id: &StatefulSetId, replicas: u32) -> Result<()> {
+        let stateful_set = {
+            let mut sets = self.stateful_sets.write().await;
+            let stateful_set = sets.get_mut(id).ok_or_else(|| not_found(id))?;
+            stateful_set.replicas = replicas;
+            stateful_set.template.replicas = replicas;
+            stateful_set.clone()
+        };
+        resources::put(self.state_store.as_ref(), &stateful_set).await?;
+        self.state_store
+            .put_workload(stateful_set.template.clone())
+            .await?;
+        self.reconcile_stateful_set(&stateful_set).await
+    }
+
+    /// Replace the instance template, starting a rolling update from the highest ordinal.
+    pub async fn update_template(
+        &self,
+        id: &StatefulSetId,
+        mut template: WorkloadDefinition,
+    ) -> Result<u64> {
+        let stateful_set = {
+            let mut sets = self.stateful_sets.write().await;
+            let stateful_set = sets.get_mut(id).ok_or_else(|| not_found(id))?;
+            template.id = stateful_set.workload_id();
+            template.name = stateful_set.name.clone();
+            template.replicas = stateful_set.replicas;
+            template.labels.insert(
+                STATEFUL_SET_NAME_LABEL.to_string(),
+                stateful_set.name.clone(),
+            );
+            let previous = std::mem::replace(&mut stateful_set.template, template);
+            stateful_set.history.push(StatefulSetRevision {
+                revision: stateful_set.revision,
+                template: previous,
+                replaced_at: Utc::now(),
+            });
+            stateful_set.revision += 1;
+            stateful_set.clone()
+        };
+        resources::put(self.state_store.as_ref(), &stateful_set).await?;
+        self.state_store
+            .put_workload(stateful_set.template.clone())
+            .await?;
+        info!(
+            "Stateful set {}: rolling out revision {}",
+            stateful_set.name, stateful_set.revision
+        );
+        self.reconcile_stateful_set(&stateful_set).await?;
+        Ok(stateful_set.revision)
+    }
+
+    /// Trim every set's history to its `keep` newest revisions, returning how
+    /// many were dropped. Revisions still run by an instance mid-rollout are kept.
+    pub async fn prune_revisions(&self, keep: usize) -> Result<usize> {
+        let ids: Vec<StatefulSetId> = self.stateful_sets.read().await.keys().copied().collect();
+        let mut pruned = 0;
+        for id in ids {
+            let Some(stateful_set) = self.stateful_sets.read().await.get(&id).cloned() else {
+                continue;
+            };
+            let excess = stateful_set.history.len().saturating_sub(keep);
+            if excess == 0 {
+                continue;
+            }
+
+            let instances = self
+                .state_store
+                .list_instances_for_workload(&stateful_set.workload_id())
+                .await?;
+            let in_use: HashSet<u64> = {
+                let records = self.records.read().await;
+                instances
+                    .iter()
+                    .filter_map(|i| records.get(&i.id).map(|r| r.revision))
+                    .collect()
+            };
+
+            let pruned_set = {
+                let mut sets = self.stateful_sets.write().await;
+                let Some(stateful_set) = sets.get_mut(&id) else {
+                    continue;
+                };
+                let mut dropped = 0;
+                stateful_set.history.retain(|r| {
+                    if dropped < excess && !in_use.contains(&r.revision) {
+                        dropped += 1;
+                        false
+                    } else {
+                        true
+                    }
+                });
+                if dropped == 0 {
+                    continue;
+                }
+                debug!(
+                    "Stateful set {}: pruned {} old revision(s)",
+                    stateful_set.name, dropped
+                );
+                pruned += dropped;
+                stateful_set.clone()
+            };
+            resources::put(self.state_store.as_ref(), &pruned_set).await?;
+        }
+        Ok(pruned)
+    }
+
+    /// Load the stored stateful sets, then reconcile every `resync_interval`
+    /// until the task is aborted.
+    pub fn spawn(self: Arc<Self>, resync_interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(resync_interval);
+            loop {
+                interval.tick().await;
+                // Until loaded, a set would look like it had no history
+                if let Err(e) = self.load().await {
+                    error!("Failed to load stateful sets: {:?}", e);
+                    continue;
+                }
+                break;
+            }
+            loop {
+                if let Err(e) = self.reconcile_all().await {
+                    error!("Stateful set reconciliation failed: {:?}", e);
+                }
+                interval.tick().await;
+            }
+        })
+    }
+
+    /// Take the next step for every registered stateful set.
+    pub async fn reconcile_all(&self) -> Result<()> {
+        let stateful_sets: Vec<StatefulSet> =
+            self.stateful_sets.read().await.values().cloned().collect();
+        for stateful_set in stateful_sets {
+            if let Err(e) = self.reconcile_stateful_set(&stateful_set).await {
+                error!(
+                    "Failed to reconcile stateful set {}: {:?}",
+                    stateful_set.name, e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn reconcile_stateful_set(&self, stateful_set: &StatefulSet) -> Result<()> {
+        let instances = self.ordered_instances(stateful_set).await?;
+        let by_ordinal: HashMap<u32, &WorkloadInstance> =
+            instances.iter().map(|(o, i)| (*o, i)).collect();
+
+        // Failed or finished instances are replaced in place, keeping their identity
+        for (ordinal, instance) in &instances {
+            if !matches!(
+                instance.status,
+                WorkloadInstanceStatus::Running
+                    | WorkloadInstanceStatus::Pending
+                    | WorkloadInstanceStatus::CrashLoopBackOff
+            ) {
+                info!(
+                    "Stateful set {}: replacing {} ({:?})",
+                    stateful_set.name,
+                    stateful_set.instance_name(*ordinal),
+                    instance.status
+                );
+                self.remove_instance(instance).await;
+                return Ok(());
+            }
+        }
+
+        // Wait for in-flight instances before taking any further step
+        if let Some((ordinal, _)) = instances
+            .iter()
+            .find(|(_, i)| i.status != WorkloadInstanceStatus::Running)
+        {
+            debug!(
+                "Stateful set {}: waiting for {} to become Running",
+                stateful_set.name,
+                stateful_set.instance_name(*ordinal)
+            );
+            return Ok(());
+        }
+
+        // Scale up: lowest missing ordinal, only once every lower ordinal is Running
+        if let Some(ordinal) = (0..stateful_set.replicas).find(|o| !by_ordinal.contains_key(o)) {
+            return self
+                .create_instance(stateful_set, ordinal, &instances)
+                .await;
+        }
+
+        // Scale down: highest ordinal first
+        if let Some((ordinal, instance)) = instances
+            .iter()
+            .rev()
+            .find(|(o, _)| *o >= stateful_set.replicas)
+        {
+            info!(
+                "Stateful set {}: scaling down {}",
+                stateful_set.name,
+                stateful_set.instance_name(*ordinal)
+            );
+            self.remove_instance(instance).await;
+            return Ok(());
+        }
+
+        // Rolling update: highest outdated ordinal first
+        let records = self.records.read().await.clone();
+        if let Some((ordinal, instance)) = instances.iter().rev().find(|(_, i)| {
+            records
+                .get(&i.id)
+                .is_some_and(|r| r.revision < stateful_set.revision)
+        }) {
+            let name = stateful_set.instance_name(*ordinal);
+            if let Some(disruption) = &self.disruption {
+                if !disruption.evict(instance).await? {
+                    debug!(
+                        "Stateful set {}: update of {} held by a disruption budget",
+                        stateful_set.name, name
+                    );
+                    return Ok(());
+                }
+            } else {
+                self.remove_instance(instance).await;
+            }
+            info!(
+                "Stateful set {}: updating {} to revision {}",
+                stateful_set.name, name, stateful_set.revision
+            );
+        }
+        Ok(())
+    }
+
+    /// This set's instances with their ordinals, in ascending ordinal order.
+    async fn ordered_instances(
+        &self,
+        stateful_set: &StatefulSet,
+    ) -> Result<Vec<(u32, WorkloadInstance)>> {
+        let instances = self
+            .state_store
+            .list_instances_for_workload(&stateful_set.workload_id())
+            .await?;
+        let limit = stateful_set.replicas.max(instances.len() as u32) + instances.len() as u32;
+        let mut ordered = Vec::with_capacity(instances.len());
+        for instance in instances {
+            match stateful_set.ordinal_of(&instance.id, limit) {
+                Some(ordinal) => ordered.push((ordinal, instance)),
+                None => {
+                    warn!(
+                        "Stateful set {}: removing instance {} with no ordinal identity",
+                        stateful_set.name, instance.id
+                    );
+                    self.remove_instance(&instance).await;
+                }
+            }
+        }
+        ordered.sort_by_key(|(ordinal, _)| *ordinal);
+        Ok(ordered)
+    }
+
+    async fn create_instance(
+        &self,
+        stateful_set: &StatefulSet,
+        ordinal: u32,
+        current: &[(u32, WorkloadInstance)],
+    ) -> Result<()> {
+        let instance_id = stateful_set.instance_id(ordinal);
+        let node_id = self.place(stateful_set, instance_id, current).await?;
+        let name = stateful_set.instance_name(ordinal);
+
+        let options = CreateContainerOptions {
+            workload_id: stateful_set.workload_id(),
+            node_id,
+            pull_priority: PullPriority::Interactive,
+        };
+        if stateful_set.template.containers.is_empty() {
+            return Err(OrchestrationError::ConfigError(format!(
+                "Stateful set {} has no containers",
+                stateful_set.name
+            )));
+        }
+        // Init containers and sidecars see the same identity and volumes
+        let spec =
+            InstanceSpec::from_workload(&stateful_set.template).map_containers(|container| {
+                container.name = format!("{}-{}", name, container.name);
+                container
+                    .env_vars
+                    .insert(INSTANCE_NAME_ENV.to_string(), name.clone());
+                container
+                    .env_vars
+                    .insert(INSTANCE_ORDINAL_ENV.to_string(), ordinal.to_string());
+                for claim in &stateful_set.volume_claims {
+                    container.volume_mounts.push(VolumeMount {
+                        name: claim.name.clone(),
+                        host_path: stateful_set
+                            .volume_path(claim, ordinal)
+                            .display()
+                            .to_string(),
+                        mount_path: claim.mount_path.clone(),
+                        read_only: false,
+                    });
+                }
+            });
+        let container_ids = self.runtime.start_instance(&spec, &options).await?;
+
+        let record = InstanceRecord {
+            instance_id,
+            stateful_set_id: stateful_set.id,
+            node_id,
+            revision: stateful_set.revision,
+        };
+        self.records.write().await.insert(instance_id, record);
+        resources::put(self.state_store.as_ref(), &record).await?;
+        info!(
+            "Stateful set {}: created {} on node {}",
+            stateful_set.name, name, node_id
+        );
+        self.state_store
+            .put_instance(WorkloadInstance {
+                id: instance_id,
+                workload_id: stateful_set.workload_id(),
+                node_id,
+                container_ids,
+                status: WorkloadInstanceStatus::Pending,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: stateful_set.template.namespace.clone(),
+                resource_version: 0,
+            })
+            .await
+    }
+
+    /// Pick a node for an instance, preferring the node that holds its volumes.
+    async fn place(
+        &self,
+        stateful_set: &StatefulSet,
+        instance_id: Uuid,
+        current: &[(u32, WorkloadInstance)],
+    ) -> Result<NodeId> {
+        let nodes: Vec<Node> = list_nodes(self.cluster_manager.as_ref(), self.state_store.as_ref())
+            .await?
+            .into_iter()
+            .filter(Node::is_schedulable)
+            .collect();
+
+        let previous = self
+            .records
+            .read()
+            .await
+            .get(&instance_id)
+            .map(|r| r.node_id);
+        if let Some(node_id) = previous {
+            if nodes.iter().any(|n| n.id == node_id) {
+                return Ok(node_id);
+            }
+            warn!(
+                "Stateful set {}: node {} holding volumes for instance {} is unavailable",
+                stateful_set.name, node_id, instance_id
+            );
+        }
+
+        let mut workload = stateful_set.template.clone();
+        workload.replicas = current.len() as u32 + 1;
+        let request = ScheduleRequest {
+            workload_definition: Arc::new(workload),
+            current_instances: current.iter().map(|(_, i)| i.clone()).collect(),
+        };
+        let decisions = self.scheduler.schedule(&request, &nodes).await?;
+        match decisions.into_iter().next() {
+            Some(ScheduleDecision::AssignNode(node_id)) => Ok(node_id),
+            Some(ScheduleDecision::NoPlacement(reason)) | Some(ScheduleDecision::Error(reason)) => {
+                Err(OrchestrationError::SchedulingError(reason))
+            }
+            None => Err(OrchestrationError::SchedulingError(format!(
+                "No placement for stateful set {}",
+                stateful_set.name
+            ))),
+        }
+    }
+
+    async fn remove_instance(&self, instance: &WorkloadInstance) {
+        if let Err(e) = self.runtime.stop_instance(&instance.container_ids).await {
+            warn!(
+                "Failed to stop containers of instance {}: {:?}",
+                instance.id, e
+            );
+        }
+        if let Err(e) = self
+            .state_store
+            .delete_instance(&instance.id.to_string())
+            .await
+        {
+            error!(
+                "Failed to delete instance {} from state: {:?}",
+                instance.id, e
+            );
+        }
+    }
+}
+
+fn not_found(id: &StatefulSetId) -> OrchestrationError {
+    OrchestrationError::WorkloadNotFound(*id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disruption::DisruptionBudget;
+    use crate::test_support::{self, StubRuntime};
+    use async_trait::async_trait;
+    use cluster_manager_interface::ClusterEvent;
+    use orchestrator_shared_types::{ContainerConfig, Keypair, NodeResources, NodeStatus};
+    use scheduler_interface::SimpleScheduler;
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use tokio::sync::watch;
+
+    struct StaticClusterManager {
+        nodes: Vec<Node>,
+        events_tx: watch::Sender<Option<ClusterEvent>>,
+    }
+
+    #[async_trait]
+    impl ClusterManager for StaticClusterManager {
+        async fn initialize(&self) -> Result<()> {
+            Ok(())
+        }
+        async fn get_node(&self, node_id: &NodeId) -> Result<Option<Node>> {
+            Ok(self.nodes.iter().find(|n| &n.id == node_id).cloned())
+        }
+        async fn list_nodes(&self) -> Result<Vec<Node>> {
+            Ok(self.nodes.clone())
+        }
+        async fn subscribe_to_events(&self) -> Result<watch::Receiver<Option<ClusterEvent>>> {
+            Ok(self.events_tx.subscribe())
+        }
+    }
+
+    fn template(image: &str) -> WorkloadDefinition {
+        WorkloadDefinition {
+            containers: vec![ContainerConfig {
+                image: image.to_string(),
+                ..test_support::container("postgres")
+            }],
+            ..test_support::workload("db")
+        }
+    }
+
+    struct Harness {
+        controller: StatefulSetController,
+        store: Arc<InMemoryStateStore>,
+        runtime: Arc<StubRuntime>,
+    }
+
+    fn harness() -> Harness {
+        let store = Arc::new(InMemoryStateStore::new());
+        let runtime = Arc::new(StubRuntime::default());
+        let (events_tx, _) = watch::channel(None);
+        let cluster = Arc::new(StaticClusterManager {
+            nodes: vec![Node {
+                id: Keypair::generate().public_key(),
+                address: "10.0.0.1:8080".to_string(),
+                status: NodeStatus::Ready,
+                labels: HashMap::new(),
+                resources_capacity: NodeResources::default(),
+                resources_allocatable: NodeResources::default(),
+                unschedulable: false,
+                resource_version: 0,
+            }],
+            events_tx,
+        });
+        let controller = StatefulSetController::new(
+            store.clone(),
+            runtime.clone(),
+            cluster,
+            Arc::new(SimpleScheduler),
+        );
+        Harness {
+            controller,
+            store,
+            runtime,
+        }
+    }
+
+    impl Harness {
+        /// Names of the set's instances, in ordinal order.
+        async fn names(&self, set: &StatefulSet) -> Vec<String> {
+            let instances = self.controller.ordered_instances(set).await.unwrap();
+            instances
+                .iter()
+                .map(|(o, _)| set.instance_name(*o))
+                .collect()
+        }
+
+        /// Mark every instance of the set Running, as the status monitor would.
+        async fn mark_running(&self, set: &StatefulSet) {
+            for mut instance in self
+                .store
+                .list_instances_for_workload(&set.workload_id())
+                .await
+                .unwrap()
+            {
+                instance.status = WorkloadInstanceStatus::Running;
+                self.store.put_instance(instance).await.unwrap();
+            }
+        }
+
+        async fn step(&self, set: &StatefulSet) {
+            self.mark_running(set).await;
+            self.controller.reconcile_all().await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ordered_scale_up_and_down() {
+        let h = harness();
+        let set = StatefulSet::new("db", template("postgres:16"), 3);
+        let id = h.controller.add_stateful_set(set.clone()).await.unwrap();
+
+        // Only ordinal 0 exists until it is Running
+        assert_eq!(h.names(&set).await, vec!["db-0"]);
+        h.controller.reconcile_all().await.unwrap();
+        assert_eq!(h.names(&set).await, vec!["db-0"]);
+
+        h.step(&set).await;
+        assert_eq!(h.names(&set).await, vec!["db-0", "db-1"]);
+        h.step(&set).await;
+        assert_eq!(h.names(&set).await, vec!["db-0", "db-1", "db-2"]);
+
+        h.mark_running(&set).await;
+        h.controller.scale(&id, 1).await.unwrap();
+        assert_eq!(h.names(&set).await, vec!["db-0", "db-1"]);
+        h.step(&set).await;
+        assert_eq!(h.names(&set).await, vec!["db-0"]);
+    }
+
+    #[tokio::test]
+    async fn test_stable_identity_and_volumes_across_replacement() {
+        let h = harness();
+        let set = StatefulSet::new("db", template("postgres:16"), 1)
+            .with_volume_claim("data", "/var/lib/postgresql/data")
+            .with_volume_root("/tmp/volumes");
+        h.controller.add_stateful_set(set.clone()).await.unwrap();
+
+        let first = h
+            .store
+            .list_instances_for_workload(&set.workload_id())
+            .await
+            .unwrap();
+        assert_eq!(first[0].id, set.instance_id(0));
+
+        // The instance fails and is replaced with the same id, node and volume
+        let mut failed = first[0].clone();
+        failed.status = WorkloadInstanceStatus::Failed;
+        h.store.put_instance(failed).await.unwrap();
+        h.controller.reconcile_all().await.unwrap();
+        h.controller.reconcile_all().await.unwrap();
+
+        let second = h
+            .store
+            .list_instances_for_workload(&set.workload_id())
+            .await
+            .unwrap();
+        assert_eq!(second[0].id, first[0].id);
+        assert_eq!(second[0].node_id, first[0].node_id);
+
+        let created = h.runtime.created.lock().unwrap();
+        assert_eq!(created.len(), 2);
+        for config in created.iter() {
+            assert_eq!(config.name, "db-0-postgres");
+            assert_eq!(config.env_vars[INSTANCE_NAME_ENV], "db-0");
+            assert_eq!(
+                config.volume_mounts[0].host_path,
+                "/tmp/volumes/db/data-db-0"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rolling_update_goes_highest_ordinal_first() {
+        let h = harness();
+        let set = StatefulSet::new("db", template("postgres:15"), 2);
+        let id = h.controller.add_stateful_set(set.clone()).await.unwrap();
+        h.step(&set).await;
+        h.mark_running(&set).await;
+
+        h.controller
+            .update_template(&id, template("postgres:16"))
+            .await
+            .unwrap();
+        // db-1 is taken down first, then recreated on the new image
+        assert_eq!(h.names(&set).await, vec!["db-0"]);
+        h.step(&set).await;
+        assert_eq!(h.names(&set).await, vec!["db-0", "db-1"]);
+        h.step(&set).await;
+        assert_eq!(h.names(&set).await, vec!["db-1"]);
+        h.step(&set).await;
+
+        let images: Vec<(String, String)> = h
+            .runtime
+            .created
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|c| (c.name.clone(), c.image.clone()))
+            .collect();
+        assert_eq!(
+            images[2..],
+            [
+                ("db-1-postgres".to_string(), "postgres:16".to_string()),
+                ("db-0-postgres".to_string(), "postgres:16".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rolling_update_waits_for_disruption_budget() {
+        let h = harness();
+        let disruption = Arc::new(DisruptionController::new(
+            h.store.clone(),
+            h.runtime.clone(),
+        ));
+        let h = Harness {
+            controller: h.controller.with_disruption_budgets(disruption.clone()),
+            ..h
+        };
+        let set = StatefulSet::new("db", template("postgres:15"), 2);
+        let id = h.controller.add_stateful_set(set.clone()).await.unwrap();
+        h.step(&set).await;
+        h.mark_running(&set).await;
+
+        let selector = HashMap::from([(STATEFUL_SET_NAME_LABEL.to_string(), "db".to_string())]);
+        let budget = disruption
+            .add_budget(DisruptionBudget::new("db", selector).with_min_available(2))
+            .await
+            .unwrap();
+        h.controller
+            .update_template(&id, template("postgres:16"))
+            .await
+            .unwrap();
+        h.step(&set).await;
+        assert_eq!(h.names(&set).await, vec!["db-0", "db-1"]);
+
+        disruption.remove_budget(&budget).await;
+        h.step(&set).await;
+        assert_eq!(h.names(&set).await, vec!["db-0"]);
+    }
+
+    #[tokio::test]
+    async fn test_prune_revisions_keeps_newest_and_in_use() {
+        let h = harness();
+        let set = StatefulSet::new("db", template("postgres:14"), 1);
+        let id = h.controller.add_stateful_set(set.clone()).await.unwrap();
+        let history = |set: Option<StatefulSet>| -> Vec<u64> {
+            set.unwrap().history.iter().map(|r| r.revision).collect()
+        };
+
+        // db-0 never becomes Running, so it stays on revision 1 throughout
+        for image in ["postgres:15", "postgres:16", "postgres:17"] {
+            h.controller
+                .update_template(&id, template(image))
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            history(h.controller.get_stateful_set(&id).await.unwrap()),
+            vec![1, 2, 3]
+        );
+
+        assert_eq!(h.controller.prune_revisions(1).await.unwrap(), 2);
+        assert_eq!(
+            history(h.controller.get_stateful_set(&id).await.unwrap()),
+            vec![1]
+        );
+
+        // Once db-0 is rolled to the current template nothing pins revision 1
+        h.step(&set).await;
+        h.step(&set).await;
+        assert_eq!(h.controller.prune_revisions(0).await.unwrap(), 1);
+        assert!(history(h.controller.get_stateful_set(&id).await.unwrap()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reload_keeps_sets_and_instance_records() {
+        let h = harness();
+        let set = StatefulSet::new("db", template("postgres:15"), 1);
+        let id = h.controller.add_stateful_set(set.clone()).await.unwrap();
+        h.mark_running(&set).await;
+
+        // A new leader's controller knows db-0 still runs revision 1
+        let h2 = Harness {
+            controller: StatefulSetController::new(
+                h.store.clone(),
+                h.runtime.clone(),
+                h.controller.cluster_manager.clone(),
+                Arc::new(SimpleScheduler),
+            ),
+            ..h
+        };
+        h2.controller.load().await.unwrap();
+        assert_eq!(h2.controller.list_stateful_sets().await.unwrap().len(), 1);
+        h2.controller
+            .update_template(&id, template("postgres:16"))
+            .await
+            .unwrap();
+        assert!(h2.names(&set).await.is_empty());
+        h2.step(&set).await;
+        assert_eq!(h2.names(&set).await, vec!["db-0"]);
+
+        let created = h2.runtime.created.lock().unwrap();
+        assert_eq!(created.last().unwrap().image, "postgres:16");
+    }
+}
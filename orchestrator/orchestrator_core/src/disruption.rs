@@ -0,0 +1,496 @@
+//! Disruption budgets.
+//!
+//! A [`DisruptionBudget`] caps how many instances of the workloads matching
+//! its label selector may be taken down on purpose at once. Voluntary
+//! evictions (node drains, stateful set rolling updates and priority
+//! preemption) go through [`DisruptionController::evict`], which
+//! refuses an eviction that would leave a matching budget with fewer healthy
+//! instances than it allows.
+//!
+//! Only Running instances count as healthy. Evicting an instance that is not
+//! Running never uses up a budget, and failures the orchestrator did not
+//! cause (crashes, lost nodes) are not blocked, only counted.
+//!
+//! Draining a node first cordons it: the node's record in the state store is
+//! marked unschedulable so replacements for the evicted instances land
+//! elsewhere.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use container_runtime_interface::ContainerRuntime;
+use orchestrator_shared_types::{
+    Node, NodeId, OrchestrationError, Result, WorkloadDefinition, WorkloadId, WorkloadInstance,
+    WorkloadInstanceStatus,
+};
+use state_store_interface::StateStore;
+
+pub type DisruptionBudgetId = Uuid;
+
+/// Limits voluntary disruption of the workloads matching `selector`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct DisruptionBudget {
+    #[cfg_attr(feature = "rest-api", schema(value_type = uuid::Uuid))]
+    pub id: DisruptionBudgetId,
+    pub name: String,
+    /// Workload labels the budget applies to. An empty selector matches every workload.
+    #[serde(default)]
+    pub selector: HashMap<String, String>,
+    /// Healthy instances that must remain after an eviction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_available: Option<u32>,
+    /// Instances that may be unhealthy at once, counting the one being evicted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_unavailable: Option<u32>,
+}
+
+impl DisruptionBudget {
+    pub fn new(name: impl Into<String>, selector: HashMap<String, String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            selector,
+            min_available: None,
+            max_unavailable: None,
+        }
+    }
+
+    pub fn with_min_available(mut self, min_available: u32) -> Self {
+        self.min_available = Some(min_available);
+        self
+    }
+
+    pub fn with_max_unavailable(mut self, max_unavailable: u32) -> Self {
+        self.max_unavailable = Some(max_unavailable);
+        self
+    }
+
+    /// Whether the budget covers a workload.
+    pub fn matches(&self, workload: &WorkloadDefinition) -> bool {
+        self.selector
+            .iter()
+            .all(|(key, value)| workload.labels.get(key) == Some(value))
+    }
+
+    /// Evictions allowed right now, given the desired and healthy instance
+    /// counts of the matching workloads. With both limits set, the stricter wins.
+    pub fn disruptions_allowed(&self, expected: u32, healthy: u32) -> u32 {
+        let by_min = self.min_available.map(|min| healthy.saturating_sub(min));
+        let by_max = self
+            .max_unavailable
+            .map(|max| max.saturating_sub(expected.saturating_sub(healthy)));
+        match (by_min, by_max) {
+            (Some(a), Some(b)) => a.min(b),
+            (Some(a), None) | (None, Some(a)) => a,
+            (None, None) => u32::MAX,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.min_available.is_none() && self.max_unavailable.is_none() {
+            return Err(OrchestrationError::ConfigError(format!(
+                "Disruption budget {} sets neither min_available nor max_unavailable",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// How much room a budget currently has.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct DisruptionBudgetStatus {
+    /// Desired replicas across matching workloads.
+    pub expected: u32,
+    /// Running instances across matching workloads.
+    pub healthy: u32,
+    pub disruptions_allowed: u32,
+}
+
+/// Outcome of draining a node.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DrainReport {
+    pub evicted: Vec<Uuid>,
+    /// Instances left running because a budget had no room; drain again later.
+    pub blocked: Vec<Uuid>,
+    /// Workloads that lost an instance and need a replacement scheduled.
+    pub workloads: Vec<WorkloadId>,
+}
+
+impl DrainReport {
+    /// Whether every instance left the node.
+    pub fn is_complete(&self) -> bool {
+        self.blocked.is_empty()
+    }
+}
+
+/// Nodes marked unschedulable in the state store.
+///
+/// Nodes reported by the cluster manager do not carry the flag, so placement
+/// based on them must consult this set.
+pub async fn cordoned_nodes(state_store: &dyn StateStore) -> Result<HashSet<NodeId>> {
+    Ok(state_store
+        .list_nodes()
+        .await?
+        .into_iter()
+        .filter(|n| n.unschedulable)
+        .map(|n| n.id)
+        .collect())
+}
+
+/// Holds disruption budgets and performs evictions that respect them.
+pub struct DisruptionController {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    budgets: RwLock<HashMap<DisruptionBudgetId, DisruptionBudget>>,
+    /// Held from budget check to deletion so concurrent evictions cannot
+    /// overdraw a budget.
+    eviction: Mutex<()>,
+}
+
+impl DisruptionController {
+    pub fn new(state_store: Arc<dyn StateStore>, runtime: Arc<dyn ContainerRuntime>) -> Self {
+        Self {
+            state_store,
+            runtime,
+            budgets: RwLock::new(HashMap::new()),
+            eviction: Mutex::new(()),
+        }
+    }
+
+    /// Register a budget, replacing any budget with the same id.
+    pub async fn add_budget(&self, budget: DisruptionBudget) -> Result<DisruptionBudgetId> {
+        budget.validate()?;
+        let id = budget.id;
+        info!("Registered disruption budget {} ({})", budget.name, id);
+        self.budgets.write().await.insert(id, budget);
+        Ok(id)
+    }
+
+    pub async fn remove_budget(&self, id: &DisruptionBudgetId) -> Option<DisruptionBudget> {
+        self.budgets.write().await.remove(id)
+    }
+
+    pub async fn get_budget(&self, id: &DisruptionBudgetId) -> Option<DisruptionBudget> {
+        self.budgets.read().await.get(id).cloned()
+    }
+
+    pub async fn list_budgets(&self) -> Vec<DisruptionBudget> {
+        self.budgets.read().await.values().cloned().collect()
+    }
+
+    /// Current expected, healthy and allowed counts for a budget.
+    pub async fn status(&self, budget: &DisruptionBudget) -> Result<DisruptionBudgetStatus> {
+        let mut expected = 0;
+        let mut healthy = 0;
+        for workload in self.state_store.list_workloads().await? {
+            if !budget.matches(&workload) {
+                continue;
+            }
+            expected += workload.replicas;
+            healthy += self
+                .state_store
+                .list_instances_for_workload(&workload.id)
+                .await?
+                .iter()
+                .filter(|i| i.status == WorkloadInstanceStatus::Running)
+                .count() as u32;
+        }
+        Ok(DisruptionBudgetStatus {
+            expected,
+            healthy,
+            disruptions_allowed: budget.disruptions_allowed(expected, healthy),
+        })
+    }
+
+    /// The first budget covering `instance` that has no room to evict it.
+    async fn blocking_budget(&self, instance: &WorkloadInstance) -> Result<Option<String>> {
+        if instance.status != WorkloadInstanceStatus::Running {
+            return Ok(None);
+        }
+        let Some(workload) = self.state_store.get_workload(&instance.workload_id).await? else {
+            return Ok(None);
+        };
+        let budgets: Vec<DisruptionBudget> = self
+            .budgets
+            .read()
+            .await
+            .values()
+            .filter(|b| b.matches(&workload))
+            .cloned()
+            .collect();
+        for budget in budgets {
+            if self.status(&budget).await?.disruptions_allowed == 0 {
+                return Ok(Some(budget.name));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Stop and delete an instance if every budget covering it allows.
+    /// Returns `false`, leaving the instance untouched, when a budget blocks it.
+    pub async fn evict(&self, instance: &WorkloadInstance) -> Result<bool> {
+        let _guard = self.eviction.lock().await;
+        if let Some(budget) = self.blocking_budget(instance).await? {
+            debug!(
+                "Eviction of instance {} blocked by disruption budget {}",
+                instance.id, budget
+            );
+            return Ok(false);
+        }
+        if let Err(e) = self.runtime.stop_instance(&instance.container_ids).await {
+            warn!(
+                "Failed to stop containers of evicted instance {}: {:?}",
+                instance.id, e
+            );
+        }
+        self.state_store
+            .delete_instance(&instance.id.to_string())
+            .await?;
+        info!("Evicted instance {}", instance.id);
+        Ok(true)
+    }
+
+    /// Mark a node unschedulable, or schedulable again. Instances already on
+    /// the node keep running.
+    pub async fn cordon(&self, node_id: &NodeId, unschedulable: bool) -> Result<Node> {
+        let mut node = self
+            .state_store
+            .get_node(node_id)
+            .await?
+            .ok_or(OrchestrationError::NodeNotFound(*node_id))?;
+        if node.unschedulable != unschedulable {
+            node.unschedulable = unschedulable;
+            self.state_store.put_node(node.clone()).await?;
+            info!(
+                "Node {} {}",
+                node_id,
+                if unschedulable {
+                    "cordoned"
+                } else {
+                    "uncordoned"
+                }
+            );
+        }
+        Ok(node)
+    }
+
+    /// Cordon a node and evict every instance on it that the budgets allow.
+    /// Replacements are scheduled by whichever controller owns each workload.
+    pub async fn drain_node(&self, node_id: &NodeId) -> Result<DrainReport> {
+        self.cordon(node_id, true).await?;
+        let mut report = DrainReport::default();
+        let instances: Vec<WorkloadInstance> = self
+            .state_store
+            .list_all_instances()
+            .await?
+            .into_iter()
+            .filter(|i| i.node_id == *node_id)
+            .collect();
+        for instance in instances {
+            if self.evict(&instance).await? {
+                report.evicted.push(instance.id);
+                if !report.workloads.contains(&instance.workload_id) {
+                    report.workloads.push(instance.workload_id);
+                }
+            } else {
+                report.blocked.push(instance.id);
+            }
+        }
+        info!(
+            "Drained node {}: {} evicted, {} blocked",
+            node_id,
+            report.evicted.len(),
+            report.blocked.len()
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, StubRuntime};
+    use orchestrator_shared_types::{Keypair, NodeStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    fn selector(app: &str) -> HashMap<String, String> {
+        HashMap::from([("app".to_string(), app.to_string())])
+    }
+
+    async fn add_workload(
+        store: &InMemoryStateStore,
+        app: &str,
+        nodes: &[NodeId],
+    ) -> Vec<WorkloadInstance> {
+        let workload = WorkloadDefinition {
+            replicas: nodes.len() as u32,
+            labels: selector(app),
+            ..test_support::workload(app)
+        };
+        store.put_workload(workload.clone()).await.unwrap();
+        let mut instances = Vec::new();
+        for node_id in nodes {
+            let instance = WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id: workload.id,
+                node_id: *node_id,
+                container_ids: vec![],
+                status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
+            };
+            store.put_instance(instance.clone()).await.unwrap();
+            instances.push(instance);
+        }
+        instances
+    }
+
+    async fn add_node(store: &InMemoryStateStore) -> NodeId {
+        let node = Node {
+            id: Keypair::generate().public_key(),
+            address: "127.0.0.1:7280".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            resources_capacity: Default::default(),
+            resources_allocatable: Default::default(),
+            unschedulable: false,
+            resource_version: 0,
+        };
+        let id = node.id;
+        store.put_node(node).await.unwrap();
+        id
+    }
+
+    fn controller(store: &InMemoryStateStore) -> DisruptionController {
+        DisruptionController::new(Arc::new(store.clone()), Arc::new(StubRuntime::default()))
+    }
+
+    #[test]
+    fn test_disruptions_allowed() {
+        let budget = DisruptionBudget::new("web", selector("web"));
+        assert_eq!(
+            budget
+                .clone()
+                .with_min_available(2)
+                .disruptions_allowed(3, 3),
+            1
+        );
+        assert_eq!(
+            budget
+                .clone()
+                .with_min_available(2)
+                .disruptions_allowed(3, 2),
+            0
+        );
+        assert_eq!(
+            budget
+                .clone()
+                .with_max_unavailable(1)
+                .disruptions_allowed(3, 3),
+            1
+        );
+        assert_eq!(
+            budget
+                .clone()
+                .with_max_unavailable(1)
+                .disruptions_allowed(3, 2),
+            0
+        );
+        let both = budget.with_min_available(1).with_max_unavailable(2);
+        assert_eq!(both.disruptions_allowed(5, 5), 2);
+        assert_eq!(both.disruptions_allowed(5, 2), 0);
+    }
+
+    #[tokio::test]
+    async fn test_budget_without_limits_is_rejected() {
+        let store = InMemoryStateStore::new();
+        let result = controller(&store)
+            .add_budget(DisruptionBudget::new("web", selector("web")))
+            .await;
+        assert!(matches!(result, Err(OrchestrationError::ConfigError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_drain_stops_at_budget() {
+        let store = InMemoryStateStore::new();
+        let node = add_node(&store).await;
+        let other = Keypair::generate().public_key();
+        let web = add_workload(&store, "web", &[node, node, other]).await;
+        let batch = add_workload(&store, "batch", &[node]).await;
+
+        let controller = controller(&store);
+        let budget = DisruptionBudget::new("web", selector("web")).with_max_unavailable(1);
+        controller.add_budget(budget.clone()).await.unwrap();
+
+        let report = controller.drain_node(&node).await.unwrap();
+        assert!(!report.is_complete());
+        assert_eq!(report.blocked.len(), 1);
+        // One web instance and the unbudgeted batch instance leave the node
+        assert_eq!(report.evicted.len(), 2);
+        assert!(report.evicted.contains(&batch[0].id));
+        assert!(web[..2].iter().any(|i| report.evicted.contains(&i.id)));
+        assert_eq!(report.workloads.len(), 2);
+
+        // The node stays cordoned so replacements land elsewhere
+        assert_eq!(cordoned_nodes(&store).await.unwrap(), HashSet::from([node]));
+
+        let status = controller.status(&budget).await.unwrap();
+        assert_eq!(status.expected, 3);
+        assert_eq!(status.healthy, 2);
+        assert_eq!(status.disruptions_allowed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cordon_and_uncordon() {
+        let store = InMemoryStateStore::new();
+        let node = add_node(&store).await;
+        let controller = controller(&store);
+
+        assert!(controller.cordon(&node, true).await.unwrap().unschedulable);
+        assert!(!store
+            .get_node(&node)
+            .await
+            .unwrap()
+            .unwrap()
+            .is_schedulable());
+        assert!(!controller.cordon(&node, false).await.unwrap().unschedulable);
+        assert!(cordoned_nodes(&store).await.unwrap().is_empty());
+
+        let unknown = Keypair::generate().public_key();
+        assert!(matches!(
+            controller.cordon(&unknown, true).await,
+            Err(OrchestrationError::NodeNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_instances_not_running_are_always_evictable() {
+        let store = InMemoryStateStore::new();
+        let node = Keypair::generate().public_key();
+        let mut instance = add_workload(&store, "web", &[node]).await.remove(0);
+        instance.status = WorkloadInstanceStatus::CrashLoopBackOff;
+        store.put_instance(instance.clone()).await.unwrap();
+
+        let controller = controller(&store);
+        controller
+            .add_budget(DisruptionBudget::new("web", selector("web")).with_min_available(1))
+            .await
+            .unwrap();
+        assert!(controller.evict(&instance).await.unwrap());
+        assert!(store
+            .get_instance(&instance.id.to_string())
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
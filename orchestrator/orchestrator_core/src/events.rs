@@ -0,0 +1,269 @@
+//! Recording events about objects.
+//!
+//! Controllers and the orchestrator report what they do to workloads,
+//! instances and nodes as [`Event`]s, so users can see why an object is in its
+//! state, e.g. why an instance is pending or failed. A recorder counts an
+//! event about the same object for the same reason within
+//! [`EventRecorder::REPEAT_WINDOW`] of the last one on that record, taking
+//! its message, instead of adding another. Repeats are found in the
+//! recorder's own cache rather than in the store, so a recorder that starts
+//! afresh begins new records. The garbage collector deletes events once they
+//! are older than its `event_ttl`.
+//!
+//! Recording never fails the caller: stores that keep no events report
+//! `NotImplemented` and other errors are logged.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use orchestrator_shared_types::{
+    Event, EventType, ObjectReference, OrchestrationError, Result, WorkloadDefinition,
+    WorkloadInstance,
+};
+use state_store_interface::StateStore;
+
+/// What makes an event a repeat: the object's kind and ID, and the reason.
+/// The source is the recorder's own.
+type RepeatKey = (String, String, String);
+
+/// Records events from one component.
+pub struct EventRecorder {
+    state_store: Arc<dyn StateStore>,
+    source: String,
+    /// Latest record of each repeat key, held only while it is updated.
+    latest: Mutex<HashMap<RepeatKey, Event>>,
+}
+
+impl EventRecorder {
+    /// How long after its last occurrence a record still counts repeats.
+    pub const REPEAT_WINDOW: Duration = Duration::from_secs(600);
+    /// Records cached before those outside the repeat window are dropped.
+    const MAX_CACHED: usize = 4096;
+
+    pub fn new(state_store: Arc<dyn StateStore>, source: impl Into<String>) -> Self {
+        Self {
+            state_store,
+            source: source.into(),
+            latest: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a normal event about `object` in `namespace`.
+    pub async fn normal(
+        &self,
+        object: ObjectReference,
+        namespace: &str,
+        reason: &str,
+        message: impl Into<String>,
+    ) {
+        self.emit(object, namespace, EventType::Normal, reason, message.into())
+            .await
+    }
+
+    /// Record a warning about `object` in `namespace`.
+    pub async fn warning(
+        &self,
+        object: ObjectReference,
+        namespace: &str,
+        reason: &str,
+        message: impl Into<String>,
+    ) {
+        self.emit(
+            object,
+            namespace,
+            EventType::Warning,
+            reason,
+            message.into(),
+        )
+        .await
+    }
+
+    /// Record a warning about a workload.
+    pub async fn workload_warning(
+        &self,
+        workload: &WorkloadDefinition,
+        reason: &str,
+        message: impl Into<String>,
+    ) {
+        let object = ObjectReference::workload(workload);
+        self.warning(object, &workload.namespace, reason, message)
+            .await
+    }
+
+    /// Record a normal event about an instance.
+    pub async fn instance_normal(
+        &self,
+        instance: &WorkloadInstance,
+        reason: &str,
+        message: impl Into<String>,
+    ) {
+        let object = ObjectReference::instance(instance);
+        self.normal(object, &instance.namespace, reason, message)
+            .await
+    }
+
+    /// Record a warning about an instance.
+    pub async fn instance_warning(
+        &self,
+        instance: &WorkloadInstance,
+        reason: &str,
+        message: impl Into<String>,
+    ) {
+        let object = ObjectReference::instance(instance);
+        self.warning(object, &instance.namespace, reason, message)
+            .await
+    }
+
+    async fn emit(
+        &self,
+        object: ObjectReference,
+        namespace: &str,
+        event_type: EventType,
+        reason: &str,
+        message: String,
+    ) {
+        match self
+            .record(object, namespace, event_type, reason, message)
+            .await
+        {
+            Ok(_) | Err(OrchestrationError::NotImplemented(_)) => {}
+            Err(e) => warn!("Failed to record {} event: {}", reason, e),
+        }
+    }
+
+    /// Store the event, or count it on the latest record of the same object
+    /// and reason.
+    pub async fn record(
+        &self,
+        involved_object: ObjectReference,
+        namespace: &str,
+        event_type: EventType,
+        reason: &str,
+        message: String,
+    ) -> Result<Event> {
+        let now = Utc::now().timestamp_millis() as u64;
+        let window = Self::REPEAT_WINDOW.as_millis() as u64;
+        let key = (
+            involved_object.kind.clone(),
+            involved_object.id.clone(),
+            reason.to_string(),
+        );
+        let event = {
+            let mut latest = self.latest.lock().unwrap();
+            if latest.len() >= Self::MAX_CACHED {
+                latest.retain(|_, event| now.saturating_sub(event.last_timestamp_ms) < window);
+            }
+            match latest.get_mut(&key) {
+                Some(event) if now.saturating_sub(event.last_timestamp_ms) < window => {
+                    event.count += 1;
+                    event.last_timestamp_ms = now;
+                    event.event_type = event_type;
+                    event.message = message;
+                    event.clone()
+                }
+                _ => {
+                    let event = Event {
+                        id: Uuid::new_v4(),
+                        involved_object,
+                        namespace: namespace.to_string(),
+                        event_type,
+                        reason: reason.to_string(),
+                        message,
+                        source: self.source.clone(),
+                        count: 1,
+                        first_timestamp_ms: now,
+                        last_timestamp_ms: now,
+                    };
+                    latest.insert(key, event.clone());
+                    event
+                }
+            }
+        };
+        debug!(
+            "Event {} on {} {}: {}",
+            event.reason, event.involved_object.kind, event.involved_object.name, event.message
+        );
+        self.state_store.put_event(event.clone()).await?;
+        Ok(event)
+    }
+}
+
+/// Delete events whose last occurrence is before `cutoff_ms`, returning how
+/// many were deleted.
+pub async fn prune_events(store: &dyn StateStore, cutoff_ms: u64) -> Result<usize> {
+    let events = match store.list_events().await {
+        Ok(events) => events,
+        Err(OrchestrationError::NotImplemented(_)) => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut pruned = 0;
+    for event in events.iter().filter(|e| e.last_timestamp_ms < cutoff_ms) {
+        store.delete_event(&event.id).await?;
+        pruned += 1;
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    #[tokio::test]
+    async fn test_repeats_are_counted() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let recorder = EventRecorder::new(store.clone(), "scheduler");
+        let object = ObjectReference {
+            kind: "Workload".to_string(),
+            id: Uuid::new_v4().to_string(),
+            name: "web".to_string(),
+        };
+
+        for _ in 0..3 {
+            recorder
+                .warning(object.clone(), "default", "FailedScheduling", "no nodes")
+                .await;
+        }
+        // A new message for the same reason updates the record
+        recorder
+            .warning(
+                object.clone(),
+                "default",
+                "FailedScheduling",
+                "insufficient memory",
+            )
+            .await;
+        recorder
+            .normal(object.clone(), "default", "Scheduled", "Assigned")
+            .await;
+
+        let events = store.list_events().await.unwrap();
+        assert_eq!(events.len(), 2);
+        let repeated = events
+            .iter()
+            .find(|e| e.reason == "FailedScheduling")
+            .unwrap();
+        assert_eq!(repeated.count, 4);
+        assert_eq!(repeated.message, "insufficient memory");
+        assert_eq!(repeated.namespace, "default");
+        assert_eq!(repeated.source, "scheduler");
+        assert!(repeated.first_timestamp_ms <= repeated.last_timestamp_ms);
+        assert_eq!(events.iter().map(|e| e.count).sum::<u32>(), 5);
+
+        // Other components keep records of their own
+        EventRecorder::new(store.clone(), "kubelet")
+            .warning(object.clone(), "default", "FailedScheduling", "no nodes")
+            .await;
+        let events = store.list_events().await.unwrap();
+        assert_eq!(events.len(), 3);
+
+        let cutoff = events.iter().map(|e| e.last_timestamp_ms).max().unwrap() + 1;
+        assert_eq!(prune_events(&*store, cutoff).await.unwrap(), 3);
+        assert!(store.list_events().await.unwrap().is_empty());
+    }
+}
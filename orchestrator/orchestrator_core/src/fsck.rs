@@ -0,0 +1,527 @@
+//! Cluster state consistency checking.
+//!
+//! The [`ConsistencyChecker`] cross-checks the state store against what the
+//! container runtime and cluster manager report, which is worth doing after a
+//! crash or after editing state by hand. It finds:
+//!
+//! - **Instances without containers**: running instances whose containers the
+//!   runtime no longer knows. Repair deletes the instance and resubmits its
+//!   workload so a replacement is scheduled.
+//! - **Orphaned containers**: containers no instance refers to. Repair stops
+//!   and removes them.
+//! - **Instances of deleted workloads**: repair stops their containers and
+//!   deletes them.
+//! - **Instances on unknown nodes**: instances assigned to a node the store
+//!   has no record of. Repair deletes the instance and resubmits its workload.
+//! - **Stale node leases**: nodes marked Ready that hold no lease. Repair
+//!   marks them NotReady, leaving eviction to the node lifecycle controller.
+//!
+//! Checks whose source cannot be reached (a node's runtime, the cluster's
+//! leases) are skipped and listed in the report rather than failing the run.
+//! A container created between listing containers and storing its instance
+//! looks orphaned, so repair re-reads instances before removing any.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use cluster_manager_interface::ClusterManager;
+use container_runtime_interface::ContainerRuntime;
+use orchestrator_shared_types::{
+    ContainerId, NodeId, NodeStatus, OrchestrationError, Result, WorkloadDefinition, WorkloadId,
+    WorkloadInstance, WorkloadInstanceStatus,
+};
+use state_store_interface::StateStore;
+
+/// Kinds of inconsistency the checker finds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiscrepancyKind {
+    InstanceWithoutContainers,
+    OrphanedContainer,
+    InstanceOfDeletedWorkload,
+    InstanceOnUnknownNode,
+    StaleNodeLease,
+}
+
+impl DiscrepancyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiscrepancyKind::InstanceWithoutContainers => "instance_without_containers",
+            DiscrepancyKind::OrphanedContainer => "orphaned_container",
+            DiscrepancyKind::InstanceOfDeletedWorkload => "instance_of_deleted_workload",
+            DiscrepancyKind::InstanceOnUnknownNode => "instance_on_unknown_node",
+            DiscrepancyKind::StaleNodeLease => "stale_node_lease",
+        }
+    }
+}
+
+/// One inconsistency between the state store and the cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discrepancy {
+    pub kind: DiscrepancyKind,
+    /// ID of the instance, container or node concerned.
+    pub object: String,
+    pub node_id: Option<NodeId>,
+    pub detail: String,
+    pub repaired: bool,
+}
+
+/// Result of one check.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FsckReport {
+    pub discrepancies: Vec<Discrepancy>,
+    /// Checks that could not run, with the reason.
+    pub skipped: Vec<String>,
+}
+
+impl FsckReport {
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+
+    /// Number of discrepancies that were repaired.
+    pub fn repaired(&self) -> usize {
+        self.discrepancies.iter().filter(|d| d.repaired).count()
+    }
+
+    fn push(
+        &mut self,
+        kind: DiscrepancyKind,
+        object: impl Into<String>,
+        node_id: Option<NodeId>,
+        detail: impl Into<String>,
+    ) -> usize {
+        self.discrepancies.push(Discrepancy {
+            kind,
+            object: object.into(),
+            node_id,
+            detail: detail.into(),
+            repaired: false,
+        });
+        self.discrepancies.len() - 1
+    }
+}
+
+/// What repairing a discrepancy involves.
+enum Repair {
+    /// Stop the instance's containers, delete it and optionally resubmit its
+    /// workload.
+    RemoveInstance {
+        instance: WorkloadInstance,
+        resubmit: bool,
+    },
+    RemoveContainer(ContainerId),
+    MarkNotReady(NodeId),
+}
+
+/// Cross-checks the state store against the runtime and cluster manager.
+pub struct ConsistencyChecker {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    cluster: Arc<dyn ClusterManager>,
+    workload_tx: mpsc::Sender<WorkloadDefinition>,
+}
+
+impl ConsistencyChecker {
+    pub fn new(
+        state_store: Arc<dyn StateStore>,
+        runtime: Arc<dyn ContainerRuntime>,
+        cluster: Arc<dyn ClusterManager>,
+        workload_tx: mpsc::Sender<WorkloadDefinition>,
+    ) -> Self {
+        Self {
+            state_store,
+            runtime,
+            cluster,
+            workload_tx,
+        }
+    }
+
+    /// Report discrepancies without changing anything.
+    pub async fn check(&self) -> Result<FsckReport> {
+        Ok(self.scan().await?.0)
+    }
+
+    /// Report discrepancies and repair them.
+    pub async fn repair(&self) -> Result<FsckReport> {
+        let (mut report, repairs) = self.scan().await?;
+        let mut resubmit = Vec::new();
+        let referenced = if repairs
+            .iter()
+            .any(|(_, repair)| matches!(repair, Repair::RemoveContainer(_)))
+        {
+            self.referenced_containers().await?
+        } else {
+            HashSet::new()
+        };
+
+        for (index, repair) in repairs {
+            let discrepancy = &mut report.discrepancies[index];
+            let result = match repair {
+                Repair::RemoveInstance {
+                    instance,
+                    resubmit: again,
+                } => {
+                    if again && !resubmit.contains(&instance.workload_id) {
+                        resubmit.push(instance.workload_id);
+                    }
+                    self.remove_instance(&instance).await
+                }
+                Repair::RemoveContainer(id) if referenced.contains(&id) => {
+                    // Claimed by an instance stored since the scan
+                    discrepancy.detail.push_str("; now referenced, left alone");
+                    continue;
+                }
+                Repair::RemoveContainer(id) => self.runtime.stop_instance(&[id]).await,
+                Repair::MarkNotReady(node_id) => self.mark_not_ready(&node_id).await,
+            };
+            match result {
+                Ok(()) => {
+                    info!(
+                        "fsck repaired {} {}",
+                        discrepancy.kind.as_str(),
+                        discrepancy.object
+                    );
+                    discrepancy.repaired = true;
+                }
+                Err(e) => {
+                    warn!(
+                        "fsck could not repair {} {}: {:?}",
+                        discrepancy.kind.as_str(),
+                        discrepancy.object,
+                        e
+                    );
+                    discrepancy
+                        .detail
+                        .push_str(&format!("; repair failed: {}", e));
+                }
+            }
+        }
+
+        for workload_id in resubmit {
+            self.resubmit(&workload_id).await?;
+        }
+        Ok(report)
+    }
+
+    async fn scan(&self) -> Result<(FsckReport, Vec<(usize, Repair)>)> {
+        let nodes = self.state_store.list_nodes().await?;
+        let workloads: HashSet<WorkloadId> = self
+            .state_store
+            .list_workloads()
+            .await?
+            .into_iter()
+            .map(|w| w.id)
+            .collect();
+        let instances = self.state_store.list_all_instances().await?;
+
+        let mut report = FsckReport::default();
+        let mut repairs = Vec::new();
+
+        // Containers the runtime reports for each node it could be asked about
+        let mut containers: HashMap<NodeId, HashSet<ContainerId>> = HashMap::new();
+        for node in &nodes {
+            match self.runtime.list_containers(node.id).await {
+                Ok(listed) => {
+                    containers.insert(node.id, listed.into_iter().map(|c| c.id).collect());
+                }
+                Err(e) => report
+                    .skipped
+                    .push(format!("containers on node {}: {}", node.id, e)),
+            }
+        }
+
+        for instance in &instances {
+            let object = instance.id.to_string();
+            if !workloads.contains(&instance.workload_id) {
+                let index = report.push(
+                    DiscrepancyKind::InstanceOfDeletedWorkload,
+                    object,
+                    Some(instance.node_id),
+                    format!("workload {} does not exist", instance.workload_id),
+                );
+                repairs.push((
+                    index,
+                    Repair::RemoveInstance {
+                        instance: instance.clone(),
+                        resubmit: false,
+                    },
+                ));
+                continue;
+            }
+            if !nodes.iter().any(|n| n.id == instance.node_id) {
+                let index = report.push(
+                    DiscrepancyKind::InstanceOnUnknownNode,
+                    object,
+                    Some(instance.node_id),
+                    format!("node {} is not registered", instance.node_id),
+                );
+                repairs.push((
+                    index,
+                    Repair::RemoveInstance {
+                        instance: instance.clone(),
+                        resubmit: true,
+                    },
+                ));
+                continue;
+            }
+
+            // Instances still starting or already finished may rightly lack containers
+            let expects_containers = matches!(
+                instance.status,
+                WorkloadInstanceStatus::Running | WorkloadInstanceStatus::CrashLoopBackOff
+            );
+            let Some(on_node) = containers.get(&instance.node_id) else {
+                continue;
+            };
+            let missing = instance
+                .container_ids
+                .iter()
+                .filter(|id| !on_node.contains(*id))
+                .count();
+            if expects_containers && (instance.container_ids.is_empty() || missing > 0) {
+                let index = report.push(
+                    DiscrepancyKind::InstanceWithoutContainers,
+                    object,
+                    Some(instance.node_id),
+                    format!(
+                        "{} of {} containers missing from the runtime",
+                        missing,
+                        instance.container_ids.len()
+                    ),
+                );
+                repairs.push((
+                    index,
+                    Repair::RemoveInstance {
+                        instance: instance.clone(),
+                        resubmit: true,
+                    },
+                ));
+            }
+        }
+
+        let referenced: HashSet<&ContainerId> = instances
+            .iter()
+            .flat_map(|i| i.container_ids.iter())
+            .collect();
+        for (node_id, on_node) in &containers {
+            for id in on_node.iter().filter(|id| !referenced.contains(id)) {
+                let index = report.push(
+                    DiscrepancyKind::OrphanedContainer,
+                    id.clone(),
+                    Some(*node_id),
+                    "no instance refers to this container",
+                );
+                repairs.push((index, Repair::RemoveContainer(id.clone())));
+            }
+        }
+
+        match self.cluster.node_leases().await {
+            Ok(leases) => {
+                let leased: HashSet<NodeId> = leases.into_iter().map(|(id, _)| id).collect();
+                for node in &nodes {
+                    if node.status == NodeStatus::Ready && !leased.contains(&node.id) {
+                        let index = report.push(
+                            DiscrepancyKind::StaleNodeLease,
+                            node.id.to_string(),
+                            Some(node.id),
+                            "node is Ready but holds no lease",
+                        );
+                        repairs.push((index, Repair::MarkNotReady(node.id)));
+                    }
+                }
+            }
+            Err(e) => report.skipped.push(format!("node leases: {}", e)),
+        }
+
+        Ok((report, repairs))
+    }
+
+    async fn referenced_containers(&self) -> Result<HashSet<ContainerId>> {
+        Ok(self
+            .state_store
+            .list_all_instances()
+            .await?
+            .into_iter()
+            .flat_map(|i| i.container_ids)
+            .collect())
+    }
+
+    async fn remove_instance(&self, instance: &WorkloadInstance) -> Result<()> {
+        // Containers that are already gone make stopping fail; the instance
+        // record goes regardless
+        if let Err(e) = self.runtime.stop_instance(&instance.container_ids).await {
+            warn!(
+                "fsck could not stop containers of instance {}: {:?}",
+                instance.id, e
+            );
+        }
+        self.state_store
+            .delete_instance(&instance.id.to_string())
+            .await
+    }
+
+    async fn mark_not_ready(&self, node_id: &NodeId) -> Result<()> {
+        if let Some(mut node) = self.state_store.get_node(node_id).await? {
+            node.status = NodeStatus::NotReady;
+            self.state_store.put_node(node).await?;
+        }
+        Ok(())
+    }
+
+    async fn resubmit(&self, workload_id: &WorkloadId) -> Result<()> {
+        if let Some(workload) = self.state_store.get_workload(workload_id).await? {
+            self.workload_tx.send(workload).await.map_err(|_| {
+                OrchestrationError::InternalError(
+                    "Orchestrator workload channel closed".to_string(),
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, StubRuntime};
+    use async_trait::async_trait;
+    use cluster_manager_interface::ClusterEvent;
+    use orchestrator_shared_types::{Keypair, Node, NodeLease, NodeResources};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use tokio::sync::watch;
+    use uuid::Uuid;
+
+    /// Cluster manager reporting a lease for the given nodes only.
+    struct LeaseCluster(Vec<NodeId>);
+
+    #[async_trait]
+    impl ClusterManager for LeaseCluster {
+        async fn initialize(&self) -> Result<()> {
+            Ok(())
+        }
+        async fn get_node(&self, _node_id: &NodeId) -> Result<Option<Node>> {
+            Ok(None)
+        }
+        async fn list_nodes(&self) -> Result<Vec<Node>> {
+            Ok(vec![])
+        }
+        async fn subscribe_to_events(&self) -> Result<watch::Receiver<Option<ClusterEvent>>> {
+            Ok(watch::channel(None).1)
+        }
+        async fn node_leases(&self) -> Result<Vec<(NodeId, NodeLease)>> {
+            Ok(self
+                .0
+                .iter()
+                .map(|id| {
+                    (
+                        *id,
+                        NodeLease {
+                            renewals: 1,
+                            duration_secs: 40,
+                        },
+                    )
+                })
+                .collect())
+        }
+    }
+
+    fn node(id: NodeId) -> Node {
+        Node {
+            id,
+            address: "10.0.0.1:8080".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            resources_capacity: NodeResources::default(),
+            resources_allocatable: NodeResources::default(),
+            unschedulable: false,
+            resource_version: 0,
+        }
+    }
+
+    fn instance(workload_id: WorkloadId, node_id: NodeId, containers: &[&str]) -> WorkloadInstance {
+        WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id,
+            node_id,
+            container_ids: containers.iter().map(|c| c.to_string()).collect(),
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fsck_finds_and_repairs_discrepancies() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let runtime = Arc::new(StubRuntime::default());
+        let (healthy, stale) = (
+            Keypair::generate().public_key(),
+            Keypair::generate().public_key(),
+        );
+        store.put_node(node(healthy)).await.unwrap();
+        store.put_node(node(stale)).await.unwrap();
+        runtime
+            .containers
+            .lock()
+            .unwrap()
+            .insert(healthy, vec!["ok".to_string(), "orphan".to_string()]);
+
+        let web = test_support::workload("web");
+        store.put_workload(web.clone()).await.unwrap();
+        let ok = instance(web.id, healthy, &["ok"]);
+        let lost = instance(web.id, healthy, &["gone"]);
+        let deleted = instance(Uuid::new_v4(), healthy, &[]);
+        let unknown = instance(web.id, Keypair::generate().public_key(), &[]);
+        for i in [&ok, &lost, &deleted, &unknown] {
+            store.put_instance(i.clone()).await.unwrap();
+        }
+
+        let (workload_tx, mut workload_rx) = mpsc::channel(10);
+        let checker = ConsistencyChecker::new(
+            store.clone(),
+            runtime.clone(),
+            Arc::new(LeaseCluster(vec![healthy])),
+            workload_tx,
+        );
+
+        let report = checker.check().await.unwrap();
+        let mut found: Vec<(&str, String)> = report
+            .discrepancies
+            .iter()
+            .map(|d| (d.kind.as_str(), d.object.clone()))
+            .collect();
+        found.sort();
+        let mut expected = vec![
+            ("instance_of_deleted_workload", deleted.id.to_string()),
+            ("instance_on_unknown_node", unknown.id.to_string()),
+            ("instance_without_containers", lost.id.to_string()),
+            ("orphaned_container", "orphan".to_string()),
+            ("stale_node_lease", stale.to_string()),
+        ];
+        expected.sort();
+        assert_eq!(found, expected);
+        assert_eq!(report.repaired(), 0);
+        assert_eq!(store.list_all_instances().await.unwrap().len(), 4);
+
+        let report = checker.repair().await.unwrap();
+        assert_eq!(report.repaired(), 5);
+        let remaining = store.list_all_instances().await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, ok.id);
+        assert_eq!(
+            store.get_node(&stale).await.unwrap().unwrap().status,
+            NodeStatus::NotReady
+        );
+        assert_eq!(runtime.containers.lock().unwrap()[&healthy], vec!["ok"]);
+        // Resubmitted once for its two lost instances
+        assert_eq!(workload_rx.try_recv().unwrap().id, web.id);
+        assert!(workload_rx.try_recv().is_err());
+
+        assert!(checker.check().await.unwrap().is_clean());
+    }
+}
@@ -0,0 +1,494 @@
+//! Garbage collection of finished and superseded objects.
+//!
+//! The [`GarbageCollector`] enforces a [`RetentionPolicy`] on a fixed interval
+//! so the state store does not grow without bound over months of operation:
+//!
+//! - **Succeeded jobs**: once every replica of a job has succeeded and the job
+//!   has stayed that way for `succeeded_job_ttl`, its instances and workload
+//!   are deleted together. Deleting only the instances would make the
+//!   orchestrator schedule the job again. Jobs created by a cron job are left
+//!   to the cron job's own history limits.
+//! - **Revisions**: stateful set and workload revision histories are trimmed
+//!   to the newest `revision_history_limit` replaced entries.
+//! - **Events**: events are deleted once `event_ttl` has passed since they
+//!   last occurred.
+//!
+//! The job TTL is measured from when the collector first observes the job as
+//! succeeded, so it restarts if the collector does.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use container_runtime_interface::ContainerRuntime;
+use orchestrator_shared_types::{Result, WorkloadId};
+use state_store_interface::StateStore;
+
+use crate::controllers::StatefulSetController;
+use crate::events;
+use crate::jobs::{is_job_workload, JobStatus, CRON_JOB_NAME_LABEL};
+use crate::rollout;
+
+/// How long finished objects are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// How long a succeeded job is kept; `None` keeps them forever.
+    pub succeeded_job_ttl: Option<Duration>,
+    /// Replaced revisions kept per stateful set and workload; `None` keeps all
+    /// of them.
+    pub revision_history_limit: Option<usize>,
+    /// How long an event is kept after it last occurred; `None` keeps them
+    /// forever.
+    pub event_ttl: Option<Duration>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            succeeded_job_ttl: Some(Duration::from_secs(3600)),
+            revision_history_limit: Some(10),
+            event_ttl: Some(Duration::from_secs(3600)),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// A policy that reclaims nothing.
+    pub fn keep_all() -> Self {
+        Self {
+            succeeded_job_ttl: None,
+            revision_history_limit: None,
+            event_ttl: None,
+        }
+    }
+
+    pub fn with_succeeded_job_ttl(mut self, ttl: Duration) -> Self {
+        self.succeeded_job_ttl = Some(ttl);
+        self
+    }
+
+    pub fn with_revision_history_limit(mut self, limit: usize) -> Self {
+        self.revision_history_limit = Some(limit);
+        self
+    }
+
+    pub fn with_event_ttl(mut self, ttl: Duration) -> Self {
+        self.event_ttl = Some(ttl);
+        self
+    }
+}
+
+/// Kinds of object the collector reclaims.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReclaimedKind {
+    JobInstance,
+    JobWorkload,
+    Revision,
+    Event,
+}
+
+impl ReclaimedKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReclaimedKind::JobInstance => "job_instance",
+            ReclaimedKind::JobWorkload => "job_workload",
+            ReclaimedKind::Revision => "revision",
+            ReclaimedKind::Event => "event",
+        }
+    }
+}
+
+/// Receives reclaim counts, e.g. to export them as metrics.
+pub trait GcObserver: Send + Sync {
+    /// Called after each pass for every kind with a non-zero count.
+    fn on_reclaimed(&self, kind: ReclaimedKind, count: u64);
+}
+
+#[cfg(feature = "observability")]
+impl GcObserver for observability::OrchestratorMetrics {
+    fn on_reclaimed(&self, kind: ReclaimedKind, count: u64) {
+        self.inc_gc_reclaimed(kind.as_str(), count);
+    }
+}
+
+/// Objects reclaimed by one or more passes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub job_instances: u64,
+    pub job_workloads: u64,
+    pub revisions: u64,
+    pub events: u64,
+}
+
+impl GcReport {
+    pub fn is_empty(&self) -> bool {
+        self.counts().iter().all(|(_, count)| *count == 0)
+    }
+
+    fn counts(&self) -> [(ReclaimedKind, u64); 4] {
+        [
+            (ReclaimedKind::JobInstance, self.job_instances),
+            (ReclaimedKind::JobWorkload, self.job_workloads),
+            (ReclaimedKind::Revision, self.revisions),
+            (ReclaimedKind::Event, self.events),
+        ]
+    }
+
+    fn add(&mut self, other: &GcReport) {
+        self.job_instances += other.job_instances;
+        self.job_workloads += other.job_workloads;
+        self.revisions += other.revisions;
+        self.events += other.events;
+    }
+}
+
+/// Periodically reclaims objects according to a [`RetentionPolicy`].
+pub struct GarbageCollector {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    policy: RetentionPolicy,
+    stateful_sets: Option<Arc<StatefulSetController>>,
+    observer: Option<Arc<dyn GcObserver>>,
+    /// When each succeeded job was first seen finished.
+    finished_at: Mutex<HashMap<WorkloadId, DateTime<Utc>>>,
+    totals: Mutex<GcReport>,
+}
+
+impl GarbageCollector {
+    /// How often [`spawn`](Self::spawn) collects when run with defaults.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+    pub fn new(
+        state_store: Arc<dyn StateStore>,
+        runtime: Arc<dyn ContainerRuntime>,
+        policy: RetentionPolicy,
+    ) -> Self {
+        Self {
+            state_store,
+            runtime,
+            policy,
+            stateful_sets: None,
+            observer: None,
+            finished_at: Mutex::new(HashMap::new()),
+            totals: Mutex::new(GcReport::default()),
+        }
+    }
+
+    /// Prune the revision histories of this controller's stateful sets.
+    pub fn with_stateful_sets(mut self, controller: Arc<StatefulSetController>) -> Self {
+        self.stateful_sets = Some(controller);
+        self
+    }
+
+    /// Report reclaimed objects to `observer`.
+    pub fn with_observer(mut self, observer: Arc<dyn GcObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    pub fn policy(&self) -> &RetentionPolicy {
+        &self.policy
+    }
+
+    /// Everything reclaimed since the collector was created.
+    pub async fn totals(&self) -> GcReport {
+        *self.totals.lock().await
+    }
+
+    /// Run one collection pass now.
+    pub async fn collect(&self) -> Result<GcReport> {
+        self.collect_at(Utc::now()).await
+    }
+
+    /// Run one collection pass as of `now`.
+    pub async fn collect_at(&self, now: DateTime<Utc>) -> Result<GcReport> {
+        let mut report = GcReport::default();
+
+        if let Some(ttl) = self.policy.succeeded_job_ttl {
+            self.collect_jobs(now, ttl, &mut report).await?;
+        }
+        if let Some(limit) = self.policy.revision_history_limit {
+            report.revisions += rollout::prune_revisions(&*self.state_store, limit).await? as u64;
+            if let Some(stateful_sets) = &self.stateful_sets {
+                report.revisions += stateful_sets.prune_revisions(limit).await? as u64;
+            }
+        }
+        if let Some(ttl) = self.policy.event_ttl {
+            let cutoff_ms =
+                (now.timestamp_millis().max(0) as u64).saturating_sub(ttl.as_millis() as u64);
+            report.events += events::prune_events(&*self.state_store, cutoff_ms).await? as u64;
+        }
+
+        if !report.is_empty() {
+            info!(
+                "Garbage collection reclaimed {} job instance(s), {} job workload(s), \
+                 {} revision(s), {} event(s)",
+                report.job_instances, report.job_workloads, report.revisions, report.events
+            );
+            if let Some(observer) = &self.observer {
+                for (kind, count) in report.counts() {
+                    if count > 0 {
+                        observer.on_reclaimed(kind, count);
+                    }
+                }
+            }
+            self.totals.lock().await.add(&report);
+        }
+        Ok(report)
+    }
+
+    async fn collect_jobs(
+        &self,
+        now: DateTime<Utc>,
+        ttl: Duration,
+        report: &mut GcReport,
+    ) -> Result<()> {
+        let workloads = self.state_store.list_workloads().await?;
+        let mut finished_at = self.finished_at.lock().await;
+        finished_at.retain(|id, _| workloads.iter().any(|w| w.id == *id));
+
+        for workload in workloads
+            .iter()
+            .filter(|w| is_job_workload(w) && !w.labels.contains_key(CRON_JOB_NAME_LABEL))
+        {
+            let instances = self
+                .state_store
+                .list_instances_for_workload(&workload.id)
+                .await?;
+            if JobStatus::from_instances(workload.replicas, &instances) != JobStatus::Succeeded {
+                finished_at.remove(&workload.id);
+                continue;
+            }
+
+            let since = *finished_at.entry(workload.id).or_insert(now);
+            if (now - since).to_std().unwrap_or_default() < ttl {
+                continue;
+            }
+
+            debug!("Reclaiming succeeded job {}", workload.name);
+            for instance in &instances {
+                if let Err(e) = self.runtime.stop_instance(&instance.container_ids).await {
+                    warn!(
+                        "Failed to remove containers of job {}: {:?}",
+                        workload.name, e
+                    );
+                }
+            }
+            self.state_store
+                .delete_instances_for_workload(&workload.id)
+                .await?;
+            self.state_store.delete_workload(&workload.id).await?;
+            finished_at.remove(&workload.id);
+
+            report.job_instances += instances.len() as u64;
+            report.job_workloads += 1;
+        }
+        Ok(())
+    }
+
+    /// Collect every `interval` until the task is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.collect().await {
+                    error!("Garbage collection failed: {:?}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::Job;
+    use crate::test_support::{self, StubRuntime};
+    use chrono::TimeZone;
+    use orchestrator_shared_types::{
+        Event, EventType, Keypair, ObjectReference, WorkloadDefinition, WorkloadInstance,
+        WorkloadInstanceStatus,
+    };
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use std::sync::Mutex as StdMutex;
+    use uuid::Uuid;
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        reclaimed: StdMutex<Vec<(ReclaimedKind, u64)>>,
+    }
+
+    impl GcObserver for RecordingObserver {
+        fn on_reclaimed(&self, kind: ReclaimedKind, count: u64) {
+            self.reclaimed.lock().unwrap().push((kind, count));
+        }
+    }
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, h, m, 0).unwrap()
+    }
+
+    async fn add_job(
+        store: &InMemoryStateStore,
+        name: &str,
+        replicas: u32,
+        statuses: &[WorkloadInstanceStatus],
+    ) -> Job {
+        let job = Job::new(
+            name,
+            WorkloadDefinition {
+                replicas,
+                ..test_support::workload(name)
+            },
+        );
+        store.put_workload(job.template.clone()).await.unwrap();
+        for status in statuses {
+            store
+                .put_instance(WorkloadInstance {
+                    id: Uuid::new_v4(),
+                    workload_id: job.workload_id(),
+                    node_id: Keypair::generate().public_key(),
+                    container_ids: vec![Uuid::new_v4().to_string()],
+                    status: status.clone(),
+                    ip_addresses: vec![],
+                    restarts: vec![],
+                    namespace: "default".to_string(),
+                    resource_version: 0,
+                })
+                .await
+                .unwrap();
+        }
+        job
+    }
+
+    #[tokio::test]
+    async fn test_succeeded_jobs_reclaimed_after_ttl() {
+        use WorkloadInstanceStatus::*;
+
+        let store = Arc::new(InMemoryStateStore::new());
+        let observer = Arc::new(RecordingObserver::default());
+        let gc = GarbageCollector::new(
+            store.clone(),
+            Arc::new(StubRuntime::exited(0)),
+            RetentionPolicy::default().with_succeeded_job_ttl(Duration::from_secs(600)),
+        )
+        .with_observer(observer.clone());
+
+        let done = add_job(&store, "done", 2, &[Succeeded, Succeeded]).await;
+        let running = add_job(&store, "running", 2, &[Succeeded, Running]).await;
+        let failed = add_job(&store, "failed", 1, &[Failed]).await;
+        let mut scheduled = add_job(&store, "scheduled", 1, &[Succeeded]).await;
+        scheduled
+            .template
+            .labels
+            .insert(CRON_JOB_NAME_LABEL.to_string(), "nightly".to_string());
+        store
+            .put_workload(scheduled.template.clone())
+            .await
+            .unwrap();
+
+        // First seen finished at 00:00; still within the TTL at 00:09
+        assert!(gc.collect_at(at(0, 0)).await.unwrap().is_empty());
+        assert!(gc.collect_at(at(0, 9)).await.unwrap().is_empty());
+
+        let report = gc.collect_at(at(0, 10)).await.unwrap();
+        assert_eq!(
+            report,
+            GcReport {
+                job_instances: 2,
+                job_workloads: 1,
+                revisions: 0,
+                events: 0,
+            }
+        );
+        assert!(store
+            .get_workload(&done.workload_id())
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store
+            .list_instances_for_workload(&done.workload_id())
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Unfinished, failed and cron-owned jobs are untouched
+        for job in [&running, &failed, &scheduled] {
+            assert!(store
+                .get_workload(&job.workload_id())
+                .await
+                .unwrap()
+                .is_some());
+        }
+        assert_eq!(gc.totals().await, report);
+        assert_eq!(
+            *observer.reclaimed.lock().unwrap(),
+            vec![
+                (ReclaimedKind::JobInstance, 2),
+                (ReclaimedKind::JobWorkload, 1)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_keep_all_reclaims_nothing() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let gc = GarbageCollector::new(
+            store.clone(),
+            Arc::new(StubRuntime::exited(0)),
+            RetentionPolicy::keep_all(),
+        );
+        let done = add_job(&store, "done", 1, &[WorkloadInstanceStatus::Succeeded]).await;
+
+        gc.collect_at(at(0, 0)).await.unwrap();
+        assert!(gc.collect_at(at(23, 0)).await.unwrap().is_empty());
+        assert!(store
+            .get_workload(&done.workload_id())
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_events_expire_after_ttl() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let gc = GarbageCollector::new(
+            store.clone(),
+            Arc::new(StubRuntime::exited(0)),
+            RetentionPolicy::keep_all().with_event_ttl(Duration::from_secs(3600)),
+        );
+        for last_seen in [at(0, 0), at(0, 45)] {
+            let ms = last_seen.timestamp_millis() as u64;
+            store
+                .put_event(Event {
+                    id: Uuid::new_v4(),
+                    involved_object: ObjectReference::node(&Keypair::generate().public_key()),
+                    namespace: String::new(),
+                    event_type: EventType::Warning,
+                    reason: "NodeNotReady".to_string(),
+                    message: "Node stopped renewing its lease".to_string(),
+                    source: "node-lifecycle-controller".to_string(),
+                    count: 1,
+                    first_timestamp_ms: ms,
+                    last_timestamp_ms: ms,
+                })
+                .await
+                .unwrap();
+        }
+
+        assert!(gc.collect_at(at(0, 59)).await.unwrap().is_empty());
+        assert_eq!(gc.collect_at(at(1, 30)).await.unwrap().events, 1);
+        let remaining = store.list_events().await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(
+            remaining[0].last_timestamp_ms,
+            at(0, 45).timestamp_millis() as u64
+        );
+    }
+}
@@ -0,0 +1,513 @@
+//! Container status reporting from nodes to the control plane.
+//!
+//! Each node heartbeats a [`ContainerStatusReport`] through the cluster
+//! manager. Rather than the full container list, a report carries only the
+//! changes made since the revision the control plane last acknowledged, so
+//! steady-state heartbeats stay small however many containers a node runs.
+//!
+//! - [`ContainerStatusTracker`] (node side) numbers every change with a
+//!   revision and builds the report from the unacknowledged tail.
+//! - [`ContainerStatusTable`] (control plane) applies reports and answers with
+//!   a [`ContainerStatusAck`]. A report that does not line up with what the
+//!   table holds is not applied, and the ack it gets back makes the node send
+//!   a full report instead.
+//!
+//! [`StatusReporter`] and [`StatusCollector`] run the two sides on an interval.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use cluster_manager_interface::ClusterManager;
+use container_runtime_interface::{ContainerRuntime, ContainerStatus};
+use orchestrator_shared_types::{
+    ContainerId, ContainerStatusAck, ContainerStatusEntry, ContainerStatusReport, NodeId, Result,
+};
+
+/// Node-side record of container status changes.
+#[derive(Debug)]
+pub struct ContainerStatusTracker {
+    epoch: u64,
+    revision: u64,
+    /// Each container's current status and the revision it last changed at.
+    containers: HashMap<ContainerId, (ContainerStatusEntry, u64)>,
+    /// Removed containers the control plane has not acknowledged yet.
+    removed: HashMap<ContainerId, u64>,
+    /// `None` until the control plane acknowledges a report of this epoch.
+    acked: Option<u64>,
+    /// Removals up to this revision have been forgotten.
+    pruned_through: u64,
+}
+
+impl ContainerStatusTracker {
+    /// A tracker with a fresh epoch, so the control plane discards whatever
+    /// it kept from this node's previous run.
+    pub fn new() -> Self {
+        Self::with_epoch(Uuid::new_v4().as_u64_pair().0)
+    }
+
+    pub fn with_epoch(epoch: u64) -> Self {
+        Self {
+            epoch,
+            revision: 0,
+            containers: HashMap::new(),
+            removed: HashMap::new(),
+            acked: None,
+            pruned_through: 0,
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Records the node's complete current container list. Containers missing
+    /// from it are treated as removed. Returns whether anything changed.
+    pub fn observe(&mut self, statuses: impl IntoIterator<Item = ContainerStatusEntry>) -> bool {
+        let start = self.revision;
+        let mut seen = HashMap::new();
+        for entry in statuses {
+            seen.insert(entry.container_id.clone(), entry);
+        }
+
+        let gone: Vec<ContainerId> = self
+            .containers
+            .keys()
+            .filter(|id| !seen.contains_key(*id))
+            .cloned()
+            .collect();
+        for id in gone {
+            self.containers.remove(&id);
+            self.revision += 1;
+            self.removed.insert(id, self.revision);
+        }
+
+        for (id, entry) in seen {
+            if self
+                .containers
+                .get(&id)
+                .is_some_and(|(known, _)| *known == entry)
+            {
+                continue;
+            }
+            self.revision += 1;
+            self.removed.remove(&id);
+            self.containers.insert(id, (entry, self.revision));
+        }
+
+        self.revision != start
+    }
+
+    /// Applies the control plane's acknowledgement. Acks from another epoch
+    /// are ignored. An ack older than the removals already forgotten means
+    /// the control plane lost state, and the next report is sent in full.
+    pub fn acknowledge(&mut self, ack: ContainerStatusAck) {
+        if ack.epoch != self.epoch || ack.revision > self.revision {
+            return;
+        }
+        if ack.revision < self.pruned_through {
+            self.acked = None;
+            return;
+        }
+        self.acked = Some(ack.revision);
+        self.removed.retain(|_, revision| *revision > ack.revision);
+        self.pruned_through = ack.revision;
+    }
+
+    /// The changes the control plane has not acknowledged, or every
+    /// container if it has nothing to build on.
+    pub fn report(&self) -> ContainerStatusReport {
+        let Some(acked) = self.acked else {
+            return ContainerStatusReport {
+                epoch: self.epoch,
+                base_revision: 0,
+                revision: self.revision,
+                full: true,
+                updated: self.containers.values().map(|(e, _)| e.clone()).collect(),
+                removed: Vec::new(),
+            };
+        };
+        ContainerStatusReport {
+            epoch: self.epoch,
+            base_revision: acked,
+            revision: self.revision,
+            full: false,
+            updated: self
+                .containers
+                .values()
+                .filter(|(_, revision)| *revision > acked)
+                .map(|(e, _)| e.clone())
+                .collect(),
+            removed: self
+                .removed
+                .iter()
+                .filter(|(_, revision)| **revision > acked)
+                .map(|(id, _)| id.clone())
+                .collect(),
+        }
+    }
+}
+
+impl Default for ContainerStatusTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Default)]
+struct NodeContainers {
+    epoch: u64,
+    revision: u64,
+    containers: HashMap<ContainerId, ContainerStatusEntry>,
+}
+
+/// Control-plane view of every node's containers, built from heartbeats.
+#[derive(Debug, Default)]
+pub struct ContainerStatusTable {
+    nodes: HashMap<NodeId, NodeContainers>,
+}
+
+impl ContainerStatusTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a node's report if it follows on from what the table holds,
+    /// and returns the acknowledgement to send back either way.
+    pub fn apply(&mut self, node_id: NodeId, report: &ContainerStatusReport) -> ContainerStatusAck {
+        if report.full {
+            let containers = report
+                .updated
+                .iter()
+                .map(|entry| (entry.container_id.clone(), entry.clone()))
+                .collect();
+            self.nodes.insert(
+                node_id,
+                NodeContainers {
+                    epoch: report.epoch,
+                    revision: report.revision,
+                    containers,
+                },
+            );
+        } else if let Some(node) = self
+            .nodes
+            .get_mut(&node_id)
+            .filter(|node| node.epoch == report.epoch && report.base_revision <= node.revision)
+        {
+            // Older reports would roll back newer changes
+            if report.revision > node.revision {
+                for id in &report.removed {
+                    node.containers.remove(id);
+                }
+                for entry in &report.updated {
+                    node.containers
+                        .insert(entry.container_id.clone(), entry.clone());
+                }
+                node.revision = report.revision;
+            }
+        } else {
+            // A gap, or a delta from an epoch the table never saw in full.
+            // Acknowledging less than the node expects makes it resync.
+            return match self.ack(&node_id) {
+                Some(ack) if ack.epoch == report.epoch => ack,
+                _ => ContainerStatusAck {
+                    epoch: report.epoch,
+                    revision: 0,
+                },
+            };
+        }
+        self.ack(&node_id).unwrap_or_default()
+    }
+
+    /// What the table has applied for a node.
+    pub fn ack(&self, node_id: &NodeId) -> Option<ContainerStatusAck> {
+        self.nodes.get(node_id).map(|node| ContainerStatusAck {
+            epoch: node.epoch,
+            revision: node.revision,
+        })
+    }
+
+    /// The last reported status of a node's containers.
+    pub fn containers(&self, node_id: &NodeId) -> Vec<ContainerStatusEntry> {
+        self.nodes
+            .get(node_id)
+            .map(|node| node.containers.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn container_count(&self) -> usize {
+        self.nodes.values().map(|node| node.containers.len()).sum()
+    }
+
+    /// Forgets a node that left the cluster.
+    pub fn remove_node(&mut self, node_id: &NodeId) {
+        self.nodes.remove(node_id);
+    }
+}
+
+fn entry(status: ContainerStatus) -> ContainerStatusEntry {
+    ContainerStatusEntry {
+        container_id: status.id,
+        state: status.state,
+        exit_code: status.exit_code,
+        oom_killed: status.oom_killed,
+    }
+}
+
+/// Publishes this node's container status deltas with its heartbeat.
+pub struct StatusReporter {
+    node_id: NodeId,
+    runtime: Arc<dyn ContainerRuntime>,
+    cluster: Arc<dyn ClusterManager>,
+    tracker: Mutex<ContainerStatusTracker>,
+    /// `(base_revision, revision, full)` of the last published report.
+    last_published: Mutex<Option<(u64, u64, bool)>>,
+}
+
+impl StatusReporter {
+    /// How often the node's containers are listed and reported.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+
+    pub fn new(
+        node_id: NodeId,
+        runtime: Arc<dyn ContainerRuntime>,
+        cluster: Arc<dyn ClusterManager>,
+    ) -> Self {
+        Self {
+            node_id,
+            runtime,
+            cluster,
+            tracker: Mutex::new(ContainerStatusTracker::new()),
+            last_published: Mutex::new(None),
+        }
+    }
+
+    /// Lists the node's containers and publishes whatever the control plane
+    /// has not acknowledged. Returns whether a new report was published.
+    pub async fn report(&self) -> Result<bool> {
+        let statuses = self.runtime.list_containers(self.node_id).await?;
+        let ack = self.cluster.container_status_ack(&self.node_id).await?;
+        let report = {
+            let mut tracker = self.tracker.lock().unwrap();
+            if let Some(ack) = ack {
+                tracker.acknowledge(ack);
+            }
+            tracker.observe(statuses.into_iter().map(entry));
+            tracker.report()
+        };
+
+        let key = (report.base_revision, report.revision, report.full);
+        if *self.last_published.lock().unwrap() == Some(key) {
+            return Ok(false);
+        }
+        debug!(
+            node_id = %self.node_id,
+            revision = report.revision,
+            updated = report.updated.len(),
+            removed = report.removed.len(),
+            full = report.full,
+            "Publishing container status"
+        );
+        self.cluster
+            .publish_container_status(&self.node_id, report)
+            .await?;
+        *self.last_published.lock().unwrap() = Some(key);
+        Ok(true)
+    }
+
+    /// Report every `interval` until the task is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.report().await {
+                    error!("Container status report failed: {:?}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Applies the container status heartbeats of every node on the control plane.
+pub struct StatusCollector {
+    cluster: Arc<dyn ClusterManager>,
+    table: Mutex<ContainerStatusTable>,
+    /// The last report applied per node, by `(epoch, base_revision, revision)`.
+    /// Reports with the same key carry the same changes and are skipped.
+    seen: Mutex<HashMap<NodeId, (u64, u64, u64)>>,
+}
+
+impl StatusCollector {
+    /// How often heartbeated reports are applied.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+
+    pub fn new(cluster: Arc<dyn ClusterManager>) -> Self {
+        Self {
+            cluster,
+            table: Mutex::new(ContainerStatusTable::new()),
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Applies new reports and acknowledges them. Returns how many were applied.
+    pub async fn collect(&self) -> Result<usize> {
+        let reports = self.cluster.container_status_reports().await?;
+        let mut applied = 0;
+        for (node_id, report) in reports {
+            let key = (report.epoch, report.base_revision, report.revision);
+            if self.seen.lock().unwrap().get(&node_id) == Some(&key) {
+                continue;
+            }
+            let ack = self.table.lock().unwrap().apply(node_id, &report);
+            self.cluster
+                .acknowledge_container_status(&node_id, ack)
+                .await?;
+            self.seen.lock().unwrap().insert(node_id, key);
+            applied += 1;
+        }
+        Ok(applied)
+    }
+
+    /// The last reported status of a node's containers.
+    pub fn containers(&self, node_id: &NodeId) -> Vec<ContainerStatusEntry> {
+        self.table.lock().unwrap().containers(node_id)
+    }
+
+    /// Containers reported across the cluster.
+    pub fn container_count(&self) -> usize {
+        self.table.lock().unwrap().container_count()
+    }
+
+    /// Collect every `interval` until the task is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.collect().await {
+                    error!("Container status collection failed: {:?}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orchestrator_shared_types::Keypair;
+
+    fn status(id: &str, state: &str) -> ContainerStatusEntry {
+        ContainerStatusEntry {
+            container_id: id.to_string(),
+            state: state.to_string(),
+            exit_code: None,
+            oom_killed: false,
+        }
+    }
+
+    fn sorted(mut entries: Vec<ContainerStatusEntry>) -> Vec<ContainerStatusEntry> {
+        entries.sort_by(|a, b| a.container_id.cmp(&b.container_id));
+        entries
+    }
+
+    #[test]
+    fn test_reports_shrink_to_unacknowledged_changes() {
+        let node_id = Keypair::generate().public_key();
+        let mut tracker = ContainerStatusTracker::with_epoch(1);
+        let mut table = ContainerStatusTable::new();
+
+        tracker.observe([status("a", "running"), status("b", "running")]);
+        let report = tracker.report();
+        assert!(report.full);
+        assert_eq!(report.updated.len(), 2);
+        tracker.acknowledge(table.apply(node_id, &report));
+
+        // Nothing changed: the heartbeat carries no container status
+        assert!(!tracker.observe([status("a", "running"), status("b", "running")]));
+        let report = tracker.report();
+        assert!(!report.full);
+        assert!(report.is_empty());
+
+        // Only the changed and removed containers are sent
+        tracker.observe([status("a", "stopped"), status("c", "running")]);
+        let report = tracker.report();
+        assert!(!report.full);
+        assert_eq!(
+            sorted(report.updated.clone()),
+            vec![status("a", "stopped"), status("c", "running")]
+        );
+        assert_eq!(report.removed, vec!["b".to_string()]);
+
+        let ack = table.apply(node_id, &report);
+        assert_eq!(ack.revision, tracker.revision());
+        tracker.acknowledge(ack);
+        assert!(tracker.report().is_empty());
+        assert_eq!(
+            sorted(table.containers(&node_id)),
+            vec![status("a", "stopped"), status("c", "running")]
+        );
+    }
+
+    #[test]
+    fn test_gap_falls_back_to_full_report() {
+        let node_id = Keypair::generate().public_key();
+        let mut tracker = ContainerStatusTracker::with_epoch(1);
+        let mut table = ContainerStatusTable::new();
+
+        tracker.observe([status("a", "running")]);
+        tracker.acknowledge(table.apply(node_id, &tracker.report()));
+
+        // The control plane misses this heartbeat...
+        tracker.observe([status("a", "running"), status("b", "running")]);
+        let acked = table.ack(&node_id).unwrap();
+        let missed = tracker.report();
+        // ...and the node pretends it was acknowledged
+        tracker.acknowledge(ContainerStatusAck {
+            epoch: 1,
+            revision: missed.revision,
+        });
+
+        tracker.observe([status("b", "stopped")]);
+        let report = tracker.report();
+        assert_eq!(report.base_revision, missed.revision);
+
+        // The table refuses the gap and the node falls back to a full report
+        let ack = table.apply(node_id, &report);
+        assert_eq!(ack, acked);
+        tracker.acknowledge(ack);
+        let report = tracker.report();
+        assert!(report.full);
+        tracker.acknowledge(table.apply(node_id, &report));
+        assert_eq!(table.containers(&node_id), vec![status("b", "stopped")]);
+    }
+
+    #[test]
+    fn test_new_epoch_replaces_previous_run() {
+        let node_id = Keypair::generate().public_key();
+        let mut table = ContainerStatusTable::new();
+
+        let mut before = ContainerStatusTracker::with_epoch(1);
+        before.observe([status("old", "running")]);
+        before.acknowledge(table.apply(node_id, &before.report()));
+
+        // Acks for the previous run are ignored by the restarted node
+        let mut after = ContainerStatusTracker::with_epoch(2);
+        after.observe([status("new", "running")]);
+        after.acknowledge(table.ack(&node_id).unwrap());
+        let report = after.report();
+        assert!(report.full);
+
+        table.apply(node_id, &report);
+        assert_eq!(table.containers(&node_id), vec![status("new", "running")]);
+    }
+}
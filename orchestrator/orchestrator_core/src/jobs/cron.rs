@@ -0,0 +1,487 @@
+//! Cron schedule parsing.
+//!
+//! Supports the standard five-field format used by crontab and Kubernetes
+//! CronJobs:
+//!
+//! ```text
+//! ┌───────────── minute (0 - 59)
+//! │ ┌───────────── hour (0 - 23)
+//! │ │ ┌───────────── day of month (1 - 31)
+//! │ │ │ ┌───────────── month (1 - 12 or JAN-DEC)
+//! │ │ │ │ ┌───────────── day of week (0 - 7 or SUN-SAT, 0 and 7 are Sunday)
+//! │ │ │ │ │
+//! * * * * *
+//! ```
+//!
+//! Each field accepts `*`, single values, ranges (`1-5`), steps (`*/15`,
+//! `0-30/10`) and comma-separated lists of those. The macros `@yearly`,
+//! `@annually`, `@monthly`, `@weekly`, `@daily`, `@midnight` and `@hourly`
+//! are also accepted. All schedules are evaluated in UTC.
+
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+/// Upper bound on how far ahead `next_after` searches before giving up.
+/// Schedules like `0 0 30 2 *` (February 30th) never fire.
+const MAX_SEARCH_YEARS: i32 = 5;
+
+/// Upper bound on the missed fire times `latest_between` walks and counts.
+pub const MAX_MISSED_RUNS: usize = 100;
+
+/// Error returned when a cron expression cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronParseError(pub String);
+
+impl fmt::Display for CronParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid cron expression: {}", self.0)
+    }
+}
+
+impl std::error::Error for CronParseError {}
+
+/// A parsed cron schedule.
+///
+/// Each field is stored as a bitset of the values it matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Whether the day-of-month field was restricted (not `*`).
+    dom_restricted: bool,
+    /// Whether the day-of-week field was restricted (not `*`).
+    dow_restricted: bool,
+}
+
+impl CronSchedule {
+    /// Parse a cron expression.
+    pub fn parse(expression: &str) -> Result<Self, CronParseError> {
+        let trimmed = expression.trim();
+        let expanded = match trimmed {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other if other.starts_with('@') => {
+                return Err(CronParseError(format!("unknown macro '{}'", other)));
+            }
+            other => other,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(CronParseError(format!(
+                "expected 5 fields, found {} in '{}'",
+                fields.len(),
+                trimmed
+            )));
+        }
+
+        let minutes = parse_field(fields[0], 0, 59, &[])?;
+        let hours = parse_field(fields[1], 0, 23, &[])?;
+        let days_of_month = parse_field(fields[2], 1, 31, &[])?;
+        let months = parse_field(fields[3], 1, 12, &MONTH_NAMES)?;
+        let mut days_of_week = parse_field(fields[4], 0, 7, &DAY_NAMES)?;
+        // 7 is an alias for Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            expression: trimmed.to_string(),
+            minutes,
+            hours,
+            days_of_month,
+            months,
+            days_of_week,
+            dom_restricted: fields[2] != "*",
+            dow_restricted: fields[4] != "*",
+        })
+    }
+
+    /// The original expression this schedule was parsed from.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Returns the first fire time strictly after `after`.
+    ///
+    /// Returns `None` if the schedule never fires (e.g. February 30th).
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        // Start at the next whole minute
+        let mut t = Utc
+            .with_ymd_and_hms(
+                after.year(),
+                after.month(),
+                after.day(),
+                after.hour(),
+                after.minute(),
+                0,
+            )
+            .single()?
+            + Duration::minutes(1);
+        let limit_year = after.year() + MAX_SEARCH_YEARS;
+
+        while t.year() <= limit_year {
+            if !bit(self.months, t.month()) {
+                t = start_of_next_month(t)?;
+                continue;
+            }
+            if !self.day_matches(t) {
+                t = Utc
+                    .with_ymd_and_hms(t.year(), t.month(), t.day(), 0, 0, 0)
+                    .single()?
+                    + Duration::days(1);
+                continue;
+            }
+            if !bit(self.hours, t.hour()) {
+                t = Utc
+                    .with_ymd_and_hms(t.year(), t.month(), t.day(), t.hour(), 0, 0)
+                    .single()?
+                    + Duration::hours(1);
+                continue;
+            }
+            if !bit(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+                continue;
+            }
+            return Some(t);
+        }
+        None
+    }
+
+    /// Returns the last fire time at or before `at`.
+    ///
+    /// Returns `None` if the schedule did not fire in the preceding years.
+    pub fn latest_at_or_before(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut t = Utc
+            .with_ymd_and_hms(at.year(), at.month(), at.day(), at.hour(), at.minute(), 0)
+            .single()?;
+        let limit_year = at.year() - MAX_SEARCH_YEARS;
+
+        while t.year() >= limit_year {
+            if !bit(self.months, t.month()) {
+                t = Utc
+                    .with_ymd_and_hms(t.year(), t.month(), 1, 0, 0, 0)
+                    .single()?
+                    - Duration::minutes(1);
+                continue;
+            }
+            if !self.day_matches(t) {
+                t = Utc
+                    .with_ymd_and_hms(t.year(), t.month(), t.day(), 0, 0, 0)
+                    .single()?
+                    - Duration::minutes(1);
+                continue;
+            }
+            if !bit(self.hours, t.hour()) {
+                t = Utc
+                    .with_ymd_and_hms(t.year(), t.month(), t.day(), t.hour(), 0, 0)
+                    .single()?
+                    - Duration::minutes(1);
+                continue;
+            }
+            if !bit(self.minutes, t.minute()) {
+                t -= Duration::minutes(1);
+                continue;
+            }
+            return Some(t);
+        }
+        None
+    }
+
+    /// Returns the most recent fire time in `(after, now]`, together with the
+    /// number of fire times that fell in that window.
+    ///
+    /// At most [`MAX_MISSED_RUNS`] fire times are walked, so the count stops
+    /// there however long the window is.
+    pub fn latest_between(
+        &self,
+        after: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> (Option<DateTime<Utc>>, usize) {
+        let latest = self.latest_at_or_before(now).filter(|t| *t > after);
+        if latest.is_none() {
+            return (None, 0);
+        }
+        let mut count = 0;
+        let mut cursor = after;
+        while count < MAX_MISSED_RUNS {
+            match self.next_after(cursor) {
+                Some(next) if next <= now => {
+                    count += 1;
+                    cursor = next;
+                }
+                _ => break,
+            }
+        }
+        (latest, count)
+    }
+
+    fn day_matches(&self, t: DateTime<Utc>) -> bool {
+        let dom = bit(self.days_of_month, t.day());
+        let dow = bit(self.days_of_week, t.weekday().num_days_from_sunday());
+        // Standard cron semantics: when both day fields are restricted a
+        // match on either one is enough.
+        if self.dom_restricted && self.dow_restricted {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = CronParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+const MONTH_NAMES: [(&str, u32); 12] = [
+    ("JAN", 1),
+    ("FEB", 2),
+    ("MAR", 3),
+    ("APR", 4),
+    ("MAY", 5),
+    ("JUN", 6),
+    ("JUL", 7),
+    ("AUG", 8),
+    ("SEP", 9),
+    ("OCT", 10),
+    ("NOV", 11),
+    ("DEC", 12),
+];
+
+const DAY_NAMES: [(&str, u32); 7] = [
+    ("SUN", 0),
+    ("MON", 1),
+    ("TUE", 2),
+    ("WED", 3),
+    ("THU", 4),
+    ("FRI", 5),
+    ("SAT", 6),
+];
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1u64 << value) != 0
+}
+
+fn start_of_next_month(t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let (year, month) = if t.month() == 12 {
+        (t.year() + 1, 1)
+    } else {
+        (t.year(), t.month() + 1)
+    };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()
+}
+
+fn parse_value(
+    raw: &str,
+    min: u32,
+    max: u32,
+    names: &[(&str, u32)],
+) -> Result<u32, CronParseError> {
+    let upper = raw.to_ascii_uppercase();
+    if let Some((_, v)) = names.iter().find(|(name, _)| *name == upper) {
+        return Ok(*v);
+    }
+    let value: u32 = raw
+        .parse()
+        .map_err(|_| CronParseError(format!("invalid value '{}'", raw)))?;
+    if value < min || value > max {
+        return Err(CronParseError(format!(
+            "value {} out of range {}-{}",
+            value, min, max
+        )));
+    }
+    Ok(value)
+}
+
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[(&str, u32)],
+) -> Result<u64, CronParseError> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| CronParseError(format!("invalid step '{}'", step)))?;
+                if step == 0 {
+                    return Err(CronParseError("step must be greater than zero".to_string()));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((lo, hi)) = range.split_once('-') {
+            let lo = parse_value(lo, min, max, names)?;
+            let hi = parse_value(hi, min, max, names)?;
+            if lo > hi {
+                return Err(CronParseError(format!("invalid range '{}'", range)));
+            }
+            (lo, hi)
+        } else {
+            let value = parse_value(range, min, max, names)?;
+            // "5/10" means "starting at 5, every 10"
+            if step > 1 {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        let mut v = start;
+        while v <= end {
+            set |= 1u64 << v;
+            v += step;
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_every_minute() {
+        let s = CronSchedule::parse("* * * * *").unwrap();
+        assert_eq!(
+            s.next_after(at(2024, 1, 1, 0, 0)),
+            Some(at(2024, 1, 1, 0, 1))
+        );
+    }
+
+    #[test]
+    fn test_step_and_list() {
+        let s = CronSchedule::parse("*/15 9,17 * * *").unwrap();
+        assert_eq!(
+            s.next_after(at(2024, 1, 1, 9, 10)),
+            Some(at(2024, 1, 1, 9, 15))
+        );
+        assert_eq!(
+            s.next_after(at(2024, 1, 1, 9, 45)),
+            Some(at(2024, 1, 1, 17, 0))
+        );
+        assert_eq!(
+            s.next_after(at(2024, 1, 1, 17, 45)),
+            Some(at(2024, 1, 2, 9, 0))
+        );
+    }
+
+    #[test]
+    fn test_names_and_sunday_alias() {
+        let s = CronSchedule::parse("0 12 * JAN-MAR 7").unwrap();
+        // 2024-01-07 is a Sunday
+        assert_eq!(
+            s.next_after(at(2024, 1, 1, 0, 0)),
+            Some(at(2024, 1, 7, 12, 0))
+        );
+        let s = CronSchedule::parse("30 6 * * mon").unwrap();
+        assert_eq!(
+            s.next_after(at(2024, 1, 3, 0, 0)),
+            Some(at(2024, 1, 8, 6, 30))
+        );
+    }
+
+    #[test]
+    fn test_dom_dow_or_semantics() {
+        // 1st of the month OR any Friday
+        let s = CronSchedule::parse("0 0 1 * 5").unwrap();
+        // 2024-01-05 is a Friday
+        assert_eq!(
+            s.next_after(at(2024, 1, 1, 0, 0)),
+            Some(at(2024, 1, 5, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_macros() {
+        let s = CronSchedule::parse("@monthly").unwrap();
+        assert_eq!(
+            s.next_after(at(2024, 12, 15, 0, 0)),
+            Some(at(2025, 1, 1, 0, 0))
+        );
+        assert!(CronSchedule::parse("@fortnightly").is_err());
+    }
+
+    #[test]
+    fn test_impossible_schedule() {
+        let s = CronSchedule::parse("0 0 30 2 *").unwrap();
+        assert_eq!(s.next_after(at(2024, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("10-5 * * * *").is_err());
+        assert!(CronSchedule::parse("* * * FOO *").is_err());
+    }
+
+    #[test]
+    fn test_latest_between_counts_missed_runs() {
+        let s = CronSchedule::parse("0 * * * *").unwrap();
+        let (latest, count) = s.latest_between(at(2024, 1, 1, 0, 0), at(2024, 1, 1, 3, 30));
+        assert_eq!(latest, Some(at(2024, 1, 1, 3, 0)));
+        assert_eq!(count, 3);
+
+        let (latest, count) = s.latest_between(at(2024, 1, 1, 3, 0), at(2024, 1, 1, 3, 30));
+        assert_eq!((latest, count), (None, 0));
+    }
+
+    #[test]
+    fn test_latest_between_caps_missed_runs() {
+        let s = CronSchedule::parse("* * * * *").unwrap();
+        let (latest, count) = s.latest_between(at(2020, 1, 1, 0, 0), at(2024, 6, 1, 12, 30));
+        assert_eq!(latest, Some(at(2024, 6, 1, 12, 30)));
+        assert_eq!(count, MAX_MISSED_RUNS);
+    }
+
+    #[test]
+    fn test_latest_at_or_before() {
+        let s = CronSchedule::parse("30 6 * * mon").unwrap();
+        // 2024-01-08 is a Monday
+        assert_eq!(
+            s.latest_at_or_before(at(2024, 1, 10, 0, 0)),
+            Some(at(2024, 1, 8, 6, 30))
+        );
+        assert_eq!(
+            s.latest_at_or_before(at(2024, 1, 8, 6, 30)),
+            Some(at(2024, 1, 8, 6, 30))
+        );
+        let s = CronSchedule::parse("@yearly").unwrap();
+        assert_eq!(
+            s.latest_at_or_before(at(2024, 3, 1, 0, 0)),
+            Some(at(2024, 1, 1, 0, 0))
+        );
+        let s = CronSchedule::parse("0 0 30 2 *").unwrap();
+        assert_eq!(s.latest_at_or_before(at(2024, 1, 1, 0, 0)), None);
+    }
+}
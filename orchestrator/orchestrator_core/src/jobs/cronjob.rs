@@ -0,0 +1,777 @@
+//! CronJob resource and controller.
+//!
+//! A [`CronJob`] creates a [`Job`] from its template each time its cron
+//! schedule fires. The [`CronJobController`] is driven by a periodic tick:
+//! on every tick it refreshes the status of the jobs it owns, works out
+//! whether a schedule slot is due, applies the concurrency policy, submits
+//! the new job's workload to the orchestrator, and prunes finished jobs
+//! beyond the configured history limits.
+//!
+//! Cron jobs and the jobs they create are stored as resources and reloaded
+//! each time the controller starts, so a newly elected leader neither
+//! forgets a schedule nor runs a slot its predecessor already ran.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use orchestrator_core::jobs::{CronJob, CronJobController};
+//!
+//! let controller = Arc::new(CronJobController::new(state_store, runtime, workload_tx));
+//! controller.add_cron_job(CronJob::new("nightly-backup", "0 2 * * *", template)).await?;
+//! controller.spawn(CronJobController::DEFAULT_TICK_INTERVAL);
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use container_runtime_interface::ContainerRuntime;
+use orchestrator_shared_types::{OrchestrationError, Result, WorkloadDefinition};
+use state_store_interface::StateStore;
+
+use super::cron::{CronSchedule, MAX_MISSED_RUNS};
+use super::{Job, JobId, JobStatus, CRON_JOB_NAME_LABEL};
+use crate::resources::{self, Resource};
+
+pub type CronJobId = Uuid;
+
+/// How a cron job treats a new schedule slot while a previous job is still running.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ConcurrencyPolicy {
+    /// Run jobs concurrently.
+    #[default]
+    Allow,
+    /// Skip the new run if the previous one has not finished.
+    Forbid,
+    /// Terminate the running job and start the new one.
+    Replace,
+}
+
+fn default_successful_jobs_history_limit() -> u32 {
+    3
+}
+
+fn default_failed_jobs_history_limit() -> u32 {
+    1
+}
+
+/// A job that is created on a repeating cron schedule.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CronJob {
+    pub id: CronJobId,
+    pub name: String,
+    /// Five-field cron expression, evaluated in UTC.
+    pub schedule: String,
+    /// Workload template used for every job this cron job creates.
+    pub job_template: WorkloadDefinition,
+    #[serde(default)]
+    pub concurrency_policy: ConcurrencyPolicy,
+    /// A run that could not start within this many seconds of its scheduled
+    /// time is skipped. `None` means runs are never considered too late.
+    #[serde(default)]
+    pub starting_deadline_seconds: Option<u64>,
+    /// Number of successful jobs to keep.
+    #[serde(default = "default_successful_jobs_history_limit")]
+    pub successful_jobs_history_limit: u32,
+    /// Number of failed jobs to keep.
+    #[serde(default = "default_failed_jobs_history_limit")]
+    pub failed_jobs_history_limit: u32,
+    /// Suspended cron jobs do not create new jobs.
+    #[serde(default)]
+    pub suspend: bool,
+    pub created_at: DateTime<Utc>,
+    /// The most recent schedule slot a job was created for.
+    #[serde(default)]
+    pub last_schedule_time: Option<DateTime<Utc>>,
+}
+
+impl CronJob {
+    pub fn new(
+        name: impl Into<String>,
+        schedule: impl Into<String>,
+        job_template: WorkloadDefinition,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            schedule: schedule.into(),
+            job_template,
+            concurrency_policy: ConcurrencyPolicy::default(),
+            starting_deadline_seconds: None,
+            successful_jobs_history_limit: default_successful_jobs_history_limit(),
+            failed_jobs_history_limit: default_failed_jobs_history_limit(),
+            suspend: false,
+            created_at: Utc::now(),
+            last_schedule_time: None,
+        }
+    }
+
+    pub fn with_concurrency_policy(mut self, policy: ConcurrencyPolicy) -> Self {
+        self.concurrency_policy = policy;
+        self
+    }
+
+    pub fn with_starting_deadline_seconds(mut self, seconds: u64) -> Self {
+        self.starting_deadline_seconds = Some(seconds);
+        self
+    }
+
+    pub fn with_history_limits(mut self, successful: u32, failed: u32) -> Self {
+        self.successful_jobs_history_limit = successful;
+        self.failed_jobs_history_limit = failed;
+        self
+    }
+}
+
+impl Resource for CronJob {
+    const KIND: &'static str = "cronjobs";
+
+    fn key(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+struct CronJobEntry {
+    cron_job: CronJob,
+    schedule: CronSchedule,
+}
+
+/// Controller that creates jobs for registered cron jobs.
+pub struct CronJobController {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    workload_tx: mpsc::Sender<WorkloadDefinition>,
+    cron_jobs: RwLock<HashMap<CronJobId, CronJobEntry>>,
+    jobs: RwLock<HashMap<JobId, Job>>,
+}
+
+impl CronJobController {
+    /// How often the controller checks schedules when spawned with defaults.
+    pub const DEFAULT_TICK_INTERVAL: StdDuration = StdDuration::from_secs(10);
+
+    pub fn new(
+        state_store: Arc<dyn StateStore>,
+        runtime: Arc<dyn ContainerRuntime>,
+        workload_tx: mpsc::Sender<WorkloadDefinition>,
+    ) -> Self {
+        Self {
+            state_store,
+            runtime,
+            workload_tx,
+            cron_jobs: RwLock::new(HashMap::new()),
+            jobs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Register a cron job. Fails if its schedule cannot be parsed.
+    pub async fn add_cron_job(&self, cron_job: CronJob) -> Result<CronJobId> {
+        let schedule = CronSchedule::parse(&cron_job.schedule)
+            .map_err(|e| OrchestrationError::ConfigError(e.to_string()))?;
+        resources::put(self.state_store.as_ref(), &cron_job).await?;
+        let id = cron_job.id;
+        info!(
+            "Registered cron job {} ({}) with schedule '{}'",
+            cron_job.name, id, schedule
+        );
+        self.cron_jobs
+            .write()
+            .await
+            .insert(id, CronJobEntry { cron_job, schedule });
+        Ok(id)
+    }
+
+    /// Remove a cron job along with every job it created.
+    pub async fn remove_cron_job(&self, id: &CronJobId) -> Result<Option<CronJob>> {
+        let removed = self.cron_jobs.write().await.remove(id);
+        if removed.is_some() {
+            for job in self.jobs_for(id).await {
+                self.delete_job(&job).await?;
+            }
+            resources::delete::<CronJob>(self.state_store.as_ref(), &id.to_string()).await?;
+        }
+        Ok(removed.map(|entry| entry.cron_job))
+    }
+
+    /// Get a cron job as stored, which any node can answer.
+    pub async fn get_cron_job(&self, id: &CronJobId) -> Result<Option<CronJob>> {
+        resources::get(self.state_store.as_ref(), &id.to_string()).await
+    }
+
+    /// List the stored cron jobs, which any node can answer.
+    pub async fn list_cron_jobs(&self) -> Result<Vec<CronJob>> {
+        resources::list(self.state_store.as_ref()).await
+    }
+
+    /// Jobs created by the given cron job as stored, oldest first, which any
+    /// node can answer.
+    pub async fn list_jobs(&self, id: &CronJobId) -> Result<Vec<Job>> {
+        let mut jobs: Vec<Job> = resources::list::<Job>(self.state_store.as_ref())
+            .await?
+            .into_iter()
+            .filter(|job| job.owner == Some(*id))
+            .collect();
+        jobs.sort_by_key(|job| job.created_at);
+        Ok(jobs)
+    }
+
+    /// Replace the cron jobs and jobs held in memory with those in the state
+    /// store, as the controller does each time it starts.
+    pub async fn load(&self) -> Result<()> {
+        let stored: Vec<CronJob> = resources::list(self.state_store.as_ref()).await?;
+        let jobs: Vec<Job> = resources::list(self.state_store.as_ref()).await?;
+        let mut cron_jobs = HashMap::new();
+        for cron_job in stored {
+            match CronSchedule::parse(&cron_job.schedule) {
+                Ok(schedule) => {
+                    cron_jobs.insert(cron_job.id, CronJobEntry { cron_job, schedule });
+                }
+                Err(e) => error!("Skipping stored cron job {}: {}", cron_job.name, e),
+            }
+        }
+        info!(
+            "Loaded {} cron job(s) and {} job(s)",
+            cron_jobs.len(),
+            jobs.len()
+        );
+        *self.cron_jobs.write().await = cron_jobs;
+        *self.jobs.write().await = jobs.into_iter().map(|j| (j.id, j)).collect();
+        Ok(())
+    }
+
+    /// Suspend or resume a cron job.
+    pub async fn set_suspended(&self, id: &CronJobId, suspend: bool) -> Result<()> {
+        let mut cron_jobs = self.cron_jobs.write().await;
+        let entry = cron_jobs
+            .get_mut(id)
+            .ok_or_else(|| OrchestrationError::ConfigError(format!("Cron job {} not found", id)))?;
+        entry.cron_job.suspend = suspend;
+        resources::put(self.state_store.as_ref(), &entry.cron_job).await
+    }
+
+    /// Jobs created by the given cron job, oldest first.
+    pub async fn jobs_for(&self, id: &CronJobId) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|job| job.owner == Some(*id))
+            .cloned()
+            .collect();
+        jobs.sort_by_key(|job| job.created_at);
+        jobs
+    }
+
+    /// Load the stored cron jobs and then run the controller on a fixed
+    /// interval until the task is aborted.
+    pub fn spawn(self: Arc<Self>, tick_interval: StdDuration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tick_interval);
+            loop {
+                interval.tick().await;
+                // Until loaded, every slot since creation would look due
+                if let Err(e) = self.load().await {
+                    error!("Failed to load cron jobs: {:?}", e);
+                    continue;
+                }
+                break;
+            }
+            loop {
+                if let Err(e) = self.tick(Utc::now()).await {
+                    error!("Cron job controller tick failed: {:?}", e);
+                }
+                interval.tick().await;
+            }
+        })
+    }
+
+    /// Process every registered cron job as of `now`.
+    pub async fn tick(&self, now: DateTime<Utc>) -> Result<()> {
+        let ids: Vec<CronJobId> = self.cron_jobs.read().await.keys().copied().collect();
+        for id in ids {
+            if let Err(e) = self.sync_cron_job(&id, now).await {
+                error!("Failed to sync cron job {}: {:?}", id, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn sync_cron_job(&self, id: &CronJobId, now: DateTime<Utc>) -> Result<()> {
+        let (cron_job, schedule) = match self.cron_jobs.read().await.get(id) {
+            Some(entry) => (entry.cron_job.clone(), entry.schedule.clone()),
+            None => return Ok(()),
+        };
+
+        self.refresh_job_statuses(id, now).await?;
+
+        if cron_job.suspend {
+            debug!("Cron job {} is suspended", cron_job.name);
+        } else if let Some(scheduled_time) = self.due_slot(&cron_job, &schedule, now) {
+            self.start_scheduled_run(&cron_job, scheduled_time).await?;
+        }
+
+        self.prune_history(&cron_job).await
+    }
+
+    /// The most recent schedule slot that still needs a job, if any.
+    fn due_slot(
+        &self,
+        cron_job: &CronJob,
+        schedule: &CronSchedule,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let mut earliest = cron_job.last_schedule_time.unwrap_or(cron_job.created_at);
+        if let Some(deadline) = cron_job.starting_deadline_seconds {
+            // Slots older than the deadline are skipped outright
+            let cutoff = now - Duration::seconds(deadline as i64);
+            if cutoff > earliest {
+                earliest = cutoff;
+            }
+        }
+
+        let (latest, missed) = schedule.latest_between(earliest, now);
+        if missed >= MAX_MISSED_RUNS {
+            warn!(
+                "Cron job {} missed at least {} start times; only the most recent will run",
+                cron_job.name, missed
+            );
+        } else if missed > 1 {
+            warn!(
+                "Cron job {} missed {} start times; only the most recent will run",
+                cron_job.name, missed
+            );
+        }
+        latest
+    }
+
+    async fn start_scheduled_run(
+        &self,
+        cron_job: &CronJob,
+        scheduled_time: DateTime<Utc>,
+    ) -> Result<()> {
+        let active: Vec<Job> = self
+            .jobs_for(&cron_job.id)
+            .await
+            .into_iter()
+            .filter(|job| job.status == JobStatus::Active)
+            .collect();
+
+        if !active.is_empty() {
+            match cron_job.concurrency_policy {
+                ConcurrencyPolicy::Allow => {}
+                ConcurrencyPolicy::Forbid => {
+                    info!(
+                        "Cron job {} has {} active job(s); skipping run scheduled for {}",
+                        cron_job.name,
+                        active.len(),
+                        scheduled_time
+                    );
+                    return Ok(());
+                }
+                ConcurrencyPolicy::Replace => {
+                    for job in &active {
+                        info!(
+                            "Cron job {} replacing active job {}",
+                            cron_job.name, job.name
+                        );
+                        self.delete_job(job).await?;
+                    }
+                }
+            }
+        }
+
+        let mut job = Job::new(
+            format!("{}-{}", cron_job.name, scheduled_time.timestamp() / 60),
+            cron_job.job_template.clone(),
+        );
+        job.owner = Some(cron_job.id);
+        job.scheduled_time = Some(scheduled_time);
+        job.template
+            .labels
+            .insert(CRON_JOB_NAME_LABEL.to_string(), cron_job.name.clone());
+
+        self.workload_tx
+            .send(job.template.clone())
+            .await
+            .map_err(|e| {
+                OrchestrationError::InternalError(format!("Failed to submit job workload: {}", e))
+            })?;
+
+        info!(
+            "Cron job {} created job {} for {}",
+            cron_job.name, job.name, scheduled_time
+        );
+        resources::put(self.state_store.as_ref(), &job).await?;
+        self.jobs.write().await.insert(job.id, job);
+
+        if let Some(entry) = self.cron_jobs.write().await.get_mut(&cron_job.id) {
+            entry.cron_job.last_schedule_time = Some(scheduled_time);
+            resources::put(self.state_store.as_ref(), &entry.cron_job).await?;
+        }
+        Ok(())
+    }
+
+    async fn refresh_job_statuses(&self, id: &CronJobId, now: DateTime<Utc>) -> Result<()> {
+        for job in self.jobs_for(id).await {
+            if job.status.is_finished() {
+                continue;
+            }
+            let instances = self
+                .state_store
+                .list_instances_for_workload(&job.workload_id())
+                .await?;
+            let status = JobStatus::from_instances(job.template.replicas, &instances);
+            if status.is_finished() {
+                info!("Job {} finished with status {:?}", job.name, status);
+                if let Some(stored) = self.jobs.write().await.get_mut(&job.id) {
+                    stored.status = status;
+                    stored.completed_at = Some(now);
+                    resources::put(self.state_store.as_ref(), &*stored).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete finished jobs beyond the cron job's history limits, oldest first.
+    async fn prune_history(&self, cron_job: &CronJob) -> Result<()> {
+        let mut finished: Vec<Job> = self
+            .jobs_for(&cron_job.id)
+            .await
+            .into_iter()
+            .filter(|job| job.status.is_finished())
+            .collect();
+        // Newest first
+        finished.sort_by_key(|job| std::cmp::Reverse(job.completed_at.unwrap_or(job.created_at)));
+
+        let (succeeded, failed): (Vec<Job>, Vec<Job>) = finished
+            .into_iter()
+            .partition(|job| job.status == JobStatus::Succeeded);
+
+        let expired = succeeded
+            .into_iter()
+            .skip(cron_job.successful_jobs_history_limit as usize)
+            .chain(
+                failed
+                    .into_iter()
+                    .skip(cron_job.failed_jobs_history_limit as usize),
+            );
+
+        for job in expired {
+            debug!(
+                "Pruning job {} from history of cron job {}",
+                job.name, cron_job.name
+            );
+            self.delete_job(&job).await?;
+        }
+        Ok(())
+    }
+
+    /// Stop a job's containers and remove it and its workload from state.
+    async fn delete_job(&self, job: &Job) -> Result<()> {
+        let workload_id = job.workload_id();
+        let instances = self
+            .state_store
+            .list_instances_for_workload(&workload_id)
+            .await?;
+        for instance in &instances {
+            if let Err(e) = self.runtime.stop_instance(&instance.container_ids).await {
+                warn!("Failed to stop containers of job {}: {:?}", job.name, e);
+            }
+        }
+        self.state_store
+            .delete_instances_for_workload(&workload_id)
+            .await?;
+        self.state_store.delete_workload(&workload_id).await?;
+        resources::delete::<Job>(self.state_store.as_ref(), &job.id.to_string()).await?;
+        self.jobs.write().await.remove(&job.id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, StubRuntime};
+    use chrono::TimeZone;
+    use orchestrator_shared_types::{Keypair, WorkloadInstance, WorkloadInstanceStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, h, m, 0).unwrap()
+    }
+
+    fn cron_job(schedule: &str) -> CronJob {
+        let mut cron_job = CronJob::new("report", schedule, test_support::workload("report"));
+        cron_job.created_at = at(0, 0);
+        cron_job
+    }
+
+    struct Harness {
+        controller: CronJobController,
+        store: Arc<InMemoryStateStore>,
+        workload_rx: mpsc::Receiver<WorkloadDefinition>,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let store = Arc::new(InMemoryStateStore::new());
+            let (workload_tx, workload_rx) = mpsc::channel(16);
+            let controller = CronJobController::new(
+                store.clone(),
+                Arc::new(StubRuntime::default()),
+                workload_tx,
+            );
+            Self {
+                controller,
+                store,
+                workload_rx,
+            }
+        }
+
+        /// Mimic the orchestrator persisting submitted workloads.
+        async fn drain_submissions(&mut self) -> Vec<WorkloadDefinition> {
+            let mut submitted = Vec::new();
+            while let Ok(workload) = self.workload_rx.try_recv() {
+                self.store.put_workload(workload.clone()).await.unwrap();
+                submitted.push(workload);
+            }
+            submitted
+        }
+
+        async fn finish(&self, job: &Job, status: WorkloadInstanceStatus) {
+            self.store
+                .put_instance(WorkloadInstance {
+                    id: Uuid::new_v4(),
+                    workload_id: job.workload_id(),
+                    node_id: Keypair::generate().public_key(),
+                    container_ids: vec![],
+                    status,
+                    ip_addresses: vec![],
+                    restarts: vec![],
+                    namespace: "default".to_string(),
+                    resource_version: 0,
+                })
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_creates_job_when_schedule_fires() {
+        let mut h = Harness::new();
+        let id = h
+            .controller
+            .add_cron_job(cron_job("*/5 * * * *"))
+            .await
+            .unwrap();
+
+        h.controller.tick(at(0, 4)).await.unwrap();
+        assert!(h.drain_submissions().await.is_empty());
+
+        h.controller.tick(at(0, 5)).await.unwrap();
+        let submitted = h.drain_submissions().await;
+        assert_eq!(submitted.len(), 1);
+        assert_eq!(
+            submitted[0].labels.get(CRON_JOB_NAME_LABEL).unwrap(),
+            "report"
+        );
+
+        let jobs = h.controller.jobs_for(&id).await;
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].scheduled_time, Some(at(0, 5)));
+
+        // Same slot is not run twice
+        h.controller.tick(at(0, 6)).await.unwrap();
+        assert!(h.drain_submissions().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_missed_runs_only_start_latest() {
+        let mut h = Harness::new();
+        let id = h
+            .controller
+            .add_cron_job(cron_job("*/5 * * * *"))
+            .await
+            .unwrap();
+
+        h.controller.tick(at(0, 22)).await.unwrap();
+        assert_eq!(h.drain_submissions().await.len(), 1);
+        let jobs = h.controller.jobs_for(&id).await;
+        assert_eq!(jobs[0].scheduled_time, Some(at(0, 20)));
+    }
+
+    #[tokio::test]
+    async fn test_forbid_skips_while_active() {
+        let mut h = Harness::new();
+        let id = h
+            .controller
+            .add_cron_job(
+                cron_job("*/5 * * * *").with_concurrency_policy(ConcurrencyPolicy::Forbid),
+            )
+            .await
+            .unwrap();
+
+        h.controller.tick(at(0, 5)).await.unwrap();
+        h.drain_submissions().await;
+        h.controller.tick(at(0, 10)).await.unwrap();
+        assert!(h.drain_submissions().await.is_empty());
+        assert_eq!(h.controller.jobs_for(&id).await.len(), 1);
+
+        // Once the job finishes the pending slot runs
+        let job = h.controller.jobs_for(&id).await.remove(0);
+        h.finish(&job, WorkloadInstanceStatus::Succeeded).await;
+        h.controller.tick(at(0, 11)).await.unwrap();
+        assert_eq!(h.drain_submissions().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replace_terminates_active_job() {
+        let mut h = Harness::new();
+        let id = h
+            .controller
+            .add_cron_job(
+                cron_job("*/5 * * * *").with_concurrency_policy(ConcurrencyPolicy::Replace),
+            )
+            .await
+            .unwrap();
+
+        h.controller.tick(at(0, 5)).await.unwrap();
+        h.drain_submissions().await;
+        let first = h.controller.jobs_for(&id).await.remove(0);
+
+        h.controller.tick(at(0, 10)).await.unwrap();
+        assert_eq!(h.drain_submissions().await.len(), 1);
+
+        let jobs = h.controller.jobs_for(&id).await;
+        assert_eq!(jobs.len(), 1);
+        assert_ne!(jobs[0].id, first.id);
+        assert!(h
+            .store
+            .get_workload(&first.workload_id())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_starting_deadline_skips_late_runs() {
+        let mut h = Harness::new();
+        let id = h
+            .controller
+            .add_cron_job(cron_job("0 * * * *").with_starting_deadline_seconds(60))
+            .await
+            .unwrap();
+
+        // The 01:00 slot is 3 minutes old, past the 60s deadline
+        h.controller.tick(at(1, 3)).await.unwrap();
+        assert!(h.drain_submissions().await.is_empty());
+        assert!(h.controller.jobs_for(&id).await.is_empty());
+
+        h.controller.tick(at(2, 0)).await.unwrap();
+        assert_eq!(h.drain_submissions().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_history_limits_prune_finished_jobs() {
+        let mut h = Harness::new();
+        let id = h
+            .controller
+            .add_cron_job(cron_job("* * * * *").with_history_limits(2, 1))
+            .await
+            .unwrap();
+
+        for minute in 1..=4 {
+            h.controller.tick(at(0, minute)).await.unwrap();
+            h.drain_submissions().await;
+            let job = h.controller.jobs_for(&id).await.pop().unwrap();
+            h.finish(&job, WorkloadInstanceStatus::Succeeded).await;
+        }
+        for minute in 5..=6 {
+            h.controller.tick(at(0, minute)).await.unwrap();
+            h.drain_submissions().await;
+            let job = h.controller.jobs_for(&id).await.pop().unwrap();
+            h.finish(&job, WorkloadInstanceStatus::Failed).await;
+        }
+        // Next tick picks up the last failure and prunes
+        h.controller.tick(at(0, 6)).await.unwrap();
+
+        let jobs = h.controller.jobs_for(&id).await;
+        let succeeded = jobs
+            .iter()
+            .filter(|j| j.status == JobStatus::Succeeded)
+            .count();
+        let failed = jobs
+            .iter()
+            .filter(|j| j.status == JobStatus::Failed)
+            .count();
+        assert_eq!(succeeded, 2);
+        assert_eq!(failed, 1);
+        assert_eq!(h.store.list_workloads().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_suspended_cron_job_does_not_run() {
+        let mut h = Harness::new();
+        let id = h
+            .controller
+            .add_cron_job(cron_job("* * * * *"))
+            .await
+            .unwrap();
+        h.controller.set_suspended(&id, true).await.unwrap();
+
+        h.controller.tick(at(0, 5)).await.unwrap();
+        assert!(h.drain_submissions().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_new_leader_resumes_from_stored_state() {
+        let mut h = Harness::new();
+        let id = h
+            .controller
+            .add_cron_job(cron_job("*/5 * * * *"))
+            .await
+            .unwrap();
+        h.controller.tick(at(0, 5)).await.unwrap();
+        let job = h.controller.jobs_for(&id).await.remove(0);
+        h.finish(&job, WorkloadInstanceStatus::Succeeded).await;
+        h.controller.tick(at(0, 6)).await.unwrap();
+        assert_eq!(h.drain_submissions().await.len(), 1);
+
+        let (workload_tx, mut workload_rx) = mpsc::channel(16);
+        let successor = CronJobController::new(
+            h.store.clone(),
+            Arc::new(StubRuntime::default()),
+            workload_tx,
+        );
+        successor.load().await.unwrap();
+        let jobs = successor.jobs_for(&id).await;
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].status, JobStatus::Succeeded);
+        assert_eq!(successor.list_jobs(&id).await.unwrap(), jobs);
+
+        // The slot the last leader ran is not run again
+        successor.tick(at(0, 7)).await.unwrap();
+        assert!(workload_rx.try_recv().is_err());
+        successor.tick(at(0, 10)).await.unwrap();
+        assert!(workload_rx.try_recv().is_ok());
+
+        successor.remove_cron_job(&id).await.unwrap();
+        assert!(successor.list_cron_jobs().await.unwrap().is_empty());
+        assert!(successor.list_jobs(&id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_schedule_rejected() {
+        let h = Harness::new();
+        let result = h.controller.add_cron_job(cron_job("not a schedule")).await;
+        assert!(matches!(result, Err(OrchestrationError::ConfigError(_))));
+    }
+}
@@ -0,0 +1,183 @@
+//! Run-to-completion workloads.
+//!
+//! A [`Job`] wraps a [`WorkloadDefinition`] template whose instances are
+//! expected to terminate. The job is complete once every replica has reached
+//! `Succeeded`, and failed as soon as one replica reaches `Failed`.
+//!
+//! [`CronJob`](cronjob::CronJob)s create jobs on a cron schedule; see the
+//! [`cronjob`] module for the controller that drives them.
+
+pub mod cron;
+pub mod cronjob;
+
+pub use cron::{CronParseError, CronSchedule};
+pub use cronjob::{ConcurrencyPolicy, CronJob, CronJobController, CronJobId};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use orchestrator_shared_types::{
+    RestartMode, WorkloadDefinition, WorkloadInstance, WorkloadInstanceStatus,
+};
+
+use crate::resources::Resource;
+
+/// Label set on every workload created for a job, holding the job name.
+pub const JOB_NAME_LABEL: &str = "job-name";
+
+/// Label set on workloads created by a cron job, holding the cron job name.
+pub const CRON_JOB_NAME_LABEL: &str = "cronjob-name";
+
+pub type JobId = Uuid;
+
+/// Lifecycle state of a job.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum JobStatus {
+    /// At least one replica has not yet finished.
+    Active,
+    /// Every replica exited successfully.
+    Succeeded,
+    /// At least one replica failed.
+    Failed,
+}
+
+impl JobStatus {
+    /// Whether the job has reached a terminal state.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed)
+    }
+
+    /// Derive a job status from the instances of its workload.
+    pub fn from_instances(replicas: u32, instances: &[WorkloadInstance]) -> Self {
+        if instances
+            .iter()
+            .any(|i| i.status == WorkloadInstanceStatus::Failed)
+        {
+            return JobStatus::Failed;
+        }
+        let succeeded = instances
+            .iter()
+            .filter(|i| i.status == WorkloadInstanceStatus::Succeeded)
+            .count() as u32;
+        if succeeded >= replicas.max(1) {
+            JobStatus::Succeeded
+        } else {
+            JobStatus::Active
+        }
+    }
+}
+
+/// A run-to-completion workload.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Job {
+    pub id: JobId,
+    pub name: String,
+    /// The workload this job runs. Its `id` is unique to the job.
+    pub template: WorkloadDefinition,
+    /// The cron job that created this job, if any.
+    pub owner: Option<Uuid>,
+    /// The schedule slot this job was created for (cron jobs only).
+    pub scheduled_time: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub status: JobStatus,
+}
+
+impl Job {
+    /// Create a new active job from a workload template.
+    ///
+    /// The template is given a fresh workload id and labelled with the job
+    /// name so the reconciler treats its finished instances as complete. A
+    /// template that restarts `Always` is switched to `OnFailure`, since job
+    /// containers are meant to exit.
+    pub fn new(name: impl Into<String>, mut template: WorkloadDefinition) -> Self {
+        let name = name.into();
+        template.id = Uuid::new_v4();
+        template.name = name.clone();
+        template
+            .labels
+            .insert(JOB_NAME_LABEL.to_string(), name.clone());
+        if template.restart_policy.mode == RestartMode::Always {
+            template.restart_policy.mode = RestartMode::OnFailure;
+        }
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            template,
+            owner: None,
+            scheduled_time: None,
+            created_at: Utc::now(),
+            completed_at: None,
+            status: JobStatus::Active,
+        }
+    }
+
+    /// The id of the workload backing this job.
+    pub fn workload_id(&self) -> Uuid {
+        self.template.id
+    }
+}
+
+impl Resource for Job {
+    const KIND: &'static str = "jobs";
+
+    fn key(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+/// Whether a workload definition belongs to a job.
+pub fn is_job_workload(workload: &WorkloadDefinition) -> bool {
+    workload.labels.contains_key(JOB_NAME_LABEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn instance(status: WorkloadInstanceStatus) -> WorkloadInstance {
+        WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: Uuid::new_v4(),
+            node_id: orchestrator_shared_types::Keypair::generate().public_key(),
+            container_ids: vec![],
+            status,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        }
+    }
+
+    #[test]
+    fn test_job_status_from_instances() {
+        use WorkloadInstanceStatus::*;
+        assert_eq!(JobStatus::from_instances(2, &[]), JobStatus::Active);
+        assert_eq!(
+            JobStatus::from_instances(2, &[instance(Succeeded), instance(Running)]),
+            JobStatus::Active
+        );
+        assert_eq!(
+            JobStatus::from_instances(2, &[instance(Succeeded), instance(Succeeded)]),
+            JobStatus::Succeeded
+        );
+        assert_eq!(
+            JobStatus::from_instances(2, &[instance(Succeeded), instance(Failed)]),
+            JobStatus::Failed
+        );
+    }
+
+    #[test]
+    fn test_new_job_labels_template() {
+        let template = test_support::workload("backup");
+        let original_id = template.id;
+        let job = Job::new("backup-123", template);
+        assert_ne!(job.workload_id(), original_id);
+        assert_eq!(job.template.name, "backup-123");
+        assert!(is_job_workload(&job.template));
+        assert_eq!(job.template.restart_policy.mode, RestartMode::OnFailure);
+        assert_eq!(job.status, JobStatus::Active);
+    }
+}
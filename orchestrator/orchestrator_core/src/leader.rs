@@ -0,0 +1,264 @@
+//! Leader election between control-plane instances.
+//!
+//! Instances sharing a state store race for one [`LeaderLease`]. The holder
+//! renews it well within its duration; if it stops (crash, partition, or lost
+//! store connection) another candidate takes over once the lease runs out.
+//!
+//! Only the leader runs controllers. The orchestrator skips reconciliation on
+//! followers and reconciles everything when elected and on every resync, and
+//! [`LeaderElector::spawn_while_leader`] starts and stops periodic controllers
+//! as leadership comes and goes. Writes served by a follower's API land in
+//! the shared store and are picked up by the leader's next resync.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use orchestrator_shared_types::{LeaderLease, Result};
+use state_store_interface::StateStore;
+
+/// Name of the lease control-plane instances compete for.
+pub const LEADER_LEASE_NAME: &str = "control-plane";
+
+/// How long leadership survives without a renewal.
+pub const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(15);
+
+/// How often the leader reconciles every workload, picking up writes made
+/// through followers.
+pub const DEFAULT_RESYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Campaigns for leadership and tracks whether this instance holds it.
+pub struct LeaderElector {
+    state_store: Arc<dyn StateStore>,
+    identity: String,
+    lease_duration: Duration,
+    is_leader: watch::Sender<bool>,
+    /// Expiry of the last lease this instance renewed, in Unix milliseconds.
+    held_until_ms: AtomicU64,
+}
+
+impl LeaderElector {
+    /// How often to campaign: a third of the default lease duration, so two
+    /// renewals can fail before the lease lapses.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(state_store: Arc<dyn StateStore>, identity: impl Into<String>) -> Self {
+        Self {
+            state_store,
+            identity: identity.into(),
+            lease_duration: DEFAULT_LEASE_DURATION,
+            is_leader: watch::channel(false).0,
+            held_until_ms: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_lease_duration(mut self, duration: Duration) -> Self {
+        self.lease_duration = duration;
+        self
+    }
+
+    /// Identity this instance campaigns under.
+    pub fn identity(&self) -> &str {
+        &self.identity
+    }
+
+    pub fn is_leader(&self) -> bool {
+        *self.is_leader.borrow()
+    }
+
+    /// Watch leadership changes of this instance.
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.is_leader.subscribe()
+    }
+
+    /// The current leader's lease, if any instance holds one.
+    pub async fn leader(&self) -> Result<Option<LeaderLease>> {
+        let lease = self.state_store.get_lease(LEADER_LEASE_NAME).await?;
+        Ok(lease.filter(|lease| lease.is_held_at(unix_millis())))
+    }
+
+    /// Acquire or renew the lease; returns whether this instance leads.
+    pub async fn campaign(&self) -> Result<bool> {
+        self.campaign_at(unix_millis()).await
+    }
+
+    async fn campaign_at(&self, now_ms: u64) -> Result<bool> {
+        let lease = match self
+            .state_store
+            .try_acquire_lease(
+                LEADER_LEASE_NAME,
+                &self.identity,
+                now_ms,
+                self.lease_duration,
+            )
+            .await
+        {
+            Ok(lease) => lease,
+            Err(e) => {
+                // Others may take over once our last renewal expires, so stop
+                // leading by then even though we cannot reach the store
+                if now_ms >= self.held_until_ms.load(Ordering::Relaxed) {
+                    self.set_leader(false, None);
+                }
+                return Err(e);
+            }
+        };
+
+        let leading = lease.holder == self.identity;
+        if leading {
+            self.held_until_ms
+                .store(lease.expires_at_ms, Ordering::Relaxed);
+        }
+        self.set_leader(leading, Some(&lease));
+        Ok(leading)
+    }
+
+    /// Give up leadership so another candidate can take over at once.
+    pub async fn resign(&self) -> Result<()> {
+        self.set_leader(false, None);
+        self.held_until_ms.store(0, Ordering::Relaxed);
+        self.state_store
+            .release_lease(LEADER_LEASE_NAME, &self.identity)
+            .await
+    }
+
+    fn set_leader(&self, leading: bool, lease: Option<&LeaderLease>) {
+        if self.is_leader() == leading {
+            return;
+        }
+        match (leading, lease) {
+            (true, Some(lease)) => info!("Elected leader for term {}", lease.term),
+            (false, Some(lease)) => warn!("Lost leadership to {}", lease.holder),
+            _ => warn!("Stepped down as leader"),
+        }
+        self.is_leader.send_replace(leading);
+    }
+
+    /// Campaign every `interval` until the task is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.campaign().await {
+                    error!("Leader election failed: {:?}", e);
+                }
+            }
+        })
+    }
+
+    /// Call `start` each time this instance is elected, and abort the tasks
+    /// it returned when leadership is lost.
+    pub fn spawn_while_leader<F>(&self, mut start: F) -> JoinHandle<()>
+    where
+        F: FnMut() -> Vec<JoinHandle<()>> + Send + 'static,
+    {
+        let mut leading = self.subscribe();
+        tokio::spawn(async move {
+            let mut tasks: Option<Vec<JoinHandle<()>>> = None;
+            loop {
+                let is_leader = *leading.borrow_and_update();
+                if is_leader && tasks.is_none() {
+                    tasks = Some(start());
+                } else if !is_leader {
+                    for task in tasks.take().into_iter().flatten() {
+                        task.abort();
+                    }
+                }
+                if leading.changed().await.is_err() {
+                    break;
+                }
+            }
+            for task in tasks.into_iter().flatten() {
+                task.abort();
+            }
+        })
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    fn candidates() -> (LeaderElector, LeaderElector) {
+        let store: Arc<dyn StateStore> = Arc::new(InMemoryStateStore::new());
+        let duration = Duration::from_secs(15);
+        (
+            LeaderElector::new(store.clone(), "a").with_lease_duration(duration),
+            LeaderElector::new(store, "b").with_lease_duration(duration),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_single_leader_and_failover() {
+        let (a, b) = candidates();
+        let start = 1_000_000;
+
+        assert!(a.campaign_at(start).await.unwrap());
+        assert!(!b.campaign_at(start + 1_000).await.unwrap());
+        assert!(a.campaign_at(start + 5_000).await.unwrap());
+
+        // a stops renewing; b takes over once the lease expires
+        assert!(!b.campaign_at(start + 19_999).await.unwrap());
+        assert!(b.campaign_at(start + 20_000).await.unwrap());
+        assert!(b.is_leader());
+
+        // a learns it lost on its next attempt
+        let a_leading = a.subscribe();
+        assert!(!a.campaign_at(start + 21_000).await.unwrap());
+        assert!(a_leading.has_changed().unwrap());
+        assert!(!a.is_leader());
+    }
+
+    #[tokio::test]
+    async fn test_resign_hands_over_immediately() {
+        let (a, b) = candidates();
+
+        assert!(a.campaign().await.unwrap());
+        assert_eq!(a.leader().await.unwrap().unwrap().holder, "a");
+
+        a.resign().await.unwrap();
+        assert!(!a.is_leader());
+        assert!(a.leader().await.unwrap().is_none());
+        assert!(b.campaign().await.unwrap());
+        assert_eq!(b.leader().await.unwrap().unwrap().term, 2);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_while_leader_starts_and_stops_tasks() {
+        let (a, _) = candidates();
+        let (started_tx, mut started_rx) = tokio::sync::mpsc::unbounded_channel();
+        let supervisor = a.spawn_while_leader(move || {
+            // The task drops its sender when aborted
+            let (stopped_tx, stopped_rx) = tokio::sync::oneshot::channel::<()>();
+            started_tx.send(stopped_rx).unwrap();
+            vec![tokio::spawn(async move {
+                let _stopped_tx = stopped_tx;
+                std::future::pending::<()>().await
+            })]
+        });
+
+        a.campaign().await.unwrap();
+        let stopped = started_rx.recv().await.unwrap();
+
+        a.resign().await.unwrap();
+        assert!(stopped.await.is_err());
+
+        a.campaign().await.unwrap();
+        assert!(started_rx.recv().await.is_some());
+
+        supervisor.abort();
+    }
+}
@@ -1,21 +1,64 @@
+pub mod accounting;
+pub mod admission;
+pub mod anomaly;
 #[cfg(feature = "rest-api")]
 pub mod api;
-
+pub mod autoscaling;
+pub mod backup;
+pub mod capacity;
+pub mod container_events;
+
+pub mod controllers;
+pub mod disruption;
+pub mod events;
+pub mod fsck;
+pub mod gc;
+pub mod heartbeat;
+pub mod jobs;
+pub mod leader;
+#[cfg(feature = "log-forwarding")]
+pub mod log_forwarder;
+pub mod migration;
+pub mod network;
+pub mod node_lifecycle;
+pub mod prepull;
 pub mod reconciliation;
-
+#[cfg(feature = "registry-cache")]
+pub mod registry_cache;
+pub mod replay;
+pub mod resources;
+pub mod restart;
+pub mod rollout;
+pub mod scheduling;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod usage;
+
+use std::collections::HashSet;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::time::Instant;
+use tokio::sync::{mpsc, watch};
+use tokio::time::Interval;
 use uuid;
 
 use orchestrator_shared_types::{
-    Node, Result, WorkloadDefinition, WorkloadInstance,
+    Node, NodeId, Result, WorkloadDefinition, WorkloadInstance,
     WorkloadInstanceStatus,
 };
-use container_runtime_interface::ContainerRuntime;
+use container_runtime_interface::{ContainerRuntime, PullPriority};
 use cluster_manager_interface::{ClusterEvent, ClusterManager};
 use scheduler_interface::{ScheduleDecision, ScheduleRequest, Scheduler};
 use state_store_interface::StateStore;
-use tracing::{error, info, warn, trace};
+use tracing::{error, info, warn, trace, Instrument};
+
+/// The `traceparent` header value of the current span, to pass on to outgoing
+/// calls. Only set when traces are exported.
+pub fn current_traceparent() -> Option<String> {
+    #[cfg(feature = "observability")]
+    return observability::current_traceparent();
+    #[cfg(not(feature = "observability"))]
+    None
+}
 
 pub struct Orchestrator {
     state_store: Arc<dyn StateStore>,
@@ -25,6 +68,22 @@ pub struct Orchestrator {
     // Channel for submitting new workloads or updates
     workload_tx: mpsc::Sender<WorkloadDefinition>,
     workload_rx: mpsc::Receiver<WorkloadDefinition>,
+    // Told about instances the moment they start terminating
+    service_proxy: Option<Arc<network::ServiceProxy>>,
+    // Gives new instances their addresses; None leaves them to the runtime
+    instance_addresses: Option<Arc<network::InstanceAddressAllocator>>,
+    // Reconciles only while this instance leads; None means always
+    leader: Option<Arc<leader::LeaderElector>>,
+    // Records why instances are or are not scheduled
+    events: events::EventRecorder,
+    // Told about scheduling latency and replica drift
+    observer: Option<Arc<dyn scheduling::SchedulingObserver>>,
+    // Workloads short of replicas, for time-to-schedule
+    pending: scheduling::PendingTracker,
+    // Replicas each workload is missing or has in excess
+    drift: scheduling::DriftTracker,
+    // Budgets preemption evicts through; None evicts unconditionally
+    disruption: Option<Arc<disruption::DisruptionController>>,
 }
 
 impl Orchestrator {
@@ -36,15 +95,68 @@ impl Orchestrator {
     ) -> Self {
         let (workload_tx, workload_rx) = mpsc::channel(100); // Buffer size 100
         Orchestrator {
+            events: events::EventRecorder::new(state_store.clone(), "scheduler"),
             state_store,
             runtime,
             cluster_manager,
             scheduler,
             workload_tx,
             workload_rx,
+            service_proxy: None,
+            instance_addresses: None,
+            leader: None,
+            observer: None,
+            pending: scheduling::PendingTracker::new(),
+            drift: scheduling::DriftTracker::new(),
+            disruption: None,
         }
     }
 
+    /// Pull terminating instances out of service endpoints before their
+    /// containers are stopped.
+    pub fn with_service_proxy(mut self, proxy: Arc<network::ServiceProxy>) -> Self {
+        self.service_proxy = Some(proxy);
+        self
+    }
+
+    /// Assign each new instance addresses from `allocator`, passed to its
+    /// containers as the `ip_addresses` annotation.
+    pub fn with_instance_addresses(
+        mut self,
+        allocator: Arc<network::InstanceAddressAllocator>,
+    ) -> Self {
+        self.instance_addresses = Some(allocator);
+        self
+    }
+
+    /// Reconcile only while `elector` holds leadership, for control planes
+    /// running several instances against one state store.
+    pub fn with_leader_elector(mut self, elector: Arc<leader::LeaderElector>) -> Self {
+        self.leader = Some(elector);
+        self
+    }
+
+    /// Report scheduling latency, placement attempts and replica drift to
+    /// `observer`.
+    pub fn with_observer(mut self, observer: Arc<dyn scheduling::SchedulingObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Preempt lower-priority instances only where `controller`'s disruption
+    /// budgets allow.
+    pub fn with_disruption_budgets(
+        mut self,
+        disruption: Arc<disruption::DisruptionController>,
+    ) -> Self {
+        self.disruption = Some(disruption);
+        self
+    }
+
+    fn is_leader(&self) -> bool {
+        self.leader.as_ref().is_none_or(|elector| elector.is_leader())
+    }
+
     pub fn get_workload_sender(&self) -> mpsc::Sender<WorkloadDefinition> {
         self.workload_tx.clone()
     }
@@ -57,6 +169,14 @@ impl Orchestrator {
         
         info!("Cluster manager initialized and subscribed to events.");
 
+        // Workloads written through followers reach the leader only via the
+        // shared store, so the leader also resyncs periodically
+        let mut leader_rx = self.leader.as_ref().map(|elector| elector.subscribe());
+        let mut resync = self
+            .leader
+            .as_ref()
+            .map(|_| tokio::time::interval(leader::DEFAULT_RESYNC_INTERVAL));
+
         loop {
             tokio::select! {
                 // Listen for new/updated workload definitions
@@ -94,6 +214,25 @@ impl Orchestrator {
                         trace!("Cluster event Channerl updated to None or was initialized to None");
                     }
                 }
+                Some(leading) = leadership_changed(&mut leader_rx) => {
+                    if leading {
+                        info!("Elected leader; reconciling all workloads.");
+                        if let Err(e) = self.reconcile_all_workloads().await {
+                            error!("Failed during reconciliation after election: {:?}", e);
+                        }
+                    } else {
+                        // Drift is reported by the leader only
+                        self.drift.retain(&HashSet::new());
+                        self.report_drift();
+                    }
+                }
+                Some(()) = tick(&mut resync) => {
+                    if self.is_leader() {
+                        if let Err(e) = self.reconcile_all_workloads().await {
+                            error!("Failed during leader resync: {:?}", e);
+                        }
+                    }
+                }
                 // TODO: Periodic reconciliation loop (e.g., every 30 seconds)
                 // This would ensure desired state matches actual state.
                 // _ = tokio::time::sleep(tokio::time::Duration::from_secs(30)) => {
@@ -112,19 +251,41 @@ impl Orchestrator {
         Ok(())
     }
 
+    /// Store a workload and reconcile it, as the run loop does for each
+    /// submitted definition.
+    pub async fn apply_workload(&self, workload_def: WorkloadDefinition) -> Result<()> {
+        self.handle_workload_update(workload_def).await
+    }
+
+    /// Apply a cluster event and reconcile every workload, as the run loop
+    /// does for each event it receives.
+    pub async fn apply_cluster_event(&self, event: ClusterEvent) -> Result<()> {
+        self.handle_cluster_event(event).await?;
+        self.reconcile_all_workloads().await
+    }
+
     async fn handle_workload_update(&self, workload_def: WorkloadDefinition) -> Result<()> {
         let workload_id = workload_def.id;
 
-        // Store workload in persistent state
-        self.state_store.put_workload(workload_def.clone()).await?;
+        // Store workload in persistent state, unless it arrives as already
+        // stored: writing it again would bump the version clients hold
+        let stored = self.state_store.get_workload(&workload_id).await?;
+        if stored.as_ref() != Some(&workload_def) {
+            self.state_store.put_workload(workload_def.clone()).await?;
+        }
 
         info!("Workload {} registered. Triggering reconciliation.", workload_id);
-        self.reconcile_workload(&Arc::new(workload_def)).await
+        let started = Instant::now();
+        let result = self.reconcile_workload(&Arc::new(workload_def)).await;
+        if let Some(observer) = &self.observer {
+            observer.on_reconciled(started.elapsed());
+        }
+        result
     }
     
     async fn handle_cluster_event(&self, event: ClusterEvent) -> Result<()> {
         match event {
-            ClusterEvent::NodeAdded(node) | ClusterEvent::NodeUpdated(node) => {
+            ClusterEvent::NodeAdded(mut node) | ClusterEvent::NodeUpdated(mut node) => {
                 info!("Node {} added/updated.", node.id);
 
                 // Check if node already exists
@@ -138,6 +299,11 @@ impl Orchestrator {
                      }
                 }
 
+                // Cordoning is recorded here only; the cluster manager does not know about it
+                if let Some(existing) = &existing {
+                    node.unschedulable = existing.unschedulable;
+                }
+
                 // Store node in persistent state
                 self.state_store.put_node(node).await?;
             }
@@ -147,7 +313,8 @@ impl Orchestrator {
                 // Remove node from persistent state
                 self.state_store.delete_node(&node_id).await?;
 
-                // TODO: Handle workloads running on the removed node (reschedule them)
+                // Instances still assigned to it are rescheduled by the
+                // node lifecycle controller once their grace period passes
             }
         }
         Ok(())
@@ -155,9 +322,14 @@ impl Orchestrator {
 
     async fn reconcile_all_workloads(&self) -> Result<()> {
         info!("Reconciling all workloads...");
+        let started = Instant::now();
 
         // Get all workloads from persistent state
         let workloads = self.state_store.list_workloads().await?;
+        let workload_ids: HashSet<_> = workloads.iter().map(|workload| workload.id).collect();
+        self.pending.retain(&workload_ids);
+        self.drift.retain(&workload_ids);
+        self.report_drift();
 
         for workload_def in workloads {
             if let Err(e) = self.reconcile_workload(&Arc::new(workload_def.clone())).await {
@@ -165,14 +337,26 @@ impl Orchestrator {
                 // Continue to next workload
             }
         }
+        if let Some(observer) = &self.observer {
+            observer.on_reconciled(started.elapsed());
+        }
         info!("Finished reconciling all workloads.");
         Ok(())
     }
 
 
     async fn reconcile_workload(&self, workload_def: &Arc<WorkloadDefinition>) -> Result<()> {
+        if !self.is_leader() {
+            trace!("Not the leader; leaving workload {} to the leader.", workload_def.id);
+            return Ok(());
+        }
         info!("Reconciling workload: {} ({})", workload_def.name, workload_def.id);
 
+        if controllers::is_controller_managed(workload_def) {
+            trace!("Workload {} is placed by its controller; skipping.", workload_def.id);
+            return Ok(());
+        }
+
         // Phase 1: Determine what needs to be done (read state, decide action)
         // Get current instances from persistent state
         let current_instances = self.state_store
@@ -180,10 +364,19 @@ impl Orchestrator {
             .await?;
 
         let desired_replicas = workload_def.replicas;
+        let is_job = jobs::is_job_workload(workload_def);
         let current_active_replicas = current_instances
             .iter()
             .filter(|inst| {
-                matches!(inst.status, WorkloadInstanceStatus::Running | WorkloadInstanceStatus::Pending)
+                // Crash-looping replicas are restarted in place, not replaced
+                matches!(
+                    inst.status,
+                    WorkloadInstanceStatus::Running
+                        | WorkloadInstanceStatus::Pending
+                        | WorkloadInstanceStatus::CrashLoopBackOff
+                )
+                    // Replicas of a job that ran to completion must not be restarted
+                    || (is_job && inst.status == WorkloadInstanceStatus::Succeeded)
             })
             .count() as u32;
 
@@ -191,6 +384,20 @@ impl Orchestrator {
             "Workload {}: Desired replicas: {}, Current active (running/pending): {}",
             workload_def.id, desired_replicas, current_active_replicas
         );
+        if current_active_replicas >= desired_replicas {
+            self.pending.satisfied(workload_def.id);
+        }
+        let drift = if current_active_replicas < desired_replicas {
+            let missing = desired_replicas - current_active_replicas;
+            Some((scheduling::DriftKind::MissingReplicas, missing))
+        } else if current_active_replicas > desired_replicas {
+            let excess = current_active_replicas - desired_replicas;
+            Some((scheduling::DriftKind::ExcessReplicas, excess))
+        } else {
+            None
+        };
+        self.drift.record(workload_def.id, drift);
+        self.report_drift();
 
         let action = if current_active_replicas < desired_replicas {
             let num_to_schedule = desired_replicas - current_active_replicas;
@@ -199,14 +406,22 @@ impl Orchestrator {
             let all_nodes = self.state_store.list_nodes().await?;
             let available_nodes_for_scheduling: Vec<Node> = all_nodes
                 .into_iter()
-                .filter(|n| n.status == orchestrator_shared_types::NodeStatus::Ready)
+                .filter(Node::is_schedulable)
                 .collect();
 
             if available_nodes_for_scheduling.is_empty() {
                 warn!(
-                    "No ready nodes available to schedule {} new instances for workload {}",
+                    "No schedulable nodes available to schedule {} new instances for workload {}",
                     num_to_schedule, workload_def.id
                 );
+                self.pending.attempt(workload_def.id);
+                if let Some(observer) = &self.observer {
+                    observer.on_failed("no_nodes");
+                }
+                let message = format!("0 nodes are available for {} replicas", num_to_schedule);
+                self.events
+                    .workload_warning(workload_def, "FailedScheduling", message)
+                    .await;
                 WorkloadAction::None
             } else {
                 WorkloadAction::ScheduleNew {
@@ -222,7 +437,12 @@ impl Orchestrator {
             let instances_to_remove = current_instances
                 .iter()
                 .filter(|inst| {
-                    matches!(inst.status, WorkloadInstanceStatus::Running | WorkloadInstanceStatus::Pending)
+                    matches!(
+                        inst.status,
+                        WorkloadInstanceStatus::Running
+                            | WorkloadInstanceStatus::Pending
+                            | WorkloadInstanceStatus::CrashLoopBackOff
+                    )
                 })
                 .take(num_to_remove as usize)
                 .cloned()
@@ -253,42 +473,145 @@ impl Orchestrator {
                     current_instances: current_instances_state, // Use the owned clone
                 };
 
+                self.pending.attempt(workload_def.id);
+                let started = Instant::now();
                 let decisions = self
                     .scheduler
                     .schedule(&schedule_request, &available_nodes)
-                    .await?;
+                    .instrument(tracing::info_span!("schedule", workload = %workload_def.name))
+                    .await;
+                if let Some(observer) = &self.observer {
+                    observer.on_attempt(started.elapsed());
+                }
+                let decisions = decisions?;
 
+                let mut placed = 0;
                 for decision in decisions.into_iter().take(num_to_schedule as usize) {
+                    let decision = match decision {
+                        ScheduleDecision::NoPlacement(reason) => {
+                            match self.preempt(workload_def, &available_nodes).await {
+                                Ok(Some(node_id)) => ScheduleDecision::AssignNode(node_id),
+                                Ok(None) => ScheduleDecision::NoPlacement(reason),
+                                Err(e) => {
+                                    warn!(
+                                        "Failed to preempt for workload {}: {:?}",
+                                        workload_def.id, e
+                                    );
+                                    ScheduleDecision::NoPlacement(reason)
+                                }
+                            }
+                        }
+                        decision => decision,
+                    };
                     match decision {
                         ScheduleDecision::AssignNode(node_id) => {
                             info!(
                                 "Scheduler assigned workload {} instance to node {}",
                                 workload_def.id, node_id
                             );
-                            if let Some(container_config) = workload_def.containers.first() {
+                            if !workload_def.containers.is_empty() {
                                 let options = container_runtime_interface::CreateContainerOptions {
                                     workload_id: workload_def.id,
                                     node_id,
+                                    // Jobs wait behind interactive deploys for image pulls
+                                    pull_priority: if is_job {
+                                        PullPriority::Batch
+                                    } else {
+                                        PullPriority::Interactive
+                                    },
+                                };
+                                let spec = container_runtime_interface::InstanceSpec::from_workload(workload_def);
+                                let ip_addresses = match &self.instance_addresses {
+                                    Some(allocator) => match allocator.allocate().await {
+                                        Ok(addresses) => addresses,
+                                        Err(e) => {
+                                            error!(
+                                                "Failed to allocate addresses for workload {}: {}",
+                                                workload_def.id, e
+                                            );
+                                            let message =
+                                                format!("Failed to allocate addresses: {}", e);
+                                            self.events
+                                                .workload_warning(
+                                                    workload_def,
+                                                    "FailedCreate",
+                                                    message,
+                                                )
+                                                .await;
+                                            continue;
+                                        }
+                                    },
+                                    None => vec![],
+                                };
+                                let spec = if ip_addresses.is_empty() {
+                                    spec
+                                } else {
+                                    let joined = ip_addresses
+                                        .iter()
+                                        .map(ToString::to_string)
+                                        .collect::<Vec<_>>()
+                                        .join(",");
+                                    spec.map_containers(|container| {
+                                        container.annotations.insert(
+                                            WorkloadInstance::IP_ADDRESSES_ANNOTATION.to_string(),
+                                            joined.clone(),
+                                        );
+                                    })
                                 };
 
-                                match self.runtime.create_container(container_config, &options).await {
-                                    Ok(container_id) => {
+                                // Init containers run to completion inside start_instance
+                                let start_span = tracing::info_span!(
+                                    "runtime.start_instance",
+                                    workload = %workload_def.name,
+                                    node = %node_id
+                                );
+                                let started = self
+                                    .runtime
+                                    .start_instance(&spec, &options)
+                                    .instrument(start_span)
+                                    .await;
+                                match started {
+                                    Ok(container_ids) => {
                                         info!(
-                                            "Container {} created for workload {} on node {}",
-                                            container_id, workload_def.id, node_id
+                                            "Containers {:?} created for workload {} on node {}",
+                                            container_ids, workload_def.id, node_id
                                         );
+                                        placed += 1;
+                                        let waited = self.pending.placed(workload_def.id);
+                                        if let (Some(observer), Some((waited, attempts))) =
+                                            (&self.observer, waited)
+                                        {
+                                            observer.on_placed(waited, attempts);
+                                        }
 
                                         // Create new instance and save to persistent state
                                         let new_instance = WorkloadInstance {
                                             id: uuid::Uuid::new_v4(),
                                             workload_id: workload_def.id,
                                             node_id,
-                                            container_ids: vec![container_id],
+                                            container_ids,
                                             status: WorkloadInstanceStatus::Pending,
+                                            ip_addresses,
+                                            restarts: vec![],
+                                            namespace: workload_def.namespace.clone(),
+                                            resource_version: 0,
                                         };
 
-                                        if let Err(e) = self.state_store.put_instance(new_instance).await {
-                                            error!("Failed to store instance in state: {:?}", e);
+                                        let message = format!("Assigned to node {}", node_id);
+                                        self.events
+                                            .instance_normal(&new_instance, "Scheduled", message)
+                                            .await;
+                                        let instance_id = new_instance.id;
+                                        let addresses = new_instance.ip_addresses.clone();
+                                        match self.state_store.put_instance(new_instance).await {
+                                            Ok(()) => {
+                                                if let Some(allocator) = &self.instance_addresses {
+                                                    allocator.assign(instance_id, &addresses).await;
+                                                }
+                                            }
+                                            Err(e) => {
+                                                error!("Failed to store instance in state: {:?}", e);
+                                            }
                                         }
                                     }
                                     Err(e) => {
@@ -296,6 +619,16 @@ impl Orchestrator {
                                             "Failed to create container for workload {} on node {}: {:?}",
                                             workload_def.id, node_id, e
                                         );
+                                        if let Some(allocator) = &self.instance_addresses {
+                                            allocator.release(&ip_addresses).await;
+                                        }
+                                        let message = format!(
+                                            "Failed to start instance on node {}: {}",
+                                            node_id, e
+                                        );
+                                        self.events
+                                            .workload_warning(workload_def, "FailedCreate", message)
+                                            .await;
                                     }
                                 }
                             } else {
@@ -310,37 +643,75 @@ impl Orchestrator {
                                 "Could not place instance of workload {}: {}",
                                 workload_def.id, reason
                             );
+                            if let Some(observer) = &self.observer {
+                                observer.on_failed("no_placement");
+                            }
+                            self.events
+                                .workload_warning(workload_def, "FailedScheduling", reason)
+                                .await;
                         }
                         ScheduleDecision::Error(err_msg) => {
                             error!(
                                 "Scheduler error for workload {}: {}",
                                 workload_def.id, err_msg
                             );
+                            if let Some(observer) = &self.observer {
+                                observer.on_failed("error");
+                            }
+                            self.events
+                                .workload_warning(workload_def, "FailedScheduling", err_msg)
+                                .await;
                         }
                     }
                 }
+                if placed == num_to_schedule {
+                    self.pending.satisfied(workload_def.id);
+                }
             }
             WorkloadAction::RemoveInstances { instances_to_remove } => {
                 info!("Need to remove {} instances for workload {}", instances_to_remove.len(), workload_def.id);
                 for instance_to_remove in instances_to_remove {
                     info!("Attempting to remove instance {} (containers: {:?}) of workload {}", instance_to_remove.id, instance_to_remove.container_ids, workload_def.id);
 
-                    // Stop and remove containers
-                    for container_id in &instance_to_remove.container_ids {
-                        match self.runtime.stop_container(container_id).await {
-                            Ok(_) => info!("Stopped container {}", container_id),
-                            Err(e) => error!("Failed to stop container {}: {:?}", container_id, e),
-                        }
-                        match self.runtime.remove_container(container_id).await {
-                            Ok(_) => info!("Removed container {}", container_id),
-                            Err(e) => error!("Failed to remove container {}: {:?}", container_id, e),
-                        }
+                    // Stop routing traffic to it before the containers go away
+                    if let Some(proxy) = &self.service_proxy {
+                        proxy.instance_terminating(instance_to_remove.id);
+                    }
+                    self.events
+                        .instance_normal(&instance_to_remove, "Killing", "Scaling down")
+                        .await;
+                    let mut terminating = instance_to_remove.clone();
+                    terminating.status = WorkloadInstanceStatus::Terminating;
+                    if let Err(e) = self.state_store.put_instance(terminating).await {
+                        warn!("Failed to mark instance {} as terminating: {:?}", instance_to_remove.id, e);
+                    }
+
+                    // Stop and remove containers, main containers before sidecars
+                    let stop_span = tracing::info_span!(
+                        "runtime.stop_instance",
+                        instance = %instance_to_remove.id
+                    );
+                    let stopped = self
+                        .runtime
+                        .stop_instance(&instance_to_remove.container_ids)
+                        .instrument(stop_span)
+                        .await;
+                    match stopped {
+                        Ok(_) => info!("Stopped containers of instance {}", instance_to_remove.id),
+                        Err(e) => error!("Failed to stop containers of instance {}: {:?}", instance_to_remove.id, e),
                     }
 
                     // Remove instance from persistent state
                     let instance_id = instance_to_remove.id.to_string();
-                    if let Err(e) = self.state_store.delete_instance(&instance_id).await {
-                        error!("Failed to delete instance {} from state: {:?}", instance_id, e);
+                    match self.state_store.delete_instance(&instance_id).await {
+                        Ok(()) => {
+                            if let Some(allocator) = &self.instance_addresses {
+                                allocator.release(&instance_to_remove.ip_addresses).await;
+                            }
+                        }
+                        Err(e) => {
+                            error!("Failed to delete instance {} from state: {:?}", instance_id, e);
+                        }
                     }
                 }
             }
@@ -357,6 +728,75 @@ impl Orchestrator {
 
         Ok(())
     }
+
+    /// Evict instances of lower-priority workloads so a replica of
+    /// `workload_def` fits, returning the node freed up. A disruption budget
+    /// blocking an eviction leaves the replica unplaced.
+    async fn preempt(
+        &self,
+        workload_def: &WorkloadDefinition,
+        nodes: &[Node],
+    ) -> Result<Option<NodeId>> {
+        let workloads = self.state_store.list_workloads().await?;
+        let instances = self.state_store.list_all_instances().await?;
+        let Some(preemption) =
+            scheduling::select_victims(workload_def, nodes, &workloads, &instances)
+        else {
+            return Ok(None);
+        };
+
+        for victim in &preemption.victims {
+            match &self.disruption {
+                Some(disruption) => {
+                    if !disruption.evict(victim).await? {
+                        info!(
+                            "Disruption budget blocked preempting instance {} for {}",
+                            victim.id, workload_def.id
+                        );
+                        return Ok(None);
+                    }
+                    if let Some(proxy) = &self.service_proxy {
+                        proxy.instance_terminating(victim.id);
+                    }
+                }
+                None => {
+                    if let Some(proxy) = &self.service_proxy {
+                        proxy.instance_terminating(victim.id);
+                    }
+                    if let Err(e) = self.runtime.stop_instance(&victim.container_ids).await {
+                        error!(
+                            "Failed to stop containers of instance {}: {:?}",
+                            victim.id, e
+                        );
+                    }
+                    self.state_store
+                        .delete_instance(&victim.id.to_string())
+                        .await?;
+                }
+            }
+            info!(
+                "Preempted instance {} on node {} for workload {}",
+                victim.id, preemption.node_id, workload_def.id
+            );
+            let message = format!("Preempted by workload {}", workload_def.name);
+            self.events
+                .instance_normal(victim, "Preempted", message)
+                .await;
+            if let Some(observer) = &self.observer {
+                observer.on_preempted("priority");
+            }
+        }
+        Ok(Some(preemption.node_id))
+    }
+
+    /// Report the replicas currently drifted across all workloads.
+    fn report_drift(&self) {
+        if let Some(observer) = &self.observer {
+            for (kind, replicas) in self.drift.totals() {
+                observer.on_drift(kind, replicas);
+            }
+        }
+    }
 }
 
 // Helper enum for clarity in reconcile_workload
@@ -373,6 +813,28 @@ enum WorkloadAction {
     None,
 }
 
+/// Resolves with the new leadership state when it changes; never without an elector.
+async fn leadership_changed(leader_rx: &mut Option<watch::Receiver<bool>>) -> Option<bool> {
+    match leader_rx {
+        Some(rx) => {
+            rx.changed().await.ok()?;
+            Some(*rx.borrow_and_update())
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Resolves on the interval's next tick; never without one.
+async fn tick(interval: &mut Option<Interval>) -> Option<()> {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+            Some(())
+        }
+        None => std::future::pending().await,
+    }
+}
+
 // This would typically be in a `main.rs` file if `orchestrator_core` was a binary crate.
 // For now, let's imagine a function that sets it up.
 pub async fn start_orchestrator_service(
@@ -0,0 +1,645 @@
+//! Shipping of container logs to a log store.
+//!
+//! A node can run a [`LogForwarder`] that tails the log file of every
+//! container of its running instances, labels each line with the container's
+//! namespace, workload, instance and node, and pushes the lines in batches to
+//! a [`LogSink`]: Loki's push API or Elasticsearch's bulk API.
+//!
+//! Batches the sink does not take are buffered on disk and retried, oldest
+//! first, before anything newer is sent. Once the buffer is full the forwarder
+//! stops reading, so the backlog stays in the container logs until the sink
+//! catches up. Read offsets are kept next to the buffer, so a restarted
+//! forwarder resumes where it stopped.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+use container_runtime_interface::{ContainerRuntime, LogMatch};
+use orchestrator_shared_types::{
+    ContainerId, NodeId, OrchestrationError, Result, WorkloadDefinition, WorkloadId,
+    WorkloadInstanceStatus,
+};
+use state_store_interface::StateStore;
+
+/// Most bytes read from one container log per pass.
+const READ_CHUNK: u64 = 256 * 1024;
+
+/// File in the buffer directory holding the read offsets.
+const OFFSETS_FILE: &str = "offsets.json";
+
+/// Where logs are shipped.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogSink {
+    /// Loki at this base URL.
+    Loki { url: String },
+    /// Elasticsearch at this base URL, into `index`.
+    Elasticsearch { url: String, index: String },
+}
+
+impl LogSink {
+    /// Index used when an Elasticsearch sink names none.
+    pub const DEFAULT_INDEX: &'static str = "orchestrator-logs";
+
+    fn request(&self, client: &reqwest::Client, lines: &[LogLine]) -> reqwest::RequestBuilder {
+        match self {
+            Self::Loki { url } => client
+                .post(format!("{}/loki/api/v1/push", url))
+                .json(&loki_push(lines)),
+            Self::Elasticsearch { url, index } => client
+                .post(format!("{}/_bulk", url))
+                .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+                .body(bulk_body(index, lines)),
+        }
+    }
+}
+
+impl FromStr for LogSink {
+    type Err = String;
+
+    /// Parse "loki+http://loki:3100" or "elasticsearch+http://es:9200[/index]".
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        if let Some(url) = s.strip_prefix("loki+") {
+            return Ok(Self::Loki {
+                url: url.trim_end_matches('/').to_string(),
+            });
+        }
+        if let Some(url) = s.strip_prefix("elasticsearch+") {
+            let (scheme, rest) = url
+                .split_once("://")
+                .ok_or_else(|| format!("invalid URL '{}'", url))?;
+            let (host, index) = match rest.split_once('/') {
+                Some((host, index)) => (host, index.trim_matches('/')),
+                None => (rest, ""),
+            };
+            let index = if index.is_empty() {
+                Self::DEFAULT_INDEX
+            } else {
+                index
+            };
+            return Ok(Self::Elasticsearch {
+                url: format!("{}://{}", scheme, host),
+                index: index.to_string(),
+            });
+        }
+        Err(format!(
+            "'{}', expected loki+http://host:port or elasticsearch+http://host:port[/index]",
+            s
+        ))
+    }
+}
+
+/// One line of container output with the labels it is shipped under.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogLine {
+    pub namespace: String,
+    /// Name of the instance's workload.
+    pub workload: String,
+    pub instance_id: Uuid,
+    pub container: String,
+    pub node_id: NodeId,
+    /// RFC3339 timestamp the runtime recorded for the line.
+    pub timestamp: String,
+    /// "stdout" or "stderr".
+    pub stream: String,
+    pub message: String,
+}
+
+/// Loki push request with one stream per label set.
+fn loki_push(lines: &[LogLine]) -> serde_json::Value {
+    let mut streams: BTreeMap<[String; 6], Vec<[String; 2]>> = BTreeMap::new();
+    for line in lines {
+        let labels = [
+            line.namespace.clone(),
+            line.workload.clone(),
+            line.instance_id.to_string(),
+            line.container.clone(),
+            line.node_id.to_string(),
+            line.stream.clone(),
+        ];
+        let nanos = chrono::DateTime::parse_from_rfc3339(&line.timestamp)
+            .ok()
+            .and_then(|t| t.timestamp_nanos_opt())
+            .unwrap_or_else(|| chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0));
+        streams
+            .entry(labels)
+            .or_default()
+            .push([nanos.to_string(), line.message.clone()]);
+    }
+    let streams: Vec<_> = streams
+        .into_iter()
+        .map(
+            |([namespace, workload, instance, container, node, stream], values)| {
+                serde_json::json!({
+                    "stream": {
+                        "namespace": namespace,
+                        "workload": workload,
+                        "instance": instance,
+                        "container": container,
+                        "node": node,
+                        "stream": stream,
+                    },
+                    "values": values,
+                })
+            },
+        )
+        .collect();
+    serde_json::json!({ "streams": streams })
+}
+
+/// Elasticsearch bulk request creating one document per line.
+fn bulk_body(index: &str, lines: &[LogLine]) -> String {
+    let action = serde_json::json!({ "create": { "_index": index } }).to_string();
+    let mut body = String::new();
+    for line in lines {
+        let document = serde_json::json!({
+            "@timestamp": line.timestamp,
+            "message": line.message,
+            "stream": line.stream,
+            "namespace": line.namespace,
+            "workload": line.workload,
+            "instance": line.instance_id,
+            "container": line.container,
+            "node": line.node_id.to_string(),
+        });
+        body.push_str(&action);
+        body.push('\n');
+        body.push_str(&document.to_string());
+        body.push('\n');
+    }
+    body
+}
+
+/// Settings of a [`LogForwarder`].
+#[derive(Debug, Clone)]
+pub struct LogForwarderConfig {
+    pub sink: LogSink,
+    /// Directory holding unsent batches and the read offsets.
+    pub buffer_dir: PathBuf,
+    /// Disk space buffered batches may take before reading pauses.
+    pub max_buffer_bytes: u64,
+    /// Most lines sent in one request.
+    pub batch_size: usize,
+    /// How long the sink has to take a batch.
+    pub timeout: Duration,
+}
+
+impl LogForwarderConfig {
+    pub fn new(sink: LogSink, buffer_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            sink,
+            buffer_dir: buffer_dir.into(),
+            max_buffer_bytes: 256 * 1024 * 1024,
+            batch_size: 1000,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn with_max_buffer_bytes(mut self, bytes: u64) -> Self {
+        self.max_buffer_bytes = bytes;
+        self
+    }
+
+    pub fn with_batch_size(mut self, lines: usize) -> Self {
+        self.batch_size = lines.max(1);
+        self
+    }
+}
+
+/// Tails the logs of the containers running on one node into a [`LogSink`].
+pub struct LogForwarder {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    node_id: NodeId,
+    config: LogForwarderConfig,
+    client: reqwest::Client,
+    /// Bytes of each container's log already read.
+    offsets: Mutex<HashMap<ContainerId, u64>>,
+}
+
+impl LogForwarder {
+    /// How often [`spawn`](Self::spawn) forwards when run with defaults.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+
+    /// Fails if the buffer directory cannot be created.
+    pub fn new(
+        state_store: Arc<dyn StateStore>,
+        runtime: Arc<dyn ContainerRuntime>,
+        node_id: NodeId,
+        config: LogForwarderConfig,
+    ) -> Result<Self> {
+        std::fs::create_dir_all(&config.buffer_dir)
+            .map_err(|e| buffer_error(&config.buffer_dir, e))?;
+        let offsets = match std::fs::read(config.buffer_dir.join(OFFSETS_FILE)) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable log offsets: {}", e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Ok(Self {
+            state_store,
+            runtime,
+            node_id,
+            config,
+            client: reqwest::Client::new(),
+            offsets: Mutex::new(offsets),
+        })
+    }
+
+    /// Ship what the node's containers logged since the last pass, after any
+    /// buffered batches. Returns the number of lines read.
+    pub async fn forward(&self) -> Result<usize> {
+        let mut offsets = self.offsets.lock().await;
+        let buffered = self.flush_buffer().await?;
+        if buffered >= self.config.max_buffer_bytes {
+            debug!("Log buffer full, leaving new lines in the container logs");
+            return Ok(0);
+        }
+
+        let (lines, next_offsets) = self.read_new_lines(&offsets).await?;
+        // Nothing new goes out ahead of buffered batches
+        if !lines.is_empty() && (buffered > 0 || !self.send(&lines).await) {
+            self.buffer(&lines).await?;
+        }
+        if *offsets != next_offsets {
+            *offsets = next_offsets;
+            let path = self.config.buffer_dir.join(OFFSETS_FILE);
+            let bytes = serde_json::to_vec(&*offsets).map_err(|e| buffer_error(&path, e))?;
+            tokio::fs::write(&path, bytes)
+                .await
+                .map_err(|e| buffer_error(&path, e))?;
+        }
+        Ok(lines.len())
+    }
+
+    /// Forward every `interval` until the task is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.forward().await {
+                    error!("Log forwarding failed: {:?}", e);
+                }
+            }
+        })
+    }
+
+    /// Up to a batch of new lines across the node's running containers, and
+    /// the offsets of all of them once those lines are read.
+    async fn read_new_lines(
+        &self,
+        offsets: &HashMap<ContainerId, u64>,
+    ) -> Result<(Vec<LogLine>, HashMap<ContainerId, u64>)> {
+        let instances = self.state_store.list_all_instances().await?;
+        let mut workloads: HashMap<WorkloadId, Option<WorkloadDefinition>> = HashMap::new();
+        let mut lines = Vec::new();
+        let mut next_offsets = HashMap::new();
+
+        for instance in instances
+            .iter()
+            .filter(|i| i.node_id == self.node_id && i.status == WorkloadInstanceStatus::Running)
+        {
+            let workload = match workloads.get(&instance.workload_id) {
+                Some(workload) => workload.clone(),
+                None => {
+                    let workload = self.state_store.get_workload(&instance.workload_id).await?;
+                    workloads.insert(instance.workload_id, workload.clone());
+                    workload
+                }
+            };
+            let Some(workload) = workload else {
+                continue;
+            };
+
+            // Instances list sidecars before main containers
+            let containers = workload
+                .sidecars
+                .iter()
+                .chain(&workload.containers)
+                .zip(&instance.container_ids);
+            for (container, container_id) in containers {
+                let path = match self.runtime.container_log_path(container_id).await {
+                    Ok(path) => path,
+                    Err(e) => {
+                        debug!("No log file for container {}: {:?}", container_id, e);
+                        continue;
+                    }
+                };
+                let offset = offsets.get(container_id).copied().unwrap_or(0);
+                let limit = self.config.batch_size.saturating_sub(lines.len());
+                let (raw_lines, next) = match read_lines(&path, offset, limit).await {
+                    Ok(read) => read,
+                    Err(e) => {
+                        debug!("Cannot read log of container {}: {}", container_id, e);
+                        (Vec::new(), offset)
+                    }
+                };
+                next_offsets.insert(container_id.clone(), next);
+
+                for raw in raw_lines {
+                    // Lines without a timestamp are stamped with the time they are read
+                    let entry = match LogMatch::parse(&raw) {
+                        Some(entry) => entry,
+                        None => LogMatch {
+                            timestamp: chrono::Utc::now().to_rfc3339(),
+                            stream: "stdout".to_string(),
+                            message: raw,
+                        },
+                    };
+                    lines.push(LogLine {
+                        namespace: instance.namespace.clone(),
+                        workload: workload.name.clone(),
+                        instance_id: instance.id,
+                        container: container.name.clone(),
+                        node_id: self.node_id,
+                        timestamp: entry.timestamp,
+                        stream: entry.stream,
+                        message: entry.message,
+                    });
+                }
+            }
+        }
+        Ok((lines, next_offsets))
+    }
+
+    /// Push `lines` to the sink; false if it did not take them.
+    async fn send(&self, lines: &[LogLine]) -> bool {
+        let result = self
+            .config
+            .sink
+            .request(&self.client, lines)
+            .timeout(self.config.timeout)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Failed to ship {} log lines: {}", lines.len(), e);
+                return false;
+            }
+        };
+        // Documents Elasticsearch rejects would be rejected again, so they
+        // are dropped rather than retried
+        if let LogSink::Elasticsearch { .. } = self.config.sink {
+            if let Ok(body) = response.json::<serde_json::Value>().await {
+                if body["errors"].as_bool() == Some(true) {
+                    warn!("Elasticsearch rejected some of {} log lines", lines.len());
+                }
+            }
+        }
+        true
+    }
+
+    /// Write `lines` to the disk buffer as the newest batch.
+    async fn buffer(&self, lines: &[LogLine]) -> Result<()> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path = self
+            .config
+            .buffer_dir
+            .join(format!("batch-{:032}.json", nanos));
+        let bytes = serde_json::to_vec(lines).map_err(|e| buffer_error(&path, e))?;
+        tokio::fs::write(&path, bytes)
+            .await
+            .map_err(|e| buffer_error(&path, e))
+    }
+
+    /// Retry buffered batches oldest first, stopping at the first the sink
+    /// does not take. Returns the bytes still buffered.
+    async fn flush_buffer(&self) -> Result<u64> {
+        let dir = &self.config.buffer_dir;
+        let mut batches: Vec<(PathBuf, u64)> = std::fs::read_dir(dir)
+            .map_err(|e| buffer_error(dir, e))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("batch-"))
+            .map(|entry| (entry.path(), entry.metadata().map_or(0, |m| m.len())))
+            .collect();
+        batches.sort();
+
+        let mut remaining: u64 = batches.iter().map(|(_, size)| size).sum();
+        for (path, size) in batches {
+            let bytes = tokio::fs::read(&path)
+                .await
+                .map_err(|e| buffer_error(&path, e))?;
+            match serde_json::from_slice::<Vec<LogLine>>(&bytes) {
+                Ok(lines) if !self.send(&lines).await => break,
+                Ok(_) => {}
+                Err(e) => warn!("Dropping unreadable log batch {}: {}", path.display(), e),
+            }
+            tokio::fs::remove_file(&path)
+                .await
+                .map_err(|e| buffer_error(&path, e))?;
+            remaining -= size;
+        }
+        Ok(remaining)
+    }
+}
+
+fn buffer_error(path: &Path, e: impl std::fmt::Display) -> OrchestrationError {
+    OrchestrationError::InternalError(format!("log buffer {}: {}", path.display(), e))
+}
+
+/// Up to `limit` complete lines of the file at `path` after `offset`, and the
+/// offset after them. A line longer than a whole read is cut at the read.
+async fn read_lines(path: &Path, offset: u64, limit: usize) -> std::io::Result<(Vec<String>, u64)> {
+    let mut file = tokio::fs::File::open(path).await?;
+    // A log shorter than what was read of it was truncated or replaced
+    let offset = if file.metadata().await?.len() < offset {
+        0
+    } else {
+        offset
+    };
+    file.seek(SeekFrom::Start(offset)).await?;
+    let mut buf = Vec::new();
+    file.take(READ_CHUNK).read_to_end(&mut buf).await?;
+
+    let mut lines = Vec::new();
+    let mut consumed = 0;
+    for chunk in buf.split_inclusive(|b| *b == b'\n') {
+        if lines.len() >= limit || chunk.last() != Some(&b'\n') {
+            break;
+        }
+        consumed += chunk.len();
+        let line = String::from_utf8_lossy(&chunk[..chunk.len() - 1]);
+        lines.push(line.trim_end_matches('\r').to_string());
+    }
+    if lines.is_empty() && limit > 0 && buf.len() as u64 == READ_CHUNK {
+        consumed = buf.len();
+        lines.push(String::from_utf8_lossy(&buf).into_owned());
+    }
+    Ok((lines, offset + consumed as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, StubRuntime};
+    use orchestrator_shared_types::{Keypair, WorkloadInstance};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use std::io::Write;
+
+    fn append(path: &Path, text: &str) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn line(node_id: NodeId, message: &str, stream: &str) -> LogLine {
+        LogLine {
+            namespace: "shop".to_string(),
+            workload: "web".to_string(),
+            instance_id: Uuid::nil(),
+            container: "web".to_string(),
+            node_id,
+            timestamp: "2024-01-15T10:30:00Z".to_string(),
+            stream: stream.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_log_sink() {
+        assert_eq!(
+            "loki+http://loki:3100/".parse::<LogSink>().unwrap(),
+            LogSink::Loki {
+                url: "http://loki:3100".to_string()
+            }
+        );
+        assert_eq!(
+            "elasticsearch+https://es:9200/app-logs"
+                .parse::<LogSink>()
+                .unwrap(),
+            LogSink::Elasticsearch {
+                url: "https://es:9200".to_string(),
+                index: "app-logs".to_string(),
+            }
+        );
+        assert_eq!(
+            "elasticsearch+http://es:9200".parse::<LogSink>().unwrap(),
+            LogSink::Elasticsearch {
+                url: "http://es:9200".to_string(),
+                index: LogSink::DEFAULT_INDEX.to_string(),
+            }
+        );
+        assert!("http://loki:3100".parse::<LogSink>().is_err());
+    }
+
+    #[test]
+    fn test_sink_payloads() {
+        let node_id = Keypair::generate().public_key();
+        let lines = [
+            line(node_id, "hello", "stdout"),
+            line(node_id, "again", "stdout"),
+            line(node_id, "oops", "stderr"),
+        ];
+        let push = loki_push(&lines);
+        let streams = push["streams"].as_array().unwrap();
+        assert_eq!(streams.len(), 2);
+        assert_eq!(streams[0]["stream"]["stream"], "stderr");
+        assert_eq!(streams[1]["stream"]["workload"], "web");
+        assert_eq!(
+            streams[1]["values"][0],
+            serde_json::json!(["1705314600000000000", "hello"])
+        );
+
+        let body = bulk_body("logs", &lines[..1]);
+        let mut records = body.lines();
+        assert_eq!(records.next().unwrap(), r#"{"create":{"_index":"logs"}}"#);
+        let document: serde_json::Value = serde_json::from_str(records.next().unwrap()).unwrap();
+        assert_eq!(document["message"], "hello");
+        assert_eq!(document["@timestamp"], "2024-01-15T10:30:00Z");
+        assert!(records.next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_forward_buffers_while_sink_is_down() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(InMemoryStateStore::new());
+        let node_id = Keypair::generate().public_key();
+        let workload = WorkloadDefinition {
+            namespace: "shop".to_string(),
+            ..test_support::workload("web")
+        };
+        store.put_workload(workload.clone()).await.unwrap();
+        store
+            .put_instance(WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id: workload.id,
+                node_id,
+                container_ids: vec!["c1".to_string()],
+                status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "shop".to_string(),
+                resource_version: 0,
+            })
+            .await
+            .unwrap();
+
+        let log = dir.path().join("c1.log");
+        append(
+            &log,
+            "2024-01-15T10:30:00Z stdout one\n2024-01-15T10:30:01Z stderr two\npart",
+        );
+        let runtime = Arc::new(StubRuntime::default().with_log_dir(dir.path()));
+        // Nothing listens on port 1
+        let sink: LogSink = "loki+http://127.0.0.1:1".parse().unwrap();
+        let buffer_dir = dir.path().join("buffer");
+        let config = LogForwarderConfig::new(sink, &buffer_dir);
+        let forwarder =
+            LogForwarder::new(store.clone(), runtime.clone(), node_id, config.clone()).unwrap();
+
+        assert_eq!(forwarder.forward().await.unwrap(), 2);
+        append(&log, "ial\n");
+        assert_eq!(forwarder.forward().await.unwrap(), 1);
+        assert_eq!(forwarder.forward().await.unwrap(), 0);
+
+        let batches = || {
+            let mut names: Vec<_> = std::fs::read_dir(&buffer_dir)
+                .unwrap()
+                .map(|e| e.unwrap().path())
+                .filter(|p| p.file_name().unwrap() != OFFSETS_FILE)
+                .collect();
+            names.sort();
+            names
+        };
+        let buffered = batches();
+        assert_eq!(buffered.len(), 2);
+        let first: Vec<LogLine> =
+            serde_json::from_slice(&std::fs::read(&buffered[0]).unwrap()).unwrap();
+        assert_eq!(first[1].stream, "stderr");
+        assert_eq!(first[1].message, "two");
+        let second: Vec<LogLine> =
+            serde_json::from_slice(&std::fs::read(&buffered[1]).unwrap()).unwrap();
+        assert_eq!(second[0].message, "partial");
+        assert_eq!(second[0].instance_id, first[0].instance_id);
+
+        // A restarted forwarder resumes at the saved offset but stops reading
+        // while its buffer is full
+        append(&log, "2024-01-15T10:30:02Z stdout three\n");
+        let full = config.with_max_buffer_bytes(1);
+        let forwarder = LogForwarder::new(store, runtime, node_id, full).unwrap();
+        assert_eq!(forwarder.forward().await.unwrap(), 0);
+        assert_eq!(batches().len(), 2);
+        let offsets = forwarder.offsets.lock().await;
+        assert_eq!(offsets["c1"], std::fs::metadata(&log).unwrap().len() - 34);
+    }
+}
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use tokio;
 
 use orchestrator_core::start_orchestrator_service;
-use orchestrator_shared_types::{NodeId, WorkloadDefinition, ContainerConfig, NodeResources, PortMapping, Node, Result as OrchestrationResult, OrchestrationError, ContainerId, Keypair};
+use orchestrator_shared_types::{NodeId, WorkloadDefinition, ContainerConfig, NodeResources, PortMapping, Node, Result as OrchestrationResult, OrchestrationError, ContainerId, Keypair, DEFAULT_NAMESPACE};
 use scheduler_interface::SimpleScheduler;
 use state_store_interface::{StateStore, SqliteStateStore};
 use uuid::Uuid;
@@ -26,7 +26,7 @@ impl ContainerRuntime for MockRuntime {
     async fn init_node(&self, _node_id: NodeId) -> OrchestrationResult<()> { Ok(()) }
     async fn create_container(&self, config: &ContainerConfig, _options: &CreateContainerOptions) -> OrchestrationResult<ContainerId> {
         let id = Uuid::new_v4().to_string();
-        let status = ContainerStatus { id: id.clone(), state: "Pending".to_string(), exit_code: None, error_message: None };
+        let status = ContainerStatus { id: id.clone(), state: "Pending".to_string(), exit_code: None, error_message: None, oom_killed: false };
         self.containers.lock().await.insert(id.clone(), (config.clone(), status));
         tracing::info!("[MockRuntime] Created container {}", id);
         let containers_clone = self.containers.clone();
@@ -160,6 +160,8 @@ async fn main() -> anyhow::Result<()> {
                 labels: Default::default(),
                 resources_capacity: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
                 resources_allocatable: NodeResources { cpu_cores: 3.8, memory_mb: 7000, disk_mb: 90000 },
+                unschedulable: false,
+                resource_version: 0,
             };
             tracing::info!("[main] Simulating add_node: {}", node1_id);
             mock_cm_for_spawn.add_node(node1).await; // Call add_node on the concrete type
@@ -180,6 +182,8 @@ async fn main() -> anyhow::Result<()> {
             labels: Default::default(),
             resources_capacity: NodeResources { cpu_cores: 2.0, memory_mb: 4096, disk_mb: 50000 },
             resources_allocatable: NodeResources { cpu_cores: 1.8, memory_mb: 3500, disk_mb: 45000 },
+            unschedulable: false,
+            resource_version: 0,
         };
         // The actual downcast
         // Direct cast to the concrete type
@@ -205,11 +209,29 @@ async fn main() -> anyhow::Result<()> {
             command: None,
             args: None,
             env_vars: Default::default(),
-            ports: vec![PortMapping { container_port: 80, host_port: Some(8080), protocol: "tcp".to_string() }],
+            ports: vec![PortMapping { container_port: 80, host_port: Some(8080), protocol: "tcp".to_string(), host_ip: None }],
             resource_requests: NodeResources { cpu_cores: 0.5, memory_mb: 256, disk_mb: 0 },
+            volume_mounts: vec![],
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+            readiness_probe: None,
+            limits: Default::default(),
+            sysctls: Default::default(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
         }],
         replicas: 1, // Reduced for quicker testing
         labels: Default::default(),
+        init_containers: vec![],
+        sidecars: vec![],
+        placement: Default::default(),
+        restart_policy: Default::default(),
+        namespace: DEFAULT_NAMESPACE.to_string(),
+        resource_version: 0,
     };
     tracing::info!("[main] Submitting workload: {}", workload_def.name);
     if workload_tx.send(workload_def.clone()).await.is_err() {
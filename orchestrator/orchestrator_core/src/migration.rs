@@ -0,0 +1,488 @@
+//! Moving running instances between nodes without restarting them.
+//!
+//! [`InstanceMigrator::migrate`] checkpoints every container of an instance,
+//! restores the checkpoints on the target node and points the instance at
+//! the restored containers. The processes resume where they stopped, so a
+//! long-running job such as a training run survives its node being drained
+//! instead of starting over in a replacement. The instance keeps its ID.
+//!
+//! Checkpoints stop the containers, main containers before their sidecars,
+//! and restores start them sidecars first. If any step fails, the containers
+//! restored so far are removed and the checkpoints restored on the original
+//! node instead; an instance that cannot be brought back there either is
+//! marked Failed, so its controller replaces it.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tracing::{info, warn};
+
+use crate::events::EventRecorder;
+use container_runtime_interface::{
+    Checkpoint, CheckpointOptions, ContainerRuntime, CreateContainerOptions,
+};
+use orchestrator_shared_types::{
+    ContainerId, NodeId, NodeStatus, OrchestrationError, Result, WorkloadInstance,
+    WorkloadInstanceStatus,
+};
+use state_store_interface::StateStore;
+
+/// Moves instances to other nodes through container checkpoints.
+pub struct InstanceMigrator {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    events: EventRecorder,
+}
+
+impl InstanceMigrator {
+    pub fn new(state_store: Arc<dyn StateStore>, runtime: Arc<dyn ContainerRuntime>) -> Self {
+        Self {
+            events: EventRecorder::new(state_store.clone(), "migration"),
+            state_store,
+            runtime,
+        }
+    }
+
+    /// Move a running instance to the node `node_id`, which must be ready
+    /// and schedulable. Returns the instance as stored afterwards.
+    pub async fn migrate(
+        &self,
+        instance: &WorkloadInstance,
+        node_id: NodeId,
+    ) -> Result<WorkloadInstance> {
+        self.check(instance, &node_id).await?;
+        info!(
+            "Migrating instance {} from node {} to {}",
+            instance.id, instance.node_id, node_id
+        );
+
+        let mut checkpoints = Vec::with_capacity(instance.container_ids.len());
+        for id in instance.container_ids.iter().rev() {
+            match self
+                .runtime
+                .checkpoint_container(id, &CheckpointOptions::default())
+                .await
+            {
+                Ok(checkpoint) => checkpoints.push(checkpoint),
+                Err(e) => return Err(self.abort(instance, &checkpoints, e).await),
+            }
+        }
+        checkpoints.reverse();
+
+        let mut restored = HashMap::new();
+        for checkpoint in &checkpoints {
+            match self.restore(instance, checkpoint, node_id).await {
+                Ok(id) => {
+                    restored.insert(checkpoint.container_id.clone(), id);
+                }
+                Err(e) => {
+                    let ids: Vec<ContainerId> = restored.into_values().collect();
+                    let _ = self.runtime.stop_instance(&ids).await;
+                    return Err(self.abort(instance, &checkpoints, e).await);
+                }
+            }
+        }
+
+        let migrated = match self.replace_containers(instance, node_id, &restored).await {
+            Ok(migrated) => migrated,
+            Err(e) => {
+                let ids: Vec<ContainerId> = restored.into_values().collect();
+                let _ = self.runtime.stop_instance(&ids).await;
+                self.clean_up(instance, &checkpoints).await;
+                return Err(e);
+            }
+        };
+        self.clean_up(instance, &checkpoints).await;
+        let message = format!("Migrated from node {} to {}", instance.node_id, node_id);
+        self.events
+            .instance_normal(&migrated, "Migrated", message)
+            .await;
+        info!("Migrated instance {} to node {}", instance.id, node_id);
+        Ok(migrated)
+    }
+
+    /// Fail unless `instance` is running and `node_id` can take it.
+    async fn check(&self, instance: &WorkloadInstance, node_id: &NodeId) -> Result<()> {
+        if instance.status != WorkloadInstanceStatus::Running {
+            return Err(OrchestrationError::Conflict(format!(
+                "Instance {} is {:?}; only running instances can be migrated",
+                instance.id, instance.status
+            )));
+        }
+        if instance.node_id == *node_id {
+            return Err(OrchestrationError::Conflict(format!(
+                "Instance {} already runs on node {}",
+                instance.id, node_id
+            )));
+        }
+        let node = self
+            .state_store
+            .get_node(node_id)
+            .await?
+            .ok_or(OrchestrationError::NodeNotFound(*node_id))?;
+        if node.status != NodeStatus::Ready {
+            return Err(OrchestrationError::Conflict(format!(
+                "Node {} is {:?}",
+                node_id, node.status
+            )));
+        }
+        if node.unschedulable {
+            return Err(OrchestrationError::Conflict(format!(
+                "Node {} is cordoned",
+                node_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Restore a checkpoint of one of `instance`'s containers on `node_id`.
+    async fn restore(
+        &self,
+        instance: &WorkloadInstance,
+        checkpoint: &Checkpoint,
+        node_id: NodeId,
+    ) -> Result<ContainerId> {
+        let options = CreateContainerOptions {
+            workload_id: instance.workload_id,
+            node_id,
+            pull_priority: Default::default(),
+        };
+        self.runtime.restore_container(checkpoint, &options).await
+    }
+
+    /// Bring the containers checkpointed so far back up on the instance's
+    /// own node after a failed migration, and return `error` to report.
+    async fn abort(
+        &self,
+        instance: &WorkloadInstance,
+        checkpoints: &[Checkpoint],
+        error: OrchestrationError,
+    ) -> OrchestrationError {
+        warn!("Migration of instance {} failed: {}", instance.id, error);
+        let mut restored = HashMap::new();
+        let mut lost = false;
+        for checkpoint in checkpoints {
+            match self.restore(instance, checkpoint, instance.node_id).await {
+                Ok(id) => {
+                    restored.insert(checkpoint.container_id.clone(), id);
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to restore container {} of instance {} on its node: {}",
+                        checkpoint.container_id, instance.id, e
+                    );
+                    lost = true;
+                }
+            }
+        }
+
+        let message = format!("Migration failed: {}", error);
+        let recorded = if lost {
+            self.mark_failed(instance, &restored).await
+        } else {
+            self.replace_containers(instance, instance.node_id, &restored)
+                .await
+        };
+        match recorded {
+            Ok(updated) => {
+                self.events
+                    .instance_warning(&updated, "MigrationFailed", message)
+                    .await
+            }
+            Err(e) => warn!("Failed to update instance {}: {}", instance.id, e),
+        }
+        self.clean_up(instance, checkpoints).await;
+        error
+    }
+
+    /// Point the stored instance at `node_id` and the containers that
+    /// replace its old ones.
+    async fn replace_containers(
+        &self,
+        instance: &WorkloadInstance,
+        node_id: NodeId,
+        replacements: &HashMap<ContainerId, ContainerId>,
+    ) -> Result<WorkloadInstance> {
+        let mut current = self
+            .state_store
+            .get_instance(&instance.id.to_string())
+            .await?
+            .ok_or_else(|| {
+                OrchestrationError::Conflict(format!(
+                    "Instance {} was deleted during migration",
+                    instance.id
+                ))
+            })?;
+        current.node_id = node_id;
+        for id in &mut current.container_ids {
+            if let Some(replacement) = replacements.get(id) {
+                *id = replacement.clone();
+            }
+        }
+        self.state_store.put_instance(current.clone()).await?;
+        Ok(current)
+    }
+
+    /// Mark an instance whose containers could not all be restored Failed,
+    /// tearing down the ones that were.
+    async fn mark_failed(
+        &self,
+        instance: &WorkloadInstance,
+        restored: &HashMap<ContainerId, ContainerId>,
+    ) -> Result<WorkloadInstance> {
+        let mut failed = self
+            .replace_containers(instance, instance.node_id, restored)
+            .await?;
+        let _ = self.runtime.stop_instance(&failed.container_ids).await;
+        failed.status = WorkloadInstanceStatus::Failed;
+        self.state_store.put_instance(failed.clone()).await?;
+        Ok(failed)
+    }
+
+    /// Remove the checkpointed containers and their checkpoints.
+    async fn clean_up(&self, instance: &WorkloadInstance, checkpoints: &[Checkpoint]) {
+        for checkpoint in checkpoints {
+            if let Err(e) = self
+                .runtime
+                .remove_container(&checkpoint.container_id)
+                .await
+            {
+                warn!(
+                    "Failed to remove checkpointed container {} of instance {}: {}",
+                    checkpoint.container_id, instance.id, e
+                );
+            }
+            if let Err(e) = self.runtime.remove_checkpoint(checkpoint).await {
+                warn!(
+                    "Failed to remove checkpoint {}: {}",
+                    checkpoint.path.display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use container_runtime_interface::ContainerStatus;
+    use orchestrator_shared_types::{ContainerConfig, Keypair, Node};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use std::path::PathBuf;
+    use tokio::sync::Mutex;
+    use uuid::Uuid;
+
+    /// Keeps container states by ID; restores onto `broken` fail.
+    #[derive(Default)]
+    struct CheckpointingRuntime {
+        containers: Mutex<HashMap<ContainerId, (NodeId, String)>>,
+        broken: Option<NodeId>,
+    }
+
+    impl CheckpointingRuntime {
+        async fn spawn(&self, node_id: NodeId) -> ContainerId {
+            let id = Uuid::new_v4().to_string();
+            self.containers
+                .lock()
+                .await
+                .insert(id.clone(), (node_id, "running".to_string()));
+            id
+        }
+    }
+
+    #[async_trait]
+    impl ContainerRuntime for CheckpointingRuntime {
+        async fn init_node(&self, _node_id: NodeId) -> Result<()> {
+            Ok(())
+        }
+        async fn create_container(
+            &self,
+            _config: &ContainerConfig,
+            options: &CreateContainerOptions,
+        ) -> Result<ContainerId> {
+            Ok(self.spawn(options.node_id).await)
+        }
+        async fn stop_container(&self, container_id: &ContainerId) -> Result<()> {
+            if let Some(container) = self.containers.lock().await.get_mut(container_id) {
+                container.1 = "stopped".to_string();
+            }
+            Ok(())
+        }
+        async fn remove_container(&self, container_id: &ContainerId) -> Result<()> {
+            self.containers.lock().await.remove(container_id);
+            Ok(())
+        }
+        async fn get_container_status(
+            &self,
+            container_id: &ContainerId,
+        ) -> Result<ContainerStatus> {
+            let containers = self.containers.lock().await;
+            let (_, state) = containers
+                .get(container_id)
+                .ok_or_else(|| OrchestrationError::RuntimeError("not found".to_string()))?;
+            Ok(ContainerStatus {
+                id: container_id.clone(),
+                state: state.clone(),
+                exit_code: None,
+                error_message: None,
+                oom_killed: false,
+            })
+        }
+        async fn list_containers(&self, node_id: NodeId) -> Result<Vec<ContainerStatus>> {
+            let containers = self.containers.lock().await;
+            Ok(containers
+                .iter()
+                .filter(|(_, (node, _))| *node == node_id)
+                .map(|(id, (_, state))| ContainerStatus {
+                    id: id.clone(),
+                    state: state.clone(),
+                    exit_code: None,
+                    error_message: None,
+                    oom_killed: false,
+                })
+                .collect())
+        }
+        async fn checkpoint_container(
+            &self,
+            container_id: &ContainerId,
+            _options: &CheckpointOptions,
+        ) -> Result<Checkpoint> {
+            self.stop_container(container_id).await?;
+            Ok(Checkpoint {
+                container_id: container_id.clone(),
+                path: PathBuf::from("/checkpoints").join(container_id),
+                created_at: std::time::SystemTime::now(),
+            })
+        }
+        async fn restore_container(
+            &self,
+            _checkpoint: &Checkpoint,
+            options: &CreateContainerOptions,
+        ) -> Result<ContainerId> {
+            if self.broken == Some(options.node_id) {
+                return Err(OrchestrationError::RuntimeError(
+                    "restore failed".to_string(),
+                ));
+            }
+            Ok(self.spawn(options.node_id).await)
+        }
+        async fn remove_checkpoint(&self, _checkpoint: &Checkpoint) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    async fn add_node(store: &InMemoryStateStore) -> NodeId {
+        let node = Node {
+            id: Keypair::generate().public_key(),
+            address: "127.0.0.1:7280".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            resources_capacity: Default::default(),
+            resources_allocatable: Default::default(),
+            unschedulable: false,
+            resource_version: 0,
+        };
+        let id = node.id;
+        store.put_node(node).await.unwrap();
+        id
+    }
+
+    async fn add_instance(
+        store: &InMemoryStateStore,
+        runtime: &CheckpointingRuntime,
+        node_id: NodeId,
+    ) -> WorkloadInstance {
+        let instance = WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: Uuid::new_v4(),
+            node_id,
+            container_ids: vec![runtime.spawn(node_id).await, runtime.spawn(node_id).await],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        store.put_instance(instance.clone()).await.unwrap();
+        instance
+    }
+
+    #[tokio::test]
+    async fn test_migrate_moves_containers_to_the_node() {
+        let store = InMemoryStateStore::new();
+        let runtime = Arc::new(CheckpointingRuntime::default());
+        let (source, target) = (add_node(&store).await, add_node(&store).await);
+        let instance = add_instance(&store, &runtime, source).await;
+        let migrator = InstanceMigrator::new(Arc::new(store.clone()), runtime.clone());
+
+        let migrated = migrator.migrate(&instance, target).await.unwrap();
+        assert_eq!(migrated.id, instance.id);
+        assert_eq!(migrated.node_id, target);
+        assert_eq!(migrated.container_ids.len(), 2);
+        assert!(migrated
+            .container_ids
+            .iter()
+            .all(|id| !instance.container_ids.contains(id)));
+        assert!(runtime.list_containers(source).await.unwrap().is_empty());
+        assert_eq!(runtime.list_containers(target).await.unwrap().len(), 2);
+
+        let stored = store
+            .get_instance(&instance.id.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.node_id, target);
+        assert_eq!(stored.container_ids, migrated.container_ids);
+
+        // Already there
+        assert!(migrator.migrate(&stored, target).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_restore_resumes_on_the_original_node() {
+        let store = InMemoryStateStore::new();
+        let (source, target) = (add_node(&store).await, add_node(&store).await);
+        let runtime = Arc::new(CheckpointingRuntime {
+            broken: Some(target),
+            ..Default::default()
+        });
+        let instance = add_instance(&store, &runtime, source).await;
+        let migrator = InstanceMigrator::new(Arc::new(store.clone()), runtime.clone());
+
+        assert!(migrator.migrate(&instance, target).await.is_err());
+        let stored = store
+            .get_instance(&instance.id.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.node_id, source);
+        assert_eq!(stored.status, WorkloadInstanceStatus::Running);
+        let running = runtime.list_containers(source).await.unwrap();
+        assert_eq!(running.len(), 2);
+        assert!(running
+            .iter()
+            .all(|c| c.state == "running" && stored.container_ids.contains(&c.id)));
+        assert!(runtime.list_containers(target).await.unwrap().is_empty());
+
+        let events = store.list_events().await.unwrap();
+        assert!(events.iter().any(|e| e.reason == "MigrationFailed"));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_rejects_cordoned_nodes() {
+        let store = InMemoryStateStore::new();
+        let runtime = Arc::new(CheckpointingRuntime::default());
+        let (source, target) = (add_node(&store).await, add_node(&store).await);
+        let mut node = store.get_node(&target).await.unwrap().unwrap();
+        node.unschedulable = true;
+        store.put_node(node).await.unwrap();
+        let instance = add_instance(&store, &runtime, source).await;
+        let migrator = InstanceMigrator::new(Arc::new(store.clone()), runtime.clone());
+
+        let err = migrator.migrate(&instance, target).await.unwrap_err();
+        assert!(matches!(err, OrchestrationError::Conflict(_)));
+        assert_eq!(runtime.list_containers(source).await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,838 @@
+//! Node-local DNS cache.
+//!
+//! Every node runs a small caching forwarder on a link-local address
+//! ([`DEFAULT_LISTEN_ADDR`]) that instances use as their first nameserver.
+//! Queries are answered from cache while the record TTL lasts; misses are
+//! forwarded to the node's upstream resolvers. This keeps resolver load from
+//! high-QPS services on the node rather than on the upstream servers.
+//!
+//! The cache works on raw DNS messages: it only parses enough of a response to
+//! find its question and record TTLs. Cached answers are returned with the
+//! client's transaction id and TTLs reduced by the time spent in cache.
+//!
+//! Lookups and upstream latency are counted in [`DnsCacheStats`] and can also
+//! be reported to a [`DnsCacheObserver`], e.g. the Prometheus metrics.
+//!
+//! No interface holds the link-local address out of the box. With
+//! [`DnsCacheConfig::link`] set, the server adds it to that dummy interface
+//! ([`LINK_NAME`] on nodes), creating the interface if needed, before
+//! binding.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::net::UdpSocket;
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use orchestrator_shared_types::{IpFamily, OrchestrationError, Result};
+
+/// Link-local address the cache listens on by default. Must match the
+/// nameserver the runtime writes into instance `resolv.conf` files.
+pub const DEFAULT_LISTEN_ADDR: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 254, 20, 10)), 53);
+
+/// Dummy interface nodes put the cache's listen address on.
+pub const LINK_NAME: &str = "nodelocaldns";
+
+const DNS_HEADER_LEN: usize = 12;
+const MAX_UDP_MESSAGE: usize = 4096;
+const TYPE_OPT: u16 = 41;
+const RCODE_NOERROR: u8 = 0;
+const RCODE_SERVFAIL: u8 = 2;
+const RCODE_NXDOMAIN: u8 = 3;
+
+/// Configuration for the node-local DNS cache.
+#[derive(Debug, Clone)]
+pub struct DnsCacheConfig {
+    /// Address to serve DNS on.
+    pub listen_addr: SocketAddr,
+    /// Upstream resolvers, tried in order.
+    pub upstreams: Vec<SocketAddr>,
+    /// Maximum number of cached responses.
+    pub max_entries: usize,
+    /// Upper bound on how long a response is cached, regardless of its TTL.
+    pub max_ttl: Duration,
+    /// How long NXDOMAIN and empty responses are cached.
+    pub negative_ttl: Duration,
+    /// How long to wait for each upstream before trying the next.
+    pub upstream_timeout: Duration,
+    /// Dummy interface the listen address is added to before binding
+    /// (default: none, the address must already be the node's).
+    pub link: Option<String>,
+}
+
+impl Default for DnsCacheConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: DEFAULT_LISTEN_ADDR,
+            upstreams: Vec::new(),
+            max_entries: 10_000,
+            max_ttl: Duration::from_secs(300),
+            negative_ttl: Duration::from_secs(30),
+            upstream_timeout: Duration::from_secs(2),
+            link: None,
+        }
+    }
+}
+
+impl DnsCacheConfig {
+    /// Use the nameservers from a resolv.conf file as upstreams.
+    ///
+    /// The cache's own listen address is skipped so the node's resolv.conf
+    /// can point at the cache without creating a forwarding loop.
+    pub fn with_upstreams_from_resolv_conf(mut self, content: &str) -> Self {
+        let own = self.listen_addr.ip();
+        self.upstreams = parse_resolv_conf(content)
+            .into_iter()
+            .filter(|ip| *ip != own)
+            .map(|ip| SocketAddr::new(ip, 53))
+            .collect();
+        self
+    }
+}
+
+/// Put `address` on the dummy interface `link`, creating it if missing.
+async fn add_listen_address(link: &str, address: IpAddr) -> Result<()> {
+    let exists = Command::new("ip")
+        .args(["link", "show", "dev", link])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .is_ok_and(|status| status.success());
+    let mut commands = Vec::new();
+    if !exists {
+        commands.push(vec![
+            "link".to_string(),
+            "add".to_string(),
+            link.to_string(),
+            "type".to_string(),
+            "dummy".to_string(),
+        ]);
+    }
+    commands.extend(address_commands(link, address));
+
+    for args in commands {
+        let output =
+            Command::new("ip").args(&args).output().await.map_err(|e| {
+                OrchestrationError::NetworkError(format!("Failed to run ip: {}", e))
+            })?;
+        if !output.status.success() {
+            return Err(OrchestrationError::NetworkError(format!(
+                "Failed to add {} to {}: ip {}: {}",
+                address,
+                link,
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `ip` arguments putting `address` on the existing interface `link` and
+/// bringing it up. Re-running them is harmless.
+fn address_commands(link: &str, address: IpAddr) -> Vec<Vec<String>> {
+    let prefix = if address.is_ipv4() { 32 } else { 128 };
+    vec![
+        vec![
+            "addr".to_string(),
+            "replace".to_string(),
+            format!("{}/{}", address, prefix),
+            "dev".to_string(),
+            link.to_string(),
+        ],
+        vec![
+            "link".to_string(),
+            "set".to_string(),
+            "dev".to_string(),
+            link.to_string(),
+            "up".to_string(),
+        ],
+    ]
+}
+
+/// Extract the `nameserver` addresses from resolv.conf content.
+pub fn parse_resolv_conf(content: &str) -> Vec<IpAddr> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("nameserver"), Some(addr)) => addr.parse().ok(),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Receives cache events, e.g. to export them as metrics.
+pub trait DnsCacheObserver: Send + Sync {
+    /// Called for every query, with whether it was answered from cache.
+    fn on_lookup(&self, hit: bool);
+    /// Called for every upstream query made on a miss.
+    fn on_upstream_query(&self, latency: Duration, success: bool);
+}
+
+#[cfg(feature = "observability")]
+impl DnsCacheObserver for observability::OrchestratorMetrics {
+    fn on_lookup(&self, hit: bool) {
+        self.inc_dns_cache_lookups(hit);
+    }
+
+    fn on_upstream_query(&self, latency: Duration, success: bool) {
+        self.record_dns_upstream_query(latency.as_secs_f64(), success);
+    }
+}
+
+/// Running counters for the cache.
+#[derive(Debug, Default)]
+pub struct DnsCacheStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    upstream_queries: AtomicU64,
+    upstream_errors: AtomicU64,
+    upstream_latency_micros: AtomicU64,
+}
+
+/// Point-in-time copy of [`DnsCacheStats`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DnsCacheStatsSnapshot {
+    pub hits: u64,
+    pub misses: u64,
+    pub upstream_queries: u64,
+    pub upstream_errors: u64,
+    /// Mean latency of successful upstream queries.
+    pub avg_upstream_latency: Duration,
+}
+
+impl DnsCacheStatsSnapshot {
+    /// Fraction of lookups answered from cache, 0.0 when there were none.
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+impl DnsCacheStats {
+    pub fn snapshot(&self) -> DnsCacheStatsSnapshot {
+        let upstream_queries = self.upstream_queries.load(Ordering::Relaxed);
+        let upstream_errors = self.upstream_errors.load(Ordering::Relaxed);
+        let successes = upstream_queries.saturating_sub(upstream_errors);
+        let latency_total = self.upstream_latency_micros.load(Ordering::Relaxed);
+        DnsCacheStatsSnapshot {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            upstream_queries,
+            upstream_errors,
+            avg_upstream_latency: latency_total
+                .checked_div(successes)
+                .map_or(Duration::ZERO, Duration::from_micros),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    name: String,
+    qtype: u16,
+    qclass: u16,
+}
+
+struct CacheEntry {
+    response: Vec<u8>,
+    /// Byte offsets of every TTL field in `response`, rewritten on hits.
+    ttl_offsets: Vec<usize>,
+    inserted: Instant,
+    expires: Instant,
+}
+
+/// TTL-bounded cache of raw DNS responses keyed by question.
+pub struct DnsCache {
+    entries: Mutex<HashMap<CacheKey, CacheEntry>>,
+    max_entries: usize,
+    max_ttl: Duration,
+    negative_ttl: Duration,
+}
+
+impl DnsCache {
+    pub fn new(max_entries: usize, max_ttl: Duration, negative_ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_entries,
+            max_ttl,
+            negative_ttl,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return a cached response for `query`, if one is still fresh.
+    pub fn lookup(&self, query: &[u8]) -> Option<Vec<u8>> {
+        self.lookup_at(query, Instant::now())
+    }
+
+    /// Cache `response` as the answer to `query`, if it is cacheable.
+    ///
+    /// Returns whether the response was stored.
+    pub fn insert(&self, query: &[u8], response: &[u8]) -> bool {
+        self.insert_at(query, response, Instant::now())
+    }
+
+    fn lookup_at(&self, query: &[u8], now: Instant) -> Option<Vec<u8>> {
+        let (key, _) = parse_question(query)?;
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(&key)?;
+        if entry.expires <= now {
+            entries.remove(&key);
+            return None;
+        }
+
+        let mut response = entry.response.clone();
+        response[0..2].copy_from_slice(&query[0..2]);
+        let elapsed = now.duration_since(entry.inserted).as_secs() as u32;
+        for &offset in &entry.ttl_offsets {
+            let original = u32::from_be_bytes(response[offset..offset + 4].try_into().ok()?);
+            let remaining = original.saturating_sub(elapsed);
+            response[offset..offset + 4].copy_from_slice(&remaining.to_be_bytes());
+        }
+        Some(response)
+    }
+
+    fn insert_at(&self, query: &[u8], response: &[u8], now: Instant) -> bool {
+        let Some((key, _)) = parse_question(query) else {
+            return false;
+        };
+        let Some(parsed) = parse_response(response) else {
+            return false;
+        };
+        if parsed.key != key || parsed.truncated {
+            return false;
+        }
+
+        let ttl = match parsed.rcode {
+            RCODE_NOERROR if parsed.answer_count > 0 => match parsed.min_ttl {
+                Some(ttl) => Duration::from_secs(ttl as u64).min(self.max_ttl),
+                None => self.negative_ttl,
+            },
+            // NODATA and NXDOMAIN are cached for the negative TTL, or less if
+            // the authority section says so
+            RCODE_NOERROR | RCODE_NXDOMAIN => match parsed.min_ttl {
+                Some(ttl) => Duration::from_secs(ttl as u64).min(self.negative_ttl),
+                None => self.negative_ttl,
+            },
+            _ => return false,
+        };
+        if ttl.is_zero() {
+            return false;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, e| e.expires > now);
+            if entries.len() >= self.max_entries {
+                // Evict the entry closest to expiry
+                if let Some(victim) = entries
+                    .iter()
+                    .min_by_key(|(_, e)| e.expires)
+                    .map(|(k, _)| k.clone())
+                {
+                    entries.remove(&victim);
+                }
+            }
+        }
+        entries.insert(
+            key,
+            CacheEntry {
+                response: response.to_vec(),
+                ttl_offsets: parsed.ttl_offsets,
+                inserted: now,
+                expires: now + ttl,
+            },
+        );
+        true
+    }
+}
+
+/// Caching DNS forwarder serving one node.
+pub struct NodeLocalDnsServer {
+    config: DnsCacheConfig,
+    cache: DnsCache,
+    stats: DnsCacheStats,
+    observer: Option<Arc<dyn DnsCacheObserver>>,
+}
+
+impl NodeLocalDnsServer {
+    pub fn new(config: DnsCacheConfig) -> Self {
+        let cache = DnsCache::new(config.max_entries, config.max_ttl, config.negative_ttl);
+        Self {
+            config,
+            cache,
+            stats: DnsCacheStats::default(),
+            observer: None,
+        }
+    }
+
+    /// Report lookups and upstream latency to `observer`.
+    pub fn with_observer(mut self, observer: Arc<dyn DnsCacheObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    pub fn config(&self) -> &DnsCacheConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> DnsCacheStatsSnapshot {
+        self.stats.snapshot()
+    }
+
+    pub fn cache(&self) -> &DnsCache {
+        &self.cache
+    }
+
+    /// Bind the listen address and serve queries until the task is aborted.
+    pub async fn spawn(self: Arc<Self>) -> Result<JoinHandle<()>> {
+        if self.config.upstreams.is_empty() {
+            return Err(OrchestrationError::ConfigError(
+                "DNS cache has no upstream resolvers".to_string(),
+            ));
+        }
+        if let Some(link) = &self.config.link {
+            add_listen_address(link, self.config.listen_addr.ip()).await?;
+        }
+        let socket = UdpSocket::bind(self.config.listen_addr)
+            .await
+            .map_err(|e| {
+                OrchestrationError::NetworkError(format!(
+                    "Failed to bind DNS cache on {}: {}",
+                    self.config.listen_addr, e
+                ))
+            })?;
+        let socket = Arc::new(socket);
+        info!(
+            "Node-local DNS cache listening on {} (upstreams: {:?})",
+            self.config.listen_addr, self.config.upstreams
+        );
+
+        Ok(tokio::spawn(async move {
+            let mut buf = vec![0u8; MAX_UDP_MESSAGE];
+            loop {
+                let (len, client) = match socket.recv_from(&mut buf).await {
+                    Ok(received) => received,
+                    Err(e) => {
+                        error!("DNS cache receive failed: {}", e);
+                        continue;
+                    }
+                };
+                let query = buf[..len].to_vec();
+                let server = Arc::clone(&self);
+                let socket = Arc::clone(&socket);
+                tokio::spawn(async move {
+                    if let Some(response) = server.resolve(&query).await {
+                        if let Err(e) = socket.send_to(&response, client).await {
+                            debug!("Failed to send DNS response to {}: {}", client, e);
+                        }
+                    }
+                });
+            }
+        }))
+    }
+
+    /// Answer a raw DNS query, from cache or upstream.
+    ///
+    /// Returns `None` for messages too malformed to answer at all.
+    pub async fn resolve(&self, query: &[u8]) -> Option<Vec<u8>> {
+        if query.len() < DNS_HEADER_LEN {
+            return None;
+        }
+
+        let cached = self.cache.lookup(query);
+        let hit = cached.is_some();
+        if hit {
+            self.stats.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.stats.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(observer) = &self.observer {
+            observer.on_lookup(hit);
+        }
+        if hit {
+            return cached;
+        }
+
+        for upstream in &self.config.upstreams {
+            let started = Instant::now();
+            let result = self.query_upstream(*upstream, query).await;
+            let latency = started.elapsed();
+            self.stats.upstream_queries.fetch_add(1, Ordering::Relaxed);
+            if let Some(observer) = &self.observer {
+                observer.on_upstream_query(latency, result.is_ok());
+            }
+            match result {
+                Ok(response) => {
+                    self.stats
+                        .upstream_latency_micros
+                        .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+                    self.cache.insert(query, &response);
+                    return Some(response);
+                }
+                Err(e) => {
+                    self.stats.upstream_errors.fetch_add(1, Ordering::Relaxed);
+                    warn!("Upstream DNS {} failed: {}", upstream, e);
+                }
+            }
+        }
+        Some(servfail(query))
+    }
+
+    async fn query_upstream(&self, upstream: SocketAddr, query: &[u8]) -> std::io::Result<Vec<u8>> {
+        let bind_addr = SocketAddr::new(IpFamily::of(&upstream.ip()).unspecified(), 0);
+        let socket = UdpSocket::bind(bind_addr).await?;
+        socket.connect(upstream).await?;
+        socket.send(query).await?;
+
+        let mut buf = vec![0u8; MAX_UDP_MESSAGE];
+        let deadline = tokio::time::Instant::now() + self.config.upstream_timeout;
+        loop {
+            let len = tokio::time::timeout_at(deadline, socket.recv(&mut buf))
+                .await
+                .map_err(|_| {
+                    std::io::Error::new(std::io::ErrorKind::TimedOut, "upstream timed out")
+                })??;
+            // Ignore stray datagrams that don't answer this query
+            if len >= DNS_HEADER_LEN && buf[0..2] == query[0..2] {
+                buf.truncate(len);
+                return Ok(buf);
+            }
+        }
+    }
+}
+
+struct ParsedResponse {
+    key: CacheKey,
+    rcode: u8,
+    truncated: bool,
+    answer_count: u16,
+    ttl_offsets: Vec<usize>,
+    min_ttl: Option<u32>,
+}
+
+fn read_u16(msg: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*msg.get(pos)?, *msg.get(pos + 1)?]))
+}
+
+/// Skip a possibly-compressed domain name, returning the offset after it.
+fn skip_name(msg: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *msg.get(pos)? as usize;
+        if len == 0 {
+            return Some(pos + 1);
+        }
+        if len & 0xC0 == 0xC0 {
+            return Some(pos + 2);
+        }
+        pos += 1 + len;
+    }
+}
+
+/// Parse the single question of a message into a cache key.
+fn parse_question(msg: &[u8]) -> Option<(CacheKey, usize)> {
+    if msg.len() < DNS_HEADER_LEN || read_u16(msg, 4)? != 1 {
+        return None;
+    }
+    let mut pos = DNS_HEADER_LEN;
+    let mut labels = Vec::new();
+    loop {
+        let len = *msg.get(pos)? as usize;
+        if len == 0 {
+            pos += 1;
+            break;
+        }
+        // Questions are never compressed in practice; refuse rather than chase pointers
+        if len & 0xC0 != 0 {
+            return None;
+        }
+        let label = msg.get(pos + 1..pos + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+        pos += 1 + len;
+    }
+    let key = CacheKey {
+        name: labels.join("."),
+        qtype: read_u16(msg, pos)?,
+        qclass: read_u16(msg, pos + 2)?,
+    };
+    Some((key, pos + 4))
+}
+
+fn parse_response(msg: &[u8]) -> Option<ParsedResponse> {
+    let (key, mut pos) = parse_question(msg)?;
+    let flags = msg[2];
+    if flags & 0x80 == 0 {
+        // Not a response
+        return None;
+    }
+    let answer_count = read_u16(msg, 6)?;
+    let record_count =
+        answer_count as usize + read_u16(msg, 8)? as usize + read_u16(msg, 10)? as usize;
+
+    let mut ttl_offsets = Vec::new();
+    let mut min_ttl: Option<u32> = None;
+    for _ in 0..record_count {
+        pos = skip_name(msg, pos)?;
+        let rtype = read_u16(msg, pos)?;
+        let ttl_offset = pos + 4;
+        let rdlength = read_u16(msg, pos + 8)? as usize;
+        if rtype != TYPE_OPT {
+            let ttl = u32::from_be_bytes(msg.get(ttl_offset..ttl_offset + 4)?.try_into().ok()?);
+            ttl_offsets.push(ttl_offset);
+            min_ttl = Some(min_ttl.map_or(ttl, |m| m.min(ttl)));
+        }
+        pos += 10 + rdlength;
+        if pos > msg.len() {
+            return None;
+        }
+    }
+
+    Some(ParsedResponse {
+        key,
+        rcode: msg[3] & 0x0F,
+        truncated: flags & 0x02 != 0,
+        answer_count,
+        ttl_offsets,
+        min_ttl,
+    })
+}
+
+/// Build a SERVFAIL response echoing the query's header and question.
+fn servfail(query: &[u8]) -> Vec<u8> {
+    let question_end = parse_question(query).map_or(DNS_HEADER_LEN, |(_, end)| end);
+    let mut response = query[..question_end.min(query.len())].to_vec();
+    response[2] |= 0x80; // QR
+    response[3] = (response[3] & 0xF0) | 0x80 | RCODE_SERVFAIL; // RA + rcode
+    response[6..12].fill(0); // no answer, authority or additional records
+    if question_end == DNS_HEADER_LEN {
+        response[4..6].fill(0);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(id: u16, name: &str) -> Vec<u8> {
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&id.to_be_bytes());
+        msg.extend_from_slice(&[0x01, 0x00]); // RD
+        msg.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+        for label in name.split('.') {
+            msg.push(label.len() as u8);
+            msg.extend_from_slice(label.as_bytes());
+        }
+        msg.push(0);
+        msg.extend_from_slice(&[0, 1, 0, 1]); // A, IN
+        msg
+    }
+
+    fn a_response(query: &[u8], ttl: u32, addr: [u8; 4]) -> Vec<u8> {
+        let mut msg = query.to_vec();
+        msg[2] = 0x81; // QR + RD
+        msg[3] = 0x80; // RA, NOERROR
+        msg[6..8].copy_from_slice(&1u16.to_be_bytes());
+        msg.extend_from_slice(&[0xC0, 0x0C]); // pointer to question name
+        msg.extend_from_slice(&[0, 1, 0, 1]);
+        msg.extend_from_slice(&ttl.to_be_bytes());
+        msg.extend_from_slice(&[0, 4]);
+        msg.extend_from_slice(&addr);
+        msg
+    }
+
+    fn nxdomain(query: &[u8]) -> Vec<u8> {
+        let mut msg = query.to_vec();
+        msg[2] = 0x81;
+        msg[3] = 0x80 | RCODE_NXDOMAIN;
+        msg
+    }
+
+    fn ttl_of(response: &[u8]) -> u32 {
+        let parsed = parse_response(response).unwrap();
+        let offset = parsed.ttl_offsets[0];
+        u32::from_be_bytes(response[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn cache() -> DnsCache {
+        DnsCache::new(100, Duration::from_secs(300), Duration::from_secs(30))
+    }
+
+    #[test]
+    fn test_hit_rewrites_id_and_ttl() {
+        let cache = cache();
+        let q = query(1, "api.example.com");
+        let now = Instant::now();
+        assert!(cache.insert_at(&q, &a_response(&q, 60, [10, 0, 0, 1]), now));
+
+        let q2 = query(99, "API.example.com");
+        let hit = cache.lookup_at(&q2, now + Duration::from_secs(15)).unwrap();
+        assert_eq!(&hit[0..2], &99u16.to_be_bytes());
+        assert_eq!(ttl_of(&hit), 45);
+    }
+
+    #[test]
+    fn test_entry_expires_with_ttl() {
+        let cache = cache();
+        let q = query(1, "short.example.com");
+        let now = Instant::now();
+        cache.insert_at(&q, &a_response(&q, 5, [10, 0, 0, 2]), now);
+        assert!(cache.lookup_at(&q, now + Duration::from_secs(4)).is_some());
+        assert!(cache.lookup_at(&q, now + Duration::from_secs(5)).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_ttl_capped_and_negative_cached() {
+        let cache = cache();
+        let now = Instant::now();
+
+        let q = query(1, "long.example.com");
+        cache.insert_at(&q, &a_response(&q, 86_400, [10, 0, 0, 3]), now);
+        assert!(cache
+            .lookup_at(&q, now + Duration::from_secs(301))
+            .is_none());
+
+        let q = query(2, "missing.example.com");
+        assert!(cache.insert_at(&q, &nxdomain(&q), now));
+        assert!(cache.lookup_at(&q, now + Duration::from_secs(29)).is_some());
+        assert!(cache.lookup_at(&q, now + Duration::from_secs(30)).is_none());
+    }
+
+    #[test]
+    fn test_uncacheable_responses_rejected() {
+        let cache = cache();
+        let q = query(1, "a.example.com");
+
+        let mut truncated = a_response(&q, 60, [10, 0, 0, 4]);
+        truncated[2] |= 0x02;
+        assert!(!cache.insert(&q, &truncated));
+
+        let mut servfail_response = a_response(&q, 60, [10, 0, 0, 4]);
+        servfail_response[3] = 0x80 | RCODE_SERVFAIL;
+        assert!(!cache.insert(&q, &servfail_response));
+
+        let other = query(1, "b.example.com");
+        assert!(!cache.insert(&q, &a_response(&other, 60, [10, 0, 0, 4])));
+    }
+
+    #[test]
+    fn test_eviction_respects_max_entries() {
+        let cache = DnsCache::new(2, Duration::from_secs(300), Duration::from_secs(30));
+        let now = Instant::now();
+        for (i, name) in ["a.test", "b.test", "c.test"].iter().enumerate() {
+            let q = query(1, name);
+            cache.insert_at(&q, &a_response(&q, 60 + i as u32, [10, 0, 0, 1]), now);
+        }
+        assert_eq!(cache.len(), 2);
+        // The entry closest to expiry was evicted
+        assert!(cache.lookup_at(&query(1, "a.test"), now).is_none());
+    }
+
+    #[test]
+    fn test_address_commands() {
+        let commands = address_commands(LINK_NAME, DEFAULT_LISTEN_ADDR.ip());
+        assert_eq!(
+            commands
+                .iter()
+                .map(|args| args.join(" "))
+                .collect::<Vec<_>>(),
+            [
+                "addr replace 169.254.20.10/32 dev nodelocaldns",
+                "link set dev nodelocaldns up",
+            ]
+        );
+        let commands = address_commands(LINK_NAME, "fd00::a".parse().unwrap());
+        assert_eq!(commands[0][2], "fd00::a/128");
+    }
+
+    #[test]
+    fn test_parse_resolv_conf() {
+        let content = "# comment\nsearch svc.local\nnameserver 10.0.0.2\nnameserver fd00::53\noptions ndots:5\n";
+        assert_eq!(
+            parse_resolv_conf(content),
+            vec![
+                "10.0.0.2".parse::<IpAddr>().unwrap(),
+                "fd00::53".parse().unwrap()
+            ]
+        );
+
+        let config = DnsCacheConfig::default()
+            .with_upstreams_from_resolv_conf("nameserver 169.254.20.10\nnameserver 1.1.1.1\n");
+        assert_eq!(
+            config.upstreams,
+            vec!["1.1.1.1:53".parse::<SocketAddr>().unwrap()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_forwards_misses_and_serves_hits() {
+        // Fake upstream answering every query with a fixed A record
+        let upstream = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = upstream.local_addr().unwrap();
+        let upstream_task = tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            loop {
+                let (len, from) = upstream.recv_from(&mut buf).await.unwrap();
+                let reply = a_response(&buf[..len], 120, [192, 0, 2, 1]);
+                upstream.send_to(&reply, from).await.unwrap();
+            }
+        });
+
+        let server = NodeLocalDnsServer::new(DnsCacheConfig {
+            upstreams: vec![upstream_addr],
+            ..Default::default()
+        });
+
+        let first = server.resolve(&query(7, "svc.example.com")).await.unwrap();
+        assert_eq!(&first[0..2], &7u16.to_be_bytes());
+        let second = server.resolve(&query(8, "svc.example.com")).await.unwrap();
+        assert_eq!(&second[0..2], &8u16.to_be_bytes());
+
+        let stats = server.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.upstream_queries, 1);
+        assert_eq!(stats.hit_ratio(), 0.5);
+        upstream_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_server_returns_servfail_when_upstreams_fail() {
+        // Bound but never answers
+        let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let server = NodeLocalDnsServer::new(DnsCacheConfig {
+            upstreams: vec![silent.local_addr().unwrap()],
+            upstream_timeout: Duration::from_millis(50),
+            ..Default::default()
+        });
+
+        let response = server.resolve(&query(3, "down.example.com")).await.unwrap();
+        assert_eq!(response[3] & 0x0F, RCODE_SERVFAIL);
+        assert_eq!(server.stats().upstream_errors, 1);
+        assert!(server.cache().is_empty());
+    }
+}
@@ -0,0 +1,386 @@
+//! Service traffic forwarding.
+//!
+//! The [`ServiceForwarder`] carries connections to a service's virtual IPs to
+//! the endpoint [`ServiceProxy::select_endpoint`] picks for each of them, so
+//! weighted round-robin and client-IP affinity apply to real traffic. For
+//! every TCP port of every service it listens on a local port of its own, and
+//! an nftables rule redirects the virtual IP and service port there. Each
+//! accepted connection is relayed to the chosen endpoint's address of the
+//! virtual IP's family, at the port's target port.
+//!
+//! Redirecting keeps the source address, so the client IP affinity is keyed
+//! on is the one the connection came from. The rules live in one table that
+//! is replaced whenever the listeners change. UDP ports are not forwarded.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use orchestrator_shared_types::{IpFamily, OrchestrationError, Result};
+
+use super::policy::Firewall;
+use super::proxy::ServiceProxy;
+use super::service::ServiceId;
+
+/// Name of the nftables table service traffic is redirected in.
+pub const NFT_TABLE: &str = "orchestrator_services";
+
+/// One TCP port of one virtual IP of a service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct ForwardKey {
+    service_id: ServiceId,
+    cluster_ip: IpAddr,
+    port: u16,
+    target_port: u16,
+}
+
+/// A local listener relaying the connections of one [`ForwardKey`].
+struct Listener {
+    /// `namespace/name` of the service, for the ruleset.
+    service: String,
+    local_port: u16,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Relays connections to service virtual IPs to the endpoints selected for them.
+pub struct ServiceForwarder {
+    proxy: Arc<ServiceProxy>,
+    firewall: Arc<dyn Firewall>,
+    listeners: Mutex<BTreeMap<ForwardKey, Listener>>,
+    /// Last ruleset installed, so unchanged rulesets are not applied again.
+    applied: Mutex<Option<String>>,
+}
+
+impl ServiceForwarder {
+    /// How often listeners and rules are brought up to date with the services.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(proxy: Arc<ServiceProxy>, firewall: Arc<dyn Firewall>) -> Self {
+        Self {
+            proxy,
+            firewall,
+            listeners: Mutex::new(BTreeMap::new()),
+            applied: Mutex::new(None),
+        }
+    }
+
+    /// Listen for every TCP port of the registered services, stop listening
+    /// for ports that are gone, and install the matching ruleset.
+    pub async fn sync(&self) -> Result<()> {
+        let mut wanted = BTreeMap::new();
+        for service in self.proxy.list_services() {
+            for port in &service.ports {
+                if !port.protocol.eq_ignore_ascii_case("tcp") {
+                    debug!(
+                        "Service {}/{}: {} port {} is not forwarded",
+                        service.namespace, service.name, port.protocol, port.port
+                    );
+                    continue;
+                }
+                for &cluster_ip in &service.cluster_ips {
+                    let key = ForwardKey {
+                        service_id: service.id,
+                        cluster_ip,
+                        port: port.port,
+                        target_port: port.target_port,
+                    };
+                    wanted.insert(key, format!("{}/{}", service.namespace, service.name));
+                }
+            }
+        }
+
+        let ruleset = {
+            let mut listeners = self.listeners.lock().await;
+            listeners.retain(|key, _| wanted.contains_key(key));
+            for (key, service) in wanted {
+                if listeners.contains_key(&key) {
+                    continue;
+                }
+                match self.listen(key, service).await {
+                    Ok(listener) => {
+                        listeners.insert(key, listener);
+                    }
+                    Err(e) => error!("Failed to forward {}:{}: {}", key.cluster_ip, key.port, e),
+                }
+            }
+            render_ruleset(&listeners)
+        };
+
+        let mut applied = self.applied.lock().await;
+        if applied.as_deref() == Some(ruleset.as_str()) {
+            return Ok(());
+        }
+        self.firewall.apply(&ruleset).await?;
+        *applied = Some(ruleset);
+        Ok(())
+    }
+
+    /// Sync every `interval`, as services are added, changed and removed.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.sync().await {
+                    error!("Failed to forward service traffic: {}", e);
+                }
+            }
+        })
+    }
+
+    async fn listen(&self, key: ForwardKey, service: String) -> Result<Listener> {
+        let any = match IpFamily::of(&key.cluster_ip) {
+            IpFamily::IPv4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpFamily::IPv6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        };
+        let listener = TcpListener::bind(SocketAddr::new(any, 0))
+            .await
+            .map_err(|e| OrchestrationError::NetworkError(format!("Failed to listen: {}", e)))?;
+        let local_port = listener
+            .local_addr()
+            .map_err(|e| OrchestrationError::NetworkError(e.to_string()))?
+            .port();
+        info!(
+            "Forwarding {} {} to local port {}",
+            service,
+            SocketAddr::new(key.cluster_ip, key.port),
+            local_port
+        );
+
+        let proxy = self.proxy.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((inbound, client)) => {
+                        tokio::spawn(relay(proxy.clone(), key, inbound, client));
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to accept a connection for {}: {}",
+                            key.cluster_ip, e
+                        );
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+            }
+        });
+        Ok(Listener {
+            service,
+            local_port,
+            task,
+        })
+    }
+}
+
+/// Relay one connection to the endpoint selected for its client.
+async fn relay(
+    proxy: Arc<ServiceProxy>,
+    key: ForwardKey,
+    mut inbound: TcpStream,
+    client: SocketAddr,
+) {
+    let Some(endpoint) = proxy.select_endpoint(&key.service_id, client.ip()) else {
+        debug!(
+            "No ready endpoint for {}:{}, dropping {}",
+            key.cluster_ip, key.port, client
+        );
+        return;
+    };
+    let family = IpFamily::of(&key.cluster_ip);
+    let Some(address) = endpoint
+        .addresses
+        .iter()
+        .find(|a| IpFamily::of(a) == family)
+        .or(endpoint.addresses.first())
+    else {
+        warn!("Endpoint {} has no address", endpoint.instance_id);
+        return;
+    };
+    let target = SocketAddr::new(*address, key.target_port);
+    let mut outbound = match TcpStream::connect(target).await {
+        Ok(outbound) => outbound,
+        Err(e) => {
+            warn!(
+                "Failed to connect {} to endpoint {} at {}: {}",
+                client, endpoint.instance_id, target, e
+            );
+            return;
+        }
+    };
+    if let Err(e) = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await {
+        debug!("Connection from {} to {} ended: {}", client, target, e);
+    }
+}
+
+/// Render the nftables table redirecting each virtual IP and port to its listener.
+///
+/// The table is deleted and recreated in one transaction, so applying the
+/// result replaces the previous ruleset atomically.
+fn render_ruleset(listeners: &BTreeMap<ForwardKey, Listener>) -> String {
+    let mut rules = String::new();
+    for (key, listener) in listeners {
+        let nat = match IpFamily::of(&key.cluster_ip) {
+            IpFamily::IPv4 => "ip",
+            IpFamily::IPv6 => "ip6",
+        };
+        let _ = writeln!(rules, "\t\t# {}", listener.service);
+        let _ = writeln!(
+            rules,
+            "\t\t{} daddr {} tcp dport {} redirect to :{}",
+            nat, key.cluster_ip, key.port, listener.local_port
+        );
+    }
+
+    let mut ruleset = format!("table inet {0}\ndelete table inet {0}\n", NFT_TABLE);
+    let _ = writeln!(ruleset, "table inet {} {{", NFT_TABLE);
+    for (chain, hook, priority) in [
+        ("prerouting", "prerouting", "dstnat"),
+        ("output", "output", "-100"),
+    ] {
+        let _ = writeln!(ruleset, "\tchain {} {{", chain);
+        let _ = writeln!(
+            ruleset,
+            "\t\ttype nat hook {} priority {}; policy accept;",
+            hook, priority
+        );
+        let _ = writeln!(ruleset, "\t\tjump services");
+        let _ = writeln!(ruleset, "\t}}");
+    }
+    let _ = writeln!(ruleset, "\tchain services {{");
+    ruleset.push_str(&rules);
+    let _ = writeln!(ruleset, "\t}}");
+    ruleset.push_str("}\n");
+    ruleset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use orchestrator_shared_types::{
+        Keypair, WorkloadDefinition, WorkloadInstance, WorkloadInstanceStatus,
+    };
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use uuid::Uuid;
+
+    use crate::network::Service;
+
+    #[derive(Default)]
+    struct FakeFirewall {
+        applied: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Firewall for FakeFirewall {
+        async fn apply(&self, ruleset: &str) -> Result<()> {
+            self.applied.lock().unwrap().push(ruleset.to_string());
+            Ok(())
+        }
+    }
+
+    /// A backend on loopback answering every connection with `name`.
+    async fn backend(name: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let _ = stream.write_all(name.as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    async fn put_backend(store: &InMemoryStateStore) {
+        let workload = WorkloadDefinition {
+            id: Uuid::new_v4(),
+            name: "web".to_string(),
+            containers: vec![],
+            replicas: 1,
+            labels: HashMap::from([("app".to_string(), "web".to_string())]),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        store
+            .put_instance(WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id: workload.id,
+                node_id: Keypair::generate().public_key(),
+                container_ids: vec![],
+                status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec!["127.0.0.1".parse().unwrap()],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
+            })
+            .await
+            .unwrap();
+        store.put_workload(workload).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_forwards_connections_to_selected_endpoint() {
+        let store = Arc::new(InMemoryStateStore::new());
+        put_backend(&store).await;
+        let target_port = backend("web").await;
+        let proxy = Arc::new(ServiceProxy::new(store.clone()));
+        let mut service = Service::new(
+            "web",
+            HashMap::from([("app".to_string(), "web".to_string())]),
+        )
+        .with_port(80, target_port, "tcp")
+        .with_port(53, 53, "udp");
+        service.cluster_ips = vec!["10.96.0.10".parse().unwrap()];
+        let service = proxy.add_service(service).await.unwrap();
+
+        let firewall = Arc::new(FakeFirewall::default());
+        let forwarder = ServiceForwarder::new(proxy.clone(), firewall.clone());
+        forwarder.sync().await.unwrap();
+        forwarder.sync().await.unwrap();
+
+        let local_port = {
+            let listeners = forwarder.listeners.lock().await;
+            assert_eq!(listeners.len(), 1, "only the TCP port is forwarded");
+            listeners.values().next().unwrap().local_port
+        };
+        {
+            let applied = firewall.applied.lock().unwrap();
+            assert_eq!(applied.len(), 1);
+            assert!(applied[0].contains(&format!(
+                "ip daddr 10.96.0.10 tcp dport 80 redirect to :{}",
+                local_port
+            )));
+        }
+
+        let mut stream = TcpStream::connect(("127.0.0.1", local_port)).await.unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).await.unwrap();
+        assert_eq!(reply, "web");
+
+        proxy.remove_service(&service.id).await.unwrap();
+        forwarder.sync().await.unwrap();
+        assert!(forwarder.listeners.lock().await.is_empty());
+        let applied = firewall.applied.lock().unwrap();
+        assert_eq!(applied.len(), 2);
+        assert!(!applied[1].contains("redirect"));
+    }
+}
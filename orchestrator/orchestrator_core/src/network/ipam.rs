@@ -0,0 +1,722 @@
+//! IP address management.
+//!
+//! [`IpPool`] hands out addresses from a single CIDR range.
+//! [`DualStackAllocator`] pairs an IPv4 and an IPv6 pool and allocates
+//! according to an [`IpFamilyPolicy`]. The family of the first configured
+//! range is the cluster's primary family, used for single-stack allocations.
+//! [`InstanceAddressAllocator`] gives each new instance one address per
+//! family, tracks which instance holds them and takes them back once that
+//! instance is deleted.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use orchestrator_shared_types::{
+    IpFamily, IpFamilyPolicy, OrchestrationError, Result, WorkloadInstance,
+};
+use state_store_interface::{StateStore, WatchEventType};
+
+/// An IPv4 or IPv6 network in CIDR notation, e.g. `10.96.0.0/12` or `fd00:10:96::/112`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl Cidr {
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self> {
+        let max = Self::max_prefix(IpFamily::of(&addr));
+        if prefix_len > max {
+            return Err(OrchestrationError::NetworkError(format!(
+                "prefix length /{} exceeds /{} for {}",
+                prefix_len, max, addr
+            )));
+        }
+        // Mask off host bits so the stored address is the network address
+        let bits = to_bits(&addr) & Self::mask(max, prefix_len);
+        Ok(Self {
+            network: from_bits(IpFamily::of(&addr), bits),
+            prefix_len,
+        })
+    }
+
+    pub fn family(&self) -> IpFamily {
+        IpFamily::of(&self.network)
+    }
+
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        if IpFamily::of(addr) != self.family() {
+            return false;
+        }
+        let mask = Self::mask(Self::max_prefix(self.family()), self.prefix_len);
+        to_bits(addr) & mask == to_bits(&self.network)
+    }
+
+    /// Number of host bits in the range.
+    fn host_bits(&self) -> u32 {
+        (Self::max_prefix(self.family()) - self.prefix_len) as u32
+    }
+
+    fn max_prefix(family: IpFamily) -> u8 {
+        match family {
+            IpFamily::IPv4 => 32,
+            IpFamily::IPv6 => 128,
+        }
+    }
+
+    fn mask(max: u8, prefix_len: u8) -> u128 {
+        if prefix_len == 0 {
+            return 0;
+        }
+        let full = if max == 32 {
+            u32::MAX as u128
+        } else {
+            u128::MAX
+        };
+        full & !full.checked_shr(prefix_len as u32).unwrap_or(0)
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = OrchestrationError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (addr, prefix) = s
+            .split_once('/')
+            .ok_or_else(|| OrchestrationError::NetworkError(format!("invalid CIDR '{}'", s)))?;
+        let addr: IpAddr = addr.parse().map_err(|_| {
+            OrchestrationError::NetworkError(format!("invalid address in CIDR '{}'", s))
+        })?;
+        let prefix: u8 = prefix.parse().map_err(|_| {
+            OrchestrationError::NetworkError(format!("invalid prefix in CIDR '{}'", s))
+        })?;
+        Self::new(addr, prefix)
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+fn to_bits(addr: &IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(v4) => u32::from(*v4) as u128,
+        IpAddr::V6(v6) => u128::from(*v6),
+    }
+}
+
+fn from_bits(family: IpFamily, bits: u128) -> IpAddr {
+    match family {
+        IpFamily::IPv4 => IpAddr::V4(Ipv4Addr::from(bits as u32)),
+        IpFamily::IPv6 => IpAddr::V6(Ipv6Addr::from(bits)),
+    }
+}
+
+/// Allocates individual addresses from one CIDR range.
+///
+/// The network address is never handed out, nor is the broadcast address of
+/// IPv4 ranges.
+#[derive(Debug, Clone)]
+pub struct IpPool {
+    cidr: Cidr,
+    allocated: HashSet<u128>,
+    /// Offset to try next, so allocation does not rescan from the start.
+    cursor: u128,
+}
+
+impl IpPool {
+    pub fn new(cidr: Cidr) -> Self {
+        Self {
+            cidr,
+            allocated: HashSet::new(),
+            cursor: 1,
+        }
+    }
+
+    pub fn cidr(&self) -> &Cidr {
+        &self.cidr
+    }
+
+    /// Number of addresses currently allocated.
+    pub fn allocated_count(&self) -> usize {
+        self.allocated.len()
+    }
+
+    /// Addresses currently allocated, in no particular order.
+    pub fn allocated(&self) -> impl Iterator<Item = IpAddr> + '_ {
+        let network = to_bits(&self.cidr.network);
+        self.allocated
+            .iter()
+            .map(move |offset| from_bits(self.cidr.family(), network + offset))
+    }
+
+    fn last_offset(&self) -> u128 {
+        let host_bits = self.cidr.host_bits();
+        let size = if host_bits >= 128 {
+            u128::MAX
+        } else {
+            (1u128 << host_bits) - 1
+        };
+        match self.cidr.family() {
+            // Skip the broadcast address, except in /31 and /32 ranges
+            IpFamily::IPv4 if host_bits > 1 => size - 1,
+            _ => size,
+        }
+    }
+
+    /// Allocate the next free address.
+    pub fn allocate(&mut self) -> Result<IpAddr> {
+        let last = self.last_offset();
+        if last == 0 {
+            return Err(OrchestrationError::NetworkError(format!(
+                "range {} has no allocatable addresses",
+                self.cidr
+            )));
+        }
+        let start = self.cursor.clamp(1, last);
+        let mut offset = start;
+        loop {
+            if !self.allocated.contains(&offset) {
+                self.allocated.insert(offset);
+                self.cursor = if offset == last { 1 } else { offset + 1 };
+                return Ok(from_bits(
+                    self.cidr.family(),
+                    to_bits(&self.cidr.network) + offset,
+                ));
+            }
+            offset = if offset == last { 1 } else { offset + 1 };
+            if offset == start {
+                return Err(OrchestrationError::NetworkError(format!(
+                    "range {} is exhausted",
+                    self.cidr
+                )));
+            }
+        }
+    }
+
+    /// Reserve a specific address. Fails if it is outside the range or taken.
+    pub fn reserve(&mut self, addr: &IpAddr) -> Result<()> {
+        if !self.cidr.contains(addr) {
+            return Err(OrchestrationError::NetworkError(format!(
+                "{} is not in range {}",
+                addr, self.cidr
+            )));
+        }
+        let offset = to_bits(addr) - to_bits(&self.cidr.network);
+        if offset == 0 || offset > self.last_offset() {
+            return Err(OrchestrationError::NetworkError(format!(
+                "{} is reserved in range {}",
+                addr, self.cidr
+            )));
+        }
+        if !self.allocated.insert(offset) {
+            return Err(OrchestrationError::NetworkError(format!(
+                "{} is already allocated",
+                addr
+            )));
+        }
+        Ok(())
+    }
+
+    /// Return an address to the pool. Addresses outside the range are ignored.
+    pub fn release(&mut self, addr: &IpAddr) {
+        if self.cidr.contains(addr) {
+            self.allocated
+                .remove(&(to_bits(addr) - to_bits(&self.cidr.network)));
+        }
+    }
+}
+
+/// Allocates addresses from up to one IPv4 and one IPv6 range.
+#[derive(Debug, Clone)]
+pub struct DualStackAllocator {
+    /// Pools in family preference order; the first is the primary family.
+    pools: Vec<IpPool>,
+}
+
+impl DualStackAllocator {
+    /// Build an allocator from one or two CIDR ranges of different families.
+    pub fn new(ranges: &[Cidr]) -> Result<Self> {
+        if ranges.is_empty() || ranges.len() > 2 {
+            return Err(OrchestrationError::NetworkError(
+                "expected one or two CIDR ranges".to_string(),
+            ));
+        }
+        if ranges.len() == 2 && ranges[0].family() == ranges[1].family() {
+            return Err(OrchestrationError::NetworkError(
+                "dual-stack ranges must be one IPv4 and one IPv6 range".to_string(),
+            ));
+        }
+        Ok(Self {
+            pools: ranges.iter().copied().map(IpPool::new).collect(),
+        })
+    }
+
+    /// The cluster's primary address family.
+    pub fn primary_family(&self) -> IpFamily {
+        self.pools[0].cidr().family()
+    }
+
+    pub fn is_dual_stack(&self) -> bool {
+        self.pools.len() == 2
+    }
+
+    pub fn families(&self) -> Vec<IpFamily> {
+        self.pools.iter().map(|p| p.cidr().family()).collect()
+    }
+
+    /// Allocate addresses for the given policy, primary family first.
+    pub fn allocate(&mut self, policy: IpFamilyPolicy) -> Result<Vec<IpAddr>> {
+        let count = match policy {
+            IpFamilyPolicy::SingleStack => 1,
+            IpFamilyPolicy::PreferDualStack => self.pools.len(),
+            IpFamilyPolicy::RequireDualStack => {
+                if !self.is_dual_stack() {
+                    return Err(OrchestrationError::NetworkError(
+                        "RequireDualStack requested on a single-stack cluster".to_string(),
+                    ));
+                }
+                2
+            }
+        };
+
+        let mut addresses = Vec::with_capacity(count);
+        let mut failure = None;
+        for pool in self.pools.iter_mut().take(count) {
+            match pool.allocate() {
+                Ok(addr) => addresses.push(addr),
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        if let Some(e) = failure {
+            // Don't leak a partial allocation
+            self.release_all(&addresses);
+            return Err(e);
+        }
+        Ok(addresses)
+    }
+
+    /// Allocate a single address from a specific family.
+    pub fn allocate_family(&mut self, family: IpFamily) -> Result<IpAddr> {
+        self.pools
+            .iter_mut()
+            .find(|p| p.cidr().family() == family)
+            .ok_or_else(|| {
+                OrchestrationError::NetworkError(format!("no {:?} range configured", family))
+            })?
+            .allocate()
+    }
+
+    /// Reserve a specific address in the range holding it.
+    pub fn reserve(&mut self, addr: &IpAddr) -> Result<()> {
+        self.pools
+            .iter_mut()
+            .find(|p| p.cidr().contains(addr))
+            .ok_or_else(|| {
+                OrchestrationError::NetworkError(format!("{} is in no configured range", addr))
+            })?
+            .reserve(addr)
+    }
+
+    /// Addresses currently allocated from every range.
+    pub fn allocated(&self) -> Vec<IpAddr> {
+        self.pools.iter().flat_map(IpPool::allocated).collect()
+    }
+
+    pub fn release_all(&mut self, addresses: &[IpAddr]) {
+        for addr in addresses {
+            for pool in &mut self.pools {
+                pool.release(addr);
+            }
+        }
+    }
+}
+
+/// Who holds an allocated instance address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Holder {
+    /// Handed out, its instance not started or stored yet.
+    Pending(Instant),
+    Instance(Uuid),
+}
+
+struct InstanceAddresses {
+    allocator: DualStackAllocator,
+    holders: HashMap<IpAddr, Holder>,
+    /// Whether the stored instances have been read since startup.
+    synced: bool,
+}
+
+impl InstanceAddresses {
+    fn release(&mut self, addresses: &[IpAddr]) {
+        for addr in addresses {
+            self.holders.remove(addr);
+        }
+        self.allocator.release_all(addresses);
+    }
+
+    /// Bring the tracked addresses in line with the stored instances.
+    fn sync(&mut self, instances: &[WorkloadInstance]) {
+        let stored: HashSet<Uuid> = instances.iter().map(|instance| instance.id).collect();
+        let released: Vec<IpAddr> = self
+            .holders
+            .iter()
+            .filter(|(_, holder)| match holder {
+                Holder::Pending(at) => at.elapsed() >= InstanceAddressAllocator::RECLAIM_GRACE,
+                Holder::Instance(id) => !stored.contains(id),
+            })
+            .map(|(addr, _)| *addr)
+            .collect();
+        if !released.is_empty() {
+            debug!("Reclaiming {} instance addresses", released.len());
+            self.release(&released);
+        }
+        for instance in instances {
+            for addr in &instance.ip_addresses {
+                // Taken already, or outside the ranges after a reconfiguration
+                let _ = self.allocator.reserve(addr);
+                self.holders.insert(*addr, Holder::Instance(instance.id));
+            }
+        }
+        self.synced = true;
+    }
+}
+
+/// Allocates instance addresses, one per configured family.
+///
+/// Allocations are tracked in memory. The stored instances are read once,
+/// before the first allocation, so addresses held before a restart or
+/// handed out by a previous leader are not reused. After that the
+/// orchestrator reports what happened to each allocation through
+/// [`Self::assign`] and [`Self::release`], and [`Self::spawn`] reclaims the
+/// addresses of instances deleted anywhere in the cluster. An address
+/// without a stored instance stays taken for [`Self::RECLAIM_GRACE`].
+pub struct InstanceAddressAllocator {
+    state_store: Arc<dyn StateStore>,
+    inner: Mutex<InstanceAddresses>,
+}
+
+impl InstanceAddressAllocator {
+    /// How long an address handed out is kept without a stored instance.
+    pub const RECLAIM_GRACE: Duration = Duration::from_secs(300);
+    /// How often the tracked addresses are checked against the store.
+    pub const DEFAULT_RESYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+    pub fn new(state_store: Arc<dyn StateStore>, allocator: DualStackAllocator) -> Self {
+        Self {
+            state_store,
+            inner: Mutex::new(InstanceAddresses {
+                allocator,
+                holders: HashMap::new(),
+                synced: false,
+            }),
+        }
+    }
+
+    /// Allocate addresses for a new instance, primary family first.
+    pub async fn allocate(&self) -> Result<Vec<IpAddr>> {
+        let mut inner = self.inner.lock().await;
+        if !inner.synced {
+            let instances = self.state_store.list_all_instances().await?;
+            inner.sync(&instances);
+        }
+        let addresses = inner.allocator.allocate(IpFamilyPolicy::PreferDualStack)?;
+        for addr in &addresses {
+            inner.holders.insert(*addr, Holder::Pending(Instant::now()));
+        }
+        Ok(addresses)
+    }
+
+    /// Record that `addresses` belong to the stored instance `instance_id`.
+    pub async fn assign(&self, instance_id: Uuid, addresses: &[IpAddr]) {
+        let mut inner = self.inner.lock().await;
+        for addr in addresses {
+            inner.holders.insert(*addr, Holder::Instance(instance_id));
+        }
+    }
+
+    /// Give back addresses whose instance failed to start or was removed.
+    pub async fn release(&self, addresses: &[IpAddr]) {
+        self.inner.lock().await.release(addresses);
+    }
+
+    /// Check the tracked addresses against the stored instances.
+    pub async fn resync(&self) -> Result<()> {
+        let instances = self.state_store.list_all_instances().await?;
+        self.inner.lock().await.sync(&instances);
+        Ok(())
+    }
+
+    /// Give back the addresses a deleted instance still holds.
+    async fn reclaim(&self, instance: &WorkloadInstance) {
+        let mut inner = self.inner.lock().await;
+        let held: Vec<IpAddr> = instance
+            .ip_addresses
+            .iter()
+            .filter(|addr| inner.holders.get(addr) == Some(&Holder::Instance(instance.id)))
+            .copied()
+            .collect();
+        inner.release(&held);
+    }
+
+    /// Reclaim the addresses of deleted instances as the store reports them,
+    /// and resync every `interval` until the task is aborted. Stores without
+    /// instance watches are only resynced.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            let mut changes = match self.state_store.watch_instances().await {
+                Ok(changes) => Some(changes),
+                Err(e) => {
+                    debug!("Reclaiming instance addresses on resync only: {}", e);
+                    None
+                }
+            };
+            loop {
+                match changes.as_mut() {
+                    Some(receiver) => tokio::select! {
+                        _ = interval.tick() => {}
+                        received = receiver.recv() => match received {
+                            Some(event) => {
+                                if event.event_type == WatchEventType::Deleted {
+                                    self.reclaim(&event.object).await;
+                                }
+                                continue;
+                            }
+                            None => {
+                                warn!("Instance watch closed, reclaiming addresses on resync only");
+                                changes = None;
+                            }
+                        },
+                    },
+                    None => {
+                        interval.tick().await;
+                    }
+                }
+                if let Err(e) = self.resync().await {
+                    warn!("Failed to resync instance addresses: {:?}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orchestrator_shared_types::{Keypair, WorkloadInstance, WorkloadInstanceStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use uuid::Uuid;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cidr_parse_and_contains() {
+        let v4: Cidr = "10.96.1.7/16".parse().unwrap();
+        assert_eq!(v4.network(), ip("10.96.0.0"));
+        assert!(v4.contains(&ip("10.96.255.1")));
+        assert!(!v4.contains(&ip("10.97.0.1")));
+        assert!(!v4.contains(&ip("::1")));
+
+        let v6: Cidr = "fd00:10:96::/112".parse().unwrap();
+        assert_eq!(v6.family(), IpFamily::IPv6);
+        assert!(v6.contains(&ip("fd00:10:96::ffff")));
+        assert!(!v6.contains(&ip("fd00:10:97::1")));
+        assert_eq!(v6.to_string(), "fd00:10:96::/112");
+
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("10.0.0.0".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn test_ipv4_pool_skips_network_and_broadcast() {
+        let mut pool = IpPool::new("192.168.0.0/30".parse().unwrap());
+        assert_eq!(pool.allocate().unwrap(), ip("192.168.0.1"));
+        assert_eq!(pool.allocate().unwrap(), ip("192.168.0.2"));
+        assert!(pool.allocate().is_err());
+
+        pool.release(&ip("192.168.0.1"));
+        assert_eq!(pool.allocate().unwrap(), ip("192.168.0.1"));
+    }
+
+    #[test]
+    fn test_ipv6_pool_allocate_and_reserve() {
+        let mut pool = IpPool::new("fd00::/120".parse().unwrap());
+        pool.reserve(&ip("fd00::1")).unwrap();
+        assert!(pool.reserve(&ip("fd00::1")).is_err());
+        assert!(pool.reserve(&ip("fd01::1")).is_err());
+        assert_eq!(pool.allocate().unwrap(), ip("fd00::2"));
+        assert_eq!(pool.allocated_count(), 2);
+    }
+
+    #[test]
+    fn test_dual_stack_policies() {
+        let mut alloc = DualStackAllocator::new(&[
+            "10.96.0.0/24".parse().unwrap(),
+            "fd00:10:96::/112".parse().unwrap(),
+        ])
+        .unwrap();
+        assert!(alloc.is_dual_stack());
+        assert_eq!(alloc.primary_family(), IpFamily::IPv4);
+
+        let single = alloc.allocate(IpFamilyPolicy::SingleStack).unwrap();
+        assert_eq!(single, vec![ip("10.96.0.1")]);
+
+        let dual = alloc.allocate(IpFamilyPolicy::RequireDualStack).unwrap();
+        assert_eq!(dual, vec![ip("10.96.0.2"), ip("fd00:10:96::1")]);
+    }
+
+    #[test]
+    fn test_ipv6_only_cluster() {
+        let mut alloc = DualStackAllocator::new(&["fd00:10:96::/112".parse().unwrap()]).unwrap();
+        assert_eq!(alloc.primary_family(), IpFamily::IPv6);
+        assert_eq!(
+            alloc.allocate(IpFamilyPolicy::PreferDualStack).unwrap(),
+            vec![ip("fd00:10:96::1")]
+        );
+        assert!(alloc.allocate(IpFamilyPolicy::RequireDualStack).is_err());
+        assert!(alloc.allocate_family(IpFamily::IPv4).is_err());
+    }
+
+    #[test]
+    fn test_dual_stack_rejects_same_family() {
+        let result = DualStackAllocator::new(&[
+            "10.0.0.0/24".parse().unwrap(),
+            "10.1.0.0/24".parse().unwrap(),
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_dual_stack_reserve() {
+        let mut alloc = DualStackAllocator::new(&[
+            "10.96.0.0/24".parse().unwrap(),
+            "fd00:10:96::/112".parse().unwrap(),
+        ])
+        .unwrap();
+        alloc.reserve(&ip("fd00:10:96::1")).unwrap();
+        assert!(alloc.reserve(&ip("10.97.0.1")).is_err());
+        assert_eq!(alloc.allocated(), vec![ip("fd00:10:96::1")]);
+        assert_eq!(
+            alloc.allocate_family(IpFamily::IPv6).unwrap(),
+            ip("fd00:10:96::2")
+        );
+    }
+
+    fn instance(addresses: Vec<IpAddr>) -> WorkloadInstance {
+        WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: Uuid::new_v4(),
+            node_id: Keypair::generate().public_key(),
+            container_ids: vec![],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: addresses,
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        }
+    }
+
+    fn instance_allocator(store: Arc<InMemoryStateStore>) -> Arc<InstanceAddressAllocator> {
+        Arc::new(InstanceAddressAllocator::new(
+            store,
+            DualStackAllocator::new(&["192.168.0.0/29".parse().unwrap()]).unwrap(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_instance_addresses_are_tracked() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let existing = instance(vec![ip("192.168.0.1")]);
+        store.put_instance(existing.clone()).await.unwrap();
+
+        // Addresses held before a restart are not handed out again
+        let allocator = instance_allocator(store.clone());
+        let first = allocator.allocate().await.unwrap();
+        assert_eq!(first, vec![ip("192.168.0.2")]);
+        let started = instance(first.clone());
+        store.put_instance(started.clone()).await.unwrap();
+        allocator.assign(started.id, &first).await;
+
+        // Not stored yet, but still taken across a resync
+        let failed = allocator.allocate().await.unwrap();
+        assert_eq!(failed, vec![ip("192.168.0.3")]);
+        allocator.resync().await.unwrap();
+        for _ in 0..3 {
+            allocator.allocate().await.unwrap();
+        }
+        assert!(allocator.allocate().await.is_err());
+
+        // A failed start gives its addresses back
+        allocator.release(&failed).await;
+        assert_eq!(allocator.allocate().await.unwrap(), failed);
+
+        // So does deleting an instance, once resynced
+        store
+            .delete_instance(&existing.id.to_string())
+            .await
+            .unwrap();
+        allocator.resync().await.unwrap();
+        assert_eq!(allocator.allocate().await.unwrap(), vec![ip("192.168.0.1")]);
+        assert!(allocator.allocate().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deleted_instances_are_reclaimed_from_the_watch() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let allocator = instance_allocator(store.clone());
+        let mut stored = Vec::new();
+        for _ in 0..6 {
+            let addresses = allocator.allocate().await.unwrap();
+            let instance = instance(addresses.clone());
+            store.put_instance(instance.clone()).await.unwrap();
+            allocator.assign(instance.id, &addresses).await;
+            stored.push(instance);
+        }
+        assert!(allocator.allocate().await.is_err());
+
+        let task = allocator.clone().spawn(Duration::from_secs(3600));
+        // Let the task subscribe and run its first resync
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        store
+            .delete_instance(&stored[2].id.to_string())
+            .await
+            .unwrap();
+        let reclaimed = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Ok(addresses) = allocator.allocate().await {
+                    return addresses;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(reclaimed, stored[2].ip_addresses);
+        task.abort();
+    }
+}
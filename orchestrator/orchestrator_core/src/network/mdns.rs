@@ -0,0 +1,760 @@
+//! mDNS advertisement for development clusters.
+//!
+//! A single-node or dev cluster usually has neither ingress nor DNS entries
+//! for what it runs. The [`MdnsResponder`] makes workloads that publish a host
+//! port reachable from the LAN as `<name>.local`, and browsable through DNS-SD
+//! as `_http._tcp` services, by answering multicast DNS queries (RFC 6762 and
+//! RFC 6763) with the node's own addresses.
+//!
+//! Only workloads with a running instance and a TCP port mapped to a host port
+//! are advertised. The set is refreshed from the state store; new names are
+//! announced and withdrawn ones get a goodbye record so browsers drop them
+//! straight away. Queries are received over IPv4 multicast, but IPv6 node
+//! addresses are still served as AAAA records.
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+
+use orchestrator_shared_types::{OrchestrationError, Result, WorkloadInstanceStatus};
+use state_store_interface::StateStore;
+
+/// IPv4 multicast group mDNS queries are sent to.
+pub const MDNS_GROUP_V4: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+pub const MDNS_PORT: u16 = 5353;
+/// TTL for advertised records, the RFC 6762 recommendation for host records.
+pub const DEFAULT_TTL: u32 = 120;
+pub const DEFAULT_SERVICE_TYPE: &str = "_http._tcp";
+
+const HEADER_LEN: usize = 12;
+const MAX_MESSAGE: usize = 9000;
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const TYPE_SRV: u16 = 33;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+// Top bit of the class: cache-flush in records, unicast-response in questions
+const CLASS_TOP_BIT: u16 = 0x8000;
+const SERVICES_META: &str = "_services._dns-sd._udp";
+// Resolvers that are not mDNS-aware must not cache answers for long
+const LEGACY_UNICAST_TTL: u32 = 10;
+
+/// Configuration for the mDNS responder.
+#[derive(Debug, Clone)]
+pub struct MdnsConfig {
+    /// Addresses the advertised names resolve to, normally the node's LAN addresses.
+    pub addresses: Vec<IpAddr>,
+    pub domain: String,
+    pub service_type: String,
+    pub ttl: u32,
+    /// How often the advertised set is refreshed from the state store.
+    pub sync_interval: Duration,
+}
+
+impl MdnsConfig {
+    pub fn new(addresses: Vec<IpAddr>) -> Self {
+        Self {
+            addresses,
+            domain: "local".to_string(),
+            service_type: DEFAULT_SERVICE_TYPE.to_string(),
+            ttl: DEFAULT_TTL,
+            sync_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// An advertised name and the host port it is served on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdnsAdvertisement {
+    pub name: String,
+    pub port: u16,
+}
+
+/// The address this host uses to reach the local network, for advertising.
+///
+/// Connecting a UDP socket sends nothing; it only selects a route.
+pub fn lan_address() -> Option<IpAddr> {
+    let socket = std::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((MDNS_GROUP_V4, MDNS_PORT)).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_unspecified()).then_some(ip)
+}
+
+/// Turn a workload name into a DNS label: lowercase alphanumerics and dashes.
+pub fn to_label(name: &str) -> Option<String> {
+    let mut label = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            label.push(c.to_ascii_lowercase());
+        } else if !label.ends_with('-') {
+            label.push('-');
+        }
+    }
+    let label = label.trim_matches('-');
+    let label = &label[..label.len().min(63)];
+    let label = label.trim_end_matches('-');
+    (!label.is_empty()).then(|| label.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RData {
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    Ptr(String),
+    Srv { port: u16, target: String },
+    Txt(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Record {
+    name: String,
+    ttl: u32,
+    data: RData,
+}
+
+impl Record {
+    fn rtype(&self) -> u16 {
+        match self.data {
+            RData::A(_) => TYPE_A,
+            RData::Aaaa(_) => TYPE_AAAA,
+            RData::Ptr(_) => TYPE_PTR,
+            RData::Srv { .. } => TYPE_SRV,
+            RData::Txt(_) => TYPE_TXT,
+        }
+    }
+
+    fn matches(&self, qtype: u16) -> bool {
+        qtype == TYPE_ANY || qtype == self.rtype()
+    }
+
+    fn encode(&self, out: &mut Vec<u8>, cache_flush: bool) {
+        encode_name(&self.name, out);
+        out.extend(self.rtype().to_be_bytes());
+        // PTR records are shared between responders, everything else is ours alone
+        let unique = cache_flush && !matches!(self.data, RData::Ptr(_));
+        let class = if unique {
+            CLASS_IN | CLASS_TOP_BIT
+        } else {
+            CLASS_IN
+        };
+        out.extend(class.to_be_bytes());
+        out.extend(self.ttl.to_be_bytes());
+
+        let mut rdata = Vec::new();
+        match &self.data {
+            RData::A(ip) => rdata.extend(ip.octets()),
+            RData::Aaaa(ip) => rdata.extend(ip.octets()),
+            RData::Ptr(name) => encode_name(name, &mut rdata),
+            RData::Srv { port, target } => {
+                rdata.extend(0u16.to_be_bytes()); // priority
+                rdata.extend(0u16.to_be_bytes()); // weight
+                rdata.extend(port.to_be_bytes());
+                encode_name(target, &mut rdata);
+            }
+            RData::Txt(entries) if entries.is_empty() => rdata.push(0),
+            RData::Txt(entries) => {
+                for entry in entries {
+                    let bytes = &entry.as_bytes()[..entry.len().min(255)];
+                    rdata.push(bytes.len() as u8);
+                    rdata.extend_from_slice(bytes);
+                }
+            }
+        }
+        out.extend((rdata.len() as u16).to_be_bytes());
+        out.extend(rdata);
+    }
+}
+
+struct Question {
+    name: String,
+    qtype: u16,
+    unicast: bool,
+}
+
+/// Answers mDNS queries for the workloads exposed on this node.
+pub struct MdnsResponder {
+    config: MdnsConfig,
+    // Label to host port
+    advertised: RwLock<BTreeMap<String, u16>>,
+}
+
+impl MdnsResponder {
+    pub fn new(config: MdnsConfig) -> Self {
+        Self {
+            config,
+            advertised: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &MdnsConfig {
+        &self.config
+    }
+
+    /// Advertise `name` on `port`. Returns false if nothing changed or the
+    /// name has no usable DNS label.
+    pub fn advertise(&self, name: &str, port: u16) -> bool {
+        let Some(label) = to_label(name) else {
+            return false;
+        };
+        self.advertised.write().unwrap().insert(label, port) != Some(port)
+    }
+
+    /// Stop advertising `name`, returning the port it was advertised on.
+    pub fn withdraw(&self, name: &str) -> Option<u16> {
+        let label = to_label(name)?;
+        self.advertised.write().unwrap().remove(&label)
+    }
+
+    pub fn advertised(&self) -> Vec<MdnsAdvertisement> {
+        self.advertised
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, port)| MdnsAdvertisement {
+                name: name.clone(),
+                port: *port,
+            })
+            .collect()
+    }
+
+    /// Answer a raw mDNS query received from `from`.
+    ///
+    /// Returns the response and where to send it: the multicast group, or the
+    /// querier itself for unicast-response and legacy (non-5353) queries.
+    pub fn respond(&self, query: &[u8], from: SocketAddr) -> Option<(Vec<u8>, SocketAddr)> {
+        let (questions, question_end) = parse_questions(query)?;
+        let legacy = from.port() != MDNS_PORT;
+
+        let mut answers = Vec::new();
+        let mut additionals = Vec::new();
+        for question in &questions {
+            self.answer(question, &mut answers, &mut additionals);
+        }
+        if answers.is_empty() {
+            return None;
+        }
+        additionals.retain(|r| !answers.contains(r));
+
+        if legacy {
+            for record in answers.iter_mut().chain(additionals.iter_mut()) {
+                record.ttl = record.ttl.min(LEGACY_UNICAST_TTL);
+            }
+            let id = u16::from_be_bytes([query[0], query[1]]);
+            let echoed = &query[HEADER_LEN..question_end];
+            let packet =
+                encode_message(id, Some((questions.len(), echoed)), &answers, &additionals);
+            return Some((packet, from));
+        }
+
+        let packet = encode_message(0, None, &answers, &additionals);
+        let destination = if questions.iter().all(|q| q.unicast) {
+            from
+        } else {
+            SocketAddr::new(IpAddr::V4(MDNS_GROUP_V4), MDNS_PORT)
+        };
+        Some((packet, destination))
+    }
+
+    /// Unsolicited response announcing every advertised name.
+    pub fn announcement(&self) -> Option<Vec<u8>> {
+        let advertised = self.advertised.read().unwrap().clone();
+        let records: Vec<Record> = advertised
+            .iter()
+            .flat_map(|(label, port)| self.records_for(label, *port, self.config.ttl))
+            .collect();
+        (!records.is_empty()).then(|| encode_message(0, None, &records, &[]))
+    }
+
+    /// Refresh the advertised set from the state store.
+    ///
+    /// Returns a packet announcing new names and saying goodbye to withdrawn
+    /// ones, if anything changed.
+    pub async fn sync(&self, state_store: &dyn StateStore) -> Result<Option<Vec<u8>>> {
+        let mut desired = BTreeMap::new();
+        for workload in state_store.list_workloads().await? {
+            let exposed = workload
+                .containers
+                .iter()
+                .flat_map(|c| &c.ports)
+                .find(|p| p.protocol.eq_ignore_ascii_case("tcp") && p.host_port.is_some());
+            let (Some(port), Some(label)) = (exposed, to_label(&workload.name)) else {
+                continue;
+            };
+            let running = state_store
+                .list_instances_for_workload(&workload.id)
+                .await?
+                .iter()
+                .any(|i| i.status == WorkloadInstanceStatus::Running);
+            if running {
+                desired.insert(label, port.host_port.unwrap_or_default());
+            }
+        }
+
+        let mut records = Vec::new();
+        let mut advertised = self.advertised.write().unwrap();
+        for (label, port) in advertised.iter() {
+            if desired.get(label) != Some(port) {
+                info!("mDNS: withdrawing {}.{}", label, self.config.domain);
+                records.extend(self.records_for(label, *port, 0));
+            }
+        }
+        for (label, port) in &desired {
+            if advertised.get(label) != Some(port) {
+                info!(
+                    "mDNS: advertising {}.{} on port {}",
+                    label, self.config.domain, port
+                );
+                records.extend(self.records_for(label, *port, self.config.ttl));
+            }
+        }
+        *advertised = desired;
+        Ok((!records.is_empty()).then(|| encode_message(0, None, &records, &[])))
+    }
+
+    /// Join the mDNS group and serve queries until the task is aborted.
+    pub async fn spawn(
+        self: Arc<Self>,
+        state_store: Arc<dyn StateStore>,
+    ) -> Result<JoinHandle<()>> {
+        let socket = bind_multicast().map_err(|e| {
+            OrchestrationError::NetworkError(format!(
+                "Failed to join mDNS group on port {}: {}",
+                MDNS_PORT, e
+            ))
+        })?;
+        info!(
+            "mDNS responder advertising on .{} as {:?}",
+            self.config.domain, self.config.addresses
+        );
+        let group = SocketAddr::new(IpAddr::V4(MDNS_GROUP_V4), MDNS_PORT);
+
+        Ok(tokio::spawn(async move {
+            let mut buf = vec![0u8; MAX_MESSAGE];
+            let mut interval = tokio::time::interval(self.config.sync_interval);
+            loop {
+                tokio::select! {
+                    received = socket.recv_from(&mut buf) => {
+                        let (len, from) = match received {
+                            Ok(received) => received,
+                            Err(e) => {
+                                error!("mDNS receive failed: {}", e);
+                                continue;
+                            }
+                        };
+                        if let Some((response, to)) = self.respond(&buf[..len], from) {
+                            if let Err(e) = socket.send_to(&response, to).await {
+                                debug!("Failed to send mDNS response to {}: {}", to, e);
+                            }
+                        }
+                    }
+                    _ = interval.tick() => {
+                        match self.sync(state_store.as_ref()).await {
+                            Ok(Some(packet)) => {
+                                if let Err(e) = socket.send_to(&packet, group).await {
+                                    debug!("Failed to send mDNS announcement: {}", e);
+                                }
+                            }
+                            Ok(None) => {}
+                            Err(e) => error!("mDNS sync failed: {}", e),
+                        }
+                    }
+                }
+            }
+        }))
+    }
+
+    fn host_name(&self, label: &str) -> String {
+        format!("{}.{}", label, self.config.domain)
+    }
+
+    fn service_type_name(&self) -> String {
+        format!("{}.{}", self.config.service_type, self.config.domain)
+    }
+
+    fn instance_name(&self, label: &str) -> String {
+        format!("{}.{}", label, self.service_type_name())
+    }
+
+    fn address_records(&self, label: &str, ttl: u32) -> Vec<Record> {
+        let name = self.host_name(label);
+        self.config
+            .addresses
+            .iter()
+            .map(|ip| Record {
+                name: name.clone(),
+                ttl,
+                data: match ip {
+                    IpAddr::V4(v4) => RData::A(*v4),
+                    IpAddr::V6(v6) => RData::Aaaa(*v6),
+                },
+            })
+            .collect()
+    }
+
+    fn service_records(&self, label: &str, port: u16, ttl: u32) -> [Record; 2] {
+        let instance = self.instance_name(label);
+        [
+            Record {
+                name: instance.clone(),
+                ttl,
+                data: RData::Srv {
+                    port,
+                    target: self.host_name(label),
+                },
+            },
+            Record {
+                name: instance,
+                ttl,
+                data: RData::Txt(vec!["path=/".to_string()]),
+            },
+        ]
+    }
+
+    fn pointer_record(&self, label: &str, ttl: u32) -> Record {
+        Record {
+            name: self.service_type_name(),
+            ttl,
+            data: RData::Ptr(self.instance_name(label)),
+        }
+    }
+
+    fn records_for(&self, label: &str, port: u16, ttl: u32) -> Vec<Record> {
+        let mut records = vec![self.pointer_record(label, ttl)];
+        records.extend(self.service_records(label, port, ttl));
+        records.extend(self.address_records(label, ttl));
+        records
+    }
+
+    fn answer(
+        &self,
+        question: &Question,
+        answers: &mut Vec<Record>,
+        additionals: &mut Vec<Record>,
+    ) {
+        let ttl = self.config.ttl;
+        let advertised = self.advertised.read().unwrap();
+        fn push(list: &mut Vec<Record>, record: Record) {
+            if !list.contains(&record) {
+                list.push(record);
+            }
+        }
+
+        if advertised.is_empty() {
+            return;
+        }
+        if question.name == format!("{}.{}", SERVICES_META, self.config.domain)
+            && (question.qtype == TYPE_PTR || question.qtype == TYPE_ANY)
+        {
+            push(
+                answers,
+                Record {
+                    name: question.name.clone(),
+                    ttl,
+                    data: RData::Ptr(self.service_type_name()),
+                },
+            );
+        }
+        for (label, port) in advertised.iter() {
+            if question.name == self.service_type_name()
+                && (question.qtype == TYPE_PTR || question.qtype == TYPE_ANY)
+            {
+                push(answers, self.pointer_record(label, ttl));
+                for record in self.service_records(label, *port, ttl) {
+                    push(additionals, record);
+                }
+                for record in self.address_records(label, ttl) {
+                    push(additionals, record);
+                }
+            } else if question.name == self.instance_name(label) {
+                for record in self.service_records(label, *port, ttl) {
+                    if record.matches(question.qtype) {
+                        push(answers, record);
+                    }
+                }
+                for record in self.address_records(label, ttl) {
+                    push(additionals, record);
+                }
+            } else if question.name == self.host_name(label) {
+                for record in self.address_records(label, ttl) {
+                    if record.matches(question.qtype) {
+                        push(answers, record);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn bind_multicast() -> std::io::Result<UdpSocket> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    // Share the port with any system responder (Avahi, mDNSResponder)
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.bind(&SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), MDNS_PORT).into())?;
+    socket.join_multicast_v4(&MDNS_GROUP_V4, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_multicast_ttl_v4(255)?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket.into())
+}
+
+fn encode_name(name: &str, out: &mut Vec<u8>) {
+    for label in name.split('.').filter(|l| !l.is_empty()) {
+        let bytes = &label.as_bytes()[..label.len().min(63)];
+        out.push(bytes.len() as u8);
+        out.extend_from_slice(bytes);
+    }
+    out.push(0);
+}
+
+fn encode_message(
+    id: u16,
+    questions: Option<(usize, &[u8])>,
+    answers: &[Record],
+    additionals: &[Record],
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(512);
+    out.extend(id.to_be_bytes());
+    out.extend(0x8400u16.to_be_bytes()); // QR + AA
+    let (question_count, question_bytes) = questions.unwrap_or((0, &[][..]));
+    out.extend((question_count as u16).to_be_bytes());
+    out.extend((answers.len() as u16).to_be_bytes());
+    out.extend(0u16.to_be_bytes());
+    out.extend((additionals.len() as u16).to_be_bytes());
+    out.extend_from_slice(question_bytes);
+    // Legacy resolvers don't understand the cache-flush bit
+    let cache_flush = questions.is_none();
+    for record in answers.iter().chain(additionals) {
+        record.encode(&mut out, cache_flush);
+    }
+    out
+}
+
+fn read_u16(msg: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*msg.get(pos)?, *msg.get(pos + 1)?]))
+}
+
+/// Read a possibly-compressed name, returning it and the offset after it.
+fn read_name(msg: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut jumps = 0;
+    loop {
+        let len = *msg.get(pos)? as usize;
+        if len == 0 {
+            pos += 1;
+            break;
+        }
+        if len & 0xC0 == 0xC0 {
+            // Multi-question queries compress later names; guard against loops
+            jumps += 1;
+            if jumps > 16 {
+                return None;
+            }
+            end.get_or_insert(pos + 2);
+            pos = ((len & 0x3F) << 8) | *msg.get(pos + 1)? as usize;
+            continue;
+        }
+        let label = msg.get(pos + 1..pos + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+        pos += 1 + len;
+    }
+    Some((labels.join("."), end.unwrap_or(pos)))
+}
+
+/// Parse the questions of a query, returning them and the offset after them.
+fn parse_questions(msg: &[u8]) -> Option<(Vec<Question>, usize)> {
+    // Responses from other responders are not queries
+    if msg.len() < HEADER_LEN || msg[2] & 0x80 != 0 {
+        return None;
+    }
+    let count = read_u16(msg, 4)?;
+    let mut pos = HEADER_LEN;
+    let mut questions = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let (name, next) = read_name(msg, pos)?;
+        let qclass = read_u16(msg, next + 2)?;
+        questions.push(Question {
+            name,
+            qtype: read_u16(msg, next)?,
+            unicast: qclass & CLASS_TOP_BIT != 0,
+        });
+        pos = next + 4;
+    }
+    Some((questions, pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use orchestrator_shared_types::{
+        ContainerConfig, Keypair, PortMapping, WorkloadDefinition, WorkloadInstance,
+    };
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use uuid::Uuid;
+
+    fn responder() -> MdnsResponder {
+        let responder = MdnsResponder::new(MdnsConfig::new(vec![
+            "192.168.1.10".parse().unwrap(),
+            "fd00::10".parse().unwrap(),
+        ]));
+        assert!(responder.advertise("Grafana UI", 3000));
+        responder
+    }
+
+    fn query(id: u16, name: &str, qtype: u16, unicast: bool) -> Vec<u8> {
+        let mut msg = id.to_be_bytes().to_vec();
+        msg.extend([0, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+        encode_name(name, &mut msg);
+        msg.extend(qtype.to_be_bytes());
+        let class = if unicast {
+            CLASS_IN | CLASS_TOP_BIT
+        } else {
+            CLASS_IN
+        };
+        msg.extend(class.to_be_bytes());
+        msg
+    }
+
+    fn mdns_peer() -> SocketAddr {
+        "192.168.1.20:5353".parse().unwrap()
+    }
+
+    /// Type, TTL and rdata of every record in a response.
+    fn records(msg: &[u8]) -> Vec<(u16, u32, Vec<u8>)> {
+        let mut pos = HEADER_LEN;
+        for _ in 0..read_u16(msg, 4).unwrap() {
+            pos = read_name(msg, pos).unwrap().1 + 4;
+        }
+        let count = read_u16(msg, 6).unwrap() + read_u16(msg, 10).unwrap();
+        (0..count)
+            .map(|_| {
+                pos = read_name(msg, pos).unwrap().1;
+                let rtype = read_u16(msg, pos).unwrap();
+                let ttl = u32::from_be_bytes(msg[pos + 4..pos + 8].try_into().unwrap());
+                let len = read_u16(msg, pos + 8).unwrap() as usize;
+                let rdata = msg[pos + 10..pos + 10 + len].to_vec();
+                pos += 10 + len;
+                (rtype, ttl, rdata)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_to_label() {
+        assert_eq!(to_label("Grafana UI").as_deref(), Some("grafana-ui"));
+        assert_eq!(to_label("--api__v2--").as_deref(), Some("api-v2"));
+        assert_eq!(to_label("日本"), None);
+        assert_eq!(to_label(&"a".repeat(80)).unwrap().len(), 63);
+    }
+
+    #[test]
+    fn test_answers_host_query_over_multicast() {
+        let responder = responder();
+        let (response, to) = responder
+            .respond(&query(0, "grafana-ui.local", TYPE_A, false), mdns_peer())
+            .unwrap();
+        assert_eq!(to, SocketAddr::new(IpAddr::V4(MDNS_GROUP_V4), MDNS_PORT));
+        assert_eq!(read_u16(&response, 6), Some(1));
+        assert_eq!(
+            records(&response),
+            vec![(TYPE_A, DEFAULT_TTL, vec![192, 168, 1, 10])]
+        );
+
+        // Case-insensitive, and silent about names we don't own
+        assert!(responder
+            .respond(&query(0, "Grafana-UI.local", TYPE_AAAA, false), mdns_peer())
+            .is_some());
+        assert!(responder
+            .respond(&query(0, "other.local", TYPE_A, false), mdns_peer())
+            .is_none());
+    }
+
+    #[test]
+    fn test_browse_returns_srv_and_addresses() {
+        let responder = responder();
+        let (response, to) = responder
+            .respond(&query(0, "_http._tcp.local", TYPE_PTR, true), mdns_peer())
+            .unwrap();
+        assert_eq!(to, mdns_peer());
+
+        let types: Vec<u16> = records(&response).iter().map(|r| r.0).collect();
+        assert_eq!(types, vec![TYPE_PTR, TYPE_SRV, TYPE_TXT, TYPE_A, TYPE_AAAA]);
+        let (_, _, srv) = &records(&response)[1];
+        assert_eq!(u16::from_be_bytes([srv[4], srv[5]]), 3000);
+    }
+
+    #[test]
+    fn test_legacy_unicast_echoes_query() {
+        let responder = responder();
+        let from: SocketAddr = "192.168.1.20:40000".parse().unwrap();
+        let (response, to) = responder
+            .respond(&query(0x1234, "grafana-ui.local", TYPE_A, false), from)
+            .unwrap();
+        assert_eq!(to, from);
+        assert_eq!(read_u16(&response, 0), Some(0x1234));
+        assert_eq!(read_u16(&response, 4), Some(1));
+        assert_eq!(records(&response)[0].1, LEGACY_UNICAST_TTL);
+    }
+
+    #[tokio::test]
+    async fn test_sync_advertises_running_exposed_workloads() {
+        let store = InMemoryStateStore::new();
+        let mut ids = Vec::new();
+        for (name, host_port) in [("web", Some(8080)), ("worker", None)] {
+            let workload = WorkloadDefinition {
+                containers: vec![ContainerConfig {
+                    ports: vec![PortMapping {
+                        container_port: 80,
+                        host_port,
+                        protocol: "tcp".to_string(),
+                        host_ip: None,
+                    }],
+                    ..test_support::container(name)
+                }],
+                ..test_support::workload(name)
+            };
+            let instance = WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id: workload.id,
+                node_id: Keypair::generate().public_key(),
+                container_ids: vec![],
+                status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
+            };
+            ids.push(instance.id);
+            store.put_workload(workload).await.unwrap();
+            store.put_instance(instance).await.unwrap();
+        }
+
+        let responder = MdnsResponder::new(MdnsConfig::new(vec!["192.168.1.10".parse().unwrap()]));
+        let announcement = responder.sync(&store).await.unwrap().unwrap();
+        assert!(records(&announcement).iter().all(|r| r.1 == DEFAULT_TTL));
+        assert_eq!(
+            responder.advertised(),
+            vec![MdnsAdvertisement {
+                name: "web".to_string(),
+                port: 8080
+            }]
+        );
+        assert!(responder.sync(&store).await.unwrap().is_none());
+
+        store.delete_instance(&ids[0].to_string()).await.unwrap();
+        let goodbye = responder.sync(&store).await.unwrap().unwrap();
+        assert!(records(&goodbye).iter().all(|r| r.1 == 0));
+        assert!(responder.advertised().is_empty());
+    }
+}
@@ -0,0 +1,47 @@
+//! Cluster networking.
+//!
+//! Address management for instances and service virtual IPs. Every range is
+//! typed by [`IpFamily`](orchestrator_shared_types::IpFamily), so a cluster
+//! can be IPv4-only, IPv6-only, or dual-stack depending on which ranges it is
+//! configured with.
+//!
+//! Services put a stable virtual IP in front of a set of workloads; the
+//! [`proxy`] picks one of their endpoints for each connection, and the
+//! [`forwarder`] carries the connection there. Instances with readiness
+//! probes only count as endpoints while [`readiness`] reports them ready.
+//!
+//! [`dns_cache`] provides the node-local caching resolver instances use as
+//! their first nameserver, and [`mdns`] advertises exposed workloads on the
+//! LAN for dev clusters. [`tunnel`] opens temporary public ports on edge
+//! nodes that forward to a workload's instances.
+//!
+//! [`policy`] restricts which workloads may talk to each other, enforced on
+//! each node with nftables.
+//!
+//! [`ports`] publishes the host ports of the instances running on each node
+//! with nftables DNAT rules, on the address their `host_ip` names.
+
+pub mod dns_cache;
+pub mod forwarder;
+pub mod ipam;
+pub mod mdns;
+pub mod policy;
+pub mod ports;
+pub mod proxy;
+pub mod readiness;
+pub mod service;
+pub mod tunnel;
+
+pub use dns_cache::{DnsCache, DnsCacheConfig, DnsCacheObserver, NodeLocalDnsServer};
+pub use forwarder::ServiceForwarder;
+pub use ipam::{Cidr, DualStackAllocator, InstanceAddressAllocator, IpPool};
+pub use mdns::{MdnsConfig, MdnsResponder};
+pub use policy::{
+    Firewall, NetworkPolicy, NetworkPolicyController, NetworkPolicyId, NftablesFirewall,
+    PolicyPort, PolicyRule,
+};
+pub use ports::HostPortPublisher;
+pub use proxy::ServiceProxy;
+pub use readiness::{NetworkProbeRunner, ProbeRunner, ReadinessProber};
+pub use service::{Endpoint, Service, ServiceEndpoints, ServiceId, ServicePort, SessionAffinity};
+pub use tunnel::{Tunnel, TunnelManager};
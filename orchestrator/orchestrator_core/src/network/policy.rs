@@ -0,0 +1,624 @@
+//! Network policies between workloads.
+//!
+//! A [`NetworkPolicy`] selects workloads of its namespace by label and
+//! restricts the traffic their instances accept (ingress) or send (egress) to
+//! what its rules allow: instances of the workloads a rule selects, on the
+//! rule's ports. Traffic is only restricted in a direction some policy
+//! selecting the workload sets rules for, and the rules of every such policy
+//! add up. A direction with an empty rule list allows nothing, so isolating
+//! `payments` from everything except `api-gateway` is one ingress rule:
+//!
+//! ```text
+//! selector: {app: payments}
+//! ingress:  [{selector: {app: api-gateway}, ports: [{port: 8443}]}]
+//! ```
+//!
+//! The [`NetworkPolicyController`] renders the policies and the addresses of
+//! the instances they cover into one nftables table, filtering the node's
+//! forward hook, and replaces it whenever either changes. Replies to allowed
+//! connections always pass, and so does DNS to the node-local cache.
+//! Connections through the [`ServiceProxy`](super::ServiceProxy) come from
+//! the node itself and are not filtered.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use orchestrator_shared_types::{
+    OrchestrationError, Result, WorkloadDefinition, WorkloadInstance, DEFAULT_NAMESPACE,
+};
+use state_store_interface::StateStore;
+
+use super::dns_cache::DEFAULT_LISTEN_ADDR;
+
+pub type NetworkPolicyId = Uuid;
+
+/// Name of the nftables table policies are enforced in.
+pub const NFT_TABLE: &str = "orchestrator_policy";
+
+/// Restricts the traffic of the workloads matching `selector`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct NetworkPolicy {
+    #[cfg_attr(feature = "rest-api", schema(value_type = uuid::Uuid))]
+    pub id: NetworkPolicyId,
+    pub name: String,
+    /// Namespace of the workloads the policy and its rules select.
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    /// Workload labels the policy applies to. An empty selector matches every
+    /// workload of the namespace.
+    #[serde(default)]
+    pub selector: HashMap<String, String>,
+    /// Traffic the selected instances accept; unrestricted when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress: Option<Vec<PolicyRule>>,
+    /// Traffic the selected instances send; unrestricted when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress: Option<Vec<PolicyRule>>,
+}
+
+/// Peers and ports a policy allows traffic with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct PolicyRule {
+    /// Labels of the peer workloads; empty matches every workload of the
+    /// policy's namespace.
+    #[serde(default)]
+    pub selector: HashMap<String, String>,
+    /// Destination ports allowed; every port when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<PolicyPort>,
+}
+
+/// A destination port of a [`PolicyRule`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct PolicyPort {
+    pub port: u16,
+    /// "tcp" or "udp".
+    #[serde(default = "default_protocol")]
+    pub protocol: String,
+}
+
+fn default_namespace() -> String {
+    DEFAULT_NAMESPACE.to_string()
+}
+
+fn default_protocol() -> String {
+    "tcp".to_string()
+}
+
+impl NetworkPolicy {
+    pub fn new(name: impl Into<String>, selector: HashMap<String, String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            namespace: default_namespace(),
+            selector,
+            ingress: None,
+            egress: None,
+        }
+    }
+
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    pub fn with_ingress(mut self, rules: Vec<PolicyRule>) -> Self {
+        self.ingress = Some(rules);
+        self
+    }
+
+    pub fn with_egress(mut self, rules: Vec<PolicyRule>) -> Self {
+        self.egress = Some(rules);
+        self
+    }
+
+    /// Whether the policy applies to a workload.
+    pub fn selects(&self, workload: &WorkloadDefinition) -> bool {
+        workload.namespace == self.namespace && labels_match(&self.selector, workload)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty() {
+            return Err(OrchestrationError::ConfigError(
+                "Network policy name cannot be empty".to_string(),
+            ));
+        }
+        if self.ingress.is_none() && self.egress.is_none() {
+            return Err(OrchestrationError::ConfigError(format!(
+                "Network policy {} sets neither ingress nor egress rules",
+                self.name
+            )));
+        }
+        let rules = self.ingress.iter().chain(&self.egress).flatten();
+        for port in rules.flat_map(|rule| &rule.ports) {
+            if port.port == 0 || !matches!(port.protocol.as_str(), "tcp" | "udp") {
+                return Err(OrchestrationError::ConfigError(format!(
+                    "Network policy {} has invalid port {}/{}",
+                    self.name, port.port, port.protocol
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PolicyRule {
+    /// Allow traffic with the workloads matching `selector`, on every port.
+    pub fn from_workloads(selector: HashMap<String, String>) -> Self {
+        Self {
+            selector,
+            ports: Vec::new(),
+        }
+    }
+
+    /// Only allow destination `port` of `protocol`.
+    pub fn with_port(mut self, protocol: impl Into<String>, port: u16) -> Self {
+        self.ports.push(PolicyPort {
+            port,
+            protocol: protocol.into(),
+        });
+        self
+    }
+}
+
+fn labels_match(selector: &HashMap<String, String>, workload: &WorkloadDefinition) -> bool {
+    selector
+        .iter()
+        .all(|(key, value)| workload.labels.get(key) == Some(value))
+}
+
+/// Installs a rendered nftables ruleset on the node.
+#[async_trait]
+pub trait Firewall: Send + Sync {
+    async fn apply(&self, ruleset: &str) -> Result<()>;
+}
+
+/// Applies rulesets with `nft -f -`, atomically.
+#[derive(Debug, Clone)]
+pub struct NftablesFirewall {
+    binary: PathBuf,
+}
+
+impl NftablesFirewall {
+    pub fn new() -> Self {
+        Self {
+            binary: PathBuf::from("nft"),
+        }
+    }
+
+    pub fn with_binary(mut self, binary: impl Into<PathBuf>) -> Self {
+        self.binary = binary.into();
+        self
+    }
+}
+
+impl Default for NftablesFirewall {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Firewall for NftablesFirewall {
+    async fn apply(&self, ruleset: &str) -> Result<()> {
+        let failed = |e: std::io::Error| {
+            OrchestrationError::NetworkError(format!("Failed to run {:?}: {}", self.binary, e))
+        };
+        let mut child = Command::new(&self.binary)
+            .args(["-f", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(failed)?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin.write_all(ruleset.as_bytes()).await.map_err(failed)?;
+        drop(stdin);
+
+        let output = child.wait_with_output().await.map_err(failed)?;
+        if !output.status.success() {
+            return Err(OrchestrationError::NetworkError(format!(
+                "nft rejected the ruleset: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Holds network policies and keeps the node's firewall in line with them.
+pub struct NetworkPolicyController {
+    state_store: Arc<dyn StateStore>,
+    firewall: Arc<dyn Firewall>,
+    policies: RwLock<HashMap<NetworkPolicyId, NetworkPolicy>>,
+    /// Last ruleset installed, so unchanged rulesets are not applied again.
+    applied: Mutex<Option<String>>,
+}
+
+impl NetworkPolicyController {
+    /// How often the ruleset is brought up to date with the instances.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(state_store: Arc<dyn StateStore>, firewall: Arc<dyn Firewall>) -> Self {
+        Self {
+            state_store,
+            firewall,
+            policies: RwLock::new(HashMap::new()),
+            applied: Mutex::new(None),
+        }
+    }
+
+    /// Register a policy, replacing any policy with the same id, and enforce
+    /// it. A policy the firewall failed to take stays registered, and is
+    /// enforced on a later sync.
+    pub async fn add_policy(&self, policy: NetworkPolicy) -> Result<NetworkPolicyId> {
+        policy.validate()?;
+        let id = policy.id;
+        info!("Registered network policy {} ({})", policy.name, id);
+        self.policies.write().await.insert(id, policy);
+        self.sync().await?;
+        Ok(id)
+    }
+
+    /// Remove a policy and lift its restrictions.
+    pub async fn remove_policy(&self, id: &NetworkPolicyId) -> Result<Option<NetworkPolicy>> {
+        let removed = self.policies.write().await.remove(id);
+        if removed.is_some() {
+            self.sync().await?;
+        }
+        Ok(removed)
+    }
+
+    pub async fn get_policy(&self, id: &NetworkPolicyId) -> Option<NetworkPolicy> {
+        self.policies.read().await.get(id).cloned()
+    }
+
+    pub async fn list_policies(&self) -> Vec<NetworkPolicy> {
+        self.policies.read().await.values().cloned().collect()
+    }
+
+    /// The ruleset enforcing the registered policies on the current instances.
+    pub async fn ruleset(&self) -> Result<String> {
+        let workloads = self.state_store.list_workloads().await?;
+        let instances = self.state_store.list_all_instances().await?;
+        let policies = self.list_policies().await;
+        Ok(render_ruleset(&policies, &workloads, &instances))
+    }
+
+    /// Install the current ruleset unless it is already installed.
+    pub async fn sync(&self) -> Result<()> {
+        let ruleset = self.ruleset().await?;
+        let mut applied = self.applied.lock().await;
+        if applied.as_deref() == Some(ruleset.as_str()) {
+            return Ok(());
+        }
+        self.firewall.apply(&ruleset).await?;
+        *applied = Some(ruleset);
+        Ok(())
+    }
+
+    /// Sync every `interval`, as instances start, move and stop.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.sync().await {
+                    error!("Failed to enforce network policies: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// The ingress or egress rules of a policy, if it restricts that direction.
+fn rules_of(policy: &NetworkPolicy, ingress: bool) -> Option<&Vec<PolicyRule>> {
+    if ingress {
+        policy.ingress.as_ref()
+    } else {
+        policy.egress.as_ref()
+    }
+}
+
+/// Addresses of the instances of each workload, sorted.
+fn addresses(instances: &[WorkloadInstance]) -> HashMap<Uuid, Vec<IpAddr>> {
+    let mut addresses: HashMap<Uuid, Vec<IpAddr>> = HashMap::new();
+    for instance in instances {
+        addresses
+            .entry(instance.workload_id)
+            .or_default()
+            .extend(&instance.ip_addresses);
+    }
+    for ips in addresses.values_mut() {
+        ips.sort();
+        ips.dedup();
+    }
+    addresses
+}
+
+/// An nft set of addresses of one family, e.g. `ip saddr { 10.0.0.5 }`.
+fn match_addresses(field: &str, ips: &[IpAddr]) -> Vec<String> {
+    let (v4, v6): (Vec<&IpAddr>, Vec<&IpAddr>) = ips.iter().partition(|ip| ip.is_ipv4());
+    let set = |ips: Vec<&IpAddr>| {
+        let ips: Vec<String> = ips.iter().map(|ip| ip.to_string()).collect();
+        format!("{{ {} }}", ips.join(", "))
+    };
+    let mut matches = Vec::new();
+    if !v4.is_empty() {
+        matches.push(format!("ip {} {}", field, set(v4)));
+    }
+    if !v6.is_empty() {
+        matches.push(format!("ip6 {} {}", field, set(v6)));
+    }
+    matches
+}
+
+/// nft port matches of a rule, one per protocol; a single empty match when
+/// the rule allows every port.
+fn match_ports(ports: &[PolicyPort]) -> Vec<String> {
+    if ports.is_empty() {
+        return vec![String::new()];
+    }
+    let mut by_protocol: BTreeMap<&str, Vec<u16>> = BTreeMap::new();
+    for port in ports {
+        by_protocol
+            .entry(port.protocol.as_str())
+            .or_default()
+            .push(port.port);
+    }
+    by_protocol
+        .into_iter()
+        .map(|(protocol, mut ports)| {
+            ports.sort_unstable();
+            ports.dedup();
+            let ports: Vec<String> = ports.iter().map(u16::to_string).collect();
+            format!(" {} dport {{ {} }}", protocol, ports.join(", "))
+        })
+        .collect()
+}
+
+/// Render the nftables table enforcing `policies` on `instances`.
+///
+/// The table is deleted and recreated in one transaction, so applying the
+/// result replaces the previous ruleset atomically.
+pub fn render_ruleset(
+    policies: &[NetworkPolicy],
+    workloads: &[WorkloadDefinition],
+    instances: &[WorkloadInstance],
+) -> String {
+    let addresses = addresses(instances);
+    let mut policies: Vec<&NetworkPolicy> = policies.iter().collect();
+    policies.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+    let mut workloads: Vec<&WorkloadDefinition> = workloads.iter().collect();
+    workloads.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+
+    // Addresses of the workloads a rule of `policy` selects
+    let peers = |policy: &NetworkPolicy, rule: &PolicyRule| -> Vec<IpAddr> {
+        let mut peers: Vec<IpAddr> = workloads
+            .iter()
+            .filter(|w| w.namespace == policy.namespace && labels_match(&rule.selector, w))
+            .flat_map(|w| addresses.get(&w.id).into_iter().flatten().copied())
+            .collect();
+        peers.sort();
+        peers.dedup();
+        peers
+    };
+
+    let mut jumps = Vec::new();
+    let mut chains = String::new();
+    for (index, workload) in workloads.iter().enumerate() {
+        let Some(own) = addresses.get(&workload.id) else {
+            continue;
+        };
+        let selecting: Vec<&NetworkPolicy> = policies
+            .iter()
+            .copied()
+            .filter(|p| p.selects(workload))
+            .collect();
+
+        for (direction, own_field, peer_field) in
+            [("ingress", "daddr", "saddr"), ("egress", "saddr", "daddr")]
+        {
+            let ingress = direction == "ingress";
+            if !selecting.iter().any(|p| rules_of(p, ingress).is_some()) {
+                continue;
+            }
+            let rules: Vec<(&NetworkPolicy, &PolicyRule)> = selecting
+                .iter()
+                .flat_map(|p| {
+                    rules_of(p, ingress)
+                        .into_iter()
+                        .flatten()
+                        .map(move |r| (*p, r))
+                })
+                .collect();
+
+            let chain = format!("{}_{}", direction, index);
+            for own_match in match_addresses(own_field, own) {
+                jumps.push(format!("{} jump {}", own_match, chain));
+            }
+            let _ = writeln!(chains, "\tchain {} {{", chain);
+            let _ = writeln!(chains, "\t\t# {}/{}", workload.namespace, workload.name);
+            if direction == "egress" {
+                for protocol in ["udp", "tcp"] {
+                    let _ = writeln!(
+                        chains,
+                        "\t\tip daddr {} {} dport 53 accept",
+                        DEFAULT_LISTEN_ADDR.ip(),
+                        protocol
+                    );
+                }
+            }
+            for (policy, rule) in rules {
+                for peer_match in match_addresses(peer_field, &peers(policy, rule)) {
+                    for port_match in match_ports(&rule.ports) {
+                        let _ = writeln!(chains, "\t\t{}{} accept", peer_match, port_match);
+                    }
+                }
+            }
+            let _ = writeln!(chains, "\t\tdrop");
+            let _ = writeln!(chains, "\t}}");
+        }
+    }
+
+    let mut ruleset = format!("table inet {0}\ndelete table inet {0}\n", NFT_TABLE);
+    let _ = writeln!(ruleset, "table inet {} {{", NFT_TABLE);
+    let _ = writeln!(ruleset, "\tchain forward {{");
+    let _ = writeln!(
+        ruleset,
+        "\t\ttype filter hook forward priority filter; policy accept;"
+    );
+    let _ = writeln!(ruleset, "\t\tct state established,related accept");
+    for jump in jumps {
+        let _ = writeln!(ruleset, "\t\t{}", jump);
+    }
+    let _ = writeln!(ruleset, "\t}}");
+    ruleset.push_str(&chains);
+    ruleset.push_str("}\n");
+    ruleset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use orchestrator_shared_types::{Keypair, WorkloadInstanceStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    fn workload(name: &str) -> WorkloadDefinition {
+        WorkloadDefinition {
+            labels: app(name),
+            ..test_support::workload(name)
+        }
+    }
+
+    fn instance(workload: &WorkloadDefinition, ip: &str) -> WorkloadInstance {
+        WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: workload.id,
+            node_id: Keypair::generate().public_key(),
+            container_ids: vec![],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![ip.parse().unwrap()],
+            restarts: vec![],
+            namespace: workload.namespace.clone(),
+            resource_version: 0,
+        }
+    }
+
+    fn app(name: &str) -> HashMap<String, String> {
+        HashMap::from([("app".to_string(), name.to_string())])
+    }
+
+    #[derive(Default)]
+    struct FakeFirewall {
+        applied: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Firewall for FakeFirewall {
+        async fn apply(&self, ruleset: &str) -> Result<()> {
+            self.applied.lock().unwrap().push(ruleset.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_render_isolates_payments_from_all_but_the_gateway() {
+        let payments = workload("payments");
+        let gateway = workload("api-gateway");
+        let web = workload("web");
+        let instances = vec![
+            instance(&payments, "10.42.0.5"),
+            instance(&payments, "fd00::5"),
+            instance(&gateway, "10.42.0.9"),
+            instance(&web, "10.42.0.7"),
+        ];
+        let policy = NetworkPolicy::new("payments-ingress", app("payments")).with_ingress(vec![
+            PolicyRule::from_workloads(app("api-gateway")).with_port("tcp", 8443),
+        ]);
+
+        let ruleset = render_ruleset(&[policy], &[payments, gateway, web], &instances);
+        let lines: Vec<&str> = ruleset.lines().map(str::trim).collect();
+        // Sorted by name: api-gateway, payments, web
+        assert!(lines.contains(&"ip daddr { 10.42.0.5 } jump ingress_1"));
+        assert!(lines.contains(&"ip6 daddr { fd00::5 } jump ingress_1"));
+        assert!(lines.contains(&"ip saddr { 10.42.0.9 } tcp dport { 8443 } accept"));
+        assert!(!ruleset.contains("10.42.0.7"));
+        assert!(!ruleset.contains("egress"));
+        assert!(ruleset.starts_with("table inet orchestrator_policy\ndelete table"));
+    }
+
+    #[test]
+    fn test_render_empty_egress_allows_only_dns() {
+        let batch = workload("batch");
+        let instances = vec![instance(&batch, "10.42.0.3")];
+        let policy = NetworkPolicy::new("no-egress", app("batch")).with_egress(vec![]);
+
+        let ruleset = render_ruleset(&[policy], &[batch], &instances);
+        assert!(ruleset.contains("ip saddr { 10.42.0.3 } jump egress_0"));
+        let chain: Vec<&str> = ruleset
+            .split("chain egress_0 {")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .map(str::trim)
+            .collect();
+        assert_eq!(chain[2], "ip daddr 169.254.20.10 udp dport 53 accept");
+        assert_eq!(chain[4], "drop");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(NetworkPolicy::new("p", app("a")).validate().is_err());
+        let policy = NetworkPolicy::new("p", app("a"))
+            .with_ingress(vec![PolicyRule::default().with_port("sctp", 80)]);
+        assert!(policy.validate().is_err());
+        let policy = NetworkPolicy::new("p", app("a"))
+            .with_ingress(vec![PolicyRule::default().with_port("udp", 53)]);
+        assert!(policy.validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_controller_applies_changes_once() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let payments = workload("payments");
+        store.put_workload(payments.clone()).await.unwrap();
+        store
+            .put_instance(instance(&payments, "10.42.0.5"))
+            .await
+            .unwrap();
+        let firewall = Arc::new(FakeFirewall::default());
+        let controller = NetworkPolicyController::new(store, firewall.clone());
+
+        let id = controller
+            .add_policy(NetworkPolicy::new("deny", app("payments")).with_ingress(vec![]))
+            .await
+            .unwrap();
+        controller.sync().await.unwrap();
+        assert_eq!(firewall.applied.lock().unwrap().len(), 1);
+        assert!(firewall.applied.lock().unwrap()[0].contains("jump ingress_0"));
+
+        controller.remove_policy(&id).await.unwrap();
+        let applied = firewall.applied.lock().unwrap();
+        assert_eq!(applied.len(), 2);
+        assert!(!applied[1].contains("jump"));
+    }
+}
@@ -0,0 +1,349 @@
+//! Host port publishing.
+//!
+//! A container port with a `host_port` is published on the node running the
+//! instance: connections to that port on the node are forwarded to the
+//! instance's address of the same family. With a `host_ip`, only connections
+//! to that address are forwarded, to the instance's address of its family;
+//! without one, the port is published on every local address of each family
+//! the instance has an address in. A port already published on the node by
+//! another instance stays with the first, in instance id order.
+//!
+//! The [`HostPortPublisher`] renders the ports of the node's running
+//! instances into one nftables table of DNAT rules, and replaces it whenever
+//! they change.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+
+use orchestrator_shared_types::{
+    format_host_port, IpFamily, NodeId, PortMapping, Result, WorkloadDefinition, WorkloadInstance,
+    WorkloadInstanceStatus,
+};
+use state_store_interface::StateStore;
+
+use super::policy::Firewall;
+
+/// Name of the nftables table host ports are published in.
+pub const NFT_TABLE: &str = "orchestrator_ports";
+
+/// Publishes the host ports of the instances running on one node.
+pub struct HostPortPublisher {
+    state_store: Arc<dyn StateStore>,
+    firewall: Arc<dyn Firewall>,
+    node_id: NodeId,
+    /// Last ruleset installed, so unchanged rulesets are not applied again.
+    applied: Mutex<Option<String>>,
+}
+
+impl HostPortPublisher {
+    /// How often the ruleset is brought up to date with the instances.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(
+        state_store: Arc<dyn StateStore>,
+        firewall: Arc<dyn Firewall>,
+        node_id: NodeId,
+    ) -> Self {
+        Self {
+            state_store,
+            firewall,
+            node_id,
+            applied: Mutex::new(None),
+        }
+    }
+
+    /// The ruleset publishing the ports of the node's running instances.
+    pub async fn ruleset(&self) -> Result<String> {
+        let workloads = self.state_store.list_workloads().await?;
+        let instances: Vec<WorkloadInstance> = self
+            .state_store
+            .list_all_instances()
+            .await?
+            .into_iter()
+            .filter(|i| i.node_id == self.node_id && i.status == WorkloadInstanceStatus::Running)
+            .collect();
+        Ok(render_ruleset(&workloads, &instances))
+    }
+
+    /// Install the current ruleset unless it is already installed.
+    pub async fn sync(&self) -> Result<()> {
+        let ruleset = self.ruleset().await?;
+        let mut applied = self.applied.lock().await;
+        if applied.as_deref() == Some(ruleset.as_str()) {
+            return Ok(());
+        }
+        self.firewall.apply(&ruleset).await?;
+        *applied = Some(ruleset);
+        Ok(())
+    }
+
+    /// Sync every `interval`, as instances start, move and stop.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.sync().await {
+                    error!("Failed to publish host ports: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// nft DNAT rules publishing `port` on the node, forwarding to `addresses`.
+fn dnat_rules(port: &PortMapping, host_port: u16, addresses: &[IpAddr]) -> Vec<String> {
+    let protocol = port.protocol.to_ascii_lowercase();
+    let families = match port.host_ip {
+        Some(host_ip) => vec![IpFamily::of(&host_ip)],
+        None => vec![IpFamily::IPv4, IpFamily::IPv6],
+    };
+    families
+        .into_iter()
+        .filter_map(|family| {
+            let target = addresses.iter().find(|a| IpFamily::of(a) == family)?;
+            let (nfproto, nat) = match family {
+                IpFamily::IPv4 => ("ipv4", "ip"),
+                IpFamily::IPv6 => ("ipv6", "ip6"),
+            };
+            let destination = match port.host_ip {
+                // The wildcard address stands for every local address
+                Some(host_ip) if !host_ip.is_unspecified() => {
+                    format!("{} daddr {}", nat, host_ip)
+                }
+                _ => format!("meta nfproto {}", nfproto),
+            };
+            Some(format!(
+                "{} {} dport {} dnat {} to {}",
+                destination,
+                protocol,
+                host_port,
+                nat,
+                format_host_port(target, port.container_port)
+            ))
+        })
+        .collect()
+}
+
+/// Render the nftables table publishing the host ports of `instances`.
+///
+/// The table is deleted and recreated in one transaction, so applying the
+/// result replaces the previous ruleset atomically.
+pub fn render_ruleset(workloads: &[WorkloadDefinition], instances: &[WorkloadInstance]) -> String {
+    let workloads: HashMap<_, _> = workloads.iter().map(|w| (w.id, w)).collect();
+    let mut instances: Vec<&WorkloadInstance> = instances.iter().collect();
+    instances.sort_by_key(|i| i.id);
+
+    let mut published = HashSet::new();
+    let mut rules = String::new();
+    for instance in instances {
+        let Some(workload) = workloads.get(&instance.workload_id) else {
+            continue;
+        };
+        let ports = workload
+            .containers
+            .iter()
+            .chain(&workload.sidecars)
+            .flat_map(|c| &c.ports);
+        for port in ports {
+            let Some(host_port) = port.host_port else {
+                continue;
+            };
+            let key = (port.host_ip, port.protocol.to_ascii_lowercase(), host_port);
+            if !published.insert(key) {
+                warn!(
+                    "Host port {} of {}/{} is already published on this node",
+                    host_port, workload.namespace, workload.name
+                );
+                continue;
+            }
+            let dnat = dnat_rules(port, host_port, &instance.ip_addresses);
+            if dnat.is_empty() {
+                continue;
+            }
+            let _ = writeln!(
+                rules,
+                "\t\t# {}/{} instance {}",
+                workload.namespace, workload.name, instance.id
+            );
+            for rule in dnat {
+                let _ = writeln!(rules, "\t\t{}", rule);
+            }
+        }
+    }
+
+    let mut ruleset = format!("table inet {0}\ndelete table inet {0}\n", NFT_TABLE);
+    let _ = writeln!(ruleset, "table inet {} {{", NFT_TABLE);
+    for (chain, hook, priority) in [
+        ("prerouting", "prerouting", "dstnat"),
+        ("output", "output", "-100"),
+    ] {
+        let _ = writeln!(ruleset, "\tchain {} {{", chain);
+        let _ = writeln!(
+            ruleset,
+            "\t\ttype nat hook {} priority {}; policy accept;",
+            hook, priority
+        );
+        let _ = writeln!(ruleset, "\t\tfib daddr type local jump publish");
+        let _ = writeln!(ruleset, "\t}}");
+    }
+    let _ = writeln!(ruleset, "\tchain publish {{");
+    ruleset.push_str(&rules);
+    let _ = writeln!(ruleset, "\t}}");
+    ruleset.push_str("}\n");
+    ruleset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use orchestrator_shared_types::{ContainerConfig, Keypair};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use uuid::Uuid;
+
+    fn port(host_port: u16, host_ip: Option<&str>) -> PortMapping {
+        PortMapping {
+            container_port: 80,
+            host_port: Some(host_port),
+            protocol: "tcp".to_string(),
+            host_ip: host_ip.map(|ip| ip.parse().unwrap()),
+        }
+    }
+
+    fn workload(name: &str, ports: Vec<PortMapping>) -> WorkloadDefinition {
+        WorkloadDefinition {
+            containers: vec![ContainerConfig {
+                image: "nginx:latest".to_string(),
+                ports,
+                ..test_support::container(name)
+            }],
+            ..test_support::workload(name)
+        }
+    }
+
+    fn instance(workload: &WorkloadDefinition, node_id: NodeId, ips: &[&str]) -> WorkloadInstance {
+        WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: workload.id,
+            node_id,
+            container_ids: vec![],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: ips.iter().map(|ip| ip.parse().unwrap()).collect(),
+            restarts: vec![],
+            namespace: workload.namespace.clone(),
+            resource_version: 0,
+        }
+    }
+
+    fn publish_chain(ruleset: &str) -> Vec<&str> {
+        ruleset
+            .split("chain publish {")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#') && *line != "}" && !line.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn test_render_without_host_ip_publishes_every_family() {
+        let node = Keypair::generate().public_key();
+        let web = workload("web", vec![port(8080, None)]);
+        let instances = vec![instance(&web, node, &["10.244.0.5", "fd00::5"])];
+
+        let ruleset = render_ruleset(&[web], &instances);
+        assert!(ruleset.starts_with("table inet orchestrator_ports\ndelete table"));
+        assert_eq!(
+            publish_chain(&ruleset),
+            vec![
+                "meta nfproto ipv4 tcp dport 8080 dnat ip to 10.244.0.5:80",
+                "meta nfproto ipv6 tcp dport 8080 dnat ip6 to [fd00::5]:80",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_honors_host_ip() {
+        let node = Keypair::generate().public_key();
+        let loopback = workload("admin", vec![port(9000, Some("127.0.0.1"))]);
+        let v6 = workload("api", vec![port(8443, Some("::"))]);
+        let instances = vec![
+            instance(&loopback, node, &["10.244.0.6", "fd00::6"]),
+            instance(&v6, node, &["10.244.0.7", "fd00::7"]),
+        ];
+
+        let rules = render_ruleset(&[loopback, v6], &instances);
+        let mut rules = publish_chain(&rules);
+        rules.sort();
+        assert_eq!(
+            rules,
+            vec![
+                "ip daddr 127.0.0.1 tcp dport 9000 dnat ip to 10.244.0.6:80",
+                "meta nfproto ipv6 tcp dport 8443 dnat ip6 to [fd00::7]:80",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_skips_ports_taken_and_families_missing() {
+        let node = Keypair::generate().public_key();
+        let web = workload("web", vec![port(8080, None), port(8081, Some("fd00::1"))]);
+        let instances = vec![
+            instance(&web, node, &["10.244.0.5"]),
+            instance(&web, node, &["10.244.0.6"]),
+        ];
+
+        let ruleset = render_ruleset(&[web], &instances);
+        let rules = publish_chain(&ruleset);
+        assert_eq!(rules.len(), 1);
+        assert!(rules[0].starts_with("meta nfproto ipv4 tcp dport 8080"));
+    }
+
+    #[derive(Default)]
+    struct FakeFirewall {
+        applied: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Firewall for FakeFirewall {
+        async fn apply(&self, ruleset: &str) -> Result<()> {
+            self.applied.lock().unwrap().push(ruleset.to_string());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publisher_covers_only_its_node() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let local = Keypair::generate().public_key();
+        let remote = Keypair::generate().public_key();
+        let web = workload("web", vec![port(8080, None)]);
+        store.put_workload(web.clone()).await.unwrap();
+        store
+            .put_instance(instance(&web, local, &["10.244.0.5"]))
+            .await
+            .unwrap();
+        store
+            .put_instance(instance(&web, remote, &["10.244.1.5"]))
+            .await
+            .unwrap();
+        let firewall = Arc::new(FakeFirewall::default());
+        let publisher = HostPortPublisher::new(store, firewall.clone(), local);
+
+        publisher.sync().await.unwrap();
+        publisher.sync().await.unwrap();
+        let applied = firewall.applied.lock().unwrap();
+        assert_eq!(applied.len(), 1);
+        assert!(applied[0].contains("10.244.0.5:80"));
+        assert!(!applied[0].contains("10.244.1.5"));
+    }
+}
@@ -0,0 +1,805 @@
+//! Service proxy routing table.
+//!
+//! [`ServiceProxy`] holds the registered services and their current endpoints
+//! and picks a backend for each new connection. Selection is smooth weighted
+//! round-robin, so a 90/10 split interleaves evenly instead of sending bursts
+//! to one side, and is deterministic given the same sequence of calls.
+//!
+//! Services are kept in the state store, so a service added through any
+//! node's proxy is routed by every node. [`ServiceProxy::sync`], which
+//! [`ServiceProxy::spawn`] runs periodically, picks up stored services and
+//! refreshes every service's endpoints. The
+//! [`ServiceForwarder`](super::forwarder::ServiceForwarder) carries
+//! connections to the services' virtual IPs to the endpoints selected here.
+//!
+//! Changes that must not wait for the next sync are pushed in directly:
+//! readiness verdicts from the
+//! [`ReadinessProber`](super::readiness::ReadinessProber) via
+//! [`ServiceProxy::set_instance_ready`], and instances starting to terminate
+//! via [`ServiceProxy::instance_terminating`].
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use orchestrator_shared_types::{
+    OrchestrationError, Result, WorkloadDefinition, WorkloadId, WorkloadInstance,
+};
+use state_store_interface::StateStore;
+
+use super::ipam::DualStackAllocator;
+use super::service::{
+    build_endpoints, Endpoint, Service, ServiceEndpoints, ServiceId, SessionAffinity,
+};
+use crate::resources;
+
+struct ServiceEntry {
+    service: Service,
+    endpoints: Vec<Endpoint>,
+    /// Smooth weighted round-robin state per endpoint instance.
+    current_weights: HashMap<Uuid, i64>,
+    /// Client IP to (endpoint instance, last used).
+    affinity: HashMap<IpAddr, (Uuid, Instant)>,
+}
+
+impl ServiceEntry {
+    fn new(service: Service) -> Self {
+        Self {
+            service,
+            endpoints: Vec::new(),
+            current_weights: HashMap::new(),
+            affinity: HashMap::new(),
+        }
+    }
+
+    /// Replace the service definition, keeping bindings that still apply.
+    fn update(&mut self, service: Service) {
+        if service.session_affinity != self.service.session_affinity {
+            self.affinity.clear();
+        }
+        self.service = service;
+    }
+
+    /// Replace the endpoints, dropping state kept for ones that are gone.
+    fn set_endpoints(&mut self, endpoints: Vec<Endpoint>) {
+        let live: HashSet<Uuid> = endpoints.iter().map(|e| e.instance_id).collect();
+        self.current_weights.retain(|id, _| live.contains(id));
+        self.affinity
+            .retain(|_, (instance_id, _)| live.contains(instance_id));
+        // Weights may have changed since the endpoints were computed
+        self.endpoints = endpoints
+            .into_iter()
+            .map(|mut e| {
+                e.weight = self.service.weight_for(&e.workload_id);
+                e
+            })
+            .collect();
+    }
+}
+
+/// Registered services, their endpoints, and per-connection backend selection.
+pub struct ServiceProxy {
+    state_store: Arc<dyn StateStore>,
+    vip_allocator: Option<Mutex<DualStackAllocator>>,
+    services: RwLock<HashMap<ServiceId, ServiceEntry>>,
+    /// Latest readiness probe verdict per instance.
+    readiness: RwLock<HashMap<Uuid, bool>>,
+    /// Instances that have started terminating but may still be in the store.
+    terminating: RwLock<HashSet<Uuid>>,
+}
+
+impl ServiceProxy {
+    /// How often endpoints are refreshed from the state store.
+    pub const DEFAULT_SYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(state_store: Arc<dyn StateStore>) -> Self {
+        Self {
+            state_store,
+            vip_allocator: None,
+            services: RwLock::new(HashMap::new()),
+            readiness: RwLock::new(HashMap::new()),
+            terminating: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// Assign virtual IPs to services from `allocator` when they are added.
+    pub fn with_vip_allocator(mut self, allocator: DualStackAllocator) -> Self {
+        self.vip_allocator = Some(Mutex::new(allocator));
+        self
+    }
+
+    /// Register a service, allocating its virtual IPs and computing its endpoints.
+    ///
+    /// The service is stored, so every other node's proxy picks it up on its
+    /// next sync.
+    pub async fn add_service(&self, mut service: Service) -> Result<Service> {
+        if service.cluster_ips.is_empty() {
+            if let Some(allocator) = &self.vip_allocator {
+                // Addresses given out through other nodes are taken too
+                let stored: Vec<Service> = resources::list(self.state_store.as_ref()).await?;
+                let mut allocator = allocator.lock().unwrap();
+                for ip in stored.iter().flat_map(|s| &s.cluster_ips) {
+                    let _ = allocator.reserve(ip);
+                }
+                service.cluster_ips = allocator.allocate(service.ip_family_policy)?;
+            }
+        }
+        if let Err(e) = resources::put(self.state_store.as_ref(), &service).await {
+            if let Some(allocator) = &self.vip_allocator {
+                allocator.lock().unwrap().release_all(&service.cluster_ips);
+            }
+            return Err(e);
+        }
+
+        let endpoints = self.current_endpoints(&service).await?;
+        info!(
+            "Registered service {} ({}) with {} endpoints",
+            service.name,
+            service.id,
+            endpoints.len()
+        );
+        let mut entry = ServiceEntry::new(service.clone());
+        entry.set_endpoints(endpoints);
+        self.services.write().unwrap().insert(service.id, entry);
+        Ok(service)
+    }
+
+    /// Replace a service's selector, ports, session affinity and backend
+    /// weights with those of `update`. Its name, namespace and virtual IPs
+    /// stay as they are.
+    pub async fn update_service(&self, update: Service) -> Result<Service> {
+        let mut service: Service =
+            resources::get(self.state_store.as_ref(), &update.id.to_string())
+                .await?
+                .ok_or_else(|| not_found(&update.id))?;
+        service.selector = update.selector;
+        service.ports = update.ports;
+        service.session_affinity = update.session_affinity;
+        service.backend_weights = update.backend_weights;
+        resources::put(self.state_store.as_ref(), &service).await?;
+
+        let endpoints = self.current_endpoints(&service).await?;
+        let mut services = self.services.write().unwrap();
+        let entry = services
+            .entry(service.id)
+            .or_insert_with(|| ServiceEntry::new(service.clone()));
+        entry.update(service.clone());
+        entry.set_endpoints(endpoints);
+        info!("Updated service {} ({})", service.name, service.id);
+        Ok(service)
+    }
+
+    /// Remove a service and release its virtual IPs.
+    pub async fn remove_service(&self, id: &ServiceId) -> Result<Option<Service>> {
+        let stored: Option<Service> =
+            resources::get(self.state_store.as_ref(), &id.to_string()).await?;
+        resources::delete::<Service>(self.state_store.as_ref(), &id.to_string()).await?;
+        let entry = self.services.write().unwrap().remove(id);
+        let service = entry.map(|e| e.service).or(stored);
+        if let (Some(service), Some(allocator)) = (&service, &self.vip_allocator) {
+            allocator.lock().unwrap().release_all(&service.cluster_ips);
+        }
+        Ok(service)
+    }
+
+    pub fn get_service(&self, id: &ServiceId) -> Option<Service> {
+        self.services
+            .read()
+            .unwrap()
+            .get(id)
+            .map(|e| e.service.clone())
+    }
+
+    pub fn list_services(&self) -> Vec<Service> {
+        self.services
+            .read()
+            .unwrap()
+            .values()
+            .map(|e| e.service.clone())
+            .collect()
+    }
+
+    /// Change how traffic is split across a service's backend workloads.
+    ///
+    /// Existing affinity to endpoints whose weight drops to zero is discarded
+    /// so draining a workload takes effect immediately.
+    pub async fn set_backend_weight(
+        &self,
+        id: &ServiceId,
+        workload_id: WorkloadId,
+        weight: u32,
+    ) -> Result<()> {
+        let service = {
+            let mut services = self.services.write().unwrap();
+            let entry = services.get_mut(id).ok_or_else(|| not_found(id))?;
+            entry.service.backend_weights.insert(workload_id, weight);
+            for endpoint in entry
+                .endpoints
+                .iter_mut()
+                .filter(|e| e.workload_id == workload_id)
+            {
+                endpoint.weight = weight;
+            }
+            if weight == 0 {
+                let drained: Vec<Uuid> = entry
+                    .endpoints
+                    .iter()
+                    .filter(|e| e.workload_id == workload_id)
+                    .map(|e| e.instance_id)
+                    .collect();
+                entry
+                    .affinity
+                    .retain(|_, (instance_id, _)| !drained.contains(instance_id));
+            }
+            entry.service.clone()
+        };
+        resources::put(self.state_store.as_ref(), &service).await
+    }
+
+    /// Change a service's session affinity, clearing any existing bindings.
+    pub async fn set_session_affinity(
+        &self,
+        id: &ServiceId,
+        affinity: SessionAffinity,
+    ) -> Result<()> {
+        let service = {
+            let mut services = self.services.write().unwrap();
+            let entry = services.get_mut(id).ok_or_else(|| not_found(id))?;
+            entry.service.session_affinity = affinity;
+            entry.affinity.clear();
+            entry.service.clone()
+        };
+        resources::put(self.state_store.as_ref(), &service).await
+    }
+
+    /// Current endpoints of a service.
+    pub fn endpoints(&self, id: &ServiceId) -> Option<ServiceEndpoints> {
+        self.services
+            .read()
+            .unwrap()
+            .get(id)
+            .map(|e| ServiceEndpoints {
+                service_id: e.service.id,
+                session_affinity: e.service.session_affinity,
+                endpoints: e.endpoints.clone(),
+            })
+    }
+
+    /// Latest readiness verdict for an instance, if it has been probed.
+    pub fn instance_ready(&self, instance_id: &Uuid) -> Option<bool> {
+        self.readiness.read().unwrap().get(instance_id).copied()
+    }
+
+    /// Record a readiness verdict for an instance and apply it to every
+    /// service it backs. A not-ready instance stops receiving new connections
+    /// and loses its session affinity bindings immediately.
+    pub fn set_instance_ready(&self, instance_id: Uuid, ready: bool) {
+        let previous = self.readiness.write().unwrap().insert(instance_id, ready);
+        if previous == Some(ready) {
+            return;
+        }
+        let mut services = self.services.write().unwrap();
+        for entry in services.values_mut() {
+            let mut found = false;
+            for endpoint in entry
+                .endpoints
+                .iter_mut()
+                .filter(|e| e.instance_id == instance_id)
+            {
+                found = true;
+                if !ready {
+                    endpoint.ready = false;
+                }
+            }
+            if found && !ready {
+                info!(
+                    "Service {}: endpoint {} is no longer ready",
+                    entry.service.name, instance_id
+                );
+                entry.affinity.retain(|_, (id, _)| *id != instance_id);
+            }
+        }
+        // Becoming ready also requires the instance to be Running, which the
+        // next sync checks against the store
+    }
+
+    /// Remove an instance from every service as soon as it starts terminating.
+    pub fn instance_terminating(&self, instance_id: Uuid) {
+        self.terminating.write().unwrap().insert(instance_id);
+        let mut services = self.services.write().unwrap();
+        for entry in services.values_mut() {
+            let before = entry.endpoints.len();
+            entry.endpoints.retain(|e| e.instance_id != instance_id);
+            if entry.endpoints.len() != before {
+                info!(
+                    "Service {}: removed terminating endpoint {}",
+                    entry.service.name, instance_id
+                );
+                entry.current_weights.remove(&instance_id);
+                entry.affinity.retain(|_, (id, _)| *id != instance_id);
+            }
+        }
+    }
+
+    /// Pick the endpoint a new connection from `client_ip` should go to.
+    pub fn select_endpoint(&self, id: &ServiceId, client_ip: IpAddr) -> Option<Endpoint> {
+        self.select_endpoint_at(id, client_ip, Instant::now())
+    }
+
+    fn select_endpoint_at(
+        &self,
+        id: &ServiceId,
+        client_ip: IpAddr,
+        now: Instant,
+    ) -> Option<Endpoint> {
+        let mut services = self.services.write().unwrap();
+        let entry = services.get_mut(id)?;
+
+        if let SessionAffinity::ClientIp { timeout_seconds } = entry.service.session_affinity {
+            let timeout = Duration::from_secs(timeout_seconds);
+            if let Some((instance_id, last_used)) = entry.affinity.get(&client_ip).copied() {
+                let still_routable = entry
+                    .endpoints
+                    .iter()
+                    .find(|e| e.instance_id == instance_id && e.is_routable())
+                    .cloned();
+                match still_routable {
+                    Some(endpoint) if now.duration_since(last_used) < timeout => {
+                        entry.affinity.insert(client_ip, (instance_id, now));
+                        return Some(endpoint);
+                    }
+                    _ => {
+                        entry.affinity.remove(&client_ip);
+                    }
+                }
+            }
+        }
+
+        let endpoint = Self::next_weighted(entry)?;
+        if matches!(
+            entry.service.session_affinity,
+            SessionAffinity::ClientIp { .. }
+        ) {
+            entry
+                .affinity
+                .insert(client_ip, (endpoint.instance_id, now));
+        }
+        Some(endpoint)
+    }
+
+    /// Smooth weighted round-robin over the routable endpoints.
+    fn next_weighted(entry: &mut ServiceEntry) -> Option<Endpoint> {
+        let mut total = 0i64;
+        let mut best: Option<(usize, i64)> = None;
+        for (index, endpoint) in entry.endpoints.iter().enumerate() {
+            if !endpoint.is_routable() {
+                continue;
+            }
+            let weight = endpoint.weight as i64;
+            total += weight;
+            let current = entry
+                .current_weights
+                .entry(endpoint.instance_id)
+                .or_insert(0);
+            *current += weight;
+            if best.is_none_or(|(_, w)| *current > w) {
+                best = Some((index, *current));
+            }
+        }
+        let (index, _) = best?;
+        let endpoint = entry.endpoints[index].clone();
+        if let Some(current) = entry.current_weights.get_mut(&endpoint.instance_id) {
+            *current -= total;
+        }
+        Some(endpoint)
+    }
+
+    /// Pick up the stored services and recompute every service's endpoints
+    /// from the state store.
+    pub async fn sync(&self) -> Result<()> {
+        let stored: Vec<Service> = resources::list(self.state_store.as_ref()).await?;
+        let workloads = self.state_store.list_workloads().await?;
+        let instances = self.state_store.list_all_instances().await?;
+
+        // Forget verdicts and terminations for instances that are gone
+        let present: HashSet<Uuid> = instances.iter().map(|i| i.id).collect();
+        self.readiness
+            .write()
+            .unwrap()
+            .retain(|id, _| present.contains(id));
+        self.terminating
+            .write()
+            .unwrap()
+            .retain(|id| present.contains(id));
+
+        self.adopt(stored);
+        let services: Vec<Service> = self.list_services();
+        for service in services {
+            let endpoints = self.compute_endpoints(&service, &workloads, &instances);
+            if let Some(entry) = self.services.write().unwrap().get_mut(&service.id) {
+                entry.set_endpoints(endpoints);
+            }
+        }
+        Ok(())
+    }
+
+    /// Bring the registered services in line with the stored ones.
+    fn adopt(&self, stored: Vec<Service>) {
+        let mut services = self.services.write().unwrap();
+        let mut allocator = self.vip_allocator.as_ref().map(|a| a.lock().unwrap());
+        let ids: HashSet<ServiceId> = stored.iter().map(|s| s.id).collect();
+        services.retain(|id, entry| {
+            if ids.contains(id) {
+                return true;
+            }
+            info!("Service {} ({}) was removed", entry.service.name, id);
+            if let Some(allocator) = allocator.as_mut() {
+                allocator.release_all(&entry.service.cluster_ips);
+            }
+            false
+        });
+        for service in stored {
+            match services.get_mut(&service.id) {
+                Some(entry) => entry.update(service),
+                None => {
+                    if let Some(allocator) = allocator.as_mut() {
+                        for ip in &service.cluster_ips {
+                            let _ = allocator.reserve(ip);
+                        }
+                    }
+                    info!("Picked up service {} ({})", service.name, service.id);
+                    services.insert(service.id, ServiceEntry::new(service));
+                }
+            }
+        }
+    }
+
+    /// Keep endpoints in sync with the state store every `interval`.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.sync().await {
+                    error!("Service endpoint sync failed: {:?}", e);
+                } else {
+                    debug!("Service endpoints synced");
+                }
+            }
+        })
+    }
+
+    async fn current_endpoints(&self, service: &Service) -> Result<Vec<Endpoint>> {
+        let workloads = self.state_store.list_workloads().await?;
+        let instances = self.state_store.list_all_instances().await?;
+        Ok(self.compute_endpoints(service, &workloads, &instances))
+    }
+
+    fn compute_endpoints(
+        &self,
+        service: &Service,
+        workloads: &[WorkloadDefinition],
+        instances: &[WorkloadInstance],
+    ) -> Vec<Endpoint> {
+        let terminating = self.terminating.read().unwrap();
+        let live: Vec<WorkloadInstance> = instances
+            .iter()
+            .filter(|i| !terminating.contains(&i.id))
+            .cloned()
+            .collect();
+        build_endpoints(service, workloads, &live, &self.readiness.read().unwrap())
+    }
+}
+
+fn not_found(id: &ServiceId) -> OrchestrationError {
+    OrchestrationError::NetworkError(format!("Service {} not found", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orchestrator_shared_types::{IpFamilyPolicy, Keypair, WorkloadInstanceStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    struct Fixture {
+        proxy: ServiceProxy,
+        store: Arc<InMemoryStateStore>,
+        stable: WorkloadId,
+        canary: WorkloadId,
+    }
+
+    async fn put_workload(store: &InMemoryStateStore, track: &str, replicas: usize) -> WorkloadId {
+        let workload = WorkloadDefinition {
+            id: Uuid::new_v4(),
+            name: format!("web-{}", track),
+            containers: vec![],
+            replicas: replicas as u32,
+            labels: HashMap::from([
+                ("app".to_string(), "web".to_string()),
+                ("track".to_string(), track.to_string()),
+            ]),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        let id = workload.id;
+        store.put_workload(workload).await.unwrap();
+        for _ in 0..replicas {
+            store
+                .put_instance(WorkloadInstance {
+                    id: Uuid::new_v4(),
+                    workload_id: id,
+                    node_id: Keypair::generate().public_key(),
+                    container_ids: vec![],
+                    status: WorkloadInstanceStatus::Running,
+                    ip_addresses: vec![],
+                    restarts: vec![],
+                    namespace: "default".to_string(),
+                    resource_version: 0,
+                })
+                .await
+                .unwrap();
+        }
+        id
+    }
+
+    async fn fixture() -> Fixture {
+        let store = Arc::new(InMemoryStateStore::new());
+        let stable = put_workload(&store, "stable", 2).await;
+        let canary = put_workload(&store, "canary", 1).await;
+        let proxy = ServiceProxy::new(store.clone());
+        Fixture {
+            proxy,
+            store,
+            stable,
+            canary,
+        }
+    }
+
+    fn web_service() -> Service {
+        Service::new(
+            "web",
+            HashMap::from([("app".to_string(), "web".to_string())]),
+        )
+        .with_port(80, 8080, "tcp")
+    }
+
+    fn client(n: u8) -> IpAddr {
+        IpAddr::from([192, 168, 0, n])
+    }
+
+    #[tokio::test]
+    async fn test_weighted_split() {
+        let f = fixture().await;
+        let service = f
+            .proxy
+            .add_service(
+                web_service()
+                    .with_backend_weight(f.stable, 40)
+                    .with_backend_weight(f.canary, 20),
+            )
+            .await
+            .unwrap();
+
+        // Two stable endpoints at 40 each, one canary at 20: 80/20 split
+        let mut canary_hits = 0;
+        for i in 0..100 {
+            let endpoint = f.proxy.select_endpoint(&service.id, client(i)).unwrap();
+            if endpoint.workload_id == f.canary {
+                canary_hits += 1;
+            }
+        }
+        assert_eq!(canary_hits, 20);
+
+        // Promote: drain stable
+        f.proxy
+            .set_backend_weight(&service.id, f.stable, 0)
+            .await
+            .unwrap();
+        for i in 0..10 {
+            let endpoint = f.proxy.select_endpoint(&service.id, client(i)).unwrap();
+            assert_eq!(endpoint.workload_id, f.canary);
+        }
+        let status = f.proxy.endpoints(&service.id).unwrap();
+        assert_eq!(status.traffic_split()[&f.canary], 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_client_ip_affinity_sticks_until_timeout() {
+        let f = fixture().await;
+        let service = f
+            .proxy
+            .add_service(
+                web_service().with_session_affinity(SessionAffinity::ClientIp {
+                    timeout_seconds: 60,
+                }),
+            )
+            .await
+            .unwrap();
+
+        let now = Instant::now();
+        let first = f
+            .proxy
+            .select_endpoint_at(&service.id, client(1), now)
+            .unwrap();
+        for i in 1..10 {
+            let again = f
+                .proxy
+                .select_endpoint_at(&service.id, client(1), now + Duration::from_secs(i * 30))
+                .unwrap();
+            assert_eq!(again.instance_id, first.instance_id);
+        }
+
+        // After an idle period longer than the timeout the client is rebalanced
+        let later = now + Duration::from_secs(9 * 30 + 61);
+        let other = f
+            .proxy
+            .select_endpoint_at(&service.id, client(2), later)
+            .unwrap();
+        let rebalanced = f
+            .proxy
+            .select_endpoint_at(&service.id, client(1), later)
+            .unwrap();
+        assert_ne!(other.instance_id, rebalanced.instance_id);
+    }
+
+    #[tokio::test]
+    async fn test_affinity_dropped_when_endpoint_drained() {
+        let f = fixture().await;
+        let service = f
+            .proxy
+            .add_service(web_service().with_session_affinity(SessionAffinity::client_ip()))
+            .await
+            .unwrap();
+
+        let mut pinned = None;
+        for i in 0..10 {
+            let endpoint = f.proxy.select_endpoint(&service.id, client(i)).unwrap();
+            if endpoint.workload_id == f.canary {
+                pinned = Some(client(i));
+                break;
+            }
+        }
+        let pinned = pinned.expect("some client lands on the canary");
+
+        f.proxy
+            .set_backend_weight(&service.id, f.canary, 0)
+            .await
+            .unwrap();
+        let endpoint = f.proxy.select_endpoint(&service.id, pinned).unwrap();
+        assert_eq!(endpoint.workload_id, f.stable);
+    }
+
+    #[tokio::test]
+    async fn test_sync_picks_up_new_instances() {
+        let f = fixture().await;
+        let allocator = DualStackAllocator::new(&[
+            "10.96.0.0/24".parse().unwrap(),
+            "fd00:96::/120".parse().unwrap(),
+        ])
+        .unwrap();
+        let proxy = ServiceProxy::new(f.store.clone()).with_vip_allocator(allocator);
+        let service = proxy
+            .add_service(web_service().with_ip_family_policy(IpFamilyPolicy::RequireDualStack))
+            .await
+            .unwrap();
+        assert_eq!(service.cluster_ips.len(), 2);
+        assert_eq!(proxy.endpoints(&service.id).unwrap().endpoints.len(), 3);
+
+        put_workload(&f.store, "canary", 2).await;
+        proxy.sync().await.unwrap();
+        assert_eq!(proxy.endpoints(&service.id).unwrap().endpoints.len(), 5);
+
+        let removed = proxy.remove_service(&service.id).await.unwrap().unwrap();
+        assert_eq!(removed.cluster_ips, service.cluster_ips);
+        assert!(proxy.endpoints(&service.id).is_none());
+        assert!(proxy.list_services().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stored_services_reach_every_proxy() {
+        let f = fixture().await;
+        let allocator = || DualStackAllocator::new(&["10.96.0.0/24".parse().unwrap()]).unwrap();
+        let node_a = ServiceProxy::new(f.store.clone()).with_vip_allocator(allocator());
+        let node_b = ServiceProxy::new(f.store.clone()).with_vip_allocator(allocator());
+
+        let web = node_a.add_service(web_service()).await.unwrap();
+        node_b.sync().await.unwrap();
+        assert_eq!(node_b.get_service(&web.id), Some(web.clone()));
+        assert_eq!(node_b.endpoints(&web.id).unwrap().endpoints.len(), 3);
+
+        // An address handed out through another node is not handed out again
+        let api = node_b.add_service(web_service()).await.unwrap();
+        assert_ne!(api.cluster_ips, web.cluster_ips);
+
+        let updated = node_b
+            .update_service(web_service().with_backend_weight(f.canary, 0))
+            .await;
+        assert!(updated.is_err(), "a service must exist to be updated");
+        let mut update = web.clone();
+        update.backend_weights.insert(f.canary, 0);
+        update.cluster_ips.clear();
+        let updated = node_b.update_service(update).await.unwrap();
+        assert_eq!(updated.cluster_ips, web.cluster_ips);
+        node_a.sync().await.unwrap();
+        for i in 0..10 {
+            let endpoint = node_a.select_endpoint(&web.id, client(i)).unwrap();
+            assert_eq!(endpoint.workload_id, f.stable);
+        }
+
+        node_b.remove_service(&web.id).await.unwrap();
+        node_a.sync().await.unwrap();
+        assert!(node_a.get_service(&web.id).is_none());
+        assert_eq!(node_a.list_services(), vec![api]);
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_endpoint_stops_receiving_traffic() {
+        let f = fixture().await;
+        let service = f
+            .proxy
+            .add_service(web_service().with_session_affinity(SessionAffinity::client_ip()))
+            .await
+            .unwrap();
+        let canary = f
+            .store
+            .list_instances_for_workload(&f.canary)
+            .await
+            .unwrap()[0]
+            .id;
+
+        assert_eq!(f.proxy.instance_ready(&canary), None);
+        f.proxy.set_instance_ready(canary, false);
+        assert_eq!(f.proxy.instance_ready(&canary), Some(false));
+        for i in 0..10 {
+            let endpoint = f.proxy.select_endpoint(&service.id, client(i)).unwrap();
+            assert_ne!(endpoint.instance_id, canary);
+        }
+        // Still listed for debugging, just not routable; a sync keeps it that way
+        f.proxy.sync().await.unwrap();
+        let status = f.proxy.endpoints(&service.id).unwrap();
+        assert_eq!(status.endpoints.len(), 3);
+        assert!(
+            !status
+                .endpoints
+                .iter()
+                .find(|e| e.instance_id == canary)
+                .unwrap()
+                .ready
+        );
+
+        f.proxy.set_instance_ready(canary, true);
+        f.proxy.sync().await.unwrap();
+        let status = f.proxy.endpoints(&service.id).unwrap();
+        assert!(status.endpoints.iter().all(|e| e.ready));
+    }
+
+    #[tokio::test]
+    async fn test_terminating_instance_removed_immediately() {
+        let f = fixture().await;
+        let service = f.proxy.add_service(web_service()).await.unwrap();
+        let canary = f
+            .store
+            .list_instances_for_workload(&f.canary)
+            .await
+            .unwrap()[0]
+            .id;
+
+        f.proxy.instance_terminating(canary);
+        let status = f.proxy.endpoints(&service.id).unwrap();
+        assert_eq!(status.endpoints.len(), 2);
+        assert!(status.endpoints.iter().all(|e| e.instance_id != canary));
+
+        // The store has not caught up yet, but a sync must not resurrect it
+        f.proxy.sync().await.unwrap();
+        assert_eq!(f.proxy.endpoints(&service.id).unwrap().endpoints.len(), 2);
+    }
+}
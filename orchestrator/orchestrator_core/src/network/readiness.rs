@@ -0,0 +1,413 @@
+//! Readiness probing.
+//!
+//! The [`ReadinessProber`] runs each container's readiness probe against its
+//! instance on the probe's own schedule, applies the success and failure
+//! thresholds, and pushes every change of verdict straight into the
+//! [`ServiceProxy`] so an instance leaves its services as soon as it fails
+//! rather than on the next endpoint sync.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use orchestrator_shared_types::{
+    Probe, ProbeAction, Result, WorkloadInstance, WorkloadInstanceStatus,
+};
+use state_store_interface::StateStore;
+
+use super::proxy::ServiceProxy;
+
+/// Executes a single probe against an instance.
+#[async_trait]
+pub trait ProbeRunner: Send + Sync {
+    /// Returns `Err` with a short reason when the probe fails.
+    async fn probe(
+        &self,
+        instance: &WorkloadInstance,
+        probe: &Probe,
+    ) -> std::result::Result<(), String>;
+}
+
+/// Probes instances over the network at their first assigned address.
+#[derive(Debug, Default, Clone)]
+pub struct NetworkProbeRunner;
+
+impl NetworkProbeRunner {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn target(instance: &WorkloadInstance) -> std::result::Result<IpAddr, String> {
+        instance
+            .ip_addresses
+            .first()
+            .copied()
+            .ok_or_else(|| "instance has no address".to_string())
+    }
+}
+
+#[async_trait]
+impl ProbeRunner for NetworkProbeRunner {
+    async fn probe(
+        &self,
+        instance: &WorkloadInstance,
+        probe: &Probe,
+    ) -> std::result::Result<(), String> {
+        let ip = Self::target(instance)?;
+        match &probe.action {
+            ProbeAction::TcpSocket { port } => TcpStream::connect(SocketAddr::new(ip, *port))
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            ProbeAction::HttpGet { port, path } => {
+                let addr = SocketAddr::new(ip, *port);
+                let mut stream = TcpStream::connect(addr).await.map_err(|e| e.to_string())?;
+                let request = format!(
+                    "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+                    path, addr
+                );
+                stream
+                    .write_all(request.as_bytes())
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                // The status line is all we need
+                let mut buf = [0u8; 64];
+                let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+                let status = parse_status_code(&buf[..n])
+                    .ok_or_else(|| "malformed HTTP response".to_string())?;
+                if (200..400).contains(&status) {
+                    Ok(())
+                } else {
+                    Err(format!("HTTP status {}", status))
+                }
+            }
+        }
+    }
+}
+
+fn parse_status_code(response: &[u8]) -> Option<u16> {
+    let line = std::str::from_utf8(response).ok()?.lines().next()?;
+    let mut parts = line.split_whitespace();
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
+#[derive(Debug)]
+struct ProbeState {
+    first_seen: Instant,
+    last_probe: Option<Instant>,
+    successes: u32,
+    failures: u32,
+    ready: bool,
+}
+
+impl ProbeState {
+    fn new(now: Instant) -> Self {
+        Self {
+            first_seen: now,
+            last_probe: None,
+            successes: 0,
+            failures: 0,
+            ready: false,
+        }
+    }
+
+    fn is_due(&self, probe: &Probe, now: Instant) -> bool {
+        if now < self.first_seen + Duration::from_secs(probe.initial_delay_seconds as u64) {
+            return false;
+        }
+        match self.last_probe {
+            Some(last) => now >= last + Duration::from_secs(probe.period_seconds as u64),
+            None => true,
+        }
+    }
+
+    fn record(&mut self, probe: &Probe, success: bool) {
+        if success {
+            self.successes += 1;
+            self.failures = 0;
+            if self.successes >= probe.success_threshold.max(1) {
+                self.ready = true;
+            }
+        } else {
+            self.failures += 1;
+            self.successes = 0;
+            if self.failures >= probe.failure_threshold.max(1) {
+                self.ready = false;
+            }
+        }
+    }
+}
+
+/// Periodically probes running instances and feeds verdicts to the proxy.
+pub struct ReadinessProber {
+    state_store: Arc<dyn StateStore>,
+    proxy: Arc<ServiceProxy>,
+    runner: Arc<dyn ProbeRunner>,
+    // Keyed by instance and container index
+    states: Mutex<HashMap<(Uuid, usize), ProbeState>>,
+    verdicts: Mutex<HashMap<Uuid, bool>>,
+}
+
+impl ReadinessProber {
+    /// How often [`spawn`](Self::spawn) checks whether probes are due.
+    pub const DEFAULT_PROBE_TICK: Duration = Duration::from_secs(1);
+
+    pub fn new(
+        state_store: Arc<dyn StateStore>,
+        proxy: Arc<ServiceProxy>,
+        runner: Arc<dyn ProbeRunner>,
+    ) -> Self {
+        Self {
+            state_store,
+            proxy,
+            runner,
+            states: Mutex::new(HashMap::new()),
+            verdicts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Current verdict for an instance, if it has been probed.
+    pub async fn is_ready(&self, instance_id: &Uuid) -> Option<bool> {
+        self.verdicts.lock().await.get(instance_id).copied()
+    }
+
+    /// Run every probe that is due now.
+    pub async fn probe_all(&self) -> Result<()> {
+        self.probe_all_at(Instant::now()).await
+    }
+
+    /// Run every probe that is due at `now`.
+    pub async fn probe_all_at(&self, now: Instant) -> Result<()> {
+        let instances = self.state_store.list_all_instances().await?;
+        let mut states = self.states.lock().await;
+        let mut verdicts = self.verdicts.lock().await;
+
+        // Drop state for instances that are gone or no longer running
+        states.retain(|(id, _), _| {
+            instances
+                .iter()
+                .any(|i| i.id == *id && i.status == WorkloadInstanceStatus::Running)
+        });
+        verdicts.retain(|id, _| states.keys().any(|(i, _)| i == id));
+
+        for instance in instances
+            .iter()
+            .filter(|i| i.status == WorkloadInstanceStatus::Running)
+        {
+            let Some(workload) = self.state_store.get_workload(&instance.workload_id).await? else {
+                continue;
+            };
+
+            let mut probed = false;
+            let mut ready = true;
+            for (index, container) in workload.containers.iter().enumerate() {
+                let Some(probe) = &container.readiness_probe else {
+                    continue;
+                };
+                probed = true;
+                let state = states
+                    .entry((instance.id, index))
+                    .or_insert_with(|| ProbeState::new(now));
+                if state.is_due(probe, now) {
+                    state.last_probe = Some(now);
+                    let timeout = Duration::from_secs(probe.timeout_seconds.max(1) as u64);
+                    let outcome =
+                        match tokio::time::timeout(timeout, self.runner.probe(instance, probe))
+                            .await
+                        {
+                            Ok(outcome) => outcome,
+                            Err(_) => Err("timed out".to_string()),
+                        };
+                    if let Err(reason) = &outcome {
+                        debug!(
+                            "Readiness probe for {} container {} failed: {}",
+                            instance.id, container.name, reason
+                        );
+                    }
+                    state.record(probe, outcome.is_ok());
+                }
+                ready &= state.ready;
+            }
+
+            if !probed {
+                continue;
+            }
+            if verdicts.insert(instance.id, ready) != Some(ready) {
+                info!(
+                    "Instance {} is {}",
+                    instance.id,
+                    if ready { "ready" } else { "not ready" }
+                );
+                self.proxy.set_instance_ready(instance.id, ready);
+            }
+        }
+        Ok(())
+    }
+
+    /// Probe on a fixed tick until the task is aborted.
+    pub fn spawn(self: Arc<Self>, tick: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tick);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.probe_all().await {
+                    error!("Readiness probing failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::service::Service;
+    use crate::test_support;
+    use orchestrator_shared_types::{ContainerConfig, Keypair, WorkloadDefinition};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tokio::net::TcpListener;
+
+    /// Succeeds or fails depending on a switch the test flips.
+    struct ScriptedRunner {
+        healthy: AtomicBool,
+    }
+
+    #[async_trait]
+    impl ProbeRunner for ScriptedRunner {
+        async fn probe(
+            &self,
+            _instance: &WorkloadInstance,
+            _probe: &Probe,
+        ) -> std::result::Result<(), String> {
+            if self.healthy.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err("unhealthy".to_string())
+            }
+        }
+    }
+
+    fn instance(workload_id: Uuid, addresses: Vec<IpAddr>) -> WorkloadInstance {
+        WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id,
+            node_id: Keypair::generate().public_key(),
+            container_ids: vec![],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: addresses,
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_thresholds_drive_endpoint_readiness() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let mut probe = Probe::new(ProbeAction::TcpSocket { port: 8080 });
+        probe.initial_delay_seconds = 5;
+        probe.failure_threshold = 2;
+        let workload = WorkloadDefinition {
+            containers: vec![ContainerConfig {
+                image: "web:1".to_string(),
+                readiness_probe: Some(probe),
+                ..test_support::container("web")
+            }],
+            labels: HashMap::from([("app".to_string(), "web".to_string())]),
+            ..test_support::workload("web")
+        };
+        let web = instance(workload.id, vec![]);
+        store.put_workload(workload).await.unwrap();
+        store.put_instance(web.clone()).await.unwrap();
+
+        let proxy = Arc::new(ServiceProxy::new(store.clone()));
+        let service = proxy
+            .add_service(Service::new(
+                "web",
+                HashMap::from([("app".to_string(), "web".to_string())]),
+            ))
+            .await
+            .unwrap();
+        let runner = Arc::new(ScriptedRunner {
+            healthy: AtomicBool::new(true),
+        });
+        let prober = ReadinessProber::new(store.clone(), proxy.clone(), runner.clone());
+        let ready = || proxy.endpoints(&service.id).unwrap().endpoints[0].ready;
+
+        // Not ready until the initial delay has passed and a probe succeeds
+        assert!(!ready());
+        let start = Instant::now();
+        prober.probe_all_at(start).await.unwrap();
+        assert_eq!(prober.is_ready(&web.id).await, Some(false));
+        prober
+            .probe_all_at(start + Duration::from_secs(5))
+            .await
+            .unwrap();
+        proxy.sync().await.unwrap();
+        assert!(ready());
+
+        // One failure is tolerated, the second takes it out without a sync
+        runner.healthy.store(false, Ordering::SeqCst);
+        prober
+            .probe_all_at(start + Duration::from_secs(15))
+            .await
+            .unwrap();
+        assert!(ready());
+        prober
+            .probe_all_at(start + Duration::from_secs(20))
+            .await
+            .unwrap();
+        assert!(ready());
+        prober
+            .probe_all_at(start + Duration::from_secs(25))
+            .await
+            .unwrap();
+        assert!(!ready());
+        assert_eq!(prober.is_ready(&web.id).await, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_network_runner_probes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            for status in ["200 OK", "503 Service Unavailable"] {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 512];
+                let _ = socket.read(&mut buf).await.unwrap();
+                let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let runner = NetworkProbeRunner::new();
+        let web = instance(Uuid::new_v4(), vec!["127.0.0.1".parse().unwrap()]);
+        let http = Probe::new(ProbeAction::HttpGet {
+            port,
+            path: "/healthz".to_string(),
+        });
+        assert!(runner.probe(&web, &http).await.is_ok());
+        assert_eq!(
+            runner.probe(&web, &http).await,
+            Err("HTTP status 503".to_string())
+        );
+
+        let unaddressed = instance(Uuid::new_v4(), vec![]);
+        let tcp = Probe::new(ProbeAction::TcpSocket { port });
+        assert!(runner.probe(&unaddressed, &tcp).await.is_err());
+    }
+}
@@ -0,0 +1,398 @@
+//! Service definitions and their endpoints.
+//!
+//! A [`Service`] gives a stable virtual IP and port set to every instance of
+//! the workloads its label selector matches. The instances backing a service
+//! at any moment are its [`Endpoint`]s; [`build_endpoints`] derives them from
+//! the state store's workloads and instances.
+//!
+//! An endpoint only receives traffic while it is ready: its instance is
+//! Running and, if any of its containers has a readiness probe, the probes
+//! are passing. Instances that are terminating or finished are not endpoints
+//! at all.
+//!
+//! Traffic is spread across endpoints by weight. Weights are set per backend
+//! workload, which lets a canary workload take a small share of a service's
+//! traffic before it is promoted. With [`SessionAffinity::ClientIp`] a client
+//! keeps reaching the same endpoint until it has been idle for the timeout.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use orchestrator_shared_types::{
+    IpFamilyPolicy, NodeId, WorkloadDefinition, WorkloadId, WorkloadInstance,
+    WorkloadInstanceStatus, DEFAULT_NAMESPACE,
+};
+
+use crate::resources::Resource;
+
+pub type ServiceId = Uuid;
+
+/// Weight given to endpoints of workloads without an explicit weight.
+pub const DEFAULT_ENDPOINT_WEIGHT: u32 = 100;
+
+/// Default idle timeout for client-IP session affinity (3 hours).
+pub const DEFAULT_AFFINITY_TIMEOUT_SECONDS: u64 = 10_800;
+
+/// A port exposed by a service.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ServicePort {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Port on the service's virtual IPs.
+    pub port: u16,
+    /// Container port traffic is forwarded to.
+    pub target_port: u16,
+    /// "tcp" or "udp"
+    pub protocol: String,
+}
+
+/// How repeat connections from one client are routed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionAffinity {
+    /// Every connection is balanced independently.
+    #[default]
+    None,
+    /// Connections from the same client IP go to the same endpoint until the
+    /// client has been idle for `timeout_seconds`.
+    ClientIp { timeout_seconds: u64 },
+}
+
+impl SessionAffinity {
+    pub fn client_ip() -> Self {
+        SessionAffinity::ClientIp {
+            timeout_seconds: DEFAULT_AFFINITY_TIMEOUT_SECONDS,
+        }
+    }
+}
+
+/// A stable network identity in front of a set of workloads.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Service {
+    pub id: ServiceId,
+    pub name: String,
+    /// Only workloads in this namespace back the service.
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    /// Workloads carrying all of these labels back the service.
+    pub selector: HashMap<String, String>,
+    pub ports: Vec<ServicePort>,
+    /// Which address families the service's virtual IPs come from.
+    #[serde(default)]
+    pub ip_family_policy: IpFamilyPolicy,
+    /// Virtual IPs, one per family. Assigned when the service is registered.
+    #[serde(default)]
+    pub cluster_ips: Vec<IpAddr>,
+    #[serde(default)]
+    pub session_affinity: SessionAffinity,
+    /// Relative traffic share per backend workload. Workloads not listed get
+    /// [`DEFAULT_ENDPOINT_WEIGHT`]; a weight of 0 drains the workload.
+    #[serde(default)]
+    pub backend_weights: HashMap<WorkloadId, u32>,
+}
+
+fn default_namespace() -> String {
+    DEFAULT_NAMESPACE.to_string()
+}
+
+impl Service {
+    pub fn new(name: impl Into<String>, selector: HashMap<String, String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            namespace: default_namespace(),
+            selector,
+            ports: Vec::new(),
+            ip_family_policy: IpFamilyPolicy::default(),
+            cluster_ips: Vec::new(),
+            session_affinity: SessionAffinity::None,
+            backend_weights: HashMap::new(),
+        }
+    }
+
+    pub fn with_port(mut self, port: u16, target_port: u16, protocol: impl Into<String>) -> Self {
+        self.ports.push(ServicePort {
+            name: None,
+            port,
+            target_port,
+            protocol: protocol.into(),
+        });
+        self
+    }
+
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    pub fn with_ip_family_policy(mut self, policy: IpFamilyPolicy) -> Self {
+        self.ip_family_policy = policy;
+        self
+    }
+
+    pub fn with_session_affinity(mut self, affinity: SessionAffinity) -> Self {
+        self.session_affinity = affinity;
+        self
+    }
+
+    pub fn with_backend_weight(mut self, workload_id: WorkloadId, weight: u32) -> Self {
+        self.backend_weights.insert(workload_id, weight);
+        self
+    }
+
+    /// Whether the service routes to instances of `workload`.
+    pub fn selects(&self, workload: &WorkloadDefinition) -> bool {
+        !self.selector.is_empty()
+            && workload.namespace == self.namespace
+            && self
+                .selector
+                .iter()
+                .all(|(k, v)| workload.labels.get(k) == Some(v))
+    }
+
+    /// Traffic weight for endpoints of the given workload.
+    pub fn weight_for(&self, workload_id: &WorkloadId) -> u32 {
+        self.backend_weights
+            .get(workload_id)
+            .copied()
+            .unwrap_or(DEFAULT_ENDPOINT_WEIGHT)
+    }
+}
+
+impl Resource for Service {
+    const KIND: &'static str = "services";
+
+    fn key(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+/// One instance backing a service.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Endpoint {
+    pub instance_id: Uuid,
+    pub workload_id: WorkloadId,
+    pub node_id: NodeId,
+    pub addresses: Vec<IpAddr>,
+    /// Relative share of new connections this endpoint receives.
+    pub weight: u32,
+    /// Whether the endpoint may receive traffic.
+    pub ready: bool,
+}
+
+impl Endpoint {
+    /// Whether new connections may be routed here.
+    pub fn is_routable(&self) -> bool {
+        self.ready && self.weight > 0
+    }
+}
+
+/// Current endpoints of a service along with its routing configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServiceEndpoints {
+    pub service_id: ServiceId,
+    pub session_affinity: SessionAffinity,
+    pub endpoints: Vec<Endpoint>,
+}
+
+impl ServiceEndpoints {
+    /// Sum of the weights of all routable endpoints.
+    pub fn total_weight(&self) -> u64 {
+        self.endpoints
+            .iter()
+            .filter(|e| e.is_routable())
+            .map(|e| e.weight as u64)
+            .sum()
+    }
+
+    /// Fraction of new traffic each workload receives, by workload id.
+    pub fn traffic_split(&self) -> HashMap<WorkloadId, f64> {
+        let total = self.total_weight();
+        let mut split = HashMap::new();
+        if total == 0 {
+            return split;
+        }
+        for endpoint in self.endpoints.iter().filter(|e| e.is_routable()) {
+            *split.entry(endpoint.workload_id).or_insert(0.0) +=
+                endpoint.weight as f64 / total as f64;
+        }
+        split
+    }
+}
+
+/// Whether any container of the workload has a readiness probe.
+pub fn has_readiness_probe(workload: &WorkloadDefinition) -> bool {
+    workload
+        .containers
+        .iter()
+        .any(|c| c.readiness_probe.is_some())
+}
+
+/// Derive a service's endpoints from the current workloads and instances.
+///
+/// Pending and Running instances of selected workloads become endpoints.
+/// `readiness` holds the latest probe verdict per instance; instances of
+/// probed workloads without a verdict yet are not ready.
+pub fn build_endpoints(
+    service: &Service,
+    workloads: &[WorkloadDefinition],
+    instances: &[WorkloadInstance],
+    readiness: &HashMap<Uuid, bool>,
+) -> Vec<Endpoint> {
+    // Workload id -> (weight, probed)
+    let selected: HashMap<WorkloadId, (u32, bool)> = workloads
+        .iter()
+        .filter(|w| service.selects(w))
+        .map(|w| (w.id, (service.weight_for(&w.id), has_readiness_probe(w))))
+        .collect();
+
+    instances
+        .iter()
+        .filter(|instance| {
+            matches!(
+                instance.status,
+                WorkloadInstanceStatus::Running | WorkloadInstanceStatus::Pending
+            )
+        })
+        .filter_map(|instance| {
+            let (weight, probed) = *selected.get(&instance.workload_id)?;
+            let probe_passing = readiness.get(&instance.id).copied().unwrap_or(!probed);
+            Some(Endpoint {
+                instance_id: instance.id,
+                workload_id: instance.workload_id,
+                node_id: instance.node_id,
+                addresses: instance.ip_addresses.clone(),
+                weight,
+                ready: instance.status == WorkloadInstanceStatus::Running && probe_passing,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use orchestrator_shared_types::{Keypair, Probe, ProbeAction};
+
+    fn workload(labels: &[(&str, &str)]) -> WorkloadDefinition {
+        WorkloadDefinition {
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..test_support::workload("web")
+        }
+    }
+
+    fn instance(workload_id: WorkloadId, status: WorkloadInstanceStatus) -> WorkloadInstance {
+        WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id,
+            node_id: Keypair::generate().public_key(),
+            container_ids: vec![],
+            status,
+            ip_addresses: vec!["10.1.0.5".parse().unwrap()],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        }
+    }
+
+    #[test]
+    fn test_build_endpoints_applies_selector_and_weights() {
+        let stable = workload(&[("app", "web"), ("track", "stable")]);
+        let canary = workload(&[("app", "web"), ("track", "canary")]);
+        let other = workload(&[("app", "db")]);
+
+        let service = Service::new(
+            "web",
+            HashMap::from([("app".to_string(), "web".to_string())]),
+        )
+        .with_backend_weight(stable.id, 90)
+        .with_backend_weight(canary.id, 10);
+
+        let instances = vec![
+            instance(stable.id, WorkloadInstanceStatus::Running),
+            instance(canary.id, WorkloadInstanceStatus::Running),
+            instance(canary.id, WorkloadInstanceStatus::Pending),
+            instance(other.id, WorkloadInstanceStatus::Running),
+            instance(stable.id, WorkloadInstanceStatus::Terminating),
+        ];
+        let endpoints = build_endpoints(
+            &service,
+            &[stable.clone(), canary.clone(), other],
+            &instances,
+            &HashMap::new(),
+        );
+        assert_eq!(endpoints.len(), 3);
+        assert_eq!(endpoints.iter().filter(|e| e.ready).count(), 2);
+
+        let status = ServiceEndpoints {
+            service_id: service.id,
+            session_affinity: service.session_affinity,
+            endpoints,
+        };
+        assert_eq!(status.total_weight(), 100);
+        let split = status.traffic_split();
+        assert!((split[&stable.id] - 0.9).abs() < f64::EPSILON);
+        assert!((split[&canary.id] - 0.1).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_probed_workload_needs_passing_verdict() {
+        let mut web = workload(&[("app", "web")]);
+        web.containers[0].readiness_probe = Some(Probe::new(ProbeAction::TcpSocket { port: 80 }));
+        let service = Service::new(
+            "web",
+            HashMap::from([("app".to_string(), "web".to_string())]),
+        );
+
+        let passing = instance(web.id, WorkloadInstanceStatus::Running);
+        let failing = instance(web.id, WorkloadInstanceStatus::Running);
+        let unprobed = instance(web.id, WorkloadInstanceStatus::Running);
+        let readiness = HashMap::from([(passing.id, true), (failing.id, false)]);
+
+        let endpoints = build_endpoints(
+            &service,
+            &[web],
+            &[passing.clone(), failing, unprobed],
+            &readiness,
+        );
+        let ready: Vec<Uuid> = endpoints
+            .iter()
+            .filter(|e| e.ready)
+            .map(|e| e.instance_id)
+            .collect();
+        assert_eq!(ready, vec![passing.id]);
+    }
+
+    #[test]
+    fn test_empty_selector_selects_nothing() {
+        let service = Service::new("none", HashMap::new());
+        assert!(!service.selects(&workload(&[("app", "web")])));
+    }
+
+    #[test]
+    fn test_selector_stays_within_namespace() {
+        let selector = HashMap::from([("app".to_string(), "web".to_string())]);
+        let service = Service::new("web", selector).with_namespace("team-a");
+        let mut web = workload(&[("app", "web")]);
+        assert!(!service.selects(&web));
+
+        web.namespace = "team-a".to_string();
+        assert!(service.selects(&web));
+    }
+
+    #[test]
+    fn test_session_affinity_serde() {
+        let json = serde_json::to_string(&SessionAffinity::client_ip()).unwrap();
+        assert_eq!(json, r#"{"type":"client_ip","timeout_seconds":10800}"#);
+        let none: SessionAffinity = serde_json::from_str(r#"{"type":"none"}"#).unwrap();
+        assert_eq!(none, SessionAffinity::None);
+    }
+}
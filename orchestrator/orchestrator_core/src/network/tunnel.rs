@@ -0,0 +1,436 @@
+//! Temporary public TCP tunnels to a workload.
+//!
+//! A tunnel listens on a public port of an edge node, one running a
+//! [`TunnelManager`], and forwards each connection to a running instance of
+//! its workload, round-robin. It is meant for demos and webhooks that need a
+//! reachable endpoint without setting up ingress, so every tunnel expires:
+//! [`TunnelManager::spawn`] closes tunnels whose time is up, which stops the
+//! listener and frees the port.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use orchestrator_shared_types::{OrchestrationError, Result, WorkloadId, WorkloadInstanceStatus};
+use state_store_interface::StateStore;
+
+/// How long a tunnel stays open unless asked otherwise.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Longest lifetime a tunnel may be given.
+pub const MAX_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Public ports tunnels are allocated from.
+pub const DEFAULT_PORT_RANGE: RangeInclusive<u16> = 40000..=40999;
+
+/// An open tunnel.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct Tunnel {
+    pub id: Uuid,
+    #[cfg_attr(feature = "rest-api", schema(value_type = uuid::Uuid))]
+    pub workload_id: WorkloadId,
+    /// Container port connections are forwarded to.
+    pub target_port: u16,
+    /// Address clients connect to.
+    #[cfg_attr(feature = "rest-api", schema(value_type = String))]
+    pub public_addr: SocketAddr,
+    pub expires_at: DateTime<Utc>,
+}
+
+struct TunnelEntry {
+    tunnel: Tunnel,
+    listener: JoinHandle<()>,
+}
+
+/// Opens, serves and expires tunnels on this node.
+pub struct TunnelManager {
+    state_store: Arc<dyn StateStore>,
+    /// Address advertised to clients, normally the node's public IP.
+    public_ip: IpAddr,
+    bind_ip: IpAddr,
+    ports: RangeInclusive<u16>,
+    tunnels: Mutex<HashMap<Uuid, TunnelEntry>>,
+}
+
+impl TunnelManager {
+    /// How often expired tunnels are torn down.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(state_store: Arc<dyn StateStore>, public_ip: IpAddr) -> Self {
+        let bind_ip = match public_ip {
+            IpAddr::V4(_) => IpAddr::from([0, 0, 0, 0]),
+            IpAddr::V6(_) => IpAddr::from([0u16; 8]),
+        };
+        Self {
+            state_store,
+            public_ip,
+            bind_ip,
+            ports: DEFAULT_PORT_RANGE,
+            tunnels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_port_range(mut self, ports: RangeInclusive<u16>) -> Self {
+        self.ports = ports;
+        self
+    }
+
+    /// Listen on `ip` instead of every address of the public IP's family.
+    pub fn with_bind_ip(mut self, ip: IpAddr) -> Self {
+        self.bind_ip = ip;
+        self
+    }
+
+    /// Open a tunnel to `workload_id`, forwarding to `target_port` or, if
+    /// not given, the first port its containers declare.
+    pub async fn open(
+        &self,
+        workload_id: WorkloadId,
+        target_port: Option<u16>,
+        ttl: Duration,
+    ) -> Result<Tunnel> {
+        if ttl.is_zero() || ttl > MAX_TTL {
+            return Err(OrchestrationError::ConfigError(format!(
+                "Tunnel lifetime must be between 1s and {}s",
+                MAX_TTL.as_secs()
+            )));
+        }
+
+        let workload = self
+            .state_store
+            .get_workload(&workload_id)
+            .await?
+            .ok_or(OrchestrationError::WorkloadNotFound(workload_id))?;
+        let target_port = match target_port {
+            Some(port) => port,
+            None => workload
+                .containers
+                .iter()
+                .flat_map(|c| &c.ports)
+                .map(|p| p.container_port)
+                .next()
+                .ok_or_else(|| {
+                    OrchestrationError::ConfigError(format!(
+                        "Workload {} declares no ports; specify the port to expose",
+                        workload.name
+                    ))
+                })?,
+        };
+
+        let (listener, port) = self.bind().await?;
+        let tunnel = Tunnel {
+            id: Uuid::new_v4(),
+            workload_id,
+            target_port,
+            public_addr: SocketAddr::new(self.public_ip, port),
+            expires_at: Utc::now() + chrono::Duration::milliseconds(ttl.as_millis() as i64),
+        };
+
+        let task = tokio::spawn(serve(
+            listener,
+            self.state_store.clone(),
+            workload_id,
+            target_port,
+        ));
+        self.tunnels.lock().unwrap().insert(
+            tunnel.id,
+            TunnelEntry {
+                tunnel: tunnel.clone(),
+                listener: task,
+            },
+        );
+        info!(
+            "Opened tunnel {} on {} to workload {} port {}, expires {}",
+            tunnel.id, tunnel.public_addr, workload.name, target_port, tunnel.expires_at
+        );
+        Ok(tunnel)
+    }
+
+    /// Bind the first free port in the range that no tunnel holds.
+    async fn bind(&self) -> Result<(TcpListener, u16)> {
+        for port in self.ports.clone() {
+            let in_use = self
+                .tunnels
+                .lock()
+                .unwrap()
+                .values()
+                .any(|e| e.tunnel.public_addr.port() == port);
+            if in_use {
+                continue;
+            }
+            if let Ok(listener) = TcpListener::bind(SocketAddr::new(self.bind_ip, port)).await {
+                return Ok((listener, port));
+            }
+        }
+        Err(OrchestrationError::NetworkError(format!(
+            "No free tunnel port in {}-{}",
+            self.ports.start(),
+            self.ports.end()
+        )))
+    }
+
+    /// Close a tunnel, stopping its listener.
+    pub fn close(&self, id: &Uuid) -> Option<Tunnel> {
+        let entry = self.tunnels.lock().unwrap().remove(id)?;
+        entry.listener.abort();
+        info!("Closed tunnel {} on {}", id, entry.tunnel.public_addr);
+        Some(entry.tunnel)
+    }
+
+    pub fn get(&self, id: &Uuid) -> Option<Tunnel> {
+        self.tunnels
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|e| e.tunnel.clone())
+    }
+
+    /// Open tunnels, soonest to expire first.
+    pub fn list(&self) -> Vec<Tunnel> {
+        let mut tunnels: Vec<Tunnel> = self
+            .tunnels
+            .lock()
+            .unwrap()
+            .values()
+            .map(|e| e.tunnel.clone())
+            .collect();
+        tunnels.sort_by_key(|t| t.expires_at);
+        tunnels
+    }
+
+    /// Close every tunnel that has expired by `now`, returning them.
+    pub fn expire_at(&self, now: DateTime<Utc>) -> Vec<Tunnel> {
+        let expired: Vec<Uuid> = self
+            .tunnels
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|e| e.tunnel.expires_at <= now)
+            .map(|e| e.tunnel.id)
+            .collect();
+        expired.iter().filter_map(|id| self.close(id)).collect()
+    }
+
+    /// Tear down expired tunnels every `interval`.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let expired = self.expire_at(Utc::now());
+                if !expired.is_empty() {
+                    debug!("Expired {} tunnel(s)", expired.len());
+                }
+            }
+        })
+    }
+}
+
+impl Drop for TunnelManager {
+    fn drop(&mut self) {
+        for entry in self.tunnels.get_mut().unwrap().values() {
+            entry.listener.abort();
+        }
+    }
+}
+
+/// Accept connections and forward each one to a running instance.
+async fn serve(
+    listener: TcpListener,
+    state_store: Arc<dyn StateStore>,
+    workload_id: WorkloadId,
+    target_port: u16,
+) {
+    let next = Arc::new(AtomicUsize::new(0));
+    loop {
+        let (mut inbound, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Tunnel accept failed: {}", e);
+                continue;
+            }
+        };
+        let state_store = state_store.clone();
+        let next = next.clone();
+        tokio::spawn(async move {
+            let backend = match pick_backend(&*state_store, workload_id, target_port, &next).await {
+                Ok(Some(backend)) => backend,
+                Ok(None) => {
+                    warn!("No running instance for tunnel connection from {}", peer);
+                    return;
+                }
+                Err(e) => {
+                    error!("Failed to find tunnel backend: {:?}", e);
+                    return;
+                }
+            };
+            match TcpStream::connect(backend).await {
+                Ok(mut outbound) => {
+                    if let Err(e) = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await
+                    {
+                        debug!("Tunnel connection {} -> {} ended: {}", peer, backend, e);
+                    }
+                }
+                Err(e) => warn!("Tunnel could not reach {}: {}", backend, e),
+            }
+        });
+    }
+}
+
+async fn pick_backend(
+    state_store: &dyn StateStore,
+    workload_id: WorkloadId,
+    target_port: u16,
+    next: &AtomicUsize,
+) -> Result<Option<SocketAddr>> {
+    let addresses: Vec<IpAddr> = state_store
+        .list_instances_for_workload(&workload_id)
+        .await?
+        .into_iter()
+        .filter(|i| i.status == WorkloadInstanceStatus::Running)
+        .filter_map(|i| i.ip_addresses.first().copied())
+        .collect();
+    if addresses.is_empty() {
+        return Ok(None);
+    }
+    let index = next.fetch_add(1, Ordering::Relaxed) % addresses.len();
+    Ok(Some(SocketAddr::new(addresses[index], target_port)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use orchestrator_shared_types::{
+        ContainerConfig, Keypair, PortMapping, WorkloadDefinition, WorkloadInstance,
+    };
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn echo_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (mut reader, mut writer) = stream.split();
+                    let _ = tokio::io::copy(&mut reader, &mut writer).await;
+                });
+            }
+        });
+        port
+    }
+
+    async fn store_with_workload(container_port: u16) -> (Arc<dyn StateStore>, WorkloadId) {
+        let store: Arc<dyn StateStore> = Arc::new(InMemoryStateStore::new());
+        let workload = WorkloadDefinition {
+            containers: vec![ContainerConfig {
+                image: "model:latest".to_string(),
+                ports: vec![PortMapping {
+                    container_port,
+                    host_port: None,
+                    protocol: "tcp".to_string(),
+                    host_ip: None,
+                }],
+                ..test_support::container("server")
+            }],
+            ..test_support::workload("model")
+        };
+        let id = workload.id;
+        store.put_workload(workload).await.unwrap();
+        store
+            .put_instance(WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id: id,
+                node_id: Keypair::generate().public_key(),
+                container_ids: vec![],
+                status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![IpAddr::from([127, 0, 0, 1])],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
+            })
+            .await
+            .unwrap();
+        (store, id)
+    }
+
+    fn manager(store: Arc<dyn StateStore>) -> TunnelManager {
+        TunnelManager::new(store, IpAddr::from([127, 0, 0, 1]))
+            .with_bind_ip(IpAddr::from([127, 0, 0, 1]))
+            .with_port_range(47100..=47199)
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_forwards_and_expires() {
+        let port = echo_server().await;
+        let (store, workload_id) = store_with_workload(port).await;
+        let tunnels = manager(store);
+
+        let tunnel = tunnels
+            .open(workload_id, None, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(tunnel.target_port, port);
+        assert_eq!(tunnels.list(), vec![tunnel.clone()]);
+
+        let mut client = TcpStream::connect(tunnel.public_addr).await.unwrap();
+        client.write_all(b"ping").await.unwrap();
+        let mut reply = [0u8; 4];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"ping");
+
+        // Not expired yet
+        assert!(tunnels.expire_at(Utc::now()).is_empty());
+
+        let expired = tunnels.expire_at(tunnel.expires_at);
+        assert_eq!(expired, vec![tunnel.clone()]);
+        assert!(tunnels.list().is_empty());
+
+        // The listener is gone once the aborted task has been dropped
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(TcpStream::connect(tunnel.public_addr).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_open_validates_request() {
+        let (store, workload_id) = store_with_workload(8080).await;
+        let tunnels = manager(store);
+
+        assert!(tunnels
+            .open(workload_id, None, Duration::ZERO)
+            .await
+            .is_err());
+        assert!(tunnels
+            .open(workload_id, None, MAX_TTL + Duration::from_secs(1))
+            .await
+            .is_err());
+        assert!(tunnels
+            .open(Uuid::new_v4(), None, DEFAULT_TTL)
+            .await
+            .is_err());
+
+        let first = tunnels
+            .open(workload_id, Some(9000), DEFAULT_TTL)
+            .await
+            .unwrap();
+        let second = tunnels.open(workload_id, None, DEFAULT_TTL).await.unwrap();
+        assert_eq!(first.target_port, 9000);
+        assert_eq!(second.target_port, 8080);
+        assert_ne!(first.public_addr.port(), second.public_addr.port());
+
+        assert_eq!(tunnels.close(&first.id), Some(first));
+        assert_eq!(tunnels.list(), vec![second]);
+    }
+}
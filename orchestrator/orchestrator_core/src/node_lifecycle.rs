@@ -0,0 +1,511 @@
+//! Node leases and failure handling.
+//!
+//! Every node runs a [`LeaseRenewer`] that renews its [`NodeLease`] through the
+//! cluster manager. On the control plane, [`NodeLifecycleController`] watches
+//! the renewals:
+//!
+//! - A node whose lease lapses is marked NotReady, so nothing new is
+//!   scheduled onto it.
+//! - Once its lease has been lapsed for the eviction grace period, the node's
+//!   instances are removed from the state store and their workloads are
+//!   resubmitted, so replacements are scheduled on healthy nodes.
+//! - A node that renews again is marked Ready.
+//!
+//! Renewals are timed with the controller's clock when it sees a lease's
+//! counter move, never with the node's, so clocks need not agree. Nodes that
+//! already left the cluster are handled the same way through the instances
+//! still assigned to them. Containers left running on a node that comes back
+//! after its instances were evicted are no longer tracked.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::events::EventRecorder;
+use cluster_manager_interface::ClusterManager;
+use orchestrator_shared_types::{
+    NodeId, NodeLease, NodeStatus, ObjectReference, OrchestrationError, Result, WorkloadDefinition,
+};
+use state_store_interface::StateStore;
+
+/// How long a node may go without renewing its lease.
+pub const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(40);
+
+/// How long a node's lease stays lapsed before its instances are rescheduled.
+pub const DEFAULT_EVICTION_GRACE_PERIOD: Duration = Duration::from_secs(300);
+
+/// Renews this node's lease.
+pub struct LeaseRenewer {
+    cluster: Arc<dyn ClusterManager>,
+    node_id: NodeId,
+    duration: Duration,
+    renewals: AtomicU64,
+}
+
+impl LeaseRenewer {
+    /// How often the lease is renewed: a quarter of the default duration, so
+    /// a few lost heartbeats do not let it lapse.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+
+    pub fn new(cluster: Arc<dyn ClusterManager>, node_id: NodeId, duration: Duration) -> Self {
+        Self {
+            cluster,
+            node_id,
+            duration,
+            renewals: AtomicU64::new(0),
+        }
+    }
+
+    pub async fn renew(&self) -> Result<()> {
+        let lease = NodeLease {
+            renewals: self.renewals.fetch_add(1, Ordering::Relaxed) + 1,
+            duration_secs: self.duration.as_secs().max(1),
+        };
+        self.cluster.renew_lease(&self.node_id, lease).await
+    }
+
+    /// Renew every `interval` until the task is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.renew().await {
+                    error!("Node lease renewal failed: {:?}", e);
+                }
+            }
+        })
+    }
+}
+
+/// What the controller last saw of a node's lease.
+struct LeaseObservation {
+    renewals: Option<u64>,
+    renewed_at: Instant,
+    duration: Duration,
+    /// When the lease was first seen lapsed; cleared on renewal.
+    lapsed_at: Option<Instant>,
+}
+
+/// Changes made by one lifecycle pass.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LifecycleReport {
+    /// Nodes marked NotReady because their lease lapsed.
+    pub not_ready: Vec<NodeId>,
+    /// Nodes marked Ready again after renewing.
+    pub recovered: Vec<NodeId>,
+    /// Instances removed from failed nodes.
+    pub evicted: Vec<Uuid>,
+}
+
+/// Marks nodes with lapsed leases NotReady and reschedules their instances.
+pub struct NodeLifecycleController {
+    cluster: Arc<dyn ClusterManager>,
+    state_store: Arc<dyn StateStore>,
+    workload_tx: mpsc::Sender<WorkloadDefinition>,
+    /// Assumed for nodes that have not renewed a lease yet.
+    lease_duration: Duration,
+    eviction_grace_period: Duration,
+    observed: Mutex<HashMap<NodeId, LeaseObservation>>,
+    events: EventRecorder,
+}
+
+impl NodeLifecycleController {
+    /// How often leases are checked.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(
+        cluster: Arc<dyn ClusterManager>,
+        state_store: Arc<dyn StateStore>,
+        workload_tx: mpsc::Sender<WorkloadDefinition>,
+    ) -> Self {
+        Self {
+            events: EventRecorder::new(state_store.clone(), "node-lifecycle-controller"),
+            cluster,
+            state_store,
+            workload_tx,
+            lease_duration: DEFAULT_LEASE_DURATION,
+            eviction_grace_period: DEFAULT_EVICTION_GRACE_PERIOD,
+            observed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Lease duration assumed for a node until its first renewal is seen.
+    pub fn with_lease_duration(mut self, duration: Duration) -> Self {
+        self.lease_duration = duration;
+        self
+    }
+
+    pub fn with_eviction_grace_period(mut self, grace_period: Duration) -> Self {
+        self.eviction_grace_period = grace_period;
+        self
+    }
+
+    pub async fn check(&self) -> Result<LifecycleReport> {
+        self.check_at(Instant::now()).await
+    }
+
+    async fn check_at(&self, now: Instant) -> Result<LifecycleReport> {
+        let leases: HashMap<NodeId, NodeLease> =
+            self.cluster.node_leases().await?.into_iter().collect();
+        let nodes = self.state_store.list_nodes().await?;
+        let instances = self.state_store.list_all_instances().await?;
+
+        let mut known: HashSet<NodeId> = nodes.iter().map(|n| n.id).collect();
+        known.extend(instances.iter().map(|i| i.node_id));
+
+        let mut lapsed = HashSet::new();
+        let mut renewed = HashSet::new();
+        let mut evict = HashSet::new();
+        {
+            let mut observed = self.observed.lock().unwrap();
+            observed.retain(|node_id, _| known.contains(node_id));
+            for node_id in &known {
+                // A node first seen now gets a full lease duration to renew
+                let observation = observed.entry(*node_id).or_insert(LeaseObservation {
+                    renewals: None,
+                    renewed_at: now,
+                    duration: self.lease_duration,
+                    lapsed_at: None,
+                });
+                if let Some(lease) = leases.get(node_id) {
+                    if observation.renewals != Some(lease.renewals) {
+                        observation.renewals = Some(lease.renewals);
+                        observation.renewed_at = now;
+                        observation.duration = Duration::from_secs(lease.duration_secs);
+                    }
+                }
+
+                if now.duration_since(observation.renewed_at) <= observation.duration {
+                    if observation.lapsed_at.take().is_some() {
+                        renewed.insert(*node_id);
+                    }
+                    continue;
+                }
+                let lapsed_at = *observation.lapsed_at.get_or_insert(now);
+                lapsed.insert(*node_id);
+                if now.duration_since(lapsed_at) >= self.eviction_grace_period {
+                    evict.insert(*node_id);
+                }
+            }
+        }
+
+        let mut report = LifecycleReport::default();
+        for mut node in nodes {
+            if lapsed.contains(&node.id) && node.status == NodeStatus::Ready {
+                warn!(
+                    "Node {} stopped renewing its lease; marking NotReady",
+                    node.id
+                );
+                node.status = NodeStatus::NotReady;
+                report.not_ready.push(node.id);
+                self.events
+                    .warning(
+                        ObjectReference::node(&node.id),
+                        "",
+                        "NodeNotReady",
+                        "Node stopped renewing its lease",
+                    )
+                    .await;
+                self.state_store.put_node(node).await?;
+            } else if renewed.contains(&node.id) && node.status == NodeStatus::NotReady {
+                info!("Node {} renewed its lease; marking Ready", node.id);
+                node.status = NodeStatus::Ready;
+                report.recovered.push(node.id);
+                self.events
+                    .normal(
+                        ObjectReference::node(&node.id),
+                        "",
+                        "NodeReady",
+                        "Node renewed its lease",
+                    )
+                    .await;
+                self.state_store.put_node(node).await?;
+            }
+        }
+
+        let mut workloads = Vec::new();
+        for instance in instances.iter().filter(|i| evict.contains(&i.node_id)) {
+            info!(
+                "Evicting instance {} from failed node {}",
+                instance.id, instance.node_id
+            );
+            self.state_store
+                .delete_instance(&instance.id.to_string())
+                .await?;
+            let message = format!("Node {} stopped renewing its lease", instance.node_id);
+            self.events
+                .instance_warning(instance, "Evicted", message)
+                .await;
+            report.evicted.push(instance.id);
+            if !workloads.contains(&instance.workload_id) {
+                workloads.push(instance.workload_id);
+            }
+        }
+        // Resubmit so the orchestrator schedules replacements; controller-owned
+        // workloads are recreated by their controller's own resync
+        for workload_id in workloads {
+            if let Some(workload) = self.state_store.get_workload(&workload_id).await? {
+                self.workload_tx.send(workload).await.map_err(|_| {
+                    OrchestrationError::InternalError(
+                        "Orchestrator workload channel closed".to_string(),
+                    )
+                })?;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Check every `interval` until the task is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.check().await {
+                    error!("Node lifecycle check failed: {:?}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use cluster_manager_interface::ClusterEvent;
+    use orchestrator_shared_types::{
+        Keypair, Node, NodeResources, WorkloadInstance, WorkloadInstanceStatus,
+    };
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use tokio::sync::watch;
+
+    #[derive(Default)]
+    struct LeaseClusterManager {
+        leases: Mutex<HashMap<NodeId, NodeLease>>,
+    }
+
+    #[async_trait]
+    impl ClusterManager for LeaseClusterManager {
+        async fn initialize(&self) -> Result<()> {
+            Ok(())
+        }
+        async fn get_node(&self, _node_id: &NodeId) -> Result<Option<Node>> {
+            Ok(None)
+        }
+        async fn list_nodes(&self) -> Result<Vec<Node>> {
+            Ok(Vec::new())
+        }
+        async fn subscribe_to_events(&self) -> Result<watch::Receiver<Option<ClusterEvent>>> {
+            Ok(watch::channel(None).1)
+        }
+        async fn renew_lease(&self, node_id: &NodeId, lease: NodeLease) -> Result<()> {
+            self.leases.lock().unwrap().insert(*node_id, lease);
+            Ok(())
+        }
+        async fn node_leases(&self) -> Result<Vec<(NodeId, NodeLease)>> {
+            Ok(self
+                .leases
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(id, lease)| (*id, *lease))
+                .collect())
+        }
+    }
+
+    struct Harness {
+        cluster: Arc<LeaseClusterManager>,
+        store: InMemoryStateStore,
+        controller: NodeLifecycleController,
+        workload_rx: mpsc::Receiver<WorkloadDefinition>,
+    }
+
+    fn harness() -> Harness {
+        let cluster = Arc::new(LeaseClusterManager::default());
+        let store = InMemoryStateStore::new();
+        let (workload_tx, workload_rx) = mpsc::channel(16);
+        let controller =
+            NodeLifecycleController::new(cluster.clone(), Arc::new(store.clone()), workload_tx)
+                .with_lease_duration(Duration::from_secs(40))
+                .with_eviction_grace_period(Duration::from_secs(60));
+        Harness {
+            cluster,
+            store,
+            controller,
+            workload_rx,
+        }
+    }
+
+    async fn add_node(store: &InMemoryStateStore) -> NodeId {
+        let node = Node {
+            id: Keypair::generate().public_key(),
+            address: "10.0.0.1:7280".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            resources_capacity: NodeResources::default(),
+            resources_allocatable: NodeResources::default(),
+            unschedulable: false,
+            resource_version: 0,
+        };
+        let id = node.id;
+        store.put_node(node).await.unwrap();
+        id
+    }
+
+    async fn add_instance(store: &InMemoryStateStore, node_id: NodeId) -> WorkloadInstance {
+        let workload = WorkloadDefinition {
+            id: Uuid::new_v4(),
+            name: "web".to_string(),
+            containers: vec![],
+            init_containers: vec![],
+            sidecars: vec![],
+            replicas: 1,
+            labels: HashMap::new(),
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        let instance = WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: workload.id,
+            node_id,
+            container_ids: vec![],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        store.put_workload(workload).await.unwrap();
+        store.put_instance(instance.clone()).await.unwrap();
+        instance
+    }
+
+    async fn status(store: &InMemoryStateStore, node_id: &NodeId) -> NodeStatus {
+        store.get_node(node_id).await.unwrap().unwrap().status
+    }
+
+    #[tokio::test]
+    async fn test_lapsed_lease_marks_node_not_ready_then_evicts() {
+        let mut h = harness();
+        let node = add_node(&h.store).await;
+        let instance = add_instance(&h.store, node).await;
+        let renewer = LeaseRenewer::new(h.cluster.clone(), node, Duration::from_secs(40));
+        let start = Instant::now();
+
+        renewer.renew().await.unwrap();
+        assert_eq!(
+            h.controller.check_at(start).await.unwrap(),
+            Default::default()
+        );
+
+        // Within the lease: still Ready
+        let report = h
+            .controller
+            .check_at(start + Duration::from_secs(30))
+            .await
+            .unwrap();
+        assert!(report.not_ready.is_empty());
+        assert_eq!(status(&h.store, &node).await, NodeStatus::Ready);
+
+        // Lease lapsed: NotReady, but instances stay for the grace period
+        let report = h
+            .controller
+            .check_at(start + Duration::from_secs(41))
+            .await
+            .unwrap();
+        assert_eq!(report.not_ready, vec![node]);
+        assert!(report.evicted.is_empty());
+        assert_eq!(status(&h.store, &node).await, NodeStatus::NotReady);
+
+        let report = h
+            .controller
+            .check_at(start + Duration::from_secs(101))
+            .await
+            .unwrap();
+        assert_eq!(report.evicted, vec![instance.id]);
+        assert!(h
+            .store
+            .get_instance(&instance.id.to_string())
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(h.workload_rx.try_recv().unwrap().id, instance.workload_id);
+
+        let events = h.store.list_events().await.unwrap();
+        assert_eq!(events.len(), 2);
+        let evicted = events.iter().find(|e| e.reason == "Evicted").unwrap();
+        assert_eq!(evicted.involved_object.id, instance.id.to_string());
+        assert!(events.iter().any(|e| e.reason == "NodeNotReady"));
+    }
+
+    #[tokio::test]
+    async fn test_renewal_marks_node_ready_again() {
+        let h = harness();
+        let node = add_node(&h.store).await;
+        let instance = add_instance(&h.store, node).await;
+        let renewer = LeaseRenewer::new(h.cluster.clone(), node, Duration::from_secs(40));
+        let start = Instant::now();
+
+        renewer.renew().await.unwrap();
+        h.controller.check_at(start).await.unwrap();
+        h.controller
+            .check_at(start + Duration::from_secs(50))
+            .await
+            .unwrap();
+        assert_eq!(status(&h.store, &node).await, NodeStatus::NotReady);
+
+        renewer.renew().await.unwrap();
+        let report = h
+            .controller
+            .check_at(start + Duration::from_secs(55))
+            .await
+            .unwrap();
+        assert_eq!(report.recovered, vec![node]);
+        assert_eq!(status(&h.store, &node).await, NodeStatus::Ready);
+
+        // The grace period restarts with the next lapse
+        let report = h
+            .controller
+            .check_at(start + Duration::from_secs(120))
+            .await
+            .unwrap();
+        assert!(report.evicted.is_empty());
+        assert!(h
+            .store
+            .get_instance(&instance.id.to_string())
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_instances_on_departed_node_are_evicted() {
+        let h = harness();
+        // The node record is gone but an instance still points at it
+        let instance = add_instance(&h.store, Keypair::generate().public_key()).await;
+        let start = Instant::now();
+
+        h.controller.check_at(start).await.unwrap();
+        h.controller
+            .check_at(start + Duration::from_secs(41))
+            .await
+            .unwrap();
+        let report = h
+            .controller
+            .check_at(start + Duration::from_secs(101))
+            .await
+            .unwrap();
+        assert_eq!(report.evicted, vec![instance.id]);
+    }
+}
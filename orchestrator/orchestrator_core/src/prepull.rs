@@ -0,0 +1,328 @@
+//! Image pre-pulls.
+//!
+//! Pulling a multi-gigabyte image on every node at the moment a rollout
+//! starts makes all of them hit the registry at once. An [`ImagePrepuller`]
+//! instead has the ready nodes matching a label selector pull the image
+//! ahead of time, each into its own image cache, and tracks how far every
+//! node got. Pulls are queued at [`PullPriority::Batch`], so interactive
+//! deploys running meanwhile are not held up.
+//!
+//! Pre-pulls are kept in memory; the most recent [`ImagePrepuller::MAX_RETAINED`]
+//! are reported.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use container_runtime_interface::{ContainerRuntime, PullPriority};
+use orchestrator_shared_types::{NodeId, NodeStatus, OrchestrationError, Result};
+use state_store_interface::StateStore;
+
+pub type PrepullId = Uuid;
+
+/// Where one node is with a pre-pull.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum NodePullState {
+    /// Waiting for the node's pull queue.
+    Pending,
+    /// Downloading or unpacking, or queued behind other pulls.
+    Pulling,
+    Pulled,
+    Failed,
+}
+
+/// Progress of a pre-pull on one node.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct NodePullProgress {
+    #[cfg_attr(feature = "rest-api", schema(value_type = String))]
+    pub node_id: NodeId,
+    pub state: NodePullState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// An image being pulled onto a set of nodes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct Prepull {
+    #[cfg_attr(feature = "rest-api", schema(value_type = String))]
+    pub id: PrepullId,
+    pub image: String,
+    /// Labels of the nodes pulling the image; every ready node when empty.
+    #[serde(default)]
+    pub node_selector: HashMap<String, String>,
+    pub created_at: DateTime<Utc>,
+    pub nodes: Vec<NodePullProgress>,
+}
+
+impl Prepull {
+    /// Whether every node has finished, successfully or not.
+    pub fn is_complete(&self) -> bool {
+        self.nodes
+            .iter()
+            .all(|node| matches!(node.state, NodePullState::Pulled | NodePullState::Failed))
+    }
+
+    /// Nodes in `state`.
+    pub fn count(&self, state: NodePullState) -> usize {
+        self.nodes.iter().filter(|node| node.state == state).count()
+    }
+}
+
+/// Starts pre-pulls and tracks their progress.
+pub struct ImagePrepuller {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    prepulls: Arc<RwLock<HashMap<PrepullId, Prepull>>>,
+}
+
+impl ImagePrepuller {
+    /// Most pre-pulls kept; the oldest complete ones are forgotten first.
+    pub const MAX_RETAINED: usize = 100;
+
+    pub fn new(state_store: Arc<dyn StateStore>, runtime: Arc<dyn ContainerRuntime>) -> Self {
+        Self {
+            state_store,
+            runtime,
+            prepulls: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Have every ready node matching `node_selector` pull `image`. Returns
+    /// at once; the pulls continue in the background.
+    pub async fn start(
+        &self,
+        image: impl Into<String>,
+        node_selector: HashMap<String, String>,
+    ) -> Result<Prepull> {
+        let image = image.into();
+        if image.trim().is_empty() {
+            return Err(OrchestrationError::ConfigError(
+                "Image cannot be empty".to_string(),
+            ));
+        }
+        let nodes: Vec<NodeId> = self
+            .state_store
+            .list_nodes()
+            .await?
+            .into_iter()
+            .filter(|node| node.status == NodeStatus::Ready)
+            .filter(|node| {
+                node_selector
+                    .iter()
+                    .all(|(key, value)| node.labels.get(key) == Some(value))
+            })
+            .map(|node| node.id)
+            .collect();
+        if nodes.is_empty() {
+            return Err(OrchestrationError::ConfigError(
+                "No ready node matches the node selector".to_string(),
+            ));
+        }
+
+        let prepull = Prepull {
+            id: Uuid::new_v4(),
+            image: image.clone(),
+            node_selector,
+            created_at: Utc::now(),
+            nodes: nodes
+                .iter()
+                .map(|&node_id| NodePullProgress {
+                    node_id,
+                    state: NodePullState::Pending,
+                    error: None,
+                    started_at: None,
+                    finished_at: None,
+                })
+                .collect(),
+        };
+        {
+            let mut prepulls = self.prepulls.write().await;
+            evict_oldest_complete(&mut prepulls);
+            prepulls.insert(prepull.id, prepull.clone());
+        }
+        info!(image = %image, nodes = nodes.len(), "Pre-pulling image");
+
+        for node_id in nodes {
+            let (runtime, prepulls) = (self.runtime.clone(), self.prepulls.clone());
+            let (id, image) = (prepull.id, image.clone());
+            tokio::spawn(async move {
+                update(&prepulls, id, node_id, |progress| {
+                    progress.state = NodePullState::Pulling;
+                    progress.started_at = Some(Utc::now());
+                })
+                .await;
+                let pulled = runtime
+                    .pull_image(node_id, &image, PullPriority::Batch)
+                    .await;
+                if let Err(e) = &pulled {
+                    warn!(image = %image, node = %node_id, "Pre-pull failed: {}", e);
+                }
+                update(&prepulls, id, node_id, |progress| {
+                    match pulled {
+                        Ok(()) => progress.state = NodePullState::Pulled,
+                        Err(e) => {
+                            progress.state = NodePullState::Failed;
+                            progress.error = Some(e.to_string());
+                        }
+                    }
+                    progress.finished_at = Some(Utc::now());
+                })
+                .await;
+            });
+        }
+        Ok(prepull)
+    }
+
+    pub async fn get(&self, id: &PrepullId) -> Option<Prepull> {
+        self.prepulls.read().await.get(id).cloned()
+    }
+
+    /// Pre-pulls, oldest first.
+    pub async fn list(&self) -> Vec<Prepull> {
+        let mut prepulls: Vec<Prepull> = self.prepulls.read().await.values().cloned().collect();
+        prepulls.sort_by_key(|prepull| prepull.created_at);
+        prepulls
+    }
+}
+
+/// Change the progress of `node_id` in pre-pull `id`, unless it was forgotten.
+async fn update(
+    prepulls: &RwLock<HashMap<PrepullId, Prepull>>,
+    id: PrepullId,
+    node_id: NodeId,
+    f: impl FnOnce(&mut NodePullProgress),
+) {
+    let mut prepulls = prepulls.write().await;
+    let progress = prepulls
+        .get_mut(&id)
+        .and_then(|prepull| prepull.nodes.iter_mut().find(|n| n.node_id == node_id));
+    if let Some(progress) = progress {
+        f(progress);
+    }
+}
+
+/// Make room for one more pre-pull by forgetting the oldest complete one.
+fn evict_oldest_complete(prepulls: &mut HashMap<PrepullId, Prepull>) {
+    if prepulls.len() < ImagePrepuller::MAX_RETAINED {
+        return;
+    }
+    let oldest = prepulls
+        .values()
+        .filter(|prepull| prepull.is_complete())
+        .min_by_key(|prepull| prepull.created_at)
+        .map(|prepull| prepull.id);
+    if let Some(id) = oldest {
+        prepulls.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::StubRuntime;
+    use orchestrator_shared_types::{Keypair, Node, NodeResources};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use std::time::Duration;
+
+    async fn add_node(store: &InMemoryStateStore, gpu: bool, status: NodeStatus) -> NodeId {
+        let id = Keypair::generate().public_key();
+        let mut labels = HashMap::new();
+        if gpu {
+            labels.insert("gpu".to_string(), "true".to_string());
+        }
+        store
+            .put_node(Node {
+                id,
+                address: "10.0.0.1:8080".to_string(),
+                status,
+                labels,
+                resources_capacity: NodeResources::default(),
+                resources_allocatable: NodeResources::default(),
+                unschedulable: false,
+                resource_version: 0,
+            })
+            .await
+            .unwrap();
+        id
+    }
+
+    async fn wait_complete(prepuller: &ImagePrepuller, id: &PrepullId) -> Prepull {
+        for _ in 0..100 {
+            let prepull = prepuller.get(id).await.unwrap();
+            if prepull.is_complete() {
+                return prepull;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("pre-pull did not complete");
+    }
+
+    #[tokio::test]
+    async fn test_prepull_selected_ready_nodes() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let gpu = add_node(&store, true, NodeStatus::Ready).await;
+        add_node(&store, false, NodeStatus::Ready).await;
+        add_node(&store, true, NodeStatus::NotReady).await;
+        let runtime = Arc::new(StubRuntime::default());
+        let prepuller = ImagePrepuller::new(store, runtime.clone());
+
+        let selector = HashMap::from([("gpu".to_string(), "true".to_string())]);
+        let prepull = prepuller.start("vllm:0.6", selector).await.unwrap();
+        assert_eq!(prepull.nodes.len(), 1);
+        assert_eq!(prepull.nodes[0].node_id, gpu);
+
+        let prepull = wait_complete(&prepuller, &prepull.id).await;
+        assert_eq!(prepull.count(NodePullState::Pulled), 1);
+        assert!(prepull.nodes[0].finished_at >= prepull.nodes[0].started_at);
+        assert_eq!(prepuller.list().await, vec![prepull]);
+        assert_eq!(
+            *runtime.pulls.lock().unwrap(),
+            [("vllm:0.6".to_string(), PullPriority::Batch)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prepull_reports_failures() {
+        let store = Arc::new(InMemoryStateStore::new());
+        add_node(&store, false, NodeStatus::Ready).await;
+        add_node(&store, false, NodeStatus::Ready).await;
+        let runtime = StubRuntime::default().with_missing_image("missing:latest");
+        let prepuller = ImagePrepuller::new(store, Arc::new(runtime));
+
+        let prepull = prepuller
+            .start("missing:latest", HashMap::new())
+            .await
+            .unwrap();
+        let prepull = wait_complete(&prepuller, &prepull.id).await;
+        assert_eq!(prepull.count(NodePullState::Failed), 2);
+        assert_eq!(
+            prepull.nodes[0].error.as_deref(),
+            Some("Container runtime error: manifest unknown")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prepull_needs_image_and_nodes() {
+        let store = Arc::new(InMemoryStateStore::new());
+        add_node(&store, false, NodeStatus::Ready).await;
+        let prepuller = ImagePrepuller::new(store, Arc::new(StubRuntime::default()));
+
+        assert!(prepuller.start(" ", HashMap::new()).await.is_err());
+        let selector = HashMap::from([("gpu".to_string(), "true".to_string())]);
+        assert!(prepuller.start("vllm:0.6", selector).await.is_err());
+        assert!(prepuller.list().await.is_empty());
+    }
+}
@@ -0,0 +1,777 @@
+//! Pull-through cache of container registries.
+//!
+//! The control plane can run a [`RegistryCache`] that serves the pull side of
+//! the Distribution API v2 to node agents, which use it as their registry
+//! mirror. Blobs and manifests are fetched from the upstream registry the
+//! first time any node asks for them, checked against their digest and kept
+//! on disk, so nodes behind a slow link share a single download:
+//!
+//! - `GET /v2/`
+//! - `GET|HEAD /v2/<name>/manifests/<tag or digest>`
+//! - `GET|HEAD /v2/<name>/blobs/<digest>`
+//!
+//! The upstream is named by the `ns` query parameter, as containerd does for
+//! mirrors, and defaults to Docker Hub. Only the configured upstreams are
+//! pulled from, so the cache cannot be used to reach arbitrary hosts; it does
+//! not authenticate nodes and is meant to listen on the cluster network only.
+//!
+//! Tags are resolved upstream again once `tag_ttl` has passed. When the
+//! upstream cannot be reached, the last manifest seen for a tag is served.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::body::Body;
+use axum::extract::{Path as UrlPath, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio_util::io::ReaderStream;
+use tracing::{debug, error, info, warn};
+
+use orchestrator_shared_types::{OrchestrationError, Result};
+
+/// Address the cache listens on unless configured otherwise.
+pub const DEFAULT_LISTEN_ADDR: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 5000);
+
+/// Registry host of Docker Hub.
+pub const DOCKER_HUB: &str = "registry-1.docker.io";
+
+/// Manifest types asked for when the client sends no Accept header.
+const MANIFEST_ACCEPT: &str = "application/vnd.oci.image.index.v1+json, \
+    application/vnd.oci.image.manifest.v1+json, \
+    application/vnd.docker.distribution.manifest.list.v2+json, \
+    application/vnd.docker.distribution.manifest.v2+json";
+
+/// Largest manifest accepted from an upstream.
+const MAX_MANIFEST_SIZE: usize = 4 * 1024 * 1024;
+
+/// Configuration for the registry cache.
+#[derive(Debug, Clone)]
+pub struct RegistryCacheConfig {
+    /// Address to serve the registry API on.
+    pub listen_addr: SocketAddr,
+    /// Directory holding cached blobs and manifests.
+    pub cache_dir: PathBuf,
+    /// Registries that may be pulled through the cache (default: Docker Hub).
+    pub upstreams: Vec<String>,
+    /// How long a tag is served from the cache before it is resolved
+    /// upstream again (default: 5m).
+    pub tag_ttl: Duration,
+}
+
+impl RegistryCacheConfig {
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            listen_addr: DEFAULT_LISTEN_ADDR,
+            cache_dir: cache_dir.into(),
+            upstreams: vec![DOCKER_HUB.to_string()],
+            tag_ttl: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Errors returned to clients, in the Distribution API's error format.
+#[derive(Debug, thiserror::Error)]
+enum CacheError {
+    #[error("invalid repository name or reference")]
+    NameInvalid,
+    #[error("upstream registry '{0}' is not allowed")]
+    Denied(String),
+    #[error("manifest {0} not found")]
+    ManifestUnknown(String),
+    #[error("blob {0} not found")]
+    BlobUnknown(String),
+    #[error("upstream returned {actual} for {expected}")]
+    DigestMismatch { expected: String, actual: String },
+    #[error("upstream error: {0}")]
+    Upstream(String),
+    #[error("cache error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl IntoResponse for CacheError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            CacheError::NameInvalid => (StatusCode::BAD_REQUEST, "NAME_INVALID"),
+            CacheError::Denied(_) => (StatusCode::FORBIDDEN, "DENIED"),
+            CacheError::ManifestUnknown(_) => (StatusCode::NOT_FOUND, "MANIFEST_UNKNOWN"),
+            CacheError::BlobUnknown(_) => (StatusCode::NOT_FOUND, "BLOB_UNKNOWN"),
+            CacheError::DigestMismatch { .. } | CacheError::Upstream(_) => {
+                (StatusCode::BAD_GATEWAY, "UNAVAILABLE")
+            }
+            CacheError::Io(_) => (StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN"),
+        };
+        let body = serde_json::json!({
+            "errors": [{ "code": code, "message": self.to_string() }]
+        });
+        (status, Json(body)).into_response()
+    }
+}
+
+/// What a request under `/v2/` asks for.
+#[derive(Debug, PartialEq)]
+enum Target<'a> {
+    Manifest { name: &'a str, reference: &'a str },
+    Blob { name: &'a str, digest: &'a str },
+}
+
+/// Parse `<name>/manifests/<reference>` or `<name>/blobs/<digest>`.
+fn parse_target(path: &str) -> Option<Target<'_>> {
+    let mut parts = path.rsplitn(3, '/');
+    let (reference, kind, name) = (parts.next()?, parts.next()?, parts.next()?);
+    if !valid_name(name) {
+        return None;
+    }
+    match kind {
+        "manifests" if valid_tag(reference) || valid_digest(reference) => {
+            Some(Target::Manifest { name, reference })
+        }
+        "blobs" if valid_digest(reference) => Some(Target::Blob {
+            name,
+            digest: reference,
+        }),
+        _ => None,
+    }
+}
+
+/// Whether `name` is a repository name: lowercase path components that start
+/// and end with a letter or digit.
+fn valid_name(name: &str) -> bool {
+    name.len() <= 255
+        && name.split('/').all(|component| {
+            let bytes = component.as_bytes();
+            !bytes.is_empty()
+                && bytes[0].is_ascii_alphanumeric()
+                && bytes[bytes.len() - 1].is_ascii_alphanumeric()
+                && bytes.iter().all(|b| {
+                    b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'.' | b'_' | b'-')
+                })
+        })
+}
+
+fn valid_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.len() <= 128
+        && !tag.starts_with(['.', '-'])
+        && tag
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+}
+
+/// Only sha256 digests are cached, as they are what blobs are verified with.
+fn valid_digest(digest: &str) -> bool {
+    digest.strip_prefix("sha256:").is_some_and(|hex| {
+        hex.len() == 64
+            && hex
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    })
+}
+
+fn sha256_digest(data: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(data)))
+}
+
+/// Map the `ns` a client names to the registry host to pull from.
+fn upstream_host(ns: Option<&str>) -> &str {
+    match ns {
+        None | Some("docker.io") | Some("index.docker.io") => DOCKER_HUB,
+        Some(ns) => ns,
+    }
+}
+
+/// Parameters of a `WWW-Authenticate: Bearer` challenge.
+fn parse_bearer_challenge(value: &str) -> Option<(String, Option<String>)> {
+    let params = value.strip_prefix("Bearer ")?;
+    let mut realm = None;
+    let mut service = None;
+    for param in params.split(',') {
+        let Some((key, value)) = param.trim().split_once('=') else {
+            continue;
+        };
+        let value = value.trim_matches('"').to_string();
+        match key {
+            "realm" => realm = Some(value),
+            "service" => service = Some(value),
+            _ => {}
+        }
+    }
+    Some((realm?, service))
+}
+
+#[derive(Deserialize)]
+struct PullQuery {
+    ns: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    #[serde(alias = "access_token")]
+    token: String,
+    expires_in: Option<u64>,
+}
+
+/// A tag resolved upstream.
+struct ResolvedTag {
+    digest: String,
+    resolved_at: Instant,
+}
+
+/// Pull-through cache serving the registry API.
+pub struct RegistryCache {
+    config: RegistryCacheConfig,
+    client: reqwest::Client,
+    /// Upstream tokens by upstream and repository, with their expiry.
+    tokens: Mutex<HashMap<String, (String, Instant)>>,
+    /// Resolved tags by upstream, repository, tag and Accept header.
+    tags: Mutex<HashMap<String, ResolvedTag>>,
+    /// Locks held while a blob is being downloaded, by digest.
+    downloads: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    tmp_counter: AtomicU64,
+}
+
+impl RegistryCache {
+    pub fn new(config: RegistryCacheConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
+        Self {
+            config,
+            client,
+            tokens: Mutex::new(HashMap::new()),
+            tags: Mutex::new(HashMap::new()),
+            downloads: Mutex::new(HashMap::new()),
+            tmp_counter: AtomicU64::new(0),
+        }
+    }
+
+    /// Routes of the registry API.
+    pub fn router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route("/v2/", get(api_version))
+            .route("/v2/*path", get(pull))
+            .with_state(self)
+    }
+
+    /// Start serving the cache, dropping downloads a previous run left
+    /// unfinished.
+    pub async fn spawn(self: Arc<Self>) -> Result<JoinHandle<()>> {
+        let dir = &self.config.cache_dir;
+        let tmp = dir.join("tmp");
+        let _ = tokio::fs::remove_dir_all(&tmp).await;
+        for subdir in [dir.join("blobs"), dir.join("manifests"), tmp] {
+            tokio::fs::create_dir_all(&subdir).await.map_err(|e| {
+                OrchestrationError::ConfigError(format!(
+                    "Failed to create registry cache directory {}: {}",
+                    subdir.display(),
+                    e
+                ))
+            })?;
+        }
+
+        let listener = TcpListener::bind(self.config.listen_addr)
+            .await
+            .map_err(|e| {
+                OrchestrationError::NetworkError(format!(
+                    "Failed to bind registry cache on {}: {}",
+                    self.config.listen_addr, e
+                ))
+            })?;
+        info!(
+            "Registry cache listening on {} (upstreams: {:?})",
+            self.config.listen_addr, self.config.upstreams
+        );
+
+        let router = self.router();
+        Ok(tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                error!("Registry cache stopped: {}", e);
+            }
+        }))
+    }
+
+    /// The allowed upstream a client names.
+    fn upstream(&self, ns: Option<&str>) -> std::result::Result<String, CacheError> {
+        let host = upstream_host(ns);
+        if self
+            .config
+            .upstreams
+            .iter()
+            .any(|allowed| upstream_host(Some(allowed)) == host)
+        {
+            Ok(host.to_string())
+        } else {
+            Err(CacheError::Denied(host.to_string()))
+        }
+    }
+
+    fn blob_path(&self, digest: &str) -> PathBuf {
+        self.config
+            .cache_dir
+            .join("blobs")
+            .join(digest.replace(':', "/"))
+    }
+
+    fn manifest_path(&self, digest: &str) -> PathBuf {
+        self.config
+            .cache_dir
+            .join("manifests")
+            .join(digest.replace(':', "/"))
+    }
+
+    fn tmp_path(&self) -> PathBuf {
+        let n = self.tmp_counter.fetch_add(1, Ordering::Relaxed);
+        self.config.cache_dir.join("tmp").join(n.to_string())
+    }
+
+    /// GET `path` of repository `name` upstream, answering a bearer token
+    /// challenge if the upstream sends one.
+    async fn upstream_get(
+        &self,
+        upstream: &str,
+        name: &str,
+        path: &str,
+        accept: Option<&str>,
+    ) -> std::result::Result<reqwest::Response, CacheError> {
+        let url = format!("https://{}/v2/{}/{}", upstream, name, path);
+        let token_key = format!("{}/{}", upstream, name);
+        let cached = self
+            .tokens
+            .lock()
+            .await
+            .get(&token_key)
+            .filter(|(_, expires)| *expires > Instant::now())
+            .map(|(token, _)| token.clone());
+
+        let request = |token: Option<&str>| {
+            let mut request = self.client.get(&url);
+            if let Some(accept) = accept {
+                request = request.header(header::ACCEPT, accept);
+            }
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            request.send()
+        };
+        let upstream_err = |e: reqwest::Error| CacheError::Upstream(e.to_string());
+
+        let response = request(cached.as_deref()).await.map_err(upstream_err)?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+        let challenge = response
+            .headers()
+            .get(header::WWW_AUTHENTICATE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_bearer_challenge)
+            .ok_or_else(|| CacheError::Upstream(format!("{} requires authentication", upstream)))?;
+        let token = self.fetch_token(challenge, name).await?;
+        self.tokens.lock().await.insert(token_key, token.clone());
+        request(Some(&token.0)).await.map_err(upstream_err)
+    }
+
+    /// Get an anonymous pull token for `name` from the challenge's realm.
+    async fn fetch_token(
+        &self,
+        (realm, service): (String, Option<String>),
+        name: &str,
+    ) -> std::result::Result<(String, Instant), CacheError> {
+        let mut query = vec![("scope", format!("repository:{}:pull", name))];
+        if let Some(service) = service {
+            query.push(("service", service));
+        }
+        let response = self
+            .client
+            .get(&realm)
+            .query(&query)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| CacheError::Upstream(format!("token request failed: {}", e)))?;
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| CacheError::Upstream(format!("invalid token response: {}", e)))?;
+        // Renew a little early so a token never expires mid-request
+        let lifetime = Duration::from_secs(token.expires_in.unwrap_or(60).saturating_sub(10));
+        Ok((token.token, Instant::now() + lifetime))
+    }
+
+    /// Path of the cached blob, downloading it first if needed.
+    async fn fetch_blob(
+        &self,
+        upstream: &str,
+        name: &str,
+        digest: &str,
+    ) -> std::result::Result<PathBuf, CacheError> {
+        let path = self.blob_path(digest);
+        if tokio::fs::try_exists(&path).await? {
+            return Ok(path);
+        }
+
+        // Nodes pulling the same image at once share one download
+        let lock = self
+            .downloads
+            .lock()
+            .await
+            .entry(digest.to_string())
+            .or_default()
+            .clone();
+        let _guard = lock.lock().await;
+        let result = if tokio::fs::try_exists(&path).await? {
+            Ok(())
+        } else {
+            self.download_blob(upstream, name, digest, &path).await
+        };
+        self.downloads.lock().await.remove(digest);
+        result.map(|()| path)
+    }
+
+    async fn download_blob(
+        &self,
+        upstream: &str,
+        name: &str,
+        digest: &str,
+        path: &Path,
+    ) -> std::result::Result<(), CacheError> {
+        let mut response = self
+            .upstream_get(upstream, name, &format!("blobs/{}", digest), None)
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => return Err(CacheError::BlobUnknown(digest.to_string())),
+            status if !status.is_success() => {
+                return Err(CacheError::Upstream(format!(
+                    "{} returned {}",
+                    upstream, status
+                )))
+            }
+            _ => {}
+        }
+        info!("Caching blob {} of {}/{}", digest, upstream, name);
+
+        let tmp = self.tmp_path();
+        let result = async {
+            let mut file = tokio::fs::File::create(&tmp).await?;
+            let mut hasher = Sha256::new();
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .map_err(|e| CacheError::Upstream(e.to_string()))?
+            {
+                hasher.update(&chunk);
+                file.write_all(&chunk).await?;
+            }
+            file.sync_all().await?;
+
+            let actual = format!("sha256:{}", hex::encode(hasher.finalize()));
+            if actual != digest {
+                return Err(CacheError::DigestMismatch {
+                    expected: digest.to_string(),
+                    actual,
+                });
+            }
+            store(&tmp, path).await
+        }
+        .await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+        result
+    }
+
+    /// The manifest `reference` names, with its media type, from the cache
+    /// or upstream.
+    async fn manifest(
+        &self,
+        upstream: &str,
+        name: &str,
+        reference: &str,
+        accept: &str,
+    ) -> std::result::Result<(String, String, Vec<u8>), CacheError> {
+        if valid_digest(reference) {
+            if let Some((media_type, data)) = self.cached_manifest(reference).await? {
+                return Ok((reference.to_string(), media_type, data));
+            }
+            let (digest, media_type, data) = self
+                .fetch_manifest(upstream, name, reference, accept)
+                .await?;
+            if digest != reference {
+                return Err(CacheError::DigestMismatch {
+                    expected: reference.to_string(),
+                    actual: digest,
+                });
+            }
+            self.store_manifest(&digest, &media_type, &data).await?;
+            return Ok((digest, media_type, data));
+        }
+
+        let tag_key = format!("{}/{}:{} {}", upstream, name, reference, accept);
+        let cached = self.tags.lock().await.get(&tag_key).map(|tag| {
+            (
+                tag.digest.clone(),
+                tag.resolved_at.elapsed() < self.config.tag_ttl,
+            )
+        });
+        if let Some((digest, true)) = &cached {
+            if let Some((media_type, data)) = self.cached_manifest(digest).await? {
+                return Ok((digest.clone(), media_type, data));
+            }
+        }
+
+        match self.fetch_manifest(upstream, name, reference, accept).await {
+            Ok((digest, media_type, data)) => {
+                self.store_manifest(&digest, &media_type, &data).await?;
+                self.tags.lock().await.insert(
+                    tag_key,
+                    ResolvedTag {
+                        digest: digest.clone(),
+                        resolved_at: Instant::now(),
+                    },
+                );
+                Ok((digest, media_type, data))
+            }
+            Err(CacheError::Upstream(e)) => {
+                let Some((digest, _)) = cached else {
+                    return Err(CacheError::Upstream(e));
+                };
+                let Some((media_type, data)) = self.cached_manifest(&digest).await? else {
+                    return Err(CacheError::Upstream(e));
+                };
+                warn!(
+                    "Serving {}/{}:{} from cache, upstream unavailable: {}",
+                    upstream, name, reference, e
+                );
+                Ok((digest, media_type, data))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn fetch_manifest(
+        &self,
+        upstream: &str,
+        name: &str,
+        reference: &str,
+        accept: &str,
+    ) -> std::result::Result<(String, String, Vec<u8>), CacheError> {
+        let response = self
+            .upstream_get(
+                upstream,
+                name,
+                &format!("manifests/{}", reference),
+                Some(accept),
+            )
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => {
+                return Err(CacheError::ManifestUnknown(format!(
+                    "{}:{}",
+                    name, reference
+                )))
+            }
+            status if !status.is_success() => {
+                return Err(CacheError::Upstream(format!(
+                    "{} returned {}",
+                    upstream, status
+                )))
+            }
+            _ => {}
+        }
+        let media_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("application/vnd.oci.image.manifest.v1+json")
+            .to_string();
+        let data = response
+            .bytes()
+            .await
+            .map_err(|e| CacheError::Upstream(e.to_string()))?;
+        if data.len() > MAX_MANIFEST_SIZE {
+            return Err(CacheError::Upstream(format!(
+                "manifest {}:{} is larger than {} bytes",
+                name, reference, MAX_MANIFEST_SIZE
+            )));
+        }
+        debug!("Fetched manifest {}/{}:{}", upstream, name, reference);
+        Ok((sha256_digest(&data), media_type, data.to_vec()))
+    }
+
+    async fn cached_manifest(
+        &self,
+        digest: &str,
+    ) -> std::result::Result<Option<(String, Vec<u8>)>, CacheError> {
+        let path = self.manifest_path(digest);
+        let data = match tokio::fs::read(&path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let media_type = tokio::fs::read_to_string(path.with_extension("type")).await?;
+        Ok(Some((media_type, data)))
+    }
+
+    async fn store_manifest(
+        &self,
+        digest: &str,
+        media_type: &str,
+        data: &[u8],
+    ) -> std::result::Result<(), CacheError> {
+        let path = self.manifest_path(digest);
+        // The media type goes first, so a stored manifest always has one
+        let tmp = self.tmp_path();
+        tokio::fs::write(&tmp, media_type).await?;
+        store(&tmp, &path.with_extension("type")).await?;
+        tokio::fs::write(&tmp, data).await?;
+        store(&tmp, &path).await
+    }
+}
+
+/// Move a finished download from the tmp directory into place.
+async fn store(tmp: &Path, path: &Path) -> std::result::Result<(), CacheError> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::rename(tmp, path).await?;
+    Ok(())
+}
+
+async fn api_version() -> Response {
+    (
+        [("Docker-Distribution-API-Version", "registry/2.0")],
+        Json(serde_json::json!({})),
+    )
+        .into_response()
+}
+
+async fn pull(
+    State(cache): State<Arc<RegistryCache>>,
+    UrlPath(path): UrlPath<String>,
+    Query(query): Query<PullQuery>,
+    headers: HeaderMap,
+) -> std::result::Result<Response, CacheError> {
+    let upstream = cache.upstream(query.ns.as_deref())?;
+    match parse_target(&path).ok_or(CacheError::NameInvalid)? {
+        Target::Manifest { name, reference } => {
+            let accept = headers
+                .get(header::ACCEPT)
+                .and_then(|v| v.to_str().ok())
+                .filter(|v| !v.is_empty())
+                .unwrap_or(MANIFEST_ACCEPT);
+            let (digest, media_type, data) =
+                cache.manifest(&upstream, name, reference, accept).await?;
+            Ok((
+                [
+                    (header::CONTENT_TYPE, media_type),
+                    (
+                        header::HeaderName::from_static("docker-content-digest"),
+                        digest,
+                    ),
+                ],
+                data,
+            )
+                .into_response())
+        }
+        Target::Blob { name, digest } => {
+            let path = cache.fetch_blob(&upstream, name, digest).await?;
+            let file = tokio::fs::File::open(&path).await?;
+            let size = file.metadata().await?.len();
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+                    (header::CONTENT_LENGTH, size.to_string()),
+                    (
+                        header::HeaderName::from_static("docker-content-digest"),
+                        digest.to_string(),
+                    ),
+                ],
+                Body::from_stream(ReaderStream::new(file)),
+            )
+                .into_response())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "sha256:2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            parse_target("library/nginx/manifests/1.25-alpine"),
+            Some(Target::Manifest {
+                name: "library/nginx",
+                reference: "1.25-alpine"
+            })
+        );
+        assert_eq!(
+            parse_target(&format!("org/team/app/blobs/{}", DIGEST)),
+            Some(Target::Blob {
+                name: "org/team/app",
+                digest: DIGEST
+            })
+        );
+        assert_eq!(
+            parse_target(&format!("app/manifests/{}", DIGEST)),
+            Some(Target::Manifest {
+                name: "app",
+                reference: DIGEST
+            })
+        );
+
+        // Blobs are only addressed by digest, and names cannot escape the cache
+        assert_eq!(parse_target("app/blobs/latest"), None);
+        assert_eq!(parse_target("../etc/manifests/latest"), None);
+        assert_eq!(parse_target("App/manifests/latest"), None);
+        assert_eq!(parse_target("app/tags/list"), None);
+        assert_eq!(parse_target("manifests/latest"), None);
+        assert_eq!(parse_target("app/blobs/sha256:abc"), None);
+        assert_eq!(sha256_digest(b"hello"), DIGEST);
+    }
+
+    #[test]
+    fn test_upstream_allowlist() {
+        let mut config = RegistryCacheConfig::new("/tmp/registry-cache");
+        config.upstreams.push("ghcr.io".to_string());
+        let cache = RegistryCache::new(config);
+
+        assert_eq!(cache.upstream(None).unwrap(), DOCKER_HUB);
+        assert_eq!(cache.upstream(Some("docker.io")).unwrap(), DOCKER_HUB);
+        assert_eq!(cache.upstream(Some("ghcr.io")).unwrap(), "ghcr.io");
+        assert!(matches!(
+            cache.upstream(Some("169.254.169.254")),
+            Err(CacheError::Denied(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_bearer_challenge() {
+        assert_eq!(
+            parse_bearer_challenge(
+                r#"Bearer realm="https://auth.docker.io/token",service="registry.docker.io""#
+            ),
+            Some((
+                "https://auth.docker.io/token".to_string(),
+                Some("registry.docker.io".to_string())
+            ))
+        );
+        assert_eq!(parse_bearer_challenge(r#"Basic realm="registry""#), None);
+    }
+}
@@ -0,0 +1,283 @@
+//! Recording and replaying control-plane history.
+//!
+//! An event log is a JSON-lines file of [`RecordedEntry`] values: the workload
+//! and node changes a control plane saw, in order. [`spawn_recorder`] writes
+//! one from a live state store, starting with a snapshot of its contents, and
+//! the `replay` feature's [`Replayer`] feeds a log through the reconciler
+//! against a mock runtime to reproduce an incident locally.
+//!
+//! Replay reproduces the order of decisions, not their identifiers: instance
+//! and container IDs are generated afresh on every run.
+
+#[cfg(feature = "replay")]
+mod runner;
+
+#[cfg(feature = "replay")]
+pub use runner::{ReplayStep, Replayer, WorkloadSnapshot};
+
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use orchestrator_shared_types::{Node, NodeId, OrchestrationError, Result, WorkloadDefinition};
+use state_store_interface::{StateStore, WatchEvent, WatchEventType};
+
+/// A change recorded in the event log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    /// A workload was created or updated.
+    WorkloadApplied {
+        workload: Box<WorkloadDefinition>,
+    },
+    WorkloadDeleted {
+        workload_id: Uuid,
+    },
+    NodeAdded {
+        node: Node,
+    },
+    /// A node's status, resources or cordon changed.
+    NodeUpdated {
+        node: Node,
+    },
+    NodeRemoved {
+        node_id: NodeId,
+    },
+}
+
+impl RecordedEvent {
+    /// One-line description for replay output.
+    pub fn describe(&self) -> String {
+        match self {
+            RecordedEvent::WorkloadApplied { workload } => format!(
+                "workload {} applied ({} replicas)",
+                workload.name, workload.replicas
+            ),
+            RecordedEvent::WorkloadDeleted { workload_id } => {
+                format!("workload {} deleted", workload_id)
+            }
+            RecordedEvent::NodeAdded { node } => {
+                format!("node {} added ({:?})", node.id, node.status)
+            }
+            RecordedEvent::NodeUpdated { node } => format!(
+                "node {} updated ({:?}{})",
+                node.id,
+                node.status,
+                if node.unschedulable { ", cordoned" } else { "" }
+            ),
+            RecordedEvent::NodeRemoved { node_id } => format!("node {} removed", node_id),
+        }
+    }
+
+    fn from_workload(event: WatchEvent<WorkloadDefinition>) -> Self {
+        match event.event_type {
+            WatchEventType::Added | WatchEventType::Modified => RecordedEvent::WorkloadApplied {
+                workload: Box::new(event.object),
+            },
+            WatchEventType::Deleted => RecordedEvent::WorkloadDeleted {
+                workload_id: event.object.id,
+            },
+        }
+    }
+
+    fn from_node(event: WatchEvent<Node>) -> Self {
+        match event.event_type {
+            WatchEventType::Added => RecordedEvent::NodeAdded { node: event.object },
+            WatchEventType::Modified => RecordedEvent::NodeUpdated { node: event.object },
+            WatchEventType::Deleted => RecordedEvent::NodeRemoved {
+                node_id: event.object.id,
+            },
+        }
+    }
+}
+
+/// One line of the event log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEntry {
+    /// When the control plane saw the change.
+    pub at: DateTime<Utc>,
+    pub event: RecordedEvent,
+}
+
+impl RecordedEntry {
+    pub fn now(event: RecordedEvent) -> Self {
+        Self {
+            at: Utc::now(),
+            event,
+        }
+    }
+}
+
+/// Parse an event log, skipping blank lines.
+pub fn parse_log(contents: &str) -> Result<Vec<RecordedEntry>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                OrchestrationError::ConfigError(format!("event log line {}: {}", index + 1, e))
+            })
+        })
+        .collect()
+}
+
+/// Append every workload and node change in `store` to the event log at
+/// `path`, preceded by the nodes and workloads it already holds.
+pub async fn spawn_recorder(
+    store: Arc<dyn StateStore>,
+    path: impl AsRef<Path>,
+) -> Result<JoinHandle<()>> {
+    let path = path.as_ref().to_path_buf();
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+        .map_err(|e| {
+            OrchestrationError::ConfigError(format!(
+                "cannot open event log {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+    // Subscribe before the snapshot so no change falls between the two
+    let mut workloads = store.watch_workloads().await?;
+    let mut nodes = store.watch_nodes().await?;
+    let mut snapshot: Vec<RecordedEvent> = store
+        .list_nodes()
+        .await?
+        .into_iter()
+        .map(|node| RecordedEvent::NodeAdded { node })
+        .collect();
+    snapshot.extend(
+        store
+            .list_workloads()
+            .await?
+            .into_iter()
+            .map(Box::new)
+            .map(|workload| RecordedEvent::WorkloadApplied { workload }),
+    );
+
+    info!("Recording control-plane events to {}", path.display());
+    Ok(tokio::spawn(async move {
+        for event in snapshot {
+            if let Err(e) = append(&mut file, &RecordedEntry::now(event)).await {
+                error!("Failed to write event log {}: {}", path.display(), e);
+                return;
+            }
+        }
+        loop {
+            let event = tokio::select! {
+                Some(event) = workloads.recv() => RecordedEvent::from_workload(event),
+                Some(event) = nodes.recv() => RecordedEvent::from_node(event),
+                else => break,
+            };
+            if let Err(e) = append(&mut file, &RecordedEntry::now(event)).await {
+                error!("Failed to write event log {}: {}", path.display(), e);
+                return;
+            }
+        }
+    }))
+}
+
+async fn append(file: &mut tokio::fs::File, entry: &RecordedEntry) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    file.write_all(&line).await?;
+    file.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orchestrator_shared_types::{Keypair, NodeResources, NodeStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn node() -> Node {
+        Node {
+            id: Keypair::generate().public_key(),
+            address: "10.0.0.1:8080".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            resources_capacity: NodeResources::default(),
+            resources_allocatable: NodeResources::default(),
+            unschedulable: false,
+            resource_version: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let node = node();
+        let entries = [
+            RecordedEntry::now(RecordedEvent::NodeAdded { node: node.clone() }),
+            RecordedEntry::now(RecordedEvent::NodeRemoved { node_id: node.id }),
+        ];
+        let log = entries
+            .iter()
+            .map(|entry| serde_json::to_string(entry).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        assert_eq!(parse_log(&log).unwrap(), entries);
+        assert!(log.contains(r#""kind":"node_added""#));
+
+        let err = parse_log(&format!("{}\nnot json", log)).unwrap_err();
+        assert!(err.to_string().contains("line 4"));
+    }
+
+    #[tokio::test]
+    async fn test_recorder_writes_snapshot_then_changes() {
+        let store = Arc::new(InMemoryStateStore::new());
+        store.put_node(node()).await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let recorder = spawn_recorder(store.clone(), &path).await.unwrap();
+
+        let workload = WorkloadDefinition {
+            id: Uuid::new_v4(),
+            name: "web".to_string(),
+            containers: vec![],
+            init_containers: vec![],
+            sidecars: vec![],
+            replicas: 2,
+            labels: HashMap::new(),
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        store.put_workload(workload.clone()).await.unwrap();
+        store.delete_workload(&workload.id).await.unwrap();
+
+        let mut kinds = Vec::new();
+        for _ in 0..50 {
+            let contents = tokio::fs::read_to_string(&path).await.unwrap();
+            kinds = parse_log(&contents)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.event.describe())
+                .collect();
+            if kinds.len() == 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        recorder.abort();
+
+        assert_eq!(kinds.len(), 3);
+        assert!(kinds[0].contains("added"));
+        assert_eq!(kinds[1], "workload web applied (2 replicas)");
+        assert_eq!(kinds[2], format!("workload {} deleted", workload.id));
+    }
+}
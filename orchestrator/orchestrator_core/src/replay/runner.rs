@@ -0,0 +1,291 @@
+//! Replays an event log through the reconciler against [`MockRuntime`].
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use cluster_manager_interface::{ClusterEvent, ClusterManager};
+use container_runtime::MockRuntime;
+use orchestrator_shared_types::{Node, NodeId, Result};
+use scheduler_interface::SimpleScheduler;
+use state_store_interface::in_memory::InMemoryStateStore;
+use state_store_interface::StateStore;
+
+use super::{RecordedEntry, RecordedEvent};
+use crate::restart::{RestartManager, RestartReport};
+use crate::Orchestrator;
+
+/// Cluster manager that reports the nodes in the replay's state store.
+struct ReplayCluster {
+    state_store: Arc<dyn StateStore>,
+    events_tx: watch::Sender<Option<ClusterEvent>>,
+}
+
+#[async_trait]
+impl ClusterManager for ReplayCluster {
+    async fn initialize(&self) -> Result<()> {
+        Ok(())
+    }
+    async fn get_node(&self, node_id: &NodeId) -> Result<Option<Node>> {
+        self.state_store.get_node(node_id).await
+    }
+    async fn list_nodes(&self) -> Result<Vec<Node>> {
+        self.state_store.list_nodes().await
+    }
+    async fn subscribe_to_events(&self) -> Result<watch::Receiver<Option<ClusterEvent>>> {
+        Ok(self.events_tx.subscribe())
+    }
+}
+
+/// A workload's instances after a replay step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkloadSnapshot {
+    pub id: Uuid,
+    pub name: String,
+    pub replicas: u32,
+    /// Instance count by status, e.g. `Running`.
+    pub instances: BTreeMap<String, usize>,
+}
+
+/// Outcome of replaying one entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayStep {
+    /// Position of the entry in the log, from 1.
+    pub index: usize,
+    pub at: DateTime<Utc>,
+    pub event: String,
+    /// Error the control plane would have logged for this event.
+    pub error: Option<String>,
+    pub restarts: RestartReport,
+    pub workloads: Vec<WorkloadSnapshot>,
+}
+
+/// Replays recorded events against an in-memory control plane.
+///
+/// Each entry is applied the way the orchestrator's run loop applies it,
+/// followed by a restart-manager pass at the entry's recorded time, one at a
+/// time and in log order.
+pub struct Replayer {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<MockRuntime>,
+    orchestrator: Orchestrator,
+    restarts: RestartManager,
+}
+
+impl Replayer {
+    pub async fn new() -> Result<Self> {
+        let state_store: Arc<dyn StateStore> = Arc::new(InMemoryStateStore::new());
+        state_store.initialize().await?;
+        let runtime = Arc::new(MockRuntime::new());
+        let (events_tx, _) = watch::channel(None);
+        let cluster = Arc::new(ReplayCluster {
+            state_store: state_store.clone(),
+            events_tx,
+        });
+        let orchestrator = Orchestrator::new(
+            state_store.clone(),
+            runtime.clone(),
+            cluster,
+            Arc::new(SimpleScheduler),
+        );
+        let restarts = RestartManager::new(state_store.clone(), runtime.clone());
+        Ok(Self {
+            state_store,
+            runtime,
+            orchestrator,
+            restarts,
+        })
+    }
+
+    /// The mock runtime, e.g. to make an image's containers exit.
+    pub fn runtime(&self) -> &MockRuntime {
+        &self.runtime
+    }
+
+    pub fn state_store(&self) -> Arc<dyn StateStore> {
+        self.state_store.clone()
+    }
+
+    /// Replay every entry in order.
+    pub async fn replay(&self, entries: &[RecordedEntry]) -> Result<Vec<ReplayStep>> {
+        let mut steps = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.iter().enumerate() {
+            steps.push(self.step(index + 1, entry).await?);
+        }
+        Ok(steps)
+    }
+
+    /// Replay one entry; `index` only labels the step.
+    pub async fn step(&self, index: usize, entry: &RecordedEntry) -> Result<ReplayStep> {
+        let error = self.apply(&entry.event).await.err().map(|e| e.to_string());
+        let restarts = self.restarts.check_at(entry.at.into()).await?;
+        Ok(ReplayStep {
+            index,
+            at: entry.at,
+            event: entry.event.describe(),
+            error,
+            restarts,
+            workloads: self.snapshot().await?,
+        })
+    }
+
+    async fn apply(&self, event: &RecordedEvent) -> Result<()> {
+        match event {
+            RecordedEvent::WorkloadApplied { workload } => {
+                self.orchestrator.apply_workload(*workload.clone()).await
+            }
+            RecordedEvent::WorkloadDeleted { workload_id } => {
+                // As the API deletes it; the instances' containers are left
+                // for garbage collection
+                self.state_store
+                    .delete_instances_for_workload(workload_id)
+                    .await?;
+                self.state_store.delete_workload(workload_id).await
+            }
+            RecordedEvent::NodeAdded { node } => {
+                self.orchestrator
+                    .apply_cluster_event(ClusterEvent::NodeAdded(node.clone()))
+                    .await
+            }
+            RecordedEvent::NodeUpdated { node } => {
+                self.orchestrator
+                    .apply_cluster_event(ClusterEvent::NodeUpdated(node.clone()))
+                    .await?;
+                // Cluster events keep the stored cordon, which was set
+                // through the API; take the recorded one instead
+                if let Some(mut stored) = self.state_store.get_node(&node.id).await? {
+                    if stored.unschedulable != node.unschedulable {
+                        stored.unschedulable = node.unschedulable;
+                        self.state_store.put_node(stored).await?;
+                    }
+                }
+                Ok(())
+            }
+            RecordedEvent::NodeRemoved { node_id } => {
+                self.orchestrator
+                    .apply_cluster_event(ClusterEvent::NodeRemoved(*node_id))
+                    .await
+            }
+        }
+    }
+
+    async fn snapshot(&self) -> Result<Vec<WorkloadSnapshot>> {
+        let mut snapshots = Vec::new();
+        for workload in self.state_store.list_workloads().await? {
+            let mut instances = BTreeMap::new();
+            for instance in self
+                .state_store
+                .list_instances_for_workload(&workload.id)
+                .await?
+            {
+                *instances
+                    .entry(format!("{:?}", instance.status))
+                    .or_insert(0) += 1;
+            }
+            snapshots.push(WorkloadSnapshot {
+                id: workload.id,
+                name: workload.name,
+                replicas: workload.replicas,
+                instances,
+            });
+        }
+        snapshots.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+        Ok(snapshots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use orchestrator_shared_types::{
+        ContainerConfig, Keypair, NodeResources, NodeStatus, WorkloadDefinition,
+    };
+    use std::collections::HashMap;
+
+    fn node(status: NodeStatus) -> Node {
+        Node {
+            id: Keypair::generate().public_key(),
+            address: "10.0.0.1:8080".to_string(),
+            status,
+            labels: HashMap::new(),
+            resources_capacity: NodeResources {
+                cpu_cores: 8.0,
+                memory_mb: 16384,
+                disk_mb: 100_000,
+            },
+            resources_allocatable: NodeResources {
+                cpu_cores: 8.0,
+                memory_mb: 16384,
+                disk_mb: 100_000,
+            },
+            unschedulable: false,
+            resource_version: 0,
+        }
+    }
+
+    fn workload(replicas: u32) -> WorkloadDefinition {
+        WorkloadDefinition {
+            containers: vec![ContainerConfig {
+                image: "nginx:latest".to_string(),
+                ..test_support::container("web")
+            }],
+            replicas,
+            ..test_support::workload("web")
+        }
+    }
+
+    fn entry(event: RecordedEvent) -> RecordedEntry {
+        RecordedEntry::now(event)
+    }
+
+    #[tokio::test]
+    async fn test_replay_schedules_scales_and_deletes() {
+        let web = workload(3);
+        let log = vec![
+            entry(RecordedEvent::NodeAdded {
+                node: node(NodeStatus::Ready),
+            }),
+            entry(RecordedEvent::WorkloadApplied {
+                workload: Box::new(web.clone()),
+            }),
+            entry(RecordedEvent::WorkloadApplied {
+                workload: Box::new(WorkloadDefinition {
+                    replicas: 1,
+                    ..web.clone()
+                }),
+            }),
+            entry(RecordedEvent::WorkloadDeleted {
+                workload_id: web.id,
+            }),
+        ];
+
+        let replayer = Replayer::new().await.unwrap();
+        let steps = replayer.replay(&log).await.unwrap();
+
+        assert_eq!(steps.len(), 4);
+        assert!(steps.iter().all(|step| step.error.is_none()));
+        let live = |step: &ReplayStep| -> usize { step.workloads[0].instances.values().sum() };
+        assert_eq!(live(&steps[1]), 3);
+        assert_eq!(live(&steps[2]), 1);
+        assert!(steps[3].workloads.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_without_nodes_reports_pending_work() {
+        let log = vec![entry(RecordedEvent::WorkloadApplied {
+            workload: Box::new(workload(2)),
+        })];
+
+        let replayer = Replayer::new().await.unwrap();
+        let steps = replayer.replay(&log).await.unwrap();
+
+        let running = steps[0].workloads[0].instances.get("Running").copied();
+        assert_eq!(running.unwrap_or(0), 0);
+    }
+}
@@ -0,0 +1,129 @@
+//! Controller objects kept in the state store.
+//!
+//! Stateful sets and the other objects a controller owns are stored through
+//! the state store's resource API, as JSON documents of their
+//! [`Resource::KIND`] named by [`Resource::key`]. Controllers write every
+//! change through before acting on it and reload their objects when their
+//! node becomes leader, so a new leader carries on where the last one stopped.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use orchestrator_shared_types::{OrchestrationError, Result};
+use state_store_interface::StateStore;
+
+/// An object kept in the state store under a kind of its own.
+pub trait Resource: Serialize + DeserializeOwned {
+    /// Kind the objects are stored under, e.g. "statefulsets".
+    const KIND: &'static str;
+
+    /// Name of the object, unique within its kind.
+    fn key(&self) -> String;
+}
+
+/// A kind of resource the control plane stores.
+pub struct ResourceKind {
+    pub kind: &'static str,
+    /// Name of a stored document of the kind, as [`Resource::key`] gives it.
+    pub key: fn(&serde_json::Value) -> Result<String>,
+}
+
+impl ResourceKind {
+    const fn of<T: Resource>() -> Self {
+        Self {
+            kind: T::KIND,
+            key: key_of::<T>,
+        }
+    }
+}
+
+/// Every kind of resource the control plane stores, for backups.
+pub const KINDS: &[ResourceKind] = &[
+    ResourceKind::of::<crate::controllers::DaemonSet>(),
+    ResourceKind::of::<crate::jobs::CronJob>(),
+    ResourceKind::of::<crate::jobs::Job>(),
+    ResourceKind::of::<crate::controllers::StatefulSet>(),
+    ResourceKind::of::<crate::controllers::statefulset::InstanceRecord>(),
+    ResourceKind::of::<crate::network::Service>(),
+    #[cfg(feature = "rest-api")]
+    ResourceKind::of::<crate::api::federation::StoredCluster>(),
+];
+
+/// Store `object`, replacing the one with the same key.
+pub async fn put<T: Resource>(store: &dyn StateStore, object: &T) -> Result<()> {
+    let data = serde_json::to_value(object).map_err(|e| {
+        OrchestrationError::StateError(format!("Failed to serialize {}: {}", T::KIND, e))
+    })?;
+    store.put_resource(T::KIND, &object.key(), data).await
+}
+
+/// Get the object named `key`.
+pub async fn get<T: Resource>(store: &dyn StateStore, key: &str) -> Result<Option<T>> {
+    store
+        .get_resource(T::KIND, key)
+        .await?
+        .map(decode)
+        .transpose()
+}
+
+/// List every object of the kind.
+pub async fn list<T: Resource>(store: &dyn StateStore) -> Result<Vec<T>> {
+    store
+        .list_resources(T::KIND)
+        .await?
+        .into_iter()
+        .map(decode)
+        .collect()
+}
+
+/// Delete the object named `key`; a missing one is fine.
+pub async fn delete<T: Resource>(store: &dyn StateStore, key: &str) -> Result<()> {
+    store.delete_resource(T::KIND, key).await
+}
+
+fn key_of<T: Resource>(data: &serde_json::Value) -> Result<String> {
+    decode::<T>(data.clone()).map(|object| object.key())
+}
+
+fn decode<T: Resource>(data: serde_json::Value) -> Result<T> {
+    serde_json::from_value(data)
+        .map_err(|e| OrchestrationError::StateError(format!("Invalid stored {}: {}", T::KIND, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Widget {
+        name: String,
+        size: u32,
+    }
+
+    impl Resource for Widget {
+        const KIND: &'static str = "widgets";
+
+        fn key(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resource_round_trip() {
+        let store = InMemoryStateStore::new();
+        let widget = |size| Widget {
+            name: "w".to_string(),
+            size,
+        };
+        put(&store, &widget(1)).await.unwrap();
+        put(&store, &widget(2)).await.unwrap();
+
+        assert_eq!(get::<Widget>(&store, "w").await.unwrap(), Some(widget(2)));
+        assert_eq!(list::<Widget>(&store).await.unwrap(), [widget(2)]);
+
+        delete::<Widget>(&store, "w").await.unwrap();
+        assert!(list::<Widget>(&store).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,785 @@
+//! Restarting exited containers with crash-loop back-off.
+//!
+//! The [`RestartManager`] polls the containers of every live instance. When
+//! one has exited, its workload's [`RestartPolicy`] decides from the exit code
+//! whether it is restarted in place, whether the instance has succeeded, or
+//! whether the instance has failed.
+//!
+//! Restarts are spaced by a capped exponential [`BackOffPolicy`]: the first
+//! restart after an exit is immediate, each further consecutive failure
+//! doubles the wait. While a container waits, its instance is reported as
+//! `CrashLoopBackOff` and the container's restart status carries the time the
+//! back-off ends. A container that stays up for `reset_after` has its failure
+//! count cleared.
+//!
+//! Runtimes that publish container events wake the manager as soon as a
+//! container exits; the poll still runs to end back-offs and catch exits a
+//! runtime could not report.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use crate::events::EventRecorder;
+use container_runtime_interface::{
+    ContainerEventKind, ContainerRuntime, CreateContainerOptions, InstanceSpec,
+};
+use orchestrator_shared_types::{
+    ContainerConfig, ContainerRestartStatus, ContainerTermination, RestartPolicy, Result,
+    TerminationReason, WorkloadDefinition, WorkloadInstance, WorkloadInstanceStatus,
+};
+use state_store_interface::StateStore;
+
+/// Spacing of restarts after consecutive failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackOffPolicy {
+    /// Wait before the second restart in a row (default: 10s).
+    pub initial: Duration,
+    /// Longest wait between restarts (default: 5m).
+    pub max: Duration,
+    /// Uptime after which a container's failures are forgotten (default: 10m).
+    pub reset_after: Duration,
+}
+
+impl Default for BackOffPolicy {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(10),
+            max: Duration::from_secs(300),
+            reset_after: Duration::from_secs(600),
+        }
+    }
+}
+
+impl BackOffPolicy {
+    /// Wait before restarting a container that has failed
+    /// `consecutive_failures` times in a row.
+    pub fn delay(&self, consecutive_failures: u32) -> Duration {
+        match consecutive_failures {
+            0 | 1 => Duration::ZERO,
+            n => {
+                let factor = 1u32.checked_shl(n - 2).unwrap_or(u32::MAX);
+                self.initial.saturating_mul(factor).min(self.max)
+            }
+        }
+    }
+}
+
+/// Outcome of one or more passes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RestartReport {
+    pub restarted: u64,
+    /// Containers that exited and are waiting out a back-off.
+    pub backing_off: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+}
+
+/// What a pass decided for one instance.
+#[derive(Debug, Default)]
+struct InstanceOutcome {
+    changed: bool,
+    restarted: u64,
+    backing_off: u64,
+    /// How a container that may not be restarted exited.
+    fatal_exit: Option<ContainerTermination>,
+    running: usize,
+    completed: usize,
+}
+
+/// Periodically applies restart policies to exited containers.
+pub struct RestartManager {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    back_off: BackOffPolicy,
+    events: EventRecorder,
+}
+
+impl RestartManager {
+    /// How often [`spawn`](Self::spawn) checks containers when run with defaults.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(state_store: Arc<dyn StateStore>, runtime: Arc<dyn ContainerRuntime>) -> Self {
+        Self {
+            events: EventRecorder::new(state_store.clone(), "restart-manager"),
+            state_store,
+            runtime,
+            back_off: BackOffPolicy::default(),
+        }
+    }
+
+    pub fn with_back_off(mut self, back_off: BackOffPolicy) -> Self {
+        self.back_off = back_off;
+        self
+    }
+
+    pub fn back_off(&self) -> &BackOffPolicy {
+        &self.back_off
+    }
+
+    /// Check every live instance now.
+    pub async fn check(&self) -> Result<RestartReport> {
+        self.check_at(SystemTime::now()).await
+    }
+
+    /// Check every live instance as of `now`.
+    pub async fn check_at(&self, now: SystemTime) -> Result<RestartReport> {
+        let mut report = RestartReport::default();
+        for workload in self.state_store.list_workloads().await? {
+            for instance in self
+                .state_store
+                .list_instances_for_workload(&workload.id)
+                .await?
+            {
+                if matches!(
+                    instance.status,
+                    WorkloadInstanceStatus::Pending
+                        | WorkloadInstanceStatus::Running
+                        | WorkloadInstanceStatus::CrashLoopBackOff
+                ) {
+                    self.check_instance(&workload, instance, now, &mut report)
+                        .await?;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    async fn check_instance(
+        &self,
+        workload: &WorkloadDefinition,
+        mut instance: WorkloadInstance,
+        now: SystemTime,
+        report: &mut RestartReport,
+    ) -> Result<()> {
+        // start_instance returns sidecars first, then the main containers
+        let spec = InstanceSpec::from_workload(workload);
+        let configs: Vec<(&ContainerConfig, bool)> = spec
+            .sidecars
+            .iter()
+            .map(|c| (c, false))
+            .chain(spec.containers.iter().map(|c| (c, true)))
+            .collect();
+        if spec.containers.is_empty() || configs.len() != instance.container_ids.len() {
+            debug!(
+                "Instance {} has {} containers but workload {} defines {}; skipping",
+                instance.id,
+                instance.container_ids.len(),
+                workload.name,
+                configs.len()
+            );
+            return Ok(());
+        }
+
+        let options = CreateContainerOptions {
+            workload_id: workload.id,
+            node_id: instance.node_id,
+            pull_priority: Default::default(),
+        };
+        let mut outcome = InstanceOutcome::default();
+        for (index, (config, is_main)) in configs.into_iter().enumerate() {
+            self.check_container(
+                &workload.restart_policy,
+                &mut instance,
+                index,
+                config,
+                is_main,
+                &options,
+                now,
+                &mut outcome,
+            )
+            .await;
+            if outcome.fatal_exit.is_some() {
+                break;
+            }
+        }
+
+        let main_containers = spec.containers.len();
+        let status = if let Some(termination) = &outcome.fatal_exit {
+            warn!(
+                "Instance {} of workload {} failed ({}, exit code {:?}); not restarting",
+                instance.id, workload.name, termination.reason, termination.exit_code
+            );
+            report.failed += 1;
+            WorkloadInstanceStatus::Failed
+        } else if outcome.completed == main_containers {
+            info!(
+                "Instance {} of workload {} completed",
+                instance.id, workload.name
+            );
+            report.succeeded += 1;
+            WorkloadInstanceStatus::Succeeded
+        } else if outcome.backing_off > 0 {
+            WorkloadInstanceStatus::CrashLoopBackOff
+        } else if outcome.running + outcome.completed == instance.container_ids.len() {
+            WorkloadInstanceStatus::Running
+        } else {
+            instance.status.clone()
+        };
+        report.restarted += outcome.restarted;
+        report.backing_off += outcome.backing_off;
+        self.record_events(&instance, &status, &outcome).await;
+
+        if matches!(
+            status,
+            WorkloadInstanceStatus::Failed | WorkloadInstanceStatus::Succeeded
+        ) {
+            // Sidecars and any surviving containers go with the instance
+            if let Err(e) = self.runtime.stop_instance(&instance.container_ids).await {
+                warn!(
+                    "Failed to stop containers of instance {}: {:?}",
+                    instance.id, e
+                );
+            }
+        }
+        if status != instance.status || outcome.changed {
+            instance.status = status;
+            self.state_store.put_instance(instance).await?;
+        }
+        Ok(())
+    }
+
+    async fn record_events(
+        &self,
+        instance: &WorkloadInstance,
+        status: &WorkloadInstanceStatus,
+        outcome: &InstanceOutcome,
+    ) {
+        if outcome.restarted > 0 {
+            let message = format!("Restarted {} exited container(s)", outcome.restarted);
+            self.events
+                .instance_normal(instance, "Restarted", message)
+                .await;
+        }
+        if *status == instance.status {
+            return;
+        }
+        match status {
+            WorkloadInstanceStatus::Failed => {
+                let message = match &outcome.fatal_exit {
+                    Some(termination) if termination.reason == TerminationReason::OomKilled => {
+                        "Container was OOM killed".to_string()
+                    }
+                    Some(ContainerTermination {
+                        exit_code: Some(code),
+                        ..
+                    }) => format!("Container exited with code {}", code),
+                    _ => "Container exited".to_string(),
+                };
+                self.events
+                    .instance_warning(instance, "Failed", message)
+                    .await
+            }
+            WorkloadInstanceStatus::Succeeded => {
+                self.events
+                    .instance_normal(instance, "Completed", "All containers completed")
+                    .await
+            }
+            WorkloadInstanceStatus::CrashLoopBackOff => {
+                self.events
+                    .instance_warning(instance, "BackOff", "Back-off restarting exited container")
+                    .await
+            }
+            _ => {}
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn check_container(
+        &self,
+        policy: &RestartPolicy,
+        instance: &mut WorkloadInstance,
+        index: usize,
+        config: &ContainerConfig,
+        is_main: bool,
+        options: &CreateContainerOptions,
+        now: SystemTime,
+        outcome: &mut InstanceOutcome,
+    ) {
+        let container_id = instance.container_ids[index].clone();
+        let status = match self.runtime.get_container_status(&container_id).await {
+            Ok(status) => status,
+            Err(e) => {
+                debug!(
+                    "Could not get status of container {}: {:?}",
+                    container_id, e
+                );
+                return;
+            }
+        };
+
+        let position = instance
+            .restarts
+            .iter()
+            .position(|r| r.container_name == config.name);
+        if !status.has_exited() {
+            outcome.running += 1;
+            // Forget past failures once the container has stayed up long enough
+            if let Some(restart) = position.map(|i| &mut instance.restarts[i]) {
+                let healthy_since =
+                    restart.last_restart_at.unwrap_or(now) + self.back_off.reset_after;
+                if restart.consecutive_failures > 0 && healthy_since <= now {
+                    restart.consecutive_failures = 0;
+                    outcome.changed = true;
+                }
+            }
+            return;
+        }
+
+        let restart = match position {
+            Some(i) => &mut instance.restarts[i],
+            None => {
+                instance
+                    .restarts
+                    .push(ContainerRestartStatus::new(config.name.clone()));
+                instance.restarts.last_mut().unwrap()
+            }
+        };
+        let exit_code = status.exit_code;
+        let termination = ContainerTermination {
+            reason: TerminationReason::classify(exit_code, status.oom_killed),
+            exit_code,
+            finished_at: now,
+        };
+        // Kept on the instance so `describe` can tell why the container stopped
+        if record_termination(restart, &termination) {
+            outcome.changed = true;
+        }
+
+        if !policy.should_restart(exit_code) {
+            if is_main && exit_code == Some(0) {
+                outcome.completed += 1;
+            } else if is_main || exit_code != Some(0) {
+                outcome.fatal_exit = Some(termination);
+            }
+            return;
+        }
+
+        // A fresh exit starts a back-off; a finished back-off restarts
+        let back_off_until = match restart.back_off_until {
+            Some(until) => until,
+            None => {
+                restart.consecutive_failures += 1;
+                restart.last_exit_code = exit_code;
+                let until = now + self.back_off.delay(restart.consecutive_failures);
+                restart.back_off_until = Some(until);
+                outcome.changed = true;
+                until
+            }
+        };
+        if back_off_until > now {
+            debug!(
+                "Container {} of instance {} exited ({:?}); restarting in {:?}",
+                config.name,
+                instance.id,
+                exit_code,
+                back_off_until.duration_since(now).unwrap_or_default()
+            );
+            outcome.backing_off += 1;
+            return;
+        }
+
+        let _ = self.runtime.remove_container(&container_id).await;
+        outcome.changed = true;
+        match self.runtime.create_container(config, options).await {
+            Ok(new_id) => {
+                info!(
+                    "Restarted container {} of instance {} (exit code {:?}, restart {})",
+                    config.name,
+                    instance.id,
+                    exit_code,
+                    restart.restart_count + 1
+                );
+                restart.restart_count += 1;
+                restart.last_restart_at = Some(now);
+                restart.back_off_until = None;
+                instance.container_ids[index] = new_id;
+                outcome.restarted += 1;
+                outcome.running += 1;
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to restart container {} of instance {}: {:?}",
+                    config.name, instance.id, e
+                );
+                // Retry after the next back-off step
+                restart.consecutive_failures += 1;
+                restart.back_off_until =
+                    Some(now + self.back_off.delay(restart.consecutive_failures));
+                outcome.backing_off += 1;
+            }
+        }
+    }
+
+    /// Check every `interval` until the task is aborted, and right away when
+    /// the runtime reports that a container exited, if it reports events.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            let mut events = self.runtime.events();
+            loop {
+                match events.as_mut() {
+                    Some(receiver) => tokio::select! {
+                        _ = interval.tick() => {}
+                        received = receiver.recv() => match received {
+                            Ok(event) if is_exit(&event.kind) => {}
+                            Ok(_) => continue,
+                            // Missed events may have been exits
+                            Err(RecvError::Lagged(_)) => {}
+                            Err(RecvError::Closed) => events = None,
+                        },
+                    },
+                    None => {
+                        interval.tick().await;
+                    }
+                }
+                if let Some(receiver) = events.as_mut() {
+                    // One pass covers every exit reported so far
+                    while receiver.try_recv().is_ok() {}
+                }
+                if let Err(e) = self.check().await {
+                    error!("Container restart check failed: {:?}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Whether a container event may call for a restart.
+/// Record how a container exited, unless the exit is already recorded: the
+/// container stays exited across checks until it is restarted.
+fn record_termination(
+    restart: &mut ContainerRestartStatus,
+    termination: &ContainerTermination,
+) -> bool {
+    let recorded = restart.last_termination.as_ref().is_some_and(|last| {
+        restart
+            .last_restart_at
+            .is_none_or(|restarted| restarted < last.finished_at)
+    });
+    if !recorded {
+        restart.last_termination = Some(termination.clone());
+    }
+    !recorded
+}
+
+fn is_exit(kind: &ContainerEventKind) -> bool {
+    matches!(
+        kind,
+        ContainerEventKind::Exited { .. } | ContainerEventKind::OomKilled
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use async_trait::async_trait;
+    use container_runtime_interface::{ContainerEvent, ContainerStatus};
+    use orchestrator_shared_types::{ContainerId, Keypair, NodeId};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::{Mutex, OnceLock};
+    use tokio::sync::broadcast;
+    use uuid::Uuid;
+
+    /// Containers run until a test makes them exit.
+    #[derive(Default)]
+    struct ScriptedRuntime {
+        exits: Mutex<HashMap<ContainerId, Option<i32>>>,
+        oom_kills: Mutex<HashSet<ContainerId>>,
+        created: Mutex<Vec<ContainerId>>,
+        events: OnceLock<broadcast::Sender<ContainerEvent>>,
+    }
+
+    impl ScriptedRuntime {
+        fn exit(&self, id: &ContainerId, code: Option<i32>) {
+            self.exits.lock().unwrap().insert(id.clone(), code);
+            let kind = ContainerEventKind::Exited { exit_code: code };
+            let _ = self.publisher().send(ContainerEvent::now(id.clone(), kind));
+        }
+
+        fn oom_kill(&self, id: &ContainerId) {
+            self.oom_kills.lock().unwrap().insert(id.clone());
+            self.exit(id, Some(137));
+        }
+
+        fn publisher(&self) -> &broadcast::Sender<ContainerEvent> {
+            self.events.get_or_init(|| broadcast::channel(16).0)
+        }
+    }
+
+    #[async_trait]
+    impl ContainerRuntime for ScriptedRuntime {
+        async fn init_node(&self, _node_id: NodeId) -> Result<()> {
+            Ok(())
+        }
+        async fn create_container(
+            &self,
+            config: &ContainerConfig,
+            _options: &CreateContainerOptions,
+        ) -> Result<ContainerId> {
+            let id = format!("{}-{}", config.name, Uuid::new_v4());
+            self.created.lock().unwrap().push(id.clone());
+            Ok(id)
+        }
+        async fn stop_container(&self, _container_id: &ContainerId) -> Result<()> {
+            Ok(())
+        }
+        async fn remove_container(&self, _container_id: &ContainerId) -> Result<()> {
+            Ok(())
+        }
+        async fn get_container_status(
+            &self,
+            container_id: &ContainerId,
+        ) -> Result<ContainerStatus> {
+            let exit = self.exits.lock().unwrap().get(container_id).copied();
+            Ok(ContainerStatus {
+                id: container_id.clone(),
+                state: if exit.is_some() { "exited" } else { "running" }.to_string(),
+                exit_code: exit.flatten(),
+                error_message: None,
+                oom_killed: self.oom_kills.lock().unwrap().contains(container_id),
+            })
+        }
+        async fn list_containers(&self, _node_id: NodeId) -> Result<Vec<ContainerStatus>> {
+            Ok(vec![])
+        }
+        fn events(&self) -> Option<broadcast::Receiver<ContainerEvent>> {
+            Some(self.publisher().subscribe())
+        }
+    }
+
+    async fn setup(
+        policy: RestartPolicy,
+    ) -> (
+        Arc<InMemoryStateStore>,
+        Arc<ScriptedRuntime>,
+        RestartManager,
+        WorkloadInstance,
+    ) {
+        let store = Arc::new(InMemoryStateStore::new());
+        let runtime = Arc::new(ScriptedRuntime::default());
+        let workload = WorkloadDefinition {
+            sidecars: vec![test_support::container("proxy")],
+            restart_policy: policy,
+            ..test_support::workload("api")
+        };
+        store.put_workload(workload.clone()).await.unwrap();
+        let instance = WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: workload.id,
+            node_id: Keypair::generate().public_key(),
+            container_ids: vec!["proxy-0".to_string(), "api-0".to_string()],
+            status: WorkloadInstanceStatus::Pending,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        store.put_instance(instance.clone()).await.unwrap();
+        let manager = RestartManager::new(store.clone(), runtime.clone());
+        (store, runtime, manager, instance)
+    }
+
+    async fn reload(store: &InMemoryStateStore, instance: &WorkloadInstance) -> WorkloadInstance {
+        store
+            .list_instances_for_workload(&instance.workload_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|i| i.id == instance.id)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_back_off_doubles_up_to_cap() {
+        let policy = BackOffPolicy::default();
+        assert_eq!(policy.delay(1), Duration::ZERO);
+        assert_eq!(policy.delay(2), Duration::from_secs(10));
+        assert_eq!(policy.delay(3), Duration::from_secs(20));
+        assert_eq!(policy.delay(6), Duration::from_secs(160));
+        assert_eq!(policy.delay(7), Duration::from_secs(300));
+        assert_eq!(policy.delay(100), Duration::from_secs(300));
+    }
+
+    #[tokio::test]
+    async fn test_exit_events_trigger_a_check() {
+        let (store, runtime, manager, instance) = setup(RestartPolicy::default()).await;
+        // Only the first tick of the poll runs during the test
+        let task = Arc::new(manager).spawn(Duration::from_secs(3600));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while reload(&store, &instance).await.status != WorkloadInstanceStatus::Running {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        runtime.exit(&"api-0".to_string(), Some(1));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while runtime.created.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_crash_loop_backs_off_and_recovers() {
+        let (store, runtime, manager, instance) = setup(RestartPolicy::default()).await;
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+
+        manager.check_at(t0).await.unwrap();
+        assert_eq!(
+            reload(&store, &instance).await.status,
+            WorkloadInstanceStatus::Running
+        );
+
+        // First exit restarts at once
+        runtime.exit(&"api-0".to_string(), Some(1));
+        let report = manager.check_at(at(1)).await.unwrap();
+        assert_eq!(report.restarted, 1);
+        let current = reload(&store, &instance).await;
+        assert_eq!(current.status, WorkloadInstanceStatus::Running);
+        assert_eq!(current.container_ids[0], "proxy-0");
+        let restarted_id = current.container_ids[1].clone();
+        assert_ne!(restarted_id, "api-0");
+
+        // Second exit in a row waits 10s
+        runtime.exit(&restarted_id, Some(1));
+        let report = manager.check_at(at(2)).await.unwrap();
+        assert_eq!(report.backing_off, 1);
+        let current = reload(&store, &instance).await;
+        assert_eq!(current.status, WorkloadInstanceStatus::CrashLoopBackOff);
+        assert_eq!(
+            current.back_off_remaining(at(5)),
+            Some(Duration::from_secs(7))
+        );
+        let restart = current.restart_status("api").unwrap();
+        assert_eq!(
+            (
+                restart.restart_count,
+                restart.consecutive_failures,
+                restart.last_exit_code
+            ),
+            (1, 2, Some(1))
+        );
+        let mut reasons: Vec<String> = store
+            .list_events()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.reason)
+            .collect();
+        reasons.sort();
+        assert_eq!(reasons, ["BackOff", "Restarted"]);
+
+        assert_eq!(manager.check_at(at(11)).await.unwrap().restarted, 0);
+        assert_eq!(manager.check_at(at(12)).await.unwrap().restarted, 1);
+        let current = reload(&store, &instance).await;
+        assert_eq!(current.status, WorkloadInstanceStatus::Running);
+        assert_eq!(current.back_off_remaining(at(12)), None);
+
+        // Staying up past reset_after clears the failure count
+        manager.check_at(at(12 + 600)).await.unwrap();
+        let current = reload(&store, &instance).await;
+        let restart = current.restart_status("api").unwrap();
+        assert_eq!(
+            (restart.restart_count, restart.consecutive_failures),
+            (2, 0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exit_codes_decide_success_and_failure() {
+        let (store, runtime, manager, instance) = setup(RestartPolicy::on_failure()).await;
+        runtime.exit(&"api-0".to_string(), Some(0));
+        let report = manager.check_at(SystemTime::now()).await.unwrap();
+        assert_eq!(report.succeeded, 1);
+        assert_eq!(
+            reload(&store, &instance).await.status,
+            WorkloadInstanceStatus::Succeeded
+        );
+
+        let policy = RestartPolicy::on_failure().with_fatal_exit_codes([78]);
+        let (store, runtime, manager, instance) = setup(policy).await;
+        runtime.exit(&"api-0".to_string(), Some(78));
+        let report = manager.check_at(SystemTime::now()).await.unwrap();
+        assert_eq!((report.failed, report.restarted), (1, 0));
+        assert_eq!(
+            reload(&store, &instance).await.status,
+            WorkloadInstanceStatus::Failed
+        );
+        assert!(runtime.created.lock().unwrap().is_empty());
+        let events = store.list_events().await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].message, "Container exited with code 78");
+
+        let (store, runtime, manager, instance) = setup(RestartPolicy::never()).await;
+        runtime.exit(&"proxy-0".to_string(), Some(2));
+        manager.check_at(SystemTime::now()).await.unwrap();
+        assert_eq!(
+            reload(&store, &instance).await.status,
+            WorkloadInstanceStatus::Failed
+        );
+    }
+    #[tokio::test]
+    async fn test_last_termination_is_kept() {
+        let (store, runtime, manager, instance) = setup(RestartPolicy::default()).await;
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+        let last_termination = |instance: &WorkloadInstance| {
+            let restart = instance.restart_status("api").unwrap();
+            restart.last_termination.clone().unwrap()
+        };
+
+        runtime.oom_kill(&"api-0".to_string());
+        manager.check_at(at(1)).await.unwrap();
+        let oom_killed = ContainerTermination {
+            reason: TerminationReason::OomKilled,
+            exit_code: Some(137),
+            finished_at: at(1),
+        };
+        assert_eq!(
+            last_termination(&reload(&store, &instance).await),
+            oom_killed
+        );
+
+        // Kept while the restarted container runs, replaced by its next exit
+        manager.check_at(at(2)).await.unwrap();
+        let current = reload(&store, &instance).await;
+        assert_eq!(last_termination(&current), oom_killed);
+        runtime.exit(&current.container_ids[1], Some(1));
+        manager.check_at(at(3)).await.unwrap();
+        manager.check_at(at(4)).await.unwrap();
+        let termination = last_termination(&reload(&store, &instance).await);
+        assert_eq!(
+            (
+                termination.reason,
+                termination.exit_code,
+                termination.finished_at
+            ),
+            (TerminationReason::Error, Some(1), at(3))
+        );
+
+        let (store, runtime, manager, instance) = setup(RestartPolicy::never()).await;
+        runtime.oom_kill(&"api-0".to_string());
+        manager.check_at(at(1)).await.unwrap();
+        let current = reload(&store, &instance).await;
+        assert_eq!(current.status, WorkloadInstanceStatus::Failed);
+        assert_eq!(
+            last_termination(&current).reason,
+            TerminationReason::OomKilled
+        );
+        let events = store.list_events().await.unwrap();
+        assert_eq!(events[0].message, "Container was OOM killed");
+    }
+}
@@ -0,0 +1,208 @@
+//! Rollout history of workloads.
+//!
+//! Every change to a workload's template (its containers, init containers,
+//! sidecars, placement and restart policy) is recorded in the state store as a
+//! numbered [`WorkloadRevision`], so a bad change such as a broken image can be
+//! undone by rolling back to an earlier revision. Scaling and relabelling do
+//! not make revisions. A rollback is itself a template change and becomes the
+//! newest revision.
+//!
+//! Stores that do not keep revisions report `NotImplemented`; recording is then
+//! skipped. The garbage collector trims histories to its
+//! `revision_history_limit`, always keeping the current revision.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use orchestrator_shared_types::{
+    OrchestrationError, Result, WorkloadDefinition, WorkloadId, WorkloadInstance,
+    WorkloadInstanceStatus, WorkloadRevision,
+};
+use state_store_interface::StateStore;
+
+/// Progress of a workload towards its desired replicas.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(utoipa::ToSchema))]
+pub struct RolloutStatus {
+    #[cfg_attr(feature = "rest-api", schema(value_type = uuid::Uuid))]
+    pub workload_id: WorkloadId,
+    /// Current revision; absent when the store keeps no history.
+    pub revision: Option<u64>,
+    pub replicas: u32,
+    /// Instances that exist, in any state.
+    pub current_replicas: u32,
+    pub running_replicas: u32,
+    /// Whether exactly `replicas` instances exist and all of them are running.
+    pub complete: bool,
+}
+
+impl RolloutStatus {
+    pub fn new(
+        workload: &WorkloadDefinition,
+        revision: Option<u64>,
+        instances: &[WorkloadInstance],
+    ) -> Self {
+        let running = instances
+            .iter()
+            .filter(|i| i.status == WorkloadInstanceStatus::Running)
+            .count() as u32;
+        let current = instances.len() as u32;
+        Self {
+            workload_id: workload.id,
+            revision,
+            replicas: workload.replicas,
+            current_replicas: current,
+            running_replicas: running,
+            complete: current == workload.replicas && running == workload.replicas,
+        }
+    }
+}
+
+/// The workload's revisions, oldest first; `None` if the store keeps none.
+pub async fn revisions(
+    store: &dyn StateStore,
+    workload_id: &WorkloadId,
+) -> Result<Option<Vec<WorkloadRevision>>> {
+    match store.list_workload_revisions(workload_id).await {
+        Ok(revisions) => Ok(Some(revisions)),
+        Err(OrchestrationError::NotImplemented(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Record `workload`'s template as a new revision unless it matches the
+/// newest one. Returns the number of the revision it runs, or `None` if the
+/// store keeps no history.
+pub async fn record_revision(
+    store: &dyn StateStore,
+    workload: &WorkloadDefinition,
+) -> Result<Option<u64>> {
+    let Some(history) = revisions(store, &workload.id).await? else {
+        return Ok(None);
+    };
+    if let Some(latest) = history
+        .last()
+        .filter(|r| r.workload.same_template(workload))
+    {
+        return Ok(Some(latest.revision));
+    }
+
+    let revision = history.last().map_or(1, |r| r.revision + 1);
+    store
+        .put_workload_revision(WorkloadRevision {
+            workload_id: workload.id,
+            revision,
+            workload: workload.clone(),
+            created_at_ms: Utc::now().timestamp_millis() as u64,
+        })
+        .await?;
+    debug!("Workload {} is at revision {}", workload.name, revision);
+    Ok(Some(revision))
+}
+
+/// The revision to roll `workload` back to: revision `to`, or when absent
+/// the newest one whose template differs from the workload's.
+pub fn rollback_target<'a>(
+    workload: &WorkloadDefinition,
+    history: &'a [WorkloadRevision],
+    to: Option<u64>,
+) -> Option<&'a WorkloadRevision> {
+    match to {
+        Some(to) => history.iter().find(|r| r.revision == to),
+        None => history
+            .iter()
+            .rev()
+            .find(|r| !r.workload.same_template(workload)),
+    }
+}
+
+/// Trim every workload's history to the current revision and the `keep`
+/// newest before it, returning how many revisions were deleted.
+pub async fn prune_revisions(store: &dyn StateStore, keep: usize) -> Result<usize> {
+    let mut pruned = 0;
+    for workload in store.list_workloads().await? {
+        let Some(history) = revisions(store, &workload.id).await? else {
+            return Ok(0);
+        };
+        let excess = history.len().saturating_sub(keep + 1);
+        for revision in &history[..excess] {
+            store
+                .delete_workload_revision(&workload.id, revision.revision)
+                .await?;
+        }
+        pruned += excess;
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use orchestrator_shared_types::ContainerConfig;
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use uuid::Uuid;
+
+    fn workload(image: &str) -> WorkloadDefinition {
+        WorkloadDefinition {
+            id: Uuid::nil(),
+            containers: vec![ContainerConfig {
+                image: image.to_string(),
+                ..test_support::container("web")
+            }],
+            ..test_support::workload("web")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_template_changes_make_revisions() {
+        let store = InMemoryStateStore::new();
+        let mut web = workload("web:1");
+        store.put_workload(web.clone()).await.unwrap();
+        assert_eq!(record_revision(&store, &web).await.unwrap(), Some(1));
+
+        // Scaling is not a template change
+        web.replicas = 5;
+        assert_eq!(record_revision(&store, &web).await.unwrap(), Some(1));
+
+        web.containers[0].image = "web:2".to_string();
+        assert_eq!(record_revision(&store, &web).await.unwrap(), Some(2));
+
+        let history = revisions(&store, &web.id).await.unwrap().unwrap();
+        let target = rollback_target(&web, &history, None).unwrap();
+        assert_eq!(target.revision, 1);
+        let rolled_back = web.with_template_of(&target.workload);
+        assert_eq!(rolled_back.containers[0].image, "web:1");
+        assert_eq!(rolled_back.replicas, 5);
+
+        // The rollback is recorded as the newest revision
+        assert_eq!(
+            record_revision(&store, &rolled_back).await.unwrap(),
+            Some(3)
+        );
+        assert!(rollback_target(&web, &history, Some(9)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_prune_keeps_current_revision() {
+        let store = InMemoryStateStore::new();
+        let mut web = workload("web:0");
+        store.put_workload(web.clone()).await.unwrap();
+        for i in 1..=5 {
+            web.containers[0].image = format!("web:{}", i);
+            record_revision(&store, &web).await.unwrap();
+        }
+
+        assert_eq!(prune_revisions(&store, 2).await.unwrap(), 2);
+        let numbers: Vec<u64> = revisions(&store, &web.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .iter()
+            .map(|r| r.revision)
+            .collect();
+        assert_eq!(numbers, [3, 4, 5]);
+        assert_eq!(prune_revisions(&store, 0).await.unwrap(), 2);
+    }
+}
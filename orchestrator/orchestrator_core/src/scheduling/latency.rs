@@ -0,0 +1,253 @@
+//! Scheduling latency and replica drift as seen by the reconciler.
+//!
+//! A single scheduler call is fast even when a workload waits minutes for
+//! capacity, so the reconciler remembers in a [`PendingTracker`] when each
+//! workload fell short of replicas and how often placement was tried since.
+//! Every replica placed then reports its time to schedule and attempt count
+//! to a [`SchedulingObserver`].
+//!
+//! Drift is a level, not an event: a [`DriftTracker`] keeps each workload's
+//! current gap so the observer sees the cluster total, which stays put while
+//! a replica is stuck instead of growing with every reconciliation pass.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use orchestrator_shared_types::WorkloadId;
+
+/// Ways the observed replicas of a workload differ from its desired count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DriftKind {
+    MissingReplicas,
+    ExcessReplicas,
+}
+
+impl DriftKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DriftKind::MissingReplicas => "missing_replicas",
+            DriftKind::ExcessReplicas => "excess_replicas",
+        }
+    }
+}
+
+/// Receives scheduling and reconciliation measurements, e.g. to export them
+/// as metrics.
+pub trait SchedulingObserver: Send + Sync {
+    /// Called for each scheduler call, with how long it took.
+    fn on_attempt(&self, duration: Duration);
+
+    /// Called for each replica placed, with the time since its workload fell
+    /// short of replicas and the attempts made in that time.
+    fn on_placed(&self, time_to_schedule: Duration, attempts: u32);
+
+    /// Called for each replica that could not be placed.
+    fn on_failed(&self, reason: &str);
+
+    /// Called for each instance evicted to make room for another.
+    fn on_preempted(&self, reason: &str);
+
+    /// Called with the replicas currently missing or in excess across all
+    /// workloads, whenever that may have changed.
+    fn on_drift(&self, kind: DriftKind, replicas: u32);
+
+    /// Called after each reconciliation run, with how long it took.
+    fn on_reconciled(&self, duration: Duration);
+}
+
+#[cfg(feature = "observability")]
+impl SchedulingObserver for observability::OrchestratorMetrics {
+    fn on_attempt(&self, duration: Duration) {
+        self.inc_scheduling_attempts();
+        self.record_scheduling_duration(duration.as_secs_f64());
+    }
+
+    fn on_placed(&self, time_to_schedule: Duration, attempts: u32) {
+        self.inc_scheduling_successes();
+        self.record_time_to_schedule(time_to_schedule.as_secs_f64());
+        self.record_placement_attempts(attempts);
+    }
+
+    fn on_failed(&self, reason: &str) {
+        self.inc_scheduling_failures(reason);
+    }
+
+    fn on_preempted(&self, reason: &str) {
+        self.inc_scheduling_preemptions(reason);
+    }
+
+    fn on_drift(&self, kind: DriftKind, replicas: u32) {
+        self.set_reconciliation_drift(kind.as_str(), replicas.into());
+    }
+
+    fn on_reconciled(&self, duration: Duration) {
+        self.inc_reconciliation_runs();
+        self.record_reconciliation_duration(duration.as_secs_f64());
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    since: Instant,
+    attempts: u32,
+}
+
+/// Workloads short of replicas, with when they fell short and the placement
+/// attempts made since.
+#[derive(Debug, Default)]
+pub struct PendingTracker {
+    pending: Mutex<HashMap<WorkloadId, Pending>>,
+}
+
+impl PendingTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a placement attempt, starting the workload's clock on the first.
+    pub fn attempt(&self, workload_id: WorkloadId) {
+        let mut pending = self.pending.lock().unwrap();
+        let entry = pending.entry(workload_id).or_insert_with(|| Pending {
+            since: Instant::now(),
+            attempts: 0,
+        });
+        entry.attempts += 1;
+    }
+
+    /// Time to schedule and attempts of a replica just placed. Replicas still
+    /// missing keep waiting on the same clock.
+    pub fn placed(&self, workload_id: WorkloadId) -> Option<(Duration, u32)> {
+        let pending = self.pending.lock().unwrap();
+        pending
+            .get(&workload_id)
+            .map(|entry| (entry.since.elapsed(), entry.attempts))
+    }
+
+    /// Forget a workload that is no longer short of replicas.
+    pub fn satisfied(&self, workload_id: WorkloadId) {
+        self.pending.lock().unwrap().remove(&workload_id);
+    }
+
+    /// Forget workloads other than `workload_ids`, e.g. deleted ones.
+    pub fn retain(&self, workload_ids: &HashSet<WorkloadId>) {
+        self.pending
+            .lock()
+            .unwrap()
+            .retain(|workload_id, _| workload_ids.contains(workload_id));
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Replicas each workload is currently missing or has in excess.
+#[derive(Debug, Default)]
+pub struct DriftTracker {
+    drift: Mutex<HashMap<WorkloadId, (DriftKind, u32)>>,
+}
+
+impl DriftTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a workload's current drift; `None` once it has its desired
+    /// replicas.
+    pub fn record(&self, workload_id: WorkloadId, drift: Option<(DriftKind, u32)>) {
+        let mut tracked = self.drift.lock().unwrap();
+        match drift {
+            Some(drift) => tracked.insert(workload_id, drift),
+            None => tracked.remove(&workload_id),
+        };
+    }
+
+    /// Forget workloads other than `workload_ids`, e.g. deleted ones.
+    pub fn retain(&self, workload_ids: &HashSet<WorkloadId>) {
+        self.drift
+            .lock()
+            .unwrap()
+            .retain(|workload_id, _| workload_ids.contains(workload_id));
+    }
+
+    /// Replicas drifted across all workloads, for each kind.
+    pub fn totals(&self) -> [(DriftKind, u32); 2] {
+        let tracked = self.drift.lock().unwrap();
+        [DriftKind::MissingReplicas, DriftKind::ExcessReplicas].map(|kind| {
+            let replicas = tracked
+                .values()
+                .filter(|(drifted, _)| *drifted == kind)
+                .map(|(_, replicas)| replicas)
+                .sum();
+            (kind, replicas)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_pending_tracker() {
+        let tracker = PendingTracker::new();
+        let web = Uuid::new_v4();
+        let db = Uuid::new_v4();
+        assert_eq!(tracker.placed(web), None);
+
+        tracker.attempt(web);
+        tracker.attempt(web);
+        tracker.attempt(db);
+        let (waited, attempts) = tracker.placed(web).unwrap();
+        assert_eq!(attempts, 2);
+
+        // The clock keeps running for the replicas still missing
+        tracker.attempt(web);
+        let (waited_again, attempts) = tracker.placed(web).unwrap();
+        assert_eq!(attempts, 3);
+        assert!(waited_again >= waited);
+
+        tracker.satisfied(web);
+        assert_eq!(tracker.placed(web), None);
+        tracker.attempt(web);
+        assert_eq!(tracker.placed(web).unwrap().1, 1);
+
+        tracker.retain(&HashSet::from([web]));
+        assert_eq!(tracker.len(), 1);
+        assert_eq!(tracker.placed(db), None);
+    }
+    #[test]
+    fn test_drift_tracker() {
+        let tracker = DriftTracker::new();
+        let web = Uuid::new_v4();
+        let db = Uuid::new_v4();
+
+        tracker.record(web, Some((DriftKind::MissingReplicas, 2)));
+        tracker.record(db, Some((DriftKind::ExcessReplicas, 1)));
+        // A stuck replica is counted once however often it is seen
+        tracker.record(web, Some((DriftKind::MissingReplicas, 2)));
+        assert_eq!(
+            tracker.totals(),
+            [
+                (DriftKind::MissingReplicas, 2),
+                (DriftKind::ExcessReplicas, 1)
+            ]
+        );
+
+        tracker.record(web, None);
+        tracker.retain(&HashSet::from([web]));
+        assert_eq!(
+            tracker.totals(),
+            [
+                (DriftKind::MissingReplicas, 0),
+                (DriftKind::ExcessReplicas, 0)
+            ]
+        );
+    }
+}
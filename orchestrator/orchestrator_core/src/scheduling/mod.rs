@@ -0,0 +1,710 @@
+//! Pluggable scheduling strategies.
+//!
+//! The [`StrategyScheduler`] filters nodes on the workload's placement rules
+//! and on free resources, including the GPUs, MPS shares and MIG instances
+//! the workload's containers ask for, then ranks the survivors with a
+//! [`SchedulerStrategy`] plus any registered [`ScorePlugin`]s:
+//!
+//! ```text
+//! score = strategy + preferred affinity + Σ(plugin weight × plugin score)
+//! ```
+//!
+//! Each term is on a 0-100 scale. The cluster default strategy applies unless
+//! a workload names another in `placement.scheduling_strategy`. Built in are
+//! [`BinPackStrategy`], [`SpreadStrategy`] and [`RandomStrategy`]; custom
+//! strategies and plugins are registered on the scheduler without touching
+//! the placement loop.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let scheduler = StrategyScheduler::new(state_store)
+//!     .with_strategy(Arc::new(MyStrategy))
+//!     .with_plugin(Arc::new(PreferLabelledNodes), 0.5)
+//!     .with_default_strategy(BinPackStrategy::NAME)?;
+//! ```
+
+pub mod latency;
+pub mod preemption;
+pub mod strategies;
+
+pub use latency::{DriftKind, DriftTracker, PendingTracker, SchedulingObserver};
+pub use preemption::{select_victims, Preemption};
+pub use strategies::{BinPackStrategy, RandomStrategy, SpreadStrategy};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use orchestrator_shared_types::{
+    GpuPool, GpuRequest, Node, NodeId, NodeResources, OrchestrationError, Result,
+    WorkloadDefinition, WorkloadInstanceStatus,
+};
+use scheduler_interface::filter::{
+    AffinityFilter, Filter, Pod, RejectionReason, ResourceFeasibilityChecker,
+};
+use scheduler_interface::score::{AffinityScorer, Scorer, ScoringContext, WorkloadInstanceInfo};
+use scheduler_interface::{ScheduleDecision, ScheduleRequest, Scheduler};
+use state_store_interface::StateStore;
+
+/// A candidate node together with what is already running on it.
+#[derive(Debug, Clone)]
+pub struct NodeView<'a> {
+    pub node: &'a Node,
+    /// Resources requested by instances on the node, including replicas
+    /// placed earlier in the same scheduling pass.
+    pub requested: NodeResources,
+    pub instance_count: usize,
+    /// Instances of the workload being scheduled.
+    pub workload_instance_count: usize,
+}
+
+impl NodeView<'_> {
+    /// Mean CPU and memory utilization (0.0-1.0) of the node's allocatable
+    /// resources once `request` is added. Dimensions the node does not report
+    /// are ignored.
+    pub fn utilization_after(&self, request: &NodeResources) -> f64 {
+        let allocatable = &self.node.resources_allocatable;
+        let mut ratios = Vec::with_capacity(2);
+        if allocatable.cpu_cores > 0.0 {
+            ratios.push(
+                f64::from(self.requested.cpu_cores + request.cpu_cores)
+                    / f64::from(allocatable.cpu_cores),
+            );
+        }
+        if allocatable.memory_mb > 0 {
+            ratios.push(
+                (self.requested.memory_mb + request.memory_mb) as f64
+                    / allocatable.memory_mb as f64,
+            );
+        }
+        if ratios.is_empty() {
+            return 0.0;
+        }
+        (ratios.iter().sum::<f64>() / ratios.len() as f64).clamp(0.0, 1.0)
+    }
+
+    /// Allocatable resources not yet requested.
+    fn remaining(&self) -> NodeResources {
+        let allocatable = &self.node.resources_allocatable;
+        NodeResources {
+            cpu_cores: (allocatable.cpu_cores - self.requested.cpu_cores).max(0.0),
+            memory_mb: allocatable
+                .memory_mb
+                .saturating_sub(self.requested.memory_mb),
+            disk_mb: allocatable.disk_mb.saturating_sub(self.requested.disk_mb),
+        }
+    }
+}
+
+/// Ranks nodes that can host one more replica of a workload.
+pub trait SchedulerStrategy: Send + Sync {
+    /// Name workloads use to select the strategy.
+    fn name(&self) -> &str;
+
+    /// Scores a feasible node from 0 (worst) to 100 (best) for one replica
+    /// requesting `request`.
+    fn score(
+        &self,
+        workload: &WorkloadDefinition,
+        request: &NodeResources,
+        node: &NodeView<'_>,
+    ) -> f64;
+}
+
+/// An additional scoring term applied on top of every strategy.
+pub trait ScorePlugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Scores a feasible node from 0 (worst) to 100 (best).
+    fn score(&self, workload: &WorkloadDefinition, node: &NodeView<'_>) -> f64;
+}
+
+/// Scheduler that delegates node ranking to named strategies.
+pub struct StrategyScheduler {
+    state_store: Arc<dyn StateStore>,
+    strategies: HashMap<String, Arc<dyn SchedulerStrategy>>,
+    default_strategy: Arc<dyn SchedulerStrategy>,
+    plugins: Vec<(Arc<dyn ScorePlugin>, f64)>,
+}
+
+impl StrategyScheduler {
+    /// A scheduler with the built-in strategies, spreading by default.
+    pub fn new(state_store: Arc<dyn StateStore>) -> Self {
+        let default_strategy: Arc<dyn SchedulerStrategy> = Arc::new(SpreadStrategy);
+        let scheduler = Self {
+            state_store,
+            strategies: HashMap::new(),
+            default_strategy: default_strategy.clone(),
+            plugins: Vec::new(),
+        };
+        scheduler
+            .with_strategy(default_strategy)
+            .with_strategy(Arc::new(BinPackStrategy))
+            .with_strategy(Arc::new(RandomStrategy::new()))
+    }
+
+    /// Register a strategy, replacing any with the same name.
+    pub fn with_strategy(mut self, strategy: Arc<dyn SchedulerStrategy>) -> Self {
+        self.strategies
+            .insert(strategy.name().to_string(), strategy);
+        self
+    }
+
+    /// Make a registered strategy the cluster default.
+    pub fn with_default_strategy(mut self, name: &str) -> Result<Self> {
+        self.default_strategy = self.strategies.get(name).cloned().ok_or_else(|| {
+            OrchestrationError::ConfigError(format!(
+                "Unknown scheduling strategy '{}' (registered: {})",
+                name,
+                self.strategy_names().join(", ")
+            ))
+        })?;
+        Ok(self)
+    }
+
+    /// Add a scoring plugin whose 0-100 score is multiplied by `weight`.
+    pub fn with_plugin(mut self, plugin: Arc<dyn ScorePlugin>, weight: f64) -> Self {
+        self.plugins.push((plugin, weight));
+        self
+    }
+
+    /// Names of the registered strategies, sorted.
+    pub fn strategy_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.strategies.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub fn default_strategy(&self) -> &str {
+        self.default_strategy.name()
+    }
+
+    /// The strategy a workload is scheduled with. Unknown names fall back to the default.
+    pub fn strategy_for(&self, workload: &WorkloadDefinition) -> Arc<dyn SchedulerStrategy> {
+        match &workload.placement.scheduling_strategy {
+            Some(name) => self.strategies.get(name).cloned().unwrap_or_else(|| {
+                warn!(
+                    "Workload {} requests unknown scheduling strategy '{}'; using '{}'",
+                    workload.name,
+                    name,
+                    self.default_strategy.name()
+                );
+                self.default_strategy.clone()
+            }),
+            None => self.default_strategy.clone(),
+        }
+    }
+
+    /// What the instances on each node request, from the state store.
+    async fn node_usage(&self) -> Result<HashMap<NodeId, NodeUsage>> {
+        let workloads: HashMap<_, _> = self
+            .state_store
+            .list_workloads()
+            .await?
+            .into_iter()
+            .map(|w| {
+                // Invalid requests are rejected by the API, so none are running
+                let gpus = w.gpu_requests().unwrap_or_default();
+                (w.id, (Pod::from(&w).resources, gpus))
+            })
+            .collect();
+
+        let mut usage: HashMap<NodeId, NodeUsage> = HashMap::new();
+        for instance in self.state_store.list_all_instances().await? {
+            if matches!(
+                instance.status,
+                WorkloadInstanceStatus::Succeeded | WorkloadInstanceStatus::Failed
+            ) {
+                continue;
+            }
+            let entry = usage.entry(instance.node_id).or_default();
+            match workloads.get(&instance.workload_id) {
+                Some((resources, gpus)) => entry.add(resources, gpus),
+                None => entry.instances += 1,
+            }
+        }
+        Ok(usage)
+    }
+}
+
+/// What the instances on a node request.
+#[derive(Debug, Clone, Default)]
+struct NodeUsage {
+    requested: NodeResources,
+    instances: usize,
+    gpus: Vec<GpuRequest>,
+}
+
+impl NodeUsage {
+    fn add(&mut self, resources: &NodeResources, gpus: &[GpuRequest]) {
+        self.requested.cpu_cores += resources.cpu_cores;
+        self.requested.memory_mb += resources.memory_mb;
+        self.requested.disk_mb += resources.disk_mb;
+        self.instances += 1;
+        self.gpus.extend_from_slice(gpus);
+    }
+}
+
+/// Whether `requests` fit on the node's GPUs beside those of its instances.
+fn check_gpus(
+    node: &Node,
+    used: &[GpuRequest],
+    requests: &[GpuRequest],
+) -> std::result::Result<(), RejectionReason> {
+    if requests.is_empty() {
+        return Ok(());
+    }
+    let insufficient = |details: String| RejectionReason::InsufficientGpu { details };
+    let mut pool = GpuPool::new(
+        node.gpu_devices()
+            .map_err(|e| insufficient(e.to_string()))?,
+    );
+    // One at a time, so a request that no longer fits (the node's GPUs were
+    // repartitioned) does not hide the others
+    for request in used {
+        let _ = pool.allocate(std::slice::from_ref(request));
+    }
+    pool.allocate(requests).map(|_| ()).map_err(insufficient)
+}
+
+#[async_trait]
+impl Scheduler for StrategyScheduler {
+    async fn schedule(
+        &self,
+        request: &ScheduleRequest,
+        available_nodes: &[Node],
+    ) -> Result<Vec<ScheduleDecision>> {
+        let workload = &request.workload_definition;
+        let needed_replicas = workload
+            .replicas
+            .saturating_sub(request.current_instances.len() as u32);
+        if needed_replicas == 0 {
+            return Ok(Vec::new());
+        }
+
+        let strategy = self.strategy_for(workload);
+        let pod = Pod::from(workload.as_ref());
+        let gpus = workload.gpu_requests()?;
+        let checker = ResourceFeasibilityChecker::new();
+        let affinity_scorer = AffinityScorer::new();
+        let mut usage = self.node_usage().await?;
+        let mut placed: Vec<WorkloadInstanceInfo> = request
+            .current_instances
+            .iter()
+            .map(|instance| WorkloadInstanceInfo {
+                node_id: instance.node_id,
+                labels: workload.labels.clone(),
+                workload_name: workload.name.clone(),
+            })
+            .collect();
+
+        let mut decisions = Vec::with_capacity(needed_replicas as usize);
+        for _ in 0..needed_replicas {
+            let filtered = AffinityFilter::new(placed.clone()).filter(&pod, available_nodes);
+            let mut rejections: Vec<String> = filtered
+                .filtered_nodes
+                .iter()
+                .map(|(_, reason)| format!("{:?}", reason))
+                .collect();
+
+            let mut views = Vec::new();
+            for node in available_nodes
+                .iter()
+                .filter(|n| filtered.eligible_nodes.contains(&n.id))
+            {
+                let node_usage = usage.get(&node.id).cloned().unwrap_or_default();
+                let view = NodeView {
+                    node,
+                    requested: node_usage.requested,
+                    instance_count: node_usage.instances,
+                    workload_instance_count: placed.iter().filter(|p| p.node_id == node.id).count(),
+                };
+                let mut remaining = node.clone();
+                remaining.resources_allocatable = view.remaining();
+                match checker
+                    .check_feasibility(&pod, &remaining)
+                    .and_then(|()| check_gpus(node, &node_usage.gpus, &gpus))
+                {
+                    Ok(()) => views.push(view),
+                    Err(reason) => rejections.push(format!("{:?}", reason)),
+                }
+            }
+
+            if views.is_empty() {
+                decisions.push(ScheduleDecision::NoPlacement(
+                    if available_nodes.is_empty() {
+                        "No nodes available".to_string()
+                    } else {
+                        format!(
+                            "No node can host {}: {}",
+                            workload.name,
+                            rejections.join("; ")
+                        )
+                    },
+                ));
+                continue;
+            }
+
+            let candidates: Vec<NodeId> = views.iter().map(|v| v.node.id).collect();
+            let context = ScoringContext::new(workload.clone(), available_nodes.to_vec())
+                .with_instances(placed.clone());
+            let affinity = affinity_scorer.score(&candidates, &context);
+
+            // Ties go to the earliest node so placement is deterministic
+            let mut best: Option<(f64, &NodeView)> = None;
+            for view in &views {
+                let mut score = strategy.score(workload, &pod.resources, view)
+                    + affinity.score_for_node(&view.node.id).unwrap_or(0.0);
+                for (plugin, weight) in &self.plugins {
+                    score += weight * plugin.score(workload, view);
+                }
+                let better = match best {
+                    Some((top, _)) => score > top,
+                    None => true,
+                };
+                if better {
+                    best = Some((score, view));
+                }
+            }
+
+            let (_, chosen) = best.expect("views is not empty");
+            let node_id = chosen.node.id;
+            usage.entry(node_id).or_default().add(&pod.resources, &gpus);
+            placed.push(WorkloadInstanceInfo {
+                node_id,
+                labels: workload.labels.clone(),
+                workload_name: workload.name.clone(),
+            });
+            decisions.push(ScheduleDecision::AssignNode(node_id));
+        }
+        Ok(decisions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use orchestrator_shared_types::{
+        ContainerConfig, Keypair, NodeStatus, WorkloadInstance, NODE_GPU_DEVICES_LABEL,
+        NODE_GPU_LABEL,
+    };
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use uuid::Uuid;
+
+    fn node(cpu: f32, memory_mb: u64, labels: &[(&str, &str)]) -> Node {
+        let resources = NodeResources {
+            cpu_cores: cpu,
+            memory_mb,
+            disk_mb: 100_000,
+        };
+        Node {
+            id: Keypair::generate().public_key(),
+            address: "127.0.0.1:8080".to_string(),
+            status: NodeStatus::Ready,
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            resources_capacity: resources.clone(),
+            resources_allocatable: resources,
+            unschedulable: false,
+            resource_version: 0,
+        }
+    }
+
+    fn workload(name: &str, replicas: u32, cpu: f32, memory_mb: u64) -> WorkloadDefinition {
+        WorkloadDefinition {
+            containers: vec![ContainerConfig {
+                resource_requests: NodeResources {
+                    cpu_cores: cpu,
+                    memory_mb,
+                    disk_mb: 0,
+                },
+                ..test_support::container(name)
+            }],
+            replicas,
+            labels: HashMap::from([("app".to_string(), name.to_string())]),
+            ..test_support::workload(name)
+        }
+    }
+
+    /// A store where `busy` already runs one 2-core, 2 GiB instance.
+    async fn store_with_load(busy: &Node) -> Arc<InMemoryStateStore> {
+        let store = Arc::new(InMemoryStateStore::new());
+        let existing = workload("existing", 1, 2.0, 2048);
+        store.put_workload(existing.clone()).await.unwrap();
+        store
+            .put_instance(WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id: existing.id,
+                node_id: busy.id,
+                container_ids: vec![],
+                status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
+            })
+            .await
+            .unwrap();
+        store
+    }
+
+    fn request(workload: WorkloadDefinition) -> ScheduleRequest {
+        ScheduleRequest {
+            workload_definition: Arc::new(workload),
+            current_instances: vec![],
+        }
+    }
+
+    fn assigned(decisions: &[ScheduleDecision]) -> Vec<NodeId> {
+        decisions
+            .iter()
+            .filter_map(|d| match d {
+                ScheduleDecision::AssignNode(id) => Some(*id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_bin_pack_and_spread_choose_opposite_nodes() {
+        let idle = node(4.0, 4096, &[]);
+        let busy = node(4.0, 4096, &[]);
+        let nodes = vec![idle.clone(), busy.clone()];
+        let store = store_with_load(&busy).await;
+
+        let spread = StrategyScheduler::new(store.clone());
+        let decisions = spread
+            .schedule(&request(workload("web", 1, 1.0, 1024)), &nodes)
+            .await
+            .unwrap();
+        assert_eq!(assigned(&decisions), vec![idle.id]);
+
+        let bin_pack = StrategyScheduler::new(store)
+            .with_default_strategy(BinPackStrategy::NAME)
+            .unwrap();
+        let decisions = bin_pack
+            .schedule(&request(workload("web", 1, 1.0, 1024)), &nodes)
+            .await
+            .unwrap();
+        assert_eq!(assigned(&decisions), vec![busy.id]);
+    }
+
+    #[tokio::test]
+    async fn test_workload_overrides_cluster_strategy() {
+        let idle = node(4.0, 4096, &[]);
+        let busy = node(4.0, 4096, &[]);
+        let nodes = vec![idle.clone(), busy.clone()];
+        let scheduler = StrategyScheduler::new(store_with_load(&busy).await);
+        assert_eq!(scheduler.default_strategy(), SpreadStrategy::NAME);
+
+        let mut packed = workload("batch", 1, 1.0, 1024);
+        packed.placement.scheduling_strategy = Some(BinPackStrategy::NAME.to_string());
+        let decisions = scheduler.schedule(&request(packed), &nodes).await.unwrap();
+        assert_eq!(assigned(&decisions), vec![busy.id]);
+
+        // Unknown names fall back to the default
+        let mut unknown = workload("batch", 1, 1.0, 1024);
+        unknown.placement.scheduling_strategy = Some("nope".to_string());
+        let decisions = scheduler.schedule(&request(unknown), &nodes).await.unwrap();
+        assert_eq!(assigned(&decisions), vec![idle.id]);
+
+        assert!(StrategyScheduler::new(Arc::new(InMemoryStateStore::new()))
+            .with_default_strategy("nope")
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_capacity_is_tracked_within_a_pass() {
+        let small = node(2.0, 2048, &[]);
+        let nodes = vec![small.clone()];
+        let scheduler = StrategyScheduler::new(Arc::new(InMemoryStateStore::new()))
+            .with_default_strategy(BinPackStrategy::NAME)
+            .unwrap();
+
+        let decisions = scheduler
+            .schedule(&request(workload("web", 3, 1.0, 1024)), &nodes)
+            .await
+            .unwrap();
+        assert_eq!(assigned(&decisions), vec![small.id, small.id]);
+        assert!(matches!(decisions[2], ScheduleDecision::NoPlacement(_)));
+    }
+
+    #[tokio::test]
+    async fn test_plugins_and_custom_strategies() {
+        struct PreferGpu;
+
+        impl ScorePlugin for PreferGpu {
+            fn name(&self) -> &str {
+                "prefer-gpu"
+            }
+
+            fn score(&self, _workload: &WorkloadDefinition, node: &NodeView<'_>) -> f64 {
+                if node.node.labels.contains_key("gpu") {
+                    100.0
+                } else {
+                    0.0
+                }
+            }
+        }
+
+        struct LastNode;
+
+        impl SchedulerStrategy for LastNode {
+            fn name(&self) -> &str {
+                "last-node"
+            }
+
+            fn score(
+                &self,
+                _workload: &WorkloadDefinition,
+                _request: &NodeResources,
+                node: &NodeView<'_>,
+            ) -> f64 {
+                node.node.address.len() as f64
+            }
+        }
+
+        let plain = node(4.0, 4096, &[]);
+        let gpu = node(4.0, 4096, &[("gpu", "a100")]);
+        let nodes = vec![plain.clone(), gpu.clone()];
+        let store: Arc<dyn StateStore> = Arc::new(InMemoryStateStore::new());
+
+        let scheduler = StrategyScheduler::new(store.clone()).with_plugin(Arc::new(PreferGpu), 1.0);
+        let decisions = scheduler
+            .schedule(&request(workload("train", 1, 1.0, 1024)), &nodes)
+            .await
+            .unwrap();
+        assert_eq!(assigned(&decisions), vec![gpu.id]);
+
+        let mut far = node(4.0, 4096, &[]);
+        far.address = "10.100.100.100:8080".to_string();
+        let scheduler = StrategyScheduler::new(store)
+            .with_strategy(Arc::new(LastNode))
+            .with_default_strategy("last-node")
+            .unwrap();
+        assert!(scheduler
+            .strategy_names()
+            .contains(&"last-node".to_string()));
+        let decisions = scheduler
+            .schedule(
+                &request(workload("web", 1, 1.0, 1024)),
+                &[plain, far.clone()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(assigned(&decisions), vec![far.id]);
+    }
+
+    #[tokio::test]
+    async fn test_random_strategy_only_picks_feasible_nodes() {
+        let full = node(1.0, 1024, &[]);
+        let open = node(8.0, 8192, &[]);
+        let nodes = vec![full.clone(), open.clone()];
+        let scheduler = StrategyScheduler::new(Arc::new(InMemoryStateStore::new()))
+            .with_strategy(Arc::new(RandomStrategy::with_seed(7)))
+            .with_default_strategy(RandomStrategy::NAME)
+            .unwrap();
+
+        let decisions = scheduler
+            .schedule(&request(workload("web", 4, 2.0, 512)), &nodes)
+            .await
+            .unwrap();
+        assert_eq!(assigned(&decisions), vec![open.id; 4]);
+    }
+
+    fn gpu_workload(name: &str, replicas: u32, gpu: &str) -> WorkloadDefinition {
+        let mut workload = workload(name, replicas, 0.5, 256);
+        workload.containers[0]
+            .annotations
+            .insert(GpuRequest::ANNOTATION.to_string(), gpu.to_string());
+        workload
+    }
+
+    fn no_placement(decision: &ScheduleDecision) -> &str {
+        match decision {
+            ScheduleDecision::NoPlacement(reason) => reason,
+            other => panic!("expected no placement, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gpu_shares_and_devices() {
+        let cpu_only = node(8.0, 8192, &[]);
+        let shared = node(8.0, 8192, &[(NODE_GPU_DEVICES_LABEL, "exclusive,mps")]);
+        let nodes = vec![cpu_only.clone(), shared.clone()];
+        let scheduler = StrategyScheduler::new(Arc::new(InMemoryStateStore::new()));
+
+        // Two 40% shares fit on the MPS GPU, a third does not
+        let decisions = scheduler
+            .schedule(&request(gpu_workload("infer", 3, "0.4")), &nodes)
+            .await
+            .unwrap();
+        assert_eq!(assigned(&decisions), vec![shared.id, shared.id]);
+        assert!(no_placement(&decisions[2]).contains("no MPS GPU has it free"));
+
+        // Only one GPU is exclusive; nodes counting GPUs have exclusive ones
+        let counted = node(8.0, 8192, &[(NODE_GPU_LABEL, "2")]);
+        let decisions = scheduler
+            .schedule(
+                &request(gpu_workload("train", 2, "2")),
+                &[shared.clone(), counted.clone()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(assigned(&decisions), vec![counted.id]);
+        assert!(
+            no_placement(&decisions[1]).contains("2 GPUs requested, 1 of 1 exclusive GPUs free")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mig_profiles_count_running_instances() {
+        let mig = node(
+            8.0,
+            8192,
+            &[(NODE_GPU_DEVICES_LABEL, "mig:1g.10gb+1g.10gb+2g.20gb")],
+        );
+        let store = Arc::new(InMemoryStateStore::new());
+        let existing = gpu_workload("existing", 1, "mig-1g.10gb");
+        store.put_workload(existing.clone()).await.unwrap();
+        store
+            .put_instance(WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id: existing.id,
+                node_id: mig.id,
+                container_ids: vec![],
+                status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
+            })
+            .await
+            .unwrap();
+        let scheduler = StrategyScheduler::new(store);
+
+        let decisions = scheduler
+            .schedule(
+                &request(gpu_workload("infer", 2, "mig-1g.10gb")),
+                std::slice::from_ref(&mig),
+            )
+            .await
+            .unwrap();
+        assert_eq!(assigned(&decisions), vec![mig.id]);
+        let reason = no_placement(&decisions[1]);
+        assert!(reason.contains("InsufficientGpu"));
+        assert!(reason.contains("MIG profile 1g.10gb requested, all 2 instances in use"));
+
+        let decisions = scheduler
+            .schedule(&request(gpu_workload("big", 1, "mig-7g.80gb")), &[mig])
+            .await
+            .unwrap();
+        assert!(no_placement(&decisions[0]).contains("no GPU offers it"));
+    }
+}
@@ -0,0 +1,254 @@
+//! Priority preemption.
+//!
+//! A replica that fits on no node may take the place of instances whose
+//! workloads have a lower `placement.priority`. [`select_victims`] finds the
+//! node where the fewest, lowest-priority instances have to go; the
+//! reconciler evicts them and places the replica there.
+
+use std::collections::HashMap;
+
+use orchestrator_shared_types::{
+    GpuRequest, Node, NodeId, NodeResources, WorkloadDefinition, WorkloadInstance,
+    WorkloadInstanceStatus,
+};
+use scheduler_interface::filter::{AffinityFilter, Filter, Pod, ResourceFeasibilityChecker};
+use scheduler_interface::score::WorkloadInstanceInfo;
+
+use super::{check_gpus, NodeUsage, NodeView};
+
+/// Instances to evict from a node so a replica fits on it.
+#[derive(Debug, Clone)]
+pub struct Preemption {
+    pub node_id: NodeId,
+    /// Lowest priority first.
+    pub victims: Vec<WorkloadInstance>,
+}
+
+/// An instance on a node with what its workload requests.
+struct Resident<'a> {
+    instance: &'a WorkloadInstance,
+    priority: i32,
+    resources: NodeResources,
+    gpus: Vec<GpuRequest>,
+}
+
+/// Where one replica of `workload` fits once lower-priority instances are
+/// evicted, given every workload and instance in the cluster.
+///
+/// Nodes the replica already fits on, or that its placement rules exclude,
+/// are skipped. Among the rest the node whose highest-priority victim is
+/// lowest wins, then the one with the fewest victims. Returns `None` when no
+/// node can be freed up.
+pub fn select_victims(
+    workload: &WorkloadDefinition,
+    nodes: &[Node],
+    workloads: &[WorkloadDefinition],
+    instances: &[WorkloadInstance],
+) -> Option<Preemption> {
+    // Invalid requests are rejected by the API, so none are scheduled
+    let gpus = workload.gpu_requests().ok()?;
+    let pod = Pod::from(workload);
+    let priority = workload.placement.priority;
+    let workloads: HashMap<_, _> = workloads.iter().map(|w| (w.id, w)).collect();
+    let placed: Vec<WorkloadInstanceInfo> = instances
+        .iter()
+        .filter(|i| i.workload_id == workload.id)
+        .map(|i| WorkloadInstanceInfo {
+            node_id: i.node_id,
+            labels: workload.labels.clone(),
+            workload_name: workload.name.clone(),
+        })
+        .collect();
+    let eligible = AffinityFilter::new(placed)
+        .filter(&pod, nodes)
+        .eligible_nodes;
+    let checker = ResourceFeasibilityChecker::new();
+
+    let fits = |node: &Node, residents: &[&Resident]| {
+        let mut usage = NodeUsage::default();
+        for resident in residents {
+            usage.add(&resident.resources, &resident.gpus);
+        }
+        let view = NodeView {
+            node,
+            requested: usage.requested,
+            instance_count: usage.instances,
+            workload_instance_count: 0,
+        };
+        let mut remaining = node.clone();
+        remaining.resources_allocatable = view.remaining();
+        checker.check_feasibility(&pod, &remaining).is_ok()
+            && check_gpus(node, &usage.gpus, &gpus).is_ok()
+    };
+
+    let mut best: Option<((i32, usize), Preemption)> = None;
+    for node in nodes
+        .iter()
+        .filter(|n| n.is_schedulable() && eligible.contains(&n.id))
+    {
+        let residents: Vec<Resident> = instances
+            .iter()
+            .filter(|i| i.node_id == node.id)
+            .filter(|i| {
+                !matches!(
+                    i.status,
+                    WorkloadInstanceStatus::Succeeded | WorkloadInstanceStatus::Failed
+                )
+            })
+            .filter_map(|instance| {
+                let owner = workloads.get(&instance.workload_id)?;
+                Some(Resident {
+                    instance,
+                    priority: owner.placement.priority,
+                    resources: Pod::from(*owner).resources,
+                    gpus: owner.gpu_requests().unwrap_or_default(),
+                })
+            })
+            .collect();
+        let mut kept: Vec<&Resident> = residents.iter().collect();
+        if fits(node, &kept) {
+            continue;
+        }
+
+        // Evict the lowest priorities first until the replica fits
+        let mut candidates: Vec<&Resident> =
+            residents.iter().filter(|r| r.priority < priority).collect();
+        candidates.sort_by_key(|r| (r.priority, r.instance.id));
+        let mut victims = Vec::new();
+        for candidate in candidates {
+            kept.retain(|r| r.instance.id != candidate.instance.id);
+            victims.push(candidate);
+            if fits(node, &kept) {
+                break;
+            }
+        }
+        if victims.is_empty() || !fits(node, &kept) {
+            continue;
+        }
+
+        let cost = (
+            victims.iter().map(|v| v.priority).max().unwrap_or(i32::MIN),
+            victims.len(),
+        );
+        // Ties go to the earliest node so preemption is deterministic
+        if best.as_ref().is_none_or(|(top, _)| cost < *top) {
+            best = Some((
+                cost,
+                Preemption {
+                    node_id: node.id,
+                    victims: victims.iter().map(|v| v.instance.clone()).collect(),
+                },
+            ));
+        }
+    }
+    best.map(|(_, preemption)| preemption)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use orchestrator_shared_types::{ContainerConfig, Keypair, NodeStatus, Placement, WorkloadId};
+    use uuid::Uuid;
+
+    fn node(cpu: f32) -> Node {
+        let resources = NodeResources {
+            cpu_cores: cpu,
+            memory_mb: 8192,
+            disk_mb: 100_000,
+        };
+        Node {
+            id: Keypair::generate().public_key(),
+            address: "127.0.0.1:8080".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            resources_capacity: resources.clone(),
+            resources_allocatable: resources,
+            unschedulable: false,
+            resource_version: 0,
+        }
+    }
+
+    fn workload(name: &str, cpu: f32, priority: i32) -> WorkloadDefinition {
+        WorkloadDefinition {
+            containers: vec![ContainerConfig {
+                resource_requests: NodeResources {
+                    cpu_cores: cpu,
+                    memory_mb: 0,
+                    disk_mb: 0,
+                },
+                ..test_support::container(name)
+            }],
+            labels: HashMap::from([("app".to_string(), name.to_string())]),
+            placement: Placement {
+                priority,
+                ..Default::default()
+            },
+            ..test_support::workload(name)
+        }
+    }
+
+    fn instance(workload_id: WorkloadId, node: &Node) -> WorkloadInstance {
+        WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id,
+            node_id: node.id,
+            container_ids: vec![],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        }
+    }
+
+    #[test]
+    fn test_evicts_lowest_priority_instances() {
+        let full = node(4.0);
+        let batch = workload("batch", 2.0, -10);
+        let web = workload("web", 2.0, 0);
+        let critical = workload("critical", 2.0, 100);
+        let instances = vec![instance(web.id, &full), instance(batch.id, &full)];
+        let workloads = vec![batch.clone(), web.clone(), critical.clone()];
+
+        let preemption = select_victims(
+            &critical,
+            std::slice::from_ref(&full),
+            &workloads,
+            &instances,
+        )
+        .unwrap();
+        assert_eq!(preemption.node_id, full.id);
+        assert_eq!(preemption.victims.len(), 1);
+        assert_eq!(preemption.victims[0].workload_id, batch.id);
+
+        // Equal priorities are never preempted
+        assert!(select_victims(&batch, &[full], &workloads, &instances).is_none());
+    }
+
+    #[test]
+    fn test_prefers_node_with_lower_priority_victims() {
+        let first = node(2.0);
+        let second = node(2.0);
+        let batch = workload("batch", 2.0, -10);
+        let web = workload("web", 2.0, 0);
+        let critical = workload("critical", 2.0, 100);
+        let instances = vec![instance(web.id, &first), instance(batch.id, &second)];
+        let workloads = vec![batch, web, critical.clone()];
+
+        let nodes = [first, second.clone()];
+        let preemption = select_victims(&critical, &nodes, &workloads, &instances).unwrap();
+        assert_eq!(preemption.node_id, second.id);
+    }
+
+    #[test]
+    fn test_no_preemption_when_evictions_cannot_make_room() {
+        let small = node(2.0);
+        let batch = workload("batch", 1.0, -10);
+        let huge = workload("huge", 4.0, 100);
+        let instances = vec![instance(batch.id, &small)];
+        let workloads = vec![batch, huge.clone()];
+
+        assert!(select_victims(&huge, &[small], &workloads, &instances).is_none());
+    }
+}
@@ -0,0 +1,111 @@
+//! Built-in scheduling strategies.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use uuid::Uuid;
+
+use orchestrator_shared_types::{NodeResources, WorkloadDefinition};
+
+use super::{NodeView, SchedulerStrategy};
+
+/// Fills the busiest node that still fits, keeping others free for large
+/// workloads or for scale-down.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BinPackStrategy;
+
+impl BinPackStrategy {
+    pub const NAME: &'static str = "bin-pack";
+}
+
+impl SchedulerStrategy for BinPackStrategy {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn score(
+        &self,
+        _workload: &WorkloadDefinition,
+        request: &NodeResources,
+        node: &NodeView<'_>,
+    ) -> f64 {
+        node.utilization_after(request) * 100.0
+    }
+}
+
+/// Prefers the least loaded node, then the one running the fewest replicas
+/// of the workload.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpreadStrategy;
+
+impl SpreadStrategy {
+    pub const NAME: &'static str = "spread";
+}
+
+impl SchedulerStrategy for SpreadStrategy {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn score(
+        &self,
+        _workload: &WorkloadDefinition,
+        request: &NodeResources,
+        node: &NodeView<'_>,
+    ) -> f64 {
+        let headroom = 1.0 - node.utilization_after(request);
+        let replicas = 1.0 / (1.0 + node.workload_instance_count as f64);
+        (headroom + replicas) * 50.0
+    }
+}
+
+/// Picks uniformly among feasible nodes.
+#[derive(Debug)]
+pub struct RandomStrategy {
+    state: AtomicU64,
+}
+
+impl RandomStrategy {
+    pub const NAME: &'static str = "random";
+
+    pub fn new() -> Self {
+        Self::with_seed(Uuid::new_v4().as_u64_pair().0)
+    }
+
+    /// A strategy producing the same sequence of picks for the same seed.
+    pub fn with_seed(seed: u64) -> Self {
+        // xorshift never leaves zero
+        Self {
+            state: AtomicU64::new(seed.max(1)),
+        }
+    }
+
+    fn next(&self) -> u64 {
+        let mut x = self.state.load(Ordering::Relaxed);
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state.store(x, Ordering::Relaxed);
+        x
+    }
+}
+
+impl Default for RandomStrategy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchedulerStrategy for RandomStrategy {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn score(
+        &self,
+        _workload: &WorkloadDefinition,
+        _request: &NodeResources,
+        _node: &NodeView<'_>,
+    ) -> f64 {
+        (self.next() % 10_000) as f64 / 100.0
+    }
+}
@@ -0,0 +1,205 @@
+//! Fixtures shared by the crate's unit tests.
+//!
+//! Tests start from [`workload`] and [`container`] and change only the
+//! fields they care about, so adding a field to the shared types touches one
+//! literal. [`StubRuntime`] stands in for a container runtime wherever a test
+//! needs one that runs nothing.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use container_runtime_interface::{
+    ContainerRuntime, ContainerStatus, ContainerUsage, CreateContainerOptions, PullPriority,
+};
+use orchestrator_shared_types::{
+    ContainerConfig, ContainerId, NodeId, OrchestrationError, Result, WorkloadDefinition,
+    DEFAULT_NAMESPACE,
+};
+
+/// A container `name` running the image `{name}:latest`, requesting nothing.
+pub(crate) fn container(name: &str) -> ContainerConfig {
+    ContainerConfig {
+        name: name.to_string(),
+        image: format!("{}:latest", name),
+        command: None,
+        args: None,
+        env_vars: HashMap::new(),
+        ports: vec![],
+        resource_requests: Default::default(),
+        volume_mounts: vec![],
+        termination_grace_period_seconds: None,
+        pre_stop: None,
+        readiness_probe: None,
+        limits: Default::default(),
+        sysctls: HashMap::new(),
+        rlimits: vec![],
+        shm_size_mb: None,
+        hostname: None,
+        host_aliases: vec![],
+        dns: Default::default(),
+        annotations: Default::default(),
+    }
+}
+
+/// A workload `name` in the default namespace running one replica of
+/// [`container(name)`](container).
+pub(crate) fn workload(name: &str) -> WorkloadDefinition {
+    WorkloadDefinition {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        containers: vec![container(name)],
+        init_containers: vec![],
+        sidecars: vec![],
+        replicas: 1,
+        labels: HashMap::new(),
+        placement: Default::default(),
+        restart_policy: Default::default(),
+        namespace: DEFAULT_NAMESPACE.to_string(),
+        resource_version: 0,
+    }
+}
+
+/// A container runtime that runs nothing and records what it was asked to do.
+///
+/// Containers report running, or exited once built with
+/// [`exited`](Self::exited). Only the containers a test put in
+/// [`containers`](Self::containers) are listed, and only those given usage are
+/// measured. Every image pulls, except those made missing.
+#[derive(Default)]
+pub(crate) struct StubRuntime {
+    /// Configs of the containers created, oldest first.
+    pub created: Mutex<Vec<ContainerConfig>>,
+    /// Containers removed.
+    pub removed: AtomicUsize,
+    /// Images pulled, with their priority.
+    pub pulls: Mutex<Vec<(String, PullPriority)>>,
+    /// Containers listed on each node; removed ones are dropped.
+    pub containers: Mutex<HashMap<NodeId, Vec<ContainerId>>>,
+    exit_code: Option<i32>,
+    usage: HashMap<ContainerId, ContainerUsage>,
+    missing_images: HashSet<String>,
+    log_dir: Option<PathBuf>,
+}
+
+impl StubRuntime {
+    /// Every container reports it exited with `exit_code`.
+    pub fn exited(exit_code: i32) -> Self {
+        Self {
+            exit_code: Some(exit_code),
+            ..Default::default()
+        }
+    }
+
+    /// Report `usage` for `container_id`.
+    pub fn with_usage(mut self, container_id: &str, usage: ContainerUsage) -> Self {
+        self.usage.insert(container_id.to_string(), usage);
+        self
+    }
+
+    /// Fail pulls of `image` as the registry would.
+    pub fn with_missing_image(mut self, image: &str) -> Self {
+        self.missing_images.insert(image.to_string());
+        self
+    }
+
+    /// Serve each container's log from `{dir}/{container_id}.log`.
+    #[cfg(feature = "log-forwarding")]
+    pub fn with_log_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.log_dir = Some(dir.into());
+        self
+    }
+
+    fn status(&self, container_id: &ContainerId) -> ContainerStatus {
+        ContainerStatus {
+            id: container_id.clone(),
+            state: match self.exit_code {
+                Some(_) => "exited",
+                None => "running",
+            }
+            .to_string(),
+            exit_code: self.exit_code,
+            error_message: None,
+            oom_killed: false,
+        }
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for StubRuntime {
+    async fn init_node(&self, _node_id: NodeId) -> Result<()> {
+        Ok(())
+    }
+
+    async fn create_container(
+        &self,
+        config: &ContainerConfig,
+        _options: &CreateContainerOptions,
+    ) -> Result<ContainerId> {
+        self.created.lock().unwrap().push(config.clone());
+        Ok(Uuid::new_v4().to_string())
+    }
+
+    async fn stop_container(&self, _container_id: &ContainerId) -> Result<()> {
+        Ok(())
+    }
+
+    async fn remove_container(&self, container_id: &ContainerId) -> Result<()> {
+        self.removed.fetch_add(1, Ordering::SeqCst);
+        for ids in self.containers.lock().unwrap().values_mut() {
+            ids.retain(|id| id != container_id);
+        }
+        Ok(())
+    }
+
+    async fn get_container_status(&self, container_id: &ContainerId) -> Result<ContainerStatus> {
+        Ok(self.status(container_id))
+    }
+
+    async fn list_containers(&self, node_id: NodeId) -> Result<Vec<ContainerStatus>> {
+        let containers = self.containers.lock().unwrap();
+        Ok(containers
+            .get(&node_id)
+            .into_iter()
+            .flatten()
+            .map(|id| self.status(id))
+            .collect())
+    }
+
+    async fn container_log_path(&self, container_id: &ContainerId) -> Result<PathBuf> {
+        match &self.log_dir {
+            Some(dir) => Ok(dir.join(format!("{}.log", container_id))),
+            None => Err(OrchestrationError::RuntimeError(
+                "Log files not supported by this runtime".to_string(),
+            )),
+        }
+    }
+
+    async fn container_usage(&self, container_id: &ContainerId) -> Result<ContainerUsage> {
+        self.usage.get(container_id).cloned().ok_or_else(|| {
+            OrchestrationError::RuntimeError(format!("no container {}", container_id))
+        })
+    }
+
+    async fn pull_image(
+        &self,
+        _node_id: NodeId,
+        image: &str,
+        priority: PullPriority,
+    ) -> Result<()> {
+        self.pulls
+            .lock()
+            .unwrap()
+            .push((image.to_string(), priority));
+        if self.missing_images.contains(image) {
+            return Err(OrchestrationError::RuntimeError(
+                "manifest unknown".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+//! Per-container resource usage.
+//!
+//! Every node runs a [`UsageScraper`] that reads the usage of its running
+//! instances' containers from the runtime on an interval. Each reading is a
+//! [`ContainerSample`] labeled with the container's namespace, workload,
+//! instance and node, handed to [`UsageObserver`]s such as the Prometheus
+//! metrics or the [`AnomalyDetector`](crate::anomaly::AnomalyDetector).
+//! Containers the runtime cannot measure are skipped. Scrapers can also count
+//! the lines each container logged since the previous scrape that match an
+//! error pattern.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use container_runtime_interface::{ContainerRuntime, ContainerUsage, LogMatcher, LogSearchOptions};
+use orchestrator_shared_types::{
+    NodeId, Result, WorkloadDefinition, WorkloadId, WorkloadInstanceStatus,
+};
+use state_store_interface::StateStore;
+
+/// Resource usage of one container of an instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerSample {
+    pub namespace: String,
+    /// Name of the instance's workload.
+    pub workload: String,
+    pub instance_id: Uuid,
+    pub container: String,
+    pub node_id: NodeId,
+    pub usage: ContainerUsage,
+    pub restarts: u32,
+    /// Error lines logged since the previous scrape, when the scraper counts
+    /// them.
+    pub log_errors: Option<u64>,
+}
+
+/// Receives container samples, e.g. to export them as metrics.
+pub trait UsageObserver: Send + Sync {
+    fn on_sample(&self, sample: &ContainerSample);
+}
+
+#[cfg(feature = "observability")]
+impl UsageObserver for observability::OrchestratorMetrics {
+    fn on_sample(&self, sample: &ContainerSample) {
+        self.record_container_usage(&observability::ContainerUsageSample {
+            namespace: sample.namespace.clone(),
+            workload: sample.workload.clone(),
+            instance: sample.instance_id.to_string(),
+            container: sample.container.clone(),
+            node: sample.node_id.to_string(),
+            cpu_seconds: sample.usage.cpu_usage_ns as f64 / 1e9,
+            memory_working_set_bytes: sample.usage.memory_working_set_bytes,
+            restarts: sample.restarts as u64,
+            oom_kills: sample.usage.oom_kills,
+        });
+    }
+}
+
+/// Periodically samples the containers running on one node.
+pub struct UsageScraper {
+    state_store: Arc<dyn StateStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    node_id: NodeId,
+    observers: Vec<Arc<dyn UsageObserver>>,
+    log_errors: Option<LogSearchOptions>,
+    // RFC3339 start of the previous scrape
+    last_scrape: Mutex<Option<String>>,
+}
+
+impl UsageScraper {
+    /// How often [`spawn`](Self::spawn) scrapes when run with defaults.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(15);
+
+    pub fn new(
+        state_store: Arc<dyn StateStore>,
+        runtime: Arc<dyn ContainerRuntime>,
+        node_id: NodeId,
+    ) -> Self {
+        Self {
+            state_store,
+            runtime,
+            node_id,
+            observers: Vec::new(),
+            log_errors: None,
+            last_scrape: Mutex::new(None),
+        }
+    }
+
+    /// Report every sample to `observer`, after those added before.
+    pub fn with_observer(mut self, observer: Arc<dyn UsageObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Count log lines matching the regex `pattern` as errors.
+    pub fn with_log_errors(mut self, pattern: &str) -> Result<Self> {
+        let search = LogSearchOptions {
+            query: pattern.to_string(),
+            regex: true,
+            since: None,
+        };
+        LogMatcher::new(&search)?;
+        self.log_errors = Some(search);
+        Ok(self)
+    }
+
+    /// Sample every container of the node's running instances once.
+    pub async fn scrape(&self) -> Result<Vec<ContainerSample>> {
+        let since = self
+            .last_scrape
+            .lock()
+            .unwrap()
+            .replace(Utc::now().to_rfc3339());
+        let instances = self.state_store.list_all_instances().await?;
+        let mut workloads: HashMap<WorkloadId, Option<WorkloadDefinition>> = HashMap::new();
+        let mut samples = Vec::new();
+
+        for instance in instances
+            .iter()
+            .filter(|i| i.node_id == self.node_id && i.status == WorkloadInstanceStatus::Running)
+        {
+            let workload = match workloads.get(&instance.workload_id) {
+                Some(workload) => workload.clone(),
+                None => {
+                    let workload = self.state_store.get_workload(&instance.workload_id).await?;
+                    workloads.insert(instance.workload_id, workload.clone());
+                    workload
+                }
+            };
+            let Some(workload) = workload else {
+                continue;
+            };
+
+            // Instances list sidecars before main containers
+            let containers = workload
+                .sidecars
+                .iter()
+                .chain(&workload.containers)
+                .zip(&instance.container_ids);
+            for (container, container_id) in containers {
+                let usage = match self.runtime.container_usage(container_id).await {
+                    Ok(usage) => usage,
+                    Err(e) => {
+                        debug!("No usage for container {}: {:?}", container_id, e);
+                        continue;
+                    }
+                };
+                let log_errors = match (&self.log_errors, &since) {
+                    (Some(search), Some(since)) => {
+                        let search = LogSearchOptions {
+                            since: Some(since.clone()),
+                            ..search.clone()
+                        };
+                        self.runtime
+                            .search_container_logs(container_id, &search)
+                            .await
+                            .map(|matches| matches.len() as u64)
+                            .ok()
+                    }
+                    _ => None,
+                };
+                let sample = ContainerSample {
+                    namespace: instance.namespace.clone(),
+                    workload: workload.name.clone(),
+                    instance_id: instance.id,
+                    container: container.name.clone(),
+                    node_id: self.node_id,
+                    usage,
+                    restarts: instance
+                        .restart_status(&container.name)
+                        .map_or(0, |r| r.restart_count),
+                    log_errors,
+                };
+                for observer in &self.observers {
+                    observer.on_sample(&sample);
+                }
+                samples.push(sample);
+            }
+        }
+        Ok(samples)
+    }
+
+    /// Scrape every `interval` until the task is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.scrape().await {
+                    error!("Container usage scrape failed: {:?}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, container, StubRuntime};
+    use orchestrator_shared_types::{ContainerRestartStatus, Keypair, WorkloadInstance};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        samples: Mutex<Vec<String>>,
+    }
+
+    impl UsageObserver for RecordingObserver {
+        fn on_sample(&self, sample: &ContainerSample) {
+            self.samples.lock().unwrap().push(sample.container.clone());
+        }
+    }
+
+    fn instance(
+        workload: &WorkloadDefinition,
+        node_id: NodeId,
+        status: WorkloadInstanceStatus,
+        container_ids: &[&str],
+    ) -> WorkloadInstance {
+        WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: workload.id,
+            node_id,
+            container_ids: container_ids.iter().map(|id| id.to_string()).collect(),
+            status,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: workload.namespace.clone(),
+            resource_version: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scrape_samples_running_containers_on_node() {
+        let store = Arc::new(InMemoryStateStore::new());
+        let node_id = Keypair::generate().public_key();
+        let workload = WorkloadDefinition {
+            containers: vec![container("web"), container("worker")],
+            sidecars: vec![container("proxy")],
+            namespace: "shop".to_string(),
+            ..test_support::workload("web")
+        };
+        store.put_workload(workload.clone()).await.unwrap();
+
+        let mut running = instance(
+            &workload,
+            node_id,
+            WorkloadInstanceStatus::Running,
+            &["c-proxy", "c-web", "gone"],
+        );
+        let mut restarts = ContainerRestartStatus::new("web");
+        restarts.restart_count = 3;
+        running.restarts.push(restarts);
+        store.put_instance(running.clone()).await.unwrap();
+        for (node, status) in [
+            (node_id, WorkloadInstanceStatus::Pending),
+            (
+                Keypair::generate().public_key(),
+                WorkloadInstanceStatus::Running,
+            ),
+        ] {
+            let other = instance(&workload, node, status, &["c-other"]);
+            store.put_instance(other).await.unwrap();
+        }
+
+        let usage = ContainerUsage {
+            cpu_usage_ns: 2_000_000_000,
+            memory_working_set_bytes: 1024,
+            oom_kills: 1,
+        };
+        let runtime = StubRuntime::default()
+            .with_usage("c-proxy", usage.clone())
+            .with_usage("c-web", usage.clone())
+            .with_usage("c-other", usage);
+        let observer = Arc::new(RecordingObserver::default());
+        let scraper =
+            UsageScraper::new(store, Arc::new(runtime), node_id).with_observer(observer.clone());
+        let samples = scraper.scrape().await.unwrap();
+
+        assert_eq!(*observer.samples.lock().unwrap(), ["proxy", "web"]);
+        let web = &samples[1];
+        assert_eq!(web.namespace, "shop");
+        assert_eq!(web.workload, "web");
+        assert_eq!(web.instance_id, running.id);
+        assert_eq!(web.restarts, 3);
+        assert_eq!(web.usage.oom_kills, 1);
+        assert_eq!(samples[0].restarts, 0);
+    }
+}
@@ -39,7 +39,7 @@ use orchestrator_shared_types::{
 use orchestrator_core::api::{
     build_router, ApiState, AuthConfig,
     handlers::{
-        ListResponse, NodeResponse, WorkloadResponse, ClusterStatusResponse,
+        ListResponse, NodeResponse, WorkloadResponse, ClusterStatusResponse, ScaleResponse,
     },
 };
 
@@ -49,9 +49,18 @@ use orchestrator_core::api::{
 
 #[cfg(feature = "rest-api")]
 mod mock {
+    use std::collections::HashMap;
+
     use async_trait::async_trait;
     use cluster_manager_interface::{ClusterEvent, ClusterManager};
-    use orchestrator_shared_types::{Node, NodeId, Result};
+    use container_runtime_interface::{
+        Checkpoint, CheckpointOptions, ContainerRuntime, ContainerStatus, CreateContainerOptions,
+        ImageInspection, ImageLayer, ImageScan, LogOptions, PullPriority, ResourceLimits, Severity,
+        Vulnerability,
+    };
+    use orchestrator_shared_types::{
+        ContainerConfig, ContainerId, Node, NodeId, OrchestrationError, Result, WorkloadId,
+    };
     use tokio::sync::watch;
 
     #[derive(Default)]
@@ -76,6 +85,209 @@ mod mock {
             Ok(rx)
         }
     }
+
+    /// Runtime that accepts every call without running anything.
+    pub struct NoopRuntime;
+
+    #[async_trait]
+    impl ContainerRuntime for NoopRuntime {
+        async fn init_node(&self, _node_id: NodeId) -> Result<()> {
+            Ok(())
+        }
+
+        async fn create_container(
+            &self,
+            _config: &ContainerConfig,
+            _options: &CreateContainerOptions,
+        ) -> Result<ContainerId> {
+            Ok(uuid::Uuid::new_v4().to_string())
+        }
+
+        async fn stop_container(&self, _container_id: &ContainerId) -> Result<()> {
+            Ok(())
+        }
+
+        async fn remove_container(&self, _container_id: &ContainerId) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_container_status(
+            &self,
+            container_id: &ContainerId,
+        ) -> Result<ContainerStatus> {
+            Ok(ContainerStatus {
+                id: container_id.clone(),
+                state: "running".to_string(),
+                exit_code: None,
+                error_message: None,
+                oom_killed: false,
+            })
+        }
+
+        async fn list_containers(&self, _node_id: NodeId) -> Result<Vec<ContainerStatus>> {
+            Ok(vec![])
+        }
+
+        async fn checkpoint_container(
+            &self,
+            container_id: &ContainerId,
+            _options: &CheckpointOptions,
+        ) -> Result<Checkpoint> {
+            Ok(Checkpoint {
+                container_id: container_id.clone(),
+                path: std::path::PathBuf::from("/checkpoints").join(container_id),
+                created_at: std::time::SystemTime::now(),
+            })
+        }
+
+        async fn restore_container(
+            &self,
+            _checkpoint: &Checkpoint,
+            _options: &CreateContainerOptions,
+        ) -> Result<ContainerId> {
+            Ok(uuid::Uuid::new_v4().to_string())
+        }
+
+        async fn remove_checkpoint(&self, _checkpoint: &Checkpoint) -> Result<()> {
+            Ok(())
+        }
+
+        async fn update_container_resources(
+            &self,
+            _container_id: &ContainerId,
+            _resources: &ResourceLimits,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn pull_image(
+            &self,
+            _node_id: NodeId,
+            _image: &str,
+            _priority: PullPriority,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn inspect_image(&self, image: &str) -> Result<ImageInspection> {
+            Ok(ImageInspection {
+                reference: image.to_string(),
+                digest: "sha256:abc".to_string(),
+                platform: "linux/amd64".to_string(),
+                layers: vec![
+                    ImageLayer {
+                        digest: "sha256:l1".to_string(),
+                        size_bytes: 300,
+                    },
+                    ImageLayer {
+                        digest: "sha256:l2".to_string(),
+                        size_bytes: 200,
+                    },
+                ],
+                exposed_ports: vec!["8000/tcp".to_string()],
+                ..Default::default()
+            })
+        }
+
+        /// Images with "vulnerable" in their reference carry a critical CVE.
+        async fn scan_image(&self, image: &str) -> Result<ImageScan> {
+            let vulnerabilities = if image.contains("vulnerable") {
+                vec![Vulnerability {
+                    id: "CVE-2024-3094".to_string(),
+                    package: "xz-utils".to_string(),
+                    installed_version: "5.6.0".to_string(),
+                    fixed_version: Some("5.6.1".to_string()),
+                    severity: Severity::Critical,
+                }]
+            } else {
+                vec![]
+            };
+            Ok(ImageScan {
+                image: image.to_string(),
+                digest: "sha256:abc".to_string(),
+                scanner: "trivy".to_string(),
+                scanned_at: std::time::SystemTime::now(),
+                vulnerabilities,
+            })
+        }
+
+        async fn image_scans(&self) -> Result<Vec<ImageScan>> {
+            Ok(vec![self.scan_image("vulnerable:v1").await?])
+        }
+    }
+
+    /// Runtime that serves canned logs and runs nothing.
+    #[derive(Default)]
+    pub struct LogRuntime {
+        pub logs: HashMap<ContainerId, String>,
+        pub archived: Vec<ContainerId>,
+    }
+
+    #[async_trait]
+    impl ContainerRuntime for LogRuntime {
+        async fn init_node(&self, node_id: NodeId) -> Result<()> {
+            NoopRuntime.init_node(node_id).await
+        }
+
+        async fn create_container(
+            &self,
+            config: &ContainerConfig,
+            options: &CreateContainerOptions,
+        ) -> Result<ContainerId> {
+            NoopRuntime.create_container(config, options).await
+        }
+
+        async fn stop_container(&self, container_id: &ContainerId) -> Result<()> {
+            NoopRuntime.stop_container(container_id).await
+        }
+
+        async fn remove_container(&self, container_id: &ContainerId) -> Result<()> {
+            NoopRuntime.remove_container(container_id).await
+        }
+
+        async fn get_container_status(
+            &self,
+            container_id: &ContainerId,
+        ) -> Result<ContainerStatus> {
+            NoopRuntime.get_container_status(container_id).await
+        }
+
+        async fn list_containers(&self, node_id: NodeId) -> Result<Vec<ContainerStatus>> {
+            NoopRuntime.list_containers(node_id).await
+        }
+
+        async fn get_container_logs(
+            &self,
+            container_id: &ContainerId,
+            _options: &LogOptions,
+        ) -> Result<String> {
+            self.logs
+                .get(container_id)
+                .cloned()
+                .ok_or_else(|| OrchestrationError::RuntimeError("no such container".to_string()))
+        }
+
+        async fn archived_log_containers(
+            &self,
+            _workload_id: WorkloadId,
+        ) -> Result<Vec<ContainerId>> {
+            Ok(self.archived.clone())
+        }
+    }
+
+    /// Firewall that keeps the last ruleset instead of installing it.
+    #[derive(Default)]
+    pub struct RecordingFirewall {
+        pub ruleset: std::sync::Mutex<Option<String>>,
+    }
+
+    #[async_trait]
+    impl orchestrator_core::network::Firewall for RecordingFirewall {
+        async fn apply(&self, ruleset: &str) -> Result<()> {
+            *self.ruleset.lock().unwrap() = Some(ruleset.to_string());
+            Ok(())
+        }
+    }
 }
 
 #[cfg(feature = "rest-api")]
@@ -343,6 +555,196 @@ async fn test_cluster_status_empty() {
     let status: ClusterStatusResponse = serde_json::from_slice(&body).unwrap();
     assert_eq!(status.total_nodes, 0);
     assert_eq!(status.total_workloads, 0);
+    assert_eq!(status.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_cluster_leader() {
+    use orchestrator_core::api::handlers::LeaderResponse;
+    use orchestrator_core::leader::LeaderElector;
+
+    let (mut state, _workload_rx) = create_test_state();
+    let follower = LeaderElector::new(state.state_store.clone(), "replica-b");
+    let elector = Arc::new(LeaderElector::new(state.state_store.clone(), "replica-a"));
+    elector.campaign().await.unwrap();
+    assert!(!follower.campaign().await.unwrap());
+
+    state.set_leader_elector(elector);
+    let router = build_router(state);
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/cluster/leader")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let leader: LeaderResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(leader.leader.as_deref(), Some("replica-a"));
+    assert_eq!(leader.term, Some(1));
+    assert!(leader.is_leader);
+    assert!(leader.lease_expires_in_ms.unwrap() > 0);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_v1beta1_converts_and_warns() {
+    use orchestrator_core::api::v1beta1;
+
+    let (state, mut workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    let body = serde_json::json!({
+        "name": "legacy",
+        "replicas": 1,
+        "strategy": "spread",
+        "containers": [{
+            "name": "web",
+            "image": "nginx:latest",
+            "env": {"MODE": "beta"}
+        }]
+    });
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1beta1/workloads")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.headers()["deprecation"], "true");
+    let warnings: Vec<_> = response
+        .headers()
+        .get_all("warning")
+        .iter()
+        .map(|v| v.to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(warnings.len(), 3);
+    assert!(warnings.iter().any(|w| w.contains("field strategy")));
+    assert!(warnings.iter().any(|w| w.contains("containers[].env")));
+    assert!(warnings.iter().any(|w| w.contains("API version v1beta1")));
+
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let created: v1beta1::WorkloadResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(created.strategy.as_deref(), Some("spread"));
+    assert_eq!(created.containers[0].env["MODE"], "beta");
+
+    let workload = workload_rx.try_recv().unwrap();
+    assert_eq!(workload.placement.scheduling_strategy.as_deref(), Some("spread"));
+    assert_eq!(workload.containers[0].env_vars["MODE"], "beta");
+
+    // The same workload through v1 uses the new field names and no warnings
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/api/v1/workloads/{}", created.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("deprecation").is_none());
+    assert!(response.headers().get("warning").is_none());
+
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let workload: WorkloadResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(workload.containers[0].env_vars["MODE"], "beta");
+    assert_eq!(workload.placement.scheduling_strategy.as_deref(), Some("spread"));
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_open_and_close_tunnel() {
+    use orchestrator_core::network::{Tunnel, TunnelManager};
+
+    let (mut state, _workload_rx) = create_test_state();
+    let loopback = std::net::IpAddr::from([127, 0, 0, 1]);
+    state.set_tunnel_manager(Arc::new(
+        TunnelManager::new(state.state_store.clone(), loopback)
+            .with_bind_ip(loopback)
+            .with_port_range(47200..=47299),
+    ));
+    let router = build_router(state);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/workloads")
+                .header("content-type", "application/json")
+                .body(Body::from(create_workload_json()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let workload: WorkloadResponse = serde_json::from_slice(&body).unwrap();
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/workloads/{}/tunnels", workload.id))
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"ttl_secs": 600}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let tunnel: Tunnel = serde_json::from_slice(&body).unwrap();
+    assert_eq!(tunnel.workload_id, workload.id);
+    assert_eq!(tunnel.target_port, 80);
+    assert_eq!(tunnel.public_addr.ip(), loopback);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/api/v1/tunnels/{}", tunnel.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/tunnels")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let list: ListResponse<Tunnel> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(list.count, 0);
 }
 
 #[cfg(feature = "rest-api")]
@@ -406,6 +808,7 @@ async fn test_workload_crud_flow() {
     let body = axum::body::to_bytes(create_response.into_body(), 1024 * 1024).await.unwrap();
     let created: WorkloadResponse = serde_json::from_slice(&body).unwrap();
     let workload_id = created.id;
+    assert!(created.resource_version > 0);
 
     // 2. Get workload
     let get_response = router
@@ -432,7 +835,8 @@ async fn test_workload_crud_flow() {
             "resource_requests": {"cpu_cores": 1.0, "memory_mb": 1024, "disk_mb": 2048}
         }],
         "replicas": 5,
-        "labels": {"app": "updated"}
+        "labels": {"app": "updated"},
+        "resource_version": created.resource_version
     }).to_string();
 
     let update_response = router
@@ -506,151 +910,3598 @@ async fn test_workload_crud_flow() {
 
 #[cfg(feature = "rest-api")]
 #[tokio::test]
-async fn test_validation_errors() {
-    let (state, _workload_rx) = create_test_state();
+async fn test_workload_update_conflicts() {
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    let state_store: Arc<dyn state_store_interface::StateStore> =
+        Arc::new(InMemoryStateStore::new());
+    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
+        Arc::new(mock::MockClusterManager);
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let state = ApiState::new_without_auth(state_store, cluster_manager, workload_tx);
     let router = build_router(state);
 
-    // Empty name
-    let invalid_body = serde_json::json!({
-        "name": "",
-        "containers": [{"name": "test", "image": "test:latest"}],
-        "replicas": 1
-    }).to_string();
+    let send = |method: &str, uri: String, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
 
     let response = router
         .clone()
-        .oneshot(
-            Request::builder()
-                .method("POST")
-                .uri("/api/v1/workloads")
-                .header("content-type", "application/json")
-                .body(Body::from(invalid_body))
-                .unwrap(),
-        )
+        .oneshot(send(
+            "POST",
+            "/api/v1/workloads".to_string(),
+            serde_json::from_str(&create_workload_json()).unwrap(),
+        ))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let created: WorkloadResponse = serde_json::from_slice(&body).unwrap();
+    let uri = format!("/api/v1/workloads/{}", created.id);
 
-    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-
-    // No containers
-    let no_containers = serde_json::json!({
-        "name": "test",
-        "containers": [],
-        "replicas": 1
-    }).to_string();
+    let mut update: serde_json::Value = serde_json::from_str(&create_workload_json()).unwrap();
 
+    // PUT without a version is rejected outright
     let response = router
         .clone()
-        .oneshot(
-            Request::builder()
-                .method("POST")
-                .uri("/api/v1/workloads")
-                .header("content-type", "application/json")
-                .body(Body::from(no_containers))
-                .unwrap(),
-        )
+        .oneshot(send("PUT", uri.clone(), update.clone()))
         .await
         .unwrap();
-
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
-    // Zero replicas
-    let zero_replicas = serde_json::json!({
-        "name": "test",
-        "containers": [{"name": "test", "image": "test:latest"}],
-        "replicas": 0
-    }).to_string();
-
+    // A concurrent writer gets in first
     let response = router
-        .oneshot(
-            Request::builder()
-                .method("POST")
-                .uri("/api/v1/workloads")
-                .header("content-type", "application/json")
-                .body(Body::from(zero_replicas))
-                .unwrap(),
-        )
+        .clone()
+        .oneshot(send(
+            "PATCH",
+            uri.clone(),
+            serde_json::json!({"resource_version": created.resource_version, "replicas": 4}),
+        ))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let patched: WorkloadResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(patched.replicas, 4);
+    assert!(patched.resource_version > created.resource_version);
 
-    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-}
-
-#[cfg(feature = "rest-api")]
-#[tokio::test]
+    // The stale version now conflicts on both PUT and PATCH
+    update["resource_version"] = created.resource_version.into();
+    let response = router
+        .clone()
+        .oneshot(send("PUT", uri.clone(), update.clone()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = router
+        .clone()
+        .oneshot(send(
+            "PATCH",
+            uri.clone(),
+            serde_json::json!({"resource_version": created.resource_version, "replicas": 1}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Retrying with the current version succeeds
+    update["resource_version"] = patched.resource_version.into();
+    let response = router.oneshot(send("PUT", uri, update)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_workload_dry_run_writes_nothing() {
+    let (state, mut workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    let send = |method: &str, uri: String, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let read = |response: axum::response::Response| async move {
+        let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        serde_json::from_slice::<WorkloadResponse>(&body).unwrap()
+    };
+    let workload: serde_json::Value = serde_json::from_str(&create_workload_json()).unwrap();
+
+    // A dry-run create is validated but neither stored nor scheduled
+    let response = router
+        .clone()
+        .oneshot(send("POST", "/api/v1/workloads?dryRun=true".to_string(), workload.clone()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(read(response).await.replicas, 2);
+    let mut invalid = workload.clone();
+    invalid["replicas"] = 0.into();
+    let response = router
+        .clone()
+        .oneshot(send("POST", "/api/v1/workloads?dryRun=true".to_string(), invalid))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(workload_rx.try_recv().is_err());
+
+    let response = router
+        .clone()
+        .oneshot(send("POST", "/api/v1/workloads".to_string(), workload.clone()))
+        .await
+        .unwrap();
+    let created = read(response).await;
+    workload_rx.try_recv().unwrap();
+    let uri = format!("/api/v1/workloads/{}", created.id);
+
+    // A dry-run update returns the workload as it would be stored
+    let mut update = workload;
+    update["replicas"] = 5.into();
+    update["resource_version"] = created.resource_version.into();
+    let response = router
+        .clone()
+        .oneshot(send("PUT", format!("{}?dryRun=true", uri), update.clone()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let would_be = read(response).await;
+    assert_eq!(would_be.id, created.id);
+    assert_eq!(would_be.replicas, 5);
+    assert!(workload_rx.try_recv().is_err());
+
+    let response = router
+        .clone()
+        .oneshot(Request::builder().uri(uri.clone()).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let current = read(response).await;
+    assert_eq!(current.replicas, created.replicas);
+    assert_eq!(current.resource_version, created.resource_version);
+
+    // Stale versions conflict as they would on a real update
+    update["resource_version"] = (created.resource_version + 1).into();
+    let response = router
+        .oneshot(send("PUT", format!("{}?dryRun=true", uri), update))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_create_workload_with_container_limits() {
+    let (state, mut workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    let workload_with = |resources: serde_json::Value| {
+        let body = serde_json::json!({
+            "name": "trainer",
+            "containers": [{
+                "name": "trainer",
+                "image": "pytorch:2",
+                "resource_requests": resources,
+            }],
+            "replicas": 1,
+        });
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/workloads")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = router
+        .clone()
+        .oneshot(workload_with(serde_json::json!({
+            "cpu_cores": 4.0,
+            "memory_mb": 16384,
+            "pids_limit": 4096,
+            "io_weight": 500,
+            "io_throttle": [{"major": 259, "minor": 0, "write_bps": 104857600}],
+            "hugepages": [{"page_size": "2MB", "limit_bytes": 1073741824u64}],
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let resources = &created["containers"][0]["resource_requests"];
+    assert_eq!(resources["pids_limit"], 4096);
+    assert_eq!(resources["io_throttle"][0]["write_bps"], 104857600);
+    assert!(resources["io_throttle"][0].get("read_bps").is_none());
+
+    let workload = workload_rx.recv().await.unwrap();
+    let limits = &workload.containers[0].limits;
+    assert_eq!(limits.io_weight, Some(500));
+    assert_eq!(limits.hugepages[0].limit_bytes, 1 << 30);
+
+    for resources in [
+        serde_json::json!({"io_weight": 5}),
+        serde_json::json!({"pids_limit": 0}),
+        serde_json::json!({"hugepages": [{"page_size": "huge", "limit_bytes": 1}]}),
+    ] {
+        let response = router.clone().oneshot(workload_with(resources)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_create_workload_with_sysctls_and_rlimits() {
+    let (state, mut workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    let workload_with = |settings: serde_json::Value| {
+        let mut container = serde_json::json!({"name": "db", "image": "postgres:16"});
+        container
+            .as_object_mut()
+            .unwrap()
+            .extend(settings.as_object().unwrap().clone());
+        let body = serde_json::json!({"name": "db", "containers": [container], "replicas": 1});
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/workloads")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = router
+        .clone()
+        .oneshot(workload_with(serde_json::json!({
+            "sysctls": {"net.core.somaxconn": "4096", "kernel.shmmax": "68719476736"},
+            "rlimits": [{"name": "nofile", "soft": 65536, "hard": 65536}],
+            "shm_size_mb": 1024,
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        created["containers"][0]["sysctls"]["net.core.somaxconn"],
+        "4096"
+    );
+    assert_eq!(created["containers"][0]["shm_size_mb"], 1024);
+
+    let workload = workload_rx.recv().await.unwrap();
+    let container = &workload.containers[0];
+    assert_eq!(container.rlimits[0].name, "nofile");
+    assert_eq!(container.sysctls.len(), 2);
+
+    for settings in [
+        serde_json::json!({"sysctls": {"kernel.hostname": "db"}}),
+        serde_json::json!({"sysctls": {"vm.swappiness": "0"}}),
+        serde_json::json!({"rlimits": [{"name": "files", "soft": 1, "hard": 1}]}),
+        serde_json::json!({"rlimits": [{"name": "nofile", "soft": 2, "hard": 1}]}),
+        serde_json::json!({"shm_size_mb": 0}),
+    ] {
+        let response = router
+            .clone()
+            .oneshot(workload_with(settings))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_create_workload_with_hostname_and_dns() {
+    let (state, mut workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    let workload_with = |settings: serde_json::Value| {
+        let mut container = serde_json::json!({"name": "db", "image": "postgres:16"});
+        container
+            .as_object_mut()
+            .unwrap()
+            .extend(settings.as_object().unwrap().clone());
+        let body = serde_json::json!({"name": "db", "containers": [container], "replicas": 1});
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/workloads")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = router
+        .clone()
+        .oneshot(workload_with(serde_json::json!({
+            "hostname": "db-primary",
+            "host_aliases": [{"ip": "10.0.0.7", "hostnames": ["replica", "replica.local"]}],
+            "dns": {"searches": ["db.svc.cluster.local"], "options": ["ndots:2"]},
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(created["containers"][0]["hostname"], "db-primary");
+    assert_eq!(created["containers"][0]["dns"]["options"][0], "ndots:2");
+
+    let workload = workload_rx.recv().await.unwrap();
+    let container = &workload.containers[0];
+    assert_eq!(container.effective_hostname(), "db-primary");
+    assert_eq!(container.host_aliases[0].hostnames.len(), 2);
+    assert!(container.dns.nameservers.is_empty());
+
+    for settings in [
+        serde_json::json!({"hostname": "DB_Primary"}),
+        serde_json::json!({"host_aliases": [{"ip": "10.0.0.7", "hostnames": []}]}),
+        serde_json::json!({"dns": {"nameservers": ["1.1.1.1", "1.0.0.1", "8.8.8.8", "8.8.4.4"]}}),
+        serde_json::json!({"dns": {"options": ["ndots: 2"]}}),
+    ] {
+        let response = router
+            .clone()
+            .oneshot(workload_with(settings))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_create_workload_with_bandwidth_limits() {
+    use orchestrator_shared_types::BandwidthLimits;
+
+    let (state, mut workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    let workload_with = |annotations: serde_json::Value| {
+        let body = serde_json::json!({
+            "name": "sync",
+            "containers": [{"name": "sync", "image": "rclone:latest", "annotations": annotations}],
+            "replicas": 1,
+        });
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/workloads")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = router
+        .clone()
+        .oneshot(workload_with(serde_json::json!({
+            "ingress_bandwidth": "1G",
+            "egress_bandwidth": "50M",
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        created["containers"][0]["annotations"]["egress_bandwidth"],
+        "50M"
+    );
+
+    let workload = workload_rx.recv().await.unwrap();
+    assert_eq!(
+        workload.containers[0].bandwidth_limits().unwrap(),
+        BandwidthLimits {
+            ingress_bps: Some(1_000_000_000),
+            egress_bps: Some(50_000_000),
+        }
+    );
+
+    for annotations in [
+        serde_json::json!({"egress_bandwidth": "0"}),
+        serde_json::json!({"ingress_bandwidth": "fast"}),
+        serde_json::json!({"ingress_bandwidth": "10Mbit"}),
+    ] {
+        let response = router
+            .clone()
+            .oneshot(workload_with(annotations))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_create_workload_with_model_artifact() {
+    use orchestrator_shared_types::ModelArtifact;
+
+    let (state, mut workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    let workload_with = |annotations: serde_json::Value| {
+        let body = serde_json::json!({
+            "name": "llm",
+            "containers": [{"name": "server", "image": "vllm:latest", "annotations": annotations}],
+            "replicas": 1,
+        });
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/workloads")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let digest = format!("sha256:{}", "a".repeat(64));
+    let response = router
+        .clone()
+        .oneshot(workload_with(serde_json::json!({
+            "model_uri": "hf://org/model@v1",
+            "model_digest": digest,
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let workload = workload_rx.recv().await.unwrap();
+    assert_eq!(
+        workload.containers[0].model_artifact().unwrap(),
+        Some(ModelArtifact {
+            uri: "hf://org/model@v1".to_string(),
+            digest: Some(digest),
+            mount_path: ModelArtifact::DEFAULT_MOUNT_PATH.to_string(),
+        })
+    );
+
+    for annotations in [
+        serde_json::json!({"model_uri": "https://example.com/model.bin"}),
+        serde_json::json!({"model_uri": "s3://bucket/model", "model_digest": "sha256:abc"}),
+        serde_json::json!({
+            "model_uri": "oci://ghcr.io/org/model:v1",
+            "model_mount_path": "models",
+        }),
+    ] {
+        let response = router
+            .clone()
+            .oneshot(workload_with(annotations))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_create_workload_with_gpu_request() {
+    use orchestrator_shared_types::GpuRequest;
+
+    let (state, mut workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    let workload_with = |gpu: &str| {
+        let body = serde_json::json!({
+            "name": "train",
+            "containers": [{
+                "name": "trainer",
+                "image": "pytorch:latest",
+                "annotations": {"gpu": gpu},
+            }],
+            "replicas": 1,
+        });
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/workloads")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = router
+        .clone()
+        .oneshot(workload_with("mig-1g.10gb"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let workload = workload_rx.recv().await.unwrap();
+    assert_eq!(
+        workload.containers[0].gpu_request().unwrap(),
+        Some(GpuRequest::Mig("1g.10gb".to_string()))
+    );
+
+    for gpu in ["0", "1.5", "0.333", "mig-large", "all"] {
+        let response = router.clone().oneshot(workload_with(gpu)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "gpu {}", gpu);
+    }
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_workload_merge_and_json_patch() {
+    let (state, _workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    let send = |method: &str, uri: &str, content_type: &str, body: String| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", content_type)
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let read = |response: axum::response::Response| async move {
+        let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        serde_json::from_slice::<WorkloadResponse>(&body).unwrap()
+    };
+
+    let response = router
+        .clone()
+        .oneshot(send("POST", "/api/v1/workloads", "application/json", create_workload_json()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created = read(response).await;
+    let uri = format!("/api/v1/workloads/{}", created.id);
+
+    // Merge patch bumps a label without resending the workload
+    let merge = serde_json::json!({"replicas": 3, "labels": {"tier": "web"}});
+    let response = router
+        .clone()
+        .oneshot(send("PATCH", &uri, "application/merge-patch+json", merge.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let merged = read(response).await;
+    assert_eq!(merged.replicas, 3);
+    assert_eq!(merged.labels["tier"], "web");
+    assert_eq!(merged.containers.len(), created.containers.len());
+
+    // JSON patch changes one image, guarded by the version it was based on
+    let ops = serde_json::json!([
+        {"op": "test", "path": "/resource_version", "value": merged.resource_version},
+        {"op": "replace", "path": "/containers/0/image", "value": "nginx:1.27"},
+    ]);
+    let response = router
+        .clone()
+        .oneshot(send("PATCH", &uri, "application/json-patch+json", ops.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let patched = read(response).await;
+    assert_eq!(patched.containers[0].image, "nginx:1.27");
+    assert_eq!(patched.replicas, 3);
+
+    // Replaying it fails the version test
+    let response = router
+        .clone()
+        .oneshot(send("PATCH", &uri, "application/json-patch+json", ops.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Patches are held to the same checks as creates and updates
+    for ops in [
+        serde_json::json!([
+            {"op": "add", "path": "/containers/0/sysctls", "value": {"vm.swappiness": "1"}},
+        ]),
+        serde_json::json!([{"op": "add", "path": "/containers/0/shm_size_mb", "value": 0}]),
+        serde_json::json!([{"op": "replace", "path": "/containers", "value": []}]),
+    ] {
+        let response = router
+            .clone()
+            .oneshot(send("PATCH", &uri, "application/json-patch+json", ops.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", ops);
+    }
+
+    // Missing paths, moves between namespaces and other media types are refused
+    let missing = serde_json::json!([{"op": "remove", "path": "/containers/9"}]);
+    let response = router
+        .clone()
+        .oneshot(send("PATCH", &uri, "application/json-patch+json", missing.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let moved = serde_json::json!({"namespace": "other"});
+    let response = router
+        .clone()
+        .oneshot(send("PATCH", &uri, "application/merge-patch+json", moved.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = router
+        .oneshot(send("PATCH", &uri, "text/plain", "replicas=1".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_patch_node_labels() {
+    use orchestrator_shared_types::Keypair;
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+
+    let state_store = Arc::new(InMemoryStateStore::new());
+    let node_id = Keypair::generate().public_key();
+    state_store
+        .put_node(Node {
+            id: node_id,
+            address: "10.0.0.1:8080".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::from([("zone".to_string(), "a".to_string())]),
+            resources_capacity: NodeResources::default(),
+            resources_allocatable: NodeResources::default(),
+            unschedulable: false,
+            resource_version: 0,
+        })
+        .await
+        .unwrap();
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let state = ApiState::new_without_auth(
+        state_store.clone(),
+        Arc::new(mock::MockClusterManager),
+        workload_tx,
+    );
+    let router = build_router(state);
+    let uri = format!("/api/v1/nodes/{}", node_id);
+    let send = |body: serde_json::Value| {
+        Request::builder()
+            .method("PATCH")
+            .uri(&uri)
+            .header("content-type", "application/merge-patch+json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = router
+        .clone()
+        .oneshot(send(serde_json::json!({"labels": {"gpu": "a100"}, "unschedulable": true})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let node: NodeResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(node.labels.len(), 2);
+    assert!(node.unschedulable);
+    let stored = state_store.get_node(&node_id).await.unwrap().unwrap();
+    assert_eq!(stored.labels["gpu"], "a100");
+
+    // Fields the node reports are read-only
+    let response = router
+        .clone()
+        .oneshot(send(serde_json::json!({"address": "10.0.0.2:8080"})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // A stale version conflicts
+    let stale = serde_json::json!({"resource_version": 1, "labels": {"gpu": null}});
+    let response = router.oneshot(send(stale)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_scale_workload() {
+    let (state, _workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    let send = |method: &str, uri: &str, body: Option<serde_json::Value>| {
+        let builder = Request::builder().method(method).uri(uri);
+        match body {
+            Some(body) => builder
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+            None => builder.body(Body::empty()).unwrap(),
+        }
+    };
+    let read = |response: axum::response::Response| async move {
+        let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        serde_json::from_slice::<ScaleResponse>(&body).unwrap()
+    };
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/workloads")
+        .header("content-type", "application/json")
+        .body(Body::from(create_workload_json()))
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let created: WorkloadResponse = serde_json::from_slice(&body).unwrap();
+    let uri = format!("/api/v1/workloads/{}/scale", created.id);
+
+    let response = router.clone().oneshot(send("GET", &uri, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let scale = read(response).await;
+    assert_eq!(scale.replicas, 2);
+    assert_eq!(scale.current_replicas, 0);
+
+    // No resource_version needed to scale the latest version
+    let response = router
+        .clone()
+        .oneshot(send("POST", &uri, Some(serde_json::json!({"replicas": 5}))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let scaled = read(response).await;
+    assert_eq!(scaled.replicas, 5);
+    assert!(scaled.resource_version > created.resource_version);
+
+    // A stale resource_version conflicts
+    let stale = serde_json::json!({"replicas": 1, "resource_version": created.resource_version});
+    let response = router.clone().oneshot(send("POST", &uri, Some(stale))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let missing = format!("/api/v1/workloads/{}/scale", Uuid::new_v4());
+    let response = router.oneshot(send("GET", &missing, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_rollout_history_and_undo() {
+    let (state, _workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    let send = |method: &str, uri: &str, content_type: &str, body: String| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", content_type)
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let read = |response: axum::response::Response| async move {
+        let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    let response = router
+        .clone()
+        .oneshot(send("POST", "/api/v1/workloads", "application/json", create_workload_json()))
+        .await
+        .unwrap();
+    let created: WorkloadResponse = serde_json::from_slice(
+        &axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap(),
+    )
+    .unwrap();
+    let uri = format!("/api/v1/workloads/{}", created.id);
+
+    // A bad image push makes revision 2; scaling makes none
+    for (content_type, patch) in [
+        (
+            "application/json-patch+json",
+            r#"[{"op": "replace", "path": "/containers/0/image", "value": "nginx:broken"}]"#,
+        ),
+        ("application/merge-patch+json", r#"{"replicas": 4}"#),
+    ] {
+        let request = send("PATCH", &uri, content_type, patch.to_string());
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let empty = |method: &str, path: &str| {
+        send(method, &format!("{}{}", uri, path), "application/json", String::new())
+    };
+    let history = read(router.clone().oneshot(empty("GET", "/revisions")).await.unwrap()).await;
+    assert_eq!(history["count"], 2);
+    assert_eq!(history["items"][1]["current"], true);
+
+    let response = router
+        .clone()
+        .oneshot(empty("POST", "/rollout/undo"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let rolled_back = read(response).await;
+    assert_eq!(rolled_back["containers"][0]["image"], "nginx:latest");
+    assert_eq!(rolled_back["replicas"], 4);
+
+    let history = read(router.clone().oneshot(empty("GET", "/revisions")).await.unwrap()).await;
+    assert_eq!(history["count"], 3);
+    let status = read(router.clone().oneshot(empty("GET", "/rollout/status")).await.unwrap()).await;
+    assert_eq!(status["revision"], 3);
+    assert_eq!(status["replicas"], 4);
+
+    let response = router
+        .oneshot(empty("POST", "/rollout/undo?to=9"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_restart_and_delete_instance() {
+    use orchestrator_shared_types::{Keypair, WorkloadInstance, WorkloadInstanceStatus};
+
+    let (mut state, mut workload_rx) = create_test_state();
+    state.set_runtime(Arc::new(mock::NoopRuntime));
+    let store = state.state_store.clone();
+    let router = build_router(state);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/workloads")
+                .header("Content-Type", "application/json")
+                .body(Body::from(create_workload_json()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let workload = workload_rx.recv().await.unwrap();
+
+    let mut instance_ids = Vec::new();
+    for _ in 0..2 {
+        let instance = WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: workload.id,
+            node_id: Keypair::generate().public_key(),
+            container_ids: vec![Uuid::new_v4().to_string()],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        instance_ids.push(instance.id);
+        store.put_instance(instance).await.unwrap();
+    }
+    let send = |method: &str, uri: String| {
+        Request::builder().method(method).uri(uri).body(Body::empty()).unwrap()
+    };
+
+    let uri = format!("/api/v1/instances/{}/restart", instance_ids[0]);
+    let response = router.clone().oneshot(send("POST", uri)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    // The workload goes back to the orchestrator to schedule a replacement
+    assert_eq!(workload_rx.recv().await.unwrap().id, workload.id);
+
+    let uri = format!("/api/v1/instances/{}?namespace=ml", instance_ids[1]);
+    let response = router.clone().oneshot(send("DELETE", uri)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let uri = format!("/api/v1/instances/{}", instance_ids[1]);
+    let response = router.clone().oneshot(send("DELETE", uri.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(workload_rx.recv().await.unwrap().id, workload.id);
+    assert!(store.list_all_instances().await.unwrap().is_empty());
+
+    let response = router.oneshot(send("DELETE", uri)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_migrate_instance() {
+    use orchestrator_shared_types::{Keypair, WorkloadInstance, WorkloadInstanceStatus};
+
+    let (mut state, _workload_rx) = create_test_state();
+    state.set_runtime(Arc::new(mock::NoopRuntime));
+    let store = state.state_store.clone();
+    let router = build_router(state);
+
+    let mut nodes = Vec::new();
+    for address in ["10.0.0.1:7280", "10.0.0.2:7280"] {
+        let node = Node {
+            id: Keypair::generate().public_key(),
+            address: address.to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            resources_capacity: NodeResources::default(),
+            resources_allocatable: NodeResources::default(),
+            unschedulable: false,
+            resource_version: 0,
+        };
+        nodes.push(node.id);
+        store.put_node(node).await.unwrap();
+    }
+    let instance = WorkloadInstance {
+        id: Uuid::new_v4(),
+        workload_id: Uuid::new_v4(),
+        node_id: nodes[0],
+        container_ids: vec!["sidecar-1".to_string(), "trainer-1".to_string()],
+        status: WorkloadInstanceStatus::Running,
+        ip_addresses: vec![],
+        restarts: vec![],
+        namespace: "default".to_string(),
+        resource_version: 0,
+    };
+    store.put_instance(instance.clone()).await.unwrap();
+
+    let uri = format!("/api/v1/instances/{}/migrate", instance.id);
+    let migrate = |node_id: String| {
+        Request::builder()
+            .method("POST")
+            .uri(&uri)
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::json!({ "node_id": node_id }).to_string()))
+            .unwrap()
+    };
+
+    let response = router.clone().oneshot(migrate("not-a-node".to_string())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = router.clone().oneshot(migrate(nodes[1].to_string())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let migrated: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(migrated["id"], instance.id.to_string());
+    assert_eq!(migrated["node_id"], nodes[1].to_string());
+    let stored = store
+        .get_instance(&instance.id.to_string())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.node_id, nodes[1]);
+    assert_eq!(stored.container_ids.len(), 2);
+    assert!(!stored.container_ids.contains(&"trainer-1".to_string()));
+
+    // It already runs there
+    let response = router.oneshot(migrate(nodes[1].to_string())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_update_instance_resources() {
+    use orchestrator_shared_types::{Keypair, WorkloadInstance, WorkloadInstanceStatus};
+
+    let (mut state, mut workload_rx) = create_test_state();
+    state.set_runtime(Arc::new(mock::NoopRuntime));
+    let store = state.state_store.clone();
+    let router = build_router(state);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/workloads")
+                .header("Content-Type", "application/json")
+                .body(Body::from(create_workload_json()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let workload = workload_rx.recv().await.unwrap();
+    let instance = WorkloadInstance {
+        id: Uuid::new_v4(),
+        workload_id: workload.id,
+        node_id: Keypair::generate().public_key(),
+        container_ids: vec![Uuid::new_v4().to_string()],
+        status: WorkloadInstanceStatus::Running,
+        ip_addresses: vec![],
+        restarts: vec![],
+        namespace: "default".to_string(),
+        resource_version: 0,
+    };
+    store.put_instance(instance.clone()).await.unwrap();
+
+    let uri = format!("/api/v1/instances/{}/resources", instance.id);
+    let patch = |body: serde_json::Value| {
+        Request::builder()
+            .method("PATCH")
+            .uri(&uri)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = router
+        .clone()
+        .oneshot(patch(
+            serde_json::json!({"cpu_cores": 2.0, "memory_mb": 2048}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let events = store.list_events().await.unwrap();
+    assert!(events
+        .iter()
+        .any(|e| e.reason == "ResourcesUpdated"
+            && e.message.ends_with("cpu 2 cores, memory 2048 MB")));
+
+    for body in [
+        serde_json::json!({}),
+        serde_json::json!({"memory_mb": 0}),
+        serde_json::json!({"cpu_cores": 1.0, "container": "missing"}),
+    ] {
+        let response = router.clone().oneshot(patch(body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_list_events_for_object() {
+    use orchestrator_core::events::EventRecorder;
+    use orchestrator_shared_types::ObjectReference;
+
+    let (state, _workload_rx) = create_test_state();
+    let recorder = EventRecorder::new(state.state_store.clone(), "scheduler");
+    let web = ObjectReference {
+        kind: "Workload".to_string(),
+        id: Uuid::new_v4().to_string(),
+        name: "web".to_string(),
+    };
+    for _ in 0..2 {
+        recorder
+            .warning(web.clone(), "default", "FailedScheduling", "0 nodes are available")
+            .await;
+    }
+    let node = Uuid::new_v4().to_string();
+    let other = ObjectReference {
+        kind: "Node".to_string(),
+        id: node.clone(),
+        name: node,
+    };
+    recorder.warning(other, "", "NodeNotReady", "Node stopped renewing its lease").await;
+    let router = build_router(state);
+
+    let request = Request::builder()
+        .uri(format!("/api/v1/events?involvedObject={}", web.id))
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let events: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(events["count"], 1);
+    let event = &events["items"][0];
+    assert_eq!(event["type"], "Warning");
+    assert_eq!(event["reason"], "FailedScheduling");
+    assert_eq!(event["count"], 2);
+    assert_eq!(event["involved_object"]["name"], "web");
+
+    let request = Request::builder()
+        .uri("/api/v1/events?namespace=default")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let events: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(events["count"], 1);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_usage_report() {
+    use orchestrator_shared_types::UsageRecord;
+
+    let (state, _workload_rx) = create_test_state();
+    // 2026-03-01T09:00:00Z and 10:00
+    let nine = 1_772_355_600_000;
+    for (name, namespace, period, cpu_core_seconds) in [
+        ("web", "shop", nine, 7200.0),
+        ("web", "shop", nine + UsageRecord::PERIOD_MS, 3600.0),
+        ("train", "ml", nine, 1800.0),
+    ] {
+        state
+            .state_store
+            .put_usage_record(UsageRecord {
+                workload_id: Uuid::from_u128(name.len() as u128),
+                workload_name: name.to_string(),
+                namespace: namespace.to_string(),
+                period_start_ms: period,
+                cpu_core_seconds,
+                memory_gb_seconds: 3600.0,
+                gpu_seconds: 0.0,
+            })
+            .await
+            .unwrap();
+    }
+    let router = build_router(state);
+
+    // The hour 09:30 falls in counts whole; 10:00 onwards does not
+    let request = Request::builder()
+        .uri("/api/v1/reports/usage?from=2026-03-01T09:30:00Z&to=2026-03-01T10:00:00Z")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(report["total"]["cpu_core_hours"], 2.5);
+    assert_eq!(report["namespaces"][0]["namespace"], "ml");
+    assert_eq!(report["namespaces"][1]["memory_gb_hours"], 1.0);
+    assert_eq!(report["workloads"][1]["workload_name"], "web");
+    assert_eq!(report["workloads"][1]["cpu_core_hours"], 2.0);
+
+    let request = Request::builder()
+        .uri("/api/v1/reports/usage?from=2026-03-01T00:00:00Z&namespace=shop")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(report["total"]["cpu_core_hours"], 3.0);
+
+    let request = Request::builder()
+        .uri("/api/v1/reports/usage?from=2026-03-02T00:00:00Z&to=2026-03-01T00:00:00Z")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_federation() {
+    use orchestrator_core::api::Federation;
+    use user_config::{ConfigPaths, SecretStore};
+
+    let (mut state, _workload_rx) = create_test_state();
+    let key = SecretStore::generate(ConfigPaths::with_base("/tmp"));
+    let federation =
+        Federation::new(Federation::DEFAULT_TIMEOUT, state.state_store.clone(), key).unwrap();
+    state.set_federation(Arc::new(federation));
+    let router = build_router(state);
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/workloads")
+        .header("Content-Type", "application/json")
+        .body(Body::from(create_workload_json()))
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Nothing listens on port 1
+    let body = serde_json::json!({
+        "name": "prod-eu",
+        "endpoint": "http://127.0.0.1:1/",
+        "token": "t",
+    });
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/federation/clusters")
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let cluster: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cluster["endpoint"], "http://127.0.0.1:1");
+    assert_eq!(cluster["authenticated"], true);
+    assert!(cluster.get("token").is_none());
+
+    let request = Request::builder()
+        .uri("/api/v1/federation/workloads")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let list: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(list["count"], 1);
+    assert_eq!(list["items"][0]["cluster"], "local");
+    assert_eq!(list["items"][0]["object"]["name"], "test-workload");
+    assert_eq!(list["unreachable"][0]["cluster"], "prod-eu");
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/federation/clusters/prod-eu/workloads")
+        .header("Content-Type", "application/json")
+        .body(Body::from(create_workload_json()))
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+
+    for (uri, status) in [
+        (
+            "/api/v1/federation/clusters/prod-us/proxy/api/v1/workloads",
+            StatusCode::NOT_FOUND,
+        ),
+        (
+            "/api/v1/federation/clusters/prod-eu/proxy/debug/pprof/heap",
+            StatusCode::BAD_REQUEST,
+        ),
+    ] {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), status, "{}", uri);
+    }
+
+    let request = Request::builder()
+        .method("DELETE")
+        .uri("/api/v1/federation/clusters/prod-eu")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let request = Request::builder()
+        .uri("/api/v1/federation/clusters")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let list: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(list["count"], 0);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_list_workloads_pages_sorted_and_selected() {
+    let (state, _workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    for (name, app) in [("d", "web"), ("b", "web"), ("e", "db"), ("a", "web"), ("c", "web")] {
+        let body = serde_json::json!({
+            "name": name,
+            "containers": [{"name": "main", "image": "nginx:latest"}],
+            "replicas": 1,
+            "labels": {"app": app}
+        });
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/v1/workloads")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    let list = |query: String| {
+        let router = router.clone();
+        async move {
+            let request = Request::builder()
+                .uri(format!("/api/v1/workloads?{}", query))
+                .body(Body::empty())
+                .unwrap();
+            let response = router.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+            (status, serde_json::from_slice::<ListResponse<WorkloadResponse>>(&body).ok())
+        }
+    };
+
+    // Two pages of web workloads by name, descending
+    let mut names = Vec::new();
+    let mut token: Option<String> = None;
+    loop {
+        let mut query = "limit=2&sort=-name&labelSelector=app%3Dweb".to_string();
+        if let Some(token) = &token {
+            query.push_str(&format!("&continue={}", token));
+        }
+        let (status, page) = list(query).await;
+        assert_eq!(status, StatusCode::OK);
+        let page = page.unwrap();
+        assert!(page.count <= 2);
+        names.extend(page.items.into_iter().map(|w| w.name));
+        token = page.continue_token;
+        if token.is_none() {
+            break;
+        }
+    }
+    assert_eq!(names, ["d", "c", "b", "a"]);
+
+    // Without a limit everything comes back on one page
+    let (_, page) = list("labelSelector=app%20in%20(db)".to_string()).await;
+    let page = page.unwrap();
+    assert_eq!(page.count, 1);
+    assert!(page.continue_token.is_none());
+
+    for bad in ["limit=0", "sort=image", "continue=%25%25", "labelSelector=app%20in%20db"] {
+        let (status, _) = list(bad.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", bad);
+    }
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_validation_errors() {
+    let (state, _workload_rx) = create_test_state();
+    let router = build_router(state);
+
+    // Empty name
+    let invalid_body = serde_json::json!({
+        "name": "",
+        "containers": [{"name": "test", "image": "test:latest"}],
+        "replicas": 1
+    }).to_string();
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/workloads")
+                .header("content-type", "application/json")
+                .body(Body::from(invalid_body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // No containers
+    let no_containers = serde_json::json!({
+        "name": "test",
+        "containers": [],
+        "replicas": 1
+    }).to_string();
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/workloads")
+                .header("content-type", "application/json")
+                .body(Body::from(no_containers))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Zero replicas
+    let zero_replicas = serde_json::json!({
+        "name": "test",
+        "containers": [{"name": "test", "image": "test:latest"}],
+        "replicas": 0
+    }).to_string();
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/workloads")
+                .header("content-type", "application/json")
+                .body(Body::from(zero_replicas))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_admission_limits_reject_with_codes() {
+    use orchestrator_core::admission::AdmissionLimits;
+
+    let (mut state, _workload_rx) = create_test_state();
+    state.set_admission_limits(
+        AdmissionLimits::unlimited()
+            .with_max_workloads_per_namespace(1)
+            .with_max_containers_per_workload(1),
+    );
+    let router = build_router(state);
+
+    let post = |body: String| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/workloads")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let response = router.clone().oneshot(post(create_workload_json())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // The default namespace is now full
+    let response = router.clone().oneshot(post(create_workload_json())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error["code"], "QUOTA_EXCEEDED");
+    assert_eq!(error["details"]["namespace"], "default");
+    assert_eq!(error["details"]["limit"], 1);
+
+    // Too many containers, in a namespace that still has room
+    let two_containers = serde_json::json!({
+        "name": "test",
+        "containers": [
+            {"name": "a", "image": "test:latest"},
+            {"name": "b", "image": "test:latest"}
+        ],
+        "replicas": 1,
+        "labels": {"namespace": "team-a"}
+    }).to_string();
+    let response = router.oneshot(post(two_containers)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error["code"], "TOO_MANY_CONTAINERS");
+    assert_eq!(error["details"]["count"], 2);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_admission_fills_resource_defaults() {
+    use orchestrator_core::admission::{AdmissionLimits, ResourceDefaults, ALL_NAMESPACES};
+
+    let (mut state, _workload_rx) = create_test_state();
+    state.set_admission_limits(AdmissionLimits::unlimited().with_resource_defaults(
+        ALL_NAMESPACES,
+        ResourceDefaults::parse("cpu=0.25,memory_mb=256,max_memory_mb=1024").unwrap(),
+    ));
+    let router = build_router(state);
+
+    let post = |body: String| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/workloads")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let no_requests = serde_json::json!({
+        "name": "test",
+        "containers": [{"name": "a", "image": "test:latest"}],
+        "replicas": 1
+    }).to_string();
+    let response = router.clone().oneshot(post(no_requests)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let workload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let requests = &workload["containers"][0]["resource_requests"];
+    assert_eq!(requests["cpu_cores"], 0.25);
+    assert_eq!(requests["memory_mb"], 256);
+
+    // Explicit requests above the maximum are rejected
+    let response = router.oneshot(post(create_workload_json().replace("512", "2048"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error["code"], "LIMIT_EXCEEDED");
+    assert_eq!(error["details"]["resource"], "memory_mb");
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_admission_policy_rules() {
+    use orchestrator_core::admission::AdmissionLimits;
+
+    let (mut state, _workload_rx) = create_test_state();
+    state.set_admission_limits(
+        AdmissionLimits::unlimited()
+            .with_required_labels(vec!["app".to_string()])
+            .with_allowed_registries(vec!["docker.io/library".to_string()])
+            .with_resource_requests_required(),
+    );
+    let router = build_router(state);
+
+    let post = |body: String| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/workloads")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let code = |response: axum::response::Response| async move {
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()["code"].clone()
+    };
+
+    let response = router.clone().oneshot(post(create_workload_json())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let unlabelled = create_workload_json().replace(r#""labels":{"app":"test"}"#, r#""labels":{}"#);
+    let response = router.clone().oneshot(post(unlabelled)).await.unwrap();
+    assert_eq!(code(response).await, "MISSING_LABEL");
+
+    let foreign = create_workload_json().replace("nginx:latest", "quay.io/x/nginx:latest");
+    let response = router.clone().oneshot(post(foreign)).await.unwrap();
+    assert_eq!(code(response).await, "IMAGE_NOT_ALLOWED");
+
+    let unbounded = create_workload_json().replace("0.5", "0");
+    let response = router.oneshot(post(unbounded)).await.unwrap();
+    assert_eq!(code(response).await, "RESOURCES_REQUIRED");
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
 async fn test_cluster_status_with_nodes() {
     use state_store_interface::in_memory::InMemoryStateStore;
 
-    let state_store = Arc::new(InMemoryStateStore::new());
+    let state_store = Arc::new(InMemoryStateStore::new());
+
+    // Add some nodes to state store
+    let node1 = Node {
+        id: Uuid::new_v4(),
+        address: "10.0.0.1:8080".to_string(),
+        status: NodeStatus::Ready,
+        labels: HashMap::new(),
+        resources_capacity: NodeResources {
+            cpu_cores: 4.0,
+            memory_mb: 8192,
+            disk_mb: 102400,
+        },
+        resources_allocatable: NodeResources {
+            cpu_cores: 3.6,
+            memory_mb: 7372,
+            disk_mb: 92160,
+        },
+        unschedulable: false,
+        resource_version: 0,
+    };
+
+    let node2 = Node {
+        id: Uuid::new_v4(),
+        address: "10.0.0.2:8080".to_string(),
+        status: NodeStatus::NotReady,
+        labels: HashMap::new(),
+        resources_capacity: NodeResources {
+            cpu_cores: 2.0,
+            memory_mb: 4096,
+            disk_mb: 51200,
+        },
+        resources_allocatable: NodeResources {
+            cpu_cores: 1.8,
+            memory_mb: 3686,
+            disk_mb: 46080,
+        },
+        unschedulable: false,
+        resource_version: 0,
+    };
+
+    state_store.put_node(node1).await.unwrap();
+    state_store.put_node(node2).await.unwrap();
+
+    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
+        Arc::new(mock::MockClusterManager);
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+
+    let state = ApiState::new_without_auth(
+        state_store as Arc<dyn state_store_interface::StateStore>,
+        cluster_manager,
+        workload_tx,
+    );
+    let router = build_router(state);
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/cluster/status")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let status: ClusterStatusResponse = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(status.total_nodes, 2);
+    assert_eq!(status.ready_nodes, 1);
+    assert_eq!(status.not_ready_nodes, 1);
+    assert_eq!(status.total_cpu_capacity, 6.0);
+    assert_eq!(status.total_memory_mb, 12288);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_service_crud() {
+    use orchestrator_core::api::handlers::ServiceResponse;
+    use orchestrator_core::network::{DualStackAllocator, ServiceProxy};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+
+    let state_store = Arc::new(InMemoryStateStore::new());
+    let allocator = DualStackAllocator::new(&["10.96.0.0/24".parse().unwrap()]).unwrap();
+    let proxy = Arc::new(ServiceProxy::new(state_store.clone()).with_vip_allocator(allocator));
+    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
+        Arc::new(mock::MockClusterManager);
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let mut state = ApiState::new_without_auth(
+        state_store.clone() as Arc<dyn StateStore>,
+        cluster_manager,
+        workload_tx,
+    );
+    state.set_service_proxy(proxy.clone());
+    let router = build_router(state);
+    let send = |method: &str, uri: String, body: Option<serde_json::Value>| {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        let body = body.map_or_else(Body::empty, |b| Body::from(b.to_string()));
+        router.clone().oneshot(request.body(body).unwrap())
+    };
+    let read = |response: axum::response::Response| async move {
+        let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+            .await
+            .unwrap();
+        serde_json::from_slice::<ServiceResponse>(&body).unwrap()
+    };
+
+    let create = serde_json::json!({
+        "name": "web",
+        "selector": {"app": "web"},
+        "ports": [{"port": 80, "target_port": 8080}],
+        "session_affinity": {"type": "client_ip", "timeout_seconds": 60},
+    });
+    let response = send("POST", "/api/v1/services".into(), Some(create.clone()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created = read(response).await;
+    assert_eq!(created.cluster_ips, ["10.96.0.1"]);
+    assert_eq!(created.ports[0].protocol, "tcp");
+
+    let response = send("POST", "/api/v1/services".into(), Some(create))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = send(
+        "POST",
+        "/api/v1/services".into(),
+        Some(serde_json::json!({"name": "none", "selector": {}, "ports": [{"port": 80}]})),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Stored, so another node's proxy serves it too
+    let other = ServiceProxy::new(state_store.clone());
+    other.sync().await.unwrap();
+    assert_eq!(other.list_services()[0].id, created.id);
+
+    let uri = format!("/api/v1/services/{}", created.id);
+    let update = serde_json::json!({
+        "selector": {"app": "web", "track": "stable"},
+        "ports": [{"port": 443, "target_port": 8443}],
+    });
+    let response = send("PUT", uri.clone(), Some(update)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated = read(response).await;
+    assert_eq!(updated.cluster_ips, created.cluster_ips);
+    assert_eq!(updated.ports[0].port, 443);
+    assert_eq!(
+        proxy.get_service(&created.id).unwrap().ports[0].target_port,
+        8443
+    );
+
+    let response = send("GET", "/api/v1/services?namespace=default".into(), None)
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    let listed: Vec<ServiceResponse> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].selector["track"], "stable");
+
+    let response = send("DELETE", uri.clone(), None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = send("GET", uri, None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(proxy.list_services().is_empty());
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_service_endpoints_report_readiness() {
+    use orchestrator_core::api::handlers::ServiceEndpointsResponse;
+    use orchestrator_core::network::{Service, ServiceProxy};
+    use orchestrator_shared_types::{
+        ContainerConfig, Keypair, Probe, ProbeAction, WorkloadInstance, WorkloadInstanceStatus,
+    };
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+
+    let state_store = Arc::new(InMemoryStateStore::new());
+    let workload = WorkloadDefinition {
+        id: Uuid::new_v4(),
+        name: "web".to_string(),
+        containers: vec![ContainerConfig {
+            name: "web".to_string(),
+            image: "nginx:latest".to_string(),
+            command: None,
+            args: None,
+            env_vars: HashMap::new(),
+            ports: vec![],
+            resource_requests: NodeResources::default(),
+            volume_mounts: vec![],
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+            readiness_probe: Some(Probe::new(ProbeAction::HttpGet {
+                port: 80,
+                path: "/".to_string(),
+            })),
+            limits: Default::default(),
+            sysctls: HashMap::new(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
+        }],
+        replicas: 2,
+        labels: HashMap::from([("app".to_string(), "web".to_string())]),
+        init_containers: vec![],
+        sidecars: vec![],
+        placement: Default::default(),
+        restart_policy: Default::default(),
+        namespace: "default".to_string(),
+        resource_version: 0,
+    };
+    let mut instance_ids = Vec::new();
+    for _ in 0..2 {
+        let instance = WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: workload.id,
+            node_id: Keypair::generate().public_key(),
+            container_ids: vec![],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        instance_ids.push(instance.id);
+        state_store.put_instance(instance).await.unwrap();
+    }
+    state_store.put_workload(workload).await.unwrap();
+
+    let proxy = Arc::new(ServiceProxy::new(state_store.clone()));
+    let service = proxy
+        .add_service(Service::new(
+            "web",
+            HashMap::from([("app".to_string(), "web".to_string())]),
+        ))
+        .await
+        .unwrap();
+    proxy.set_instance_ready(instance_ids[0], true);
+    proxy.sync().await.unwrap();
+
+    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
+        Arc::new(mock::MockClusterManager);
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let mut state = ApiState::new_without_auth(
+        state_store as Arc<dyn StateStore>,
+        cluster_manager,
+        workload_tx,
+    );
+    state.set_service_proxy(proxy);
+    let router = build_router(state);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/api/v1/services/{}/endpoints", service.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let endpoints: ServiceEndpointsResponse = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(endpoints.service_name, "web");
+    assert_eq!(endpoints.endpoints.len(), 2);
+    assert_eq!(endpoints.ready_count, 1);
+    assert_eq!(endpoints.not_ready_count, 1);
+    let ready = endpoints.endpoints.iter().find(|e| e.ready).unwrap();
+    assert_eq!(ready.instance_id, instance_ids[0]);
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/api/v1/services/{}/endpoints", Uuid::new_v4()))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_drain_node_respects_disruption_budget() {
+    use orchestrator_core::api::handlers::{DisruptionBudgetResponse, DrainNodeResponse};
+    use orchestrator_core::disruption::DisruptionController;
+    use orchestrator_shared_types::{Keypair, WorkloadInstance, WorkloadInstanceStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+
+    let state_store = Arc::new(InMemoryStateStore::new());
+    let node_id = Keypair::generate().public_key();
+    state_store
+        .put_node(Node {
+            id: node_id,
+            address: "10.0.0.1:8080".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            resources_capacity: NodeResources::default(),
+            resources_allocatable: NodeResources::default(),
+            unschedulable: false,
+            resource_version: 0,
+        })
+        .await
+        .unwrap();
+    let workload = WorkloadDefinition {
+        id: Uuid::new_v4(),
+        name: "web".to_string(),
+        containers: vec![],
+        replicas: 2,
+        labels: HashMap::from([("app".to_string(), "web".to_string())]),
+        init_containers: vec![],
+        sidecars: vec![],
+        placement: Default::default(),
+        restart_policy: Default::default(),
+        namespace: "default".to_string(),
+        resource_version: 0,
+    };
+    for _ in 0..2 {
+        state_store
+            .put_instance(WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id: workload.id,
+                node_id,
+                container_ids: vec![],
+                status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
+            })
+            .await
+            .unwrap();
+    }
+    state_store.put_workload(workload).await.unwrap();
+
+    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
+        Arc::new(mock::MockClusterManager);
+    let (workload_tx, mut workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let mut state = ApiState::new_without_auth(
+        state_store.clone() as Arc<dyn StateStore>,
+        cluster_manager,
+        workload_tx,
+    );
+    state.set_disruption_controller(Arc::new(DisruptionController::new(
+        state_store.clone(),
+        Arc::new(mock::NoopRuntime),
+    )));
+    let router = build_router(state);
+
+    let create = serde_json::json!({
+        "name": "web",
+        "selector": {"app": "web"},
+        "min_available": 1
+    });
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/disruption-budgets")
+                .header("Content-Type", "application/json")
+                .body(Body::from(create.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let budget: DisruptionBudgetResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(budget.status.expected, 2);
+    assert_eq!(budget.status.disruptions_allowed, 1);
+
+    let drain = || {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/api/v1/nodes/{}/drain", node_id))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = router.clone().oneshot(drain()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let report: DrainNodeResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(report.evicted.len(), 1);
+    assert_eq!(report.blocked.len(), 1);
+    assert!(!report.complete);
+
+    // The node is cordoned and the workload resubmitted for a replacement
+    assert!(state_store.get_node(&node_id).await.unwrap().unwrap().unschedulable);
+    assert_eq!(workload_rx.try_recv().unwrap().name, "web");
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/api/v1/disruption-budgets/{}", budget.budget.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = router.clone().oneshot(drain()).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let report: DrainNodeResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(report.evicted.len(), 1);
+    assert!(report.complete);
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/nodes/{}/uncordon", node_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let node: NodeResponse = serde_json::from_slice(&body).unwrap();
+    assert!(!node.unschedulable);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_network_policies() {
+    use orchestrator_core::network::{NetworkPolicy, NetworkPolicyController};
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    let (mut state, _rx) = create_test_state();
+    let firewall = Arc::new(mock::RecordingFirewall::default());
+    state.set_network_policy_controller(Arc::new(NetworkPolicyController::new(
+        Arc::new(InMemoryStateStore::new()),
+        firewall.clone(),
+    )));
+    let router = build_router(state);
+
+    let create = serde_json::json!({
+        "name": "payments",
+        "selector": {"app": "payments"},
+        "ingress": [{"selector": {"app": "gateway"}, "ports": [{"port": 8443}]}]
+    });
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/network-policies")
+                .header("Content-Type", "application/json")
+                .body(Body::from(create.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let policy: NetworkPolicy = serde_json::from_slice(&body).unwrap();
+    assert_eq!(policy.namespace, "default");
+    assert_eq!(policy.ingress.as_ref().unwrap()[0].ports[0].protocol, "tcp");
+    assert!(firewall.ruleset.lock().unwrap().is_some());
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/network-policies")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let policies: Vec<NetworkPolicy> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(policies, vec![policy.clone()]);
+
+    // A policy must restrict some direction
+    let invalid = serde_json::json!({"name": "empty", "selector": {"app": "web"}});
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/network-policies")
+                .header("Content-Type", "application/json")
+                .body(Body::from(invalid.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let uri = format!("/api/v1/network-policies/{}", policy.id);
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = router
+        .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_cron_jobs() {
+    use orchestrator_core::api::handlers::CronJobResponse;
+    use orchestrator_core::jobs::CronJobController;
+
+    let (mut state, _rx) = create_test_state();
+    state.set_cron_job_controller(Arc::new(CronJobController::new(
+        state.state_store.clone(),
+        Arc::new(mock::NoopRuntime),
+        state.workload_tx.clone(),
+    )));
+    let router = build_router(state);
+
+    let create = serde_json::json!({
+        "name": "backup",
+        "schedule": "0 2 * * *",
+        "template": {
+            "name": "backup",
+            "containers": [{"name": "backup", "image": "backup:latest"}],
+            "replicas": 1
+        },
+        "concurrency_policy": "Forbid"
+    });
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/cronjobs")
+                .header("Content-Type", "application/json")
+                .body(Body::from(create.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let cron_job: CronJobResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cron_job.namespace, "default");
+    assert_eq!(cron_job.concurrency_policy, "Forbid");
+    assert!(!cron_job.suspend);
+    assert!(cron_job.jobs.is_empty());
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/cronjobs")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let cron_jobs: Vec<CronJobResponse> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cron_jobs.len(), 1);
+    assert_eq!(cron_jobs[0].id, cron_job.id);
+
+    // The schedule must be a valid cron expression
+    let mut invalid = create.clone();
+    invalid["schedule"] = serde_json::json!("every night");
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/cronjobs")
+                .header("Content-Type", "application/json")
+                .body(Body::from(invalid.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let uri = format!("/api/v1/cronjobs/{}", cron_job.id);
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("{}/suspend", uri))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let suspended: CronJobResponse = serde_json::from_slice(&body).unwrap();
+    assert!(suspended.suspend);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = router
+        .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_daemon_sets() {
+    use orchestrator_core::api::handlers::DaemonSetResponse;
+    use orchestrator_core::controllers::DaemonSetController;
+
+    let (mut state, _rx) = create_test_state();
+    state.set_daemon_set_controller(Arc::new(DaemonSetController::new(
+        state.state_store.clone(),
+        Arc::new(mock::NoopRuntime),
+        Arc::new(mock::MockClusterManager),
+    )));
+    let router = build_router(state);
+
+    let create = serde_json::json!({
+        "name": "log-shipper",
+        "template": {
+            "name": "log-shipper",
+            "containers": [{"name": "shipper", "image": "vector:latest"}],
+            "replicas": 1
+        },
+        "node_selector": {"role": "worker"}
+    });
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/daemonsets")
+                .header("Content-Type", "application/json")
+                .body(Body::from(create.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let daemon_set: DaemonSetResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(daemon_set.namespace, "default");
+    assert_eq!(
+        daemon_set.node_selector.get("role").map(String::as_str),
+        Some("worker")
+    );
+    // The cluster has no nodes to run it on yet
+    assert!(daemon_set.instances.is_empty());
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/daemonsets")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let daemon_sets: Vec<DaemonSetResponse> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(daemon_sets.len(), 1);
+    assert_eq!(daemon_sets[0].workload_id, daemon_set.workload_id);
+
+    let uri = format!("/api/v1/daemonsets/{}", daemon_set.id);
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = router
+        .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_prepull_image_reports_node_progress() {
+    use orchestrator_core::prepull::{ImagePrepuller, NodePullState, Prepull};
+    use orchestrator_shared_types::Keypair;
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+
+    let state_store = Arc::new(InMemoryStateStore::new());
+    let node_id = Keypair::generate().public_key();
+    state_store
+        .put_node(Node {
+            id: node_id,
+            address: "10.0.0.1:8080".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::from([("gpu".to_string(), "true".to_string())]),
+            resources_capacity: NodeResources::default(),
+            resources_allocatable: NodeResources::default(),
+            unschedulable: false,
+            resource_version: 0,
+        })
+        .await
+        .unwrap();
+
+    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
+        Arc::new(mock::MockClusterManager);
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let mut state = ApiState::new_without_auth(
+        state_store.clone() as Arc<dyn StateStore>,
+        cluster_manager,
+        workload_tx,
+    );
+    state.set_image_prepuller(Arc::new(ImagePrepuller::new(
+        state_store,
+        Arc::new(mock::NoopRuntime),
+    )));
+    let router = build_router(state);
+
+    let prepull = |body: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/images/prepull")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let response = router
+        .clone()
+        .oneshot(prepull(serde_json::json!({
+            "image": "vllm/vllm-openai:v0.6.0",
+            "node_selector": {"gpu": "true"}
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let started: Prepull = serde_json::from_slice(&body).unwrap();
+    assert_eq!(started.nodes.len(), 1);
+    assert_eq!(started.nodes[0].node_id, node_id);
+
+    let uri = format!("/api/v1/images/prepull/{}", started.id);
+    let mut progress = started;
+    for _ in 0..100 {
+        let response = router
+            .clone()
+            .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        progress = serde_json::from_slice(&body).unwrap();
+        if progress.is_complete() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(progress.nodes[0].state, NodePullState::Pulled);
+
+    // No node has the selected label
+    let response = router
+        .clone()
+        .oneshot(prepull(serde_json::json!({
+            "image": "vllm/vllm-openai:v0.6.0",
+            "node_selector": {"gpu": "h100"}
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/images/prepull/{}", Uuid::new_v4()))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_inspect_image() {
+    use orchestrator_core::api::handlers::ImageInspectionResponse;
+
+    let (mut state, _workload_rx) = create_test_state();
+    state.set_runtime(Arc::new(mock::NoopRuntime));
+    let router = build_router(state);
+
+    // `/` in the reference is percent-encoded into a single path segment
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/images/ghcr.io%2Fowner%2Fapp:v1/inspect")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let image: ImageInspectionResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(image.reference, "ghcr.io/owner/app:v1");
+    assert_eq!(image.digest, "sha256:abc");
+    assert_eq!(image.size_bytes, 500);
+    assert_eq!(image.layers.len(), 2);
+    assert_eq!(image.exposed_ports, vec!["8000/tcp"]);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_image_scans_and_severity_admission() {
+    use container_runtime_interface::Severity;
+    use orchestrator_core::admission::AdmissionLimits;
+    use orchestrator_core::api::handlers::ImageScanResponse;
+
+    let (mut state, _workload_rx) = create_test_state();
+    state.set_runtime(Arc::new(mock::NoopRuntime));
+    state.set_admission_limits(
+        AdmissionLimits::unlimited().with_max_image_severity(Severity::High),
+    );
+    let router = build_router(state);
+
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let response = router.clone().oneshot(get("/api/v1/images/vulnerable:v1/scan")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let scan: ImageScanResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(scan.digest, "sha256:abc");
+    assert_eq!(scan.summary.critical, 1);
+    assert_eq!(scan.vulnerabilities[0].id, "CVE-2024-3094");
+
+    // Listing summarizes scans without their vulnerabilities
+    let response = router.clone().oneshot(get("/api/v1/images/scans")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let scans: Vec<ImageScanResponse> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(scans.len(), 1);
+    assert_eq!(scans[0].summary.critical, 1);
+    assert!(scans[0].vulnerabilities.is_empty());
+
+    let post = |body: String| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/workloads")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let vulnerable = create_workload_json().replace("nginx:latest", "vulnerable:v1");
+    let response = router.clone().oneshot(post(vulnerable)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error["code"], "VULNERABLE_IMAGE");
+    assert_eq!(error["details"]["max_severity"], "high");
+
+    let response = router.oneshot(post(create_workload_json())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_admin_fsck_reports_and_repairs() {
+    use orchestrator_core::api::handlers::FsckResponse;
+    use orchestrator_shared_types::{Keypair, WorkloadInstance, WorkloadInstanceStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+
+    // An instance whose workload was deleted behind the API's back
+    let state_store = Arc::new(InMemoryStateStore::new());
+    state_store
+        .put_instance(WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id: Uuid::new_v4(),
+            node_id: Keypair::generate().public_key(),
+            container_ids: vec![],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        })
+        .await
+        .unwrap();
+
+    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
+        Arc::new(mock::MockClusterManager);
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let mut state = ApiState::new_without_auth(
+        state_store.clone() as Arc<dyn StateStore>,
+        cluster_manager,
+        workload_tx,
+    );
+    state.set_runtime(Arc::new(mock::NoopRuntime));
+    let router = build_router(state);
+
+    let fsck = |method: &str| {
+        Request::builder()
+            .method(method)
+            .uri("/api/v1/admin/fsck")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = router.clone().oneshot(fsck("GET")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let report: FsckResponse = serde_json::from_slice(&body).unwrap();
+    assert!(!report.clean);
+    assert_eq!(report.discrepancies[0].kind, "instance_of_deleted_workload");
+    // The mock cluster manager keeps no node leases
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(state_store.list_all_instances().await.unwrap().len(), 1);
+
+    let response = router.oneshot(fsck("POST")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let report: FsckResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(report.repaired, 1);
+    assert!(state_store.list_all_instances().await.unwrap().is_empty());
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_cluster_backup_and_restore() {
+    use orchestrator_core::api::handlers::RestoreResponse;
+
+    let (source_state, _source_rx) = create_test_state();
+    let source = build_router(source_state);
+    let response = source
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/workloads")
+                .header("Content-Type", "application/json")
+                .body(Body::from(create_workload_json()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = source
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/cluster/backup")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let backup = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+
+    // Restore into a fresh control plane
+    let (target_state, mut target_rx) = create_test_state();
+    let target = build_router(target_state);
+    let restore = |backup: axum::body::Bytes| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/cluster/backup/restore")
+            .header("Content-Type", "application/json")
+            .body(Body::from(backup))
+            .unwrap()
+    };
+
+    let response = target.clone().oneshot(restore(backup.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let restored: RestoreResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(restored.workloads, 1);
+    assert_eq!(restored.nodes, 0);
+    // The workload's history comes along
+    assert_eq!(restored.revisions, 1);
+    // Restored workloads are handed to the orchestrator
+    assert_eq!(target_rx.recv().await.unwrap().name, "test-workload");
+
+    let response = target
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/workloads")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let list: ListResponse<WorkloadResponse> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(list.count, 1);
+
+    // The target is no longer empty
+    let response = target.oneshot(restore(backup)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_watch_workloads_streams_events() {
+    use http_body_util::BodyExt;
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+
+    let state_store = Arc::new(InMemoryStateStore::new());
+    let workload = WorkloadDefinition {
+        id: Uuid::new_v4(),
+        name: "watched".to_string(),
+        containers: vec![],
+        replicas: 1,
+        labels: HashMap::new(),
+        init_containers: vec![],
+        sidecars: vec![],
+        placement: Default::default(),
+        restart_policy: Default::default(),
+        namespace: "default".to_string(),
+        resource_version: 0,
+    };
+    state_store.put_workload(workload.clone()).await.unwrap();
+
+    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
+        Arc::new(mock::MockClusterManager);
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let state = ApiState::new_without_auth(
+        state_store.clone() as Arc<dyn StateStore>,
+        cluster_manager,
+        workload_tx,
+    );
+    let router = build_router(state);
+
+    let request = Request::builder()
+        .uri("/api/v1/workloads?watch=true")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut body = response.into_body();
+
+    // Read until an event of the given type arrives
+    async fn next_event(body: &mut Body, event_type: &str) -> String {
+        let mut seen = String::new();
+        loop {
+            let frame = tokio::time::timeout(std::time::Duration::from_secs(5), body.frame())
+                .await
+                .expect("no watch event")
+                .unwrap()
+                .unwrap();
+            if let Ok(data) = frame.into_data() {
+                seen.push_str(&String::from_utf8_lossy(&data));
+            }
+            if seen.contains(&format!("event: {}", event_type)) {
+                return seen;
+            }
+        }
+    }
+
+    let added = next_event(&mut body, "ADDED").await;
+    assert!(added.contains("\"watched\""));
+
+    state_store.delete_workload(&workload.id).await.unwrap();
+    let deleted = next_event(&mut body, "DELETED").await;
+    assert!(deleted.contains(&workload.id.to_string()));
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_watch_instances_in_namespace() {
+    use http_body_util::BodyExt;
+    use orchestrator_shared_types::{Keypair, WorkloadInstance, WorkloadInstanceStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+
+    let state_store = Arc::new(InMemoryStateStore::new());
+    let instance = |namespace: &str| WorkloadInstance {
+        id: Uuid::new_v4(),
+        workload_id: Uuid::new_v4(),
+        node_id: Keypair::generate().public_key(),
+        container_ids: vec![],
+        status: WorkloadInstanceStatus::Pending,
+        ip_addresses: vec![],
+        restarts: vec![],
+        namespace: namespace.to_string(),
+        resource_version: 0,
+    };
+    let other = instance("other");
+    state_store.put_instance(other.clone()).await.unwrap();
+
+    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
+        Arc::new(mock::MockClusterManager);
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let state = ApiState::new_without_auth(
+        state_store.clone() as Arc<dyn StateStore>,
+        cluster_manager,
+        workload_tx,
+    );
+    let router = build_router(state);
+
+    let request = Request::builder()
+        .uri("/api/v1/instances?watch=true&labelSelector=app%3Dweb")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let request = Request::builder()
+        .uri("/api/v1/instances?watch=true&namespace=default")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let mut body = response.into_body();
+
+    // Changes in other namespaces are left out of the stream
+    state_store.put_instance(instance("other")).await.unwrap();
+    let watched = instance("default");
+    state_store.put_instance(watched.clone()).await.unwrap();
+    let mut seen = String::new();
+    while !seen.contains(&watched.id.to_string()) {
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), body.frame())
+            .await
+            .expect("no watch event")
+            .unwrap()
+            .unwrap();
+        if let Ok(data) = frame.into_data() {
+            seen.push_str(&String::from_utf8_lossy(&data));
+        }
+    }
+    assert!(!seen.contains(&other.id.to_string()));
+    assert!(!seen.contains("\"other\""));
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_search_workload_logs() {
+    use orchestrator_core::api::handlers::LogSearchHit;
+    use orchestrator_shared_types::{Keypair, WorkloadInstance, WorkloadInstanceStatus};
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+
+    let state_store = Arc::new(InMemoryStateStore::new());
+    let workload = WorkloadDefinition {
+        id: Uuid::new_v4(),
+        name: "searched".to_string(),
+        containers: vec![],
+        replicas: 2,
+        labels: HashMap::new(),
+        init_containers: vec![],
+        sidecars: vec![],
+        placement: Default::default(),
+        restart_policy: Default::default(),
+        namespace: "default".to_string(),
+        resource_version: 0,
+    };
+    state_store.put_workload(workload.clone()).await.unwrap();
+    let instance_id = Uuid::new_v4();
+    state_store
+        .put_instance(WorkloadInstance {
+            id: instance_id,
+            workload_id: workload.id,
+            node_id: Keypair::generate().public_key(),
+            container_ids: vec!["c1".to_string(), "c2".to_string()],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        })
+        .await
+        .unwrap();
+
+    let runtime = mock::LogRuntime {
+        logs: HashMap::from([
+            (
+                "c1".to_string(),
+                "2024-01-01T00:00:01Z stdout request ok\n\
+                 2024-01-01T00:00:03Z stderr request failed"
+                    .to_string(),
+            ),
+            (
+                "c2".to_string(),
+                "2024-01-01T00:00:02Z stdout request failed".to_string(),
+            ),
+            (
+                "old".to_string(),
+                "2023-12-31T23:59:59Z stdout request failed".to_string(),
+            ),
+        ]),
+        archived: vec!["old".to_string()],
+    };
+    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
+        Arc::new(mock::MockClusterManager);
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let mut state = ApiState::new_without_auth(
+        state_store.clone() as Arc<dyn StateStore>,
+        cluster_manager,
+        workload_tx,
+    );
+    state.set_runtime(Arc::new(runtime));
+    let router = build_router(state);
+
+    let search = |query: &str| {
+        Request::builder()
+            .uri(format!(
+                "/api/v1/workloads/{}/logs/search?{}",
+                workload.id, query
+            ))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = router.clone().oneshot(search("q=failed")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let hits: Vec<LogSearchHit> = String::from_utf8_lossy(&body)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let containers: Vec<&str> = hits.iter().map(|h| h.container_id.as_str()).collect();
+    assert_eq!(containers, ["old", "c2", "c1"]);
+    assert_eq!(hits[0].instance_id, None);
+    assert_eq!(hits[1].instance_id, Some(instance_id));
+    assert_eq!(hits[2].stream, "stderr");
+
+    let response = router
+        .clone()
+        .oneshot(search(
+            "q=request%20(ok%7Cfailed)&regex=true&since=2024-01-01T00:00:01Z",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    assert_eq!(String::from_utf8_lossy(&body).lines().count(), 3);
+
+    let response = router.oneshot(search("q=(&regex=true")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_namespaces_scope_and_cascade() {
+    use orchestrator_core::api::handlers::NamespaceResponse;
+
+    let (state, _workload_rx) = create_test_state();
+    let router = build_router(state);
+    let send = |method: &str, uri: &str, body: Option<serde_json::Value>| {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json");
+        match body {
+            Some(body) => builder.body(Body::from(body.to_string())).unwrap(),
+            None => builder.body(Body::empty()).unwrap(),
+        }
+    };
+    let workload_in = |namespace: &str| {
+        let mut workload: serde_json::Value =
+            serde_json::from_str(&create_workload_json()).unwrap();
+        workload["namespace"] = serde_json::json!(namespace);
+        workload
+    };
+
+    // Unknown namespaces must be created first
+    let response = router
+        .clone()
+        .oneshot(send(
+            "POST",
+            "/api/v1/workloads",
+            Some(workload_in("team-a")),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = router
+        .clone()
+        .oneshot(send(
+            "POST",
+            "/api/v1/namespaces",
+            Some(serde_json::json!({"name": "team-a"})),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = router
+        .clone()
+        .oneshot(send(
+            "POST",
+            "/api/v1/namespaces",
+            Some(serde_json::json!({"name": "team-a"})),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = router
+        .clone()
+        .oneshot(send(
+            "POST",
+            "/api/v1/namespaces",
+            Some(serde_json::json!({"name": "Team_A"})),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // The same workload name in two namespaces
+    for namespace in ["default", "team-a"] {
+        let response = router
+            .clone()
+            .oneshot(send(
+                "POST",
+                "/api/v1/workloads",
+                Some(workload_in(namespace)),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    let list = |uri: &'static str| {
+        let router = router.clone();
+        async move {
+            let response = router.oneshot(send("GET", uri, None)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+                .await
+                .unwrap();
+            serde_json::from_slice::<ListResponse<WorkloadResponse>>(&body).unwrap()
+        }
+    };
+    assert_eq!(list("/api/v1/workloads").await.count, 2);
+    let scoped = list("/api/v1/workloads?namespace=team-a").await;
+    assert_eq!(scoped.count, 1);
+    assert_eq!(scoped.items[0].namespace, "team-a");
+
+    // Outside the requested namespace a workload is not found
+    let uri = format!("/api/v1/workloads/{}?namespace=default", scoped.items[0].id);
+    let response = router
+        .clone()
+        .oneshot(send("GET", &uri, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = router
+        .clone()
+        .oneshot(send("GET", "/api/v1/namespaces", None))
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    let namespaces: ListResponse<NamespaceResponse> = serde_json::from_slice(&body).unwrap();
+    let names: Vec<_> = namespaces.items.iter().map(|ns| ns.name.as_str()).collect();
+    assert_eq!(names, vec!["default", "team-a"]);
+
+    // Deleting a namespace removes its workloads
+    let response = router
+        .clone()
+        .oneshot(send("DELETE", "/api/v1/namespaces/team-a", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let remaining = list("/api/v1/workloads").await;
+    assert_eq!(remaining.count, 1);
+    assert_eq!(remaining.items[0].namespace, "default");
+
+    let response = router
+        .clone()
+        .oneshot(send("DELETE", "/api/v1/namespaces/default", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_rbac_denies_what_bindings_do_not_grant() {
+    use orchestrator_core::api::handlers::WhoAmIResponse;
+    use orchestrator_core::api::{RbacPolicy, Role, RoleBinding};
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    let admin = SigningKey::generate(&mut OsRng);
+    let dev = SigningKey::generate(&mut OsRng);
+    let stranger = SigningKey::generate(&mut OsRng);
+    let subject = |key: &SigningKey| BASE64_STANDARD.encode(key.verifying_key().as_bytes());
+    let policy = RbacPolicy::new()
+        .with_binding(RoleBinding {
+            subject: subject(&admin),
+            role: Role::Admin,
+            namespace: None,
+        })
+        .with_binding(RoleBinding {
+            subject: subject(&dev),
+            role: Role::Operator,
+            namespace: Some("ml".to_string()),
+        });
+
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let state = ApiState::new(
+        Arc::new(InMemoryStateStore::new()),
+        Arc::new(mock::MockClusterManager),
+        workload_tx,
+        AuthConfig::default().with_rbac(policy),
+    );
+    let router = build_router(state);
+    let send = |key: &SigningKey, method: &str, uri: &str, body: Option<serde_json::Value>| {
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        let path = uri.split('?').next().unwrap();
+        let (pub_key, timestamp, signature) = sign_request(method, path, body.as_bytes(), key);
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("X-Auth-PublicKey", pub_key)
+            .header("X-Auth-Timestamp", timestamp)
+            .header("X-Auth-Signature", signature)
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let workload_in = |namespace: &str| {
+        let mut workload: serde_json::Value =
+            serde_json::from_str(&create_workload_json()).unwrap();
+        workload["namespace"] = serde_json::json!(namespace);
+        workload
+    };
+    let status = |request: Request<Body>| {
+        let router = router.clone();
+        async move { router.oneshot(request).await.unwrap().status() }
+    };
+
+    // Only a cluster admin may create namespaces
+    let namespace = serde_json::json!({"name": "ml"});
+    assert_eq!(
+        status(send(&dev, "POST", "/api/v1/namespaces", Some(namespace.clone()))).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        status(send(&admin, "POST", "/api/v1/namespaces", Some(namespace))).await,
+        StatusCode::CREATED
+    );
+
+    // An operator bound to ml can deploy there and nowhere else
+    assert_eq!(
+        status(send(&dev, "POST", "/api/v1/workloads", Some(workload_in("ml")))).await,
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        status(send(&dev, "POST", "/api/v1/workloads", Some(workload_in("default")))).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        status(send(&dev, "GET", "/api/v1/workloads?namespace=ml", None)).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(send(&dev, "GET", "/api/v1/workloads", None)).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        status(send(&dev, "GET", "/api/v1/nodes", None)).await,
+        StatusCode::FORBIDDEN
+    );
+
+    // Authenticated keys without bindings are denied everything but whoami
+    assert_eq!(
+        status(send(&stranger, "GET", "/api/v1/workloads?namespace=ml", None)).await,
+        StatusCode::FORBIDDEN
+    );
+    let response = router
+        .clone()
+        .oneshot(send(&dev, "GET", "/api/v1/auth/whoami", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let whoami: WhoAmIResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(whoami.subject, subject(&dev));
+    assert!(whoami.authenticated && whoami.rbac_enabled);
+    assert_eq!(whoami.bindings.len(), 1);
+    assert_eq!(whoami.bindings[0].namespace.as_deref(), Some("ml"));
+
+    let response = router
+        .oneshot(send(&stranger, "GET", "/api/v1/auth/whoami", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let whoami: WhoAmIResponse = serde_json::from_slice(&body).unwrap();
+    assert!(whoami.bindings.is_empty());
+}
 
-    // Add some nodes to state store
-    let node1 = Node {
-        id: Uuid::new_v4(),
-        address: "10.0.0.1:8080".to_string(),
-        status: NodeStatus::Ready,
-        labels: HashMap::new(),
-        resources_capacity: NodeResources {
-            cpu_cores: 4.0,
-            memory_mb: 8192,
-            disk_mb: 102400,
-        },
-        resources_allocatable: NodeResources {
-            cpu_cores: 3.6,
-            memory_mb: 7372,
-            disk_mb: 92160,
-        },
-    };
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_login_token_authenticates_requests() {
+    use orchestrator_core::api::handlers::{TokenResponse, WhoAmIResponse};
+    use orchestrator_core::api::LocalTokenIssuer;
+    use state_store_interface::in_memory::InMemoryStateStore;
 
-    let node2 = Node {
-        id: Uuid::new_v4(),
-        address: "10.0.0.2:8080".to_string(),
-        status: NodeStatus::NotReady,
-        labels: HashMap::new(),
-        resources_capacity: NodeResources {
-            cpu_cores: 2.0,
-            memory_mb: 4096,
-            disk_mb: 51200,
-        },
-        resources_allocatable: NodeResources {
-            cpu_cores: 1.8,
-            memory_mb: 3686,
-            disk_mb: 46080,
-        },
+    let signing_key = SigningKey::generate(&mut OsRng);
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let state = ApiState::new(
+        Arc::new(InMemoryStateStore::new()),
+        Arc::new(mock::MockClusterManager),
+        workload_tx,
+        AuthConfig::default()
+            .with_token_issuer(LocalTokenIssuer::new(b"0123456789abcdef0123456789abcdef")),
+    );
+    let router = build_router(state);
+    let bearer = |token: &str, method: &str, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
     };
 
-    state_store.put_node(node1).await.unwrap();
-    state_store.put_node(node2).await.unwrap();
+    let (pub_key, timestamp, signature) =
+        sign_request("POST", "/api/v1/auth/token", b"", &signing_key);
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/token")
+                .header("X-Auth-PublicKey", pub_key.clone())
+                .header("X-Auth-Timestamp", timestamp)
+                .header("X-Auth-Signature", signature)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let token: TokenResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(token.subject, pub_key);
+
+    let response = router
+        .clone()
+        .oneshot(bearer(&token.token, "GET", "/api/v1/auth/whoami"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let whoami: WhoAmIResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(whoami.subject, pub_key);
+
+    // Tokens cannot be traded for fresh ones, and forged tokens are rejected
+    let response = router
+        .clone()
+        .oneshot(bearer(&token.token, "POST", "/api/v1/auth/token"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = router
+        .oneshot(bearer("not.a.token", "GET", "/api/v1/workloads"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_register_node_with_bootstrap_token() {
+    use orchestrator_core::api::handlers::NodeResponse;
+    use orchestrator_shared_types::Keypair;
+    use state_store_interface::in_memory::InMemoryStateStore;
 
-    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
-        Arc::new(mock::MockClusterManager);
     let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let state = ApiState::new(
+        Arc::new(InMemoryStateStore::new()),
+        Arc::new(mock::MockClusterManager),
+        workload_tx,
+        AuthConfig::default().with_bootstrap_token("join-secret"),
+    );
+    let router = build_router(state);
+    let node_id = Keypair::generate().public_key().to_string();
+    let request = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", "Bearer join-secret")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let register = |labels: serde_json::Value| {
+        request(
+            "POST",
+            "/api/v1/nodes/register",
+            serde_json::json!({
+                "id": node_id,
+                "address": "10.0.0.7:7280",
+                "labels": labels,
+                "capacity": { "cpu_cores": 8.0, "memory_mb": 16384, "disk_mb": 102400 },
+            }),
+        )
+    };
 
-    let state = ApiState::new_without_auth(
-        state_store as Arc<dyn state_store_interface::StateStore>,
-        cluster_manager,
+    let response = router
+        .clone()
+        .oneshot(register(serde_json::json!({ "zone": "eu-1" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let node: NodeResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(node.status, "Ready");
+    assert_eq!(node.resources_allocatable.memory_mb, 14745);
+
+    // Re-registering keeps labels set before
+    let response = router
+        .clone()
+        .oneshot(register(serde_json::json!({ "disk": "ssd" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let node: NodeResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(node.labels["zone"], "eu-1");
+    assert_eq!(node.labels["disk"], "ssd");
+
+    // Bootstrap tokens only reach registration
+    let response = router
+        .clone()
+        .oneshot(request("GET", "/api/v1/workloads", serde_json::Value::Null))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let uri = format!("/api/v1/nodes/{}", node_id);
+    let response = router
+        .clone()
+        .oneshot(request("DELETE", &uri, serde_json::Value::Null))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = router
+        .oneshot(request("DELETE", &uri, serde_json::Value::Null))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "mtls")]
+#[tokio::test]
+async fn test_client_certificate_issued_and_accepted() {
+    use orchestrator_core::api::handlers::{CertificateResponse, WhoAmIResponse};
+    use orchestrator_core::api::{ClientCertificate, ClusterCa};
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    let signing_key = SigningKey::generate(&mut OsRng);
+    let ca = Arc::new(ClusterCa::generate("test").unwrap());
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let state = ApiState::new(
+        Arc::new(InMemoryStateStore::new()),
+        Arc::new(mock::MockClusterManager),
         workload_tx,
+        AuthConfig::default().with_certificate_authority(ca.clone()),
     );
     let router = build_router(state);
 
+    let key = rcgen::KeyPair::generate().unwrap();
+    let csr = rcgen::CertificateParams::default()
+        .serialize_request(&key)
+        .unwrap()
+        .pem()
+        .unwrap();
+    let body = serde_json::to_vec(&serde_json::json!({ "csr": csr })).unwrap();
+    let (pub_key, timestamp, signature) =
+        sign_request("POST", "/api/v1/auth/certificate", &body, &signing_key);
     let response = router
+        .clone()
         .oneshot(
             Request::builder()
-                .method("GET")
-                .uri("/api/v1/cluster/status")
-                .body(Body::empty())
+                .method("POST")
+                .uri("/api/v1/auth/certificate")
+                .header("Content-Type", "application/json")
+                .header("X-Auth-PublicKey", pub_key.clone())
+                .header("X-Auth-Timestamp", timestamp)
+                .header("X-Auth-Signature", signature)
+                .body(Body::from(body))
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let issued: CertificateResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(issued.subject, pub_key);
+    assert_eq!(issued.ca_certificate, ca.certificate_pem());
 
+    // The TLS server adds the verified certificate to each request
+    let der = pem_body(&issued.certificate);
+    let certificate = ClientCertificate::from_der(&der).unwrap();
+    assert_eq!(certificate.subject, pub_key);
+    let mut request = Request::builder()
+        .uri("/api/v1/auth/whoami")
+        .body(Body::empty())
+        .unwrap();
+    request.extensions_mut().insert(certificate);
+    let response = router.oneshot(request).await.unwrap();
     assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let whoami: WhoAmIResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(whoami.subject, pub_key);
+}
+
+#[cfg(feature = "mtls")]
+fn pem_body(pem: &str) -> Vec<u8> {
+    let encoded: String = pem.lines().filter(|line| !line.starts_with("-----")).collect();
+    BASE64_STANDARD.decode(encoded).unwrap()
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_audit_log_records_mutating_calls() {
+    use orchestrator_core::api::{AuditLog, AuditRecord, RbacPolicy, Role, RoleBinding};
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    let admin = SigningKey::generate(&mut OsRng);
+    let dev = SigningKey::generate(&mut OsRng);
+    let subject = |key: &SigningKey| BASE64_STANDARD.encode(key.verifying_key().as_bytes());
+    let policy = RbacPolicy::new()
+        .with_binding(RoleBinding {
+            subject: subject(&admin),
+            role: Role::Admin,
+            namespace: None,
+        })
+        .with_binding(RoleBinding {
+            subject: subject(&dev),
+            role: Role::Operator,
+            namespace: Some("ml".to_string()),
+        });
+
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let mut state = ApiState::new(
+        Arc::new(InMemoryStateStore::new()),
+        Arc::new(mock::MockClusterManager),
+        workload_tx,
+        AuthConfig::default().with_rbac(policy),
+    );
+    state.set_audit_log(Arc::new(AuditLog::new(Vec::new())));
+    let router = build_router(state);
+    let send = |key: &SigningKey, method: &str, uri: &str, body: Option<serde_json::Value>| {
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        let path = uri.split('?').next().unwrap();
+        let (pub_key, timestamp, signature) = sign_request(method, path, body.as_bytes(), key);
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("X-Auth-PublicKey", pub_key)
+            .header("X-Auth-Timestamp", timestamp)
+            .header("X-Auth-Signature", signature)
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let audit = |uri: &str| {
+        let request = send(&admin, "GET", uri, None);
+        let router = router.clone();
+        async move {
+            let response = router.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+            serde_json::from_slice::<ListResponse<AuditRecord>>(&body).unwrap()
+        }
+    };
+
+    // A denied create is recorded
+    let workload: serde_json::Value = serde_json::from_str(&create_workload_json()).unwrap();
+    let response = router
+        .clone()
+        .oneshot(send(&dev, "POST", "/api/v1/workloads", Some(workload.clone())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
+    // A create is recorded under the ID from its response
+    let response = router
+        .clone()
+        .oneshot(send(&admin, "POST", "/api/v1/workloads", Some(workload)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
     let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
-    let status: ClusterStatusResponse = serde_json::from_slice(&body).unwrap();
+    let created: WorkloadResponse = serde_json::from_slice(&body).unwrap();
 
-    assert_eq!(status.total_nodes, 2);
-    assert_eq!(status.ready_nodes, 1);
-    assert_eq!(status.not_ready_nodes, 1);
-    assert_eq!(status.total_cpu_capacity, 6.0);
-    assert_eq!(status.total_memory_mb, 12288);
+    let uri = format!("/api/v1/workloads/{}", created.id);
+    let patch = serde_json::json!({"resource_version": created.resource_version, "replicas": 5});
+    let response = router
+        .clone()
+        .oneshot(send(&admin, "PATCH", &uri, Some(patch)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Reads are not recorded
+    let all = audit("/api/v1/audit").await;
+    assert_eq!(all.count, 3);
+
+    let patched = &all.items[0];
+    assert_eq!(patched.method, "PATCH");
+    assert_eq!(patched.subject, subject(&admin));
+    let resource = patched.resource.as_ref().unwrap();
+    assert_eq!(resource.kind, "workload");
+    assert_eq!(resource.name, Some(created.id.to_string()));
+    let replicas = patched.changes.iter().find(|c| c.path == "/replicas").unwrap();
+    assert_eq!(replicas.before, Some(serde_json::json!(2)));
+    assert_eq!(replicas.after, Some(serde_json::json!(5)));
+
+    let create = &all.items[1];
+    assert_eq!(create.status, 201);
+    assert!(create.before.is_none());
+    assert_eq!(
+        create.resource.as_ref().unwrap().name,
+        Some(created.id.to_string())
+    );
+
+    let denied = audit("/api/v1/audit?outcome=denied").await;
+    assert_eq!(denied.count, 1);
+    assert_eq!(denied.items[0].subject, subject(&dev));
+    assert_eq!(denied.items[0].status, 403);
+
+    let patches = audit("/api/v1/audit?kind=workload&method=patch").await;
+    assert_eq!(patches.count, 1);
+
+    // Only cluster admins may read the audit log
+    let response = router
+        .clone()
+        .oneshot(send(&dev, "GET", "/api/v1/audit", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_rate_limit_throttles_each_client() {
+    use orchestrator_core::api::{RateLimitConfig, RateLimiter};
+    use state_store_interface::in_memory::InMemoryStateStore;
+
+    let (workload_tx, _workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let mut state = ApiState::new(
+        Arc::new(InMemoryStateStore::new()),
+        Arc::new(mock::MockClusterManager),
+        workload_tx,
+        AuthConfig::default(),
+    );
+    let limit = RateLimitConfig::per_second(0.5).with_burst(2);
+    state.set_rate_limiter(Arc::new(RateLimiter::new(limit)));
+    let router = build_router(state);
+    let list = |key: &SigningKey| {
+        let (pub_key, timestamp, signature) = sign_request("GET", "/api/v1/nodes", b"", key);
+        let request = Request::builder()
+            .method("GET")
+            .uri("/api/v1/nodes")
+            .header("X-Auth-PublicKey", pub_key)
+            .header("X-Auth-Timestamp", timestamp)
+            .header("X-Auth-Signature", signature)
+            .body(Body::empty())
+            .unwrap();
+        router.clone().oneshot(request)
+    };
+
+    let pipeline = SigningKey::generate(&mut OsRng);
+    let user = SigningKey::generate(&mut OsRng);
+    for _ in 0..2 {
+        assert_eq!(list(&pipeline).await.unwrap().status(), StatusCode::OK);
+    }
+    let response = list(&pipeline).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["retry-after"], "2");
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error["code"], "RATE_LIMITED");
+
+    // Other clients are unaffected
+    assert_eq!(list(&user).await.unwrap().status(), StatusCode::OK);
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_openapi_document_served_without_auth() {
+    let (state, _rx, _signing_key) = create_test_state_with_auth(None);
+    let router = build_router(state);
+
+    let request = Request::builder()
+        .uri("/api/v1/openapi.json")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 4 * 1024 * 1024).await.unwrap();
+    let doc: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+    assert!(doc["paths"]["/api/v1/workloads"]["post"].is_object());
+    assert!(doc["components"]["schemas"]["CreateWorkloadRequest"].is_object());
+}
+
+#[cfg(feature = "rest-api")]
+#[tokio::test]
+async fn test_import_kubernetes_manifests() {
+    use orchestrator_core::api::kubernetes::KubernetesImportResponse;
+    use orchestrator_core::network::ServiceProxy;
+    use state_store_interface::in_memory::InMemoryStateStore;
+    use state_store_interface::StateStore;
+
+    let state_store = Arc::new(InMemoryStateStore::new());
+    let proxy = Arc::new(ServiceProxy::new(state_store.clone()));
+    let cluster_manager: Arc<dyn cluster_manager_interface::ClusterManager> =
+        Arc::new(mock::MockClusterManager);
+    let (workload_tx, mut workload_rx) = mpsc::channel::<WorkloadDefinition>(100);
+    let mut state = ApiState::new_without_auth(
+        state_store as Arc<dyn StateStore>,
+        cluster_manager,
+        workload_tx,
+    );
+    state.set_service_proxy(proxy.clone());
+    let router = build_router(state);
+
+    let manifests = serde_json::json!({
+        "objects": [
+            {"kind": "Namespace", "metadata": {"name": "shop"}},
+            {"kind": "ConfigMap", "metadata": {"name": "web", "namespace": "shop"},
+             "data": {"LEVEL": "debug"}},
+            {
+                "kind": "Deployment",
+                "metadata": {"name": "web", "namespace": "shop"},
+                "spec": {
+                    "replicas": 2,
+                    "template": {
+                        "metadata": {"labels": {"app": "web"}},
+                        "spec": {"containers": [{
+                            "name": "web",
+                            "image": "nginx:1.27",
+                            "ports": [{"name": "http", "containerPort": 8080}],
+                            "envFrom": [{"configMapRef": {"name": "web"}}],
+                            "livenessProbe": {"tcpSocket": {"port": 8080}}
+                        }]}
+                    }
+                }
+            },
+            {
+                "kind": "Service",
+                "metadata": {"name": "web", "namespace": "shop"},
+                "spec": {"selector": {"app": "web"}, "ports": [{"port": 80, "targetPort": "http"}]}
+            }
+        ]
+    });
+    let import = |uri: &str| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(manifests.to_string()))
+            .unwrap()
+    };
+    let read = |response: axum::response::Response| async move {
+        let body = axum::body::to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        serde_json::from_slice::<KubernetesImportResponse>(&body).unwrap()
+    };
+    let get_namespace = || {
+        Request::builder()
+            .uri("/api/v1/namespaces/shop")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // A dry run converts without creating anything
+    let response = router
+        .clone()
+        .oneshot(import("/api/v1/import/kubernetes?dryRun=true"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let converted = read(response).await;
+    assert_eq!(converted.namespaces, ["shop"]);
+    assert_eq!(converted.workloads[0].containers[0].env_vars["LEVEL"], "debug");
+    assert_eq!(converted.services[0].ports[0].target_port, 8080);
+    assert!(converted.services[0].id.is_none());
+    assert!(converted.created.is_empty());
+    assert_eq!(converted.warnings.len(), 1);
+    assert!(converted.warnings[0].contains("livenessProbe is not supported"));
+    assert!(workload_rx.try_recv().is_err());
+    assert!(proxy.list_services().is_empty());
+    let response = router.clone().oneshot(get_namespace()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = router
+        .clone()
+        .oneshot(import("/api/v1/import/kubernetes"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let imported = read(response).await;
+    assert_eq!(imported.created.len(), 1);
+    assert!(imported.services[0].id.is_some());
+
+    let workload = workload_rx.try_recv().unwrap();
+    assert_eq!(workload.namespace, "shop");
+    assert_eq!(workload.replicas, 2);
+    assert_eq!(workload.labels["app"], "web");
+    assert_eq!(proxy.list_services()[0].namespace, "shop");
+    let response = router.oneshot(get_namespace()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+/// Fails when an operation is added, removed or renamed without updating
+/// `tests/snapshots/openapi_operations.txt`; set `UPDATE_SNAPSHOTS=1` to
+/// rewrite it.
+#[cfg(all(feature = "rest-api", feature = "mtls"))]
+#[test]
+fn test_openapi_operations_snapshot() {
+    use orchestrator_core::api::openapi;
+
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots/openapi_operations.txt");
+    let mut actual = openapi::operations(&openapi::openapi()).join("\n");
+    actual.push('\n');
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(path, &actual).unwrap();
+    }
+    let expected = std::fs::read_to_string(path).unwrap();
+    assert_eq!(actual, expected, "OpenAPI operations changed; rerun with UPDATE_SNAPSHOTS=1");
 }
@@ -133,6 +133,7 @@ impl ContainerRuntime for MockContainerRuntime {
                 state: container.state.clone(),
                 exit_code: None,
                 error_message: None,
+                oom_killed: false,
             })
         } else {
             Err(OrchestrationError::RuntimeError(format!(
@@ -152,6 +153,7 @@ impl ContainerRuntime for MockContainerRuntime {
                 state: c.state.clone(),
                 exit_code: None,
                 error_message: None,
+                oom_killed: false,
             })
             .collect())
     }
@@ -236,6 +238,8 @@ fn create_test_node(id: NodeId, status: NodeStatus) -> Node {
             memory_mb: 7000,
             disk_mb: 90000,
         },
+        unschedulable: false,
+        resource_version: 0,
     }
 }
 
@@ -253,15 +257,34 @@ fn create_test_workload(name: &str, replicas: u32) -> WorkloadDefinition {
                 container_port: 8080,
                 host_port: None,
                 protocol: "tcp".to_string(),
+                host_ip: None,
             }],
             resource_requests: NodeResources {
                 cpu_cores: 0.5,
                 memory_mb: 256,
                 disk_mb: 0,
             },
+            volume_mounts: vec![],
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+            readiness_probe: None,
+            limits: Default::default(),
+            sysctls: HashMap::new(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
         }],
         replicas,
         labels: HashMap::new(),
+        init_containers: vec![],
+        sidecars: vec![],
+        placement: Default::default(),
+        restart_policy: Default::default(),
+        namespace: "default".to_string(),
+        resource_version: 0,
     }
 }
 
@@ -532,6 +555,12 @@ async fn test_workload_without_containers_definition() {
         containers: vec![], // No container definitions
         replicas: 2,
         labels: HashMap::new(),
+        init_containers: vec![],
+        sidecars: vec![],
+        placement: Default::default(),
+        restart_policy: Default::default(),
+        namespace: "default".to_string(),
+        resource_version: 0,
     };
     let workload_id = workload.id;
 
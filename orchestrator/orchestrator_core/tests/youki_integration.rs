@@ -43,6 +43,13 @@ mod tests {
             state_root: temp_dir.path().join("state"),
             command_timeout: Duration::from_secs(60),
             stop_timeout: Duration::from_secs(10),
+            pull_queue: Default::default(),
+            log_archive_root: Some(temp_dir.path().join("log-archive")),
+            log_rotation: Default::default(),
+            event_poll_interval: Duration::from_secs(1),
+            checkpoint_root: temp_dir.path().join("checkpoints"),
+            checkpoint_timeout: Duration::from_secs(300),
+            ..Default::default()
         };
 
         YoukiCliRuntime::with_config(config).await.map_err(|e| e.to_string())
@@ -87,6 +94,7 @@ mod tests {
                     container_port: 80,
                     host_port: None,
                     protocol: "tcp".to_string(),
+                    host_ip: None,
                 },
             ],
             resource_requests: NodeResources {
@@ -94,11 +102,24 @@ mod tests {
                 memory_mb: 128,
                 disk_mb: 256,
             },
+            volume_mounts: vec![],
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+            readiness_probe: None,
+            limits: Default::default(),
+            sysctls: HashMap::new(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
         };
 
         let options = CreateContainerOptions {
             node_id,
             workload_id,
+            pull_priority: Default::default(),
         };
 
         // Create and start container
@@ -209,11 +230,24 @@ mod tests {
                 memory_mb: 32,
                 disk_mb: 64,
             },
+            volume_mounts: vec![],
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+            readiness_probe: None,
+            limits: Default::default(),
+            sysctls: HashMap::new(),
+            rlimits: vec![],
+            shm_size_mb: None,
+            hostname: None,
+            host_aliases: vec![],
+            dns: Default::default(),
+            annotations: Default::default(),
         };
 
         let options = CreateContainerOptions {
             node_id,
             workload_id,
+            pull_priority: Default::default(),
         };
 
         println!("Creating busybox container...");
@@ -254,6 +288,13 @@ mod tests {
             state_root: temp_dir.path().join("state"),
             command_timeout: Duration::from_secs(30),
             stop_timeout: Duration::from_secs(10),
+            pull_queue: Default::default(),
+            log_archive_root: Some(temp_dir.path().join("log-archive")),
+            log_rotation: Default::default(),
+            event_poll_interval: Duration::from_secs(1),
+            checkpoint_root: temp_dir.path().join("checkpoints"),
+            checkpoint_timeout: Duration::from_secs(300),
+            ..Default::default()
         };
 
         // Should fail gracefully with a clear error
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -38,17 +40,61 @@ pub enum OrchestrationError {
     InternalError(String),
     #[error("Feature not implemented: {0}")]
     NotImplemented(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
 }
 
 // Represents a node in the cluster
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Node {
     pub id: NodeId,
-    pub address: String, // e.g., "10.0.0.1:8080"
+    pub address: String, // e.g., "10.0.0.1:8080" or "[fd00::1]:8080"
     pub status: NodeStatus,
     pub labels: HashMap<String, String>,
     pub resources_capacity: NodeResources,
     pub resources_allocatable: NodeResources, // Capacity - system overhead
+    // Cordoned: keeps running its instances but receives no new ones
+    #[serde(default)]
+    pub unschedulable: bool,
+    // Assigned by the state store on every write (0 = never stored)
+    #[serde(default)]
+    pub resource_version: u64,
+}
+
+/// Label a node agent sets to its release version.
+pub const NODE_VERSION_LABEL: &str = "orchestrator/version";
+
+/// Label a node agent sets to the number of GPUs it detected.
+pub const NODE_GPU_LABEL: &str = "orchestrator/gpus";
+
+/// Label a node agent sets to how each of its GPUs is shared, in index
+/// order, e.g. "exclusive,mps,mig:3g.40gb+3g.40gb". See [`GpuDevice`].
+pub const NODE_GPU_DEVICES_LABEL: &str = "orchestrator/gpu-devices";
+
+impl Node {
+    /// Whether new instances may be placed on this node.
+    pub fn is_schedulable(&self) -> bool {
+        self.status == NodeStatus::Ready && !self.unschedulable
+    }
+
+    /// The node's GPUs as its [`NODE_GPU_DEVICES_LABEL`] describes them,
+    /// else as many exclusive GPUs as its [`NODE_GPU_LABEL`] counts.
+    pub fn gpu_devices(&self) -> Result<Vec<GpuDevice>> {
+        if let Some(devices) = self.labels.get(NODE_GPU_DEVICES_LABEL) {
+            return GpuDevice::parse_list(devices);
+        }
+        let count = self
+            .labels
+            .get(NODE_GPU_LABEL)
+            .and_then(|count| count.parse::<u32>().ok())
+            .unwrap_or(0);
+        Ok((0..count)
+            .map(|index| GpuDevice {
+                index,
+                sharing: GpuSharing::Exclusive,
+            })
+            .collect())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -68,6 +114,17 @@ pub struct NodeResources {
     // Potentially GPU resources, custom resources, etc.
 }
 
+impl NodeResources {
+    /// What instances may use of this capacity, keeping 10% for the system.
+    pub fn allocatable(&self) -> Self {
+        Self {
+            cpu_cores: self.cpu_cores * 0.9,
+            memory_mb: (self.memory_mb as f64 * 0.9) as u64,
+            disk_mb: (self.disk_mb as f64 * 0.9) as u64,
+        }
+    }
+}
+
 // Configuration for a single container within a workload
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ContainerConfig {
@@ -78,7 +135,861 @@ pub struct ContainerConfig {
     pub env_vars: HashMap<String, String>,
     pub ports: Vec<PortMapping>,
     pub resource_requests: NodeResources,
-    // Volume mounts, health checks, etc. would go here
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volume_mounts: Vec<VolumeMount>,
+    // Time from stop request to SIGKILL, preStop hook included; the runtime's default if None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub termination_grace_period_seconds: Option<u64>,
+    // Run before the container is sent SIGTERM, e.g. to drain connections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_stop: Option<LifecycleHandler>,
+    // Gates service traffic: the instance only receives traffic while this passes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readiness_probe: Option<Probe>,
+    // PID, block I/O and hugepage limits, on top of the CPU and memory requests
+    #[serde(default, skip_serializing_if = "ContainerLimits::is_empty")]
+    pub limits: ContainerLimits,
+    // Kernel parameters of the container's namespaces, e.g. "net.core.somaxconn"
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sysctls: HashMap<String, String>,
+    // Process limits such as memlock for RDMA and pinned memory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rlimits: Vec<Rlimit>,
+    // Size of /dev/shm, e.g. for PyTorch data loaders; the runtime's default if None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shm_size_mb: Option<u64>,
+    // Hostname of the container's UTS namespace; the container name if None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    // Extra /etc/hosts entries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub host_aliases: Vec<HostAlias>,
+    // /etc/resolv.conf settings; the node-local DNS cache if empty
+    #[serde(default, skip_serializing_if = "DnsConfig::is_empty")]
+    pub dns: DnsConfig,
+    // Runtime settings without a field of their own, e.g. "ingress_bandwidth"
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub annotations: HashMap<String, String>,
+}
+
+impl ContainerConfig {
+    /// How long a stop may take before the container is killed, if set.
+    pub fn termination_grace(&self) -> Option<std::time::Duration> {
+        self.termination_grace_period_seconds
+            .map(std::time::Duration::from_secs)
+    }
+
+    /// The hostname the container sees: its preferred one, else its name.
+    pub fn effective_hostname(&self) -> &str {
+        self.hostname.as_deref().unwrap_or(&self.name)
+    }
+
+    /// The bandwidth limits set by the container's `ingress_bandwidth` and
+    /// `egress_bandwidth` annotations.
+    pub fn bandwidth_limits(&self) -> Result<BandwidthLimits> {
+        let limit = |annotation: &str| -> Result<Option<u64>> {
+            self.annotations
+                .get(annotation)
+                .map(|value| {
+                    parse_bandwidth(value).ok_or_else(|| {
+                        OrchestrationError::ConfigError(format!(
+                            "Invalid {} '{}', expected a positive rate such as 500K, 10M or 1G",
+                            annotation, value
+                        ))
+                    })
+                })
+                .transpose()
+        };
+        Ok(BandwidthLimits {
+            ingress_bps: limit(BandwidthLimits::INGRESS_ANNOTATION)?,
+            egress_bps: limit(BandwidthLimits::EGRESS_ANNOTATION)?,
+        })
+    }
+
+    /// The GPUs set by the container's `gpu` annotation, if any.
+    pub fn gpu_request(&self) -> Result<Option<GpuRequest>> {
+        self.annotations
+            .get(GpuRequest::ANNOTATION)
+            .map(|value| GpuRequest::parse(value))
+            .transpose()
+    }
+
+    /// The model set by the container's `model_uri`, `model_digest` and
+    /// `model_mount_path` annotations, if any.
+    pub fn model_artifact(&self) -> Result<Option<ModelArtifact>> {
+        ModelArtifact::from_annotations(&self.annotations)
+    }
+
+    /// Checks the container's limits, kernel settings, names and annotations
+    /// are ones the runtime can apply.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Err(OrchestrationError::ConfigError(message));
+        self.limits.validate()?;
+        if let Some(name) = self.sysctls.keys().find(|name| !is_allowed_sysctl(name)) {
+            return invalid(format!(
+                "Sysctl '{}' is not allowed; only namespaced kernel.msg*, kernel.sem, \
+                 kernel.shm*, fs.mqueue.* and net.* are",
+                name
+            ));
+        }
+        for rlimit in &self.rlimits {
+            rlimit.validate()?;
+        }
+        if self.shm_size_mb == Some(0) {
+            return invalid("/dev/shm size must be positive".to_string());
+        }
+        if let Some(hostname) = self.hostname.as_ref().filter(|h| !is_valid_hostname(h)) {
+            return invalid(format!(
+                "Invalid hostname '{}': use at most 63 lowercase letters, digits and hyphens",
+                hostname
+            ));
+        }
+        if let Some(alias) = self.host_aliases.iter().find(|a| a.hostnames.is_empty()) {
+            return invalid(format!("Host alias {} needs a hostname", alias.ip));
+        }
+        self.dns.validate()?;
+        self.bandwidth_limits()?;
+        self.model_artifact()?;
+        self.gpu_request()?;
+        if let Some(LifecycleHandler::Exec { command }) = &self.pre_stop {
+            if command.is_empty() {
+                return invalid("preStop hook needs a command".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A model the node downloads and mounts read-only into a container before
+/// it starts, e.g. the weights an inference server loads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelArtifact {
+    // s3://bucket/key, hf://org/name[@revision] or oci://registry/repository:tag
+    pub uri: String,
+    // sha256 digest the downloaded files must match
+    pub digest: Option<String>,
+    // Path inside the container
+    pub mount_path: String,
+}
+
+impl ModelArtifact {
+    pub const URI_ANNOTATION: &'static str = "model_uri";
+    pub const DIGEST_ANNOTATION: &'static str = "model_digest";
+    pub const MOUNT_PATH_ANNOTATION: &'static str = "model_mount_path";
+    pub const DEFAULT_MOUNT_PATH: &'static str = "/models";
+
+    /// The model set by a container's annotations, if any.
+    pub fn from_annotations(annotations: &HashMap<String, String>) -> Result<Option<Self>> {
+        let Some(uri) = annotations.get(Self::URI_ANNOTATION) else {
+            return Ok(None);
+        };
+        let model = ModelArtifact {
+            uri: uri.clone(),
+            digest: annotations.get(Self::DIGEST_ANNOTATION).cloned(),
+            mount_path: annotations
+                .get(Self::MOUNT_PATH_ANNOTATION)
+                .cloned()
+                .unwrap_or_else(|| Self::DEFAULT_MOUNT_PATH.to_string()),
+        };
+        model.validate()?;
+        Ok(Some(model))
+    }
+
+    /// Annotations setting this model on a container.
+    pub fn annotations(&self) -> HashMap<String, String> {
+        let mut annotations = HashMap::from([
+            (Self::URI_ANNOTATION.to_string(), self.uri.clone()),
+            (
+                Self::MOUNT_PATH_ANNOTATION.to_string(),
+                self.mount_path.clone(),
+            ),
+        ]);
+        if let Some(digest) = &self.digest {
+            annotations.insert(Self::DIGEST_ANNOTATION.to_string(), digest.clone());
+        }
+        annotations
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Err(OrchestrationError::ConfigError(message));
+        let source = ["s3://", "hf://", "oci://"]
+            .iter()
+            .find_map(|scheme| self.uri.strip_prefix(scheme));
+        if !source.is_some_and(|rest| rest.contains('/') && !rest.starts_with('/')) {
+            return invalid(format!(
+                "Invalid {} '{}', expected s3://bucket/key, hf://org/name or \
+                 oci://registry/repository:tag",
+                Self::URI_ANNOTATION,
+                self.uri
+            ));
+        }
+        if let Some(digest) = &self.digest {
+            let valid = digest.strip_prefix("sha256:").is_some_and(|hex| {
+                hex.len() == 64
+                    && hex
+                        .bytes()
+                        .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+            });
+            if !valid {
+                return invalid(format!(
+                    "Invalid {} '{}', expected sha256:<64 hex digits>",
+                    Self::DIGEST_ANNOTATION,
+                    digest
+                ));
+            }
+        }
+        if !self.mount_path.starts_with('/') {
+            return invalid(format!(
+                "Invalid {} '{}', expected an absolute path",
+                Self::MOUNT_PATH_ANNOTATION,
+                self.mount_path
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Rates a container's network traffic is shaped to, in bits per second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BandwidthLimits {
+    // Traffic the container receives
+    pub ingress_bps: Option<u64>,
+    // Traffic the container sends
+    pub egress_bps: Option<u64>,
+}
+
+impl BandwidthLimits {
+    pub const INGRESS_ANNOTATION: &'static str = "ingress_bandwidth";
+    pub const EGRESS_ANNOTATION: &'static str = "egress_bandwidth";
+
+    pub fn is_empty(&self) -> bool {
+        self.ingress_bps.is_none() && self.egress_bps.is_none()
+    }
+}
+
+/// Parses a rate in bits per second with an optional decimal suffix, e.g.
+/// "10M" for 10 Mbit/s. None if it is malformed or zero.
+pub fn parse_bandwidth(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (digits, multiplier) = match value.char_indices().last()? {
+        (i, 'k' | 'K') => (&value[..i], 1_000),
+        (i, 'M') => (&value[..i], 1_000_000),
+        (i, 'G') => (&value[..i], 1_000_000_000),
+        (i, 'T') => (&value[..i], 1_000_000_000_000),
+        _ => (value, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .filter(|&bps| bps > 0)
+}
+
+/// How one GPU of a node is handed to containers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuSharing {
+    // Whole, to one container at a time
+    Exclusive,
+    // Shared through CUDA MPS, each container getting a percentage of its threads
+    Mps,
+    // Partitioned into MIG instances of these profiles, e.g. "1g.10gb"
+    Mig(Vec<String>),
+}
+
+impl std::fmt::Display for GpuSharing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuSharing::Exclusive => write!(f, "exclusive"),
+            GpuSharing::Mps => write!(f, "mps"),
+            GpuSharing::Mig(profiles) => write!(f, "mig:{}", profiles.join("+")),
+        }
+    }
+}
+
+/// A GPU of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuDevice {
+    pub index: u32,
+    pub sharing: GpuSharing,
+}
+
+impl GpuDevice {
+    /// Parses a comma-separated list of "exclusive", "mps" and
+    /// "mig:<profile>+<profile>..." entries, one per GPU in index order.
+    pub fn parse_list(s: &str) -> Result<Vec<GpuDevice>> {
+        let invalid = |entry: &str| {
+            OrchestrationError::ConfigError(format!(
+                "Invalid GPU '{}', expected exclusive, mps or mig:<profile>+<profile>...",
+                entry
+            ))
+        };
+        s.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .enumerate()
+            .map(|(index, entry)| {
+                let sharing = match entry {
+                    "exclusive" => GpuSharing::Exclusive,
+                    "mps" => GpuSharing::Mps,
+                    _ => {
+                        let profiles: Vec<String> = entry
+                            .strip_prefix("mig:")
+                            .ok_or_else(|| invalid(entry))?
+                            .split('+')
+                            .map(|profile| profile.trim().to_string())
+                            .collect();
+                        if !profiles.iter().all(|profile| is_mig_profile(profile)) {
+                            return Err(invalid(entry));
+                        }
+                        GpuSharing::Mig(profiles)
+                    }
+                };
+                Ok(GpuDevice {
+                    index: index as u32,
+                    sharing,
+                })
+            })
+            .collect()
+    }
+
+    /// The list [`parse_list`](Self::parse_list) reads back.
+    pub fn format_list(devices: &[GpuDevice]) -> String {
+        devices
+            .iter()
+            .map(|device| device.sharing.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Whether `profile` names a MIG profile, such as "1g.10gb" or "3g.40gb".
+pub fn is_mig_profile(profile: &str) -> bool {
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    profile
+        .split_once("g.")
+        .and_then(|(slices, memory)| Some((slices, memory.strip_suffix("gb")?)))
+        .is_some_and(|(slices, memory)| digits(slices) && digits(memory))
+}
+
+/// GPUs a container asks for with its `gpu` annotation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuRequest {
+    // Whole exclusive GPUs, e.g. "2"
+    Devices(u32),
+    // Percent of an MPS GPU, e.g. "0.25" for 25
+    Share(u32),
+    // One MIG instance of a profile, e.g. "mig-1g.10gb"
+    Mig(String),
+}
+
+impl GpuRequest {
+    pub const ANNOTATION: &'static str = "gpu";
+
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let request = if let Some(profile) = value.strip_prefix("mig-") {
+            is_mig_profile(profile).then(|| GpuRequest::Mig(profile.to_string()))
+        } else if let Ok(devices) = value.parse::<u32>() {
+            (devices > 0).then_some(GpuRequest::Devices(devices))
+        } else {
+            value
+                .parse::<f64>()
+                .ok()
+                .map(|share| share * 100.0)
+                .filter(|percent| (percent - percent.round()).abs() < 1e-6)
+                .map(|percent| percent.round())
+                .filter(|percent| (1.0..100.0).contains(percent))
+                .map(|percent| GpuRequest::Share(percent as u32))
+        };
+        request.ok_or_else(|| {
+            OrchestrationError::ConfigError(format!(
+                "Invalid {} '{}', expected a number of GPUs such as 2, a share of one \
+                 such as 0.25, or a MIG profile such as mig-1g.10gb",
+                Self::ANNOTATION,
+                value
+            ))
+        })
+    }
+
+    /// GPUs the request takes; a MIG instance counts as its compute slices
+    /// out of the seven of a whole GPU.
+    pub fn gpus(&self) -> f64 {
+        match self {
+            GpuRequest::Devices(devices) => f64::from(*devices),
+            GpuRequest::Share(percent) => f64::from(*percent) / 100.0,
+            GpuRequest::Mig(profile) => profile
+                .split_once("g.")
+                .and_then(|(slices, _)| slices.parse::<f64>().ok())
+                .map_or(0.0, |slices| (slices / 7.0).min(1.0)),
+        }
+    }
+}
+
+/// A GPU, GPU share or MIG instance handed to a container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuAssignment {
+    Device(u32),
+    Share { device: u32, percent: u32 },
+    // The instance is the profile's position on the device
+    Mig { device: u32, instance: u32 },
+}
+
+/// Which GPUs, shares and MIG instances of a node are in use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuPool {
+    devices: Vec<GpuDevice>,
+    // Per device: percent in use, and for MIG GPUs which instances are taken
+    used: Vec<(u32, Vec<bool>)>,
+}
+
+impl GpuPool {
+    pub fn new(devices: Vec<GpuDevice>) -> Self {
+        let used = devices
+            .iter()
+            .map(|device| match &device.sharing {
+                GpuSharing::Mig(profiles) => (0, vec![false; profiles.len()]),
+                _ => (0, Vec::new()),
+            })
+            .collect();
+        Self { devices, used }
+    }
+
+    pub fn devices(&self) -> &[GpuDevice] {
+        &self.devices
+    }
+
+    /// Take what `requests` ask for, all or nothing. The error says why
+    /// they do not fit.
+    pub fn allocate(
+        &mut self,
+        requests: &[GpuRequest],
+    ) -> std::result::Result<Vec<GpuAssignment>, String> {
+        let mut pool = self.clone();
+        let mut assignments = Vec::new();
+        for request in requests {
+            assignments.extend(pool.allocate_one(request)?);
+        }
+        *self = pool;
+        Ok(assignments)
+    }
+
+    fn allocate_one(
+        &mut self,
+        request: &GpuRequest,
+    ) -> std::result::Result<Vec<GpuAssignment>, String> {
+        let positions = |wanted: fn(&GpuSharing) -> bool| -> Vec<usize> {
+            (0..self.devices.len())
+                .filter(|&i| wanted(&self.devices[i].sharing))
+                .collect()
+        };
+        match request {
+            GpuRequest::Devices(count) => {
+                let exclusive = positions(|s| *s == GpuSharing::Exclusive);
+                let free: Vec<usize> = exclusive
+                    .iter()
+                    .copied()
+                    .filter(|&i| self.used[i].0 == 0)
+                    .collect();
+                if free.len() < *count as usize {
+                    return Err(format!(
+                        "{} GPUs requested, {} of {} exclusive GPUs free",
+                        count,
+                        free.len(),
+                        exclusive.len()
+                    ));
+                }
+                Ok(free[..*count as usize]
+                    .iter()
+                    .map(|&i| {
+                        self.used[i].0 = 100;
+                        GpuAssignment::Device(self.devices[i].index)
+                    })
+                    .collect())
+            }
+            GpuRequest::Share(percent) => {
+                let mps = positions(|s| *s == GpuSharing::Mps);
+                if mps.is_empty() {
+                    return Err(format!("{}% of a GPU requested, no MPS GPUs", percent));
+                }
+                // The fullest GPU it fits on, so whole GPUs stay free
+                let Some(i) = mps
+                    .into_iter()
+                    .filter(|&i| self.used[i].0 + percent <= 100)
+                    .max_by_key(|&i| (self.used[i].0, std::cmp::Reverse(i)))
+                else {
+                    return Err(format!(
+                        "{}% of a GPU requested, no MPS GPU has it free",
+                        percent
+                    ));
+                };
+                self.used[i].0 += percent;
+                Ok(vec![GpuAssignment::Share {
+                    device: self.devices[i].index,
+                    percent: *percent,
+                }])
+            }
+            GpuRequest::Mig(profile) => {
+                let mut offered = 0;
+                for (i, device) in self.devices.iter().enumerate() {
+                    let GpuSharing::Mig(profiles) = &device.sharing else {
+                        continue;
+                    };
+                    for (instance, candidate) in profiles.iter().enumerate() {
+                        if candidate != profile {
+                            continue;
+                        }
+                        offered += 1;
+                        if !self.used[i].1[instance] {
+                            self.used[i].1[instance] = true;
+                            return Ok(vec![GpuAssignment::Mig {
+                                device: device.index,
+                                instance: instance as u32,
+                            }]);
+                        }
+                    }
+                }
+                Err(if offered == 0 {
+                    format!("MIG profile {} requested, no GPU offers it", profile)
+                } else {
+                    format!(
+                        "MIG profile {} requested, all {} instances in use",
+                        profile, offered
+                    )
+                })
+            }
+        }
+    }
+
+    /// Free what was handed out by [`allocate`](Self::allocate).
+    pub fn release(&mut self, assignments: &[GpuAssignment]) {
+        let position = |index: u32| self.devices.iter().position(|d| d.index == index);
+        for assignment in assignments {
+            match *assignment {
+                GpuAssignment::Device(device) => {
+                    if let Some(i) = position(device) {
+                        self.used[i].0 = 0;
+                    }
+                }
+                GpuAssignment::Share { device, percent } => {
+                    if let Some(i) = position(device) {
+                        self.used[i].0 = self.used[i].0.saturating_sub(percent);
+                    }
+                }
+                GpuAssignment::Mig { device, instance } => {
+                    if let Some(used) =
+                        position(device).and_then(|i| self.used[i].1.get_mut(instance as usize))
+                    {
+                        *used = false;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether `name` is a valid container hostname: a DNS label, with the
+/// same rules as namespace names.
+pub fn is_valid_hostname(name: &str) -> bool {
+    Namespace::is_valid_name(name)
+}
+
+/// An /etc/hosts entry of a container.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HostAlias {
+    pub ip: IpAddr,
+    pub hostnames: Vec<String>,
+}
+
+/// The /etc/resolv.conf of a container.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct DnsConfig {
+    // Replace the default nameservers when not empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nameservers: Vec<IpAddr>,
+    // Search domains, e.g. "svc.cluster.local"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub searches: Vec<String>,
+    // Resolver options, e.g. "ndots:2" or "edns0"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+impl DnsConfig {
+    /// Most nameservers the resolver uses.
+    pub const MAX_NAMESERVERS: usize = 3;
+
+    /// Most search domains a container may set.
+    pub const MAX_SEARCHES: usize = 32;
+
+    pub fn is_empty(&self) -> bool {
+        self.nameservers.is_empty() && self.searches.is_empty() && self.options.is_empty()
+    }
+
+    /// Checks the counts fit the resolver and that no entry would break
+    /// the line it is written on.
+    pub fn validate(&self) -> Result<()> {
+        if self.nameservers.len() > Self::MAX_NAMESERVERS {
+            return Err(OrchestrationError::ConfigError(format!(
+                "At most {} nameservers are supported, got {}",
+                Self::MAX_NAMESERVERS,
+                self.nameservers.len()
+            )));
+        }
+        if self.searches.len() > Self::MAX_SEARCHES {
+            return Err(OrchestrationError::ConfigError(format!(
+                "At most {} search domains are supported, got {}",
+                Self::MAX_SEARCHES,
+                self.searches.len()
+            )));
+        }
+        let malformed = |s: &String| s.is_empty() || s.contains(char::is_whitespace);
+        if let Some(entry) = self
+            .searches
+            .iter()
+            .chain(&self.options)
+            .find(|s| malformed(s))
+        {
+            return Err(OrchestrationError::ConfigError(format!(
+                "Invalid DNS search domain or option '{}'",
+                entry
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A process resource limit, as `ulimit` sets them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Rlimit {
+    // Lowercase name without the RLIMIT_ prefix, e.g. "memlock" or "nofile"
+    pub name: String,
+    // u64::MAX for unlimited
+    pub soft: u64,
+    pub hard: u64,
+}
+
+impl Rlimit {
+    /// Names of the limits a container may set.
+    pub const NAMES: [&'static str; 16] = [
+        "as",
+        "core",
+        "cpu",
+        "data",
+        "fsize",
+        "locks",
+        "memlock",
+        "msgqueue",
+        "nice",
+        "nofile",
+        "nproc",
+        "rss",
+        "rtprio",
+        "rttime",
+        "sigpending",
+        "stack",
+    ];
+
+    /// An unlimited limit, like `ulimit -l unlimited` for `memlock`.
+    pub fn unlimited(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            soft: u64::MAX,
+            hard: u64::MAX,
+        }
+    }
+
+    /// Checks the name is known and the soft limit within the hard one.
+    pub fn validate(&self) -> Result<()> {
+        if !Self::NAMES.contains(&self.name.as_str()) {
+            return Err(OrchestrationError::ConfigError(format!(
+                "Unknown rlimit '{}', expected one of {}",
+                self.name,
+                Self::NAMES.join(", ")
+            )));
+        }
+        if self.soft > self.hard {
+            return Err(OrchestrationError::ConfigError(format!(
+                "Soft {} limit {} exceeds the hard limit {}",
+                self.name, self.soft, self.hard
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Whether a container may set the sysctl `name`. Only sysctls of the IPC
+/// and network namespaces each container gets are allowed, so none changes
+/// the node.
+pub fn is_allowed_sysctl(name: &str) -> bool {
+    const IPC: [&str; 8] = [
+        "kernel.msgmax",
+        "kernel.msgmnb",
+        "kernel.msgmni",
+        "kernel.sem",
+        "kernel.shmall",
+        "kernel.shmmax",
+        "kernel.shmmni",
+        "kernel.shm_rmid_forced",
+    ];
+    IPC.contains(&name)
+        || name.strip_prefix("fs.mqueue.").is_some_and(|rest| !rest.is_empty())
+        || name.strip_prefix("net.").is_some_and(|rest| !rest.is_empty())
+}
+
+/// Limits of a container beyond CPU and memory, enforced through its cgroup.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct ContainerLimits {
+    // Maximum number of processes; the runtime's default if None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids: Option<i64>,
+    // Relative block I/O weight, from 10 to 1000
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_weight: Option<u16>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub io_throttle: Vec<IoThrottle>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hugepages: Vec<HugepageLimit>,
+}
+
+impl ContainerLimits {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Checks the limits are in the ranges cgroups accept.
+    pub fn validate(&self) -> Result<()> {
+        if self.pids.is_some_and(|pids| pids <= 0) {
+            return Err(OrchestrationError::ConfigError(
+                "PID limit must be positive".to_string(),
+            ));
+        }
+        if let Some(weight) = self.io_weight.filter(|w| !(10..=1000).contains(w)) {
+            return Err(OrchestrationError::ConfigError(format!(
+                "I/O weight must be between 10 and 1000, got {}",
+                weight
+            )));
+        }
+        for hugepage in &self.hugepages {
+            if !HugepageLimit::is_valid_page_size(&hugepage.page_size) {
+                return Err(OrchestrationError::ConfigError(format!(
+                    "Invalid hugepage size '{}', expected e.g. 2MB or 1GB",
+                    hugepage.page_size
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rate limits on a block device, named by its `major:minor` numbers. Rates
+/// left `None` are unlimited.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IoThrottle {
+    pub major: i64,
+    pub minor: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_bps: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_bps: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_iops: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_iops: Option<u64>,
+}
+
+/// The most memory a container may back with hugepages of one size.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HugepageLimit {
+    // Page size as the kernel names it, e.g. "2MB" or "1GB"
+    pub page_size: String,
+    pub limit_bytes: u64,
+}
+
+impl HugepageLimit {
+    /// Whether `size` names a page size the way cgroups do, e.g. "2MB".
+    pub fn is_valid_page_size(size: &str) -> bool {
+        ["KB", "MB", "GB"].iter().any(|unit| {
+            size.strip_suffix(unit)
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        })
+    }
+}
+
+/// An action run against a container at a point of its lifecycle.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LifecycleHandler {
+    /// Runs a command in the container; a non-zero exit fails the hook.
+    Exec { command: Vec<String> },
+    /// Fails unless an HTTP GET to the container's port returns 200-399.
+    HttpGet { port: u16, path: String },
+}
+
+/// What a probe checks.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProbeAction {
+    /// Succeeds if a TCP connection to the port can be opened.
+    TcpSocket { port: u16 },
+    /// Succeeds on an HTTP status in the 200-399 range.
+    HttpGet { port: u16, path: String },
+}
+
+/// A periodic check run against each instance of a container.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Probe {
+    pub action: ProbeAction,
+    #[serde(default)]
+    pub initial_delay_seconds: u32,
+    #[serde(default = "Probe::default_period_seconds")]
+    pub period_seconds: u32,
+    #[serde(default = "Probe::default_timeout_seconds")]
+    pub timeout_seconds: u32,
+    // Consecutive successes needed to become ready
+    #[serde(default = "Probe::default_success_threshold")]
+    pub success_threshold: u32,
+    // Consecutive failures needed to become not ready
+    #[serde(default = "Probe::default_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+impl Probe {
+    pub fn new(action: ProbeAction) -> Self {
+        Self {
+            action,
+            initial_delay_seconds: 0,
+            period_seconds: Self::default_period_seconds(),
+            timeout_seconds: Self::default_timeout_seconds(),
+            success_threshold: Self::default_success_threshold(),
+            failure_threshold: Self::default_failure_threshold(),
+        }
+    }
+
+    fn default_period_seconds() -> u32 {
+        10
+    }
+
+    fn default_timeout_seconds() -> u32 {
+        1
+    }
+
+    fn default_success_threshold() -> u32 {
+        1
+    }
+
+    fn default_failure_threshold() -> u32 {
+        3
+    }
+}
+
+/// A host directory bind-mounted into a container.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VolumeMount {
+    pub name: String,
+    pub host_path: String,  // Created on the node if missing
+    pub mount_path: String, // Path inside the container
+    #[serde(default)]
+    pub read_only: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -86,6 +997,95 @@ pub struct PortMapping {
     pub container_port: u16,
     pub host_port: Option<u16>, // If None, runtime chooses an ephemeral port
     pub protocol: String,       // "tcp" or "udp"
+    // Host address to bind the port on, v4 or v6. If None, binds all addresses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_ip: Option<IpAddr>,
+}
+
+/// IP address family.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum IpFamily {
+    IPv4,
+    IPv6,
+}
+
+impl IpFamily {
+    pub fn of(addr: &IpAddr) -> Self {
+        match addr {
+            IpAddr::V4(_) => IpFamily::IPv4,
+            IpAddr::V6(_) => IpFamily::IPv6,
+        }
+    }
+
+    /// The wildcard address for this family (`0.0.0.0` or `::`).
+    pub fn unspecified(&self) -> IpAddr {
+        match self {
+            IpFamily::IPv4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpFamily::IPv6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        }
+    }
+}
+
+/// How many address families a service should receive virtual IPs from.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum IpFamilyPolicy {
+    /// One address from the cluster's primary family.
+    #[default]
+    SingleStack,
+    /// One address per configured family, falling back to single-stack.
+    PreferDualStack,
+    /// One address per family; fails on clusters that are not dual-stack.
+    RequireDualStack,
+}
+
+/// Format an address and port as `host:port`, bracketing IPv6 hosts.
+pub fn format_host_port(ip: &IpAddr, port: u16) -> String {
+    match ip {
+        IpAddr::V4(v4) => format!("{}:{}", v4, port),
+        IpAddr::V6(v6) => format!("[{}]:{}", v6, port),
+    }
+}
+
+/// Namespace of objects created without one. It always exists and cannot be
+/// deleted.
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+fn default_namespace() -> String {
+    DEFAULT_NAMESPACE.to_string()
+}
+
+// Groups workloads, their instances and services, so teams sharing a cluster
+// do not collide on names. Deleting a namespace deletes everything in it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Namespace {
+    pub name: String,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    // Assigned by the state store on every write (0 = never stored)
+    #[serde(default)]
+    pub resource_version: u64,
+}
+
+impl Namespace {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            labels: HashMap::new(),
+            resource_version: 0,
+        }
+    }
+
+    /// Whether `name` is a valid namespace name: 1 to 63 lowercase letters,
+    /// digits and hyphens, starting and ending with a letter or digit.
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.is_empty()
+            && name.len() <= 63
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+            && !name.starts_with('-')
+            && !name.ends_with('-')
+    }
 }
 
 // Defines a workload to be run on the cluster
@@ -94,9 +1094,259 @@ pub struct WorkloadDefinition {
     pub id: WorkloadId,
     pub name: String, // User-friendly name
     pub containers: Vec<ContainerConfig>,
+    // Run to completion, in order, before any other container starts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub init_containers: Vec<ContainerConfig>,
+    // Start before and stop after the main containers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sidecars: Vec<ContainerConfig>,
     pub replicas: u32,
     pub labels: HashMap<String, String>, // For scheduling, selection
-    // Placement constraints, update strategy, etc.
+    #[serde(default, skip_serializing_if = "Placement::is_empty")]
+    pub placement: Placement,
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    // Assigned by the state store on every write (0 = never stored)
+    #[serde(default)]
+    pub resource_version: u64,
+    // Update strategy, etc.
+}
+
+impl WorkloadDefinition {
+    /// Whether `other` runs the same containers the same way: containers,
+    /// init containers, sidecars, placement and restart policy. Changing
+    /// these makes a new [`WorkloadRevision`]; replicas and labels do not.
+    pub fn same_template(&self, other: &WorkloadDefinition) -> bool {
+        self.containers == other.containers
+            && self.init_containers == other.init_containers
+            && self.sidecars == other.sidecars
+            && self.placement == other.placement
+            && self.restart_policy == other.restart_policy
+    }
+
+    /// GPUs the workload's containers and sidecars ask for, per replica.
+    pub fn gpu_requests(&self) -> Result<Vec<GpuRequest>> {
+        let mut requests = Vec::new();
+        for container in self.containers.iter().chain(&self.sidecars) {
+            requests.extend(container.gpu_request()?);
+        }
+        Ok(requests)
+    }
+
+    /// Checks the workload can be stored and run: it has a name, a valid
+    /// namespace and at least one container, and every container, init
+    /// container and sidecar passes [`ContainerConfig::validate`].
+    ///
+    /// Every path that writes a workload runs this, whatever form the
+    /// request came in.
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty() {
+            return Err(OrchestrationError::ConfigError(
+                "Workload name cannot be empty".to_string(),
+            ));
+        }
+        if self.containers.is_empty() {
+            return Err(OrchestrationError::ConfigError(
+                "Workload must have at least one container".to_string(),
+            ));
+        }
+        if !Namespace::is_valid_name(&self.namespace) {
+            return Err(OrchestrationError::ConfigError(format!(
+                "Invalid namespace name '{}': use at most 63 lowercase letters, digits and \
+                 hyphens",
+                self.namespace
+            )));
+        }
+        for container in self
+            .containers
+            .iter()
+            .chain(&self.init_containers)
+            .chain(&self.sidecars)
+        {
+            container.validate().map_err(|e| match e {
+                OrchestrationError::ConfigError(message) => OrchestrationError::ConfigError(
+                    format!("Container '{}': {}", container.name, message),
+                ),
+                other => other,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// This workload with the template fields of `other`.
+    pub fn with_template_of(&self, other: &WorkloadDefinition) -> WorkloadDefinition {
+        WorkloadDefinition {
+            containers: other.containers.clone(),
+            init_containers: other.init_containers.clone(),
+            sidecars: other.sidecars.clone(),
+            placement: other.placement.clone(),
+            restart_policy: other.restart_policy.clone(),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RestartMode {
+    #[default]
+    Always,
+    /// Restart only containers that exit with a non-zero code.
+    OnFailure,
+    Never,
+}
+
+/// When the containers of an instance are restarted after they exit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RestartPolicy {
+    #[serde(default)]
+    pub mode: RestartMode,
+    /// Exit codes that are never restarted, such as a configuration error a
+    /// restart cannot fix. The instance fails instead.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fatal_exit_codes: Vec<i32>,
+}
+
+impl RestartPolicy {
+    pub fn on_failure() -> Self {
+        Self {
+            mode: RestartMode::OnFailure,
+            ..Default::default()
+        }
+    }
+
+    pub fn never() -> Self {
+        Self {
+            mode: RestartMode::Never,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_fatal_exit_codes(mut self, codes: impl IntoIterator<Item = i32>) -> Self {
+        self.fatal_exit_codes = codes.into_iter().collect();
+        self
+    }
+
+    /// Whether a container that exited with `exit_code` is restarted. An
+    /// unknown exit code counts as a failure.
+    pub fn should_restart(&self, exit_code: Option<i32>) -> bool {
+        if exit_code.is_some_and(|code| self.fatal_exit_codes.contains(&code)) {
+            return false;
+        }
+        match self.mode {
+            RestartMode::Always => true,
+            RestartMode::OnFailure => exit_code != Some(0),
+            RestartMode::Never => false,
+        }
+    }
+}
+
+/// Constraints on which nodes a workload's instances may run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Placement {
+    /// Labels a node must carry, with exactly these values.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub node_selector: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_affinity: Option<NodeAffinityRules>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance_anti_affinity: Option<InstanceAntiAffinity>,
+    /// Name of the scheduling strategy ranking nodes; the cluster default when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduling_strategy: Option<String>,
+    /// Instances that fit on no node may preempt instances of workloads with
+    /// a lower priority (default: 0).
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl Placement {
+    pub fn is_empty(&self) -> bool {
+        self.node_selector.is_empty()
+            && self.node_affinity.is_none()
+            && self.instance_anti_affinity.is_none()
+            && self.scheduling_strategy.is_none()
+            && self.priority == 0
+    }
+}
+
+/// Node label rules; `required` must all hold, `preferred` only rank nodes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NodeAffinityRules {
+    #[serde(default)]
+    pub required: Vec<NodeSelectorRequirement>,
+    #[serde(default)]
+    pub preferred: Vec<PreferredNodeTerm>,
+}
+
+/// A condition on a single node label.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NodeSelectorRequirement {
+    pub key: String,
+    pub operator: NodeSelectorOperator,
+    // Ignored by Exists and DoesNotExist; Gt and Lt compare against the first as an integer
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum NodeSelectorOperator {
+    In,
+    NotIn,
+    Exists,
+    DoesNotExist,
+    Gt,
+    Lt,
+}
+
+/// Nodes matching every expression gain `weight` when ranked.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PreferredNodeTerm {
+    pub weight: i32,
+    pub match_expressions: Vec<NodeSelectorRequirement>,
+}
+
+/// Keeps instances of the same workload apart.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InstanceAntiAffinity {
+    // When false, sharing a domain only lowers a node's rank by `weight` per instance
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default = "InstanceAntiAffinity::default_weight")]
+    pub weight: i32,
+    // Node label defining the domain to spread across; each node is its own domain when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topology_key: Option<String>,
+}
+
+impl InstanceAntiAffinity {
+    /// At most one instance per node.
+    pub fn required() -> Self {
+        Self {
+            required: true,
+            weight: Self::default_weight(),
+            topology_key: None,
+        }
+    }
+
+    /// Spread across nodes where possible.
+    pub fn preferred(weight: i32) -> Self {
+        Self {
+            required: false,
+            weight,
+            topology_key: None,
+        }
+    }
+
+    pub fn with_topology_key(mut self, key: impl Into<String>) -> Self {
+        self.topology_key = Some(key.into());
+        self
+    }
+
+    fn default_weight() -> i32 {
+        100
+    }
 }
 
 // Represents an instance of a workload running on a specific node
@@ -107,17 +1357,314 @@ pub struct WorkloadInstance {
     pub node_id: NodeId,
     pub container_ids: Vec<ContainerId>, // IDs of containers run by the runtime for this instance
     pub status: WorkloadInstanceStatus,
+    // Addresses assigned to the instance; dual-stack instances have one per family
+    #[serde(default)]
+    pub ip_addresses: Vec<IpAddr>,
+    // Restart history of containers that have exited at least once
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub restarts: Vec<ContainerRestartStatus>,
+    // Always the namespace of the instance's workload
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    // Assigned by the state store on every write (0 = never stored)
+    #[serde(default)]
+    pub resource_version: u64,
+}
+
+impl WorkloadInstance {
+    /// Container annotation listing the instance's addresses, comma-separated,
+    /// which the runtime adds to the container's interface.
+    pub const IP_ADDRESSES_ANNOTATION: &'static str = "ip_addresses";
+
+    /// Restart history of a container, if it has exited before.
+    pub fn restart_status(&self, container_name: &str) -> Option<&ContainerRestartStatus> {
+        self.restarts
+            .iter()
+            .find(|r| r.container_name == container_name)
+    }
+
+    /// Time until the last backed-off container of the instance may restart.
+    pub fn back_off_remaining(&self, now: SystemTime) -> Option<Duration> {
+        self.restarts
+            .iter()
+            .filter_map(|r| r.back_off_remaining(now))
+            .max()
+    }
+}
+
+/// Restart history of one container of an instance.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContainerRestartStatus {
+    pub container_name: String,
+    pub restart_count: u32,
+    /// Exits since the container last stayed up long enough to count as healthy.
+    pub consecutive_failures: u32,
+    pub last_exit_code: Option<i32>,
+    pub last_restart_at: Option<SystemTime>,
+    /// Set while the container waits out its crash-loop back-off.
+    pub back_off_until: Option<SystemTime>,
+    /// How the container last exited, kept after it is restarted.
+    #[serde(default)]
+    pub last_termination: Option<ContainerTermination>,
+}
+
+impl ContainerRestartStatus {
+    pub fn new(container_name: impl Into<String>) -> Self {
+        Self {
+            container_name: container_name.into(),
+            restart_count: 0,
+            consecutive_failures: 0,
+            last_exit_code: None,
+            last_restart_at: None,
+            back_off_until: None,
+            last_termination: None,
+        }
+    }
+
+    pub fn back_off_remaining(&self, now: SystemTime) -> Option<Duration> {
+        self.back_off_until
+            .and_then(|until| until.duration_since(now).ok())
+            .filter(|remaining| !remaining.is_zero())
+    }
+}
+
+/// Why a container's process exited.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TerminationReason {
+    /// Exited with code 0.
+    Completed,
+    /// Exited with another code, or one the runtime could not tell.
+    Error,
+    /// Killed by the kernel for exceeding its memory limit.
+    #[serde(rename = "OOMKilled")]
+    OomKilled,
+}
+
+impl TerminationReason {
+    pub fn classify(exit_code: Option<i32>, oom_killed: bool) -> Self {
+        match exit_code {
+            _ if oom_killed => TerminationReason::OomKilled,
+            Some(0) => TerminationReason::Completed,
+            _ => TerminationReason::Error,
+        }
+    }
+}
+
+impl std::fmt::Display for TerminationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TerminationReason::Completed => write!(f, "Completed"),
+            TerminationReason::Error => write!(f, "Error"),
+            TerminationReason::OomKilled => write!(f, "OOMKilled"),
+        }
+    }
+}
+
+/// How a container's process exited.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContainerTermination {
+    pub reason: TerminationReason,
+    pub exit_code: Option<i32>,
+    /// When the exit was noticed, which can trail the exit by a poll interval.
+    pub finished_at: SystemTime,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum WorkloadInstanceStatus {
     Pending,
     Running,
+    /// A container keeps exiting and is waiting out a back-off before its
+    /// next restart.
+    CrashLoopBackOff,
     Succeeded,
     Failed,
     Unknown,
     Terminating,
 }
 
+/// Last observed state of one container, as reported by its node.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContainerStatusEntry {
+    pub container_id: ContainerId,
+    pub state: String, // OCI state, e.g. "running" or "stopped"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub oom_killed: bool,
+}
+
+/// Container status changes a node carries on its heartbeat.
+///
+/// A delta holds the changes made after `base_revision` up to and including
+/// `revision`. A `full` report lists every container and replaces whatever
+/// the control plane holds for the node. Revisions are only comparable within
+/// one `epoch`, which changes whenever the node restarts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContainerStatusReport {
+    pub epoch: u64,
+    pub base_revision: u64,
+    pub revision: u64,
+    #[serde(default)]
+    pub full: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub updated: Vec<ContainerStatusEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<ContainerId>,
+}
+
+impl ContainerStatusReport {
+    /// Whether the report carries no changes.
+    pub fn is_empty(&self) -> bool {
+        self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// The control plane's acknowledgement of a node's container status: every
+/// change up to `revision` of `epoch` has been applied.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContainerStatusAck {
+    pub epoch: u64,
+    pub revision: u64,
+}
+
+/// A node's heartbeat lease, renewed by the node itself. Observers time
+/// renewals with their own clock, so node clocks need not agree.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NodeLease {
+    /// Incremented on every renewal.
+    pub renewals: u64,
+    /// How long the lease holds after a renewal is observed.
+    pub duration_secs: u64,
+}
+
+/// A named lease in the state store, held by at most one control-plane
+/// instance at a time. Expiry is wall-clock time, so candidates' clocks must
+/// agree to well within the lease duration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LeaderLease {
+    /// Identity of the instance holding the lease.
+    pub holder: String,
+    /// Incremented every time the lease changes hands.
+    pub term: u64,
+    /// Unix time in milliseconds at which the holder acquired the lease.
+    pub acquired_at_ms: u64,
+    /// Unix time in milliseconds after which others may take the lease over.
+    pub expires_at_ms: u64,
+}
+
+impl LeaderLease {
+    /// Whether the lease is still held at `now_ms`.
+    pub fn is_held_at(&self, now_ms: u64) -> bool {
+        now_ms < self.expires_at_ms
+    }
+}
+
+/// A workload's template as of one change to it, kept so the change can be
+/// rolled back.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WorkloadRevision {
+    pub workload_id: WorkloadId,
+    /// Numbered from 1, one higher with each template change.
+    pub revision: u64,
+    /// The workload as it was; a rollback restores only its template fields.
+    pub workload: WorkloadDefinition,
+    /// Unix time in milliseconds at which the revision was recorded.
+    pub created_at_ms: u64,
+}
+
+/// Whether an event reports normal operation or something that needs
+/// attention.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EventType {
+    Normal,
+    Warning,
+}
+
+/// The object an [`Event`] is about.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ObjectReference {
+    /// `Workload`, `Instance` or `Node`.
+    pub kind: String,
+    pub id: String,
+    /// The workload's name; the ID for instances and nodes.
+    pub name: String,
+}
+
+impl ObjectReference {
+    pub fn workload(workload: &WorkloadDefinition) -> Self {
+        Self {
+            kind: "Workload".to_string(),
+            id: workload.id.to_string(),
+            name: workload.name.clone(),
+        }
+    }
+
+    pub fn instance(instance: &WorkloadInstance) -> Self {
+        Self {
+            kind: "Instance".to_string(),
+            id: instance.id.to_string(),
+            name: instance.id.to_string(),
+        }
+    }
+
+    pub fn node(node_id: &NodeId) -> Self {
+        Self {
+            kind: "Node".to_string(),
+            id: node_id.to_string(),
+            name: node_id.to_string(),
+        }
+    }
+}
+
+/// Something that happened to an object, such as a failed scheduling attempt
+/// or a container restart, kept for a while so users can see why an object is
+/// in its state. Repeats of the same event are counted on one record.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Event {
+    pub id: Uuid,
+    pub involved_object: ObjectReference,
+    /// Namespace of the involved object; empty for nodes.
+    #[serde(default)]
+    pub namespace: String,
+    pub event_type: EventType,
+    /// Short CamelCase cause, e.g. `FailedScheduling`.
+    pub reason: String,
+    pub message: String,
+    /// Component that reported the event, e.g. `scheduler`.
+    pub source: String,
+    /// Times the event occurred between the first and last timestamps.
+    pub count: u32,
+    /// Unix time in milliseconds of the first occurrence.
+    pub first_timestamp_ms: u64,
+    /// Unix time in milliseconds of the latest occurrence.
+    pub last_timestamp_ms: u64,
+}
+
+/// Resources a workload held during one hour, kept for chargeback. Usage is
+/// what the workload's running instances requested, so idle reservations are
+/// charged like busy ones.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsageRecord {
+    pub workload_id: WorkloadId,
+    /// Name and namespace of the workload when the record was last written.
+    pub workload_name: String,
+    pub namespace: String,
+    /// Unix time in milliseconds of the start of the hour covered.
+    pub period_start_ms: u64,
+    pub cpu_core_seconds: f64,
+    pub memory_gb_seconds: f64,
+    pub gpu_seconds: f64,
+}
+
+impl UsageRecord {
+    /// Length of the period a record covers.
+    pub const PERIOD_MS: u64 = 3_600_000;
+
+    /// Start of the period containing `timestamp_ms`.
+    pub fn period_start(timestamp_ms: u64) -> u64 {
+        timestamp_ms - timestamp_ms % Self::PERIOD_MS
+    }
+}
+
 // Generic result type for orchestration operations
 pub type Result<T> = std::result::Result<T, OrchestrationError>;
\ No newline at end of file
@@ -28,9 +28,15 @@
 //! enables safe concurrent evaluation of nodes without race conditions, crucial for
 //! performance in large clusters (1000+ nodes).
 
-use orchestrator_shared_types::{Node, NodeId, NodeResources, WorkloadDefinition};
+use crate::score::WorkloadInstanceInfo;
+use orchestrator_shared_types::{
+    Node, NodeId, NodeResources, NodeSelectorOperator, NodeSelectorRequirement, WorkloadDefinition,
+};
 use std::collections::HashMap;
 
+/// Topology key under which every node forms its own domain.
+pub const HOSTNAME_TOPOLOGY_KEY: &str = "kubernetes.io/hostname";
+
 /// Represents a Pod for scheduling purposes.
 ///
 /// This is a simplified representation that can be extended as needed.
@@ -61,7 +67,68 @@ impl From<&WorkloadDefinition> for Pod {
             name: workload.name.clone(),
             labels: workload.labels.clone(),
             resources: total_resources,
-            predicates: FilterPredicates::default(),
+            predicates: FilterPredicates::from(workload),
+        }
+    }
+}
+
+impl From<&WorkloadDefinition> for FilterPredicates {
+    /// Translates the workload's placement into predicates. Instance anti-affinity
+    /// selects on the workload's own labels so its replicas repel each other.
+    fn from(workload: &WorkloadDefinition) -> Self {
+        let placement = &workload.placement;
+
+        let node_selector = (!placement.node_selector.is_empty()).then(|| NodeSelector {
+            label_requirements: placement.node_selector.clone(),
+            field_requirements: HashMap::new(),
+        });
+
+        let node_affinity = placement.node_affinity.as_ref().map(|rules| NodeAffinity {
+            required_terms: if rules.required.is_empty() {
+                vec![]
+            } else {
+                vec![AffinityTerm::from(rules.required.as_slice())]
+            },
+            preferred_terms: rules
+                .preferred
+                .iter()
+                .map(|preferred| WeightedAffinityTerm {
+                    term: AffinityTerm::from(preferred.match_expressions.as_slice()),
+                    weight: preferred.weight,
+                })
+                .collect(),
+        });
+
+        let pod_anti_affinity = placement.instance_anti_affinity.as_ref().map(|anti| {
+            let term = PodAffinityTerm {
+                label_selector: workload.labels.clone(),
+                topology_key: anti
+                    .topology_key
+                    .clone()
+                    .unwrap_or_else(|| HOSTNAME_TOPOLOGY_KEY.to_string()),
+                namespaces: vec![],
+            };
+            if anti.required {
+                PodAntiAffinity {
+                    required_terms: vec![term],
+                    preferred_terms: vec![],
+                }
+            } else {
+                PodAntiAffinity {
+                    required_terms: vec![],
+                    preferred_terms: vec![WeightedPodAffinityTerm {
+                        term,
+                        weight: anti.weight,
+                    }],
+                }
+            }
+        });
+
+        FilterPredicates {
+            node_selector,
+            node_affinity,
+            pod_anti_affinity,
+            ..Default::default()
         }
     }
 }
@@ -168,6 +235,50 @@ pub struct PodAffinityTerm {
     pub namespaces: Vec<String>,
 }
 
+impl PodAffinityTerm {
+    /// Counts placed pods matching the label selector that share `node`'s topology domain.
+    ///
+    /// A node without a value for the topology key belongs to no domain and never counts.
+    pub fn count_in_domain(
+        &self,
+        node: &Node,
+        nodes: &[Node],
+        placed: &[WorkloadInstanceInfo],
+    ) -> usize {
+        let Some(domain) = topology_domain(node, &self.topology_key) else {
+            return 0;
+        };
+        placed
+            .iter()
+            .filter(|pod| {
+                self.label_selector
+                    .iter()
+                    .all(|(key, value)| pod.labels.get(key) == Some(value))
+            })
+            .filter(|pod| {
+                let pod_domain = match nodes.iter().find(|n| n.id == pod.node_id) {
+                    Some(pod_node) => topology_domain(pod_node, &self.topology_key),
+                    // The node may have left the pool; its id still identifies the host
+                    None if self.topology_key == HOSTNAME_TOPOLOGY_KEY => {
+                        Some(pod.node_id.to_string())
+                    }
+                    None => None,
+                };
+                pod_domain.as_deref() == Some(domain.as_str())
+            })
+            .count()
+    }
+}
+
+/// Returns the node's value for a topology key. Nodes without a hostname label
+/// fall back to their id for [`HOSTNAME_TOPOLOGY_KEY`].
+pub fn topology_domain(node: &Node, topology_key: &str) -> Option<String> {
+    node.labels
+        .get(topology_key)
+        .cloned()
+        .or_else(|| (topology_key == HOSTNAME_TOPOLOGY_KEY).then(|| node.id.to_string()))
+}
+
 /// Weighted pod affinity term for preferred constraints.
 #[derive(Debug, Clone, PartialEq)]
 pub struct WeightedPodAffinityTerm {
@@ -190,6 +301,84 @@ pub struct MatchExpression {
     pub values: Vec<String>,
 }
 
+impl MatchExpression {
+    /// Evaluates the expression against a set of labels or fields.
+    ///
+    /// `NotIn` and `DoesNotExist` match when the key is absent. `Gt` and `Lt`
+    /// compare the value and the first entry of `values` as integers and fail
+    /// if either does not parse.
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        let value = labels.get(&self.key);
+        match self.operator {
+            MatchOperator::In => value.is_some_and(|v| self.values.contains(v)),
+            MatchOperator::NotIn => !value.is_some_and(|v| self.values.contains(v)),
+            MatchOperator::Exists => value.is_some(),
+            MatchOperator::DoesNotExist => value.is_none(),
+            MatchOperator::Gt | MatchOperator::Lt => {
+                let parsed = value.and_then(|v| v.parse::<i64>().ok());
+                let bound = self.values.first().and_then(|b| b.parse::<i64>().ok());
+                match (parsed, bound) {
+                    (Some(v), Some(bound)) if self.operator == MatchOperator::Gt => v > bound,
+                    (Some(v), Some(bound)) => v < bound,
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+impl From<&NodeSelectorRequirement> for MatchExpression {
+    fn from(requirement: &NodeSelectorRequirement) -> Self {
+        MatchExpression {
+            key: requirement.key.clone(),
+            operator: requirement.operator.into(),
+            values: requirement.values.clone(),
+        }
+    }
+}
+
+impl From<&[NodeSelectorRequirement]> for AffinityTerm {
+    fn from(requirements: &[NodeSelectorRequirement]) -> Self {
+        AffinityTerm {
+            match_expressions: requirements.iter().map(Into::into).collect(),
+            match_fields: vec![],
+        }
+    }
+}
+
+impl AffinityTerm {
+    /// Whether the node satisfies every label and field expression.
+    pub fn matches(&self, node: &Node) -> bool {
+        let fields = node_fields(node);
+        self.match_expressions
+            .iter()
+            .all(|e| e.matches(&node.labels))
+            && self.match_fields.iter().all(|e| e.matches(&fields))
+    }
+}
+
+impl NodeSelector {
+    /// Whether the node carries every required label and field value.
+    pub fn matches(&self, node: &Node) -> bool {
+        let fields = node_fields(node);
+        self.label_requirements
+            .iter()
+            .all(|(key, value)| node.labels.get(key) == Some(value))
+            && self
+                .field_requirements
+                .iter()
+                .all(|(key, value)| fields.get(key) == Some(value))
+    }
+}
+
+/// Node fields addressable by field selectors.
+fn node_fields(node: &Node) -> HashMap<String, String> {
+    HashMap::from([
+        ("metadata.name".to_string(), node.id.to_string()),
+        ("address".to_string(), node.address.clone()),
+    ])
+}
+
 /// Operators for match expressions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MatchOperator {
@@ -207,6 +396,19 @@ pub enum MatchOperator {
     Lt,
 }
 
+impl From<NodeSelectorOperator> for MatchOperator {
+    fn from(operator: NodeSelectorOperator) -> Self {
+        match operator {
+            NodeSelectorOperator::In => MatchOperator::In,
+            NodeSelectorOperator::NotIn => MatchOperator::NotIn,
+            NodeSelectorOperator::Exists => MatchOperator::Exists,
+            NodeSelectorOperator::DoesNotExist => MatchOperator::DoesNotExist,
+            NodeSelectorOperator::Gt => MatchOperator::Gt,
+            NodeSelectorOperator::Lt => MatchOperator::Lt,
+        }
+    }
+}
+
 /// A toleration allows a pod to schedule on nodes with matching taints.
 ///
 /// Tolerations enable pods to "tolerate" node taints, allowing them to be scheduled
@@ -293,6 +495,8 @@ pub enum RejectionReason {
     InsufficientMemory { requested: u64, available: u64 },
     /// Insufficient disk capacity
     InsufficientDisk { requested: u64, available: u64 },
+    /// Requested GPUs, GPU shares or MIG instances not free
+    InsufficientGpu { details: String },
     /// Node selector did not match
     NodeSelectorMismatch { details: String },
     /// Node affinity requirement not satisfied
@@ -501,6 +705,92 @@ impl Filter for ResourceFeasibilityChecker {
     }
 }
 
+/// Enforces node selectors, required node affinity and required pod anti-affinity.
+///
+/// Anti-affinity is evaluated against the pods in `placed`, which the caller
+/// extends as it assigns replicas so later replicas see earlier ones.
+#[derive(Debug, Clone, Default)]
+pub struct AffinityFilter {
+    placed: Vec<WorkloadInstanceInfo>,
+}
+
+impl AffinityFilter {
+    /// Creates a filter aware of the given already-placed pods.
+    pub fn new(placed: Vec<WorkloadInstanceInfo>) -> Self {
+        Self { placed }
+    }
+
+    /// Checks a single node; `nodes` resolves the topology domains of placed pods.
+    pub fn check_node(
+        &self,
+        pod: &Pod,
+        node: &Node,
+        nodes: &[Node],
+    ) -> Result<(), RejectionReason> {
+        let predicates = &pod.predicates;
+
+        if let Some(selector) = &predicates.node_selector {
+            if !selector.matches(node) {
+                return Err(RejectionReason::NodeSelectorMismatch {
+                    details: format!(
+                        "node labels do not satisfy selector {:?}",
+                        selector.label_requirements
+                    ),
+                });
+            }
+        }
+
+        if let Some(affinity) = &predicates.node_affinity {
+            if let Some(term) = affinity.required_terms.iter().find(|t| !t.matches(node)) {
+                return Err(RejectionReason::NodeAffinityMismatch {
+                    details: format!("required term not satisfied: {:?}", term.match_expressions),
+                });
+            }
+        }
+
+        if let Some(anti_affinity) = &predicates.pod_anti_affinity {
+            for term in &anti_affinity.required_terms {
+                let count = term.count_in_domain(node, nodes, &self.placed);
+                if count > 0 {
+                    return Err(RejectionReason::PodAntiAffinityViolation {
+                        details: format!(
+                            "{} matching pod(s) already in topology domain '{}'",
+                            count, term.topology_key
+                        ),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Filter for AffinityFilter {
+    fn filter(&self, pod: &Pod, nodes: &[Node]) -> FilterResult {
+        let mut eligible_nodes = Vec::new();
+        let mut filtered_nodes = Vec::new();
+        let mut admission_reasons = HashMap::new();
+
+        for node in nodes {
+            match self.check_node(pod, node, nodes) {
+                Ok(()) => {
+                    eligible_nodes.push(node.id);
+                    admission_reasons
+                        .insert(node.id, vec!["Placement constraints satisfied".to_string()]);
+                }
+                Err(reason) => filtered_nodes.push((node.id, reason)),
+            }
+        }
+
+        FilterResult {
+            eligible_nodes,
+            filtered_nodes,
+            admission_reasons,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -526,6 +816,8 @@ mod tests {
                 memory_mb: memory,
                 disk_mb: disk,
             },
+            unschedulable: false,
+            resource_version: 0,
         }
     }
 
@@ -630,4 +922,108 @@ mod tests {
         ];
         assert_eq!(operators.len(), 6);
     }
+
+    fn labelled_node(labels: &[(&str, &str)]) -> Node {
+        let mut node = create_test_node(4.0, 8192, 100000);
+        node.labels = labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        node
+    }
+
+    fn expression(key: &str, operator: MatchOperator, values: &[&str]) -> MatchExpression {
+        MatchExpression {
+            key: key.to_string(),
+            operator,
+            values: values.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_match_expression_evaluation() {
+        let labels: HashMap<String, String> = [("zone", "a"), ("cores", "8")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        assert!(expression("zone", MatchOperator::In, &["a", "b"]).matches(&labels));
+        assert!(!expression("zone", MatchOperator::NotIn, &["a"]).matches(&labels));
+        assert!(expression("gpu", MatchOperator::NotIn, &["yes"]).matches(&labels));
+        assert!(expression("zone", MatchOperator::Exists, &[]).matches(&labels));
+        assert!(expression("gpu", MatchOperator::DoesNotExist, &[]).matches(&labels));
+        assert!(expression("cores", MatchOperator::Gt, &["4"]).matches(&labels));
+        assert!(!expression("cores", MatchOperator::Lt, &["4"]).matches(&labels));
+        assert!(!expression("zone", MatchOperator::Gt, &["4"]).matches(&labels));
+    }
+
+    #[test]
+    fn test_affinity_filter_node_rules() {
+        let ssd_a = labelled_node(&[("disk", "ssd"), ("zone", "a")]);
+        let ssd_b = labelled_node(&[("disk", "ssd"), ("zone", "b")]);
+        let hdd_a = labelled_node(&[("disk", "hdd"), ("zone", "a")]);
+        let nodes = vec![ssd_a.clone(), ssd_b.clone(), hdd_a.clone()];
+
+        let mut pod = create_test_pod(1.0, 512, 1024);
+        pod.predicates.node_selector = Some(NodeSelector {
+            label_requirements: HashMap::from([("disk".to_string(), "ssd".to_string())]),
+            field_requirements: HashMap::new(),
+        });
+        pod.predicates.node_affinity = Some(NodeAffinity {
+            required_terms: vec![AffinityTerm {
+                match_expressions: vec![expression("zone", MatchOperator::In, &["b"])],
+                match_fields: vec![],
+            }],
+            preferred_terms: vec![],
+        });
+
+        let result = AffinityFilter::default().filter(&pod, &nodes);
+        assert_eq!(result.eligible_nodes, vec![ssd_b.id]);
+        assert!(matches!(
+            result.filtered_nodes.iter().find(|(id, _)| *id == ssd_a.id),
+            Some((_, RejectionReason::NodeAffinityMismatch { .. }))
+        ));
+        assert!(matches!(
+            result.filtered_nodes.iter().find(|(id, _)| *id == hdd_a.id),
+            Some((_, RejectionReason::NodeSelectorMismatch { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_required_anti_affinity_by_topology_domain() {
+        let a1 = labelled_node(&[("zone", "a")]);
+        let a2 = labelled_node(&[("zone", "a")]);
+        let b1 = labelled_node(&[("zone", "b")]);
+        let nodes = vec![a1.clone(), a2.clone(), b1.clone()];
+        let labels = HashMap::from([("app".to_string(), "web".to_string())]);
+
+        let mut pod = create_test_pod(1.0, 512, 1024);
+        pod.labels = labels.clone();
+        pod.predicates.pod_anti_affinity = Some(PodAntiAffinity {
+            required_terms: vec![PodAffinityTerm {
+                label_selector: labels.clone(),
+                topology_key: "zone".to_string(),
+                namespaces: vec![],
+            }],
+            preferred_terms: vec![],
+        });
+
+        let placed = vec![WorkloadInstanceInfo {
+            node_id: a1.id,
+            labels,
+            workload_name: "web".to_string(),
+        }];
+
+        // The whole of zone a is taken by the replica on a1
+        let result = AffinityFilter::new(placed).filter(&pod, &nodes);
+        assert_eq!(result.eligible_nodes, vec![b1.id]);
+        assert!(matches!(
+            result.filtered_nodes.iter().find(|(id, _)| *id == a2.id),
+            Some((_, RejectionReason::PodAntiAffinityViolation { .. }))
+        ));
+
+        // Nothing placed yet means nothing to avoid
+        let result = AffinityFilter::default().filter(&pod, &nodes);
+        assert_eq!(result.eligible_nodes.len(), 3);
+    }
 }
@@ -5,6 +5,9 @@ use orchestrator_shared_types::{
 // To get node information
 use std::sync::Arc;
 
+use filter::{AffinityFilter, Filter, Pod};
+use score::{AffinityScorer, Scorer, ScoringContext, WorkloadInstanceInfo};
+
 pub mod bind;
 pub mod filter;
 pub mod resources;
@@ -91,23 +94,205 @@ impl Scheduler for SimpleScheduler {
             return Ok(decisions);
         }
 
-        // Super simple: round-robin or pick first available that meets basic criteria (if any)
-        // For now, just pick the first available node for all needed replicas (very naive)
-        let mut node_iter = available_nodes.iter().cycle(); // Cycle through nodes
+        let workload = &request.workload_definition;
+        let pod = Pod::from(workload.as_ref());
+        let scorer = AffinityScorer::new();
+        // Replicas assigned in this pass count towards anti-affinity for the next
+        let mut placed: Vec<WorkloadInstanceInfo> = request
+            .current_instances
+            .iter()
+            .map(|instance| WorkloadInstanceInfo {
+                node_id: instance.node_id,
+                labels: workload.labels.clone(),
+                workload_name: workload.name.clone(),
+            })
+            .collect();
 
         for _ in 0..needed_replicas {
-            if let Some(node) = node_iter.next() {
-                // TODO: Actual resource checking against node.resources_allocatable
-                // and request.workload_definition.containers[*].resource_requests
-                decisions.push(ScheduleDecision::AssignNode(node.id));
-            } else {
-                // This case should not be hit if available_nodes is not empty due to cycle()
-                // but as a safeguard:
-                decisions.push(ScheduleDecision::NoPlacement(
+            // TODO: Actual resource checking against node.resources_allocatable
+            // and request.workload_definition.containers[*].resource_requests
+            let filtered = AffinityFilter::new(placed.clone()).filter(&pod, available_nodes);
+            if filtered.eligible_nodes.is_empty() {
+                let reasons: Vec<String> = filtered
+                    .filtered_nodes
+                    .iter()
+                    .map(|(_, reason)| format!("{:?}", reason))
+                    .collect();
+                decisions.push(ScheduleDecision::NoPlacement(format!(
+                    "No node satisfies placement constraints: {}",
+                    reasons.join("; ")
+                )));
+                continue;
+            }
+
+            // Fewest replicas first, so unconstrained workloads still round-robin;
+            // the stable ranking keeps this order among equally scored nodes
+            let mut candidates = filtered.eligible_nodes;
+            candidates.sort_by_key(|id| placed.iter().filter(|p| p.node_id == *id).count());
+
+            let context = ScoringContext::new(workload.clone(), available_nodes.to_vec())
+                .with_instances(placed.clone());
+            let ranking = scorer.score(&candidates, &context);
+            match ranking.best_node() {
+                Some(best) => {
+                    decisions.push(ScheduleDecision::AssignNode(best.node_id));
+                    placed.push(WorkloadInstanceInfo {
+                        node_id: best.node_id,
+                        labels: workload.labels.clone(),
+                        workload_name: workload.name.clone(),
+                    });
+                }
+                None => decisions.push(ScheduleDecision::NoPlacement(
                     "Failed to pick a node (internal error)".to_string(),
-                ));
+                )),
             }
         }
         Ok(decisions)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orchestrator_shared_types::{
+        InstanceAntiAffinity, Keypair, NodeAffinityRules, NodeResources, NodeSelectorOperator,
+        NodeSelectorRequirement, NodeStatus, Placement, PreferredNodeTerm,
+    };
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn node(labels: &[(&str, &str)]) -> Node {
+        let resources = NodeResources {
+            cpu_cores: 4.0,
+            memory_mb: 8192,
+            disk_mb: 100000,
+        };
+        Node {
+            id: Keypair::generate().public_key(),
+            address: "127.0.0.1:8080".to_string(),
+            status: NodeStatus::Ready,
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            resources_capacity: resources.clone(),
+            resources_allocatable: resources,
+            unschedulable: false,
+            resource_version: 0,
+        }
+    }
+
+    fn request(replicas: u32, placement: Placement) -> ScheduleRequest {
+        ScheduleRequest {
+            workload_definition: Arc::new(WorkloadDefinition {
+                id: Uuid::new_v4(),
+                name: "web".to_string(),
+                containers: vec![],
+                init_containers: vec![],
+                sidecars: vec![],
+                replicas,
+                labels: HashMap::from([("app".to_string(), "web".to_string())]),
+                placement,
+                restart_policy: Default::default(),
+                namespace: "default".to_string(),
+                resource_version: 0,
+            }),
+            current_instances: vec![],
+        }
+    }
+
+    fn assigned(decisions: &[ScheduleDecision]) -> Vec<NodeId> {
+        decisions
+            .iter()
+            .filter_map(|d| match d {
+                ScheduleDecision::AssignNode(id) => Some(*id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_required_anti_affinity_spreads_replicas() {
+        let nodes = vec![node(&[]), node(&[]), node(&[])];
+        let placement = Placement {
+            instance_anti_affinity: Some(InstanceAntiAffinity::required()),
+            ..Default::default()
+        };
+
+        let decisions = SimpleScheduler
+            .schedule(&request(4, placement), &nodes)
+            .await
+            .unwrap();
+
+        let mut placed = assigned(&decisions);
+        assert_eq!(placed.len(), 3);
+        placed.sort_by_key(|id| id.to_string());
+        placed.dedup();
+        assert_eq!(placed.len(), 3);
+        assert!(matches!(decisions[3], ScheduleDecision::NoPlacement(_)));
+    }
+
+    #[tokio::test]
+    async fn test_node_selector_and_preferred_affinity() {
+        let hdd = node(&[("disk", "hdd"), ("zone", "a")]);
+        let ssd_a = node(&[("disk", "ssd"), ("zone", "a")]);
+        let ssd_b = node(&[("disk", "ssd"), ("zone", "b")]);
+        let nodes = vec![hdd.clone(), ssd_b.clone(), ssd_a.clone()];
+
+        let placement = Placement {
+            node_selector: HashMap::from([("disk".to_string(), "ssd".to_string())]),
+            node_affinity: Some(NodeAffinityRules {
+                required: vec![],
+                preferred: vec![PreferredNodeTerm {
+                    weight: 50,
+                    match_expressions: vec![NodeSelectorRequirement {
+                        key: "zone".to_string(),
+                        operator: NodeSelectorOperator::In,
+                        values: vec!["a".to_string()],
+                    }],
+                }],
+            }),
+            instance_anti_affinity: None,
+            scheduling_strategy: None,
+            priority: 0,
+        };
+
+        let decisions = SimpleScheduler
+            .schedule(&request(2, placement), &nodes)
+            .await
+            .unwrap();
+
+        // Both replicas go to the preferred zone and never to the hdd node
+        assert_eq!(assigned(&decisions), vec![ssd_a.id, ssd_a.id]);
+    }
+
+    #[tokio::test]
+    async fn test_preferred_anti_affinity_outweighs_node_preference() {
+        let zone_a = node(&[("zone", "a")]);
+        let zone_b = node(&[("zone", "b")]);
+        let nodes = vec![zone_a.clone(), zone_b.clone()];
+
+        let placement = Placement {
+            node_affinity: Some(NodeAffinityRules {
+                required: vec![],
+                preferred: vec![PreferredNodeTerm {
+                    weight: 10,
+                    match_expressions: vec![NodeSelectorRequirement {
+                        key: "zone".to_string(),
+                        operator: NodeSelectorOperator::In,
+                        values: vec!["a".to_string()],
+                    }],
+                }],
+            }),
+            instance_anti_affinity: Some(InstanceAntiAffinity::preferred(100)),
+            ..Default::default()
+        };
+
+        let decisions = SimpleScheduler
+            .schedule(&request(2, placement), &nodes)
+            .await
+            .unwrap();
+
+        assert_eq!(assigned(&decisions), vec![zone_a.id, zone_b.id]);
+    }
+}
@@ -33,6 +33,7 @@
 //! After scoring, nodes are sorted in descending order by final_score, and the
 //! scheduler selects the highest-scoring node for placement.
 
+use crate::filter::Pod;
 use orchestrator_shared_types::{Node, NodeId, WorkloadDefinition};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -508,6 +509,85 @@ pub trait Scorer: Send + Sync {
     fn score(&self, eligible_nodes: &[NodeId], context: &ScoringContext) -> ScoringResult;
 }
 
+/// Scores nodes by preferred node affinity and preferred pod anti-affinity.
+///
+/// Each node's raw score is the sum of the weights of the preferred node terms
+/// it matches, minus each preferred anti-affinity weight for every matching
+/// instance already in its topology domain. Raw scores are then rescaled to
+/// 0-100 across the eligible nodes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AffinityScorer;
+
+impl AffinityScorer {
+    /// Name under which the weight of this scorer is looked up in the context.
+    pub const WEIGHT_KEY: &'static str = "affinity";
+
+    /// Creates a new affinity scorer.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Unnormalized preference for placing the pod on `node`.
+    pub fn raw_score(&self, pod: &Pod, node: &Node, context: &ScoringContext) -> i64 {
+        let predicates = &pod.predicates;
+        let mut score = 0i64;
+
+        if let Some(affinity) = &predicates.node_affinity {
+            score += affinity
+                .preferred_terms
+                .iter()
+                .filter(|preferred| preferred.term.matches(node))
+                .map(|preferred| i64::from(preferred.weight))
+                .sum::<i64>();
+        }
+
+        if let Some(anti_affinity) = &predicates.pod_anti_affinity {
+            for preferred in &anti_affinity.preferred_terms {
+                let count = preferred.term.count_in_domain(
+                    node,
+                    &context.nodes,
+                    &context.existing_instances,
+                );
+                score -= i64::from(preferred.weight) * count as i64;
+            }
+        }
+
+        score
+    }
+}
+
+impl Scorer for AffinityScorer {
+    fn score(&self, eligible_nodes: &[NodeId], context: &ScoringContext) -> ScoringResult {
+        let pod = Pod::from(context.workload.as_ref());
+        let raw: Vec<(NodeId, i64)> = eligible_nodes
+            .iter()
+            .filter_map(|id| context.nodes.iter().find(|node| node.id == *id))
+            .map(|node| (node.id, self.raw_score(&pod, node, context)))
+            .collect();
+
+        let min = raw.iter().map(|(_, score)| *score).min().unwrap_or(0);
+        let max = raw.iter().map(|(_, score)| *score).max().unwrap_or(0);
+        let coefficient = context
+            .weights
+            .get(Self::WEIGHT_KEY)
+            .map_or(1.0, |weight| weight.coefficient);
+
+        let scores = raw
+            .into_iter()
+            .map(|(node_id, score)| {
+                let normalized = if max == min {
+                    100
+                } else {
+                    ((score - min) * 100 / (max - min)) as u32
+                };
+                NodeScore::new(node_id, normalized, coefficient)
+            })
+            .collect();
+
+        ScoringResult::new(scores)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -603,6 +683,12 @@ mod tests {
             containers: vec![],
             replicas: 3,
             labels: HashMap::new(),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
         });
 
         let node_id = generate_node_id();
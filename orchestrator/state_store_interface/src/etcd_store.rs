@@ -1,20 +1,47 @@
 use async_trait::async_trait;
-use etcd_client::{Client, GetOptions, Error as EtcdError};
+use etcd_client::{
+    Client, Compare, CompareOp, DeleteOptions, EventType, GetOptions, PutOptions, Txn, TxnOp,
+    WatchOptions,
+};
 use orchestrator_shared_types::{
-    Node, NodeId, OrchestrationError, Result, WorkloadDefinition, WorkloadId, WorkloadInstance,
+    Event, LeaderLease, Namespace, Node, NodeId, OrchestrationError, Result, UsageRecord,
+    WorkloadDefinition, WorkloadId, WorkloadInstance, WorkloadRevision,
 };
 use serde_json;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::{
+    check_resource_version, next_lease, StateStore, StateStoreError, Versioned, WatchEvent,
+    WatchEventType,
+};
+
+/// Most operations etcd accepts in one transaction (its `--max-txn-ops` default).
+///
+/// Even, so the pair of operations writing or deleting an instance and its
+/// workload index entry is never split across transactions.
+const MAX_TXN_OPS: usize = 128;
+const _: () = assert!(MAX_TXN_OPS % 2 == 0);
 
-use crate::{StateStore, StateStoreError};
+/// Updates buffered per watch before the watcher waits for the receiver.
+const WATCH_BUFFER: usize = 256;
 
 /// Etcd-backed implementation of StateStore
 ///
 /// This implementation uses etcd for distributed, persistent state storage.
 /// Suitable for production multi-node deployments with high availability.
+///
+/// Instances are written together with their per-workload index in one
+/// transaction, so the two never disagree. Resource versions are etcd mod
+/// revisions, and the `watch_*` methods stream etcd watch events.
+/// With [`with_node_ttl`](Self::with_node_ttl), node records are attached to
+/// an etcd lease and vanish when a node stops re-registering.
 pub struct EtcdStateStore {
     client: Arc<tokio::sync::Mutex<Client>>,
     prefix: String, // Key prefix for namespacing (e.g., "/orchestrator/")
+    /// Lifetime of node records after each write (None = permanent)
+    node_ttl: Option<Duration>,
 }
 
 impl EtcdStateStore {
@@ -30,6 +57,7 @@ impl EtcdStateStore {
         Ok(Self {
             client: Arc::new(tokio::sync::Mutex::new(client)),
             prefix: "/orchestrator".to_string(),
+            node_ttl: None,
         })
     }
 
@@ -42,9 +70,17 @@ impl EtcdStateStore {
         Ok(Self {
             client: Arc::new(tokio::sync::Mutex::new(client)),
             prefix,
+            node_ttl: None,
         })
     }
 
+    /// Expire node records `ttl` after their last write, rounded up to
+    /// whole seconds.
+    pub fn with_node_ttl(mut self, ttl: Duration) -> Self {
+        self.node_ttl = Some(ttl);
+        self
+    }
+
     // Helper methods for key construction
     fn node_key(&self, node_id: &NodeId) -> String {
         format!("{}/nodes/{}", self.prefix, node_id)
@@ -74,6 +110,102 @@ impl EtcdStateStore {
         format!("{}/instances_by_workload/{}/", self.prefix, workload_id)
     }
 
+    fn instance_by_workload_key(&self, instance: &WorkloadInstance) -> String {
+        format!(
+            "{}/instances_by_workload/{}/{}",
+            self.prefix, instance.workload_id, instance.id
+        )
+    }
+
+    fn namespace_key(&self, name: &str) -> String {
+        format!("{}/namespaces/{}", self.prefix, name)
+    }
+
+    fn namespaces_prefix(&self) -> String {
+        format!("{}/namespaces/", self.prefix)
+    }
+
+    fn revisions_prefix(&self, workload_id: &WorkloadId) -> String {
+        format!("{}/workload_revisions/{}/", self.prefix, workload_id)
+    }
+
+    // Zero-padded so keys list in revision order
+    fn revision_key(&self, workload_id: &WorkloadId, revision: u64) -> String {
+        format!("{}{:020}", self.revisions_prefix(workload_id), revision)
+    }
+
+    fn event_key(&self, event_id: &uuid::Uuid) -> String {
+        format!("{}/events/{}", self.prefix, event_id)
+    }
+
+    fn events_prefix(&self) -> String {
+        format!("{}/events/", self.prefix)
+    }
+
+    // Zero-padded so keys list in period order
+    fn usage_period_prefix(&self, period_start_ms: u64) -> String {
+        format!("{}/usage/{:020}/", self.prefix, period_start_ms)
+    }
+
+    fn usage_key(&self, record: &UsageRecord) -> String {
+        format!(
+            "{}{}",
+            self.usage_period_prefix(record.period_start_ms),
+            record.workload_id
+        )
+    }
+
+    fn resources_prefix(&self, kind: &str) -> String {
+        format!("{}/resources/{}/", self.prefix, kind)
+    }
+
+    fn resource_key(&self, kind: &str, name: &str) -> String {
+        format!("{}{}", self.resources_prefix(kind), name)
+    }
+
+    fn lease_key(&self, name: &str) -> String {
+        format!("{}/leases/{}", self.prefix, name)
+    }
+
+    // Helper to read a lease with the revision it was last written at (0 if absent)
+    async fn get_lease_with_revision(&self, key: &str) -> Result<(Option<LeaderLease>, i64)> {
+        let mut client = self.client.lock().await;
+        let response = client
+            .get(key, None)
+            .await
+            .map_err(|e| StateStoreError::InternalError(format!("etcd get failed: {}", e)))?;
+
+        match response.kvs().first() {
+            Some(kv) => {
+                let lease = serde_json::from_slice(kv.value())
+                    .map_err(|e| StateStoreError::SerializationError(e.to_string()))?;
+                Ok((Some(lease), kv.mod_revision()))
+            }
+            None => Ok((None, 0)),
+        }
+    }
+
+    // Helper to write a lease only if it is unchanged since `revision`
+    async fn put_lease_if_unchanged(
+        &self,
+        key: &str,
+        revision: i64,
+        lease: &LeaderLease,
+    ) -> Result<bool> {
+        let json = serde_json::to_string(lease)
+            .map_err(|e| StateStoreError::SerializationError(e.to_string()))?;
+        let txn = Txn::new()
+            .when(vec![Compare::mod_revision(key, CompareOp::Equal, revision)])
+            .and_then(vec![TxnOp::put(key, json, None)]);
+
+        let mut client = self.client.lock().await;
+        let response = client
+            .txn(txn)
+            .await
+            .map_err(|e| StateStoreError::TransactionError(format!("etcd txn failed: {}", e)))?;
+        Ok(response.succeeded())
+    }
+
     // Helper to serialize and put a value
     async fn put_value<T: serde::Serialize>(&self, key: String, value: &T) -> Result<()> {
         let json = serde_json::to_string(value)
@@ -88,8 +220,11 @@ impl EtcdStateStore {
         Ok(())
     }
 
-    // Helper to get and deserialize a value
-    async fn get_value<T: serde::de::DeserializeOwned>(&self, key: String) -> Result<Option<T>> {
+    // Helper to get and deserialize a value, versioned by its mod revision
+    async fn get_value<T: serde::de::DeserializeOwned + Versioned>(
+        &self,
+        key: String,
+    ) -> Result<Option<T>> {
         let mut client = self.client.lock().await;
         let response = client
             .get(key, None)
@@ -100,8 +235,9 @@ impl EtcdStateStore {
             let json = kv.value_str()
                 .map_err(|e| StateStoreError::SerializationError(format!("Invalid UTF-8: {}", e)))?;
 
-            let value: T = serde_json::from_str(json)
+            let mut value: T = serde_json::from_str(json)
                 .map_err(|e| StateStoreError::SerializationError(e.to_string()))?;
+            value.set_resource_version(kv.mod_revision() as u64);
 
             Ok(Some(value))
         } else {
@@ -110,7 +246,10 @@ impl EtcdStateStore {
     }
 
     // Helper to list all values with a prefix
-    async fn list_with_prefix<T: serde::de::DeserializeOwned>(&self, prefix: String) -> Result<Vec<T>> {
+    async fn list_with_prefix<T: serde::de::DeserializeOwned + Versioned>(
+        &self,
+        prefix: String,
+    ) -> Result<Vec<T>> {
         let mut client = self.client.lock().await;
         let get_options = GetOptions::new().with_prefix();
 
@@ -124,8 +263,9 @@ impl EtcdStateStore {
             let json = kv.value_str()
                 .map_err(|e| StateStoreError::SerializationError(format!("Invalid UTF-8: {}", e)))?;
 
-            let value: T = serde_json::from_str(json)
+            let mut value: T = serde_json::from_str(json)
                 .map_err(|e| StateStoreError::SerializationError(e.to_string()))?;
+            value.set_resource_version(kv.mod_revision() as u64);
 
             results.push(value);
         }
@@ -143,6 +283,109 @@ impl EtcdStateStore {
 
         Ok(())
     }
+
+    // Helper to put a value attached to a new lease of `ttl`
+    async fn put_value_with_ttl<T: serde::Serialize>(
+        &self,
+        key: String,
+        value: &T,
+        ttl: Duration,
+    ) -> Result<()> {
+        let json = serde_json::to_string(value)
+            .map_err(|e| StateStoreError::SerializationError(e.to_string()))?;
+        let ttl_secs = ttl.as_secs() + u64::from(ttl.subsec_nanos() > 0);
+
+        let mut client = self.client.lock().await;
+        let lease = client
+            .lease_grant(ttl_secs.max(1) as i64, None)
+            .await
+            .map_err(|e| StateStoreError::InternalError(format!("etcd lease grant failed: {}", e)))?;
+        client
+            .put(key, json, Some(PutOptions::new().with_lease(lease.id())))
+            .await
+            .map_err(|e| StateStoreError::InternalError(format!("etcd put failed: {}", e)))?;
+
+        Ok(())
+    }
+
+    // Helper to apply operations in order, in as few transactions as etcd's
+    // per-transaction limit allows. Only each transaction is atomic: when one
+    // fails, those before it stay applied. Callers with more than
+    // MAX_TXN_OPS operations keep the store consistent between transactions
+    // and safe to retry.
+    async fn commit(&self, ops: Vec<TxnOp>) -> Result<()> {
+        let mut client = self.client.lock().await;
+        let mut ops = ops.into_iter().peekable();
+        while ops.peek().is_some() {
+            let chunk: Vec<TxnOp> = ops.by_ref().take(MAX_TXN_OPS).collect();
+            client
+                .txn(Txn::new().and_then(chunk))
+                .await
+                .map_err(|e| StateStoreError::TransactionError(format!("etcd txn failed: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    // Helper to build the puts storing an instance and its workload index entry
+    fn instance_put_ops(&self, instance: &WorkloadInstance) -> Result<[TxnOp; 2]> {
+        let json = serde_json::to_string(instance)
+            .map_err(|e| StateStoreError::SerializationError(e.to_string()))?;
+        Ok([
+            TxnOp::put(self.instance_key(&instance.id.to_string()), json.clone(), None),
+            TxnOp::put(self.instance_by_workload_key(instance), json, None),
+        ])
+    }
+
+    // Helper to stream decoded changes under a prefix
+    async fn watch_prefix<T>(&self, prefix: String) -> Result<mpsc::Receiver<WatchEvent<T>>>
+    where
+        T: serde::de::DeserializeOwned + Versioned + Send + 'static,
+    {
+        let (watcher, mut stream) = {
+            let mut client = self.client.lock().await;
+            // Deletes carry no value, so ask for the previous one
+            let options = WatchOptions::new().with_prefix().with_prev_key();
+            client
+                .watch(prefix, Some(options))
+                .await
+                .map_err(|e| StateStoreError::ConnectionError(format!("etcd watch failed: {}", e)))?
+        };
+
+        let (tx, rx) = mpsc::channel(WATCH_BUFFER);
+        tokio::spawn(async move {
+            // Dropping the watcher cancels the watch, so keep it with the stream
+            let _watcher = watcher;
+            while let Ok(Some(response)) = stream.message().await {
+                for event in response.events() {
+                    let Some(kv) = event.kv() else {
+                        continue;
+                    };
+                    let (event_type, stored) = if matches!(event.event_type(), EventType::Put) {
+                        let event_type = if kv.create_revision() == kv.mod_revision() {
+                            WatchEventType::Added
+                        } else {
+                            WatchEventType::Modified
+                        };
+                        (event_type, kv)
+                    } else {
+                        let Some(prev) = event.prev_kv() else {
+                            continue;
+                        };
+                        (WatchEventType::Deleted, prev)
+                    };
+                    let Ok(mut object) = serde_json::from_slice::<T>(stored.value()) else {
+                        continue;
+                    };
+                    object.set_resource_version(stored.mod_revision().max(0) as u64);
+                    let revision = kv.mod_revision().max(0) as u64;
+                    if tx.send(WatchEvent::new(event_type, object, revision)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(rx)
+    }
 }
 
 #[async_trait]
@@ -166,7 +409,10 @@ impl StateStore for EtcdStateStore {
 
     async fn put_node(&self, node: Node) -> Result<()> {
         let key = self.node_key(&node.id);
-        self.put_value(key, &node).await
+        match self.node_ttl {
+            Some(ttl) => self.put_value_with_ttl(key, &node, ttl).await,
+            None => self.put_value(key, &node).await,
+        }
     }
 
     async fn get_node(&self, node_id: &NodeId) -> Result<Option<Node>> {
@@ -202,25 +448,62 @@ impl StateStore for EtcdStateStore {
     }
 
     async fn delete_workload(&self, workload_id: &WorkloadId) -> Result<()> {
-        let key = self.workload_key(workload_id);
-        self.delete_key(key).await
+        self.commit(vec![
+            TxnOp::delete(self.workload_key(workload_id), None),
+            TxnOp::delete(
+                self.revisions_prefix(workload_id),
+                Some(DeleteOptions::new().with_prefix()),
+            ),
+        ])
+        .await
+    }
+
+    async fn compare_and_put_workload(
+        &self,
+        mut workload: WorkloadDefinition,
+    ) -> Result<WorkloadDefinition> {
+        let key = self.workload_key(&workload.id);
+        let json = serde_json::to_string(&workload)
+            .map_err(|e| StateStoreError::SerializationError(e.to_string()))?;
+        // A missing key compares as mod revision 0
+        let txn = Txn::new()
+            .when(vec![Compare::mod_revision(
+                key.clone(),
+                CompareOp::Equal,
+                workload.resource_version as i64,
+            )])
+            .and_then(vec![TxnOp::put(key, json, None)]);
+
+        let response = {
+            let mut client = self.client.lock().await;
+            client
+                .txn(txn)
+                .await
+                .map_err(|e| StateStoreError::TransactionError(format!("etcd txn failed: {}", e)))?
+        };
+        if !response.succeeded() {
+            let id = workload.id.to_string();
+            let stored = self.get_workload(&workload.id).await?;
+            check_resource_version(
+                "workload",
+                &id,
+                stored.map(|w| w.resource_version),
+                workload.resource_version,
+            )?;
+            return Err(OrchestrationError::Conflict(format!(
+                "workload {} was modified concurrently",
+                id
+            )));
+        }
+        workload.resource_version = response.header().map_or(0, |h| h.revision()) as u64;
+        Ok(workload)
     }
 
     // ===== Instance Operations =====
 
     async fn put_instance(&self, instance: WorkloadInstance) -> Result<()> {
-        let key = self.instance_key(&instance.id.to_string());
-        // Also store under workload-specific prefix for efficient lookups
-        let workload_key = format!(
-            "{}/instances_by_workload/{}/{}",
-            self.prefix, instance.workload_id, instance.id
-        );
-
-        // Store in both locations
-        self.put_value(key, &instance).await?;
-        self.put_value(workload_key, &instance).await?;
-
-        Ok(())
+        // Store under the workload-specific prefix too, for efficient lookups
+        self.commit(Vec::from(self.instance_put_ops(&instance)?)).await
     }
 
     async fn get_instance(&self, instance_id: &str) -> Result<Option<WorkloadInstance>> {
@@ -242,39 +525,252 @@ impl StateStore for EtcdStateStore {
         // Need to delete from both locations
         // First get the instance to know its workload_id
         if let Some(instance) = self.get_instance(instance_id).await? {
-            let key = self.instance_key(instance_id);
-            let workload_key = format!(
-                "{}/instances_by_workload/{}/{}",
-                self.prefix, instance.workload_id, instance.id
-            );
-
-            self.delete_key(key).await?;
-            self.delete_key(workload_key).await?;
+            self.commit(vec![
+                TxnOp::delete(self.instance_key(instance_id), None),
+                TxnOp::delete(self.instance_by_workload_key(&instance), None),
+            ])
+            .await?;
         }
 
         Ok(())
     }
 
     async fn delete_instances_for_workload(&self, workload_id: &WorkloadId) -> Result<()> {
-        // Get all instances for this workload
         let instances = self.list_instances_for_workload(workload_id).await?;
 
-        // Delete each instance
-        for instance in instances {
-            self.delete_instance(&instance.id.to_string()).await?;
+        // Each instance leaves both keys in the same transaction, so a failed
+        // call leaves the rest listed for a retry to delete
+        let mut ops = Vec::with_capacity(instances.len() * 2 + 1);
+        for instance in &instances {
+            ops.push(TxnOp::delete(
+                self.instance_key(&instance.id.to_string()),
+                None,
+            ));
+            ops.push(TxnOp::delete(self.instance_by_workload_key(instance), None));
         }
+        ops.push(TxnOp::delete(
+            self.instances_for_workload_prefix(workload_id),
+            Some(DeleteOptions::new().with_prefix()),
+        ));
+        self.commit(ops).await
+    }
 
-        Ok(())
+    // ===== Namespace Operations =====
+
+    async fn put_namespace(&self, namespace: Namespace) -> Result<()> {
+        let key = self.namespace_key(&namespace.name);
+        self.put_value(key, &namespace).await
+    }
+
+    async fn get_namespace(&self, name: &str) -> Result<Option<Namespace>> {
+        let key = self.namespace_key(name);
+        self.get_value(key).await
+    }
+
+    async fn list_namespaces(&self) -> Result<Vec<Namespace>> {
+        let prefix = self.namespaces_prefix();
+        self.list_with_prefix(prefix).await
+    }
+
+    async fn delete_namespace(&self, name: &str) -> Result<()> {
+        let key = self.namespace_key(name);
+        self.delete_key(key).await
     }
 
     async fn put_instances_batch(&self, instances: Vec<WorkloadInstance>) -> Result<()> {
-        // Etcd supports transactions, but for simplicity we'll do sequential writes
-        // TODO: Optimize with etcd transactions
-        for instance in instances {
-            self.put_instance(instance).await?;
+        // Each instance's puts land in the same transaction, so a failed
+        // batch leaves instances either fully written or untouched, and
+        // putting the batch again is harmless
+        let mut ops = Vec::with_capacity(instances.len() * 2);
+        for instance in &instances {
+            ops.extend(self.instance_put_ops(instance)?);
+        }
+        self.commit(ops).await
+    }
+
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        now_ms: u64,
+        duration: Duration,
+    ) -> Result<LeaderLease> {
+        let key = self.lease_key(name);
+        // Retry until no other candidate wrote the lease between read and write
+        loop {
+            let (current, revision) = self.get_lease_with_revision(&key).await?;
+            let Some(lease) = next_lease(current.as_ref(), holder, now_ms, duration) else {
+                return Ok(current.unwrap_or_default());
+            };
+            if self.put_lease_if_unchanged(&key, revision, &lease).await? {
+                return Ok(lease);
+            }
+        }
+    }
+
+    async fn get_lease(&self, name: &str) -> Result<Option<LeaderLease>> {
+        let (lease, _) = self.get_lease_with_revision(&self.lease_key(name)).await?;
+        Ok(lease)
+    }
+
+    async fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+        let key = self.lease_key(name);
+        let (current, revision) = self.get_lease_with_revision(&key).await?;
+        if let Some(mut lease) = current.filter(|lease| lease.holder == holder) {
+            lease.expires_at_ms = 0;
+            // Losing the race means someone else holds it now; nothing to release
+            self.put_lease_if_unchanged(&key, revision, &lease).await?;
         }
         Ok(())
     }
+
+    // ===== Workload Revisions =====
+
+    async fn put_workload_revision(&self, revision: WorkloadRevision) -> Result<()> {
+        let key = self.revision_key(&revision.workload_id, revision.revision);
+        self.put_value(key, &revision).await
+    }
+
+    async fn list_workload_revisions(
+        &self,
+        workload_id: &WorkloadId,
+    ) -> Result<Vec<WorkloadRevision>> {
+        let mut client = self.client.lock().await;
+        let response = client
+            .get(
+                self.revisions_prefix(workload_id),
+                Some(GetOptions::new().with_prefix()),
+            )
+            .await
+            .map_err(|e| StateStoreError::InternalError(format!("etcd list failed: {}", e)))?;
+
+        let mut revisions = Vec::new();
+        for kv in response.kvs() {
+            let revision = serde_json::from_slice(kv.value())
+                .map_err(|e| StateStoreError::SerializationError(e.to_string()))?;
+            revisions.push(revision);
+        }
+        Ok(revisions)
+    }
+
+    async fn delete_workload_revision(
+        &self,
+        workload_id: &WorkloadId,
+        revision: u64,
+    ) -> Result<()> {
+        self.delete_key(self.revision_key(workload_id, revision)).await
+    }
+
+    // ===== Events =====
+
+    async fn put_event(&self, event: Event) -> Result<()> {
+        self.put_value(self.event_key(&event.id), &event).await
+    }
+
+    async fn list_events(&self) -> Result<Vec<Event>> {
+        let mut client = self.client.lock().await;
+        let response = client
+            .get(self.events_prefix(), Some(GetOptions::new().with_prefix()))
+            .await
+            .map_err(|e| StateStoreError::InternalError(format!("etcd list failed: {}", e)))?;
+
+        let mut events = Vec::new();
+        for kv in response.kvs() {
+            let event: Event = serde_json::from_slice(kv.value())
+                .map_err(|e| StateStoreError::SerializationError(e.to_string()))?;
+            events.push(event);
+        }
+        events.sort_by_key(|e| (e.last_timestamp_ms, e.id));
+        Ok(events)
+    }
+
+    async fn delete_event(&self, event_id: &uuid::Uuid) -> Result<()> {
+        self.delete_key(self.event_key(event_id)).await
+    }
+
+    // ===== Usage Records =====
+
+    async fn put_usage_record(&self, record: UsageRecord) -> Result<()> {
+        self.put_value(self.usage_key(&record), &record).await
+    }
+
+    async fn list_usage_records(&self, from_ms: u64, to_ms: u64) -> Result<Vec<UsageRecord>> {
+        if from_ms >= to_ms {
+            return Ok(Vec::new());
+        }
+        let mut client = self.client.lock().await;
+        let response = client
+            .get(
+                self.usage_period_prefix(from_ms),
+                Some(GetOptions::new().with_range(self.usage_period_prefix(to_ms))),
+            )
+            .await
+            .map_err(|e| StateStoreError::InternalError(format!("etcd list failed: {}", e)))?;
+
+        let mut records = Vec::new();
+        for kv in response.kvs() {
+            let record: UsageRecord = serde_json::from_slice(kv.value())
+                .map_err(|e| StateStoreError::SerializationError(e.to_string()))?;
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    // ===== Resources =====
+
+    async fn put_resource(&self, kind: &str, name: &str, data: serde_json::Value) -> Result<()> {
+        self.put_value(self.resource_key(kind, name), &data).await
+    }
+
+    async fn get_resource(&self, kind: &str, name: &str) -> Result<Option<serde_json::Value>> {
+        let mut client = self.client.lock().await;
+        let response = client
+            .get(self.resource_key(kind, name), None)
+            .await
+            .map_err(|e| StateStoreError::InternalError(format!("etcd get failed: {}", e)))?;
+        response
+            .kvs()
+            .first()
+            .map(|kv| {
+                serde_json::from_slice(kv.value())
+                    .map_err(|e| StateStoreError::SerializationError(e.to_string()).into())
+            })
+            .transpose()
+    }
+
+    async fn list_resources(&self, kind: &str) -> Result<Vec<serde_json::Value>> {
+        let mut client = self.client.lock().await;
+        let response = client
+            .get(self.resources_prefix(kind), Some(GetOptions::new().with_prefix()))
+            .await
+            .map_err(|e| StateStoreError::InternalError(format!("etcd list failed: {}", e)))?;
+
+        let mut resources = Vec::new();
+        for kv in response.kvs() {
+            let data = serde_json::from_slice(kv.value())
+                .map_err(|e| StateStoreError::SerializationError(e.to_string()))?;
+            resources.push(data);
+        }
+        Ok(resources)
+    }
+
+    async fn delete_resource(&self, kind: &str, name: &str) -> Result<()> {
+        self.delete_key(self.resource_key(kind, name)).await
+    }
+
+    // ===== Watch/Subscribe =====
+
+    async fn watch_nodes(&self) -> Result<mpsc::Receiver<WatchEvent<Node>>> {
+        self.watch_prefix(self.nodes_prefix()).await
+    }
+
+    async fn watch_workloads(&self) -> Result<mpsc::Receiver<WatchEvent<WorkloadDefinition>>> {
+        self.watch_prefix(self.workloads_prefix()).await
+    }
+
+    async fn watch_instances(&self) -> Result<mpsc::Receiver<WatchEvent<WorkloadInstance>>> {
+        self.watch_prefix(self.instances_prefix()).await
+    }
 }
 
 #[cfg(test)]
@@ -300,13 +796,18 @@ mod tests {
         .expect("Failed to create etcd store")
     }
 
-    #[tokio::test]
-    #[ignore] // Ignore by default since it requires etcd
-    async fn test_etcd_node_operations() {
-        let store = create_test_store().await;
+    // A store under its own prefix so tests do not see each other's keys
+    async fn create_isolated_store() -> EtcdStateStore {
+        EtcdStateStore::with_prefix(
+            vec!["127.0.0.1:2379".to_string()],
+            format!("/test_orchestrator/{}", Uuid::new_v4()),
+        )
+        .await
+        .expect("Failed to create etcd store")
+    }
 
-        let node_id = generate_node_id();
-        let node = Node {
+    fn test_node(node_id: NodeId) -> Node {
+        Node {
             id: node_id,
             address: "10.0.0.1:8080".to_string(),
             status: NodeStatus::Ready,
@@ -321,7 +822,18 @@ mod tests {
                 memory_mb: 7000,
                 disk_mb: 90000,
             },
-        };
+            unschedulable: false,
+            resource_version: 0,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Ignore by default since it requires etcd
+    async fn test_etcd_node_operations() {
+        let store = create_test_store().await;
+
+        let node_id = generate_node_id();
+        let node = test_node(node_id);
 
         // Put node
         store.put_node(node.clone()).await.unwrap();
@@ -334,4 +846,92 @@ mod tests {
         // Clean up
         store.delete_node(&node_id).await.unwrap();
     }
+
+    #[tokio::test]
+    #[ignore] // Ignore by default since it requires etcd
+    async fn test_etcd_instance_transactions() {
+        let store = create_isolated_store().await;
+        let workload_id = Uuid::new_v4();
+        let node_id = generate_node_id();
+
+        // More instances than fit in one transaction
+        let instances: Vec<WorkloadInstance> = (0..100)
+            .map(|_| WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id,
+                node_id,
+                container_ids: vec![],
+                status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
+            })
+            .collect();
+        store.put_instances_batch(instances.clone()).await.unwrap();
+        assert_eq!(store.list_all_instances().await.unwrap().len(), 100);
+        assert_eq!(
+            store.list_instances_for_workload(&workload_id).await.unwrap().len(),
+            100
+        );
+
+        store
+            .delete_instance(&instances[0].id.to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            store.list_instances_for_workload(&workload_id).await.unwrap().len(),
+            99
+        );
+
+        store.delete_instances_for_workload(&workload_id).await.unwrap();
+        assert!(store.list_all_instances().await.unwrap().is_empty());
+        assert!(store
+            .list_instances_for_workload(&workload_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore] // Ignore by default since it requires etcd
+    async fn test_etcd_watch_nodes() {
+        let store = create_isolated_store().await;
+        let mut updates = store.watch_nodes().await.unwrap();
+
+        let node = test_node(generate_node_id());
+        store.put_node(node.clone()).await.unwrap();
+
+        let seen = tokio::time::timeout(Duration::from_secs(5), updates.recv())
+            .await
+            .expect("no watch event")
+            .unwrap();
+        assert_eq!(seen.event_type, WatchEventType::Added);
+        assert_eq!(seen.object.id, node.id);
+        assert_eq!(seen.object.resource_version, seen.resource_version);
+
+        store.delete_node(&node.id).await.unwrap();
+        let seen = tokio::time::timeout(Duration::from_secs(5), updates.recv())
+            .await
+            .expect("no watch event")
+            .unwrap();
+        assert_eq!(seen.event_type, WatchEventType::Deleted);
+        assert_eq!(seen.object.id, node.id);
+    }
+
+    #[tokio::test]
+    #[ignore] // Ignore by default since it requires etcd
+    async fn test_etcd_node_ttl() {
+        let store = create_isolated_store()
+            .await
+            .with_node_ttl(Duration::from_secs(1));
+
+        let node_id = generate_node_id();
+        store.put_node(test_node(node_id)).await.unwrap();
+        assert!(store.get_node(&node_id).await.unwrap().is_some());
+
+        // etcd revokes expired leases within a second or so of their TTL
+        tokio::time::sleep(Duration::from_secs(4)).await;
+        assert!(store.get_node(&node_id).await.unwrap().is_none());
+    }
 }
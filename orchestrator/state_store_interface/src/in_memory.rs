@@ -1,12 +1,22 @@
 use async_trait::async_trait;
 use orchestrator_shared_types::{
-    Node, NodeId, Result, WorkloadDefinition, WorkloadId, WorkloadInstance,
+    Event, LeaderLease, Namespace, Node, NodeId, Result, UsageRecord, WorkloadDefinition,
+    WorkloadId, WorkloadInstance, WorkloadRevision,
 };
-use std::collections::HashMap;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, RwLock};
 
-use crate::StateStore;
+use crate::{
+    check_resource_version, next_lease, StateStore, Versioned, WatchEvent, WatchEventType,
+};
+
+/// Changes buffered per watch before a receiver that falls behind is closed.
+const WATCH_BUFFER: usize = 1024;
 
 /// In-memory implementation of StateStore
 ///
@@ -18,6 +28,94 @@ pub struct InMemoryStateStore {
     nodes: Arc<RwLock<HashMap<NodeId, Node>>>,
     workloads: Arc<RwLock<HashMap<WorkloadId, WorkloadDefinition>>>,
     instances: Arc<RwLock<HashMap<String, WorkloadInstance>>>, // Key: instance.id.to_string()
+    leases: Arc<RwLock<HashMap<String, LeaderLease>>>,
+    namespaces: Arc<RwLock<HashMap<String, Namespace>>>,
+    revisions: Arc<RwLock<HashMap<WorkloadId, BTreeMap<u64, WorkloadRevision>>>>,
+    events: Arc<RwLock<HashMap<uuid::Uuid, Event>>>,
+    usage: Arc<RwLock<BTreeMap<(u64, WorkloadId), UsageRecord>>>,
+    resources: Arc<RwLock<BTreeMap<(String, String), serde_json::Value>>>,
+    watchers: Arc<Watchers>,
+}
+
+/// Stamps writes with the store's revision and fans them out to watches.
+struct Watchers {
+    revision: AtomicU64,
+    nodes: broadcast::Sender<WatchEvent<Node>>,
+    workloads: broadcast::Sender<WatchEvent<WorkloadDefinition>>,
+    instances: broadcast::Sender<WatchEvent<WorkloadInstance>>,
+    namespaces: broadcast::Sender<WatchEvent<Namespace>>,
+}
+
+impl Watchers {
+    fn new() -> Self {
+        Self {
+            revision: AtomicU64::new(0),
+            nodes: broadcast::channel(WATCH_BUFFER).0,
+            workloads: broadcast::channel(WATCH_BUFFER).0,
+            instances: broadcast::channel(WATCH_BUFFER).0,
+            namespaces: broadcast::channel(WATCH_BUFFER).0,
+        }
+    }
+
+    // Both helpers run with the map locked, so each watch sees its changes
+    // in revision order
+
+    fn put<K: Eq + Hash, T: Versioned + Clone>(
+        &self,
+        map: &mut HashMap<K, T>,
+        key: K,
+        mut object: T,
+        sender: &broadcast::Sender<WatchEvent<T>>,
+    ) -> T {
+        let revision = self.revision.fetch_add(1, Ordering::SeqCst) + 1;
+        object.set_resource_version(revision);
+        let event_type = match map.insert(key, object.clone()) {
+            Some(_) => WatchEventType::Modified,
+            None => WatchEventType::Added,
+        };
+        // No receivers is not an error
+        let _ = sender.send(WatchEvent::new(event_type, object.clone(), revision));
+        object
+    }
+
+    fn remove<K, Q, T>(
+        &self,
+        map: &mut HashMap<K, T>,
+        key: &Q,
+        sender: &broadcast::Sender<WatchEvent<T>>,
+    ) where
+        K: Eq + Hash + Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if let Some(object) = map.remove(key) {
+            let revision = self.revision.fetch_add(1, Ordering::SeqCst) + 1;
+            let _ = sender.send(WatchEvent::new(WatchEventType::Deleted, object, revision));
+        }
+    }
+
+    fn subscribe<T: Clone + Send + 'static>(
+        sender: &broadcast::Sender<WatchEvent<T>>,
+    ) -> mpsc::Receiver<WatchEvent<T>> {
+        let mut events = sender.subscribe();
+        let (tx, rx) = mpsc::channel(WATCH_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tx.closed() => return,
+                    event = events.recv() => match event {
+                        Ok(event) => {
+                            if tx.send(event).await.is_err() {
+                                return;
+                            }
+                        }
+                        // Lagged: end the watch rather than silently skip changes
+                        Err(_) => return,
+                    },
+                }
+            }
+        });
+        rx
+    }
 }
 
 impl InMemoryStateStore {
@@ -27,6 +125,13 @@ impl InMemoryStateStore {
             nodes: Arc::new(RwLock::new(HashMap::new())),
             workloads: Arc::new(RwLock::new(HashMap::new())),
             instances: Arc::new(RwLock::new(HashMap::new())),
+            leases: Arc::new(RwLock::new(HashMap::new())),
+            namespaces: Arc::new(RwLock::new(HashMap::new())),
+            revisions: Arc::new(RwLock::new(HashMap::new())),
+            events: Arc::new(RwLock::new(HashMap::new())),
+            usage: Arc::new(RwLock::new(BTreeMap::new())),
+            resources: Arc::new(RwLock::new(BTreeMap::new())),
+            watchers: Arc::new(Watchers::new()),
         }
     }
 }
@@ -53,7 +158,8 @@ impl StateStore for InMemoryStateStore {
 
     async fn put_node(&self, node: Node) -> Result<()> {
         let mut nodes = self.nodes.write().await;
-        nodes.insert(node.id, node);
+        self.watchers
+            .put(&mut nodes, node.id, node, &self.watchers.nodes);
         Ok(())
     }
 
@@ -69,7 +175,8 @@ impl StateStore for InMemoryStateStore {
 
     async fn delete_node(&self, node_id: &NodeId) -> Result<()> {
         let mut nodes = self.nodes.write().await;
-        nodes.remove(node_id);
+        self.watchers
+            .remove(&mut nodes, node_id, &self.watchers.nodes);
         Ok(())
     }
 
@@ -77,7 +184,12 @@ impl StateStore for InMemoryStateStore {
 
     async fn put_workload(&self, workload: WorkloadDefinition) -> Result<()> {
         let mut workloads = self.workloads.write().await;
-        workloads.insert(workload.id, workload);
+        self.watchers.put(
+            &mut workloads,
+            workload.id,
+            workload,
+            &self.watchers.workloads,
+        );
         Ok(())
     }
 
@@ -93,16 +205,38 @@ impl StateStore for InMemoryStateStore {
 
     async fn delete_workload(&self, workload_id: &WorkloadId) -> Result<()> {
         let mut workloads = self.workloads.write().await;
-        workloads.remove(workload_id);
+        self.watchers
+            .remove(&mut workloads, workload_id, &self.watchers.workloads);
+        self.revisions.write().await.remove(workload_id);
         Ok(())
     }
 
+    async fn compare_and_put_workload(
+        &self,
+        workload: WorkloadDefinition,
+    ) -> Result<WorkloadDefinition> {
+        let mut workloads = self.workloads.write().await;
+        check_resource_version(
+            "workload",
+            &workload.id.to_string(),
+            workloads.get(&workload.id).map(|w| w.resource_version),
+            workload.resource_version,
+        )?;
+        Ok(self.watchers.put(
+            &mut workloads,
+            workload.id,
+            workload,
+            &self.watchers.workloads,
+        ))
+    }
+
     // ===== Instance Operations =====
 
     async fn put_instance(&self, instance: WorkloadInstance) -> Result<()> {
         let mut instances = self.instances.write().await;
         let key = instance.id.to_string();
-        instances.insert(key, instance);
+        self.watchers
+            .put(&mut instances, key, instance, &self.watchers.instances);
         Ok(())
     }
 
@@ -127,13 +261,49 @@ impl StateStore for InMemoryStateStore {
 
     async fn delete_instance(&self, instance_id: &str) -> Result<()> {
         let mut instances = self.instances.write().await;
-        instances.remove(instance_id);
+        self.watchers
+            .remove(&mut instances, instance_id, &self.watchers.instances);
         Ok(())
     }
 
     async fn delete_instances_for_workload(&self, workload_id: &WorkloadId) -> Result<()> {
         let mut instances = self.instances.write().await;
-        instances.retain(|_, inst| &inst.workload_id != workload_id);
+        let removed: Vec<String> = instances
+            .iter()
+            .filter(|(_, inst)| &inst.workload_id == workload_id)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in removed {
+            self.watchers
+                .remove(&mut instances, &key, &self.watchers.instances);
+        }
+        Ok(())
+    }
+
+    // ===== Namespace Operations =====
+
+    async fn put_namespace(&self, namespace: Namespace) -> Result<()> {
+        let mut namespaces = self.namespaces.write().await;
+        let key = namespace.name.clone();
+        self.watchers
+            .put(&mut namespaces, key, namespace, &self.watchers.namespaces);
+        Ok(())
+    }
+
+    async fn get_namespace(&self, name: &str) -> Result<Option<Namespace>> {
+        let namespaces = self.namespaces.read().await;
+        Ok(namespaces.get(name).cloned())
+    }
+
+    async fn list_namespaces(&self) -> Result<Vec<Namespace>> {
+        let namespaces = self.namespaces.read().await;
+        Ok(namespaces.values().cloned().collect())
+    }
+
+    async fn delete_namespace(&self, name: &str) -> Result<()> {
+        let mut namespaces = self.namespaces.write().await;
+        self.watchers
+            .remove(&mut namespaces, name, &self.watchers.namespaces);
         Ok(())
     }
 
@@ -141,16 +311,181 @@ impl StateStore for InMemoryStateStore {
         let mut instances = self.instances.write().await;
         for instance in instances_batch {
             let key = instance.id.to_string();
-            instances.insert(key, instance);
+            self.watchers
+                .put(&mut instances, key, instance, &self.watchers.instances);
+        }
+        Ok(())
+    }
+
+    // ===== Leases =====
+
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        now_ms: u64,
+        duration: Duration,
+    ) -> Result<LeaderLease> {
+        let mut leases = self.leases.write().await;
+        let current = leases.get(name);
+        match next_lease(current, holder, now_ms, duration) {
+            Some(lease) => {
+                leases.insert(name.to_string(), lease.clone());
+                Ok(lease)
+            }
+            None => Ok(current.cloned().unwrap_or_default()),
+        }
+    }
+
+    async fn get_lease(&self, name: &str) -> Result<Option<LeaderLease>> {
+        let leases = self.leases.read().await;
+        Ok(leases.get(name).cloned())
+    }
+
+    async fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+        let mut leases = self.leases.write().await;
+        if let Some(lease) = leases.get_mut(name).filter(|lease| lease.holder == holder) {
+            lease.expires_at_ms = 0;
+        }
+        Ok(())
+    }
+
+    // ===== Workload Revisions =====
+
+    async fn put_workload_revision(&self, revision: WorkloadRevision) -> Result<()> {
+        let mut revisions = self.revisions.write().await;
+        revisions
+            .entry(revision.workload_id)
+            .or_default()
+            .insert(revision.revision, revision);
+        Ok(())
+    }
+
+    async fn list_workload_revisions(
+        &self,
+        workload_id: &WorkloadId,
+    ) -> Result<Vec<WorkloadRevision>> {
+        let revisions = self.revisions.read().await;
+        Ok(revisions
+            .get(workload_id)
+            .map(|history| history.values().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn delete_workload_revision(
+        &self,
+        workload_id: &WorkloadId,
+        revision: u64,
+    ) -> Result<()> {
+        let mut revisions = self.revisions.write().await;
+        if let Some(history) = revisions.get_mut(workload_id) {
+            history.remove(&revision);
+        }
+        Ok(())
+    }
+
+    // ===== Events =====
+
+    async fn put_event(&self, event: Event) -> Result<()> {
+        self.events.write().await.insert(event.id, event);
+        Ok(())
+    }
+
+    async fn list_events(&self) -> Result<Vec<Event>> {
+        let mut events: Vec<Event> = self.events.read().await.values().cloned().collect();
+        events.sort_by_key(|e| (e.last_timestamp_ms, e.id));
+        Ok(events)
+    }
+
+    async fn delete_event(&self, event_id: &uuid::Uuid) -> Result<()> {
+        self.events.write().await.remove(event_id);
+        Ok(())
+    }
+
+    // ===== Usage Records =====
+
+    async fn put_usage_record(&self, record: UsageRecord) -> Result<()> {
+        self.usage
+            .write()
+            .await
+            .insert((record.period_start_ms, record.workload_id), record);
+        Ok(())
+    }
+
+    async fn list_usage_records(&self, from_ms: u64, to_ms: u64) -> Result<Vec<UsageRecord>> {
+        if from_ms >= to_ms {
+            return Ok(Vec::new());
         }
+        Ok(self
+            .usage
+            .read()
+            .await
+            .range((from_ms, uuid::Uuid::nil())..(to_ms, uuid::Uuid::nil()))
+            .map(|(_, record)| record.clone())
+            .collect())
+    }
+
+    // ===== Resources =====
+
+    async fn put_resource(&self, kind: &str, name: &str, data: serde_json::Value) -> Result<()> {
+        self.resources
+            .write()
+            .await
+            .insert((kind.to_string(), name.to_string()), data);
+        Ok(())
+    }
+
+    async fn get_resource(&self, kind: &str, name: &str) -> Result<Option<serde_json::Value>> {
+        Ok(self
+            .resources
+            .read()
+            .await
+            .get(&(kind.to_string(), name.to_string()))
+            .cloned())
+    }
+
+    async fn list_resources(&self, kind: &str) -> Result<Vec<serde_json::Value>> {
+        Ok(self
+            .resources
+            .read()
+            .await
+            .iter()
+            .filter(|((k, _), _)| k == kind)
+            .map(|(_, data)| data.clone())
+            .collect())
+    }
+
+    async fn delete_resource(&self, kind: &str, name: &str) -> Result<()> {
+        self.resources
+            .write()
+            .await
+            .remove(&(kind.to_string(), name.to_string()));
         Ok(())
     }
+
+    // ===== Watch/Subscribe =====
+
+    async fn watch_nodes(&self) -> Result<mpsc::Receiver<WatchEvent<Node>>> {
+        Ok(Watchers::subscribe(&self.watchers.nodes))
+    }
+
+    async fn watch_workloads(&self) -> Result<mpsc::Receiver<WatchEvent<WorkloadDefinition>>> {
+        Ok(Watchers::subscribe(&self.watchers.workloads))
+    }
+
+    async fn watch_instances(&self) -> Result<mpsc::Receiver<WatchEvent<WorkloadInstance>>> {
+        Ok(Watchers::subscribe(&self.watchers.instances))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use orchestrator_shared_types::{NodeStatus, NodeResources, WorkloadInstanceStatus, Keypair};
+    use crate::PageRequest;
+    use orchestrator_shared_types::{
+        EventType, Keypair, NodeResources, NodeStatus, ObjectReference, OrchestrationError,
+        WorkloadInstanceStatus,
+    };
     use std::collections::HashMap;
     use uuid::Uuid;
 
@@ -178,6 +513,8 @@ mod tests {
                 memory_mb: 7000,
                 disk_mb: 90000,
             },
+            unschedulable: false,
+            resource_version: 0,
         };
 
         // Put node
@@ -209,6 +546,12 @@ mod tests {
             containers: vec![],
             replicas: 3,
             labels: HashMap::new(),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
         };
 
         // Put workload
@@ -240,6 +583,10 @@ mod tests {
             node_id: generate_node_id(),
             container_ids: vec!["container-123".to_string()],
             status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
         };
 
         let instance_id = instance.id.to_string();
@@ -262,6 +609,19 @@ mod tests {
         assert!(retrieved.is_none());
     }
 
+    #[tokio::test]
+    async fn test_namespace_operations() {
+        let store = InMemoryStateStore::new();
+
+        store.put_namespace(Namespace::new("team-a")).await.unwrap();
+        let stored = store.get_namespace("team-a").await.unwrap().unwrap();
+        assert!(stored.resource_version > 0);
+        assert_eq!(store.list_namespaces().await.unwrap().len(), 1);
+
+        store.delete_namespace("team-a").await.unwrap();
+        assert!(store.get_namespace("team-a").await.unwrap().is_none());
+    }
+
     // ===== Comprehensive Test Suite =====
 
     // --- Initialization and Health Check Tests ---
@@ -362,6 +722,8 @@ mod tests {
             labels: HashMap::new(),
             resources_capacity: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
             resources_allocatable: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
+            unschedulable: false,
+            resource_version: 0,
         };
 
         let node_v2 = Node {
@@ -371,6 +733,8 @@ mod tests {
             labels: HashMap::new(),
             resources_capacity: NodeResources { cpu_cores: 8.0, memory_mb: 16384, disk_mb: 200000 },
             resources_allocatable: NodeResources { cpu_cores: 7.5, memory_mb: 15000, disk_mb: 180000 },
+            unschedulable: false,
+            resource_version: 0,
         };
 
         store.put_node(node_v1).await.unwrap();
@@ -396,6 +760,12 @@ mod tests {
             containers: vec![],
             replicas: 1,
             labels: HashMap::new(),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
         };
 
         let workload_v2 = WorkloadDefinition {
@@ -404,6 +774,12 @@ mod tests {
             containers: vec![],
             replicas: 5,
             labels: HashMap::new(),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
         };
 
         store.put_workload(workload_v1).await.unwrap();
@@ -427,6 +803,10 @@ mod tests {
             node_id: generate_node_id(),
             container_ids: vec!["container-1".to_string()],
             status: WorkloadInstanceStatus::Pending,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
         };
 
         let instance_v2 = WorkloadInstance {
@@ -435,6 +815,10 @@ mod tests {
             node_id: generate_node_id(),
             container_ids: vec!["container-2".to_string(), "container-3".to_string()],
             status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
         };
 
         store.put_instance(instance_v1).await.unwrap();
@@ -460,6 +844,8 @@ mod tests {
                 labels: HashMap::new(),
                 resources_capacity: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
                 resources_allocatable: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
+                unschedulable: false,
+                resource_version: 0,
             };
             store.put_node(node).await.unwrap();
         }
@@ -479,6 +865,12 @@ mod tests {
                 containers: vec![],
                 replicas: i as u32,
                 labels: HashMap::new(),
+                init_containers: vec![],
+                sidecars: vec![],
+                placement: Default::default(),
+                restart_policy: Default::default(),
+                namespace: "default".to_string(),
+                resource_version: 0,
             };
             store.put_workload(workload).await.unwrap();
         }
@@ -502,6 +894,10 @@ mod tests {
                 node_id: generate_node_id(),
                 container_ids: vec!["c1".to_string()],
                 status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
             };
             store.put_instance(instance).await.unwrap();
         }
@@ -514,6 +910,10 @@ mod tests {
                 node_id: generate_node_id(),
                 container_ids: vec!["c2".to_string()],
                 status: WorkloadInstanceStatus::Pending,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
             };
             store.put_instance(instance).await.unwrap();
         }
@@ -546,6 +946,10 @@ mod tests {
                 node_id: generate_node_id(),
                 container_ids: vec![format!("container-{}", i)],
                 status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
             })
             .collect();
 
@@ -588,6 +992,10 @@ mod tests {
                 node_id: generate_node_id(),
                 container_ids: vec![],
                 status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
             }).await.unwrap();
         }
 
@@ -598,6 +1006,10 @@ mod tests {
                 node_id: generate_node_id(),
                 container_ids: vec![],
                 status: WorkloadInstanceStatus::Running,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
             }).await.unwrap();
         }
 
@@ -633,6 +1045,8 @@ mod tests {
                 labels: HashMap::new(),
                 resources_capacity: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
                 resources_allocatable: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
+                unschedulable: false,
+                resource_version: 0,
             }).await.unwrap();
         }
 
@@ -675,6 +1089,8 @@ mod tests {
                         labels: HashMap::new(),
                         resources_capacity: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
                         resources_allocatable: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
+                        unschedulable: false,
+                        resource_version: 0,
                     };
                     store_clone.put_node(node).await.unwrap();
                     counter_clone.fetch_add(1, Ordering::SeqCst);
@@ -709,6 +1125,8 @@ mod tests {
             labels: HashMap::new(),
             resources_capacity: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
             resources_allocatable: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
+            unschedulable: false,
+            resource_version: 0,
         }).await.unwrap();
 
         let mut handles = vec![];
@@ -737,6 +1155,8 @@ mod tests {
                         labels: HashMap::new(),
                         resources_capacity: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
                         resources_allocatable: NodeResources { cpu_cores: 4.0, memory_mb: 8192, disk_mb: 100000 },
+                        unschedulable: false,
+                        resource_version: 0,
                     }).await.unwrap();
                 }
             }));
@@ -769,6 +1189,8 @@ mod tests {
             labels: labels.clone(),
             resources_capacity: NodeResources { cpu_cores: 16.0, memory_mb: 65536, disk_mb: 1000000 },
             resources_allocatable: NodeResources { cpu_cores: 15.0, memory_mb: 60000, disk_mb: 900000 },
+            unschedulable: false,
+            resource_version: 0,
         };
 
         store.put_node(node.clone()).await.unwrap();
@@ -800,6 +1222,10 @@ mod tests {
                 node_id: generate_node_id(),
                 container_ids: vec!["container-1".to_string()],
                 status: status.clone(),
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
             };
 
             store.put_instance(instance).await.unwrap();
@@ -824,10 +1250,352 @@ mod tests {
             node_id: generate_node_id(),
             container_ids: vec![],
             status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
         }).await.unwrap();
 
         // Query for empty workload should return empty list
         let instances = store.list_instances_for_workload(&empty_workload_id).await.unwrap();
         assert!(instances.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_lease_acquire_renew_and_takeover() {
+        let store = InMemoryStateStore::new();
+        let ttl = Duration::from_secs(10);
+
+        let lease = store.try_acquire_lease("leader", "a", 1_000, ttl).await.unwrap();
+        assert_eq!((lease.holder.as_str(), lease.term), ("a", 1));
+        assert_eq!(lease.expires_at_ms, 11_000);
+
+        // Held by a: b is refused, a renews without a new term
+        let lease = store.try_acquire_lease("leader", "b", 5_000, ttl).await.unwrap();
+        assert_eq!(lease.holder, "a");
+        let lease = store.try_acquire_lease("leader", "a", 6_000, ttl).await.unwrap();
+        assert_eq!((lease.term, lease.expires_at_ms), (1, 16_000));
+
+        // Expired: b takes over in a new term
+        let lease = store.try_acquire_lease("leader", "b", 16_000, ttl).await.unwrap();
+        assert_eq!((lease.holder.as_str(), lease.term), ("b", 2));
+
+        // Only the holder can release; the term carries on afterwards
+        store.release_lease("leader", "a").await.unwrap();
+        assert!(store.get_lease("leader").await.unwrap().unwrap().is_held_at(17_000));
+        store.release_lease("leader", "b").await.unwrap();
+        let lease = store.try_acquire_lease("leader", "a", 17_000, ttl).await.unwrap();
+        assert_eq!((lease.holder.as_str(), lease.term), ("a", 3));
+    }
+
+    #[tokio::test]
+    async fn test_default_pagination() {
+        let store = InMemoryStateStore::new();
+        for i in 0..5 {
+            store
+                .put_workload(WorkloadDefinition {
+                    id: Uuid::new_v4(),
+                    name: format!("workload-{}", i),
+                    containers: vec![],
+                    replicas: 1,
+                    labels: HashMap::new(),
+                    init_containers: vec![],
+                    sidecars: vec![],
+                    placement: Default::default(),
+                    restart_policy: Default::default(),
+                    namespace: "default".to_string(),
+                    resource_version: 0,
+                })
+                .await
+                .unwrap();
+        }
+
+        let first = PageRequest::first(2);
+        let page = store.list_workloads_page(first.clone()).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        let second = first.next(&page).unwrap();
+        let page = store.list_workloads_page(second.clone()).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        let last = store.list_workloads_page(second.next(&page).unwrap()).await.unwrap();
+        assert_eq!(last.items.len(), 1);
+        assert!(last.next.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pagination_filters() {
+        let store = InMemoryStateStore::new();
+        let node_id = generate_node_id();
+        for (i, (app, namespace)) in [("web", "default"), ("web", "ml"), ("db", "default")]
+            .into_iter()
+            .enumerate()
+        {
+            let workload_id = Uuid::new_v4();
+            store
+                .put_workload(WorkloadDefinition {
+                    id: workload_id,
+                    name: format!("workload-{}", i),
+                    containers: vec![],
+                    replicas: 1,
+                    labels: HashMap::from([("app".to_string(), app.to_string())]),
+                    init_containers: vec![],
+                    sidecars: vec![],
+                    placement: Default::default(),
+                    restart_policy: Default::default(),
+                    namespace: namespace.to_string(),
+                    resource_version: 0,
+                })
+                .await
+                .unwrap();
+            store
+                .put_instance(WorkloadInstance {
+                    id: Uuid::new_v4(),
+                    workload_id,
+                    node_id,
+                    container_ids: vec![],
+                    status: WorkloadInstanceStatus::Running,
+                    ip_addresses: vec![],
+                    restarts: vec![],
+                    namespace: namespace.to_string(),
+                    resource_version: 0,
+                })
+                .await
+                .unwrap();
+        }
+
+        let web = PageRequest::first(10).with_selector("app=web".parse().unwrap());
+        let page = store.list_workloads_page(web.clone()).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        let page = store
+            .list_workloads_page(web.clone().with_namespace("ml"))
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+
+        // Instances are selected by their workload's labels
+        let page = store.list_instances_page(web).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        let page = store
+            .list_instances_page(PageRequest::first(10).with_namespace("default"))
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_watch_events() {
+        let store = InMemoryStateStore::new();
+        let mut node_events = store.watch_nodes().await.unwrap();
+        let mut instance_events = store.watch_instances().await.unwrap();
+
+        let node_id = generate_node_id();
+        let mut node = Node {
+            id: node_id,
+            address: "10.0.0.1:8080".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            resources_capacity: NodeResources::default(),
+            resources_allocatable: NodeResources::default(),
+            unschedulable: false,
+            resource_version: 0,
+        };
+        store.put_node(node.clone()).await.unwrap();
+        node.unschedulable = true;
+        store.put_node(node).await.unwrap();
+        store.delete_node(&node_id).await.unwrap();
+
+        let workload_id = Uuid::new_v4();
+        store
+            .put_instances_batch(vec![WorkloadInstance {
+                id: Uuid::new_v4(),
+                workload_id,
+                node_id,
+                container_ids: vec![],
+                status: WorkloadInstanceStatus::Pending,
+                ip_addresses: vec![],
+                restarts: vec![],
+                namespace: "default".to_string(),
+                resource_version: 0,
+            }])
+            .await
+            .unwrap();
+        store.delete_instances_for_workload(&workload_id).await.unwrap();
+
+        let mut versions = Vec::new();
+        for expected in [
+            WatchEventType::Added,
+            WatchEventType::Modified,
+            WatchEventType::Deleted,
+        ] {
+            let event = node_events.recv().await.unwrap();
+            assert_eq!(event.event_type, expected);
+            assert_eq!(event.object.id, node_id);
+            versions.push(event.resource_version);
+        }
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let added = instance_events.recv().await.unwrap();
+        assert_eq!(added.event_type, WatchEventType::Added);
+        let deleted = instance_events.recv().await.unwrap();
+        assert_eq!(deleted.event_type, WatchEventType::Deleted);
+        assert_eq!(deleted.object.id, added.object.id);
+        assert!(deleted.resource_version > added.resource_version);
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_put_workload() {
+        let store = InMemoryStateStore::new();
+        let workload = WorkloadDefinition {
+            id: Uuid::new_v4(),
+            name: "web".to_string(),
+            containers: vec![],
+            replicas: 1,
+            labels: HashMap::new(),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+
+        let created = store.compare_and_put_workload(workload.clone()).await.unwrap();
+        assert!(created.resource_version > 0);
+        // Version 0 only creates
+        assert!(matches!(
+            store.compare_and_put_workload(workload).await,
+            Err(OrchestrationError::Conflict(_))
+        ));
+
+        let mut update = created.clone();
+        update.replicas = 3;
+        let updated = store.compare_and_put_workload(update).await.unwrap();
+        assert!(updated.resource_version > created.resource_version);
+        assert_eq!(
+            store.get_workload(&created.id).await.unwrap().unwrap().resource_version,
+            updated.resource_version
+        );
+
+        // Writing from the stale copy must not undo the update
+        assert!(matches!(
+            store.compare_and_put_workload(created.clone()).await,
+            Err(OrchestrationError::Conflict(_))
+        ));
+        assert_eq!(store.get_workload(&created.id).await.unwrap().unwrap().replicas, 3);
+    }
+
+    #[tokio::test]
+    async fn test_workload_revisions() {
+        let store = InMemoryStateStore::new();
+        let workload = WorkloadDefinition {
+            id: Uuid::new_v4(),
+            name: "web".to_string(),
+            containers: vec![],
+            replicas: 1,
+            labels: HashMap::new(),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        store.put_workload(workload.clone()).await.unwrap();
+        for revision in [2, 1, 3] {
+            store
+                .put_workload_revision(WorkloadRevision {
+                    workload_id: workload.id,
+                    revision,
+                    workload: workload.clone(),
+                    created_at_ms: revision * 1000,
+                })
+                .await
+                .unwrap();
+        }
+
+        store.delete_workload_revision(&workload.id, 2).await.unwrap();
+        let revisions = store.list_workload_revisions(&workload.id).await.unwrap();
+        let numbers: Vec<u64> = revisions.iter().map(|r| r.revision).collect();
+        assert_eq!(numbers, [1, 3]);
+
+        // Revisions go with their workload
+        store.delete_workload(&workload.id).await.unwrap();
+        assert!(store.list_workload_revisions(&workload.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_events() {
+        let store = InMemoryStateStore::new();
+        let event = |last: u64| Event {
+            id: Uuid::new_v4(),
+            involved_object: ObjectReference::node(&generate_node_id()),
+            namespace: String::new(),
+            event_type: EventType::Warning,
+            reason: "NodeNotReady".to_string(),
+            message: "Node stopped renewing its lease".to_string(),
+            source: "node-lifecycle".to_string(),
+            count: 1,
+            first_timestamp_ms: last,
+            last_timestamp_ms: last,
+        };
+        let (late, early) = (event(2000), event(1000));
+        store.put_event(late.clone()).await.unwrap();
+        store.put_event(early.clone()).await.unwrap();
+        assert_eq!(store.list_events().await.unwrap(), [early.clone(), late.clone()]);
+
+        store.delete_event(&early.id).await.unwrap();
+        assert_eq!(store.list_events().await.unwrap(), [late]);
+    }
+
+    #[tokio::test]
+    async fn test_usage_records() {
+        let store = InMemoryStateStore::new();
+        let hour = UsageRecord::PERIOD_MS;
+        let record = |workload_id: Uuid, period: u64, cpu: f64| UsageRecord {
+            workload_id,
+            workload_name: "web".to_string(),
+            namespace: "shop".to_string(),
+            period_start_ms: period * hour,
+            cpu_core_seconds: cpu,
+            memory_gb_seconds: 0.0,
+            gpu_seconds: 0.0,
+        };
+        let (web, batch) = (Uuid::new_v4(), Uuid::new_v4());
+        store.put_usage_record(record(web, 2, 10.0)).await.unwrap();
+        store.put_usage_record(record(batch, 1, 5.0)).await.unwrap();
+        store.put_usage_record(record(web, 1, 1.0)).await.unwrap();
+        // Replaces the record of the same workload and period
+        store.put_usage_record(record(web, 1, 3.0)).await.unwrap();
+
+        let records = store.list_usage_records(hour, 2 * hour).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records.contains(&record(web, 1, 3.0)));
+        assert!(records.contains(&record(batch, 1, 5.0)));
+        assert_eq!(
+            store.list_usage_records(2 * hour, 3 * hour).await.unwrap(),
+            [record(web, 2, 10.0)]
+        );
+        let all = store.list_usage_records(0, 3 * hour).await.unwrap();
+        assert_eq!(all.len(), 3);
+        let backwards = store.list_usage_records(3 * hour, hour).await.unwrap();
+        assert!(backwards.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resources() {
+        let store = InMemoryStateStore::new();
+        let set = |replicas: u32| serde_json::json!({ "name": "db", "replicas": replicas });
+        store.put_resource("statefulsets", "db", set(1)).await.unwrap();
+        store.put_resource("statefulsets", "db", set(3)).await.unwrap();
+        store.put_resource("cronjobs", "db", serde_json::json!({})).await.unwrap();
+
+        assert_eq!(
+            store.get_resource("statefulsets", "db").await.unwrap(),
+            Some(set(3))
+        );
+        assert_eq!(store.list_resources("statefulsets").await.unwrap(), [set(3)]);
+
+        store.delete_resource("statefulsets", "db").await.unwrap();
+        assert!(store.list_resources("statefulsets").await.unwrap().is_empty());
+        assert_eq!(store.list_resources("cronjobs").await.unwrap().len(), 1);
+    }
 }
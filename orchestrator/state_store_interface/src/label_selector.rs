@@ -0,0 +1,211 @@
+//! Label selectors for filtered listings.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// One condition on a label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelRequirement {
+    Equals(String, String),
+    /// Also matches objects without the label.
+    NotEquals(String, String),
+    In(String, Vec<String>),
+    /// Also matches objects without the label.
+    NotIn(String, Vec<String>),
+    Exists(String),
+    DoesNotExist(String),
+}
+
+impl LabelRequirement {
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        match self {
+            LabelRequirement::Equals(key, value) => labels.get(key) == Some(value),
+            LabelRequirement::NotEquals(key, value) => labels.get(key) != Some(value),
+            LabelRequirement::In(key, values) => {
+                labels.get(key).is_some_and(|v| values.contains(v))
+            }
+            LabelRequirement::NotIn(key, values) => {
+                labels.get(key).is_none_or(|v| !values.contains(v))
+            }
+            LabelRequirement::Exists(key) => labels.contains_key(key),
+            LabelRequirement::DoesNotExist(key) => !labels.contains_key(key),
+        }
+    }
+}
+
+/// Requirements an object's labels must all meet, parsed from the syntax
+/// `app=web,env!=prod,tier in (web,api),!canary`. The empty selector matches
+/// everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSelector {
+    pub requirements: Vec<LabelRequirement>,
+}
+
+impl LabelSelector {
+    pub fn is_empty(&self) -> bool {
+        self.requirements.is_empty()
+    }
+
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.requirements.iter().all(|r| r.matches(labels))
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut requirements = Vec::new();
+        for term in split_terms(s)? {
+            requirements.push(parse_requirement(term)?);
+        }
+        Ok(Self { requirements })
+    }
+}
+
+impl fmt::Display for LabelSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, requirement) in self.requirements.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            match requirement {
+                LabelRequirement::Equals(key, value) => write!(f, "{}={}", key, value)?,
+                LabelRequirement::NotEquals(key, value) => write!(f, "{}!={}", key, value)?,
+                LabelRequirement::In(key, values) => {
+                    write!(f, "{} in ({})", key, values.join(","))?
+                }
+                LabelRequirement::NotIn(key, values) => {
+                    write!(f, "{} notin ({})", key, values.join(","))?
+                }
+                LabelRequirement::Exists(key) => f.write_str(key)?,
+                LabelRequirement::DoesNotExist(key) => write!(f, "!{}", key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Split on commas outside the parentheses of set requirements
+fn split_terms(s: &str) -> Result<Vec<&str>, String> {
+    let mut terms = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Err(format!("unbalanced ')' in selector '{}'", s)),
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                terms.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err(format!("unbalanced '(' in selector '{}'", s));
+    }
+    terms.push(&s[start..]);
+    Ok(terms
+        .into_iter()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect())
+}
+
+fn parse_requirement(term: &str) -> Result<LabelRequirement, String> {
+    let key = |key: &str| {
+        let key = key.trim();
+        let valid = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+        if valid {
+            Ok(key.to_string())
+        } else {
+            Err(format!("invalid label key in '{}'", term))
+        }
+    };
+
+    if let Some((k, v)) = term.split_once("!=") {
+        return Ok(LabelRequirement::NotEquals(key(k)?, v.trim().to_string()));
+    }
+    if let Some((k, v)) = term.split_once("==").or_else(|| term.split_once('=')) {
+        return Ok(LabelRequirement::Equals(key(k)?, v.trim().to_string()));
+    }
+    if let Some((k, set)) = term.split_once(" notin ") {
+        return Ok(LabelRequirement::NotIn(key(k)?, parse_set(term, set)?));
+    }
+    if let Some((k, set)) = term.split_once(" in ") {
+        return Ok(LabelRequirement::In(key(k)?, parse_set(term, set)?));
+    }
+    match term.strip_prefix('!') {
+        Some(k) => Ok(LabelRequirement::DoesNotExist(key(k)?)),
+        None => Ok(LabelRequirement::Exists(key(term)?)),
+    }
+}
+
+fn parse_set(term: &str, set: &str) -> Result<Vec<String>, String> {
+    let values = set
+        .trim()
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .ok_or_else(|| format!("expected a (value,...) set in '{}'", term))?;
+    Ok(values
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_equality_requirements() {
+        let selector: LabelSelector = "app=web,env!=prod".parse().unwrap();
+        assert!(selector.matches(&labels(&[("app", "web"), ("env", "dev")])));
+        assert!(selector.matches(&labels(&[("app", "web")])));
+        assert!(!selector.matches(&labels(&[("app", "web"), ("env", "prod")])));
+        assert!(!selector.matches(&labels(&[("app", "api")])));
+        assert_eq!(selector.to_string(), "app=web,env!=prod");
+    }
+
+    #[test]
+    fn test_set_and_existence_requirements() {
+        let selector: LabelSelector = "tier in (web, api),env notin (prod),team,!canary"
+            .parse()
+            .unwrap();
+        assert_eq!(selector.requirements.len(), 4);
+        assert!(selector.matches(&labels(&[("tier", "api"), ("team", "core")])));
+        assert!(!selector.matches(&labels(&[("tier", "db"), ("team", "core")])));
+        assert!(!selector.matches(&labels(&[("tier", "web")])));
+        assert!(!selector.matches(&labels(&[
+            ("tier", "web"),
+            ("team", "core"),
+            ("canary", "true")
+        ])));
+    }
+
+    #[test]
+    fn test_empty_and_invalid() {
+        let selector: LabelSelector = "".parse().unwrap();
+        assert!(selector.is_empty());
+        assert!(selector.matches(&HashMap::new()));
+
+        assert!("=web".parse::<LabelSelector>().is_err());
+        assert!("tier in (web".parse::<LabelSelector>().is_err());
+        assert!("tier in web".parse::<LabelSelector>().is_err());
+        assert!("a b".parse::<LabelSelector>().is_err());
+    }
+}
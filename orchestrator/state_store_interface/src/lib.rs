@@ -1,9 +1,14 @@
 use async_trait::async_trait;
 use orchestrator_shared_types::{
-    Node, NodeId, OrchestrationError, Result, WorkloadDefinition, WorkloadId, WorkloadInstance,
+    Event, LeaderLease, Namespace, Node, NodeId, OrchestrationError, Result, UsageRecord,
+    WorkloadDefinition, WorkloadId, WorkloadInstance, WorkloadRevision,
 };
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
+use tokio::sync::mpsc;
 
 /// Errors specific to state store operations
 #[derive(Debug, Error)]
@@ -38,6 +43,13 @@ impl From<StateStoreError> for OrchestrationError {
 /// This trait provides the abstraction layer for storing and retrieving
 /// orchestration state. Implementations can be in-memory (for testing),
 /// etcd-backed (for production), or any other distributed key-value store.
+///
+/// Every write gives the stored node, workload, instance or namespace a new,
+/// higher `resource_version`, which reads return. The version on an object
+/// passed to a `put_*` method is ignored; see [`compare_and_put_workload`] for
+/// writes that must not overwrite a concurrent change.
+///
+/// [`compare_and_put_workload`]: StateStore::compare_and_put_workload
 #[async_trait]
 pub trait StateStore: Send + Sync {
     // ===== Initialization =====
@@ -73,9 +85,33 @@ pub trait StateStore: Send + Sync {
     /// List all workload definitions
     async fn list_workloads(&self) -> Result<Vec<WorkloadDefinition>>;
 
-    /// Delete a workload definition
+    /// Delete a workload definition, and its revisions if the store keeps them
     async fn delete_workload(&self, workload_id: &WorkloadId) -> Result<()>;
 
+    /// Store `workload` only if the stored copy is still at
+    /// `workload.resource_version` (0 = only if there is none yet), and
+    /// return it as stored. Fails with `OrchestrationError::Conflict` otherwise.
+    ///
+    /// The default reads, compares and writes in separate steps, so two racing
+    /// writers can both succeed; backends with transactions should override it.
+    async fn compare_and_put_workload(
+        &self,
+        workload: WorkloadDefinition,
+    ) -> Result<WorkloadDefinition> {
+        let current = self.get_workload(&workload.id).await?;
+        check_resource_version(
+            "workload",
+            &workload.id.to_string(),
+            current.map(|w| w.resource_version),
+            workload.resource_version,
+        )?;
+        let workload_id = workload.id;
+        self.put_workload(workload).await?;
+        self.get_workload(&workload_id)
+            .await?
+            .ok_or(OrchestrationError::WorkloadNotFound(workload_id))
+    }
+
     // ===== Instance Operations =====
 
     /// Store or update a workload instance
@@ -94,11 +130,31 @@ pub trait StateStore: Send + Sync {
     async fn delete_instance(&self, instance_id: &str) -> Result<()>;
 
     /// Delete all instances for a workload (bulk operation)
+    ///
+    /// Not atomic across instances: after a failure some may remain, and
+    /// calling again deletes them.
     async fn delete_instances_for_workload(&self, workload_id: &WorkloadId) -> Result<()>;
 
+    // ===== Namespace Operations =====
+
+    /// Store or update a namespace
+    async fn put_namespace(&self, namespace: Namespace) -> Result<()>;
+
+    /// Get a namespace by name
+    async fn get_namespace(&self, name: &str) -> Result<Option<Namespace>>;
+
+    /// List all namespaces
+    async fn list_namespaces(&self) -> Result<Vec<Namespace>>;
+
+    /// Delete a namespace record, leaving the objects in it alone
+    async fn delete_namespace(&self, name: &str) -> Result<()>;
+
     // ===== Batch Operations (for efficiency) =====
 
     /// Batch put operations for multiple instances
+    ///
+    /// Not atomic across instances: after a failure only some may be
+    /// written, and calling again writes the rest.
     async fn put_instances_batch(&self, instances: Vec<WorkloadInstance>) -> Result<()> {
         // Default implementation calls put_instance sequentially
         // Implementations can override for better performance
@@ -108,20 +164,414 @@ pub trait StateStore: Send + Sync {
         Ok(())
     }
 
-    // ===== Watch/Subscribe (optional, for future event-driven updates) =====
-    // These can be no-ops for simple implementations
+    // ===== Pagination =====
+
+    /// List up to `page.limit` nodes matching `page.selector` in ID order,
+    /// after `page.after`.
+    async fn list_nodes_page(&self, page: PageRequest) -> Result<Page<Node>> {
+        let nodes = self
+            .list_nodes()
+            .await?
+            .into_iter()
+            .filter(|n| page.selector.matches(&n.labels))
+            .collect();
+        Ok(Page::of(nodes, &page, |n| n.id.to_string()))
+    }
+
+    /// List up to `page.limit` workloads matching `page` in ID order, after
+    /// `page.after`. The default pages through `list_workloads`; backends that
+    /// can seek should override it.
+    async fn list_workloads_page(&self, page: PageRequest) -> Result<Page<WorkloadDefinition>> {
+        let workloads = self
+            .list_workloads()
+            .await?
+            .into_iter()
+            .filter(|w| page.selects_workload(w))
+            .collect();
+        Ok(Page::of(workloads, &page, |w| w.id.to_string()))
+    }
+
+    /// List up to `page.limit` instances across all workloads in ID order,
+    /// after `page.after`. `page.selector` applies to the labels of each
+    /// instance's workload.
+    async fn list_instances_page(&self, page: PageRequest) -> Result<Page<WorkloadInstance>> {
+        let workloads = if page.selector.is_empty() {
+            None
+        } else {
+            Some(page.selected_workloads(self.list_workloads().await?))
+        };
+        let instances = self
+            .list_all_instances()
+            .await?
+            .into_iter()
+            .filter(|i| page.selects_instance(i, workloads.as_ref()))
+            .collect();
+        Ok(Page::of(instances, &page, |i| i.id.to_string()))
+    }
+
+    // ===== Leases (optional, for leader election) =====
+
+    /// Atomically acquire or renew the named lease for `holder`, so that it
+    /// expires `duration` after `now_ms`. Succeeds when the lease is free,
+    /// expired as of `now_ms`, or already held by `holder`; otherwise the
+    /// lease is left alone. Returns the lease as stored afterwards, so the
+    /// caller holds it exactly when its holder matches.
+    async fn try_acquire_lease(
+        &self,
+        _name: &str,
+        _holder: &str,
+        _now_ms: u64,
+        _duration: Duration,
+    ) -> Result<LeaderLease> {
+        Err(OrchestrationError::NotImplemented("leases not implemented".to_string()))
+    }
+
+    /// Get the named lease, expired or not
+    async fn get_lease(&self, _name: &str) -> Result<Option<LeaderLease>> {
+        Err(OrchestrationError::NotImplemented("leases not implemented".to_string()))
+    }
+
+    /// Expire the named lease at once if `holder` still holds it
+    async fn release_lease(&self, _name: &str, _holder: &str) -> Result<()> {
+        Err(OrchestrationError::NotImplemented("leases not implemented".to_string()))
+    }
+
+    // ===== Workload Revisions (optional, for rollout history) =====
+
+    /// Store a revision of a workload's template, replacing any with the same
+    /// number
+    async fn put_workload_revision(&self, _revision: WorkloadRevision) -> Result<()> {
+        Err(OrchestrationError::NotImplemented("workload revisions not implemented".to_string()))
+    }
+
+    /// List a workload's revisions, oldest first
+    async fn list_workload_revisions(
+        &self,
+        _workload_id: &WorkloadId,
+    ) -> Result<Vec<WorkloadRevision>> {
+        Err(OrchestrationError::NotImplemented("workload revisions not implemented".to_string()))
+    }
+
+    /// Delete one revision of a workload
+    async fn delete_workload_revision(
+        &self,
+        _workload_id: &WorkloadId,
+        _revision: u64,
+    ) -> Result<()> {
+        Err(OrchestrationError::NotImplemented("workload revisions not implemented".to_string()))
+    }
+
+    // ===== Events (optional, for explaining object states) =====
+
+    /// Store an event, replacing any with the same ID
+    async fn put_event(&self, _event: Event) -> Result<()> {
+        Err(OrchestrationError::NotImplemented("events not implemented".to_string()))
+    }
+
+    /// List all events, oldest first by last occurrence
+    async fn list_events(&self) -> Result<Vec<Event>> {
+        Err(OrchestrationError::NotImplemented("events not implemented".to_string()))
+    }
+
+    /// Delete an event
+    async fn delete_event(&self, _event_id: &uuid::Uuid) -> Result<()> {
+        Err(OrchestrationError::NotImplemented("events not implemented".to_string()))
+    }
+
+    // ===== Usage Records (optional, for chargeback) =====
+
+    /// Store a usage record, replacing any for the same workload and period
+    async fn put_usage_record(&self, _record: UsageRecord) -> Result<()> {
+        Err(OrchestrationError::NotImplemented("usage records not implemented".to_string()))
+    }
+
+    /// List the usage records of periods starting in `[from_ms, to_ms)`,
+    /// oldest period first
+    async fn list_usage_records(&self, _from_ms: u64, _to_ms: u64) -> Result<Vec<UsageRecord>> {
+        Err(OrchestrationError::NotImplemented("usage records not implemented".to_string()))
+    }
+
+    // ===== Resources (optional, for objects kept by controllers) =====
+    // Stateful sets, cron jobs and other objects that controllers own are
+    // stored as JSON documents of a `kind`, e.g. "statefulsets", each under a
+    // name unique within its kind.
+
+    /// Store the object of `kind` named `name`, replacing any stored before
+    async fn put_resource(&self, _kind: &str, _name: &str, _data: serde_json::Value) -> Result<()> {
+        Err(OrchestrationError::NotImplemented("resources not implemented".to_string()))
+    }
+
+    /// Get the object of `kind` named `name`
+    async fn get_resource(&self, _kind: &str, _name: &str) -> Result<Option<serde_json::Value>> {
+        Err(OrchestrationError::NotImplemented("resources not implemented".to_string()))
+    }
+
+    /// List every object of `kind`, in name order
+    async fn list_resources(&self, _kind: &str) -> Result<Vec<serde_json::Value>> {
+        Err(OrchestrationError::NotImplemented("resources not implemented".to_string()))
+    }
+
+    /// Delete the object of `kind` named `name`
+    async fn delete_resource(&self, _kind: &str, _name: &str) -> Result<()> {
+        Err(OrchestrationError::NotImplemented("resources not implemented".to_string()))
+    }
+
+    // ===== Watch/Subscribe (optional, for event-driven updates) =====
+    // A watch reports changes made after it starts, in revision order. One
+    // that falls too far behind is closed; list again and start a new watch.
 
     /// Subscribe to node changes (optional)
-    async fn watch_nodes(&self) -> Result<tokio::sync::mpsc::Receiver<Node>> {
+    async fn watch_nodes(&self) -> Result<mpsc::Receiver<WatchEvent<Node>>> {
         Err(OrchestrationError::NotImplemented("watch_nodes not implemented".to_string()))
     }
 
     /// Subscribe to workload changes (optional)
-    async fn watch_workloads(&self) -> Result<tokio::sync::mpsc::Receiver<WorkloadDefinition>> {
+    async fn watch_workloads(&self) -> Result<mpsc::Receiver<WatchEvent<WorkloadDefinition>>> {
         Err(OrchestrationError::NotImplemented("watch_workloads not implemented".to_string()))
     }
+
+    /// Subscribe to instance changes across all workloads (optional)
+    async fn watch_instances(&self) -> Result<mpsc::Receiver<WatchEvent<WorkloadInstance>>> {
+        Err(OrchestrationError::NotImplemented("watch_instances not implemented".to_string()))
+    }
 }
 
+/// What happened to the object in a [`WatchEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum WatchEventType {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl WatchEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WatchEventType::Added => "ADDED",
+            WatchEventType::Modified => "MODIFIED",
+            WatchEventType::Deleted => "DELETED",
+        }
+    }
+}
+
+/// One change to a stored object. Deleted events carry the object as it was
+/// last stored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchEvent<T> {
+    #[serde(rename = "type")]
+    pub event_type: WatchEventType,
+    pub object: T,
+    /// Store revision of the change; later changes have higher versions
+    pub resource_version: u64,
+}
+
+impl<T> WatchEvent<T> {
+    pub fn new(event_type: WatchEventType, object: T, resource_version: u64) -> Self {
+        Self {
+            event_type,
+            object,
+            resource_version,
+        }
+    }
+
+    /// Convert the object, keeping the event type and version.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> WatchEvent<U> {
+        WatchEvent {
+            event_type: self.event_type,
+            object: f(self.object),
+            resource_version: self.resource_version,
+        }
+    }
+}
+
+/// Objects that carry the `resource_version` of their last write.
+pub trait Versioned {
+    fn resource_version(&self) -> u64;
+    fn set_resource_version(&mut self, version: u64);
+}
+
+macro_rules! impl_versioned {
+    ($($ty:ty),*) => {$(
+        impl Versioned for $ty {
+            fn resource_version(&self) -> u64 {
+                self.resource_version
+            }
+
+            fn set_resource_version(&mut self, version: u64) {
+                self.resource_version = version;
+            }
+        }
+    )*};
+}
+
+impl_versioned!(Node, WorkloadDefinition, WorkloadInstance, Namespace);
+
+/// Check a conditional write of the `kind` object `id` made against
+/// `expected`, given the version now `stored` (None when absent).
+pub fn check_resource_version(
+    kind: &str,
+    id: &str,
+    stored: Option<u64>,
+    expected: u64,
+) -> Result<()> {
+    match (stored, expected) {
+        (None, 0) => Ok(()),
+        (None, _) => Err(OrchestrationError::Conflict(format!(
+            "{} {} no longer exists",
+            kind, id
+        ))),
+        (Some(_), 0) => Err(OrchestrationError::Conflict(format!(
+            "{} {} already exists",
+            kind, id
+        ))),
+        (Some(stored), expected) if stored != expected => {
+            Err(OrchestrationError::Conflict(format!(
+                "{} {} was modified: version {} is stale, current is {}",
+                kind, id, expected, stored
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Where a paginated listing starts, how much it returns and which objects
+/// it includes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageRequest {
+    /// Return items whose ID sorts after this one (None = from the start)
+    pub after: Option<String>,
+    pub limit: usize,
+    /// Only objects whose labels match (workload labels for instances)
+    pub selector: LabelSelector,
+    /// Only objects in this namespace; ignored for nodes
+    pub namespace: Option<String>,
+}
+
+impl PageRequest {
+    pub fn first(limit: usize) -> Self {
+        Self {
+            after: None,
+            limit,
+            selector: LabelSelector::default(),
+            namespace: None,
+        }
+    }
+
+    pub fn with_selector(mut self, selector: LabelSelector) -> Self {
+        self.selector = selector;
+        self
+    }
+
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// The page following `page`, if it was not the last.
+    pub fn next<T>(&self, page: &Page<T>) -> Option<Self> {
+        page.next.clone().map(|after| Self {
+            after: Some(after),
+            ..self.clone()
+        })
+    }
+
+    fn in_namespace(&self, namespace: &str) -> bool {
+        self.namespace.as_deref().is_none_or(|ns| ns == namespace)
+    }
+
+    /// Whether a workload belongs in this listing.
+    pub fn selects_workload(&self, workload: &WorkloadDefinition) -> bool {
+        self.in_namespace(&workload.namespace) && self.selector.matches(&workload.labels)
+    }
+
+    /// IDs of the `workloads` whose labels match the selector.
+    pub fn selected_workloads(&self, workloads: Vec<WorkloadDefinition>) -> HashSet<WorkloadId> {
+        workloads
+            .into_iter()
+            .filter(|w| self.selector.matches(&w.labels))
+            .map(|w| w.id)
+            .collect()
+    }
+
+    /// Whether an instance belongs in this listing, given the IDs of the
+    /// workloads the selector matches (None when there is no selector).
+    pub fn selects_instance(
+        &self,
+        instance: &WorkloadInstance,
+        workloads: Option<&HashSet<WorkloadId>>,
+    ) -> bool {
+        self.in_namespace(&instance.namespace)
+            && workloads.is_none_or(|ids| ids.contains(&instance.workload_id))
+    }
+}
+
+/// One page of a listing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass as `PageRequest::after` to continue; None on the last page
+    pub next: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Cut the requested page out of a full, unordered listing.
+    pub fn of(items: Vec<T>, page: &PageRequest, id: impl Fn(&T) -> String) -> Self {
+        let mut keyed: Vec<(String, T)> = items
+            .into_iter()
+            .map(|item| (id(&item), item))
+            .filter(|(key, _)| page.after.as_ref().is_none_or(|after| key > after))
+            .collect();
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+        Self::from_ordered(keyed, page.limit)
+    }
+
+    /// Build a page from items already in ID order, of which up to `limit + 1`
+    /// were fetched to tell whether more follow.
+    pub fn from_ordered(mut keyed: Vec<(String, T)>, limit: usize) -> Self {
+        let more = keyed.len() > limit;
+        keyed.truncate(limit);
+        let next = if more {
+            keyed.last().map(|(key, _)| key.clone())
+        } else {
+            None
+        };
+        Self {
+            items: keyed.into_iter().map(|(_, item)| item).collect(),
+            next,
+        }
+    }
+}
+
+/// The lease `holder` ends up with when trying to acquire or renew
+/// `current` at `now_ms`, or `None` while someone else holds it.
+///
+/// Backends apply this inside whatever atomic update they offer.
+pub fn next_lease(
+    current: Option<&LeaderLease>,
+    holder: &str,
+    now_ms: u64,
+    duration: Duration,
+) -> Option<LeaderLease> {
+    let expires_at_ms = now_ms + duration.as_millis() as u64;
+    match current {
+        Some(lease) if lease.holder == holder => Some(LeaderLease {
+            expires_at_ms,
+            ..lease.clone()
+        }),
+        Some(lease) if lease.is_held_at(now_ms) => None,
+        _ => Some(LeaderLease {
+            holder: holder.to_string(),
+            term: current.map_or(0, |lease| lease.term) + 1,
+            acquired_at_ms: now_ms,
+            expires_at_ms,
+        }),
+    }
+}
+
+pub mod label_selector;
+pub use label_selector::{LabelRequirement, LabelSelector};
+
 // Re-export implementations based on features
 #[cfg(feature = "sqlite")]
 pub mod sqlite_store;
@@ -129,9 +579,12 @@ pub mod sqlite_store;
 #[cfg(feature = "in-memory")]
 pub mod in_memory;
 
-#[cfg(feature = "etcd")]
+#[cfg(feature = "etcd-store")]
 pub mod etcd_store;
 
+#[cfg(feature = "sql-store")]
+pub mod sql_store;
+
 // Re-export SQLite store as the default when enabled
 #[cfg(feature = "sqlite")]
 pub use sqlite_store::SqliteStateStore;
@@ -151,11 +604,18 @@ pub fn create_state_store(config: StateStoreConfig) -> Result<Arc<dyn StateStore
             Ok(Arc::new(in_memory::InMemoryStateStore::new()))
         }
 
-        #[cfg(feature = "etcd")]
-        StateStoreConfig::Etcd { endpoints } => {
-            // Will be implemented in etcd_store module
-            etcd_store::EtcdStateStore::new(endpoints)
-                .map(|store| Arc::new(store) as Arc<dyn StateStore>)
+        #[cfg(feature = "etcd-store")]
+        StateStoreConfig::Etcd { .. } => {
+            Err(OrchestrationError::ConfigError(
+                "etcd store requires async initialization. Use create_state_store_async instead.".to_string()
+            ))
+        }
+
+        #[cfg(feature = "sql-store")]
+        StateStoreConfig::Sql { .. } => {
+            Err(OrchestrationError::ConfigError(
+                "SQL store requires async initialization. Use create_state_store_async instead.".to_string()
+            ))
         }
 
         #[allow(unreachable_patterns)]
@@ -180,10 +640,16 @@ pub async fn create_state_store_async(config: StateStoreConfig) -> Result<Arc<dy
             Ok(Arc::new(in_memory::InMemoryStateStore::new()))
         }
 
-        #[cfg(feature = "etcd")]
+        #[cfg(feature = "etcd-store")]
         StateStoreConfig::Etcd { endpoints } => {
-            etcd_store::EtcdStateStore::new(endpoints)
-                .map(|store| Arc::new(store) as Arc<dyn StateStore>)
+            let store = etcd_store::EtcdStateStore::new(endpoints).await?;
+            Ok(Arc::new(store) as Arc<dyn StateStore>)
+        }
+
+        #[cfg(feature = "sql-store")]
+        StateStoreConfig::Sql { url } => {
+            let store = sql_store::SqlStateStore::connect(&url).await?;
+            Ok(Arc::new(store) as Arc<dyn StateStore>)
         }
 
         #[allow(unreachable_patterns)]
@@ -207,8 +673,16 @@ pub enum StateStoreConfig {
     #[cfg(feature = "in-memory")]
     InMemory,
 
-    #[cfg(feature = "etcd")]
+    #[cfg(feature = "etcd-store")]
     Etcd {
         endpoints: Vec<String>,
     },
+
+    /// SQL backend via sqlx: SQLite for a single node, Postgres for HA
+    #[cfg(feature = "sql-store")]
+    Sql {
+        /// Database URL, e.g. "sqlite:///var/lib/orchestrator/state.db" or
+        /// "postgres://user:pass@db/orchestrator"
+        url: String,
+    },
 }
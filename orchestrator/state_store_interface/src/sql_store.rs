@@ -0,0 +1,1173 @@
+//! SQL implementation of StateStore using sqlx.
+//!
+//! The database is chosen by URL: SQLite (`sqlite://...`) for a single node,
+//! Postgres (`postgres://...`) for control planes that share state. Objects
+//! are stored as JSON documents keyed by ID next to a version column that
+//! every write increments, and the schema is created and upgraded by the
+//! migrations in [`MIGRATIONS`] when the store connects.
+
+use async_trait::async_trait;
+use orchestrator_shared_types::{
+    Event, LeaderLease, Namespace, Node, NodeId, OrchestrationError, Result, UsageRecord,
+    WorkloadDefinition, WorkloadId, WorkloadInstance, WorkloadRevision,
+};
+use sqlx::any::{AnyPoolOptions, AnyRow};
+use sqlx::{AnyPool, Row};
+use std::time::Duration;
+
+use crate::{
+    check_resource_version, next_lease, Page, PageRequest, StateStore, StateStoreError, Versioned,
+};
+
+/// Connections kept open to a file or server database.
+const DEFAULT_MAX_CONNECTIONS: u32 = 8;
+
+/// Most rows read per query while filling a page.
+const MAX_PAGE_BATCH: usize = 500;
+
+/// Schema migrations, applied in order and recorded in `schema_migrations`.
+/// Each statement must run unchanged on both SQLite and Postgres; append new
+/// versions rather than editing released ones.
+pub const MIGRATIONS: &[(i64, &[&str])] = &[
+    (
+        1,
+        &[
+            "CREATE TABLE IF NOT EXISTS nodes (id TEXT PRIMARY KEY, data TEXT NOT NULL)",
+            "CREATE TABLE IF NOT EXISTS workloads (id TEXT PRIMARY KEY, data TEXT NOT NULL)",
+            "CREATE TABLE IF NOT EXISTS instances (
+                id TEXT PRIMARY KEY,
+                workload_id TEXT NOT NULL,
+                data TEXT NOT NULL
+            )",
+            "CREATE INDEX IF NOT EXISTS instances_by_workload ON instances (workload_id)",
+            "CREATE TABLE IF NOT EXISTS leases (
+                name TEXT PRIMARY KEY,
+                version BIGINT NOT NULL,
+                data TEXT NOT NULL
+            )",
+        ],
+    ),
+    (
+        2,
+        &[
+            "ALTER TABLE nodes ADD COLUMN version BIGINT NOT NULL DEFAULT 0",
+            "ALTER TABLE workloads ADD COLUMN version BIGINT NOT NULL DEFAULT 0",
+            "ALTER TABLE instances ADD COLUMN version BIGINT NOT NULL DEFAULT 0",
+        ],
+    ),
+    (
+        3,
+        &["CREATE TABLE IF NOT EXISTS namespaces (
+                id TEXT PRIMARY KEY,
+                version BIGINT NOT NULL,
+                data TEXT NOT NULL
+            )"],
+    ),
+    (
+        4,
+        &["CREATE TABLE IF NOT EXISTS workload_revisions (
+                workload_id TEXT NOT NULL,
+                revision BIGINT NOT NULL,
+                data TEXT NOT NULL,
+                PRIMARY KEY (workload_id, revision)
+            )"],
+    ),
+    (
+        5,
+        &[
+            "CREATE TABLE IF NOT EXISTS events (
+                id TEXT PRIMARY KEY,
+                last_timestamp BIGINT NOT NULL,
+                data TEXT NOT NULL
+            )",
+            "CREATE INDEX IF NOT EXISTS events_by_time ON events (last_timestamp)",
+        ],
+    ),
+    (
+        6,
+        &["CREATE TABLE IF NOT EXISTS usage_records (
+                period_start BIGINT NOT NULL,
+                workload_id TEXT NOT NULL,
+                data TEXT NOT NULL,
+                PRIMARY KEY (period_start, workload_id)
+            )"],
+    ),
+    (
+        7,
+        &["CREATE TABLE IF NOT EXISTS resources (
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                data TEXT NOT NULL,
+                PRIMARY KEY (kind, name)
+            )"],
+    ),
+];
+
+/// SQL-backed implementation of StateStore.
+///
+/// Every write is a single statement or one transaction, so batches of
+/// instances land together or not at all. Leases are updated with a
+/// compare-and-swap on a version column, which keeps leader election safe
+/// when several control planes share a Postgres database.
+pub struct SqlStateStore {
+    pool: AnyPool,
+}
+
+impl SqlStateStore {
+    /// Connect to the database at `url` and bring its schema up to date.
+    pub async fn connect(url: &str) -> Result<Self> {
+        sqlx::any::install_default_drivers();
+
+        // Every connection to an in-memory SQLite database gets its own
+        // database, so share a single one
+        let max_connections = if url.contains(":memory:") || url.contains("mode=memory") {
+            1
+        } else {
+            DEFAULT_MAX_CONNECTIONS
+        };
+        let pool = AnyPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(url)
+            .await
+            .map_err(|e| {
+                StateStoreError::ConnectionError(format!(
+                    "Failed to connect to {}: {}",
+                    redact(url),
+                    e
+                ))
+            })?;
+
+        let store = Self { pool };
+        store.migrate().await?;
+        Ok(store)
+    }
+
+    /// Create an in-memory SQLite store (for testing).
+    pub async fn in_memory() -> Result<Self> {
+        Self::connect("sqlite::memory:").await
+    }
+
+    /// Apply the migrations this database has not seen yet.
+    pub async fn migrate(&self) -> Result<()> {
+        sqlx::query("CREATE TABLE IF NOT EXISTS schema_migrations (version BIGINT PRIMARY KEY)")
+            .execute(&self.pool)
+            .await
+            .map_err(query_error)?;
+
+        let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM schema_migrations")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(query_error)?;
+
+        for (version, statements) in MIGRATIONS {
+            if applied.contains(version) {
+                continue;
+            }
+            let mut tx = self.pool.begin().await.map_err(transaction_error)?;
+            for statement in *statements {
+                sqlx::query(statement)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| {
+                        StateStoreError::InternalError(format!(
+                            "Migration {} failed: {}",
+                            version, e
+                        ))
+                    })?;
+            }
+            sqlx::query(
+                "INSERT INTO schema_migrations (version) VALUES ($1) ON CONFLICT (version) DO NOTHING",
+            )
+            .bind(*version)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+            tx.commit().await.map_err(transaction_error)?;
+        }
+        Ok(())
+    }
+
+    /// Latest migration applied to this database (0 before any).
+    pub async fn schema_version(&self) -> Result<i64> {
+        let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_migrations")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(query_error)?;
+        Ok(version.unwrap_or(0))
+    }
+
+    // Helper to upsert a JSON document into a table keyed by `id`
+    async fn put_document<T: serde::Serialize>(
+        &self,
+        table: &str,
+        id: &str,
+        value: &T,
+    ) -> Result<()> {
+        sqlx::query(&format!(
+            "INSERT INTO {0} (id, version, data) VALUES ($1, 1, $2)
+             ON CONFLICT (id) DO UPDATE SET data = excluded.data, version = {0}.version + 1",
+            table
+        ))
+        .bind(id)
+        .bind(to_json(value)?)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+        Ok(())
+    }
+
+    // Helper to get and deserialize a document by ID
+    async fn get_document<T: serde::de::DeserializeOwned + Versioned>(
+        &self,
+        table: &str,
+        id: &str,
+    ) -> Result<Option<T>> {
+        let row: Option<AnyRow> = sqlx::query(&format!(
+            "SELECT version, data FROM {} WHERE id = $1",
+            table
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?;
+        row.as_ref().map(from_row).transpose()
+    }
+
+    // Helper to list every document of a table
+    async fn list_documents<T: serde::de::DeserializeOwned + Versioned>(
+        &self,
+        table: &str,
+    ) -> Result<Vec<T>> {
+        let rows: Vec<AnyRow> =
+            sqlx::query(&format!("SELECT version, data FROM {} ORDER BY id", table))
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+        rows.iter().map(from_row).collect()
+    }
+
+    // Helper to read one page of a table in ID order, of the documents `keep`
+    // accepts
+    async fn page_documents<T: serde::de::DeserializeOwned + Versioned>(
+        &self,
+        table: &str,
+        page: &PageRequest,
+        keep: impl Fn(&T) -> bool + Send,
+    ) -> Result<Page<T>> {
+        // One match past the limit tells whether another page follows
+        let wanted = page.limit.saturating_add(1);
+        let batch = wanted.min(MAX_PAGE_BATCH);
+        let mut after = page.after.clone().unwrap_or_default();
+        let mut keyed = Vec::new();
+        loop {
+            let rows: Vec<AnyRow> = sqlx::query(&format!(
+                "SELECT id, version, data FROM {} WHERE id > $1 ORDER BY id LIMIT $2",
+                table
+            ))
+            .bind(after.clone())
+            .bind(batch as i64)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(query_error)?;
+
+            let fetched = rows.len();
+            for row in &rows {
+                let id: String = row.try_get("id").map_err(query_error)?;
+                let document: T = from_row(row)?;
+                after = id.clone();
+                if keep(&document) {
+                    keyed.push((id, document));
+                }
+            }
+            if fetched < batch || keyed.len() >= wanted {
+                break;
+            }
+        }
+        Ok(Page::from_ordered(keyed, page.limit))
+    }
+
+    // Helper to delete a document by ID
+    async fn delete_document(&self, table: &str, id: &str) -> Result<()> {
+        sqlx::query(&format!("DELETE FROM {} WHERE id = $1", table))
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(query_error)?;
+        Ok(())
+    }
+
+    // Helper to read a lease with its version (0 if absent)
+    async fn get_lease_with_version(&self, name: &str) -> Result<(Option<LeaderLease>, i64)> {
+        let row: Option<AnyRow> = sqlx::query("SELECT version, data FROM leases WHERE name = $1")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(query_error)?;
+
+        match row {
+            Some(row) => {
+                let version: i64 = row.try_get("version").map_err(query_error)?;
+                let data: String = row.try_get("data").map_err(query_error)?;
+                Ok((Some(from_json(&data)?), version))
+            }
+            None => Ok((None, 0)),
+        }
+    }
+
+    // Helper to write a lease only if it is unchanged since `version`
+    async fn put_lease_if_unchanged(
+        &self,
+        name: &str,
+        version: i64,
+        lease: &LeaderLease,
+    ) -> Result<bool> {
+        let data = to_json(lease)?;
+        let result = if version == 0 {
+            sqlx::query("INSERT INTO leases (name, version, data) VALUES ($1, 1, $2) ON CONFLICT (name) DO NOTHING")
+                .bind(name)
+                .bind(data)
+                .execute(&self.pool)
+                .await
+        } else {
+            sqlx::query("UPDATE leases SET version = version + 1, data = $1 WHERE name = $2 AND version = $3")
+                .bind(data)
+                .bind(name)
+                .bind(version)
+                .execute(&self.pool)
+                .await
+        }
+        .map_err(transaction_error)?;
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string(value)
+        .map_err(|e| StateStoreError::SerializationError(e.to_string()).into())
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(data: &str) -> Result<T> {
+    serde_json::from_str(data)
+        .map_err(|e| StateStoreError::SerializationError(e.to_string()).into())
+}
+
+fn from_row<T: serde::de::DeserializeOwned + Versioned>(row: &AnyRow) -> Result<T> {
+    let version: i64 = row.try_get("version").map_err(query_error)?;
+    let data: String = row.try_get("data").map_err(query_error)?;
+    let mut value: T = from_json(&data)?;
+    value.set_resource_version(version as u64);
+    Ok(value)
+}
+
+fn query_error(e: sqlx::Error) -> orchestrator_shared_types::OrchestrationError {
+    StateStoreError::InternalError(format!("SQL query failed: {}", e)).into()
+}
+
+fn transaction_error(e: sqlx::Error) -> orchestrator_shared_types::OrchestrationError {
+    StateStoreError::TransactionError(format!("SQL transaction failed: {}", e)).into()
+}
+
+/// `url` with any password replaced, for error messages.
+fn redact(url: &str) -> String {
+    match (url.find("://"), url.rfind('@')) {
+        (Some(scheme_end), Some(at)) if at > scheme_end => {
+            let credentials = &url[scheme_end + 3..at];
+            match credentials.split_once(':') {
+                Some((user, _)) => format!("{}{}:***{}", &url[..scheme_end + 3], user, &url[at..]),
+                None => url.to_string(),
+            }
+        }
+        _ => url.to_string(),
+    }
+}
+
+#[async_trait]
+impl StateStore for SqlStateStore {
+    async fn initialize(&self) -> Result<()> {
+        // The schema is migrated on connect
+        self.health_check().await?;
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        match sqlx::query("SELECT 1").execute(&self.pool).await {
+            Ok(_) => Ok(true),
+            Err(_) => Ok(false),
+        }
+    }
+
+    // ===== Node Operations =====
+
+    async fn put_node(&self, node: Node) -> Result<()> {
+        self.put_document("nodes", &node.id.to_string(), &node)
+            .await
+    }
+
+    async fn get_node(&self, node_id: &NodeId) -> Result<Option<Node>> {
+        self.get_document("nodes", &node_id.to_string()).await
+    }
+
+    async fn list_nodes(&self) -> Result<Vec<Node>> {
+        self.list_documents("nodes").await
+    }
+
+    async fn delete_node(&self, node_id: &NodeId) -> Result<()> {
+        self.delete_document("nodes", &node_id.to_string()).await
+    }
+
+    // ===== Workload Operations =====
+
+    async fn put_workload(&self, workload: WorkloadDefinition) -> Result<()> {
+        self.put_document("workloads", &workload.id.to_string(), &workload)
+            .await
+    }
+
+    async fn get_workload(&self, workload_id: &WorkloadId) -> Result<Option<WorkloadDefinition>> {
+        self.get_document("workloads", &workload_id.to_string())
+            .await
+    }
+
+    async fn list_workloads(&self) -> Result<Vec<WorkloadDefinition>> {
+        self.list_documents("workloads").await
+    }
+
+    async fn delete_workload(&self, workload_id: &WorkloadId) -> Result<()> {
+        let mut tx = self.pool.begin().await.map_err(transaction_error)?;
+        for statement in [
+            "DELETE FROM workloads WHERE id = $1",
+            "DELETE FROM workload_revisions WHERE workload_id = $1",
+        ] {
+            sqlx::query(statement)
+                .bind(workload_id.to_string())
+                .execute(&mut *tx)
+                .await
+                .map_err(query_error)?;
+        }
+        tx.commit().await.map_err(transaction_error)
+    }
+
+    async fn compare_and_put_workload(
+        &self,
+        mut workload: WorkloadDefinition,
+    ) -> Result<WorkloadDefinition> {
+        let id = workload.id.to_string();
+        let expected = workload.resource_version;
+        let data = to_json(&workload)?;
+        let result = if expected == 0 {
+            sqlx::query("INSERT INTO workloads (id, version, data) VALUES ($1, 1, $2) ON CONFLICT (id) DO NOTHING")
+                .bind(&id)
+                .bind(data)
+                .execute(&self.pool)
+                .await
+        } else {
+            sqlx::query("UPDATE workloads SET version = version + 1, data = $1 WHERE id = $2 AND version = $3")
+                .bind(data)
+                .bind(&id)
+                .bind(expected as i64)
+                .execute(&self.pool)
+                .await
+        }
+        .map_err(transaction_error)?;
+
+        if result.rows_affected() != 1 {
+            let stored: Option<WorkloadDefinition> = self.get_document("workloads", &id).await?;
+            check_resource_version(
+                "workload",
+                &id,
+                stored.map(|w| w.resource_version),
+                expected,
+            )?;
+            return Err(OrchestrationError::Conflict(format!(
+                "workload {} was modified concurrently",
+                id
+            )));
+        }
+        workload.resource_version = expected + 1;
+        Ok(workload)
+    }
+
+    // ===== Instance Operations =====
+
+    async fn put_instance(&self, instance: WorkloadInstance) -> Result<()> {
+        self.put_instances_batch(vec![instance]).await
+    }
+
+    async fn get_instance(&self, instance_id: &str) -> Result<Option<WorkloadInstance>> {
+        self.get_document("instances", instance_id).await
+    }
+
+    async fn list_instances_for_workload(
+        &self,
+        workload_id: &WorkloadId,
+    ) -> Result<Vec<WorkloadInstance>> {
+        let rows: Vec<AnyRow> =
+            sqlx::query("SELECT version, data FROM instances WHERE workload_id = $1 ORDER BY id")
+                .bind(workload_id.to_string())
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+        rows.iter().map(from_row).collect()
+    }
+
+    async fn list_all_instances(&self) -> Result<Vec<WorkloadInstance>> {
+        self.list_documents("instances").await
+    }
+
+    async fn delete_instance(&self, instance_id: &str) -> Result<()> {
+        self.delete_document("instances", instance_id).await
+    }
+
+    async fn delete_instances_for_workload(&self, workload_id: &WorkloadId) -> Result<()> {
+        sqlx::query("DELETE FROM instances WHERE workload_id = $1")
+            .bind(workload_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(query_error)?;
+        Ok(())
+    }
+
+    // ===== Namespace Operations =====
+
+    async fn put_namespace(&self, namespace: Namespace) -> Result<()> {
+        self.put_document("namespaces", &namespace.name, &namespace)
+            .await
+    }
+
+    async fn get_namespace(&self, name: &str) -> Result<Option<Namespace>> {
+        self.get_document("namespaces", name).await
+    }
+
+    async fn list_namespaces(&self) -> Result<Vec<Namespace>> {
+        self.list_documents("namespaces").await
+    }
+
+    async fn delete_namespace(&self, name: &str) -> Result<()> {
+        self.delete_document("namespaces", name).await
+    }
+
+    async fn put_instances_batch(&self, instances: Vec<WorkloadInstance>) -> Result<()> {
+        let mut tx = self.pool.begin().await.map_err(transaction_error)?;
+        for instance in &instances {
+            sqlx::query(
+                "INSERT INTO instances (id, workload_id, version, data) VALUES ($1, $2, 1, $3)
+                 ON CONFLICT (id) DO UPDATE SET workload_id = excluded.workload_id,
+                    data = excluded.data, version = instances.version + 1",
+            )
+            .bind(instance.id.to_string())
+            .bind(instance.workload_id.to_string())
+            .bind(to_json(instance)?)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+        }
+        tx.commit().await.map_err(transaction_error)
+    }
+
+    // ===== Pagination =====
+
+    async fn list_nodes_page(&self, page: PageRequest) -> Result<Page<Node>> {
+        self.page_documents("nodes", &page, |n: &Node| page.selector.matches(&n.labels))
+            .await
+    }
+
+    async fn list_workloads_page(&self, page: PageRequest) -> Result<Page<WorkloadDefinition>> {
+        self.page_documents("workloads", &page, |w: &WorkloadDefinition| {
+            page.selects_workload(w)
+        })
+        .await
+    }
+
+    async fn list_instances_page(&self, page: PageRequest) -> Result<Page<WorkloadInstance>> {
+        let workloads = if page.selector.is_empty() {
+            None
+        } else {
+            Some(page.selected_workloads(self.list_workloads().await?))
+        };
+        self.page_documents("instances", &page, |i: &WorkloadInstance| {
+            page.selects_instance(i, workloads.as_ref())
+        })
+        .await
+    }
+
+    // ===== Workload Revisions =====
+
+    async fn put_workload_revision(&self, revision: WorkloadRevision) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO workload_revisions (workload_id, revision, data) VALUES ($1, $2, $3)
+             ON CONFLICT (workload_id, revision) DO UPDATE SET data = excluded.data",
+        )
+        .bind(revision.workload_id.to_string())
+        .bind(revision.revision as i64)
+        .bind(to_json(&revision)?)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+        Ok(())
+    }
+
+    async fn list_workload_revisions(
+        &self,
+        workload_id: &WorkloadId,
+    ) -> Result<Vec<WorkloadRevision>> {
+        let rows: Vec<String> = sqlx::query_scalar(
+            "SELECT data FROM workload_revisions WHERE workload_id = $1 ORDER BY revision",
+        )
+        .bind(workload_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)?;
+        rows.iter().map(|data| from_json(data)).collect()
+    }
+
+    async fn delete_workload_revision(
+        &self,
+        workload_id: &WorkloadId,
+        revision: u64,
+    ) -> Result<()> {
+        sqlx::query("DELETE FROM workload_revisions WHERE workload_id = $1 AND revision = $2")
+            .bind(workload_id.to_string())
+            .bind(revision as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(query_error)?;
+        Ok(())
+    }
+
+    // ===== Events =====
+
+    async fn put_event(&self, event: Event) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO events (id, last_timestamp, data) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO UPDATE
+             SET last_timestamp = excluded.last_timestamp, data = excluded.data",
+        )
+        .bind(event.id.to_string())
+        .bind(event.last_timestamp_ms as i64)
+        .bind(to_json(&event)?)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+        Ok(())
+    }
+
+    async fn list_events(&self) -> Result<Vec<Event>> {
+        let rows: Vec<String> =
+            sqlx::query_scalar("SELECT data FROM events ORDER BY last_timestamp, id")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+        rows.iter().map(|data| from_json(data)).collect()
+    }
+
+    async fn delete_event(&self, event_id: &uuid::Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM events WHERE id = $1")
+            .bind(event_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(query_error)?;
+        Ok(())
+    }
+
+    // ===== Usage Records =====
+
+    async fn put_usage_record(&self, record: UsageRecord) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO usage_records (period_start, workload_id, data) VALUES ($1, $2, $3)
+             ON CONFLICT (period_start, workload_id) DO UPDATE SET data = excluded.data",
+        )
+        .bind(record.period_start_ms as i64)
+        .bind(record.workload_id.to_string())
+        .bind(to_json(&record)?)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+        Ok(())
+    }
+
+    async fn list_usage_records(&self, from_ms: u64, to_ms: u64) -> Result<Vec<UsageRecord>> {
+        let rows: Vec<String> = sqlx::query_scalar(
+            "SELECT data FROM usage_records WHERE period_start >= $1 AND period_start < $2
+             ORDER BY period_start, workload_id",
+        )
+        .bind(from_ms.min(i64::MAX as u64) as i64)
+        .bind(to_ms.min(i64::MAX as u64) as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)?;
+        rows.iter().map(|data| from_json(data)).collect()
+    }
+
+    // ===== Resources =====
+
+    async fn put_resource(&self, kind: &str, name: &str, data: serde_json::Value) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO resources (kind, name, data) VALUES ($1, $2, $3)
+             ON CONFLICT (kind, name) DO UPDATE SET data = excluded.data",
+        )
+        .bind(kind)
+        .bind(name)
+        .bind(to_json(&data)?)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+        Ok(())
+    }
+
+    async fn get_resource(&self, kind: &str, name: &str) -> Result<Option<serde_json::Value>> {
+        let data: Option<String> =
+            sqlx::query_scalar("SELECT data FROM resources WHERE kind = $1 AND name = $2")
+                .bind(kind)
+                .bind(name)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(query_error)?;
+        data.as_deref().map(from_json).transpose()
+    }
+
+    async fn list_resources(&self, kind: &str) -> Result<Vec<serde_json::Value>> {
+        let rows: Vec<String> =
+            sqlx::query_scalar("SELECT data FROM resources WHERE kind = $1 ORDER BY name")
+                .bind(kind)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+        rows.iter().map(|data| from_json(data)).collect()
+    }
+
+    async fn delete_resource(&self, kind: &str, name: &str) -> Result<()> {
+        sqlx::query("DELETE FROM resources WHERE kind = $1 AND name = $2")
+            .bind(kind)
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(query_error)?;
+        Ok(())
+    }
+
+    // ===== Leases =====
+
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        now_ms: u64,
+        duration: Duration,
+    ) -> Result<LeaderLease> {
+        // Retry until no other candidate wrote the lease between read and write
+        loop {
+            let (current, version) = self.get_lease_with_version(name).await?;
+            let Some(lease) = next_lease(current.as_ref(), holder, now_ms, duration) else {
+                return Ok(current.unwrap_or_default());
+            };
+            if self.put_lease_if_unchanged(name, version, &lease).await? {
+                return Ok(lease);
+            }
+        }
+    }
+
+    async fn get_lease(&self, name: &str) -> Result<Option<LeaderLease>> {
+        Ok(self.get_lease_with_version(name).await?.0)
+    }
+
+    async fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+        let (current, version) = self.get_lease_with_version(name).await?;
+        if let Some(mut lease) = current.filter(|lease| lease.holder == holder) {
+            lease.expires_at_ms = 0;
+            // Losing the race means someone else holds it now; nothing to release
+            self.put_lease_if_unchanged(name, version, &lease).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orchestrator_shared_types::{
+        EventType, Keypair, NodeResources, NodeStatus, ObjectReference, WorkloadInstanceStatus,
+    };
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn test_node() -> Node {
+        Node {
+            id: Keypair::generate().public_key(),
+            address: "10.0.0.1:8080".to_string(),
+            status: NodeStatus::Ready,
+            labels: HashMap::new(),
+            resources_capacity: NodeResources {
+                cpu_cores: 4.0,
+                memory_mb: 8192,
+                disk_mb: 100000,
+            },
+            resources_allocatable: NodeResources {
+                cpu_cores: 3.8,
+                memory_mb: 7000,
+                disk_mb: 90000,
+            },
+            unschedulable: false,
+            resource_version: 0,
+        }
+    }
+
+    fn test_instance(workload_id: WorkloadId, node_id: NodeId) -> WorkloadInstance {
+        WorkloadInstance {
+            id: Uuid::new_v4(),
+            workload_id,
+            node_id,
+            container_ids: vec![],
+            status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sql_migrations_are_idempotent() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        let latest = MIGRATIONS.last().unwrap().0;
+        assert_eq!(store.schema_version().await.unwrap(), latest);
+
+        store.migrate().await.unwrap();
+        assert_eq!(store.schema_version().await.unwrap(), latest);
+        assert!(store.health_check().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_sql_node_operations() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        let mut node = test_node();
+
+        store.put_node(node.clone()).await.unwrap();
+        node.unschedulable = true;
+        store.put_node(node.clone()).await.unwrap();
+
+        let nodes = store.list_nodes().await.unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert!(
+            store
+                .get_node(&node.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .unschedulable
+        );
+
+        store.delete_node(&node.id).await.unwrap();
+        assert!(store.get_node(&node.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sql_compare_and_put_workload() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        let workload = WorkloadDefinition {
+            id: Uuid::new_v4(),
+            name: "web".to_string(),
+            containers: vec![],
+            init_containers: vec![],
+            sidecars: vec![],
+            replicas: 1,
+            labels: HashMap::new(),
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+
+        let created = store
+            .compare_and_put_workload(workload.clone())
+            .await
+            .unwrap();
+        assert_eq!(created.resource_version, 1);
+        assert!(matches!(
+            store.compare_and_put_workload(workload).await,
+            Err(OrchestrationError::Conflict(_))
+        ));
+
+        // A plain write moves the version on, so the first copy is now stale
+        store.put_workload(created.clone()).await.unwrap();
+        let stored = store.get_workload(&created.id).await.unwrap().unwrap();
+        assert_eq!(stored.resource_version, 2);
+        assert!(matches!(
+            store.compare_and_put_workload(created).await,
+            Err(OrchestrationError::Conflict(_))
+        ));
+        let updated = store.compare_and_put_workload(stored).await.unwrap();
+        assert_eq!(updated.resource_version, 3);
+    }
+
+    #[tokio::test]
+    async fn test_sql_workload_revisions() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        let workload = WorkloadDefinition {
+            id: Uuid::new_v4(),
+            name: "web".to_string(),
+            containers: vec![],
+            init_containers: vec![],
+            sidecars: vec![],
+            replicas: 1,
+            labels: HashMap::new(),
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        store.put_workload(workload.clone()).await.unwrap();
+        for revision in [2, 1, 3, 3] {
+            store
+                .put_workload_revision(WorkloadRevision {
+                    workload_id: workload.id,
+                    revision,
+                    workload: workload.clone(),
+                    created_at_ms: revision * 1000,
+                })
+                .await
+                .unwrap();
+        }
+        store.delete_workload_revision(&workload.id, 1).await.unwrap();
+        let numbers: Vec<u64> = store
+            .list_workload_revisions(&workload.id)
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.revision)
+            .collect();
+        assert_eq!(numbers, [2, 3]);
+
+        store.delete_workload(&workload.id).await.unwrap();
+        assert!(store.get_workload(&workload.id).await.unwrap().is_none());
+        assert!(store
+            .list_workload_revisions(&workload.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sql_events() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        let node_id = test_node().id;
+        let mut event = Event {
+            id: Uuid::new_v4(),
+            involved_object: ObjectReference::node(&node_id),
+            namespace: String::new(),
+            event_type: EventType::Warning,
+            reason: "NodeNotReady".to_string(),
+            message: "Node stopped renewing its lease".to_string(),
+            source: "node-lifecycle".to_string(),
+            count: 1,
+            first_timestamp_ms: 1000,
+            last_timestamp_ms: 1000,
+        };
+        store.put_event(event.clone()).await.unwrap();
+        event.count = 2;
+        event.last_timestamp_ms = 2000;
+        store.put_event(event.clone()).await.unwrap();
+        assert_eq!(store.list_events().await.unwrap(), [event.clone()]);
+
+        store.delete_event(&event.id).await.unwrap();
+        assert!(store.list_events().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sql_usage_records() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        let hour = UsageRecord::PERIOD_MS;
+        let mut record = UsageRecord {
+            workload_id: Uuid::new_v4(),
+            workload_name: "web".to_string(),
+            namespace: "shop".to_string(),
+            period_start_ms: hour,
+            cpu_core_seconds: 1800.0,
+            memory_gb_seconds: 900.0,
+            gpu_seconds: 0.0,
+        };
+        store.put_usage_record(record.clone()).await.unwrap();
+        record.cpu_core_seconds = 3600.0;
+        store.put_usage_record(record.clone()).await.unwrap();
+        let next = UsageRecord {
+            period_start_ms: 2 * hour,
+            ..record.clone()
+        };
+        store.put_usage_record(next.clone()).await.unwrap();
+
+        assert_eq!(
+            store.list_usage_records(0, 3 * hour).await.unwrap(),
+            [record.clone(), next]
+        );
+        assert_eq!(
+            store.list_usage_records(hour, 2 * hour).await.unwrap(),
+            [record]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sql_resources() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        let job = |name: &str| serde_json::json!({ "name": name });
+        store.put_resource("cronjobs", "b", job("b")).await.unwrap();
+        store.put_resource("cronjobs", "a", job("a")).await.unwrap();
+        store.put_resource("daemonsets", "a", job("a")).await.unwrap();
+
+        assert_eq!(
+            store.list_resources("cronjobs").await.unwrap(),
+            [job("a"), job("b")]
+        );
+        assert_eq!(store.get_resource("cronjobs", "b").await.unwrap(), Some(job("b")));
+
+        store.delete_resource("cronjobs", "a").await.unwrap();
+        assert_eq!(store.list_resources("cronjobs").await.unwrap(), [job("b")]);
+        assert!(store.get_resource("cronjobs", "a").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sql_instances_by_workload() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        let node_id = test_node().id;
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let batch: Vec<_> = (0..5).map(|_| test_instance(a, node_id)).collect();
+        store.put_instances_batch(batch.clone()).await.unwrap();
+        store.put_instance(test_instance(b, node_id)).await.unwrap();
+
+        assert_eq!(
+            store.list_instances_for_workload(&a).await.unwrap().len(),
+            5
+        );
+        assert_eq!(store.list_all_instances().await.unwrap().len(), 6);
+        let id = batch[0].id.to_string();
+        assert_eq!(
+            store.get_instance(&id).await.unwrap().unwrap().workload_id,
+            a
+        );
+
+        store.delete_instances_for_workload(&a).await.unwrap();
+        assert!(store
+            .list_instances_for_workload(&a)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(store.list_all_instances().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sql_pagination() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        let node_id = test_node().id;
+        let workload_id = Uuid::new_v4();
+        let instances: Vec<_> = (0..7)
+            .map(|_| test_instance(workload_id, node_id))
+            .collect();
+        store.put_instances_batch(instances).await.unwrap();
+
+        let mut request = Some(PageRequest::first(3));
+        let mut seen = Vec::new();
+        let mut pages = 0;
+        while let Some(page_request) = request {
+            let page = store
+                .list_instances_page(page_request.clone())
+                .await
+                .unwrap();
+            seen.extend(page.items.iter().map(|i| i.id.to_string()));
+            request = page_request.next(&page);
+            pages += 1;
+        }
+        assert_eq!(pages, 3);
+        let mut sorted = seen.clone();
+        sorted.sort();
+        assert_eq!(seen, sorted);
+        assert_eq!(seen.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_sql_pagination_with_selector() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        for i in 0..12 {
+            let mut node = test_node();
+            let pool = if i % 3 == 0 { "gpu" } else { "cpu" };
+            node.labels.insert("pool".to_string(), pool.to_string());
+            store.put_node(node).await.unwrap();
+        }
+
+        let selector = "pool=gpu".parse().unwrap();
+        let first = PageRequest::first(3).with_selector(selector);
+        let page = store.list_nodes_page(first.clone()).await.unwrap();
+        assert_eq!(page.items.len(), 3);
+        assert!(page.items.iter().all(|n| n.labels["pool"] == "gpu"));
+        let last = store
+            .list_nodes_page(first.next(&page).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(last.items.len(), 1);
+        assert!(last.next.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sql_namespace_operations() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        store.put_namespace(Namespace::new("team-a")).await.unwrap();
+        store.put_namespace(Namespace::new("team-a")).await.unwrap();
+
+        let stored = store.get_namespace("team-a").await.unwrap().unwrap();
+        assert_eq!(stored.resource_version, 2);
+        assert_eq!(store.list_namespaces().await.unwrap().len(), 1);
+
+        store.delete_namespace("team-a").await.unwrap();
+        assert!(store.get_namespace("team-a").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sql_lease_handover() {
+        let store = SqlStateStore::in_memory().await.unwrap();
+        let ttl = Duration::from_secs(10);
+
+        let lease = store
+            .try_acquire_lease("leader", "a", 1_000, ttl)
+            .await
+            .unwrap();
+        assert_eq!((lease.holder.as_str(), lease.term), ("a", 1));
+        let lease = store
+            .try_acquire_lease("leader", "b", 2_000, ttl)
+            .await
+            .unwrap();
+        assert_eq!(lease.holder, "a");
+
+        store.release_lease("leader", "a").await.unwrap();
+        let lease = store
+            .try_acquire_lease("leader", "b", 3_000, ttl)
+            .await
+            .unwrap();
+        assert_eq!((lease.holder.as_str(), lease.term), ("b", 2));
+    }
+
+    #[test]
+    fn test_redact_password() {
+        assert_eq!(
+            redact("postgres://orch:secret@db:5432/orchestrator"),
+            "postgres://orch:***@db:5432/orchestrator"
+        );
+        assert_eq!(redact("sqlite::memory:"), "sqlite::memory:");
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Postgres; set DATABASE_URL, e.g. postgres://postgres@localhost/orchestrator_test
+    async fn test_postgres_lease_and_pages() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL");
+        let store = SqlStateStore::connect(&url).await.unwrap();
+        let name = format!("test-{}", Uuid::new_v4());
+        let ttl = Duration::from_secs(10);
+
+        let lease = store
+            .try_acquire_lease(&name, "a", 1_000, ttl)
+            .await
+            .unwrap();
+        assert_eq!(lease.holder, "a");
+        let page = store
+            .list_workloads_page(PageRequest::first(1))
+            .await
+            .unwrap();
+        assert!(page.items.len() <= 1);
+    }
+}
@@ -5,20 +5,27 @@
 
 use async_trait::async_trait;
 use orchestrator_shared_types::{
-    Node, NodeId, Result, OrchestrationError, WorkloadDefinition, WorkloadId, WorkloadInstance,
+    Namespace, Node, NodeId, Result, OrchestrationError, WorkloadDefinition, WorkloadId,
+    WorkloadInstance,
 };
 use std::path::Path;
 use std::sync::Arc;
+use tokio::sync::Mutex;
 use univrs_state::{SqliteStore, StateStore as UnivrsStateStore};
 
-use crate::StateStore;
+use crate::{check_resource_version, StateStore, Versioned};
 
 /// SQLite-backed implementation of StateStore.
 ///
 /// Uses univrs-state SqliteStore for the underlying storage,
 /// providing durable persistence with ACID transactions.
+///
+/// Workload writes are serialized by a lock, so a compare-and-put reads,
+/// checks and writes with no other workload write in between. The lock is
+/// per store, so every writer must share one `SqliteStateStore`.
 pub struct SqliteStateStore {
     store: Arc<SqliteStore>,
+    workload_writes: Mutex<()>,
 }
 
 impl SqliteStateStore {
@@ -29,6 +36,7 @@ impl SqliteStateStore {
             .map_err(|e| OrchestrationError::StateError(e.to_string()))?;
         Ok(Self {
             store: Arc::new(store),
+            workload_writes: Mutex::new(()),
         })
     }
 
@@ -39,6 +47,7 @@ impl SqliteStateStore {
             .map_err(|e| OrchestrationError::StateError(e.to_string()))?;
         Ok(Self {
             store: Arc::new(store),
+            workload_writes: Mutex::new(()),
         })
     }
 
@@ -53,6 +62,31 @@ impl SqliteStateStore {
     fn instance_key(instance_id: &str) -> String {
         format!("/instances/{}", instance_id)
     }
+
+    fn namespace_key(name: &str) -> String {
+        format!("/namespaces/{}", name)
+    }
+
+    fn resources_prefix(kind: &str) -> String {
+        format!("/resources/{}/", kind)
+    }
+
+    // Helper to store `value` one version past the stored copy
+    async fn set_versioned<T>(&self, key: &str, mut value: T) -> Result<()>
+    where
+        T: Versioned + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let stored: Option<T> = self
+            .store
+            .get_json(key)
+            .await
+            .map_err(|e| OrchestrationError::StateError(e.to_string()))?;
+        value.set_resource_version(stored.map_or(0, |s| s.resource_version()) + 1);
+        self.store
+            .set_json(key, &value)
+            .await
+            .map_err(|e| OrchestrationError::StateError(e.to_string()))
+    }
 }
 
 #[async_trait]
@@ -74,11 +108,7 @@ impl StateStore for SqliteStateStore {
 
     async fn put_node(&self, node: Node) -> Result<()> {
         let key = Self::node_key(&node.id);
-        self.store
-            .set_json(&key, &node)
-            .await
-            .map_err(|e| OrchestrationError::StateError(e.to_string()))?;
-        Ok(())
+        self.set_versioned(&key, node).await
     }
 
     async fn get_node(&self, node_id: &NodeId) -> Result<Option<Node>> {
@@ -121,12 +151,9 @@ impl StateStore for SqliteStateStore {
     // ===== Workload Operations =====
 
     async fn put_workload(&self, workload: WorkloadDefinition) -> Result<()> {
+        let _writing = self.workload_writes.lock().await;
         let key = Self::workload_key(&workload.id);
-        self.store
-            .set_json(&key, &workload)
-            .await
-            .map_err(|e| OrchestrationError::StateError(e.to_string()))?;
-        Ok(())
+        self.set_versioned(&key, workload).await
     }
 
     async fn get_workload(&self, workload_id: &WorkloadId) -> Result<Option<WorkloadDefinition>> {
@@ -159,6 +186,7 @@ impl StateStore for SqliteStateStore {
     }
 
     async fn delete_workload(&self, workload_id: &WorkloadId) -> Result<()> {
+        let _writing = self.workload_writes.lock().await;
         let key = Self::workload_key(workload_id);
         self.store
             .delete(&key)
@@ -166,15 +194,37 @@ impl StateStore for SqliteStateStore {
             .map_err(|e| OrchestrationError::StateError(e.to_string()))
     }
 
+    async fn compare_and_put_workload(
+        &self,
+        mut workload: WorkloadDefinition,
+    ) -> Result<WorkloadDefinition> {
+        let _writing = self.workload_writes.lock().await;
+        let key = Self::workload_key(&workload.id);
+        let current = self
+            .store
+            .get_json::<WorkloadDefinition>(&key)
+            .await
+            .map_err(|e| OrchestrationError::StateError(e.to_string()))?
+            .map(|w| w.resource_version);
+        check_resource_version(
+            "workload",
+            &workload.id.to_string(),
+            current,
+            workload.resource_version,
+        )?;
+        workload.resource_version = current.unwrap_or(0) + 1;
+        self.store
+            .set_json(&key, &workload)
+            .await
+            .map_err(|e| OrchestrationError::StateError(e.to_string()))?;
+        Ok(workload)
+    }
+
     // ===== Instance Operations =====
 
     async fn put_instance(&self, instance: WorkloadInstance) -> Result<()> {
         let key = Self::instance_key(&instance.id.to_string());
-        self.store
-            .set_json(&key, &instance)
-            .await
-            .map_err(|e| OrchestrationError::StateError(e.to_string()))?;
-        Ok(())
+        self.set_versioned(&key, instance).await
     }
 
     async fn get_instance(&self, instance_id: &str) -> Result<Option<WorkloadInstance>> {
@@ -239,6 +289,98 @@ impl StateStore for SqliteStateStore {
         }
         Ok(())
     }
+
+    // ===== Namespace Operations =====
+
+    async fn put_namespace(&self, namespace: Namespace) -> Result<()> {
+        let key = Self::namespace_key(&namespace.name);
+        self.set_versioned(&key, namespace).await
+    }
+
+    async fn get_namespace(&self, name: &str) -> Result<Option<Namespace>> {
+        let key = Self::namespace_key(name);
+        self.store
+            .get_json(&key)
+            .await
+            .map_err(|e| OrchestrationError::StateError(e.to_string()))
+    }
+
+    async fn list_namespaces(&self) -> Result<Vec<Namespace>> {
+        let keys = self
+            .store
+            .list("/namespaces/")
+            .await
+            .map_err(|e| OrchestrationError::StateError(e.to_string()))?;
+
+        let mut namespaces = Vec::new();
+        for key in keys {
+            if let Some(namespace) = self
+                .store
+                .get_json::<Namespace>(&key)
+                .await
+                .map_err(|e| OrchestrationError::StateError(e.to_string()))?
+            {
+                namespaces.push(namespace);
+            }
+        }
+        Ok(namespaces)
+    }
+
+    async fn delete_namespace(&self, name: &str) -> Result<()> {
+        let key = Self::namespace_key(name);
+        self.store
+            .delete(&key)
+            .await
+            .map_err(|e| OrchestrationError::StateError(e.to_string()))
+    }
+
+    // ===== Resources =====
+
+    async fn put_resource(&self, kind: &str, name: &str, data: serde_json::Value) -> Result<()> {
+        let key = format!("{}{}", Self::resources_prefix(kind), name);
+        self.store
+            .set_json(&key, &data)
+            .await
+            .map_err(|e| OrchestrationError::StateError(e.to_string()))
+    }
+
+    async fn get_resource(&self, kind: &str, name: &str) -> Result<Option<serde_json::Value>> {
+        let key = format!("{}{}", Self::resources_prefix(kind), name);
+        self.store
+            .get_json(&key)
+            .await
+            .map_err(|e| OrchestrationError::StateError(e.to_string()))
+    }
+
+    async fn list_resources(&self, kind: &str) -> Result<Vec<serde_json::Value>> {
+        let mut keys = self
+            .store
+            .list(&Self::resources_prefix(kind))
+            .await
+            .map_err(|e| OrchestrationError::StateError(e.to_string()))?;
+        keys.sort();
+
+        let mut resources = Vec::new();
+        for key in keys {
+            if let Some(data) = self
+                .store
+                .get_json::<serde_json::Value>(&key)
+                .await
+                .map_err(|e| OrchestrationError::StateError(e.to_string()))?
+            {
+                resources.push(data);
+            }
+        }
+        Ok(resources)
+    }
+
+    async fn delete_resource(&self, kind: &str, name: &str) -> Result<()> {
+        let key = format!("{}{}", Self::resources_prefix(kind), name);
+        self.store
+            .delete(&key)
+            .await
+            .map_err(|e| OrchestrationError::StateError(e.to_string()))
+    }
 }
 
 #[cfg(test)]
@@ -273,6 +415,8 @@ mod tests {
                 memory_mb: 7000,
                 disk_mb: 90000,
             },
+            unschedulable: false,
+            resource_version: 0,
         };
 
         // Put node
@@ -304,6 +448,12 @@ mod tests {
             containers: vec![],
             replicas: 3,
             labels: HashMap::new(),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
         };
 
         store.put_workload(workload.clone()).await.unwrap();
@@ -319,6 +469,47 @@ mod tests {
         assert!(store.get_workload(&workload_id).await.unwrap().is_none());
     }
 
+    #[tokio::test]
+    async fn test_sqlite_compare_and_put_workload_is_atomic() {
+        let store = Arc::new(SqliteStateStore::in_memory().await.unwrap());
+        let workload = WorkloadDefinition {
+            id: Uuid::new_v4(),
+            name: "web".to_string(),
+            containers: vec![],
+            replicas: 1,
+            labels: HashMap::new(),
+            init_containers: vec![],
+            sidecars: vec![],
+            placement: Default::default(),
+            restart_policy: Default::default(),
+            namespace: "default".to_string(),
+            resource_version: 0,
+        };
+        let stored = store.compare_and_put_workload(workload).await.unwrap();
+        assert_eq!(stored.resource_version, 1);
+
+        // Writers racing from the same version: exactly one wins
+        let writers: Vec<_> = (0..8)
+            .map(|replicas| {
+                let store = store.clone();
+                let mut update = stored.clone();
+                update.replicas = replicas;
+                tokio::spawn(async move { store.compare_and_put_workload(update).await })
+            })
+            .collect();
+        let mut won = 0;
+        for writer in writers {
+            match writer.await.unwrap() {
+                Ok(written) => {
+                    assert_eq!(written.resource_version, 2);
+                    won += 1;
+                }
+                Err(e) => assert!(matches!(e, OrchestrationError::Conflict(_))),
+            }
+        }
+        assert_eq!(won, 1);
+    }
+
     #[tokio::test]
     async fn test_sqlite_instance_operations() {
         let store = SqliteStateStore::in_memory().await.unwrap();
@@ -331,6 +522,10 @@ mod tests {
             node_id,
             container_ids: vec!["container-123".to_string()],
             status: WorkloadInstanceStatus::Running,
+            ip_addresses: vec![],
+            restarts: vec![],
+            namespace: "default".to_string(),
+            resource_version: 0,
         };
 
         let instance_id = instance.id.to_string();
@@ -1,22 +1,84 @@
 //! HTTP client for REST API communication with Ed25519 request signing.
+//!
+//! After `orch login`, requests to the same API URL carry the stored bearer
+//! token instead, until it expires. After `orch init --ca-cert`, connections
+//! to that API URL use mutual TLS with the stored client certificate, which
+//! is renewed as it nears expiry.
+//!
+//! With `orch --cluster NAME`, requests go to the federated cluster `NAME`
+//! through the federation proxy of the API server instead.
 
 #![allow(dead_code)]
 
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
 use base64::prelude::*;
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
 use ed25519_dalek::{Signer, SigningKey};
-use reqwest::{Client, Method, RequestBuilder, Response};
-use serde::{de::DeserializeOwned, Serialize};
+use reqwest::{Certificate, Client, Identity, Method, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use user_config::UserConfig;
 
+use crate::context;
 use crate::error::{CliError, Result};
+use crate::watch::WatchStream;
+
+/// Name the API server's own cluster goes by in its federation.
+pub const LOCAL_CLUSTER: &str = "local";
+
+/// The federated cluster requests of this invocation go to, set once at
+/// startup.
+static CLUSTER: OnceLock<String> = OnceLock::new();
+
+/// Send the requests of this invocation to the federated cluster `name`.
+pub fn select_cluster(name: String) {
+    let _ = CLUSTER.set(name);
+}
+
+/// The federated cluster requests go to, unless it is the API server's own.
+pub fn selected_cluster() -> Option<&'static str> {
+    CLUSTER
+        .get()
+        .map(String::as_str)
+        .filter(|name| *name != LOCAL_CLUSTER)
+}
+
+/// Fail while a cluster is selected: the federation proxy does not carry
+/// WebSockets, which `what` needs.
+pub fn require_local_cluster(what: &str) -> Result<()> {
+    match selected_cluster() {
+        Some(cluster) => Err(CliError::invalid_argument(format!(
+            "{} is not proxied to federated clusters; use the API URL of '{}' instead",
+            what, cluster
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// `path` through the federation proxy for `cluster`. Federation requests
+/// themselves go to the API server.
+fn proxy_path<'a>(cluster: Option<&str>, path: &'a str) -> Cow<'a, str> {
+    match cluster {
+        Some(cluster) if path.starts_with("/api/") && !path.starts_with("/api/v1/federation/") => {
+            let cluster: String =
+                url::form_urlencoded::byte_serialize(cluster.as_bytes()).collect();
+            Cow::Owned(format!(
+                "/api/v1/federation/clusters/{}/proxy{}",
+                cluster, path
+            ))
+        }
+        _ => Cow::Borrowed(path),
+    }
+}
 
 /// API client with request signing.
 pub struct ApiClient {
     client: Client,
     base_url: String,
     signing_key: Option<SigningKey>,
+    bearer_token: Option<String>,
 }
 
 impl ApiClient {
@@ -26,22 +88,95 @@ impl ApiClient {
             client: Client::new(),
             base_url: base_url.trim_end_matches('/').to_string(),
             signing_key: None,
+            bearer_token: None,
         }
     }
 
-    /// Create an authenticated API client using user config identity.
+    /// Create an authenticated API client using user config identity, and
+    /// the token of the active context or from `orch login` if one is
+    /// stored for `base_url`.
     pub async fn authenticated(base_url: &str) -> Result<Self> {
         let config = UserConfig::load_or_create().await?;
-        let identity = config.identity();
+        let client = Self::with_identity(base_url, &config).await?;
+        if let Some(token) = context::active_for(&client.base_url).and_then(|c| c.token.clone()) {
+            return Ok(client.with_bearer_token(token));
+        }
+        match StoredToken::load(&config).await {
+            Some(token) if token.is_valid_for(&client.base_url) => {
+                Ok(client.with_bearer_token(token.token))
+            }
+            _ => Ok(client),
+        }
+    }
+
+    /// Create an API client that always signs with the user config identity.
+    pub async fn signed(base_url: &str) -> Result<Self> {
+        let config = UserConfig::load_or_create().await?;
+        Self::with_identity(base_url, &config).await
+    }
 
-        // Get the signing key from identity
-        let signing_key = identity.signing_key();
+    /// Sign with the user config identity, over mutual TLS when a client
+    /// certificate is stored for `base_url`, renewing it if due. Without one,
+    /// the server is verified against the CA of the active context, if set.
+    async fn with_identity(base_url: &str, config: &UserConfig) -> Result<Self> {
+        let client = Self::new(base_url).with_signing_key(config.identity().signing_key());
+        let stored = match StoredCertificate::load(config).await {
+            Some(stored) if stored.is_for(&client.base_url) => stored,
+            _ => return client.with_context_ca(),
+        };
+        let current = Some(&stored).filter(|stored| stored.expires_at > Utc::now());
+        let client = client.with_tls(&stored.ca_certificate, current)?;
+        if !stored.needs_renewal() {
+            return Ok(client);
+        }
+        match StoredCertificate::request(&client).await {
+            Ok(renewed) => {
+                renewed.save(config).await?;
+                client.with_tls(&renewed.ca_certificate, Some(&renewed))
+            }
+            Err(e) => {
+                eprintln!("Warning: failed to renew client certificate: {}", e);
+                Ok(client)
+            }
+        }
+    }
 
-        Ok(Self {
-            client: Client::new(),
-            base_url: base_url.trim_end_matches('/').to_string(),
-            signing_key: Some(signing_key),
-        })
+    /// Verify the API server against `ca_pem` instead of public roots and,
+    /// given a stored certificate, present it as the client certificate.
+    pub fn with_tls(
+        mut self,
+        ca_pem: &str,
+        certificate: Option<&StoredCertificate>,
+    ) -> Result<Self> {
+        let mut builder = Client::builder()
+            .use_rustls_tls()
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(Certificate::from_pem(ca_pem.as_bytes())?);
+        if let Some(stored) = certificate {
+            let pem = format!("{}\n{}", stored.certificate, stored.key);
+            builder = builder.identity(Identity::from_pem(pem.as_bytes())?);
+        }
+        self.client = builder.build()?;
+        Ok(self)
+    }
+
+    /// Verify the server against the CA certificate of the active context,
+    /// if it sets one for this server.
+    fn with_context_ca(self) -> Result<Self> {
+        let Some(path) =
+            context::active_for(&self.base_url).and_then(|c| c.ca_certificate.as_ref())
+        else {
+            return Ok(self);
+        };
+        let ca_pem = std::fs::read_to_string(path).map_err(|e| {
+            CliError::config_error(format!("Cannot read {}: {}", path.display(), e))
+        })?;
+        self.with_tls(&ca_pem, None)
+    }
+
+    /// The API URL requests are sent to, without a trailing slash.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
     }
 
     /// Set the signing key directly (for testing).
@@ -50,9 +185,17 @@ impl ApiClient {
         self
     }
 
-    /// Sign a request and add authentication headers.
+    /// Send a bearer token instead of signing requests.
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// Sign a request and add authentication headers. The server verifies
+    /// the path without its query string, so that is what gets signed.
     fn sign_request(&self, method: &str, path: &str, body: &[u8]) -> Option<SignedHeaders> {
         let signing_key = self.signing_key.as_ref()?;
+        let path = path.split('?').next().unwrap_or(path);
 
         let timestamp = Utc::now().to_rfc3339();
         let body_hash = hex::encode(Sha256::digest(body));
@@ -70,7 +213,9 @@ impl ApiClient {
 
     /// Apply authentication headers to a request builder.
     fn apply_auth(&self, builder: RequestBuilder, method: &str, path: &str, body: &[u8]) -> RequestBuilder {
-        if let Some(headers) = self.sign_request(method, path, body) {
+        if let Some(token) = &self.bearer_token {
+            builder.bearer_auth(token)
+        } else if let Some(headers) = self.sign_request(method, &self.route(path), body) {
             builder
                 .header("X-Auth-PublicKey", headers.public_key)
                 .header("X-Auth-Timestamp", headers.timestamp)
@@ -80,9 +225,32 @@ impl ApiClient {
         }
     }
 
+    /// Authentication headers for a request, for connections made without
+    /// `reqwest` such as WebSockets. These are not routed to a selected
+    /// cluster.
+    pub fn auth_headers(&self, method: &str, path: &str) -> Vec<(&'static str, String)> {
+        if let Some(token) = &self.bearer_token {
+            vec![("Authorization", format!("Bearer {}", token))]
+        } else if let Some(headers) = self.sign_request(method, path, &[]) {
+            vec![
+                ("X-Auth-PublicKey", headers.public_key),
+                ("X-Auth-Timestamp", headers.timestamp),
+                ("X-Auth-Signature", headers.signature),
+            ]
+        } else {
+            Vec::new()
+        }
+    }
+
     /// Build the full URL for a path.
     fn url(&self, path: &str) -> String {
-        format!("{}{}", self.base_url, path)
+        format!("{}{}", self.base_url, self.route(path))
+    }
+
+    /// The path requested for `path`, through the federation proxy when a
+    /// cluster is selected.
+    fn route<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        proxy_path(selected_cluster(), path)
     }
 
     /// Perform a GET request.
@@ -119,6 +287,31 @@ impl ApiClient {
         self.handle_response(response).await
     }
 
+    /// Perform a PATCH request with JSON body.
+    pub async fn patch<T: DeserializeOwned, B: Serialize>(&self, path: &str, body: &B) -> Result<T> {
+        let url = self.url(path);
+        let body_bytes = serde_json::to_vec(body)?;
+        let builder = self.client.patch(&url).body(body_bytes.clone());
+        let builder = self.apply_auth(builder, "PATCH", path, &body_bytes);
+        let builder = builder.header("Content-Type", "application/json");
+
+        let response = builder.send().await?;
+        self.handle_response(response).await
+    }
+
+    /// Perform a PATCH request with JSON body, for endpoints that respond
+    /// without one.
+    pub async fn patch_empty<B: Serialize>(&self, path: &str, body: &B) -> Result<()> {
+        let url = self.url(path);
+        let body_bytes = serde_json::to_vec(body)?;
+        let builder = self.client.patch(&url).body(body_bytes.clone());
+        let builder = self.apply_auth(builder, "PATCH", path, &body_bytes);
+        let builder = builder.header("Content-Type", "application/json");
+
+        let response = builder.send().await?;
+        self.handle_empty_response(response).await
+    }
+
     /// Perform a DELETE request.
     pub async fn delete(&self, path: &str) -> Result<()> {
         let url = self.url(path);
@@ -129,6 +322,34 @@ impl ApiClient {
         self.handle_empty_response(response).await
     }
 
+    /// Perform a GET request for a binary body, such as an archive.
+    pub async fn get_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        let url = self.url(path);
+        let builder = self.client.get(&url);
+        let builder = self.apply_auth(builder, "GET", path, &[]);
+
+        let response = builder.send().await?;
+        report_warnings(&response);
+        let status = response.status();
+
+        if status.is_success() {
+            Ok(response.bytes().await?.to_vec())
+        } else {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            Err(status_error(status, error_text))
+        }
+    }
+
+    /// Perform a PUT request with a binary body of `content_type`.
+    pub async fn put_bytes(&self, path: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+        let url = self.url(path);
+        let builder = self.apply_auth(self.client.put(&url), "PUT", path, &body);
+        let builder = builder.header("Content-Type", content_type).body(body);
+
+        let response = builder.send().await?;
+        self.handle_empty_response(response).await
+    }
+
     /// Perform a raw request and return the response.
     pub async fn request(&self, method: Method, path: &str) -> Result<Response> {
         let url = self.url(path);
@@ -139,37 +360,80 @@ impl ApiClient {
         Ok(builder.send().await?)
     }
 
+    /// Watch a list endpoint: its current objects, then changes as they
+    /// happen.
+    pub async fn watch(&self, path: &str) -> Result<WatchStream> {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let path = format!("{}{}watch=true", path, separator);
+        let response = self.request(Method::GET, &path).await?;
+        report_warnings(&response);
+        let status = response.status();
+
+        if status.is_success() {
+            Ok(WatchStream::new(response))
+        } else {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            Err(status_error(status, error_text))
+        }
+    }
+
     /// Handle a JSON response.
     async fn handle_response<T: DeserializeOwned>(&self, response: Response) -> Result<T> {
+        report_warnings(&response);
         let status = response.status();
 
         if status.is_success() {
             Ok(response.json().await?)
         } else {
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            Err(CliError::api_error(format!(
-                "Request failed with status {}: {}",
-                status, error_text
-            )))
+            Err(status_error(status, error_text))
         }
     }
 
     /// Handle an empty response (for DELETE, etc.).
     async fn handle_empty_response(&self, response: Response) -> Result<()> {
+        report_warnings(&response);
         let status = response.status();
 
         if status.is_success() {
             Ok(())
         } else {
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            Err(CliError::api_error(format!(
-                "Request failed with status {}: {}",
-                status, error_text
-            )))
+            Err(status_error(status, error_text))
         }
     }
 }
 
+/// The error for a failed request; 409 becomes [`CliError::Conflict`] so
+/// callers can re-read and retry.
+fn status_error(status: StatusCode, error_text: String) -> CliError {
+    if status == StatusCode::CONFLICT {
+        return CliError::Conflict(error_text);
+    }
+    CliError::api_error(format!(
+        "Request failed with status {}: {}",
+        status, error_text
+    ))
+}
+
+/// Print `Warning` headers, such as deprecation notices, to stderr so they
+/// never mix with command output.
+fn report_warnings(response: &Response) {
+    for value in response.headers().get_all(reqwest::header::WARNING) {
+        if let Ok(value) = value.to_str() {
+            eprintln!("Warning: {}", warning_text(value));
+        }
+    }
+}
+
+/// The quoted text of a `Warning` header value (`299 - "text"`).
+fn warning_text(value: &str) -> &str {
+    match (value.find('"'), value.rfind('"')) {
+        (Some(start), Some(end)) if start < end => &value[start + 1..end],
+        _ => value,
+    }
+}
+
 /// Signed request headers.
 struct SignedHeaders {
     public_key: String,
@@ -177,6 +441,143 @@ struct SignedHeaders {
     signature: String,
 }
 
+/// Bearer token saved by `orch login`, encrypted in the user's secret store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredToken {
+    /// API URL the token was issued for.
+    pub api_url: String,
+    pub token: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl StoredToken {
+    /// Name of the secret holding the token.
+    const SECRET_NAME: &'static str = "api_token";
+
+    /// Tokens this close to expiry are no longer sent.
+    const EXPIRY_MARGIN_SECS: i64 = 30;
+
+    /// The stored token, if any.
+    pub async fn load(config: &UserConfig) -> Option<Self> {
+        if !config.secret_exists(Self::SECRET_NAME) {
+            return None;
+        }
+        let bytes = config.get_secret(Self::SECRET_NAME).await.ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Store the token, replacing any other.
+    pub async fn save(&self, config: &UserConfig) -> Result<()> {
+        config
+            .store_secret(Self::SECRET_NAME, &serde_json::to_vec(self)?)
+            .await?;
+        Ok(())
+    }
+
+    /// Remove the stored token; returns whether there was one.
+    pub async fn delete(config: &UserConfig) -> Result<bool> {
+        if !config.secret_exists(Self::SECRET_NAME) {
+            return Ok(false);
+        }
+        config.delete_secret(Self::SECRET_NAME).await?;
+        Ok(true)
+    }
+
+    /// Whether the token may be sent to `api_url` now.
+    pub fn is_valid_for(&self, api_url: &str) -> bool {
+        self.api_url.trim_end_matches('/') == api_url.trim_end_matches('/')
+            && self.expires_at.is_none_or(|expires_at| {
+                expires_at - Duration::seconds(Self::EXPIRY_MARGIN_SECS) > Utc::now()
+            })
+    }
+}
+
+/// Client certificate from `orch init --ca-cert`, encrypted in the user's
+/// secret store with its private key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCertificate {
+    /// API URL the certificate was issued by.
+    pub api_url: String,
+    /// PEM certificate of the cluster CA the API server is verified against.
+    pub ca_certificate: String,
+    pub certificate: String,
+    pub key: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Client certificate returned by the API.
+#[derive(Debug, Deserialize)]
+struct IssuedCertificate {
+    certificate: String,
+    ca_certificate: String,
+    expires_at: DateTime<Utc>,
+}
+
+impl StoredCertificate {
+    /// Name of the secret holding the certificate.
+    const SECRET_NAME: &'static str = "api_certificate";
+
+    /// Certificates are renewed once fewer days than this remain.
+    const RENEW_BEFORE_DAYS: i64 = 10;
+
+    /// The stored certificate, if any.
+    pub async fn load(config: &UserConfig) -> Option<Self> {
+        if !config.secret_exists(Self::SECRET_NAME) {
+            return None;
+        }
+        let bytes = config.get_secret(Self::SECRET_NAME).await.ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Store the certificate, replacing any other.
+    pub async fn save(&self, config: &UserConfig) -> Result<()> {
+        config
+            .store_secret(Self::SECRET_NAME, &serde_json::to_vec(self)?)
+            .await?;
+        Ok(())
+    }
+
+    /// Request a certificate for a fresh key pair from the API `client` talks to.
+    pub async fn request(client: &ApiClient) -> Result<Self> {
+        let key = rcgen::KeyPair::generate()
+            .map_err(|e| CliError::config_error(format!("Cannot generate key: {}", e)))?;
+        let csr = rcgen::CertificateParams::default()
+            .serialize_request(&key)
+            .and_then(|request| request.pem())
+            .map_err(|e| {
+                CliError::config_error(format!("Cannot create certificate request: {}", e))
+            })?;
+        let issued: IssuedCertificate = client
+            .post("/api/v1/auth/certificate", &serde_json::json!({ "csr": csr }))
+            .await?;
+        Ok(Self {
+            api_url: client.base_url.clone(),
+            ca_certificate: issued.ca_certificate,
+            certificate: issued.certificate,
+            key: key.serialize_pem(),
+            expires_at: issued.expires_at,
+        })
+    }
+
+    /// Whether the certificate was issued for `api_url`.
+    pub fn is_for(&self, api_url: &str) -> bool {
+        self.api_url.trim_end_matches('/') == api_url.trim_end_matches('/')
+    }
+
+    /// Whether the certificate is close enough to expiry to renew.
+    pub fn needs_renewal(&self) -> bool {
+        self.expires_at - Duration::days(Self::RENEW_BEFORE_DAYS) < Utc::now()
+    }
+}
+
+/// The `exp` claim of a JWT, read without verifying it.
+pub fn token_expiry(token: &str) -> Option<DateTime<Utc>> {
+    let payload = token.split('.').nth(1)?;
+    let claims: serde_json::Value =
+        serde_json::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    DateTime::from_timestamp(claims.get("exp")?.as_i64()?, 0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,6 +598,38 @@ mod tests {
         assert!(!headers.signature.is_empty());
     }
 
+    #[test]
+    fn test_auth_headers() {
+        let client = ApiClient::new("http://localhost:9090");
+        assert!(client.auth_headers("GET", "/api/v1/instances").is_empty());
+
+        let client = client.with_signing_key(SigningKey::generate(&mut OsRng));
+        let names: Vec<_> = client
+            .auth_headers("GET", "/api/v1/instances")
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["X-Auth-PublicKey", "X-Auth-Timestamp", "X-Auth-Signature"]);
+
+        let client = client.with_bearer_token("abc");
+        let headers = client.auth_headers("GET", "/api/v1/instances");
+        assert_eq!(headers, [("Authorization", "Bearer abc".to_string())]);
+    }
+
+    #[test]
+    fn test_proxy_path() {
+        assert_eq!(proxy_path(None, "/api/v1/workloads"), "/api/v1/workloads");
+        assert_eq!(
+            proxy_path(Some("prod-eu"), "/api/v1/workloads?namespace=ml"),
+            "/api/v1/federation/clusters/prod-eu/proxy/api/v1/workloads?namespace=ml"
+        );
+        assert_eq!(
+            proxy_path(Some("prod-eu"), "/api/v1/federation/clusters"),
+            "/api/v1/federation/clusters"
+        );
+        assert_eq!(proxy_path(Some("prod-eu"), "/health"), "/health");
+    }
+
     #[test]
     fn test_url_construction() {
         let client = ApiClient::new("http://localhost:9090/");
@@ -205,4 +638,53 @@ mod tests {
         let client = ApiClient::new("http://localhost:9090");
         assert_eq!(client.url("/api/v1/nodes"), "http://localhost:9090/api/v1/nodes");
     }
+
+    #[test]
+    fn test_stored_token_validity() {
+        let token = StoredToken {
+            api_url: "http://localhost:9090/".to_string(),
+            token: "t".to_string(),
+            expires_at: Some(Utc::now() + Duration::minutes(10)),
+        };
+        assert!(token.is_valid_for("http://localhost:9090"));
+        assert!(!token.is_valid_for("http://prod:9090"));
+
+        let expiring = StoredToken {
+            expires_at: Some(Utc::now() + Duration::seconds(5)),
+            ..token
+        };
+        assert!(!expiring.is_valid_for("http://localhost:9090"));
+    }
+
+    #[test]
+    fn test_stored_certificate_renewal() {
+        let certificate = |days: i64| StoredCertificate {
+            api_url: "https://localhost:9090".to_string(),
+            ca_certificate: String::new(),
+            certificate: String::new(),
+            key: String::new(),
+            expires_at: Utc::now() + Duration::days(days),
+        };
+        assert!(certificate(30).is_for("https://localhost:9090/"));
+        assert!(!certificate(30).is_for("http://localhost:9090"));
+        assert!(!certificate(30).needs_renewal());
+        assert!(certificate(3).needs_renewal());
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        let payload = BASE64_URL_SAFE_NO_PAD.encode(r#"{"sub":"alex","exp":1900000000}"#);
+        let token = format!("eyJhbGciOiJSUzI1NiJ9.{}.c2ln", payload);
+        assert_eq!(token_expiry(&token).unwrap().timestamp(), 1_900_000_000);
+        assert_eq!(token_expiry("not-a-jwt"), None);
+    }
+
+    #[test]
+    fn test_warning_text() {
+        assert_eq!(
+            warning_text("299 - \"API version v1beta1 is deprecated; use /api/v1\""),
+            "API version v1beta1 is deprecated; use /api/v1"
+        );
+        assert_eq!(warning_text("unquoted"), "unquoted");
+    }
 }
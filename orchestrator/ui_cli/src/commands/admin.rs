@@ -0,0 +1,109 @@
+//! Admin command - cluster maintenance operations.
+
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::error::CliError;
+use crate::output::{self, print_data};
+use crate::OutputFormat;
+
+/// Arguments for the admin command.
+#[derive(Args)]
+pub struct AdminArgs {
+    #[command(subcommand)]
+    command: AdminCommand,
+}
+
+#[derive(Subcommand)]
+enum AdminCommand {
+    /// Cross-check stored state against the container runtime and cluster
+    Fsck {
+        /// Repair the discrepancies found
+        #[arg(long)]
+        repair: bool,
+    },
+}
+
+/// Consistency check response from API.
+#[derive(Debug, Deserialize)]
+struct FsckResponse {
+    repaired: usize,
+    discrepancies: Vec<DiscrepancyResponse>,
+    #[serde(default)]
+    skipped: Vec<String>,
+}
+
+/// One discrepancy in a consistency check response.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct DiscrepancyResponse {
+    #[tabled(rename = "Kind")]
+    kind: String,
+    #[tabled(rename = "Object")]
+    object: String,
+    #[tabled(rename = "Node", display_with = "display_node")]
+    #[serde(default)]
+    node_id: Option<String>,
+    #[tabled(rename = "Detail")]
+    detail: String,
+    #[tabled(rename = "Repaired")]
+    repaired: bool,
+}
+
+fn display_node(node_id: &Option<String>) -> String {
+    node_id.clone().unwrap_or_else(|| "-".to_string())
+}
+
+/// Execute the admin command.
+pub async fn execute(args: AdminArgs, api_url: &str, format: OutputFormat) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for admin operations. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        AdminCommand::Fsck { repair } => fsck(&client, repair, format).await,
+    }
+}
+
+async fn fsck(client: &ApiClient, repair: bool, format: OutputFormat) -> anyhow::Result<()> {
+    let report: FsckResponse = if repair {
+        client.post("/api/v1/admin/fsck", &()).await?
+    } else {
+        client.get("/api/v1/admin/fsck").await?
+    };
+
+    for skipped in &report.skipped {
+        output::warn(&format!("Skipped {}", skipped));
+    }
+    if report.discrepancies.is_empty() {
+        output::success("State is consistent");
+        return Ok(());
+    }
+
+    print_data(&report.discrepancies, format)?;
+    let unrepaired = report.discrepancies.len() - report.repaired;
+    if repair {
+        output::success(&format!(
+            "Repaired {} of {} discrepancies",
+            report.repaired,
+            report.discrepancies.len()
+        ));
+    }
+    if unrepaired > 0 {
+        let hint = if repair {
+            "see the details above"
+        } else {
+            "rerun with --repair to fix them"
+        };
+        return Err(CliError::Other(format!(
+            "{} discrepancies left unrepaired; {}",
+            unrepaired, hint
+        ))
+        .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,693 @@
+//! Apply command - create or update objects from YAML manifests.
+//!
+//! A manifest holds one or more YAML documents, each an object with a `kind`
+//! and the fields the API takes to create it:
+//!
+//! ```yaml
+//! kind: Namespace
+//! name: shop
+//! ---
+//! kind: Workload
+//! name: web
+//! namespace: shop
+//! replicas: 2
+//! containers:
+//!   - name: web
+//!     image: nginx:1.27
+//! ```
+//!
+//! An `InferenceService` is a workload whose containers serve a model. Its
+//! `model` names the artifact nodes download, verify and mount read-only
+//! into each container before it starts:
+//!
+//! ```yaml
+//! kind: InferenceService
+//! name: llm
+//! namespace: shop
+//! model:
+//!   uri: hf://meta-llama/Llama-3.1-8B-Instruct  # or s3://bucket/key, oci://registry/repo:tag
+//!   digest: sha256:...                          # optional
+//!   mountPath: /models                          # default
+//! containers:
+//!   - name: server
+//!     image: vllm/vllm-openai:v0.6.3
+//!     args: ["--model", "/models"]
+//! ```
+//!
+//! It is applied as a workload whose containers carry the model in their
+//! `model_uri`, `model_digest` and `model_mount_path` annotations.
+//!
+//! Namespaces are created when missing. Workloads are created when missing
+//! and otherwise updated, after showing how the live workload would change as
+//! computed by the server (`?dryRun=true`). Workloads applied this way carry
+//! the [`APPLIED_LABEL`] label, which `--prune` uses to find the ones that
+//! were removed from the manifests.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use clap::Args;
+use colored::Colorize;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::client::ApiClient;
+use crate::error::{CliError, Result};
+use crate::output;
+
+/// Label marking workloads created or updated by `orch apply`.
+pub const APPLIED_LABEL: &str = "orch.apply/managed";
+
+const DEFAULT_NAMESPACE: &str = "default";
+
+/// Arguments for the apply command.
+#[derive(Args)]
+pub struct ApplyArgs {
+    /// Manifest file, or directory of .yaml, .yml and .json manifests (can be
+    /// specified multiple times)
+    #[arg(short = 'f', long = "filename", required = true)]
+    filename: Vec<PathBuf>,
+
+    /// Namespace of workloads whose manifest names none (default: "default")
+    #[arg(short, long)]
+    namespace: Option<String>,
+
+    /// Delete applied workloads that are no longer in the manifests, in the
+    /// namespaces the manifests use
+    #[arg(long)]
+    prune: bool,
+
+    /// Show what would change without changing anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Kinds of object that can be applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    Namespace,
+    Workload,
+}
+
+/// One object of a manifest.
+#[derive(Debug, Clone, PartialEq)]
+struct Manifest {
+    kind: Kind,
+    name: String,
+    /// Namespace of a workload; the name of a namespace.
+    namespace: String,
+    /// The object as sent to the API, without `kind`.
+    body: Map<String, Value>,
+    /// File and document the object came from, for messages.
+    source: String,
+}
+
+impl Manifest {
+    fn display_name(&self) -> String {
+        match self.kind {
+            Kind::Namespace => format!("namespace/{}", self.name),
+            Kind::Workload => format!("workload/{}/{}", self.namespace, self.name),
+        }
+    }
+}
+
+/// List response wrapper.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+/// Parse the YAML documents of `text`, read from `source`.
+fn parse_manifests(text: &str, source: &str, default_namespace: &str) -> Result<Vec<Manifest>> {
+    let mut manifests = Vec::new();
+    for (index, document) in serde_yaml_ng::Deserializer::from_str(text).enumerate() {
+        let location = format!("{} (document {})", source, index + 1);
+        let value = Value::deserialize(document)
+            .map_err(|e| CliError::invalid_argument(format!("{}: {}", location, e)))?;
+        let mut body = match value {
+            // Empty documents, e.g. after a trailing `---`
+            Value::Null => continue,
+            Value::Object(body) => body,
+            _ => {
+                return Err(CliError::invalid_argument(format!(
+                    "{}: expected a mapping",
+                    location
+                )))
+            }
+        };
+
+        let kind = match body.remove("kind") {
+            Some(Value::String(kind)) => kind,
+            _ => {
+                return Err(CliError::invalid_argument(format!(
+                    "{}: missing 'kind'",
+                    location
+                )))
+            }
+        };
+        let name = body
+            .get("name")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| CliError::invalid_argument(format!("{}: missing 'name'", location)))?;
+
+        let (kind, namespace) = match kind.as_str() {
+            "Namespace" => (Kind::Namespace, name.clone()),
+            "Workload" | "InferenceService" => {
+                if kind == "InferenceService" {
+                    annotate_model(&mut body, &location)?;
+                }
+                let namespace = body
+                    .get("namespace")
+                    .and_then(Value::as_str)
+                    .unwrap_or(default_namespace)
+                    .to_string();
+                body.insert("namespace".to_string(), Value::String(namespace.clone()));
+                (Kind::Workload, namespace)
+            }
+            "Service" | "Secret" => {
+                return Err(CliError::invalid_argument(format!(
+                    "{}: {} objects cannot be applied; the API has no endpoint to create them",
+                    location, kind
+                )))
+            }
+            other => {
+                return Err(CliError::invalid_argument(format!(
+                    "{}: unknown kind '{}' (expected Namespace, Workload or InferenceService)",
+                    location, other
+                )))
+            }
+        };
+
+        manifests.push(Manifest {
+            kind,
+            name,
+            namespace,
+            body,
+            source: location,
+        });
+    }
+    Ok(manifests)
+}
+
+/// Move the `model` of an InferenceService into the annotations of each of
+/// its containers, where nodes look for it.
+fn annotate_model(body: &mut Map<String, Value>, location: &str) -> Result<()> {
+    let invalid = |message: &str| CliError::invalid_argument(format!("{}: {}", location, message));
+    let model = match body.remove("model") {
+        Some(Value::Object(model)) => model,
+        _ => return Err(invalid("an InferenceService needs a 'model' mapping")),
+    };
+
+    let mut annotations = Map::new();
+    for (field, annotation) in [
+        ("uri", "model_uri"),
+        ("digest", "model_digest"),
+        ("mountPath", "model_mount_path"),
+    ] {
+        match model.get(field) {
+            Some(Value::String(value)) => {
+                annotations.insert(annotation.to_string(), Value::String(value.clone()));
+            }
+            None if field != "uri" => {}
+            _ => return Err(invalid(&format!("'model.{}' must be a string", field))),
+        }
+    }
+
+    let containers = body
+        .get_mut("containers")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| invalid("an InferenceService needs 'containers'"))?;
+    for container in containers.iter_mut().filter_map(Value::as_object_mut) {
+        let existing = container
+            .entry("annotations")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(existing) = existing.as_object_mut() {
+            existing.extend(annotations.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Manifest files named by `paths`: files as given, and the .yaml, .yml and
+/// .json files of directories in name order.
+fn manifest_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| matches!(ext, "yaml" | "yml" | "json"))
+            })
+            .collect();
+        entries.sort();
+        files.extend(entries);
+    }
+    Ok(files)
+}
+
+/// Read every manifest under `paths`.
+fn load_manifests(paths: &[PathBuf], default_namespace: &str) -> Result<Vec<Manifest>> {
+    let mut manifests = Vec::new();
+    for file in manifest_files(paths)? {
+        let text = std::fs::read_to_string(&file).map_err(|e| {
+            CliError::invalid_argument(format!("Cannot read {}: {}", file.display(), e))
+        })?;
+        manifests.extend(parse_manifests(
+            &text,
+            &file.display().to_string(),
+            default_namespace,
+        )?);
+    }
+    order_manifests(manifests)
+}
+
+/// Reject objects given twice and put namespaces first.
+fn order_manifests(mut manifests: Vec<Manifest>) -> Result<Vec<Manifest>> {
+    let mut seen = BTreeSet::new();
+    for manifest in &manifests {
+        if !seen.insert(manifest.display_name()) {
+            return Err(CliError::invalid_argument(format!(
+                "{}: {} is defined more than once",
+                manifest.source,
+                manifest.display_name()
+            )));
+        }
+    }
+    // Workloads need their namespace to exist
+    manifests.sort_by_key(|manifest| manifest.kind);
+    Ok(manifests)
+}
+
+/// A difference between a live object and its applied version.
+#[derive(Debug, Clone, PartialEq)]
+enum Change {
+    Added(String, Value),
+    Removed(String, Value),
+    Changed(String, Value, Value),
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Added(path, new) => write!(f, "+ {}: {}", path, new),
+            Change::Removed(path, old) => write!(f, "- {}: {}", path, old),
+            Change::Changed(path, old, new) => write!(f, "~ {}: {} -> {}", path, old, new),
+        }
+    }
+}
+
+/// Fields that change on every write and are left out of diffs.
+const IGNORED_FIELDS: &[&str] = &["resource_version"];
+
+/// Changes turning `live` into `desired`, by field path.
+fn diff(live: &Value, desired: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_into(&mut changes, "", live, desired);
+    changes
+}
+
+fn diff_into(changes: &mut Vec<Change>, path: &str, live: &Value, desired: &Value) {
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match (live, desired) {
+        (Value::Object(live), Value::Object(desired)) => {
+            let keys: BTreeSet<&String> = live.keys().chain(desired.keys()).collect();
+            for key in keys {
+                if path.is_empty() && IGNORED_FIELDS.contains(&key.as_str()) {
+                    continue;
+                }
+                match (live.get(key), desired.get(key)) {
+                    (Some(old), Some(new)) => diff_into(changes, &child(key), old, new),
+                    (Some(old), None) => changes.push(Change::Removed(child(key), old.clone())),
+                    (None, Some(new)) => changes.push(Change::Added(child(key), new.clone())),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(live), Value::Array(desired)) if live.len() == desired.len() => {
+            for (index, (old, new)) in live.iter().zip(desired).enumerate() {
+                diff_into(changes, &format!("{}[{}]", path, index), old, new);
+            }
+        }
+        _ if live != desired => changes.push(Change::Changed(
+            path.to_string(),
+            live.clone(),
+            desired.clone(),
+        )),
+        _ => {}
+    }
+}
+
+/// Execute the apply command.
+pub async fn execute(args: ApplyArgs, api_url: &str) -> anyhow::Result<()> {
+    let default_namespace = args.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE);
+    let manifests = load_manifests(&args.filename, default_namespace)?;
+    apply(manifests, api_url, args.prune, args.dry_run).await
+}
+
+/// Apply the manifests of `text`, e.g. rendered from a template, as `orch
+/// apply` applies a file.
+pub async fn apply_text(
+    text: &str,
+    source: &str,
+    namespace: Option<&str>,
+    api_url: &str,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let manifests = parse_manifests(text, source, namespace.unwrap_or(DEFAULT_NAMESPACE))?;
+    apply(order_manifests(manifests)?, api_url, false, dry_run).await
+}
+
+/// Create or update `manifests`, and with `prune` delete the applied workloads
+/// they no longer hold.
+async fn apply(
+    manifests: Vec<Manifest>,
+    api_url: &str,
+    prune: bool,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    if manifests.is_empty() {
+        return Err(CliError::invalid_argument("No objects found in the manifests").into());
+    }
+
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for apply. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    let namespaces: ListResponse<Value> = client.get("/api/v1/namespaces").await?;
+    let namespaces: HashMap<String, Value> = namespaces
+        .items
+        .into_iter()
+        .filter_map(|ns| Some((ns.get("name")?.as_str()?.to_string(), ns)))
+        .collect();
+
+    // Live workloads of every namespace workloads are applied to
+    let workload_namespaces: BTreeSet<&str> = manifests
+        .iter()
+        .filter(|manifest| manifest.kind == Kind::Workload)
+        .map(|manifest| manifest.namespace.as_str())
+        .collect();
+    let mut live_workloads: HashMap<(String, String), Value> = HashMap::new();
+    for namespace in &workload_namespaces {
+        if !namespaces.contains_key(*namespace) {
+            continue;
+        }
+        let listed: ListResponse<Value> = client
+            .get(&format!("/api/v1/workloads?namespace={}", namespace))
+            .await?;
+        for workload in listed.items {
+            if let Some(name) = workload.get("name").and_then(Value::as_str) {
+                live_workloads.insert((namespace.to_string(), name.to_string()), workload);
+            }
+        }
+    }
+
+    for manifest in &manifests {
+        match manifest.kind {
+            Kind::Namespace => {
+                apply_namespace(&client, manifest, namespaces.get(&manifest.name), dry_run).await?
+            }
+            Kind::Workload => {
+                let key = (manifest.namespace.clone(), manifest.name.clone());
+                apply_workload(&client, manifest, live_workloads.get(&key), dry_run).await?
+            }
+        }
+    }
+
+    if prune {
+        let applied: BTreeSet<(String, String)> = manifests
+            .iter()
+            .filter(|manifest| manifest.kind == Kind::Workload)
+            .map(|manifest| (manifest.namespace.clone(), manifest.name.clone()))
+            .collect();
+        let mut stale: Vec<(&(String, String), &Value)> = live_workloads
+            .iter()
+            .filter(|(key, workload)| {
+                !applied.contains(*key)
+                    && workload
+                        .pointer(&format!("/labels/{}", APPLIED_LABEL.replace('/', "~1")))
+                        .and_then(Value::as_str)
+                        == Some("true")
+            })
+            .collect();
+        stale.sort_by(|a, b| a.0.cmp(b.0));
+        for ((namespace, name), workload) in stale {
+            let id = workload
+                .get("id")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if dry_run {
+                output::info(&format!("workload/{}/{} would be pruned", namespace, name));
+            } else {
+                client.delete(&format!("/api/v1/workloads/{}", id)).await?;
+                output::success(&format!("workload/{}/{} pruned", namespace, name));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Create a namespace unless it exists. Namespaces cannot be updated, so
+/// differing labels are only reported.
+async fn apply_namespace(
+    client: &ApiClient,
+    manifest: &Manifest,
+    live: Option<&Value>,
+    dry_run: bool,
+) -> Result<()> {
+    let name = manifest.display_name();
+    match live {
+        Some(live) => {
+            let labels = |object: &Value| object.get("labels").cloned().unwrap_or_default();
+            let desired = labels(&Value::Object(manifest.body.clone()));
+            if desired
+                .as_object()
+                .is_some_and(|desired| !desired.is_empty())
+                && desired != labels(live)
+            {
+                output::warn(&format!(
+                    "{} exists with other labels; namespaces cannot be updated",
+                    name
+                ));
+            } else {
+                output::info(&format!("{} unchanged", name));
+            }
+        }
+        None if dry_run => output::info(&format!("{} would be created", name)),
+        None => {
+            let _: Value = client.post("/api/v1/namespaces", &manifest.body).await?;
+            output::success(&format!("{} created", name));
+        }
+    }
+    Ok(())
+}
+
+/// Create or update a workload, showing how an update changes it.
+async fn apply_workload(
+    client: &ApiClient,
+    manifest: &Manifest,
+    live: Option<&Value>,
+    dry_run: bool,
+) -> Result<()> {
+    let name = manifest.display_name();
+    let mut body = manifest.body.clone();
+    let labels = body
+        .entry("labels")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Value::Object(labels) = labels {
+        labels.insert(APPLIED_LABEL.to_string(), Value::String("true".to_string()));
+    }
+
+    let Some(live) = live else {
+        // The server validates the workload either way
+        let _: Value = client.post("/api/v1/workloads?dryRun=true", &body).await?;
+        if dry_run {
+            output::info(&format!("{} would be created", name));
+        } else {
+            let _: Value = client.post("/api/v1/workloads", &body).await?;
+            output::success(&format!("{} created", name));
+        }
+        return Ok(());
+    };
+
+    let id = live.get("id").and_then(Value::as_str).unwrap_or_default();
+    if let Some(version) = live.get("resource_version") {
+        body.insert("resource_version".to_string(), version.clone());
+    }
+    let path = format!("/api/v1/workloads/{}", id);
+    let desired: Value = client.put(&format!("{}?dryRun=true", path), &body).await?;
+    let changes = diff(live, &desired);
+    if changes.is_empty() {
+        output::info(&format!("{} unchanged", name));
+        return Ok(());
+    }
+
+    if dry_run {
+        output::info(&format!("{} would be configured:", name));
+    } else {
+        let _: Value = client.put(&path, &body).await?;
+        output::success(&format!("{} configured:", name));
+    }
+    for change in changes {
+        let line = change.to_string();
+        let line = match change {
+            Change::Added(..) => line.green(),
+            Change::Removed(..) => line.red(),
+            Change::Changed(..) => line.yellow(),
+        };
+        println!("    {}", line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_manifests() {
+        let text = r#"
+kind: Namespace
+name: shop
+---
+kind: Workload
+name: web
+replicas: 2
+containers:
+  - name: web
+    image: nginx:1.27
+---
+"#;
+        let manifests = parse_manifests(text, "shop.yaml", "staging").unwrap();
+        assert_eq!(manifests.len(), 2);
+        assert_eq!(manifests[0].kind, Kind::Namespace);
+        assert_eq!(manifests[0].display_name(), "namespace/shop");
+        assert!(!manifests[0].body.contains_key("kind"));
+
+        // Workloads without a namespace go to the default one
+        assert_eq!(manifests[1].display_name(), "workload/staging/web");
+        assert_eq!(manifests[1].body["namespace"], "staging");
+        assert_eq!(manifests[1].source, "shop.yaml (document 2)");
+
+        let err = parse_manifests("kind: Secret\nname: db\n", "s.yaml", "default").unwrap_err();
+        assert!(err.to_string().contains("no endpoint"));
+        assert!(parse_manifests("name: web\n", "w.yaml", "default").is_err());
+        assert!(parse_manifests("- a\n- b\n", "l.yaml", "default").is_err());
+    }
+
+    #[test]
+    fn test_parse_inference_service() {
+        let text = r#"
+kind: InferenceService
+name: llm
+model:
+  uri: hf://org/model@v1
+  mountPath: /weights
+containers:
+  - name: server
+    image: vllm:latest
+    annotations:
+      egress_bandwidth: 50M
+"#;
+        let manifests = parse_manifests(text, "llm.yaml", "default").unwrap();
+        assert_eq!(manifests[0].kind, Kind::Workload);
+        assert_eq!(manifests[0].display_name(), "workload/default/llm");
+        assert!(!manifests[0].body.contains_key("model"));
+        assert_eq!(
+            manifests[0].body["containers"][0]["annotations"],
+            json!({
+                "egress_bandwidth": "50M",
+                "model_uri": "hf://org/model@v1",
+                "model_mount_path": "/weights",
+            })
+        );
+
+        for text in [
+            "kind: InferenceService\nname: llm\ncontainers: []\n",
+            "kind: InferenceService\nname: llm\nmodel: {digest: x}\ncontainers: []\n",
+            "kind: InferenceService\nname: llm\nmodel: {uri: hf://org/model}\n",
+        ] {
+            assert!(
+                parse_manifests(text, "llm.yaml", "default").is_err(),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_load_manifests_from_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("b.yaml"),
+            "kind: Workload\nname: web\nnamespace: shop\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("a.yml"), "kind: Namespace\nname: shop\n").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a manifest").unwrap();
+
+        let manifests = load_manifests(&[dir.path().to_path_buf()], "default").unwrap();
+        let names: Vec<_> = manifests.iter().map(Manifest::display_name).collect();
+        assert_eq!(names, ["namespace/shop", "workload/shop/web"]);
+
+        // The same workload twice is rejected
+        std::fs::write(
+            dir.path().join("c.json"),
+            r#"{"kind": "Workload", "name": "web", "namespace": "shop"}"#,
+        )
+        .unwrap();
+        let err = load_manifests(&[dir.path().to_path_buf()], "default").unwrap_err();
+        assert!(err.to_string().contains("more than once"));
+    }
+
+    #[test]
+    fn test_diff() {
+        let live = json!({
+            "name": "web",
+            "replicas": 2,
+            "resource_version": 4,
+            "labels": {"app": "web", "tier": "front"},
+            "containers": [{"image": "nginx:1.26"}],
+        });
+        let desired = json!({
+            "name": "web",
+            "replicas": 3,
+            "resource_version": 5,
+            "labels": {"app": "web", "team": "shop"},
+            "containers": [{"image": "nginx:1.27"}],
+        });
+        let changes: Vec<String> = diff(&live, &desired)
+            .iter()
+            .map(Change::to_string)
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                r#"~ containers[0].image: "nginx:1.26" -> "nginx:1.27""#,
+                r#"+ labels.team: "shop""#,
+                r#"- labels.tier: "front""#,
+                "~ replicas: 2 -> 3",
+            ]
+        );
+        assert!(diff(&live, &live).is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+//! Cluster command - back up and restore control-plane state.
+
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+use serde::Deserialize;
+
+use crate::client::ApiClient;
+use crate::error::CliError;
+use crate::output;
+
+/// Arguments for the cluster command.
+#[derive(Args)]
+pub struct ClusterArgs {
+    #[command(subcommand)]
+    command: ClusterCommand,
+}
+
+#[derive(Subcommand)]
+enum ClusterCommand {
+    /// Save all namespaces, nodes, workloads and instances to a file
+    Backup {
+        /// File to write the backup to
+        file: PathBuf,
+    },
+    /// Restore a backup into an empty control plane
+    Restore {
+        /// Backup file written by `orch cluster backup`
+        file: PathBuf,
+    },
+}
+
+/// Restore response from API.
+#[derive(Debug, Deserialize)]
+struct RestoreResponse {
+    #[serde(default)]
+    namespaces: usize,
+    nodes: usize,
+    workloads: usize,
+    instances: usize,
+    #[serde(default)]
+    revisions: usize,
+    #[serde(default)]
+    events: usize,
+    #[serde(default)]
+    usage_records: usize,
+    #[serde(default)]
+    resources: usize,
+}
+
+/// Execute the cluster command.
+pub async fn execute(args: ClusterArgs, api_url: &str) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for cluster operations. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        ClusterCommand::Backup { file } => backup(&client, file).await,
+        ClusterCommand::Restore { file } => restore(&client, file).await,
+    }
+}
+
+async fn backup(client: &ApiClient, path: PathBuf) -> anyhow::Result<()> {
+    // Kept opaque so backups round-trip unchanged across CLI versions
+    let backup: serde_json::Value = client.get("/api/v1/cluster/backup").await?;
+    std::fs::write(&path, serde_json::to_vec_pretty(&backup)?)?;
+    output::success(&format!("Cluster state backed up to {}", path.display()));
+    Ok(())
+}
+
+async fn restore(client: &ApiClient, path: PathBuf) -> anyhow::Result<()> {
+    let contents = std::fs::read(&path)?;
+    let backup: serde_json::Value = serde_json::from_slice(&contents).map_err(|e| {
+        CliError::InvalidArgument(format!("{} is not a valid backup: {}", path.display(), e))
+    })?;
+    let restored: RestoreResponse = client
+        .post("/api/v1/cluster/backup/restore", &backup)
+        .await?;
+    output::success(&format!(
+        "Restored {} namespaces, {} nodes, {} workloads and {} instances",
+        restored.namespaces, restored.nodes, restored.workloads, restored.instances
+    ));
+    output::info(&format!(
+        "Restored {} revisions, {} events, {} usage records and {} controller resources",
+        restored.revisions, restored.events, restored.usage_records, restored.resources
+    ));
+    Ok(())
+}
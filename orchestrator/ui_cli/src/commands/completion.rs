@@ -0,0 +1,147 @@
+//! Completion command - print the shell script that completes `orch`.
+//!
+//! The script calls `orch` back with `COMPLETE=<shell>` set, so completion
+//! knows every subcommand and flag of the installed binary, and completes
+//! workload names and node IDs by asking the API server.
+
+use std::time::Duration;
+
+use clap::{Args, ValueEnum};
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::{Bash, EnvCompleter, Fish, Powershell, Zsh};
+use serde::Deserialize;
+
+use crate::client::ApiClient;
+use crate::context::{self, CliConfig};
+use crate::DEFAULT_API_URL;
+
+/// Environment variable the completion script sets when calling `orch`.
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+/// How long a completion waits for the API server before offering nothing.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Arguments for the completion command.
+#[derive(Args)]
+#[command(after_help = "Load completions in the current shell:\n  \
+    bash:       source <(orch completion bash)\n  \
+    zsh:        source <(orch completion zsh)\n  \
+    fish:       orch completion fish | source\n  \
+    powershell: orch completion powershell | Out-String | Invoke-Expression")]
+pub struct CompletionArgs {
+    /// Shell to print the completion script for
+    shell: Shell,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl Shell {
+    fn completer(self) -> &'static dyn EnvCompleter {
+        match self {
+            Shell::Bash => &Bash,
+            Shell::Zsh => &Zsh,
+            Shell::Fish => &Fish,
+            Shell::Powershell => &Powershell,
+        }
+    }
+}
+
+/// Execute the completion command.
+pub async fn execute(args: CompletionArgs) -> anyhow::Result<()> {
+    // Call back the binary that printed the script, wherever it is installed
+    let completer = std::env::current_exe()?;
+    let mut out = std::io::stdout().lock();
+    args.shell.completer().write_registration(
+        COMPLETE_VAR,
+        "orch",
+        "orch",
+        &completer.to_string_lossy(),
+        &mut out,
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Named {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Identified {
+    id: String,
+}
+
+/// Names of the workloads, for completing workload arguments.
+pub fn workload_names() -> Vec<CompletionCandidate> {
+    lookup(|client| async move {
+        let list: ListResponse<Named> = client.get("/api/v1/workloads").await?;
+        Ok(list.items.into_iter().map(|w| w.name).collect())
+    })
+}
+
+/// IDs of the nodes, for completing node arguments.
+pub fn node_ids() -> Vec<CompletionCandidate> {
+    lookup(|client| async move {
+        let list: ListResponse<Identified> = client.get("/api/v1/nodes").await?;
+        Ok(list.items.into_iter().map(|n| n.id).collect())
+    })
+}
+
+/// Candidates fetched by `fetch` from the server of `ORCH_API_URL` or the
+/// context, or none if it cannot be reached in time.
+///
+/// Completers are called synchronously from within `main`'s runtime, so the
+/// request runs on a runtime of its own thread.
+fn lookup<F, Fut>(fetch: F) -> Vec<CompletionCandidate>
+where
+    F: FnOnce(ApiClient) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = crate::error::Result<Vec<String>>>,
+{
+    let names = std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .ok()?;
+        runtime.block_on(async {
+            let client = ApiClient::authenticated(&completion_api_url()).await.ok()?;
+            tokio::time::timeout(LOOKUP_TIMEOUT, fetch(client))
+                .await
+                .ok()?
+                .ok()
+        })
+    })
+    .join()
+    .ok()
+    .flatten()
+    .unwrap_or_default();
+
+    names.into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// The API server to complete against, activating its context so requests
+/// use the context's credentials.
+fn completion_api_url() -> String {
+    let selected = CliConfig::load().ok().and_then(|config| {
+        let name = std::env::var("ORCH_CONTEXT").ok();
+        config.select(name.as_deref()).ok().flatten().cloned()
+    });
+    let api_url = std::env::var("ORCH_API_URL")
+        .ok()
+        .or_else(|| selected.as_ref().map(|context| context.server.clone()))
+        .unwrap_or_else(|| DEFAULT_API_URL.to_string());
+    if let Some(context) = selected {
+        context::activate(context);
+    }
+    api_url
+}
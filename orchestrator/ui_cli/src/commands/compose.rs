@@ -0,0 +1,1115 @@
+//! Deploy the services of a docker-compose file as workloads.
+//!
+//! Every compose service becomes a workload with one container named after
+//! the service. Images, command and entrypoint, environment and env files,
+//! ports, labels, restart, replicas and resources translate directly, and
+//! services are created in `depends_on` order, each once its dependencies
+//! run. Fields without a counterpart in the API, such as volumes, networks,
+//! builds and healthchecks, are reported instead of being silently dropped.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::{Mapping, Value};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::error::{CliError, Result};
+use crate::output::{self, print_data, print_value};
+use crate::OutputFormat;
+
+/// Label naming the compose project a workload was deployed from.
+pub const PROJECT_LABEL: &str = "com.docker.compose.project";
+
+/// Label naming the compose service a workload was deployed from.
+pub const SERVICE_LABEL: &str = "com.docker.compose.service";
+
+/// CPU and memory of services that reserve and limit neither, as for
+/// `orch deploy`.
+const DEFAULT_CPU_CORES: f32 = 0.1;
+const DEFAULT_MEMORY_MB: u64 = 128;
+
+/// How often a dependency is checked while waiting for it to run.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Create workload request - matches API's CreateWorkloadRequest.
+#[derive(Debug, Serialize)]
+struct CreateWorkloadRequest {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+    replicas: u32,
+    labels: BTreeMap<String, String>,
+    containers: Vec<ContainerConfigRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    restart_policy: Option<RestartPolicyRequest>,
+}
+
+/// Container configuration - matches API's ContainerConfigRequest.
+#[derive(Debug, Serialize)]
+struct ContainerConfigRequest {
+    name: String,
+    image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    args: Option<Vec<String>>,
+    env_vars: BTreeMap<String, String>,
+    ports: Vec<PortMappingRequest>,
+    resource_requests: ResourceRequestsRequest,
+}
+
+/// Port mapping - matches API's PortMappingRequest.
+#[derive(Debug, PartialEq, Serialize)]
+struct PortMappingRequest {
+    container_port: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host_port: Option<u16>,
+    protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host_ip: Option<IpAddr>,
+}
+
+/// Resource requests - matches API's ResourceRequestsRequest.
+#[derive(Debug, Serialize)]
+struct ResourceRequestsRequest {
+    cpu_cores: f32,
+    memory_mb: u64,
+    disk_mb: u64,
+}
+
+/// Restart policy - matches the API's RestartPolicy.
+#[derive(Debug, Serialize)]
+struct RestartPolicyRequest {
+    mode: &'static str,
+}
+
+/// Workload response from API.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct WorkloadResponse {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Replicas")]
+    replicas: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstanceResponse {
+    status: String,
+    #[serde(default)]
+    ready: Option<bool>,
+}
+
+/// What a service waits for before it is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Condition {
+    /// `service_started`: an instance of the dependency runs.
+    Started,
+    /// `service_healthy`: an instance runs and is not failing its readiness
+    /// probe.
+    Healthy,
+    /// `service_completed_successfully`: an instance exited successfully.
+    Completed,
+}
+
+/// A compose service translated into a workload.
+#[derive(Debug)]
+struct Service {
+    request: CreateWorkloadRequest,
+    depends_on: BTreeMap<String, Condition>,
+}
+
+/// The services of a compose file, in the order they are deployed.
+#[derive(Debug)]
+struct Project {
+    services: Vec<Service>,
+    /// Fields that were not translated, one line each.
+    warnings: Vec<String>,
+}
+
+/// Deploy every service of the compose file at `path` into `namespace`.
+pub async fn deploy(
+    path: &Path,
+    api_url: &str,
+    namespace: Option<String>,
+    wait_timeout: Duration,
+    dry_run: bool,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let project = load(path, namespace)?;
+    for warning in &project.warnings {
+        output::warn(warning);
+    }
+
+    if dry_run {
+        let requests: Vec<&CreateWorkloadRequest> = project
+            .services
+            .iter()
+            .map(|service| &service.request)
+            .collect();
+        return print_value(&requests, format);
+    }
+
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for deploy. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    let mut deployed: HashMap<&str, String> = HashMap::new();
+    let mut created = Vec::new();
+    for service in &project.services {
+        for (dependency, condition) in &service.depends_on {
+            let id = &deployed[dependency.as_str()];
+            wait_for(&client, dependency, id, *condition, wait_timeout).await?;
+        }
+
+        let name = &service.request.name;
+        output::info(&format!("Deploying service '{}'...", name));
+        let created_workload = client.post("/api/v1/workloads", &service.request).await;
+        let response: WorkloadResponse = created_workload.map_err(|e| {
+            CliError::api_error(format!("Failed to deploy service '{}': {}", name, e))
+        })?;
+        deployed.insert(name.as_str(), response.id.clone());
+        created.push(response);
+    }
+
+    output::success(&format!(
+        "Deployed {} service(s) from {}",
+        created.len(),
+        path.display()
+    ));
+    print_data(&created, format)?;
+    output::info("Use 'orch status' to check deployment progress");
+    Ok(())
+}
+
+/// Wait until the workload `id` of the service `name` meets `condition`.
+async fn wait_for(
+    client: &ApiClient,
+    name: &str,
+    id: &str,
+    condition: Condition,
+    timeout: Duration,
+) -> Result<()> {
+    output::info(&format!("Waiting for service '{}'...", name));
+    let deadline = Instant::now() + timeout;
+    loop {
+        let path = format!("/api/v1/workloads/{}/instances", id);
+        let instances: ListResponse<InstanceResponse> = client.get(&path).await?;
+        let met = instances.items.iter().any(|instance| match condition {
+            Condition::Started => instance.status == "Running",
+            Condition::Healthy => instance.status == "Running" && instance.ready != Some(false),
+            Condition::Completed => instance.status == "Succeeded",
+        });
+        if met {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(CliError::Other(format!(
+                "Service '{}' was not ready within {}s; services deployed so far keep running",
+                name,
+                timeout.as_secs()
+            )));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Read and translate the compose file at `path`, with variables from the
+/// `.env` file next to it and the environment.
+fn load(path: &Path, namespace: Option<String>) -> Result<Project> {
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        CliError::invalid_argument(format!("Cannot read {}: {}", path.display(), e))
+    })?;
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let dotenv = match std::fs::read_to_string(dir.join(".env")) {
+        Ok(text) => parse_env_file(&text).into_iter().collect(),
+        Err(_) => HashMap::new(),
+    };
+    let vars = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .or_else(|| dotenv.get(name).cloned())
+    };
+
+    let default_name = std::fs::canonicalize(dir)
+        .ok()
+        .and_then(|dir| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().to_lowercase())
+        })
+        .unwrap_or_else(|| "default".to_string());
+    parse(&text, dir, &default_name, namespace, &vars)
+}
+
+/// Translate compose file `text`. Env files are read relative to `dir`, and
+/// the project is named `default_name` unless the file names it.
+fn parse(
+    text: &str,
+    dir: &Path,
+    default_name: &str,
+    namespace: Option<String>,
+    vars: &dyn Fn(&str) -> Option<String>,
+) -> Result<Project> {
+    let mut warnings = Vec::new();
+    let text = interpolate(text, vars, &mut warnings)?;
+    let file: Mapping = serde_yaml_ng::from_str(&text)
+        .map_err(|e| CliError::invalid_argument(format!("Invalid compose file: {}", e)))?;
+
+    let mut project_name = default_name.to_string();
+    let mut services = Mapping::new();
+    for (key, value) in &file {
+        match key.as_str().unwrap_or_default() {
+            "name" => project_name = string(value, "name")?,
+            "services" => {
+                services = value.as_mapping().cloned().ok_or_else(|| {
+                    CliError::invalid_argument("services must be a mapping of service names")
+                })?
+            }
+            // Obsolete, and extension fields only feed YAML anchors
+            "version" => {}
+            key if key.starts_with("x-") => {}
+            key @ ("volumes" | "networks" | "secrets" | "configs") => warnings.push(format!(
+                "top-level {}: not supported; {} are not created",
+                key, key
+            )),
+            key => warnings.push(format!("top-level {}: not supported, ignored", key)),
+        }
+    }
+    if services.is_empty() {
+        return Err(CliError::invalid_argument(
+            "Compose file defines no services",
+        ));
+    }
+
+    let mut translated = BTreeMap::new();
+    for (name, service) in &services {
+        let name = string(name, "service name")?;
+        let service = service.as_mapping().cloned().unwrap_or_default();
+        let service = translate(&name, &service, dir, vars, &mut warnings)?;
+        translated.insert(name, service);
+    }
+    for (name, service) in &mut translated {
+        let labels = &mut service.request.labels;
+        labels.insert(PROJECT_LABEL.to_string(), project_name.clone());
+        labels.insert(SERVICE_LABEL.to_string(), name.clone());
+        service.request.namespace = namespace.clone();
+    }
+
+    let mut seen = HashSet::new();
+    warnings.retain(|warning| seen.insert(warning.clone()));
+    Ok(Project {
+        services: deploy_order(translated)?,
+        warnings,
+    })
+}
+
+/// Translate the compose service `name`.
+fn translate(
+    name: &str,
+    service: &Mapping,
+    dir: &Path,
+    vars: &dyn Fn(&str) -> Option<String>,
+    warnings: &mut Vec<String>,
+) -> Result<Service> {
+    let mut image = None;
+    let mut command = None;
+    let mut args = None;
+    let mut env_files = BTreeMap::new();
+    let mut environment = BTreeMap::new();
+    let mut ports = Vec::new();
+    let mut labels = BTreeMap::new();
+    let mut depends_on = BTreeMap::new();
+    let mut restart_policy = None;
+    let mut replicas = 1;
+    let mut resources = Resources::default();
+    let mut has_build = false;
+
+    for (key, value) in service {
+        let key = key.as_str().unwrap_or_default();
+        let field = format!("{}.{}", name, key);
+        match key {
+            "image" => image = Some(string(value, &field)?),
+            "entrypoint" => command = Some(words(value, &field)?),
+            "command" => args = Some(words(value, &field)?),
+            "environment" => environment = pairs(value, &field, vars)?,
+            "env_file" => {
+                for file in words(value, &field)? {
+                    let text = std::fs::read_to_string(dir.join(&file)).map_err(|e| {
+                        CliError::invalid_argument(format!(
+                            "{}: cannot read {}: {}",
+                            field, file, e
+                        ))
+                    })?;
+                    env_files.extend(parse_env_file(&text));
+                }
+            }
+            "ports" => {
+                for port in value.as_sequence().into_iter().flatten() {
+                    ports.extend(port_mappings(port, &field)?);
+                }
+            }
+            "labels" => labels = pairs(value, &field, &|_| None)?,
+            "depends_on" => depends_on = dependencies(value, &field, warnings)?,
+            "restart" => restart_policy = Some(restart(value, &field, warnings)?),
+            "deploy" => {
+                let deploy = value.as_mapping().cloned().unwrap_or_default();
+                for (key, value) in &deploy {
+                    let key = key.as_str().unwrap_or_default();
+                    let field = format!("{}.{}", field, key);
+                    match key {
+                        "replicas" => replicas = number(value, &field)? as u32,
+                        "resources" => resources.read(value, &field, warnings)?,
+                        "restart_policy" => {
+                            let condition = value.get("condition").unwrap_or(&Value::Null);
+                            restart_policy = Some(deploy_restart(condition, &field)?);
+                        }
+                        _ => warnings.push(format!("{}: not supported, ignored", field)),
+                    }
+                }
+            }
+            "cpus" => resources.cpu_limit = Some(number(value, &field)? as f32),
+            "mem_limit" => resources.memory_limit = Some(memory_mb(value, &field)?),
+            "mem_reservation" => resources.memory_reservation = Some(memory_mb(value, &field)?),
+            "build" => {
+                has_build = true;
+                warnings.push(format!(
+                    "{}: not supported; the service runs its image instead of being built",
+                    field
+                ));
+            }
+            "volumes" | "tmpfs" => warnings.push(format!(
+                "{}: not supported; data the service writes is lost when its instance is \
+                 replaced",
+                field
+            )),
+            "healthcheck" => warnings.push(format!(
+                "{}: not supported; dependencies on this service wait only until it runs",
+                field
+            )),
+            "networks" | "network_mode" | "links" | "extra_hosts" | "dns" | "hostname" => warnings
+                .push(format!(
+                    "{}: not supported; networking is managed by the platform",
+                    field
+                )),
+            // Names the container, which the platform names after the instance
+            "container_name" => warnings.push(format!("{}: ignored", field)),
+            _ => warnings.push(format!("{}: not supported, ignored", field)),
+        }
+    }
+
+    let image = image.ok_or_else(|| {
+        CliError::invalid_argument(if has_build {
+            format!(
+                "Service '{}' is built, not pulled; push its image and set `image`",
+                name
+            )
+        } else {
+            format!("Service '{}' has no image", name)
+        })
+    })?;
+    let mut env_vars = env_files;
+    env_vars.extend(environment);
+
+    Ok(Service {
+        request: CreateWorkloadRequest {
+            name: name.to_string(),
+            namespace: None,
+            replicas,
+            labels,
+            containers: vec![ContainerConfigRequest {
+                name: name.to_string(),
+                image,
+                command,
+                args,
+                env_vars,
+                ports,
+                resource_requests: resources.requests(),
+            }],
+            restart_policy,
+        },
+        depends_on,
+    })
+}
+
+/// CPU and memory limits and reservations of a service.
+#[derive(Debug, Default)]
+struct Resources {
+    cpu_limit: Option<f32>,
+    cpu_reservation: Option<f32>,
+    memory_limit: Option<u64>,
+    memory_reservation: Option<u64>,
+}
+
+impl Resources {
+    /// Read `deploy.resources`.
+    fn read(&mut self, value: &Value, field: &str, warnings: &mut Vec<String>) -> Result<()> {
+        for (kind, resources) in value.as_mapping().into_iter().flatten() {
+            let kind = kind.as_str().unwrap_or_default();
+            let (cpu, memory) = match kind {
+                "limits" => (&mut self.cpu_limit, &mut self.memory_limit),
+                "reservations" => (&mut self.cpu_reservation, &mut self.memory_reservation),
+                _ => {
+                    warnings.push(format!("{}.{}: not supported, ignored", field, kind));
+                    continue;
+                }
+            };
+            for (key, value) in resources.as_mapping().into_iter().flatten() {
+                let key = key.as_str().unwrap_or_default();
+                let field = format!("{}.{}.{}", field, kind, key);
+                match key {
+                    "cpus" => *cpu = Some(number(value, &field)? as f32),
+                    "memory" => *memory = Some(memory_mb(value, &field)?),
+                    _ => warnings.push(format!("{}: not supported, ignored", field)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// What the workload requests: the reservation, else the limit.
+    fn requests(&self) -> ResourceRequestsRequest {
+        ResourceRequestsRequest {
+            cpu_cores: self
+                .cpu_reservation
+                .or(self.cpu_limit)
+                .unwrap_or(DEFAULT_CPU_CORES),
+            memory_mb: self
+                .memory_reservation
+                .or(self.memory_limit)
+                .unwrap_or(DEFAULT_MEMORY_MB),
+            disk_mb: 0,
+        }
+    }
+}
+
+/// Services ordered so that each comes after the services it depends on.
+fn deploy_order(mut services: BTreeMap<String, Service>) -> Result<Vec<Service>> {
+    for (name, service) in &services {
+        if let Some(missing) = service
+            .depends_on
+            .keys()
+            .find(|dependency| !services.contains_key(*dependency))
+        {
+            return Err(CliError::invalid_argument(format!(
+                "Service '{}' depends on undefined service '{}'",
+                name, missing
+            )));
+        }
+    }
+
+    let mut ordered = Vec::new();
+    let mut deployed = HashSet::new();
+    while !services.is_empty() {
+        let ready: Vec<String> = services
+            .iter()
+            .filter(|(_, service)| service.depends_on.keys().all(|d| deployed.contains(d)))
+            .map(|(name, _)| name.clone())
+            .collect();
+        if ready.is_empty() {
+            let names: Vec<&str> = services.keys().map(String::as_str).collect();
+            return Err(CliError::invalid_argument(format!(
+                "Services depend on each other in a cycle: {}",
+                names.join(", ")
+            )));
+        }
+        for name in ready {
+            ordered.extend(services.remove(&name));
+            deployed.insert(name);
+        }
+    }
+    Ok(ordered)
+}
+
+/// `depends_on` as a list of services or a mapping to conditions.
+fn dependencies(
+    value: &Value,
+    field: &str,
+    warnings: &mut Vec<String>,
+) -> Result<BTreeMap<String, Condition>> {
+    if let Some(list) = value.as_sequence() {
+        return list
+            .iter()
+            .map(|name| Ok((string(name, field)?, Condition::Started)))
+            .collect();
+    }
+    let map = value.as_mapping().ok_or_else(|| {
+        CliError::invalid_argument(format!("{}: expected a list or mapping", field))
+    })?;
+    let mut dependencies = BTreeMap::new();
+    for (name, options) in map {
+        let name = string(name, field)?;
+        let condition = match options.get("condition").and_then(Value::as_str) {
+            None | Some("service_started") => Condition::Started,
+            Some("service_healthy") => {
+                warnings.push(format!(
+                    "{}.{}: service_healthy waits for readiness probes, which compose \
+                     healthchecks do not set",
+                    field, name
+                ));
+                Condition::Healthy
+            }
+            Some("service_completed_successfully") => Condition::Completed,
+            Some(other) => {
+                return Err(CliError::invalid_argument(format!(
+                    "{}.{}: unknown condition '{}'",
+                    field, name, other
+                )))
+            }
+        };
+        dependencies.insert(name, condition);
+    }
+    Ok(dependencies)
+}
+
+/// The restart policy of a service's `restart`.
+fn restart(value: &Value, field: &str, warnings: &mut Vec<String>) -> Result<RestartPolicyRequest> {
+    let restart = string(value, field)?;
+    let mode = match restart.as_str() {
+        "no" => "Never",
+        "always" | "unless-stopped" => "Always",
+        "on-failure" => "OnFailure",
+        on_failure if on_failure.starts_with("on-failure:") => {
+            warnings.push(format!(
+                "{}: the retry limit is not supported; failed containers restart until \
+                 they succeed",
+                field
+            ));
+            "OnFailure"
+        }
+        other => {
+            return Err(CliError::invalid_argument(format!(
+                "{}: unknown restart policy '{}'",
+                field, other
+            )))
+        }
+    };
+    Ok(RestartPolicyRequest { mode })
+}
+
+/// The restart policy of `deploy.restart_policy.condition`.
+fn deploy_restart(condition: &Value, field: &str) -> Result<RestartPolicyRequest> {
+    let mode = match condition.as_str().unwrap_or("any") {
+        "none" => "Never",
+        "on-failure" => "OnFailure",
+        "any" => "Always",
+        other => {
+            return Err(CliError::invalid_argument(format!(
+                "{}: unknown condition '{}'",
+                field, other
+            )))
+        }
+    };
+    Ok(RestartPolicyRequest { mode })
+}
+
+/// Port mappings of one `ports` entry, in short (`[IP:][HOST:]CONTAINER[/PROTOCOL]`,
+/// with ranges) or long syntax.
+fn port_mappings(value: &Value, field: &str) -> Result<Vec<PortMappingRequest>> {
+    let invalid =
+        |spec: &str| CliError::invalid_argument(format!("{}: invalid port '{}'", field, spec));
+    if let Some(long) = value.as_mapping() {
+        let spec = long.get("target").and_then(scalar).unwrap_or_default();
+        let container_port = spec.parse().map_err(|_| invalid(&spec))?;
+        let host_port = match long.get("published").and_then(scalar).as_deref() {
+            None | Some("") => None,
+            Some(published) => Some(published.parse().map_err(|_| invalid(published))?),
+        };
+        let host_ip = match long.get("host_ip").and_then(Value::as_str) {
+            Some(ip) => Some(ip.parse().map_err(|_| invalid(ip))?),
+            None => None,
+        };
+        let protocol = long
+            .get("protocol")
+            .and_then(Value::as_str)
+            .unwrap_or("tcp");
+        return Ok(vec![PortMappingRequest {
+            container_port,
+            host_port,
+            protocol: protocol.to_lowercase(),
+            host_ip,
+        }]);
+    }
+
+    let spec = scalar(value).ok_or_else(|| invalid("not a string"))?;
+    let (mapping, protocol) = spec.rsplit_once('/').unwrap_or((spec.as_str(), "tcp"));
+    let (host_ip, rest) = if let Some(bracketed) = mapping.strip_prefix('[') {
+        let (ip, rest) = bracketed.split_once("]:").ok_or_else(|| invalid(&spec))?;
+        (Some(ip), rest)
+    } else if mapping.matches(':').count() == 2 {
+        let (ip, rest) = mapping.split_once(':').unwrap_or_default();
+        (Some(ip), rest)
+    } else {
+        (None, mapping)
+    };
+    let (host, container) = match rest.split_once(':') {
+        Some((host, container)) => (Some(host).filter(|host| !host.is_empty()), container),
+        None => (None, rest),
+    };
+    let host_ip = match host_ip {
+        Some(ip) => Some(ip.parse::<IpAddr>().map_err(|_| invalid(&spec))?),
+        None => None,
+    };
+
+    let container_ports = port_range(container).ok_or_else(|| invalid(&spec))?;
+    let host_ports = match host {
+        Some(host) => port_range(host).ok_or_else(|| invalid(&spec))?,
+        None => vec![],
+    };
+    if !host_ports.is_empty() && host_ports.len() != container_ports.len() {
+        return Err(invalid(&spec));
+    }
+    Ok(container_ports
+        .into_iter()
+        .enumerate()
+        .map(|(i, container_port)| PortMappingRequest {
+            container_port,
+            host_port: host_ports.get(i).copied(),
+            protocol: protocol.to_lowercase(),
+            host_ip,
+        })
+        .collect())
+}
+
+/// The ports of `80` or `8000-8010`.
+fn port_range(spec: &str) -> Option<Vec<u16>> {
+    let (start, end) = spec.split_once('-').unwrap_or((spec, spec));
+    let (start, end): (u16, u16) = (start.parse().ok()?, end.parse().ok()?);
+    (start <= end).then(|| (start..=end).collect())
+}
+
+/// Megabytes of a compose byte value such as `512m`, `1g` or `1048576`,
+/// rounded up.
+fn memory_mb(value: &Value, field: &str) -> Result<u64> {
+    let spec = scalar(value).unwrap_or_default().to_lowercase();
+    let invalid = || CliError::invalid_argument(format!("{}: invalid size '{}'", field, spec));
+    let digits = spec
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(spec.len());
+    let (amount, unit) = spec.split_at(digits);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let multiplier: u64 = match unit.trim() {
+        "" | "b" => 1,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        _ => return Err(invalid()),
+    };
+    Ok((amount * multiplier).div_ceil(1 << 20))
+}
+
+/// Key-value pairs of a mapping or a list of `KEY=VALUE`. Keys without a
+/// value take theirs from `vars`, and are left out when it has none.
+fn pairs(
+    value: &Value,
+    field: &str,
+    vars: &dyn Fn(&str) -> Option<String>,
+) -> Result<BTreeMap<String, String>> {
+    let mut pairs = BTreeMap::new();
+    if let Some(map) = value.as_mapping() {
+        for (key, value) in map {
+            let key = string(key, field)?;
+            if let Some(value) = scalar(value).or_else(|| vars(&key)) {
+                pairs.insert(key, value);
+            }
+        }
+    } else if let Some(list) = value.as_sequence() {
+        for entry in list {
+            let entry = string(entry, field)?;
+            match entry.split_once('=') {
+                Some((key, value)) => {
+                    pairs.insert(key.to_string(), value.to_string());
+                }
+                None => {
+                    if let Some(value) = vars(&entry) {
+                        pairs.insert(entry, value);
+                    }
+                }
+            }
+        }
+    } else {
+        return Err(CliError::invalid_argument(format!(
+            "{}: expected a list or mapping",
+            field
+        )));
+    }
+    Ok(pairs)
+}
+
+/// Words of a list, or of a string split like a shell would.
+fn words(value: &Value, field: &str) -> Result<Vec<String>> {
+    match value.as_sequence() {
+        Some(list) => list.iter().map(|word| string(word, field)).collect(),
+        None => Ok(split_words(&string(value, field)?)),
+    }
+}
+
+/// `line` split on whitespace outside single and double quotes.
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = None::<String>;
+    let mut quote = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// A scalar as a string, or an error naming `field`.
+fn string(value: &Value, field: &str) -> Result<String> {
+    scalar(value).ok_or_else(|| CliError::invalid_argument(format!("{}: expected a string", field)))
+}
+
+/// A scalar as a number, or an error naming `field`.
+fn number(value: &Value, field: &str) -> Result<f64> {
+    scalar(value)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| CliError::invalid_argument(format!("{}: expected a number", field)))
+}
+
+/// The text of a string, number or boolean.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// `KEY=VALUE` lines of an env file, skipping blanks and comments and
+/// unquoting values.
+fn parse_env_file(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = ['"', '\'']
+                .iter()
+                .find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// `text` with `$VAR`, `${VAR}`, `${VAR:-default}`, `${VAR-default}`,
+/// `${VAR:?error}` and `${VAR?error}` substituted from `vars`, and `$$`
+/// unescaped.
+fn interpolate(
+    text: &str,
+    vars: &dyn Fn(&str) -> Option<String>,
+    warnings: &mut Vec<String>,
+) -> Result<String> {
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        if let Some(after) = rest.strip_prefix('$') {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .ok_or_else(|| CliError::invalid_argument("Unclosed ${ in compose file"))?;
+            out.push_str(&substitute(&after[..end], vars, warnings)?);
+            rest = &after[end + 1..];
+        } else {
+            let len = rest.find(|c: char| !is_name(c)).unwrap_or(rest.len());
+            if len == 0 {
+                out.push('$');
+            } else {
+                out.push_str(&substitute(&rest[..len], vars, warnings)?);
+                rest = &rest[len..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// The value of one `${...}` expression.
+fn substitute(
+    expression: &str,
+    vars: &dyn Fn(&str) -> Option<String>,
+    warnings: &mut Vec<String>,
+) -> Result<String> {
+    let end = expression
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(expression.len());
+    let (name, modifier) = expression.split_at(end);
+    let value = vars(name);
+    if modifier.is_empty() {
+        return Ok(value.unwrap_or_else(|| {
+            warnings.push(format!(
+                "variable {} is not set; substituting an empty string",
+                name
+            ));
+            String::new()
+        }));
+    }
+    // With a colon, an empty variable counts as unset
+    let (unset_if_empty, rest) = match modifier.strip_prefix(':') {
+        Some(rest) => (true, rest),
+        None => (false, modifier),
+    };
+    let value = value.filter(|value| !(unset_if_empty && value.is_empty()));
+    if let Some(default) = rest.strip_prefix('-') {
+        return Ok(value.unwrap_or_else(|| default.to_string()));
+    }
+    if let Some(error) = rest.strip_prefix('?') {
+        return value.ok_or_else(|| {
+            CliError::invalid_argument(format!("Variable {} is required: {}", name, error))
+        });
+    }
+    Err(CliError::invalid_argument(format!(
+        "Unsupported substitution ${{{}}}",
+        expression
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(name: &str) -> Option<String> {
+        match name {
+            "TAG" => Some("1.25".to_string()),
+            "EMPTY" => Some(String::new()),
+            "DB_PASSWORD" => Some("secret".to_string()),
+            _ => None,
+        }
+    }
+
+    fn project(text: &str) -> Project {
+        parse(text, Path::new("."), "shop", None, &vars).unwrap()
+    }
+
+    #[test]
+    fn test_translates_services() {
+        let project = project(
+            r#"
+services:
+  web:
+    image: nginx:${TAG}
+    command: nginx -g "daemon off;"
+    ports: ["8080:80", "127.0.0.1:8443:443/tcp", 9000, {target: 53, protocol: udp}]
+    environment:
+      MODE: production
+      DB_PASSWORD:
+      MISSING:
+    labels: [tier=frontend]
+    restart: unless-stopped
+    deploy:
+      replicas: 3
+      resources:
+        limits: {cpus: "1", memory: 1G}
+        reservations: {memory: 256M}
+"#,
+        );
+        let request = &project.services[0].request;
+        assert_eq!(request.replicas, 3);
+        assert_eq!(request.labels["tier"], "frontend");
+        assert_eq!(request.labels[PROJECT_LABEL], "shop");
+        assert_eq!(request.labels[SERVICE_LABEL], "web");
+        assert_eq!(request.restart_policy.as_ref().unwrap().mode, "Always");
+
+        let container = &request.containers[0];
+        assert_eq!(container.image, "nginx:1.25");
+        assert_eq!(container.command, None);
+        assert_eq!(
+            container.args.as_deref().unwrap(),
+            ["nginx", "-g", "daemon off;"]
+        );
+        assert_eq!(container.env_vars.len(), 2);
+        assert_eq!(container.env_vars["DB_PASSWORD"], "secret");
+        assert_eq!(container.resource_requests.cpu_cores, 1.0);
+        assert_eq!(container.resource_requests.memory_mb, 256);
+
+        let ports: Vec<(u16, Option<u16>, &str, Option<IpAddr>)> = container
+            .ports
+            .iter()
+            .map(|p| {
+                (
+                    p.container_port,
+                    p.host_port,
+                    p.protocol.as_str(),
+                    p.host_ip,
+                )
+            })
+            .collect();
+        assert_eq!(
+            ports,
+            [
+                (80, Some(8080), "tcp", None),
+                (443, Some(8443), "tcp", Some("127.0.0.1".parse().unwrap())),
+                (9000, None, "tcp", None),
+                (53, None, "udp", None),
+            ]
+        );
+        assert!(project.warnings.is_empty(), "{:?}", project.warnings);
+    }
+
+    #[test]
+    fn test_reports_unsupported_fields() {
+        let project = project(
+            r#"
+version: "3.8"
+services:
+  db:
+    image: postgres
+    volumes: ["data:/var/lib/postgresql/data"]
+    healthcheck: {test: ["CMD", "pg_isready"]}
+    networks: [backend]
+    stop_grace_period: 30s
+volumes:
+  data: {}
+"#,
+        );
+        let warnings = project.warnings.join("\n");
+        assert_eq!(project.warnings.len(), 5, "{}", warnings);
+        for field in [
+            "db.volumes",
+            "db.healthcheck",
+            "db.networks",
+            "db.stop_grace_period",
+            "top-level volumes",
+        ] {
+            assert!(warnings.contains(field), "{} not reported", field);
+        }
+
+        let error = parse(
+            "services:\n  app:\n    build: .\n",
+            Path::new("."),
+            "shop",
+            None,
+            &vars,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("push its image"));
+    }
+
+    #[test]
+    fn test_deploys_dependencies_first() {
+        let project = project(
+            r#"
+services:
+  web:
+    image: web
+    depends_on: [api]
+  api:
+    image: api
+    depends_on:
+      db: {condition: service_started}
+      migrate: {condition: service_completed_successfully}
+  migrate:
+    image: migrate
+    depends_on: [db]
+  db:
+    image: postgres
+"#,
+        );
+        let order: Vec<&str> = project
+            .services
+            .iter()
+            .map(|service| service.request.name.as_str())
+            .collect();
+        assert_eq!(order, ["db", "migrate", "api", "web"]);
+        assert_eq!(
+            project.services[2].depends_on["migrate"],
+            Condition::Completed
+        );
+
+        let cycle =
+            "services:\n  a: {image: a, depends_on: [b]}\n  b: {image: b, depends_on: [a]}\n";
+        let error = parse(cycle, Path::new("."), "shop", None, &vars).unwrap_err();
+        assert!(error.to_string().contains("cycle: a, b"));
+
+        let missing = "services:\n  a: {image: a, depends_on: [b]}\n";
+        let error = parse(missing, Path::new("."), "shop", None, &vars).unwrap_err();
+        assert!(error.to_string().contains("undefined service 'b'"));
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let mut warnings = Vec::new();
+        let text = "$TAG ${TAG} ${EMPTY:-fallback} ${EMPTY-kept} ${UNSET-default} $$HOME $UNSET";
+        assert_eq!(
+            interpolate(text, &vars, &mut warnings).unwrap(),
+            "1.25 1.25 fallback  default $HOME "
+        );
+        assert_eq!(warnings.len(), 1);
+
+        let error = interpolate("${UNSET:?set it}", &vars, &mut warnings).unwrap_err();
+        assert!(error.to_string().contains("set it"));
+    }
+
+    #[test]
+    fn test_port_and_memory_syntax() {
+        let ports = port_mappings(&Value::from("8000-8001:80-81/UDP"), "ports").unwrap();
+        assert_eq!(ports.len(), 2);
+        assert_eq!(
+            (ports[1].container_port, ports[1].host_port),
+            (81, Some(8001))
+        );
+        assert_eq!(ports[1].protocol, "udp");
+
+        let ports = port_mappings(&Value::from("[::1]::80"), "ports").unwrap();
+        assert_eq!(ports[0].host_port, None);
+        assert_eq!(ports[0].host_ip, Some("::1".parse().unwrap()));
+
+        assert!(port_mappings(&Value::from("8000-8002:80-81"), "ports").is_err());
+
+        assert_eq!(memory_mb(&Value::from("512m"), "memory").unwrap(), 512);
+        assert_eq!(memory_mb(&Value::from("2gb"), "memory").unwrap(), 2048);
+        assert_eq!(memory_mb(&Value::from(1_000_000), "memory").unwrap(), 1);
+        assert!(memory_mb(&Value::from("lots"), "memory").is_err());
+    }
+
+    #[test]
+    fn test_env_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("app.env"),
+            "# comment\nexport LEVEL=debug\nNAME=\"app one\"\nMODE=file\n",
+        )
+        .unwrap();
+        let text = "services:\n  app:\n    image: app\n    env_file: app.env\n    \
+                    environment: [MODE=inline]\n";
+        let project = parse(text, dir.path(), "shop", None, &vars).unwrap();
+        let env = &project.services[0].request.containers[0].env_vars;
+        assert_eq!(env["LEVEL"], "debug");
+        assert_eq!(env["NAME"], "app one");
+        assert_eq!(env["MODE"], "inline");
+    }
+}
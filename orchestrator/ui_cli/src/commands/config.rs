@@ -0,0 +1,150 @@
+//! Config command - manage the named contexts of `~/.orch/config.yaml`.
+
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+use serde::Serialize;
+use tabled::Tabled;
+
+use crate::context::{CliConfig, Context};
+use crate::error::CliError;
+use crate::output::{self, print_data};
+use crate::OutputFormat;
+
+/// Arguments for the config command.
+#[derive(Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Make a context the one commands use by default
+    UseContext {
+        /// Context name
+        name: String,
+    },
+    /// Show the current context
+    CurrentContext,
+    /// List contexts
+    GetContexts,
+    /// Create a context, or change fields of an existing one
+    SetContext {
+        /// Context name
+        name: String,
+
+        /// API server URL (required for a new context)
+        #[arg(long)]
+        server: Option<String>,
+
+        /// Namespace commands default to in this context
+        #[arg(long)]
+        namespace: Option<String>,
+
+        /// Bearer token to send instead of signing requests
+        #[arg(long)]
+        token: Option<String>,
+
+        /// PEM file of the CA the server is verified against
+        #[arg(long)]
+        ca_certificate: Option<PathBuf>,
+    },
+    /// Delete a context
+    DeleteContext {
+        /// Context name
+        name: String,
+    },
+}
+
+/// Display-friendly context for table output.
+#[derive(Debug, Serialize, Tabled)]
+struct ContextDisplay {
+    #[tabled(rename = "Current")]
+    current: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+}
+
+/// Execute the config command.
+pub async fn execute(args: ConfigArgs, format: OutputFormat) -> anyhow::Result<()> {
+    let mut config = CliConfig::load()?;
+
+    match args.command {
+        ConfigCommand::UseContext { name } => {
+            config.context(&name)?;
+            config.current_context = Some(name.clone());
+            config.save()?;
+            output::success(&format!("Switched to context {}", name));
+        }
+        ConfigCommand::CurrentContext => match &config.current_context {
+            Some(name) => println!("{}", name),
+            None => {
+                return Err(CliError::config_error("No current context is set").into());
+            }
+        },
+        ConfigCommand::GetContexts => {
+            let displays: Vec<ContextDisplay> = config
+                .contexts
+                .iter()
+                .map(|context| ContextDisplay {
+                    current: if config.current_context.as_ref() == Some(&context.name) {
+                        "*".to_string()
+                    } else {
+                        String::new()
+                    },
+                    name: context.name.clone(),
+                    server: context.server.clone(),
+                    namespace: context.namespace.clone().unwrap_or_else(|| "-".to_string()),
+                })
+                .collect();
+            print_data(&displays, format)?;
+        }
+        ConfigCommand::SetContext {
+            name,
+            server,
+            namespace,
+            token,
+            ca_certificate,
+        } => {
+            let mut context = match config.context(&name) {
+                Ok(context) => context.clone(),
+                Err(_) => Context {
+                    name: name.clone(),
+                    server: server.clone().ok_or_else(|| {
+                        CliError::invalid_argument("--server is required for a new context")
+                    })?,
+                    namespace: None,
+                    token: None,
+                    ca_certificate: None,
+                },
+            };
+            if let Some(server) = server {
+                context.server = server;
+            }
+            if namespace.is_some() {
+                context.namespace = namespace;
+            }
+            if token.is_some() {
+                context.token = token;
+            }
+            if ca_certificate.is_some() {
+                context.ca_certificate = ca_certificate;
+            }
+            config.set_context(context);
+            config.save()?;
+            output::success(&format!("Context {} saved", name));
+        }
+        ConfigCommand::DeleteContext { name } => {
+            config.delete_context(&name)?;
+            config.save()?;
+            output::success(&format!("Context {} deleted", name));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,295 @@
+//! Convert command - map Kubernetes manifests onto workloads.
+//!
+//! Deployments, ReplicaSets, StatefulSets and Pods become workloads, with the
+//! values of referenced ConfigMaps and Secrets inlined into their
+//! environment; Namespaces become namespaces and Services are registered with
+//! the service proxy. The mapping is done by the server
+//! (`POST /api/v1/import/kubernetes`), which also reports every field it could
+//! not carry over.
+//!
+//! By default the objects are printed as `orch apply` manifests. With
+//! `--import` they are created directly, services included.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client::ApiClient;
+use crate::error::{CliError, Result};
+use crate::output;
+use crate::OutputFormat;
+
+/// Arguments for the convert command.
+#[derive(Args)]
+pub struct ConvertArgs {
+    /// Kubernetes manifest file, or directory of .yaml, .yml and .json
+    /// manifests (can be specified multiple times)
+    #[arg(short = 'f', long = "filename", required = true)]
+    filename: Vec<PathBuf>,
+
+    /// Namespace of objects whose metadata names none (default: "default")
+    #[arg(short, long)]
+    namespace: Option<String>,
+
+    /// Create the namespaces, workloads and services instead of printing
+    /// manifests
+    #[arg(long)]
+    import: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportRequest {
+    objects: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportResponse {
+    namespaces: Vec<String>,
+    workloads: Vec<Value>,
+    created: Vec<CreatedWorkload>,
+    services: Vec<ImportedService>,
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedWorkload {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportedService {
+    name: String,
+    namespace: String,
+    #[serde(default)]
+    cluster_ips: Vec<String>,
+}
+
+/// Execute the convert command.
+pub async fn execute(args: ConvertArgs, api_url: &str, format: OutputFormat) -> anyhow::Result<()> {
+    let mut objects = Vec::new();
+    for file in manifest_files(&args.filename)? {
+        let text = std::fs::read_to_string(&file).map_err(|e| {
+            CliError::invalid_argument(format!("Cannot read {}: {}", file.display(), e))
+        })?;
+        objects.extend(parse_objects(&text, &file.display().to_string())?);
+    }
+    if objects.is_empty() {
+        return Err(CliError::invalid_argument("The manifests hold no objects").into());
+    }
+
+    let client = ApiClient::authenticated(api_url).await?;
+    let path = if args.import {
+        "/api/v1/import/kubernetes"
+    } else {
+        "/api/v1/import/kubernetes?dryRun=true"
+    };
+    let request = ImportRequest {
+        objects,
+        namespace: args.namespace,
+    };
+    let response: ImportResponse = client.post(path, &request).await?;
+    for warning in &response.warnings {
+        output::warn(warning);
+    }
+
+    if !args.import {
+        if !response.services.is_empty() {
+            output::warn(&format!(
+                "{} service(s) are only registered with --import; orch apply does not manage \
+                 services",
+                response.services.len()
+            ));
+        }
+        match format {
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&manifests(
+                    &response.namespaces,
+                    &response.workloads
+                ))?
+            ),
+            _ => print!(
+                "{}",
+                render_yaml(&response.namespaces, &response.workloads)?
+            ),
+        }
+        return Ok(());
+    }
+
+    for workload in &response.created {
+        output::success(&format!(
+            "Workload '{}' created ({})",
+            workload.name, workload.id
+        ));
+    }
+    for service in &response.services {
+        output::success(&format!(
+            "Service '{}/{}' registered at {}",
+            service.namespace,
+            service.name,
+            service.cluster_ips.join(", ")
+        ));
+    }
+    output::info(&format!(
+        "Imported {} namespace(s), {} workload(s) and {} service(s)",
+        response.namespaces.len(),
+        response.created.len(),
+        response.services.len()
+    ));
+    Ok(())
+}
+
+/// Manifest files named by `paths`: files as given, and the .yaml, .yml and
+/// .json files of directories in name order.
+fn manifest_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| matches!(ext, "yaml" | "yml" | "json"))
+            })
+            .collect();
+        entries.sort();
+        files.extend(entries);
+    }
+    Ok(files)
+}
+
+/// The objects of the YAML documents of `text`, read from `source`.
+fn parse_objects(text: &str, source: &str) -> Result<Vec<Value>> {
+    let mut objects = Vec::new();
+    for (index, document) in serde_yaml_ng::Deserializer::from_str(text).enumerate() {
+        let value = Value::deserialize(document).map_err(|e| {
+            CliError::invalid_argument(format!("{} (document {}): {}", source, index + 1, e))
+        })?;
+        match value {
+            // Empty documents, e.g. after a trailing `---`
+            Value::Null => continue,
+            Value::Object(_) => objects.push(value),
+            _ => {
+                return Err(CliError::invalid_argument(format!(
+                    "{} (document {}): expected a mapping",
+                    source,
+                    index + 1
+                )))
+            }
+        }
+    }
+    Ok(objects)
+}
+
+/// `orch apply` manifests of `namespaces` and the create requests of
+/// `workloads`, without the fields left at their defaults.
+fn manifests(namespaces: &[String], workloads: &[Value]) -> Vec<Value> {
+    let namespaces = namespaces
+        .iter()
+        .map(|name| serde_json::json!({ "kind": "Namespace", "name": name }));
+    let workloads = workloads.iter().map(|workload| {
+        let mut manifest = serde_json::Map::new();
+        manifest.insert("kind".to_string(), Value::String("Workload".to_string()));
+        if let Some(Value::Object(fields)) = prune(workload.clone()) {
+            manifest.extend(fields);
+        }
+        Value::Object(manifest)
+    });
+    namespaces.chain(workloads).collect()
+}
+
+/// The manifests as YAML documents, `kind` first.
+fn render_yaml(namespaces: &[String], workloads: &[Value]) -> Result<String> {
+    let mut documents = Vec::new();
+    for mut manifest in manifests(namespaces, workloads) {
+        let kind = manifest
+            .as_object_mut()
+            .and_then(|fields| fields.remove("kind"))
+            .unwrap_or(Value::Null);
+        let fields = serde_yaml_ng::to_string(&manifest)
+            .map_err(|e| CliError::Other(format!("Cannot render manifest: {}", e)))?;
+        documents.push(format!(
+            "kind: {}\n{}",
+            kind.as_str().unwrap_or_default(),
+            fields
+        ));
+    }
+    Ok(documents.join("---\n"))
+}
+
+/// `value` without nulls, empty strings, arrays and objects, recursively.
+fn prune(value: Value) -> Option<Value> {
+    match value {
+        Value::Null => None,
+        Value::String(text) if text.is_empty() => None,
+        Value::Array(items) => {
+            let items: Vec<Value> = items.into_iter().filter_map(prune).collect();
+            (!items.is_empty()).then_some(Value::Array(items))
+        }
+        Value::Object(fields) => {
+            let fields: serde_json::Map<String, Value> = fields
+                .into_iter()
+                .filter_map(|(key, value)| prune(value).map(|value| (key, value)))
+                .collect();
+            (!fields.is_empty()).then_some(Value::Object(fields))
+        }
+        other => Some(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_objects_skips_empty_documents() {
+        let text = "kind: Namespace\nmetadata:\n  name: shop\n---\n---\nkind: Pod\n";
+        let objects = parse_objects(text, "shop.yaml").unwrap();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[1]["kind"], "Pod");
+
+        let err = parse_objects("- a\n- b\n", "list.yaml").unwrap_err();
+        assert!(err.to_string().contains("list.yaml (document 1)"));
+    }
+
+    #[test]
+    fn test_manifests_prune_defaults() {
+        let workload = json!({
+            "name": "web",
+            "namespace": "shop",
+            "replicas": 2,
+            "labels": {},
+            "init_containers": [],
+            "node_selector": null,
+            "containers": [{"name": "web", "image": "nginx", "args": [], "env_vars": {}}],
+        });
+        let manifests = manifests(&["shop".to_string()], std::slice::from_ref(&workload));
+        assert_eq!(manifests[0], json!({"kind": "Namespace", "name": "shop"}));
+        assert_eq!(
+            manifests[1],
+            json!({
+                "kind": "Workload",
+                "name": "web",
+                "namespace": "shop",
+                "replicas": 2,
+                "containers": [{"name": "web", "image": "nginx"}],
+            })
+        );
+
+        let yaml = render_yaml(&[], &[workload]).unwrap();
+        assert!(yaml.starts_with("kind: Workload\n"));
+        assert!(!yaml.contains("labels"));
+    }
+}
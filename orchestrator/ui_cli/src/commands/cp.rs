@@ -0,0 +1,402 @@
+//! Cp command - copy files between the local machine and containers.
+//!
+//! One side is local and the other `WORKLOAD:PATH`, an absolute path in a
+//! running instance of the workload:
+//!
+//! ```text
+//! orch cp ./model.onnx my-workload:/models/        into /models
+//! orch cp ./model.onnx my-workload:/models/a.onnx  as /models/a.onnx
+//! orch cp my-workload:/models ./models             a directory, out
+//! ```
+//!
+//! Files travel as tar archives through `/api/v1/instances/:id/files`, so
+//! the image needs a `tar` binary. A container path ending in `/` is a
+//! directory to copy into, which must exist; otherwise it names the copy.
+
+use std::path::{Component, Path, PathBuf};
+
+use clap::Args;
+use serde::Deserialize;
+
+use crate::client::ApiClient;
+use crate::commands::scale::find_workload_id;
+use crate::error::{CliError, Result};
+use crate::output;
+
+/// Content type of the archives the files endpoint sends and takes.
+const TAR: &str = "application/x-tar";
+
+/// Arguments for the cp command.
+#[derive(Args)]
+pub struct CpArgs {
+    /// File or directory to copy: a local path or WORKLOAD:PATH
+    source: String,
+
+    /// Where to copy it: a local path or WORKLOAD:PATH
+    dest: String,
+
+    /// Container to copy from or to (default: the first main container)
+    #[arg(short, long)]
+    container: Option<String>,
+
+    /// Instance ID or unique ID prefix to use (default: the first running
+    /// instance of the workload)
+    #[arg(long, conflicts_with = "all")]
+    instance: Option<String>,
+
+    /// Copy into every running instance of the workload
+    #[arg(long)]
+    all: bool,
+
+    /// Namespace of the workload (default: look in every namespace)
+    #[arg(short, long)]
+    namespace: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstanceResponse {
+    id: String,
+    status: String,
+}
+
+/// One side of a copy.
+#[derive(Debug, PartialEq)]
+enum Location {
+    Local(PathBuf),
+    Remote { workload: String, path: String },
+}
+
+impl Location {
+    /// Parse `WORKLOAD:PATH`, or a local path. Paths starting with `.` or
+    /// `/`, or with a `/` before the first `:`, are always local.
+    fn parse(s: &str) -> Self {
+        match s.split_once(':') {
+            Some((workload, path))
+                if !workload.is_empty()
+                    && !workload.starts_with('.')
+                    && !workload.contains('/') =>
+            {
+                Location::Remote {
+                    workload: workload.to_string(),
+                    path: path.to_string(),
+                }
+            }
+            _ => Location::Local(PathBuf::from(s)),
+        }
+    }
+}
+
+/// Execute the cp command.
+pub async fn execute(args: CpArgs, api_url: &str) -> anyhow::Result<()> {
+    let (local, workload, path, upload) =
+        match (Location::parse(&args.source), Location::parse(&args.dest)) {
+            (Location::Local(local), Location::Remote { workload, path }) => {
+                (local, workload, path, true)
+            }
+            (Location::Remote { workload, path }, Location::Local(local)) => {
+                (local, workload, path, false)
+            }
+            (Location::Local(_), Location::Local(_)) => {
+                return Err(CliError::invalid_argument(
+                    "One of the paths must be in a container, as WORKLOAD:PATH",
+                )
+                .into())
+            }
+            (Location::Remote { .. }, Location::Remote { .. }) => {
+                return Err(CliError::invalid_argument(
+                    "Copying between containers is not supported; copy through a local path",
+                )
+                .into())
+            }
+        };
+    if !path.starts_with('/') {
+        return Err(CliError::invalid_argument(format!(
+            "Container path '{}' must be absolute",
+            path
+        ))
+        .into());
+    }
+    if args.all && !upload {
+        return Err(
+            CliError::invalid_argument("--all only applies to copies into containers").into(),
+        );
+    }
+
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for cp. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+    let instances = target_instances(&client, &workload, &args).await?;
+
+    if upload {
+        let (dir, name) = upload_target(&path, &local)?;
+        let archive = pack(&local, &name)?;
+        for instance in &instances {
+            let query = files_query(dir, &args);
+            client
+                .put_bytes(
+                    &format!("/api/v1/instances/{}/files?{}", instance, query),
+                    archive.clone(),
+                    TAR,
+                )
+                .await?;
+            output::success(&format!(
+                "Copied {} to {}:{} ({})",
+                local.display(),
+                workload,
+                path,
+                short(instance)
+            ));
+        }
+    } else {
+        let query = files_query(&path, &args);
+        let archive = client
+            .get_bytes(&format!(
+                "/api/v1/instances/{}/files?{}",
+                instances[0], query
+            ))
+            .await?;
+        let files = unpack(&archive, &local)?;
+        output::success(&format!(
+            "Copied {}:{} to {} ({} file(s))",
+            workload,
+            path,
+            local.display(),
+            files
+        ));
+    }
+    Ok(())
+}
+
+/// The instances of `workload` to copy from or to: the one `--instance`
+/// names, every running one with `--all`, else the first running one.
+async fn target_instances(
+    client: &ApiClient,
+    workload: &str,
+    args: &CpArgs,
+) -> Result<Vec<String>> {
+    let id = find_workload_id(client, workload, args.namespace.as_deref()).await?;
+    let response: ListResponse<InstanceResponse> = client
+        .get(&format!("/api/v1/workloads/{}/instances", id))
+        .await?;
+
+    if let Some(prefix) = &args.instance {
+        let matching: Vec<_> = response
+            .items
+            .iter()
+            .filter(|i| i.id.starts_with(prefix.as_str()))
+            .collect();
+        return match matching.len() {
+            0 => Err(CliError::InstanceNotFound(prefix.to_string())),
+            1 => Ok(vec![matching[0].id.clone()]),
+            _ => Err(CliError::invalid_argument(format!(
+                "Ambiguous instance reference '{}', matches {} instances. Use full ID.",
+                prefix,
+                matching.len()
+            ))),
+        };
+    }
+
+    let mut running: Vec<String> = response
+        .items
+        .into_iter()
+        .filter(|i| i.status == "Running")
+        .map(|i| i.id)
+        .collect();
+    if running.is_empty() {
+        return Err(CliError::invalid_argument(format!(
+            "Workload '{}' has no running instances",
+            workload
+        )));
+    }
+    if !args.all {
+        running.truncate(1);
+    }
+    Ok(running)
+}
+
+/// Query string of a copy of `path`.
+fn files_query(path: &str, args: &CpArgs) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    query.append_pair("path", path);
+    if let Some(container) = &args.container {
+        query.append_pair("container", container);
+    }
+    if let Some(namespace) = &args.namespace {
+        query.append_pair("namespace", namespace);
+    }
+    query.finish()
+}
+
+/// The container directory to extract an upload of `local` into, and the
+/// name it gets there.
+fn upload_target<'a>(path: &'a str, local: &Path) -> Result<(&'a str, String)> {
+    if let Some(dir) = path.strip_suffix('/') {
+        let name = local
+            .canonicalize()?
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| {
+                CliError::invalid_argument(format!("Cannot copy {}", local.display()))
+            })?;
+        let dir = if dir.is_empty() { "/" } else { dir };
+        return Ok((dir, name));
+    }
+    match path.rsplit_once('/') {
+        Some(("", name)) => Ok(("/", name.to_string())),
+        Some((dir, name)) => Ok((dir, name.to_string())),
+        None => Err(CliError::invalid_argument(format!(
+            "Container path '{}' must be absolute",
+            path
+        ))),
+    }
+}
+
+/// A tar archive of the file or directory `source`, named `name`.
+fn pack(source: &Path, name: &str) -> Result<Vec<u8>> {
+    let metadata = std::fs::metadata(source).map_err(|e| {
+        CliError::invalid_argument(format!("Cannot read {}: {}", source.display(), e))
+    })?;
+    let mut builder = tar::Builder::new(Vec::new());
+    if metadata.is_dir() {
+        builder.append_dir_all(name, source)?;
+    } else {
+        builder.append_path_with_name(source, name)?;
+    }
+    Ok(builder.into_inner()?)
+}
+
+/// Extract an archive holding one file or directory to `dest`, or into it
+/// when it is an existing directory. Returns the number of files written.
+fn unpack(archive: &[u8], dest: &Path) -> Result<usize> {
+    let into = dest.is_dir();
+    let mut files = 0;
+    let mut archive = tar::Archive::new(archive);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let mut components = path.components();
+        let root = match components.next() {
+            Some(Component::Normal(root)) => root.to_owned(),
+            _ => continue,
+        };
+        let rest = components.as_path();
+        // Entries must stay under the copy
+        if rest
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(CliError::api_error(format!(
+                "Refusing to extract {}",
+                path.display()
+            )));
+        }
+
+        let base = if into {
+            dest.join(&root)
+        } else {
+            dest.to_path_buf()
+        };
+        let target = if rest.as_os_str().is_empty() {
+            base
+        } else {
+            base.join(rest)
+        };
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&target)?;
+        if entry.header().entry_type().is_file() {
+            files += 1;
+        }
+    }
+    Ok(files)
+}
+
+/// The first 8 characters of an id.
+fn short(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_location() {
+        assert_eq!(
+            Location::parse("my-workload:/models/"),
+            Location::Remote {
+                workload: "my-workload".to_string(),
+                path: "/models/".to_string()
+            }
+        );
+        assert_eq!(
+            Location::parse("./model.onnx"),
+            Location::Local(PathBuf::from("./model.onnx"))
+        );
+        assert_eq!(
+            Location::parse("data/a:b"),
+            Location::Local(PathBuf::from("data/a:b"))
+        );
+        assert_eq!(
+            Location::parse("/tmp/a:b"),
+            Location::Local(PathBuf::from("/tmp/a:b"))
+        );
+    }
+
+    #[test]
+    fn test_upload_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("model.onnx");
+        std::fs::write(&model, "weights").unwrap();
+        assert_eq!(
+            upload_target("/models/", &model).unwrap(),
+            ("/models", "model.onnx".to_string())
+        );
+        assert_eq!(
+            upload_target("/models/a.onnx", &model).unwrap(),
+            ("/models", "a.onnx".to_string())
+        );
+        assert_eq!(
+            upload_target("/a.onnx", &model).unwrap(),
+            ("/", "a.onnx".to_string())
+        );
+    }
+
+    #[test]
+    fn test_pack_and_unpack() {
+        let dir = tempfile::tempdir().unwrap();
+        let models = dir.path().join("models");
+        std::fs::create_dir_all(models.join("v1")).unwrap();
+        std::fs::write(models.join("v1").join("model.onnx"), "weights").unwrap();
+        std::fs::write(models.join("labels.txt"), "cat\ndog\n").unwrap();
+        let archive = pack(&models, "models").unwrap();
+
+        // Into an existing directory, under the archived name
+        let out = dir.path().join("out");
+        std::fs::create_dir(&out).unwrap();
+        assert_eq!(unpack(&archive, &out).unwrap(), 2);
+        assert_eq!(
+            std::fs::read_to_string(out.join("models/v1/model.onnx")).unwrap(),
+            "weights"
+        );
+
+        // To a new path, renamed
+        let renamed = dir.path().join("copy");
+        assert_eq!(unpack(&archive, &renamed).unwrap(), 2);
+        assert!(renamed.join("labels.txt").is_file());
+
+        let file = pack(&models.join("labels.txt"), "labels.txt").unwrap();
+        let target = dir.path().join("names.txt");
+        assert_eq!(unpack(&file, &target).unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(target).unwrap(), "cat\ndog\n");
+    }
+}
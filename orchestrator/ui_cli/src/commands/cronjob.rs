@@ -0,0 +1,250 @@
+//! Cron job command - create and manage jobs run on a cron schedule.
+//!
+//! Cron jobs are kept and run by the control-plane leader, so writes sent to
+//! another node are refused.
+
+use chrono::{DateTime, Utc};
+use clap::{Args, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::error::CliError;
+use crate::output::{self, print_data, print_item};
+use crate::OutputFormat;
+
+/// Arguments for the cronjob command.
+#[derive(Args)]
+pub struct CronJobArgs {
+    #[command(subcommand)]
+    command: CronJobCommand,
+}
+
+#[derive(Subcommand)]
+enum CronJobCommand {
+    /// Create a cron job
+    Create {
+        /// Cron job name, also given to the jobs it creates
+        name: String,
+
+        /// Five-field cron expression in UTC, e.g. "0 2 * * *"
+        #[arg(long)]
+        schedule: String,
+
+        /// Container image each job runs
+        #[arg(short, long)]
+        image: String,
+
+        /// Namespace of the jobs (default: "default")
+        #[arg(long)]
+        namespace: Option<String>,
+
+        /// What to do when a job is still running at the next scheduled time
+        #[arg(long, value_enum, default_value = "allow")]
+        concurrency_policy: ConcurrencyPolicy,
+
+        /// Create the cron job suspended
+        #[arg(long)]
+        suspend: bool,
+
+        /// Command run in the container, overriding the image's entrypoint
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
+    /// List cron jobs
+    List,
+    /// Show a cron job and its recent jobs
+    Get {
+        /// Cron job ID
+        id: String,
+    },
+    /// Delete a cron job and the jobs it created
+    Delete {
+        /// Cron job ID
+        id: String,
+    },
+    /// Stop a cron job from creating jobs
+    Suspend {
+        /// Cron job ID
+        id: String,
+    },
+    /// Let a suspended cron job create jobs again
+    Resume {
+        /// Cron job ID
+        id: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ConcurrencyPolicy {
+    /// Run jobs concurrently
+    Allow,
+    /// Skip a run while the previous job is still running
+    Forbid,
+    /// Stop the running job and start the new one
+    Replace,
+}
+
+impl ConcurrencyPolicy {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ConcurrencyPolicy::Allow => "Allow",
+            ConcurrencyPolicy::Forbid => "Forbid",
+            ConcurrencyPolicy::Replace => "Replace",
+        }
+    }
+}
+
+/// Create cron job request - matches API's CreateCronJobRequest.
+#[derive(Debug, Serialize)]
+struct CreateCronJobRequest {
+    name: String,
+    schedule: String,
+    template: CreateWorkloadRequest,
+    concurrency_policy: String,
+    suspend: bool,
+}
+
+/// Job template - matches API's CreateWorkloadRequest.
+#[derive(Debug, Serialize)]
+struct CreateWorkloadRequest {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+    replicas: u32,
+    containers: Vec<ContainerConfigRequest>,
+}
+
+/// Container configuration - matches API's ContainerConfigRequest.
+#[derive(Debug, Serialize)]
+struct ContainerConfigRequest {
+    name: String,
+    image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<Vec<String>>,
+}
+
+/// Cron job response from API.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct CronJobResponse {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Schedule")]
+    schedule: String,
+    #[tabled(rename = "Concurrency")]
+    concurrency_policy: String,
+    #[tabled(rename = "Suspended")]
+    suspend: bool,
+    #[tabled(skip)]
+    created_at: DateTime<Utc>,
+    #[tabled(rename = "Last Schedule", display_with = "display_time")]
+    #[serde(default)]
+    last_schedule_time: Option<DateTime<Utc>>,
+    #[tabled(skip)]
+    #[serde(default)]
+    jobs: Vec<CronJobRunResponse>,
+}
+
+/// Job created by a cron job, from API.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct CronJobRunResponse {
+    #[tabled(rename = "Job")]
+    name: String,
+    #[tabled(rename = "Workload")]
+    workload_id: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Scheduled", display_with = "display_time")]
+    #[serde(default)]
+    scheduled_time: Option<DateTime<Utc>>,
+    #[tabled(rename = "Completed", display_with = "display_time")]
+    #[serde(default)]
+    completed_at: Option<DateTime<Utc>>,
+}
+
+fn display_time(time: &Option<DateTime<Utc>>) -> String {
+    time.map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Execute the cronjob command.
+pub async fn execute(args: CronJobArgs, api_url: &str, format: OutputFormat) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for cron job operations. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        CronJobCommand::Create {
+            name,
+            schedule,
+            image,
+            namespace,
+            concurrency_policy,
+            suspend,
+            command,
+        } => {
+            let request = CreateCronJobRequest {
+                name: name.clone(),
+                schedule,
+                template: CreateWorkloadRequest {
+                    name: name.clone(),
+                    namespace,
+                    replicas: 1,
+                    containers: vec![ContainerConfigRequest {
+                        name,
+                        image,
+                        command: (!command.is_empty()).then_some(command),
+                    }],
+                },
+                concurrency_policy: concurrency_policy.as_str().to_string(),
+                suspend,
+            };
+            let created: CronJobResponse = client.post("/api/v1/cronjobs", &request).await?;
+            output::success(&format!(
+                "Cron job {} created ({})",
+                created.name, created.id
+            ));
+            Ok(())
+        }
+        CronJobCommand::List => {
+            let cron_jobs: Vec<CronJobResponse> = client.get("/api/v1/cronjobs").await?;
+            print_data(&cron_jobs, format)?;
+            Ok(())
+        }
+        CronJobCommand::Get { id } => {
+            let cron_job: CronJobResponse = client.get(&format!("/api/v1/cronjobs/{}", id)).await?;
+            print_item(&cron_job, format)?;
+            if matches!(format, OutputFormat::Table | OutputFormat::Wide) {
+                output::section("Jobs");
+                print_data(&cron_job.jobs, format)?;
+            }
+            Ok(())
+        }
+        CronJobCommand::Delete { id } => {
+            client.delete(&format!("/api/v1/cronjobs/{}", id)).await?;
+            output::success(&format!("Cron job {} and its jobs deleted", id));
+            Ok(())
+        }
+        CronJobCommand::Suspend { id } => {
+            let _: CronJobResponse = client
+                .post(&format!("/api/v1/cronjobs/{}/suspend", id), &())
+                .await?;
+            output::success(&format!("Cron job {} suspended", id));
+            Ok(())
+        }
+        CronJobCommand::Resume { id } => {
+            let _: CronJobResponse = client
+                .post(&format!("/api/v1/cronjobs/{}/resume", id), &())
+                .await?;
+            output::success(&format!("Cron job {} resumed", id));
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Debug command - troubleshoot instances whose images have no shell.
+//!
+//! `orch debug instance` starts a throwaway container from an image with
+//! tools, such as `busybox`, that shares the PID, network, IPC and UTS
+//! namespaces of a container of the instance, and attaches to it like
+//! `orch exec -it`. The target's processes show up in `ps`, its ports on
+//! `localhost` and its files under `/proc/1/root`. The container is removed
+//! when the session ends.
+
+use std::io::IsTerminal;
+
+use clap::{Args, Subcommand};
+
+use crate::client::ApiClient;
+use crate::commands::exec::attach;
+use crate::commands::instance::find_instance_id;
+use crate::error::CliError;
+
+/// Arguments for the debug command.
+#[derive(Args)]
+pub struct DebugArgs {
+    #[command(subcommand)]
+    command: DebugCommand,
+}
+
+#[derive(Subcommand)]
+enum DebugCommand {
+    /// Attach a debug container to a running instance
+    Instance(DebugInstanceArgs),
+}
+
+#[derive(Args)]
+struct DebugInstanceArgs {
+    /// Instance ID or unique ID prefix
+    instance: String,
+
+    /// Image of the debug container
+    #[arg(long, default_value = "busybox")]
+    image: String,
+
+    /// Container whose namespaces to share (default: the first main
+    /// container)
+    #[arg(short, long)]
+    container: Option<String>,
+
+    /// Namespace of the instance (default: look in every namespace)
+    #[arg(long)]
+    namespace: Option<String>,
+
+    /// Command to run instead of the image's shell, after `--`
+    #[arg(last = true)]
+    command: Vec<String>,
+}
+
+/// Execute the debug command.
+pub async fn execute(args: DebugArgs, api_url: &str) -> anyhow::Result<()> {
+    match args.command {
+        DebugCommand::Instance(args) => debug_instance(args, api_url).await,
+    }
+}
+
+async fn debug_instance(args: DebugInstanceArgs, api_url: &str) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for debug. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+    let id = find_instance_id(&client, &args.instance, args.namespace.as_deref()).await?;
+    let path = format!("/api/v1/instances/{}/debug", id);
+    let tty = std::io::stdin().is_terminal();
+    attach(&client, &path, &debug_query(&args, tty), true, tty).await
+}
+
+/// Query string of a debug container, with one `cmd` per argument.
+fn debug_query(args: &DebugInstanceArgs, tty: bool) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    query.append_pair("image", &args.image);
+    for arg in &args.command {
+        query.append_pair("cmd", arg);
+    }
+    if let Some(container) = &args.container {
+        query.append_pair("container", container);
+    }
+    if tty {
+        query.append_pair("tty", "true");
+    }
+    if let Some(namespace) = &args.namespace {
+        query.append_pair("namespace", namespace);
+    }
+    query.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        debug: DebugArgs,
+    }
+
+    #[test]
+    fn test_debug_query() {
+        let cli = Cli::parse_from(["orch", "instance", "web-1", "-c", "web"]);
+        let DebugCommand::Instance(args) = cli.debug.command;
+        assert_eq!(
+            debug_query(&args, true),
+            "image=busybox&container=web&tty=true"
+        );
+
+        let cli = Cli::parse_from([
+            "orch",
+            "instance",
+            "web-1",
+            "--image",
+            "nicolaka/netshoot",
+            "--",
+            "ss",
+            "-tlnp",
+        ]);
+        let DebugCommand::Instance(args) = cli.debug.command;
+        assert_eq!(
+            debug_query(&args, false),
+            "image=nicolaka%2Fnetshoot&cmd=ss&cmd=-tlnp"
+        );
+    }
+}
@@ -1,24 +1,45 @@
-//! Deploy command - deploy a new workload.
-
-use clap::Args;
+//! Deploy command - deploy a new workload, the services of a compose file, or
+//! the manifests rendered from a template.
+//!
+//! With `--wait`, a new workload is watched until all its replicas run and are
+//! ready, with a spinner per replica. An instance that fails, or a timeout,
+//! ends the command with an error after showing the instance's events and
+//! last log lines.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use clap::{ArgGroup, Args};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
 use tabled::Tabled;
 
 use crate::client::ApiClient;
-use crate::error::CliError;
+use crate::commands::render::{self, ValuesArgs};
+use crate::commands::{apply, compose};
+use crate::error::{CliError, Result};
 use crate::output::{self, print_item};
 use crate::OutputFormat;
 
+/// Log lines shown of an instance that fails a `--wait`.
+const LOG_TAIL: usize = 20;
+
+/// How often spinners advance.
+const SPINNER_TICK: Duration = Duration::from_millis(100);
+
 /// Arguments for the deploy command.
 #[derive(Args)]
+#[command(group(ArgGroup::new("source").args(["compose", "template"])))]
 pub struct DeployArgs {
     /// Workload name
-    #[arg(short, long)]
-    name: String,
+    #[arg(short, long, required_unless_present_any = ["compose", "template"])]
+    name: Option<String>,
 
     /// Container image
-    #[arg(short, long)]
-    image: String,
+    #[arg(short, long, required_unless_present_any = ["compose", "template"])]
+    image: Option<String>,
 
     /// Number of replicas
     #[arg(short, long, default_value = "1")]
@@ -50,6 +71,49 @@ pub struct DeployArgs {
     /// Example: --label app=nginx --label tier=frontend
     #[arg(short, long, value_parser = parse_env_var)]
     label: Vec<(String, String)>,
+
+    /// Namespace to deploy into (default: "default")
+    #[arg(long)]
+    namespace: Option<String>,
+
+    /// Deploy every service of a docker-compose file as a workload, instead
+    /// of a single workload
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["name", "image", "port", "env", "label"]
+    )]
+    compose: Option<PathBuf>,
+
+    /// Render a template file or chart directory, as `orch render` does, and
+    /// apply the manifests, instead of deploying a single workload
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["name", "image", "port", "env", "label"]
+    )]
+    template: Option<PathBuf>,
+
+    #[command(flatten)]
+    values: ValuesArgs,
+
+    /// With --compose, seconds to wait for a service's dependencies to run
+    #[arg(long, default_value = "120", requires = "compose")]
+    wait_timeout: u64,
+
+    /// With --compose or --template, show what would be deployed without
+    /// deploying it
+    #[arg(long, requires = "source")]
+    dry_run: bool,
+
+    /// Wait until all replicas are running and ready, and fail if an instance
+    /// fails
+    #[arg(long, conflicts_with = "source")]
+    wait: bool,
+
+    /// With --wait, how long to wait (e.g. 90s, 5m, 1h30m)
+    #[arg(long, default_value = "5m", requires = "wait", value_parser = parse_duration)]
+    timeout: Duration,
 }
 
 /// Parsed port specification.
@@ -90,6 +154,36 @@ fn parse_port_mapping(s: &str) -> std::result::Result<PortSpec, String> {
     }
 }
 
+/// Parse a duration such as `300`, `90s`, `5m` or `1h30m`; plain numbers are
+/// seconds.
+pub fn parse_duration(s: &str) -> std::result::Result<Duration, String> {
+    if let Ok(secs) = s.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    let invalid = || format!("Invalid duration '{}', expected e.g. 90s, 5m or 1h30m", s);
+    let mut total = 0;
+    let mut number = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        let value: u64 = number.parse().map_err(|_| invalid())?;
+        total += value * unit;
+        number.clear();
+    }
+    if !number.is_empty() || s.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
 fn parse_env_var(s: &str) -> std::result::Result<(String, String), String> {
     let parts: Vec<&str> = s.splitn(2, '=').collect();
     if parts.len() != 2 {
@@ -102,6 +196,8 @@ fn parse_env_var(s: &str) -> std::result::Result<(String, String), String> {
 #[derive(Debug, Serialize)]
 struct CreateWorkloadRequest {
     name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
     replicas: u32,
     #[serde(default)]
     labels: std::collections::HashMap<String, String>,
@@ -156,8 +252,94 @@ struct WorkloadResponse {
     replicas: u32,
 }
 
+/// Instance response from API.
+#[derive(Debug, Deserialize)]
+struct InstanceResponse {
+    id: String,
+    node_id: String,
+    status: String,
+    /// Latest readiness probe verdict, if the instance has been probed.
+    #[serde(default)]
+    ready: Option<bool>,
+}
+
+impl InstanceResponse {
+    /// Running and, if it has a readiness probe, ready.
+    fn is_ready(&self) -> bool {
+        self.status == "Running" && self.ready != Some(false)
+    }
+
+    fn has_failed(&self) -> bool {
+        matches!(self.status.as_str(), "Failed" | "CrashLoopBackOff")
+    }
+
+    fn summary(&self) -> String {
+        let readiness = match (self.status.as_str(), self.ready) {
+            ("Running", Some(false)) => ", not ready",
+            ("Running", _) => ", ready",
+            _ => "",
+        };
+        format!(
+            "instance {} on node {}: {}{}",
+            short(&self.id),
+            short(&self.node_id),
+            self.status,
+            readiness
+        )
+    }
+}
+
+/// List response wrapper.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+/// Event response from API.
+#[derive(Debug, Deserialize)]
+struct EventResponse {
+    #[serde(rename = "type")]
+    event_type: String,
+    reason: String,
+    message: String,
+    last_timestamp: DateTime<Utc>,
+}
+
+/// Log response from API.
+#[derive(Debug, Deserialize)]
+struct LogsResponse {
+    logs: String,
+}
+
 /// Execute the deploy command.
 pub async fn execute(args: DeployArgs, api_url: &str, format: OutputFormat) -> anyhow::Result<()> {
+    if let Some(path) = &args.compose {
+        let wait_timeout = Duration::from_secs(args.wait_timeout);
+        return compose::deploy(
+            path,
+            api_url,
+            args.namespace,
+            wait_timeout,
+            args.dry_run,
+            format,
+        )
+        .await;
+    }
+    if let Some(template) = &args.template {
+        let text = render::render(template, &args.values)?;
+        return apply::apply_text(
+            &text,
+            &template.display().to_string(),
+            args.namespace.as_deref(),
+            api_url,
+            args.dry_run,
+        )
+        .await;
+    }
+    if !args.values.files.is_empty() || !args.values.set.is_empty() {
+        return Err(CliError::invalid_argument("--values and --set require --template").into());
+    }
+
     let client = ApiClient::authenticated(api_url).await.map_err(|e| {
         CliError::config_error(format!(
             "Authentication required for deploy. Run 'orch init' first. Error: {}",
@@ -165,7 +347,12 @@ pub async fn execute(args: DeployArgs, api_url: &str, format: OutputFormat) -> a
         ))
     })?;
 
-    output::info(&format!("Deploying workload '{}'...", args.name));
+    // Both are required unless --compose or --template is given
+    let (Some(name), Some(image)) = (args.name, args.image) else {
+        return Err(CliError::invalid_argument("--name and --image are required").into());
+    };
+
+    output::info(&format!("Deploying workload '{}'...", name));
 
     // Build the request
     let mut labels = std::collections::HashMap::new();
@@ -206,12 +393,13 @@ pub async fn execute(args: DeployArgs, api_url: &str, format: OutputFormat) -> a
     let cpu_cores = args.cpu as f32 / 1000.0;
 
     let request = CreateWorkloadRequest {
-        name: args.name.clone(),
+        name: name.clone(),
+        namespace: args.namespace.clone(),
         replicas: args.replicas,
         labels,
         containers: vec![ContainerConfigRequest {
-            name: args.name.clone(),
-            image: args.image.clone(),
+            name: name.clone(),
+            image: image.clone(),
             command: None,
             args: None,
             env_vars: env,
@@ -227,13 +415,17 @@ pub async fn execute(args: DeployArgs, api_url: &str, format: OutputFormat) -> a
     // Send the request
     let response: WorkloadResponse = client.post("/api/v1/workloads", &request).await?;
 
-    output::success(&format!("Workload '{}' deployed successfully!", args.name));
+    output::success(&format!("Workload '{}' deployed successfully!", name));
     print_item(&response, format)?;
 
+    if args.wait {
+        return wait_for_rollout(&client, &response, args.timeout).await;
+    }
+
     // Build informative status message
     let mut info_parts = vec![format!(
         "Scheduling {} replica(s) with image '{}'",
-        args.replicas, args.image
+        args.replicas, image
     )];
 
     if port_count > 0 {
@@ -251,3 +443,196 @@ pub async fn execute(args: DeployArgs, api_url: &str, format: OutputFormat) -> a
 
     Ok(())
 }
+
+/// Watch the instances of `workload` until all its replicas are ready.
+async fn wait_for_rollout(
+    client: &ApiClient,
+    workload: &WorkloadResponse,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let path = format!("/api/v1/workloads/{}/instances", workload.id);
+    let mut stream = client.watch(&path).await?;
+
+    let progress = MultiProgress::new();
+    let overall = progress.add(spinner());
+    let mut bars: BTreeMap<String, ProgressBar> = BTreeMap::new();
+    let mut instances: BTreeMap<String, InstanceResponse> = BTreeMap::new();
+    let deadline = tokio::time::sleep(timeout);
+    tokio::pin!(deadline);
+
+    let failed = loop {
+        let ready = instances.values().filter(|i| i.is_ready()).count();
+        overall.set_message(format!(
+            "Waiting for '{}': {}/{} replicas ready",
+            workload.name, ready, workload.replicas
+        ));
+        if ready >= workload.replicas as usize {
+            overall.finish_with_message(format!(
+                "Workload '{}' is ready: {}/{} replicas",
+                workload.name, ready, workload.replicas
+            ));
+            return Ok(());
+        }
+
+        tokio::select! {
+            event = stream.next::<InstanceResponse>() => {
+                let Some(event) = event? else {
+                    overall.abandon();
+                    return Err(CliError::api_error(
+                        "The server closed the watch before the workload was ready",
+                    )
+                    .into());
+                };
+                let instance = event.object;
+                if event.event_type == "DELETED" {
+                    if let Some(bar) = bars.remove(&instance.id) {
+                        bar.finish_and_clear();
+                    }
+                    instances.remove(&instance.id);
+                    continue;
+                }
+                let bar = bars
+                    .entry(instance.id.clone())
+                    .or_insert_with(|| progress.add(spinner()));
+                if instance.is_ready() {
+                    bar.finish_with_message(format!("✓ {}", instance.summary()));
+                } else if instance.has_failed() {
+                    bar.abandon_with_message(format!("✗ {}", instance.summary()));
+                } else {
+                    bar.set_message(instance.summary());
+                }
+                let failed = instance.has_failed();
+                let id = instance.id.clone();
+                instances.insert(id.clone(), instance);
+                if failed {
+                    break Some(id);
+                }
+            }
+            () = &mut deadline => break None,
+        }
+    };
+
+    overall.abandon();
+    for bar in bars.values() {
+        bar.abandon();
+    }
+    let (instance, error) = match failed {
+        Some(id) => {
+            let instance = instances.get(&id);
+            let error = format!(
+                "Instance {} of workload '{}' failed",
+                short(&id),
+                workload.name
+            );
+            (instance, error)
+        }
+        None => {
+            let error = format!(
+                "Workload '{}' was not ready within {}s",
+                workload.name,
+                timeout.as_secs()
+            );
+            (instances.values().find(|i| !i.is_ready()), error)
+        }
+    };
+    if let Some(instance) = instance {
+        show_instance(client, &workload.id, instance).await;
+    }
+    Err(CliError::Other(error).into())
+}
+
+/// Print the events and last log lines of `instance`, as far as the server
+/// has them.
+async fn show_instance(client: &ApiClient, workload_id: &str, instance: &InstanceResponse) {
+    output::section(&format!("Instance {}", short(&instance.id)));
+    println!("  {}", instance.summary());
+
+    let path = format!("/api/v1/events?involvedObject={}", instance.id);
+    if let Ok(mut events) = client.get::<ListResponse<EventResponse>>(&path).await {
+        events.items.sort_by_key(|event| event.last_timestamp);
+        if !events.items.is_empty() {
+            output::section("Events");
+        }
+        for event in events.items {
+            println!(
+                "  {}  {:<8} {:<20} {}",
+                event.last_timestamp.format("%H:%M:%S"),
+                event.event_type,
+                event.reason,
+                event.message
+            );
+        }
+    }
+
+    let path = format!(
+        "/api/v1/workloads/{}/instances/{}/logs?tail={}",
+        workload_id, instance.id, LOG_TAIL
+    );
+    match fetch_logs(client, &path).await {
+        Ok(logs) if logs.is_empty() => output::info("No logs"),
+        Ok(logs) => {
+            output::section(&format!("Last {} log lines", LOG_TAIL));
+            for line in logs.lines() {
+                println!("  {}", line);
+            }
+        }
+        Err(e) => output::warn(&format!("Failed to fetch logs: {}", e)),
+    }
+}
+
+async fn fetch_logs(client: &ApiClient, path: &str) -> Result<String> {
+    let response: LogsResponse = client.get(path).await?;
+    Ok(response.logs)
+}
+
+fn spinner() -> ProgressBar {
+    let bar = ProgressBar::new_spinner();
+    bar.set_style(
+        ProgressStyle::with_template("{spinner:.cyan} {msg}")
+            .expect("valid template")
+            .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ "),
+    );
+    bar.enable_steady_tick(SPINNER_TICK);
+    bar
+}
+
+fn short(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("300"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("5").is_ok());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("5d").is_err());
+        assert!(parse_duration("1h30").is_err());
+    }
+
+    #[test]
+    fn test_instance_readiness() {
+        let instance = |status: &str, ready: Option<bool>| InstanceResponse {
+            id: "0123456789".to_string(),
+            node_id: "node0000-1111".to_string(),
+            status: status.to_string(),
+            ready,
+        };
+        assert!(instance("Running", None).is_ready());
+        assert!(instance("Running", Some(true)).is_ready());
+        assert!(!instance("Running", Some(false)).is_ready());
+        assert!(!instance("Pending", None).is_ready());
+        assert!(instance("CrashLoopBackOff", None).has_failed());
+        assert_eq!(
+            instance("Running", Some(false)).summary(),
+            "instance 01234567 on node node0000: Running, not ready"
+        );
+    }
+}
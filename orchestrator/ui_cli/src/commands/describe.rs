@@ -0,0 +1,1265 @@
+//! Describe command - show a workload, instance or node in detail: spec,
+//! current status, containers, placement, probes and recent events.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use clap::{Args, Subcommand};
+use clap_complete::engine::ArgValueCandidates;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+use url::Url;
+use user_config::{LlmProvider, UserConfig};
+
+use crate::client::ApiClient;
+use crate::commands::completion;
+use crate::error::{CliError, Result};
+use crate::output::{self, print_data};
+use crate::OutputFormat;
+
+/// How long to wait for the LLM provider to answer.
+const LLM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Instructions sent ahead of the workload description.
+const SUMMARY_INSTRUCTIONS: &str = "You explain the state of a workload running on a container \
+     orchestrator to an ML engineer who is not an infrastructure specialist. Given the JSON \
+     description below, write one short paragraph of plain English: whether the workload is \
+     healthy, how many replicas are running against how many were asked for, and which containers \
+     are restarting or crash-looping and what their exit codes and recent events suggest. Avoid \
+     jargon and do not invent facts that are not in the description.";
+
+/// Arguments for the describe command.
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct DescribeArgs {
+    #[command(subcommand)]
+    command: Option<DescribeCommand>,
+
+    /// Workload ID or name; short for `describe workload`
+    #[arg(add = ArgValueCandidates::new(completion::workload_names))]
+    workload: Option<String>,
+
+    #[command(flatten)]
+    options: WorkloadOptions,
+}
+
+#[derive(Subcommand)]
+enum DescribeCommand {
+    /// Spec, containers, placement, instances, restart history and events of
+    /// a workload
+    Workload {
+        /// Workload ID or name
+        #[arg(add = ArgValueCandidates::new(completion::workload_names))]
+        workload: String,
+
+        #[command(flatten)]
+        options: WorkloadOptions,
+    },
+    /// Status, readiness, containers and events of an instance
+    Instance {
+        /// Instance ID or unique ID prefix
+        instance: String,
+
+        /// Namespace of the instance (default: look in every namespace)
+        #[arg(long)]
+        namespace: Option<String>,
+    },
+    /// Capacity, allocated resources, instances and events of a node
+    Node {
+        /// Node ID or unique ID prefix
+        #[arg(add = ArgValueCandidates::new(completion::node_ids))]
+        node: String,
+    },
+}
+
+#[derive(Args)]
+struct WorkloadOptions {
+    /// Namespace of the workload (default: look in every namespace)
+    #[arg(long)]
+    namespace: Option<String>,
+
+    /// Append a plain-English summary written by the LLM provider configured
+    /// in settings.toml; sends the description to that provider
+    #[arg(long)]
+    ai: bool,
+}
+
+/// Generic list response wrapper from API.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+/// Workload response from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct WorkloadResponse {
+    id: String,
+    name: String,
+    #[serde(default)]
+    namespace: String,
+    replicas: u32,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+    containers: Vec<ContainerResponse>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    init_containers: Vec<ContainerResponse>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sidecars: Vec<ContainerResponse>,
+    /// Kept as the API sends it; every rule is shown as is.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    placement: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    restart_policy: RestartPolicyResponse,
+}
+
+/// Container of a workload from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct ContainerResponse {
+    name: String,
+    image: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    command: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    args: Option<Vec<String>>,
+    /// Values may be secrets, so only names are shown and the map is never
+    /// printed or sent to the LLM provider.
+    #[serde(default, skip_serializing)]
+    env_vars: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ports: Vec<PortResponse>,
+    #[serde(default)]
+    resource_requests: ResourcesResponse,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    readiness_probe: Option<ProbeResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    termination_grace_period_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pre_stop: Option<LifecycleHandlerResponse>,
+}
+
+/// Port mapping of a container from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct PortResponse {
+    container_port: u16,
+    #[serde(default)]
+    host_port: Option<u16>,
+    protocol: String,
+}
+
+/// Resources requested by a container or offered by a node from API.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ResourcesResponse {
+    cpu_cores: f32,
+    memory_mb: u64,
+    #[serde(default)]
+    disk_mb: u64,
+}
+
+/// Readiness probe of a container from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct ProbeResponse {
+    action: ProbeActionResponse,
+    #[serde(default)]
+    initial_delay_seconds: u32,
+    period_seconds: u32,
+    timeout_seconds: u32,
+    success_threshold: u32,
+    failure_threshold: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ProbeActionResponse {
+    TcpSocket { port: u16 },
+    HttpGet { port: u16, path: String },
+}
+
+/// Hook run against a container from API.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum LifecycleHandlerResponse {
+    Exec { command: Vec<String> },
+    HttpGet { port: u16, path: String },
+}
+
+/// Restart policy of a workload from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct RestartPolicyResponse {
+    mode: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fatal_exit_codes: Vec<i32>,
+}
+
+impl Default for RestartPolicyResponse {
+    fn default() -> Self {
+        Self {
+            mode: "Always".to_string(),
+            fatal_exit_codes: Vec::new(),
+        }
+    }
+}
+
+/// Instance response from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct InstanceResponse {
+    id: String,
+    #[serde(default)]
+    workload_id: String,
+    node_id: String,
+    status: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    container_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ip_addresses: Vec<String>,
+    #[serde(default)]
+    restarts: Vec<RestartResponse>,
+    #[serde(default)]
+    namespace: String,
+    /// Latest readiness probe verdict, if the instance has been probed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ready: Option<bool>,
+}
+
+/// Restart history of one container from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct RestartResponse {
+    container_name: String,
+    restart_count: u32,
+    consecutive_failures: u32,
+    last_exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    back_off_remaining_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_termination: Option<TerminationResponse>,
+}
+
+/// How a container last exited, from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct TerminationResponse {
+    reason: String,
+    exit_code: Option<i32>,
+    finished_at: DateTime<Utc>,
+}
+
+/// Node response from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct NodeResponse {
+    id: String,
+    address: String,
+    status: String,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+    resources_capacity: ResourcesResponse,
+    resources_allocatable: ResourcesResponse,
+    #[serde(default)]
+    unschedulable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image_pulls: Option<ImagePullsResponse>,
+}
+
+/// Image pulls of a node from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct ImagePullsResponse {
+    active: usize,
+    queued_interactive: usize,
+    queued_batch: usize,
+    max_concurrent_pulls: usize,
+}
+
+/// Event about the workload or one of its instances from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct EventResponse {
+    involved_object: EventObject,
+    #[serde(rename = "type")]
+    event_type: String,
+    reason: String,
+    message: String,
+    count: u32,
+    last_timestamp: DateTime<Utc>,
+}
+
+/// The object an event is about.
+#[derive(Debug, Serialize, Deserialize)]
+struct EventObject {
+    kind: String,
+    id: String,
+}
+
+/// Everything shown by describe workload.
+#[derive(Debug, Serialize)]
+struct Description {
+    workload: WorkloadResponse,
+    running: usize,
+    instances: Vec<InstanceResponse>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    events: Vec<EventResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+}
+
+/// Everything shown by describe instance.
+#[derive(Debug, Serialize)]
+struct InstanceDescription {
+    instance: InstanceResponse,
+    /// Absent if the workload was deleted while the instance stops.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workload: Option<WorkloadResponse>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    events: Vec<EventResponse>,
+}
+
+/// Everything shown by describe node.
+#[derive(Debug, Serialize)]
+struct NodeDescription {
+    node: NodeResponse,
+    /// Resources requested by the instances on the node.
+    allocated: ResourcesResponse,
+    instances: Vec<NodeInstance>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    events: Vec<EventResponse>,
+}
+
+/// An instance on a described node.
+#[derive(Debug, Serialize, Tabled)]
+struct NodeInstance {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Workload")]
+    workload: String,
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "CPU")]
+    cpu_cores: f32,
+    #[tabled(rename = "Memory (MB)")]
+    memory_mb: u64,
+}
+
+/// Display-friendly instance for table output.
+#[derive(Debug, Serialize, Tabled)]
+struct InstanceDisplay {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Node")]
+    node: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Ready")]
+    ready: String,
+    #[tabled(rename = "IP")]
+    ip: String,
+    #[tabled(rename = "Restarts")]
+    restarts: u32,
+}
+
+impl From<&InstanceResponse> for InstanceDisplay {
+    fn from(instance: &InstanceResponse) -> Self {
+        Self {
+            id: short(&instance.id),
+            node: short(&instance.node_id),
+            status: instance.status.clone(),
+            ready: readiness(instance.ready).to_string(),
+            ip: instance
+                .ip_addresses
+                .first()
+                .cloned()
+                .unwrap_or_else(|| "-".to_string()),
+            restarts: instance.restarts.iter().map(|r| r.restart_count).sum(),
+        }
+    }
+}
+
+/// Display-friendly event for table output.
+#[derive(Debug, Serialize, Tabled)]
+struct EventDisplay {
+    #[tabled(rename = "Type")]
+    event_type: String,
+    #[tabled(rename = "Reason")]
+    reason: String,
+    #[tabled(rename = "Object")]
+    object: String,
+    #[tabled(rename = "Last Seen")]
+    last_seen: String,
+    #[tabled(rename = "Count")]
+    count: u32,
+    #[tabled(rename = "Message")]
+    message: String,
+}
+
+impl From<&EventResponse> for EventDisplay {
+    fn from(event: &EventResponse) -> Self {
+        Self {
+            event_type: event.event_type.clone(),
+            reason: event.reason.clone(),
+            object: format!(
+                "{}/{}",
+                event.involved_object.kind.to_lowercase(),
+                short(&event.involved_object.id)
+            ),
+            last_seen: format!("{} ago", age(Utc::now() - event.last_timestamp)),
+            count: event.count,
+            message: event.message.clone(),
+        }
+    }
+}
+
+fn short(id: &str) -> String {
+    id.chars().take(8).collect()
+}
+
+/// Largest whole unit of `elapsed`, e.g. `5m`.
+fn age(elapsed: chrono::Duration) -> String {
+    let secs = elapsed.num_seconds().max(0);
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+/// The readiness probe verdict as shown, `-` when there is none.
+fn readiness(ready: Option<bool>) -> &'static str {
+    match ready {
+        Some(true) => "True",
+        Some(false) => "False",
+        None => "-",
+    }
+}
+
+/// A port as `8080/TCP`, or `8080:80/TCP` when bound on the host.
+fn port(port: &PortResponse) -> String {
+    match port.host_port {
+        Some(host) => format!("{}:{}/{}", host, port.container_port, port.protocol),
+        None => format!("{}/{}", port.container_port, port.protocol),
+    }
+}
+
+/// A probe in kubectl's one-line form, e.g.
+/// `http-get :8080/healthz delay=0s timeout=1s period=10s #success=1 #failure=3`.
+fn probe(probe: &ProbeResponse) -> String {
+    let action = match &probe.action {
+        ProbeActionResponse::TcpSocket { port } => format!("tcp-socket :{}", port),
+        ProbeActionResponse::HttpGet { port, path } => format!("http-get :{}{}", port, path),
+    };
+    format!(
+        "{} delay={}s timeout={}s period={}s #success={} #failure={}",
+        action,
+        probe.initial_delay_seconds,
+        probe.timeout_seconds,
+        probe.period_seconds,
+        probe.success_threshold,
+        probe.failure_threshold
+    )
+}
+
+fn hook(hook: &LifecycleHandlerResponse) -> String {
+    match hook {
+        LifecycleHandlerResponse::Exec { command } => format!("exec {}", command.join(" ")),
+        LifecycleHandlerResponse::HttpGet { port, path } => format!("http-get :{}{}", port, path),
+    }
+}
+
+/// `labels` as sorted `key=value` pairs.
+fn labels(labels: &HashMap<String, String>) -> String {
+    let mut pairs: Vec<String> = labels.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    pairs.sort();
+    pairs.join(", ")
+}
+
+/// Print a `Name:  value` line at `indent`.
+fn field(indent: usize, name: &str, value: impl std::fmt::Display) {
+    println!(
+        "{:indent$}{} {}",
+        "",
+        format!("{:<16}", format!("{}:", name)).dimmed(),
+        value,
+        indent = indent
+    );
+}
+
+/// Total requests of the containers of `workload`.
+fn requests(workload: &WorkloadResponse) -> ResourcesResponse {
+    workload.containers.iter().chain(&workload.sidecars).fold(
+        ResourcesResponse::default(),
+        |total, container| {
+            let requested = &container.resource_requests;
+            ResourcesResponse {
+                cpu_cores: total.cpu_cores + requested.cpu_cores,
+                memory_mb: total.memory_mb + requested.memory_mb,
+                disk_mb: total.disk_mb + requested.disk_mb,
+            }
+        },
+    )
+}
+
+/// Events about any of `object_ids`, oldest first. Servers that keep no
+/// events give none.
+async fn fetch_events(client: &ApiClient, object_ids: &[&str]) -> Vec<EventResponse> {
+    let mut events = Vec::new();
+    for id in object_ids {
+        let path = format!("/api/v1/events?involvedObject={}", id);
+        if let Ok(list) = client.get::<ListResponse<EventResponse>>(&path).await {
+            events.extend(list.items);
+        }
+    }
+    events.sort_by_key(|e| e.last_timestamp);
+    events
+}
+
+/// Execute the describe command.
+pub async fn execute(
+    args: DescribeArgs,
+    api_url: &str,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for describe. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        Some(DescribeCommand::Workload { workload, options }) => {
+            describe_workload(&client, &workload, options, format).await
+        }
+        Some(DescribeCommand::Instance {
+            instance,
+            namespace,
+        }) => describe_instance(&client, &instance, namespace.as_deref(), format).await,
+        Some(DescribeCommand::Node { node }) => describe_node(&client, &node, format).await,
+        None => match args.workload {
+            Some(workload) => describe_workload(&client, &workload, args.options, format).await,
+            None => Err(CliError::invalid_argument(
+                "Name what to describe: orch describe workload|instance|node <name>",
+            )
+            .into()),
+        },
+    }
+}
+
+async fn describe_workload(
+    client: &ApiClient,
+    name: &str,
+    options: WorkloadOptions,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let workload_id = find_workload_id(client, name, options.namespace.as_deref()).await?;
+    let workload: WorkloadResponse = client
+        .get(&format!("/api/v1/workloads/{}", workload_id))
+        .await?;
+    let instances: ListResponse<InstanceResponse> = client
+        .get(&format!("/api/v1/workloads/{}/instances", workload_id))
+        .await?;
+
+    let running = instances
+        .items
+        .iter()
+        .filter(|i| i.status == "Running")
+        .count();
+    let object_ids: Vec<&str> = std::iter::once(workload.id.as_str())
+        .chain(instances.items.iter().map(|i| i.id.as_str()))
+        .collect();
+    let events = fetch_events(client, &object_ids).await;
+    let mut description = Description {
+        workload,
+        running,
+        instances: instances.items,
+        events,
+        summary: None,
+    };
+
+    if options.ai {
+        match summarize(&description).await {
+            Ok(summary) => description.summary = Some(summary),
+            Err(e) => output::warn(&format!("No AI summary: {}", e)),
+        }
+    }
+
+    match format {
+        OutputFormat::Table | OutputFormat::Wide => print_description(&description)?,
+        _ => output::print_value(&description, format)?,
+    }
+    Ok(())
+}
+
+async fn describe_instance(
+    client: &ApiClient,
+    prefix: &str,
+    namespace: Option<&str>,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let instance = find_instance(client, prefix, namespace).await?;
+    let workload = client
+        .get::<WorkloadResponse>(&format!("/api/v1/workloads/{}", instance.workload_id))
+        .await
+        .ok();
+    let events = fetch_events(client, &[instance.id.as_str()]).await;
+    let description = InstanceDescription {
+        instance,
+        workload,
+        events,
+    };
+
+    match format {
+        OutputFormat::Table | OutputFormat::Wide => print_instance(&description)?,
+        _ => output::print_value(&description, format)?,
+    }
+    Ok(())
+}
+
+async fn describe_node(
+    client: &ApiClient,
+    prefix: &str,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let node = find_node(client, prefix).await?;
+    let instances: ListResponse<InstanceResponse> = client.get("/api/v1/instances").await?;
+    let workloads: ListResponse<WorkloadResponse> = client.get("/api/v1/workloads").await?;
+    let workloads: HashMap<&str, &WorkloadResponse> =
+        workloads.items.iter().map(|w| (w.id.as_str(), w)).collect();
+
+    let mut allocated = ResourcesResponse::default();
+    let mut on_node = Vec::new();
+    for instance in instances.items.iter().filter(|i| i.node_id == node.id) {
+        let workload = workloads.get(instance.workload_id.as_str());
+        let requested = workload.map(|w| requests(w)).unwrap_or_default();
+        allocated.cpu_cores += requested.cpu_cores;
+        allocated.memory_mb += requested.memory_mb;
+        allocated.disk_mb += requested.disk_mb;
+        on_node.push(NodeInstance {
+            id: short(&instance.id),
+            workload: workload.map_or_else(|| short(&instance.workload_id), |w| w.name.clone()),
+            namespace: instance.namespace.clone(),
+            status: instance.status.clone(),
+            cpu_cores: requested.cpu_cores,
+            memory_mb: requested.memory_mb,
+        });
+    }
+    let events = fetch_events(client, &[node.id.as_str()]).await;
+    let description = NodeDescription {
+        node,
+        allocated,
+        instances: on_node,
+        events,
+    };
+
+    match format {
+        OutputFormat::Table | OutputFormat::Wide => print_node(&description)?,
+        _ => output::print_value(&description, format)?,
+    }
+    Ok(())
+}
+
+fn print_description(description: &Description) -> anyhow::Result<()> {
+    let workload = &description.workload;
+    output::section("Workload");
+    field(2, "Name", &workload.name);
+    field(2, "ID", &workload.id);
+    field(2, "Namespace", &workload.namespace);
+    field(
+        2,
+        "Replicas",
+        format!("{}/{} running", description.running, workload.replicas),
+    );
+    if !workload.labels.is_empty() {
+        field(2, "Labels", labels(&workload.labels));
+    }
+    let mut restart_policy = workload.restart_policy.mode.clone();
+    if !workload.restart_policy.fatal_exit_codes.is_empty() {
+        let codes: Vec<String> = workload
+            .restart_policy
+            .fatal_exit_codes
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        restart_policy.push_str(&format!(" (fatal exit codes: {})", codes.join(", ")));
+    }
+    field(2, "Restart Policy", restart_policy);
+
+    if !workload.placement.is_empty() {
+        output::section("Placement");
+        for (rule, value) in &workload.placement {
+            let value = match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            field(2, &rule.replace('_', " "), value);
+        }
+    }
+
+    output::section("Containers");
+    let containers = workload
+        .init_containers
+        .iter()
+        .map(|c| (c, " (init)"))
+        .chain(workload.containers.iter().map(|c| (c, "")))
+        .chain(workload.sidecars.iter().map(|c| (c, " (sidecar)")));
+    for (container, role) in containers {
+        print_container(container, role, None);
+    }
+
+    output::section("Instances");
+    let displays: Vec<InstanceDisplay> = description.instances.iter().map(Into::into).collect();
+    print_data(&displays, OutputFormat::Table)?;
+
+    let restarted: Vec<(&InstanceResponse, &RestartResponse)> = description
+        .instances
+        .iter()
+        .flat_map(|i| i.restarts.iter().map(move |r| (i, r)))
+        .filter(|(_, r)| r.restart_count > 0)
+        .collect();
+    if !restarted.is_empty() {
+        output::section("Restart History");
+        for (instance, restart) in restarted {
+            println!(
+                "  {}/{}: {}",
+                short(&instance.id),
+                restart.container_name,
+                restart_summary(restart)
+            );
+        }
+    }
+
+    print_events(&description.events)?;
+
+    if let Some(summary) = &description.summary {
+        output::section("Summary");
+        println!("  {}", summary);
+    }
+    Ok(())
+}
+
+fn print_container(container: &ContainerResponse, role: &str, restart: Option<&RestartResponse>) {
+    println!("  {}{}", container.name.bold(), role);
+    field(4, "Image", &container.image);
+    if let Some(command) = &container.command {
+        field(4, "Command", command.join(" "));
+    }
+    if let Some(args) = &container.args {
+        field(4, "Args", args.join(" "));
+    }
+    if !container.ports.is_empty() {
+        let ports: Vec<String> = container.ports.iter().map(port).collect();
+        field(4, "Ports", ports.join(", "));
+    }
+    let requested = &container.resource_requests;
+    field(
+        4,
+        "Requests",
+        format!(
+            "cpu {}, memory {} MB, disk {} MB",
+            requested.cpu_cores, requested.memory_mb, requested.disk_mb
+        ),
+    );
+    if let Some(readiness_probe) = &container.readiness_probe {
+        field(4, "Readiness", probe(readiness_probe));
+    }
+    if let Some(pre_stop) = &container.pre_stop {
+        field(4, "PreStop", hook(pre_stop));
+    }
+    if let Some(seconds) = container.termination_grace_period_seconds {
+        field(4, "Grace Period", format!("{}s", seconds));
+    }
+    if !container.env_vars.is_empty() {
+        let mut names: Vec<&str> = container.env_vars.keys().map(String::as_str).collect();
+        names.sort();
+        field(4, "Environment", names.join(", "));
+    }
+    if let Some(restart) = restart {
+        field(4, "Restarts", restart_summary(restart));
+        if let Some(termination) = &restart.last_termination {
+            field(
+                4,
+                "Last State",
+                termination_summary(termination, Utc::now()),
+            );
+        }
+    }
+}
+
+/// How a container last exited, e.g. `OOMKilled, exit code 137, 5m ago`.
+fn termination_summary(termination: &TerminationResponse, now: DateTime<Utc>) -> String {
+    let exit = termination
+        .exit_code
+        .map(|code| format!(", exit code {}", code))
+        .unwrap_or_default();
+    format!(
+        "{}{}, {} ago",
+        termination.reason,
+        exit,
+        age(now - termination.finished_at)
+    )
+}
+
+/// A container's restarts, e.g. `4 restarts, 4 consecutive failures, last
+/// exit code 137`.
+fn restart_summary(restart: &RestartResponse) -> String {
+    let exit = restart
+        .last_exit_code
+        .map(|code| format!(", last exit code {}", code))
+        .unwrap_or_default();
+    let back_off = restart
+        .back_off_remaining_secs
+        .map(|secs| format!(", backing off for {}s", secs))
+        .unwrap_or_default();
+    format!(
+        "{} restarts, {} consecutive failures{}{}",
+        restart.restart_count, restart.consecutive_failures, exit, back_off
+    )
+}
+
+fn print_events(events: &[EventResponse]) -> anyhow::Result<()> {
+    if !events.is_empty() {
+        output::section("Events");
+        let displays: Vec<EventDisplay> = events.iter().map(Into::into).collect();
+        print_data(&displays, OutputFormat::Table)?;
+    }
+    Ok(())
+}
+
+fn print_instance(description: &InstanceDescription) -> anyhow::Result<()> {
+    let instance = &description.instance;
+    output::section("Instance");
+    field(2, "ID", &instance.id);
+    match &description.workload {
+        Some(workload) => field(
+            2,
+            "Workload",
+            format!("{} ({})", workload.name, workload.id),
+        ),
+        None => field(2, "Workload", &instance.workload_id),
+    }
+    field(2, "Namespace", &instance.namespace);
+    field(2, "Node", &instance.node_id);
+    field(2, "Status", &instance.status);
+    field(2, "Ready", readiness(instance.ready));
+    if !instance.ip_addresses.is_empty() {
+        field(2, "IP", instance.ip_addresses.join(", "));
+    }
+
+    if let Some(workload) = &description.workload {
+        output::section("Containers");
+        for (index, container) in workload.containers.iter().enumerate() {
+            let restart = instance
+                .restarts
+                .iter()
+                .find(|r| r.container_name == container.name);
+            print_container(container, "", restart);
+            if let Some(container_id) = instance.container_ids.get(index) {
+                field(4, "Container ID", container_id);
+            }
+        }
+    }
+
+    print_events(&description.events)
+}
+
+fn print_node(description: &NodeDescription) -> anyhow::Result<()> {
+    let node = &description.node;
+    output::section("Node");
+    field(2, "ID", &node.id);
+    field(2, "Address", &node.address);
+    field(2, "Status", &node.status);
+    field(
+        2,
+        "Schedulable",
+        if node.unschedulable {
+            "No (cordoned)"
+        } else {
+            "Yes"
+        },
+    );
+    if !node.labels.is_empty() {
+        field(2, "Labels", labels(&node.labels));
+    }
+    if let Some(pulls) = &node.image_pulls {
+        field(
+            2,
+            "Image Pulls",
+            format!(
+                "{} active, {} queued (limit {})",
+                pulls.active,
+                pulls.queued_interactive + pulls.queued_batch,
+                pulls.max_concurrent_pulls
+            ),
+        );
+    }
+
+    output::section("Resources");
+    let capacity = &node.resources_capacity;
+    let allocatable = &node.resources_allocatable;
+    let allocated = &description.allocated;
+    let percent = |used: f64, of: f64| {
+        if of > 0.0 {
+            format!(" ({:.0}%)", used * 100.0 / of)
+        } else {
+            String::new()
+        }
+    };
+    field(
+        2,
+        "CPU",
+        format!(
+            "{:.2} requested of {:.2} allocatable{}, {:.2} capacity",
+            allocated.cpu_cores,
+            allocatable.cpu_cores,
+            percent(allocated.cpu_cores as f64, allocatable.cpu_cores as f64),
+            capacity.cpu_cores
+        ),
+    );
+    field(
+        2,
+        "Memory",
+        format!(
+            "{} MB requested of {} MB allocatable{}, {} MB capacity",
+            allocated.memory_mb,
+            allocatable.memory_mb,
+            percent(allocated.memory_mb as f64, allocatable.memory_mb as f64),
+            capacity.memory_mb
+        ),
+    );
+    field(
+        2,
+        "Disk",
+        format!(
+            "{} MB requested of {} MB allocatable, {} MB capacity",
+            allocated.disk_mb, allocatable.disk_mb, capacity.disk_mb
+        ),
+    );
+
+    output::section("Instances");
+    print_data(&description.instances, OutputFormat::Table)?;
+
+    print_events(&description.events)
+}
+
+/// Ask the configured LLM provider for a one-paragraph summary.
+async fn summarize(description: &Description) -> Result<String> {
+    let config = UserConfig::load().await?;
+    let provider = config.llm().ok_or_else(|| {
+        CliError::config_error(format!(
+            "no LLM provider configured; add an [llm] section with base_url and model to {}",
+            config.paths().config_dir().join("settings.toml").display()
+        ))
+    })?;
+
+    let url = provider.chat_completions_url();
+    let host = Url::parse(&url)
+        .ok()
+        .and_then(|u| u.host_str().map(String::from))
+        .ok_or_else(|| CliError::config_error(format!("invalid LLM provider URL {}", url)))?;
+    if !config.trust_policy().allows_network_access(&host) {
+        return Err(CliError::config_error(format!(
+            "the trust policy does not allow network access to {}",
+            host
+        )));
+    }
+
+    let client = reqwest::Client::builder().timeout(LLM_TIMEOUT).build()?;
+    let mut request = client
+        .post(&url)
+        .json(&chat_request(provider, description)?);
+    if let Some(key) = provider.api_key() {
+        request = request.bearer_auth(key);
+    }
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(CliError::api_error(format!(
+            "LLM provider returned {}: {}",
+            response.status(),
+            response.text().await.unwrap_or_default()
+        )));
+    }
+    completion_text(&response.json().await?)
+}
+
+/// Chat completions request body for a description.
+fn chat_request(provider: &LlmProvider, description: &Description) -> Result<serde_json::Value> {
+    Ok(serde_json::json!({
+        "model": provider.model,
+        "messages": [
+            {"role": "system", "content": SUMMARY_INSTRUCTIONS},
+            {"role": "user", "content": serde_json::to_string_pretty(description)?},
+        ],
+    }))
+}
+
+/// Text of the first choice in a chat completions response.
+fn completion_text(response: &serde_json::Value) -> Result<String> {
+    response["choices"][0]["message"]["content"]
+        .as_str()
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .ok_or_else(|| CliError::api_error("LLM provider returned no summary"))
+}
+
+/// Find workload ID by name, ID or unique ID prefix.
+async fn find_workload_id(
+    client: &ApiClient,
+    name_or_id: &str,
+    namespace: Option<&str>,
+) -> Result<String> {
+    if uuid::Uuid::parse_str(name_or_id).is_ok() {
+        return Ok(name_or_id.to_string());
+    }
+
+    let path = match namespace {
+        Some(namespace) => format!("/api/v1/workloads?namespace={}", namespace),
+        None => "/api/v1/workloads".to_string(),
+    };
+    let workloads: ListResponse<WorkloadResponse> = client.get(&path).await?;
+
+    let matching: Vec<_> = workloads
+        .items
+        .iter()
+        .filter(|w| w.name == name_or_id || w.id.starts_with(name_or_id))
+        .collect();
+
+    match matching.len() {
+        0 => Err(CliError::WorkloadNotFound(name_or_id.to_string())),
+        1 => Ok(matching[0].id.clone()),
+        _ => Err(CliError::invalid_argument(format!(
+            "Ambiguous workload reference '{}', matches {} workloads. Use full ID.",
+            name_or_id,
+            matching.len()
+        ))),
+    }
+}
+
+/// Find an instance by ID or unique ID prefix.
+async fn find_instance(
+    client: &ApiClient,
+    prefix: &str,
+    namespace: Option<&str>,
+) -> Result<InstanceResponse> {
+    let path = match namespace {
+        Some(namespace) => format!("/api/v1/instances?namespace={}", namespace),
+        None => "/api/v1/instances".to_string(),
+    };
+    let instances: ListResponse<InstanceResponse> = client.get(&path).await?;
+
+    let mut matching: Vec<InstanceResponse> = instances
+        .items
+        .into_iter()
+        .filter(|i| i.id.starts_with(prefix))
+        .collect();
+
+    match matching.len() {
+        0 => Err(CliError::InstanceNotFound(prefix.to_string())),
+        1 => Ok(matching.remove(0)),
+        _ => Err(CliError::invalid_argument(format!(
+            "Ambiguous instance reference '{}', matches {} instances. Use full ID.",
+            prefix,
+            matching.len()
+        ))),
+    }
+}
+
+/// Find a node by ID or unique ID prefix.
+async fn find_node(client: &ApiClient, prefix: &str) -> Result<NodeResponse> {
+    let nodes: ListResponse<NodeResponse> = client.get("/api/v1/nodes").await?;
+
+    let mut matching: Vec<NodeResponse> = nodes
+        .items
+        .into_iter()
+        .filter(|n| n.id.starts_with(prefix))
+        .collect();
+
+    match matching.len() {
+        0 => Err(CliError::NodeNotFound(prefix.to_string())),
+        1 => Ok(matching.remove(0)),
+        _ => Err(CliError::invalid_argument(format!(
+            "Ambiguous node reference '{}', matches {} nodes. Use full ID.",
+            prefix,
+            matching.len()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(name: &str, cpu_cores: f32, memory_mb: u64) -> ContainerResponse {
+        ContainerResponse {
+            name: name.to_string(),
+            image: "pytorch:2.3".to_string(),
+            command: None,
+            args: None,
+            env_vars: HashMap::new(),
+            ports: Vec::new(),
+            resource_requests: ResourcesResponse {
+                cpu_cores,
+                memory_mb,
+                disk_mb: 0,
+            },
+            readiness_probe: None,
+            termination_grace_period_seconds: None,
+            pre_stop: None,
+        }
+    }
+
+    fn description() -> Description {
+        Description {
+            workload: WorkloadResponse {
+                id: "0b6c2f4e-0000-0000-0000-000000000000".to_string(),
+                name: "trainer".to_string(),
+                namespace: "ml".to_string(),
+                replicas: 2,
+                labels: HashMap::new(),
+                containers: vec![container("trainer", 2.0, 4096)],
+                init_containers: Vec::new(),
+                sidecars: vec![container("metrics", 0.5, 128)],
+                placement: serde_json::Map::new(),
+                restart_policy: RestartPolicyResponse::default(),
+            },
+            running: 1,
+            instances: vec![InstanceResponse {
+                id: "5d1e9a7c-0000-0000-0000-000000000000".to_string(),
+                workload_id: "0b6c2f4e-0000-0000-0000-000000000000".to_string(),
+                node_id: "node-a".to_string(),
+                status: "Failed".to_string(),
+                container_ids: Vec::new(),
+                ip_addresses: Vec::new(),
+                restarts: vec![RestartResponse {
+                    container_name: "trainer".to_string(),
+                    restart_count: 4,
+                    consecutive_failures: 4,
+                    last_exit_code: Some(137),
+                    back_off_remaining_secs: Some(40),
+                    last_termination: Some(TerminationResponse {
+                        reason: "OOMKilled".to_string(),
+                        exit_code: Some(137),
+                        finished_at: Utc::now(),
+                    }),
+                }],
+                namespace: "ml".to_string(),
+                ready: Some(false),
+            }],
+            events: vec![EventResponse {
+                involved_object: EventObject {
+                    kind: "Instance".to_string(),
+                    id: "5d1e9a7c-0000-0000-0000-000000000000".to_string(),
+                },
+                event_type: "Warning".to_string(),
+                reason: "BackOff".to_string(),
+                message: "Back-off restarting exited container".to_string(),
+                count: 4,
+                last_timestamp: Utc::now(),
+            }],
+            summary: None,
+        }
+    }
+
+    #[test]
+    fn test_chat_request_carries_description() {
+        let provider = LlmProvider {
+            base_url: "http://localhost:11434/v1".to_string(),
+            model: "llama3".to_string(),
+            api_key_env: None,
+        };
+        let request = chat_request(&provider, &description()).unwrap();
+        assert_eq!(request["model"], "llama3");
+        assert_eq!(request["messages"][0]["role"], "system");
+        let content = request["messages"][1]["content"].as_str().unwrap();
+        assert!(content.contains("\"last_exit_code\": 137"));
+        assert!(content.contains("\"reason\": \"OOMKilled\""));
+        assert!(content.contains("\"reason\": \"BackOff\""));
+        assert!(!content.contains("summary"));
+    }
+
+    #[test]
+    fn test_completion_text() {
+        let response = serde_json::json!({
+            "choices": [{"message": {"role": "assistant", "content": "  One replica is down.\n"}}]
+        });
+        assert_eq!(completion_text(&response).unwrap(), "One replica is down.");
+        assert!(completion_text(&serde_json::json!({"choices": []})).is_err());
+    }
+
+    #[test]
+    fn test_age() {
+        assert_eq!(age(chrono::Duration::seconds(42)), "42s");
+        assert_eq!(age(chrono::Duration::seconds(150)), "2m");
+        assert_eq!(age(chrono::Duration::hours(5)), "5h");
+        assert_eq!(age(chrono::Duration::days(3)), "3d");
+        assert_eq!(age(chrono::Duration::seconds(-1)), "0s");
+    }
+
+    #[test]
+    fn test_termination_summary() {
+        let now = Utc::now();
+        let termination = TerminationResponse {
+            reason: "OOMKilled".to_string(),
+            exit_code: Some(137),
+            finished_at: now - chrono::Duration::minutes(5),
+        };
+        assert_eq!(
+            termination_summary(&termination, now),
+            "OOMKilled, exit code 137, 5m ago"
+        );
+        let termination = TerminationResponse {
+            reason: "Error".to_string(),
+            exit_code: None,
+            finished_at: now,
+        };
+        assert_eq!(termination_summary(&termination, now), "Error, 0s ago");
+    }
+
+    #[test]
+    fn test_container_details() {
+        let api: WorkloadResponse = serde_json::from_value(serde_json::json!({
+            "id": "w1",
+            "name": "web",
+            "replicas": 1,
+            "labels": {},
+            "containers": [{
+                "name": "web",
+                "image": "nginx",
+                "command": null,
+                "args": null,
+                "env_vars": {},
+                "ports": [{"container_port": 80, "host_port": 8080, "protocol": "TCP"}],
+                "resource_requests": {"cpu_cores": 0.5, "memory_mb": 256, "disk_mb": 0},
+                "readiness_probe": {
+                    "action": {"type": "http_get", "port": 80, "path": "/healthz"},
+                    "initial_delay_seconds": 5,
+                    "period_seconds": 10,
+                    "timeout_seconds": 1,
+                    "success_threshold": 1,
+                    "failure_threshold": 3
+                },
+                "termination_grace_period_seconds": 45,
+                "pre_stop": {"type": "exec", "command": ["nginx", "-s", "quit"]}
+            }],
+            "placement": {"node_selector": {"gpu": "a100"}},
+            "restart_policy": {"mode": "OnFailure"}
+        }))
+        .unwrap();
+        let web = &api.containers[0];
+        assert_eq!(port(&web.ports[0]), "8080:80/TCP");
+        assert_eq!(
+            probe(web.readiness_probe.as_ref().unwrap()),
+            "http-get :80/healthz delay=5s timeout=1s period=10s #success=1 #failure=3"
+        );
+        assert_eq!(hook(web.pre_stop.as_ref().unwrap()), "exec nginx -s quit");
+        assert_eq!(api.restart_policy.mode, "OnFailure");
+        assert!(api.placement.contains_key("node_selector"));
+    }
+
+    #[test]
+    fn test_requests_include_sidecars() {
+        let requested = requests(&description().workload);
+        assert_eq!(requested.cpu_cores, 2.5);
+        assert_eq!(requested.memory_mb, 4224);
+
+        let display = InstanceDisplay::from(&description().instances[0]);
+        assert_eq!(display.ready, "False");
+        assert_eq!(display.restarts, 4);
+    }
+}
@@ -0,0 +1,58 @@
+//! Docs command - generate reference documentation for `orch`.
+
+use std::path::PathBuf;
+
+use clap::{Args, Command, Subcommand};
+
+use crate::output;
+
+/// Arguments for the docs command.
+#[derive(Args)]
+pub struct DocsArgs {
+    #[command(subcommand)]
+    command: DocsCommand,
+}
+
+#[derive(Subcommand)]
+enum DocsCommand {
+    /// Generate man pages
+    Man {
+        /// Write a page for every command into this directory instead of
+        /// printing the page of `orch` itself
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+/// Execute the docs command for the CLI `command`.
+pub async fn execute(args: DocsArgs, command: Command) -> anyhow::Result<()> {
+    match args.command {
+        DocsCommand::Man { out_dir: None } => {
+            clap_mangen::Man::new(command).render(&mut std::io::stdout().lock())?;
+        }
+        DocsCommand::Man { out_dir: Some(dir) } => {
+            std::fs::create_dir_all(&dir)?;
+            clap_mangen::generate_to(command, &dir)?;
+            output::success(&format!("Man pages written to {}", dir.display()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_man_pages_per_subcommand() {
+        let command = Command::new("orch")
+            .about("Orchestrator CLI")
+            .subcommand(Command::new("status").about("Show status"));
+        let dir = tempfile::tempdir().unwrap();
+        clap_mangen::generate_to(command, dir.path()).unwrap();
+
+        let page = std::fs::read_to_string(dir.path().join("orch.1")).unwrap();
+        assert!(page.contains("Orchestrator CLI"));
+        assert!(dir.path().join("orch-status.1").exists());
+    }
+}
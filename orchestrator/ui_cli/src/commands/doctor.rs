@@ -0,0 +1,585 @@
+//! Doctor command - diagnose common client and cluster problems.
+
+use std::collections::HashMap;
+
+use clap::Args;
+use colored::Colorize;
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use user_config::{ConfigPaths, UserConfig};
+
+use orchestrator_shared_types::NODE_VERSION_LABEL;
+
+use crate::client::ApiClient;
+use crate::error::CliError;
+use crate::output;
+use crate::OutputFormat;
+
+/// Version of this CLI.
+const CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Arguments for the doctor command.
+#[derive(Args)]
+pub struct DoctorArgs {}
+
+/// Outcome of one check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// Result of one diagnostic check, with a fix when something is wrong.
+#[derive(Debug, Serialize)]
+struct Check {
+    name: &'static str,
+    severity: Severity,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            severity: Severity::Ok,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            severity: Severity::Warn,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            severity: Severity::Fail,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Generic list response wrapper from API.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+/// Cluster status response from API.
+#[derive(Debug, Deserialize)]
+struct ClusterStatusResponse {
+    #[serde(default)]
+    version: String,
+    pending_instances: usize,
+    failed_instances: usize,
+}
+
+/// Node response from API.
+#[derive(Debug, Deserialize)]
+struct NodeResponse {
+    id: String,
+    status: String,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+    #[serde(default)]
+    unschedulable: bool,
+    #[serde(default)]
+    image_pulls: Option<ImagePullsResponse>,
+}
+
+/// Image pull queue of a node from API.
+#[derive(Debug, Deserialize)]
+struct ImagePullsResponse {
+    active: usize,
+    queued_interactive: usize,
+    queued_batch: usize,
+}
+
+/// Authentication failure body from API.
+#[derive(Debug, Deserialize)]
+struct AuthErrorResponse {
+    error: String,
+    code: String,
+}
+
+/// Execute the doctor command.
+pub async fn execute(_args: DoctorArgs, api_url: &str, format: OutputFormat) -> anyhow::Result<()> {
+    let checks = run_checks(api_url).await;
+
+    match format {
+        OutputFormat::Table | OutputFormat::Wide => print_checks(&checks),
+        _ => output::print_value(&checks, format)?,
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|c| c.severity == Severity::Fail)
+        .count();
+    if failed > 0 {
+        return Err(CliError::Other(format!("{} check(s) failed", failed)).into());
+    }
+    Ok(())
+}
+
+/// Run every check, skipping those that depend on an earlier failure.
+async fn run_checks(api_url: &str) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let identity = load_identity().await;
+    checks.push(match &identity {
+        Ok(public_key) => Check::ok(
+            "identity",
+            format!(
+                "Loaded identity {}...",
+                &public_key[..12.min(public_key.len())]
+            ),
+        ),
+        Err(reason) => Check::fail("identity", reason.clone(), "Run 'orch init' to create one"),
+    });
+
+    checks.push(check_api_url(api_url));
+
+    // Connectivity is checked unsigned, which also reveals whether the API
+    // enforces authentication at all
+    let anonymous = ApiClient::new(api_url);
+    let unsigned_status = match anonymous
+        .request(Method::GET, "/api/v1/cluster/status")
+        .await
+    {
+        Ok(response) => {
+            checks.push(Check::ok(
+                "connectivity",
+                format!("API reachable at {}", api_url),
+            ));
+            response.status()
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "connectivity",
+                format!("Cannot reach the API at {}: {}", api_url, e),
+                "Check that the control-plane node is running and that --api-url \
+                 (or ORCH_API_URL) points at its API_PORT (default 9090)",
+            ));
+            return checks;
+        }
+    };
+
+    let client = match &identity {
+        Ok(_) => ApiClient::authenticated(api_url).await.unwrap_or(anonymous),
+        Err(_) => anonymous,
+    };
+
+    match client.request(Method::GET, "/api/v1/nodes").await {
+        Ok(response) if response.status().is_success() => {
+            if unsigned_status.is_success() {
+                checks.push(Check::warn(
+                    "auth",
+                    "The API accepts unsigned requests",
+                    "Set AUTH_DISABLED=false on the control-plane node for any shared cluster",
+                ));
+            } else {
+                checks.push(Check::ok("auth", "Signed requests are accepted"));
+            }
+        }
+        Ok(response) if response.status() == StatusCode::UNAUTHORIZED => {
+            let body: Option<AuthErrorResponse> = response.json().await.ok();
+            checks.push(auth_failure(body, identity.as_deref().ok()));
+            return checks;
+        }
+        Ok(response) => {
+            checks.push(Check::fail(
+                "auth",
+                format!("Unexpected response {} listing nodes", response.status()),
+                "Check the control-plane node's logs",
+            ));
+            return checks;
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "auth",
+                format!("Request failed: {}", e),
+                "Re-run 'orch doctor'; if it persists, check the control-plane node's logs",
+            ));
+            return checks;
+        }
+    }
+
+    let status = client
+        .get::<ClusterStatusResponse>("/api/v1/cluster/status")
+        .await;
+    let nodes = client
+        .get::<ListResponse<NodeResponse>>("/api/v1/nodes")
+        .await;
+    let (status, nodes) = match (status, nodes) {
+        (Ok(status), Ok(nodes)) => (status, nodes.items),
+        (Err(e), _) | (_, Err(e)) => {
+            checks.push(Check::fail(
+                "cluster",
+                format!("Could not read cluster state: {}", e),
+                "Check the control-plane node's logs",
+            ));
+            return checks;
+        }
+    };
+
+    checks.push(check_versions(CLI_VERSION, &status.version, &nodes));
+    checks.extend(check_nodes(&nodes));
+    checks.push(check_image_pulls(&nodes));
+    checks.extend(check_instances(&status));
+    checks
+}
+
+/// Loads the local identity without creating one, returning its public key.
+async fn load_identity() -> Result<String, String> {
+    let paths = ConfigPaths::new().map_err(|e| format!("Cannot locate configuration: {}", e))?;
+    if !paths.config_exists() {
+        return Err("No identity found".to_string());
+    }
+    let config = UserConfig::load_with_paths(paths)
+        .await
+        .map_err(|e| format!("Identity could not be loaded: {}", e))?;
+    Ok(config.identity().public_key_base64())
+}
+
+/// Explains a rejected signed request.
+fn auth_failure(body: Option<AuthErrorResponse>, public_key: Option<&str>) -> Check {
+    let Some(public_key) = public_key else {
+        return Check::fail(
+            "auth",
+            "The API requires signed requests and no identity is configured",
+            "Run 'orch init', then have an administrator trust the new public key",
+        );
+    };
+    let Some(body) = body else {
+        return Check::fail(
+            "auth",
+            "Signed request rejected",
+            "Re-run 'orch init' if the identity was replaced",
+        );
+    };
+    let fix = match body.code.as_str() {
+        "TIMESTAMP_EXPIRED" => {
+            "Synchronize this machine's clock (e.g. enable NTP); signatures are \
+             only valid for a few minutes"
+                .to_string()
+        }
+        "UNTRUSTED_KEY" => format!(
+            "Ask a cluster administrator to trust public key {}",
+            public_key
+        ),
+        _ => "Re-run 'orch init' if the identity was replaced".to_string(),
+    };
+    Check::fail(
+        "auth",
+        format!("Signed request rejected: {} ({})", body.error, body.code),
+        fix,
+    )
+}
+
+/// Flags API URLs that send traffic to another machine unencrypted.
+fn check_api_url(api_url: &str) -> Check {
+    let url = match Url::parse(api_url) {
+        Ok(url) => url,
+        Err(e) => {
+            return Check::fail(
+                "api-url",
+                format!("'{}' is not a valid URL: {}", api_url, e),
+                "Pass a URL like http://localhost:9090 with --api-url or ORCH_API_URL",
+            )
+        }
+    };
+    let local = match url.host() {
+        Some(url::Host::Domain(domain)) => domain == "localhost",
+        Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    };
+    if url.scheme() == "http" && !local {
+        return Check::warn(
+            "api-url",
+            format!("Requests to {} are sent unencrypted", api_url),
+            "Use an https:// URL, or reach the API through an SSH tunnel to localhost",
+        );
+    }
+    Check::ok("api-url", format!("Using {}", api_url))
+}
+
+/// `major.minor` of a version; releases sharing it are compatible.
+fn minor_version(version: &str) -> Option<(u64, u64)> {
+    let mut parts = version.trim_start_matches('v').split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// Compares the CLI, API server and node agent versions.
+fn check_versions(cli: &str, server: &str, nodes: &[NodeResponse]) -> Check {
+    if server.is_empty() {
+        return Check::warn(
+            "versions",
+            format!(
+                "The API server does not report its version (CLI is {})",
+                cli
+            ),
+            "Upgrade the control plane to the CLI's release",
+        );
+    }
+    if minor_version(cli) != minor_version(server) {
+        return Check::warn(
+            "versions",
+            format!("CLI {} and API server {} differ", cli, server),
+            format!("Install orch {} to match the server", server),
+        );
+    }
+
+    let skewed: Vec<String> = nodes
+        .iter()
+        .filter(|n| {
+            let version = n.labels.get(NODE_VERSION_LABEL).map(String::as_str);
+            version.and_then(minor_version) != minor_version(server)
+        })
+        .map(|n| {
+            let version = n
+                .labels
+                .get(NODE_VERSION_LABEL)
+                .map_or("unknown", String::as_str);
+            format!("{} ({})", short_id(&n.id), version)
+        })
+        .collect();
+    if !skewed.is_empty() {
+        return Check::warn(
+            "versions",
+            format!(
+                "Node agents not on server version {}: {}",
+                server,
+                skewed.join(", ")
+            ),
+            format!("Upgrade these node agents to {}", server),
+        );
+    }
+
+    Check::ok(
+        "versions",
+        format!(
+            "CLI {}, API server {} and all node agents agree",
+            cli, server
+        ),
+    )
+}
+
+/// Checks that nodes have joined and can take work.
+fn check_nodes(nodes: &[NodeResponse]) -> Vec<Check> {
+    if nodes.is_empty() {
+        return vec![Check::fail(
+            "nodes",
+            "No nodes have joined the cluster",
+            "Start a node with NODE_ROLE=bootstrap, and check SEED_NODES on workers",
+        )];
+    }
+
+    let mut checks = Vec::new();
+    let not_ready: Vec<&str> = nodes
+        .iter()
+        .filter(|n| n.status != "Ready")
+        .map(|n| short_id(&n.id))
+        .collect();
+    let cordoned: Vec<&str> = nodes
+        .iter()
+        .filter(|n| n.unschedulable)
+        .map(|n| short_id(&n.id))
+        .collect();
+    let schedulable = nodes
+        .iter()
+        .filter(|n| n.status == "Ready" && !n.unschedulable)
+        .count();
+
+    if schedulable == 0 {
+        checks.push(Check::fail(
+            "nodes",
+            format!("None of {} node(s) can take new instances", nodes.len()),
+            "Bring a node back to Ready or uncordon one with 'orch node uncordon <id>'",
+        ));
+    } else {
+        checks.push(Check::ok(
+            "nodes",
+            format!("{}/{} node(s) schedulable", schedulable, nodes.len()),
+        ));
+    }
+    if !not_ready.is_empty() {
+        checks.push(Check::warn(
+            "nodes",
+            format!("Not ready: {}", not_ready.join(", ")),
+            "Check those node agents are running and can reach their seed nodes; \
+             their instances are rescheduled once NODE_EVICTION_GRACE_SECS passes",
+        ));
+    }
+    if !cordoned.is_empty() {
+        checks.push(Check::warn(
+            "nodes",
+            format!("Cordoned: {}", cordoned.join(", ")),
+            "Run 'orch node uncordon <id>' once maintenance is done",
+        ));
+    }
+    checks
+}
+
+/// Reports image pulls waiting for a download slot.
+fn check_image_pulls(nodes: &[NodeResponse]) -> Check {
+    let mut active = 0;
+    let mut backlog = Vec::new();
+    for node in nodes {
+        if let Some(pulls) = &node.image_pulls {
+            active += pulls.active;
+            let queued = pulls.queued_interactive + pulls.queued_batch;
+            if queued > 0 {
+                backlog.push(format!("{} ({} queued)", short_id(&node.id), queued));
+            }
+        }
+    }
+
+    if !backlog.is_empty() {
+        return Check::warn(
+            "image-pulls",
+            format!("Image pulls waiting on {}", backlog.join(", ")),
+            "Raise IMAGE_PULL_CONCURRENCY or IMAGE_PULL_BANDWIDTH_KBPS on those nodes, \
+             or pre-pull large images",
+        );
+    }
+    Check::ok(
+        "image-pulls",
+        format!("{} pull(s) in progress, none queued", active),
+    )
+}
+
+/// Reports instances that are stuck or failing.
+fn check_instances(status: &ClusterStatusResponse) -> Vec<Check> {
+    let mut checks = Vec::new();
+    if status.pending_instances > 0 {
+        checks.push(Check::warn(
+            "instances",
+            format!("{} instance(s) pending", status.pending_instances),
+            "Compare workload resource requests with allocatable capacity in 'orch status'",
+        ));
+    }
+    if status.failed_instances > 0 {
+        checks.push(Check::warn(
+            "instances",
+            format!("{} instance(s) failed", status.failed_instances),
+            "Inspect them with 'orch status --detailed' and 'orch logs <workload>'",
+        ));
+    }
+    if checks.is_empty() {
+        checks.push(Check::ok("instances", "No pending or failed instances"));
+    }
+    checks
+}
+
+fn short_id(id: &str) -> &str {
+    &id[..8.min(id.len())]
+}
+
+fn print_checks(checks: &[Check]) {
+    output::section("Diagnostics");
+    for check in checks {
+        let line = format!("{:<12} {}", check.name, check.message);
+        match check.severity {
+            Severity::Ok => output::success(&line),
+            Severity::Warn => output::warn(&line),
+            Severity::Fail => output::error(&line),
+        }
+        if let Some(fix) = &check.fix {
+            println!("  {} {}", "fix:".dimmed(), fix);
+        }
+    }
+
+    let count = |severity| checks.iter().filter(|c| c.severity == severity).count();
+    println!(
+        "\n{} passed, {} warning(s), {} failed",
+        count(Severity::Ok),
+        count(Severity::Warn),
+        count(Severity::Fail)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, status: &str, version: Option<&str>) -> NodeResponse {
+        NodeResponse {
+            id: id.to_string(),
+            status: status.to_string(),
+            labels: version
+                .map(|v| HashMap::from([(NODE_VERSION_LABEL.to_string(), v.to_string())]))
+                .unwrap_or_default(),
+            unschedulable: false,
+            image_pulls: None,
+        }
+    }
+
+    #[test]
+    fn test_version_skew() {
+        let nodes = vec![node("aaaaaaaaaa", "Ready", Some("0.3.1"))];
+        assert_eq!(
+            check_versions("0.3.0", "0.3.2", &nodes).severity,
+            Severity::Ok
+        );
+
+        let check = check_versions("0.2.0", "0.3.0", &nodes);
+        assert_eq!(check.severity, Severity::Warn);
+        assert!(check.fix.unwrap().contains("0.3.0"));
+
+        let nodes = vec![
+            node("aaaaaaaaaa", "Ready", Some("0.3.0")),
+            node("bbbbbbbbbb", "Ready", None),
+        ];
+        let check = check_versions("0.3.0", "0.3.0", &nodes);
+        assert_eq!(check.severity, Severity::Warn);
+        assert!(check.message.contains("bbbbbbbb (unknown)"));
+    }
+
+    #[test]
+    fn test_node_checks() {
+        assert_eq!(check_nodes(&[])[0].severity, Severity::Fail);
+
+        let mut cordoned = node("aaaaaaaaaa", "Ready", None);
+        cordoned.unschedulable = true;
+        let checks = check_nodes(&[cordoned, node("bbbbbbbbbb", "NotReady", None)]);
+        assert_eq!(checks[0].severity, Severity::Fail);
+        assert_eq!(checks.len(), 3);
+
+        let checks = check_nodes(&[node("aaaaaaaaaa", "Ready", None)]);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].severity, Severity::Ok);
+    }
+
+    #[test]
+    fn test_api_url_check() {
+        assert_eq!(
+            check_api_url("http://localhost:9090").severity,
+            Severity::Ok
+        );
+        assert_eq!(check_api_url("http://[::1]:9090").severity, Severity::Ok);
+        assert_eq!(
+            check_api_url("https://orch.example.com").severity,
+            Severity::Ok
+        );
+        assert_eq!(
+            check_api_url("http://10.0.0.5:9090").severity,
+            Severity::Warn
+        );
+        assert_eq!(check_api_url("not a url").severity, Severity::Fail);
+    }
+}
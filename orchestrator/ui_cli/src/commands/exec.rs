@@ -0,0 +1,295 @@
+//! Exec command - run a command in a container of an instance.
+//!
+//! Talks to `GET /api/v1/instances/:id/exec` over a WebSocket whose binary
+//! frames start with a channel byte: 0 stdin, 1 stdout, 2 stderr, 3 the final
+//! status and 4 terminal resizes.
+
+use std::io::{IsTerminal, Write};
+
+use clap::Args;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use crate::client::{require_local_cluster, ApiClient};
+use crate::commands::instance::find_instance_id;
+use crate::error::{CliError, Result};
+
+const STDIN: u8 = 0;
+const STDOUT: u8 = 1;
+const STDERR: u8 = 2;
+const STATUS: u8 = 3;
+const RESIZE: u8 = 4;
+
+/// Arguments for the exec command.
+#[derive(Args)]
+pub struct ExecArgs {
+    /// Instance ID or unique ID prefix
+    instance: String,
+
+    /// Command to run and its arguments, after `--`
+    #[arg(last = true, required = true)]
+    command: Vec<String>,
+
+    /// Container to run in (default: the first main container)
+    #[arg(short, long)]
+    container: Option<String>,
+
+    /// Pass stdin to the command
+    #[arg(short, long)]
+    interactive: bool,
+
+    /// Run the command on a terminal
+    #[arg(short, long)]
+    tty: bool,
+
+    /// Namespace of the instance (default: look in every namespace)
+    #[arg(long)]
+    namespace: Option<String>,
+}
+
+/// Final status frame.
+#[derive(Debug, Deserialize)]
+struct ExecStatus {
+    exit_code: Option<i32>,
+    error: Option<String>,
+}
+
+/// Puts the terminal in raw mode while alive, so keys go to the command.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> Result<Self> {
+        crossterm::terminal::enable_raw_mode()
+            .map_err(|e| CliError::api_error(format!("Failed to set up terminal: {}", e)))?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+/// Execute the exec command.
+pub async fn execute(args: ExecArgs, api_url: &str) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for exec. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+    let id = find_instance_id(&client, &args.instance, args.namespace.as_deref()).await?;
+    let path = format!("/api/v1/instances/{}/exec", id);
+    attach(
+        &client,
+        &path,
+        &exec_query(&args),
+        args.interactive,
+        args.tty,
+    )
+    .await
+}
+
+/// Open the exec WebSocket at `path`, relay the terminal through it and exit
+/// with the command's exit code. Also serves `orch debug`, which speaks the
+/// same protocol.
+pub(crate) async fn attach(
+    client: &ApiClient,
+    path: &str,
+    query: &str,
+    interactive: bool,
+    tty: bool,
+) -> anyhow::Result<()> {
+    require_local_cluster("Exec")?;
+    let url = format!("{}{}?{}", ws_url(client.base_url()), path, query);
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| CliError::api_error(format!("Invalid exec URL: {}", e)))?;
+    for (name, value) in client.auth_headers("GET", path) {
+        let value = HeaderValue::from_str(&value)
+            .map_err(|e| CliError::api_error(format!("Invalid {} header: {}", name, e)))?;
+        request.headers_mut().insert(name, value);
+    }
+    let (ws_stream, _) = connect_async(request)
+        .await
+        .map_err(|e| CliError::api_error(format!("Failed to connect to WebSocket: {}", e)))?;
+    let (mut write, mut read) = ws_stream.split();
+
+    let tty = tty && std::io::stdin().is_terminal();
+    let raw_mode = if tty { Some(RawMode::enable()?) } else { None };
+    let (input_tx, mut input) = mpsc::channel::<Vec<u8>>(16);
+    if interactive {
+        tokio::spawn(read_stdin(input_tx.clone()));
+    } else {
+        let _ = input_tx.send(vec![STDIN]).await;
+    }
+    if tty {
+        tokio::spawn(watch_terminal_size(input_tx.clone()));
+    }
+    drop(input_tx);
+
+    let mut status = None;
+    let mut input_open = true;
+    loop {
+        tokio::select! {
+            frame = input.recv(), if input_open => match frame {
+                Some(frame) => {
+                    if write.send(Message::Binary(frame)).await.is_err() {
+                        break;
+                    }
+                }
+                None => input_open = false,
+            },
+            message = read.next() => match message {
+                Some(Ok(Message::Binary(frame))) => match frame.split_first() {
+                    Some((&STDOUT, data)) => write_all(&mut std::io::stdout(), data),
+                    Some((&STDERR, data)) => write_all(&mut std::io::stderr(), data),
+                    Some((&STATUS, data)) => {
+                        status = serde_json::from_slice::<ExecStatus>(data).ok();
+                        break;
+                    }
+                    _ => {}
+                },
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Err(e)) => {
+                    drop(raw_mode);
+                    return Err(CliError::api_error(format!("WebSocket error: {}", e)).into());
+                }
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    drop(raw_mode);
+
+    match status {
+        Some(ExecStatus {
+            error: Some(error), ..
+        }) => Err(CliError::api_error(error).into()),
+        Some(ExecStatus {
+            exit_code: Some(code),
+            ..
+        }) if code != 0 => std::process::exit(code),
+        Some(_) => Ok(()),
+        None => Err(CliError::api_error("Connection closed before the command exited").into()),
+    }
+}
+
+/// The WebSocket URL of an HTTP API URL.
+fn ws_url(api_url: &str) -> String {
+    api_url
+        .replace("http://", "ws://")
+        .replace("https://", "wss://")
+}
+
+/// Query string of an exec, with one `cmd` per argument.
+fn exec_query(args: &ExecArgs) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    for arg in &args.command {
+        query.append_pair("cmd", arg);
+    }
+    if let Some(container) = &args.container {
+        query.append_pair("container", container);
+    }
+    if args.tty {
+        query.append_pair("tty", "true");
+    }
+    if let Some(namespace) = &args.namespace {
+        query.append_pair("namespace", namespace);
+    }
+    query.finish()
+}
+
+/// Forward stdin as frames, then close the command's stdin at EOF.
+async fn read_stdin(frames: mpsc::Sender<Vec<u8>>) {
+    let mut stdin = tokio::io::stdin();
+    let mut buf = vec![0; 4096];
+    loop {
+        match stdin.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                if frames.send(frame(STDIN, &buf[..n])).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+    let _ = frames.send(vec![STDIN]).await;
+}
+
+/// Send the terminal size now and whenever the window changes.
+async fn watch_terminal_size(frames: mpsc::Sender<Vec<u8>>) {
+    #[cfg(unix)]
+    let mut changes =
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::window_change()) {
+            Ok(changes) => changes,
+            Err(_) => return,
+        };
+    loop {
+        if let Ok((cols, rows)) = crossterm::terminal::size() {
+            if frames.send(resize_frame(cols, rows)).await.is_err() {
+                return;
+            }
+        }
+        #[cfg(unix)]
+        if changes.recv().await.is_none() {
+            return;
+        }
+        #[cfg(not(unix))]
+        return;
+    }
+}
+
+fn frame(channel: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 1);
+    frame.push(channel);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn resize_frame(cols: u16, rows: u16) -> Vec<u8> {
+    let size = serde_json::json!({ "cols": cols, "rows": rows });
+    frame(RESIZE, size.to_string().as_bytes())
+}
+
+fn write_all(out: &mut impl Write, data: &[u8]) {
+    let _ = out.write_all(data);
+    let _ = out.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        exec: ExecArgs,
+    }
+
+    #[test]
+    fn test_exec_query() {
+        let cli = Cli::parse_from([
+            "orch", "-it", "web-1", "-c", "web", "--", "sh", "-c", "ls /",
+        ]);
+        assert!(cli.exec.interactive);
+        assert!(cli.exec.tty);
+        assert_eq!(
+            exec_query(&cli.exec),
+            "cmd=sh&cmd=-c&cmd=ls+%2F&container=web&tty=true"
+        );
+    }
+
+    #[test]
+    fn test_frames() {
+        assert_eq!(frame(STDIN, b"ls\n"), b"\0ls\n");
+        assert_eq!(resize_frame(80, 24), b"\x04{\"cols\":80,\"rows\":24}");
+        assert_eq!(ws_url("https://api:9090"), "wss://api:9090");
+    }
+}
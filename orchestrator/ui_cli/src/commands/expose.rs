@@ -0,0 +1,187 @@
+//! Expose command - open a temporary public tunnel to a workload.
+
+use std::time::Duration;
+
+use clap::Args;
+use clap_complete::engine::ArgValueCandidates;
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::commands::completion;
+use crate::error::{CliError, Result};
+use crate::output::{self, print_item};
+use crate::OutputFormat;
+
+/// Arguments for the expose command.
+#[derive(Args)]
+pub struct ExposeArgs {
+    /// Workload ID or name
+    #[arg(add = ArgValueCandidates::new(completion::workload_names))]
+    workload: String,
+
+    /// Open a time-limited tunnel on the edge node the API URL points at
+    #[arg(long)]
+    temporary: bool,
+
+    /// Container port to forward to (default: the workload's first declared port)
+    #[arg(short, long)]
+    port: Option<u16>,
+
+    /// How long the tunnel stays open, e.g. "90s", "30m" or "2h"
+    #[arg(long, default_value = "1h", value_parser = parse_ttl)]
+    ttl: Duration,
+
+    /// Namespace of the workload (default: look in every namespace)
+    #[arg(long)]
+    namespace: Option<String>,
+}
+
+/// Open tunnel request.
+#[derive(Debug, Serialize)]
+struct OpenTunnelRequest {
+    port: Option<u16>,
+    ttl_secs: u64,
+}
+
+/// Tunnel response from API.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct TunnelResponse {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Public Address")]
+    public_addr: String,
+    #[tabled(rename = "Target Port")]
+    target_port: u16,
+    #[tabled(rename = "Expires")]
+    expires_at: String,
+}
+
+/// Generic list response wrapper from API.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+/// Workload response from API.
+#[derive(Debug, Deserialize)]
+struct WorkloadResponse {
+    id: String,
+    name: String,
+}
+
+/// Execute the expose command.
+pub async fn execute(args: ExposeArgs, api_url: &str, format: OutputFormat) -> anyhow::Result<()> {
+    if !args.temporary {
+        return Err(CliError::invalid_argument(
+            "Only temporary exposure is supported; pass --temporary or configure ingress",
+        )
+        .into());
+    }
+
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for expose. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    let workload_id = find_workload_id(&client, &args.workload, args.namespace.as_deref()).await?;
+    let request = OpenTunnelRequest {
+        port: args.port,
+        ttl_secs: args.ttl.as_secs(),
+    };
+    let tunnel: TunnelResponse = client
+        .post(
+            &format!("/api/v1/workloads/{}/tunnels", workload_id),
+            &request,
+        )
+        .await?;
+
+    output::success(&format!(
+        "Workload '{}' exposed at {} until {}",
+        args.workload, tunnel.public_addr, tunnel.expires_at
+    ));
+    print_item(&tunnel, format)?;
+    output::info(&format!(
+        "The tunnel closes automatically; close it sooner with DELETE /api/v1/tunnels/{}",
+        tunnel.id
+    ));
+
+    Ok(())
+}
+
+/// Parse a lifetime such as "90s", "30m", "2h" or a plain number of seconds.
+fn parse_ttl(value: &str) -> std::result::Result<Duration, String> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, "s"),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", value))?;
+    let seconds = match unit {
+        "s" => number,
+        "m" => number.saturating_mul(60),
+        "h" => number.saturating_mul(60 * 60),
+        _ => {
+            return Err(format!(
+                "invalid duration unit in '{}', use s, m or h",
+                value
+            ))
+        }
+    };
+    if seconds == 0 {
+        return Err("duration must be greater than zero".to_string());
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Find workload ID by name, ID or unique ID prefix.
+async fn find_workload_id(
+    client: &ApiClient,
+    name_or_id: &str,
+    namespace: Option<&str>,
+) -> Result<String> {
+    if uuid::Uuid::parse_str(name_or_id).is_ok() {
+        return Ok(name_or_id.to_string());
+    }
+
+    let path = match namespace {
+        Some(namespace) => format!("/api/v1/workloads?namespace={}", namespace),
+        None => "/api/v1/workloads".to_string(),
+    };
+    let workloads: ListResponse<WorkloadResponse> = client.get(&path).await?;
+
+    let matching: Vec<_> = workloads
+        .items
+        .iter()
+        .filter(|w| w.name == name_or_id || w.id.starts_with(name_or_id))
+        .collect();
+
+    match matching.len() {
+        0 => Err(CliError::WorkloadNotFound(name_or_id.to_string())),
+        1 => Ok(matching[0].id.clone()),
+        _ => Err(CliError::invalid_argument(format!(
+            "Ambiguous workload reference '{}', matches {} workloads. Use full ID.",
+            name_or_id,
+            matching.len()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_ttl("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_ttl("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert_eq!(parse_ttl("45"), Ok(Duration::from_secs(45)));
+        assert!(parse_ttl("0m").is_err());
+        assert!(parse_ttl("1d").is_err());
+        assert!(parse_ttl("m").is_err());
+    }
+}
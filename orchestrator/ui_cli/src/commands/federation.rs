@@ -0,0 +1,230 @@
+//! Federation command - register remote clusters and list them together.
+//!
+//! Other commands reach a registered cluster with `orch --cluster NAME`.
+
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::error::CliError;
+use crate::output::{self, print_data};
+use crate::OutputFormat;
+
+/// Arguments for the federation command.
+#[derive(Args)]
+pub struct FederationArgs {
+    #[command(subcommand)]
+    command: FederationCommand,
+}
+
+#[derive(Subcommand)]
+enum FederationCommand {
+    /// Register a remote cluster
+    Add {
+        /// Name to select the cluster by, e.g. prod-eu
+        name: String,
+
+        /// API URL of the cluster, e.g. https://eu.example.com:9090
+        #[arg(long)]
+        endpoint: String,
+
+        /// Bearer token issued by the cluster, e.g. by `orch login` against it
+        #[arg(long, env = "ORCH_FEDERATION_TOKEN")]
+        token: Option<String>,
+    },
+    /// List registered clusters
+    List,
+    /// Forget a registered cluster
+    Remove {
+        /// Cluster name
+        name: String,
+    },
+    /// List the workloads of every cluster
+    Workloads {
+        /// Only workloads in this namespace
+        #[arg(short, long)]
+        namespace: Option<String>,
+    },
+    /// List the nodes of every cluster
+    Nodes,
+}
+
+/// Remote cluster response from API.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct ClusterResponse {
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Endpoint")]
+    endpoint: String,
+    #[tabled(rename = "Token")]
+    authenticated: bool,
+}
+
+/// List response wrapper.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+/// Objects of every cluster from API.
+#[derive(Debug, Deserialize)]
+struct FederatedList<T> {
+    items: Vec<Federated<T>>,
+    #[serde(default)]
+    unreachable: Vec<UnreachableCluster>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Federated<T> {
+    cluster: String,
+    object: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct UnreachableCluster {
+    cluster: String,
+    error: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkloadObject {
+    id: String,
+    name: String,
+    #[serde(default)]
+    namespace: String,
+    replicas: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct NodeObject {
+    id: String,
+    address: String,
+    status: String,
+}
+
+#[derive(Debug, Serialize, Tabled)]
+struct WorkloadRow {
+    #[tabled(rename = "Cluster")]
+    cluster: String,
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Replicas")]
+    replicas: u32,
+    #[tabled(rename = "ID")]
+    id: String,
+}
+
+#[derive(Debug, Serialize, Tabled)]
+struct NodeRow {
+    #[tabled(rename = "Cluster")]
+    cluster: String,
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Address")]
+    address: String,
+    #[tabled(rename = "Status")]
+    status: String,
+}
+
+/// Warn about the clusters missing from a list.
+fn warn_unreachable(unreachable: &[UnreachableCluster]) {
+    for cluster in unreachable {
+        output::warn(&format!(
+            "Cluster {} is unreachable: {}",
+            cluster.cluster, cluster.error
+        ));
+    }
+}
+
+/// Execute the federation command.
+pub async fn execute(
+    args: FederationArgs,
+    api_url: &str,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for federation. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        FederationCommand::Add {
+            name,
+            endpoint,
+            token,
+        } => {
+            let request = serde_json::json!({
+                "name": name,
+                "endpoint": endpoint,
+                "token": token,
+            });
+            let cluster: ClusterResponse =
+                client.post("/api/v1/federation/clusters", &request).await?;
+            output::success(&format!(
+                "Cluster {} registered at {}",
+                cluster.name, cluster.endpoint
+            ));
+            if !cluster.authenticated {
+                output::warn("No token given; the cluster will see requests as anonymous");
+            }
+            Ok(())
+        }
+        FederationCommand::List => {
+            let response: ListResponse<ClusterResponse> =
+                client.get("/api/v1/federation/clusters").await?;
+            print_data(&response.items, format)?;
+            Ok(())
+        }
+        FederationCommand::Remove { name } => {
+            client
+                .delete(&format!("/api/v1/federation/clusters/{}", name))
+                .await?;
+            output::success(&format!("Cluster {} removed", name));
+            Ok(())
+        }
+        FederationCommand::Workloads { namespace } => {
+            let mut path = "/api/v1/federation/workloads".to_string();
+            if let Some(namespace) = namespace {
+                path.push_str("?namespace=");
+                path.extend(url::form_urlencoded::byte_serialize(namespace.as_bytes()));
+            }
+            let response: FederatedList<WorkloadObject> = client.get(&path).await?;
+            let rows: Vec<WorkloadRow> = response
+                .items
+                .into_iter()
+                .map(|item| WorkloadRow {
+                    cluster: item.cluster,
+                    namespace: item.object.namespace,
+                    name: item.object.name,
+                    replicas: item.object.replicas,
+                    id: item.object.id,
+                })
+                .collect();
+            print_data(&rows, format)?;
+            warn_unreachable(&response.unreachable);
+            Ok(())
+        }
+        FederationCommand::Nodes => {
+            let response: FederatedList<NodeObject> =
+                client.get("/api/v1/federation/nodes").await?;
+            let rows: Vec<NodeRow> = response
+                .items
+                .into_iter()
+                .map(|item| NodeRow {
+                    cluster: item.cluster,
+                    id: item.object.id,
+                    address: item.object.address,
+                    status: item.object.status,
+                })
+                .collect();
+            print_data(&rows, format)?;
+            warn_unreachable(&response.unreachable);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,730 @@
+//! Image command - inspect images, show their vulnerability scans, pre-pull
+//! them onto nodes ahead of a rollout and push locally built images.
+//!
+//! `orch image push` talks to the registry directly rather than through the
+//! API, so CI pipelines can publish images without a cluster.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use container_runtime::{ImageManager, OciLayout, RegistryCredentials};
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::commands::deploy::parse_duration;
+use crate::error::{CliError, Result};
+use crate::output::{self, print_data};
+use crate::OutputFormat;
+
+/// How often progress is fetched while waiting for a pre-pull.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Arguments for the image command.
+#[derive(Args)]
+pub struct ImageArgs {
+    #[command(subcommand)]
+    command: ImageCommand,
+}
+
+#[derive(Subcommand)]
+enum ImageCommand {
+    /// Have nodes pull an image now, so a later rollout starts without waiting for it
+    Prepull {
+        /// Image to pull, e.g. vllm/vllm-openai:v0.6.0
+        image: String,
+
+        /// Only pull onto nodes with this label (key=value, can be specified multiple times)
+        #[arg(short = 'l', long = "selector", value_parser = parse_label)]
+        selector: Vec<(String, String)>,
+
+        /// Return once the pulls have started instead of following them
+        #[arg(long)]
+        no_wait: bool,
+
+        /// How long to follow the pulls (e.g. 90s, 30m, 1h)
+        #[arg(long, default_value = "30m", value_parser = parse_duration)]
+        timeout: Duration,
+    },
+
+    /// Show the progress of pre-pulls
+    Status {
+        /// Pre-pull ID; lists every pre-pull when omitted
+        id: Option<String>,
+    },
+
+    /// Show what an image reference resolves to in its registry, without pulling it
+    Inspect {
+        /// Image reference, e.g. ghcr.io/owner/app:v1
+        image: String,
+    },
+
+    /// Show the vulnerabilities found in an image, scanning it if it has not been yet
+    Scan {
+        /// Image reference, e.g. ghcr.io/owner/app:v1
+        image: String,
+    },
+
+    /// List the images scanned so far, newest first
+    Scans,
+
+    /// Publish a local OCI image layout to a registry, uploading only the blobs it lacks
+    Push {
+        /// OCI image layout directory, e.g. written by `buildah push IMAGE oci:DIR`
+        layout: PathBuf,
+
+        /// Where to push, e.g. registry.internal/team/app:v1; the layout's manifest
+        /// with this tag is pushed, or its only manifest
+        image: String,
+
+        /// Registry username
+        #[arg(long, env = "ORCH_REGISTRY_USERNAME")]
+        username: Option<String>,
+
+        /// Registry password or token
+        #[arg(long, env = "ORCH_REGISTRY_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+    },
+}
+
+/// Pre-pull from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct PrepullResponse {
+    id: String,
+    image: String,
+    #[serde(default)]
+    node_selector: HashMap<String, String>,
+    created_at: DateTime<Utc>,
+    nodes: Vec<NodePullProgress>,
+}
+
+/// Progress of a pre-pull on one node from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct NodePullProgress {
+    node_id: String,
+    state: String,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    started_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    finished_at: Option<DateTime<Utc>>,
+}
+
+/// Inspected image from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct ImageInspectionResponse {
+    reference: String,
+    digest: String,
+    platform: String,
+    size_bytes: u64,
+    layers: Vec<ImageLayerResponse>,
+    #[serde(default)]
+    exposed_ports: Vec<String>,
+    #[serde(default)]
+    env: Vec<String>,
+    #[serde(default)]
+    entrypoint: Vec<String>,
+    #[serde(default)]
+    cmd: Vec<String>,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+}
+
+/// Layer of an inspected image from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct ImageLayerResponse {
+    digest: String,
+    size_bytes: u64,
+}
+
+/// Vulnerability scan of an image from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct ImageScanResponse {
+    image: String,
+    digest: String,
+    scanner: String,
+    scanned_at: DateTime<Utc>,
+    summary: SeverityCounts,
+    #[serde(default)]
+    vulnerabilities: Vec<VulnerabilityResponse>,
+}
+
+/// Vulnerabilities found at each severity from API.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SeverityCounts {
+    critical: usize,
+    high: usize,
+    medium: usize,
+    low: usize,
+    unknown: usize,
+}
+
+/// Vulnerability found by a scan from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct VulnerabilityResponse {
+    id: String,
+    package: String,
+    installed_version: String,
+    #[serde(default)]
+    fixed_version: Option<String>,
+    severity: String,
+}
+
+impl SeverityCounts {
+    /// Non-zero counts on one line, e.g. "2 critical, 5 high".
+    fn summary(&self) -> String {
+        let counts = [
+            (self.critical, "critical"),
+            (self.high, "high"),
+            (self.medium, "medium"),
+            (self.low, "low"),
+            (self.unknown, "unknown"),
+        ];
+        let summary: Vec<String> = counts
+            .iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, severity)| format!("{} {}", count, severity))
+            .collect();
+        if summary.is_empty() {
+            "no vulnerabilities".to_string()
+        } else {
+            summary.join(", ")
+        }
+    }
+}
+
+impl PrepullResponse {
+    fn count(&self, state: &str) -> usize {
+        self.nodes.iter().filter(|n| n.state == state).count()
+    }
+
+    fn is_complete(&self) -> bool {
+        self.count("pulled") + self.count("failed") == self.nodes.len()
+    }
+
+    /// Progress on one line, e.g. "3/10 pulled, 1 failed".
+    fn summary(&self) -> String {
+        let mut summary = format!("{}/{} pulled", self.count("pulled"), self.nodes.len());
+        for state in ["failed", "pulling", "pending"] {
+            let count = self.count(state);
+            if count > 0 {
+                summary.push_str(&format!(", {} {}", count, state));
+            }
+        }
+        summary
+    }
+}
+
+/// Row of the pre-pull list.
+#[derive(Debug, Serialize, Tabled)]
+struct PrepullRow {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Image")]
+    image: String,
+    #[tabled(rename = "Selector")]
+    selector: String,
+    #[tabled(rename = "Progress")]
+    progress: String,
+    #[tabled(rename = "Created")]
+    created_at: String,
+}
+
+impl From<&PrepullResponse> for PrepullRow {
+    fn from(p: &PrepullResponse) -> Self {
+        let mut selector: Vec<String> = p
+            .node_selector
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        selector.sort();
+        Self {
+            id: p.id.clone(),
+            image: p.image.clone(),
+            selector: if selector.is_empty() {
+                "-".to_string()
+            } else {
+                selector.join(",")
+            },
+            progress: p.summary(),
+            created_at: p.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+/// Row of the per-node progress table.
+#[derive(Debug, Serialize, Tabled)]
+struct NodeRow {
+    #[tabled(rename = "Node")]
+    node: String,
+    #[tabled(rename = "State")]
+    state: String,
+    #[tabled(rename = "Took")]
+    took: String,
+    #[tabled(rename = "Error")]
+    error: String,
+}
+
+impl From<&NodePullProgress> for NodeRow {
+    fn from(n: &NodePullProgress) -> Self {
+        let took = match (n.started_at, n.finished_at) {
+            (Some(started), Some(finished)) => {
+                format!("{}s", (finished - started).num_seconds())
+            }
+            _ => "-".to_string(),
+        };
+        Self {
+            node: n.node_id.clone(),
+            state: n.state.clone(),
+            took,
+            error: n.error.clone().unwrap_or_else(|| "-".to_string()),
+        }
+    }
+}
+
+/// Row of the layer table.
+#[derive(Debug, Serialize, Tabled)]
+struct LayerRow {
+    #[tabled(rename = "Digest")]
+    digest: String,
+    #[tabled(rename = "Size")]
+    size: String,
+}
+
+impl From<&ImageLayerResponse> for LayerRow {
+    fn from(l: &ImageLayerResponse) -> Self {
+        Self {
+            digest: l.digest.clone(),
+            size: format_size(l.size_bytes),
+        }
+    }
+}
+
+/// Row of the scan list.
+#[derive(Debug, Serialize, Tabled)]
+struct ScanRow {
+    #[tabled(rename = "Image")]
+    image: String,
+    #[tabled(rename = "Digest")]
+    digest: String,
+    #[tabled(rename = "Vulnerabilities")]
+    vulnerabilities: String,
+    #[tabled(rename = "Scanned")]
+    scanned_at: String,
+}
+
+impl From<&ImageScanResponse> for ScanRow {
+    fn from(s: &ImageScanResponse) -> Self {
+        Self {
+            image: s.image.clone(),
+            digest: s.digest.chars().take(19).collect(),
+            vulnerabilities: s.summary.summary(),
+            scanned_at: s.scanned_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+/// Row of the vulnerability table.
+#[derive(Debug, Serialize, Tabled)]
+struct VulnerabilityRow {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Severity")]
+    severity: String,
+    #[tabled(rename = "Package")]
+    package: String,
+    #[tabled(rename = "Installed")]
+    installed: String,
+    #[tabled(rename = "Fixed In")]
+    fixed: String,
+}
+
+impl From<&VulnerabilityResponse> for VulnerabilityRow {
+    fn from(v: &VulnerabilityResponse) -> Self {
+        Self {
+            id: v.id.clone(),
+            severity: v.severity.clone(),
+            package: v.package.clone(),
+            installed: v.installed_version.clone(),
+            fixed: v.fixed_version.clone().unwrap_or_else(|| "-".to_string()),
+        }
+    }
+}
+
+/// Bytes in binary units, e.g. "1.5 GiB".
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// API path of `action` on `image`; `/` and `:` in the reference are percent-encoded.
+fn image_path(image: &str, action: &str) -> String {
+    let image: String = url::form_urlencoded::byte_serialize(image.as_bytes()).collect();
+    format!("/api/v1/images/{}/{}", image, action)
+}
+
+/// Parse a key=value label.
+fn parse_label(s: &str) -> std::result::Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid label format '{}'. Expected key=value", s))?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Execute the image command.
+pub async fn execute(args: ImageArgs, api_url: &str, format: OutputFormat) -> anyhow::Result<()> {
+    if let ImageCommand::Push {
+        layout,
+        image,
+        username,
+        password,
+    } = args.command
+    {
+        return push(layout, &image, username, password, format).await;
+    }
+
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for image operations. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        ImageCommand::Prepull {
+            image,
+            selector,
+            no_wait,
+            timeout,
+        } => {
+            let request = serde_json::json!({
+                "image": image,
+                "node_selector": selector.into_iter().collect::<HashMap<_, _>>(),
+            });
+            let prepull: PrepullResponse = client.post("/api/v1/images/prepull", &request).await?;
+            output::info(&format!(
+                "Pulling {} onto {} node(s) (pre-pull {})",
+                prepull.image,
+                prepull.nodes.len(),
+                prepull.id
+            ));
+            if no_wait {
+                output::info("Use 'orch image status' to follow the pulls");
+                return Ok(());
+            }
+            let prepull = follow(&client, &prepull.id, timeout).await?;
+            print_nodes(&prepull, format)?;
+            let failed = prepull.count("failed");
+            if failed > 0 {
+                return Err(CliError::Other(format!(
+                    "{} failed on {} of {} node(s)",
+                    prepull.image,
+                    failed,
+                    prepull.nodes.len()
+                ))
+                .into());
+            }
+            output::success(&format!(
+                "{} is cached on {} node(s)",
+                prepull.image,
+                prepull.nodes.len()
+            ));
+            Ok(())
+        }
+        ImageCommand::Status { id: Some(id) } => {
+            let prepull: PrepullResponse = client
+                .get(&format!("/api/v1/images/prepull/{}", id))
+                .await?;
+            output::info(&format!("{}: {}", prepull.image, prepull.summary()));
+            print_nodes(&prepull, format)
+        }
+        ImageCommand::Status { id: None } => {
+            let prepulls: Vec<PrepullResponse> = client.get("/api/v1/images/prepull").await?;
+            match format {
+                OutputFormat::Table | OutputFormat::Wide => {
+                    let rows: Vec<PrepullRow> = prepulls.iter().map(Into::into).collect();
+                    print_data(&rows, format)
+                }
+                // Other formats keep the API's shape
+                _ => output::print_value(&prepulls, format),
+            }
+        }
+        ImageCommand::Inspect { image } => {
+            let image: ImageInspectionResponse = client.get(&image_path(&image, "inspect")).await?;
+            match format {
+                OutputFormat::Table | OutputFormat::Wide => print_inspection(&image, format),
+                _ => output::print_value(&image, format),
+            }
+        }
+        ImageCommand::Scan { image } => {
+            let scan: ImageScanResponse = client.get(&image_path(&image, "scan")).await?;
+            match format {
+                OutputFormat::Table | OutputFormat::Wide => print_scan(&scan, format),
+                _ => output::print_value(&scan, format),
+            }
+        }
+        ImageCommand::Scans => {
+            let scans: Vec<ImageScanResponse> = client.get("/api/v1/images/scans").await?;
+            match format {
+                OutputFormat::Table | OutputFormat::Wide => {
+                    let rows: Vec<ScanRow> = scans.iter().map(Into::into).collect();
+                    print_data(&rows, format)
+                }
+                _ => output::print_value(&scans, format),
+            }
+        }
+        ImageCommand::Push { .. } => unreachable!("pushes do not go through the API"),
+    }
+}
+
+fn print_inspection(image: &ImageInspectionResponse, format: OutputFormat) -> anyhow::Result<()> {
+    let or_dash = |values: &[String]| {
+        if values.is_empty() {
+            "-".to_string()
+        } else {
+            values.join(" ")
+        }
+    };
+    println!("  {} {}", "Reference:".dimmed(), image.reference);
+    println!("  {} {}", "Digest:".dimmed(), image.digest);
+    println!("  {} {}", "Platform:".dimmed(), image.platform);
+    println!("  {} {}", "Size:".dimmed(), format_size(image.size_bytes));
+    println!(
+        "  {} {}",
+        "Entrypoint:".dimmed(),
+        or_dash(&image.entrypoint)
+    );
+    println!("  {} {}", "Cmd:".dimmed(), or_dash(&image.cmd));
+    println!("  {} {}", "Ports:".dimmed(), or_dash(&image.exposed_ports));
+
+    if !image.env.is_empty() {
+        output::section("Environment");
+        for var in &image.env {
+            println!("  {}", var);
+        }
+    }
+    if !image.labels.is_empty() {
+        output::section("Labels");
+        for (key, value) in &image.labels {
+            println!("  {} {}", format!("{}:", key).dimmed(), value);
+        }
+    }
+
+    output::section("Layers");
+    let rows: Vec<LayerRow> = image.layers.iter().map(Into::into).collect();
+    print_data(&rows, format)
+}
+
+/// Push a local OCI image layout to `image`.
+async fn push(
+    layout: PathBuf,
+    image: &str,
+    username: Option<String>,
+    password: Option<String>,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let image_ref = ImageManager::parse_image_ref(image)
+        .map_err(|e| CliError::InvalidArgument(e.to_string()))?;
+    let layout = OciLayout::open(layout).map_err(|e| CliError::InvalidArgument(e.to_string()))?;
+
+    let mut manager = ImageManager::new(std::env::temp_dir().join("orch-images"))
+        .map_err(|e| CliError::Other(e.to_string()))?;
+    match (username, password) {
+        (Some(username), Some(password)) => {
+            manager = manager.with_registry_credentials(
+                image_ref.registry.clone(),
+                RegistryCredentials { username, password },
+            );
+        }
+        (None, None) => {}
+        _ => {
+            return Err(CliError::InvalidArgument(
+                "--username and --password must be given together".to_string(),
+            )
+            .into())
+        }
+    }
+
+    output::info(&format!("Pushing to {}", image_ref));
+    let summary = manager
+        .push(&image_ref, &layout)
+        .await
+        .map_err(|e| CliError::Other(format!("Push failed: {}", e)))?;
+    match format {
+        OutputFormat::Table | OutputFormat::Wide => {
+            output::success(&format!(
+                "Pushed {}@{}: {} blob(s) uploaded ({}), {} already present",
+                image_ref,
+                summary.digest,
+                summary.uploaded,
+                format_size(summary.bytes),
+                summary.existing
+            ));
+            Ok(())
+        }
+        _ => output::print_value(
+            &serde_json::json!({
+                "image": image_ref.to_string(),
+                "digest": summary.digest,
+                "uploaded": summary.uploaded,
+                "existing": summary.existing,
+                "bytes": summary.bytes,
+            }),
+            format,
+        ),
+    }
+}
+
+fn print_scan(scan: &ImageScanResponse, format: OutputFormat) -> anyhow::Result<()> {
+    println!("  {} {}", "Image:".dimmed(), scan.image);
+    println!("  {} {}", "Digest:".dimmed(), scan.digest);
+    println!(
+        "  {} {} at {}",
+        "Scanned:".dimmed(),
+        scan.scanner,
+        scan.scanned_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    let summary = scan.summary.summary();
+    let summary = if scan.summary.critical + scan.summary.high > 0 {
+        summary.red()
+    } else if scan.vulnerabilities.is_empty() {
+        summary.green()
+    } else {
+        summary.yellow()
+    };
+    println!("  {} {}", "Found:".dimmed(), summary);
+
+    if scan.vulnerabilities.is_empty() {
+        return Ok(());
+    }
+    output::section("Vulnerabilities");
+    let rows: Vec<VulnerabilityRow> = scan.vulnerabilities.iter().map(Into::into).collect();
+    print_data(&rows, format)
+}
+
+/// Poll a pre-pull until every node is done, reporting progress as it changes.
+async fn follow(client: &ApiClient, id: &str, timeout: Duration) -> Result<PrepullResponse> {
+    let path = format!("/api/v1/images/prepull/{}", id);
+    let started = Instant::now();
+    let mut last = String::new();
+    loop {
+        let prepull: PrepullResponse = client.get(&path).await?;
+        let summary = prepull.summary();
+        if summary != last {
+            output::info(&summary);
+            last = summary;
+        }
+        if prepull.is_complete() {
+            return Ok(prepull);
+        }
+        if started.elapsed() + POLL_INTERVAL > timeout {
+            return Err(CliError::Other(format!(
+                "Timed out following pre-pull {} ({}). The pulls continue; \
+                 run 'orch image status {}' to check on them.",
+                id, last, id
+            )));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn print_nodes(prepull: &PrepullResponse, format: OutputFormat) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Table | OutputFormat::Wide => {
+            let rows: Vec<NodeRow> = prepull.nodes.iter().map(Into::into).collect();
+            print_data(&rows, format)
+        }
+        _ => output::print_value(prepull, format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepull(states: &[&str]) -> PrepullResponse {
+        PrepullResponse {
+            id: "p1".to_string(),
+            image: "vllm:0.6".to_string(),
+            node_selector: HashMap::from([("gpu".to_string(), "true".to_string())]),
+            created_at: Utc::now(),
+            nodes: states
+                .iter()
+                .enumerate()
+                .map(|(i, state)| NodePullProgress {
+                    node_id: format!("node-{}", i),
+                    state: state.to_string(),
+                    error: None,
+                    started_at: None,
+                    finished_at: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_summary_and_completion() {
+        let running = prepull(&["pulled", "pulling", "pending", "failed"]);
+        assert_eq!(
+            running.summary(),
+            "1/4 pulled, 1 failed, 1 pulling, 1 pending"
+        );
+        assert!(!running.is_complete());
+
+        let done = prepull(&["pulled", "pulled"]);
+        assert_eq!(done.summary(), "2/2 pulled");
+        assert!(done.is_complete());
+        assert_eq!(PrepullRow::from(&done).selector, "gpu=true");
+    }
+
+    #[test]
+    fn test_node_row() {
+        let started = Utc::now();
+        let node = NodePullProgress {
+            node_id: "node-1".to_string(),
+            state: "failed".to_string(),
+            error: Some("manifest unknown".to_string()),
+            started_at: Some(started),
+            finished_at: Some(started + chrono::Duration::seconds(42)),
+        };
+        let row = NodeRow::from(&node);
+        assert_eq!(row.took, "42s");
+        assert_eq!(row.error, "manifest unknown");
+    }
+
+    #[test]
+    fn test_image_path_and_sizes() {
+        assert_eq!(
+            image_path("ghcr.io/owner/app:v1", "inspect"),
+            "/api/v1/images/ghcr.io%2Fowner%2Fapp%3Av1/inspect"
+        );
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_severity_summary() {
+        let counts = SeverityCounts {
+            critical: 2,
+            low: 5,
+            ..Default::default()
+        };
+        assert_eq!(counts.summary(), "2 critical, 5 low");
+        assert_eq!(SeverityCounts::default().summary(), "no vulnerabilities");
+    }
+}
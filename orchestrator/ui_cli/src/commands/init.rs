@@ -1,9 +1,13 @@
 //! Initialize user configuration and identity.
 
+use std::path::{Path, PathBuf};
+
 use clap::Args;
 use colored::Colorize;
 use user_config::{ConfigPaths, UserConfig};
 
+use crate::client::{ApiClient, StoredCertificate};
+use crate::error::CliError;
 use crate::output;
 
 /// Arguments for the init command.
@@ -20,10 +24,15 @@ pub struct InitArgs {
     /// Email for the identity
     #[arg(short, long)]
     email: Option<String>,
+
+    /// Cluster CA certificate (`ca.crt` from the nodes' TLS_CA_DIR); requests
+    /// a client certificate from --api-url for mutual TLS, or renews it
+    #[arg(long, value_name = "FILE")]
+    ca_cert: Option<PathBuf>,
 }
 
 /// Execute the init command.
-pub async fn execute(args: InitArgs) -> anyhow::Result<()> {
+pub async fn execute(args: InitArgs, api_url: &str) -> anyhow::Result<()> {
     let paths = ConfigPaths::new()?;
 
     // Check if config already exists
@@ -57,6 +66,9 @@ pub async fn execute(args: InitArgs) -> anyhow::Result<()> {
             identity.created_at().format("%Y-%m-%d %H:%M:%S UTC")
         );
 
+        if let Some(ca_cert) = &args.ca_cert {
+            request_certificate(&config, api_url, ca_cert).await?;
+        }
         return Ok(());
     }
 
@@ -119,6 +131,10 @@ pub async fn execute(args: InitArgs) -> anyhow::Result<()> {
         paths.secrets_dir().display()
     );
 
+    if let Some(ca_cert) = &args.ca_cert {
+        request_certificate(&config, api_url, ca_cert).await?;
+    }
+
     output::section("Next Steps");
     println!("  1. Share your public key with cluster administrators");
     println!("  2. Configure trust policy: {}", paths.trust_policy_file().display());
@@ -126,3 +142,30 @@ pub async fn execute(args: InitArgs) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Request a client certificate from the API, verifying it against the CA
+/// certificate at `ca_path`.
+async fn request_certificate(
+    config: &UserConfig,
+    api_url: &str,
+    ca_path: &Path,
+) -> anyhow::Result<()> {
+    let ca_certificate = tokio::fs::read_to_string(ca_path).await.map_err(|e| {
+        CliError::config_error(format!("Cannot read {}: {}", ca_path.display(), e))
+    })?;
+    let client = ApiClient::new(api_url)
+        .with_signing_key(config.identity().signing_key())
+        .with_tls(&ca_certificate, None)?;
+    let certificate = StoredCertificate::request(&client).await?;
+    certificate.save(config).await?;
+
+    output::success("Client certificate issued");
+    output::section("Client Certificate");
+    println!("  {} {}", "API:".dimmed(), certificate.api_url);
+    println!(
+        "  {} {}",
+        "Expires:".dimmed(),
+        certificate.expires_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    Ok(())
+}
@@ -0,0 +1,244 @@
+//! Instance command - list instances, restart, migrate, resize or delete a
+//! single one.
+
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::commands::node::find_node_id;
+use crate::error::{CliError, Result};
+use crate::output::{self, print_data, print_item};
+use crate::watch::{self, Watched};
+use crate::OutputFormat;
+
+/// Arguments for the instance command.
+#[derive(Args)]
+pub struct InstanceArgs {
+    #[command(subcommand)]
+    command: InstanceCommand,
+}
+
+#[derive(Subcommand)]
+enum InstanceCommand {
+    /// List instances
+    List {
+        /// Show only instances in this namespace
+        #[arg(long)]
+        namespace: Option<String>,
+
+        /// Keep the table up to date as instances change
+        #[arg(short, long)]
+        watch: bool,
+    },
+    /// Replace an instance with a fresh one
+    Restart(InstanceRef),
+    /// Checkpoint a running instance and resume it on another node
+    Migrate {
+        #[command(flatten)]
+        instance: InstanceRef,
+
+        /// Node ID or unique ID prefix to move the instance to
+        #[arg(long)]
+        to: String,
+    },
+    /// Change the resource limits of a container of an instance in place
+    Resize(ResizeArgs),
+    /// Stop and remove an instance; its workload gets a replacement
+    Delete(InstanceRef),
+}
+
+#[derive(Args)]
+struct ResizeArgs {
+    #[command(flatten)]
+    instance: InstanceRef,
+
+    /// Container to resize (default: the first main container)
+    #[arg(short, long)]
+    container: Option<String>,
+
+    /// CPU limit in cores, e.g. 0.5
+    #[arg(long)]
+    cpu: Option<f32>,
+
+    /// Memory limit in megabytes
+    #[arg(long)]
+    memory: Option<u64>,
+
+    /// Maximum number of processes
+    #[arg(long)]
+    pids: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateResourcesRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    container: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_cores: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory_mb: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pids_limit: Option<i64>,
+}
+
+#[derive(Args)]
+struct InstanceRef {
+    /// Instance ID or unique ID prefix
+    instance: String,
+
+    /// Namespace of the instance (default: look in every namespace)
+    #[arg(long)]
+    namespace: Option<String>,
+}
+
+/// Generic list response wrapper from API.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+/// Instance response from API.
+#[derive(Debug, Clone, Serialize, Deserialize, Tabled)]
+struct InstanceResponse {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Workload")]
+    workload_id: String,
+    #[tabled(rename = "Node")]
+    node_id: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(skip)]
+    #[serde(default)]
+    namespace: String,
+    #[tabled(skip)]
+    #[serde(default)]
+    ip_addresses: Vec<String>,
+}
+
+impl Watched for InstanceResponse {
+    type Row = InstanceResponse;
+    const KIND: &'static str = "instance";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn state(&self) -> String {
+        self.status.clone()
+    }
+
+    fn row(&self) -> InstanceResponse {
+        self.clone()
+    }
+}
+
+/// Execute the instance command.
+pub async fn execute(
+    args: InstanceArgs,
+    api_url: &str,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for instance operations. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        InstanceCommand::List { namespace, watch } => {
+            let path = match namespace {
+                Some(namespace) => format!("/api/v1/instances?namespace={}", namespace),
+                None => "/api/v1/instances".to_string(),
+            };
+            if watch {
+                let stream = client.watch(&path).await?;
+                watch::watch_table::<InstanceResponse>(stream, "Instances", format).await?;
+            } else {
+                let instances: ListResponse<InstanceResponse> = client.get(&path).await?;
+                print_data(&instances.items, format)?;
+            }
+        }
+        InstanceCommand::Restart(instance) => {
+            let namespace = instance.namespace.as_deref();
+            let id = find_instance_id(&client, &instance.instance, namespace).await?;
+            let response: InstanceResponse = client
+                .post(&format!("/api/v1/instances/{}/restart", id), &())
+                .await?;
+            output::success(&format!("Instance {} is being replaced by a fresh one", id));
+            print_item(&response, format)?;
+        }
+        InstanceCommand::Migrate { instance, to } => {
+            let namespace = instance.namespace.as_deref();
+            let id = find_instance_id(&client, &instance.instance, namespace).await?;
+            let node_id = find_node_id(&client, &to).await?;
+            let response: InstanceResponse = client
+                .post(
+                    &format!("/api/v1/instances/{}/migrate", id),
+                    &serde_json::json!({ "node_id": node_id }),
+                )
+                .await?;
+            output::success(&format!("Instance {} migrated to node {}", id, node_id));
+            print_item(&response, format)?;
+        }
+        InstanceCommand::Resize(args) => {
+            if args.cpu.is_none() && args.memory.is_none() && args.pids.is_none() {
+                return Err(CliError::invalid_argument(
+                    "Set at least one of --cpu, --memory and --pids",
+                )
+                .into());
+            }
+            let namespace = args.instance.namespace.as_deref();
+            let id = find_instance_id(&client, &args.instance.instance, namespace).await?;
+            let request = UpdateResourcesRequest {
+                container: args.container,
+                cpu_cores: args.cpu,
+                memory_mb: args.memory,
+                pids_limit: args.pids,
+            };
+            client
+                .patch_empty(&format!("/api/v1/instances/{}/resources", id), &request)
+                .await?;
+            output::success(&format!("Resource limits of instance {} updated", id));
+        }
+        InstanceCommand::Delete(instance) => {
+            let namespace = instance.namespace.as_deref();
+            let id = find_instance_id(&client, &instance.instance, namespace).await?;
+            client.delete(&format!("/api/v1/instances/{}", id)).await?;
+            output::success(&format!("Instance {} deleted", id));
+        }
+    }
+
+    Ok(())
+}
+
+/// Find instance ID by full ID or unique prefix.
+pub(crate) async fn find_instance_id(
+    client: &ApiClient,
+    prefix: &str,
+    namespace: Option<&str>,
+) -> Result<String> {
+    let path = match namespace {
+        Some(namespace) => format!("/api/v1/instances?namespace={}", namespace),
+        None => "/api/v1/instances".to_string(),
+    };
+    let instances: ListResponse<InstanceResponse> = client.get(&path).await?;
+
+    let matching: Vec<_> = instances
+        .items
+        .iter()
+        .filter(|i| i.id.starts_with(prefix))
+        .collect();
+
+    match matching.len() {
+        0 => Err(CliError::InstanceNotFound(prefix.to_string())),
+        1 => Ok(matching[0].id.clone()),
+        _ => Err(CliError::invalid_argument(format!(
+            "Ambiguous instance reference '{}', matches {} instances. Use full ID.",
+            prefix,
+            matching.len()
+        ))),
+    }
+}
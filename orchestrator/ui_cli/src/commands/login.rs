@@ -0,0 +1,110 @@
+//! Login and logout commands - store or remove a bearer token for the API.
+
+use clap::Args;
+use serde::Deserialize;
+use user_config::UserConfig;
+
+use crate::client::{token_expiry, ApiClient, StoredToken};
+use crate::error::CliError;
+use crate::output;
+
+/// Arguments for the login command.
+#[derive(Args)]
+pub struct LoginArgs {
+    /// Store this token from your OIDC provider (e.g. Keycloak or Auth0)
+    /// instead of requesting one from the cluster with your identity key
+    #[arg(long, env = "ORCH_OIDC_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+}
+
+/// Token issued by the cluster.
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    token: String,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Caller identity reported by the API.
+#[derive(Debug, Deserialize)]
+struct WhoAmIResponse {
+    subject: String,
+    rbac_enabled: bool,
+    #[serde(default)]
+    bindings: Vec<RoleBinding>,
+}
+
+/// Role binding reported by the API.
+#[derive(Debug, Deserialize)]
+struct RoleBinding {
+    role: String,
+    namespace: Option<String>,
+}
+
+/// Execute the login command.
+pub async fn execute(args: LoginArgs, api_url: &str) -> anyhow::Result<()> {
+    let config = UserConfig::load_or_create().await?;
+    let signed = ApiClient::signed(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for login. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    let (token, expires_at) = match args.token {
+        Some(token) => {
+            let expires_at = token_expiry(&token);
+            (token, expires_at)
+        }
+        None => {
+            let issued: TokenResponse = signed
+                .post("/api/v1/auth/token", &serde_json::json!({}))
+                .await?;
+            (issued.token, Some(issued.expires_at))
+        }
+    };
+
+    // Check the token works before keeping it
+    let api_url = signed.base_url().to_string();
+    let whoami: WhoAmIResponse = signed
+        .with_bearer_token(token.clone())
+        .get("/api/v1/auth/whoami")
+        .await?;
+
+    StoredToken {
+        api_url: api_url.clone(),
+        token,
+        expires_at,
+    }
+    .save(&config)
+    .await?;
+
+    output::success(&format!("Logged in to {} as {}", api_url, whoami.subject));
+    if let Some(expires_at) = expires_at {
+        output::info(&format!("Token expires at {}", expires_at.to_rfc3339()));
+    }
+    if whoami.rbac_enabled {
+        if whoami.bindings.is_empty() {
+            output::warn("No roles are bound to you; every request will be denied");
+        }
+        for binding in &whoami.bindings {
+            let scope = binding
+                .namespace
+                .as_deref()
+                .map(|ns| format!("namespace {}", ns))
+                .unwrap_or_else(|| "cluster-wide".to_string());
+            output::info(&format!("Role: {} ({})", binding.role, scope));
+        }
+    }
+    Ok(())
+}
+
+/// Execute the logout command.
+pub async fn logout() -> anyhow::Result<()> {
+    let config = UserConfig::load_or_create().await?;
+    if StoredToken::delete(&config).await? {
+        output::success("Logged out; requests are signed with your identity key again");
+    } else {
+        output::info("Not logged in");
+    }
+    Ok(())
+}
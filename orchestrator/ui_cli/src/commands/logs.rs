@@ -1,28 +1,57 @@
 //! Logs command - view workload logs.
+//!
+//! Lines of every instance of the workload are merged by timestamp and
+//! prefixed with the instance and container they come from, in a color per
+//! instance. `--follow` streams new lines over a WebSocket and reconnects when
+//! the connection drops, resuming after the last line shown.
 
-#![allow(dead_code)]
+use std::collections::HashMap;
+use std::time::Duration;
 
+use chrono::{DateTime, SecondsFormat, Utc};
 use clap::Args;
-use colored::Colorize;
+use clap_complete::engine::ArgValueCandidates;
+use colored::{Color, ColoredString, Colorize};
 use futures_util::StreamExt;
 use serde::Deserialize;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::{connect_async, tungstenite, tungstenite::Message};
 
-use crate::client::ApiClient;
+use crate::client::{require_local_cluster, ApiClient};
+use crate::commands::{completion, deploy};
 use crate::error::{CliError, Result};
 use crate::output;
 
+/// Colors of the prefixes of instances, in the order instances are first seen.
+const INSTANCE_COLORS: &[Color] = &[
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Blue,
+    Color::BrightRed,
+];
+
+/// Reconnects in a row, without a line received in between, before
+/// `--follow` gives up.
+const MAX_RECONNECTS: u32 = 10;
+
+/// Longest wait before reconnecting.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
 /// Arguments for the logs command.
 #[derive(Args)]
 pub struct LogsArgs {
     /// Workload ID or name
+    #[arg(add = ArgValueCandidates::new(completion::workload_names))]
     workload: String,
 
     /// Follow logs (stream new entries)
     #[arg(short, long)]
     follow: bool,
 
-    /// Number of lines to show (tail)
+    /// Number of lines to show (tail) of each container
     #[arg(short = 'n', long, default_value = "100")]
     tail: usize,
 
@@ -30,27 +59,44 @@ pub struct LogsArgs {
     #[arg(short, long)]
     timestamps: bool,
 
-    /// Filter logs since timestamp (RFC3339)
+    /// Only show logs since a time (RFC3339) or for a duration back (e.g. 10m,
+    /// 1h)
     #[arg(long)]
     since: Option<String>,
 
-    /// Filter logs until timestamp (RFC3339)
-    #[arg(long)]
+    /// Only show logs until a time (RFC3339)
+    #[arg(long, conflicts_with = "follow")]
     until: Option<String>,
 
-    /// Filter by instance ID
+    /// Only show logs of this instance (ID or ID prefix)
     #[arg(short, long)]
     instance: Option<String>,
 
-    /// Filter by container name
+    /// Only show logs of this container (name or ID prefix)
     #[arg(short, long)]
     container: Option<String>,
+
+    /// Namespace of the workload (default: look in every namespace)
+    #[arg(long)]
+    namespace: Option<String>,
+}
+
+impl LogsArgs {
+    /// Whether the filters let through lines of `container_id` of `instance_id`.
+    fn shows(&self, instance_id: &str, container_id: &str) -> bool {
+        self.instance
+            .as_ref()
+            .is_none_or(|filter| instance_id.starts_with(filter.as_str()))
+            && self.container.as_ref().is_none_or(|filter| {
+                container_name(container_id) == filter || container_id.starts_with(filter.as_str())
+            })
+    }
 }
 
-/// List workloads response.
+/// List response wrapper.
 #[derive(Debug, Deserialize)]
-struct ListWorkloadsResponse {
-    items: Vec<WorkloadResponse>,
+struct ListResponse<T> {
+    items: Vec<T>,
 }
 
 /// Workload response for finding by name.
@@ -64,25 +110,12 @@ struct WorkloadResponse {
 #[derive(Debug, Deserialize)]
 struct InstanceResponse {
     id: String,
-    node_id: String,
-    status: String,
-    container_ids: Vec<String>,
-}
-
-/// Instances list response.
-#[derive(Debug, Deserialize)]
-struct ListInstancesResponse {
-    items: Vec<InstanceResponse>,
 }
 
 /// Log response from REST API.
 #[derive(Debug, Deserialize)]
 struct LogsResponse {
-    workload_id: String,
-    instance_id: Option<String>,
-    container_id: Option<String>,
     logs: String,
-    lines: usize,
 }
 
 /// WebSocket log message.
@@ -92,10 +125,115 @@ struct WsLogMessage {
     instance_id: Option<String>,
     log: Option<String>,
     error: Option<String>,
-    status: Option<String>,
     info: Option<String>,
 }
 
+/// One line of a container's log.
+#[derive(Debug, Clone, PartialEq)]
+struct LogLine {
+    /// RFC3339; empty for lines without one.
+    timestamp: String,
+    instance_id: String,
+    container_id: String,
+    message: String,
+}
+
+impl LogLine {
+    /// Parse a `TIMESTAMP STREAM MESSAGE` line as the API returns it.
+    fn parse(instance_id: &str, container_id: &str, line: &str) -> Self {
+        let (timestamp, rest) = match line.split_once(' ') {
+            Some((first, rest)) if first.contains('T') && first.len() > 10 => (first, rest),
+            _ => ("", line),
+        };
+        let message = match rest.split_once(' ') {
+            Some(("stdout" | "stderr", message)) => message,
+            _ => rest,
+        };
+        Self {
+            timestamp: timestamp.to_string(),
+            instance_id: instance_id.to_string(),
+            container_id: container_id.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Prefixes of lines, colored by instance.
+#[derive(Default)]
+struct Prefixes {
+    colors: HashMap<String, Color>,
+}
+
+impl Prefixes {
+    fn prefix(&mut self, line: &LogLine) -> ColoredString {
+        let next = INSTANCE_COLORS[self.colors.len() % INSTANCE_COLORS.len()];
+        let color = *self.colors.entry(line.instance_id.clone()).or_insert(next);
+        format!(
+            "[{}/{}]",
+            short(&line.instance_id),
+            container_name(&line.container_id)
+        )
+        .color(color)
+    }
+
+    fn print(&mut self, line: &LogLine, show_timestamps: bool) {
+        let prefix = self.prefix(line);
+        if show_timestamps && !line.timestamp.is_empty() {
+            println!("{} {} {}", prefix, line.timestamp.dimmed(), line.message);
+        } else {
+            println!("{} {}", prefix, line.message);
+        }
+    }
+}
+
+/// How far a followed container's log has been shown: the timestamp of the
+/// last line, and how many lines with that timestamp were shown.
+#[derive(Debug, Default)]
+struct Cursor {
+    timestamp: String,
+    shown: usize,
+    /// Lines at `timestamp` the server sends again after a reconnect.
+    repeated: usize,
+}
+
+impl Cursor {
+    /// Whether the line at `timestamp` is new, moving past it if so.
+    fn advance(&mut self, timestamp: &str) -> bool {
+        match timestamp.cmp(self.timestamp.as_str()) {
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Equal if self.repeated > 0 => {
+                self.repeated -= 1;
+                false
+            }
+            std::cmp::Ordering::Equal => {
+                self.shown += 1;
+                true
+            }
+            std::cmp::Ordering::Greater => {
+                self.timestamp = timestamp.to_string();
+                self.shown = 1;
+                self.repeated = 0;
+                true
+            }
+        }
+    }
+
+    /// Expect the lines at the cursor again, as a stream resumed there sends
+    /// them.
+    fn resume(&mut self) {
+        self.repeated = self.shown;
+    }
+}
+
+/// State of `--follow` across reconnects.
+#[derive(Default)]
+struct Follow {
+    cursors: HashMap<String, Cursor>,
+    prefixes: Prefixes,
+    /// Whether a line was shown since the last reconnect.
+    received: bool,
+}
+
 /// Execute the logs command.
 pub async fn execute(args: LogsArgs, api_url: &str) -> anyhow::Result<()> {
     let client = match ApiClient::authenticated(api_url).await {
@@ -107,234 +245,295 @@ pub async fn execute(args: LogsArgs, api_url: &str) -> anyhow::Result<()> {
     };
 
     // Find the workload
-    let workload_id = find_workload_id(&client, &args.workload).await?;
+    let workload_id = find_workload_id(&client, &args.workload, args.namespace.as_deref()).await?;
+    let since = args
+        .since
+        .as_deref()
+        .map(|since| resolve_since(since, Utc::now()))
+        .transpose()?;
 
     if args.follow {
-        // WebSocket streaming mode
-        stream_logs(api_url, &workload_id, &args).await
+        follow_logs(&client, &workload_id, &args, since).await
     } else {
-        // REST API fetch mode
-        fetch_logs(&client, &workload_id, &args).await
+        fetch_logs(&client, &workload_id, &args, since).await
     }
 }
 
-/// Fetch logs via REST API.
-async fn fetch_logs(client: &ApiClient, workload_id: &str, args: &LogsArgs) -> anyhow::Result<()> {
-    output::info(&format!("Fetching logs for workload '{}'...", args.workload));
-
-    // Build query parameters
-    let mut query_params = vec![format!("tail={}", args.tail)];
-
-    if args.timestamps {
-        query_params.push("timestamps=true".to_string());
-    }
-
-    if let Some(ref since) = args.since {
-        query_params.push(format!("since={}", since));
+/// Fetch the logs of every instance and print them merged by timestamp.
+async fn fetch_logs(
+    client: &ApiClient,
+    workload_id: &str,
+    args: &LogsArgs,
+    since: Option<String>,
+) -> anyhow::Result<()> {
+    let path = format!("/api/v1/workloads/{}/instances", workload_id);
+    let instances: ListResponse<InstanceResponse> = client.get(&path).await?;
+
+    // Timestamps are always fetched, to merge lines by them
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    query.append_pair("tail", &args.tail.to_string());
+    query.append_pair("timestamps", "true");
+    if let Some(since) = &since {
+        query.append_pair("since", since);
     }
-
-    if let Some(ref until) = args.until {
-        query_params.push(format!("until={}", until));
+    if let Some(until) = &args.until {
+        query.append_pair("until", until);
     }
-
-    let query_string = if query_params.is_empty() {
-        String::new()
-    } else {
-        format!("?{}", query_params.join("&"))
-    };
-
-    // Fetch logs - either for specific instance or all instances
-    let response: LogsResponse = if let Some(ref instance_id) = args.instance {
+    let query = query.finish();
+
+    let mut lines = Vec::new();
+    for instance in &instances.items {
+        if args
+            .instance
+            .as_ref()
+            .is_some_and(|filter| !instance.id.starts_with(filter.as_str()))
+        {
+            continue;
+        }
         let path = format!(
-            "/api/v1/workloads/{}/instances/{}/logs{}",
-            workload_id, instance_id, query_string
+            "/api/v1/workloads/{}/instances/{}/logs?{}",
+            workload_id, instance.id, query
         );
-        client.get(&path).await?
-    } else {
-        let path = format!("/api/v1/workloads/{}/logs{}", workload_id, query_string);
-        client.get(&path).await?
-    };
+        let response: LogsResponse = client.get(&path).await?;
+        for (container_id, text) in sections(&response.logs) {
+            if !args.shows(&instance.id, container_id) {
+                continue;
+            }
+            lines.extend(
+                text.iter()
+                    .map(|line| LogLine::parse(&instance.id, container_id, line)),
+            );
+        }
+    }
 
-    // Display logs
-    if response.logs.is_empty() {
+    if lines.is_empty() {
         output::warn("No logs found for this workload");
         return Ok(());
     }
+    // Stable, so lines of one container keep their order on equal timestamps
+    lines.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    let mut prefixes = Prefixes::default();
+    for line in &lines {
+        prefixes.print(line, args.timestamps);
+    }
+    Ok(())
+}
 
-    println!();
-
-    // Apply container filter if specified
-    for section in response.logs.split("\n\n") {
-        if section.is_empty() {
-            continue;
-        }
-
-        // Check for container header
-        if let Some(first_line) = section.lines().next() {
-            if first_line.starts_with("=== Container:") {
-                // Extract container ID
-                let container_id = first_line
-                    .trim_start_matches("=== Container: ")
-                    .trim_end_matches(" ===");
-
-                // Apply container filter
-                if let Some(ref filter) = args.container {
-                    if !container_id.contains(filter) {
-                        continue;
-                    }
+/// Stream new lines until interrupted, reconnecting when the connection drops.
+async fn follow_logs(
+    client: &ApiClient,
+    workload_id: &str,
+    args: &LogsArgs,
+    since: Option<String>,
+) -> anyhow::Result<()> {
+    require_local_cluster("Following logs")?;
+    output::info(&format!(
+        "Streaming logs for workload '{}' (Ctrl+C to stop)...",
+        args.workload
+    ));
+    let path = format!("/api/v1/workloads/{}/logs/stream", workload_id);
+    let mut follow = Follow::default();
+    let mut reconnects = 0;
+
+    loop {
+        // Resume after the container that is furthest behind; lines already
+        // shown are skipped by the cursors
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        match follow.cursors.values().map(|c| c.timestamp.as_str()).min() {
+            Some(resume) => {
+                query.append_pair("since", resume);
+            }
+            None => {
+                query.append_pair("tail", &args.tail.to_string());
+                if let Some(since) = &since {
+                    query.append_pair("since", since);
                 }
+            }
+        }
 
-                // Print header
-                println!("{}", first_line.cyan());
+        follow.received = false;
+        let reason = stream_logs(client, &path, &query.finish(), args, &mut follow).await?;
+        if follow.received {
+            reconnects = 0;
+        }
+        reconnects += 1;
+        if reconnects > MAX_RECONNECTS {
+            return Err(CliError::api_error(format!(
+                "Log stream lost ({}); gave up after {} reconnects",
+                reason, MAX_RECONNECTS
+            ))
+            .into());
+        }
 
-                // Print log lines
-                for line in section.lines().skip(1) {
-                    print_log_line(line, args.timestamps);
-                }
-                println!();
-            } else {
-                // No header, print as-is
-                for line in section.lines() {
-                    print_log_line(line, args.timestamps);
-                }
-            }
+        let backoff = Duration::from_secs(1 << (reconnects - 1).min(5)).min(MAX_BACKOFF);
+        output::warn(&format!(
+            "Log stream interrupted ({}); reconnecting in {}s",
+            reason,
+            backoff.as_secs()
+        ));
+        tokio::time::sleep(backoff).await;
+        for cursor in follow.cursors.values_mut() {
+            cursor.resume();
         }
     }
-
-    output::info(&format!("Showing {} line(s)", response.lines));
-    Ok(())
 }
 
-/// Print a single log line with optional formatting.
-fn print_log_line(line: &str, show_timestamps: bool) {
-    if line.is_empty() {
-        return;
+/// Show the lines of one connection to the log stream. Returns why the
+/// connection ended when reconnecting may help, and fails otherwise.
+async fn stream_logs(
+    client: &ApiClient,
+    path: &str,
+    query: &str,
+    args: &LogsArgs,
+    follow: &mut Follow,
+) -> Result<String> {
+    let url = format!("{}{}?{}", ws_url(client.base_url()), path, query);
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| CliError::api_error(format!("Invalid log stream URL: {}", e)))?;
+    for (name, value) in client.auth_headers("GET", path) {
+        let value = HeaderValue::from_str(&value)
+            .map_err(|e| CliError::api_error(format!("Invalid {} header: {}", name, e)))?;
+        request.headers_mut().insert(name, value);
     }
 
-    // Try to parse timestamp from line
-    let parts: Vec<&str> = line.splitn(2, ' ').collect();
+    let ws_stream = match connect_async(request).await {
+        Ok((ws_stream, _)) => ws_stream,
+        // Refused requests fail the same way again
+        Err(tungstenite::Error::Http(response)) if response.status().is_client_error() => {
+            return Err(CliError::api_error(format!(
+                "Log stream refused: {}",
+                response.status()
+            )))
+        }
+        Err(e) => return Ok(format!("failed to connect: {}", e)),
+    };
+    let (_, mut read) = ws_stream.split();
+
+    while let Some(message) = read.next().await {
+        let text = match message {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => return Ok("closed by the server".to_string()),
+            Ok(_) => continue,
+            Err(e) => return Ok(e.to_string()),
+        };
+        let Ok(message) = serde_json::from_str::<WsLogMessage>(&text) else {
+            tracing::debug!("Received: {}", text);
+            continue;
+        };
+        if let Some(error) = message.error {
+            return Err(CliError::api_error(error));
+        }
+        if let Some(info) = message.info {
+            output::info(&info);
+            continue;
+        }
+        let (Some(log), Some(instance_id), Some(container_id)) =
+            (message.log, message.instance_id, message.container_id)
+        else {
+            continue;
+        };
+        if !args.shows(&instance_id, &container_id) {
+            continue;
+        }
 
-    if parts.len() == 2 && parts[0].contains('T') && parts[0].len() > 10 {
-        // Looks like a timestamped line
-        if show_timestamps {
-            println!("{} {}", parts[0].dimmed(), parts[1]);
-        } else {
-            println!("{}", parts[1]);
+        let line = LogLine::parse(&instance_id, &container_id, &log);
+        let cursor = follow.cursors.entry(container_id).or_default();
+        if cursor.advance(&line.timestamp) {
+            follow.received = true;
+            follow.prefixes.print(&line, args.timestamps);
         }
-    } else {
-        // No timestamp or timestamps not requested
-        println!("{}", line);
     }
+    Ok("connection closed".to_string())
 }
 
-/// Stream logs via WebSocket.
-async fn stream_logs(api_url: &str, workload_id: &str, args: &LogsArgs) -> anyhow::Result<()> {
-    output::info(&format!("Streaming logs for workload '{}' (Ctrl+C to stop)...", args.workload));
-
-    // Convert HTTP URL to WebSocket URL
-    let ws_url = api_url
-        .replace("http://", "ws://")
-        .replace("https://", "wss://");
-
-    let ws_endpoint = format!("{}/api/v1/workloads/{}/logs/stream", ws_url, workload_id);
-
-    // Connect to WebSocket
-    let (ws_stream, _) = connect_async(&ws_endpoint).await.map_err(|e| {
-        CliError::api_error(format!("Failed to connect to WebSocket: {}", e))
-    })?;
-
-    output::info(&format!("Connected to {}", ws_endpoint));
-    println!();
+/// Split the logs of an instance into the lines of each container, as
+/// returned under `=== Container: ID ===` headers.
+fn sections(logs: &str) -> Vec<(&str, Vec<&str>)> {
+    let mut sections: Vec<(&str, Vec<&str>)> = Vec::new();
+    for line in logs.lines() {
+        if let Some(container_id) = line
+            .strip_prefix("=== Container: ")
+            .and_then(|rest| rest.strip_suffix(" ==="))
+        {
+            sections.push((container_id, Vec::new()));
+        } else if let Some((_, lines)) = sections.last_mut() {
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+    }
+    sections
+}
 
-    let (_, mut read) = ws_stream.split();
+/// Resolve `--since`, an RFC3339 time or a duration back from `now`, into the
+/// RFC3339 time the API compares log timestamps with.
+fn resolve_since(since: &str, now: DateTime<Utc>) -> Result<String> {
+    let time = match DateTime::parse_from_rfc3339(since) {
+        Ok(time) => time.with_timezone(&Utc),
+        Err(_) => {
+            let duration = deploy::parse_duration(since).map_err(|_| {
+                CliError::invalid_argument(format!(
+                    "Invalid --since '{}', expected an RFC3339 time or a duration such as 10m",
+                    since
+                ))
+            })?;
+            now - chrono::Duration::from_std(duration)
+                .map_err(|_| CliError::invalid_argument("--since is too far back"))?
+        }
+    };
+    // The runtime writes timestamps like this, so they compare as strings
+    Ok(time.to_rfc3339_opts(SecondsFormat::AutoSi, false))
+}
 
-    // Read messages
-    while let Some(msg) = read.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                match serde_json::from_str::<WsLogMessage>(&text) {
-                    Ok(log_msg) => {
-                        // Handle different message types
-                        if let Some(error) = log_msg.error {
-                            output::error(&error);
-                            break;
-                        }
-
-                        if let Some(status) = log_msg.status {
-                            output::info(&format!("Status: {}", status));
-                            continue;
-                        }
-
-                        if let Some(info) = log_msg.info {
-                            output::info(&info);
-                            continue;
-                        }
-
-                        // Log message
-                        if let Some(log) = log_msg.log {
-                            // Apply container filter
-                            if let Some(ref filter) = args.container {
-                                if let Some(ref cid) = log_msg.container_id {
-                                    if !cid.contains(filter) {
-                                        continue;
-                                    }
-                                }
-                            }
-
-                            // Apply instance filter
-                            if let Some(ref filter) = args.instance {
-                                if let Some(ref iid) = log_msg.instance_id {
-                                    if !iid.starts_with(filter) {
-                                        continue;
-                                    }
-                                }
-                            }
-
-                            // Format output
-                            let container_prefix = log_msg
-                                .container_id
-                                .as_ref()
-                                .map(|c| format!("[{}]", &c[..12.min(c.len())]).cyan().to_string())
-                                .unwrap_or_default();
-
-                            print_log_line(&format!("{} {}", container_prefix, log), args.timestamps);
-                        }
-                    }
-                    Err(_) => {
-                        // Try raw JSON display for debugging
-                        tracing::debug!("Received: {}", text);
-                    }
-                }
-            }
-            Ok(Message::Close(_)) => {
-                output::info("Connection closed by server");
-                break;
-            }
-            Ok(Message::Ping(_)) => {
-                // Ping handled automatically by tungstenite
-            }
-            Err(e) => {
-                output::error(&format!("WebSocket error: {}", e));
-                break;
-            }
-            _ => {}
+/// Name of the container a runtime container ID (`NAME-UUID`) belongs to.
+fn container_name(container_id: &str) -> &str {
+    let Some(at) = container_id.len().checked_sub(37) else {
+        return container_id;
+    };
+    match (container_id.get(..at), container_id.get(at..)) {
+        (Some(name), Some(suffix))
+            if !name.is_empty()
+                && suffix
+                    .strip_prefix('-')
+                    .is_some_and(|uuid| uuid::Uuid::parse_str(uuid).is_ok()) =>
+        {
+            name
         }
+        _ => container_id,
     }
+}
 
-    Ok(())
+fn short(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+fn ws_url(api_url: &str) -> String {
+    api_url
+        .replace("http://", "ws://")
+        .replace("https://", "wss://")
 }
 
 /// Find workload ID by name or ID.
-async fn find_workload_id(client: &ApiClient, name_or_id: &str) -> Result<String> {
+async fn find_workload_id(
+    client: &ApiClient,
+    name_or_id: &str,
+    namespace: Option<&str>,
+) -> Result<String> {
     // First try as UUID directly
     if uuid::Uuid::parse_str(name_or_id).is_ok() {
         return Ok(name_or_id.to_string());
     }
 
     // Otherwise search by name
-    let response: ListWorkloadsResponse = client.get("/api/v1/workloads").await?;
+    let path = match namespace {
+        Some(namespace) => format!("/api/v1/workloads?namespace={}", namespace),
+        None => "/api/v1/workloads".to_string(),
+    };
+    let response: ListResponse<WorkloadResponse> = client.get(&path).await?;
 
-    let matching: Vec<_> = response.items
+    let matching: Vec<_> = response
+        .items
         .iter()
         .filter(|w| w.name == name_or_id || w.id.starts_with(name_or_id))
         .collect();
@@ -349,3 +548,74 @@ async fn find_workload_id(client: &ApiClient, name_or_id: &str) -> Result<String
         ))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTAINER: &str = "web-0b8f5f1e-6f4d-4a57-9c3e-2d1f0e9a8b7c";
+
+    #[test]
+    fn test_parse_sections_and_lines() {
+        let logs = format!(
+            "=== Container: {} ===\n\
+             2024-01-15T10:30:00+00:00 stdout hello world\n\
+             2024-01-15T10:30:01+00:00 stderr oops\n\
+             \n\
+             === Container: sidecar ===\n\
+             plain line",
+            CONTAINER
+        );
+        let sections = sections(&logs);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].0, CONTAINER);
+        assert_eq!(sections[0].1.len(), 2);
+        assert_eq!(sections[1].1, vec!["plain line"]);
+
+        let line = LogLine::parse("instance", CONTAINER, sections[0].1[0]);
+        assert_eq!(line.timestamp, "2024-01-15T10:30:00+00:00");
+        assert_eq!(line.message, "hello world");
+        let line = LogLine::parse("instance", "sidecar", "plain line");
+        assert_eq!(line.timestamp, "");
+        assert_eq!(line.message, "plain line");
+    }
+
+    #[test]
+    fn test_container_name() {
+        assert_eq!(container_name(CONTAINER), "web");
+        assert_eq!(container_name("my-api-server"), "my-api-server");
+        assert_eq!(container_name("sidecar"), "sidecar");
+    }
+
+    #[test]
+    fn test_resolve_since() {
+        let now = DateTime::parse_from_rfc3339("2024-01-15T10:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            resolve_since("10m", now).unwrap(),
+            "2024-01-15T10:20:00+00:00"
+        );
+        assert_eq!(
+            resolve_since("2024-01-15T12:00:00+02:00", now).unwrap(),
+            "2024-01-15T10:00:00+00:00"
+        );
+        assert!(resolve_since("yesterday", now).is_err());
+    }
+
+    #[test]
+    fn test_cursor_skips_lines_shown_before_reconnect() {
+        let mut cursor = Cursor::default();
+        assert!(cursor.advance("2024-01-15T10:30:00+00:00"));
+        assert!(cursor.advance("2024-01-15T10:30:01+00:00"));
+        assert!(cursor.advance("2024-01-15T10:30:01+00:00"));
+
+        // The resumed stream sends everything since 10:30:01 again
+        cursor.resume();
+        assert!(!cursor.advance("2024-01-15T10:30:01+00:00"));
+        assert!(!cursor.advance("2024-01-15T10:30:01+00:00"));
+        assert!(cursor.advance("2024-01-15T10:30:01+00:00"));
+        assert!(cursor.advance("2024-01-15T10:30:02+00:00"));
+        assert!(!cursor.advance("2024-01-15T10:30:00+00:00"));
+    }
+}
@@ -1,7 +1,34 @@
 //! CLI command implementations.
 
+pub mod admin;
+pub mod apply;
+pub mod cluster;
+pub mod completion;
+pub mod compose;
+pub mod config;
+pub mod convert;
+pub mod cp;
+pub mod cronjob;
+pub mod debug;
 pub mod deploy;
+pub mod describe;
+pub mod docs;
+pub mod doctor;
+pub mod exec;
+pub mod expose;
+pub mod federation;
+pub mod image;
 pub mod init;
+pub mod instance;
+pub mod login;
 pub mod logs;
+pub mod namespace;
+pub mod node;
+pub mod render;
+pub mod report;
+pub mod rollout;
 pub mod scale;
+pub mod service;
+pub mod statefulset;
 pub mod status;
+pub mod top;
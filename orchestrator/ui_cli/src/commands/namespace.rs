@@ -0,0 +1,111 @@
+//! Namespace command - create, list and delete namespaces.
+
+use std::collections::HashMap;
+
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::error::CliError;
+use crate::output::{self, print_data};
+use crate::OutputFormat;
+
+/// Arguments for the namespace command.
+#[derive(Args)]
+pub struct NamespaceArgs {
+    #[command(subcommand)]
+    command: NamespaceCommand,
+}
+
+#[derive(Subcommand)]
+enum NamespaceCommand {
+    /// Create a namespace
+    Create {
+        /// Namespace name: lowercase letters, digits and hyphens
+        name: String,
+
+        /// Labels (key=value format, can be specified multiple times)
+        #[arg(short, long, value_parser = parse_label)]
+        label: Vec<(String, String)>,
+    },
+    /// List namespaces
+    List,
+    /// Delete a namespace with all its workloads and services
+    Delete {
+        /// Namespace name
+        name: String,
+    },
+}
+
+/// Namespace response from API.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct NamespaceResponse {
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Labels", display_with = "display_labels")]
+    #[serde(default)]
+    labels: HashMap<String, String>,
+}
+
+/// List response wrapper.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+fn display_labels(labels: &HashMap<String, String>) -> String {
+    if labels.is_empty() {
+        return "-".to_string();
+    }
+    let mut pairs: Vec<String> = labels.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    pairs.sort();
+    pairs.join(",")
+}
+
+/// Parse a key=value label.
+fn parse_label(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid label format '{}'. Expected key=value", s))?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Execute the namespace command.
+pub async fn execute(
+    args: NamespaceArgs,
+    api_url: &str,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for namespace operations. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        NamespaceCommand::Create { name, label } => {
+            let request = serde_json::json!({
+                "name": name,
+                "labels": label.into_iter().collect::<HashMap<_, _>>(),
+            });
+            let created: NamespaceResponse = client.post("/api/v1/namespaces", &request).await?;
+            output::success(&format!("Namespace {} created", created.name));
+            Ok(())
+        }
+        NamespaceCommand::List => {
+            let response: ListResponse<NamespaceResponse> =
+                client.get("/api/v1/namespaces").await?;
+            print_data(&response.items, format)?;
+            Ok(())
+        }
+        NamespaceCommand::Delete { name } => {
+            client
+                .delete(&format!("/api/v1/namespaces/{}", name))
+                .await?;
+            output::success(&format!("Namespace {} and its workloads deleted", name));
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Node command - list, cordon, uncordon and drain nodes.
+
+use std::time::{Duration, Instant};
+
+use clap::{Args, Subcommand};
+use clap_complete::engine::ArgValueCandidates;
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::commands::completion;
+use crate::error::{CliError, Result};
+use crate::output::{self, print_data, print_item};
+use crate::watch::{self, Watched};
+use crate::OutputFormat;
+
+/// Arguments for the node command.
+#[derive(Args)]
+pub struct NodeArgs {
+    #[command(subcommand)]
+    command: NodeCommand,
+}
+
+#[derive(Subcommand)]
+enum NodeCommand {
+    /// List nodes
+    List {
+        /// Keep the table up to date as nodes change
+        #[arg(short, long)]
+        watch: bool,
+    },
+
+    /// Stop scheduling new instances onto a node
+    Cordon {
+        /// Node ID or unique ID prefix
+        #[arg(add = ArgValueCandidates::new(completion::node_ids))]
+        node: String,
+    },
+
+    /// Allow scheduling onto a cordoned node again
+    Uncordon {
+        /// Node ID or unique ID prefix
+        #[arg(add = ArgValueCandidates::new(completion::node_ids))]
+        node: String,
+    },
+
+    /// Cordon a node and evict its instances so they are rescheduled elsewhere
+    Drain {
+        /// Node ID or unique ID prefix
+        #[arg(add = ArgValueCandidates::new(completion::node_ids))]
+        node: String,
+
+        /// Give up after this many seconds if disruption budgets keep instances on the node
+        #[arg(long, default_value = "300")]
+        timeout: u64,
+
+        /// Seconds between eviction attempts while instances are blocked
+        #[arg(long, default_value = "5")]
+        interval: u64,
+    },
+}
+
+/// Generic list response wrapper from API.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+/// Node response from API.
+#[derive(Debug, Clone, Serialize, Deserialize, Tabled)]
+struct NodeResponse {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Address")]
+    address: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Unschedulable")]
+    #[serde(default)]
+    unschedulable: bool,
+    #[tabled(skip)]
+    #[serde(default)]
+    labels: std::collections::HashMap<String, String>,
+}
+
+impl Watched for NodeResponse {
+    type Row = NodeResponse;
+    const KIND: &'static str = "node";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn state(&self) -> String {
+        self.status.clone()
+    }
+
+    fn row(&self) -> NodeResponse {
+        self.clone()
+    }
+}
+
+/// Drain response from API.
+#[derive(Debug, Deserialize)]
+struct DrainNodeResponse {
+    evicted: Vec<String>,
+    blocked: Vec<String>,
+    complete: bool,
+}
+
+/// Execute the node command.
+pub async fn execute(args: NodeArgs, api_url: &str, format: OutputFormat) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for node operations. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        NodeCommand::List { watch: true } => {
+            let stream = client.watch("/api/v1/nodes").await?;
+            watch::watch_table::<NodeResponse>(stream, "Nodes", format).await?;
+        }
+        NodeCommand::List { watch: false } => {
+            let nodes: ListResponse<NodeResponse> = client.get("/api/v1/nodes").await?;
+            print_data(&nodes.items, format)?;
+        }
+        NodeCommand::Cordon { node } => {
+            let node_id = find_node_id(&client, &node).await?;
+            let response: NodeResponse = client
+                .post(&format!("/api/v1/nodes/{}/cordon", node_id), &())
+                .await?;
+            output::success(&format!("Node {} cordoned", node_id));
+            print_item(&response, format)?;
+        }
+        NodeCommand::Uncordon { node } => {
+            let node_id = find_node_id(&client, &node).await?;
+            let response: NodeResponse = client
+                .post(&format!("/api/v1/nodes/{}/uncordon", node_id), &())
+                .await?;
+            output::success(&format!("Node {} uncordoned", node_id));
+            print_item(&response, format)?;
+        }
+        NodeCommand::Drain {
+            node,
+            timeout,
+            interval,
+        } => {
+            let node_id = find_node_id(&client, &node).await?;
+            drain(
+                &client,
+                &node_id,
+                Duration::from_secs(timeout),
+                Duration::from_secs(interval),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Drain until no instance is left on the node, reporting progress after each pass.
+async fn drain(
+    client: &ApiClient,
+    node_id: &str,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<()> {
+    output::info(&format!("Cordoning and draining node {}...", node_id));
+
+    let path = format!("/api/v1/nodes/{}/drain", node_id);
+    let started = Instant::now();
+    let mut evicted = 0;
+    loop {
+        let report: DrainNodeResponse = client.post(&path, &()).await?;
+        evicted += report.evicted.len();
+
+        if report.complete {
+            output::success(&format!(
+                "Node {} drained: {} instance(s) evicted and rescheduled elsewhere",
+                node_id, evicted
+            ));
+            return Ok(());
+        }
+
+        output::warn(&format!(
+            "{} instance(s) evicted so far, {} held on the node by disruption budgets",
+            evicted,
+            report.blocked.len()
+        ));
+
+        if started.elapsed() + interval > timeout {
+            return Err(CliError::Other(format!(
+                "Timed out draining node {}: {} instance(s) still blocked. \
+                 The node stays cordoned; run the drain again once budgets allow.",
+                node_id,
+                report.blocked.len()
+            )));
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Find node ID by full ID or unique prefix.
+pub(crate) async fn find_node_id(client: &ApiClient, id_or_prefix: &str) -> Result<String> {
+    let nodes: ListResponse<NodeResponse> = client.get("/api/v1/nodes").await?;
+
+    let matching: Vec<_> = nodes
+        .items
+        .iter()
+        .filter(|n| n.id.starts_with(id_or_prefix))
+        .collect();
+
+    match matching.len() {
+        0 => Err(CliError::NodeNotFound(id_or_prefix.to_string())),
+        1 => Ok(matching[0].id.clone()),
+        _ => Err(CliError::invalid_argument(format!(
+            "Ambiguous node reference '{}', matches {} nodes. Use full ID.",
+            id_or_prefix,
+            matching.len()
+        ))),
+    }
+}
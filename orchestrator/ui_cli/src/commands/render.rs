@@ -0,0 +1,340 @@
+//! Render command - render parameterized workload manifests.
+//!
+//! A template is a manifest file for `orch apply` with Handlebars
+//! expressions, or a directory laid out like a chart:
+//!
+//! ```text
+//! chart/
+//!   values.yaml        default values
+//!   templates/
+//!     _labels.yaml     partial, included with {{> _labels}}
+//!     web.yaml         rendered, in name order
+//! ```
+//!
+//! Templates see the values as their root, e.g. `image: {{image.repo}}:{{image.tag}}`.
+//! The chart's defaults are overridden by `--values` files, in order, and those
+//! by `--set key.path=value`. Referencing a value that is not set is an error,
+//! and nothing is HTML-escaped; `{{json labels}}` writes a value as inline
+//! JSON, which YAML reads as is.
+
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use handlebars::{handlebars_helper, Handlebars};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::error::{CliError, Result};
+
+/// File of a chart directory holding its default values.
+const VALUES_FILE: &str = "values.yaml";
+
+/// Directory of a chart holding its templates.
+const TEMPLATES_DIR: &str = "templates";
+
+/// Arguments for the render command.
+#[derive(Args)]
+pub struct RenderArgs {
+    /// Template file, or chart directory with values.yaml and templates/
+    template: PathBuf,
+
+    #[command(flatten)]
+    values: ValuesArgs,
+}
+
+/// Values given to a template on the command line.
+#[derive(Args, Debug, Default)]
+pub struct ValuesArgs {
+    /// Values file overriding the chart's values.yaml (can be specified
+    /// multiple times; later files win)
+    #[arg(long = "values", value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+
+    /// Value to set, overriding the values files (KEY.PATH=VALUE format, can be
+    /// repeated). Example: --set image.tag=v2 --set replicas=3
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_set)]
+    pub set: Vec<(String, Value)>,
+}
+
+/// Execute the render command.
+pub async fn execute(args: RenderArgs) -> anyhow::Result<()> {
+    print!("{}", render(&args.template, &args.values)?);
+    Ok(())
+}
+
+/// Render `template` with `values` into the YAML documents of a manifest.
+pub fn render(template: &Path, values: &ValuesArgs) -> Result<String> {
+    let (defaults, files) = if template.is_dir() {
+        let defaults = template.join(VALUES_FILE);
+        let defaults = if defaults.exists() {
+            read_values(&defaults)?
+        } else {
+            Value::Object(Map::new())
+        };
+        (defaults, template_files(&template.join(TEMPLATES_DIR))?)
+    } else {
+        (Value::Object(Map::new()), vec![template.to_path_buf()])
+    };
+
+    let mut context = defaults;
+    for file in &values.files {
+        merge(&mut context, read_values(file)?);
+    }
+    for (key, value) in &values.set {
+        set(&mut context, key, value.clone());
+    }
+
+    let mut registry = Handlebars::new();
+    registry.set_strict_mode(true);
+    registry.register_escape_fn(handlebars::no_escape);
+    registry.register_helper("json", Box::new(json));
+
+    let mut rendered = Vec::new();
+    for file in &files {
+        let text = std::fs::read_to_string(file).map_err(|e| {
+            CliError::invalid_argument(format!("Cannot read {}: {}", file.display(), e))
+        })?;
+        let name = file.display().to_string();
+        let template_error =
+            |e: handlebars::TemplateError| CliError::invalid_argument(format!("{}: {}", name, e));
+        match partial_name(file) {
+            Some(partial) => {
+                // A partial on a line of its own replaces the whole line,
+                // newline included, so it has to end with one
+                let mut text = text;
+                if !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                registry
+                    .register_partial(&partial, text)
+                    .map_err(template_error)?;
+            }
+            None => {
+                registry
+                    .register_template_string(&name, text)
+                    .map_err(template_error)?;
+                rendered.push(name);
+            }
+        }
+    }
+    if rendered.is_empty() {
+        return Err(CliError::invalid_argument(format!(
+            "{} holds no templates",
+            template.display()
+        )));
+    }
+
+    let mut documents = Vec::new();
+    for name in rendered {
+        let mut text = registry
+            .render(&name, &context)
+            .map_err(|e| CliError::invalid_argument(format!("{}: {}", name, e)))?;
+        // Catch broken YAML here rather than where the output is applied
+        for (index, document) in serde_yaml_ng::Deserializer::from_str(&text).enumerate() {
+            serde_yaml_ng::Value::deserialize(document).map_err(|e| {
+                CliError::invalid_argument(format!(
+                    "{} renders invalid YAML (document {}): {}",
+                    name,
+                    index + 1,
+                    e
+                ))
+            })?;
+        }
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        documents.push(text);
+    }
+    Ok(documents.join("---\n"))
+}
+
+handlebars_helper!(json: |value: Json| value.to_string());
+
+/// The .yaml and .yml files of a chart's templates directory, in name order.
+fn template_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| CliError::invalid_argument(format!("Cannot read {}: {}", dir.display(), e)))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| matches!(ext, "yaml" | "yml"))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Name a template file is included by when it is a partial, i.e. its name
+/// starts with `_`.
+fn partial_name(file: &Path) -> Option<String> {
+    file.file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| stem.starts_with('_'))
+        .map(str::to_string)
+}
+
+/// Values of a YAML file; an empty file sets none.
+fn read_values(path: &Path) -> Result<Value> {
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        CliError::invalid_argument(format!("Cannot read {}: {}", path.display(), e))
+    })?;
+    match serde_yaml_ng::from_str(&text) {
+        Ok(Value::Null) => Ok(Value::Object(Map::new())),
+        Ok(values @ Value::Object(_)) => Ok(values),
+        Ok(_) => Err(CliError::invalid_argument(format!(
+            "{}: expected a mapping of values",
+            path.display()
+        ))),
+        Err(e) => Err(CliError::invalid_argument(format!(
+            "{}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+/// Merge `overrides` into `values`: mappings key by key, anything else by
+/// replacing it.
+fn merge(values: &mut Value, overrides: Value) {
+    match (values, overrides) {
+        (Value::Object(values), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match values.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        values.insert(key, value);
+                    }
+                }
+            }
+        }
+        (values, overrides) => *values = overrides,
+    }
+}
+
+/// Set the value at the dotted `path`, creating mappings on the way.
+fn set(values: &mut Value, path: &str, value: Value) {
+    let mut current = values;
+    for key in path.split('.') {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        current = current
+            .as_object_mut()
+            .expect("replaced by a mapping above")
+            .entry(key)
+            .or_insert(Value::Null);
+    }
+    *current = value;
+}
+
+/// Parse `KEY.PATH=VALUE`. Booleans, null and integers keep their type, as
+/// in YAML; anything else, such as `1.10`, stays a string.
+fn parse_set(s: &str) -> std::result::Result<(String, Value), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid format '{}', expected KEY=VALUE", s))?;
+    if key.is_empty() || key.split('.').any(str::is_empty) {
+        return Err(format!("Invalid key '{}'", key));
+    }
+    let value = match value {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        "null" => Value::Null,
+        _ => match value.parse::<i64>() {
+            // Leading zeros would be lost, e.g. in "007"
+            Ok(number) if number.to_string() == value => Value::from(number),
+            _ => Value::String(value.to_string()),
+        },
+    };
+    Ok((key.to_string(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chart(dir: &Path) {
+        std::fs::create_dir(dir.join(TEMPLATES_DIR)).unwrap();
+        std::fs::write(
+            dir.join(VALUES_FILE),
+            "name: web\nreplicas: 1\nlabels: {}\nimage:\n  repo: nginx\n  tag: \"1.27\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join(TEMPLATES_DIR).join("_labels.yaml"),
+            "labels: {{json labels}}",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join(TEMPLATES_DIR).join("web.yaml"),
+            "kind: Workload\nname: {{name}}\nreplicas: {{replicas}}\n{{> _labels}}\n\
+             containers:\n  - name: {{name}}\n    image: {{image.repo}}:{{image.tag}}\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_render_chart_with_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        chart(dir.path());
+        let prod = dir.path().join("prod.yaml");
+        std::fs::write(&prod, "replicas: 3\nlabels:\n  tier: web\n").unwrap();
+
+        let values = ValuesArgs {
+            files: vec![prod],
+            set: vec![parse_set("image.tag=v2").unwrap()],
+        };
+        let text = render(dir.path(), &values).unwrap();
+        let manifest: Value = serde_yaml_ng::from_str(&text).unwrap();
+        assert_eq!(manifest["replicas"], 3);
+        assert_eq!(manifest["labels"]["tier"], "web");
+        assert_eq!(manifest["containers"][0]["image"], "nginx:v2");
+    }
+
+    #[test]
+    fn test_render_rejects_missing_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = dir.path().join("web.yaml");
+        std::fs::write(&template, "kind: Workload\nname: {{name}}\n").unwrap();
+        let err = render(&template, &ValuesArgs::default()).unwrap_err();
+        assert!(err.to_string().contains("web.yaml"));
+
+        let values = ValuesArgs {
+            set: vec![parse_set("name=web").unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(
+            render(&template, &values).unwrap(),
+            "kind: Workload\nname: web\n"
+        );
+    }
+
+    #[test]
+    fn test_values_merge_and_set() {
+        let mut values = json!({"image": {"repo": "nginx", "tag": "1.27"}, "ports": [80]});
+        merge(
+            &mut values,
+            json!({"image": {"tag": "1.28"}, "ports": [443]}),
+        );
+        assert_eq!(
+            values,
+            json!({"image": {"repo": "nginx", "tag": "1.28"}, "ports": [443]})
+        );
+
+        set(&mut values, "image.repo.name", json!("web"));
+        assert_eq!(values["image"]["repo"], json!({"name": "web"}));
+
+        assert_eq!(parse_set("replicas=3").unwrap().1, json!(3));
+        assert_eq!(parse_set("debug=true").unwrap().1, json!(true));
+        assert_eq!(parse_set("tag=1.10").unwrap().1, json!("1.10"));
+        assert_eq!(parse_set("id=007").unwrap().1, json!("007"));
+        assert_eq!(parse_set("empty=").unwrap().1, json!(""));
+        assert!(parse_set("replicas").is_err());
+        assert!(parse_set("image..tag=v2").is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Report command - resource usage reports for chargeback.
+
+use chrono::{DateTime, Utc};
+use clap::{Args, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::commands::deploy;
+use crate::error::{CliError, Result};
+use crate::output::{self, print_data};
+use crate::OutputFormat;
+
+/// Arguments for the report command.
+#[derive(Args)]
+pub struct ReportArgs {
+    #[command(subcommand)]
+    command: ReportCommand,
+}
+
+#[derive(Subcommand)]
+enum ReportCommand {
+    /// CPU core-hours, GB-hours and GPU-hours held by workloads
+    Usage(UsageArgs),
+}
+
+#[derive(Args)]
+struct UsageArgs {
+    /// Start of the report, an RFC3339 time or a duration back (e.g. 720h);
+    /// its whole hour is included (default: 30 days ago)
+    #[arg(long)]
+    from: Option<String>,
+
+    /// End of the report, an RFC3339 time or a duration back (default: now)
+    #[arg(long)]
+    to: Option<String>,
+
+    /// Only report workloads in this namespace
+    #[arg(short, long)]
+    namespace: Option<String>,
+
+    /// Rows of the report
+    #[arg(long, value_enum, default_value_t = GroupBy::Workload)]
+    by: GroupBy,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum GroupBy {
+    Namespace,
+    Workload,
+}
+
+/// Usage report from API.
+#[derive(Debug, Deserialize)]
+struct UsageReport {
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    total: ResourceHours,
+    namespaces: Vec<NamespaceUsage>,
+    workloads: Vec<WorkloadUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct ResourceHours {
+    #[tabled(rename = "CPU Core-Hours", display_with = "display_hours")]
+    cpu_core_hours: f64,
+    #[tabled(rename = "Memory GB-Hours", display_with = "display_hours")]
+    memory_gb_hours: f64,
+    #[tabled(rename = "GPU-Hours", display_with = "display_hours")]
+    gpu_hours: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct NamespaceUsage {
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[serde(flatten)]
+    #[tabled(inline)]
+    usage: ResourceHours,
+}
+
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct WorkloadUsage {
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Workload")]
+    workload_name: String,
+    #[tabled(skip)]
+    workload_id: String,
+    #[serde(flatten)]
+    #[tabled(inline)]
+    usage: ResourceHours,
+}
+
+fn display_hours(hours: &f64) -> String {
+    format!("{:.2}", hours)
+}
+
+/// Resolve an RFC3339 time or a duration back from `now`.
+fn resolve_time(flag: &str, value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let duration = deploy::parse_duration(value).map_err(|_| {
+        CliError::invalid_argument(format!(
+            "Invalid --{} '{}', expected an RFC3339 time or a duration such as 24h",
+            flag, value
+        ))
+    })?;
+    chrono::Duration::from_std(duration)
+        .ok()
+        .and_then(|duration| now.checked_sub_signed(duration))
+        .ok_or_else(|| CliError::invalid_argument(format!("--{} is too far back", flag)))
+}
+
+/// Execute the report command.
+pub async fn execute(args: ReportArgs, api_url: &str, format: OutputFormat) -> anyhow::Result<()> {
+    let client = match ApiClient::authenticated(api_url).await {
+        Ok(c) => c,
+        Err(e) => {
+            output::warn(&format!(
+                "Could not load identity for authentication: {}",
+                e
+            ));
+            output::info("Using unauthenticated client (some endpoints may fail)");
+            ApiClient::new(api_url)
+        }
+    };
+
+    match args.command {
+        ReportCommand::Usage(args) => usage(&client, args, format).await,
+    }
+}
+
+async fn usage(client: &ApiClient, args: UsageArgs, format: OutputFormat) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    if let Some(from) = &args.from {
+        query.append_pair("from", &resolve_time("from", from, now)?.to_rfc3339());
+    }
+    if let Some(to) = &args.to {
+        query.append_pair("to", &resolve_time("to", to, now)?.to_rfc3339());
+    }
+    if let Some(namespace) = &args.namespace {
+        query.append_pair("namespace", namespace);
+    }
+    let report: UsageReport = client
+        .get(&format!("/api/v1/reports/usage?{}", query.finish()))
+        .await?;
+
+    match args.by {
+        GroupBy::Namespace => print_data(&report.namespaces, format)?,
+        GroupBy::Workload => print_data(&report.workloads, format)?,
+    }
+    if matches!(format, OutputFormat::Table | OutputFormat::Wide) {
+        output::info(&format!(
+            "{} to {}: {:.2} CPU core-hours, {:.2} memory GB-hours, {:.2} GPU-hours",
+            report.from.format("%Y-%m-%d %H:%M"),
+            report.to.format("%Y-%m-%d %H:%M UTC"),
+            report.total.cpu_core_hours,
+            report.total.memory_gb_hours,
+            report.total.gpu_hours
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_time() {
+        let now = DateTime::parse_from_rfc3339("2026-03-31T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            resolve_time("from", "2026-03-01T00:00:00+02:00", now).unwrap(),
+            DateTime::parse_from_rfc3339("2026-02-28T22:00:00Z").unwrap()
+        );
+        assert_eq!(
+            resolve_time("from", "720h", now).unwrap(),
+            DateTime::parse_from_rfc3339("2026-03-01T12:00:00Z").unwrap()
+        );
+        assert!(resolve_time("from", "last month", now).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! Rollout command - show a workload's revisions and roll it back.
+
+use clap::{Args, Subcommand};
+use clap_complete::engine::ArgValueCandidates;
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::commands::completion;
+use crate::commands::scale::find_workload_id;
+use crate::error::CliError;
+use crate::output::{self, print_data, print_item};
+use crate::OutputFormat;
+
+/// Arguments for the rollout command.
+#[derive(Args)]
+pub struct RolloutArgs {
+    #[command(subcommand)]
+    command: RolloutCommand,
+}
+
+#[derive(Subcommand)]
+enum RolloutCommand {
+    /// List a workload's revisions
+    History(WorkloadRef),
+    /// Show a workload's current revision and replica progress
+    Status(WorkloadRef),
+    /// Roll a workload back to the previous revision, or to --to
+    Undo {
+        #[command(flatten)]
+        workload: WorkloadRef,
+
+        /// Revision to roll back to
+        #[arg(long)]
+        to: Option<u64>,
+    },
+}
+
+#[derive(Args)]
+struct WorkloadRef {
+    /// Workload ID or name
+    #[arg(add = ArgValueCandidates::new(completion::workload_names))]
+    workload: String,
+
+    /// Namespace of the workload (default: look in every namespace)
+    #[arg(long)]
+    namespace: Option<String>,
+}
+
+/// Revision from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct RevisionResponse {
+    revision: u64,
+    created_at: chrono::DateTime<chrono::Utc>,
+    current: bool,
+    workload: WorkloadResponse,
+}
+
+/// Workload response from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct WorkloadResponse {
+    name: String,
+    containers: Vec<ContainerResponse>,
+}
+
+/// Container of a workload from API.
+#[derive(Debug, Serialize, Deserialize)]
+struct ContainerResponse {
+    name: String,
+    image: String,
+}
+
+/// Row of the history table.
+#[derive(Debug, Serialize, Tabled)]
+struct RevisionRow {
+    #[tabled(rename = "Revision")]
+    revision: String,
+    #[tabled(rename = "Created")]
+    created_at: String,
+    #[tabled(rename = "Images")]
+    images: String,
+}
+
+impl From<&RevisionResponse> for RevisionRow {
+    fn from(r: &RevisionResponse) -> Self {
+        Self {
+            revision: if r.current {
+                format!("{} (current)", r.revision)
+            } else {
+                r.revision.to_string()
+            },
+            created_at: r.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            images: images(&r.workload),
+        }
+    }
+}
+
+/// Rollout status from API.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct RolloutStatus {
+    #[tabled(rename = "Revision", display_with = "display_revision")]
+    revision: Option<u64>,
+    #[tabled(rename = "Desired")]
+    replicas: u32,
+    #[tabled(rename = "Current")]
+    current_replicas: u32,
+    #[tabled(rename = "Running")]
+    running_replicas: u32,
+    #[tabled(rename = "Complete")]
+    complete: bool,
+}
+
+/// List response wrapper.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+fn display_revision(revision: &Option<u64>) -> String {
+    revision.map_or("-".to_string(), |r| r.to_string())
+}
+
+fn images(workload: &WorkloadResponse) -> String {
+    workload
+        .containers
+        .iter()
+        .map(|c| format!("{}={}", c.name, c.image))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Execute the rollout command.
+pub async fn execute(args: RolloutArgs, api_url: &str, format: OutputFormat) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for rollout. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        RolloutCommand::History(workload) => {
+            let id = find_workload_id(&client, &workload.workload, workload.namespace.as_deref())
+                .await?;
+            let response: ListResponse<RevisionResponse> = client
+                .get(&format!("/api/v1/workloads/{}/revisions", id))
+                .await?;
+            match format {
+                OutputFormat::Table | OutputFormat::Wide => {
+                    let rows: Vec<RevisionRow> = response.items.iter().map(Into::into).collect();
+                    print_data(&rows, format)?;
+                }
+                // Other formats keep the API's shape
+                _ => output::print_value(&response.items, format)?,
+            }
+            Ok(())
+        }
+        RolloutCommand::Status(workload) => {
+            let id = find_workload_id(&client, &workload.workload, workload.namespace.as_deref())
+                .await?;
+            let status: RolloutStatus = client
+                .get(&format!("/api/v1/workloads/{}/rollout/status", id))
+                .await?;
+            print_item(&status, format)?;
+            Ok(())
+        }
+        RolloutCommand::Undo { workload, to } => {
+            let id = find_workload_id(&client, &workload.workload, workload.namespace.as_deref())
+                .await?;
+            let path = match to {
+                Some(to) => format!("/api/v1/workloads/{}/rollout/undo?to={}", id, to),
+                None => format!("/api/v1/workloads/{}/rollout/undo", id),
+            };
+            let rolled_back: WorkloadResponse = client.post(&path, &serde_json::json!({})).await?;
+            output::success(&format!(
+                "Workload {} rolled back to {}",
+                rolled_back.name,
+                images(&rolled_back)
+            ));
+            output::info("Use 'orch rollout status' to follow the rollout");
+            Ok(())
+        }
+    }
+}
@@ -1,10 +1,12 @@
 //! Scale command - scale a workload.
 
 use clap::Args;
+use clap_complete::engine::ArgValueCandidates;
 use serde::{Deserialize, Serialize};
 use tabled::Tabled;
 
 use crate::client::ApiClient;
+use crate::commands::completion;
 use crate::error::{CliError, Result};
 use crate::output::{self, print_item};
 use crate::OutputFormat;
@@ -13,28 +15,48 @@ use crate::OutputFormat;
 #[derive(Args)]
 pub struct ScaleArgs {
     /// Workload ID or name
+    #[arg(add = ArgValueCandidates::new(completion::workload_names))]
     workload: String,
 
     /// Target number of replicas
     #[arg(short, long)]
     replicas: u32,
+
+    /// Namespace of the workload (default: look in every namespace)
+    #[arg(long)]
+    namespace: Option<String>,
 }
 
-/// Update workload request (partial update).
+/// Scale request.
 #[derive(Debug, Serialize)]
-struct UpdateWorkloadRequest {
+struct ScaleRequest {
     replicas: u32,
 }
 
-/// Workload response from API.
+/// Scale status from API.
 #[derive(Debug, Serialize, Deserialize, Tabled)]
-struct WorkloadResponse {
+struct ScaleResponse {
     #[tabled(rename = "ID")]
+    workload_id: String,
+    #[tabled(rename = "Desired")]
+    replicas: u32,
+    #[tabled(rename = "Current")]
+    current_replicas: u32,
+    #[tabled(rename = "Running")]
+    running_replicas: u32,
+}
+
+/// Generic list response wrapper from API.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+/// Workload response from API.
+#[derive(Debug, Deserialize)]
+struct WorkloadResponse {
     id: String,
-    #[tabled(rename = "Name")]
     name: String,
-    #[tabled(rename = "Replicas")]
-    replicas: u32,
 }
 
 /// Execute the scale command.
@@ -47,19 +69,18 @@ pub async fn execute(args: ScaleArgs, api_url: &str, format: OutputFormat) -> an
     })?;
 
     // First, try to find the workload by ID or name
-    let workload_id = find_workload_id(&client, &args.workload).await?;
+    let workload_id = find_workload_id(&client, &args.workload, args.namespace.as_deref()).await?;
 
     output::info(&format!(
         "Scaling workload '{}' to {} replica(s)...",
         args.workload, args.replicas
     ));
 
-    let request = UpdateWorkloadRequest {
+    let path = format!("/api/v1/workloads/{}/scale", workload_id);
+    let request = ScaleRequest {
         replicas: args.replicas,
     };
-
-    let path = format!("/api/v1/workloads/{}", workload_id);
-    let response: WorkloadResponse = client.put(&path, &request).await?;
+    let response: ScaleResponse = client.post(&path, &request).await?;
 
     output::success(&format!(
         "Workload scaled to {} replica(s)",
@@ -73,16 +94,25 @@ pub async fn execute(args: ScaleArgs, api_url: &str, format: OutputFormat) -> an
 }
 
 /// Find workload ID by name or ID.
-async fn find_workload_id(client: &ApiClient, name_or_id: &str) -> Result<String> {
+pub(crate) async fn find_workload_id(
+    client: &ApiClient,
+    name_or_id: &str,
+    namespace: Option<&str>,
+) -> Result<String> {
     // First try as UUID directly
     if uuid::Uuid::parse_str(name_or_id).is_ok() {
         return Ok(name_or_id.to_string());
     }
 
     // Otherwise search by name
-    let workloads: Vec<WorkloadResponse> = client.get("/api/v1/workloads").await?;
+    let path = match namespace {
+        Some(namespace) => format!("/api/v1/workloads?namespace={}", namespace),
+        None => "/api/v1/workloads".to_string(),
+    };
+    let workloads: ListResponse<WorkloadResponse> = client.get(&path).await?;
 
     let matching: Vec<_> = workloads
+        .items
         .iter()
         .filter(|w| w.name == name_or_id || w.id.starts_with(name_or_id))
         .collect();
@@ -0,0 +1,373 @@
+//! Service command - create and manage services, the stable virtual IPs in
+//! front of the workloads their selector matches.
+
+use std::collections::HashMap;
+
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::error::CliError;
+use crate::output::{self, print_data, print_item};
+use crate::OutputFormat;
+
+/// Arguments for the service command.
+#[derive(Args)]
+pub struct ServiceArgs {
+    #[command(subcommand)]
+    command: ServiceCommand,
+}
+
+#[derive(Subcommand)]
+enum ServiceCommand {
+    /// Create a service
+    Create {
+        /// Service name
+        name: String,
+
+        /// Workloads carrying this label back the service (repeatable)
+        #[arg(short = 'l', long = "selector", value_parser = parse_label, required = true)]
+        selector: Vec<(String, String)>,
+
+        /// Port as PORT[:TARGET_PORT][/PROTOCOL], e.g. 80:8080/tcp (repeatable)
+        #[arg(short, long = "port", value_parser = parse_service_port, required = true)]
+        ports: Vec<ServicePort>,
+
+        /// Namespace of the service and its workloads (default: "default")
+        #[arg(long)]
+        namespace: Option<String>,
+
+        /// Give the service a virtual IP of every configured family
+        #[arg(long)]
+        dual_stack: bool,
+
+        /// Send each client to the same endpoint until it has been idle this many seconds
+        #[arg(long, value_name = "SECONDS")]
+        client_ip_affinity: Option<u64>,
+
+        /// Traffic share of a backend workload as WORKLOAD_ID=WEIGHT (repeatable)
+        #[arg(short, long = "weight", value_parser = parse_weight)]
+        weights: Vec<(String, u32)>,
+    },
+    /// List services
+    List {
+        /// Only services in this namespace
+        #[arg(long)]
+        namespace: Option<String>,
+    },
+    /// Show a service and its endpoints
+    Get {
+        /// Service ID
+        id: String,
+    },
+    /// Change a service's selector, ports, affinity or backend weights
+    Update {
+        /// Service ID
+        id: String,
+
+        /// Replace the selector with these labels (repeatable)
+        #[arg(short = 'l', long = "selector", value_parser = parse_label)]
+        selector: Vec<(String, String)>,
+
+        /// Replace the ports with these, as PORT[:TARGET_PORT][/PROTOCOL] (repeatable)
+        #[arg(short, long = "port", value_parser = parse_service_port)]
+        ports: Vec<ServicePort>,
+
+        /// Send each client to the same endpoint until it has been idle this many seconds
+        #[arg(long, value_name = "SECONDS", conflicts_with = "no_affinity")]
+        client_ip_affinity: Option<u64>,
+
+        /// Balance every connection independently
+        #[arg(long)]
+        no_affinity: bool,
+
+        /// Set the traffic share of a backend workload as WORKLOAD_ID=WEIGHT;
+        /// 0 drains it (repeatable)
+        #[arg(short, long = "weight", value_parser = parse_weight)]
+        weights: Vec<(String, u32)>,
+    },
+    /// Delete a service and release its virtual IPs
+    Delete {
+        /// Service ID
+        id: String,
+    },
+}
+
+/// Service port - matches API's ServicePortRequest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ServicePort {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target_port: Option<u16>,
+    protocol: String,
+}
+
+/// Parse PORT[:TARGET_PORT][/PROTOCOL].
+fn parse_service_port(s: &str) -> std::result::Result<ServicePort, String> {
+    let (ports, protocol) = match s.split_once('/') {
+        Some((ports, protocol)) => (ports, protocol.to_ascii_lowercase()),
+        None => (s, "tcp".to_string()),
+    };
+    if protocol != "tcp" && protocol != "udp" {
+        return Err(format!("Invalid protocol '{}'. Use tcp or udp", protocol));
+    }
+    let parse = |p: &str| {
+        p.parse::<u16>()
+            .map_err(|_| format!("Invalid port number: {}", p))
+    };
+    let (port, target_port) = match ports.split_once(':') {
+        Some((port, target)) => (parse(port)?, Some(parse(target)?)),
+        None => (parse(ports)?, None),
+    };
+    Ok(ServicePort {
+        name: None,
+        port,
+        target_port,
+        protocol,
+    })
+}
+
+/// Parse a key=value label.
+fn parse_label(s: &str) -> std::result::Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid label format '{}'. Expected key=value", s))?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Parse a WORKLOAD_ID=WEIGHT backend weight.
+fn parse_weight(s: &str) -> std::result::Result<(String, u32), String> {
+    let (workload, weight) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid weight format '{}'. Expected WORKLOAD_ID=WEIGHT", s))?;
+    let weight = weight
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid weight: {}", weight))?;
+    Ok((workload.to_string(), weight))
+}
+
+fn client_ip_affinity(timeout_seconds: u64) -> Value {
+    serde_json::json!({ "type": "client_ip", "timeout_seconds": timeout_seconds })
+}
+
+/// Create service request - matches API's CreateServiceRequest.
+#[derive(Debug, Serialize)]
+struct CreateServiceRequest {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+    selector: HashMap<String, String>,
+    ports: Vec<ServicePort>,
+    ip_family_policy: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_affinity: Option<Value>,
+    backend_weights: HashMap<String, u32>,
+}
+
+/// Update service request - matches API's UpdateServiceRequest.
+#[derive(Debug, Serialize)]
+struct UpdateServiceRequest {
+    selector: HashMap<String, String>,
+    ports: Vec<ServicePort>,
+    session_affinity: Value,
+    backend_weights: HashMap<String, u32>,
+}
+
+/// Service response from API.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct ServiceResponse {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Cluster IPs", display_with = "display_list")]
+    cluster_ips: Vec<String>,
+    #[tabled(rename = "Ports", display_with = "display_ports")]
+    ports: Vec<ServicePort>,
+    #[tabled(skip)]
+    selector: HashMap<String, String>,
+    #[tabled(skip)]
+    session_affinity: Value,
+    #[tabled(skip)]
+    #[serde(default)]
+    backend_weights: HashMap<String, u32>,
+}
+
+/// Endpoints of a service, from API.
+#[derive(Debug, Deserialize)]
+struct ServiceEndpointsResponse {
+    endpoints: Vec<EndpointResponse>,
+}
+
+/// Endpoint of a service, from API.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct EndpointResponse {
+    #[tabled(rename = "Instance")]
+    instance_id: String,
+    #[tabled(rename = "Workload")]
+    workload_id: String,
+    #[tabled(rename = "Addresses", display_with = "display_list")]
+    addresses: Vec<String>,
+    #[tabled(rename = "Weight")]
+    weight: u32,
+    #[tabled(rename = "Ready")]
+    ready: bool,
+}
+
+fn display_list(items: &[String]) -> String {
+    if items.is_empty() {
+        "-".to_string()
+    } else {
+        items.join(",")
+    }
+}
+
+fn display_ports(ports: &[ServicePort]) -> String {
+    ports
+        .iter()
+        .map(|p| {
+            format!(
+                "{}:{}/{}",
+                p.port,
+                p.target_port.unwrap_or(p.port),
+                p.protocol
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Execute the service command.
+pub async fn execute(args: ServiceArgs, api_url: &str, format: OutputFormat) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for service operations. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        ServiceCommand::Create {
+            name,
+            selector,
+            ports,
+            namespace,
+            dual_stack,
+            client_ip_affinity: affinity,
+            weights,
+        } => {
+            let request = CreateServiceRequest {
+                name,
+                namespace,
+                selector: selector.into_iter().collect(),
+                ports,
+                ip_family_policy: if dual_stack {
+                    "PreferDualStack"
+                } else {
+                    "SingleStack"
+                }
+                .to_string(),
+                session_affinity: affinity.map(client_ip_affinity),
+                backend_weights: weights.into_iter().collect(),
+            };
+            let created: ServiceResponse = client.post("/api/v1/services", &request).await?;
+            output::success(&format!(
+                "Service {} created ({}) at {}",
+                created.name,
+                created.id,
+                display_list(&created.cluster_ips)
+            ));
+            Ok(())
+        }
+        ServiceCommand::List { namespace } => {
+            let path = match namespace {
+                Some(namespace) => format!("/api/v1/services?namespace={}", namespace),
+                None => "/api/v1/services".to_string(),
+            };
+            let services: Vec<ServiceResponse> = client.get(&path).await?;
+            print_data(&services, format)?;
+            Ok(())
+        }
+        ServiceCommand::Get { id } => {
+            let service: ServiceResponse = client.get(&format!("/api/v1/services/{}", id)).await?;
+            print_item(&service, format)?;
+            if matches!(format, OutputFormat::Table | OutputFormat::Wide) {
+                let status: ServiceEndpointsResponse = client
+                    .get(&format!("/api/v1/services/{}/endpoints", id))
+                    .await?;
+                output::section("Endpoints");
+                print_data(&status.endpoints, format)?;
+            }
+            Ok(())
+        }
+        ServiceCommand::Update {
+            id,
+            selector,
+            ports,
+            client_ip_affinity: affinity,
+            no_affinity,
+            weights,
+        } => {
+            let current: ServiceResponse = client.get(&format!("/api/v1/services/{}", id)).await?;
+            let mut backend_weights = current.backend_weights;
+            backend_weights.extend(weights);
+            let request = UpdateServiceRequest {
+                selector: if selector.is_empty() {
+                    current.selector
+                } else {
+                    selector.into_iter().collect()
+                },
+                ports: if ports.is_empty() {
+                    current.ports
+                } else {
+                    ports
+                },
+                session_affinity: match (affinity, no_affinity) {
+                    (Some(timeout_seconds), _) => client_ip_affinity(timeout_seconds),
+                    (None, true) => serde_json::json!({ "type": "none" }),
+                    (None, false) => current.session_affinity,
+                },
+                backend_weights,
+            };
+            let updated: ServiceResponse = client
+                .put(&format!("/api/v1/services/{}", id), &request)
+                .await?;
+            output::success(&format!("Service {} updated", updated.name));
+            Ok(())
+        }
+        ServiceCommand::Delete { id } => {
+            client.delete(&format!("/api/v1/services/{}", id)).await?;
+            output::success(&format!("Service {} deleted", id));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_service_port() {
+        assert_eq!(
+            parse_service_port("80:8080/UDP").unwrap(),
+            ServicePort {
+                name: None,
+                port: 80,
+                target_port: Some(8080),
+                protocol: "udp".to_string(),
+            }
+        );
+        let port = parse_service_port("443").unwrap();
+        assert_eq!((port.port, port.target_port), (443, None));
+        assert_eq!(port.protocol, "tcp");
+        assert!(parse_service_port("80/sctp").is_err());
+        assert!(parse_service_port("http").is_err());
+    }
+}
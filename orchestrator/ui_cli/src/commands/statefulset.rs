@@ -0,0 +1,284 @@
+//! Stateful set command - create and manage workloads whose instances keep
+//! their name, identity and volumes.
+//!
+//! Stateful sets are kept and run by the control-plane leader, so writes sent
+//! to another node are refused.
+
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::client::ApiClient;
+use crate::error::CliError;
+use crate::output::{self, print_data, print_item};
+use crate::OutputFormat;
+
+/// Arguments for the statefulset command.
+#[derive(Args)]
+pub struct StatefulSetArgs {
+    #[command(subcommand)]
+    command: StatefulSetCommand,
+}
+
+#[derive(Subcommand)]
+enum StatefulSetCommand {
+    /// Create a stateful set
+    Create {
+        /// Stateful set name; instance n is named <name>-<n>
+        name: String,
+
+        /// Container image each instance runs
+        #[arg(short, long)]
+        image: String,
+
+        /// Number of instances
+        #[arg(short, long, default_value = "1")]
+        replicas: u32,
+
+        /// Namespace of the instances (default: "default")
+        #[arg(long)]
+        namespace: Option<String>,
+
+        /// Volume each instance gets a directory of its own for, as NAME:MOUNT_PATH
+        #[arg(long = "volume", value_parser = parse_volume_claim)]
+        volumes: Vec<VolumeClaim>,
+
+        /// Command run in the container, overriding the image's entrypoint
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
+    /// List stateful sets
+    List,
+    /// Show a stateful set and its instances
+    Get {
+        /// Stateful set ID
+        id: String,
+    },
+    /// Roll a new image out to a stateful set, highest ordinal first
+    SetImage {
+        /// Stateful set ID
+        id: String,
+
+        /// Container image the instances run from now on
+        image: String,
+    },
+    /// Change the number of instances of a stateful set
+    Scale {
+        /// Stateful set ID
+        id: String,
+
+        /// Number of instances
+        replicas: u32,
+    },
+    /// Delete a stateful set and stop its instances; volumes are kept
+    Delete {
+        /// Stateful set ID
+        id: String,
+    },
+}
+
+/// Volume claim - matches API's VolumeClaimRequest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VolumeClaim {
+    name: String,
+    mount_path: String,
+}
+
+fn parse_volume_claim(value: &str) -> Result<VolumeClaim, String> {
+    match value.split_once(':') {
+        Some((name, mount_path)) if !name.is_empty() && mount_path.starts_with('/') => {
+            Ok(VolumeClaim {
+                name: name.to_string(),
+                mount_path: mount_path.to_string(),
+            })
+        }
+        _ => Err(format!(
+            "expected NAME:MOUNT_PATH with an absolute mount path, got '{}'",
+            value
+        )),
+    }
+}
+
+/// Create stateful set request - matches API's CreateStatefulSetRequest.
+#[derive(Debug, Serialize)]
+struct CreateStatefulSetRequest {
+    name: String,
+    template: CreateWorkloadRequest,
+    replicas: u32,
+    volume_claims: Vec<VolumeClaim>,
+}
+
+/// Update stateful set request - matches API's UpdateStatefulSetRequest.
+#[derive(Debug, Serialize)]
+struct UpdateStatefulSetRequest {
+    template: CreateWorkloadRequest,
+}
+
+/// Scale stateful set request - matches API's ScaleStatefulSetRequest.
+#[derive(Debug, Serialize)]
+struct ScaleStatefulSetRequest {
+    replicas: u32,
+}
+
+/// Instance template - matches API's CreateWorkloadRequest.
+#[derive(Debug, Serialize)]
+struct CreateWorkloadRequest {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+    replicas: u32,
+    containers: Vec<ContainerConfigRequest>,
+}
+
+/// Container configuration - matches API's ContainerConfigRequest.
+#[derive(Debug, Serialize)]
+struct ContainerConfigRequest {
+    name: String,
+    image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<Vec<String>>,
+}
+
+/// Stateful set response from API.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct StatefulSetResponse {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Namespace")]
+    namespace: String,
+    #[tabled(rename = "Image")]
+    image: String,
+    #[tabled(rename = "Replicas")]
+    replicas: u32,
+    #[tabled(rename = "Revision")]
+    revision: u64,
+    #[tabled(skip)]
+    workload_id: String,
+    #[tabled(skip)]
+    #[serde(default)]
+    volume_claims: Vec<VolumeClaim>,
+    #[tabled(skip)]
+    #[serde(default)]
+    instances: Vec<InstanceResponse>,
+}
+
+/// Instance of a stateful set, from API.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+struct InstanceResponse {
+    #[tabled(rename = "Instance")]
+    id: String,
+    #[tabled(rename = "Node")]
+    node_id: String,
+    #[tabled(rename = "Status")]
+    status: String,
+}
+
+/// Execute the statefulset command.
+pub async fn execute(
+    args: StatefulSetArgs,
+    api_url: &str,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for stateful set operations. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+
+    match args.command {
+        StatefulSetCommand::Create {
+            name,
+            image,
+            replicas,
+            namespace,
+            volumes,
+            command,
+        } => {
+            let request = CreateStatefulSetRequest {
+                name: name.clone(),
+                template: template(name, namespace, image, command),
+                replicas,
+                volume_claims: volumes,
+            };
+            let created: StatefulSetResponse =
+                client.post("/api/v1/statefulsets", &request).await?;
+            output::success(&format!(
+                "Stateful set {} created ({})",
+                created.name, created.id
+            ));
+            Ok(())
+        }
+        StatefulSetCommand::List => {
+            let stateful_sets: Vec<StatefulSetResponse> =
+                client.get("/api/v1/statefulsets").await?;
+            print_data(&stateful_sets, format)?;
+            Ok(())
+        }
+        StatefulSetCommand::Get { id } => {
+            let stateful_set: StatefulSetResponse =
+                client.get(&format!("/api/v1/statefulsets/{}", id)).await?;
+            print_item(&stateful_set, format)?;
+            if matches!(format, OutputFormat::Table | OutputFormat::Wide) {
+                output::section("Instances");
+                print_data(&stateful_set.instances, format)?;
+            }
+            Ok(())
+        }
+        StatefulSetCommand::SetImage { id, image } => {
+            let current: StatefulSetResponse =
+                client.get(&format!("/api/v1/statefulsets/{}", id)).await?;
+            let request = UpdateStatefulSetRequest {
+                template: template(current.name, Some(current.namespace), image, Vec::new()),
+            };
+            let updated: StatefulSetResponse = client
+                .put(&format!("/api/v1/statefulsets/{}", id), &request)
+                .await?;
+            output::success(&format!(
+                "Stateful set {} rolling out revision {}",
+                updated.name, updated.revision
+            ));
+            Ok(())
+        }
+        StatefulSetCommand::Scale { id, replicas } => {
+            let scaled: StatefulSetResponse = client
+                .post(
+                    &format!("/api/v1/statefulsets/{}/scale", id),
+                    &ScaleStatefulSetRequest { replicas },
+                )
+                .await?;
+            output::success(&format!(
+                "Stateful set {} scaled to {} replica(s)",
+                scaled.name, scaled.replicas
+            ));
+            Ok(())
+        }
+        StatefulSetCommand::Delete { id } => {
+            client
+                .delete(&format!("/api/v1/statefulsets/{}", id))
+                .await?;
+            output::success(&format!("Stateful set {} deleted; volumes kept", id));
+            Ok(())
+        }
+    }
+}
+
+fn template(
+    name: String,
+    namespace: Option<String>,
+    image: String,
+    command: Vec<String>,
+) -> CreateWorkloadRequest {
+    CreateWorkloadRequest {
+        name: name.clone(),
+        namespace,
+        replicas: 1,
+        containers: vec![ContainerConfigRequest {
+            name,
+            image,
+            command: (!command.is_empty()).then_some(command),
+        }],
+    }
+}
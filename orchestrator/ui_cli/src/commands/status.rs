@@ -1,17 +1,26 @@
 //! Status command - show cluster and workload status.
 
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
 use clap::Args;
 use serde::{Deserialize, Serialize};
 use tabled::Tabled;
 
 use crate::client::ApiClient;
 use crate::output::{self, print_data, section};
+use crate::watch::{self, LiveTable, Screen, Watched, REDRAW_DELAY};
 use crate::OutputFormat;
 
+/// Reason of events recorded on instances that behave abnormally.
+const ABNORMAL_REASON: &str = "AbnormalBehavior";
+/// Reason of events recorded on abnormal instances that recovered.
+const NORMAL_REASON: &str = "BehaviorNormal";
+
 /// Arguments for the status command.
 #[derive(Args)]
 pub struct StatusArgs {
-    /// Show detailed status
+    /// Show detailed status, including instances behaving abnormally
     #[arg(short, long)]
     detailed: bool,
 
@@ -26,6 +35,15 @@ pub struct StatusArgs {
     /// Show only workloads
     #[arg(long)]
     workloads_only: bool,
+
+    /// Show only workloads in this namespace
+    #[arg(long)]
+    namespace: Option<String>,
+
+    /// Keep the node and instance tables up to date as they change (`-w` is
+    /// taken by `--workload`)
+    #[arg(long)]
+    watch: bool,
 }
 
 /// Generic list response wrapper from API.
@@ -56,7 +74,7 @@ struct ClusterStatusResponse {
 }
 
 /// Resource information from API.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct ResourcesResponse {
     cpu_cores: f32,
     memory_mb: u64,
@@ -65,15 +83,26 @@ struct ResourcesResponse {
 }
 
 /// Node response from API.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct NodeResponse {
     id: String,
     status: String,
     address: String,
-    #[allow(dead_code)]
     labels: std::collections::HashMap<String, String>,
     resources_capacity: ResourcesResponse,
     resources_allocatable: ResourcesResponse,
+    #[serde(default)]
+    unschedulable: bool,
+    #[serde(default)]
+    image_pulls: Option<ImagePullsResponse>,
+}
+
+/// Image pull queue of a node from API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ImagePullsResponse {
+    active: usize,
+    queued_interactive: usize,
+    queued_batch: usize,
 }
 
 /// Display-friendly node for table output.
@@ -89,27 +118,58 @@ struct NodeDisplay {
     cpu_allocatable: String,
     #[tabled(rename = "Memory (alloc)")]
     memory_allocatable: String,
+    #[tabled(rename = "Pulls (active/queued)")]
+    image_pulls: String,
+    #[tabled(skip)]
+    labels: std::collections::HashMap<String, String>,
 }
 
 impl From<NodeResponse> for NodeDisplay {
     fn from(n: NodeResponse) -> Self {
         NodeDisplay {
             id: n.id[..8.min(n.id.len())].to_string(),
-            status: n.status,
+            status: if n.unschedulable {
+                format!("{},SchedulingDisabled", n.status)
+            } else {
+                n.status
+            },
             address: n.address,
             cpu_allocatable: format!("{:.1}/{:.1}", n.resources_allocatable.cpu_cores, n.resources_capacity.cpu_cores),
             memory_allocatable: format!("{}/{} MB", n.resources_allocatable.memory_mb, n.resources_capacity.memory_mb),
+            image_pulls: n.image_pulls.map_or_else(
+                || "-".to_string(),
+                |p| format!("{}/{}", p.active, p.queued_interactive + p.queued_batch),
+            ),
+            labels: n.labels,
         }
     }
 }
 
+impl Watched for NodeResponse {
+    type Row = NodeDisplay;
+    const KIND: &'static str = "node";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn state(&self) -> String {
+        self.status.clone()
+    }
+
+    fn row(&self) -> NodeDisplay {
+        self.clone().into()
+    }
+}
+
 /// Workload response from API.
 #[derive(Debug, Serialize, Deserialize)]
 struct WorkloadResponse {
     id: String,
     name: String,
+    #[serde(default)]
+    namespace: String,
     replicas: u32,
-    #[allow(dead_code)]
     labels: std::collections::HashMap<String, String>,
     containers: Vec<ContainerConfigResponse>,
 }
@@ -152,6 +212,12 @@ struct WorkloadDisplay {
     replicas: u32,
     #[tabled(rename = "Image")]
     image: String,
+    #[tabled(skip)]
+    namespace: String,
+    #[tabled(skip)]
+    labels: std::collections::HashMap<String, String>,
+    #[tabled(skip)]
+    images: Vec<String>,
 }
 
 impl From<&WorkloadResponse> for WorkloadDisplay {
@@ -161,18 +227,23 @@ impl From<&WorkloadResponse> for WorkloadDisplay {
             name: w.name.clone(),
             replicas: w.replicas,
             image: w.containers.first().map(|c| c.image.clone()).unwrap_or_else(|| "-".to_string()),
+            namespace: w.namespace.clone(),
+            labels: w.labels.clone(),
+            images: w.containers.iter().map(|c| c.image.clone()).collect(),
         }
     }
 }
 
 /// Workload instance response from API.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct InstanceResponse {
     id: String,
     workload_id: String,
     node_id: String,
     status: String,
     container_ids: Vec<String>,
+    #[serde(default)]
+    ip_addresses: Vec<String>,
 }
 
 /// Display-friendly instance for table output.
@@ -188,6 +259,8 @@ struct InstanceDisplay {
     status: String,
     #[tabled(rename = "Containers")]
     containers: usize,
+    #[tabled(skip)]
+    ip_addresses: Vec<String>,
 }
 
 impl From<InstanceResponse> for InstanceDisplay {
@@ -198,8 +271,64 @@ impl From<InstanceResponse> for InstanceDisplay {
             node_id: i.node_id[..8.min(i.node_id.len())].to_string(),
             status: i.status,
             containers: i.container_ids.len(),
+            ip_addresses: i.ip_addresses,
+        }
+    }
+}
+
+impl Watched for InstanceResponse {
+    type Row = InstanceDisplay;
+    const KIND: &'static str = "instance";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn state(&self) -> String {
+        self.status.clone()
+    }
+
+    fn row(&self) -> InstanceDisplay {
+        self.clone().into()
+    }
+}
+
+/// Event about an instance from API.
+#[derive(Debug, Deserialize)]
+struct EventResponse {
+    involved_object: EventObject,
+    reason: String,
+    message: String,
+    last_timestamp: DateTime<Utc>,
+}
+
+/// The object an event is about.
+#[derive(Debug, Deserialize)]
+struct EventObject {
+    kind: String,
+    id: String,
+}
+
+/// Messages of the instances whose latest anomaly event says they behave
+/// abnormally, by instance ID.
+fn abnormal_instances(events: Vec<EventResponse>) -> HashMap<String, String> {
+    let mut latest: HashMap<String, EventResponse> = HashMap::new();
+    for event in events.into_iter().filter(|e| {
+        e.involved_object.kind == "Instance"
+            && (e.reason == ABNORMAL_REASON || e.reason == NORMAL_REASON)
+    }) {
+        match latest.get(&event.involved_object.id) {
+            Some(newer) if newer.last_timestamp >= event.last_timestamp => {}
+            _ => {
+                latest.insert(event.involved_object.id.clone(), event);
+            }
         }
     }
+    latest
+        .into_iter()
+        .filter(|(_, event)| event.reason == ABNORMAL_REASON)
+        .map(|(id, event)| (id, event.message))
+        .collect()
 }
 
 /// Execute the status command.
@@ -212,6 +341,9 @@ pub async fn execute(args: StatusArgs, api_url: &str, format: OutputFormat) -> a
             ApiClient::new(api_url)
         }
     };
+    if args.watch {
+        return watch_status(&client, &args, format).await;
+    }
 
     // Show cluster status first (unless filtering)
     if !args.nodes_only && !args.workloads_only {
@@ -250,7 +382,11 @@ pub async fn execute(args: StatusArgs, api_url: &str, format: OutputFormat) -> a
     // Show workloads
     if !args.nodes_only {
         section("Workloads");
-        match client.get::<ListResponse<WorkloadResponse>>("/api/v1/workloads").await {
+        let path = match args.namespace {
+            Some(ref namespace) => format!("/api/v1/workloads?namespace={}", namespace),
+            None => "/api/v1/workloads".to_string(),
+        };
+        match client.get::<ListResponse<WorkloadResponse>>(&path).await {
             Ok(response) => {
                 let workloads = response.items;
                 let filtered: Vec<_> = if let Some(ref name) = args.workload {
@@ -267,6 +403,17 @@ pub async fn execute(args: StatusArgs, api_url: &str, format: OutputFormat) -> a
 
                 // Show instances if detailed
                 if args.detailed && !filtered.is_empty() {
+                    // Servers that keep no events report no anomalies
+                    let events_path = match args.namespace {
+                        Some(ref namespace) => format!("/api/v1/events?namespace={}", namespace),
+                        None => "/api/v1/events".to_string(),
+                    };
+                    let abnormal = client
+                        .get::<ListResponse<EventResponse>>(&events_path)
+                        .await
+                        .map(|events| abnormal_instances(events.items))
+                        .unwrap_or_default();
+
                     section("Instances");
                     for workload in &filtered {
                         let path = format!("/api/v1/workloads/{}/instances", workload.id);
@@ -274,8 +421,16 @@ pub async fn execute(args: StatusArgs, api_url: &str, format: OutputFormat) -> a
                             Ok(inst_response) => {
                                 if !inst_response.items.is_empty() {
                                     println!("\n  Workload: {} ({})", workload.name, &workload.id[..8]);
+                                    let messages: Vec<&String> = inst_response
+                                        .items
+                                        .iter()
+                                        .filter_map(|i| abnormal.get(&i.id))
+                                        .collect();
                                     let displays: Vec<InstanceDisplay> = inst_response.items.into_iter().map(Into::into).collect();
                                     print_data(&displays, format)?;
+                                    for message in messages {
+                                        output::warn(message);
+                                    }
                                 }
                             }
                             Err(e) => {
@@ -296,3 +451,110 @@ pub async fn execute(args: StatusArgs, api_url: &str, format: OutputFormat) -> a
 
     Ok(())
 }
+
+/// Show nodes and instances, redrawn as they change, until a watch ends.
+async fn watch_status(
+    client: &ApiClient,
+    args: &StatusArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let mut node_stream = if args.workloads_only {
+        None
+    } else {
+        Some(client.watch("/api/v1/nodes").await?)
+    };
+    let instances_path = match args.namespace {
+        Some(ref namespace) => format!("/api/v1/instances?namespace={}", namespace),
+        None => "/api/v1/instances".to_string(),
+    };
+    let mut instance_stream = if args.nodes_only {
+        None
+    } else {
+        Some(client.watch(&instances_path).await?)
+    };
+
+    let mut nodes = LiveTable::<NodeResponse>::new();
+    let mut instances = LiveTable::<InstanceResponse>::new();
+    let mut screen = Screen::new();
+    let redraw = tokio::time::sleep(REDRAW_DELAY);
+    tokio::pin!(redraw);
+    let mut dirty = false;
+
+    loop {
+        tokio::select! {
+            event = watch::next_event::<NodeResponse>(&mut node_stream) => match event? {
+                Some(event) if !matches!(format, OutputFormat::Table | OutputFormat::Wide) => {
+                    watch::print_event(&event, format)?
+                }
+                Some(event) => {
+                    screen.record(nodes.apply(event));
+                    dirty = true;
+                }
+                None => break,
+            },
+            event = watch::next_event::<InstanceResponse>(&mut instance_stream) => match event? {
+                Some(event) if !matches!(format, OutputFormat::Table | OutputFormat::Wide) => {
+                    watch::print_event(&event, format)?
+                }
+                Some(event) => {
+                    screen.record(instances.apply(event));
+                    dirty = true;
+                }
+                None => break,
+            },
+            () = &mut redraw, if dirty => {
+                let mut sections = Vec::new();
+                if node_stream.is_some() {
+                    sections.push(("Nodes", nodes.render()));
+                }
+                if instance_stream.is_some() {
+                    sections.push(("Instances", instances.render()));
+                }
+                screen.draw(&sections)?;
+                dirty = false;
+                continue;
+            }
+        }
+        // Draw once events stop arriving
+        redraw
+            .as_mut()
+            .reset(tokio::time::Instant::now() + REDRAW_DELAY);
+    }
+
+    output::warn("The server closed the watch; run the command again to resume");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(instance: &str, reason: &str, minute: i64) -> EventResponse {
+        EventResponse {
+            involved_object: EventObject {
+                kind: "Instance".to_string(),
+                id: instance.to_string(),
+            },
+            reason: reason.to_string(),
+            message: format!("{} {}", instance, reason),
+            last_timestamp: DateTime::UNIX_EPOCH + chrono::Duration::minutes(minute),
+        }
+    }
+
+    #[test]
+    fn test_abnormal_instances_follow_the_latest_event() {
+        let abnormal = abnormal_instances(vec![
+            event("a", ABNORMAL_REASON, 1),
+            event("a", NORMAL_REASON, 2),
+            event("b", NORMAL_REASON, 1),
+            event("b", ABNORMAL_REASON, 3),
+            event("c", "Started", 4),
+            // Out of order
+            event("d", ABNORMAL_REASON, 5),
+            event("d", NORMAL_REASON, 4),
+        ]);
+        assert_eq!(abnormal.len(), 2);
+        assert_eq!(abnormal["b"], "b AbnormalBehavior");
+        assert_eq!(abnormal["d"], "d AbnormalBehavior");
+    }
+}
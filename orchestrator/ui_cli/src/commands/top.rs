@@ -0,0 +1,780 @@
+//! Top command - interactive dashboard of nodes, workloads and instances.
+//!
+//! Nodes and workloads are refreshed every `--interval` seconds; instances are
+//! watched and update as they change. The selected instance can be described,
+//! restarted, or have its logs shown.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use clap::Args;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::Frame;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use crate::client::ApiClient;
+use crate::error::{CliError, Result};
+use crate::watch::{self, WatchStream};
+
+/// Lines of logs shown for an instance.
+const LOG_TAIL: usize = 200;
+
+/// Width of the usage bars, in cells.
+const BAR_WIDTH: usize = 10;
+
+/// Arguments for the top command.
+#[derive(Args)]
+pub struct TopArgs {
+    /// Show only workloads and instances in this namespace
+    #[arg(long)]
+    namespace: Option<String>,
+
+    /// Seconds between refreshes of nodes and workloads
+    #[arg(long, default_value = "2")]
+    interval: u64,
+}
+
+/// Generic list response wrapper from API.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
+}
+
+/// Resource information from API.
+#[derive(Debug, Clone, Deserialize)]
+struct ResourcesResponse {
+    cpu_cores: f32,
+    memory_mb: u64,
+}
+
+/// Node response from API.
+#[derive(Debug, Clone, Deserialize)]
+struct NodeResponse {
+    id: String,
+    status: String,
+    address: String,
+    resources_capacity: ResourcesResponse,
+    resources_allocatable: ResourcesResponse,
+    #[serde(default)]
+    unschedulable: bool,
+}
+
+/// Workload response from API.
+#[derive(Debug, Clone, Deserialize)]
+struct WorkloadResponse {
+    id: String,
+    name: String,
+    #[serde(default)]
+    namespace: String,
+    replicas: u32,
+    containers: Vec<ContainerResponse>,
+}
+
+/// Container of a workload from API.
+#[derive(Debug, Clone, Deserialize)]
+struct ContainerResponse {
+    name: String,
+    image: String,
+}
+
+/// Instance response from API.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct InstanceResponse {
+    id: String,
+    workload_id: String,
+    node_id: String,
+    status: String,
+    #[serde(default)]
+    ip_addresses: Vec<String>,
+    #[serde(default)]
+    restarts: Vec<RestartResponse>,
+}
+
+/// Restart history of one container from API.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct RestartResponse {
+    container_name: String,
+    restart_count: u32,
+    last_exit_code: Option<i32>,
+}
+
+/// Log response from API.
+#[derive(Debug, Deserialize)]
+struct LogsResponse {
+    logs: String,
+}
+
+/// What a key press asks for beyond changing the screen.
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    None,
+    Quit,
+    Describe(InstanceResponse),
+    Restart(InstanceResponse),
+    Logs(InstanceResponse),
+}
+
+/// A scrollable text window over the tables.
+struct Popup {
+    title: String,
+    lines: Vec<Line<'static>>,
+    scroll: u16,
+}
+
+/// State of the dashboard.
+struct App {
+    nodes: Vec<NodeResponse>,
+    workloads: Vec<WorkloadResponse>,
+    instances: BTreeMap<String, InstanceResponse>,
+    selected: TableState,
+    popup: Option<Popup>,
+    message: Option<String>,
+}
+
+impl App {
+    fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            workloads: Vec::new(),
+            instances: BTreeMap::new(),
+            selected: TableState::default(),
+            popup: None,
+            message: None,
+        }
+    }
+
+    /// Instances in display order: by workload name, then ID.
+    fn ordered_instances(&self) -> Vec<&InstanceResponse> {
+        let names: HashMap<&str, &str> = self
+            .workloads
+            .iter()
+            .map(|w| (w.id.as_str(), w.name.as_str()))
+            .collect();
+        let mut instances: Vec<&InstanceResponse> = self.instances.values().collect();
+        instances.sort_by_key(|i| (names.get(i.workload_id.as_str()).copied(), i.id.as_str()));
+        instances
+    }
+
+    fn selected_instance(&self) -> Option<InstanceResponse> {
+        let index = self.selected.selected()?;
+        self.ordered_instances().get(index).map(|i| (*i).clone())
+    }
+
+    /// Instances of `workload_id` that are running.
+    fn running(&self, workload_id: &str) -> usize {
+        self.instances
+            .values()
+            .filter(|i| i.workload_id == workload_id && i.status == "Running")
+            .count()
+    }
+
+    fn workload_name(&self, workload_id: &str) -> String {
+        self.workloads
+            .iter()
+            .find(|w| w.id == workload_id)
+            .map_or_else(|| short(workload_id), |w| w.name.clone())
+    }
+
+    /// Keep the selection on a row as instances come and go.
+    fn clamp_selection(&mut self) {
+        let count = self.instances.len();
+        match self.selected.selected() {
+            _ if count == 0 => self.selected.select(None),
+            None => self.selected.select(Some(0)),
+            Some(index) if index >= count => self.selected.select(Some(count - 1)),
+            Some(_) => {}
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Action {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+        if let Some(popup) = &mut self.popup {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => self.popup = None,
+                KeyCode::Up | KeyCode::Char('k') => popup.scroll = popup.scroll.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => popup.scroll = popup.scroll.saturating_add(1),
+                KeyCode::PageUp => popup.scroll = popup.scroll.saturating_sub(10),
+                KeyCode::PageDown => popup.scroll = popup.scroll.saturating_add(10),
+                _ => {}
+            }
+            return Action::None;
+        }
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Action::Quit,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected.select_previous();
+                Action::None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected.select_next();
+                self.clamp_selection();
+                Action::None
+            }
+            KeyCode::Char('d') => self
+                .selected_instance()
+                .map_or(Action::None, Action::Describe),
+            KeyCode::Char('r') => self
+                .selected_instance()
+                .map_or(Action::None, Action::Restart),
+            KeyCode::Char('l') => self.selected_instance().map_or(Action::None, Action::Logs),
+            _ => Action::None,
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let workload_rows = self.workloads.len().clamp(1, 10) as u16;
+        let [nodes_area, workloads_area, instances_area, footer_area] = Layout::vertical([
+            Constraint::Length(self.nodes.len().clamp(1, 8) as u16 + 3),
+            Constraint::Length(workload_rows + 3),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(self.nodes_table(), nodes_area);
+        frame.render_widget(self.workloads_table(), workloads_area);
+        let instances = self.instances_table();
+        frame.render_stateful_widget(instances, instances_area, &mut self.selected);
+
+        let help = "↑/↓ select  d describe  r restart  l logs  q quit";
+        let footer = match &self.message {
+            Some(message) => Line::from(vec![
+                Span::styled(help, Style::new().add_modifier(Modifier::DIM)),
+                Span::raw("  "),
+                Span::raw(message.clone()),
+            ]),
+            None => Line::styled(help, Style::new().add_modifier(Modifier::DIM)),
+        };
+        frame.render_widget(footer, footer_area);
+
+        if let Some(popup) = &self.popup {
+            let area = centered(frame.area());
+            frame.render_widget(Clear, area);
+            let block = Block::bordered().title(format!(" {} (Esc to close) ", popup.title));
+            let text = Paragraph::new(popup.lines.clone())
+                .block(block)
+                .wrap(Wrap { trim: false })
+                .scroll((popup.scroll, 0));
+            frame.render_widget(text, area);
+        }
+    }
+
+    fn nodes_table(&self) -> Table<'static> {
+        let rows = self.nodes.iter().map(|node| {
+            let status = if node.unschedulable {
+                format!("{},SchedulingDisabled", node.status)
+            } else {
+                node.status.clone()
+            };
+            let capacity = &node.resources_capacity;
+            let allocatable = &node.resources_allocatable;
+            let cpu = usage(
+                (capacity.cpu_cores - allocatable.cpu_cores) as f64,
+                capacity.cpu_cores as f64,
+            );
+            let memory = usage(
+                capacity.memory_mb.saturating_sub(allocatable.memory_mb) as f64,
+                capacity.memory_mb as f64,
+            );
+            Row::new(vec![
+                Cell::from(short(&node.id)),
+                Cell::from(Span::styled(status, state_style(&node.status))),
+                Cell::from(node.address.clone()),
+                usage_cell(cpu),
+                usage_cell(memory),
+            ])
+        });
+        Table::new(
+            rows,
+            [
+                Constraint::Length(10),
+                Constraint::Length(26),
+                Constraint::Length(22),
+                Constraint::Length(BAR_WIDTH as u16 + 6),
+                Constraint::Length(BAR_WIDTH as u16 + 6),
+            ],
+        )
+        .header(header(["ID", "Status", "Address", "CPU", "Memory"]))
+        .block(Block::bordered().title(format!(" Nodes ({}) ", self.nodes.len())))
+    }
+
+    fn workloads_table(&self) -> Table<'static> {
+        let rows = self.workloads.iter().map(|workload| {
+            let running = self.running(&workload.id);
+            let ready_style = if running as u32 >= workload.replicas {
+                Style::new().fg(Color::Green)
+            } else if running > 0 {
+                Style::new().fg(Color::Yellow)
+            } else {
+                Style::new().fg(Color::Red)
+            };
+            Row::new(vec![
+                Cell::from(workload.name.clone()),
+                Cell::from(workload.namespace.clone()),
+                Cell::from(Span::styled(
+                    format!("{}/{}", running, workload.replicas),
+                    ready_style,
+                )),
+                Cell::from(
+                    workload
+                        .containers
+                        .first()
+                        .map_or_else(|| "-".to_string(), |c| c.image.clone()),
+                ),
+            ])
+        });
+        Table::new(
+            rows,
+            [
+                Constraint::Length(24),
+                Constraint::Length(16),
+                Constraint::Length(8),
+                Constraint::Min(10),
+            ],
+        )
+        .header(header(["Name", "Namespace", "Ready", "Image"]))
+        .block(Block::bordered().title(format!(" Workloads ({}) ", self.workloads.len())))
+    }
+
+    fn instances_table(&self) -> Table<'static> {
+        let rows: Vec<Row> = self
+            .ordered_instances()
+            .into_iter()
+            .map(|instance| {
+                let restarts: u32 = instance.restarts.iter().map(|r| r.restart_count).sum();
+                Row::new(vec![
+                    Cell::from(short(&instance.id)),
+                    Cell::from(self.workload_name(&instance.workload_id)),
+                    Cell::from(short(&instance.node_id)),
+                    Cell::from(Span::styled(
+                        instance.status.clone(),
+                        state_style(&instance.status),
+                    )),
+                    Cell::from(restarts.to_string()),
+                    Cell::from(instance.ip_addresses.join(",")),
+                ])
+            })
+            .collect();
+        Table::new(
+            rows,
+            [
+                Constraint::Length(10),
+                Constraint::Length(24),
+                Constraint::Length(10),
+                Constraint::Length(18),
+                Constraint::Length(9),
+                Constraint::Min(10),
+            ],
+        )
+        .header(header([
+            "ID", "Workload", "Node", "Status", "Restarts", "IP",
+        ]))
+        .block(Block::bordered().title(format!(" Instances ({}) ", self.instances.len())))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+    }
+}
+
+fn header<const N: usize>(titles: [&'static str; N]) -> Row<'static> {
+    Row::new(titles).style(Style::new().add_modifier(Modifier::BOLD))
+}
+
+fn short(id: &str) -> String {
+    id.chars().take(8).collect()
+}
+
+/// Style of a node or instance state, by how healthy it is.
+fn state_style(state: &str) -> Style {
+    match state {
+        "Ready" | "Running" | "Succeeded" => Style::new().fg(Color::Green),
+        "Pending" | "Unknown" => Style::new().fg(Color::Yellow),
+        _ => Style::new().fg(Color::Red),
+    }
+}
+
+/// Fraction of `total` that `used` is, between 0 and 1.
+fn usage(used: f64, total: f64) -> f64 {
+    if total <= 0.0 {
+        return 0.0;
+    }
+    (used / total).clamp(0.0, 1.0)
+}
+
+/// A bar of [`BAR_WIDTH`] cells filled to `fraction`, with its percentage.
+fn usage_bar(fraction: f64) -> String {
+    let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+    format!(
+        "{}{} {:>3}%",
+        "█".repeat(filled),
+        "░".repeat(BAR_WIDTH - filled),
+        (fraction * 100.0).round() as u32
+    )
+}
+
+fn usage_cell(fraction: f64) -> Cell<'static> {
+    let color = match fraction {
+        f if f >= 0.9 => Color::Red,
+        f if f >= 0.7 => Color::Yellow,
+        _ => Color::Green,
+    };
+    Cell::from(Span::styled(usage_bar(fraction), Style::new().fg(color)))
+}
+
+/// The middle of `area`, for popups.
+fn centered(area: Rect) -> Rect {
+    let [_, middle, _] = Layout::vertical([
+        Constraint::Percentage(10),
+        Constraint::Percentage(80),
+        Constraint::Percentage(10),
+    ])
+    .areas(area);
+    let [_, middle, _] = Layout::horizontal([
+        Constraint::Percentage(10),
+        Constraint::Percentage(80),
+        Constraint::Percentage(10),
+    ])
+    .areas(middle);
+    middle
+}
+
+/// Terminal events, read on a thread of their own since reading blocks.
+fn terminal_events() -> mpsc::UnboundedReceiver<Event> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = crossterm::event::read() {
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+/// Execute the top command.
+pub async fn execute(args: TopArgs, api_url: &str) -> anyhow::Result<()> {
+    let client = ApiClient::authenticated(api_url).await.map_err(|e| {
+        CliError::config_error(format!(
+            "Authentication required for top. Run 'orch init' first. Error: {}",
+            e
+        ))
+    })?;
+    let scope = match &args.namespace {
+        Some(namespace) => format!("?namespace={}", namespace),
+        None => String::new(),
+    };
+
+    // Fail before taking over the terminal if the API is unreachable
+    let mut app = App::new();
+    refresh(&client, &scope, &mut app).await?;
+    let mut instances = Some(client.watch(&format!("/api/v1/instances{}", scope)).await?);
+
+    let mut terminal = ratatui::init();
+    let result = run(
+        &client,
+        &scope,
+        &mut app,
+        &mut instances,
+        &mut terminal,
+        &args,
+    )
+    .await;
+    ratatui::restore();
+    result
+}
+
+async fn run(
+    client: &ApiClient,
+    scope: &str,
+    app: &mut App,
+    instances: &mut Option<WatchStream>,
+    terminal: &mut ratatui::DefaultTerminal,
+    args: &TopArgs,
+) -> anyhow::Result<()> {
+    let mut events = terminal_events();
+    let mut tick = tokio::time::interval(Duration::from_secs(args.interval.max(1)));
+
+    loop {
+        terminal.draw(|frame| app.render(frame))?;
+
+        tokio::select! {
+            event = events.recv() => {
+                let key = match event {
+                    Some(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+                    // Resizes and the like only need a redraw
+                    Some(_) => continue,
+                    None => return Ok(()),
+                };
+                match app.on_key(key) {
+                    Action::None => {}
+                    Action::Quit => return Ok(()),
+                    Action::Describe(instance) => {
+                        app.popup = Some(describe(client, &instance).await);
+                    }
+                    Action::Restart(instance) => {
+                        app.message = Some(restart(client, &instance).await);
+                    }
+                    Action::Logs(instance) => {
+                        app.popup = Some(logs(client, &instance).await);
+                    }
+                }
+            }
+            event = watch::next_event::<InstanceResponse>(instances) => match event {
+                Ok(Some(event)) => {
+                    if event.event_type == "DELETED" {
+                        app.instances.remove(&event.object.id);
+                    } else {
+                        app.instances.insert(event.object.id.clone(), event.object);
+                    }
+                    app.clamp_selection();
+                }
+                Ok(None) | Err(_) => {
+                    // Watch again on the next tick
+                    *instances = None;
+                    app.message = Some("Instance watch closed, reconnecting".to_string());
+                }
+            },
+            _ = tick.tick() => {
+                if let Err(e) = refresh(client, scope, app).await {
+                    app.message = Some(format!("Refresh failed: {}", e));
+                }
+                if instances.is_none() {
+                    let path = format!("/api/v1/instances{}", scope);
+                    if let Ok(stream) = client.watch(&path).await {
+                        *instances = Some(stream);
+                        app.instances.clear();
+                        app.message = None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Reload nodes and workloads.
+async fn refresh(client: &ApiClient, scope: &str, app: &mut App) -> Result<()> {
+    let nodes: ListResponse<NodeResponse> = client.get("/api/v1/nodes").await?;
+    let workloads: ListResponse<WorkloadResponse> =
+        client.get(&format!("/api/v1/workloads{}", scope)).await?;
+    app.nodes = nodes.items;
+    app.workloads = workloads.items;
+    app.workloads
+        .sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+    Ok(())
+}
+
+/// Replace `instance` by a fresh one, returning what happened.
+async fn restart(client: &ApiClient, instance: &InstanceResponse) -> String {
+    let path = format!("/api/v1/instances/{}/restart", instance.id);
+    match client.post::<serde_json::Value, _>(&path, &()).await {
+        Ok(_) => format!("Instance {} is being replaced", short(&instance.id)),
+        Err(e) => format!("Restart failed: {}", e),
+    }
+}
+
+/// Popup describing `instance` and its workload.
+async fn describe(client: &ApiClient, instance: &InstanceResponse) -> Popup {
+    let field = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(
+                format!("{:<12}", name),
+                Style::new().add_modifier(Modifier::DIM),
+            ),
+            Span::raw(value),
+        ])
+    };
+    let mut lines = vec![
+        field("Instance:", instance.id.clone()),
+        field("Node:", instance.node_id.clone()),
+        Line::from(vec![
+            Span::styled(
+                format!("{:<12}", "Status:"),
+                Style::new().add_modifier(Modifier::DIM),
+            ),
+            Span::styled(instance.status.clone(), state_style(&instance.status)),
+        ]),
+    ];
+    if !instance.ip_addresses.is_empty() {
+        lines.push(field("Addresses:", instance.ip_addresses.join(", ")));
+    }
+    for restart in &instance.restarts {
+        lines.push(field(
+            "Restarts:",
+            format!(
+                "{} restarted {} time(s), last exit code {}",
+                restart.container_name,
+                restart.restart_count,
+                restart
+                    .last_exit_code
+                    .map_or_else(|| "-".to_string(), |code| code.to_string())
+            ),
+        ));
+    }
+
+    lines.push(Line::raw(""));
+    let path = format!("/api/v1/workloads/{}", instance.workload_id);
+    match client.get::<WorkloadResponse>(&path).await {
+        Ok(workload) => {
+            lines.push(field(
+                "Workload:",
+                format!("{} ({})", workload.name, workload.id),
+            ));
+            lines.push(field("Namespace:", workload.namespace));
+            lines.push(field("Replicas:", workload.replicas.to_string()));
+            for container in workload.containers {
+                lines.push(field(
+                    "Container:",
+                    format!("{} ({})", container.name, container.image),
+                ));
+            }
+        }
+        Err(e) => lines.push(field("Workload:", format!("unavailable: {}", e))),
+    }
+
+    Popup {
+        title: format!("instance {}", short(&instance.id)),
+        lines,
+        scroll: 0,
+    }
+}
+
+/// Popup with the latest logs of `instance`.
+async fn logs(client: &ApiClient, instance: &InstanceResponse) -> Popup {
+    let path = format!(
+        "/api/v1/workloads/{}/instances/{}/logs?tail={}",
+        instance.workload_id, instance.id, LOG_TAIL
+    );
+    let lines = match client.get::<LogsResponse>(&path).await {
+        Ok(response) if response.logs.is_empty() => vec![Line::raw("No logs")],
+        Ok(response) => response
+            .logs
+            .lines()
+            .map(|line| Line::raw(line.to_string()))
+            .collect(),
+        Err(e) => vec![Line::styled(
+            format!("Failed to fetch logs: {}", e),
+            Style::new().fg(Color::Red),
+        )],
+    };
+    Popup {
+        title: format!("logs of instance {}", short(&instance.id)),
+        lines,
+        scroll: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn instance(id: &str, workload_id: &str, status: &str) -> InstanceResponse {
+        InstanceResponse {
+            id: id.to_string(),
+            workload_id: workload_id.to_string(),
+            node_id: "node0000".to_string(),
+            status: status.to_string(),
+            ip_addresses: vec![],
+            restarts: vec![],
+        }
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.nodes.push(NodeResponse {
+            id: "node0000-1111".to_string(),
+            status: "Ready".to_string(),
+            address: "10.0.0.1:8080".to_string(),
+            resources_capacity: ResourcesResponse {
+                cpu_cores: 4.0,
+                memory_mb: 8192,
+            },
+            resources_allocatable: ResourcesResponse {
+                cpu_cores: 1.0,
+                memory_mb: 4096,
+            },
+            unschedulable: false,
+        });
+        app.workloads.push(WorkloadResponse {
+            id: "w1".to_string(),
+            name: "web".to_string(),
+            namespace: "default".to_string(),
+            replicas: 2,
+            containers: vec![],
+        });
+        for i in [
+            instance("b-inst", "w1", "Running"),
+            instance("a-inst", "w1", "Pending"),
+        ] {
+            app.instances.insert(i.id.clone(), i);
+        }
+        app.clamp_selection();
+        app
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_usage_bar() {
+        assert_eq!(usage_bar(usage(3.0, 4.0)), "████████░░  75%");
+        assert_eq!(usage_bar(usage(1.0, 0.0)), "░░░░░░░░░░   0%");
+        assert_eq!(usage(5.0, 4.0), 1.0);
+    }
+
+    #[test]
+    fn test_keys_select_and_act_on_instances() {
+        let mut app = app();
+        assert_eq!(app.running("w1"), 1);
+
+        let first = app.selected_instance().unwrap();
+        assert_eq!(first.id, "a-inst");
+        assert_eq!(app.on_key(key(KeyCode::Down)), Action::None);
+        assert_eq!(app.on_key(key(KeyCode::Down)), Action::None);
+        let second = app.selected_instance().unwrap();
+        assert_eq!(second.id, "b-inst");
+        assert_eq!(app.on_key(key(KeyCode::Char('r'))), Action::Restart(second));
+
+        // Keys scroll an open popup instead
+        app.popup = Some(Popup {
+            title: "logs".to_string(),
+            lines: vec![],
+            scroll: 0,
+        });
+        assert_eq!(app.on_key(key(KeyCode::Char('j'))), Action::None);
+        assert_eq!(app.popup.as_ref().unwrap().scroll, 1);
+        assert_eq!(app.on_key(key(KeyCode::Esc)), Action::None);
+        assert!(app.popup.is_none());
+        assert_eq!(app.on_key(key(KeyCode::Char('q'))), Action::Quit);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut app = app();
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..buffer.area.height)
+            .map(|y| {
+                let line: String = (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect();
+                line + "\n"
+            })
+            .collect();
+        assert!(screen.contains("Nodes (1)"));
+        assert!(screen.contains("████████░░  75%"));
+        assert!(screen.contains("1/2"));
+        assert!(screen.contains("Instances (2)"));
+        assert!(screen.contains("Pending"));
+    }
+}
@@ -0,0 +1,293 @@
+//! Named cluster contexts from `~/.orch/config.yaml`.
+//!
+//! A context names an API server together with the namespace commands default
+//! to and, optionally, a bearer token and CA certificate to reach it with:
+//!
+//! ```yaml
+//! current-context: staging
+//! contexts:
+//!   - name: staging
+//!     server: https://staging.example.com:9090
+//!     namespace: shop
+//!   - name: prod
+//!     server: https://prod.example.com:9090
+//!     ca-certificate: /etc/orch/prod-ca.pem
+//!     token: eyJ...
+//! ```
+//!
+//! The context used is the one named by `--context` (or `ORCH_CONTEXT`),
+//! else `current-context`. An explicit `--api-url` still wins over its
+//! server. `ORCH_CONFIG` points at another file.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use clap::Command;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CliError, Result};
+
+/// The context of this invocation, set once at startup.
+static ACTIVE: OnceLock<Context> = OnceLock::new();
+
+/// One named cluster.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Context {
+    pub name: String,
+    /// API server URL.
+    pub server: String,
+    /// Namespace commands default to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Bearer token sent instead of signing requests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// PEM file of the CA the server is verified against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_certificate: Option<PathBuf>,
+}
+
+impl Context {
+    /// Whether this context is for the server at `api_url`.
+    pub fn is_for(&self, api_url: &str) -> bool {
+        self.server.trim_end_matches('/') == api_url.trim_end_matches('/')
+    }
+}
+
+/// The contents of the CLI configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CliConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_context: Option<String>,
+    #[serde(default)]
+    pub contexts: Vec<Context>,
+}
+
+impl CliConfig {
+    /// Path of the configuration file: `ORCH_CONFIG`, else
+    /// `~/.orch/config.yaml`.
+    pub fn path() -> Result<PathBuf> {
+        if let Some(path) = std::env::var_os("ORCH_CONFIG") {
+            return Ok(PathBuf::from(path));
+        }
+        let home = dirs::home_dir()
+            .ok_or_else(|| CliError::config_error("Could not determine home directory"))?;
+        Ok(home.join(".orch").join("config.yaml"))
+    }
+
+    /// Load the configuration file; a missing file is an empty configuration.
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::path()?)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_yaml_ng::from_str(&text)
+            .map_err(|e| CliError::config_error(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Save the configuration file, readable only by the user since contexts
+    /// may hold tokens.
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::path()?)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let text = serde_yaml_ng::to_string(self)
+            .map_err(|e| CliError::config_error(format!("{}: {}", path.display(), e)))?;
+        std::fs::write(path, text)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+
+    pub fn context(&self, name: &str) -> Result<&Context> {
+        self.contexts
+            .iter()
+            .find(|context| context.name == name)
+            .ok_or_else(|| CliError::config_error(format!("No context named '{}'", name)))
+    }
+
+    /// Add `context`, replacing the one of the same name.
+    pub fn set_context(&mut self, context: Context) {
+        match self.contexts.iter_mut().find(|c| c.name == context.name) {
+            Some(existing) => *existing = context,
+            None => self.contexts.push(context),
+        }
+    }
+
+    /// Remove the context named `name`, and make no context current if it
+    /// was.
+    pub fn delete_context(&mut self, name: &str) -> Result<()> {
+        self.context(name)?;
+        self.contexts.retain(|context| context.name != name);
+        if self.current_context.as_deref() == Some(name) {
+            self.current_context = None;
+        }
+        Ok(())
+    }
+
+    /// The context named by `flag`, else the current one, if any.
+    pub fn select(&self, flag: Option<&str>) -> Result<Option<&Context>> {
+        match flag.or(self.current_context.as_deref()) {
+            Some(name) => self.context(name).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Make `context` the one this invocation uses.
+pub fn activate(context: Context) {
+    let _ = ACTIVE.set(context);
+}
+
+/// The active context, if it is for the server at `api_url`.
+pub fn active_for(api_url: &str) -> Option<&'static Context> {
+    ACTIVE.get().filter(|context| context.is_for(api_url))
+}
+
+/// The value of `--context` in `args`, found before the arguments are parsed
+/// since it changes their defaults.
+pub fn context_flag(args: &[String]) -> Option<String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--context" {
+            return args.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix("--context=") {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// `command` with `namespace` as the default of every `--namespace`
+/// argument of it and its subcommands.
+pub fn with_default_namespace(command: Command, namespace: &str) -> Command {
+    let command = if command
+        .get_arguments()
+        .any(|arg| arg.get_id() == "namespace")
+    {
+        command.mut_arg("namespace", |arg| arg.default_value(namespace.to_string()))
+    } else {
+        command
+    };
+    let names: Vec<String> = command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect();
+    names.iter().fold(command, |command, name| {
+        command.mut_subcommand(name, |subcommand| {
+            with_default_namespace(subcommand, namespace)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+
+    fn context(name: &str, server: &str) -> Context {
+        Context {
+            name: name.to_string(),
+            server: server.to_string(),
+            namespace: None,
+            token: None,
+            ca_certificate: None,
+        }
+    }
+
+    #[test]
+    fn test_config_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".orch").join("config.yaml");
+        assert_eq!(CliConfig::load_from(&path).unwrap(), CliConfig::default());
+
+        let mut config = CliConfig::default();
+        config.set_context(context("staging", "http://staging:9090"));
+        config.set_context(Context {
+            namespace: Some("shop".to_string()),
+            ..context("prod", "https://prod:9090")
+        });
+        config.current_context = Some("prod".to_string());
+        config.save_to(&path).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("current-context: prod"));
+        let loaded = CliConfig::load_from(&path).unwrap();
+        assert_eq!(loaded, config);
+        assert_eq!(loaded.select(None).unwrap().unwrap().name, "prod");
+        assert_eq!(
+            loaded.select(Some("staging")).unwrap().unwrap().name,
+            "staging"
+        );
+        assert!(loaded.select(Some("dev")).is_err());
+
+        // Replacing keeps one context per name; deleting the current one
+        // leaves none current
+        config.set_context(context("prod", "https://prod-2:9090"));
+        assert_eq!(config.contexts.len(), 2);
+        config.delete_context("prod").unwrap();
+        assert_eq!(config.current_context, None);
+        assert!(config.delete_context("prod").is_err());
+    }
+
+    #[test]
+    fn test_context_flag() {
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+        assert_eq!(
+            context_flag(&args(&["orch", "--context", "prod", "status"])).as_deref(),
+            Some("prod")
+        );
+        assert_eq!(
+            context_flag(&args(&["orch", "status", "--context=staging"])).as_deref(),
+            Some("staging")
+        );
+        assert_eq!(
+            context_flag(&args(&["orch", "exec", "abc", "--", "--context", "x"])),
+            None
+        );
+        assert!(context("a", "http://a:1/").is_for("http://a:1"));
+    }
+
+    #[test]
+    fn test_default_namespace() {
+        let command = Command::new("orch").subcommand(
+            Command::new("instance")
+                .subcommand(Command::new("list").arg(Arg::new("namespace").long("namespace"))),
+        );
+        let command = with_default_namespace(command, "shop");
+
+        let matches = command
+            .clone()
+            .get_matches_from(["orch", "instance", "list"]);
+        let list = matches
+            .subcommand_matches("instance")
+            .and_then(|m| m.subcommand_matches("list"))
+            .unwrap();
+        assert_eq!(list.get_one::<String>("namespace").unwrap(), "shop");
+
+        let matches = command.get_matches_from(["orch", "instance", "list", "--namespace", "web"]);
+        let list = matches
+            .subcommand_matches("instance")
+            .and_then(|m| m.subcommand_matches("list"))
+            .unwrap();
+        assert_eq!(list.get_one::<String>("namespace").unwrap(), "web");
+    }
+}
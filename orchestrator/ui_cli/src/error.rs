@@ -22,6 +22,12 @@ pub enum CliError {
     #[error("Node not found: {0}")]
     NodeNotFound(String),
 
+    #[error("Instance not found: {0}")]
+    InstanceNotFound(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
     #[error("HTTP error: {0}")]
     HttpError(#[from] reqwest::Error),
 
@@ -0,0 +1,386 @@
+//! JSONPath templates for `-o jsonpath=...` and `-o custom-columns=...`.
+//!
+//! Supports the subset of kubectl's JSONPath that scripts lean on: text with
+//! `{...}` expressions such as `{.items[*].id}`, `{.containers[0].image}`,
+//! `{['key']}`, slices `[1:3]`, wildcards, string literals `{"\n"}` and
+//! `{range .items[*]}...{end}` loops.
+
+use serde_json::Value;
+
+/// A parsed template, rendered against a value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Path(Path),
+    Range(Path, Vec<Node>),
+}
+
+/// A path of steps from a value to the values it selects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Field(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Wildcard,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        // Each open range collects its body; the bottom entry is the template
+        let mut stack: Vec<(Option<Path>, Vec<Node>)> = vec![(None, Vec::new())];
+        let mut rest = template;
+
+        while !rest.is_empty() {
+            let Some(open) = rest.find('{') else {
+                push_text(&mut stack, rest);
+                break;
+            };
+            push_text(&mut stack, &rest[..open]);
+            let close = expression_end(&rest[open..])
+                .ok_or_else(|| format!("unclosed '{{' in template '{}'", template))?;
+            let expression = rest[open + 1..open + close].trim();
+            rest = &rest[open + close + 1..];
+
+            if let Some(path) = expression.strip_prefix("range ") {
+                stack.push((Some(Path::parse(path)?), Vec::new()));
+            } else if expression == "end" {
+                if stack.len() < 2 {
+                    return Err(format!(
+                        "{{end}} without {{range}} in template '{}'",
+                        template
+                    ));
+                }
+                let (path, body) = stack.pop().expect("open range");
+                let path = path.expect("only ranges are pushed above the template");
+                stack
+                    .last_mut()
+                    .expect("template entry")
+                    .1
+                    .push(Node::Range(path, body));
+            } else if let Some(literal) = string_literal(expression) {
+                stack
+                    .last_mut()
+                    .expect("template entry")
+                    .1
+                    .push(Node::Text(literal));
+            } else {
+                let path = Path::parse(expression)?;
+                stack
+                    .last_mut()
+                    .expect("template entry")
+                    .1
+                    .push(Node::Path(path));
+            }
+        }
+
+        match stack.pop() {
+            Some((None, nodes)) if stack.is_empty() => Ok(Self { nodes }),
+            _ => Err(format!(
+                "{{range}} without {{end}} in template '{}'",
+                template
+            )),
+        }
+    }
+
+    /// The template with its expressions replaced by what they select from
+    /// `value`; several results of one expression are separated by spaces.
+    pub fn render(&self, value: &Value) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, value, &mut out);
+        out
+    }
+}
+
+fn push_text(stack: &mut [(Option<Path>, Vec<Node>)], text: &str) {
+    if !text.is_empty() {
+        let nodes = &mut stack.last_mut().expect("template entry").1;
+        nodes.push(Node::Text(text.to_string()));
+    }
+}
+
+/// Offset of the `}` closing the expression `text` starts with, skipping
+/// braces inside quotes.
+fn expression_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '}') => return Some(i),
+            (None, _) => {}
+        }
+    }
+    None
+}
+
+/// The text of a `"..."` expression, with `\n`, `\t` and `\\` escapes.
+fn string_literal(expression: &str) -> Option<String> {
+    let inner = expression.strip_prefix('"')?.strip_suffix('"')?;
+    let mut literal = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            literal.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => literal.push('\n'),
+            Some('t') => literal.push('\t'),
+            Some(other) => literal.push(other),
+            None => literal.push('\\'),
+        }
+    }
+    Some(literal)
+}
+
+fn render_nodes(nodes: &[Node], value: &Value, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Path(path) => {
+                let results: Vec<String> = path.select(value).into_iter().map(scalar).collect();
+                out.push_str(&results.join(" "));
+            }
+            Node::Range(path, body) => {
+                for item in path.select(value) {
+                    render_nodes(body, item, out);
+                }
+            }
+        }
+    }
+}
+
+/// `value` as text: strings without quotes, anything else as compact JSON.
+pub fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl Path {
+    /// Parse a path such as `.items[*].id`; a leading `$` or `@` is allowed
+    /// and `.` alone selects the value itself.
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("invalid JSONPath '{}': {}", path, reason);
+        let mut steps = Vec::new();
+        let mut rest = path.trim();
+        rest = rest
+            .strip_prefix('$')
+            .or_else(|| rest.strip_prefix('@'))
+            .unwrap_or(rest);
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                if after.starts_with('.') {
+                    return Err(invalid("recursive descent '..' is not supported"));
+                }
+                if let Some(after) = after.strip_prefix('*') {
+                    steps.push(Step::Wildcard);
+                    rest = after;
+                    continue;
+                }
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end > 0 {
+                    steps.push(Step::Field(after[..end].to_string()));
+                }
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(|| invalid("unclosed '['"))?;
+                steps.push(bracket_step(after[..end].trim()).ok_or_else(|| {
+                    invalid(&format!("unsupported subscript '[{}]'", &after[..end]))
+                })?);
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid("expected '.' or '['"));
+            }
+        }
+        Ok(Self { steps })
+    }
+
+    /// The values the path selects from `value`.
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        self.steps.iter().fold(vec![value], |values, step| {
+            values.into_iter().flat_map(|v| step.apply(v)).collect()
+        })
+    }
+}
+
+/// The step of a `[...]` subscript: `*`, an index, a slice or a quoted key.
+fn bracket_step(subscript: &str) -> Option<Step> {
+    if subscript == "*" {
+        return Some(Step::Wildcard);
+    }
+    for quote in ['\'', '"'] {
+        if let Some(key) = subscript
+            .strip_prefix(quote)
+            .and_then(|s| s.strip_suffix(quote))
+        {
+            return Some(Step::Field(key.to_string()));
+        }
+    }
+    if let Some((start, end)) = subscript.split_once(':') {
+        let bound = |s: &str| -> Option<Option<i64>> {
+            let s = s.trim();
+            if s.is_empty() {
+                Some(None)
+            } else {
+                s.parse().ok().map(Some)
+            }
+        };
+        return Some(Step::Slice(bound(start)?, bound(end)?));
+    }
+    subscript.parse().ok().map(Step::Index)
+}
+
+impl Step {
+    fn apply<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        match (self, value) {
+            (Step::Field(name), Value::Object(map)) => map.get(name).into_iter().collect(),
+            (Step::Wildcard, Value::Array(items)) => items.iter().collect(),
+            (Step::Wildcard, Value::Object(map)) => map.values().collect(),
+            (Step::Index(index), Value::Array(items)) => resolve(*index, items.len())
+                .and_then(|i| items.get(i))
+                .into_iter()
+                .collect(),
+            (Step::Slice(start, end), Value::Array(items)) => {
+                let len = items.len();
+                let start = start.map_or(0, |s| resolve(s, len).unwrap_or(0).min(len));
+                let end = end.map_or(len, |e| resolve(e, len).unwrap_or(0).min(len));
+                items
+                    .get(start..end.max(start))
+                    .unwrap_or_default()
+                    .iter()
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// `index` as an offset into `len` items, counting from the end if negative.
+fn resolve(index: i64, len: usize) -> Option<usize> {
+    if index < 0 {
+        len.checked_sub(index.unsigned_abs() as usize)
+    } else {
+        Some(index as usize)
+    }
+}
+
+/// One column of `-o custom-columns`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub header: String,
+    pub path: Path,
+}
+
+impl Column {
+    /// The cell of this column for `item`: its values joined by commas, or
+    /// `<none>`.
+    pub fn cell(&self, item: &Value) -> String {
+        let values = self.path.select(item);
+        if values.is_empty() {
+            return "<none>".to_string();
+        }
+        values.into_iter().map(scalar).collect::<Vec<_>>().join(",")
+    }
+}
+
+/// Parse a `NAME:.path,OTHER:.path` column spec.
+pub fn parse_columns(spec: &str) -> Result<Vec<Column>, String> {
+    spec.split(',')
+        .map(|column| {
+            let (header, path) = column
+                .split_once(':')
+                .ok_or_else(|| format!("custom column '{}' must look like NAME:.path", column))?;
+            let path = path.trim();
+            let path = path
+                .strip_prefix('{')
+                .and_then(|p| p.strip_suffix('}'))
+                .unwrap_or(path);
+            Ok(Column {
+                header: header.trim().to_string(),
+                path: Path::parse(path)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str, value: &Value) -> String {
+        Template::parse(template).unwrap().render(value)
+    }
+
+    #[test]
+    fn test_paths() {
+        let list = json!({
+            "items": [
+                {"id": "a", "containers": [{"image": "nginx"}, {"image": "envoy"}]},
+                {"id": "b", "containers": [], "labels": {"app.kubernetes.io/name": "web"}},
+            ]
+        });
+        assert_eq!(render("{.items[*].id}", &list), "a b");
+        assert_eq!(render("{.items[0].containers[-1].image}", &list), "envoy");
+        assert_eq!(render("{$.items[1:].id}", &list), "b");
+        assert_eq!(
+            render("{.items[1].labels['app.kubernetes.io/name']}", &list),
+            "web"
+        );
+        assert_eq!(render("{.items[5].id}{.missing}", &list), "");
+        assert_eq!(
+            render("{.items[0].containers[0]}", &list),
+            r#"{"image":"nginx"}"#
+        );
+        assert_eq!(
+            render(
+                r#"{range .items[*]}{.id}:{.containers[*].image}{"\n"}{end}"#,
+                &list
+            ),
+            "a:nginx envoy\nb:\n"
+        );
+        assert_eq!(render("count: {.items[0].id}", &list), "count: a");
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(Template::parse("{.items").is_err());
+        assert!(Template::parse("{range .items[*]}{.id}").is_err());
+        assert!(Template::parse("{.id}{end}").is_err());
+        assert!(Template::parse("{..id}").is_err());
+        assert!(Template::parse("{.items[x]}").is_err());
+    }
+
+    #[test]
+    fn test_custom_columns() {
+        let columns =
+            parse_columns("NAME:.name,IMAGE:.containers[0].image,PORTS:{.ports[*]}").unwrap();
+        let headers: Vec<&str> = columns.iter().map(|c| c.header.as_str()).collect();
+        assert_eq!(headers, ["NAME", "IMAGE", "PORTS"]);
+
+        let workload =
+            json!({"name": "web", "containers": [{"image": "nginx"}], "ports": [80, 443]});
+        let cells: Vec<String> = columns.iter().map(|c| c.cell(&workload)).collect();
+        assert_eq!(cells, ["web", "nginx", "80,443"]);
+        assert_eq!(columns[1].cell(&json!({"name": "x"})), "<none>");
+
+        assert!(parse_columns("NAME").is_err());
+    }
+}
@@ -4,13 +4,21 @@
 
 mod client;
 mod commands;
+mod context;
 mod error;
+mod jsonpath;
 mod output;
+mod watch;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::env::CompleteEnv;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
-use crate::commands::{deploy, init, logs, scale, status};
+use crate::commands::{
+    admin, apply, cluster, completion, config, convert, cp, cronjob, debug, deploy, describe, docs,
+    doctor, exec, expose, federation, image, init, instance, login, logs, namespace, node, render,
+    report, rollout, scale, service, statefulset, status, top,
+};
 
 /// AI-Native Orchestrator CLI
 #[derive(Parser)]
@@ -18,33 +26,52 @@ use crate::commands::{deploy, init, logs, scale, status};
 #[command(version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Cli {
-    /// API server URL
-    #[arg(
-        short,
-        long,
-        env = "ORCH_API_URL",
-        default_value = "http://localhost:9090"
-    )]
-    api_url: String,
+    /// API server URL (default: the server of the context, else
+    /// http://localhost:9090)
+    #[arg(short, long, env = "ORCH_API_URL")]
+    api_url: Option<String>,
+
+    /// Context of ~/.orch/config.yaml to use instead of the current one
+    #[arg(long, global = true, env = "ORCH_CONTEXT")]
+    context: Option<String>,
+
+    /// Cluster of the API server's federation to send requests to, through
+    /// that server (default: the API server's own cluster)
+    #[arg(long, global = true, env = "ORCH_CLUSTER")]
+    cluster: Option<String>,
 
     /// Verbose output
     #[arg(short, long, global = true)]
     verbose: bool,
 
-    /// Output format
-    #[arg(long, global = true, default_value = "table")]
+    /// Output format: table, wide, json, yaml, jsonpath=TEMPLATE or
+    /// custom-columns=NAME:.path,...
+    #[arg(
+        short = 'o',
+        long = "output",
+        visible_alias = "format",
+        global = true,
+        default_value = "table",
+        value_parser = output::parse_format
+    )]
     format: OutputFormat,
 
     #[command(subcommand)]
     command: Commands,
 }
 
-#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+#[derive(Clone, Copy, Debug, Default)]
 pub enum OutputFormat {
     #[default]
     Table,
+    /// Table with extra columns
+    Wide,
     Json,
     Yaml,
+    /// `jsonpath=TEMPLATE`, e.g. `jsonpath={.items[*].id}`
+    JsonPath(&'static jsonpath::Template),
+    /// `custom-columns=NAME:.path,...`
+    CustomColumns(&'static [jsonpath::Column]),
 }
 
 #[derive(Subcommand)]
@@ -52,22 +79,139 @@ enum Commands {
     /// Initialize user configuration and identity
     Init(init::InitArgs),
 
+    /// Get a bearer token for the API, or store one from your OIDC provider
+    Login(login::LoginArgs),
+
+    /// Forget the stored bearer token and sign requests again
+    Logout,
+
     /// Show cluster and workload status
     Status(status::StatusArgs),
 
+    /// Interactive dashboard of nodes, workloads and instances
+    Top(top::TopArgs),
+
     /// Deploy a new workload
     Deploy(deploy::DeployArgs),
 
+    /// Show a workload's spec, instances and restart history
+    Describe(describe::DescribeArgs),
+
+    /// Create or update workloads and namespaces from YAML manifests
+    Apply(apply::ApplyArgs),
+
+    /// Convert Kubernetes manifests into workload manifests, or import them
+    Convert(convert::ConvertArgs),
+
+    /// Render a workload template with values, without applying it
+    Render(render::RenderArgs),
+
     /// Scale a workload
     Scale(scale::ScaleArgs),
 
+    /// Show a workload's revisions and roll it back
+    Rollout(rollout::RolloutArgs),
+
+    /// Restart or delete a single instance
+    Instance(instance::InstanceArgs),
+
+    /// Run a command in a container of an instance
+    Exec(exec::ExecArgs),
+
+    /// Copy files between the local machine and a container
+    Cp(cp::CpArgs),
+
+    /// Troubleshoot an instance with a debug container sharing its namespaces
+    Debug(debug::DebugArgs),
+
     /// View workload logs
     Logs(logs::LogsArgs),
+
+    /// Open a temporary public tunnel to a workload
+    Expose(expose::ExposeArgs),
+
+    /// Create, list and delete namespaces
+    Namespace(namespace::NamespaceArgs),
+
+    /// Run workloads as jobs on a cron schedule
+    #[command(name = "cronjob")]
+    CronJob(cronjob::CronJobArgs),
+
+    /// Put a virtual IP in front of workloads and split traffic between them
+    Service(service::ServiceArgs),
+
+    /// Run workloads whose instances keep their name and volumes
+    #[command(name = "statefulset")]
+    StatefulSet(statefulset::StatefulSetArgs),
+
+    /// Cordon, uncordon or drain a node
+    Node(node::NodeArgs),
+
+    /// Inspect, scan and push images, and pre-pull them onto nodes ahead of a rollout
+    Image(image::ImageArgs),
+
+    /// Diagnose connectivity, auth, version skew and cluster health
+    Doctor(doctor::DoctorArgs),
+
+    /// Cluster maintenance: state consistency checks and repair
+    Admin(admin::AdminArgs),
+
+    /// Resource usage reports for chargeback
+    Report(report::ReportArgs),
+
+    /// Back up and restore cluster state
+    Cluster(cluster::ClusterArgs),
+
+    /// Register remote clusters and list workloads and nodes across them
+    Federation(federation::FederationArgs),
+
+    /// Manage named cluster contexts
+    Config(config::ConfigArgs),
+
+    /// Print a shell completion script
+    Completion(completion::CompletionArgs),
+
+    /// Generate reference documentation such as man pages
+    Docs(docs::DocsArgs),
 }
 
+/// API server used without `--api-url` or a context.
+const DEFAULT_API_URL: &str = "http://localhost:9090";
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    // Answer the completion script and exit when it calls us back
+    CompleteEnv::with_factory(Cli::command)
+        .var(completion::COMPLETE_VAR)
+        .complete();
+
+    // The context's namespace is the default of every --namespace, so find
+    // the context before parsing
+    let cli_config = context::CliConfig::load()?;
+    let args: Vec<String> = std::env::args().collect();
+    let context_name =
+        context::context_flag(&args).or_else(|| std::env::var("ORCH_CONTEXT").ok());
+    let mut command = Cli::command();
+    if let Some(namespace) = cli_config
+        .select(context_name.as_deref())?
+        .and_then(|context| context.namespace.as_deref())
+    {
+        command = context::with_default_namespace(command, namespace);
+    }
+    let cli = Cli::from_arg_matches(&command.get_matches()).unwrap_or_else(|e| e.exit());
+
+    let selected = cli_config.select(cli.context.as_deref())?.cloned();
+    let api_url = cli
+        .api_url
+        .clone()
+        .or_else(|| selected.as_ref().map(|context| context.server.clone()))
+        .unwrap_or_else(|| DEFAULT_API_URL.to_string());
+    if let Some(context) = selected {
+        context::activate(context);
+    }
+    if let Some(cluster) = cli.cluster.clone() {
+        client::select_cluster(cluster);
+    }
 
     // Initialize tracing
     let filter = if cli.verbose {
@@ -83,11 +227,38 @@ async fn main() -> anyhow::Result<()> {
 
     // Execute command
     let result = match cli.command {
-        Commands::Init(args) => init::execute(args).await,
-        Commands::Status(args) => status::execute(args, &cli.api_url, cli.format).await,
-        Commands::Deploy(args) => deploy::execute(args, &cli.api_url, cli.format).await,
-        Commands::Scale(args) => scale::execute(args, &cli.api_url, cli.format).await,
-        Commands::Logs(args) => logs::execute(args, &cli.api_url).await,
+        Commands::Init(args) => init::execute(args, &api_url).await,
+        Commands::Login(args) => login::execute(args, &api_url).await,
+        Commands::Logout => login::logout().await,
+        Commands::Status(args) => status::execute(args, &api_url, cli.format).await,
+        Commands::Top(args) => top::execute(args, &api_url).await,
+        Commands::Deploy(args) => deploy::execute(args, &api_url, cli.format).await,
+        Commands::Describe(args) => describe::execute(args, &api_url, cli.format).await,
+        Commands::Apply(args) => apply::execute(args, &api_url).await,
+        Commands::Convert(args) => convert::execute(args, &api_url, cli.format).await,
+        Commands::Render(args) => render::execute(args).await,
+        Commands::Scale(args) => scale::execute(args, &api_url, cli.format).await,
+        Commands::Rollout(args) => rollout::execute(args, &api_url, cli.format).await,
+        Commands::Instance(args) => instance::execute(args, &api_url, cli.format).await,
+        Commands::Exec(args) => exec::execute(args, &api_url).await,
+        Commands::Cp(args) => cp::execute(args, &api_url).await,
+        Commands::Debug(args) => debug::execute(args, &api_url).await,
+        Commands::Logs(args) => logs::execute(args, &api_url).await,
+        Commands::Expose(args) => expose::execute(args, &api_url, cli.format).await,
+        Commands::Namespace(args) => namespace::execute(args, &api_url, cli.format).await,
+        Commands::CronJob(args) => cronjob::execute(args, &api_url, cli.format).await,
+        Commands::Service(args) => service::execute(args, &api_url, cli.format).await,
+        Commands::StatefulSet(args) => statefulset::execute(args, &api_url, cli.format).await,
+        Commands::Node(args) => node::execute(args, &api_url, cli.format).await,
+        Commands::Image(args) => image::execute(args, &api_url, cli.format).await,
+        Commands::Doctor(args) => doctor::execute(args, &api_url, cli.format).await,
+        Commands::Admin(args) => admin::execute(args, &api_url, cli.format).await,
+        Commands::Report(args) => report::execute(args, &api_url, cli.format).await,
+        Commands::Cluster(args) => cluster::execute(args, &api_url).await,
+        Commands::Federation(args) => federation::execute(args, &api_url, cli.format).await,
+        Commands::Config(args) => config::execute(args, cli.format).await,
+        Commands::Completion(args) => completion::execute(args).await,
+        Commands::Docs(args) => docs::execute(args, Cli::command()).await,
     };
 
     if let Err(e) = result {
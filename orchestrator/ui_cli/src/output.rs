@@ -2,27 +2,62 @@
 
 use colored::Colorize;
 use serde::Serialize;
+use serde_json::Value;
+use tabled::builder::Builder;
+use tabled::settings::Style;
 use tabled::{Table, Tabled};
 
+use crate::jsonpath::{parse_columns, Column, Template};
 use crate::OutputFormat;
 
+/// Parse `--output`: `table`, `wide`, `json`, `yaml`, `jsonpath=TEMPLATE` or
+/// `custom-columns=NAME:.path,...`.
+pub fn parse_format(format: &str) -> Result<OutputFormat, String> {
+    // The format lives for the whole invocation, so leaking its parsed
+    // template keeps OutputFormat Copy
+    match format.split_once('=') {
+        Some(("jsonpath", template)) => Ok(OutputFormat::JsonPath(Box::leak(Box::new(
+            Template::parse(template)?,
+        )))),
+        Some(("custom-columns", spec)) => Ok(OutputFormat::CustomColumns(Box::leak(
+            parse_columns(spec)?.into_boxed_slice(),
+        ))),
+        _ => match format {
+            "table" => Ok(OutputFormat::Table),
+            "wide" => Ok(OutputFormat::Wide),
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            _ => Err(format!(
+                "unknown output format '{}': expected table, wide, json, yaml, \
+                 jsonpath=TEMPLATE or custom-columns=SPEC",
+                format
+            )),
+        },
+    }
+}
+
 /// Print data in the specified format.
+///
+/// `-o wide` adds the fields of `T` that are serialized but `#[tabled(skip)]`
+/// to the table; such fields must come after the shown ones. A JSONPath
+/// template sees the data as `{"items": [...]}`.
 pub fn print_data<T: Serialize + Tabled>(data: &[T], format: OutputFormat) -> anyhow::Result<()> {
     match format {
+        OutputFormat::Table | OutputFormat::Wide if data.is_empty() => {
+            println!("{}", "No items found.".dimmed());
+        }
         OutputFormat::Table => {
-            if data.is_empty() {
-                println!("{}", "No items found.".dimmed());
-            } else {
-                let table = Table::new(data);
-                println!("{}", table);
-            }
+            let table = Table::new(data);
+            println!("{}", table);
         }
-        OutputFormat::Json => {
-            println!("{}", serde_json::to_string_pretty(data)?);
+        OutputFormat::Wide => {
+            println!("{}", wide_table(data)?);
         }
-        OutputFormat::Yaml => {
-            println!("{}", serde_yaml_ng::to_string(data)?);
+        OutputFormat::JsonPath(template) => {
+            let list = serde_json::json!({ "items": serde_json::to_value(data)? });
+            print_rendered(template, &list);
         }
+        _ => print_value(data, format)?,
     }
     Ok(())
 }
@@ -34,16 +69,121 @@ pub fn print_item<T: Serialize + Tabled>(item: &T, format: OutputFormat) -> anyh
             let table = Table::new([item]);
             println!("{}", table);
         }
-        OutputFormat::Json => {
-            println!("{}", serde_json::to_string_pretty(item)?);
+        OutputFormat::Wide => {
+            println!("{}", wide_table(std::slice::from_ref(item))?);
+        }
+        _ => print_value(item, format)?,
+    }
+    Ok(())
+}
+
+/// Print `value` in a structured format, for commands that draw their own
+/// tables; table formats fall back to JSON. Custom columns get a row per
+/// element of an array, else one row.
+pub fn print_value<T: Serialize + ?Sized>(value: &T, format: OutputFormat) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Yaml => println!("{}", serde_yaml_ng::to_string(value)?),
+        OutputFormat::JsonPath(template) => print_rendered(template, &serde_json::to_value(value)?),
+        OutputFormat::CustomColumns(columns) => {
+            let value = serde_json::to_value(value)?;
+            let rows = match &value {
+                Value::Array(items) => items.iter().collect(),
+                other => vec![other],
+            };
+            println!("{}", columns_table(columns, &rows));
         }
-        OutputFormat::Yaml => {
-            println!("{}", serde_yaml_ng::to_string(item)?);
+        OutputFormat::Table | OutputFormat::Wide | OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(value)?)
         }
     }
     Ok(())
 }
 
+fn print_rendered(template: &Template, value: &Value) {
+    let text = template.render(value);
+    if text.ends_with('\n') {
+        print!("{}", text);
+    } else {
+        println!("{}", text);
+    }
+}
+
+/// Table of the Tabled columns of `data` followed by its skipped fields.
+fn wide_table<T: Serialize + Tabled>(data: &[T]) -> anyhow::Result<String> {
+    let mut builder = Builder::default();
+    let mut header: Vec<String> = T::headers().into_iter().map(Into::into).collect();
+    for (i, item) in data.iter().enumerate() {
+        let mut row: Vec<String> = item.fields().into_iter().map(Into::into).collect();
+        if let serde_yaml_ng::Value::Mapping(fields) = serde_yaml_ng::to_value(item)? {
+            for (key, value) in fields.iter().skip(T::LENGTH) {
+                if i == 0 {
+                    header.push(column_header(key.as_str().unwrap_or_default()));
+                }
+                row.push(wide_cell(value));
+            }
+        }
+        if i == 0 {
+            builder.push_record(header.clone());
+        }
+        builder.push_record(row);
+    }
+    Ok(builder.build().to_string())
+}
+
+/// Header for the field `key`, e.g. "IP Addresses" for `ip_addresses`.
+fn column_header(key: &str) -> String {
+    key.split('_')
+        .map(|word| match word {
+            "id" | "ip" | "cpu" | "url" => word.to_uppercase(),
+            _ => {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// A field's value in a wide table: lists joined by commas, maps as sorted
+/// `key=value` pairs and nothing as `-`.
+fn wide_cell(value: &serde_yaml_ng::Value) -> String {
+    use serde_yaml_ng::Value as Yaml;
+    match value {
+        Yaml::Null => "-".to_string(),
+        Yaml::Bool(b) => b.to_string(),
+        Yaml::Number(n) => n.to_string(),
+        Yaml::String(s) => s.clone(),
+        Yaml::Sequence(items) if items.is_empty() => "-".to_string(),
+        Yaml::Sequence(items) => items.iter().map(wide_cell).collect::<Vec<_>>().join(","),
+        Yaml::Mapping(map) if map.is_empty() => "-".to_string(),
+        Yaml::Mapping(map) => {
+            let mut pairs: Vec<String> = map
+                .iter()
+                .map(|(key, value)| format!("{}={}", wide_cell(key), wide_cell(value)))
+                .collect();
+            pairs.sort();
+            pairs.join(",")
+        }
+        Yaml::Tagged(tagged) => wide_cell(&tagged.value),
+    }
+}
+
+/// Borderless table of `columns` for each of `rows`, easy to split in
+/// scripts.
+fn columns_table(columns: &[Column], rows: &[&Value]) -> String {
+    let mut builder = Builder::default();
+    builder.push_record(columns.iter().map(|column| column.header.clone()));
+    for row in rows {
+        builder.push_record(columns.iter().map(|column| column.cell(row)));
+    }
+    let mut table = builder.build();
+    table.with(Style::blank());
+    table.to_string()
+}
+
 /// Print a success message.
 pub fn success(msg: &str) {
     println!("{} {}", "✓".green().bold(), msg);
@@ -68,3 +208,71 @@ pub fn error(msg: &str) {
 pub fn section(title: &str) {
     println!("\n{}", title.bold().underline());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Tabled)]
+    struct Row {
+        #[tabled(rename = "ID")]
+        id: String,
+        #[tabled(rename = "Status")]
+        status: String,
+        #[tabled(skip)]
+        ip_addresses: Vec<String>,
+        #[tabled(skip)]
+        labels: std::collections::BTreeMap<String, String>,
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert!(matches!(parse_format("wide"), Ok(OutputFormat::Wide)));
+        assert!(matches!(
+            parse_format("jsonpath={.items[*].id}"),
+            Ok(OutputFormat::JsonPath(_))
+        ));
+        match parse_format("custom-columns=NAME:.name,IMAGE:.containers[0].image") {
+            Ok(OutputFormat::CustomColumns(columns)) => assert_eq!(columns.len(), 2),
+            _ => panic!("expected custom columns"),
+        }
+        assert!(parse_format("xml").is_err());
+        assert!(parse_format("jsonpath={.items").is_err());
+    }
+
+    #[test]
+    fn test_wide_and_custom_columns() {
+        let rows = [Row {
+            id: "abc".to_string(),
+            status: "Running".to_string(),
+            ip_addresses: vec!["10.0.0.1".to_string(), "fd00::1".to_string()],
+            labels: [("tier", "web"), ("app", "shop")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }];
+
+        let wide = wide_table(&rows).unwrap();
+        let header = wide.lines().nth(1).unwrap();
+        for column in ["ID", "Status", "IP Addresses", "Labels"] {
+            assert!(
+                header.contains(column),
+                "{} missing from {}",
+                column,
+                header
+            );
+        }
+        assert!(wide.contains("10.0.0.1,fd00::1"));
+        assert!(wide.contains("app=shop,tier=web"));
+
+        let columns = parse_columns("ID:.id,IP:.ip_addresses[0]").unwrap();
+        let value = serde_json::to_value(&rows).unwrap();
+        let items: Vec<&Value> = value.as_array().unwrap().iter().collect();
+        let table = columns_table(&columns, &items);
+        let lines: Vec<Vec<&str>> = table
+            .lines()
+            .map(|l| l.split_whitespace().collect())
+            .collect();
+        assert_eq!(lines, [vec!["ID", "IP"], vec!["abc", "10.0.0.1"]]);
+    }
+}
@@ -0,0 +1,366 @@
+//! Live-updating tables for `--watch`.
+//!
+//! List endpoints stream Server-Sent Events with `?watch=true`: one `ADDED`
+//! event per existing object, then one event per change. A [`LiveTable`]
+//! keeps the objects of such a stream and a [`Screen`] redraws their tables
+//! as they change, with the latest state transitions beneath them.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::io::Write;
+use std::time::Duration;
+
+use colored::{ColoredString, Colorize};
+use crossterm::cursor::MoveTo;
+use crossterm::terminal::{Clear, ClearType};
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tabled::{Table, Tabled};
+use tokio::time::Instant;
+
+use crate::error::Result;
+use crate::output;
+use crate::OutputFormat;
+
+/// Quiet time after an event before the screen is redrawn, so a burst of
+/// events such as the initial listing draws once.
+pub const REDRAW_DELAY: Duration = Duration::from_millis(100);
+
+/// State transitions shown beneath the tables.
+const MAX_TRANSITIONS: usize = 10;
+
+/// One change to a watched list.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchEvent<T> {
+    /// `ADDED`, `MODIFIED` or `DELETED`.
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub object: T,
+    #[allow(dead_code)]
+    pub resource_version: u64,
+}
+
+/// Events of a watch, read from the response as they arrive.
+pub struct WatchStream {
+    response: Response,
+    buffer: Vec<u8>,
+}
+
+impl WatchStream {
+    pub(crate) fn new(response: Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// The next event, or `None` once the server closes the watch.
+    pub async fn next<T: DeserializeOwned>(&mut self) -> Result<Option<WatchEvent<T>>> {
+        loop {
+            while let Some(end) = event_end(&self.buffer) {
+                let block: Vec<u8> = self.buffer.drain(..end).collect();
+                if let Some(data) = event_data(&String::from_utf8_lossy(&block)) {
+                    return Ok(Some(serde_json::from_str(&data)?));
+                }
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// The next event of `stream`, never completing when there is no stream.
+pub async fn next_event<T: DeserializeOwned>(
+    stream: &mut Option<WatchStream>,
+) -> Result<Option<WatchEvent<T>>> {
+    match stream {
+        Some(stream) => stream.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// End of the first event in `buffer`, including the blank line closing it.
+fn event_end(buffer: &[u8]) -> Option<usize> {
+    buffer
+        .windows(2)
+        .position(|window| window == b"\n\n")
+        .map(|position| position + 2)
+}
+
+/// Data of an event block; comments such as keep-alives carry none.
+fn event_data(block: &str) -> Option<String> {
+    let data: Vec<&str> = block
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect();
+    (!data.is_empty()).then(|| data.join("\n"))
+}
+
+/// An object shown in a live table.
+pub trait Watched: DeserializeOwned {
+    type Row: Tabled;
+
+    /// Kind named in transitions, e.g. "node".
+    const KIND: &'static str;
+
+    fn id(&self) -> &str;
+
+    /// State whose transitions are reported, e.g. a node's status.
+    fn state(&self) -> String;
+
+    fn row(&self) -> Self::Row;
+}
+
+/// A state change or removal of a watched object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    kind: &'static str,
+    id: String,
+    from: String,
+    /// `None` when the object was deleted.
+    to: Option<String>,
+}
+
+impl fmt::Display for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = &self.id[..8.min(self.id.len())];
+        match &self.to {
+            Some(to) => write!(
+                f,
+                "{} {}: {} → {}",
+                self.kind,
+                id,
+                self.from.dimmed(),
+                state_color(to)
+            ),
+            None => write!(f, "{} {}: {}", self.kind, id, "deleted".red()),
+        }
+    }
+}
+
+/// `state` colored by how healthy it is.
+pub fn state_color(state: &str) -> ColoredString {
+    match state {
+        "Ready" | "Running" | "Succeeded" => state.green(),
+        "Pending" | "Unknown" => state.yellow(),
+        _ => state.red(),
+    }
+}
+
+/// The objects of a watch, by ID.
+pub struct LiveTable<T> {
+    objects: BTreeMap<String, T>,
+}
+
+impl<T: Watched> LiveTable<T> {
+    pub fn new() -> Self {
+        Self {
+            objects: BTreeMap::new(),
+        }
+    }
+
+    /// Apply `event`, returning the transition it makes if it changes the
+    /// state of a known object or deletes one.
+    pub fn apply(&mut self, event: WatchEvent<T>) -> Option<Transition> {
+        let id = event.object.id().to_string();
+        let state = event.object.state();
+        if event.event_type == "DELETED" {
+            self.objects.remove(&id);
+            return Some(Transition {
+                kind: T::KIND,
+                id,
+                from: state,
+                to: None,
+            });
+        }
+
+        let from = self.objects.insert(id.clone(), event.object)?.state();
+        (from != state).then(|| Transition {
+            kind: T::KIND,
+            id,
+            from,
+            to: Some(state),
+        })
+    }
+
+    /// The objects as a table.
+    pub fn render(&self) -> String {
+        if self.objects.is_empty() {
+            return "No items found.".dimmed().to_string();
+        }
+        Table::new(self.objects.values().map(Watched::row)).to_string()
+    }
+}
+
+/// Terminal view of live tables and their recent transitions.
+pub struct Screen {
+    transitions: VecDeque<String>,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Self {
+            transitions: VecDeque::new(),
+        }
+    }
+
+    /// Remember `transition` to show it beneath the tables.
+    pub fn record(&mut self, transition: Option<Transition>) {
+        let Some(transition) = transition else {
+            return;
+        };
+        let time = chrono::Local::now().format("%H:%M:%S").to_string();
+        self.transitions
+            .push_back(format!("{} {}", time.dimmed(), transition));
+        if self.transitions.len() > MAX_TRANSITIONS {
+            self.transitions.pop_front();
+        }
+    }
+
+    /// Clear the terminal and draw `sections`, pairs of a title and a table.
+    pub fn draw(&self, sections: &[(&str, String)]) -> std::io::Result<()> {
+        let mut out = std::io::stdout().lock();
+        crossterm::execute!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+        for (title, table) in sections {
+            writeln!(out, "{}\n{}\n", title.bold().underline(), table)?;
+        }
+        if !self.transitions.is_empty() {
+            writeln!(out, "{}", "Recent changes".bold().underline())?;
+            for transition in &self.transitions {
+                writeln!(out, "  {}", transition)?;
+            }
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", "Watching for changes, Ctrl+C to stop".dimmed())?;
+        out.flush()
+    }
+}
+
+/// Print an event as it arrives, for watches in structured formats.
+pub fn print_event<T: Serialize>(
+    event: &WatchEvent<T>,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Yaml => println!("---\n{}", serde_yaml_ng::to_string(event)?),
+        OutputFormat::JsonPath(_) | OutputFormat::CustomColumns(_) => {
+            output::print_value(event, format)?
+        }
+        _ => println!("{}", serde_json::to_string(event)?),
+    }
+    Ok(())
+}
+
+/// Show the objects of `stream` as a table titled `title`, redrawn as they
+/// change, or print its events in JSON or YAML format, until the watch ends.
+pub async fn watch_table<T: Watched + Serialize>(
+    mut stream: WatchStream,
+    title: &str,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let mut table = LiveTable::<T>::new();
+    let mut screen = Screen::new();
+    let redraw = tokio::time::sleep(REDRAW_DELAY);
+    tokio::pin!(redraw);
+    let mut dirty = false;
+
+    loop {
+        tokio::select! {
+            event = stream.next::<T>() => match event? {
+                Some(event) if !matches!(format, OutputFormat::Table | OutputFormat::Wide) => {
+                    print_event(&event, format)?
+                }
+                Some(event) => {
+                    screen.record(table.apply(event));
+                    dirty = true;
+                    redraw.as_mut().reset(Instant::now() + REDRAW_DELAY);
+                }
+                None => break,
+            },
+            () = &mut redraw, if dirty => {
+                screen.draw(&[(title, table.render())])?;
+                dirty = false;
+            }
+        }
+    }
+
+    output::warn("The server closed the watch; run the command again to resume");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Deserialize, Tabled)]
+    struct Item {
+        id: String,
+        status: String,
+    }
+
+    impl Watched for Item {
+        type Row = Item;
+        const KIND: &'static str = "item";
+
+        fn id(&self) -> &str {
+            &self.id
+        }
+
+        fn state(&self) -> String {
+            self.status.clone()
+        }
+
+        fn row(&self) -> Item {
+            self.clone()
+        }
+    }
+
+    fn event(event_type: &str, status: &str) -> WatchEvent<Item> {
+        WatchEvent {
+            event_type: event_type.to_string(),
+            object: Item {
+                id: "0123456789".to_string(),
+                status: status.to_string(),
+            },
+            resource_version: 1,
+        }
+    }
+
+    #[test]
+    fn test_event_blocks() {
+        let buffer = b": keep-alive\n\nevent: ADDED\nid: 3\ndata: {\"a\":1}\n\nevent: MOD";
+        let end = event_end(buffer).unwrap();
+        assert_eq!(event_data(&String::from_utf8_lossy(&buffer[..end])), None);
+        let rest = &buffer[end..];
+        let end = event_end(rest).unwrap();
+        assert_eq!(
+            event_data(&String::from_utf8_lossy(&rest[..end])).as_deref(),
+            Some("{\"a\":1}")
+        );
+        // An incomplete event waits for more data
+        assert_eq!(event_end(&rest[end..]), None);
+    }
+
+    #[test]
+    fn test_live_table_transitions() {
+        colored::control::set_override(false);
+        let mut table = LiveTable::new();
+
+        // New objects and unchanged states make no transition
+        assert_eq!(table.apply(event("ADDED", "Pending")), None);
+        assert_eq!(table.apply(event("MODIFIED", "Pending")), None);
+        assert!(table.render().contains("Pending"));
+
+        let transition = table.apply(event("MODIFIED", "Running")).unwrap();
+        assert_eq!(transition.to_string(), "item 01234567: Pending → Running");
+        assert!(table.render().contains("Running"));
+
+        let transition = table.apply(event("DELETED", "Running")).unwrap();
+        assert_eq!(transition.to_string(), "item 01234567: deleted");
+        assert!(table.render().contains("No items found."));
+    }
+}
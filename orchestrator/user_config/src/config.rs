@@ -9,6 +9,7 @@ use crate::automation::AutomationBoundary;
 use crate::encryption::SecretStore;
 use crate::error::{ConfigError, Result};
 use crate::identity::{Identity, IdentityFile};
+use crate::llm::LlmProvider;
 use crate::paths::ConfigPaths;
 use crate::resources::ResourceLimits;
 use crate::trust_policy::TrustPolicy;
@@ -27,6 +28,8 @@ pub struct UserConfig {
     automation: AutomationBoundary,
     /// Resource limits.
     resources: ResourceLimits,
+    /// LLM provider, if configured.
+    llm: Option<LlmProvider>,
 }
 
 impl UserConfig {
@@ -82,8 +85,8 @@ impl UserConfig {
             TrustPolicy::default()
         };
 
-        // Load automation and resources from trust policy file or use defaults
-        let (automation, resources) = Self::load_extended_config(&paths).await?;
+        // Load automation, resources and LLM provider from settings or use defaults
+        let (automation, resources, llm) = Self::load_extended_config(&paths).await?;
 
         Ok(Self {
             paths,
@@ -92,6 +95,7 @@ impl UserConfig {
             trust_policy,
             automation,
             resources,
+            llm,
         })
     }
 
@@ -142,7 +146,7 @@ impl UserConfig {
         // Create default automation and resources
         let automation = AutomationBoundary::default();
         let resources = ResourceLimits::default();
-        Self::save_extended_config(&paths, &automation, &resources).await?;
+        Self::save_extended_config(&paths, &automation, &resources, None).await?;
 
         tracing::info!(
             identity_id = %identity.id(),
@@ -157,19 +161,26 @@ impl UserConfig {
             trust_policy,
             automation,
             resources,
+            llm: None,
         })
     }
 
-    /// Load extended configuration (automation, resources).
-    async fn load_extended_config(paths: &ConfigPaths) -> Result<(AutomationBoundary, ResourceLimits)> {
+    /// Load extended configuration (automation, resources, LLM provider).
+    async fn load_extended_config(
+        paths: &ConfigPaths,
+    ) -> Result<(AutomationBoundary, ResourceLimits, Option<LlmProvider>)> {
         let extended_path = paths.config_dir().join("settings.toml");
 
         if extended_path.exists() {
             let content = tokio::fs::read_to_string(&extended_path).await?;
             let settings: ExtendedSettings = toml::from_str(&content)?;
-            Ok((settings.automation, settings.resources))
+            Ok((settings.automation, settings.resources, settings.llm))
         } else {
-            Ok((AutomationBoundary::default(), ResourceLimits::default()))
+            Ok((
+                AutomationBoundary::default(),
+                ResourceLimits::default(),
+                None,
+            ))
         }
     }
 
@@ -178,11 +189,13 @@ impl UserConfig {
         paths: &ConfigPaths,
         automation: &AutomationBoundary,
         resources: &ResourceLimits,
+        llm: Option<&LlmProvider>,
     ) -> Result<()> {
         let settings = ExtendedSettings {
             version: crate::CONFIG_VERSION.to_string(),
             automation: automation.clone(),
             resources: resources.clone(),
+            llm: llm.cloned(),
         };
 
         let content = toml::to_string_pretty(&settings)?;
@@ -231,6 +244,16 @@ impl UserConfig {
         &mut self.resources
     }
 
+    /// Get the LLM provider, if one is configured.
+    pub fn llm(&self) -> Option<&LlmProvider> {
+        self.llm.as_ref()
+    }
+
+    /// Get mutable LLM provider setting.
+    pub fn llm_mut(&mut self) -> &mut Option<LlmProvider> {
+        &mut self.llm
+    }
+
     /// Get the configuration paths.
     pub fn paths(&self) -> &ConfigPaths {
         &self.paths
@@ -246,7 +269,13 @@ impl UserConfig {
         self.trust_policy.save(self.paths.trust_policy_file()).await?;
 
         // Save extended settings
-        Self::save_extended_config(&self.paths, &self.automation, &self.resources).await?;
+        Self::save_extended_config(
+            &self.paths,
+            &self.automation,
+            &self.resources,
+            self.llm.as_ref(),
+        )
+        .await?;
 
         Ok(())
     }
@@ -314,6 +343,8 @@ struct ExtendedSettings {
     version: String,
     automation: AutomationBoundary,
     resources: ResourceLimits,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    llm: Option<LlmProvider>,
 }
 
 /// Public identity for sharing.
@@ -417,4 +448,22 @@ mod tests {
         let loaded = UserConfig::load_with_paths(paths).await.unwrap();
         assert!(!loaded.trust_policy().network.allow_outbound);
     }
+
+    #[tokio::test]
+    async fn test_llm_provider_persisted() {
+        let temp = TempDir::new().unwrap();
+        let paths = ConfigPaths::with_base(temp.path());
+
+        let mut config = UserConfig::create_new_with_paths(paths.clone()).await.unwrap();
+        assert!(config.llm().is_none());
+        *config.llm_mut() = Some(LlmProvider {
+            base_url: "http://localhost:11434/v1".to_string(),
+            model: "llama3".to_string(),
+            api_key_env: None,
+        });
+        config.save().await.unwrap();
+
+        let loaded = UserConfig::load_with_paths(paths).await.unwrap();
+        assert_eq!(loaded.llm().unwrap().model, "llama3");
+    }
 }
@@ -5,6 +5,7 @@
 //! - Age encryption for secrets
 //! - Trust policy configuration via TOML
 //! - Automation boundaries and resource limits
+//! - Optional LLM provider for AI-assisted CLI output
 //! - XDG-compliant configuration paths
 //!
 //! # Configuration Location
@@ -42,6 +43,7 @@ pub mod encryption;
 pub mod trust_policy;
 pub mod automation;
 pub mod resources;
+pub mod llm;
 pub mod paths;
 pub mod config;
 mod error;
@@ -52,6 +54,7 @@ pub use encryption::SecretStore;
 pub use trust_policy::TrustPolicy;
 pub use automation::{AutomationBoundary, AutomationLevel};
 pub use resources::ResourceLimits;
+pub use llm::LlmProvider;
 pub use paths::ConfigPaths;
 pub use error::{ConfigError, Result};
 
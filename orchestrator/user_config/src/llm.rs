@@ -0,0 +1,67 @@
+//! LLM provider configuration.
+//!
+//! Optional CLI features, such as `orch describe --ai`, send cluster data to a
+//! language model. Nothing is sent unless a provider is configured here.
+
+use serde::{Deserialize, Serialize};
+
+/// An OpenAI-compatible chat completions provider.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmProvider {
+    /// Base URL of the API, e.g. `https://api.openai.com/v1` or
+    /// `http://localhost:11434/v1` for a local model.
+    pub base_url: String,
+
+    /// Model name passed with each request.
+    pub model: String,
+
+    /// Environment variable holding the API key, if the provider needs one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+}
+
+impl LlmProvider {
+    /// URL of the chat completions endpoint.
+    pub fn chat_completions_url(&self) -> String {
+        format!("{}/chat/completions", self.base_url.trim_end_matches('/'))
+    }
+
+    /// Read the API key from the configured environment variable.
+    pub fn api_key(&self) -> Option<String> {
+        self.api_key_env
+            .as_deref()
+            .and_then(|var| std::env::var(var).ok())
+            .filter(|key| !key.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_completions_url() {
+        let provider = LlmProvider {
+            base_url: "http://localhost:11434/v1/".to_string(),
+            model: "llama3".to_string(),
+            api_key_env: None,
+        };
+        assert_eq!(
+            provider.chat_completions_url(),
+            "http://localhost:11434/v1/chat/completions"
+        );
+        assert_eq!(provider.api_key(), None);
+    }
+
+    #[test]
+    fn test_toml_roundtrip() {
+        let provider = LlmProvider {
+            base_url: "https://api.openai.com/v1".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            api_key_env: Some("OPENAI_API_KEY".to_string()),
+        };
+        let toml = toml::to_string(&provider).unwrap();
+        let parsed: LlmProvider = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed, provider);
+    }
+}